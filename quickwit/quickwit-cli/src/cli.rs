@@ -14,6 +14,7 @@
 
 use anyhow::{Context, bail};
 use clap::{Arg, ArgAction, ArgMatches, Command, arg};
+use quickwit_config::NodeConfig;
 use quickwit_serve::EnvFilterReloadFn;
 use tracing::Level;
 
@@ -86,10 +87,30 @@ impl CliCommand {
         }
     }
 
-    pub async fn execute(self, env_filter_reload_fn: EnvFilterReloadFn) -> anyhow::Result<()> {
+    /// Loads the node config of the `run` command. Parts of the telemetry of the node, set up
+    /// before the command runs, are configured in the node config.
+    pub async fn load_node_config(&self) -> anyhow::Result<Option<NodeConfig>> {
+        match self {
+            CliCommand::Run(subcommand) => subcommand.load_node_config().await.map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    pub async fn execute(
+        self,
+        node_config_opt: Option<NodeConfig>,
+        env_filter_reload_fn: EnvFilterReloadFn,
+    ) -> anyhow::Result<()> {
         match self {
             CliCommand::Index(subcommand) => subcommand.execute().await,
-            CliCommand::Run(subcommand) => subcommand.execute(env_filter_reload_fn).await,
+            CliCommand::Run(subcommand) => match node_config_opt {
+                Some(node_config) => {
+                    subcommand
+                        .execute_with_node_config(node_config, env_filter_reload_fn)
+                        .await
+                }
+                None => subcommand.execute(env_filter_reload_fn).await,
+            },
             CliCommand::Source(subcommand) => subcommand.execute().await,
             CliCommand::Split(subcommand) => subcommand.execute().await,
             CliCommand::Tool(subcommand) => subcommand.execute().await,
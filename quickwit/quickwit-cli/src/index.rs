@@ -1106,6 +1106,7 @@ pub async fn search_index(args: SearchIndexArgs) -> anyhow::Result<SearchRespons
             field_name: "_score".to_string(),
             sort_order: SortOrder::Desc as i32,
             sort_datetime_format: None,
+            script: None,
         }]
     } else {
         Vec::new()
@@ -1118,7 +1119,7 @@ pub async fn search_index(args: SearchIndexArgs) -> anyhow::Result<SearchRespons
         snippet_fields: args.snippet_fields.clone(),
         start_timestamp: args.start_timestamp,
         end_timestamp: args.end_timestamp,
-        max_hits: args.max_hits as u64,
+        max_hits: Some(args.max_hits as u64),
         start_offset: args.start_offset as u64,
         sort_by,
         count_all: CountHits::CountAll,
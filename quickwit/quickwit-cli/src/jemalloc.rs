@@ -206,10 +206,15 @@ mod profiled_tracing {
                 .with_ansi(ansi_colors)
                 .with_filter(filter_fn(profiler_tracing_filter)),
         );
-        let registry = registry.with(
-            quickwit_telemetry_exporters::logging_layer(ansi_colors)
-                .with_filter(startup_env_filter(level)?),
-        );
+        let registry = registry
+            .with(
+                quickwit_telemetry_exporters::logging_layer(ansi_colors)
+                    .with_filter(startup_env_filter(level)?),
+            )
+            .with(
+                quickwit_telemetry_exporters::log_tail_layer()
+                    .with_filter(startup_env_filter(level)?),
+            );
         Ok((
             registry,
             quickwit_telemetry_exporters::do_nothing_env_filter_reload_fn(),
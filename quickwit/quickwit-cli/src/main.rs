@@ -25,7 +25,7 @@ use quickwit_cli::metrics::register_build_info_metric;
 use quickwit_cli::proc_io::start_proc_io_metrics_loop;
 use quickwit_cli::{busy_detector, install_default_crypto_ring_provider};
 use quickwit_common::runtimes::scrape_tokio_runtime_metrics;
-use quickwit_serve::{BuildInfo, EnvFilterReloadFn};
+use quickwit_serve::{BuildInfo, EnvFilterReloadFn, set_request_duration_buckets};
 use tracing::error;
 
 #[cfg(feature = "tokio-console")]
@@ -119,6 +119,18 @@ async fn main_impl() -> anyhow::Result<()> {
 
     install_default_crypto_ring_provider();
 
+    // The buckets of the request duration histograms are read when the metrics recorder is built,
+    // so the node config is loaded before the telemetry is initialized.
+    let node_config_opt = match command.load_node_config().await {
+        Ok(node_config_opt) => node_config_opt,
+        Err(error) => {
+            eprintln!("{} command failed: {:?}\n", "✘".color(RED_COLOR), error);
+            std::process::exit(1);
+        }
+    };
+    if let Some(node_config) = &node_config_opt {
+        set_request_duration_buckets(node_config.rest_config.request_duration_buckets.clone());
+    }
     let build_info = BuildInfo::get();
     let (telemetry_handle, env_filter_reload_fn) = init_telemetry(
         &build_info.version,
@@ -136,7 +148,8 @@ async fn main_impl() -> anyhow::Result<()> {
     #[cfg(target_os = "linux")]
     start_proc_io_metrics_loop();
 
-    let return_code: i32 = if let Err(command_error) = command.execute(env_filter_reload_fn).await {
+    let command_result = command.execute(node_config_opt, env_filter_reload_fn).await;
+    let return_code: i32 = if let Err(command_error) = command_result {
         error!(error=%command_error, "command failed");
         eprintln!(
             "{} command failed: {:?}\n",
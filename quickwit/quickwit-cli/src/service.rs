@@ -22,6 +22,7 @@ use futures::future::select;
 use itertools::Itertools;
 use quickwit_common::runtimes::RuntimesConfig;
 use quickwit_common::uri::Uri;
+use quickwit_config::NodeConfig;
 use quickwit_config::service::QuickwitService;
 use quickwit_serve::tcp_listener::DefaultTcpListenerResolver;
 use quickwit_serve::{BuildInfo, EnvFilterReloadFn, reload_tls_cert, serve_quickwit};
@@ -113,14 +114,28 @@ impl RunCliCommand {
         })
     }
 
+    /// Loads the node config, with the services overridden by the command line.
+    pub async fn load_node_config(&self) -> anyhow::Result<NodeConfig> {
+        load_node_config(&self.config_uri, self.services.as_ref()).await
+    }
+
     pub async fn execute(&self, env_filter_reload_fn: EnvFilterReloadFn) -> anyhow::Result<()> {
+        let node_config = self.load_node_config().await?;
+        self.execute_with_node_config(node_config, env_filter_reload_fn)
+            .await
+    }
+
+    pub async fn execute_with_node_config(
+        &self,
+        node_config: NodeConfig,
+        env_filter_reload_fn: EnvFilterReloadFn,
+    ) -> anyhow::Result<()> {
         debug!(args = ?self, "run-service");
         let version_text = BuildInfo::get_version_text();
         info!("quickwit version: {version_text}");
         if let Some(services) = &self.services {
             info!(services = %services.iter().join(", "), "setting services from override");
         }
-        let node_config = load_node_config(&self.config_uri, self.services.as_ref()).await?;
         let (storage_resolver, metastore_resolver) =
             get_resolvers(&node_config.storage_configs, &node_config.metastore_configs);
         crate::busy_detector::set_enabled(true);
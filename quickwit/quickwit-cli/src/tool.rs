@@ -46,7 +46,7 @@ use quickwit_metastore::IndexMetadataResponseExt;
 use quickwit_proto::indexing::CpuCapacity;
 use quickwit_proto::ingest::ingester::IngesterStatus;
 use quickwit_proto::metastore::{IndexMetadataRequest, MetastoreService, MetastoreServiceClient};
-use quickwit_proto::search::{CountHits, SearchResponse};
+use quickwit_proto::search::{CountHits, ReadPreference, SearchResponse};
 use quickwit_proto::types::{IndexId, PipelineUid, SourceId, SplitId};
 use quickwit_search::{SearchResponseRest, single_node_search};
 use quickwit_serve::{
@@ -549,7 +549,7 @@ pub async fn local_search_cli(args: LocalSearchArgs) -> anyhow::Result<()> {
     let search_request_query_string = SearchRequestQueryString {
         query: args.query,
         start_offset: args.start_offset as u64,
-        max_hits: args.max_hits as u64,
+        max_hits: Some(args.max_hits as u64),
         search_fields: args.search_fields,
         snippet_fields: args.snippet_fields,
         start_timestamp: args.start_timestamp,
@@ -557,8 +557,30 @@ pub async fn local_search_cli(args: LocalSearchArgs) -> anyhow::Result<()> {
         aggs,
         format: BodyFormat::Json,
         sort_by,
+        sort_by_script: None,
+        min_score: None,
+        scoring: None,
+        missing_fields: None,
         count_all: CountHits::CountAll,
         allow_failed_splits: false,
+        stream_aggs: false,
+        csv_aggs: false,
+        explain: false,
+        explain_scores: false,
+        default_operator: None,
+        track_scores: false,
+        ignore_max_indexes_per_search: false,
+        explain_resolution: false,
+        fresh: false,
+        detailed_timings: false,
+        columnar_hits: false,
+        result_bytes: false,
+        hit_bytes: false,
+        read_preference: ReadPreference::default(),
+        per_split_timeout: None,
+        timeout: None,
+        callback: None,
+        fields: None,
     };
     let search_request =
         search_request_from_api_request(vec![args.index_id], search_request_query_string)?;
@@ -41,7 +41,7 @@ use crate::change::{ClusterChange, compute_cluster_change_events};
 use crate::grpc_gossip::spawn_catchup_callback_task;
 use crate::member::{
     AVAILABILITY_ZONE_KEY, ClusterMember, ENABLED_SERVICES_KEY, GRPC_ADVERTISE_ADDR_KEY,
-    NodeStateExt, PIPELINE_METRICS_PREFIX, READINESS_KEY, READINESS_VALUE_NOT_READY,
+    LEAVING_KEY, NodeStateExt, PIPELINE_METRICS_PREFIX, READINESS_KEY, READINESS_VALUE_NOT_READY,
     READINESS_VALUE_READY, STANDALONE_COMPACTORS_KEY,
 };
 use crate::metrics::spawn_metrics_task;
@@ -356,6 +356,17 @@ impl Cluster {
             .await
     }
 
+    /// Marks the self node as leaving the cluster. The mark is never removed: the node is
+    /// expected to shut down once it has drained its in-flight work.
+    pub async fn set_self_node_leaving(&self) {
+        self.set_self_key_value(LEAVING_KEY, true).await
+    }
+
+    /// Returns whether the self node is leaving the cluster.
+    pub async fn is_self_node_leaving(&self) -> bool {
+        self.get_self_key_value(LEAVING_KEY).await.is_some()
+    }
+
     #[cfg(any(test, feature = "testsuite"))]
     pub async fn set_self_enable_standalone_compactors(&self, enable: bool) {
         self.set_self_key_value(STANDALONE_COMPACTORS_KEY, enable)
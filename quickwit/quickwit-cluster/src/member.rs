@@ -38,6 +38,9 @@ pub(crate) const READINESS_KEY: &str = "readiness";
 pub(crate) const READINESS_VALUE_READY: &str = "READY";
 pub(crate) const READINESS_VALUE_NOT_READY: &str = "NOT_READY";
 
+// Key set once the node starts leaving the cluster gracefully.
+pub(crate) const LEAVING_KEY: &str = "leaving";
+
 pub(crate) const AVAILABILITY_ZONE_KEY: &str = "availability_zone";
 
 pub(crate) const STANDALONE_COMPACTORS_KEY: &str = "standalone_compactors";
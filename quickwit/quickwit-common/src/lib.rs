@@ -62,12 +62,14 @@ mod socket_addr_legacy_hash;
 use std::fmt::Display;
 use std::future::Future;
 use std::ops::{Range, RangeInclusive};
+use std::sync::LazyLock;
 
 pub use coolid::new_coolid;
 pub use cpus::num_cpus;
 pub use kill_switch::KillSwitch;
 pub use path_hasher::PathHasher;
 pub use progress::{Progress, ProtectedZoneGuard};
+use regex::{Captures, Regex};
 pub use socket_addr_legacy_hash::SocketAddrLegacyHash;
 pub use stream_utils::{BoxStream, ServiceStream};
 
@@ -147,6 +149,28 @@ pub fn extract_time_range(
     }
 }
 
+/// Builds the regular expression matching a glob pattern, in which `*` matches any sequence of
+/// characters. All the other regular expression meta characters are escaped.
+pub fn glob_to_regex(pattern: &str) -> String {
+    let escaped_parts: Vec<String> = pattern.split('*').map(regex::escape).collect();
+    format!("^{}$", escaped_parts.join(".*"))
+}
+
+/// Replaces the values quoted with double quotes, single quotes, or backticks in a message with
+/// `<redacted>`, so that the message can be returned to a client without the values it quotes.
+pub fn redact_quoted_values(message: &str) -> String {
+    static QUOTED_VALUE_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(r#""(?:[^"\\]|\\.)*"|'[^']*'|`[^`]*`"#)
+            .expect("quoted value regex should be valid")
+    });
+    QUOTED_VALUE_REGEX
+        .replace_all(message, |captures: &Captures| {
+            let quote = &captures[0][..1];
+            format!("{quote}<redacted>{quote}")
+        })
+        .into_owned()
+}
+
 /// Takes 2 intervals and returns true iff their intersection is empty
 pub fn is_disjoint(left: &Range<i64>, right: &RangeInclusive<i64>) -> bool {
     left.end <= *right.start() || *right.end() < left.start
@@ -311,6 +335,32 @@ mod tests {
         assert_eq!(truncate_str("hello🧑‍🔬world", 7), "hello");
     }
 
+    #[test]
+    fn test_glob_to_regex() {
+        assert_eq!(glob_to_regex(""), r"^$");
+        assert_eq!(glob_to_regex("*"), r"^.*$");
+        assert_eq!(glob_to_regex("index-1"), r"^index\-1$");
+        assert_eq!(glob_to_regex("*-index-*-1"), r"^.*\-index\-.*\-1$");
+        assert_eq!(glob_to_regex("INDEX.2*-1"), r"^INDEX\.2.*\-1$");
+        assert_eq!(
+            glob_to_regex("https://*.quickwit.io"),
+            r"^https://.*\.quickwit\.io$"
+        );
+    }
+
+    #[test]
+    fn test_redact_quoted_values() {
+        assert_eq!(redact_quoted_values("no value"), "no value");
+        assert_eq!(
+            redact_quoted_values(r#"invalid origin "secret", 'secret' and `secret`"#),
+            r#"invalid origin "<redacted>", '<redacted>' and `<redacted>`"#
+        );
+        assert_eq!(
+            redact_quoted_values(r#"value "with \"escaped\" quotes" here"#),
+            r#"value "<redacted>" here"#
+        );
+    }
+
     #[test]
     fn test_ignore_io_error_macro() {
         ignore_error_kind!(
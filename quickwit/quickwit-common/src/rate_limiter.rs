@@ -101,6 +101,11 @@ impl<C: Clock> RateLimiter<C> {
         }
     }
 
+    /// Returns the maximum number of permits that can be accumulated.
+    pub fn max_capacity(&self) -> u64 {
+        self.max_capacity
+    }
+
     /// Returns the number of permits available.
     pub fn available_permits(&mut self) -> u64 {
         self.refill(self.clock.now());
@@ -119,6 +124,29 @@ impl<C: Clock> RateLimiter<C> {
         self.acquire(bytes.as_u64())
     }
 
+    /// Returns how long it takes for `num_permits` permits, capped to the maximum capacity, to be
+    /// available if no permits are acquired in the meantime.
+    pub fn time_until_available(&mut self, num_permits: u64) -> Duration {
+        let now = self.clock.now();
+        self.refill(now);
+
+        let num_missing_permits = num_permits
+            .min(self.max_capacity)
+            .saturating_sub(self.available_permits);
+
+        if num_missing_permits == 0 {
+            return Duration::ZERO;
+        }
+        if self.refill_amount == 0 {
+            return Duration::MAX;
+        }
+        let num_refills = num_missing_permits.div_ceil(self.refill_amount);
+        let time_until_next_refill =
+            Duration::from_nanos(self.refill_at.duration_since(now).as_u64());
+        let num_subsequent_refills = u32::try_from(num_refills - 1).unwrap_or(u32::MAX);
+        time_until_next_refill + self.refill_period.saturating_mul(num_subsequent_refills)
+    }
+
     /// Drains all the permits from the rate limiter, effectively disabling all the operations
     /// guarded by the rate limiter for one refill period.
     pub fn drain(&mut self) {
@@ -210,6 +238,38 @@ mod tests {
         assert!(rate_limiter.available_permits >= ByteSize::kb(100).as_u64());
     }
 
+    #[test]
+    fn test_rate_limiter_time_until_available() {
+        let settings = RateLimiterSettings {
+            burst_limit: 3,
+            rate_limit: ConstantRate::new(1, Duration::from_secs(1)),
+            refill_period: Duration::from_secs(1),
+        };
+        let clock = FakeRelativeClock::default();
+        let mut rate_limiter = RateLimiter::from_settings_with_clock(settings, clock.clone());
+        assert_eq!(rate_limiter.time_until_available(3), Duration::ZERO);
+
+        assert!(rate_limiter.acquire(3));
+        assert_eq!(rate_limiter.time_until_available(1), Duration::from_secs(1));
+        assert_eq!(rate_limiter.time_until_available(3), Duration::from_secs(3));
+        // The number of permits is capped to the maximum capacity.
+        assert_eq!(
+            rate_limiter.time_until_available(10),
+            Duration::from_secs(3)
+        );
+
+        clock.advance(Duration::from_millis(400));
+        assert_eq!(
+            rate_limiter.time_until_available(3),
+            Duration::from_millis(2600)
+        );
+
+        clock.advance(Duration::from_millis(600));
+        assert_eq!(rate_limiter.available_permits(), 1);
+        assert_eq!(rate_limiter.time_until_available(1), Duration::ZERO);
+        assert_eq!(rate_limiter.time_until_available(3), Duration::from_secs(2));
+    }
+
     #[test]
     fn test_rate_limiter_release() {
         let settings = RateLimiterSettings {
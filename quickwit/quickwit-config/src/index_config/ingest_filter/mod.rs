@@ -0,0 +1,438 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Small expression language selecting the documents ingested into an index through the ingest
+//! API or the Elasticsearch-compatible bulk API, for instance
+//! `level in ["warn", "error"] and not exists(debug)`.
+//!
+//! Expressions combine comparisons of a field, designated by a dotted path, with a JSON literal
+//! using `and`, `or`, `not`, and parentheses. There are no functions, regular expressions, or
+//! loops: evaluating an expression takes time linear in its size, and its size and nesting are
+//! bounded.
+
+use std::cmp::Ordering;
+
+use anyhow::{Context, bail, ensure};
+use serde_json::Value as JsonValue;
+
+const MAX_INGEST_FILTER_LEN: usize = 4_096;
+
+const MAX_INGEST_FILTER_DEPTH: usize = 32;
+
+/// A predicate evaluated on each document ingested through the ingest API, parsed from the
+/// `ingest_settings.ingest_filter` expression of the index config.
+#[derive(Clone, Debug, PartialEq)]
+pub struct IngestFilter {
+    expr: Expr,
+}
+
+impl IngestFilter {
+    /// Parses an ingest filter expression.
+    pub fn parse(expr: &str) -> anyhow::Result<Self> {
+        ensure!(
+            expr.len() <= MAX_INGEST_FILTER_LEN,
+            "ingest filter exceeds the maximum length of {MAX_INGEST_FILTER_LEN} bytes"
+        );
+        let tokens = tokenize(expr)?;
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+            depth: 0,
+        };
+        let expr = parser.parse_or()?;
+
+        if let Some(token) = parser.tokens.get(parser.position) {
+            bail!("unexpected token {token} in ingest filter");
+        }
+        Ok(Self { expr })
+    }
+
+    /// Returns whether the document matches the filter.
+    pub fn matches(&self, doc: &JsonValue) -> bool {
+        self.expr.matches(doc)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Expr {
+    And(Vec<Expr>),
+    Or(Vec<Expr>),
+    Not(Box<Expr>),
+    Exists(Vec<String>),
+    Compare {
+        field_path: Vec<String>,
+        op: CompareOp,
+        value: JsonValue,
+    },
+    In {
+        field_path: Vec<String>,
+        values: Vec<JsonValue>,
+    },
+}
+
+impl Expr {
+    fn matches(&self, doc: &JsonValue) -> bool {
+        match self {
+            Expr::And(exprs) => exprs.iter().all(|expr| expr.matches(doc)),
+            Expr::Or(exprs) => exprs.iter().any(|expr| expr.matches(doc)),
+            Expr::Not(expr) => !expr.matches(doc),
+            Expr::Exists(field_path) => matches!(
+                resolve_field(doc, field_path),
+                Some(field_value) if !field_value.is_null()
+            ),
+            Expr::Compare {
+                field_path,
+                op,
+                value,
+            } => match resolve_field(doc, field_path) {
+                Some(field_value) => op.apply(field_value, value),
+                // A missing field differs from any value.
+                None => *op == CompareOp::Ne,
+            },
+            Expr::In { field_path, values } => match resolve_field(doc, field_path) {
+                Some(field_value) => values.iter().any(|value| json_eq(field_value, value)),
+                None => false,
+            },
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl CompareOp {
+    fn apply(self, field_value: &JsonValue, value: &JsonValue) -> bool {
+        match self {
+            CompareOp::Eq => json_eq(field_value, value),
+            CompareOp::Ne => !json_eq(field_value, value),
+            CompareOp::Lt => json_cmp(field_value, value) == Some(Ordering::Less),
+            CompareOp::Le => matches!(
+                json_cmp(field_value, value),
+                Some(Ordering::Less | Ordering::Equal)
+            ),
+            CompareOp::Gt => json_cmp(field_value, value) == Some(Ordering::Greater),
+            CompareOp::Ge => matches!(
+                json_cmp(field_value, value),
+                Some(Ordering::Greater | Ordering::Equal)
+            ),
+        }
+    }
+}
+
+fn resolve_field<'a>(doc: &'a JsonValue, field_path: &[String]) -> Option<&'a JsonValue> {
+    field_path
+        .iter()
+        .try_fold(doc, |json_value, key| json_value.get(key.as_str()))
+}
+
+/// Compares numbers by value, so that `1` equals `1.0`.
+fn json_eq(left: &JsonValue, right: &JsonValue) -> bool {
+    match (left, right) {
+        (JsonValue::Number(left_number), JsonValue::Number(right_number)) => {
+            left_number.as_f64() == right_number.as_f64()
+        }
+        _ => left == right,
+    }
+}
+
+/// Orders two numbers or two strings. Other values are not comparable.
+fn json_cmp(left: &JsonValue, right: &JsonValue) -> Option<Ordering> {
+    match (left, right) {
+        (JsonValue::Number(left_number), JsonValue::Number(right_number)) => {
+            left_number.as_f64()?.partial_cmp(&right_number.as_f64()?)
+        }
+        (JsonValue::String(left_str), JsonValue::String(right_str)) => {
+            Some(left_str.cmp(right_str))
+        }
+        _ => None,
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    /// A keyword or a field path.
+    Ident(String),
+    Literal(JsonValue),
+    Op(CompareOp),
+    LeftParen,
+    RightParen,
+    LeftBracket,
+    RightBracket,
+    Comma,
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let token_str = match self {
+            Token::Ident(ident) => return write!(formatter, "`{ident}`"),
+            Token::Literal(literal) => return write!(formatter, "`{literal}`"),
+            Token::Op(CompareOp::Eq) => "==",
+            Token::Op(CompareOp::Ne) => "!=",
+            Token::Op(CompareOp::Lt) => "<",
+            Token::Op(CompareOp::Le) => "<=",
+            Token::Op(CompareOp::Gt) => ">",
+            Token::Op(CompareOp::Ge) => ">=",
+            Token::LeftParen => "(",
+            Token::RightParen => ")",
+            Token::LeftBracket => "[",
+            Token::RightBracket => "]",
+            Token::Comma => ",",
+        };
+        write!(formatter, "`{token_str}`")
+    }
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_' || c == '@'
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '@' | '-' | '.')
+}
+
+fn tokenize(expr: &str) -> anyhow::Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = expr.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        let token = match c {
+            _ if c.is_whitespace() => continue,
+            '(' => Token::LeftParen,
+            ')' => Token::RightParen,
+            '[' => Token::LeftBracket,
+            ']' => Token::RightBracket,
+            ',' => Token::Comma,
+            '=' | '!' | '<' | '>' => {
+                let is_followed_by_eq = chars.next_if(|(_, next_c)| *next_c == '=').is_some();
+                let op = match (c, is_followed_by_eq) {
+                    ('=', true) => CompareOp::Eq,
+                    ('!', true) => CompareOp::Ne,
+                    ('<', false) => CompareOp::Lt,
+                    ('<', true) => CompareOp::Le,
+                    ('>', false) => CompareOp::Gt,
+                    ('>', true) => CompareOp::Ge,
+                    _ => bail!("unexpected character `{c}` at position {start} in ingest filter"),
+                };
+                Token::Op(op)
+            }
+            '"' => {
+                let mut is_escaped = false;
+                let mut end_opt = None;
+
+                for (position, string_c) in chars.by_ref() {
+                    if is_escaped {
+                        is_escaped = false;
+                    } else if string_c == '\\' {
+                        is_escaped = true;
+                    } else if string_c == '"' {
+                        end_opt = Some(position + 1);
+                        break;
+                    }
+                }
+                let end = end_opt.context("unterminated string in ingest filter")?;
+                let string_str = &expr[start..end];
+                let string_literal: String = serde_json::from_str(string_str)
+                    .with_context(|| format!("invalid string `{string_str}` in ingest filter"))?;
+                Token::Literal(JsonValue::String(string_literal))
+            }
+            _ if c == '-' || c.is_ascii_digit() => {
+                let mut end = start + 1;
+
+                while let Some((position, number_c)) = chars.next_if(|(_, number_c)| {
+                    number_c.is_ascii_alphanumeric() || matches!(number_c, '.' | '+' | '-')
+                }) {
+                    end = position + number_c.len_utf8();
+                }
+                let number_str = &expr[start..end];
+                let number_literal: serde_json::Number = serde_json::from_str(number_str)
+                    .with_context(|| format!("invalid number `{number_str}` in ingest filter"))?;
+                Token::Literal(JsonValue::Number(number_literal))
+            }
+            _ if is_ident_start(c) => {
+                let mut end = start + c.len_utf8();
+
+                while let Some((position, ident_c)) =
+                    chars.next_if(|(_, ident_c)| is_ident_char(*ident_c))
+                {
+                    end = position + ident_c.len_utf8();
+                }
+                Token::Ident(expr[start..end].to_string())
+            }
+            _ => bail!("unexpected character `{c}` at position {start} in ingest filter"),
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+/// Recursive descent parser. `or` binds looser than `and`, which binds looser than `not`.
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn next_token(&mut self) -> anyhow::Result<Token> {
+        let token = self
+            .tokens
+            .get(self.position)
+            .cloned()
+            .context("unexpected end of ingest filter")?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    fn expect_token(&mut self, expected_token: Token) -> anyhow::Result<()> {
+        let token = self.next_token()?;
+        ensure!(
+            token == expected_token,
+            "expected {expected_token} in ingest filter, got {token}"
+        );
+        Ok(())
+    }
+
+    /// Consumes the next token if it is the given keyword.
+    fn consume_keyword(&mut self, keyword: &str) -> bool {
+        let is_keyword = matches!(
+            self.tokens.get(self.position),
+            Some(Token::Ident(ident)) if ident == keyword
+        );
+        if is_keyword {
+            self.position += 1;
+        }
+        is_keyword
+    }
+
+    fn parse_or(&mut self) -> anyhow::Result<Expr> {
+        let mut exprs = vec![self.parse_and()?];
+
+        while self.consume_keyword("or") {
+            exprs.push(self.parse_and()?);
+        }
+        if exprs.len() == 1 {
+            return Ok(exprs.pop().expect("exprs should not be empty"));
+        }
+        Ok(Expr::Or(exprs))
+    }
+
+    fn parse_and(&mut self) -> anyhow::Result<Expr> {
+        let mut exprs = vec![self.parse_unary()?];
+
+        while self.consume_keyword("and") {
+            exprs.push(self.parse_unary()?);
+        }
+        if exprs.len() == 1 {
+            return Ok(exprs.pop().expect("exprs should not be empty"));
+        }
+        Ok(Expr::And(exprs))
+    }
+
+    fn parse_unary(&mut self) -> anyhow::Result<Expr> {
+        self.depth += 1;
+        ensure!(
+            self.depth <= MAX_INGEST_FILTER_DEPTH,
+            "ingest filter exceeds the maximum nesting depth of {MAX_INGEST_FILTER_DEPTH}"
+        );
+        let expr = if self.consume_keyword("not") {
+            Expr::Not(Box::new(self.parse_unary()?))
+        } else {
+            self.parse_primary()?
+        };
+        self.depth -= 1;
+        Ok(expr)
+    }
+
+    fn parse_primary(&mut self) -> anyhow::Result<Expr> {
+        let field_path_str = match self.next_token()? {
+            Token::LeftParen => {
+                let expr = self.parse_or()?;
+                self.expect_token(Token::RightParen)?;
+                return Ok(expr);
+            }
+            Token::Ident(ident)
+                if ident == "exists"
+                    && self.tokens.get(self.position) == Some(&Token::LeftParen) =>
+            {
+                self.position += 1;
+                let field_path = self.parse_field_path()?;
+                self.expect_token(Token::RightParen)?;
+                return Ok(Expr::Exists(field_path));
+            }
+            Token::Ident(ident) => ident,
+            token => bail!("expected a field or `(` in ingest filter, got {token}"),
+        };
+        let field_path = parse_field_path(&field_path_str)?;
+
+        match self.next_token()? {
+            Token::Op(op) => {
+                let value = self.parse_literal()?;
+                Ok(Expr::Compare {
+                    field_path,
+                    op,
+                    value,
+                })
+            }
+            Token::Ident(ident) if ident == "in" => {
+                self.expect_token(Token::LeftBracket)?;
+                let mut values = vec![self.parse_literal()?];
+
+                while self.tokens.get(self.position) == Some(&Token::Comma) {
+                    self.position += 1;
+                    values.push(self.parse_literal()?);
+                }
+                self.expect_token(Token::RightBracket)?;
+                Ok(Expr::In { field_path, values })
+            }
+            token => bail!("expected an operator or `in` in ingest filter, got {token}"),
+        }
+    }
+
+    fn parse_field_path(&mut self) -> anyhow::Result<Vec<String>> {
+        match self.next_token()? {
+            Token::Ident(ident) => parse_field_path(&ident),
+            token => bail!("expected a field in ingest filter, got {token}"),
+        }
+    }
+
+    fn parse_literal(&mut self) -> anyhow::Result<JsonValue> {
+        let literal = match self.next_token()? {
+            Token::Literal(literal) => literal,
+            Token::Ident(ident) if ident == "true" => JsonValue::Bool(true),
+            Token::Ident(ident) if ident == "false" => JsonValue::Bool(false),
+            Token::Ident(ident) if ident == "null" => JsonValue::Null,
+            token => bail!("expected a JSON literal in ingest filter, got {token}"),
+        };
+        Ok(literal)
+    }
+}
+
+fn parse_field_path(field_path_str: &str) -> anyhow::Result<Vec<String>> {
+    let field_path: Vec<String> = field_path_str.split('.').map(str::to_string).collect();
+    ensure!(
+        field_path.iter().all(|key| !key.is_empty()),
+        "invalid field `{field_path_str}` in ingest filter"
+    );
+    Ok(field_path)
+}
+
+#[cfg(test)]
+mod tests;
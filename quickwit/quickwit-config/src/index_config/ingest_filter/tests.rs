@@ -0,0 +1,82 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use serde_json::json;
+
+use super::*;
+
+#[test]
+fn test_ingest_filter_matches() {
+    let doc = json!({
+        "level": "error",
+        "status": 503,
+        "latency": 12.5,
+        "debug": null,
+        "service": {"name": "api", "canary": false},
+    });
+    for (expr, expected_match) in [
+        (r#"level == "error""#, true),
+        (r#"level != "error""#, false),
+        (r#"level in ["warn", "error"]"#, true),
+        (r#"level in ["info"]"#, false),
+        ("status >= 500", true),
+        ("status == 503.0", true),
+        (r#"service.name == "api""#, true),
+        ("service.canary == false", true),
+        ("exists(service.name)", true),
+        ("exists(debug)", false),
+        ("exists(trace_id)", false),
+        (r#"trace_id != "abc""#, true),
+        ("level > 500", false),
+        (r#"level == "error" and not exists(debug)"#, true),
+        (
+            r#"level == "info" or status >= 500 and service.canary == false"#,
+            true,
+        ),
+        (
+            r#"(level == "info" or status >= 500) and service.canary == true"#,
+            false,
+        ),
+        (r#"not (level == "info" or level == "warn")"#, true),
+    ] {
+        let ingest_filter = IngestFilter::parse(expr).unwrap();
+        assert_eq!(ingest_filter.matches(&doc), expected_match, "{expr}");
+    }
+}
+
+#[test]
+fn test_ingest_filter_parse_errors() {
+    for expr in [
+        "",
+        "level",
+        r#"level = "error""#,
+        r#"level == "error"#,
+        "level == error",
+        "level in []",
+        "level == 1 and",
+        "(level == 1",
+        "level == 1)",
+        "service..name == 1",
+        "level == 1 # comment",
+    ] {
+        IngestFilter::parse(expr).unwrap_err();
+    }
+    let deeply_nested_expr = format!("{}level == 1{}", "(".repeat(64), ")".repeat(64));
+    let error = IngestFilter::parse(&deeply_nested_expr).unwrap_err();
+    assert!(error.to_string().contains("maximum nesting depth"));
+
+    let long_expr = vec!["level == 1"; 1_000].join(" or ");
+    let error = IngestFilter::parse(&long_expr).unwrap_err();
+    assert!(error.to_string().contains("maximum length"));
+}
@@ -12,24 +12,30 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod ingest_filter;
 pub(crate) mod serialize;
 
-use std::collections::HashSet;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashSet};
 use std::hash::{Hash, Hasher};
-use std::num::NonZeroUsize;
+use std::num::{NonZeroU64, NonZeroUsize};
 use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
 
-use anyhow::{Context, ensure};
+use anyhow::{Context, bail, ensure};
 use bytesize::ByteSize;
 use chrono::Utc;
 use cron::Schedule;
+use http::{HeaderName, HeaderValue};
 use humantime::parse_duration;
+pub use ingest_filter::IngestFilter;
 use quickwit_common::uri::Uri;
-use quickwit_common::{is_true, true_fn};
-use quickwit_doc_mapper::{DocMapper, DocMapperBuilder, DocMapping};
+use quickwit_common::{is_false, is_true, true_fn};
+use quickwit_doc_mapper::{DYNAMIC_FIELD_NAME, DocMapper, DocMapperBuilder, DocMapping};
+use quickwit_proto::search::SortOrder;
 use quickwit_proto::types::IndexId;
+use quickwit_query::BooleanOperand;
 use serde::{Deserialize, Serialize};
 pub use serialize::{load_index_config_from_user_config, load_index_config_update};
 use siphasher::sip::SipHasher;
@@ -39,6 +45,7 @@ use crate::index_config::serialize::VersionedIndexConfig;
 use crate::merge_policy_config::MergePolicyConfig;
 #[cfg(feature = "metrics")]
 use crate::merge_policy_config::ParquetMergePolicyConfig;
+use crate::validate_identifier;
 
 #[derive(Clone, Debug, Serialize, Deserialize, utoipa::ToSchema)]
 #[serde(deny_unknown_fields)]
@@ -282,12 +289,119 @@ pub struct IngestSettings {
     #[schema(default = true, value_type = bool)]
     #[serde(default = "true_fn", skip_serializing_if = "is_true")]
     pub validate_docs: bool,
+    /// When set, ingest requests targeting the index are rejected with a 403 while the index
+    /// remains searchable. Usually toggled with `PUT /indexes/{index_id}/_block`.
+    #[schema(default = false, value_type = bool)]
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub write_block: bool,
+    /// When set, documents ingested through the ingest API are upserted: a document whose value
+    /// for this top-level field matches an already indexed document is merged into it and
+    /// replaces it instead of being appended. The field must be an indexed fast field, with the
+    /// `raw` tokenizer for text fields, and stored.
+    #[schema(value_type = Option<String>)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upsert_id_field: Option<String>,
+    /// When set, documents ingested through the ingest API whose content is identical to a
+    /// document ingested within this many seconds are dropped. Identical documents are detected
+    /// on each node with a bounded-memory probabilistic structure, so a small fraction of unique
+    /// documents may be dropped as well.
+    #[schema(value_type = Option<u64>)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_dedup_window_secs: Option<NonZeroU64>,
+    /// When not empty, ingest requests targeting the index are rejected with a 415 unless their
+    /// content type is listed, for instance `application/x-ndjson` for the ingest API or
+    /// `application/x-protobuf` for the OTLP endpoints.
+    #[schema(value_type = Vec<String>)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub accepted_content_types: Vec<String>,
+    /// When set, documents ingested through the ingest API that do not match this expression,
+    /// for instance `level in ["warn", "error"] and exists(trace_id)`, are dropped.
+    #[schema(value_type = Option<String>)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ingest_filter: Option<String>,
+    /// When set, the indexing pipelines of the index are stopped until indexing resumes, and the
+    /// documents ingested meanwhile are either buffered or rejected. Usually toggled with
+    /// `POST /indexing/{index_id}/pause` and `POST /indexing/{index_id}/resume`.
+    #[schema(value_type = Option<IndexingPauseMode>)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub indexing_pause: Option<IndexingPauseMode>,
+    /// When set, ingest requests can target the index with this alias instead of its ID, for
+    /// instance `logs` for `logs-000001`. Usually moved from an index to the next one with
+    /// `POST /indexes/{alias}/_rollover`.
+    #[schema(value_type = Option<String>)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub write_alias: Option<String>,
+}
+
+/// Fate of the documents ingested into an index whose indexing is paused.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum IndexingPauseMode {
+    /// Ingest requests are accepted and their documents are buffered, in the WAL of the ingesters
+    /// or in the ingest API queues, and indexed once indexing resumes. Ingestion starts failing
+    /// once the buffers are full.
+    #[default]
+    Buffer,
+    /// Ingest requests are rejected with a 503 until indexing resumes.
+    Reject,
 }
 
 impl IngestSettings {
     pub fn default_min_shards() -> NonZeroUsize {
         NonZeroUsize::MIN
     }
+
+    /// Returns whether ingest requests with the given content type are accepted. The parameters
+    /// of the content type, such as `charset`, are ignored.
+    pub fn accepts_content_type(&self, content_type_opt: Option<&str>) -> bool {
+        if self.accepted_content_types.is_empty() {
+            return true;
+        }
+        let Some(content_type) = content_type_opt else {
+            return false;
+        };
+        let media_type = content_type.split(';').next().unwrap_or_default().trim();
+
+        self.accepted_content_types
+            .iter()
+            .any(|accepted_content_type| accepted_content_type.eq_ignore_ascii_case(media_type))
+    }
+
+    pub(super) fn validate(&self, doc_mapper: &DocMapper) -> anyhow::Result<()> {
+        if let Some(upsert_id_field) = &self.upsert_id_field {
+            let schema = doc_mapper.schema();
+            let dynamic_field_opt = schema.get_field(DYNAMIC_FIELD_NAME).ok();
+            let Some((field, _json_path)) =
+                schema.find_field_with_default(upsert_id_field, dynamic_field_opt)
+            else {
+                bail!("unknown upsert id field `{upsert_id_field}`");
+            };
+            let field_entry = schema.get_field_entry(field);
+            ensure!(
+                field_entry.is_indexed() && field_entry.is_fast(),
+                "upsert id field `{upsert_id_field}` must be an indexed fast field"
+            );
+        }
+        for content_type in &self.accepted_content_types {
+            let is_valid = matches!(
+                content_type.split_once('/'),
+                Some((media_type, subtype)) if !media_type.is_empty()
+                    && !subtype.is_empty()
+                    && !subtype.contains(['/', ';'])
+            );
+            ensure!(
+                is_valid,
+                "invalid accepted content type `{content_type}`, expected `type/subtype`"
+            );
+        }
+        if let Some(ingest_filter) = &self.ingest_filter {
+            IngestFilter::parse(ingest_filter)?;
+        }
+        if let Some(write_alias) = &self.write_alias {
+            validate_identifier("Write alias", write_alias)?;
+        }
+        Ok(())
+    }
 }
 
 impl Default for IngestSettings {
@@ -295,6 +409,13 @@ impl Default for IngestSettings {
         Self {
             min_shards: Self::default_min_shards(),
             validate_docs: true,
+            write_block: false,
+            upsert_id_field: None,
+            content_dedup_window_secs: None,
+            accepted_content_types: Vec::new(),
+            ingest_filter: None,
+            indexing_pause: None,
+            write_alias: None,
         }
     }
 }
@@ -304,6 +425,116 @@ impl Default for IngestSettings {
 pub struct SearchSettings {
     #[serde(default)]
     pub default_search_fields: Vec<String>,
+    /// When set, search requests targeting the index are rejected with a 403. Usually toggled
+    /// with `PUT /indexes/{index_id}/_block`.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub read_block: bool,
+    /// Sort applied to the search requests that do not specify one, so that their hits are
+    /// returned in a deterministic order. Requests can still override it.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub default_sort: Vec<DefaultSortField>,
+    /// Maximum size of the hits returned by a search request targeting the index. Beyond it,
+    /// the hits are truncated and the response is flagged as `truncated`.
+    #[schema(value_type = Option<String>)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_response_size: Option<ByteSize>,
+    /// Maximum number of buckets an aggregation of a search request targeting the index may
+    /// create. It can only lower the `aggregation_bucket_limit` of the searchers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_aggregation_buckets: Option<u32>,
+    /// Whether the fields of the doc mapping absent from a document are omitted from its hits or
+    /// rendered as `null`. Requests can still override it.
+    #[serde(default, skip_serializing_if = "MissingFields::is_omit")]
+    pub missing_fields: MissingFields,
+    /// Operator combining the clauses of the user queries that do not specify one, `AND` if
+    /// unset. Requests can still override it.
+    #[schema(value_type = Option<String>)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_operator: Option<BooleanOperand>,
+    /// Number of hits returned by the search requests that do not specify one, 20 if unset.
+    /// Requests can still override it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_max_hits: Option<u64>,
+}
+
+impl SearchSettings {
+    /// Checks that the default number of hits does not exceed the maximum accepted by the
+    /// searchers. The default search fields are checked when building the doc mapper.
+    pub fn validate_query_defaults(&self) -> anyhow::Result<()> {
+        if let Some(default_max_hits) = self.default_max_hits {
+            ensure!(
+                default_max_hits <= 10_000,
+                "search settings `default_max_hits` must be at most 10,000, got {default_max_hits}"
+            );
+        }
+        Ok(())
+    }
+
+    /// Checks that the default sort fields are at most two fast fields (or `_score`, `_doc`,
+    /// `_shard_doc`) of the doc mapping.
+    fn validate_default_sort(&self, doc_mapper: &DocMapper) -> anyhow::Result<()> {
+        ensure!(
+            self.default_sort.len() <= 2,
+            "default sort must have up to 2 fields, got {}",
+            self.default_sort.len()
+        );
+        let schema = doc_mapper.schema();
+        let dynamic_field_opt = schema.get_field(DYNAMIC_FIELD_NAME).ok();
+
+        for default_sort_field in &self.default_sort {
+            let field_name = default_sort_field.field.as_str();
+
+            if ["_score", "_doc", "_shard_doc"].contains(&field_name) {
+                continue;
+            }
+            let Some((field, _json_path)) =
+                schema.find_field_with_default(field_name, dynamic_field_opt)
+            else {
+                bail!("unknown default sort field `{field_name}`");
+            };
+            ensure!(
+                schema.get_field_entry(field).is_fast(),
+                "default sort field `{field_name}` must be a fast field"
+            );
+        }
+        Ok(())
+    }
+}
+
+/// A field of the default sort of an index.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct DefaultSortField {
+    /// Name of the field to sort on, or `_score`.
+    pub field: String,
+    /// Sort order, descending by default.
+    #[serde(default = "DefaultSortField::default_order")]
+    pub order: SortOrder,
+}
+
+impl DefaultSortField {
+    fn default_order() -> SortOrder {
+        SortOrder::Desc
+    }
+}
+
+/// How the fields of the doc mapping absent from a document are rendered in its hits.
+#[derive(
+    Clone, Copy, Debug, Default, Eq, Hash, PartialEq, Serialize, Deserialize, utoipa::ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum MissingFields {
+    /// The absent fields are omitted.
+    #[default]
+    Omit,
+    /// The absent fields are rendered as `null`.
+    AsNull,
+}
+
+impl MissingFields {
+    fn is_omit(&self) -> bool {
+        *self == MissingFields::Omit
+    }
 }
 
 #[derive(Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
@@ -392,6 +623,9 @@ pub struct IndexConfig {
     pub ingest_settings: IngestSettings,
     pub search_settings: SearchSettings,
     pub retention_policy_opt: Option<RetentionPolicy>,
+    /// Headers added to the REST responses of the search and ingest requests targeting the index,
+    /// for instance to tag the responses with the classification of the index data.
+    pub response_headers: BTreeMap<String, String>,
 }
 
 impl IndexConfig {
@@ -492,6 +726,13 @@ impl IndexConfig {
                 r#"attributes.server"#.to_string(),
                 r"attributes.server\.status".to_string(),
             ],
+            read_block: false,
+            default_sort: Vec::new(),
+            max_response_size: None,
+            max_aggregation_buckets: None,
+            missing_fields: MissingFields::Omit,
+            default_operator: None,
+            default_max_hits: None,
         };
         IndexConfig {
             index_id: index_id.to_string(),
@@ -501,6 +742,7 @@ impl IndexConfig {
             ingest_settings: IngestSettings::default(),
             search_settings,
             retention_policy_opt: None,
+            response_headers: BTreeMap::new(),
         }
     }
 }
@@ -595,9 +837,23 @@ impl crate::TestableForRegression for IndexConfig {
         let ingest_settings = IngestSettings {
             min_shards: NonZeroUsize::new(12).unwrap(),
             validate_docs: true,
+            write_block: false,
+            upsert_id_field: None,
+            content_dedup_window_secs: None,
+            accepted_content_types: Vec::new(),
+            ingest_filter: None,
+            indexing_pause: None,
+            write_alias: None,
         };
         let search_settings = SearchSettings {
             default_search_fields: vec!["message".to_string()],
+            read_block: false,
+            default_sort: Vec::new(),
+            max_response_size: None,
+            max_aggregation_buckets: None,
+            missing_fields: MissingFields::Omit,
+            default_operator: None,
+            default_max_hits: None,
         };
         let retention_policy_opt = Some(RetentionPolicy {
             retention_period: "90 days".to_string(),
@@ -611,6 +867,7 @@ impl crate::TestableForRegression for IndexConfig {
             ingest_settings,
             search_settings,
             retention_policy_opt,
+            response_headers: BTreeMap::new(),
         }
     }
 
@@ -622,7 +879,106 @@ impl crate::TestableForRegression for IndexConfig {
         assert_eq!(self.ingest_settings, other.ingest_settings);
         assert_eq!(self.search_settings, other.search_settings);
         assert_eq!(self.retention_policy_opt, other.retention_policy_opt);
+        assert_eq!(self.response_headers, other.response_headers);
+    }
+}
+
+/// Levels of sensitivity of the data, from the least to the most sensitive, used to merge the
+/// values of the response headers declared by different indexes.
+const SENSITIVITY_LEVELS: [&str; 4] = ["public", "internal", "confidential", "restricted"];
+
+/// Compares the sensitivity of two response header values. The values follow the
+/// `SENSITIVITY_LEVELS` (case-insensitive), and the other values rank above them, as custom
+/// classifications such as `pii` are assumed to be stricter. Among the other values, numbers rank
+/// below words and compare as numbers, and words compare in lexicographic order.
+fn compare_sensitivity(left: &str, right: &str) -> Ordering {
+    let sensitivity_level = |value: &str| {
+        SENSITIVITY_LEVELS
+            .iter()
+            .position(|level| value.eq_ignore_ascii_case(level))
+    };
+    match (sensitivity_level(left), sensitivity_level(right)) {
+        (Some(left_level), Some(right_level)) => left_level.cmp(&right_level),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => match (left.parse::<f64>(), right.parse::<f64>()) {
+            (Ok(left_number), Ok(right_number)) => left_number
+                .total_cmp(&right_number)
+                .then_with(|| left.cmp(right)),
+            (Ok(_), Err(_)) => Ordering::Less,
+            (Err(_), Ok(_)) => Ordering::Greater,
+            (Err(_), Err(_)) => left.cmp(right),
+        },
+    }
+}
+
+/// Merges the response headers declared in the configs of the indexes targeted by a request. Header
+/// names are case-insensitive. When the indexes declare different values for the same header, the
+/// most sensitive value wins (see [`compare_sensitivity`]), so that the response carries the
+/// strictest classification of the data it touches, whatever the order of the indexes.
+pub fn merge_response_headers<'a>(
+    index_configs: impl IntoIterator<Item = &'a IndexConfig>,
+) -> BTreeMap<String, String> {
+    let mut merged_response_headers: BTreeMap<String, &str> = BTreeMap::new();
+
+    for index_config in index_configs {
+        for (header_name, header_value) in &index_config.response_headers {
+            merged_response_headers
+                .entry(header_name.to_ascii_lowercase())
+                .and_modify(|merged_header_value| {
+                    if compare_sensitivity(header_value, merged_header_value).is_gt() {
+                        *merged_header_value = header_value;
+                    }
+                })
+                .or_insert(header_value);
+        }
     }
+    merged_response_headers
+        .into_iter()
+        .map(|(header_name, header_value)| (header_name, header_value.to_string()))
+        .collect()
+}
+
+/// Headers managed by the HTTP server or by the REST API that an index cannot add to the responses:
+/// hop-by-hop headers, headers describing the body of the response, and cookies.
+const RESERVED_RESPONSE_HEADER_NAMES: &[&str] = &[
+    "connection",
+    "content-encoding",
+    "content-length",
+    "content-range",
+    "content-type",
+    "date",
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "set-cookie",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
+
+/// Prefix of the CORS headers, which are set by the REST API from the node config.
+const RESERVED_RESPONSE_HEADER_PREFIX: &str = "access-control-";
+
+pub(super) fn validate_response_headers(
+    response_headers: &BTreeMap<String, String>,
+) -> anyhow::Result<()> {
+    for (header_name, header_value) in response_headers {
+        let parsed_header_name = HeaderName::from_bytes(header_name.as_bytes())
+            .with_context(|| format!("invalid response header name `{header_name}`"))?;
+        // `HeaderName` normalizes the names to lowercase.
+        if RESERVED_RESPONSE_HEADER_NAMES.contains(&parsed_header_name.as_str())
+            || parsed_header_name
+                .as_str()
+                .starts_with(RESERVED_RESPONSE_HEADER_PREFIX)
+        {
+            bail!("response header `{header_name}` is reserved and cannot be set by an index");
+        }
+        HeaderValue::from_str(header_value)
+            .with_context(|| format!("invalid value for response header `{header_name}`"))?;
+    }
+    Ok(())
 }
 
 /// Builds and returns the doc mapper associated with an index.
@@ -644,13 +1000,30 @@ pub fn build_doc_mapper(
 pub(super) fn validate_index_config(
     doc_mapping: &DocMapping,
     indexing_settings: &IndexingSettings,
+    ingest_settings: &IngestSettings,
     search_settings: &SearchSettings,
     retention_policy_opt: &Option<RetentionPolicy>,
 ) -> anyhow::Result<()> {
     // Note: this needs a deep refactoring to separate the doc mapping configuration,
     // and doc mapper implementations.
     // TODO see if we should store the byproducton the IndexConfig.
-    build_doc_mapper(doc_mapping, search_settings)?;
+    let doc_mapper = build_doc_mapper(doc_mapping, search_settings)?;
+    search_settings.validate_default_sort(&doc_mapper)?;
+    ingest_settings.validate(&doc_mapper)?;
+
+    if let Some(max_response_size) = search_settings.max_response_size {
+        ensure!(
+            max_response_size.as_u64() > 0,
+            "search settings `max_response_size` must be strictly positive"
+        );
+    }
+    if let Some(max_aggregation_buckets) = search_settings.max_aggregation_buckets {
+        ensure!(
+            max_aggregation_buckets > 0,
+            "search settings `max_aggregation_buckets` must be strictly positive"
+        );
+    }
+    search_settings.validate_query_defaults()?;
 
     indexing_settings.merge_policy.validate()?;
     indexing_settings.resources.validate()?;
@@ -807,6 +1180,13 @@ mod tests {
             index_config.search_settings,
             SearchSettings {
                 default_search_fields: vec!["severity_text".to_string(), "body".to_string()],
+                read_block: false,
+                default_sort: Vec::new(),
+                max_response_size: None,
+                max_aggregation_buckets: None,
+                missing_fields: MissingFields::Omit,
+                default_operator: None,
+                default_max_hits: None,
             }
         );
     }
@@ -849,6 +1229,13 @@ mod tests {
 
             let expected_search_settings = SearchSettings {
                 default_search_fields: vec!["body".to_string()],
+                read_block: false,
+                default_sort: Vec::new(),
+                max_response_size: None,
+                max_aggregation_buckets: None,
+                missing_fields: MissingFields::Omit,
+                default_operator: None,
+                default_max_hits: None,
             };
             assert_eq!(index_config.search_settings, expected_search_settings);
             assert!(index_config.retention_policy_opt.is_none());
@@ -884,6 +1271,13 @@ mod tests {
                 index_config.search_settings,
                 SearchSettings {
                     default_search_fields: vec!["body".to_string()],
+                    read_block: false,
+                    default_sort: Vec::new(),
+                    max_response_size: None,
+                    max_aggregation_buckets: None,
+                    missing_fields: MissingFields::Omit,
+                    default_operator: None,
+                    default_max_hits: None,
                 }
             );
         }
@@ -948,6 +1342,257 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_index_config_default_sort() {
+        let config_yaml = |default_sort_field: &str| {
+            format!(
+                r#"
+                version: 0.8
+                index_id: hdfs-logs
+                doc_mapping:
+                  mode: strict
+                  field_mappings:
+                    - name: timestamp
+                      type: datetime
+                      fast: true
+                    - name: body
+                      type: text
+                search_settings:
+                  default_sort:
+                    - field: {default_sort_field}
+                "#
+            )
+        };
+        let index_config = load_index_config_from_user_config(
+            ConfigFormat::Yaml,
+            config_yaml("timestamp").as_bytes(),
+            &Uri::for_test("s3://my-index"),
+        )
+        .unwrap();
+        assert_eq!(
+            index_config.search_settings.default_sort,
+            [DefaultSortField {
+                field: "timestamp".to_string(),
+                order: SortOrder::Desc,
+            }]
+        );
+
+        let error = load_index_config_from_user_config(
+            ConfigFormat::Yaml,
+            config_yaml("body").as_bytes(),
+            &Uri::for_test("s3://my-index"),
+        )
+        .unwrap_err();
+        assert!(
+            error.to_string().contains("must be a fast field"),
+            "{error:?}"
+        );
+
+        let error = load_index_config_from_user_config(
+            ConfigFormat::Yaml,
+            config_yaml("unknown").as_bytes(),
+            &Uri::for_test("s3://my-index"),
+        )
+        .unwrap_err();
+        assert!(
+            error.to_string().contains("unknown default sort field"),
+            "{error:?}"
+        );
+    }
+
+    #[test]
+    fn test_index_config_upsert_id_field() {
+        let config_yaml = |upsert_id_field: &str| {
+            format!(
+                r#"
+                version: 0.8
+                index_id: hdfs-logs
+                doc_mapping:
+                  mode: strict
+                  field_mappings:
+                    - name: id
+                      type: text
+                      tokenizer: raw
+                      fast: true
+                    - name: body
+                      type: text
+                ingest_settings:
+                  upsert_id_field: {upsert_id_field}
+                "#
+            )
+        };
+        let index_config = load_index_config_from_user_config(
+            ConfigFormat::Yaml,
+            config_yaml("id").as_bytes(),
+            &Uri::for_test("s3://my-index"),
+        )
+        .unwrap();
+        assert_eq!(
+            index_config.ingest_settings.upsert_id_field.as_deref(),
+            Some("id")
+        );
+
+        let error = load_index_config_from_user_config(
+            ConfigFormat::Yaml,
+            config_yaml("body").as_bytes(),
+            &Uri::for_test("s3://my-index"),
+        )
+        .unwrap_err();
+        assert!(
+            error.to_string().contains("must be an indexed fast field"),
+            "{error:?}"
+        );
+
+        let error = load_index_config_from_user_config(
+            ConfigFormat::Yaml,
+            config_yaml("unknown").as_bytes(),
+            &Uri::for_test("s3://my-index"),
+        )
+        .unwrap_err();
+        assert!(
+            error.to_string().contains("unknown upsert id field"),
+            "{error:?}"
+        );
+    }
+
+    #[test]
+    fn test_index_config_max_response_size() {
+        let config_yaml = |max_response_size: &str| {
+            format!(
+                r#"
+                version: 0.8
+                index_id: hdfs-logs
+                doc_mapping:
+                  field_mappings:
+                    - name: body
+                      type: text
+                search_settings:
+                  max_response_size: {max_response_size}
+                "#
+            )
+        };
+        let index_config = load_index_config_from_user_config(
+            ConfigFormat::Yaml,
+            config_yaml("10 MB").as_bytes(),
+            &Uri::for_test("s3://my-index"),
+        )
+        .unwrap();
+        assert_eq!(
+            index_config.search_settings.max_response_size,
+            Some(ByteSize::mb(10))
+        );
+
+        let error = load_index_config_from_user_config(
+            ConfigFormat::Yaml,
+            config_yaml("0").as_bytes(),
+            &Uri::for_test("s3://my-index"),
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("strictly positive"), "{error:?}");
+    }
+
+    #[test]
+    fn test_index_config_max_aggregation_buckets() {
+        let config_yaml = |max_aggregation_buckets: u32| {
+            format!(
+                r#"
+                version: 0.8
+                index_id: hdfs-logs
+                doc_mapping:
+                  field_mappings:
+                    - name: body
+                      type: text
+                search_settings:
+                  max_aggregation_buckets: {max_aggregation_buckets}
+                "#
+            )
+        };
+        let index_config = load_index_config_from_user_config(
+            ConfigFormat::Yaml,
+            config_yaml(1_000).as_bytes(),
+            &Uri::for_test("s3://my-index"),
+        )
+        .unwrap();
+        assert_eq!(
+            index_config.search_settings.max_aggregation_buckets,
+            Some(1_000)
+        );
+
+        let error = load_index_config_from_user_config(
+            ConfigFormat::Yaml,
+            config_yaml(0).as_bytes(),
+            &Uri::for_test("s3://my-index"),
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("strictly positive"), "{error:?}");
+    }
+
+    #[test]
+    fn test_index_config_missing_fields() {
+        let config_yaml = r#"
+            version: 0.8
+            index_id: hdfs-logs
+            doc_mapping:
+              field_mappings:
+                - name: body
+                  type: text
+            search_settings:
+              missing_fields: as_null
+        "#;
+        let index_config = load_index_config_from_user_config(
+            ConfigFormat::Yaml,
+            config_yaml.as_bytes(),
+            &Uri::for_test("s3://my-index"),
+        )
+        .unwrap();
+        assert_eq!(
+            index_config.search_settings.missing_fields,
+            MissingFields::AsNull
+        );
+
+        let search_settings_json = serde_json::to_value(SearchSettings::default()).unwrap();
+        assert!(search_settings_json.get("missing_fields").is_none());
+    }
+
+    #[test]
+    fn test_index_config_query_defaults() {
+        let config_yaml = |default_max_hits: u64| {
+            format!(
+                r#"
+                version: 0.8
+                index_id: hdfs-logs
+                doc_mapping:
+                  field_mappings:
+                    - name: body
+                      type: text
+                search_settings:
+                  default_search_fields: [body]
+                  default_operator: OR
+                  default_max_hits: {default_max_hits}
+                "#
+            )
+        };
+        let index_config = load_index_config_from_user_config(
+            ConfigFormat::Yaml,
+            config_yaml(50).as_bytes(),
+            &Uri::for_test("s3://my-index"),
+        )
+        .unwrap();
+        assert_eq!(
+            index_config.search_settings.default_operator,
+            Some(BooleanOperand::Or)
+        );
+        assert_eq!(index_config.search_settings.default_max_hits, Some(50));
+
+        let error = load_index_config_from_user_config(
+            ConfigFormat::Yaml,
+            config_yaml(10_001).as_bytes(),
+            &Uri::for_test("s3://my-index"),
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("at most 10,000"), "{error:?}");
+    }
+
     #[test]
     fn test_retention_policy_serialization() {
         let retention_policy = RetentionPolicy {
@@ -1115,6 +1760,13 @@ mod tests {
         let settings = IngestSettings {
             min_shards: NonZeroUsize::MIN,
             validate_docs: false,
+            write_block: false,
+            upsert_id_field: None,
+            content_dedup_window_secs: None,
+            accepted_content_types: Vec::new(),
+            ingest_filter: None,
+            indexing_pause: None,
+            write_alias: None,
         };
         let settings_yaml = serde_yaml::to_string(&settings).unwrap();
         assert!(settings_yaml.contains("validate_docs"));
@@ -1125,13 +1777,50 @@ mod tests {
         let settings = IngestSettings {
             min_shards: NonZeroUsize::MIN,
             validate_docs: true,
+            write_block: false,
+            upsert_id_field: None,
+            content_dedup_window_secs: None,
+            accepted_content_types: Vec::new(),
+            ingest_filter: None,
+            indexing_pause: None,
+            write_alias: None,
         };
         let settings_yaml = serde_yaml::to_string(&settings).unwrap();
         assert!(!settings_yaml.contains("validate_docs"));
+        assert!(!settings_yaml.contains("write_block"));
+
+        let expected_settings: IngestSettings = serde_yaml::from_str(&settings_yaml).unwrap();
+        assert_eq!(settings, expected_settings);
+
+        let settings = IngestSettings {
+            min_shards: NonZeroUsize::MIN,
+            validate_docs: true,
+            write_block: true,
+            upsert_id_field: None,
+            content_dedup_window_secs: None,
+            accepted_content_types: Vec::new(),
+            ingest_filter: None,
+            indexing_pause: None,
+            write_alias: None,
+        };
+        let settings_yaml = serde_yaml::to_string(&settings).unwrap();
+        assert!(settings_yaml.contains("write_block: true"));
 
         let expected_settings: IngestSettings = serde_yaml::from_str(&settings_yaml).unwrap();
         assert_eq!(settings, expected_settings);
 
+        let settings_yaml = r#"
+            content_dedup_window_secs: 60
+        "#;
+        let settings: IngestSettings = serde_yaml::from_str(settings_yaml).unwrap();
+        assert_eq!(settings.content_dedup_window_secs, NonZeroU64::new(60));
+
+        let settings_yaml = r#"
+            indexing_pause: reject
+        "#;
+        let settings: IngestSettings = serde_yaml::from_str(settings_yaml).unwrap();
+        assert_eq!(settings.indexing_pause, Some(IndexingPauseMode::Reject));
+
         let settings_yaml = r#"
             min_shards: 0
         "#;
@@ -1139,6 +1828,61 @@ mod tests {
         assert!(error.to_string().contains("expected a nonzero"));
     }
 
+    #[test]
+    fn test_ingest_settings_accepts_content_type() {
+        let index_config = IndexConfig::for_test("test-index", "s3://test-index");
+        let doc_mapper =
+            build_doc_mapper(&index_config.doc_mapping, &index_config.search_settings).unwrap();
+        let mut settings = IngestSettings::default();
+        assert!(settings.accepts_content_type(None));
+        assert!(settings.accepts_content_type(Some("application/x-protobuf")));
+        settings.validate(&doc_mapper).unwrap();
+
+        settings.accepted_content_types = vec!["application/x-ndjson".to_string()];
+        settings.validate(&doc_mapper).unwrap();
+        assert!(settings.accepts_content_type(Some("application/x-ndjson")));
+        assert!(settings.accepts_content_type(Some("Application/X-NDJSON; charset=utf-8")));
+        assert!(!settings.accepts_content_type(Some("application/x-protobuf")));
+        assert!(!settings.accepts_content_type(None));
+
+        settings.accepted_content_types = vec!["ndjson".to_string()];
+        let error = settings.validate(&doc_mapper).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "invalid accepted content type `ndjson`, expected `type/subtype`"
+        );
+    }
+
+    #[test]
+    fn test_ingest_settings_validate_ingest_filter() {
+        let index_config = IndexConfig::for_test("test-index", "s3://test-index");
+        let doc_mapper =
+            build_doc_mapper(&index_config.doc_mapping, &index_config.search_settings).unwrap();
+        let mut settings = IngestSettings {
+            ingest_filter: Some(r#"level in ["warn", "error"]"#.to_string()),
+            ..Default::default()
+        };
+        settings.validate(&doc_mapper).unwrap();
+
+        settings.ingest_filter = Some("level = warn".to_string());
+        settings.validate(&doc_mapper).unwrap_err();
+    }
+
+    #[test]
+    fn test_ingest_settings_validate_write_alias() {
+        let index_config = IndexConfig::for_test("test-index", "s3://test-index");
+        let doc_mapper =
+            build_doc_mapper(&index_config.doc_mapping, &index_config.search_settings).unwrap();
+        let mut settings = IngestSettings {
+            write_alias: Some("logs".to_string()),
+            ..Default::default()
+        };
+        settings.validate(&doc_mapper).unwrap();
+
+        settings.write_alias = Some("logs-*".to_string());
+        settings.validate(&doc_mapper).unwrap_err();
+    }
+
     #[test]
     fn test_prepare_doc_mapping_update() {
         let current_index_config = IndexConfig::for_test("test-index", "s3://test-index");
@@ -1230,4 +1974,50 @@ mod tests {
         assert_eq!(updated_doc_mapping.doc_mapping_uid, new_doc_mapping_uid);
         assert_eq!(updated_doc_mapping.mode, Mode::Strict);
     }
+
+    #[test]
+    fn test_merge_response_headers() {
+        let mut index_config_foo = IndexConfig::for_test("foo", "ram:///indexes/foo");
+        index_config_foo.response_headers = BTreeMap::from([
+            ("X-Data-Classification".to_string(), "pii".to_string()),
+            ("X-Owner".to_string(), "team-foo".to_string()),
+        ]);
+        let mut index_config_bar = IndexConfig::for_test("bar", "ram:///indexes/bar");
+        index_config_bar.response_headers = BTreeMap::from([(
+            "x-data-classification".to_string(),
+            "confidential".to_string(),
+        )]);
+        let index_config_baz = IndexConfig::for_test("baz", "ram:///indexes/baz");
+
+        let response_headers = merge_response_headers([&index_config_baz]);
+        assert!(response_headers.is_empty());
+
+        let response_headers = merge_response_headers([&index_config_foo]);
+        assert_eq!(response_headers.len(), 2);
+        assert_eq!(response_headers["x-data-classification"], "pii");
+        assert_eq!(response_headers["x-owner"], "team-foo");
+
+        // `pii` is not a sensitivity level, so it ranks above `confidential`, whatever the order of
+        // the indexes.
+        for index_configs in [
+            [&index_config_foo, &index_config_bar, &index_config_baz],
+            [&index_config_baz, &index_config_bar, &index_config_foo],
+        ] {
+            let response_headers = merge_response_headers(index_configs);
+            assert_eq!(response_headers.len(), 2);
+            assert_eq!(response_headers["x-data-classification"], "pii");
+            assert_eq!(response_headers["x-owner"], "team-foo");
+        }
+    }
+
+    #[test]
+    fn test_compare_sensitivity() {
+        assert!(compare_sensitivity("public", "internal").is_lt());
+        assert!(compare_sensitivity("Restricted", "confidential").is_gt());
+        assert!(compare_sensitivity("restricted", "pii").is_lt());
+        assert!(compare_sensitivity("10", "2").is_gt());
+        assert!(compare_sensitivity("10", "pii").is_lt());
+        assert!(compare_sensitivity("phi", "pii").is_lt());
+        assert!(compare_sensitivity("pii", "pii").is_eq());
+    }
 }
@@ -12,13 +12,15 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::BTreeMap;
+
 use anyhow::{Context, ensure};
 use quickwit_common::uri::Uri;
 use quickwit_proto::types::{DocMappingUid, IndexId};
 use serde::{Deserialize, Serialize};
 use tracing::info;
 
-use super::{IngestSettings, validate_index_config};
+use super::{IngestSettings, validate_index_config, validate_response_headers};
 use crate::{
     ConfigFormat, DocMapping, IndexConfig, IndexingSettings, RetentionPolicy, SearchSettings,
     prepare_doc_mapping_update, validate_identifier,
@@ -96,6 +98,15 @@ pub fn load_index_config_update(
     )?;
     new_index_config.doc_mapping = updated_doc_mapping;
 
+    // The read and write blocks, the indexing pause, and the write alias are set with their own
+    // endpoints, so replacing the config leaves them unchanged.
+    new_index_config.search_settings.read_block = current_index_config.search_settings.read_block;
+    new_index_config.ingest_settings.write_block = current_index_config.ingest_settings.write_block;
+    new_index_config.ingest_settings.indexing_pause =
+        current_index_config.ingest_settings.indexing_pause;
+    new_index_config.ingest_settings.write_alias =
+        current_index_config.ingest_settings.write_alias.clone();
+
     Ok(new_index_config)
 }
 
@@ -134,13 +145,16 @@ impl IndexConfigForSerialization {
             ingest_settings: self.ingest_settings,
             search_settings: self.search_settings,
             retention_policy_opt: self.retention_policy_opt,
+            response_headers: self.response_headers,
         };
         validate_index_config(
             &index_config.doc_mapping,
             &index_config.indexing_settings,
+            &index_config.ingest_settings,
             &index_config.search_settings,
             &index_config.retention_policy_opt,
         )?;
+        validate_response_headers(&index_config.response_headers)?;
         Ok(index_config)
     }
 }
@@ -184,6 +198,8 @@ pub struct IndexConfigV0_8 {
     #[serde(rename = "retention")]
     #[serde(default)]
     pub retention_policy_opt: Option<RetentionPolicy>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub response_headers: BTreeMap<String, String>,
 }
 
 impl From<IndexConfig> for IndexConfigV0_8 {
@@ -196,6 +212,7 @@ impl From<IndexConfig> for IndexConfigV0_8 {
             ingest_settings: index_config.ingest_settings,
             search_settings: index_config.search_settings,
             retention_policy_opt: index_config.retention_policy_opt,
+            response_headers: index_config.response_headers,
         }
     }
 }
@@ -203,6 +220,7 @@ impl From<IndexConfig> for IndexConfigV0_8 {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::IndexingPauseMode;
     use crate::merge_policy_config::{MergePolicyConfig, StableLogMergePolicyConfig};
 
     fn minimal_index_config_for_serialization() -> IndexConfigForSerialization {
@@ -263,6 +281,50 @@ mod test {
         assert!(validation_err.contains("retention policy requires a timestamp field"));
     }
 
+    #[test]
+    fn test_validate_response_headers() {
+        let mut index_config = minimal_index_config_for_serialization();
+        index_config.response_headers =
+            BTreeMap::from([("X-Data-Classification".to_string(), "pii".to_string())]);
+        index_config.clone().build_and_validate(None).unwrap();
+
+        index_config.response_headers =
+            BTreeMap::from([("X Data Classification".to_string(), "pii".to_string())]);
+        let validation_err = index_config
+            .clone()
+            .build_and_validate(None)
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            validation_err,
+            "invalid response header name `X Data Classification`"
+        );
+
+        for reserved_header_name in [
+            "Content-Type",
+            "content-length",
+            "Transfer-Encoding",
+            "Connection",
+            "Set-Cookie",
+            "Access-Control-Allow-Origin",
+        ] {
+            index_config.response_headers =
+                BTreeMap::from([(reserved_header_name.to_string(), "value".to_string())]);
+            let validation_err = index_config
+                .clone()
+                .build_and_validate(None)
+                .unwrap_err()
+                .to_string();
+            assert_eq!(
+                validation_err,
+                format!(
+                    "response header `{reserved_header_name}` is reserved and cannot be set by an \
+                     index"
+                )
+            );
+        }
+    }
+
     #[test]
     fn test_minimal_index_config_missing_root_uri_no_default_uri() {
         let config_yaml = r#"
@@ -453,6 +515,44 @@ mod test {
         assert_eq!(updated_config.doc_mapping.field_mappings.len(), 1);
     }
 
+    #[test]
+    fn test_update_preserves_blocks_and_indexing_pause() {
+        let config_yaml = r#"
+            version: 0.8
+            index_id: hdfs-logs
+            doc_mapping: {}
+        "#;
+        let default_root = Uri::for_test("s3://mybucket");
+        let mut original_config: IndexConfig = load_index_config_from_user_config(
+            ConfigFormat::Yaml,
+            config_yaml.as_bytes(),
+            &default_root,
+        )
+        .unwrap();
+        original_config.search_settings.read_block = true;
+        original_config.ingest_settings.write_block = true;
+        original_config.ingest_settings.indexing_pause = Some(IndexingPauseMode::Reject);
+        original_config.ingest_settings.write_alias = Some("hdfs".to_string());
+
+        let updated_config = load_index_config_update(
+            ConfigFormat::Yaml,
+            config_yaml.as_bytes(),
+            &default_root,
+            &original_config,
+        )
+        .unwrap();
+        assert!(updated_config.search_settings.read_block);
+        assert!(updated_config.ingest_settings.write_block);
+        assert_eq!(
+            updated_config.ingest_settings.indexing_pause,
+            Some(IndexingPauseMode::Reject)
+        );
+        assert_eq!(
+            updated_config.ingest_settings.write_alias.as_deref(),
+            Some("hdfs")
+        );
+    }
+
     #[test]
     fn test_update_doc_mappings_failing_cases() {
         let original_config_yaml = r#"
@@ -14,6 +14,8 @@
 
 mod serialize;
 
+use std::collections::BTreeMap;
+
 use anyhow::ensure;
 use quickwit_common::uri::Uri;
 use quickwit_proto::types::{DocMappingUid, IndexId};
@@ -77,6 +79,7 @@ impl IndexTemplate {
             ingest_settings: self.ingest_settings.clone(),
             search_settings: self.search_settings.clone(),
             retention_policy_opt: self.retention_policy_opt.clone(),
+            response_headers: BTreeMap::new(),
         };
         Ok(index_config)
     }
@@ -94,6 +97,7 @@ impl IndexTemplate {
         validate_index_config(
             &self.doc_mapping,
             &self.indexing_settings,
+            &self.ingest_settings,
             &self.search_settings,
             &self.retention_policy_opt,
         )?;
@@ -189,6 +193,7 @@ impl crate::TestableForRegression for IndexTemplate {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::MissingFields;
 
     #[test]
     fn test_index_template_serde() {
@@ -232,6 +237,13 @@ mod tests {
         };
         index_template.search_settings = SearchSettings {
             default_search_fields: vec!["message".to_string()],
+            read_block: false,
+            default_sort: Vec::new(),
+            max_response_size: None,
+            max_aggregation_buckets: None,
+            missing_fields: MissingFields::Omit,
+            default_operator: None,
+            default_max_hits: None,
         };
         index_template.retention_policy_opt = Some(RetentionPolicy {
             retention_period: "42 days".to_string(),
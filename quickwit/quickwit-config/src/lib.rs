@@ -44,9 +44,10 @@ pub use cluster_config::ClusterConfig;
 // See #2048
 use index_config::serialize::{IndexConfigV0_8, VersionedIndexConfig};
 pub use index_config::{
-    IndexConfig, IndexingResources, IndexingSettings, IngestSettings, ParquetIndexingConfig,
-    RetentionPolicy, SearchSettings, build_doc_mapper, load_index_config_from_user_config,
-    load_index_config_update, prepare_doc_mapping_update,
+    DefaultSortField, IndexConfig, IndexingPauseMode, IndexingResources, IndexingSettings,
+    IngestFilter, IngestSettings, MissingFields, ParquetIndexingConfig, RetentionPolicy,
+    SearchSettings, build_doc_mapper, load_index_config_from_user_config, load_index_config_update,
+    merge_response_headers, prepare_doc_mapping_update,
 };
 pub use quickwit_doc_mapper::DocMapping;
 use serde::Serialize;
@@ -73,10 +74,15 @@ pub use crate::metastore_config::{
     MetastoreBackend, MetastoreConfig, MetastoreConfigs, PostgresMetastoreConfig,
 };
 pub use crate::node_config::{
-    CacheConfig, CachePolicy, CompactorConfig, DEFAULT_QW_CONFIG_PATH, GrpcConfig, HealthConfig,
-    IndexerConfig, IngestApiConfig, JaegerConfig, KeepAliveConfig, LambdaConfig,
-    LambdaDeployConfig, NodeConfig, RestConfig, SearcherConfig, SplitCacheLimits,
-    StorageTimeoutPolicy, TlsConfig,
+    AccessLogConfig, AccessLogVerbosity, CacheConfig, CachePolicy, CompactorConfig,
+    CompressionAlgorithm, DEFAULT_QW_CONFIG_PATH, DeadLetterEviction, DeadLetterQueueConfig,
+    GrpcConfig, HealthConfig, IndexerConfig, IngestApiConfig, IngestBackpressureConfig,
+    IngestBackpressureMode, IngestBodyBuffering, IngestUploadSessionConfig, JaegerConfig,
+    JwtConfig, KeepAliveConfig, LambdaConfig, LambdaDeployConfig, NodeConfig,
+    RequestDurationBucketsConfig, RestCompressionConfig, RestConfig, RestCorsPolicy,
+    RestNotFoundConfig, RestRateLimitConfig, RestRequestFilterRule, RestResponseFormat,
+    RestRootRoute, RestUnknownQueryParams, SearchConcurrencyConfig, SearchRequestCompressionConfig,
+    SearcherConfig, SplitCacheLimits, StorageTimeoutPolicy, TlsConfig, parse_rest_and_grpc_configs,
 };
 pub use crate::serde_utils::HumanDuration;
 use crate::source_config::serialize::{SourceConfigV0_7, SourceConfigV0_8, VersionedSourceConfig};
@@ -99,12 +105,14 @@ pub fn disable_ingest_v1() -> bool {
 #[derive(utoipa::OpenApi)]
 #[openapi(components(schemas(
     ConstWriteAmplificationMergePolicyConfig,
+    DefaultSortField,
     DocMapping,
     FileSourceMessageType,
     FileSourceNotification,
     FileSourceParamsForSerde,
     FileSourceSqs,
     IndexConfigV0_8,
+    IndexingPauseMode,
     IndexingResources,
     IndexingSettings,
     IndexTemplateV0_8,
@@ -112,6 +120,7 @@ pub fn disable_ingest_v1() -> bool {
     KafkaSourceParams,
     KinesisSourceParams,
     MergePolicyConfig,
+    MissingFields,
     ParquetIndexingConfig,
     ParquetMergePolicyConfig,
     PubSubSourceParams,
@@ -21,7 +21,7 @@ use std::num::{NonZeroU32, NonZeroU64, NonZeroUsize};
 use std::path::PathBuf;
 use std::time::Duration;
 
-use anyhow::{bail, ensure};
+use anyhow::{Context, bail, ensure};
 use bytesize::ByteSize;
 use http::HeaderMap;
 use quickwit_common::net::HostAddr;
@@ -36,10 +36,11 @@ use serde::{Deserialize, Deserializer, Serialize};
 use tracing::{info, warn};
 
 use crate::node_config::serialize::load_node_config_with_env;
+pub use crate::node_config::serialize::parse_rest_and_grpc_configs;
 use crate::serde_utils::HumanDuration;
 use crate::service::QuickwitService;
 use crate::storage_config::StorageConfigs;
-use crate::{ConfigFormat, MetastoreConfigs};
+use crate::{ConfigFormat, MetastoreConfigs, validate_index_id_pattern};
 
 pub const DEFAULT_QW_CONFIG_PATH: &str = "config/quickwit.yaml";
 
@@ -48,6 +49,9 @@ pub const DEFAULT_QW_CONFIG_PATH: &str = "config/quickwit.yaml";
 pub struct RestConfig {
     pub listen_addr: SocketAddr,
     pub cors_allow_origins: Vec<String>,
+    // CORS policies of specific origins, overriding the policy applied to `cors_allow_origins`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cors_policies: Vec<RestCorsPolicy>,
     #[serde(with = "http_serde::header_map")]
     pub extra_headers: HeaderMap,
     #[serde(default, rename = "tls")]
@@ -59,6 +63,319 @@ pub struct RestConfig {
     // See `GrpcConfig::max_connection_age_grace`.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub max_connection_age_grace: Option<HumanDuration>,
+    // Closes the connections that have not read nor written anything for this long, so idle
+    // keep-alive connections do not hold resources. In-flight requests are allowed to complete.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idle_connection_timeout: Option<HumanDuration>,
+    // Closes the connections pipelining more than this number of HTTP/1.1 requests, i.e. sending
+    // them without waiting for the responses to the previous ones.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_pipelined_requests: Option<NonZeroUsize>,
+    // Maximum size of the headers of a request, answered with a `431 Request Header Fields Too
+    // Large` when exceeded, before any handler runs. For HTTP/1, it bounds the whole request head.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_header_list_size: Option<ByteSize>,
+    // Maximum number of headers of an HTTP/1 request, answered with a `431` when exceeded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_header_count: Option<NonZeroUsize>,
+    #[serde(default)]
+    pub access_log: AccessLogConfig,
+    // Read when the metrics recorder is built, before the node starts.
+    #[serde(default)]
+    pub request_duration_buckets: RequestDurationBucketsConfig,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jwt: Option<JwtConfig>,
+    #[serde(default)]
+    pub compression: RestCompressionConfig,
+    #[serde(default)]
+    pub not_found: RestNotFoundConfig,
+    #[serde(default)]
+    pub default_response_format: RestResponseFormat,
+    #[serde(default)]
+    pub root_route: RestRootRoute,
+    // Number of times clients may retry a request failing with a retryable error, advertised in
+    // the `X-Quickwit-Retry-Budget` header of these errors.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_budget: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate_limit: Option<RestRateLimitConfig>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub request_filters: Vec<RestRequestFilterRule>,
+    // Lets the GET search endpoint wrap its JSON responses in the function named by the
+    // `callback` parameter (JSONP), for legacy clients unable to issue cross-origin requests.
+    // Disabled by default: any page can then read the search results with the credentials of
+    // the user visiting it.
+    #[serde(default)]
+    pub enable_jsonp: bool,
+    #[serde(default)]
+    pub unknown_query_params: RestUnknownQueryParams,
+    // Serves the endpoint validating proposed node configs. Disabled by default.
+    #[serde(default)]
+    pub enable_config_validation: bool,
+    // Lets the clients of a node not verifying tokens make it leave the cluster. When tokens are
+    // verified, the token of the request must have the `admin` claim instead.
+    #[serde(default)]
+    pub enable_cluster_leave: bool,
+    // Lets the clients of a node not verifying tokens update the CORS origins and extra headers
+    // of its REST API. When tokens are verified, the token of the request must have the `admin`
+    // claim instead.
+    #[serde(default)]
+    pub enable_rest_config_updates: bool,
+}
+
+/// Verbosity of the REST access log for a group of routes.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AccessLogVerbosity {
+    /// No request is logged.
+    #[default]
+    None,
+    /// Only requests answered with a 4xx or 5xx status code are logged.
+    Errors,
+    /// Every request is logged.
+    All,
+}
+
+/// Access log verbosity of each group of REST routes. High-volume routes can be logged at a
+/// lower verbosity than the others to keep the log volume manageable.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AccessLogConfig {
+    /// Ingest routes: native ingest, Elasticsearch `_bulk`, and OTLP.
+    #[serde(default)]
+    pub ingest: AccessLogVerbosity,
+    /// Search routes: native and Elasticsearch search, count, field caps, and Jaeger.
+    #[serde(default)]
+    pub search: AccessLogVerbosity,
+    /// All the other routes (index management, cluster, health checks, ...).
+    #[serde(default)]
+    pub other: AccessLogVerbosity,
+}
+
+/// Bucket boundaries of the REST request duration histograms, in seconds, in strictly ascending
+/// order. The histograms left unset keep their default buckets.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RequestDurationBucketsConfig {
+    /// Buckets of `request_duration_secs`, covering all the routes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub all: Option<Vec<f64>>,
+    /// Buckets of `search_request_duration_secs`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub search: Option<Vec<f64>>,
+    /// Buckets of `ingest_request_duration_secs`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ingest: Option<Vec<f64>>,
+}
+
+impl RequestDurationBucketsConfig {
+    pub(crate) fn validate(&self) -> anyhow::Result<()> {
+        let buckets_per_histogram = [
+            ("all", &self.all),
+            ("search", &self.search),
+            ("ingest", &self.ingest),
+        ];
+        for (histogram, buckets_opt) in buckets_per_histogram {
+            let Some(buckets) = buckets_opt else {
+                continue;
+            };
+            let is_strictly_ascending = buckets.windows(2).all(|pair| pair[0] < pair[1]);
+            ensure!(
+                !buckets.is_empty() && is_strictly_ascending,
+                "`rest.request_duration_buckets.{histogram}` must be a non-empty list of numbers \
+                 in strictly ascending order"
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Compression of the REST responses. Small responses are compressed with the fastest quality,
+/// while large ones, typically exports, are compressed with the best quality.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RestCompressionConfig {
+    /// Responses smaller than this size are not compressed. When unset, the
+    /// `QW_MINIMUM_COMPRESSION_SIZE` environment variable is used, and responses are not
+    /// compressed at all if it is not set either.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_size: Option<ByteSize>,
+    /// Responses of at least this size are compressed with the best quality instead of the
+    /// fastest one.
+    #[serde(default = "RestCompressionConfig::default_best_quality_min_size")]
+    pub best_quality_min_size: ByteSize,
+}
+
+impl RestCompressionConfig {
+    fn default_best_quality_min_size() -> ByteSize {
+        ByteSize::mib(1)
+    }
+}
+
+impl Default for RestCompressionConfig {
+    fn default() -> Self {
+        Self {
+            min_size: None,
+            best_quality_min_size: Self::default_best_quality_min_size(),
+        }
+    }
+}
+
+/// Body of the responses to the requests matching no REST route. The HTML body is returned to
+/// the clients accepting `text/html`, typically browsers, and the JSON body to the other ones.
+/// When a body is unset, the default `{"message": "Route not found"}` JSON body is returned.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RestNotFoundConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub json_body: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub html_body: Option<String>,
+}
+
+/// Format of the REST responses to the requests not setting the `format` query parameter. The
+/// Elasticsearch-compatible, OTLP, and Jaeger APIs always respond in JSON.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RestResponseFormat {
+    Json,
+    #[default]
+    PrettyJson,
+    Yaml,
+}
+
+/// Response to the `GET /` requests. Headless deployments, whose UI may be disabled or
+/// unreachable, can point clients to the API instead of redirecting them to the UI.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RestRootRoute {
+    /// Redirects to the search page of the UI.
+    #[default]
+    RedirectToUi,
+    /// Returns a JSON document listing the entry points of the API.
+    ApiIndex,
+    /// Responds like a request matching no route.
+    NotFound,
+}
+
+/// Handling of the query string parameters unknown to the search, ingest, and index API routes.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RestUnknownQueryParams {
+    /// Leaves the unknown parameters to the routes, most of which ignore them.
+    #[default]
+    Lenient,
+    /// Rejects the requests with unknown parameters with a 400 naming them.
+    Strict,
+}
+
+/// Token bucket limiting the rate of the API requests served by the node, shared by all the
+/// clients. The requests exceeding it are rejected with a 429.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RestRateLimitConfig {
+    /// Number of requests added to the bucket every second.
+    pub requests_per_sec: NonZeroU64,
+    /// Capacity of the bucket, i.e. the number of requests that can be served in a burst.
+    pub burst: NonZeroU64,
+}
+
+/// CORS policy of the REST requests sent from a set of origins. The requests from the other
+/// origins get the policy of `cors_allow_origins`.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RestCorsPolicy {
+    // Origins the policy applies to, e.g. `https://app.example.com`. A `*` matches a single DNS
+    // label or a port, e.g. `https://*.example.com`.
+    // Wildcards are not allowed along with `allow_credentials`.
+    pub origins: Vec<String>,
+    // Methods the requests from these origins may use.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allow_methods: Vec<String>,
+    // Headers the requests from these origins may carry.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allow_headers: Vec<String>,
+    // Lets the requests from these origins carry credentials (cookies, authorization headers).
+    #[serde(default)]
+    pub allow_credentials: bool,
+}
+
+impl RestCorsPolicy {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        ensure!(
+            !self.origins.is_empty(),
+            "a CORS policy must apply to at least one origin"
+        );
+        for origin in &self.origins {
+            http::HeaderValue::from_str(origin)
+                .with_context(|| format!("invalid origin `{origin}`"))?;
+            // The policy mirrors the origin of the request, so a wildcard would let any matching
+            // origin, possibly taken over, send credentialed requests.
+            ensure!(
+                !self.allow_credentials || !origin.contains('*'),
+                "a CORS policy allowing credentials must list exact origins, found `{origin}`"
+            );
+        }
+        for method in &self.allow_methods {
+            http::Method::from_bytes(method.to_ascii_uppercase().as_bytes())
+                .with_context(|| format!("invalid method `{method}`"))?;
+        }
+        for header_name in &self.allow_headers {
+            http::HeaderName::from_bytes(header_name.as_bytes())
+                .with_context(|| format!("invalid header name `{header_name}`"))?;
+        }
+        Ok(())
+    }
+}
+
+/// Rule rejecting the REST requests it matches with a 403 before they reach the handlers. A
+/// request matches a rule if it meets all the conditions of the rule.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RestRequestFilterRule {
+    // Regular expression the path of the request must match, e.g. `^/api/v1/[^/]+/delete-tasks`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    // Methods, one of which the request must use.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub methods: Vec<String>,
+    // Name of a header the request must carry.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub header: Option<String>,
+    // Regular expression the value of `header` must match. Requires `header` to be set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub header_value: Option<String>,
+}
+
+impl RestRequestFilterRule {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        ensure!(
+            self.path.is_some() || !self.methods.is_empty() || self.header.is_some(),
+            "a request filtering rule must set at least one of `path`, `methods`, and `header`"
+        );
+        ensure!(
+            self.header_value.is_none() || self.header.is_some(),
+            "`header_value` requires `header` to be set"
+        );
+        if let Some(path_regex) = &self.path {
+            regex::Regex::new(path_regex)
+                .with_context(|| format!("invalid `path` regular expression `{path_regex}`"))?;
+        }
+        for method in &self.methods {
+            http::Method::from_bytes(method.to_ascii_uppercase().as_bytes())
+                .with_context(|| format!("invalid method `{method}`"))?;
+        }
+        if let Some(header_name) = &self.header {
+            http::HeaderName::from_bytes(header_name.as_bytes())
+                .with_context(|| format!("invalid header name `{header_name}`"))?;
+        }
+        if let Some(header_value_regex) = &self.header_value {
+            regex::Regex::new(header_value_regex).with_context(|| {
+                format!("invalid `header_value` regular expression `{header_value_regex}`")
+            })?;
+        }
+        Ok(())
+    }
 }
 
 /// Configuration for the optional plaintext health-check HTTP server.
@@ -177,6 +494,11 @@ impl TlsConfig {
             "`tls.cert_poll_interval` must be greater than zero, got `{}`",
             self.cert_poll_interval
         );
+        ensure!(
+            !self.verify_client_cert || !self.ca_path.is_empty(),
+            "`tls.ca_path` must be set when `tls.verify_client_cert` is enabled: client \
+             certificates are validated against the CA certificate(s) it holds"
+        );
         Ok(())
     }
 }
@@ -185,6 +507,39 @@ fn default_cert_poll_interval() -> HumanDuration {
     HumanDuration::try_from("5m".to_string()).expect("`5m`should be a valid human duration")
 }
 
+/// Verification of the JSON Web Tokens (JWT) authenticating REST requests. The claims of a valid
+/// token restrict the indexes the request can target and the documents it can read.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JwtConfig {
+    // Algorithm the tokens must be signed with, e.g. `RS256`, `ES256`, or `HS256`.
+    pub algorithm: String,
+    // Path to a file holding the verification key: a PEM-encoded public key, or the shared secret
+    // for the HMAC algorithms. Mutually exclusive with `jwks_path`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_path: Option<String>,
+    // Path to a JSON Web Key Set. The verification key is selected with the `kid` header of the
+    // token.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jwks_path: Option<String>,
+    // Expected `iss` claim. Not checked when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub issuer: Option<String>,
+    // Expected `aud` claim. Not checked when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audience: Option<String>,
+}
+
+impl JwtConfig {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        ensure!(
+            self.key_path.is_some() != self.jwks_path.is_some(),
+            "exactly one of `rest.jwt.key_path` and `rest.jwt.jwks_path` must be set"
+        );
+        Ok(())
+    }
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct IndexerConfig {
@@ -404,6 +759,14 @@ pub struct SearcherConfig {
 
     pub max_num_concurrent_split_searches: usize,
     pub max_splits_per_search: Option<usize>,
+    /// Searches whose index patterns resolve to more indexes are rejected, unless admin requests
+    /// lift the limit. Unbounded if unset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_indexes_per_search: Option<usize>,
+    /// Searches whose `start_offset + max_hits` exceeds this window are rejected, as all the hits
+    /// before `start_offset` would be collected only to be discarded. Deeper hits are paginated
+    /// with `search_after` or the scroll API.
+    pub max_result_window: u64,
     // Deprecated: stream search requests are no longer supported.
     #[serde(alias = "max_num_concurrent_split_streams", default, skip_serializing)]
     pub _max_num_concurrent_split_streams: Option<serde::de::IgnoredAny>,
@@ -416,6 +779,10 @@ pub struct SearcherConfig {
     request_timeout_secs: NonZeroU64,
     #[serde(default = "SearcherConfig::default_request_timeout_secs")]
     leaf_request_timeout_secs: NonZeroU64,
+    /// Maximum timeout search requests can set to override `request_timeout_secs`, e.g. with the
+    /// `X-Search-Timeout` header. Requests can only shorten their timeout if unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_request_timeout_secs: Option<NonZeroU64>,
     #[serde(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub storage_timeout_policy: Option<StorageTimeoutPolicy>,
@@ -433,6 +800,67 @@ pub struct SearcherConfig {
     /// fail on startup.
     #[serde(default)]
     pub lambda: Option<LambdaConfig>,
+    /// Bounds the number of root searches running concurrently on the node. Unbounded if unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub search_concurrency: Option<SearchConcurrencyConfig>,
+    /// Searches whose estimated cost (number of fields read × number of documents of the
+    /// targeted splits) exceeds this budget are rejected before being executed. Unbounded if
+    /// unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_query_cost: Option<u64>,
+    /// Compresses the large requests sent to the other searchers, such as the leaf search
+    /// requests of a root search. Disabled if unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_compression: Option<SearchRequestCompressionConfig>,
+}
+
+/// Compression of the requests sent to the other searchers. Only the requests whose encoded size
+/// reaches `min_request_size` are compressed: compressing small requests costs more CPU than it
+/// saves bandwidth.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SearchRequestCompressionConfig {
+    pub algorithm: CompressionAlgorithm,
+    #[serde(default = "SearchRequestCompressionConfig::default_min_request_size")]
+    pub min_request_size: ByteSize,
+}
+
+impl SearchRequestCompressionConfig {
+    fn default_min_request_size() -> ByteSize {
+        ByteSize::kib(64)
+    }
+
+    pub fn compression_encoding(&self) -> CompressionEncoding {
+        match self.algorithm {
+            CompressionAlgorithm::Gzip => CompressionEncoding::Gzip,
+            CompressionAlgorithm::Zstd => CompressionEncoding::Zstd,
+        }
+    }
+}
+
+/// Bounds the number of root searches running concurrently on a node. The searches exceeding the
+/// limit wait in a queue, and are rejected if the queue is full or if they wait too long.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SearchConcurrencyConfig {
+    /// Maximum number of root searches running concurrently.
+    pub max_concurrent_searches: NonZeroUsize,
+    /// Maximum number of root searches waiting for a slot.
+    #[serde(default = "SearchConcurrencyConfig::default_max_queued_searches")]
+    pub max_queued_searches: usize,
+    /// Maximum time a root search waits for a slot before being rejected.
+    #[serde(default = "SearchConcurrencyConfig::default_max_queue_wait")]
+    pub max_queue_wait: HumanDuration,
+}
+
+impl SearchConcurrencyConfig {
+    fn default_max_queued_searches() -> usize {
+        100
+    }
+
+    fn default_max_queue_wait() -> HumanDuration {
+        HumanDuration::try_from("1s".to_string()).expect("`1s` should be a valid human duration")
+    }
 }
 
 /// Configuration for AWS Lambda leaf search execution.
@@ -639,17 +1067,23 @@ impl Default for SearcherConfig {
             predicate_cache: CacheConfig::default_with_capacity(ByteSize::mb(256)),
             max_num_concurrent_split_searches: 100,
             max_splits_per_search: None,
+            max_indexes_per_search: None,
+            max_result_window: 10_000,
             _max_num_concurrent_split_streams: None,
             aggregation_memory_limit: ByteSize::mb(500),
             aggregation_bucket_limit: 65000,
             split_cache: None,
             request_timeout_secs: Self::default_request_timeout_secs(),
             leaf_request_timeout_secs: Self::default_request_timeout_secs(),
+            max_request_timeout_secs: None,
             storage_timeout_policy: None,
             use_metastore_read_replica: false,
             warmup_memory_budget: ByteSize::gb(100),
             warmup_single_split_initial_allocation: ByteSize::mb(300),
             lambda: None,
+            search_concurrency: None,
+            max_query_cost: None,
+            request_compression: None,
         }
     }
 }
@@ -663,10 +1097,27 @@ impl SearcherConfig {
     pub fn leaf_request_timeout(&self) -> Duration {
         Duration::from_secs(self.leaf_request_timeout_secs.get())
     }
+    /// The maximum timeout of the search requests overriding their timeout, which is also the
+    /// timeout applied at the gRPC layer
+    pub fn max_request_timeout(&self) -> Duration {
+        let max_request_timeout_secs = self
+            .max_request_timeout_secs
+            .unwrap_or(self.request_timeout_secs);
+        Duration::from_secs(max_request_timeout_secs.get())
+    }
     fn default_request_timeout_secs() -> NonZeroU64 {
         NonZeroU64::new(30).unwrap()
     }
     fn validate(&self) -> anyhow::Result<()> {
+        if let Some(max_request_timeout_secs) = self.max_request_timeout_secs
+            && max_request_timeout_secs < self.request_timeout_secs
+        {
+            anyhow::bail!(
+                "max_request_timeout_secs ({max_request_timeout_secs}) must be greater or equal \
+                 to request_timeout_secs ({})",
+                self.request_timeout_secs
+            );
+        }
         if let Some(split_cache_limits) = self.split_cache {
             if self.max_num_concurrent_split_searches
                 > split_cache_limits.max_file_descriptors.get() as usize
@@ -698,6 +1149,113 @@ pub enum CompressionAlgorithm {
     Zstd,
 }
 
+/// How the REST ingest endpoint reads request bodies.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IngestBodyBuffering {
+    /// The whole body is read in memory before being ingested. Its size is bounded by
+    /// `content_length_limit`.
+    #[default]
+    Full,
+    /// Uncompressed NDJSON bodies are ingested in batches as they are read, so the memory used by
+    /// a request does not depend on the size of its body. Each document is bounded by
+    /// `content_length_limit`. Compressed bodies are still read in full.
+    Streaming,
+}
+
+/// Which documents a full dead-letter queue drops to make room for new ones.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeadLetterEviction {
+    /// The oldest documents are dropped, so the queue holds the latest failures.
+    #[default]
+    DropOldest,
+    /// The new documents are dropped, so the queue holds the first failures.
+    DropNewest,
+}
+
+/// Dead-letter queue of each index, holding the documents rejected by the REST ingest endpoint
+/// because they failed to parse, so they can be replayed once the doc mapping is fixed. The
+/// queues are kept in memory by the node serving the ingest requests.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DeadLetterQueueConfig {
+    /// Maximum number of documents held by the queue of an index. The dead-letter queues are
+    /// disabled when zero.
+    #[serde(default)]
+    pub max_docs_per_index: usize,
+    #[serde(default)]
+    pub eviction: DeadLetterEviction,
+}
+
+impl DeadLetterQueueConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.max_docs_per_index > 0
+    }
+}
+
+/// Bounds of the resumable upload sessions of the REST ingest endpoint. The bytes received by a
+/// session are kept in memory by the node serving it until the session is committed or expires.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct IngestUploadSessionConfig {
+    /// Maximum size of the NDJSON body assembled by a session.
+    pub max_body_size: ByteSize,
+    /// Time after which a session that was not committed is discarded, in seconds.
+    pub max_lifetime_secs: NonZeroU64,
+    /// Maximum number of sessions in progress on the node.
+    pub max_num_sessions: NonZeroUsize,
+    /// Maximum total size of the bodies assembled by the sessions in progress on the node.
+    pub max_total_size: ByteSize,
+}
+
+impl Default for IngestUploadSessionConfig {
+    fn default() -> Self {
+        Self {
+            max_body_size: ByteSize::gib(1),
+            max_lifetime_secs: NonZeroU64::new(3600).expect("3600 should be non-zero"),
+            max_num_sessions: NonZeroUsize::new(100).expect("100 should be non-zero"),
+            max_total_size: ByteSize::gib(4),
+        }
+    }
+}
+
+/// Behavior of the REST ingest endpoints when the ingest concurrency limit, set with the
+/// `QW_INGEST_MAX_CONCURRENCY` environment variable, is reached.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IngestBackpressureMode {
+    /// The requests wait for their turn, up to `timeout_secs`.
+    #[default]
+    Block,
+    /// The requests are rejected right away with a `503 Service Unavailable`.
+    FailFast,
+}
+
+/// How the REST ingest endpoints apply backpressure to the requests exceeding the ingest
+/// concurrency limit. The rejected requests are answered with a `503 Service Unavailable` and a
+/// `Retry-After` header.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct IngestBackpressureConfig {
+    pub mode: IngestBackpressureMode,
+    /// Maximum time a request waits for its turn in `block` mode before being rejected, in
+    /// seconds. The requests wait indefinitely when unset.
+    pub timeout_secs: Option<NonZeroU64>,
+    /// Number of seconds after which the clients of the rejected requests are told to retry.
+    pub retry_after_secs: NonZeroU64,
+}
+
+impl Default for IngestBackpressureConfig {
+    fn default() -> Self {
+        Self {
+            mode: IngestBackpressureMode::Block,
+            timeout_secs: None,
+            retry_after_secs: NonZeroU64::new(1).expect("1 should be non-zero"),
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[serde(deny_unknown_fields, default)]
 pub struct IngestApiConfig {
@@ -719,6 +1277,26 @@ pub struct IngestApiConfig {
     pub shard_scale_up_factor: f32,
     #[serde(default)]
     pub grpc_compression_algorithm: Option<CompressionAlgorithm>,
+    /// How the REST ingest endpoint reads request bodies.
+    #[serde(default)]
+    pub body_buffering: IngestBodyBuffering,
+    /// Maximum number of documents of a REST ingest request. Unlimited when unset.
+    #[serde(default)]
+    pub max_docs_per_request: Option<NonZeroUsize>,
+    /// Glob patterns of the indexes the REST ingest endpoint may target. All the indexes may be
+    /// targeted when unset.
+    #[serde(default)]
+    pub index_allow_list: Option<Vec<String>>,
+    /// Bounds of the dead-letter queues capturing the documents the REST ingest endpoint failed to
+    /// parse.
+    #[serde(default)]
+    pub dead_letter_queue: DeadLetterQueueConfig,
+    /// Bounds of the resumable upload sessions of the REST ingest endpoint.
+    #[serde(default)]
+    pub upload_session: IngestUploadSessionConfig,
+    /// Backpressure applied to the REST ingest requests exceeding the ingest concurrency limit.
+    #[serde(default)]
+    pub backpressure: IngestBackpressureConfig,
 }
 
 impl Default for IngestApiConfig {
@@ -732,6 +1310,12 @@ impl Default for IngestApiConfig {
             shard_burst_limit: DEFAULT_SHARD_BURST_LIMIT,
             shard_scale_up_factor: DEFAULT_SHARD_SCALE_UP_FACTOR,
             grpc_compression_algorithm: None,
+            body_buffering: IngestBodyBuffering::Full,
+            max_docs_per_request: None,
+            index_allow_list: None,
+            dead_letter_queue: DeadLetterQueueConfig::default(),
+            upload_session: IngestUploadSessionConfig::default(),
+            backpressure: IngestBackpressureConfig::default(),
         }
     }
 }
@@ -806,6 +1390,16 @@ impl IngestApiConfig {
             "shard_scale_up_factor ({}) must be greater than 1",
             self.shard_scale_up_factor,
         );
+        ensure!(
+            self.upload_session.max_total_size >= self.upload_session.max_body_size,
+            "upload_session.max_total_size ({}) must be at least upload_session.max_body_size ({})",
+            self.upload_session.max_total_size.display().si(),
+            self.upload_session.max_body_size.display().si()
+        );
+        for index_id_pattern in self.index_allow_list.iter().flatten() {
+            validate_index_id_pattern(index_id_pattern, false)
+                .context("invalid `index_allow_list`")?;
+        }
         Ok(())
     }
 }
@@ -1125,6 +1719,27 @@ mod tests {
                 "shard_throughput_limit (21.0 MB) must be within 1mb and 20mb"
             );
         }
+        {
+            let ingest_api_config: IngestApiConfig = serde_yaml::from_str(
+                r#"
+                    index_allow_list: ["logs-*", "otel-traces-v0_*"]
+                "#,
+            )
+            .unwrap();
+            ingest_api_config.validate().unwrap();
+        }
+        {
+            let ingest_api_config: IngestApiConfig = serde_yaml::from_str(
+                r#"
+                    index_allow_list: ["logs-*", "logs/app"]
+                "#,
+            )
+            .unwrap();
+            assert_eq!(
+                ingest_api_config.validate().unwrap_err().to_string(),
+                "invalid `index_allow_list`"
+            );
+        }
     }
 
     #[track_caller]
@@ -1232,6 +1847,23 @@ mod tests {
             error.contains("must be greater than zero"),
             "unexpected error: {error}"
         );
+
+        let mtls_config = TlsConfig {
+            ca_path: "/path/to/ca.crt".to_string(),
+            verify_client_cert: true,
+            ..tls_config("5m")
+        };
+        assert!(mtls_config.validate().is_ok());
+
+        let mtls_config_without_ca = TlsConfig {
+            verify_client_cert: true,
+            ..tls_config("5m")
+        };
+        let error = mtls_config_without_ca.validate().unwrap_err().to_string();
+        assert!(
+            error.contains("`tls.ca_path` must be set"),
+            "unexpected error: {error}"
+        );
     }
 
     #[test]
@@ -13,7 +13,8 @@
 // limitations under the License.
 
 use std::collections::{HashMap, HashSet};
-use std::net::{IpAddr, SocketAddr};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::num::NonZeroUsize;
 use std::str::FromStr;
 use std::time::Duration;
 
@@ -28,7 +29,11 @@ use quickwit_proto::types::NodeId;
 use serde::{Deserialize, Serialize};
 use tracing::{info, warn};
 
-use super::{GrpcConfig, HealthConfig, RestConfig};
+use super::{
+    AccessLogConfig, GrpcConfig, HealthConfig, JwtConfig, RequestDurationBucketsConfig,
+    RestCompressionConfig, RestConfig, RestCorsPolicy, RestNotFoundConfig, RestRateLimitConfig,
+    RestRequestFilterRule, RestResponseFormat, RestRootRoute, RestUnknownQueryParams,
+};
 use crate::config_value::ConfigValue;
 use crate::qw_env_vars::*;
 use crate::serde_utils::HumanDuration;
@@ -157,6 +162,22 @@ pub async fn load_node_config_with_env(
     Ok(config)
 }
 
+/// Parses the REST and gRPC sections of a node config as they are written, without rendering the
+/// config template, reading the environment variables, resolving hosts, or touching the file
+/// system, so that configs submitted by clients can be validated.
+pub fn parse_rest_and_grpc_configs(
+    config_format: ConfigFormat,
+    config_content: &[u8],
+) -> anyhow::Result<(RestConfig, GrpcConfig)> {
+    let versioned_node_config: VersionedNodeConfig = config_format.parse(config_content)?;
+    let node_config_builder: NodeConfigBuilder = versioned_node_config.into();
+    let rest_config = node_config_builder
+        .rest_config_builder
+        .build_and_validate(IpAddr::V4(Ipv4Addr::UNSPECIFIED), &HashMap::new())?;
+    node_config_builder.grpc_config.validate()?;
+    Ok((rest_config, node_config_builder.grpc_config))
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(tag = "version")]
 enum VersionedNodeConfig {
@@ -525,6 +546,8 @@ struct RestConfigBuilder {
     #[serde(default)]
     #[serde_as(deserialize_as = "serde_with::OneOrMany<_>")]
     pub cors_allow_origins: Vec<String>,
+    #[serde(default)]
+    pub cors_policies: Vec<RestCorsPolicy>,
     #[serde(with = "http_serde::header_map")]
     #[serde(default)]
     pub extra_headers: HeaderMap,
@@ -534,6 +557,44 @@ struct RestConfigBuilder {
     pub max_connection_age: Option<HumanDuration>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub max_connection_age_grace: Option<HumanDuration>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idle_connection_timeout: Option<HumanDuration>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_pipelined_requests: Option<NonZeroUsize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_header_list_size: Option<ByteSize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_header_count: Option<NonZeroUsize>,
+    #[serde(default)]
+    pub access_log: AccessLogConfig,
+    #[serde(default)]
+    pub request_duration_buckets: RequestDurationBucketsConfig,
+    #[serde(default)]
+    pub jwt: Option<JwtConfig>,
+    #[serde(default)]
+    pub compression: RestCompressionConfig,
+    #[serde(default)]
+    pub not_found: RestNotFoundConfig,
+    #[serde(default)]
+    pub default_response_format: RestResponseFormat,
+    #[serde(default)]
+    pub root_route: RestRootRoute,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_budget: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate_limit: Option<RestRateLimitConfig>,
+    #[serde(default)]
+    pub request_filters: Vec<RestRequestFilterRule>,
+    #[serde(default)]
+    pub enable_jsonp: bool,
+    #[serde(default)]
+    pub unknown_query_params: RestUnknownQueryParams,
+    #[serde(default)]
+    pub enable_config_validation: bool,
+    #[serde(default)]
+    pub enable_cluster_leave: bool,
+    #[serde(default)]
+    pub enable_rest_config_updates: bool,
 }
 
 impl RestConfigBuilder {
@@ -552,17 +613,59 @@ impl RestConfigBuilder {
         if let Some(tls_config) = &self.tls_config {
             tls_config.validate()?;
         }
+        if let Some(jwt_config) = &self.jwt {
+            jwt_config.validate()?;
+        }
+        for (policy_idx, cors_policy) in self.cors_policies.iter().enumerate() {
+            cors_policy
+                .validate()
+                .with_context(|| format!("invalid policy `rest.cors_policies[{policy_idx}]`"))?;
+        }
+        for (rule_idx, request_filter_rule) in self.request_filters.iter().enumerate() {
+            request_filter_rule
+                .validate()
+                .with_context(|| format!("invalid rule `rest.request_filters[{rule_idx}]`"))?;
+        }
+        self.request_duration_buckets.validate()?;
         ensure!(
             !(self.max_connection_age_grace.is_some() && self.max_connection_age.is_none()),
             "`rest.max_connection_age_grace` requires `rest.max_connection_age` to be set"
         );
+        if let Some(max_header_list_size) = self.max_header_list_size {
+            // The HTTP/1 request head must fit in the read buffer of hyper, which cannot be smaller
+            // than 8KiB.
+            ensure!(
+                max_header_list_size >= ByteSize::kib(8),
+                "`rest.max_header_list_size` must be at least 8KiB, got `{max_header_list_size}`"
+            );
+        }
         let rest_config = RestConfig {
             listen_addr: SocketAddr::new(listen_ip, listen_port),
             cors_allow_origins: self.cors_allow_origins,
+            cors_policies: self.cors_policies,
             extra_headers: self.extra_headers,
             tls_config: self.tls_config,
             max_connection_age: self.max_connection_age,
             max_connection_age_grace: self.max_connection_age_grace,
+            idle_connection_timeout: self.idle_connection_timeout,
+            max_pipelined_requests: self.max_pipelined_requests,
+            max_header_list_size: self.max_header_list_size,
+            max_header_count: self.max_header_count,
+            access_log: self.access_log,
+            request_duration_buckets: self.request_duration_buckets,
+            jwt: self.jwt,
+            compression: self.compression,
+            not_found: self.not_found,
+            default_response_format: self.default_response_format,
+            root_route: self.root_route,
+            retry_budget: self.retry_budget,
+            rate_limit: self.rate_limit,
+            request_filters: self.request_filters,
+            enable_jsonp: self.enable_jsonp,
+            unknown_query_params: self.unknown_query_params,
+            enable_config_validation: self.enable_config_validation,
+            enable_cluster_leave: self.enable_cluster_leave,
+            enable_rest_config_updates: self.enable_rest_config_updates,
         };
         Ok(rest_config)
     }
@@ -631,10 +734,30 @@ pub fn node_config_for_tests_from_ports(
     let rest_config = RestConfig {
         listen_addr: rest_listen_addr,
         cors_allow_origins: Vec::new(),
+        cors_policies: Vec::new(),
         extra_headers: HeaderMap::new(),
         tls_config: None,
         max_connection_age: None,
         max_connection_age_grace: None,
+        idle_connection_timeout: None,
+        max_pipelined_requests: None,
+        max_header_list_size: None,
+        max_header_count: None,
+        access_log: AccessLogConfig::default(),
+        request_duration_buckets: RequestDurationBucketsConfig::default(),
+        jwt: None,
+        compression: RestCompressionConfig::default(),
+        not_found: RestNotFoundConfig::default(),
+        default_response_format: RestResponseFormat::default(),
+        root_route: RestRootRoute::default(),
+        retry_budget: None,
+        rate_limit: None,
+        request_filters: Vec::new(),
+        enable_jsonp: false,
+        unknown_query_params: RestUnknownQueryParams::default(),
+        enable_config_validation: false,
+        enable_cluster_leave: false,
+        enable_rest_config_updates: false,
     };
     NodeConfig {
         cluster_id: default_cluster_id().unwrap(),
@@ -677,7 +800,11 @@ mod tests {
 
     use super::*;
     use crate::storage_config::StorageBackendFlavor;
-    use crate::{CacheConfig, LambdaConfig, LambdaDeployConfig};
+    use crate::{
+        AccessLogVerbosity, CacheConfig, CompressionAlgorithm, DeadLetterEviction,
+        IngestBackpressureMode, IngestBodyBuffering, IngestUploadSessionConfig, LambdaConfig,
+        LambdaDeployConfig, SearchConcurrencyConfig, SearchRequestCompressionConfig,
+    };
 
     fn get_config_filepath(config_filename: &str) -> String {
         format!(
@@ -733,6 +860,22 @@ mod tests {
             config.rest_config.max_connection_age_grace,
             Some(HumanDuration::try_from("30s".to_string()).unwrap())
         );
+        assert_eq!(
+            config.rest_config.idle_connection_timeout,
+            Some(HumanDuration::try_from("2m".to_string()).unwrap())
+        );
+        assert_eq!(
+            config.rest_config.max_pipelined_requests,
+            Some(NonZeroUsize::new(16).unwrap())
+        );
+        assert_eq!(
+            config.rest_config.access_log,
+            AccessLogConfig {
+                ingest: AccessLogVerbosity::All,
+                search: AccessLogVerbosity::Errors,
+                other: AccessLogVerbosity::None,
+            }
+        );
         assert_eq!(config.grpc_config.max_message_size, ByteSize::mb(10));
 
         let grpc_tls_config = config.grpc_config.tls_config.unwrap();
@@ -863,10 +1006,13 @@ mod tests {
                 predicate_cache: CacheConfig::default_with_capacity(ByteSize::mb(256)),
                 max_num_concurrent_split_searches: 150,
                 max_splits_per_search: None,
+                max_indexes_per_search: None,
+                max_result_window: 20_000,
                 _max_num_concurrent_split_streams: Some(serde::de::IgnoredAny),
                 split_cache: None,
                 request_timeout_secs: NonZeroU64::new(30).unwrap(),
                 leaf_request_timeout_secs: NonZeroU64::new(30).unwrap(),
+                max_request_timeout_secs: None,
                 storage_timeout_policy: Some(crate::StorageTimeoutPolicy {
                     min_throughtput_bytes_per_secs: 100_000,
                     timeout_millis: 2_000,
@@ -886,6 +1032,16 @@ mod tests {
                         invocation_timeout_secs: 15,
                     }),
                 }),
+                search_concurrency: Some(SearchConcurrencyConfig {
+                    max_concurrent_searches: NonZeroUsize::new(20).unwrap(),
+                    max_queued_searches: 50,
+                    max_queue_wait: HumanDuration::try_from("500ms".to_string()).unwrap(),
+                }),
+                max_query_cost: Some(1_000_000_000),
+                request_compression: Some(SearchRequestCompressionConfig {
+                    algorithm: CompressionAlgorithm::Zstd,
+                    min_request_size: ByteSize::kib(32),
+                }),
             }
         );
         assert_eq!(
@@ -1230,6 +1386,43 @@ mod tests {
         assert!(node_config.searcher_config.use_metastore_read_replica);
     }
 
+    #[tokio::test]
+    async fn test_searcher_max_request_timeout() {
+        let node_config = NodeConfigBuilder {
+            searcher_config: SearcherConfig {
+                max_request_timeout_secs: NonZeroU64::new(120),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+        .build_and_validate(&HashMap::new(), None)
+        .await
+        .unwrap();
+        assert_eq!(
+            node_config.searcher_config.max_request_timeout(),
+            Duration::from_secs(120)
+        );
+        let node_config = NodeConfigBuilder::default()
+            .build_and_validate(&HashMap::new(), None)
+            .await
+            .unwrap();
+        assert_eq!(
+            node_config.searcher_config.max_request_timeout(),
+            node_config.searcher_config.request_timeout()
+        );
+        let error = NodeConfigBuilder {
+            searcher_config: SearcherConfig {
+                max_request_timeout_secs: NonZeroU64::new(10),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+        .build_and_validate(&HashMap::new(), None)
+        .await
+        .unwrap_err();
+        assert!(error.to_string().contains("max_request_timeout_secs (10)"));
+    }
+
     #[tokio::test]
     async fn test_enabled_services_validates_metastore_read_replica_uri() {
         let config_yaml = "version: 0.8";
@@ -1572,6 +1765,57 @@ mod tests {
         assert_eq!(config.rest_config.cors_allow_origins, ["*"]);
     }
 
+    #[tokio::test]
+    async fn test_rest_jwt_config_requires_exactly_one_key_source() {
+        let rest_config_yaml = r#"
+            version: 0.8
+            rest:
+              jwt:
+                algorithm: RS256
+                jwks_path: /etc/quickwit/jwks.json
+                audience: quickwit
+        "#;
+        let config = load_node_config_with_env(
+            ConfigFormat::Yaml,
+            rest_config_yaml.as_bytes(),
+            &Default::default(),
+            None,
+        )
+        .await
+        .unwrap();
+        let jwt_config = config.rest_config.jwt.unwrap();
+        assert_eq!(jwt_config.algorithm, "RS256");
+        assert_eq!(jwt_config.jwks_path.unwrap(), "/etc/quickwit/jwks.json");
+        assert_eq!(jwt_config.audience.unwrap(), "quickwit");
+
+        for rest_config_yaml in [
+            r#"
+            version: 0.8
+            rest:
+              jwt:
+                algorithm: RS256
+            "#,
+            r#"
+            version: 0.8
+            rest:
+              jwt:
+                algorithm: RS256
+                key_path: /etc/quickwit/jwt.pem
+                jwks_path: /etc/quickwit/jwks.json
+            "#,
+        ] {
+            let error = load_node_config_with_env(
+                ConfigFormat::Yaml,
+                rest_config_yaml.as_bytes(),
+                &Default::default(),
+                None,
+            )
+            .await
+            .unwrap_err();
+            assert!(error.to_string().contains("exactly one of"));
+        }
+    }
+
     #[tokio::test]
     async fn test_rest_config_accepts_single_origin() {
         let rest_config_yaml = r#"
@@ -1686,6 +1930,522 @@ mod tests {
         .expect_err("Config should not allow empty origins.");
     }
 
+    #[tokio::test]
+    async fn test_rest_config_not_found() {
+        let rest_config_yaml = r#"
+            version: 0.8
+            rest:
+              not_found:
+                json_body:
+                  code: route_not_found
+                  docs: https://quickwit.io/docs/reference/rest-api
+                html_body: <h1>Page not found</h1>
+        "#;
+        let config = load_node_config_with_env(
+            ConfigFormat::Yaml,
+            rest_config_yaml.as_bytes(),
+            &Default::default(),
+            None,
+        )
+        .await
+        .unwrap();
+        let not_found_config = config.rest_config.not_found;
+        assert_eq!(
+            not_found_config.json_body.unwrap(),
+            serde_json::json!({
+                "code": "route_not_found",
+                "docs": "https://quickwit.io/docs/reference/rest-api",
+            })
+        );
+        assert_eq!(
+            not_found_config.html_body.unwrap(),
+            "<h1>Page not found</h1>"
+        );
+
+        let config = load_node_config_with_env(
+            ConfigFormat::Yaml,
+            b"version: 0.8",
+            &Default::default(),
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(config.rest_config.not_found, RestNotFoundConfig::default());
+    }
+
+    #[test]
+    fn test_parse_rest_and_grpc_configs() {
+        let node_config_yaml = r#"
+            version: 0.8
+            listen_address: unresolvable-host.invalid
+            rest:
+              cors_allow_origins: ["${QW_SECRET_ORIGIN}"]
+              enable_config_validation: true
+            grpc:
+              max_message_size: 10MB
+        "#;
+        let (rest_config, grpc_config) =
+            parse_rest_and_grpc_configs(ConfigFormat::Yaml, node_config_yaml.as_bytes()).unwrap();
+        // The config template is not rendered.
+        assert_eq!(rest_config.cors_allow_origins, ["${QW_SECRET_ORIGIN}"]);
+        assert!(rest_config.enable_config_validation);
+        assert_eq!(grpc_config.max_message_size, ByteSize::mb(10));
+
+        let node_config_yaml = r#"
+            version: 0.8
+            grpc:
+              max_message_size: 1KB
+        "#;
+        parse_rest_and_grpc_configs(ConfigFormat::Yaml, node_config_yaml.as_bytes()).unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn test_rest_config_response_settings() {
+        let rest_config_yaml = r#"
+            version: 0.8
+            rest:
+              default_response_format: yaml
+              root_route: api_index
+              enable_jsonp: true
+              enable_cluster_leave: true
+              enable_rest_config_updates: true
+              unknown_query_params: strict
+              retry_budget: 3
+              rate_limit:
+                requests_per_sec: 10
+                burst: 20
+        "#;
+        let config = load_node_config_with_env(
+            ConfigFormat::Yaml,
+            rest_config_yaml.as_bytes(),
+            &Default::default(),
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            config.rest_config.default_response_format,
+            RestResponseFormat::Yaml
+        );
+        assert_eq!(config.rest_config.root_route, RestRootRoute::ApiIndex);
+        assert!(config.rest_config.enable_jsonp);
+        assert!(config.rest_config.enable_cluster_leave);
+        assert!(config.rest_config.enable_rest_config_updates);
+        assert_eq!(
+            config.rest_config.unknown_query_params,
+            RestUnknownQueryParams::Strict
+        );
+        assert_eq!(config.rest_config.retry_budget, Some(3));
+        assert_eq!(
+            config.rest_config.rate_limit,
+            Some(RestRateLimitConfig {
+                requests_per_sec: NonZeroU64::new(10).unwrap(),
+                burst: NonZeroU64::new(20).unwrap(),
+            })
+        );
+
+        let config = load_node_config_with_env(
+            ConfigFormat::Yaml,
+            b"version: 0.8",
+            &Default::default(),
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            config.rest_config.default_response_format,
+            RestResponseFormat::PrettyJson
+        );
+        assert!(config.rest_config.retry_budget.is_none());
+        assert!(config.rest_config.rate_limit.is_none());
+        assert_eq!(config.rest_config.root_route, RestRootRoute::RedirectToUi);
+        assert!(!config.rest_config.enable_jsonp);
+        assert!(!config.rest_config.enable_cluster_leave);
+        assert!(!config.rest_config.enable_rest_config_updates);
+        assert_eq!(
+            config.rest_config.unknown_query_params,
+            RestUnknownQueryParams::Lenient
+        );
+
+        let rest_config_yaml = r#"
+            version: 0.8
+            rest:
+              default_response_format: xml
+        "#;
+        load_node_config_with_env(
+            ConfigFormat::Yaml,
+            rest_config_yaml.as_bytes(),
+            &Default::default(),
+            None,
+        )
+        .await
+        .unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn test_rest_config_header_limits() {
+        let rest_config_yaml = r#"
+            version: 0.8
+            rest:
+              max_header_list_size: 16KiB
+              max_header_count: 32
+        "#;
+        let config = load_node_config_with_env(
+            ConfigFormat::Yaml,
+            rest_config_yaml.as_bytes(),
+            &Default::default(),
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            config.rest_config.max_header_list_size,
+            Some(ByteSize::kib(16))
+        );
+        assert_eq!(
+            config.rest_config.max_header_count,
+            Some(NonZeroUsize::new(32).unwrap())
+        );
+
+        let rest_config_yaml = r#"
+            version: 0.8
+            rest:
+              max_header_list_size: 4KiB
+        "#;
+        let error = load_node_config_with_env(
+            ConfigFormat::Yaml,
+            rest_config_yaml.as_bytes(),
+            &Default::default(),
+            None,
+        )
+        .await
+        .unwrap_err();
+        assert!(format!("{error:#}").contains("max_header_list_size"));
+    }
+
+    #[tokio::test]
+    async fn test_rest_config_cors_policies() {
+        let rest_config_yaml = r#"
+            version: 0.8
+            rest:
+              cors_allow_origins: https://quickwit.io
+              cors_policies:
+                - origins: [https://*.example.com]
+                  allow_methods: [get, post]
+                  allow_headers: [authorization]
+                - origins: [https://admin.example.com]
+                  allow_credentials: true
+        "#;
+        let config = load_node_config_with_env(
+            ConfigFormat::Yaml,
+            rest_config_yaml.as_bytes(),
+            &Default::default(),
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            config.rest_config.cors_policies,
+            [
+                RestCorsPolicy {
+                    origins: vec!["https://*.example.com".to_string()],
+                    allow_methods: vec!["get".to_string(), "post".to_string()],
+                    allow_headers: vec!["authorization".to_string()],
+                    allow_credentials: false,
+                },
+                RestCorsPolicy {
+                    origins: vec!["https://admin.example.com".to_string()],
+                    allow_credentials: true,
+                    ..Default::default()
+                }
+            ]
+        );
+
+        let rest_config_yaml = r#"
+            version: 0.8
+            rest:
+              cors_policies:
+                - origins: [https://*.example.com]
+                  allow_credentials: true
+        "#;
+        let error = load_node_config_with_env(
+            ConfigFormat::Yaml,
+            rest_config_yaml.as_bytes(),
+            &Default::default(),
+            None,
+        )
+        .await
+        .unwrap_err();
+        assert!(
+            format!("{error:#}").contains("allowing credentials must list exact origins"),
+            "{error:#}"
+        );
+
+        let rest_config_yaml = r#"
+            version: 0.8
+            rest:
+              cors_policies:
+                - origins: [https://app.example.com]
+                  allow_headers: [not a header]
+        "#;
+        let error = load_node_config_with_env(
+            ConfigFormat::Yaml,
+            rest_config_yaml.as_bytes(),
+            &Default::default(),
+            None,
+        )
+        .await
+        .unwrap_err();
+        let error_message = format!("{error:#}");
+        assert!(error_message.contains("rest.cors_policies[0]"));
+        assert!(error_message.contains("invalid header name `not a header`"));
+
+        let rest_config_yaml = r#"
+            version: 0.8
+            rest:
+              cors_policies:
+                - origins: []
+        "#;
+        load_node_config_with_env(
+            ConfigFormat::Yaml,
+            rest_config_yaml.as_bytes(),
+            &Default::default(),
+            None,
+        )
+        .await
+        .unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn test_rest_config_request_filters() {
+        let rest_config_yaml = r#"
+            version: 0.8
+            rest:
+              request_filters:
+                - path: ^/api/v1/_elastic/.*/_delete_by_query
+                  methods: [post]
+                - header: user-agent
+                  header_value: (?i)badbot
+        "#;
+        let config = load_node_config_with_env(
+            ConfigFormat::Yaml,
+            rest_config_yaml.as_bytes(),
+            &Default::default(),
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            config.rest_config.request_filters,
+            [
+                RestRequestFilterRule {
+                    path: Some("^/api/v1/_elastic/.*/_delete_by_query".to_string()),
+                    methods: vec!["post".to_string()],
+                    ..Default::default()
+                },
+                RestRequestFilterRule {
+                    header: Some("user-agent".to_string()),
+                    header_value: Some("(?i)badbot".to_string()),
+                    ..Default::default()
+                },
+            ]
+        );
+
+        let rest_config_yaml = r#"
+            version: 0.8
+            rest:
+              request_filters:
+                - path: /api/v1/(
+        "#;
+        let error = load_node_config_with_env(
+            ConfigFormat::Yaml,
+            rest_config_yaml.as_bytes(),
+            &Default::default(),
+            None,
+        )
+        .await
+        .unwrap_err();
+        let error_message = format!("{error:#}");
+        assert!(error_message.contains("rest.request_filters[0]"));
+        assert!(error_message.contains("invalid `path` regular expression `/api/v1/(`"));
+
+        let rest_config_yaml = r#"
+            version: 0.8
+            rest:
+              request_filters:
+                - header_value: badbot
+        "#;
+        load_node_config_with_env(
+            ConfigFormat::Yaml,
+            rest_config_yaml.as_bytes(),
+            &Default::default(),
+            None,
+        )
+        .await
+        .unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn test_node_config_ingest_dead_letter_queue() {
+        let node_config_yaml = r#"
+            version: 0.8
+            ingest_api:
+              dead_letter_queue:
+                max_docs_per_index: 1000
+                eviction: drop_newest
+        "#;
+        let config = load_node_config_with_env(
+            ConfigFormat::Yaml,
+            node_config_yaml.as_bytes(),
+            &Default::default(),
+            None,
+        )
+        .await
+        .unwrap();
+        let dead_letter_queue_config = config.ingest_api_config.dead_letter_queue;
+        assert!(dead_letter_queue_config.is_enabled());
+        assert_eq!(dead_letter_queue_config.max_docs_per_index, 1000);
+        assert_eq!(
+            dead_letter_queue_config.eviction,
+            DeadLetterEviction::DropNewest
+        );
+
+        let config = load_node_config_with_env(
+            ConfigFormat::Yaml,
+            b"version: 0.8",
+            &Default::default(),
+            None,
+        )
+        .await
+        .unwrap();
+        assert!(!config.ingest_api_config.dead_letter_queue.is_enabled());
+    }
+
+    #[tokio::test]
+    async fn test_node_config_ingest_upload_session() {
+        let node_config_yaml = r#"
+            version: 0.8
+            ingest_api:
+              upload_session:
+                max_body_size: 5GiB
+                max_lifetime_secs: 600
+                max_num_sessions: 10
+                max_total_size: 20GiB
+        "#;
+        let config = load_node_config_with_env(
+            ConfigFormat::Yaml,
+            node_config_yaml.as_bytes(),
+            &Default::default(),
+            None,
+        )
+        .await
+        .unwrap();
+        let upload_session_config = config.ingest_api_config.upload_session;
+        assert_eq!(upload_session_config.max_body_size, ByteSize::gib(5));
+        assert_eq!(upload_session_config.max_lifetime_secs.get(), 600);
+        assert_eq!(upload_session_config.max_num_sessions.get(), 10);
+        assert_eq!(upload_session_config.max_total_size, ByteSize::gib(20));
+
+        let config = load_node_config_with_env(
+            ConfigFormat::Yaml,
+            b"version: 0.8",
+            &Default::default(),
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            config.ingest_api_config.upload_session,
+            IngestUploadSessionConfig::default()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_node_config_ingest_backpressure() {
+        let node_config_yaml = r#"
+            version: 0.8
+            ingest_api:
+              backpressure:
+                mode: fail_fast
+                retry_after_secs: 5
+        "#;
+        let config = load_node_config_with_env(
+            ConfigFormat::Yaml,
+            node_config_yaml.as_bytes(),
+            &Default::default(),
+            None,
+        )
+        .await
+        .unwrap();
+        let backpressure_config = config.ingest_api_config.backpressure;
+        assert_eq!(backpressure_config.mode, IngestBackpressureMode::FailFast);
+        assert!(backpressure_config.timeout_secs.is_none());
+        assert_eq!(backpressure_config.retry_after_secs.get(), 5);
+
+        let config = load_node_config_with_env(
+            ConfigFormat::Yaml,
+            b"version: 0.8",
+            &Default::default(),
+            None,
+        )
+        .await
+        .unwrap();
+        let backpressure_config = config.ingest_api_config.backpressure;
+        assert_eq!(backpressure_config.mode, IngestBackpressureMode::Block);
+        assert!(backpressure_config.timeout_secs.is_none());
+        assert_eq!(backpressure_config.retry_after_secs.get(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_node_config_request_duration_buckets() {
+        let node_config_yaml = r#"
+            version: 0.8
+            rest:
+              request_duration_buckets:
+                search: [0.01, 0.1, 1, 10]
+        "#;
+        let config = load_node_config_with_env(
+            ConfigFormat::Yaml,
+            node_config_yaml.as_bytes(),
+            &Default::default(),
+            None,
+        )
+        .await
+        .unwrap();
+        let request_duration_buckets = config.rest_config.request_duration_buckets;
+        assert_eq!(
+            request_duration_buckets.search,
+            Some(vec![0.01, 0.1, 1.0, 10.0])
+        );
+        assert!(request_duration_buckets.all.is_none());
+        assert!(request_duration_buckets.ingest.is_none());
+
+        for invalid_buckets in ["[]", "[1, 0.5]", "[1, 1]"] {
+            let node_config_yaml = format!(
+                r#"
+                version: 0.8
+                rest:
+                  request_duration_buckets:
+                    ingest: {invalid_buckets}
+            "#
+            );
+            let error = load_node_config_with_env(
+                ConfigFormat::Yaml,
+                node_config_yaml.as_bytes(),
+                &Default::default(),
+                None,
+            )
+            .await
+            .unwrap_err();
+            assert!(
+                error
+                    .to_string()
+                    .contains("`rest.request_duration_buckets.ingest`"),
+                "{error:?}"
+            );
+        }
+    }
+
     #[tokio::test]
     async fn test_node_config_validates_ingest_config() {
         let ingest_config = IngestApiConfig {
@@ -1719,6 +2479,40 @@ mod tests {
         assert!(error_message.contains("replication factor"));
     }
 
+    #[tokio::test]
+    async fn test_node_config_ingest_body_buffering() {
+        let node_config_yaml = r#"
+            version: 0.8
+            ingest_api:
+              body_buffering: streaming
+        "#;
+        let config = load_node_config_with_env(
+            ConfigFormat::Yaml,
+            node_config_yaml.as_bytes(),
+            &Default::default(),
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            config.ingest_api_config.body_buffering,
+            IngestBodyBuffering::Streaming
+        );
+
+        let config = load_node_config_with_env(
+            ConfigFormat::Yaml,
+            b"version: 0.8",
+            &Default::default(),
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            config.ingest_api_config.body_buffering,
+            IngestBodyBuffering::Full
+        );
+    }
+
     #[tokio::test]
     async fn test_standalone_compactors_prevents_implicit_compactor() {
         let config_yaml = r#"
@@ -38,6 +38,7 @@ use quickwit_metastore::{CreateIndexRequestExt, CreateIndexResponseExt, IndexMet
 use quickwit_proto::control_plane::{
     AdviseResetShardsRequest, AdviseResetShardsResponse, ControlPlaneError, ControlPlaneResult,
     GetOrCreateOpenShardsRequest, GetOrCreateOpenShardsResponse, GetOrCreateOpenShardsSubrequest,
+    RebalanceIndexingPipelinesRequest, RebalanceIndexingPipelinesResponse,
 };
 use quickwit_proto::indexing::ShardPositionsUpdate;
 use quickwit_proto::ingest::ingester::IngesterStatus;
@@ -938,6 +939,23 @@ impl Handler<AdviseResetShardsRequest> for ControlPlane {
     }
 }
 
+#[async_trait]
+impl Handler<RebalanceIndexingPipelinesRequest> for ControlPlane {
+    type Reply = ControlPlaneResult<RebalanceIndexingPipelinesResponse>;
+
+    async fn handle(
+        &mut self,
+        request: RebalanceIndexingPipelinesRequest,
+        _ctx: &ActorContext<Self>,
+    ) -> Result<Self::Reply, ActorExitStatus> {
+        let moves = self
+            .indexing_scheduler
+            .rebalance_plan(&self.model, request.dry_run);
+        let response = RebalanceIndexingPipelinesResponse { moves };
+        Ok(Ok(response))
+    }
+}
+
 #[async_trait]
 impl Handler<LocalShardsUpdate> for ControlPlane {
     type Reply = ControlPlaneResult<()>;
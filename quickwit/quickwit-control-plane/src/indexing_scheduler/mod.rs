@@ -16,6 +16,7 @@ mod change_tracker;
 mod scheduling;
 
 use std::cmp::Ordering;
+use std::collections::BTreeMap;
 use std::fmt;
 use std::num::NonZeroU32;
 use std::sync::{Arc, LazyLock};
@@ -28,19 +29,22 @@ use quickwit_common::pretty::PrettySample;
 use quickwit_config::{
     FileSourceParams, SourceParams, disable_ingest_v1, indexing_pipeline_params_fingerprint,
 };
+use quickwit_proto::control_plane::IndexingPipelineMove;
 use quickwit_proto::indexing::{
     ApplyIndexingPlanRequest, CpuCapacity, IndexingService, IndexingTask, PIPELINE_FULL_CAPACITY,
     PIPELINE_THROUGHPUT,
 };
 use quickwit_proto::ingest::ingester::IngesterStatus;
-use quickwit_proto::types::NodeId;
+use quickwit_proto::types::{IndexUid, NodeId};
 use scheduling::{SourceToSchedule, SourceToScheduleType};
 use serde::Serialize;
 use tracing::{debug, info, warn};
 
 use crate::indexing_plan::PhysicalIndexingPlan;
 use crate::indexing_scheduler::change_tracker::{NotifyChangeOnDrop, RebuildNotifier};
-use crate::indexing_scheduler::scheduling::build_physical_indexing_plan;
+use crate::indexing_scheduler::scheduling::{
+    build_physical_indexing_plan, build_rebalanced_physical_indexing_plan,
+};
 use crate::metrics::{APPLY_PLAN_TOTAL, SCHEDULE_TOTAL, ShardLocalityMetrics};
 use crate::model::{ControlPlaneModel, ShardEntry, ShardLocations};
 use crate::{IndexerNodeInfo, IndexerPool};
@@ -213,8 +217,18 @@ fn get_sources_to_schedule(
         if !source_config.enabled {
             continue;
         }
-        let params_fingerprint = model
-            .index_metadata(&source_uid.index_uid)
+        let index_metadata_opt = model.index_metadata(&source_uid.index_uid);
+
+        if let Some(index_metadata) = index_metadata_opt
+            && index_metadata
+                .index_config
+                .ingest_settings
+                .indexing_pause
+                .is_some()
+        {
+            continue;
+        }
+        let params_fingerprint = index_metadata_opt
             .map(|index_meta| {
                 indexing_pipeline_params_fingerprint(&index_meta.index_config, source_config)
             })
@@ -317,18 +331,7 @@ impl IndexingScheduler {
 
         let sources = get_sources_to_schedule(model, disable_ingest_v1());
 
-        let indexers: Vec<IndexerNodeInfo> = self.select_available_indexers_for_scheduling();
-
-        let indexer_id_to_cpu_capacities: FnvHashMap<String, CpuCapacity> = indexers
-            .iter()
-            .filter_map(|indexer| {
-                if indexer.indexing_capacity.cpu_millis() > 0 {
-                    Some((indexer.node_id.to_string(), indexer.indexing_capacity))
-                } else {
-                    None
-                }
-            })
-            .collect();
+        let indexer_id_to_cpu_capacities = self.indexer_id_to_cpu_capacities();
 
         if indexer_id_to_cpu_capacities.is_empty() {
             if !sources.is_empty() {
@@ -361,6 +364,50 @@ impl IndexingScheduler {
         self.state.num_schedule_indexing_plan += 1;
     }
 
+    /// Builds an indexing plan from scratch, ignoring where the pipelines currently run, and
+    /// returns the pipelines moving between indexers compared to the last applied plan. Unless
+    /// `dry_run` is set, the new plan is applied.
+    ///
+    /// Regular rebuilds keep the pipelines on their indexers as long as they have capacity, so
+    /// an indexer joining the cluster may stay idle: this is how operators even out the load.
+    pub(crate) fn rebalance_plan(
+        &mut self,
+        model: &ControlPlaneModel,
+        dry_run: bool,
+    ) -> Vec<IndexingPipelineMove> {
+        let Some(last_applied_plan) = &self.state.last_applied_physical_plan else {
+            return Vec::new();
+        };
+        let sources = get_sources_to_schedule(model, disable_ingest_v1());
+        let indexer_id_to_cpu_capacities = self.indexer_id_to_cpu_capacities();
+
+        if indexer_id_to_cpu_capacities.is_empty() {
+            return Vec::new();
+        }
+        let shard_locations = model.shard_locations();
+        let new_physical_plan = build_rebalanced_physical_indexing_plan(
+            &sources,
+            &indexer_id_to_cpu_capacities,
+            Some(last_applied_plan),
+            &shard_locations,
+        );
+        let pipeline_moves = get_indexing_pipeline_moves(
+            last_applied_plan.indexing_tasks_per_indexer(),
+            new_physical_plan.indexing_tasks_per_indexer(),
+        );
+        if dry_run || pipeline_moves.is_empty() {
+            return pipeline_moves;
+        }
+        info!(
+            num_moves = pipeline_moves.len(),
+            "rebalancing indexing pipelines across indexers"
+        );
+        let notify_on_drop = self.next_rebuild_tracker.start_rebuild();
+        self.apply_physical_indexing_plan(new_physical_plan, Some(notify_on_drop));
+        self.state.num_schedule_indexing_plan += 1;
+        pipeline_moves
+    }
+
     /// Checks if the last applied plan corresponds to the running indexing tasks present in the
     /// chitchat cluster state. If true, do nothing.
     /// - If node IDs differ, schedule a new indexing plan.
@@ -402,6 +449,19 @@ impl IndexingScheduler {
         }
     }
 
+    fn indexer_id_to_cpu_capacities(&self) -> FnvHashMap<String, CpuCapacity> {
+        self.select_available_indexers_for_scheduling()
+            .into_iter()
+            .filter_map(|indexer| {
+                if indexer.indexing_capacity.cpu_millis() > 0 {
+                    Some((indexer.node_id.to_string(), indexer.indexing_capacity))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
     fn select_available_indexers_for_scheduling(&self) -> Vec<IndexerNodeInfo> {
         let (ready, retiring): (Vec<IndexerNodeInfo>, Vec<IndexerNodeInfo>) = self
             .indexer_pool
@@ -710,6 +770,70 @@ fn get_indexing_plans_diff<'a>(
     }
 }
 
+/// Returns the pipelines moving from one indexer to another between `last_applied_plan` and
+/// `new_plan`. The pipelines of a source that are only added or only removed are not moves.
+fn get_indexing_pipeline_moves(
+    last_applied_plan: &FnvHashMap<String, Vec<IndexingTask>>,
+    new_plan: &FnvHashMap<String, Vec<IndexingTask>>,
+) -> Vec<IndexingPipelineMove> {
+    // Variation of the number of pipelines of each source on each indexer.
+    let mut num_pipelines_deltas: BTreeMap<(&IndexUid, &str), BTreeMap<&str, i64>> =
+        BTreeMap::new();
+
+    for (plan, delta) in [(last_applied_plan, -1), (new_plan, 1)] {
+        for (indexer_id, indexing_tasks) in plan {
+            for indexing_task in indexing_tasks {
+                let source_key = (indexing_task.index_uid(), indexing_task.source_id.as_str());
+                *num_pipelines_deltas
+                    .entry(source_key)
+                    .or_default()
+                    .entry(indexer_id.as_str())
+                    .or_default() += delta;
+            }
+        }
+    }
+    let mut pipeline_moves = Vec::new();
+
+    for ((index_uid, source_id), indexer_deltas) in num_pipelines_deltas {
+        let mut surpluses: Vec<(&str, i64)> = indexer_deltas
+            .iter()
+            .filter(|(_, delta)| **delta < 0)
+            .map(|(indexer_id, delta)| (*indexer_id, -delta))
+            .collect();
+        let mut deficits: Vec<(&str, i64)> = indexer_deltas
+            .iter()
+            .filter(|(_, delta)| **delta > 0)
+            .map(|(indexer_id, delta)| (*indexer_id, *delta))
+            .collect();
+        let mut surplus_idx = 0;
+        let mut deficit_idx = 0;
+
+        while surplus_idx < surpluses.len() && deficit_idx < deficits.len() {
+            let (from_node_id, surplus) = &mut surpluses[surplus_idx];
+            let (to_node_id, deficit) = &mut deficits[deficit_idx];
+            let num_pipelines = (*surplus).min(*deficit);
+
+            pipeline_moves.push(IndexingPipelineMove {
+                index_uid: Some(index_uid.clone()),
+                source_id: source_id.to_string(),
+                from_node_id: from_node_id.to_string(),
+                to_node_id: to_node_id.to_string(),
+                num_pipelines: num_pipelines as u32,
+            });
+            *surplus -= num_pipelines;
+            *deficit -= num_pipelines;
+
+            if *surplus == 0 {
+                surplus_idx += 1;
+            }
+            if *deficit == 0 {
+                deficit_idx += 1;
+            }
+        }
+    }
+    pipeline_moves
+}
+
 /// Computes the difference between `running_tasks` and `last_applied_tasks` and returns a tuple
 /// of `missing_tasks` and `unplanned_tasks`.
 /// Note: we need to handle duplicate tasks in each array, so we count them and make the diff.
@@ -758,7 +882,9 @@ mod tests {
     use std::str::FromStr;
 
     use proptest::{prop_compose, proptest};
-    use quickwit_config::{IndexConfig, KafkaSourceParams, SourceConfig, SourceParams};
+    use quickwit_config::{
+        IndexConfig, IndexingPauseMode, KafkaSourceParams, SourceConfig, SourceParams,
+    };
     use quickwit_metastore::IndexMetadata;
     use quickwit_proto::types::{IndexUid, PipelineUid, ShardId, SourceUid};
 
@@ -1044,6 +1170,57 @@ mod tests {
         assert!(!contains_any_ingest_v1_source);
     }
 
+    #[test]
+    fn test_get_sources_to_schedule_skips_paused_indexes() {
+        let mut model = ControlPlaneModel::default();
+        let index_metadata = IndexMetadata::for_test("test-index", "ram:///test-index");
+        let index_uid = index_metadata.index_uid.clone();
+        let mut index_config = index_metadata.index_config.clone();
+        model.add_index(index_metadata);
+        model
+            .add_source(
+                &index_uid,
+                SourceConfig {
+                    source_id: "test-source".to_string(),
+                    num_pipelines: NonZeroUsize::new(2).unwrap(),
+                    enabled: true,
+                    source_params: SourceParams::Kafka(KafkaSourceParams {
+                        topic: "kafka-topic".to_string(),
+                        client_log_level: None,
+                        client_params: serde_json::json!({}),
+                        enable_backfill_mode: false,
+                    }),
+                    transform_config: None,
+                    input_format: Default::default(),
+                },
+            )
+            .unwrap();
+        let sources = get_sources_to_schedule(&model, false);
+        assert_eq!(sources.len(), 1);
+
+        index_config.ingest_settings.indexing_pause = Some(IndexingPauseMode::Reject);
+        let rebuild_plan = model
+            .update_index_config(&index_uid, index_config.clone())
+            .unwrap();
+        assert!(rebuild_plan);
+        let sources = get_sources_to_schedule(&model, false);
+        assert!(sources.is_empty());
+
+        // Switching from one pause mode to another leaves the plan unchanged.
+        index_config.ingest_settings.indexing_pause = Some(IndexingPauseMode::Buffer);
+        let rebuild_plan = model
+            .update_index_config(&index_uid, index_config.clone())
+            .unwrap();
+        assert!(!rebuild_plan);
+
+        index_config.ingest_settings.indexing_pause = None;
+        let rebuild_plan = model.update_index_config(&index_uid, index_config).unwrap();
+        assert!(rebuild_plan);
+        let sources = get_sources_to_schedule(&model, false);
+        assert_eq!(sources.len(), 1);
+        assert_eq!(sources[0].source_uid.source_id, "test-source");
+    }
+
     #[test]
     fn test_build_physical_indexing_plan_simple() {
         let source_1 = SourceUid {
@@ -1436,6 +1613,141 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_get_indexing_pipeline_moves() {
+        let index_uid = IndexUid::for_test("test-index", 0);
+        let indexing_task = |source_id: &str, pipeline_uid: u128| IndexingTask {
+            pipeline_uid: Some(PipelineUid::for_test(pipeline_uid)),
+            index_uid: Some(index_uid.clone()),
+            source_id: source_id.to_string(),
+            shard_ids: Vec::new(),
+            params_fingerprint: 0,
+        };
+        let mut last_applied_plan = FnvHashMap::default();
+        last_applied_plan.insert(
+            "indexer-1".to_string(),
+            vec![
+                indexing_task("source-1", 1),
+                indexing_task("source-1", 2),
+                indexing_task("source-1", 3),
+                indexing_task("source-2", 4),
+            ],
+        );
+        let mut new_plan = FnvHashMap::default();
+        new_plan.insert("indexer-1".to_string(), vec![indexing_task("source-1", 1)]);
+        new_plan.insert(
+            "indexer-2".to_string(),
+            vec![indexing_task("source-1", 5), indexing_task("source-3", 6)],
+        );
+        new_plan.insert("indexer-3".to_string(), vec![indexing_task("source-1", 7)]);
+
+        let pipeline_moves = get_indexing_pipeline_moves(&last_applied_plan, &new_plan);
+        assert_eq!(pipeline_moves.len(), 2);
+
+        assert_eq!(pipeline_moves[0].index_uid(), &index_uid);
+        assert_eq!(pipeline_moves[0].source_id, "source-1");
+        assert_eq!(pipeline_moves[0].from_node_id, "indexer-1");
+        assert_eq!(pipeline_moves[0].to_node_id, "indexer-2");
+        assert_eq!(pipeline_moves[0].num_pipelines, 1);
+
+        assert_eq!(pipeline_moves[1].source_id, "source-1");
+        assert_eq!(pipeline_moves[1].from_node_id, "indexer-1");
+        assert_eq!(pipeline_moves[1].to_node_id, "indexer-3");
+        assert_eq!(pipeline_moves[1].num_pipelines, 1);
+    }
+
+    fn indexer_node_info_with_capacity(
+        node_id: &str,
+        mock_indexer: MockIndexingService,
+    ) -> IndexerNodeInfo {
+        IndexerNodeInfo {
+            node_id: NodeId::from_str(node_id),
+            generation_id: 0,
+            client: IndexingServiceClient::from_mock(mock_indexer),
+            indexing_tasks: Vec::new(),
+            indexing_capacity: CpuCapacity::from_cpu_millis(16_000),
+            ingester_status: IngesterStatus::Ready,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rebalance_plan() {
+        let mut model = ControlPlaneModel::default();
+        let index_metadata = IndexMetadata::for_test("test-index", "ram:///test-index");
+        let index_uid = index_metadata.index_uid.clone();
+        model.add_index(index_metadata);
+
+        for source_id in ["source-1", "source-2"] {
+            let source_config = SourceConfig {
+                source_id: source_id.to_string(),
+                num_pipelines: NonZeroUsize::MIN,
+                enabled: true,
+                source_params: kafka_source_params_for_test(),
+                transform_config: None,
+                input_format: SourceInputFormat::Json,
+            };
+            model.add_source(&index_uid, source_config).unwrap();
+        }
+        let mut mock_indexer_1 = MockIndexingService::new();
+        mock_indexer_1
+            .expect_apply_indexing_plan()
+            .returning(|_| Ok(ApplyIndexingPlanResponse {}));
+        let indexer_1 = indexer_node_info_with_capacity("indexer-1", mock_indexer_1);
+
+        let indexer_pool = IndexerPool::default();
+        indexer_pool.insert(indexer_1.node_id.clone(), indexer_1);
+
+        let mut scheduler = IndexingScheduler::new(
+            "test-cluster".to_string(),
+            NodeId::from_str("control-plane"),
+            indexer_pool.clone(),
+        );
+        // Nothing to rebalance before the first plan.
+        assert!(scheduler.rebalance_plan(&model, false).is_empty());
+
+        let waiter = scheduler.next_rebuild_tracker.next_rebuild_waiter();
+        scheduler.rebuild_plan(&model);
+        waiter.await;
+
+        // The second indexer joins the cluster after the pipelines were scheduled on the first
+        // one, which has enough capacity to keep them.
+        let mut mock_indexer_2 = MockIndexingService::new();
+        mock_indexer_2
+            .expect_apply_indexing_plan()
+            .times(1)
+            .withf(|request| request.indexing_tasks.len() == 1)
+            .returning(|_| Ok(ApplyIndexingPlanResponse {}));
+        let indexer_2 = indexer_node_info_with_capacity("indexer-2", mock_indexer_2);
+        indexer_pool.insert(indexer_2.node_id.clone(), indexer_2);
+
+        let pipeline_moves = scheduler.rebalance_plan(&model, true);
+        assert_eq!(pipeline_moves.len(), 1);
+        assert_eq!(pipeline_moves[0].index_uid(), &index_uid);
+        assert_eq!(pipeline_moves[0].from_node_id, "indexer-1");
+        assert_eq!(pipeline_moves[0].to_node_id, "indexer-2");
+        assert_eq!(pipeline_moves[0].num_pipelines, 1);
+
+        let state = scheduler.observable_state();
+        assert_eq!(state.num_applied_physical_indexing_plan, 1);
+        let last_applied_plan = state.last_applied_physical_plan.unwrap();
+        assert_eq!(last_applied_plan.indexer("indexer-1").unwrap().len(), 2);
+        assert!(last_applied_plan.indexer("indexer-2").is_none());
+
+        let waiter = scheduler.next_rebuild_tracker.next_rebuild_waiter();
+        let pipeline_moves = scheduler.rebalance_plan(&model, false);
+        assert_eq!(pipeline_moves.len(), 1);
+        waiter.await;
+
+        let state = scheduler.observable_state();
+        assert_eq!(state.num_applied_physical_indexing_plan, 2);
+        let last_applied_plan = state.last_applied_physical_plan.unwrap();
+        assert_eq!(last_applied_plan.indexer("indexer-1").unwrap().len(), 1);
+        assert_eq!(last_applied_plan.indexer("indexer-2").unwrap().len(), 1);
+
+        // The plan is now balanced.
+        assert!(scheduler.rebalance_plan(&model, false).is_empty());
+    }
+
     fn kafka_source_params_for_test() -> SourceParams {
         SourceParams::Kafka(KafkaSourceParams {
             topic: "topic".to_string(),
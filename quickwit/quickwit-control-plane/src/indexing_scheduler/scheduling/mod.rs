@@ -646,6 +646,41 @@ pub fn build_physical_indexing_plan(
     indexer_id_to_cpu_capacities: &FnvHashMap<String, CpuCapacity>,
     previous_plan_opt: Option<&PhysicalIndexingPlan>,
     shard_locations: &ShardLocations,
+) -> PhysicalIndexingPlan {
+    build_physical_indexing_plan_aux(
+        sources,
+        indexer_id_to_cpu_capacities,
+        previous_plan_opt,
+        shard_locations,
+        true,
+    )
+}
+
+/// Builds a physical indexing plan from scratch, as if no pipeline was running: contrary to
+/// [`build_physical_indexing_plan`], the pipelines are not kept on the indexers they run on in
+/// the previous plan. The previous plan is only used to preserve the pipeline UIDs of the
+/// pipelines that end up on the same indexer.
+pub fn build_rebalanced_physical_indexing_plan(
+    sources: &[SourceToSchedule],
+    indexer_id_to_cpu_capacities: &FnvHashMap<String, CpuCapacity>,
+    previous_plan_opt: Option<&PhysicalIndexingPlan>,
+    shard_locations: &ShardLocations,
+) -> PhysicalIndexingPlan {
+    build_physical_indexing_plan_aux(
+        sources,
+        indexer_id_to_cpu_capacities,
+        previous_plan_opt,
+        shard_locations,
+        false,
+    )
+}
+
+fn build_physical_indexing_plan_aux(
+    sources: &[SourceToSchedule],
+    indexer_id_to_cpu_capacities: &FnvHashMap<String, CpuCapacity>,
+    previous_plan_opt: Option<&PhysicalIndexingPlan>,
+    shard_locations: &ShardLocations,
+    start_from_previous_plan: bool,
 ) -> PhysicalIndexingPlan {
     // Asserts that the source are valid.
     check_sources(sources);
@@ -660,7 +695,7 @@ pub fn build_physical_indexing_plan(
 
     // Populate the previous solution, if any.
     let mut previous_solution = problem.new_solution();
-    if let Some(previous_plan) = previous_plan_opt {
+    if start_from_previous_plan && let Some(previous_plan) = previous_plan_opt {
         convert_physical_plan_to_solution(previous_plan, &id_to_ord_map, &mut previous_solution);
     }
 
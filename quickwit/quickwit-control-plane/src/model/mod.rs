@@ -215,8 +215,15 @@ impl ControlPlaneModel {
             bail!("index `{}` not found", index_uid.index_id);
         };
         let fp_changed = !index_model.index_config.equals_fingerprint(&index_config);
+        // Pausing or resuming the indexing of the index adds or removes its pipelines.
+        let pause_toggled = index_model
+            .index_config
+            .ingest_settings
+            .indexing_pause
+            .is_some()
+            != index_config.ingest_settings.indexing_pause.is_some();
         index_model.index_config = index_config;
-        Ok(fp_changed)
+        Ok(fp_changed || pause_toggled)
     }
 
     pub(crate) fn delete_index(&mut self, index_uid: &IndexUid) {
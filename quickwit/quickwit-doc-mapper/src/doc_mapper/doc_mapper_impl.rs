@@ -19,9 +19,9 @@ use std::sync::Arc;
 use anyhow::{Context, bail};
 use fnv::FnvHashSet;
 use quickwit_proto::types::DocMappingUid;
-use quickwit_query::create_default_quickwit_tokenizer_manager;
 use quickwit_query::query_ast::{BuildTantivyAstContext, QueryAst};
 use quickwit_query::tokenizers::TokenizerManager;
+use quickwit_query::{Scoring, create_default_quickwit_tokenizer_manager};
 use serde::{Deserialize, Serialize};
 use serde_json::{self, Value as JsonValue};
 use serde_json_borrow::Map as BorrowedJsonMap;
@@ -634,12 +634,15 @@ impl DocMapper {
     ///
     /// Considering schema evolution, splits within an index can have different schema
     /// over time. So `split_schema` is the schema of the split the query is targeting.
+    ///
+    /// The term queries score their hits with `scoring` if set, and with BM25 otherwise.
     pub fn query(
         &self,
         split_schema: Schema,
         query_ast: QueryAst,
         with_validation: bool,
         cache_context: Option<(Arc<dyn quickwit_query::query_ast::PredicateCache>, String)>,
+        scoring: Option<Scoring>,
     ) -> Result<(Box<dyn Query>, WarmupInfo), QueryParserError> {
         build_query(
             query_ast,
@@ -648,6 +651,7 @@ impl DocMapper {
                 tokenizer_manager: self.tokenizer_manager(),
                 search_fields: &self.default_search_field_names[..],
                 with_validation,
+                scoring,
             },
             cache_context,
         )
@@ -232,11 +232,14 @@ impl BinaryFormat {
     }
 }
 
+/// Format of the numeric values of the documents returned by searches.
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Default, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum NumericOutputFormat {
+    /// JSON number.
     #[default]
     Number,
+    /// JSON string, for clients losing the precision of large integers.
     String,
 }
 
@@ -282,7 +285,7 @@ impl QuickwitTextTokenizer {
     pub const fn from_static(name: &'static str) -> Self {
         Self(Cow::Borrowed(name))
     }
-    pub(crate) fn name(&self) -> &str {
+    pub fn name(&self) -> &str {
         &self.0
     }
     pub fn raw() -> Self {
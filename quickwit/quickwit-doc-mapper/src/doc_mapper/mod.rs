@@ -29,8 +29,8 @@ use std::ops::Bound;
 pub use doc_mapper_builder::DocMapperBuilder;
 pub use doc_mapper_impl::DocMapper;
 pub use field_mapping_entry::{
-    BinaryFormat, FastFieldOptions, FieldMappingEntry, QuickwitBytesOptions, QuickwitJsonOptions,
-    QuickwitTextNormalizer,
+    BinaryFormat, FastFieldOptions, FieldMappingEntry, NumericOutputFormat, QuickwitBytesOptions,
+    QuickwitJsonOptions, QuickwitTextNormalizer,
 };
 pub(crate) use field_mapping_entry::{
     FieldMappingEntryForSerialization, IndexRecordOptionSchema, QuickwitTextTokenizer,
@@ -299,7 +299,9 @@ mod tests {
         }
         .parse_user_query(&[])
         .unwrap();
-        let (query, _) = doc_mapper.query(schema, query_ast, true, None).unwrap();
+        let (query, _) = doc_mapper
+            .query(schema, query_ast, true, None, None)
+            .unwrap();
         assert_eq!(
             format!("{query:?}"),
             r#"TermQuery(Term(field=2, type=Json, path=toto.titi, type=Str, "hello"))"#
@@ -313,7 +315,9 @@ mod tests {
         let query_ast = query_ast_from_user_text("toto.titi:hello", None)
             .parse_user_query(doc_mapper.default_search_fields())
             .unwrap();
-        let (query, _) = doc_mapper.query(schema, query_ast, true, None).unwrap();
+        let (query, _) = doc_mapper
+            .query(schema, query_ast, true, None, None)
+            .unwrap();
         assert_eq!(
             format!("{query:?}"),
             r#"TermQuery(Term(field=1, type=Json, path=toto.titi, type=Str, "hello"))"#
@@ -327,7 +331,9 @@ mod tests {
         let query_ast = query_ast_from_user_text("toto:5", None)
             .parse_user_query(&[])
             .unwrap();
-        let (query, _) = doc_mapper.query(schema, query_ast, true, None).unwrap();
+        let (query, _) = doc_mapper
+            .query(schema, query_ast, true, None, None)
+            .unwrap();
         assert_eq!(
             format!("{query:?}"),
             r#"BooleanQuery { subqueries: [(Should, TermQuery(Term(field=1, type=Json, path=toto, type=I64, 5))), (Should, TermQuery(Term(field=1, type=Json, path=toto, type=Str, "5")))], minimum_number_should_match: 1 }"#
@@ -35,7 +35,7 @@ use quickwit_proto::metastore::{
     MetastoreService, MetastoreServiceClient, ResetSourceCheckpointRequest, UpdateIndexRequest,
     UpdateSourceRequest, serde_utils,
 };
-use quickwit_proto::types::{IndexUid, SplitId};
+use quickwit_proto::types::{DocMappingUid, IndexUid, SplitId};
 use quickwit_proto::{ServiceError, ServiceErrorCode};
 use quickwit_storage::{StorageResolver, StorageResolverError};
 use thiserror::Error;
@@ -114,6 +114,10 @@ impl IndexService {
         self.metastore.clone()
     }
 
+    pub fn storage_resolver(&self) -> StorageResolver {
+        self.storage_resolver.clone()
+    }
+
     /// Creates an index from `IndexConfig`.
     pub async fn create_index(
         &mut self,
@@ -179,14 +183,38 @@ impl IndexService {
         index_uid: IndexUid,
         index_config: IndexConfig,
     ) -> Result<IndexMetadata, IndexServiceError> {
-        let update_index_request = UpdateIndexRequest::try_from_updates(
+        self.update_index_inner(index_uid, index_config, None).await
+    }
+
+    /// Updates an index with the given index config, provided its doc mapping still has the UID
+    /// `expected_doc_mapping_uid`. Otherwise, the doc mapping was updated concurrently and the
+    /// update fails with a [`MetastoreError::FailedPrecondition`] error.
+    pub async fn update_index_if_doc_mapping_unchanged(
+        &self,
+        index_uid: IndexUid,
+        index_config: IndexConfig,
+        expected_doc_mapping_uid: DocMappingUid,
+    ) -> Result<IndexMetadata, IndexServiceError> {
+        self.update_index_inner(index_uid, index_config, Some(expected_doc_mapping_uid))
+            .await
+    }
+
+    async fn update_index_inner(
+        &self,
+        index_uid: IndexUid,
+        index_config: IndexConfig,
+        expected_doc_mapping_uid_opt: Option<DocMappingUid>,
+    ) -> Result<IndexMetadata, IndexServiceError> {
+        let mut update_index_request = UpdateIndexRequest::try_from_updates(
             index_uid,
             &index_config.doc_mapping,
             &index_config.indexing_settings,
             &index_config.ingest_settings,
             &index_config.search_settings,
             &index_config.retention_policy_opt,
+            &index_config.response_headers,
         )?;
+        update_index_request.expected_doc_mapping_uid = expected_doc_mapping_uid_opt;
         let update_index_response = self.metastore.update_index(update_index_request).await?;
         let index_metadata = update_index_response.deserialize_index_metadata()?;
         Ok(index_metadata)
@@ -282,6 +310,7 @@ impl IndexService {
     ) -> Result<Vec<SplitInfo>, IndexServiceError> {
         let list_indexes_metadatas_request = ListIndexesMetadataRequest {
             index_id_patterns: index_id_patterns.to_owned(),
+            paused_only: false,
         };
         // disallow index_id patterns
         for index_id_pattern in &index_id_patterns {
@@ -22,6 +22,7 @@ use quickwit_actors::{ActorContext, ActorExitStatus, Handler};
 use quickwit_proto::metastore::{MetastoreService, PublishSplitsRequest};
 use tracing::{info, instrument};
 
+use crate::actors::MergePhase;
 use crate::actors::publisher::{
     DisconnectMergePlanner, Publisher, serialize_checkpoint_delta, suggest_truncate,
 };
@@ -65,6 +66,9 @@ impl Handler<SplitsUpdate> for Publisher {
     ) -> Result<(), ActorExitStatus> {
         fail_point!("publisher:before");
 
+        if let Some(merge_task) = &split_update.merge_task {
+            merge_task.set_merge_phase(MergePhase::Publishing);
+        }
         let SplitsUpdate {
             index_uid,
             new_splits,
@@ -45,7 +45,7 @@ use tokio::runtime::Handle;
 use tokio::sync::Semaphore;
 use tracing::{debug, error, info, instrument, warn};
 
-use crate::actors::Packager;
+use crate::actors::{MergePhase, Packager};
 use crate::controlled_directory::ControlledDirectory;
 use crate::merge_policy::{MergeOperationType, MergeSource};
 use crate::models::{IndexedSplit, IndexedSplitBatch, MergeScratch, PublishLock, SplitAttrs};
@@ -98,6 +98,9 @@ impl Handler<MergeScratch> for MergeExecutor {
             merge_scratch_directory,
             ..
         } = merge_scratch;
+        if let MergeSource::Task(merge_task) = &merge_source {
+            merge_task.set_merge_phase(MergePhase::Merging);
+        }
         let merge_operation = merge_source.as_operation();
         // On nodes running the split compaction architecture, merge pipelines are ephemeral, and we
         // need to make sure there aren't too many CPU-bound operations occurring concurrently.
@@ -561,7 +564,7 @@ impl MergeExecutor {
                     parsed_query_ast
                 );
                 let (query, _) =
-                    doc_mapper.query(union_index.schema(), parsed_query_ast, false, None)?;
+                    doc_mapper.query(union_index.schema(), parsed_query_ast, false, None, None)?;
                 index_writer.delete_query(query)?;
             }
             debug!("commit-delete-operations");
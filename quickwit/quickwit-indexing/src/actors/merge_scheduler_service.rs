@@ -15,25 +15,32 @@
 use std::cmp::Reverse;
 use std::collections::BinaryHeap;
 use std::collections::binary_heap::PeekMut;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Instant;
 
 use anyhow::Context;
 use async_trait::async_trait;
 use quickwit_actors::{Actor, ActorContext, ActorExitStatus, Handler, Mailbox};
 #[cfg(feature = "metrics")]
 use quickwit_parquet_engine::merge::policy::ParquetMergeOperation;
-use tantivy::TrackedObject;
+use quickwit_proto::types::{IndexUid, SourceId, SplitId};
+use serde::{Deserialize, Serialize};
+use tantivy::{Inventory, TrackedObject};
 use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 use tracing::error;
 
 use super::MergeSplitDownloader;
 #[cfg(feature = "metrics")]
 use super::parquet_pipeline::{ParquetMergeSplitDownloader, ParquetMergeTask};
-use crate::merge_policy::{MergeOperation, MergeSource, MergeTask, compute_merge_score};
+use crate::merge_policy::{
+    MergeOperation, MergeOperationType, MergeSource, MergeTask, compute_merge_score,
+};
 use crate::metrics::{ONGOING_MERGE_OPERATIONS, PENDING_MERGE_BYTES, PENDING_MERGE_OPERATIONS};
 
 pub struct MergePermit {
     _semaphore_permit: Option<OwnedSemaphorePermit>,
+    // Keeps the merge listed as running until the permit is released.
+    running_merge_opt: Option<TrackedObject<RunningMerge>>,
     merge_scheduler_mailbox: Option<Mailbox<MergeSchedulerService>>,
 }
 
@@ -42,11 +49,117 @@ impl MergePermit {
     pub fn for_test() -> MergePermit {
         MergePermit {
             _semaphore_permit: None,
+            running_merge_opt: None,
             merge_scheduler_mailbox: None,
         }
     }
+
+    /// Records the phase the merge operation holding the permit has reached.
+    pub(crate) fn set_merge_phase(&self, merge_phase: MergePhase) {
+        if let Some(running_merge) = &self.running_merge_opt {
+            *running_merge
+                .merge_phase
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner) = merge_phase;
+        }
+    }
+}
+
+/// State of a merge operation known to the merge scheduler.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MergeOperationState {
+    /// The merge operation is waiting for a merge permit.
+    Pending,
+    /// The merge operation holds a merge permit and is being executed.
+    Running,
+}
+
+/// Phase of the merge pipeline a running merge operation has reached.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MergePhase {
+    /// The splits to merge are being downloaded.
+    #[default]
+    Downloading,
+    /// The downloaded splits are being merged.
+    Merging,
+    /// The merged split is being packaged.
+    Packaging,
+    /// The merged split is being uploaded.
+    Uploading,
+    /// The merged split is being published in place of the splits it replaces.
+    Publishing,
+}
+
+/// Describes a merge operation pending or running on this node.
+#[derive(Clone, Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct MergeOperationStatus {
+    #[schema(value_type = String)]
+    pub index_uid: IndexUid,
+    pub source_id: SourceId,
+    /// ID of the split produced by the merge operation.
+    #[schema(value_type = String)]
+    pub merge_split_id: SplitId,
+    #[schema(value_type = String)]
+    pub operation_type: MergeOperationType,
+    pub state: MergeOperationState,
+    /// IDs of the splits being merged.
+    #[schema(value_type = Vec<String>)]
+    pub split_ids: Vec<SplitId>,
+    pub num_docs: usize,
+    pub num_bytes: u64,
+    pub merge_level: usize,
+    /// Number of seconds elapsed since the merge operation started running.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub running_secs: Option<u64>,
+    /// Phase of the merge pipeline the merge operation has reached, if it is running.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phase: Option<MergePhase>,
+}
+
+impl MergeOperationStatus {
+    fn new(merge_operation: &MergeOperation, state: MergeOperationState) -> Self {
+        let (index_uid, source_id) = merge_operation
+            .splits
+            .first()
+            .map(|split| (split.index_uid.clone(), split.source_id.clone()))
+            .unwrap_or_default();
+        MergeOperationStatus {
+            index_uid,
+            source_id,
+            merge_split_id: merge_operation.merge_split_id.clone(),
+            operation_type: merge_operation.operation_type.clone(),
+            state,
+            split_ids: merge_operation
+                .splits
+                .iter()
+                .map(|split| split.split_id().clone())
+                .collect(),
+            num_docs: merge_operation
+                .splits
+                .iter()
+                .map(|split| split.num_docs)
+                .sum(),
+            num_bytes: merge_operation.total_num_bytes(),
+            merge_level: merge_operation.merge_level(),
+            running_secs: None,
+            phase: None,
+        }
+    }
+}
+
+struct RunningMerge {
+    merge_operation_status: MergeOperationStatus,
+    started_at: Instant,
+    merge_phase: Mutex<MergePhase>,
 }
 
+/// Lists the merge operations pending or running on this node, running ones first, then pending
+/// ones in the order in which they will be scheduled.
+#[derive(Debug)]
+pub struct ListMergeOperations;
+
 impl Drop for MergePermit {
     fn drop(&mut self) {
         let Some(merge_scheduler_mailbox) = self.merge_scheduler_mailbox.take() else {
@@ -178,6 +291,7 @@ pub struct MergeSchedulerService {
     pending_parquet_merge_queue: BinaryHeap<ScheduledParquetMerge>,
     next_merge_id: u64,
     pending_merge_bytes: u64,
+    running_merges: Inventory<RunningMerge>,
 }
 
 impl Default for MergeSchedulerService {
@@ -197,7 +311,38 @@ impl MergeSchedulerService {
             pending_parquet_merge_queue: BinaryHeap::default(),
             next_merge_id: 0,
             pending_merge_bytes: 0,
+            running_merges: Inventory::default(),
+        }
+    }
+
+    fn list_merge_operations(&self) -> Vec<MergeOperationStatus> {
+        let mut merge_operations: Vec<MergeOperationStatus> = self
+            .running_merges
+            .list()
+            .iter()
+            .map(|running_merge| {
+                let mut merge_operation_status = running_merge.merge_operation_status.clone();
+                let running_secs = running_merge.started_at.elapsed().as_secs();
+                let merge_phase = *running_merge
+                    .merge_phase
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner);
+                merge_operation_status.running_secs = Some(running_secs);
+                merge_operation_status.phase = Some(merge_phase);
+                merge_operation_status
+            })
+            .collect();
+        let mut pending_merges: Vec<&ScheduledMerge> = self.pending_merge_queue.iter().collect();
+        pending_merges.sort_by(|left, right| right.cmp(left));
+
+        for pending_merge in pending_merges {
+            let merge_operation_status = MergeOperationStatus::new(
+                &pending_merge.merge_operation,
+                MergeOperationState::Pending,
+            );
+            merge_operations.push(merge_operation_status);
         }
+        merge_operations
     }
 
     fn schedule_pending_merges(&mut self, ctx: &ActorContext<Self>) {
@@ -213,8 +358,17 @@ impl MergeSchedulerService {
                 // No permit available right away.
                 break;
             };
+            let running_merge = RunningMerge {
+                merge_operation_status: MergeOperationStatus::new(
+                    &next_merge.merge_operation,
+                    MergeOperationState::Running,
+                ),
+                started_at: Instant::now(),
+                merge_phase: Mutex::default(),
+            };
             let merge_permit = MergePermit {
                 _semaphore_permit: Some(semaphore_permit),
+                running_merge_opt: Some(self.running_merges.track(running_merge)),
                 merge_scheduler_mailbox: Some(ctx.mailbox().clone()),
             };
             let ScheduledMerge {
@@ -255,6 +409,7 @@ impl MergeSchedulerService {
             };
             let merge_permit = MergePermit {
                 _semaphore_permit: Some(semaphore_permit),
+                running_merge_opt: None,
                 merge_scheduler_mailbox: Some(ctx.mailbox().clone()),
             };
             let ScheduledParquetMerge {
@@ -376,6 +531,19 @@ impl Handler<PermitReleased> for MergeSchedulerService {
     }
 }
 
+#[async_trait]
+impl Handler<ListMergeOperations> for MergeSchedulerService {
+    type Reply = Vec<MergeOperationStatus>;
+
+    async fn handle(
+        &mut self,
+        _: ListMergeOperations,
+        _ctx: &ActorContext<Self>,
+    ) -> Result<Vec<MergeOperationStatus>, ActorExitStatus> {
+        Ok(self.list_merge_operations())
+    }
+}
+
 // --- Parquet merge scheduling (feature-gated) ---
 
 #[cfg(feature = "metrics")]
@@ -587,4 +755,72 @@ mod tests {
         }
         universe.assert_quit().await;
     }
+
+    #[tokio::test]
+    async fn test_merge_schedule_service_list_merge_operations() {
+        let universe = Universe::new();
+        let (merge_scheduler_service, _) = universe
+            .spawn_builder()
+            .spawn(MergeSchedulerService::new(1));
+        let inventory = Inventory::new();
+
+        let (merge_split_downloader_mailbox, merge_split_downloader_inbox) =
+            universe.create_test_mailbox();
+        let merge_operation = build_merge_operation(2, 1_000);
+        let running_merge_split_id = merge_operation.merge_split_id.clone();
+        let pending_merge_operation = build_merge_operation(3, 1_000);
+        let pending_merge_split_id = pending_merge_operation.merge_split_id.clone();
+
+        for merge_operation in [merge_operation, pending_merge_operation] {
+            schedule_merge(
+                &merge_scheduler_service,
+                inventory.track(merge_operation),
+                merge_split_downloader_mailbox.clone(),
+            )
+            .await
+            .unwrap();
+        }
+        let merge_operations = merge_scheduler_service
+            .ask(ListMergeOperations)
+            .await
+            .unwrap();
+        assert_eq!(merge_operations.len(), 2);
+        assert_eq!(merge_operations[0].merge_split_id, running_merge_split_id);
+        assert_eq!(merge_operations[0].state, MergeOperationState::Running);
+        assert_eq!(merge_operations[0].num_bytes, 2_000);
+        assert!(merge_operations[0].running_secs.is_some());
+        assert_eq!(merge_operations[0].phase, Some(MergePhase::Downloading));
+        assert_eq!(merge_operations[1].merge_split_id, pending_merge_split_id);
+        assert_eq!(merge_operations[1].state, MergeOperationState::Pending);
+        assert!(merge_operations[1].running_secs.is_none());
+        assert!(merge_operations[1].phase.is_none());
+
+        let merge_source = merge_split_downloader_inbox
+            .recv_typed_message::<MergeSource>()
+            .await
+            .unwrap();
+        let MergeSource::Task(merge_task) = &merge_source else {
+            panic!("expected a merge task");
+        };
+        merge_task.set_merge_phase(MergePhase::Uploading);
+
+        let merge_operations = merge_scheduler_service
+            .ask(ListMergeOperations)
+            .await
+            .unwrap();
+        assert_eq!(merge_operations[0].phase, Some(MergePhase::Uploading));
+
+        // Completing the running merge releases its permit and starts the pending one.
+        drop(merge_source);
+
+        let merge_operations = merge_scheduler_service
+            .ask(ListMergeOperations)
+            .await
+            .unwrap();
+        assert_eq!(merge_operations.len(), 1);
+        assert_eq!(merge_operations[0].merge_split_id, pending_merge_split_id);
+        assert_eq!(merge_operations[0].state, MergeOperationState::Running);
+
+        universe.assert_quit().await;
+    }
 }
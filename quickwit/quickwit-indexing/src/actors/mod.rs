@@ -52,7 +52,10 @@ pub(crate) use merge_planner::MergePlanner;
 pub(crate) use merge_planner::RunFinalizeMergePolicyAndQuit;
 #[cfg(feature = "metrics")]
 pub use merge_scheduler_service::schedule_parquet_merge;
-pub use merge_scheduler_service::{MergePermit, MergeSchedulerService, schedule_merge};
+pub use merge_scheduler_service::{
+    ListMergeOperations, MergeOperationState, MergeOperationStatus, MergePermit, MergePhase,
+    MergeSchedulerService, schedule_merge,
+};
 pub use merge_split_downloader::MergeSplitDownloader;
 pub use packager::Packager;
 #[cfg(feature = "metrics")]
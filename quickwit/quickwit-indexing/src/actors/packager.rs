@@ -41,7 +41,7 @@ const MAX_VALUES_PER_TAG_FIELD: usize = if cfg!(any(test, feature = "testsuite")
     1000
 };
 
-use crate::actors::Uploader;
+use crate::actors::{MergePhase, Uploader};
 use crate::models::{
     EmptySplit, IndexedSplit, IndexedSplitBatch, PackagedSplit, PackagedSplitBatch,
 };
@@ -133,6 +133,9 @@ impl Handler<IndexedSplitBatch> for Packager {
             "start-packaging-splits"
         );
         fail_point!("packager:before");
+        if let Some(merge_task) = &batch.merge_task_opt {
+            merge_task.set_merge_phase(MergePhase::Packaging);
+        }
         let mut packaged_splits = Vec::with_capacity(batch.splits.len());
         for split in batch.splits {
             if batch.publish_lock.is_dead() {
@@ -38,8 +38,8 @@ use tokio::sync::oneshot::Sender;
 use tokio::sync::{Semaphore, SemaphorePermit, oneshot};
 use tracing::{Instrument, Span, debug, info, instrument, warn};
 
-use crate::actors::Publisher;
 use crate::actors::sequencer::{Sequencer, SequencerCommand};
+use crate::actors::{MergePhase, Publisher};
 use crate::merge_policy::{MergePolicy, MergeTask};
 use crate::metrics::{AVAILABLE_CONCURRENT_UPLOAD_PERMITS, COMPONENT};
 use crate::models::{
@@ -270,6 +270,9 @@ impl Handler<PackagedSplitBatch> for Uploader {
         ctx: &ActorContext<Self>,
     ) -> Result<(), ActorExitStatus> {
         fail_point!("uploader:before");
+        if let Some(merge_task) = &batch.merge_task_opt {
+            merge_task.set_merge_phase(MergePhase::Uploading);
+        }
         let split_update_sender = self
             .split_update_mailbox
             .get_split_update_sender(ctx)
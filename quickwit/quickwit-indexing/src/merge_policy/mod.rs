@@ -27,14 +27,14 @@ use quickwit_config::IndexingSettings;
 use quickwit_config::merge_policy_config::MergePolicyConfig;
 use quickwit_metastore::{SplitMaturity, SplitMetadata};
 use quickwit_proto::types::SplitId;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 pub(crate) use stable_log_merge_policy::StableLogMergePolicy;
 use tantivy::TrackedObject;
 use tracing::{Span, info_span};
 
-use crate::actors::MergePermit;
+use crate::actors::{MergePermit, MergePhase};
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum MergeOperationType {
     Merge,
     DeleteAndMerge,
@@ -52,6 +52,11 @@ pub struct MergeTask {
 }
 
 impl MergeTask {
+    /// Records the phase of the merge pipeline the task has reached.
+    pub(crate) fn set_merge_phase(&self, merge_phase: MergePhase) {
+        self._merge_permit.set_merge_phase(merge_phase);
+    }
+
     #[cfg(any(test, feature = "testsuite"))]
     pub fn from_merge_operation_for_test(merge_operation: MergeOperation) -> MergeTask {
         let inventory = tantivy::Inventory::default();
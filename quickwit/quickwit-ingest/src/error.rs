@@ -28,6 +28,8 @@ use serde::{Deserialize, Serialize};
 pub enum IngestServiceError {
     #[error("data corruption: {0}")]
     Corruption(String),
+    #[error("forbidden: {0}")]
+    Forbidden(String),
     #[error("index `{index_id}` already exists")]
     IndexAlreadyExists { index_id: IndexId },
     #[error("index `{index_id}` not found")]
@@ -44,6 +46,8 @@ pub enum IngestServiceError {
     Unavailable(String),
     #[error("bad request ({0})")]
     BadRequest(String),
+    #[error("unsupported media type: {0}")]
+    UnsupportedMediaType(String),
 }
 
 impl From<AskError<IngestServiceError>> for IngestServiceError {
@@ -145,6 +149,7 @@ impl ServiceError for IngestServiceError {
                 );
                 ServiceErrorCode::Internal
             }
+            Self::Forbidden(_) => ServiceErrorCode::Forbidden,
             Self::IndexAlreadyExists { .. } => ServiceErrorCode::AlreadyExists,
             Self::IndexNotFound { .. } => ServiceErrorCode::NotFound,
             Self::Internal(err_msg) => {
@@ -159,6 +164,7 @@ impl ServiceError for IngestServiceError {
             Self::RateLimited(_) => ServiceErrorCode::TooManyRequests,
             Self::Unavailable(_) => ServiceErrorCode::Unavailable,
             Self::BadRequest(_) => ServiceErrorCode::BadRequest,
+            Self::UnsupportedMediaType(_) => ServiceErrorCode::UnsupportedMediaType,
         }
     }
 }
@@ -195,6 +201,7 @@ impl From<IngestServiceError> for tonic::Status {
     fn from(error: IngestServiceError) -> tonic::Status {
         let code = match &error {
             IngestServiceError::Corruption { .. } => tonic::Code::DataLoss,
+            IngestServiceError::Forbidden(_) => tonic::Code::PermissionDenied,
             IngestServiceError::IndexAlreadyExists { .. } => tonic::Code::AlreadyExists,
             IngestServiceError::IndexNotFound { .. } => tonic::Code::NotFound,
             IngestServiceError::Internal(_) => tonic::Code::Internal,
@@ -203,6 +210,7 @@ impl From<IngestServiceError> for tonic::Status {
             IngestServiceError::RateLimited(_) => tonic::Code::ResourceExhausted,
             IngestServiceError::Unavailable(_) => tonic::Code::Unavailable,
             IngestServiceError::BadRequest(_) => tonic::Code::InvalidArgument,
+            IngestServiceError::UnsupportedMediaType(_) => tonic::Code::InvalidArgument,
         };
         let message = error.to_string();
         tonic::Status::new(code, message)
@@ -650,7 +650,7 @@ impl ClusterSandbox {
                 index_id,
                 SearchRequestQueryString {
                     query: query.to_string(),
-                    max_hits: 10,
+                    max_hits: Some(10),
                     ..Default::default()
                 },
             )
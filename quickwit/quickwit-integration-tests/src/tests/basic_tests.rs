@@ -88,7 +88,7 @@ async fn test_standalone_server() {
                     "my-new-index",
                     SearchRequestQueryString {
                         query: "body:test".to_string(),
-                        max_hits: 10,
+                        max_hits: Some(10),
                         ..Default::default()
                     },
                 )
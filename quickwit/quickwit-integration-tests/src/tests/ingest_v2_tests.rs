@@ -309,6 +309,8 @@ async fn test_ingest_v2_happy_path() {
             num_ingested_docs: Some(1),
             num_rejected_docs: Some(0),
             parse_failures: None,
+            num_deduplicated_docs: None,
+            num_filtered_docs: None,
         },
     );
 
@@ -375,6 +377,8 @@ async fn test_commit_force() {
             num_ingested_docs: Some(1),
             num_rejected_docs: Some(0),
             parse_failures: None,
+            num_deduplicated_docs: None,
+            num_filtered_docs: None,
         },
     );
 
@@ -455,6 +459,8 @@ async fn test_commit_wait_for() {
             num_ingested_docs: Some(1),
             num_rejected_docs: Some(0),
             parse_failures: None,
+            num_deduplicated_docs: None,
+            num_filtered_docs: None,
         },
     );
     assert_eq!(
@@ -464,6 +470,8 @@ async fn test_commit_wait_for() {
             num_ingested_docs: Some(1),
             num_rejected_docs: Some(0),
             parse_failures: None,
+            num_deduplicated_docs: None,
+            num_filtered_docs: None,
         },
     );
 
@@ -526,6 +534,8 @@ async fn test_commit_auto() {
             num_ingested_docs: Some(1),
             num_rejected_docs: Some(0),
             parse_failures: None,
+            num_deduplicated_docs: None,
+            num_filtered_docs: None,
         },
     );
 
@@ -584,6 +594,8 @@ async fn test_detailed_ingest_response() {
                 message: "failed to parse JSON document".to_string(),
                 reason: ParseFailureReason::InvalidJson,
             }]),
+            num_deduplicated_docs: None,
+            num_filtered_docs: None,
         },
     );
     sandbox.shutdown().await.unwrap();
@@ -902,6 +914,8 @@ async fn test_graceful_shutdown_single_node() {
             num_ingested_docs: Some(1),
             num_rejected_docs: Some(0),
             parse_failures: None,
+            num_deduplicated_docs: None,
+            num_filtered_docs: None,
         },
     );
 
@@ -1025,7 +1039,7 @@ async fn test_graceful_shutdown_no_data_loss() {
                     index_id,
                     quickwit_serve::SearchRequestQueryString {
                         query: "*".to_string(),
-                        max_hits: 10,
+                        max_hits: Some(10),
                         ..Default::default()
                     },
                 )
@@ -1299,7 +1313,7 @@ async fn test_retiring_indexer_decommissions_gracefully() {
                     index_id,
                     quickwit_serve::SearchRequestQueryString {
                         query: "*".to_string(),
-                        max_hits: 10,
+                        max_hits: Some(10),
                         ..Default::default()
                     },
                 )
@@ -87,7 +87,7 @@ async fn test_searcher_and_metastore_without_control_plane() {
             "does-not-exist",
             SearchRequestQueryString {
                 query: String::new(),
-                max_hits: 10,
+                max_hits: Some(10),
                 ..Default::default()
             },
         )
@@ -31,7 +31,7 @@ async fn assert_hits_unordered(
             index_id,
             SearchRequestQueryString {
                 query: query.to_string(),
-                max_hits: expected_result.map(|hits| hits.len() as u64).unwrap_or(1),
+                max_hits: Some(expected_result.map(|hits| hits.len() as u64).unwrap_or(1)),
                 ..Default::default()
             },
         )
@@ -270,6 +270,7 @@ fn compute_deleted_indexes<'a>(
 #[cfg(test)]
 mod tests {
     use std::ops::RangeInclusive;
+    use std::sync::{Arc, Mutex};
 
     use mockall::Sequence;
     use quickwit_actors::Universe;
@@ -282,8 +283,10 @@ mod tests {
     use quickwit_proto::metastore::{
         EmptyResponse, ListIndexesMetadataResponse, ListSplitsResponse, MockMetastoreService,
     };
+    use time::OffsetDateTime;
 
     use super::*;
+    use crate::list_expired_splits;
 
     #[derive(Debug)]
     struct AssertState(Vec<(&'static str, Option<&'static str>)>);
@@ -519,6 +522,78 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_list_expired_splits_matches_retention_policy_execution() {
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+        let mut mock_metastore = MockMetastoreService::new();
+        mock_metastore
+            .expect_list_indexes_metadata()
+            .returning(|_list_indexes_request| {
+                let indexes_metadata = make_indexes(&[("index-1", Some("2 hour"))]);
+                Ok(ListIndexesMetadataResponse::for_test(indexes_metadata))
+            });
+        mock_metastore
+            .expect_list_splits()
+            .returning(move |list_splits_request| {
+                let query = list_splits_request.deserialize_list_splits_query().unwrap();
+                let max_time_range_end = query.max_time_range_end.unwrap();
+                // The metastore returns the splits lacking a time range along with the expired
+                // ones.
+                let splits: Vec<Split> = [
+                    make_split("split-1", Some(now - 10_000..=now - 8_000)),
+                    make_split("split-2", Some(now - 8_000..=now - 6_000)),
+                    make_split("split-3", None),
+                ]
+                .into_iter()
+                .filter(|split| match &split.split_metadata.time_range {
+                    Some(time_range) => *time_range.end() <= max_time_range_end,
+                    None => true,
+                })
+                .collect();
+                let splits_response = ListSplitsResponse::try_from_splits(splits).unwrap();
+                Ok(ServiceStream::from(vec![Ok(splits_response)]))
+            });
+        let marked_split_ids: Arc<Mutex<Vec<String>>> = Arc::default();
+        let marked_split_ids_clone = marked_split_ids.clone();
+        mock_metastore.expect_mark_splits_for_deletion().returning(
+            move |mark_splits_for_deletion_request| {
+                marked_split_ids_clone
+                    .lock()
+                    .unwrap()
+                    .extend(mark_splits_for_deletion_request.split_ids);
+                Ok(EmptyResponse {})
+            },
+        );
+        let metastore = MetastoreServiceClient::from_mock(mock_metastore);
+
+        let (expired_splits, ignored_splits) = list_expired_splits(
+            IndexUid::for_test("index-1", 0),
+            &metastore,
+            Duration::from_secs(2 * 3600),
+        )
+        .await
+        .unwrap();
+        let expired_split_ids: Vec<String> = expired_splits
+            .into_iter()
+            .map(|split_metadata| split_metadata.split_id)
+            .collect();
+        assert_eq!(expired_split_ids, ["split-1"]);
+        assert_eq!(ignored_splits.len(), 1);
+        assert_eq!(ignored_splits[0].split_id, "split-3");
+
+        let retention_policy_executor = RetentionPolicyExecutor::new(metastore);
+        let universe = Universe::with_accelerated_time();
+        let (_mailbox, handle) = universe.spawn_builder().spawn(retention_policy_executor);
+
+        universe.sleep(shift_time_by()).await;
+        let counters = handle.process_pending_and_observe().await.state;
+        assert_eq!(counters.num_execution_passes, 1);
+
+        // The janitor deletes the splits listed by the preview.
+        assert_eq!(*marked_split_ids.lock().unwrap(), expired_split_ids);
+        universe.assert_quit().await;
+    }
+
     #[tokio::test]
     async fn test_parquet_retention_policy_execution_calls_dependencies() -> anyhow::Result<()> {
         use quickwit_metastore::{ListParquetSplitsResponseExt, ParquetSplitRecord, SplitState};
@@ -32,6 +32,7 @@ mod metrics;
 mod retention_policy_execution;
 
 pub use janitor_service::JanitorService;
+pub use retention_policy_execution::list_expired_splits;
 
 use crate::actors::{DeleteTaskService, GarbageCollector, RetentionPolicyExecutor};
 
@@ -12,6 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::time::Duration;
+
 use quickwit_actors::ActorContext;
 use quickwit_common::is_sketches_index;
 use quickwit_common::pretty::PrettySample;
@@ -23,7 +25,7 @@ use quickwit_metastore::{
 use quickwit_parquet_engine::split::ParquetSplitKind;
 use quickwit_proto::metastore::{
     ListSplitsRequest, MarkMetricsSplitsForDeletionRequest, MarkSketchSplitsForDeletionRequest,
-    MarkSplitsForDeletionRequest, MetastoreService, MetastoreServiceClient,
+    MarkSplitsForDeletionRequest, MetastoreResult, MetastoreService, MetastoreServiceClient,
 };
 use quickwit_proto::types::{IndexUid, SplitId};
 use time::OffsetDateTime;
@@ -31,6 +33,31 @@ use tracing::{info, warn};
 
 use crate::actors::RetentionPolicyExecutor;
 
+/// Lists the published splits of an index that have been expired for `retention_period`, split
+/// into the splits having a time range, which a retention policy would mark for deletion, and the
+/// splits lacking one, which it cannot be applied to.
+pub async fn list_expired_splits(
+    index_uid: IndexUid,
+    metastore: &MetastoreServiceClient,
+    retention_period: Duration,
+) -> MetastoreResult<(Vec<SplitMetadata>, Vec<SplitMetadata>)> {
+    let current_timestamp = OffsetDateTime::now_utc().unix_timestamp();
+    let max_retention_timestamp = current_timestamp - retention_period.as_secs() as i64;
+    let query = ListSplitsQuery::for_index(index_uid)
+        .with_split_state(SplitState::Published)
+        .with_max_time_range_end(max_retention_timestamp);
+
+    let list_splits_request = ListSplitsRequest::try_from_list_splits_query(&query)?;
+    let splits = metastore
+        .list_splits(list_splits_request)
+        .await?
+        .collect_splits_metadata()
+        .await?
+        .into_iter()
+        .partition(|split_metadata| split_metadata.time_range.is_some());
+    Ok(splits)
+}
+
 /// Detect all expired splits based a retention policy and
 /// only mark them as `MarkedForDeletion`. Actual split deletion
 /// is taken care of by the garbage collector.
@@ -47,20 +74,13 @@ pub async fn run_execute_retention_policy(
 ) -> anyhow::Result<Vec<SplitMetadata>> {
     // Select splits that are published and older than the retention period.
     let retention_period = retention_policy.retention_period()?;
-    let current_timestamp = OffsetDateTime::now_utc().unix_timestamp();
-    let max_retention_timestamp = current_timestamp - retention_period.as_secs() as i64;
-    let query = ListSplitsQuery::for_index(index_uid.clone())
-        .with_split_state(SplitState::Published)
-        .with_max_time_range_end(max_retention_timestamp);
-
-    let list_splits_request = ListSplitsRequest::try_from_list_splits_query(&query)?;
-    let (expired_splits, ignored_splits): (Vec<SplitMetadata>, Vec<SplitMetadata>) = ctx
-        .protect_future(metastore.list_splits(list_splits_request))
-        .await?
-        .collect_splits_metadata()
-        .await?
-        .into_iter()
-        .partition(|split_metadata| split_metadata.time_range.is_some());
+    let (expired_splits, ignored_splits) = ctx
+        .protect_future(list_expired_splits(
+            index_uid.clone(),
+            &metastore,
+            retention_period,
+        ))
+        .await?;
 
     if !ignored_splits.is_empty() {
         let ignored_split_ids: Vec<&str> = ignored_splits
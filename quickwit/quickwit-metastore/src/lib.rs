@@ -38,7 +38,7 @@ use std::ops::Range;
 
 pub use error::MetastoreResolverError;
 pub use metastore::control_plane_metastore::ControlPlaneMetastore;
-pub use metastore::file_backed::FileBackedMetastore;
+pub use metastore::file_backed::{FileBackedMetastore, IndexIdMatcher};
 pub(crate) use metastore::index_metadata::serialize::{IndexMetadataV0_8, VersionedIndexMetadata};
 #[cfg(feature = "postgres")]
 pub use metastore::postgres::PostgresqlMetastore;
@@ -50,7 +50,8 @@ pub use metastore::{
     MetastoreServiceStreamSplitsExt, PARQUET_SPLITS_PAGE_SIZE, ParquetSplitRecord,
     ParquetSplitsPage, PublishParquetSplitsRequestExt, PublishSplitsRequestExt, SortBy,
     StageParquetSplitsRequestExt, StageSplitsRequestExt, UpdateIndexRequestExt,
-    UpdateSourceRequestExt, file_backed, list_parquet_splits_page, list_parquet_splits_paginated,
+    UpdateSourceRequestExt, UpdateSplitsTagsRequestExt, file_backed, list_parquet_splits_page,
+    list_parquet_splits_paginated,
 };
 pub use metastore_factory::{MetastoreFactory, MetastoreFactoryOptions, UnsupportedMetastore};
 pub use metastore_resolver::MetastoreResolver;
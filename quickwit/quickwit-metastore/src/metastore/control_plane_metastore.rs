@@ -37,7 +37,8 @@ use quickwit_proto::metastore::{
     PublishMetricsSplitsRequest, PublishSketchSplitsRequest, PublishSplitsRequest,
     ResetSourceCheckpointRequest, StageMetricsSplitsRequest, StageSketchSplitsRequest,
     StageSplitsRequest, ToggleSourceRequest, UpdateIndexRequest, UpdateSourceRequest,
-    UpdateSplitsDeleteOpstampRequest, UpdateSplitsDeleteOpstampResponse,
+    UpdateSplitsDeleteOpstampRequest, UpdateSplitsDeleteOpstampResponse, UpdateSplitsTagsRequest,
+    UpdateSplitsTagsResponse,
 };
 use tracing::instrument;
 
@@ -242,6 +243,14 @@ impl MetastoreService for ControlPlaneMetastore {
         self.metastore.update_splits_delete_opstamp(request).await
     }
 
+    #[instrument(name = "metastore.control_plane.update_splits_tags", skip_all, fields(index_uid = %request.index_uid()))]
+    async fn update_splits_tags(
+        &self,
+        request: UpdateSplitsTagsRequest,
+    ) -> MetastoreResult<UpdateSplitsTagsResponse> {
+        self.metastore.update_splits_tags(request).await
+    }
+
     #[instrument(name = "metastore.control_plane.list_delete_tasks", skip_all, fields(index_uid = %request.index_uid()))]
     async fn list_delete_tasks(
         &self,
@@ -19,7 +19,7 @@
 mod serialize;
 mod shards;
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::Debug;
 use std::ops::Bound;
 
@@ -33,7 +33,7 @@ use quickwit_proto::metastore::{
     AcquireShardsRequest, AcquireShardsResponse, DeleteQuery, DeleteShardsRequest,
     DeleteShardsResponse, DeleteTask, EntityKind, IndexStats, ListShardsSubrequest,
     ListShardsSubresponse, MetastoreError, MetastoreResult, OpenShardSubrequest,
-    OpenShardSubresponse, PruneShardsRequest, SplitStats,
+    OpenShardSubresponse, PruneShardsRequest, SplitStats, UpdateSplitsTagsRequest,
 };
 use quickwit_proto::types::{IndexUid, PublishToken, SourceId, SplitId};
 use serde::{Deserialize, Serialize};
@@ -45,7 +45,10 @@ use tracing::{info, warn};
 use super::MutationOccurred;
 use crate::checkpoint::IndexCheckpointDelta;
 use crate::metastore::{ListParquetSplitsQuery, SortBy, use_shard_api};
-use crate::{IndexMetadata, ListSplitsQuery, Split, SplitMetadata, SplitState, split_tag_filter};
+use crate::{
+    IndexMetadata, ListSplitsQuery, Split, SplitMetadata, SplitState, UpdateSplitsTagsRequestExt,
+    split_tag_filter,
+};
 
 /// A stored parquet split (metrics or sketch) with its state.
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -289,6 +292,7 @@ impl FileBackedIndex {
         ingest_settings: IngestSettings,
         search_settings: SearchSettings,
         retention_policy_opt: Option<RetentionPolicy>,
+        response_headers: BTreeMap<String, String>,
     ) -> MetastoreResult<bool> {
         self.metadata.update_index_config(
             doc_mapping,
@@ -296,6 +300,7 @@ impl FileBackedIndex {
             ingest_settings,
             search_settings,
             retention_policy_opt,
+            response_headers,
         )
     }
 
@@ -666,6 +671,30 @@ impl FileBackedIndex {
         Ok(true)
     }
 
+    /// Adds and removes tags to/from the published splits matching the filter of the request.
+    /// Returns the number of splits whose tags changed.
+    pub(crate) fn update_splits_tags(
+        &mut self,
+        request: &UpdateSplitsTagsRequest,
+    ) -> MetastoreResult<u64> {
+        request.validate_tags(&self.metadata.index_config.doc_mapping.tag_fields)?;
+
+        let list_splits_query = request.list_splits_query();
+        let now_timestamp = OffsetDateTime::now_utc().unix_timestamp();
+        let mut num_updated_splits = 0;
+
+        for split in self.splits.values_mut() {
+            if !split_query_predicate(&&*split, &list_splits_query) {
+                continue;
+            }
+            if request.update_tags(&mut split.split_metadata.tags) {
+                split.update_timestamp = now_timestamp;
+                num_updated_splits += 1;
+            }
+        }
+        Ok(num_updated_splits)
+    }
+
     /// Lists delete tasks with opstamp > `opstamp_start`.
     pub(crate) fn list_delete_tasks(&self, opstamp_start: u64) -> MetastoreResult<Vec<DeleteTask>> {
         let delete_tasks = self
@@ -1298,6 +1327,10 @@ fn split_query_predicate(split: &&Split, query: &ListSplitsQuery) -> bool {
         return false;
     }
 
+    if !query.split_ids.is_empty() && !query.split_ids.contains(&split.split_metadata.split_id) {
+        return false;
+    }
+
     true
 }
 
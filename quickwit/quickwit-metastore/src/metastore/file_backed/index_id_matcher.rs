@@ -12,15 +12,15 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use quickwit_common::glob_to_regex;
 use quickwit_config::validate_index_id_pattern;
 use quickwit_proto::metastore::{MetastoreError, MetastoreResult};
 use regex::RegexSet;
-use regex_syntax::escape_into;
 
 pub(super) type IndexIdPattern = String;
 
-#[derive(Debug)]
-pub(super) struct IndexIdMatcher {
+#[derive(Clone, Debug)]
+pub struct IndexIdMatcher {
     positive_matcher: RegexSet,
     negative_matcher: RegexSet,
 }
@@ -75,7 +75,7 @@ fn build_regex_set(patterns: &[&str]) -> MetastoreResult<RegexSet> {
             MetastoreError::InvalidArgument { message }
         })?;
     }
-    let regexes = patterns.iter().map(|pattern| build_regex(pattern));
+    let regexes = patterns.iter().map(|pattern| glob_to_regex(pattern));
 
     let regex_set = RegexSet::new(regexes).map_err(|error| {
         let message = format!("failed to build index ID matcher: {error}");
@@ -84,42 +84,10 @@ fn build_regex_set(patterns: &[&str]) -> MetastoreResult<RegexSet> {
     Ok(regex_set)
 }
 
-fn build_regex(pattern: &str) -> String {
-    let mut regex = String::new();
-    regex.push('^');
-
-    for (idx, part) in pattern.split('*').enumerate() {
-        if idx > 0 {
-            regex.push_str(".*");
-        }
-        escape_into(part, &mut regex);
-    }
-    regex.push('$');
-    regex
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_build_regex() {
-        let regex = build_regex("");
-        assert_eq!(regex, r"^$");
-
-        let regex = build_regex("*");
-        assert_eq!(regex, r"^.*$");
-
-        let regex = build_regex("index-1");
-        assert_eq!(regex, r"^index\-1$");
-
-        let regex = build_regex("*-index-*-1");
-        assert_eq!(regex, r"^.*\-index\-.*\-1$");
-
-        let regex = build_regex("INDEX.2*-1");
-        assert_eq!(regex, r"^INDEX\.2.*\-1$");
-    }
-
     #[test]
     fn test_build_regex_set() {
         let error = build_regex_set(&["_index-1"]).unwrap_err();
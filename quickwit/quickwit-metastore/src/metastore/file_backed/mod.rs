@@ -61,7 +61,8 @@ use quickwit_proto::metastore::{
     PublishSketchSplitsRequest, PublishSplitsRequest, ResetSourceCheckpointRequest,
     StageMetricsSplitsRequest, StageSketchSplitsRequest, StageSplitsRequest, ToggleSourceRequest,
     UpdateIndexRequest, UpdateSourceRequest, UpdateSplitsDeleteOpstampRequest,
-    UpdateSplitsDeleteOpstampResponse, serde_utils,
+    UpdateSplitsDeleteOpstampResponse, UpdateSplitsTagsRequest, UpdateSplitsTagsResponse,
+    serde_utils,
 };
 use quickwit_proto::types::{IndexId, IndexUid};
 use quickwit_storage::Storage;
@@ -73,7 +74,7 @@ use uuid::Uuid;
 
 use self::file_backed_index::FileBackedIndex;
 pub use self::file_backed_metastore_factory::FileBackedMetastoreFactory;
-use self::index_id_matcher::IndexIdMatcher;
+pub use self::index_id_matcher::IndexIdMatcher;
 use self::lazy_file_backed_index::LazyFileBackedIndex;
 use self::manifest::{MANIFEST_FILE_NAME, load_or_create_manifest, save_manifest};
 use self::state::MetastoreState;
@@ -576,15 +577,21 @@ impl MetastoreService for FileBackedMetastore {
         let ingest_settings = request.deserialize_ingest_settings()?;
         let search_settings = request.deserialize_search_settings()?;
         let retention_policy_opt = request.deserialize_retention_policy()?;
+        let response_headers = request.deserialize_response_headers()?;
+        let expected_doc_mapping_uid_opt = request.expected_doc_mapping_uid;
 
         let index_metadata = self
             .mutate(index_uid, |index| {
+                index
+                    .metadata()
+                    .check_doc_mapping_uid(expected_doc_mapping_uid_opt)?;
                 let mutation_occurred = index.update_index_config(
                     doc_mapping,
                     indexing_settings,
                     ingest_settings,
                     search_settings,
                     retention_policy_opt,
+                    response_headers,
                 )?;
                 let index_metadata = index.metadata().clone();
 
@@ -986,6 +993,14 @@ impl MetastoreService for FileBackedMetastore {
         .await?
         .into_iter()
         .flatten()
+        .filter(|index_metadata| {
+            !request.paused_only
+                || index_metadata
+                    .index_config
+                    .ingest_settings
+                    .indexing_pause
+                    .is_some()
+        })
         .collect();
         let response =
             ListIndexesMetadataResponse::try_from_indexes_metadata(indexes_metadata).await?;
@@ -1110,6 +1125,27 @@ impl MetastoreService for FileBackedMetastore {
         Ok(UpdateSplitsDeleteOpstampResponse {})
     }
 
+    #[instrument(name = "metastore.file_backed.update_splits_tags", skip_all, fields(index_uid = %request.index_uid()))]
+    async fn update_splits_tags(
+        &self,
+        request: UpdateSplitsTagsRequest,
+    ) -> MetastoreResult<UpdateSplitsTagsResponse> {
+        let index_uid = request.index_uid();
+
+        let num_updated_splits = self
+            .mutate(index_uid, |index| {
+                let num_updated_splits = index.update_splits_tags(&request)?;
+
+                if num_updated_splits > 0 {
+                    Ok(MutationOccurred::Yes(num_updated_splits))
+                } else {
+                    Ok(MutationOccurred::No(num_updated_splits))
+                }
+            })
+            .await?;
+        Ok(UpdateSplitsTagsResponse { num_updated_splits })
+    }
+
     #[instrument(name = "metastore.file_backed.list_delete_tasks", skip_all, fields(index_uid = %request.index_uid()))]
     async fn list_delete_tasks(
         &self,
@@ -14,8 +14,8 @@
 
 pub(crate) mod serialize;
 
-use std::collections::HashMap;
 use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap};
 
 use quickwit_common::uri::Uri;
 use quickwit_config::{
@@ -23,7 +23,7 @@ use quickwit_config::{
     SourceConfig, prepare_doc_mapping_update,
 };
 use quickwit_proto::metastore::{EntityKind, MetastoreError, MetastoreResult};
-use quickwit_proto::types::{IndexUid, SourceId};
+use quickwit_proto::types::{DocMappingUid, IndexUid, SourceId};
 use serde::{Deserialize, Serialize};
 use serialize::VersionedIndexMetadata;
 use time::OffsetDateTime;
@@ -96,6 +96,31 @@ impl IndexMetadata {
         &self.index_config().index_uri
     }
 
+    /// Returns an error if the doc mapping of the index no longer has the expected UID, i.e. it
+    /// was updated since the caller read it.
+    pub(crate) fn check_doc_mapping_uid(
+        &self,
+        expected_doc_mapping_uid_opt: Option<DocMappingUid>,
+    ) -> MetastoreResult<()> {
+        let Some(expected_doc_mapping_uid) = expected_doc_mapping_uid_opt else {
+            return Ok(());
+        };
+        let doc_mapping_uid = self.index_config.doc_mapping.doc_mapping_uid;
+
+        if doc_mapping_uid != expected_doc_mapping_uid {
+            let message = format!(
+                "expected doc mapping UID `{expected_doc_mapping_uid}`, found `{doc_mapping_uid}`"
+            );
+            return Err(MetastoreError::FailedPrecondition {
+                entity: EntityKind::Index {
+                    index_id: self.index_id().to_string(),
+                },
+                message,
+            });
+        }
+        Ok(())
+    }
+
     /// Updates the index config.
     ///
     /// Returns whether a mutation occurred.
@@ -106,6 +131,7 @@ impl IndexMetadata {
         ingest_settings: IngestSettings,
         search_settings: SearchSettings,
         retention_policy_opt: Option<RetentionPolicy>,
+        response_headers: BTreeMap<String, String>,
     ) -> MetastoreResult<bool> {
         let (updated_doc_mapping, mut mutation_occurred) = prepare_doc_mapping_update(
             doc_mapping,
@@ -132,6 +158,10 @@ impl IndexMetadata {
             self.index_config.retention_policy_opt = retention_policy_opt;
             mutation_occurred = true;
         }
+        if response_headers != self.index_config.response_headers {
+            self.index_config.response_headers = response_headers;
+            mutation_occurred = true;
+        }
         Ok(mutation_occurred)
     }
 
@@ -240,6 +270,7 @@ impl quickwit_config::TestableForRegression for IndexMetadata {
 
 #[cfg(test)]
 mod tests {
+    use quickwit_config::MissingFields;
     use quickwit_doc_mapper::Mode;
     use quickwit_proto::types::DocMappingUid;
 
@@ -257,12 +288,20 @@ mod tests {
                 current_index_config.ingest_settings.clone(),
                 current_index_config.search_settings.clone(),
                 current_index_config.retention_policy_opt.clone(),
+                current_index_config.response_headers.clone(),
             )
             .unwrap();
         assert!(!mutation_occurred);
 
         let new_search_settings = SearchSettings {
             default_search_fields: vec!["message".to_string(), "status".to_string()],
+            read_block: false,
+            default_sort: Vec::new(),
+            max_response_size: None,
+            max_aggregation_buckets: None,
+            missing_fields: MissingFields::Omit,
+            default_operator: None,
+            default_max_hits: None,
         };
         let mutation_occurred = current_index_metadata
             .update_index_config(
@@ -271,6 +310,7 @@ mod tests {
                 current_index_config.ingest_settings.clone(),
                 new_search_settings,
                 current_index_config.retention_policy_opt.clone(),
+                current_index_config.response_headers.clone(),
             )
             .unwrap();
         assert!(mutation_occurred);
@@ -281,6 +321,27 @@ mod tests {
                 .default_search_fields,
             ["message", "status"]
         );
+
+        let new_response_headers =
+            BTreeMap::from([("X-Data-Classification".to_string(), "pii".to_string())]);
+        let mutation_occurred = current_index_metadata
+            .update_index_config(
+                current_index_config.doc_mapping.clone(),
+                current_index_config.indexing_settings.clone(),
+                current_index_config.ingest_settings.clone(),
+                current_index_metadata
+                    .index_config()
+                    .search_settings
+                    .clone(),
+                current_index_config.retention_policy_opt.clone(),
+                new_response_headers.clone(),
+            )
+            .unwrap();
+        assert!(mutation_occurred);
+        assert_eq!(
+            current_index_metadata.index_config().response_headers,
+            new_response_headers
+        );
     }
 
     #[test]
@@ -299,6 +360,7 @@ mod tests {
                 current_index_config.ingest_settings.clone(),
                 current_index_config.search_settings.clone(),
                 current_index_config.retention_policy_opt.clone(),
+                current_index_config.response_headers.clone(),
             )
             .unwrap_err();
 
@@ -314,6 +376,7 @@ mod tests {
                 current_index_config.ingest_settings,
                 current_index_config.search_settings,
                 current_index_config.retention_policy_opt,
+                current_index_config.response_headers,
             )
             .unwrap();
         assert!(mutation_occurred);
@@ -20,7 +20,7 @@ pub mod postgres;
 pub mod control_plane_metastore;
 
 use std::cmp::Ordering;
-use std::collections::HashSet;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::ops::{Bound, RangeInclusive};
 
 use async_trait::async_trait;
@@ -33,7 +33,7 @@ use quickwit_config::{
     DocMapping, FileSourceParams, IndexConfig, IndexingSettings, IngestSettings, RetentionPolicy,
     SearchSettings, SourceConfig, SourceParams,
 };
-use quickwit_doc_mapper::tag_pruning::TagFilterAst;
+use quickwit_doc_mapper::tag_pruning::{TagFilterAst, field_tag, match_tag_field_name};
 use quickwit_proto::metastore::{
     AddSourceRequest, CreateIndexRequest, CreateIndexResponse, DeleteTask, IndexMetadataFailure,
     IndexMetadataRequest, IndexMetadataResponse, IndexesMetadataResponse,
@@ -42,7 +42,7 @@ use quickwit_proto::metastore::{
     MetastoreError, MetastoreResult, MetastoreService, MetastoreServiceClient,
     MetastoreServiceStream, PublishMetricsSplitsRequest, PublishSketchSplitsRequest,
     PublishSplitsRequest, StageMetricsSplitsRequest, StageSketchSplitsRequest, StageSplitsRequest,
-    UpdateIndexRequest, UpdateSourceRequest, serde_utils,
+    UpdateIndexRequest, UpdateSourceRequest, UpdateSplitsTagsRequest, serde_utils,
 };
 use quickwit_proto::types::{IndexUid, NodeId, SplitId};
 use serde::{Deserialize, Serialize};
@@ -350,6 +350,7 @@ pub trait UpdateIndexRequestExt {
         ingest_settings: &IngestSettings,
         search_settings: &SearchSettings,
         retention_policy_opt: &Option<RetentionPolicy>,
+        response_headers: &BTreeMap<String, String>,
     ) -> MetastoreResult<UpdateIndexRequest>;
 
     /// Deserializes the `doc_mapping_json` field of an `[UpdateIndexRequest]` into a
@@ -371,6 +372,10 @@ pub trait UpdateIndexRequestExt {
     /// Deserializes the `retention_policy_json` field of an [`UpdateIndexRequest`] into a
     /// [`RetentionPolicy`] object.
     fn deserialize_retention_policy(&self) -> MetastoreResult<Option<RetentionPolicy>>;
+
+    /// Deserializes the `response_headers_json` field of an [`UpdateIndexRequest`] into a map of
+    /// header names to header values.
+    fn deserialize_response_headers(&self) -> MetastoreResult<BTreeMap<String, String>>;
 }
 
 impl UpdateIndexRequestExt for UpdateIndexRequest {
@@ -381,6 +386,7 @@ impl UpdateIndexRequestExt for UpdateIndexRequest {
         ingest_settings: &IngestSettings,
         search_settings: &SearchSettings,
         retention_policy_opt: &Option<RetentionPolicy>,
+        response_headers: &BTreeMap<String, String>,
     ) -> MetastoreResult<UpdateIndexRequest> {
         let doc_mapping_json = serde_utils::to_json_str(doc_mapping)?;
         let indexing_settings_json = serde_utils::to_json_str(indexing_settings)?;
@@ -390,6 +396,7 @@ impl UpdateIndexRequestExt for UpdateIndexRequest {
             .as_ref()
             .map(serde_utils::to_json_str)
             .transpose()?;
+        let response_headers_json = serde_utils::to_json_str(response_headers)?;
 
         let update_request = UpdateIndexRequest {
             index_uid: Some(index_uid.into()),
@@ -398,6 +405,8 @@ impl UpdateIndexRequestExt for UpdateIndexRequest {
             ingest_settings_json,
             search_settings_json,
             retention_policy_json_opt,
+            response_headers_json,
+            expected_doc_mapping_uid: None,
         };
         Ok(update_request)
     }
@@ -423,6 +432,14 @@ impl UpdateIndexRequestExt for UpdateIndexRequest {
             .map(|policy_json| serde_utils::from_json_str(policy_json))
             .transpose()
     }
+
+    fn deserialize_response_headers(&self) -> MetastoreResult<BTreeMap<String, String>> {
+        // Requests sent by nodes running an older version do not carry the response headers.
+        if self.response_headers_json.is_empty() {
+            return Ok(BTreeMap::new());
+        }
+        serde_utils::from_json_str(&self.response_headers_json)
+    }
 }
 
 /// Helper trait to build a [`IndexMetadataResponse`] and deserialize its payload.
@@ -730,6 +747,74 @@ pub trait ListSplitsResponseExt {
     fn empty() -> Self;
 }
 
+/// Helper trait to select and update the splits targeted by an [`UpdateSplitsTagsRequest`].
+pub trait UpdateSplitsTagsRequestExt {
+    /// Returns the query selecting the published splits of the index matching the filter of the
+    /// request.
+    fn list_splits_query(&self) -> ListSplitsQuery;
+
+    /// Returns an error if the request adds or removes a tag generated from one of the tag fields
+    /// of the index, i.e. `{field_name}:{value}` or `{field_name}!`: these tags are derived from
+    /// the documents of the splits and used for pruning them at search time.
+    fn validate_tags(&self, tag_fields: &BTreeSet<String>) -> MetastoreResult<()>;
+
+    /// Removes then adds the tags of the request from/to `tags`. Returns whether `tags` changed.
+    fn update_tags(&self, tags: &mut BTreeSet<String>) -> bool;
+}
+
+impl UpdateSplitsTagsRequestExt for UpdateSplitsTagsRequest {
+    fn list_splits_query(&self) -> ListSplitsQuery {
+        let mut list_splits_query = ListSplitsQuery::for_index(self.index_uid().clone())
+            .with_split_state(SplitState::Published);
+
+        if let Some(time_range_start) = self.time_range_start {
+            list_splits_query = list_splits_query.with_time_range_start_gte(time_range_start);
+        }
+        if let Some(time_range_end) = self.time_range_end {
+            list_splits_query = list_splits_query.with_time_range_end_lt(time_range_end);
+        }
+        if !self.tags.is_empty() {
+            let tag_filters = self
+                .tags
+                .iter()
+                .map(|tag| TagFilterAst::Tag {
+                    is_present: true,
+                    tag: tag.clone(),
+                })
+                .collect();
+            list_splits_query = list_splits_query.with_tags_filter(TagFilterAst::And(tag_filters));
+        }
+        list_splits_query
+    }
+
+    fn validate_tags(&self, tag_fields: &BTreeSet<String>) -> MetastoreResult<()> {
+        for tag in self.add_tags.iter().chain(&self.remove_tags) {
+            for tag_field in tag_fields {
+                if match_tag_field_name(tag_field, tag) || *tag == field_tag(tag_field) {
+                    let message = format!(
+                        "tag `{tag}` is generated from the tag field `{tag_field}` of the index \
+                         and cannot be added or removed"
+                    );
+                    return Err(MetastoreError::InvalidArgument { message });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn update_tags(&self, tags: &mut BTreeSet<String>) -> bool {
+        let previous_tags = tags.clone();
+
+        for tag in &self.remove_tags {
+            tags.remove(tag);
+        }
+        for tag in &self.add_tags {
+            tags.insert(tag.clone());
+        }
+        *tags != previous_tags
+    }
+}
+
 /// Helper trait for [`PublishSplitsRequest`] to deserialize its payload.
 pub trait PublishSplitsRequestExt {
     /// Deserializes the `index_checkpoint_delta_json_opt` field of a [`PublishSplitsRequest`] into
@@ -1085,6 +1170,13 @@ pub struct ListSplitsQuery {
     /// metastore must still deserialize the query (an absent set means "no exclusion").
     #[serde(default)]
     pub excluded_split_ids: HashSet<SplitId>,
+
+    /// Only return the splits whose `split_id` appears in this set. Empty means no restriction.
+    ///
+    /// Skipped when empty so that the queries not using it still deserialize on older metastores,
+    /// which ignore it otherwise: callers must not rely on it to filter out the other splits.
+    #[serde(default, skip_serializing_if = "HashSet::is_empty")]
+    pub split_ids: HashSet<SplitId>,
 }
 
 #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
@@ -1162,6 +1254,7 @@ impl ListSplitsQuery {
             sort_by: SortBy::None,
             after_split: None,
             excluded_split_ids: HashSet::new(),
+            split_ids: HashSet::new(),
         }
     }
 
@@ -1187,6 +1280,7 @@ impl ListSplitsQuery {
             sort_by: SortBy::None,
             after_split: None,
             excluded_split_ids: HashSet::new(),
+            split_ids: HashSet::new(),
         })
     }
 
@@ -1208,6 +1302,7 @@ impl ListSplitsQuery {
             sort_by: SortBy::None,
             after_split: None,
             excluded_split_ids: HashSet::new(),
+            split_ids: HashSet::new(),
         }
     }
 
@@ -1410,6 +1505,12 @@ impl ListSplitsQuery {
         self.excluded_split_ids = excluded_split_ids;
         self
     }
+
+    /// Restricts the splits to the ones whose `split_id` is in the provided set.
+    pub fn with_split_ids(mut self, split_ids: HashSet<SplitId>) -> Self {
+        self.split_ids = split_ids;
+        self
+    }
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -51,10 +51,11 @@ use quickwit_proto::metastore::{
     PublishMetricsSplitsRequest, PublishSketchSplitsRequest, PublishSplitsRequest,
     ResetSourceCheckpointRequest, SplitStats, StageMetricsSplitsRequest, StageSketchSplitsRequest,
     StageSplitsRequest, ToggleSourceRequest, UpdateIndexRequest, UpdateSourceRequest,
-    UpdateSplitsDeleteOpstampRequest, UpdateSplitsDeleteOpstampResponse, serde_utils,
+    UpdateSplitsDeleteOpstampRequest, UpdateSplitsDeleteOpstampResponse, UpdateSplitsTagsRequest,
+    UpdateSplitsTagsResponse, serde_utils,
 };
 use quickwit_proto::types::{IndexId, IndexUid, Position, PublishToken, ShardId, SourceId};
-use sea_query::{Alias, Asterisk, Expr, Func, PostgresQueryBuilder, Query, UnionType};
+use sea_query::{Alias, Asterisk, Expr, Func, LockType, PostgresQueryBuilder, Query, UnionType};
 use sea_query_binder::SqlxBinder;
 use sqlx::{Acquire, Executor, Postgres, Transaction};
 use time::OffsetDateTime;
@@ -90,6 +91,7 @@ use crate::{
     AddSourceRequestExt, CreateIndexRequestExt, IndexMetadata, IndexMetadataResponseExt,
     ListIndexesMetadataResponseExt, ListSplitsRequestExt, ListSplitsResponseExt,
     MetastoreServiceExt, Split, SplitState, StageSplitsRequestExt, UpdateIndexRequestExt,
+    UpdateSplitsTagsRequestExt,
 };
 
 /// PostgreSQL metastore implementation.
@@ -511,16 +513,20 @@ impl MetastoreService for PostgresqlMetastore {
         let ingest_settings = request.deserialize_ingest_settings()?;
         let search_settings = request.deserialize_search_settings()?;
         let retention_policy_opt = request.deserialize_retention_policy()?;
+        let response_headers = request.deserialize_response_headers()?;
+        let expected_doc_mapping_uid_opt = request.expected_doc_mapping_uid;
 
         let index_uid: IndexUid = request.index_uid().clone();
         let updated_index_metadata = run_with_tx!(self.connection_pool, tx, "update index", {
             mutate_index_metadata::<MetastoreError, _>(tx, index_uid, |index_metadata| {
+                index_metadata.check_doc_mapping_uid(expected_doc_mapping_uid_opt)?;
                 let mutation_occurred = index_metadata.update_index_config(
                     doc_mapping,
                     indexing_settings,
                     ingest_settings,
                     search_settings,
                     retention_policy_opt,
+                    response_headers,
                 )?;
                 Ok(MutationOccurred::from(mutation_occurred))
             })
@@ -635,13 +641,19 @@ impl MetastoreService for PostgresqlMetastore {
         &self,
         request: ListIndexesMetadataRequest,
     ) -> MetastoreResult<ListIndexesMetadataResponse> {
-        let sql =
+        let mut sql =
             build_index_id_patterns_sql_query(&request.index_id_patterns).map_err(|error| {
                 MetastoreError::Internal {
                     message: "failed to build `list_indexes_metadata` SQL query".to_string(),
                     cause: error.to_string(),
                 }
             })?;
+        if request.paused_only {
+            sql = format!(
+                "SELECT * FROM ({sql}) AS indexes WHERE index_metadata_json::jsonb #> \
+                 '{{index_config,ingest_settings,indexing_pause}}' IS NOT NULL"
+            );
+        }
         let pg_indexes = sqlx::query_as::<_, PgIndex>(&sql)
             .fetch_all(&self.connection_pool)
             .await?;
@@ -1419,6 +1431,88 @@ impl MetastoreService for PostgresqlMetastore {
         Ok(UpdateSplitsDeleteOpstampResponse {})
     }
 
+    #[instrument(name = "metastore.postgres.update_splits_tags", skip(self))]
+    async fn update_splits_tags(
+        &self,
+        request: UpdateSplitsTagsRequest,
+    ) -> MetastoreResult<UpdateSplitsTagsResponse> {
+        let index_uid: IndexUid = request.index_uid().clone();
+        let mut sql_query_builder = Query::select();
+        sql_query_builder
+            .column(Asterisk)
+            .from(Splits::Table)
+            .lock(LockType::Update);
+        append_query_filters_and_order_by(&mut sql_query_builder, request.list_splits_query());
+        let (sql_query, values) = sql_query_builder.build_sqlx(PostgresQueryBuilder);
+
+        run_with_tx!(self.connection_pool, tx, "update splits tags", {
+            // Locking the index serializes the update with the publication of splits.
+            let index_metadata = index_metadata(tx, &index_uid.index_id, true).await?;
+            if index_metadata.index_uid != index_uid {
+                return Err(MetastoreError::NotFound(EntityKind::Index {
+                    index_id: index_uid.index_id,
+                }));
+            }
+            request.validate_tags(&index_metadata.index_config.doc_mapping.tag_fields)?;
+
+            let pg_splits: Vec<PgSplit> = sqlx::query_as_with::<_, PgSplit, _>(&sql_query, values)
+                .fetch_all(tx.as_mut())
+                .await?;
+
+            let mut split_ids = Vec::new();
+            let mut tags_list = Vec::new();
+            let mut splits_metadata_json = Vec::new();
+
+            for pg_split in pg_splits {
+                let mut split: Split = pg_split.try_into()?;
+
+                if !request.update_tags(&mut split.split_metadata.tags) {
+                    continue;
+                }
+                let split_metadata_json = serde_utils::to_json_str(&split.split_metadata)?;
+                splits_metadata_json.push(split_metadata_json);
+
+                let tags: Vec<String> = split.split_metadata.tags.into_iter().collect();
+                tags_list.push(sqlx::types::Json(tags));
+                split_ids.push(split.split_metadata.split_id);
+            }
+            if split_ids.is_empty() {
+                return Ok(UpdateSplitsTagsResponse {
+                    num_updated_splits: 0,
+                });
+            }
+            let update_result = sqlx::query(
+                r#"
+                UPDATE splits
+                SET
+                    tags = ARRAY(SELECT json_array_elements_text(updated_splits.tags_json::json)),
+                    split_metadata_json = updated_splits.split_metadata_json,
+                    update_timestamp = (CURRENT_TIMESTAMP AT TIME ZONE 'UTC')
+                FROM
+                    UNNEST($1, $2, $3)
+                    AS updated_splits (split_id, tags_json, split_metadata_json)
+                WHERE
+                    splits.index_uid = $4
+                    AND splits.split_id = updated_splits.split_id
+                "#,
+            )
+            .bind(&split_ids)
+            .bind(tags_list)
+            .bind(splits_metadata_json)
+            .bind(&index_uid)
+            .execute(tx.as_mut())
+            .await?;
+
+            info!(
+                %index_uid,
+                "updated the tags of `{}` splits", update_result.rows_affected()
+            );
+            Ok(UpdateSplitsTagsResponse {
+                num_updated_splits: update_result.rows_affected(),
+            })
+        })
+    }
+
     /// Lists the delete tasks with opstamp > `opstamp_start`.
     #[instrument(name = "metastore.postgres.list_delete_tasks", skip(self))]
     async fn list_delete_tasks(
@@ -3584,6 +3678,20 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_list_splits_query_split_ids() {
+        let mut select_statement = Query::select();
+        let sql = select_statement.column(Asterisk).from(Splits::Table);
+
+        let query = ListSplitsQuery::for_all_indexes()
+            .with_split_ids(HashSet::from([SplitId::from("s2"), SplitId::from("s1")]));
+        append_query_filters_and_order_by(sql, query);
+        assert_eq!(
+            sql.to_string(PostgresQueryBuilder),
+            r#"SELECT * FROM "splits" WHERE split_id = ANY(ARRAY ['s1','s2']::text[])"#
+        );
+    }
+
     #[test]
     fn test_index_id_pattern_like_query() {
         assert_eq!(
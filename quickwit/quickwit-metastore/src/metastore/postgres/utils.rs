@@ -240,6 +240,24 @@ pub(super) fn append_query_filters_and_order_by(sql: &mut SelectStatement, query
         ));
     }
 
+    if !query.split_ids.is_empty() {
+        let mut split_ids: Vec<String> = query
+            .split_ids
+            .into_iter()
+            .map(|split_id| split_id.to_string())
+            .collect();
+        split_ids.sort_unstable();
+        let split_ids: Vec<Value> = split_ids
+            .into_iter()
+            .map(|split_id| Value::String(Some(Box::new(split_id))))
+            .collect();
+        let split_ids_array = Value::Array(ArrayType::String, Some(Box::new(split_ids)));
+        sql.cond_where(Expr::cust_with_values(
+            "split_id = ANY($1::text[])",
+            [split_ids_array],
+        ));
+    }
+
     match query.sort_by {
         SortBy::Staleness => {
             sql.order_by(Splits::DeleteOpstamp, Order::Asc)
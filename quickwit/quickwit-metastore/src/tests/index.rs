@@ -25,8 +25,8 @@ use std::num::NonZeroUsize;
 use quickwit_common::rand::append_random_suffix;
 use quickwit_config::merge_policy_config::{MergePolicyConfig, StableLogMergePolicyConfig};
 use quickwit_config::{
-    CLI_SOURCE_ID, INGEST_V2_SOURCE_ID, IndexConfig, IndexingSettings, IngestSettings,
-    RetentionPolicy, SearchSettings, SourceConfig,
+    CLI_SOURCE_ID, INGEST_V2_SOURCE_ID, IndexConfig, IndexingPauseMode, IndexingSettings,
+    IngestSettings, MissingFields, RetentionPolicy, SearchSettings, SourceConfig,
 };
 use quickwit_doc_mapper::{Cardinality, FieldMappingEntry, FieldMappingType, QuickwitJsonOptions};
 use quickwit_proto::metastore::{
@@ -126,6 +126,7 @@ pub async fn test_metastore_update_retention_policy<
             &index_config.ingest_settings,
             &index_config.search_settings,
             &loop_retention_policy_opt,
+            &index_config.response_headers,
         )
         .unwrap();
         let response_metadata = metastore
@@ -169,6 +170,7 @@ pub async fn test_metastore_update_ingest_settings<
         &ingest_settings,
         &index_config.search_settings,
         &index_config.retention_policy_opt,
+        &index_config.response_headers,
     )
     .unwrap();
 
@@ -216,6 +218,13 @@ pub async fn test_metastore_update_search_settings<
     ] {
         let search_settings = SearchSettings {
             default_search_fields: default_search_fields.clone(),
+            read_block: false,
+            default_sort: Vec::new(),
+            max_response_size: None,
+            max_aggregation_buckets: None,
+            missing_fields: MissingFields::Omit,
+            default_operator: None,
+            default_max_hits: None,
         };
         let index_update = UpdateIndexRequest::try_from_updates(
             index_uid.clone(),
@@ -224,6 +233,7 @@ pub async fn test_metastore_update_search_settings<
             &index_config.ingest_settings,
             &search_settings,
             &index_config.retention_policy_opt,
+            &index_config.response_headers,
         )
         .unwrap();
         let response_metadata = metastore
@@ -283,6 +293,7 @@ pub async fn test_metastore_update_indexing_settings<
             &index_config.ingest_settings,
             &index_config.search_settings,
             &index_config.retention_policy_opt,
+            &index_config.response_headers,
         )
         .unwrap();
         let resp_metadata = metastore
@@ -353,6 +364,7 @@ pub async fn test_metastore_update_doc_mapping<
             &index_config.ingest_settings,
             &index_config.search_settings,
             &index_config.retention_policy_opt,
+            &index_config.response_headers,
         )
         .unwrap();
         let resp_metadata = metastore
@@ -680,7 +692,8 @@ pub async fn test_metastore_list_indexes<MetastoreToTest: MetastoreServiceExt +
 
     let index_id_5 = format!("my-exact-index-{index_id_fragment}-5");
     let index_uri_5 = format!("ram:///indexes/{index_id_5}");
-    let index_config_5 = IndexConfig::for_test(&index_id_5, &index_uri_5);
+    let mut index_config_5 = IndexConfig::for_test(&index_id_5, &index_uri_5);
+    index_config_5.ingest_settings.indexing_pause = Some(IndexingPauseMode::Buffer);
 
     let index_id_patterns = vec![
         format!("prefix-*-{index_id_fragment}-suffix-*"),
@@ -688,7 +701,10 @@ pub async fn test_metastore_list_indexes<MetastoreToTest: MetastoreServiceExt +
         format!("my-exact-index-{index_id_fragment}-5"),
     ];
     let indexes_count = metastore
-        .list_indexes_metadata(ListIndexesMetadataRequest { index_id_patterns })
+        .list_indexes_metadata(ListIndexesMetadataRequest {
+            index_id_patterns,
+            paused_only: false,
+        })
         .await
         .unwrap()
         .deserialize_indexes_metadata()
@@ -733,7 +749,10 @@ pub async fn test_metastore_list_indexes<MetastoreToTest: MetastoreServiceExt +
         format!("my-exact-index-{index_id_fragment}-5"),
     ];
     let indexes_count = metastore
-        .list_indexes_metadata(ListIndexesMetadataRequest { index_id_patterns })
+        .list_indexes_metadata(ListIndexesMetadataRequest {
+            index_id_patterns: index_id_patterns.clone(),
+            paused_only: false,
+        })
         .await
         .unwrap()
         .deserialize_indexes_metadata()
@@ -742,6 +761,19 @@ pub async fn test_metastore_list_indexes<MetastoreToTest: MetastoreServiceExt +
         .len();
     assert_eq!(indexes_count, 3);
 
+    let paused_indexes_metadata = metastore
+        .list_indexes_metadata(ListIndexesMetadataRequest {
+            index_id_patterns,
+            paused_only: true,
+        })
+        .await
+        .unwrap()
+        .deserialize_indexes_metadata()
+        .await
+        .unwrap();
+    assert_eq!(paused_indexes_metadata.len(), 1);
+    assert_eq!(paused_indexes_metadata[0].index_id(), index_id_5);
+
     cleanup_index(&mut metastore, index_uid_1).await;
     cleanup_index(&mut metastore, index_uid_2).await;
     cleanup_index(&mut metastore, index_uid_3).await;
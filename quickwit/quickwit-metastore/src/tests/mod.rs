@@ -527,6 +527,13 @@ macro_rules! metastore_test_suite {
                     .await;
             }
 
+            #[tokio::test]
+            #[serial_test::file_serial]
+            async fn test_metastore_update_splits_tags() {
+                let _ = tracing_subscriber::fmt::try_init();
+                $crate::tests::split::test_metastore_update_splits_tags::<$metastore_type>().await;
+            }
+
             #[tokio::test]
             #[serial_test::file_serial]
             async fn test_metastore_stage_splits() {
@@ -12,6 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::BTreeSet;
+use std::ops::RangeInclusive;
 use std::time::Duration;
 
 use futures::future::try_join_all;
@@ -20,7 +22,7 @@ use quickwit_config::{IndexConfig, SourceConfig, SourceParams};
 use quickwit_proto::metastore::{
     CreateIndexRequest, DeleteSplitsRequest, EntityKind, IndexMetadataRequest, ListSplitsRequest,
     ListStaleSplitsRequest, MarkSplitsForDeletionRequest, MetastoreError, PublishSplitsRequest,
-    StageSplitsRequest, UpdateSplitsDeleteOpstampRequest,
+    StageSplitsRequest, UpdateSplitsDeleteOpstampRequest, UpdateSplitsTagsRequest,
 };
 use quickwit_proto::types::{IndexUid, Position};
 use time::OffsetDateTime;
@@ -1806,3 +1808,164 @@ pub async fn test_metastore_update_splits_delete_opstamp<
         cleanup_index(&mut metastore, index_uid).await;
     }
 }
+
+pub async fn test_metastore_update_splits_tags<
+    MetastoreToTest: MetastoreServiceExt + DefaultForTest,
+>() {
+    let mut metastore = MetastoreToTest::default_for_test().await;
+    let current_timestamp = OffsetDateTime::now_utc().unix_timestamp();
+    let index_id = append_random_suffix("update-splits-tags");
+    let index_uri = format!("ram:///indexes/{index_id}");
+    let index_config = IndexConfig::for_test(&index_id, &index_uri);
+
+    {
+        info!("update splits tags on a non-existent index");
+        let update_splits_tags_request = UpdateSplitsTagsRequest {
+            index_uid: Some(IndexUid::new_with_random_ulid("index-not-found")),
+            add_tags: vec!["tier:cold".to_string()],
+            ..Default::default()
+        };
+        let metastore_err = metastore
+            .update_splits_tags(update_splits_tags_request)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            metastore_err,
+            MetastoreError::NotFound(EntityKind::Index { .. })
+        ));
+    }
+
+    {
+        info!("update splits tags on an index");
+        let create_index_request =
+            CreateIndexRequest::try_from_index_config(&index_config).unwrap();
+        let index_uid: IndexUid = metastore
+            .create_index(create_index_request)
+            .await
+            .unwrap()
+            .index_uid()
+            .clone();
+
+        let split_metadata =
+            |split_num: usize, time_range: RangeInclusive<i64>, tag: &str| SplitMetadata {
+                split_id: format!("{index_id}--split-{split_num}"),
+                index_uid: index_uid.clone(),
+                create_timestamp: current_timestamp,
+                time_range: Some(time_range),
+                tags: BTreeSet::from_iter([tag.to_string()]),
+                ..Default::default()
+            };
+        // Matches the filter.
+        let split_metadata_1 = split_metadata(1, 0..=99, "tier:hot");
+        // Outside of the time range.
+        let split_metadata_2 = split_metadata(2, 2_000..=2_999, "tier:hot");
+        // Not carrying the tag of the filter.
+        let split_metadata_3 = split_metadata(3, 0..=99, "tier:warm");
+        // Not published.
+        let split_metadata_4 = split_metadata(4, 0..=99, "tier:hot");
+
+        let stage_splits_request = StageSplitsRequest::try_from_splits_metadata(
+            index_uid.clone(),
+            [
+                split_metadata_1,
+                split_metadata_2,
+                split_metadata_3,
+                split_metadata_4,
+            ],
+        )
+        .unwrap();
+        metastore.stage_splits(stage_splits_request).await.unwrap();
+
+        let publish_splits_request = PublishSplitsRequest {
+            index_uid: Some(index_uid.clone()),
+            staged_split_ids: vec![
+                format!("{index_id}--split-1"),
+                format!("{index_id}--split-2"),
+                format!("{index_id}--split-3"),
+            ],
+            ..Default::default()
+        };
+        metastore
+            .publish_splits(publish_splits_request)
+            .await
+            .unwrap();
+
+        let update_splits_tags_request = UpdateSplitsTagsRequest {
+            index_uid: Some(index_uid.clone()),
+            time_range_start: Some(0),
+            time_range_end: Some(1_000),
+            tags: vec!["tier:hot".to_string()],
+            add_tags: vec!["tier:cold".to_string(), "retention:30d".to_string()],
+            remove_tags: vec!["tier:hot".to_string()],
+        };
+        let update_splits_tags_response = metastore
+            .update_splits_tags(update_splits_tags_request.clone())
+            .await
+            .unwrap();
+        assert_eq!(update_splits_tags_response.num_updated_splits, 1);
+
+        let list_splits_query = ListSplitsQuery::for_index(index_uid.clone())
+            .with_split_states([SplitState::Staged, SplitState::Published]);
+        let list_splits_request =
+            ListSplitsRequest::try_from_list_splits_query(&list_splits_query).unwrap();
+        let mut splits = metastore
+            .list_splits(list_splits_request)
+            .await
+            .unwrap()
+            .collect_splits()
+            .await
+            .unwrap();
+        splits.sort_by(|left, right| left.split_id().cmp(right.split_id()));
+
+        let split_tags: Vec<Vec<&str>> = splits
+            .iter()
+            .map(|split| {
+                split
+                    .split_metadata
+                    .tags
+                    .iter()
+                    .map(|tag| tag.as_str())
+                    .collect()
+            })
+            .collect();
+        assert_eq!(
+            split_tags,
+            [
+                vec!["retention:30d", "tier:cold"],
+                vec!["tier:hot"],
+                vec!["tier:warm"],
+                vec!["tier:hot"],
+            ]
+        );
+
+        // The matched splits no longer carry the tag of the filter.
+        let update_splits_tags_response = metastore
+            .update_splits_tags(update_splits_tags_request)
+            .await
+            .unwrap();
+        assert_eq!(update_splits_tags_response.num_updated_splits, 0);
+
+        // The tags generated from the tag fields of the index cannot be added or removed.
+        for (add_tags, remove_tags) in [
+            (vec!["owner:bob".to_string()], Vec::new()),
+            (Vec::new(), vec!["owner!".to_string()]),
+        ] {
+            let update_splits_tags_request = UpdateSplitsTagsRequest {
+                index_uid: Some(index_uid.clone()),
+                add_tags,
+                remove_tags,
+                ..Default::default()
+            };
+            let metastore_err = metastore
+                .update_splits_tags(update_splits_tags_request)
+                .await
+                .unwrap_err();
+            assert!(matches!(
+                metastore_err,
+                MetastoreError::InvalidArgument { .. }
+            ));
+        }
+
+        cleanup_index(&mut metastore, index_uid).await;
+    }
+}
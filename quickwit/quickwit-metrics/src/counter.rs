@@ -218,6 +218,19 @@ impl Counter {
     }
 }
 
+/// Returns the sum of the values of all the counters registered under the
+/// fully-qualified name `key_name`, whatever their labels.
+///
+/// Counters that have not been accessed yet are not registered and count
+/// as zero.
+pub fn counter_total(key_name: &str) -> u64 {
+    COUNTERS
+        .iter()
+        .filter(|entry| entry.value().info.key_name == key_name)
+        .map(|entry| entry.value().shadow.load(Ordering::Relaxed))
+        .sum()
+}
+
 /// Bridges `Counter` into the `metrics` recorder trait so it can be
 /// used wherever a `CounterFn` is expected.
 impl CounterFn for Counter {
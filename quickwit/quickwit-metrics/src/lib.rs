@@ -301,7 +301,7 @@ pub mod __inventory {
 }
 
 // ─── Public types ───
-pub use counter::{Counter, LazyCounter};
+pub use counter::{Counter, LazyCounter, counter_total};
 pub use gauge::{Gauge, GaugeGuard, LazyGauge};
 pub use histogram::{Histogram, HistogramConfig, HistogramTimer, LazyHistogram};
 pub use labels::{LabelNames, Labels};
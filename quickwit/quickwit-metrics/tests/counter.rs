@@ -17,7 +17,9 @@ mod common;
 use common::with_recorder;
 use metrics::with_local_recorder;
 use metrics_util::debugging::{DebugValue, DebuggingRecorder};
-use quickwit_metrics::{Counter, SYSTEM, counter, label_names, label_values, labels};
+use quickwit_metrics::{
+    Counter, SYSTEM, counter, counter_total, label_names, label_values, labels,
+};
 
 #[test]
 fn base_increments() {
@@ -205,6 +207,25 @@ fn observable_parent_children_share_shadow() {
     });
 }
 
+#[test]
+fn counter_total_sums_label_sets() {
+    with_recorder(|| {
+        let parent = counter!(
+            name: "oc_total",
+            description: "summed counter",
+            subsystem: "test",
+        );
+        let child_a = counter!(parent: parent, "region" => "us-east");
+        let child_b = counter!(parent: parent, "region" => "eu-west");
+
+        child_a.inc_by(3);
+        child_b.inc_by(7);
+
+        assert_eq!(counter_total("quickwit_test_oc_total"), 10);
+        assert_eq!(counter_total("quickwit_test_oc_unknown"), 0);
+    });
+}
+
 #[test]
 fn label_composition_two_labels() {
     let entries = with_recorder(|| {
@@ -75,7 +75,11 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Developer service.
     let mut prost_config = prost_build::Config::default();
     prost_config
-        .bytes(["GetDebugInfoResponse.debug_info_json"])
+        .bytes([
+            "GetDebugInfoResponse.debug_info_json",
+            "GetNodeStatsResponse.node_stats_json",
+            "ListMergeOperationsResponse.merge_operations_json",
+        ])
         .file_descriptor_set_path("src/codegen/quickwit/developer_descriptor.bin");
 
     Codegen::builder()
@@ -73,6 +73,33 @@ pub struct AdviseResetShardsResponse {
     pub shards_to_truncate: ::prost::alloc::vec::Vec<super::ingest::ShardIdPositions>,
 }
 #[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct RebalanceIndexingPipelinesRequest {
+    /// Only plans the moves without applying them.
+    #[prost(bool, tag = "1")]
+    pub dry_run: bool,
+}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RebalanceIndexingPipelinesResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub moves: ::prost::alloc::vec::Vec<IndexingPipelineMove>,
+}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct IndexingPipelineMove {
+    #[prost(message, optional, tag = "1")]
+    pub index_uid: ::core::option::Option<crate::types::IndexUid>,
+    #[prost(string, tag = "2")]
+    pub source_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub from_node_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "4")]
+    pub to_node_id: ::prost::alloc::string::String,
+    #[prost(uint32, tag = "5")]
+    pub num_pipelines: u32,
+}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
 #[serde(rename_all = "snake_case")]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
@@ -180,6 +207,11 @@ pub trait ControlPlaneService: std::fmt::Debug + Send + Sync + 'static {
         &self,
         request: super::metastore::PruneShardsRequest,
     ) -> crate::control_plane::ControlPlaneResult<super::metastore::EmptyResponse>;
+    ///Rebalances the indexing pipelines across the indexers and returns the planned moves.
+    async fn rebalance_indexing_pipelines(
+        &self,
+        request: RebalanceIndexingPipelinesRequest,
+    ) -> crate::control_plane::ControlPlaneResult<RebalanceIndexingPipelinesResponse>;
 }
 #[derive(Debug, Clone)]
 pub struct ControlPlaneServiceClient {
@@ -362,6 +394,13 @@ impl ControlPlaneService for ControlPlaneServiceClient {
     ) -> crate::control_plane::ControlPlaneResult<super::metastore::EmptyResponse> {
         self.inner.0.prune_shards(request).await
     }
+    #[tracing::instrument(skip_all, name = "control_plane.rebalance_indexing_pipelines")]
+    async fn rebalance_indexing_pipelines(
+        &self,
+        request: RebalanceIndexingPipelinesRequest,
+    ) -> crate::control_plane::ControlPlaneResult<RebalanceIndexingPipelinesResponse> {
+        self.inner.0.rebalance_indexing_pipelines(request).await
+    }
 }
 #[cfg(any(test, feature = "testsuite"))]
 pub mod mock_control_plane_service {
@@ -450,6 +489,14 @@ pub mod mock_control_plane_service {
         > {
             self.inner.lock().await.prune_shards(request).await
         }
+        async fn rebalance_indexing_pipelines(
+            &self,
+            request: super::RebalanceIndexingPipelinesRequest,
+        ) -> crate::control_plane::ControlPlaneResult<
+            super::RebalanceIndexingPipelinesResponse,
+        > {
+            self.inner.lock().await.rebalance_indexing_pipelines(request).await
+        }
     }
 }
 pub type BoxFuture<T, E> = std::pin::Pin<
@@ -623,6 +670,22 @@ for InnerControlPlaneServiceClient {
         Box::pin(fut)
     }
 }
+impl tower::Service<RebalanceIndexingPipelinesRequest> for InnerControlPlaneServiceClient {
+    type Response = RebalanceIndexingPipelinesResponse;
+    type Error = crate::control_plane::ControlPlaneError;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+    fn poll_ready(
+        &mut self,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+    fn call(&mut self, request: RebalanceIndexingPipelinesRequest) -> Self::Future {
+        let svc = self.clone();
+        let fut = async move { svc.0.rebalance_indexing_pipelines(request).await };
+        Box::pin(fut)
+    }
+}
 /// A tower service stack is a set of tower services.
 #[derive(Debug)]
 struct ControlPlaneServiceTowerServiceStack {
@@ -678,6 +741,11 @@ struct ControlPlaneServiceTowerServiceStack {
         super::metastore::EmptyResponse,
         crate::control_plane::ControlPlaneError,
     >,
+    rebalance_indexing_pipelines_svc: quickwit_common::tower::BoxService<
+        RebalanceIndexingPipelinesRequest,
+        RebalanceIndexingPipelinesResponse,
+        crate::control_plane::ControlPlaneError,
+    >,
 }
 #[async_trait::async_trait]
 impl ControlPlaneService for ControlPlaneServiceTowerServiceStack {
@@ -745,6 +813,12 @@ impl ControlPlaneService for ControlPlaneServiceTowerServiceStack {
     ) -> crate::control_plane::ControlPlaneResult<super::metastore::EmptyResponse> {
         self.prune_shards_svc.clone().ready().await?.call(request).await
     }
+    async fn rebalance_indexing_pipelines(
+        &self,
+        request: RebalanceIndexingPipelinesRequest,
+    ) -> crate::control_plane::ControlPlaneResult<RebalanceIndexingPipelinesResponse> {
+        self.rebalance_indexing_pipelines_svc.clone().ready().await?.call(request).await
+    }
 }
 type CreateIndexLayer = quickwit_common::tower::BoxLayer<
     quickwit_common::tower::BoxService<
@@ -846,6 +920,16 @@ type PruneShardsLayer = quickwit_common::tower::BoxLayer<
     super::metastore::EmptyResponse,
     crate::control_plane::ControlPlaneError,
 >;
+type RebalanceIndexingPipelinesLayer = quickwit_common::tower::BoxLayer<
+    quickwit_common::tower::BoxService<
+        RebalanceIndexingPipelinesRequest,
+        RebalanceIndexingPipelinesResponse,
+        crate::control_plane::ControlPlaneError,
+    >,
+    RebalanceIndexingPipelinesRequest,
+    RebalanceIndexingPipelinesResponse,
+    crate::control_plane::ControlPlaneError,
+>;
 #[derive(Debug, Default)]
 pub struct ControlPlaneServiceTowerLayerStack {
     create_index_layers: Vec<CreateIndexLayer>,
@@ -858,6 +942,7 @@ pub struct ControlPlaneServiceTowerLayerStack {
     get_or_create_open_shards_layers: Vec<GetOrCreateOpenShardsLayer>,
     advise_reset_shards_layers: Vec<AdviseResetShardsLayer>,
     prune_shards_layers: Vec<PruneShardsLayer>,
+    rebalance_indexing_pipelines_layers: Vec<RebalanceIndexingPipelinesLayer>,
 }
 impl ControlPlaneServiceTowerLayerStack {
     pub fn stack_layer<L>(mut self, layer: L) -> Self
@@ -1130,6 +1215,33 @@ impl ControlPlaneServiceTowerLayerStack {
         >>::Service as tower::Service<
             super::metastore::PruneShardsRequest,
         >>::Future: Send + 'static,
+        L: tower::Layer<
+                quickwit_common::tower::BoxService<
+                    RebalanceIndexingPipelinesRequest,
+                    RebalanceIndexingPipelinesResponse,
+                    crate::control_plane::ControlPlaneError,
+                >,
+            > + Clone + Send + Sync + 'static,
+        <L as tower::Layer<
+            quickwit_common::tower::BoxService<
+                RebalanceIndexingPipelinesRequest,
+                RebalanceIndexingPipelinesResponse,
+                crate::control_plane::ControlPlaneError,
+            >,
+        >>::Service: tower::Service<
+                RebalanceIndexingPipelinesRequest,
+                Response = RebalanceIndexingPipelinesResponse,
+                Error = crate::control_plane::ControlPlaneError,
+            > + Clone + Send + Sync + 'static,
+        <<L as tower::Layer<
+            quickwit_common::tower::BoxService<
+                RebalanceIndexingPipelinesRequest,
+                RebalanceIndexingPipelinesResponse,
+                crate::control_plane::ControlPlaneError,
+            >,
+        >>::Service as tower::Service<
+            RebalanceIndexingPipelinesRequest,
+        >>::Future: Send + 'static,
     {
         self.create_index_layers
             .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
@@ -1151,6 +1263,8 @@ impl ControlPlaneServiceTowerLayerStack {
             .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
         self.prune_shards_layers
             .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
+        self.rebalance_indexing_pipelines_layers
+            .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
         self
     }
     pub fn stack_create_index_layer<L>(mut self, layer: L) -> Self
@@ -1363,6 +1477,28 @@ impl ControlPlaneServiceTowerLayerStack {
         self.prune_shards_layers.push(quickwit_common::tower::BoxLayer::new(layer));
         self
     }
+    pub fn stack_rebalance_indexing_pipelines_layer<L>(mut self, layer: L) -> Self
+    where
+        L: tower::Layer<
+                quickwit_common::tower::BoxService<
+                    RebalanceIndexingPipelinesRequest,
+                    RebalanceIndexingPipelinesResponse,
+                    crate::control_plane::ControlPlaneError,
+                >,
+            > + Send + Sync + 'static,
+        L::Service: tower::Service<
+                RebalanceIndexingPipelinesRequest,
+                Response = RebalanceIndexingPipelinesResponse,
+                Error = crate::control_plane::ControlPlaneError,
+            > + Clone + Send + Sync + 'static,
+        <L::Service as tower::Service<
+            RebalanceIndexingPipelinesRequest,
+        >>::Future: Send + 'static,
+    {
+        self.rebalance_indexing_pipelines_layers
+            .push(quickwit_common::tower::BoxLayer::new(layer));
+        self
+    }
     pub fn build<T>(self, instance: T) -> ControlPlaneServiceClient
     where
         T: ControlPlaneService,
@@ -1506,6 +1642,14 @@ impl ControlPlaneServiceTowerLayerStack {
                 quickwit_common::tower::BoxService::new(inner_client.clone()),
                 |svc, layer| layer.layer(svc),
             );
+        let rebalance_indexing_pipelines_svc = self
+            .rebalance_indexing_pipelines_layers
+            .into_iter()
+            .rev()
+            .fold(
+                quickwit_common::tower::BoxService::new(inner_client.clone()),
+                |svc, layer| layer.layer(svc),
+            );
         let tower_svc_stack = ControlPlaneServiceTowerServiceStack {
             inner: inner_client,
             create_index_svc,
@@ -1518,6 +1662,7 @@ impl ControlPlaneServiceTowerLayerStack {
             get_or_create_open_shards_svc,
             advise_reset_shards_svc,
             prune_shards_svc,
+            rebalance_indexing_pipelines_svc,
         };
         ControlPlaneServiceClient::new(tower_svc_stack)
     }
@@ -1683,6 +1828,15 @@ where
                 super::metastore::EmptyResponse,
                 crate::control_plane::ControlPlaneError,
             >,
+        >
+        + tower::Service<
+            RebalanceIndexingPipelinesRequest,
+            Response = RebalanceIndexingPipelinesResponse,
+            Error = crate::control_plane::ControlPlaneError,
+            Future = BoxFuture<
+                RebalanceIndexingPipelinesResponse,
+                crate::control_plane::ControlPlaneError,
+            >,
         >,
 {
     async fn create_index(
@@ -1749,6 +1903,12 @@ where
     ) -> crate::control_plane::ControlPlaneResult<super::metastore::EmptyResponse> {
         self.clone().call(request).await
     }
+    async fn rebalance_indexing_pipelines(
+        &self,
+        request: RebalanceIndexingPipelinesRequest,
+    ) -> crate::control_plane::ControlPlaneResult<RebalanceIndexingPipelinesResponse> {
+        self.clone().call(request).await
+    }
 }
 #[derive(Debug, Clone)]
 pub struct ControlPlaneServiceGrpcClientAdapter<T> {
@@ -1968,6 +2128,24 @@ where
                 super::metastore::PruneShardsRequest::rpc_name(),
             ))
     }
+    async fn rebalance_indexing_pipelines(
+        &self,
+        request: RebalanceIndexingPipelinesRequest,
+    ) -> crate::control_plane::ControlPlaneResult<RebalanceIndexingPipelinesResponse> {
+        let mut tonic_request = tonic::Request::new(request);
+        quickwit_common::tracing_utils::inject_current_context(
+            tonic_request.metadata_mut(),
+        );
+        self.inner
+            .clone()
+            .rebalance_indexing_pipelines(tonic_request)
+            .await
+            .map(|response| response.into_inner())
+            .map_err(|status| crate::error::grpc_status_to_service_error(
+                status,
+                RebalanceIndexingPipelinesRequest::rpc_name(),
+            ))
+    }
 }
 #[derive(Debug)]
 pub struct ControlPlaneServiceGrpcServerAdapter {
@@ -2219,6 +2397,29 @@ for ControlPlaneServiceGrpcServerAdapter {
         };
         <_ as tracing::Instrument>::instrument(fut, span).await
     }
+    async fn rebalance_indexing_pipelines(
+        &self,
+        tonic_request: tonic::Request<RebalanceIndexingPipelinesRequest>,
+    ) -> Result<tonic::Response<RebalanceIndexingPipelinesResponse>, tonic::Status> {
+        let parent_context = quickwit_common::tracing_utils::extract_context(
+            tonic_request.metadata(),
+        );
+        let request = tonic_request.into_inner();
+        let span = tracing::info_span!("control_plane.rebalance_indexing_pipelines");
+        let _ = <tracing::Span as tracing_opentelemetry::OpenTelemetrySpanExt>::set_parent(
+            &span,
+            parent_context,
+        );
+        let fut = async move {
+            self.inner
+                .0
+                .rebalance_indexing_pipelines(request)
+                .await
+                .map(tonic::Response::new)
+                .map_err(crate::error::grpc_error_to_grpc_status)
+        };
+        <_ as tracing::Instrument>::instrument(fut, span).await
+    }
 }
 /// Generated client implementations.
 pub mod control_plane_service_grpc_client {
@@ -2620,6 +2821,36 @@ pub mod control_plane_service_grpc_client {
                 );
             self.inner.unary(req, path, codec).await
         }
+        /// Rebalances the indexing pipelines across the indexers and returns the planned moves.
+        pub async fn rebalance_indexing_pipelines(
+            &mut self,
+            request: impl tonic::IntoRequest<super::RebalanceIndexingPipelinesRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::RebalanceIndexingPipelinesResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic_prost::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/quickwit.control_plane.ControlPlaneService/RebalanceIndexingPipelines",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "quickwit.control_plane.ControlPlaneService",
+                        "RebalanceIndexingPipelines",
+                    ),
+                );
+            self.inner.unary(req, path, codec).await
+        }
     }
 }
 /// Generated server implementations.
@@ -2716,6 +2947,14 @@ pub mod control_plane_service_grpc_server {
             tonic::Response<super::super::metastore::EmptyResponse>,
             tonic::Status,
         >;
+        /// Rebalances the indexing pipelines across the indexers and returns the planned moves.
+        async fn rebalance_indexing_pipelines(
+            &self,
+            request: tonic::Request<super::RebalanceIndexingPipelinesRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::RebalanceIndexingPipelinesResponse>,
+            tonic::Status,
+        >;
     }
     #[derive(Debug)]
     pub struct ControlPlaneServiceGrpcServer<T> {
@@ -3307,6 +3546,55 @@ pub mod control_plane_service_grpc_server {
                     };
                     Box::pin(fut)
                 }
+                "/quickwit.control_plane.ControlPlaneService/RebalanceIndexingPipelines" => {
+                    #[allow(non_camel_case_types)]
+                    struct RebalanceIndexingPipelinesSvc<T: ControlPlaneServiceGrpc>(pub Arc<T>);
+                    impl<
+                        T: ControlPlaneServiceGrpc,
+                    > tonic::server::UnaryService<super::RebalanceIndexingPipelinesRequest>
+                    for RebalanceIndexingPipelinesSvc<T> {
+                        type Response = super::RebalanceIndexingPipelinesResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::RebalanceIndexingPipelinesRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as ControlPlaneServiceGrpc>::rebalance_indexing_pipelines(
+                                        &inner,
+                                        request,
+                                    )
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = RebalanceIndexingPipelinesSvc(inner);
+                        let codec = tonic_prost::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 _ => {
                     Box::pin(async move {
                         let mut response = http::Response::new(
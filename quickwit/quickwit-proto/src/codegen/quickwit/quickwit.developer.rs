@@ -12,6 +12,28 @@ pub struct GetDebugInfoResponse {
     #[prost(bytes = "bytes", tag = "1")]
     pub debug_info_json: ::prost::bytes::Bytes,
 }
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct GetNodeStatsRequest {}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct GetNodeStatsResponse {
+    #[prost(bytes = "bytes", tag = "1")]
+    pub node_stats_json: ::prost::bytes::Bytes,
+}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct ListMergeOperationsRequest {
+    /// Restricts the merge operations to the given index.
+    #[prost(string, tag = "1")]
+    pub index_uid: ::prost::alloc::string::String,
+}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct ListMergeOperationsResponse {
+    #[prost(bytes = "bytes", tag = "1")]
+    pub merge_operations_json: ::prost::bytes::Bytes,
+}
 /// BEGIN quickwit-codegen
 #[allow(unused_imports)]
 use std::str::FromStr;
@@ -22,6 +44,16 @@ impl RpcName for GetDebugInfoRequest {
         "get_debug_info"
     }
 }
+impl RpcName for GetNodeStatsRequest {
+    fn rpc_name() -> &'static str {
+        "get_node_stats"
+    }
+}
+impl RpcName for ListMergeOperationsRequest {
+    fn rpc_name() -> &'static str {
+        "list_merge_operations"
+    }
+}
 #[cfg_attr(any(test, feature = "testsuite"), mockall::automock)]
 #[async_trait::async_trait]
 pub trait DeveloperService: std::fmt::Debug + Send + Sync + 'static {
@@ -29,6 +61,14 @@ pub trait DeveloperService: std::fmt::Debug + Send + Sync + 'static {
         &self,
         request: GetDebugInfoRequest,
     ) -> crate::developer::DeveloperResult<GetDebugInfoResponse>;
+    async fn get_node_stats(
+        &self,
+        request: GetNodeStatsRequest,
+    ) -> crate::developer::DeveloperResult<GetNodeStatsResponse>;
+    async fn list_merge_operations(
+        &self,
+        request: ListMergeOperationsRequest,
+    ) -> crate::developer::DeveloperResult<ListMergeOperationsResponse>;
 }
 #[derive(Debug, Clone)]
 pub struct DeveloperServiceClient {
@@ -144,6 +184,20 @@ impl DeveloperService for DeveloperServiceClient {
     ) -> crate::developer::DeveloperResult<GetDebugInfoResponse> {
         self.inner.0.get_debug_info(request).await
     }
+    #[tracing::instrument(skip_all, name = "developer.get_node_stats")]
+    async fn get_node_stats(
+        &self,
+        request: GetNodeStatsRequest,
+    ) -> crate::developer::DeveloperResult<GetNodeStatsResponse> {
+        self.inner.0.get_node_stats(request).await
+    }
+    #[tracing::instrument(skip_all, name = "developer.list_merge_operations")]
+    async fn list_merge_operations(
+        &self,
+        request: ListMergeOperationsRequest,
+    ) -> crate::developer::DeveloperResult<ListMergeOperationsResponse> {
+        self.inner.0.list_merge_operations(request).await
+    }
 }
 #[cfg(any(test, feature = "testsuite"))]
 pub mod mock_developer_service {
@@ -160,6 +214,18 @@ pub mod mock_developer_service {
         ) -> crate::developer::DeveloperResult<super::GetDebugInfoResponse> {
             self.inner.lock().await.get_debug_info(request).await
         }
+        async fn get_node_stats(
+            &self,
+            request: super::GetNodeStatsRequest,
+        ) -> crate::developer::DeveloperResult<super::GetNodeStatsResponse> {
+            self.inner.lock().await.get_node_stats(request).await
+        }
+        async fn list_merge_operations(
+            &self,
+            request: super::ListMergeOperationsRequest,
+        ) -> crate::developer::DeveloperResult<super::ListMergeOperationsResponse> {
+            self.inner.lock().await.list_merge_operations(request).await
+        }
     }
 }
 pub type BoxFuture<T, E> = std::pin::Pin<
@@ -181,6 +247,38 @@ impl tower::Service<GetDebugInfoRequest> for InnerDeveloperServiceClient {
         Box::pin(fut)
     }
 }
+impl tower::Service<GetNodeStatsRequest> for InnerDeveloperServiceClient {
+    type Response = GetNodeStatsResponse;
+    type Error = crate::developer::DeveloperError;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+    fn poll_ready(
+        &mut self,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+    fn call(&mut self, request: GetNodeStatsRequest) -> Self::Future {
+        let svc = self.clone();
+        let fut = async move { svc.0.get_node_stats(request).await };
+        Box::pin(fut)
+    }
+}
+impl tower::Service<ListMergeOperationsRequest> for InnerDeveloperServiceClient {
+    type Response = ListMergeOperationsResponse;
+    type Error = crate::developer::DeveloperError;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+    fn poll_ready(
+        &mut self,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+    fn call(&mut self, request: ListMergeOperationsRequest) -> Self::Future {
+        let svc = self.clone();
+        let fut = async move { svc.0.list_merge_operations(request).await };
+        Box::pin(fut)
+    }
+}
 /// A tower service stack is a set of tower services.
 #[derive(Debug)]
 struct DeveloperServiceTowerServiceStack {
@@ -191,6 +289,16 @@ struct DeveloperServiceTowerServiceStack {
         GetDebugInfoResponse,
         crate::developer::DeveloperError,
     >,
+    get_node_stats_svc: quickwit_common::tower::BoxService<
+        GetNodeStatsRequest,
+        GetNodeStatsResponse,
+        crate::developer::DeveloperError,
+    >,
+    list_merge_operations_svc: quickwit_common::tower::BoxService<
+        ListMergeOperationsRequest,
+        ListMergeOperationsResponse,
+        crate::developer::DeveloperError,
+    >,
 }
 #[async_trait::async_trait]
 impl DeveloperService for DeveloperServiceTowerServiceStack {
@@ -200,6 +308,18 @@ impl DeveloperService for DeveloperServiceTowerServiceStack {
     ) -> crate::developer::DeveloperResult<GetDebugInfoResponse> {
         self.get_debug_info_svc.clone().ready().await?.call(request).await
     }
+    async fn get_node_stats(
+        &self,
+        request: GetNodeStatsRequest,
+    ) -> crate::developer::DeveloperResult<GetNodeStatsResponse> {
+        self.get_node_stats_svc.clone().ready().await?.call(request).await
+    }
+    async fn list_merge_operations(
+        &self,
+        request: ListMergeOperationsRequest,
+    ) -> crate::developer::DeveloperResult<ListMergeOperationsResponse> {
+        self.list_merge_operations_svc.clone().ready().await?.call(request).await
+    }
 }
 type GetDebugInfoLayer = quickwit_common::tower::BoxLayer<
     quickwit_common::tower::BoxService<
@@ -211,9 +331,31 @@ type GetDebugInfoLayer = quickwit_common::tower::BoxLayer<
     GetDebugInfoResponse,
     crate::developer::DeveloperError,
 >;
+type GetNodeStatsLayer = quickwit_common::tower::BoxLayer<
+    quickwit_common::tower::BoxService<
+        GetNodeStatsRequest,
+        GetNodeStatsResponse,
+        crate::developer::DeveloperError,
+    >,
+    GetNodeStatsRequest,
+    GetNodeStatsResponse,
+    crate::developer::DeveloperError,
+>;
+type ListMergeOperationsLayer = quickwit_common::tower::BoxLayer<
+    quickwit_common::tower::BoxService<
+        ListMergeOperationsRequest,
+        ListMergeOperationsResponse,
+        crate::developer::DeveloperError,
+    >,
+    ListMergeOperationsRequest,
+    ListMergeOperationsResponse,
+    crate::developer::DeveloperError,
+>;
 #[derive(Debug, Default)]
 pub struct DeveloperServiceTowerLayerStack {
     get_debug_info_layers: Vec<GetDebugInfoLayer>,
+    get_node_stats_layers: Vec<GetNodeStatsLayer>,
+    list_merge_operations_layers: Vec<ListMergeOperationsLayer>,
 }
 impl DeveloperServiceTowerLayerStack {
     pub fn stack_layer<L>(mut self, layer: L) -> Self
@@ -243,9 +385,63 @@ impl DeveloperServiceTowerLayerStack {
                 crate::developer::DeveloperError,
             >,
         >>::Service as tower::Service<GetDebugInfoRequest>>::Future: Send + 'static,
+        L: tower::Layer<
+                quickwit_common::tower::BoxService<
+                    GetNodeStatsRequest,
+                    GetNodeStatsResponse,
+                    crate::developer::DeveloperError,
+                >,
+            > + Clone + Send + Sync + 'static,
+        <L as tower::Layer<
+            quickwit_common::tower::BoxService<
+                GetNodeStatsRequest,
+                GetNodeStatsResponse,
+                crate::developer::DeveloperError,
+            >,
+        >>::Service: tower::Service<
+                GetNodeStatsRequest,
+                Response = GetNodeStatsResponse,
+                Error = crate::developer::DeveloperError,
+            > + Clone + Send + Sync + 'static,
+        <<L as tower::Layer<
+            quickwit_common::tower::BoxService<
+                GetNodeStatsRequest,
+                GetNodeStatsResponse,
+                crate::developer::DeveloperError,
+            >,
+        >>::Service as tower::Service<GetNodeStatsRequest>>::Future: Send + 'static,
+        L: tower::Layer<
+                quickwit_common::tower::BoxService<
+                    ListMergeOperationsRequest,
+                    ListMergeOperationsResponse,
+                    crate::developer::DeveloperError,
+                >,
+            > + Clone + Send + Sync + 'static,
+        <L as tower::Layer<
+            quickwit_common::tower::BoxService<
+                ListMergeOperationsRequest,
+                ListMergeOperationsResponse,
+                crate::developer::DeveloperError,
+            >,
+        >>::Service: tower::Service<
+                ListMergeOperationsRequest,
+                Response = ListMergeOperationsResponse,
+                Error = crate::developer::DeveloperError,
+            > + Clone + Send + Sync + 'static,
+        <<L as tower::Layer<
+            quickwit_common::tower::BoxService<
+                ListMergeOperationsRequest,
+                ListMergeOperationsResponse,
+                crate::developer::DeveloperError,
+            >,
+        >>::Service as tower::Service<ListMergeOperationsRequest>>::Future: Send + 'static,
     {
         self.get_debug_info_layers
             .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
+        self.get_node_stats_layers
+            .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
+        self.list_merge_operations_layers
+            .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
         self
     }
     pub fn stack_get_debug_info_layer<L>(mut self, layer: L) -> Self
@@ -267,6 +463,44 @@ impl DeveloperServiceTowerLayerStack {
         self.get_debug_info_layers.push(quickwit_common::tower::BoxLayer::new(layer));
         self
     }
+    pub fn stack_get_node_stats_layer<L>(mut self, layer: L) -> Self
+    where
+        L: tower::Layer<
+                quickwit_common::tower::BoxService<
+                    GetNodeStatsRequest,
+                    GetNodeStatsResponse,
+                    crate::developer::DeveloperError,
+                >,
+            > + Send + Sync + 'static,
+        L::Service: tower::Service<
+                GetNodeStatsRequest,
+                Response = GetNodeStatsResponse,
+                Error = crate::developer::DeveloperError,
+            > + Clone + Send + Sync + 'static,
+        <L::Service as tower::Service<GetNodeStatsRequest>>::Future: Send + 'static,
+    {
+        self.get_node_stats_layers.push(quickwit_common::tower::BoxLayer::new(layer));
+        self
+    }
+    pub fn stack_list_merge_operations_layer<L>(mut self, layer: L) -> Self
+    where
+        L: tower::Layer<
+                quickwit_common::tower::BoxService<
+                    ListMergeOperationsRequest,
+                    ListMergeOperationsResponse,
+                    crate::developer::DeveloperError,
+                >,
+            > + Send + Sync + 'static,
+        L::Service: tower::Service<
+                ListMergeOperationsRequest,
+                Response = ListMergeOperationsResponse,
+                Error = crate::developer::DeveloperError,
+            > + Clone + Send + Sync + 'static,
+        <L::Service as tower::Service<ListMergeOperationsRequest>>::Future: Send + 'static,
+    {
+        self.list_merge_operations_layers.push(quickwit_common::tower::BoxLayer::new(layer));
+        self
+    }
     pub fn build<T>(self, instance: T) -> DeveloperServiceClient
     where
         T: DeveloperService,
@@ -335,9 +569,27 @@ impl DeveloperServiceTowerLayerStack {
                 quickwit_common::tower::BoxService::new(inner_client.clone()),
                 |svc, layer| layer.layer(svc),
             );
+        let get_node_stats_svc = self
+            .get_node_stats_layers
+            .into_iter()
+            .rev()
+            .fold(
+                quickwit_common::tower::BoxService::new(inner_client.clone()),
+                |svc, layer| layer.layer(svc),
+            );
+        let list_merge_operations_svc = self
+            .list_merge_operations_layers
+            .into_iter()
+            .rev()
+            .fold(
+                quickwit_common::tower::BoxService::new(inner_client.clone()),
+                |svc, layer| layer.layer(svc),
+            );
         let tower_svc_stack = DeveloperServiceTowerServiceStack {
             inner: inner_client,
             get_debug_info_svc,
+            get_node_stats_svc,
+            list_merge_operations_svc,
         };
         DeveloperServiceClient::new(tower_svc_stack)
     }
@@ -415,11 +667,23 @@ where
     DeveloperServiceMailbox<
         A,
     >: tower::Service<
-        GetDebugInfoRequest,
-        Response = GetDebugInfoResponse,
-        Error = crate::developer::DeveloperError,
-        Future = BoxFuture<GetDebugInfoResponse, crate::developer::DeveloperError>,
-    >,
+            GetDebugInfoRequest,
+            Response = GetDebugInfoResponse,
+            Error = crate::developer::DeveloperError,
+            Future = BoxFuture<GetDebugInfoResponse, crate::developer::DeveloperError>,
+        >
+        + tower::Service<
+            GetNodeStatsRequest,
+            Response = GetNodeStatsResponse,
+            Error = crate::developer::DeveloperError,
+            Future = BoxFuture<GetNodeStatsResponse, crate::developer::DeveloperError>,
+        >
+        + tower::Service<
+            ListMergeOperationsRequest,
+            Response = ListMergeOperationsResponse,
+            Error = crate::developer::DeveloperError,
+            Future = BoxFuture<ListMergeOperationsResponse, crate::developer::DeveloperError>,
+        >,
 {
     async fn get_debug_info(
         &self,
@@ -427,6 +691,18 @@ where
     ) -> crate::developer::DeveloperResult<GetDebugInfoResponse> {
         self.clone().call(request).await
     }
+    async fn get_node_stats(
+        &self,
+        request: GetNodeStatsRequest,
+    ) -> crate::developer::DeveloperResult<GetNodeStatsResponse> {
+        self.clone().call(request).await
+    }
+    async fn list_merge_operations(
+        &self,
+        request: ListMergeOperationsRequest,
+    ) -> crate::developer::DeveloperResult<ListMergeOperationsResponse> {
+        self.clone().call(request).await
+    }
 }
 #[derive(Debug, Clone)]
 pub struct DeveloperServiceGrpcClientAdapter<T> {
@@ -480,6 +756,42 @@ where
                 GetDebugInfoRequest::rpc_name(),
             ))
     }
+    async fn get_node_stats(
+        &self,
+        request: GetNodeStatsRequest,
+    ) -> crate::developer::DeveloperResult<GetNodeStatsResponse> {
+        let mut tonic_request = tonic::Request::new(request);
+        quickwit_common::tracing_utils::inject_current_context(
+            tonic_request.metadata_mut(),
+        );
+        self.inner
+            .clone()
+            .get_node_stats(tonic_request)
+            .await
+            .map(|response| response.into_inner())
+            .map_err(|status| crate::error::grpc_status_to_service_error(
+                status,
+                GetNodeStatsRequest::rpc_name(),
+            ))
+    }
+    async fn list_merge_operations(
+        &self,
+        request: ListMergeOperationsRequest,
+    ) -> crate::developer::DeveloperResult<ListMergeOperationsResponse> {
+        let mut tonic_request = tonic::Request::new(request);
+        quickwit_common::tracing_utils::inject_current_context(
+            tonic_request.metadata_mut(),
+        );
+        self.inner
+            .clone()
+            .list_merge_operations(tonic_request)
+            .await
+            .map(|response| response.into_inner())
+            .map_err(|status| crate::error::grpc_status_to_service_error(
+                status,
+                ListMergeOperationsRequest::rpc_name(),
+            ))
+    }
 }
 #[derive(Debug)]
 pub struct DeveloperServiceGrpcServerAdapter {
@@ -521,6 +833,52 @@ for DeveloperServiceGrpcServerAdapter {
         };
         <_ as tracing::Instrument>::instrument(fut, span).await
     }
+    async fn get_node_stats(
+        &self,
+        tonic_request: tonic::Request<GetNodeStatsRequest>,
+    ) -> Result<tonic::Response<GetNodeStatsResponse>, tonic::Status> {
+        let parent_context = quickwit_common::tracing_utils::extract_context(
+            tonic_request.metadata(),
+        );
+        let request = tonic_request.into_inner();
+        let span = tracing::info_span!("developer.get_node_stats");
+        let _ = <tracing::Span as tracing_opentelemetry::OpenTelemetrySpanExt>::set_parent(
+            &span,
+            parent_context,
+        );
+        let fut = async move {
+            self.inner
+                .0
+                .get_node_stats(request)
+                .await
+                .map(tonic::Response::new)
+                .map_err(crate::error::grpc_error_to_grpc_status)
+        };
+        <_ as tracing::Instrument>::instrument(fut, span).await
+    }
+    async fn list_merge_operations(
+        &self,
+        tonic_request: tonic::Request<ListMergeOperationsRequest>,
+    ) -> Result<tonic::Response<ListMergeOperationsResponse>, tonic::Status> {
+        let parent_context = quickwit_common::tracing_utils::extract_context(
+            tonic_request.metadata(),
+        );
+        let request = tonic_request.into_inner();
+        let span = tracing::info_span!("developer.list_merge_operations");
+        let _ = <tracing::Span as tracing_opentelemetry::OpenTelemetrySpanExt>::set_parent(
+            &span,
+            parent_context,
+        );
+        let fut = async move {
+            self.inner
+                .0
+                .list_merge_operations(request)
+                .await
+                .map(tonic::Response::new)
+                .map_err(crate::error::grpc_error_to_grpc_status)
+        };
+        <_ as tracing::Instrument>::instrument(fut, span).await
+    }
 }
 /// Generated client implementations.
 pub mod developer_service_grpc_client {
@@ -642,6 +1000,64 @@ pub mod developer_service_grpc_client {
                 );
             self.inner.unary(req, path, codec).await
         }
+        pub async fn get_node_stats(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetNodeStatsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetNodeStatsResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic_prost::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/quickwit.developer.DeveloperService/GetNodeStats",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "quickwit.developer.DeveloperService",
+                        "GetNodeStats",
+                    ),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn list_merge_operations(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ListMergeOperationsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListMergeOperationsResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic_prost::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/quickwit.developer.DeveloperService/ListMergeOperations",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "quickwit.developer.DeveloperService",
+                        "ListMergeOperations",
+                    ),
+                );
+            self.inner.unary(req, path, codec).await
+        }
     }
 }
 /// Generated server implementations.
@@ -664,6 +1080,20 @@ pub mod developer_service_grpc_server {
             tonic::Response<super::GetDebugInfoResponse>,
             tonic::Status,
         >;
+        async fn get_node_stats(
+            &self,
+            request: tonic::Request<super::GetNodeStatsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetNodeStatsResponse>,
+            tonic::Status,
+        >;
+        async fn list_merge_operations(
+            &self,
+            request: tonic::Request<super::ListMergeOperationsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListMergeOperationsResponse>,
+            tonic::Status,
+        >;
     }
     #[derive(Debug)]
     pub struct DeveloperServiceGrpcServer<T> {
@@ -788,6 +1218,98 @@ pub mod developer_service_grpc_server {
                     };
                     Box::pin(fut)
                 }
+                "/quickwit.developer.DeveloperService/GetNodeStats" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetNodeStatsSvc<T: DeveloperServiceGrpc>(pub Arc<T>);
+                    impl<
+                        T: DeveloperServiceGrpc,
+                    > tonic::server::UnaryService<super::GetNodeStatsRequest>
+                    for GetNodeStatsSvc<T> {
+                        type Response = super::GetNodeStatsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetNodeStatsRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as DeveloperServiceGrpc>::get_node_stats(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = GetNodeStatsSvc(inner);
+                        let codec = tonic_prost::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/quickwit.developer.DeveloperService/ListMergeOperations" => {
+                    #[allow(non_camel_case_types)]
+                    struct ListMergeOperationsSvc<T: DeveloperServiceGrpc>(pub Arc<T>);
+                    impl<
+                        T: DeveloperServiceGrpc,
+                    > tonic::server::UnaryService<super::ListMergeOperationsRequest>
+                    for ListMergeOperationsSvc<T> {
+                        type Response = super::ListMergeOperationsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ListMergeOperationsRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as DeveloperServiceGrpc>::list_merge_operations(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = ListMergeOperationsSvc(inner);
+                        let codec = tonic_prost::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 _ => {
                     Box::pin(async move {
                         let mut response = http::Response::new(
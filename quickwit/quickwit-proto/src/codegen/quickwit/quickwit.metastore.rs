@@ -35,6 +35,11 @@ pub struct UpdateIndexRequest {
     pub retention_policy_json_opt: ::core::option::Option<
         ::prost::alloc::string::String,
     >,
+    #[prost(string, tag = "7")]
+    pub response_headers_json: ::prost::alloc::string::String,
+    /// When set, the update is only applied if the doc mapping of the index still has this UID.
+    #[prost(message, optional, tag = "8")]
+    pub expected_doc_mapping_uid: ::core::option::Option<crate::types::DocMappingUid>,
 }
 #[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
 #[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
@@ -44,6 +49,9 @@ pub struct ListIndexesMetadataRequest {
     /// with a '-'), and no negative pattern (a pattern starting with a '-').
     #[prost(string, repeated, tag = "2")]
     pub index_id_patterns: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// Only lists the indexes whose indexing is paused.
+    #[prost(bool, tag = "3")]
+    pub paused_only: bool,
 }
 #[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
 #[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
@@ -296,6 +304,31 @@ pub struct UpdateSplitsDeleteOpstampRequest {
 pub struct UpdateSplitsDeleteOpstampResponse {}
 #[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
 #[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct UpdateSplitsTagsRequest {
+    #[prost(message, optional, tag = "1")]
+    pub index_uid: ::core::option::Option<crate::types::IndexUid>,
+    /// Only the splits whose time range overlaps \[time_range_start, time_range_end) are updated.
+    #[prost(int64, optional, tag = "2")]
+    pub time_range_start: ::core::option::Option<i64>,
+    #[prost(int64, optional, tag = "3")]
+    pub time_range_end: ::core::option::Option<i64>,
+    /// Only the splits carrying all these tags are updated.
+    #[prost(string, repeated, tag = "4")]
+    pub tags: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(string, repeated, tag = "5")]
+    pub add_tags: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(string, repeated, tag = "6")]
+    pub remove_tags: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct UpdateSplitsTagsResponse {
+    /// Number of splits whose tags changed.
+    #[prost(uint64, tag = "1")]
+    pub num_updated_splits: u64,
+}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
 pub struct LastDeleteOpstampRequest {
     #[prost(message, optional, tag = "1")]
     pub index_uid: ::core::option::Option<crate::types::IndexUid>,
@@ -850,6 +883,11 @@ impl RpcName for UpdateSplitsDeleteOpstampRequest {
         "update_splits_delete_opstamp"
     }
 }
+impl RpcName for UpdateSplitsTagsRequest {
+    fn rpc_name() -> &'static str {
+        "update_splits_tags"
+    }
+}
 impl RpcName for ListDeleteTasksRequest {
     fn rpc_name() -> &'static str {
         "list_delete_tasks"
@@ -1074,6 +1112,11 @@ pub trait MetastoreService: std::fmt::Debug + Send + Sync + 'static {
         &self,
         request: UpdateSplitsDeleteOpstampRequest,
     ) -> crate::metastore::MetastoreResult<UpdateSplitsDeleteOpstampResponse>;
+    ///Updates the tags of the published splits matching a filter.
+    async fn update_splits_tags(
+        &self,
+        request: UpdateSplitsTagsRequest,
+    ) -> crate::metastore::MetastoreResult<UpdateSplitsTagsResponse>;
     ///Lists delete tasks with `delete_task.opstamp` > `opstamp_start` for a given `index_id`.
     async fn list_delete_tasks(
         &self,
@@ -1452,6 +1495,13 @@ impl MetastoreService for MetastoreServiceClient {
     ) -> crate::metastore::MetastoreResult<UpdateSplitsDeleteOpstampResponse> {
         self.inner.0.update_splits_delete_opstamp(request).await
     }
+    #[tracing::instrument(skip_all, name = "metastore.update_splits_tags")]
+    async fn update_splits_tags(
+        &self,
+        request: UpdateSplitsTagsRequest,
+    ) -> crate::metastore::MetastoreResult<UpdateSplitsTagsResponse> {
+        self.inner.0.update_splits_tags(request).await
+    }
     #[tracing::instrument(skip_all, name = "metastore.list_delete_tasks")]
     async fn list_delete_tasks(
         &self,
@@ -1753,6 +1803,14 @@ pub mod mock_metastore_service {
         > {
             self.inner.lock().await.update_splits_delete_opstamp(request).await
         }
+        async fn update_splits_tags(
+            &self,
+            request: super::UpdateSplitsTagsRequest,
+        ) -> crate::metastore::MetastoreResult<
+            super::UpdateSplitsTagsResponse,
+        > {
+            self.inner.lock().await.update_splits_tags(request).await
+        }
         async fn list_delete_tasks(
             &self,
             request: super::ListDeleteTasksRequest,
@@ -2222,6 +2280,22 @@ impl tower::Service<UpdateSplitsDeleteOpstampRequest> for InnerMetastoreServiceC
         Box::pin(fut)
     }
 }
+impl tower::Service<UpdateSplitsTagsRequest> for InnerMetastoreServiceClient {
+    type Response = UpdateSplitsTagsResponse;
+    type Error = crate::metastore::MetastoreError;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+    fn poll_ready(
+        &mut self,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+    fn call(&mut self, request: UpdateSplitsTagsRequest) -> Self::Future {
+        let svc = self.clone();
+        let fut = async move { svc.0.update_splits_tags(request).await };
+        Box::pin(fut)
+    }
+}
 impl tower::Service<ListDeleteTasksRequest> for InnerMetastoreServiceClient {
     type Response = ListDeleteTasksResponse;
     type Error = crate::metastore::MetastoreError;
@@ -2696,6 +2770,11 @@ struct MetastoreServiceTowerServiceStack {
         UpdateSplitsDeleteOpstampResponse,
         crate::metastore::MetastoreError,
     >,
+    update_splits_tags_svc: quickwit_common::tower::BoxService<
+        UpdateSplitsTagsRequest,
+        UpdateSplitsTagsResponse,
+        crate::metastore::MetastoreError,
+    >,
     list_delete_tasks_svc: quickwit_common::tower::BoxService<
         ListDeleteTasksRequest,
         ListDeleteTasksResponse,
@@ -2934,6 +3013,12 @@ impl MetastoreService for MetastoreServiceTowerServiceStack {
     ) -> crate::metastore::MetastoreResult<UpdateSplitsDeleteOpstampResponse> {
         self.update_splits_delete_opstamp_svc.clone().ready().await?.call(request).await
     }
+    async fn update_splits_tags(
+        &self,
+        request: UpdateSplitsTagsRequest,
+    ) -> crate::metastore::MetastoreResult<UpdateSplitsTagsResponse> {
+        self.update_splits_tags_svc.clone().ready().await?.call(request).await
+    }
     async fn list_delete_tasks(
         &self,
         request: ListDeleteTasksRequest,
@@ -3289,6 +3374,16 @@ type UpdateSplitsDeleteOpstampLayer = quickwit_common::tower::BoxLayer<
     UpdateSplitsDeleteOpstampResponse,
     crate::metastore::MetastoreError,
 >;
+type UpdateSplitsTagsLayer = quickwit_common::tower::BoxLayer<
+    quickwit_common::tower::BoxService<
+        UpdateSplitsTagsRequest,
+        UpdateSplitsTagsResponse,
+        crate::metastore::MetastoreError,
+    >,
+    UpdateSplitsTagsRequest,
+    UpdateSplitsTagsResponse,
+    crate::metastore::MetastoreError,
+>;
 type ListDeleteTasksLayer = quickwit_common::tower::BoxLayer<
     quickwit_common::tower::BoxService<
         ListDeleteTasksRequest,
@@ -3541,6 +3636,7 @@ pub struct MetastoreServiceTowerLayerStack {
     last_delete_opstamp_layers: Vec<LastDeleteOpstampLayer>,
     create_delete_task_layers: Vec<CreateDeleteTaskLayer>,
     update_splits_delete_opstamp_layers: Vec<UpdateSplitsDeleteOpstampLayer>,
+    update_splits_tags_layers: Vec<UpdateSplitsTagsLayer>,
     list_delete_tasks_layers: Vec<ListDeleteTasksLayer>,
     list_stale_splits_layers: Vec<ListStaleSplitsLayer>,
     open_shards_layers: Vec<OpenShardsLayer>,
@@ -4076,6 +4172,33 @@ impl MetastoreServiceTowerLayerStack {
         >>::Service as tower::Service<
             UpdateSplitsDeleteOpstampRequest,
         >>::Future: Send + 'static,
+        L: tower::Layer<
+                quickwit_common::tower::BoxService<
+                    UpdateSplitsTagsRequest,
+                    UpdateSplitsTagsResponse,
+                    crate::metastore::MetastoreError,
+                >,
+            > + Clone + Send + Sync + 'static,
+        <L as tower::Layer<
+            quickwit_common::tower::BoxService<
+                UpdateSplitsTagsRequest,
+                UpdateSplitsTagsResponse,
+                crate::metastore::MetastoreError,
+            >,
+        >>::Service: tower::Service<
+                UpdateSplitsTagsRequest,
+                Response = UpdateSplitsTagsResponse,
+                Error = crate::metastore::MetastoreError,
+            > + Clone + Send + Sync + 'static,
+        <<L as tower::Layer<
+            quickwit_common::tower::BoxService<
+                UpdateSplitsTagsRequest,
+                UpdateSplitsTagsResponse,
+                crate::metastore::MetastoreError,
+            >,
+        >>::Service as tower::Service<
+            UpdateSplitsTagsRequest,
+        >>::Future: Send + 'static,
         L: tower::Layer<
                 quickwit_common::tower::BoxService<
                     ListDeleteTasksRequest,
@@ -4716,6 +4839,8 @@ impl MetastoreServiceTowerLayerStack {
             .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
         self.update_splits_delete_opstamp_layers
             .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
+        self.update_splits_tags_layers
+            .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
         self.list_delete_tasks_layers
             .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
         self.list_stale_splits_layers
@@ -5158,6 +5283,28 @@ impl MetastoreServiceTowerLayerStack {
             .push(quickwit_common::tower::BoxLayer::new(layer));
         self
     }
+    pub fn stack_update_splits_tags_layer<L>(mut self, layer: L) -> Self
+    where
+        L: tower::Layer<
+                quickwit_common::tower::BoxService<
+                    UpdateSplitsTagsRequest,
+                    UpdateSplitsTagsResponse,
+                    crate::metastore::MetastoreError,
+                >,
+            > + Send + Sync + 'static,
+        L::Service: tower::Service<
+                UpdateSplitsTagsRequest,
+                Response = UpdateSplitsTagsResponse,
+                Error = crate::metastore::MetastoreError,
+            > + Clone + Send + Sync + 'static,
+        <L::Service as tower::Service<
+            UpdateSplitsTagsRequest,
+        >>::Future: Send + 'static,
+    {
+        self.update_splits_tags_layers
+            .push(quickwit_common::tower::BoxLayer::new(layer));
+        self
+    }
     pub fn stack_list_delete_tasks_layer<L>(mut self, layer: L) -> Self
     where
         L: tower::Layer<
@@ -5855,6 +6002,14 @@ impl MetastoreServiceTowerLayerStack {
                 quickwit_common::tower::BoxService::new(inner_client.clone()),
                 |svc, layer| layer.layer(svc),
             );
+        let update_splits_tags_svc = self
+            .update_splits_tags_layers
+            .into_iter()
+            .rev()
+            .fold(
+                quickwit_common::tower::BoxService::new(inner_client.clone()),
+                |svc, layer| layer.layer(svc),
+            );
         let list_delete_tasks_svc = self
             .list_delete_tasks_layers
             .into_iter()
@@ -6061,6 +6216,7 @@ impl MetastoreServiceTowerLayerStack {
             last_delete_opstamp_svc,
             create_delete_task_svc,
             update_splits_delete_opstamp_svc,
+            update_splits_tags_svc,
             list_delete_tasks_svc,
             list_stale_splits_svc,
             open_shards_svc,
@@ -6292,6 +6448,15 @@ where
                 crate::metastore::MetastoreError,
             >,
         >
+        + tower::Service<
+            UpdateSplitsTagsRequest,
+            Response = UpdateSplitsTagsResponse,
+            Error = crate::metastore::MetastoreError,
+            Future = BoxFuture<
+                UpdateSplitsTagsResponse,
+                crate::metastore::MetastoreError,
+            >,
+        >
         + tower::Service<
             ListDeleteTasksRequest,
             Response = ListDeleteTasksResponse,
@@ -6569,6 +6734,12 @@ where
     ) -> crate::metastore::MetastoreResult<UpdateSplitsDeleteOpstampResponse> {
         self.clone().call(request).await
     }
+    async fn update_splits_tags(
+        &self,
+        request: UpdateSplitsTagsRequest,
+    ) -> crate::metastore::MetastoreResult<UpdateSplitsTagsResponse> {
+        self.clone().call(request).await
+    }
     async fn list_delete_tasks(
         &self,
         request: ListDeleteTasksRequest,
@@ -7122,6 +7293,24 @@ where
                 UpdateSplitsDeleteOpstampRequest::rpc_name(),
             ))
     }
+    async fn update_splits_tags(
+        &self,
+        request: UpdateSplitsTagsRequest,
+    ) -> crate::metastore::MetastoreResult<UpdateSplitsTagsResponse> {
+        let mut tonic_request = tonic::Request::new(request);
+        quickwit_common::tracing_utils::inject_current_context(
+            tonic_request.metadata_mut(),
+        );
+        self.inner
+            .clone()
+            .update_splits_tags(tonic_request)
+            .await
+            .map(|response| response.into_inner())
+            .map_err(|status| crate::error::grpc_status_to_service_error(
+                status,
+                UpdateSplitsTagsRequest::rpc_name(),
+            ))
+    }
     async fn list_delete_tasks(
         &self,
         request: ListDeleteTasksRequest,
@@ -8034,6 +8223,29 @@ for MetastoreServiceGrpcServerAdapter {
         };
         <_ as tracing::Instrument>::instrument(fut, span).await
     }
+    async fn update_splits_tags(
+        &self,
+        tonic_request: tonic::Request<UpdateSplitsTagsRequest>,
+    ) -> Result<tonic::Response<UpdateSplitsTagsResponse>, tonic::Status> {
+        let parent_context = quickwit_common::tracing_utils::extract_context(
+            tonic_request.metadata(),
+        );
+        let request = tonic_request.into_inner();
+        let span = tracing::info_span!("metastore.update_splits_tags");
+        let _ = <tracing::Span as tracing_opentelemetry::OpenTelemetrySpanExt>::set_parent(
+            &span,
+            parent_context,
+        );
+        let fut = async move {
+            self.inner
+                .0
+                .update_splits_tags(request)
+                .await
+                .map(tonic::Response::new)
+                .map_err(crate::error::grpc_error_to_grpc_status)
+        };
+        <_ as tracing::Instrument>::instrument(fut, span).await
+    }
     async fn list_delete_tasks(
         &self,
         tonic_request: tonic::Request<ListDeleteTasksRequest>,
@@ -9261,6 +9473,36 @@ pub mod metastore_service_grpc_client {
                 );
             self.inner.unary(req, path, codec).await
         }
+        /// Updates the tags of the published splits matching a filter.
+        pub async fn update_splits_tags(
+            &mut self,
+            request: impl tonic::IntoRequest<super::UpdateSplitsTagsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::UpdateSplitsTagsResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic_prost::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/quickwit.metastore.MetastoreService/UpdateSplitsTags",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "quickwit.metastore.MetastoreService",
+                        "UpdateSplitsTags",
+                    ),
+                );
+            self.inner.unary(req, path, codec).await
+        }
         /// Lists delete tasks with `delete_task.opstamp` > `opstamp_start` for a given `index_id`.
         pub async fn list_delete_tasks(
             &mut self,
@@ -10070,6 +10312,14 @@ pub mod metastore_service_grpc_server {
             tonic::Response<super::UpdateSplitsDeleteOpstampResponse>,
             tonic::Status,
         >;
+        /// Updates the tags of the published splits matching a filter.
+        async fn update_splits_tags(
+            &self,
+            request: tonic::Request<super::UpdateSplitsTagsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::UpdateSplitsTagsResponse>,
+            tonic::Status,
+        >;
         /// Lists delete tasks with `delete_task.opstamp` > `opstamp_start` for a given `index_id`.
         async fn list_delete_tasks(
             &self,
@@ -11316,6 +11566,60 @@ pub mod metastore_service_grpc_server {
                     };
                     Box::pin(fut)
                 }
+                "/quickwit.metastore.MetastoreService/UpdateSplitsTags" => {
+                    #[allow(non_camel_case_types)]
+                    struct UpdateSplitsTagsSvc<T: MetastoreServiceGrpc>(
+                        pub Arc<T>,
+                    );
+                    impl<
+                        T: MetastoreServiceGrpc,
+                    > tonic::server::UnaryService<
+                        super::UpdateSplitsTagsRequest,
+                    > for UpdateSplitsTagsSvc<T> {
+                        type Response = super::UpdateSplitsTagsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::UpdateSplitsTagsRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as MetastoreServiceGrpc>::update_splits_tags(
+                                        &inner,
+                                        request,
+                                    )
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = UpdateSplitsTagsSvc(inner);
+                        let codec = tonic_prost::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 "/quickwit.metastore.MetastoreService/ListDeleteTasks" => {
                     #[allow(non_camel_case_types)]
                     struct ListDeleteTasksSvc<T: MetastoreServiceGrpc>(pub Arc<T>);
@@ -67,6 +67,36 @@ pub struct GetLoadResponse {
 }
 #[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
 #[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct ClearCacheRequest {
+    /// Index ID patterns whose splits are evicted from the caches of the searchers.
+    #[prost(string, repeated, tag = "1")]
+    pub index_id_patterns: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct ClearCacheResponse {
+    /// Number of searcher nodes the eviction was requested from.
+    #[prost(uint64, tag = "1")]
+    pub num_searchers: u64,
+    /// Number of searcher nodes that failed to evict the splits.
+    #[prost(uint64, tag = "2")]
+    pub num_failed_searchers: u64,
+}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct LeafClearCacheRequest {
+    #[prost(string, repeated, tag = "1")]
+    pub split_ids: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct LeafClearCacheResponse {
+    /// Number of splits evicted from the split cache of the searcher node.
+    #[prost(uint64, tag = "1")]
+    pub num_evicted_splits: u64,
+}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
 pub struct ListFieldsRequest {
     /// Index ID patterns
     #[prost(string, repeated, tag = "1")]
@@ -208,6 +238,78 @@ pub struct SearchRequest {
     /// the raw IntermediateAggregationResults bytes instead.
     #[prost(bool, tag = "19")]
     pub skip_aggregation_finalization: bool,
+    /// When true, the documents are not fetched from the doc store: hits only
+    /// hold their address, sort values, and index ID.
+    #[prost(bool, tag = "20")]
+    pub skip_fetch_docs: bool,
+    /// When set, the hits scoring below this threshold are discarded before
+    /// the top-k collection: they are neither returned nor counted in `num_hits`.
+    #[prost(message, optional, tag = "21")]
+    pub min_score: ::core::option::Option<ScoreThreshold>,
+    /// When true, the search response includes the estimated cost of the query.
+    #[prost(bool, tag = "22")]
+    pub explain: bool,
+    /// Whether the splits are listed from the most up-to-date metastore or from the
+    /// read replica, when the node is configured with one.
+    #[prost(enumeration = "ReadPreference", tag = "23")]
+    pub read_preference: i32,
+    /// When true, each hit carries its score along with its raw score, computed without the
+    /// boosts of the query.
+    #[prost(bool, tag = "24")]
+    pub explain_scores: bool,
+    /// If set, the search of a split taking longer than this duration is abandoned, and the split
+    /// is reported as a timed out failed split instead of failing the whole search.
+    #[prost(uint64, optional, tag = "25")]
+    pub per_split_timeout_millis: ::core::option::Option<u64>,
+    /// If set, overrides the timeout of the search, bounded by the maximum request timeout of the
+    /// searchers.
+    #[prost(uint64, optional, tag = "26")]
+    pub timeout_millis: ::core::option::Option<u64>,
+    /// If set, overrides the aggregation bucket limit of the searchers. Set by the root from the
+    /// `max_aggregation_buckets` search setting of the targeted indexes.
+    #[prost(uint32, optional, tag = "27")]
+    pub max_aggregation_buckets: ::core::option::Option<u32>,
+    /// If set, the similarity function scoring the hits, e.g. `bm25(k1=1.5,b=0.3)` or `tfidf`,
+    /// instead of the default BM25.
+    #[prost(string, optional, tag = "28")]
+    pub scoring: ::core::option::Option<::prost::alloc::string::String>,
+    /// If set, overrides the `missing_fields` search setting of the targeted indexes: the fields of
+    /// the doc mapping absent from a document are rendered as `null` in its hit if true, and
+    /// omitted if false.
+    #[prost(bool, optional, tag = "29")]
+    pub missing_fields_as_null: ::core::option::Option<bool>,
+    /// If true, the searchers do not serve the search from their partial result and predicate
+    /// caches, and read the splits instead. The partial result cache is still populated.
+    #[prost(bool, tag = "30")]
+    pub bypass_cache: bool,
+    /// When true, each hit carries its score even when the hits are sorted by a field rather than
+    /// by relevance. Otherwise, the scores are not computed unless `explain_scores` is set.
+    #[prost(bool, tag = "31")]
+    pub track_scores: bool,
+    /// If true, the request does not set the operator of its user queries, which is replaced by
+    /// the `default_operator` search setting of the targeted indexes, if any.
+    #[prost(bool, tag = "32")]
+    pub inherit_default_operator: bool,
+    /// If true, the request does not set `max_hits`, which is replaced by the `default_max_hits`
+    /// search setting of the targeted indexes, if any.
+    #[prost(bool, tag = "33")]
+    pub inherit_default_max_hits: bool,
+    /// If true, the search is not rejected when its index patterns resolve to more indexes than the
+    /// `max_indexes_per_search` limit of the searchers. Only admin requests can set it.
+    #[prost(bool, tag = "34")]
+    pub ignore_max_indexes_per_search: bool,
+    /// If true, the response describes how the request was resolved: the indexes matched by its
+    /// index ID patterns, the query defaults of the indexes it inherited, and the final query.
+    #[prost(bool, tag = "35")]
+    pub explain_resolution: bool,
+}
+/// Wraps the minimum score of a search request, so that `SearchRequest` can
+/// implement `Hash` and `Eq`.
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct ScoreThreshold {
+    #[prost(float, tag = "1")]
+    pub value: f32,
 }
 #[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
 #[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
@@ -221,6 +323,11 @@ pub struct SortField {
     /// unix_timestamp_nanos.
     #[prost(enumeration = "SortDatetimeFormat", optional, tag = "3")]
     pub sort_datetime_format: ::core::option::Option<i32>,
+    /// Arithmetic expression over numeric fast fields to sort by, e.g.
+    /// `popularity * 0.7 + recency * 0.3`. Must be set if and only if
+    /// the field name is `_script`.
+    #[prost(string, optional, tag = "4")]
+    pub script: ::core::option::Option<::prost::alloc::string::String>,
 }
 #[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -258,6 +365,59 @@ pub struct SearchResponse {
     /// Resource statistics for the root search.
     #[prost(message, optional, tag = "10")]
     pub resource_stats: ::core::option::Option<RootResourceStats>,
+    /// Headers to add to the REST response, declared in the configs of the targeted indexes.
+    #[prost(map = "string, string", tag = "11")]
+    pub response_headers: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+    /// Estimated cost of the query, only set if `explain` was set in the request.
+    #[prost(message, optional, tag = "12")]
+    pub cost_estimate: ::core::option::Option<QueryCostEstimate>,
+    /// Set when the hits were truncated to fit in the maximum response size of the indexes.
+    #[prost(string, optional, tag = "13")]
+    pub truncation_reason: ::core::option::Option<::prost::alloc::string::String>,
+    /// Resolution of the request, only set if `explain_resolution` was set in the request.
+    #[prost(message, optional, tag = "14")]
+    pub resolution: ::core::option::Option<SearchResolution>,
+    /// IDs of the indexes matched by the index ID patterns of the request.
+    #[prost(string, repeated, tag = "15")]
+    pub searched_index_ids: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+/// Cost of a query, estimated before executing it from the splits selected by the planning phase.
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct QueryCostEstimate {
+    /// Number of splits to scan.
+    #[prost(uint64, tag = "1")]
+    pub num_splits: u64,
+    /// Number of fields read by the query, the sort, and the aggregations.
+    #[prost(uint64, tag = "2")]
+    pub num_fields: u64,
+    /// Estimated number of documents to scan, i.e. the number of documents of the splits to scan.
+    #[prost(uint64, tag = "3")]
+    pub num_docs: u64,
+    /// Estimated cost, computed as `num_fields * num_docs`.
+    #[prost(uint64, tag = "4")]
+    pub cost: u64,
+}
+/// How a search request was resolved before being executed.
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct SearchResolution {
+    /// IDs of the indexes matched by the index ID patterns of the request.
+    #[prost(string, repeated, tag = "1")]
+    pub index_ids: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// Default operator of the user queries inherited from the search settings of the indexes.
+    #[prost(string, optional, tag = "2")]
+    pub default_operator: ::core::option::Option<::prost::alloc::string::String>,
+    /// Default number of hits inherited from the search settings of the indexes.
+    #[prost(uint64, optional, tag = "3")]
+    pub default_max_hits: ::core::option::Option<u64>,
+    /// Final query AST, serialized in JSON, after the query defaults and the doc mappings of the
+    /// indexes are applied.
+    #[prost(string, tag = "4")]
+    pub query_ast: ::prost::alloc::string::String,
 }
 #[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
 #[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
@@ -277,6 +437,9 @@ pub struct SplitSearchError {
     /// Flag to indicate if the error can be considered a retryable error
     #[prost(bool, tag = "3")]
     pub retryable_error: bool,
+    /// Flag to indicate if the search of the split exceeded the per-split timeout of the request.
+    #[prost(bool, tag = "4")]
+    pub timed_out: bool,
 }
 /// A LeafSearchRequest can span multiple indices.
 #[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
@@ -523,6 +686,9 @@ pub struct LeafHit {
     /// A snippet of the matching content
     #[prost(string, optional, tag = "3")]
     pub leaf_snippet_json: ::core::option::Option<::prost::alloc::string::String>,
+    /// The scores of the hit, only computed when requested
+    #[prost(message, optional, tag = "4")]
+    pub scores: ::core::option::Option<HitScores>,
 }
 #[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -539,6 +705,19 @@ pub struct Hit {
     /// The index id of the hit
     #[prost(string, tag = "4")]
     pub index_id: ::prost::alloc::string::String,
+    /// The scores of the hit, only computed when requested
+    #[prost(message, optional, tag = "5")]
+    pub scores: ::core::option::Option<HitScores>,
+}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct HitScores {
+    /// Score of the hit, as computed by the query.
+    #[prost(float, tag = "1")]
+    pub score: f32,
+    /// Score of the hit, as computed by the query stripped of its boosts.
+    #[prost(float, tag = "2")]
+    pub raw_score: f32,
 }
 /// A partial hit, is a hit for which we have not fetch the content yet.
 /// Instead, it holds a document_uri which is enough information to
@@ -668,6 +847,15 @@ pub struct SnippetRequest {
     pub query_ast_resolved: ::prost::alloc::string::String,
 }
 #[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[derive(Clone, PartialEq, Eq, Hash, ::prost::Message)]
+pub struct ScoreRequest {
+    #[prost(string, tag = "1")]
+    pub query_ast_resolved: ::prost::alloc::string::String,
+    /// The scoring of the search request, if any.
+    #[prost(string, optional, tag = "2")]
+    pub scoring: ::core::option::Option<::prost::alloc::string::String>,
+}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct FetchDocsRequest {
     /// Request fetching the content of a given list of partial_hits.
@@ -684,6 +872,9 @@ pub struct FetchDocsRequest {
     pub index_uri: ::prost::alloc::string::String,
     #[prost(message, optional, tag = "7")]
     pub snippet_request: ::core::option::Option<SnippetRequest>,
+    /// Set to compute the scores of the hits.
+    #[prost(message, optional, tag = "8")]
+    pub score_request: ::core::option::Option<ScoreRequest>,
     /// `DocMapper` as json serialized trait.
     #[prost(string, tag = "6")]
     pub doc_mapper: ::prost::alloc::string::String,
@@ -912,6 +1103,38 @@ impl SortDatetimeFormat {
         }
     }
 }
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum ReadPreference {
+    /// Lists the splits from the read replica of the metastore, if any. The replica may lag
+    /// behind, but offloads the primary metastore.
+    Fastest = 0,
+    /// Lists the splits from the primary metastore, so that the most recently published splits
+    /// are searched.
+    Freshest = 1,
+}
+impl ReadPreference {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Self::Fastest => "FASTEST",
+            Self::Freshest => "FRESHEST",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "FASTEST" => Some(Self::Fastest),
+            "FRESHEST" => Some(Self::Freshest),
+            _ => None,
+        }
+    }
+}
 /// Generated client implementations.
 pub mod search_service_client {
     #![allow(
@@ -1345,6 +1568,33 @@ pub mod search_service_client {
                 .insert(GrpcMethod::new("quickwit.search.SearchService", "GetLoad"));
             self.inner.unary(req, path, codec).await
         }
+        /// Evicts the given splits from the caches of this searcher node.
+        pub async fn leaf_clear_cache(
+            &mut self,
+            request: impl tonic::IntoRequest<super::LeafClearCacheRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::LeafClearCacheResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic_prost::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/quickwit.search.SearchService/LeafClearCache",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("quickwit.search.SearchService", "LeafClearCache"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
     }
 }
 /// Generated server implementations.
@@ -1470,6 +1720,14 @@ pub mod search_service_server {
             &self,
             request: tonic::Request<super::GetLoadRequest>,
         ) -> std::result::Result<tonic::Response<super::GetLoadResponse>, tonic::Status>;
+        /// Evicts the given splits from the caches of this searcher node.
+        async fn leaf_clear_cache(
+            &self,
+            request: tonic::Request<super::LeafClearCacheRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::LeafClearCacheResponse>,
+            tonic::Status,
+        >;
     }
     #[derive(Debug)]
     pub struct SearchServiceServer<T> {
@@ -2131,6 +2389,52 @@ pub mod search_service_server {
                     };
                     Box::pin(fut)
                 }
+                "/quickwit.search.SearchService/LeafClearCache" => {
+                    #[allow(non_camel_case_types)]
+                    struct LeafClearCacheSvc<T: SearchService>(pub Arc<T>);
+                    impl<
+                        T: SearchService,
+                    > tonic::server::UnaryService<super::LeafClearCacheRequest>
+                    for LeafClearCacheSvc<T> {
+                        type Response = super::LeafClearCacheResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::LeafClearCacheRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as SearchService>::leaf_clear_cache(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = LeafClearCacheSvc(inner);
+                        let codec = tonic_prost::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 _ => {
                     Box::pin(async move {
                         let mut response = http::Response::new(
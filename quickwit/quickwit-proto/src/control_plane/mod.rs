@@ -138,6 +138,12 @@ impl RpcName for AdviseResetShardsRequest {
     }
 }
 
+impl RpcName for RebalanceIndexingPipelinesRequest {
+    fn rpc_name() -> &'static str {
+        "rebalance_indexing_pipelines"
+    }
+}
+
 impl GetOrCreateOpenShardsFailureReason {
     pub fn create_failure(
         &self,
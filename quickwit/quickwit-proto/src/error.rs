@@ -34,6 +34,9 @@ const QW_ERROR_HEADER_NAME: &str = "qw-error-bin";
 pub enum ServiceErrorCode {
     AlreadyExists,
     BadRequest,
+    // The request conflicts with the state of the resource, for instance several documents
+    // sharing an id.
+    Conflict,
     // Use `Unauthenticated` if the caller cannot be identified.
     Forbidden,
     Internal,
@@ -42,6 +45,7 @@ pub enum ServiceErrorCode {
     TooManyRequests,
     Unauthenticated,
     Unavailable,
+    UnsupportedMediaType,
 }
 
 impl ServiceErrorCode {
@@ -49,6 +53,7 @@ impl ServiceErrorCode {
         match self {
             Self::AlreadyExists => tonic::Code::AlreadyExists,
             Self::BadRequest => tonic::Code::InvalidArgument,
+            Self::Conflict => tonic::Code::FailedPrecondition,
             Self::Forbidden => tonic::Code::PermissionDenied,
             Self::Internal => tonic::Code::Internal,
             Self::NotFound => tonic::Code::NotFound,
@@ -56,6 +61,7 @@ impl ServiceErrorCode {
             Self::TooManyRequests => tonic::Code::ResourceExhausted,
             Self::Unauthenticated => tonic::Code::Unauthenticated,
             Self::Unavailable => tonic::Code::Unavailable,
+            Self::UnsupportedMediaType => tonic::Code::InvalidArgument,
         }
     }
 
@@ -63,6 +69,7 @@ impl ServiceErrorCode {
         match self {
             Self::AlreadyExists => http::StatusCode::BAD_REQUEST,
             Self::BadRequest => http::StatusCode::BAD_REQUEST,
+            Self::Conflict => http::StatusCode::CONFLICT,
             Self::Forbidden => http::StatusCode::FORBIDDEN,
             Self::Internal => http::StatusCode::INTERNAL_SERVER_ERROR,
             Self::NotFound => http::StatusCode::NOT_FOUND,
@@ -70,6 +77,7 @@ impl ServiceErrorCode {
             Self::TooManyRequests => http::StatusCode::TOO_MANY_REQUESTS,
             Self::Unauthenticated => http::StatusCode::UNAUTHORIZED,
             Self::Unavailable => http::StatusCode::SERVICE_UNAVAILABLE,
+            Self::UnsupportedMediaType => http::StatusCode::UNSUPPORTED_MEDIA_TYPE,
         }
     }
 }
@@ -90,6 +90,7 @@ generate_getters! {
     impl fn index_uid() -> &IndexUid {} for
     // Control Plane API
     GetOrCreateOpenShardsSuccess,
+    IndexingPipelineMove,
 
     // Indexing API
     IndexingTask,
@@ -138,6 +139,7 @@ generate_getters! {
     UpdateIndexRequest,
     UpdateSourceRequest,
     UpdateSplitsDeleteOpstampRequest,
+    UpdateSplitsTagsRequest,
 
     // Metrics Split API
     StageMetricsSplitsRequest,
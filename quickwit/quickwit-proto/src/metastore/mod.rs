@@ -490,6 +490,7 @@ impl ListIndexesMetadataRequest {
     pub fn all() -> ListIndexesMetadataRequest {
         ListIndexesMetadataRequest {
             index_id_patterns: vec!["*".to_string()],
+            paused_only: false,
         }
     }
 }
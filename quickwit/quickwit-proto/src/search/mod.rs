@@ -57,6 +57,14 @@ impl fmt::Display for SplitSearchError {
     }
 }
 
+impl Eq for ScoreThreshold {}
+
+impl std::hash::Hash for ScoreThreshold {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.value.to_bits().hash(state);
+    }
+}
+
 impl Eq for SortByValue {}
 
 impl From<SortValue> for SortByValue {
@@ -29,6 +29,7 @@ mod error;
 mod json_literal;
 mod not_nan_f32;
 pub mod query_ast;
+mod scoring;
 pub mod tokenizers;
 
 pub use elastic_query_dsl::{ElasticQueryDsl, OneFieldMap};
@@ -36,11 +37,12 @@ pub use error::InvalidQuery;
 pub use json_literal::{InterpretUserInput, JsonLiteral};
 pub(crate) use not_nan_f32::NotNaNf32;
 pub use query_ast::utils::find_field_or_hit_dynamic;
+pub use scoring::Scoring;
 use serde::{Deserialize, Serialize};
 pub use tantivy::query::Query as TantivyQuery;
 pub use tokenizers::{
-    CodeTokenizer, DEFAULT_REMOVE_TOKEN_LENGTH, create_default_quickwit_tokenizer_manager,
-    get_quickwit_fastfield_normalizer_manager,
+    BUILTIN_TOKENIZERS, BuiltinTokenizer, CodeTokenizer, DEFAULT_REMOVE_TOKEN_LENGTH,
+    create_default_quickwit_tokenizer_manager, get_quickwit_fastfield_normalizer_manager,
 };
 
 #[derive(Serialize, Deserialize, Debug, Default, Copy, Clone, Eq, PartialEq)]
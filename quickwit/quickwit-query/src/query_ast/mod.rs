@@ -13,6 +13,7 @@
 // limitations under the License.
 
 use std::collections::HashSet;
+use std::convert::Infallible;
 
 use serde::{Deserialize, Serialize};
 use tantivy::Term;
@@ -29,6 +30,7 @@ mod phrase_prefix_query;
 mod range_query;
 mod regex_query;
 mod required_terms;
+mod scored_term_query;
 mod tantivy_query_ast;
 mod term_query;
 mod term_set_query;
@@ -44,6 +46,7 @@ pub use full_text_query::{FullTextMode, FullTextParams, FullTextQuery};
 pub use phrase_prefix_query::PhrasePrefixQuery;
 pub use range_query::RangeQuery;
 pub use regex_query::{AutomatonQuery, JsonPathPrefix, RegexQuery, ResolvedRegex};
+use scored_term_query::apply_scoring;
 use tantivy_query_ast::TantivyQueryAst;
 pub use term_query::TermQuery;
 pub use term_set_query::TermSetQuery;
@@ -51,7 +54,7 @@ pub use user_input_query::UserInputQuery;
 pub use visitor::{QueryAstTransformer, QueryAstVisitor};
 pub use wildcard_query::WildcardQuery;
 
-use crate::{BooleanOperand, InvalidQuery, NotNaNf32};
+use crate::{BooleanOperand, InvalidQuery, NotNaNf32, Scoring};
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 #[serde(tag = "type")]
@@ -173,6 +176,44 @@ impl QueryAst {
             }
         }
     }
+
+    /// Removes the boosts of the query, so that it computes the raw scores of the documents.
+    pub fn remove_boosts(self) -> Self {
+        struct RemoveBoosts;
+
+        impl QueryAstTransformer for RemoveBoosts {
+            type Err = Infallible;
+
+            fn transform_boost(
+                &mut self,
+                underlying: QueryAst,
+                _boost: NotNaNf32,
+            ) -> Result<Option<QueryAst>, Self::Err> {
+                self.transform(underlying)
+            }
+        }
+        let Ok(query_ast_opt) = RemoveBoosts.transform(self);
+        query_ast_opt.unwrap_or(QueryAst::MatchNone)
+    }
+
+    /// Sets the operator combining the clauses of the user queries of the query.
+    pub fn with_default_operator(self, default_operator: BooleanOperand) -> Self {
+        struct SetDefaultOperator(BooleanOperand);
+
+        impl QueryAstTransformer for SetDefaultOperator {
+            type Err = Infallible;
+
+            fn transform_user_text(
+                &mut self,
+                mut user_text_query: UserInputQuery,
+            ) -> Result<Option<QueryAst>, Self::Err> {
+                user_text_query.default_operator = self.0;
+                Ok(Some(QueryAst::UserInput(user_text_query)))
+            }
+        }
+        let Ok(query_ast_opt) = SetDefaultOperator(default_operator).transform(self);
+        query_ast_opt.unwrap_or(QueryAst::MatchNone)
+    }
 }
 
 /// Context used when building a tantivy ast.
@@ -181,6 +222,8 @@ pub struct BuildTantivyAstContext<'a> {
     pub tokenizer_manager: &'a TokenizerManager,
     pub search_fields: &'a [String],
     pub with_validation: bool,
+    /// Scoring of the term queries, if other than the default BM25.
+    pub scoring: Option<Scoring>,
 }
 
 impl<'a> BuildTantivyAstContext<'a> {
@@ -196,6 +239,15 @@ impl<'a> BuildTantivyAstContext<'a> {
             tokenizer_manager: &DEFAULT_TOKENIZER_MANAGER,
             search_fields: &[],
             with_validation: true,
+            scoring: None,
+        }
+    }
+
+    /// Applies the scoring of the context to the term queries of a simplified query.
+    fn apply_scoring(&self, tantivy_query_ast: TantivyQueryAst) -> TantivyQueryAst {
+        match self.scoring {
+            Some(scoring) => apply_scoring(tantivy_query_ast, scoring),
+            None => tantivy_query_ast,
         }
     }
 
@@ -244,7 +296,8 @@ impl BuildTantivyAst for QueryAst {
             QueryAst::MatchNone => Ok(TantivyQueryAst::match_none()),
             QueryAst::Boost { boost, underlying } => {
                 let underlying = underlying.build_tantivy_ast_call(context)?.simplify();
-                let boost_query = TantivyBoostQuery::new(underlying.into(), (*boost).into());
+                let scored_underlying = context.apply_scoring(underlying);
+                let boost_query = TantivyBoostQuery::new(scored_underlying.into(), (*boost).into());
                 Ok(boost_query.into())
             }
             QueryAst::TermSet(term_set) => term_set.build_tantivy_ast_call(context),
@@ -268,8 +321,8 @@ impl QueryAst {
         &self,
         context: &BuildTantivyAstContext,
     ) -> Result<Box<dyn crate::TantivyQuery>, InvalidQuery> {
-        let tantivy_query_ast = self.build_tantivy_ast_call(context)?;
-        Ok(tantivy_query_ast.simplify().into())
+        let tantivy_query_ast = self.build_tantivy_ast_call(context)?.simplify();
+        Ok(context.apply_scoring(tantivy_query_ast).into())
     }
 
     /// Like [`Self::build_tantivy_query`], but additionally returns the set of
@@ -288,7 +341,10 @@ impl QueryAst {
             context.schema,
             &mut required_terms,
         );
-        Ok((tantivy_query_ast.into(), required_terms))
+        Ok((
+            context.apply_scoring(tantivy_query_ast).into(),
+            required_terms,
+        ))
     }
 }
 
@@ -352,10 +408,10 @@ pub fn query_ast_from_user_text(user_text: &str, default_fields: Option<Vec<Stri
 mod tests {
     use crate::query_ast::tantivy_query_ast::TantivyQueryAst;
     use crate::query_ast::{
-        BoolQuery, BuildTantivyAst, BuildTantivyAstContext, QueryAst, UserInputQuery,
+        BoolQuery, BuildTantivyAst, BuildTantivyAstContext, QueryAst, TermQuery, UserInputQuery,
         query_ast_from_user_text,
     };
-    use crate::{BooleanOperand, InvalidQuery};
+    use crate::{BooleanOperand, InvalidQuery, NotNaNf32};
 
     #[test]
     fn test_user_query_not_parsed() {
@@ -455,6 +511,55 @@ mod tests {
         assert_eq!(bool_query.should.len(), 2);
     }
 
+    #[test]
+    fn test_query_ast_remove_boosts() {
+        let term_query: QueryAst = TermQuery {
+            field: "body".to_string(),
+            value: "hello".to_string(),
+        }
+        .into();
+        let boosted_query_ast: QueryAst = BoolQuery {
+            should: vec![
+                term_query
+                    .clone()
+                    .boost(Some(NotNaNf32::try_from(2.0).unwrap())),
+                QueryAst::MatchAll.boost(Some(NotNaNf32::try_from(0.5).unwrap())),
+            ],
+            ..Default::default()
+        }
+        .into();
+        let expected_query_ast: QueryAst = BoolQuery {
+            should: vec![term_query, QueryAst::MatchAll],
+            ..Default::default()
+        }
+        .into();
+        assert_eq!(boosted_query_ast.remove_boosts(), expected_query_ast);
+    }
+
+    #[test]
+    fn test_query_ast_with_default_operator() {
+        let query_ast: QueryAst = BoolQuery {
+            must: vec![query_ast_from_user_text("hello you", None)],
+            filter: vec![
+                TermQuery {
+                    field: "body".to_string(),
+                    value: "hello".to_string(),
+                }
+                .into(),
+            ],
+            ..Default::default()
+        }
+        .into();
+        let QueryAst::Bool(bool_query) = query_ast.with_default_operator(BooleanOperand::Or) else {
+            panic!()
+        };
+        let QueryAst::UserInput(user_input_query) = &bool_query.must[0] else {
+            panic!()
+        };
+        assert_eq!(user_input_query.default_operator, BooleanOperand::Or);
+        assert!(matches!(bool_query.filter[0], QueryAst::Term(_)));
+    }
+
     #[test]
     fn test_query_ast_from_user_text_default_as_and() {
         let ast = query_ast_from_user_text("hello you", None);
@@ -0,0 +1,313 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Term query scoring its hits with a [`Scoring`] other than the default BM25 of tantivy.
+//!
+//! The term queries of a query are replaced with [`ScoredTermQuery`] when the search request
+//! selects another scoring. Phrase, prefix, and other multi-term queries keep the default BM25.
+
+use tantivy::fieldnorm::FieldNormReader;
+use tantivy::postings::{Postings, SegmentPostings};
+use tantivy::query::{
+    EmptyScorer, EnableScoring, Explanation, Query, Scorer, TermQuery as TantivyTermQuery, Weight,
+};
+use tantivy::schema::IndexRecordOption;
+use tantivy::{DocId, DocSet, Score, SegmentReader, TantivyError, Term};
+
+use crate::Scoring;
+use crate::query_ast::tantivy_query_ast::{TantivyBoolQuery, TantivyQueryAst};
+
+/// Replaces the term queries of the scored clauses of the query with queries scoring their hits
+/// with `scoring`.
+pub(crate) fn apply_scoring(
+    tantivy_query_ast: TantivyQueryAst,
+    scoring: Scoring,
+) -> TantivyQueryAst {
+    if scoring == Scoring::default() {
+        return tantivy_query_ast;
+    }
+    match tantivy_query_ast {
+        TantivyQueryAst::Leaf(query) => {
+            let Some(term_query) = query.downcast_ref::<TantivyTermQuery>() else {
+                return TantivyQueryAst::Leaf(query);
+            };
+            ScoredTermQuery {
+                term_query: term_query.clone(),
+                scoring,
+            }
+            .into()
+        }
+        TantivyQueryAst::Bool(bool_query) => {
+            // The `filter` and `must_not` clauses do not contribute to the score.
+            let apply_scoring_to_clauses = |clauses: Vec<TantivyQueryAst>| {
+                clauses
+                    .into_iter()
+                    .map(|clause| apply_scoring(clause, scoring))
+                    .collect::<Vec<_>>()
+            };
+            TantivyQueryAst::Bool(TantivyBoolQuery {
+                must: apply_scoring_to_clauses(bool_query.must),
+                should: apply_scoring_to_clauses(bool_query.should),
+                ..bool_query
+            })
+        }
+        const_predicate @ TantivyQueryAst::ConstPredicate(_) => const_predicate,
+    }
+}
+
+/// Term query scoring its hits with a [`Scoring`].
+#[derive(Clone, Debug)]
+pub(crate) struct ScoredTermQuery {
+    term_query: TantivyTermQuery,
+    scoring: Scoring,
+}
+
+impl Query for ScoredTermQuery {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> tantivy::Result<Box<dyn Weight>> {
+        let EnableScoring::Enabled {
+            statistics_provider,
+            ..
+        } = enable_scoring
+        else {
+            return self.term_query.weight(enable_scoring);
+        };
+        let term = self.term_query.term().clone();
+        let total_num_docs = statistics_provider.total_num_docs()?;
+        let total_num_tokens = statistics_provider.total_num_tokens(term.field())?;
+        let doc_freq = statistics_provider.doc_freq(&term)?;
+        let average_fieldnorm = if total_num_docs > 0 {
+            total_num_tokens as Score / total_num_docs as Score
+        } else {
+            0.0
+        };
+        Ok(Box::new(ScoredTermWeight {
+            term,
+            scoring: self.scoring,
+            idf: self.scoring.idf(doc_freq, total_num_docs),
+            average_fieldnorm,
+        }))
+    }
+
+    fn query_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
+        self.term_query.query_terms(visitor)
+    }
+}
+
+struct ScoredTermWeight {
+    term: Term,
+    scoring: Scoring,
+    idf: Score,
+    average_fieldnorm: Score,
+}
+
+impl ScoredTermWeight {
+    fn scored_term_scorer(
+        &self,
+        reader: &SegmentReader,
+        boost: Score,
+    ) -> tantivy::Result<Option<ScoredTermScorer>> {
+        let field = self.term.field();
+        let inverted_index = reader.inverted_index(field)?;
+        let Some(postings) =
+            inverted_index.read_postings(&self.term, IndexRecordOption::WithFreqs)?
+        else {
+            return Ok(None);
+        };
+        // Fields without fieldnorms are scored as if they contained a single token.
+        let fieldnorm_reader = reader
+            .fieldnorms_readers()
+            .get_field(field)?
+            .unwrap_or_else(|| FieldNormReader::constant(reader.max_doc(), 1));
+        let scorer = ScoredTermScorer {
+            postings,
+            fieldnorm_reader,
+            scoring: self.scoring,
+            idf: self.idf * boost,
+            average_fieldnorm: self.average_fieldnorm,
+        };
+        Ok(Some(scorer))
+    }
+}
+
+impl Weight for ScoredTermWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> tantivy::Result<Box<dyn Scorer>> {
+        match self.scored_term_scorer(reader, boost)? {
+            Some(scorer) => Ok(Box::new(scorer)),
+            None => Ok(Box::new(EmptyScorer)),
+        }
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> tantivy::Result<Explanation> {
+        let mut scorer = self.scorer(reader, 1.0)?;
+
+        if scorer.seek(doc) != doc {
+            return Err(TantivyError::InvalidArgument(format!(
+                "document #({doc}) does not match"
+            )));
+        }
+        let mut explanation = Explanation::new("TermQuery, product of...", scorer.score());
+        explanation.add_const("idf", self.idf);
+        explanation.add_const("average_fieldnorm", self.average_fieldnorm);
+        Ok(explanation)
+    }
+}
+
+struct ScoredTermScorer {
+    postings: SegmentPostings,
+    fieldnorm_reader: FieldNormReader,
+    scoring: Scoring,
+    idf: Score,
+    average_fieldnorm: Score,
+}
+
+impl DocSet for ScoredTermScorer {
+    fn advance(&mut self) -> DocId {
+        self.postings.advance()
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        self.postings.seek(target)
+    }
+
+    fn doc(&self) -> DocId {
+        self.postings.doc()
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.postings.size_hint()
+    }
+}
+
+impl Scorer for ScoredTermScorer {
+    fn score(&mut self) -> Score {
+        let term_freq = self.postings.term_freq();
+        let fieldnorm = self.fieldnorm_reader.fieldnorm(self.doc());
+        self.scoring
+            .term_score(self.idf, term_freq, fieldnorm, self.average_fieldnorm)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tantivy::TERMINATED;
+    use tantivy::schema::{Field, Schema, TEXT};
+
+    use super::*;
+
+    /// Returns the documents matching `query` along with their score, by decreasing score.
+    fn ranked_hits(searcher: &tantivy::Searcher, query: &dyn Query) -> Vec<(DocId, Score)> {
+        let weight = query
+            .weight(EnableScoring::enabled_from_searcher(searcher))
+            .unwrap();
+        let mut scorer = weight.scorer(&searcher.segment_readers()[0], 1.0).unwrap();
+        let mut hits = Vec::new();
+
+        while scorer.doc() != TERMINATED {
+            hits.push((scorer.doc(), scorer.score()));
+            scorer.advance();
+        }
+        hits.sort_by(|left, right| right.1.total_cmp(&left.1));
+        hits
+    }
+
+    #[test]
+    fn test_scored_term_query() {
+        let mut schema_builder = Schema::builder();
+        let body_field = schema_builder.add_text_field("body", TEXT);
+        let schema = schema_builder.build();
+        let index = tantivy::IndexBuilder::new()
+            .schema(schema)
+            .create_in_ram()
+            .unwrap();
+        let mut index_writer = index.writer_with_num_threads(1, 20_000_000).unwrap();
+
+        for body in [
+            "apple banana",
+            "apple apple apple apple cherry cherry cherry cherry cherry cherry",
+            "cherry",
+        ] {
+            let mut doc = tantivy::TantivyDocument::default();
+            doc.add_text(body_field, body);
+            index_writer.add_document(doc).unwrap();
+        }
+        index_writer.commit().unwrap();
+        let searcher = index.reader().unwrap().searcher();
+
+        let term = Term::from_field_text(body_field, "apple");
+        let term_query = TantivyTermQuery::new(term, IndexRecordOption::WithFreqs);
+        let scored_term_query = |scoring: Scoring| ScoredTermQuery {
+            term_query: term_query.clone(),
+            scoring,
+        };
+        // The default BM25 scores the hits like the term query of tantivy.
+        let bm25_ranking = ranked_hits(&searcher, &scored_term_query(Scoring::default()));
+        let tantivy_ranking = ranked_hits(&searcher, &term_query);
+        assert_eq!(bm25_ranking.len(), 2);
+        assert_eq!(bm25_ranking[0].0, 1);
+        assert_eq!(bm25_ranking[1].0, 0);
+
+        for ((doc_id, score), (tantivy_doc_id, tantivy_score)) in
+            bm25_ranking.iter().zip(tantivy_ranking.iter())
+        {
+            assert_eq!(doc_id, tantivy_doc_id);
+            assert!((score - tantivy_score).abs() < 1e-5);
+        }
+        // TF-IDF favors the short document.
+        let tfidf_ranking = ranked_hits(&searcher, &scored_term_query(Scoring::TfIdf));
+        assert_eq!(tfidf_ranking.len(), 2);
+        assert_eq!(tfidf_ranking[0].0, 0);
+        assert_eq!(tfidf_ranking[1].0, 1);
+
+        // BM25 without term frequency saturation scores the hits identically.
+        let bm25_without_saturation = Scoring::Bm25 { k1: 0.0, b: 0.75 };
+        let bm25_without_saturation_ranking =
+            ranked_hits(&searcher, &scored_term_query(bm25_without_saturation));
+        assert_eq!(bm25_without_saturation_ranking.len(), 2);
+        assert_eq!(
+            bm25_without_saturation_ranking[0].1,
+            bm25_without_saturation_ranking[1].1
+        );
+    }
+
+    #[test]
+    fn test_apply_scoring() {
+        let term_query = |text: &str| -> TantivyQueryAst {
+            let term = Term::from_field_text(Field::from_field_id(0), text);
+            TantivyTermQuery::new(term, IndexRecordOption::WithFreqs).into()
+        };
+        let bool_query: TantivyQueryAst = TantivyBoolQuery {
+            must: vec![term_query("must")],
+            filter: vec![term_query("filter")],
+            ..Default::default()
+        }
+        .into();
+
+        let unchanged_query = apply_scoring(bool_query.clone(), Scoring::default());
+        assert_eq!(unchanged_query, bool_query);
+
+        let TantivyQueryAst::Bool(scored_bool_query) = apply_scoring(bool_query, Scoring::TfIdf)
+        else {
+            panic!("expected a boolean query");
+        };
+        let TantivyQueryAst::Leaf(must_query) = &scored_bool_query.must[0] else {
+            panic!("expected a leaf query");
+        };
+        assert!(must_query.downcast_ref::<ScoredTermQuery>().is_some());
+
+        let TantivyQueryAst::Leaf(filter_query) = &scored_bool_query.filter[0] else {
+            panic!("expected a leaf query");
+        };
+        assert!(filter_query.downcast_ref::<TantivyTermQuery>().is_some());
+    }
+}
@@ -0,0 +1,217 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{Context, bail};
+use tantivy::Score;
+
+const DEFAULT_BM25_K1: Score = 1.2;
+const DEFAULT_BM25_B: Score = 0.75;
+
+/// Similarity function scoring the documents matching the terms of a query.
+///
+/// Scorings are written `bm25`, `bm25(k1=1.5,b=0.3)`, or `tfidf`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Scoring {
+    /// Okapi BM25, the scoring of tantivy. `k1` controls the saturation of the term frequency,
+    /// and `b` the normalization by the length of the field.
+    Bm25 { k1: Score, b: Score },
+    /// Classic TF-IDF: `sqrt(tf) * idf / sqrt(field length)`, with
+    /// `idf = 1 + ln((num docs + 1) / (doc freq + 1))`.
+    TfIdf,
+}
+
+impl Default for Scoring {
+    fn default() -> Self {
+        Scoring::Bm25 {
+            k1: DEFAULT_BM25_K1,
+            b: DEFAULT_BM25_B,
+        }
+    }
+}
+
+impl Scoring {
+    /// Returns the inverse document frequency of a term found in `doc_freq` of the `num_docs`
+    /// searched documents.
+    pub(crate) fn idf(&self, doc_freq: u64, num_docs: u64) -> Score {
+        match self {
+            Scoring::Bm25 { .. } => {
+                let num_docs_without_term = num_docs.saturating_sub(doc_freq) as Score;
+                (1.0 + (num_docs_without_term + 0.5) / (doc_freq as Score + 0.5)).ln()
+            }
+            Scoring::TfIdf => 1.0 + ((num_docs as Score + 1.0) / (doc_freq as Score + 1.0)).ln(),
+        }
+    }
+
+    /// Returns the score of a document containing a term `term_freq` times in a field of
+    /// `fieldnorm` tokens.
+    pub(crate) fn term_score(
+        &self,
+        idf: Score,
+        term_freq: u32,
+        fieldnorm: u32,
+        average_fieldnorm: Score,
+    ) -> Score {
+        let term_freq = term_freq as Score;
+
+        match *self {
+            Scoring::Bm25 { k1, b } => {
+                let length_ratio = if average_fieldnorm > 0.0 {
+                    fieldnorm as Score / average_fieldnorm
+                } else {
+                    1.0
+                };
+                idf * (1.0 + k1) * term_freq / (term_freq + k1 * (1.0 - b + b * length_ratio))
+            }
+            Scoring::TfIdf => term_freq.sqrt() * idf / (fieldnorm.max(1) as Score).sqrt(),
+        }
+    }
+}
+
+impl fmt::Display for Scoring {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Scoring::Bm25 { k1, b } => write!(formatter, "bm25(k1={k1},b={b})"),
+            Scoring::TfIdf => write!(formatter, "tfidf"),
+        }
+    }
+}
+
+impl FromStr for Scoring {
+    type Err = anyhow::Error;
+
+    fn from_str(scoring_str: &str) -> anyhow::Result<Self> {
+        let scoring_str = scoring_str.trim();
+        let (algorithm, params_str) = match scoring_str.split_once('(') {
+            Some((algorithm, params_str)) => {
+                let Some(params_str) = params_str.strip_suffix(')') else {
+                    bail!("invalid scoring `{scoring_str}`: missing closing parenthesis");
+                };
+                (algorithm.trim(), params_str)
+            }
+            None => (scoring_str, ""),
+        };
+        let params = params_str
+            .split(',')
+            .map(str::trim)
+            .filter(|param| !param.is_empty())
+            .map(|param| {
+                let (name, value_str) = param.split_once('=').with_context(|| {
+                    format!("invalid scoring parameter `{param}`: expected `name=value`")
+                })?;
+                let value: Score = value_str.trim().parse().with_context(|| {
+                    format!("invalid value `{value_str}` for scoring parameter `{name}`")
+                })?;
+                Ok((name.trim(), value))
+            })
+            .collect::<anyhow::Result<Vec<(&str, Score)>>>()?;
+
+        match algorithm.to_ascii_lowercase().as_str() {
+            "bm25" => {
+                let mut k1 = DEFAULT_BM25_K1;
+                let mut b = DEFAULT_BM25_B;
+
+                for (name, value) in params {
+                    match name {
+                        "k1" => k1 = value,
+                        "b" => b = value,
+                        _ => bail!("unknown BM25 parameter `{name}`: expected `k1` or `b`"),
+                    }
+                }
+                if !k1.is_finite() || k1 < 0.0 {
+                    bail!("BM25 parameter `k1` must be a positive number, got `{k1}`");
+                }
+                if !(0.0..=1.0).contains(&b) {
+                    bail!("BM25 parameter `b` must be between 0 and 1, got `{b}`");
+                }
+                Ok(Scoring::Bm25 { k1, b })
+            }
+            "tfidf" => {
+                if let Some((name, _)) = params.first() {
+                    bail!("unknown TF-IDF parameter `{name}`: TF-IDF takes no parameters");
+                }
+                Ok(Scoring::TfIdf)
+            }
+            _ => bail!("unknown scoring algorithm `{algorithm}`: expected `bm25` or `tfidf`"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scoring_from_str() {
+        assert_eq!(Scoring::from_str("bm25").unwrap(), Scoring::default());
+        assert_eq!(
+            Scoring::from_str("BM25(k1=2)").unwrap(),
+            Scoring::Bm25 { k1: 2.0, b: 0.75 }
+        );
+        assert_eq!(
+            Scoring::from_str("bm25(k1=0.5, b=0)").unwrap(),
+            Scoring::Bm25 { k1: 0.5, b: 0.0 }
+        );
+        assert_eq!(Scoring::from_str("tfidf").unwrap(), Scoring::TfIdf);
+        assert_eq!(Scoring::from_str("tfidf()").unwrap(), Scoring::TfIdf);
+
+        let scoring = Scoring::Bm25 { k1: 1.5, b: 0.3 };
+        assert_eq!(Scoring::from_str(&scoring.to_string()).unwrap(), scoring);
+    }
+
+    #[test]
+    fn test_scoring_from_str_rejects_invalid_scorings() {
+        for (scoring_str, expected_error) in [
+            ("dfr", "unknown scoring algorithm `dfr`"),
+            ("bm25(k1=1.2", "missing closing parenthesis"),
+            ("bm25(k2=1)", "unknown BM25 parameter `k2`"),
+            ("bm25(k1)", "expected `name=value`"),
+            ("bm25(k1=high)", "invalid value `high`"),
+            ("bm25(k1=-1)", "`k1` must be a positive number"),
+            ("bm25(k1=inf)", "`k1` must be a positive number"),
+            ("bm25(b=1.5)", "`b` must be between 0 and 1"),
+            ("tfidf(k1=1)", "TF-IDF takes no parameters"),
+        ] {
+            let error = Scoring::from_str(scoring_str).unwrap_err();
+            assert!(
+                error.to_string().contains(expected_error),
+                "unexpected error for `{scoring_str}`: {error}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_scoring_term_score() {
+        let bm25 = Scoring::default();
+        let idf = bm25.idf(2, 2);
+        // A short field containing the term once scores lower than a long field containing it
+        // four times.
+        let short_field_score = bm25.term_score(idf, 1, 2, 6.0);
+        let long_field_score = bm25.term_score(idf, 4, 10, 6.0);
+        assert!(short_field_score < long_field_score);
+
+        // Without saturation, the score no longer depends on the term frequency.
+        let bm25_without_saturation = Scoring::Bm25 { k1: 0.0, b: 0.75 };
+        assert_eq!(
+            bm25_without_saturation.term_score(idf, 1, 2, 6.0),
+            bm25_without_saturation.term_score(idf, 4, 10, 6.0)
+        );
+
+        let tfidf = Scoring::TfIdf;
+        let idf = tfidf.idf(2, 2);
+        assert!(tfidf.term_score(idf, 1, 2, 6.0) > tfidf.term_score(idf, 4, 10, 6.0));
+    }
+}
@@ -31,6 +31,83 @@ pub use self::unicode_segmenter_tokenizer::UnicodeSegmenterTokenizer;
 
 pub const DEFAULT_REMOVE_TOKEN_LENGTH: usize = 255;
 
+/// Description of a tokenizer registered by [`create_default_quickwit_tokenizer_manager`].
+#[derive(Clone, Copy, Debug)]
+pub struct BuiltinTokenizer {
+    /// Name the tokenizer is registered under.
+    pub name: &'static str,
+    /// Tokenizer splitting the text into tokens.
+    pub tokenizer: &'static str,
+    /// Token filters applied to the tokens, in order.
+    pub filters: &'static [&'static str],
+    pub description: &'static str,
+}
+
+/// Tokenizers registered by [`create_default_quickwit_tokenizer_manager`].
+pub const BUILTIN_TOKENIZERS: &[BuiltinTokenizer] = &[
+    BuiltinTokenizer {
+        name: "raw",
+        tokenizer: "raw",
+        filters: &["remove_long"],
+        description: "Indexes the whole text as a single token.",
+    },
+    BuiltinTokenizer {
+        name: "raw_lowercase",
+        tokenizer: "raw",
+        filters: &["lower_caser", "remove_long"],
+        description: "Indexes the whole lowercased text as a single token.",
+    },
+    BuiltinTokenizer {
+        name: "lowercase",
+        tokenizer: "raw",
+        filters: &["lower_caser", "remove_long"],
+        description: "Same as `raw_lowercase`.",
+    },
+    BuiltinTokenizer {
+        name: "default",
+        tokenizer: "simple",
+        filters: &["remove_long", "lower_caser"],
+        description: "Splits the text on non-alphanumeric characters and lowercases the tokens.",
+    },
+    BuiltinTokenizer {
+        name: "whitespace",
+        tokenizer: "whitespace",
+        filters: &[],
+        description: "Splits the text on whitespaces, without normalizing the tokens.",
+    },
+    BuiltinTokenizer {
+        name: "chinese_compatible",
+        tokenizer: "chinese_compatible",
+        filters: &["remove_long", "lower_caser"],
+        description: "Like `default`, but also splits Chinese text into one token per character.",
+    },
+    BuiltinTokenizer {
+        name: "source_code_default",
+        tokenizer: "source_code",
+        filters: &["remove_long", "lower_caser", "ascii_folding"],
+        description: "Splits source code on punctuation, case changes, and digits.",
+    },
+    BuiltinTokenizer {
+        name: "source_code_with_hex",
+        tokenizer: "source_code_with_hex",
+        filters: &["remove_long", "lower_caser", "ascii_folding"],
+        description: "Like `source_code_default`, but keeps groups of hexadecimal digits as one \
+                      token.",
+    },
+    BuiltinTokenizer {
+        name: "unicode_segmenter",
+        tokenizer: "unicode_segmenter",
+        filters: &["lower_caser", "remove_long"],
+        description: "Splits the text on Unicode word boundaries and lowercases the tokens.",
+    },
+    BuiltinTokenizer {
+        name: "datadog",
+        tokenizer: "unicode_segmenter",
+        filters: &["lower_caser", "remove_long"],
+        description: "Same as `unicode_segmenter`.",
+    },
+];
+
 /// Quickwit's tokenizer/analyzer manager.
 pub fn create_default_quickwit_tokenizer_manager() -> TokenizerManager {
     let tokenizer_manager = TokenizerManager::new();
@@ -118,6 +195,23 @@ pub fn get_quickwit_fastfield_normalizer_manager() -> &'static TokenizerManager
 #[cfg(test)]
 mod tests {
 
+    #[test]
+    fn test_builtin_tokenizers_are_registered() {
+        let tokenizer_manager = super::create_default_quickwit_tokenizer_manager();
+
+        for builtin_tokenizer in super::BUILTIN_TOKENIZERS {
+            let does_lowercasing = tokenizer_manager
+                .tokenizer_does_lowercasing(builtin_tokenizer.name)
+                .unwrap();
+            assert_eq!(
+                does_lowercasing,
+                builtin_tokenizer.filters.contains(&"lower_caser"),
+                "{}",
+                builtin_tokenizer.name
+            );
+        }
+    }
+
     #[test]
     fn test_tokenizers_in_manager() {
         let tokenizer_manager = super::create_default_quickwit_tokenizer_manager();
@@ -909,6 +909,8 @@ mod test {
             num_ingested_docs: Some(2),
             num_rejected_docs: Some(0),
             parse_failures: Some(Vec::new()),
+            num_deduplicated_docs: None,
+            num_filtered_docs: None,
         };
         Mock::given(method("POST"))
             .and(path("/api/v1/my-index/ingest"))
@@ -944,6 +946,8 @@ mod test {
             num_ingested_docs: Some(2),
             num_rejected_docs: Some(0),
             parse_failures: Some(Vec::new()),
+            num_deduplicated_docs: None,
+            num_filtered_docs: None,
         };
         Mock::given(method("POST"))
             .and(path("/api/v1/my-index/ingest"))
@@ -979,6 +983,8 @@ mod test {
             num_ingested_docs: Some(2),
             num_rejected_docs: Some(0),
             parse_failures: Some(Vec::new()),
+            num_deduplicated_docs: None,
+            num_filtered_docs: None,
         };
         Mock::given(method("POST"))
             .and(path("/api/v1/my-index/ingest"))
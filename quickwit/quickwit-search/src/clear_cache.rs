@@ -0,0 +1,169 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use futures::future::join_all;
+use quickwit_proto::metastore::MetastoreServiceClient;
+use quickwit_proto::search::{ClearCacheRequest, ClearCacheResponse, LeafClearCacheRequest};
+use quickwit_proto::types::IndexUid;
+use tracing::{instrument, warn};
+
+use crate::{ClusterClient, list_relevant_splits, resolve_index_patterns};
+
+/// Evicts the published splits of the targeted indexes from the caches of all the searchers.
+///
+/// The searchers failing to evict the splits are counted in the response instead of failing the
+/// request: clearing the caches is a best effort operation.
+#[instrument(skip_all, fields(index_id_patterns = ?clear_cache_request.index_id_patterns))]
+pub async fn root_clear_cache(
+    clear_cache_request: ClearCacheRequest,
+    metastore: &MetastoreServiceClient,
+    cluster_client: &ClusterClient,
+) -> crate::Result<ClearCacheResponse> {
+    let indexes_metadata =
+        resolve_index_patterns(&clear_cache_request.index_id_patterns, metastore).await?;
+    let index_uids: Vec<IndexUid> = indexes_metadata
+        .into_iter()
+        .map(|index_metadata| index_metadata.index_uid)
+        .collect();
+    let split_ids: Vec<String> = list_relevant_splits(index_uids, None, None, None, metastore)
+        .await?
+        .into_iter()
+        .map(|split_metadata| split_metadata.split_id.to_string())
+        .collect();
+    let searchers = cluster_client.search_job_placer.all_searchers();
+    let num_searchers = searchers.len() as u64;
+
+    let leaf_clear_cache_futs = searchers.into_iter().map(|mut searcher| {
+        let leaf_clear_cache_request = LeafClearCacheRequest {
+            split_ids: split_ids.clone(),
+        };
+        async move { searcher.leaf_clear_cache(leaf_clear_cache_request).await }
+    });
+    let mut num_failed_searchers = 0;
+
+    for leaf_clear_cache_res in join_all(leaf_clear_cache_futs).await {
+        if let Err(error) = leaf_clear_cache_res {
+            warn!(error=?error, "failed to clear the cache of a searcher");
+            num_failed_searchers += 1;
+        }
+    }
+    Ok(ClearCacheResponse {
+        num_searchers,
+        num_failed_searchers,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_common::ServiceStream;
+    use quickwit_indexing::MockSplitBuilder;
+    use quickwit_metastore::{IndexMetadata, ListSplitsResponseExt};
+    use quickwit_proto::metastore::{
+        ListIndexesMetadataResponse, ListSplitsResponse, MetastoreServiceClient,
+        MockMetastoreService,
+    };
+    use quickwit_proto::search::LeafClearCacheResponse;
+
+    use super::*;
+    use crate::{
+        MockSearchService, SearchError, SearchJobPlacer, SearcherPool, searcher_pool_for_test,
+    };
+
+    #[tokio::test]
+    async fn test_root_clear_cache() {
+        let mut mock_metastore = MockMetastoreService::new();
+        let index_metadata = IndexMetadata::for_test("test-index", "ram:///test-index");
+        let index_uid = index_metadata.index_uid.clone();
+        mock_metastore.expect_list_indexes_metadata().returning(
+            move |_list_indexes_metadata_request| {
+                Ok(ListIndexesMetadataResponse::for_test(vec![
+                    index_metadata.clone(),
+                ]))
+            },
+        );
+        mock_metastore
+            .expect_list_splits()
+            .returning(move |_list_splits_request| {
+                let splits = vec![
+                    MockSplitBuilder::new("split1")
+                        .with_index_uid(&index_uid)
+                        .build(),
+                    MockSplitBuilder::new("split2")
+                        .with_index_uid(&index_uid)
+                        .build(),
+                ];
+                let splits_response = ListSplitsResponse::try_from_splits(splits).unwrap();
+                Ok(ServiceStream::from(vec![Ok(splits_response)]))
+            });
+        let mut mock_search_service_1 = MockSearchService::new();
+        mock_search_service_1
+            .expect_leaf_clear_cache()
+            .times(1)
+            .returning(|leaf_clear_cache_request| {
+                assert_eq!(leaf_clear_cache_request.split_ids, ["split1", "split2"]);
+                Ok(LeafClearCacheResponse {
+                    num_evicted_splits: 1,
+                })
+            });
+        let mut mock_search_service_2 = MockSearchService::new();
+        mock_search_service_2
+            .expect_leaf_clear_cache()
+            .times(1)
+            .returning(|_leaf_clear_cache_request| {
+                Err(SearchError::Internal("searcher unavailable".to_string()))
+            });
+        let searcher_pool = searcher_pool_for_test([
+            ("127.0.0.1:1001", mock_search_service_1),
+            ("127.0.0.1:1002", mock_search_service_2),
+        ]);
+        let cluster_client = ClusterClient::new(SearchJobPlacer::new(searcher_pool));
+
+        let clear_cache_request = ClearCacheRequest {
+            index_id_patterns: vec!["test-index".to_string()],
+        };
+        let clear_cache_response = root_clear_cache(
+            clear_cache_request,
+            &MetastoreServiceClient::from_mock(mock_metastore),
+            &cluster_client,
+        )
+        .await
+        .unwrap();
+        assert_eq!(clear_cache_response.num_searchers, 2);
+        assert_eq!(clear_cache_response.num_failed_searchers, 1);
+    }
+
+    #[tokio::test]
+    async fn test_root_clear_cache_index_not_found() {
+        let mut mock_metastore = MockMetastoreService::new();
+        mock_metastore
+            .expect_list_indexes_metadata()
+            .returning(|_list_indexes_metadata_request| {
+                Ok(ListIndexesMetadataResponse::for_test(Vec::new()))
+            });
+        mock_metastore.expect_list_splits().never();
+        let cluster_client = ClusterClient::new(SearchJobPlacer::new(SearcherPool::default()));
+
+        let clear_cache_request = ClearCacheRequest {
+            index_id_patterns: vec!["test-index".to_string()],
+        };
+        let error = root_clear_cache(
+            clear_cache_request,
+            &MetastoreServiceClient::from_mock(mock_metastore),
+            &cluster_client,
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(error, SearchError::IndexesNotFound { .. }));
+    }
+}
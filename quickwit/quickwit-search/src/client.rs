@@ -20,10 +20,13 @@ use std::time::Duration;
 use bytesize::ByteSize;
 use futures::future::{BoxFuture, FutureExt, Shared};
 use http::Uri;
+use prost::Message;
 use quickwit_common::tracing_utils::SpanContextInterceptor;
+use quickwit_config::SearchRequestCompressionConfig;
 use quickwit_proto::search::{GetKvRequest, PutKvRequest, ReportSplitsRequest};
 use quickwit_proto::tonic;
 use quickwit_proto::tonic::Request;
+use quickwit_proto::tonic::codec::CompressionEncoding;
 use quickwit_proto::tonic::codegen::InterceptedService;
 use quickwit_proto::tonic::transport::{Channel, Endpoint};
 use tokio::time::Instant;
@@ -116,13 +119,13 @@ impl LoadCache {
 #[derive(Clone)]
 enum SearchServiceClientImpl {
     Local(Arc<dyn SearchService>),
-    Grpc(
-        quickwit_proto::search::search_service_client::SearchServiceClient<
-            InterceptedService<Timeout<Channel>, SpanContextInterceptor>,
-        >,
-    ),
+    Grpc(GrpcSearchServiceClient),
 }
 
+type GrpcSearchServiceClient = quickwit_proto::search::search_service_client::SearchServiceClient<
+    InterceptedService<Timeout<Channel>, SpanContextInterceptor>,
+>;
+
 /// A search service client.
 /// It contains the client implementation and the gRPC address of the node to which the client
 /// connects.
@@ -131,6 +134,8 @@ pub struct SearchServiceClient {
     client_impl: SearchServiceClientImpl,
     grpc_addr: SocketAddr,
     load_cache: Arc<LoadCache>,
+    /// Compresses the large leaf search and fetch docs requests sent over gRPC.
+    request_compression_opt: Option<SearchRequestCompressionConfig>,
     /// In test/testsuite builds, overrides the load returned by `get_load()` for local clients,
     /// so that tests using mock services don't need to set up `get_load` expectations.
     #[cfg(any(test, feature = "testsuite"))]
@@ -162,6 +167,7 @@ impl SearchServiceClient {
             client_impl: SearchServiceClientImpl::Grpc(client),
             grpc_addr,
             load_cache: Arc::new(LoadCache::new()),
+            request_compression_opt: None,
             #[cfg(any(test, feature = "testsuite"))]
             test_load: None,
         }
@@ -173,11 +179,21 @@ impl SearchServiceClient {
             client_impl: SearchServiceClientImpl::Local(service),
             grpc_addr,
             load_cache: Arc::new(LoadCache::new()),
+            request_compression_opt: None,
             #[cfg(any(test, feature = "testsuite"))]
             test_load: None,
         }
     }
 
+    /// Sets the compression of the large requests sent over gRPC. The local clients ignore it.
+    pub fn with_request_compression(
+        mut self,
+        request_compression_opt: Option<SearchRequestCompressionConfig>,
+    ) -> Self {
+        self.request_compression_opt = request_compression_opt;
+        self
+    }
+
     /// Sets the load to return from `get_load()` for this client in test/testsuite builds.
     ///
     /// This short-circuits the call to the underlying service so that mock services
@@ -199,6 +215,17 @@ impl SearchServiceClient {
         matches!(self.client_impl, SearchServiceClientImpl::Local(_))
     }
 
+    /// Returns the encoding to compress the request with, if its encoded size reaches the
+    /// configured threshold.
+    fn compression_encoding_for(&self, request: &impl Message) -> Option<CompressionEncoding> {
+        let request_compression = self.request_compression_opt.as_ref()?;
+
+        if (request.encoded_len() as u64) < request_compression.min_request_size.as_u64() {
+            return None;
+        }
+        Some(request_compression.compression_encoding())
+    }
+
     /// Perform root search.
     pub async fn root_search(
         &mut self,
@@ -219,12 +246,16 @@ impl SearchServiceClient {
         &mut self,
         request: quickwit_proto::search::LeafSearchRequest,
     ) -> crate::Result<quickwit_proto::search::LeafSearchResponse> {
+        let compression_encoding_opt = self.compression_encoding_for(&request);
+
         match &mut self.client_impl {
-            SearchServiceClientImpl::Grpc(grpc_client) => grpc_client
-                .leaf_search(request)
-                .await
-                .map(|tonic_response| tonic_response.into_inner())
-                .map_err(|tonic_error| parse_grpc_error(&tonic_error)),
+            SearchServiceClientImpl::Grpc(grpc_client) => {
+                with_compression(grpc_client, compression_encoding_opt)
+                    .leaf_search(request)
+                    .await
+                    .map(|tonic_response| tonic_response.into_inner())
+                    .map_err(|tonic_error| parse_grpc_error(&tonic_error))
+            }
             SearchServiceClientImpl::Local(service) => service.leaf_search(request).await,
         }
     }
@@ -247,15 +278,35 @@ impl SearchServiceClient {
         }
     }
 
+    /// Evicts splits from the caches of the searcher.
+    pub async fn leaf_clear_cache(
+        &mut self,
+        request: quickwit_proto::search::LeafClearCacheRequest,
+    ) -> crate::Result<quickwit_proto::search::LeafClearCacheResponse> {
+        match &mut self.client_impl {
+            SearchServiceClientImpl::Grpc(grpc_client) => {
+                let tonic_request = Request::new(request);
+                let tonic_response = grpc_client
+                    .leaf_clear_cache(tonic_request)
+                    .await
+                    .map_err(|tonic_error| parse_grpc_error(&tonic_error))?;
+                Ok(tonic_response.into_inner())
+            }
+            SearchServiceClientImpl::Local(service) => service.leaf_clear_cache(request).await,
+        }
+    }
+
     /// Perform fetch docs.
     pub async fn fetch_docs(
         &mut self,
         request: quickwit_proto::search::FetchDocsRequest,
     ) -> crate::Result<quickwit_proto::search::FetchDocsResponse> {
+        let compression_encoding_opt = self.compression_encoding_for(&request);
+
         match &mut self.client_impl {
             SearchServiceClientImpl::Grpc(grpc_client) => {
                 let tonic_request = Request::new(request);
-                let tonic_response = grpc_client
+                let tonic_response = with_compression(grpc_client, compression_encoding_opt)
                     .fetch_docs(tonic_request)
                     .await
                     .map_err(|tonic_error| parse_grpc_error(&tonic_error))?;
@@ -381,6 +432,17 @@ async fn fetch_load_uncached(mut client_impl: SearchServiceClientImpl) -> crate:
     }
 }
 
+/// Returns a client compressing its requests with the given encoding, if any.
+fn with_compression(
+    grpc_client: &GrpcSearchServiceClient,
+    compression_encoding_opt: Option<CompressionEncoding>,
+) -> GrpcSearchServiceClient {
+    match compression_encoding_opt {
+        Some(compression_encoding) => grpc_client.clone().send_compressed(compression_encoding),
+        None => grpc_client.clone(),
+    }
+}
+
 /// Creates a [`SearchServiceClient`] from a socket address.
 /// The underlying channel connects lazily and is set up to time out after 5 seconds. It reconnects
 /// automatically should the connection be dropped.
@@ -493,6 +493,7 @@ mod tests {
                         error: "mock_error".to_string(),
                         split_id: "split_2".to_string(),
                         retryable_error: true,
+                        timed_out: false,
                     }],
                     num_attempted_splits: 1,
                     ..Default::default()
@@ -509,6 +510,7 @@ mod tests {
                         error: "mock_error".to_string(),
                         split_id: "split_3".to_string(),
                         retryable_error: true,
+                        timed_out: false,
                     }],
                     num_attempted_splits: 1,
                     ..Default::default()
@@ -536,6 +538,7 @@ mod tests {
             error: "error".to_string(),
             split_id: "split_2".to_string(),
             retryable_error: true,
+            timed_out: false,
         };
         let leaf_response = LeafSearchResponse {
             num_hits: 1,
@@ -569,6 +572,7 @@ mod tests {
             error: "error".to_string(),
             split_id: "split_2".to_string(),
             retryable_error: true,
+            timed_out: false,
         };
         let leaf_response = LeafSearchResponse {
             num_hits: 1,
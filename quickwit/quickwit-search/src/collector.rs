@@ -20,8 +20,8 @@ use itertools::Itertools;
 use quickwit_common::binary_heap::{SortKeyMapper, TopK};
 use quickwit_doc_mapper::{FastFieldWarmupInfo, WarmupInfo};
 use quickwit_proto::search::{
-    LeafResourceStats, LeafSearchResponse, PartialHit, SearchRequest, SortByValue, SortOrder,
-    SortValue, SplitSearchError,
+    LeafResourceStats, LeafSearchResponse, PartialHit, SearchRequest, SortByValue, SortField,
+    SortOrder, SortValue, SplitSearchError,
 };
 use quickwit_proto::types::SplitId;
 use serde::Deserialize;
@@ -35,8 +35,9 @@ use tantivy::tokenizer::TokenizerManager;
 use tantivy::{DateTime, DocId, Score, SegmentOrdinal, SegmentReader, TantivyError};
 
 use crate::find_trace_ids_collector::{FindTraceIdsCollector, FindTraceIdsSegmentCollector, Span};
+use crate::sort_script::{SORT_SCRIPT_FIELD_NAME, SegmentSortScript, SortScript};
 use crate::top_k_collector::{QuickwitSegmentTopKCollector, specialized_top_k_segment_collector};
-use crate::{GlobalDocAddress, add_leaf_stats, merge_leaf_stats_it};
+use crate::{GlobalDocAddress, SearchError, add_leaf_stats, merge_leaf_stats_it};
 
 #[derive(Clone, Debug)]
 pub(crate) enum SortByComponent {
@@ -50,6 +51,10 @@ pub(crate) enum SortByComponent {
     Score {
         order: SortOrder,
     },
+    Script {
+        script: String,
+        order: SortOrder,
+    },
 }
 impl From<SortByComponent> for SortByPair {
     fn from(value: SortByComponent) -> Self {
@@ -98,6 +103,12 @@ impl SortByComponent {
                 })
             }
             SortByComponent::Score { .. } => Ok(SortingFieldExtractorComponent::Score),
+            SortByComponent::Script { script, .. } => {
+                let sort_script = SortScript::parse(script)
+                    .map_err(|error| TantivyError::InvalidArgument(error.to_string()))?;
+                let segment_sort_script = sort_script.for_segment(segment_reader)?;
+                Ok(SortingFieldExtractorComponent::Script(segment_sort_script))
+            }
         }
     }
     pub fn requires_scoring(&self) -> bool {
@@ -105,15 +116,24 @@ impl SortByComponent {
             SortByComponent::DocId { .. } => false,
             SortByComponent::FastField { .. } => false,
             SortByComponent::Score { .. } => true,
+            SortByComponent::Script { .. } => false,
         }
     }
     pub fn add_fast_field(&self, set: &mut HashSet<String>) {
-        if let SortByComponent::FastField {
-            field_name,
-            order: _,
-        } = self
-        {
-            set.insert(field_name.clone());
+        match self {
+            SortByComponent::FastField {
+                field_name,
+                order: _,
+            } => {
+                set.insert(field_name.clone());
+            }
+            SortByComponent::Script { script, order: _ } => {
+                // An invalid script fails the search when the collector is built.
+                if let Ok(sort_script) = SortScript::parse(script) {
+                    set.extend(sort_script.field_names().iter().cloned());
+                }
+            }
+            SortByComponent::DocId { .. } | SortByComponent::Score { .. } => {}
         }
     }
     pub fn sort_order(&self) -> SortOrder {
@@ -121,6 +141,7 @@ impl SortByComponent {
             SortByComponent::DocId { order } => *order,
             SortByComponent::FastField { order, .. } => *order,
             SortByComponent::Score { order } => *order,
+            SortByComponent::Script { order, .. } => *order,
         }
     }
 }
@@ -144,23 +165,41 @@ pub(crate) enum SortingFieldExtractorComponent {
         sort_field_type: SortFieldType,
     },
     Score,
+    /// The value of a sort script, computed from fast fields.
+    Script(SegmentSortScript),
 }
 
 impl SortingFieldExtractorComponent {
     pub fn is_score(&self) -> bool {
         matches!(self, SortingFieldExtractorComponent::Score)
     }
+    /// Returns true if the sort values are read from fast fields, either directly or through a
+    /// sort script.
     pub fn is_fast_field(&self) -> bool {
-        matches!(self, SortingFieldExtractorComponent::FastField { .. })
+        matches!(
+            self,
+            SortingFieldExtractorComponent::FastField { .. }
+                | SortingFieldExtractorComponent::Script(_)
+        )
     }
     /// Loads the fast field values for the given doc_ids in its u64 representation. The returned
     /// u64 representation maintains the ordering of the original value.
     #[inline]
     pub fn extract_typed_sort_values_block(&self, doc_ids: &[DocId], values: &mut [Option<u64>]) {
         // In the collect block case we don't have scores to extract
-        if let SortingFieldExtractorComponent::FastField { sort_column, .. } = self {
-            let values = &mut values[..doc_ids.len()];
-            sort_column.first_vals(doc_ids, values);
+        match self {
+            SortingFieldExtractorComponent::FastField { sort_column, .. } => {
+                let values = &mut values[..doc_ids.len()];
+                sort_column.first_vals(doc_ids, values);
+            }
+            SortingFieldExtractorComponent::Script(segment_sort_script) => {
+                for (doc_id, value) in doc_ids.iter().zip(values.iter_mut()) {
+                    *value = segment_sort_script
+                        .eval(*doc_id)
+                        .map(|script_value| script_value.to_u64());
+                }
+            }
+            SortingFieldExtractorComponent::DocId | SortingFieldExtractorComponent::Score => {}
         }
     }
 
@@ -178,6 +217,9 @@ impl SortingFieldExtractorComponent {
                 sort_column.first(doc_id)
             }
             SortingFieldExtractorComponent::Score => Some((score as f64).to_u64()),
+            SortingFieldExtractorComponent::Script(segment_sort_script) => segment_sort_script
+                .eval(doc_id)
+                .map(|script_value| script_value.to_u64()),
         }
     }
 
@@ -200,7 +242,9 @@ impl SortingFieldExtractorComponent {
             SortingFieldExtractorComponent::FastField {
                 sort_field_type, ..
             } => map_fast_field_to_value(sort_value, *sort_field_type),
-            SortingFieldExtractorComponent::Score => SortValue::F64(f64::from_u64(sort_value)),
+            SortingFieldExtractorComponent::Score | SortingFieldExtractorComponent::Script(_) => {
+                SortValue::F64(f64::from_u64(sort_value))
+            }
         }
     }
     /// Converts fast field values into their u64 fast field representation.
@@ -368,6 +412,16 @@ impl SortingFieldExtractorComponent {
                 SortValue::F64(val) => Some(val.to_u64()),
                 _ => panic!("Internal error: Got non-F64 sort value for Score."),
             },
+            // Script values are f64, the `search_after` value is converted accordingly.
+            SortingFieldExtractorComponent::Script(_) => {
+                let val = match sort_value {
+                    SortValue::U64(val) => val as f64,
+                    SortValue::I64(val) => val as f64,
+                    SortValue::F64(val) => val,
+                    SortValue::Boolean(val) => val as u64 as f64,
+                };
+                Some(val.to_u64())
+            }
         }
     }
 }
@@ -476,6 +530,7 @@ pub struct QuickwitSegmentCollector {
     segment_top_k_collector: Option<Box<dyn QuickwitSegmentTopKCollector>>,
     aggregation: Option<AggregationSegmentCollectors>,
     num_hits: u64,
+    min_score: Option<Score>,
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -545,6 +600,11 @@ impl SegmentCollector for QuickwitSegmentCollector {
 
     #[inline]
     fn collect(&mut self, doc_id: DocId, score: Score) {
+        if let Some(min_score) = self.min_score
+            && score < min_score
+        {
+            return;
+        }
         self.num_hits += 1;
         if let Some(segment_top_k_collector) = self.segment_top_k_collector.as_mut() {
             segment_top_k_collector.collect_top_k(doc_id, score);
@@ -717,11 +777,13 @@ pub(crate) struct QuickwitCollector {
     pub aggregation: Option<QuickwitAggregations>,
     pub agg_context_params: AggContextParams,
     search_after: Option<PartialHit>,
+    /// Hits scoring below this threshold are not collected.
+    min_score: Option<Score>,
 }
 
 impl QuickwitCollector {
     pub fn is_count_only(&self) -> bool {
-        self.max_hits == 0 && self.aggregation.is_none()
+        self.max_hits == 0 && self.aggregation.is_none() && self.min_score.is_none()
     }
     /// Updates search parameters affecting the returned documents.
     /// Does not update aggregations.
@@ -813,6 +875,7 @@ impl Collector for QuickwitCollector {
             num_hits: 0,
             segment_top_k_collector,
             aggregation,
+            min_score: self.min_score,
         })
     }
 
@@ -820,7 +883,8 @@ impl Collector for QuickwitCollector {
         // We do not need BM25 scoring in Quickwit if it is not opted-in.
         // By returning false, we inform tantivy that it does not need to decompress
         // term frequencies.
-        self.sort_by.first.requires_scoring()
+        self.min_score.is_some()
+            || self.sort_by.first.requires_scoring()
             || self
                 .sort_by
                 .second
@@ -992,11 +1056,17 @@ fn top_k_partial_hits(
 }
 
 pub(crate) fn sort_by_from_request(search_request: &SearchRequest) -> SortByPair {
-    let to_sort_by_component = |field_name: &str, order| {
+    let to_sort_by_component = |sort_field: &SortField, order| {
+        let field_name = sort_field.field_name.as_str();
         if field_name == "_score" {
             SortByComponent::Score { order }
         } else if field_name == "_shard_doc" || field_name == "_doc" {
             SortByComponent::DocId { order }
+        } else if field_name == SORT_SCRIPT_FIELD_NAME {
+            SortByComponent::Script {
+                script: sort_field.script.clone().unwrap_or_default(),
+                order,
+            }
         } else {
             SortByComponent::FastField {
                 field_name: field_name.to_string(),
@@ -1014,15 +1084,15 @@ pub(crate) fn sort_by_from_request(search_request: &SearchRequest) -> SortByPair
     } else if num_sort_fields == 1 {
         let sort_field = &search_request.sort_fields[0];
         let order = SortOrder::try_from(sort_field.sort_order).unwrap_or(SortOrder::Desc);
-        to_sort_by_component(&sort_field.field_name, order).into()
+        to_sort_by_component(sort_field, order).into()
     } else if num_sort_fields == 2 {
         let sort_field1 = &search_request.sort_fields[0];
         let order1 = SortOrder::try_from(sort_field1.sort_order).unwrap_or(SortOrder::Desc);
         let sort_field2 = &search_request.sort_fields[1];
         let order2 = SortOrder::try_from(sort_field2.sort_order).unwrap_or(SortOrder::Desc);
         SortByPair {
-            first: to_sort_by_component(&sort_field1.field_name, order1),
-            second: Some(to_sort_by_component(&sort_field2.field_name, order2)),
+            first: to_sort_by_component(sort_field1, order1),
+            second: Some(to_sort_by_component(sort_field2, order2)),
         }
     } else {
         panic!("Sort by more than 2 fields is not supported yet.")
@@ -1048,6 +1118,9 @@ pub(crate) fn make_collector_for_split(
         aggregation,
         agg_context_params,
         search_after: search_request.search_after.clone(),
+        min_score: search_request
+            .min_score
+            .map(|score_threshold| score_threshold.value),
     })
 }
 
@@ -1076,6 +1149,9 @@ pub(crate) fn make_merge_collector(
         aggregation,
         agg_context_params,
         search_after: search_request.search_after.clone(),
+        min_score: search_request
+            .min_score
+            .map(|score_threshold| score_threshold.value),
     })
 }
 
@@ -1201,6 +1277,9 @@ pub(crate) struct IncrementalCollector {
     num_successful_splits: u64,
     start_offset: usize,
     resource_stats: Option<LeafResourceStats>,
+    /// Error of a split search exceeding the aggregation limits. Searching the split again would
+    /// fail again, so it fails the whole search instead of being reported as a failed split.
+    aggregation_limit_error_opt: Option<SearchError>,
 }
 
 impl IncrementalCollector {
@@ -1222,6 +1301,7 @@ impl IncrementalCollector {
             num_attempted_splits: 0,
             num_successful_splits: 0,
             resource_stats: None,
+            aggregation_limit_error_opt: None,
         }
     }
 
@@ -1261,6 +1341,21 @@ impl IncrementalCollector {
         self.failed_splits.push(split_error)
     }
 
+    /// Add the error of a split search to the state. Errors caused by the aggregation limits fail
+    /// the whole search, the other ones are reported as failed splits.
+    pub(crate) fn add_split_search_error(&mut self, split_id: String, search_error: SearchError) {
+        if let SearchError::InvalidAggregationRequest(_) = search_error {
+            self.aggregation_limit_error_opt.get_or_insert(search_error);
+            return;
+        }
+        self.add_failed_split(SplitSearchError {
+            split_id,
+            error: format!("{search_error}"),
+            retryable_error: true,
+            timed_out: false,
+        });
+    }
+
     /// Add the lambda dispatch totals to the state.
     ///
     /// `num_splits` is the total number of splits offloaded to lambda
@@ -1294,7 +1389,10 @@ impl IncrementalCollector {
     }
 
     /// Finalize the merge, creating a LeafSearchResponse.
-    pub(crate) fn finalize(self) -> tantivy::Result<LeafSearchResponse> {
+    pub(crate) fn finalize(self) -> crate::Result<LeafSearchResponse> {
+        if let Some(aggregation_limit_error) = self.aggregation_limit_error_opt {
+            return Err(aggregation_limit_error);
+        }
         let intermediate_aggregation_result = self.incremental_aggregation.finalize()?;
         let mut partial_hits = self.top_k_hits.finalize();
         if self.start_offset != 0 {
@@ -1425,12 +1523,14 @@ mod tests {
                             field_name: field.to_string(),
                             sort_order: SortOrder::Asc.into(),
                             sort_datetime_format: None,
+                            script: None,
                         }
                     } else {
                         SortField {
                             field_name: field.to_string(),
                             sort_order: SortOrder::Desc.into(),
                             sort_datetime_format: None,
+                            script: None,
                         }
                     }
                 })
@@ -1680,11 +1780,13 @@ mod tests {
                         field_name: "sort1".to_string(),
                         sort_order: SortOrder::Desc.into(),
                         sort_datetime_format: None,
+                        script: None,
                     },
                     SortField {
                         field_name: "sort2".to_string(),
                         sort_order: SortOrder::Asc.into(),
                         sort_datetime_format: None,
+                        script: None,
                     },
                 ],
                 search_after: Some(search_after),
@@ -1723,6 +1825,7 @@ mod tests {
                     field_name: "_shard_doc".to_string(),
                     sort_order: SortOrder::Desc.into(),
                     sort_datetime_format: None,
+                    script: None,
                 }],
                 search_after: Some(search_after),
                 ..SearchRequest::default()
@@ -1801,6 +1904,7 @@ mod tests {
                     field_name: "timestamp".to_string(),
                     sort_order: SortOrder::Desc as i32,
                     sort_datetime_format: None,
+                    script: None,
                 }],
                 aggregation_request: None,
                 ..Default::default()
@@ -1849,6 +1953,7 @@ mod tests {
                     field_name: "timestamp".to_string(),
                     sort_order: SortOrder::Desc as i32,
                     sort_datetime_format: None,
+                    script: None,
                 }],
                 aggregation_request: None,
                 ..Default::default()
@@ -1891,6 +1996,7 @@ mod tests {
                         error: "fake error".to_string(),
                         split_id: "3".to_string(),
                         retryable_error: true,
+                        timed_out: false,
                     }],
                     num_attempted_splits: 2,
                     num_successful_splits: 1,
@@ -1924,6 +2030,7 @@ mod tests {
                     error: "fake error".to_string(),
                     split_id: "3".to_string(),
                     retryable_error: true,
+                    timed_out: false,
                 }],
                 num_attempted_splits: 5,
                 num_successful_splits: 4,
@@ -1941,6 +2048,7 @@ mod tests {
                     field_name: "timestamp".to_string(),
                     sort_order: SortOrder::Asc as i32,
                     sort_datetime_format: None,
+                    script: None,
                 }],
                 aggregation_request: None,
                 ..Default::default()
@@ -1994,6 +2102,7 @@ mod tests {
                         error: "fake error".to_string(),
                         split_id: "3".to_string(),
                         retryable_error: true,
+                        timed_out: false,
                     }],
                     num_attempted_splits: 2,
                     num_successful_splits: 1,
@@ -2038,6 +2147,7 @@ mod tests {
                     error: "fake error".to_string(),
                     split_id: "3".to_string(),
                     retryable_error: true,
+                    timed_out: false,
                 }],
                 num_attempted_splits: 5,
                 num_successful_splits: 4,
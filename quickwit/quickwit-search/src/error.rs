@@ -23,6 +23,7 @@ use quickwit_proto::{GrpcServiceError, ServiceError, ServiceErrorCode, tonic};
 use quickwit_storage::StorageResolverError;
 use serde::{Deserialize, Serialize};
 use tantivy::TantivyError;
+use tantivy::aggregation::AggregationError;
 use thiserror::Error;
 use tokio::task::JoinError;
 
@@ -31,6 +32,8 @@ use tokio::task::JoinError;
 #[derive(Error, Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "snake_case")]
 pub enum SearchError {
+    #[error("forbidden: {0}")]
+    Forbidden(String),
     #[error("could not find indexes matching the IDs `{index_ids:?}`")]
     IndexesNotFound { index_ids: Vec<String> },
     #[error("internal error: `{0}`")]
@@ -71,6 +74,7 @@ impl SearchError {
 impl ServiceError for SearchError {
     fn error_code(&self) -> ServiceErrorCode {
         match self {
+            Self::Forbidden(_) => ServiceErrorCode::Forbidden,
             Self::IndexesNotFound { .. } => ServiceErrorCode::NotFound,
             Self::Internal(error_msg) => {
                 rate_limited_error!(limit_per_min = 6, "search internal error: {error_msg}");
@@ -126,6 +130,15 @@ pub fn parse_grpc_error(grpc_error: &tonic::Status) -> SearchError {
 
 impl From<TantivyError> for SearchError {
     fn from(tantivy_error: TantivyError) -> Self {
+        if let TantivyError::AggregationError(AggregationError::BucketLimitExceeded {
+            limit,
+            current,
+        }) = tantivy_error
+        {
+            return SearchError::InvalidAggregationRequest(format!(
+                "aggregation created {current} buckets, exceeding the maximum of {limit} buckets"
+            ));
+        }
         SearchError::Internal(format!("tantivy error: {tantivy_error}"))
     }
 }
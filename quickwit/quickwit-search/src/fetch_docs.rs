@@ -20,16 +20,17 @@ use futures::{StreamExt, TryFutureExt, TryStreamExt};
 use itertools::Itertools;
 use quickwit_doc_mapper::DocMapper;
 use quickwit_proto::search::{
-    FetchDocsResponse, PartialHit, SnippetRequest, SplitIdAndFooterOffsets,
+    FetchDocsResponse, HitScores, PartialHit, ScoreRequest, SnippetRequest, SplitIdAndFooterOffsets,
 };
-use quickwit_storage::Storage;
+use quickwit_storage::{ByteRangeCache, Storage};
 use tantivy::query::Query;
 use tantivy::schema::document::CompactDocValue;
 use tantivy::schema::{Document as DocumentTrait, Field, TantivyDocument, Value};
 use tantivy::snippet::SnippetGenerator;
-use tantivy::{ReloadPolicy, Score, Searcher, Term};
+use tantivy::{DocAddress, ReloadPolicy, Score, Searcher, Term};
 use tracing::{Instrument, error};
 
+use crate::hit_scorer::HitScorer;
 use crate::leaf::open_index_with_caches;
 use crate::service::SearcherContext;
 use crate::{GlobalDocAddress, convert_document_to_json_string};
@@ -45,6 +46,7 @@ async fn fetch_docs_to_map(
     splits: &[SplitIdAndFooterOffsets],
     doc_mapper: Arc<DocMapper>,
     snippet_request_opt: Option<&SnippetRequest>,
+    score_request_opt: Option<&ScoreRequest>,
 ) -> anyhow::Result<HashMap<GlobalDocAddress, Document>> {
     let mut split_fetch_docs_futures = Vec::new();
 
@@ -74,6 +76,7 @@ async fn fetch_docs_to_map(
                 split_and_offset,
                 doc_mapper.clone(),
                 snippet_request_opt,
+                score_request_opt,
             )
             .map_err(move |e| e.context(format!("split_id={split_id}"))),
         );
@@ -107,6 +110,7 @@ pub async fn fetch_docs(
     splits: &[SplitIdAndFooterOffsets],
     doc_mapper: Arc<DocMapper>,
     snippet_request_opt: Option<&SnippetRequest>,
+    score_request_opt: Option<&ScoreRequest>,
 ) -> anyhow::Result<FetchDocsResponse> {
     let global_doc_addrs: Vec<GlobalDocAddress> = partial_hits
         .iter()
@@ -120,6 +124,7 @@ pub async fn fetch_docs(
         splits,
         doc_mapper,
         snippet_request_opt,
+        score_request_opt,
     )
     .await?;
 
@@ -133,6 +138,7 @@ pub async fn fetch_docs(
                     leaf_json: document.content_json,
                     partial_hit: Some(partial_hit),
                     leaf_snippet_json: document.snippet_json,
+                    scores: document.scores_opt,
                 })
             } else {
                 None
@@ -145,11 +151,12 @@ pub async fn fetch_docs(
 // number of concurrent fetch allowed for a single split.
 const NUM_CONCURRENT_REQUESTS: usize = 30;
 
-/// A struct for holding a fetched document's content and snippet.
+/// A struct for holding a fetched document's content, snippet, and scores.
 #[derive(Debug)]
 struct Document {
     content_json: String,
     snippet_json: Option<String>,
+    scores_opt: Option<HitScores>,
 }
 
 /// Fetching docs from a specific split.
@@ -160,16 +167,21 @@ async fn fetch_docs_in_split(
     split: &SplitIdAndFooterOffsets,
     doc_mapper: Arc<DocMapper>,
     snippet_request_opt: Option<&SnippetRequest>,
+    score_request_opt: Option<&ScoreRequest>,
 ) -> anyhow::Result<Vec<(GlobalDocAddress, Document)>> {
     global_doc_addrs.sort_by_key(|doc| doc.doc_addr);
     // Opens the index without the ephemeral unbounded cache, this cache is indeed not useful
-    // when fetching docs as we will fetch them only once.
+    // when fetching docs as we will fetch them only once. Scoring the hits, however, requires the
+    // warmed up data to be kept in memory.
+    let ephemeral_unbounded_cache_opt = score_request_opt.map(|_| {
+        ByteRangeCache::with_infinite_capacity(&quickwit_storage::metrics::SHORTLIVED_CACHE)
+    });
     let (mut index, _) = open_index_with_caches(
         &searcher_context,
         index_storage,
         split,
         Some(doc_mapper.tokenizer_manager()),
-        None,
+        ephemeral_unbounded_cache_opt,
     )
     .await
     .context("open-index-for-split")?;
@@ -189,6 +201,21 @@ async fn fetch_docs_in_split(
     } else {
         None
     };
+    let mut hit_scores: HashMap<DocAddress, HitScores> =
+        if let Some(score_request) = score_request_opt {
+            let hit_scorer = HitScorer::create(&searcher, &doc_mapper, score_request).await?;
+            let doc_addrs: Vec<DocAddress> = global_doc_addrs
+                .iter()
+                .map(|global_doc_addr| global_doc_addr.doc_addr)
+                .collect();
+            let searcher_clone = searcher.clone();
+            crate::search_thread_pool()
+                .run_cpu_intensive(move || hit_scorer.score_hits(&searcher_clone, &doc_addrs))
+                .await
+                .context("failed to score the hits: thread panicked")??
+        } else {
+            HashMap::new()
+        };
 
     let doc_futures = global_doc_addrs.into_iter().map(|global_doc_addr| {
         let moved_searcher = searcher.clone();
@@ -208,6 +235,7 @@ async fn fetch_docs_in_split(
                     Document {
                         content_json,
                         snippet_json: None,
+                        scores_opt: None,
                     },
                 ));
             }
@@ -219,6 +247,7 @@ async fn fetch_docs_in_split(
                     Document {
                         content_json,
                         snippet_json: None,
+                        scores_opt: None,
                     },
                 ));
             }
@@ -238,16 +267,22 @@ async fn fetch_docs_in_split(
                 Document {
                     content_json,
                     snippet_json: Some(snippet_json),
+                    scores_opt: None,
                 },
             ))
         }
         .in_current_span()
     });
 
-    futures::stream::iter(doc_futures)
+    let mut fetched_docs = futures::stream::iter(doc_futures)
         .buffer_unordered(NUM_CONCURRENT_REQUESTS)
         .try_collect::<Vec<_>>()
-        .await
+        .await?;
+
+    for (global_doc_addr, document) in &mut fetched_docs {
+        document.scores_opt = hit_scores.remove(&global_doc_addr.doc_addr);
+    }
+    Ok(fetched_docs)
 }
 
 // A struct to hold the snippet generators associated to
@@ -297,7 +332,7 @@ async fn create_fields_snippet_generator(
     let schema = searcher.schema();
     let query_ast_resolved = serde_json::from_str(&snippet_request.query_ast_resolved)
         .context("failed to deserialize QueryAst")?;
-    let (query, _) = doc_mapper.query(schema.clone(), query_ast_resolved, false, None)?;
+    let (query, _) = doc_mapper.query(schema.clone(), query_ast_resolved, false, None, None)?;
     let mut snippet_generators = HashMap::new();
     for field_name in &snippet_request.snippet_fields {
         let field = schema.get_field(field_name)?;
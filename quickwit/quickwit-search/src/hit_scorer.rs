@@ -0,0 +1,95 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+
+use anyhow::Context;
+use quickwit_doc_mapper::DocMapper;
+use quickwit_proto::search::{HitScores, ScoreRequest};
+use quickwit_query::query_ast::QueryAst;
+use tantivy::index::SegmentId;
+use tantivy::query::{EnableScoring, Weight};
+use tantivy::{DocAddress, Searcher, Term};
+
+use crate::leaf::warmup;
+use crate::root::parse_scoring;
+
+/// Computes the scores of the fetched hits of a split, with and without the boosts of the query,
+/// when the search request sets `explain_scores` or `track_scores`.
+pub(crate) struct HitScorer {
+    weight: Box<dyn Weight>,
+    raw_weight: Box<dyn Weight>,
+}
+
+impl HitScorer {
+    /// Builds the scorer and warms up the data required to score the hits.
+    pub async fn create(
+        searcher: &Searcher,
+        doc_mapper: &DocMapper,
+        score_request: &ScoreRequest,
+    ) -> anyhow::Result<Self> {
+        let schema = searcher.schema();
+        let query_ast: QueryAst = serde_json::from_str(&score_request.query_ast_resolved)
+            .context("failed to deserialize QueryAst")?;
+        let raw_query_ast = query_ast.clone().remove_boosts();
+        let scoring_opt = parse_scoring(score_request.scoring.as_deref())?;
+        let (query, mut warmup_info) =
+            doc_mapper.query(schema.clone(), query_ast, false, None, scoring_opt)?;
+        let (raw_query, raw_warmup_info) =
+            doc_mapper.query(schema.clone(), raw_query_ast, false, None, scoring_opt)?;
+        warmup_info.merge(raw_warmup_info);
+        warmup_info.field_norms = true;
+        warmup_info.simplify();
+        warmup(
+            searcher,
+            &warmup_info,
+            &|_term: &Term, _segment_id: SegmentId| {},
+        )
+        .await?;
+
+        let weight = query.weight(EnableScoring::enabled_from_searcher(searcher))?;
+        let raw_weight = raw_query.weight(EnableScoring::enabled_from_searcher(searcher))?;
+        Ok(Self { weight, raw_weight })
+    }
+
+    /// Returns the scores of hits matching the query, keyed by their address. Explaining the score
+    /// of a hit is CPU-bound, so this must run on the search thread pool.
+    pub fn score_hits(
+        &self,
+        searcher: &Searcher,
+        doc_addrs: &[DocAddress],
+    ) -> anyhow::Result<HashMap<DocAddress, HitScores>> {
+        doc_addrs
+            .iter()
+            .map(|doc_addr| {
+                let hit_scores = self.scores(searcher, *doc_addr)?;
+                Ok((*doc_addr, hit_scores))
+            })
+            .collect()
+    }
+
+    /// Returns the scores of a hit. The hit must match the query.
+    fn scores(&self, searcher: &Searcher, doc_addr: DocAddress) -> anyhow::Result<HitScores> {
+        let segment_reader = searcher.segment_reader(doc_addr.segment_ord);
+        let score = self
+            .weight
+            .explain(segment_reader, doc_addr.doc_id)?
+            .value();
+        let raw_score = self
+            .raw_weight
+            .explain(segment_reader, doc_addr.doc_id)?
+            .value();
+        Ok(HitScores { score, raw_score })
+    }
+}
@@ -0,0 +1,105 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::watch;
+
+/// Counts the leaf searches running on the node, so that a node leaving the cluster can wait for
+/// them to finish before shutting down.
+#[derive(Clone)]
+pub struct InFlightLeafSearches {
+    num_in_flight_tx: Arc<watch::Sender<usize>>,
+}
+
+impl Default for InFlightLeafSearches {
+    fn default() -> Self {
+        let (num_in_flight_tx, _num_in_flight_rx) = watch::channel(0);
+        Self {
+            num_in_flight_tx: Arc::new(num_in_flight_tx),
+        }
+    }
+}
+
+impl InFlightLeafSearches {
+    /// Returns the number of leaf searches running on the node.
+    pub fn num_in_flight(&self) -> usize {
+        *self.num_in_flight_tx.borrow()
+    }
+
+    /// Tracks a leaf search until the returned guard is dropped.
+    pub(crate) fn track(&self) -> InFlightLeafSearchGuard {
+        self.num_in_flight_tx
+            .send_modify(|num_in_flight| *num_in_flight += 1);
+        InFlightLeafSearchGuard {
+            num_in_flight_tx: self.num_in_flight_tx.clone(),
+        }
+    }
+
+    /// Waits for the leaf searches running on the node to finish. Returns whether they all
+    /// finished before the timeout.
+    pub async fn wait_for_completion(&self, timeout: Duration) -> bool {
+        let mut num_in_flight_rx = self.num_in_flight_tx.subscribe();
+        let wait_fut = num_in_flight_rx.wait_for(|num_in_flight| *num_in_flight == 0);
+        matches!(tokio::time::timeout(timeout, wait_fut).await, Ok(Ok(_)))
+    }
+}
+
+pub(crate) struct InFlightLeafSearchGuard {
+    num_in_flight_tx: Arc<watch::Sender<usize>>,
+}
+
+impl Drop for InFlightLeafSearchGuard {
+    fn drop(&mut self) {
+        self.num_in_flight_tx
+            .send_modify(|num_in_flight| *num_in_flight -= 1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_in_flight_leaf_searches() {
+        let in_flight_leaf_searches = InFlightLeafSearches::default();
+        assert!(
+            in_flight_leaf_searches
+                .wait_for_completion(Duration::ZERO)
+                .await
+        );
+        let guard_1 = in_flight_leaf_searches.track();
+        let guard_2 = in_flight_leaf_searches.track();
+        assert_eq!(in_flight_leaf_searches.num_in_flight(), 2);
+        assert!(
+            !in_flight_leaf_searches
+                .wait_for_completion(Duration::from_millis(10))
+                .await
+        );
+        drop(guard_1);
+
+        let wait_handle = tokio::spawn({
+            let in_flight_leaf_searches = in_flight_leaf_searches.clone();
+            async move {
+                in_flight_leaf_searches
+                    .wait_for_completion(Duration::from_secs(30))
+                    .await
+            }
+        });
+        drop(guard_2);
+        assert!(wait_handle.await.unwrap());
+        assert_eq!(in_flight_leaf_searches.num_in_flight(), 0);
+    }
+}
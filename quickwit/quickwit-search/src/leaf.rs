@@ -44,8 +44,8 @@ use quickwit_query::query_ast::{
 };
 use quickwit_query::tokenizers::TokenizerManager;
 use quickwit_storage::{
-    BundleStorage, ByteRangeCache, CountingStorage, MemorySizedCache, OwnedBytes, SearchSplitCache,
-    Storage, StorageResolver, TimeoutAndRetryStorage, wrap_storage_with_cache,
+    BundleStorage, ByteRangeCache, CountingStorage, DirCache, MemorySizedCache, OwnedBytes,
+    SearchSplitCache, Storage, StorageResolver, TimeoutAndRetryStorage, wrap_storage_with_cache,
 };
 use tantivy::aggregation::AggContextParams;
 use tantivy::aggregation::agg_req::{AggregationVariants, Aggregations};
@@ -65,7 +65,7 @@ use crate::metrics::{
     LEAF_SEARCH_SINGLE_SPLIT_WARMUP_NUM_BYTES, LEAF_SEARCH_SPLIT_DURATION_SECS,
     LEAF_SEARCH_WARMUP_ONGOING_NUM_BYTES, SPLIT_SEARCH_OUTCOME_TOTAL, SplitSearchOutcomeCounters,
 };
-use crate::root::is_metadata_count_request_with_ast;
+use crate::root::{is_metadata_count_request_with_ast, parse_scoring};
 use crate::search_permit_provider::{
     SearchPermit, SearchPermitFuture, compute_initial_memory_allocation,
 };
@@ -233,10 +233,13 @@ pub(crate) async fn open_index_with_caches(
     )
     .await?;
 
-    let bundle_storage_with_cache = wrap_storage_with_cache(
+    // The fast fields of the split are cached under its ID, so that they can be evicted with it.
+    let fast_fields_cache = DirCache::new(
+        &split_and_footer_offsets.split_id,
         searcher_context.fast_fields_cache.clone(),
-        Arc::new(bundle_storage),
     );
+    let bundle_storage_with_cache =
+        wrap_storage_with_cache(Arc::new(fast_fields_cache), Arc::new(bundle_storage));
 
     let directory = StorageDirectory::new(bundle_storage_with_cache);
 
@@ -659,7 +662,7 @@ async fn leaf_search_single_split(
     ctx: Arc<LeafSearchContext>,
     storage: Arc<dyn Storage>,
     split: SplitIdAndFooterOffsets,
-    search_permit: &mut SearchPermit,
+    mut search_permit: SearchPermit,
 ) -> crate::Result<Option<LeafSearchResponse>> {
     let mut leaf_search_state_guard =
         SplitSearchStateGuard::new(ctx.split_outcome_counters.clone());
@@ -724,8 +727,9 @@ async fn leaf_search_single_split(
         agg_context_params,
     )?;
 
-    let predicate_cache = if collector.requires_scoring() {
-        // at the moment the predicate cache doesn't support scoring
+    let predicate_cache = if collector.requires_scoring() || search_request.bypass_cache {
+        // at the moment the predicate cache doesn't support scoring, and some requests bypass
+        // the caches
         None
     } else {
         Some((
@@ -734,11 +738,13 @@ async fn leaf_search_single_split(
         ))
     };
     let split_schema = index.schema();
+    let scoring_opt = parse_scoring(search_request.scoring.as_deref())?;
     let (query, mut warmup_info) = ctx.doc_mapper.query(
         split_schema.clone(),
         query_ast.clone(),
         false,
         predicate_cache,
+        scoring_opt,
     )?;
 
     let collector_warmup_info = collector.warmup_info();
@@ -755,16 +761,17 @@ async fn leaf_search_single_split(
     // required terms can only make it emptier. An empty result is segment- and
     // scoring-agnostic, so this holds even for scored queries (which the `CacheNode`
     // machinery itself does not support).
-    let cached_known_empty = warmup_info.required_terms.iter().any(|term| {
-        match ctx
-            .searcher_context
-            .predicate_cache
-            .get(split_id.clone(), term_absence_cache_key(term))
-        {
-            Some((_segment_id, hits)) => hits.is_empty(),
-            None => false,
-        }
-    });
+    let cached_known_empty = !search_request.bypass_cache
+        && warmup_info.required_terms.iter().any(|term| {
+            match ctx
+                .searcher_context
+                .predicate_cache
+                .get(split_id.clone(), term_absence_cache_key(term))
+            {
+                Some((_segment_id, hits)) => hits.is_empty(),
+                None => false,
+            }
+        });
     let provably_empty = if cached_known_empty {
         true
     } else {
@@ -852,6 +859,14 @@ async fn leaf_search_single_split(
     let search_request_and_result: Option<(SearchRequest, LeafSearchResponse)> =
         crate::search_thread_pool()
             .run_cpu_intensive(move || {
+                // The permit moves along with the split resources so that it is only released
+                // once the search is done, even if the split search timed out in the meantime.
+                // Locals are dropped in reverse order: the permit is released after the searcher
+                // and the ephemeral cache.
+                let (_split_resources, searcher) = (
+                    (index, hot_directory, byte_range_cache, search_permit),
+                    searcher,
+                );
                 leaf_search_state_guard.set_state(SplitSearchState::Cpu);
                 let cpu_start = Instant::now();
                 let cpu_thread_pool_wait_microsecs = cpu_start.duration_since(warmup_end);
@@ -1263,6 +1278,11 @@ fn is_simple_all_query(search_request: &SearchRequest) -> bool {
         return false;
     }
 
+    // Hits filtered out by their score cannot be accounted for with the split doc counts.
+    if search_request.min_score.is_some() {
+        return false;
+    }
+
     // TODO: Update the logic to handle start_timestamp end_timestamp ranges
     if search_request.start_timestamp.is_some() || search_request.end_timestamp.is_some() {
         return false;
@@ -1574,21 +1594,13 @@ pub async fn multi_index_leaf_search(
                 incremental_merge_collector.add_result(leaf_response)?;
             }
             Err(err) => {
-                incremental_merge_collector.add_failed_split(SplitSearchError {
-                    split_id: "unknown".to_string(),
-                    error: format!("{err}"),
-                    retryable_error: true,
-                });
+                incremental_merge_collector.add_split_search_error("unknown".to_string(), err);
             }
         }
     }
 
     let mut leaf_search_response: LeafSearchResponse = crate::search_thread_pool()
-        .run_cpu_intensive_with_priority(Priority::High, || {
-            incremental_merge_collector
-                .finalize()
-                .map_err(SearchError::from)
-        })
+        .run_cpu_intensive_with_priority(Priority::High, || incremental_merge_collector.finalize())
         .instrument(info_span!("incremental_merge_finalize"))
         .await
         .context("failed to merge split search responses")??;
@@ -1762,6 +1774,7 @@ async fn run_offloaded_search_tasks(
                                 split_id: split_result.split_id,
                                 error: format!("lambda split error: {error_msg}"),
                                 retryable_error: true,
+                                timed_out: false,
                             });
                         }
                         None => {
@@ -1769,6 +1782,7 @@ async fn run_offloaded_search_tasks(
                                 split_id: split_result.split_id,
                                 error: "lambda returned empty outcome".to_string(),
                                 retryable_error: true,
+                                timed_out: false,
                             });
                         }
                     }
@@ -1788,6 +1802,7 @@ async fn run_offloaded_search_tasks(
                         split_id,
                         error: format!("lambda invocation error: {err}"),
                         retryable_error: true,
+                        timed_out: false,
                     });
                 }
             }
@@ -1989,7 +2004,7 @@ pub async fn single_doc_mapping_leaf_search(
         Err(filter_merger) => filter_merger.lock().unwrap().clone(),
     };
 
-    let leaf_search_response_result: tantivy::Result<LeafSearchResponse> =
+    let leaf_search_response_result: crate::Result<LeafSearchResponse> =
         crate::search_thread_pool()
             .run_cpu_intensive_with_priority(Priority::High, || {
                 incremental_merge_collector.finalize()
@@ -2077,6 +2092,7 @@ async fn run_local_search_tasks(
             split_id,
             error: SearchError::from(split_search_join_error).to_string(),
             retryable_error: true,
+            timed_out: false,
         });
     }
 
@@ -2174,29 +2190,69 @@ struct LeafSearchContext {
     split_filter: Arc<RwLock<CanSplitDoBetter>>,
 }
 
+/// Runs the search of a split, abandoning it if it exceeds the per-split timeout of the request,
+/// if any. The split is then reported as a failed split flagged as timed out, which lets the
+/// other splits contribute their results.
+///
+/// The CPU-bound part of an abandoned split search cannot be interrupted: it completes on the
+/// search thread pool, which holds the search permit of the split until then.
+async fn run_with_per_split_timeout<T>(
+    split_search_fut: impl Future<Output = T>,
+    split_id: &str,
+    per_split_timeout_opt: Option<Duration>,
+) -> Result<T, SplitSearchError> {
+    let Some(per_split_timeout) = per_split_timeout_opt else {
+        return Ok(split_search_fut.await);
+    };
+    tokio::time::timeout(per_split_timeout, split_search_fut)
+        .await
+        .map_err(|_elapsed| SplitSearchError {
+            error: format!(
+                "split search exceeded the per-split timeout of {}ms",
+                per_split_timeout.as_millis()
+            ),
+            split_id: split_id.to_string(),
+            // Searching the split again would most likely time out again.
+            retryable_error: false,
+            timed_out: true,
+        })
+}
+
 #[instrument(skip_all, fields(split_id = split.split_id, num_docs = split.num_docs))]
 async fn leaf_search_single_split_wrapper(
     request: SearchRequest,
     ctx: Arc<LeafSearchContext>,
     index_storage: Arc<dyn Storage>,
     split: SplitIdAndFooterOffsets,
-    mut search_permit: SearchPermit,
+    search_permit: SearchPermit,
 ) {
+    let per_split_timeout_opt = request.per_split_timeout_millis.map(Duration::from_millis);
     let timer = HistogramTimer::new(&LEAF_SEARCH_SPLIT_DURATION_SECS);
-    let leaf_search_single_split_opt_res: crate::Result<Option<LeafSearchResponse>> =
-        leaf_search_single_split(
-            request,
-            ctx.clone(),
-            index_storage,
-            split.clone(),
-            &mut search_permit,
-        )
-        .await;
-
-    // Explicitly drop the permit for readability.
-    // This should always happen after the ephemeral search cache is dropped.
-    std::mem::drop(search_permit);
+    let leaf_search_single_split_fut = leaf_search_single_split(
+        request,
+        ctx.clone(),
+        index_storage,
+        split.clone(),
+        search_permit,
+    );
+    let leaf_search_single_split_timeout_res = run_with_per_split_timeout(
+        leaf_search_single_split_fut,
+        &split.split_id,
+        per_split_timeout_opt,
+    )
+    .await;
 
+    let leaf_search_single_split_opt_res: crate::Result<Option<LeafSearchResponse>> =
+        match leaf_search_single_split_timeout_res {
+            Ok(leaf_search_single_split_opt_res) => leaf_search_single_split_opt_res,
+            Err(timed_out_split_error) => {
+                ctx.incremental_merge_collector
+                    .lock()
+                    .unwrap()
+                    .add_failed_split(timed_out_split_error);
+                return;
+            }
+        };
     if leaf_search_single_split_opt_res.is_ok() {
         timer.observe_duration();
     }
@@ -2209,15 +2265,14 @@ async fn leaf_search_single_split_wrapper(
                     split_id: split.split_id.clone(),
                     error: format!("Error parsing aggregation result: {err}"),
                     retryable_error: true,
+                    timed_out: false,
                 });
             }
         }
         Ok(None) => {}
-        Err(err) => locked_incremental_merge_collector.add_failed_split(SplitSearchError {
-            split_id: split.split_id.clone(),
-            error: format!("{err}"),
-            retryable_error: true,
-        }),
+        Err(err) => {
+            locked_incremental_merge_collector.add_split_search_error(split.split_id.clone(), err)
+        }
     }
     if let Some(last_hit) = locked_incremental_merge_collector.peek_worst_hit() {
         // TODO: we could use the RWLock instead and read the value instead of updating it
@@ -2880,6 +2935,69 @@ mod tests {
         assert!(reported.is_empty());
     }
 
+    #[tokio::test(start_paused = true)]
+    async fn test_per_split_timeout_flags_slow_split() {
+        let per_split_timeout_opt = Some(Duration::from_millis(100));
+
+        let fast_split_response = LeafSearchResponse {
+            num_hits: 1,
+            partial_hits: vec![PartialHit {
+                split_id: "fast-split".to_string(),
+                segment_ord: 0,
+                doc_id: 1,
+                sort_value: None,
+                sort_value2: None,
+            }],
+            num_attempted_splits: 1,
+            num_successful_splits: 1,
+            ..Default::default()
+        };
+        let fast_split_fut = async { fast_split_response.clone() };
+        let fast_split_res =
+            run_with_per_split_timeout(fast_split_fut, "fast-split", per_split_timeout_opt).await;
+        assert_eq!(fast_split_res.unwrap(), fast_split_response);
+
+        let slow_split_fut = async {
+            tokio::time::sleep(Duration::from_secs(10)).await;
+            fast_split_response.clone()
+        };
+        let timed_out_split_error =
+            run_with_per_split_timeout(slow_split_fut, "slow-split", per_split_timeout_opt)
+                .await
+                .unwrap_err();
+        assert_eq!(timed_out_split_error.split_id, "slow-split");
+        assert!(timed_out_split_error.timed_out);
+        assert!(!timed_out_split_error.retryable_error);
+
+        // The fast split still contributes its hits.
+        let search_request = SearchRequest {
+            max_hits: 10,
+            ..Default::default()
+        };
+        let merge_collector = make_merge_collector(&search_request, Default::default()).unwrap();
+        let mut incremental_merge_collector = IncrementalCollector::new(merge_collector);
+        incremental_merge_collector
+            .add_result(fast_split_response)
+            .unwrap();
+        incremental_merge_collector.add_failed_split(timed_out_split_error.clone());
+        let leaf_search_response = incremental_merge_collector.finalize().unwrap();
+        assert_eq!(leaf_search_response.num_hits, 1);
+        assert_eq!(leaf_search_response.partial_hits.len(), 1);
+        assert_eq!(leaf_search_response.num_successful_splits, 1);
+        assert_eq!(
+            leaf_search_response.failed_splits,
+            vec![timed_out_split_error]
+        );
+
+        // Without any per-split timeout, slow splits are awaited.
+        let slow_split_fut = async {
+            tokio::time::sleep(Duration::from_secs(10)).await;
+            "slow-split-response"
+        };
+        let slow_split_res = run_with_per_split_timeout(slow_split_fut, "slow-split", None).await;
+        assert_eq!(slow_split_res.unwrap(), "slow-split-response");
+    }
+
     fn nz(n: usize) -> std::num::NonZeroUsize {
         std::num::NonZeroUsize::new(n).unwrap()
     }
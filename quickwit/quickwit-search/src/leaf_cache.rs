@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::HashSet;
 use std::ops::{Bound, RangeBounds};
 
 use prost::Message;
@@ -51,11 +52,15 @@ impl LeafSearchCache {
             ),
         }
     }
+    /// Returns the cached result of the search of the split, unless the request bypasses the cache.
     pub fn get(
         &self,
         split_info: SplitIdAndFooterOffsets,
         search_request: SearchRequest,
     ) -> Option<LeafSearchResponse> {
+        if search_request.bypass_cache {
+            return None;
+        }
         let key = CacheKey::from_split_meta_and_request(split_info, search_request);
         let encoded_result = self.content.get(&key)?;
         // this should never fail
@@ -79,6 +84,12 @@ impl LeafSearchCache {
         let encoded_result = result.encode_to_vec();
         self.content.put(key, OwnedBytes::new(encoded_result));
     }
+
+    /// Evicts the cached results of the given splits and returns the number of evicted entries.
+    pub fn evict_splits(&self, split_ids: &HashSet<&str>) -> usize {
+        self.content
+            .evict_if(|key| split_ids.contains(key.split_id.as_str()))
+    }
 }
 
 /// A key inside a [`LeafSearchCache`].
@@ -107,6 +118,8 @@ impl CacheKey {
         // it doesn't matter whether or not we count all hits at the scale of a
         // single split: either we did process it and got everything, or we didn't.
         search_request.count_hits = CountHits::CountAll.into();
+        // The results of the requests bypassing the cache are served to the other requests.
+        search_request.bypass_cache = false;
 
         CacheKey {
             split_id: split_info.split_id,
@@ -207,6 +220,13 @@ impl PredicateCacheImpl {
             ),
         }
     }
+
+    /// Evicts the cached predicates of the given splits and returns the number of evicted
+    /// entries.
+    pub fn evict_splits(&self, split_ids: &HashSet<&str>) -> usize {
+        self.content
+            .evict_if(|(split_id, _)| split_ids.contains(split_id.as_str()))
+    }
 }
 
 impl quickwit_query::query_ast::PredicateCache for PredicateCacheImpl {
@@ -436,4 +456,44 @@ mod tests {
         assert!(cache.get(split_3.clone(), query_2).is_none());
         assert!(cache.get(split_3, query_2bis).is_some());
     }
+
+    #[test]
+    fn test_leaf_search_cache_bypass() {
+        let cache = LeafSearchCache::new(&ByteSize::mb(64).into());
+
+        let split = SplitIdAndFooterOffsets {
+            split_id: "split_1".to_string(),
+            split_footer_start: 0,
+            split_footer_end: 100,
+            timestamp_start: None,
+            timestamp_end: None,
+            num_docs: 0,
+        };
+        let query = SearchRequest {
+            index_id_patterns: vec!["test-idx".to_string()],
+            query_ast: "test".to_string(),
+            max_hits: 10,
+            ..Default::default()
+        };
+        let fresh_query = SearchRequest {
+            bypass_cache: true,
+            ..query.clone()
+        };
+        let result = LeafSearchResponse {
+            num_hits: 1234,
+            ..Default::default()
+        };
+        cache.put(split.clone(), query.clone(), result.clone());
+        assert!(cache.get(split.clone(), query.clone()).is_some());
+        assert!(cache.get(split.clone(), fresh_query.clone()).is_none());
+
+        // The results of the requests bypassing the cache still populate it.
+        let other_split = SplitIdAndFooterOffsets {
+            split_id: "split_2".to_string(),
+            ..split
+        };
+        cache.put(other_split.clone(), fresh_query.clone(), result);
+        assert!(cache.get(other_split.clone(), fresh_query).is_none());
+        assert!(cache.get(other_split, query).is_some());
+    }
 }
@@ -17,12 +17,15 @@
 #![allow(clippy::bool_assert_comparison)]
 #![deny(clippy::disallowed_methods)]
 
+mod clear_cache;
 mod client;
 mod cluster_client;
 mod collector;
 mod error;
 mod fetch_docs;
 mod find_trace_ids_collector;
+mod hit_scorer;
+mod in_flight_leaf_searches;
 
 mod invoker;
 /// Leaf search operations.
@@ -31,12 +34,17 @@ mod leaf_cache;
 mod list_fields;
 mod list_terms;
 mod metrics_trackers;
+mod missing_fields;
+mod percentiles_estimation;
+mod query_cost;
 mod retry;
 mod root;
 mod scroll_context;
+mod search_concurrency_limiter;
 mod search_job_placer;
 mod search_response_rest;
 mod service;
+mod sort_script;
 pub(crate) mod top_k_collector;
 
 mod metrics;
@@ -83,16 +91,21 @@ pub use crate::client::{
 pub use crate::cluster_client::ClusterClient;
 pub use crate::error::{SearchError, parse_grpc_error};
 use crate::fetch_docs::fetch_docs;
+pub use crate::in_flight_leaf_searches::InFlightLeafSearches;
 pub use crate::invoker::LambdaLeafSearchInvoker;
 pub use crate::root::{
-    IndexMetasForLeafSearch, SearchJob, ensure_all_indexes_found, jobs_to_leaf_request,
-    root_search, search_plan,
+    IndexMetasForLeafSearch, SearchJob, ensure_all_indexes_found, ensure_no_read_blocked_indexes,
+    jobs_to_leaf_request, root_search, search_plan,
 };
 pub use crate::search_job_placer::{Job, SearchJobPlacer};
 pub use crate::search_response_rest::{
-    AggregationResults, SearchPlanResponseRest, SearchResponseRest,
+    AggregationResults, ColumnarHits, SearchPlanResponseRest, SearchPlanSplit,
+    SearchResolutionRest, SearchResponseRest, SearchTimings, SplitsSummary,
 };
-pub use crate::service::{MockSearchService, SearchService, SearchServiceImpl};
+pub use crate::service::{
+    MockSearchService, SearchService, SearchServiceImpl, scroll_search_request,
+};
+pub use crate::sort_script::SORT_SCRIPT_FIELD_NAME;
 
 /// A pool of searcher clients identified by their gRPC socket address.
 pub type SearcherPool = Pool<SocketAddr, SearchServiceClient>;
@@ -230,6 +243,7 @@ pub async fn resolve_index_patterns(
     } else {
         ListIndexesMetadataRequest {
             index_id_patterns: index_id_patterns.to_vec(),
+            paused_only: false,
         }
     };
 
@@ -260,20 +274,28 @@ fn convert_document_to_json_string(
 }
 
 /// Starts a search node, aka a `searcher`.
+///
+/// When `metastore` is a read replica, `primary_metastore_opt` holds the primary metastore, from
+/// which the searches preferring the freshest reads list their splits.
 pub async fn start_searcher_service(
     metastore: MetastoreServiceClient,
+    primary_metastore_opt: Option<MetastoreServiceClient>,
     storage_resolver: StorageResolver,
     search_job_placer: SearchJobPlacer,
     searcher_context: Arc<SearcherContext>,
 ) -> anyhow::Result<Arc<dyn SearchService>> {
     let cluster_client = ClusterClient::new(search_job_placer);
-    let search_service = Arc::new(SearchServiceImpl::new(
+    let mut search_service = SearchServiceImpl::new(
         metastore,
         storage_resolver,
         cluster_client,
         searcher_context,
-    ));
-    Ok(search_service)
+    );
+
+    if let Some(primary_metastore) = primary_metastore_opt {
+        search_service = search_service.with_primary_metastore(primary_metastore);
+    }
+    Ok(Arc::new(search_service))
 }
 
 /// Performs a search on the current node.
@@ -12,6 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::HashSet;
+
 use quickwit_config::CacheConfig;
 use quickwit_storage::{MemorySizedCache, OwnedBytes};
 
@@ -36,6 +38,12 @@ impl ListFieldsCache {
     pub fn put(&self, split_id: String, serialized_split_fields: OwnedBytes) {
         self.cache.put(split_id, serialized_split_fields);
     }
+
+    /// Evicts the cached fields of the given splits and returns the number of evicted entries.
+    pub fn evict_splits(&self, split_ids: &HashSet<&str>) -> usize {
+        self.cache
+            .evict_if(|split_id| split_ids.contains(split_id.as_str()))
+    }
 }
 
 #[cfg(test)]
@@ -38,7 +38,10 @@ use crate::leaf::open_index_with_caches;
 use crate::metrics::{LEAF_LIST_TERMS_SPLITS_TOTAL, LEAF_SEARCH_SPLIT_DURATION_SECS};
 use crate::search_job_placer::group_jobs_by_index_id;
 use crate::search_permit_provider::compute_initial_memory_allocation;
-use crate::{ClusterClient, SearchError, SearchJob, SearcherContext, resolve_index_patterns};
+use crate::{
+    ClusterClient, SearchError, SearchJob, SearcherContext, ensure_no_read_blocked_indexes,
+    resolve_index_patterns,
+};
 
 /// Performs a distributed list terms.
 /// 1. Sends leaf requests over gRPC to multiple leaf nodes.
@@ -54,6 +57,7 @@ pub async fn root_list_terms(
     let start_instant = tokio::time::Instant::now();
     let indexes_metadata =
         resolve_index_patterns(&list_terms_request.index_id_patterns, metastore).await?;
+    ensure_no_read_blocked_indexes(&indexes_metadata)?;
     // The request contains a wildcard, but couldn't find any index.
     if indexes_metadata.is_empty() {
         return Ok(ListTermsResponse {
@@ -406,6 +410,7 @@ pub async fn leaf_list_terms(
             split_id,
             error: err.to_string(),
             retryable_error: true,
+            timed_out: false,
         })
         .collect();
     let merged_search_response = LeafListTermsResponse {
@@ -0,0 +1,193 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+
+use quickwit_config::MissingFields;
+use quickwit_doc_mapper::{FieldMappingEntry, FieldMappingType, JsonObject};
+use quickwit_metastore::IndexMetadata;
+use quickwit_proto::search::Hit;
+use quickwit_proto::types::IndexId;
+use serde_json::Value as JsonValue;
+
+/// Returns the field mappings of the targeted indexes whose hits render the fields absent from
+/// their documents as `null`, according to the `missing_fields` search setting of the indexes or
+/// the override of the search request.
+pub(crate) fn field_mappings_rendering_nulls(
+    indexes_metadata: &[IndexMetadata],
+    missing_fields_as_null_opt: Option<bool>,
+) -> HashMap<IndexId, Vec<FieldMappingEntry>> {
+    indexes_metadata
+        .iter()
+        .filter(|index_metadata| {
+            missing_fields_as_null_opt.unwrap_or_else(|| {
+                index_metadata.index_config.search_settings.missing_fields == MissingFields::AsNull
+            })
+        })
+        .map(|index_metadata| {
+            let index_config = &index_metadata.index_config;
+            let field_mappings = index_config.doc_mapping.field_mappings.clone();
+            (index_config.index_id.clone(), field_mappings)
+        })
+        .collect()
+}
+
+/// Renders as `null` the stored fields of the doc mapping absent from the documents of the hits.
+/// The hits of the indexes absent from `field_mappings_per_index` are left untouched.
+pub(crate) fn render_missing_fields_as_null(
+    hits: &mut [Hit],
+    field_mappings_per_index: &HashMap<IndexId, Vec<FieldMappingEntry>>,
+) {
+    if field_mappings_per_index.is_empty() {
+        return;
+    }
+    for hit in hits {
+        let Some(field_mappings) = field_mappings_per_index.get(&hit.index_id) else {
+            continue;
+        };
+        let Ok(mut document) = serde_json::from_str::<JsonObject>(&hit.json) else {
+            continue;
+        };
+        if insert_missing_fields(&mut document, field_mappings) {
+            hit.json = serde_json::to_string(&document).expect("JSON object should serialize");
+        }
+    }
+}
+
+/// Inserts a `null` value for each stored field of `field_mappings` absent from `document`,
+/// recursing into the object fields. Returns whether any field was inserted.
+fn insert_missing_fields(document: &mut JsonObject, field_mappings: &[FieldMappingEntry]) -> bool {
+    let mut inserted = false;
+
+    for field_mapping in field_mappings {
+        if let FieldMappingType::Object(object_options) = &field_mapping.mapping_type {
+            if let Some(JsonValue::Object(sub_document)) = document.get_mut(&field_mapping.name) {
+                inserted |= insert_missing_fields(sub_document, &object_options.field_mappings);
+                continue;
+            }
+        } else if !is_stored(&field_mapping.mapping_type) {
+            continue;
+        }
+        if !document.contains_key(&field_mapping.name) {
+            document.insert(field_mapping.name.clone(), JsonValue::Null);
+            inserted = true;
+        }
+    }
+    inserted
+}
+
+/// Returns whether the values of a field are stored, and can therefore appear in the documents
+/// of the hits.
+fn is_stored(mapping_type: &FieldMappingType) -> bool {
+    match mapping_type {
+        FieldMappingType::Text(options, _) => options.stored,
+        FieldMappingType::I64(options, _)
+        | FieldMappingType::U64(options, _)
+        | FieldMappingType::F64(options, _) => options.stored,
+        FieldMappingType::DateTime(options, _) => options.stored,
+        FieldMappingType::Bool(options, _) => options.stored,
+        FieldMappingType::IpAddr(options, _) => options.stored,
+        FieldMappingType::Bytes(options, _) => options.stored,
+        FieldMappingType::Json(options, _) => options.stored,
+        FieldMappingType::Object(_) => true,
+        FieldMappingType::Concatenate(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_doc_mapper::DocMapping;
+    use serde_json::json;
+
+    use super::*;
+
+    fn field_mappings_for_test() -> Vec<FieldMappingEntry> {
+        let doc_mapping: DocMapping = serde_json::from_value(json!({
+            "field_mappings": [
+                {"name": "body", "type": "text"},
+                {"name": "severity", "type": "text"},
+                {"name": "trace_id", "type": "text", "stored": false},
+                {
+                    "name": "resource",
+                    "type": "object",
+                    "field_mappings": [{"name": "service", "type": "text"}]
+                }
+            ]
+        }))
+        .unwrap();
+        doc_mapping.field_mappings
+    }
+
+    fn hit_for_test(index_id: &str, document: JsonValue) -> Hit {
+        Hit {
+            json: document.to_string(),
+            index_id: index_id.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_render_missing_fields_as_null() {
+        let field_mappings_per_index =
+            HashMap::from([("test-index".to_string(), field_mappings_for_test())]);
+        let mut hits = vec![
+            hit_for_test("test-index", json!({"body": "hello", "resource": {}})),
+            hit_for_test("test-index", json!({"severity": "INFO"})),
+            hit_for_test("other-index", json!({"body": "hello"})),
+        ];
+        render_missing_fields_as_null(&mut hits, &field_mappings_per_index);
+
+        let documents: Vec<JsonValue> = hits
+            .iter()
+            .map(|hit| serde_json::from_str(&hit.json).unwrap())
+            .collect();
+        assert_eq!(
+            documents,
+            [
+                json!({"body": "hello", "severity": null, "resource": {"service": null}}),
+                json!({"body": null, "severity": "INFO", "resource": null}),
+                json!({"body": "hello"}),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_field_mappings_rendering_nulls() {
+        let omitting_index_metadata = IndexMetadata::for_test("omitting-index", "ram:///omitting");
+        let mut rendering_index_metadata =
+            IndexMetadata::for_test("rendering-index", "ram:///rendering");
+        rendering_index_metadata
+            .index_config
+            .search_settings
+            .missing_fields = MissingFields::AsNull;
+        let indexes_metadata = [omitting_index_metadata, rendering_index_metadata];
+
+        let field_mappings_per_index = field_mappings_rendering_nulls(&indexes_metadata, None);
+        assert_eq!(field_mappings_per_index.len(), 1);
+        assert!(field_mappings_per_index.contains_key("rendering-index"));
+
+        let field_mappings_per_index =
+            field_mappings_rendering_nulls(&indexes_metadata, Some(true));
+        assert_eq!(field_mappings_per_index.len(), 2);
+
+        // The omitted fields stay absent from the documents of the hits.
+        let field_mappings_per_index =
+            field_mappings_rendering_nulls(&indexes_metadata, Some(false));
+        assert!(field_mappings_per_index.is_empty());
+
+        let mut hits = vec![hit_for_test("rendering-index", json!({"body": "hello"}))];
+        render_missing_fields_as_null(&mut hits, &field_mappings_per_index);
+        assert_eq!(hits[0].json, r#"{"body":"hello"}"#);
+    }
+}
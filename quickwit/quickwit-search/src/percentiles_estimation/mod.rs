@@ -0,0 +1,432 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Percentiles aggregations selecting the t-digest or HDR histogram estimation method.
+//!
+//! Tantivy always summarizes the values of a percentiles aggregation with a DDSketch. When a
+//! request sets the `tdigest` or `hdr` options, the root rewrites the aggregation to read a dense
+//! grid of quantiles from the merged sketch, then estimates the requested percentiles from that
+//! grid with the selected method.
+
+#[cfg(test)]
+mod tests;
+
+use quickwit_proto::search::SearchRequest;
+use quickwit_query::aggregations::{
+    AggregationResult, AggregationResults, BucketEntries, BucketResult, MetricResult,
+    PercentileValues,
+};
+use serde::Deserialize;
+use serde_json::{Map as JsonMap, Value as JsonValue};
+use tantivy::aggregation::metric::PercentileValuesVecEntry;
+
+use crate::SearchError;
+
+/// Number of quantiles read from the DDSketch: 0%, 0.1%, 0.2%, ..., 100%.
+const NUM_GRID_QUANTILES: usize = 1_001;
+
+/// Percents returned when the request does not set them, same as Elasticsearch.
+const DEFAULT_PERCENTS: [f64; 7] = [1.0, 5.0, 25.0, 50.0, 75.0, 95.0, 99.0];
+
+const MAX_HDR_NUM_SIGNIFICANT_DIGITS: u8 = 5;
+
+fn default_compression() -> f64 {
+    100.0
+}
+
+fn default_num_significant_digits() -> u8 {
+    3
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TDigestOptions {
+    #[serde(default = "default_compression")]
+    compression: f64,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct HdrOptions {
+    #[serde(default = "default_num_significant_digits")]
+    number_of_significant_value_digits: u8,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum EstimationMethod {
+    TDigest { compression: f64 },
+    Hdr { num_significant_digits: u8 },
+}
+
+/// A percentiles aggregation whose percentiles are estimated at the root.
+#[derive(Debug, PartialEq)]
+pub(crate) struct PercentilesEstimation {
+    /// Names of the aggregations leading to the percentiles aggregation, its own name included.
+    path: Vec<String>,
+    method: EstimationMethod,
+    percents: Vec<f64>,
+    keyed: bool,
+}
+
+/// Rewrites the percentiles aggregations of the request setting the `tdigest` or `hdr` options
+/// into plain percentiles aggregations reading the quantile grid, and returns how to estimate
+/// their requested percentiles once the aggregation results are final.
+pub(crate) fn rewrite_percentiles_estimations(
+    search_request: &mut SearchRequest,
+) -> crate::Result<Vec<PercentilesEstimation>> {
+    let Some(aggregation_request) = &search_request.aggregation_request else {
+        return Ok(Vec::new());
+    };
+    // An invalid aggregation request is reported when the request is validated.
+    let Ok(mut aggs_json) = serde_json::from_str::<JsonValue>(aggregation_request) else {
+        return Ok(Vec::new());
+    };
+    let mut percentiles_estimations = Vec::new();
+    rewrite_aggregations(
+        &mut aggs_json,
+        &mut Vec::new(),
+        &mut percentiles_estimations,
+    )?;
+
+    if !percentiles_estimations.is_empty() {
+        search_request.aggregation_request = Some(aggs_json.to_string());
+    }
+    Ok(percentiles_estimations)
+}
+
+fn rewrite_aggregations(
+    aggs_json: &mut JsonValue,
+    path: &mut Vec<String>,
+    percentiles_estimations: &mut Vec<PercentilesEstimation>,
+) -> crate::Result<()> {
+    let Some(aggs) = aggs_json.as_object_mut() else {
+        return Ok(());
+    };
+    for (agg_name, agg) in aggs.iter_mut() {
+        path.push(agg_name.clone());
+
+        if let Some(percentiles) = agg
+            .get_mut("percentiles")
+            .and_then(JsonValue::as_object_mut)
+            && let Some(method) = take_estimation_method(agg_name, percentiles)?
+        {
+            let percents = percentiles
+                .get("percents")
+                .and_then(|percents| serde_json::from_value(percents.clone()).ok())
+                .unwrap_or_else(|| DEFAULT_PERCENTS.to_vec());
+            let keyed = percentiles
+                .get("keyed")
+                .and_then(JsonValue::as_bool)
+                .unwrap_or(true);
+            let grid_percents: Vec<f64> = (0..NUM_GRID_QUANTILES)
+                .map(|quantile_ord| quantile_ord as f64 * 100.0 / (NUM_GRID_QUANTILES - 1) as f64)
+                .collect();
+            percentiles.insert("percents".to_string(), grid_percents.into());
+            percentiles.insert("keyed".to_string(), false.into());
+
+            percentiles_estimations.push(PercentilesEstimation {
+                path: path.clone(),
+                method,
+                percents,
+                keyed,
+            });
+        }
+        if let Some(agg) = agg.as_object_mut() {
+            for sub_aggs_key in ["aggs", "aggregations"] {
+                if let Some(sub_aggs_json) = agg.get_mut(sub_aggs_key) {
+                    rewrite_aggregations(sub_aggs_json, path, percentiles_estimations)?;
+                }
+            }
+        }
+        path.pop();
+    }
+    Ok(())
+}
+
+/// Removes the `tdigest` or `hdr` options from the percentiles aggregation and returns the
+/// estimation method they select.
+fn take_estimation_method(
+    agg_name: &str,
+    percentiles: &mut JsonMap<String, JsonValue>,
+) -> crate::Result<Option<EstimationMethod>> {
+    let invalid_options = |message: String| {
+        SearchError::InvalidAggregationRequest(format!(
+            "percentiles aggregation `{agg_name}`: {message}"
+        ))
+    };
+    let method = match (percentiles.remove("tdigest"), percentiles.remove("hdr")) {
+        (None, None) => return Ok(None),
+        (Some(_), Some(_)) => {
+            return Err(invalid_options(
+                "`tdigest` and `hdr` cannot be set together".to_string(),
+            ));
+        }
+        (Some(tdigest_json), None) => {
+            let tdigest: TDigestOptions = serde_json::from_value(tdigest_json)
+                .map_err(|error| invalid_options(format!("invalid `tdigest` options: {error}")))?;
+            if !tdigest.compression.is_finite() || tdigest.compression <= 0.0 {
+                return Err(invalid_options(format!(
+                    "`tdigest.compression` must be positive, got {}",
+                    tdigest.compression
+                )));
+            }
+            EstimationMethod::TDigest {
+                compression: tdigest.compression,
+            }
+        }
+        (None, Some(hdr_json)) => {
+            let hdr: HdrOptions = serde_json::from_value(hdr_json)
+                .map_err(|error| invalid_options(format!("invalid `hdr` options: {error}")))?;
+            if hdr.number_of_significant_value_digits > MAX_HDR_NUM_SIGNIFICANT_DIGITS {
+                return Err(invalid_options(format!(
+                    "`hdr.number_of_significant_value_digits` must be between 0 and \
+                     {MAX_HDR_NUM_SIGNIFICANT_DIGITS}, got {}",
+                    hdr.number_of_significant_value_digits
+                )));
+            }
+            EstimationMethod::Hdr {
+                num_significant_digits: hdr.number_of_significant_value_digits,
+            }
+        }
+    };
+    Ok(Some(method))
+}
+
+/// Replaces the quantile grids of the final aggregation results by the requested percentiles.
+pub(crate) fn apply_percentiles_estimations(
+    aggregation_results: &mut AggregationResults,
+    percentiles_estimations: &[PercentilesEstimation],
+) {
+    for percentiles_estimation in percentiles_estimations {
+        apply_percentiles_estimation(
+            aggregation_results,
+            &percentiles_estimation.path,
+            percentiles_estimation,
+        );
+    }
+}
+
+fn apply_percentiles_estimation(
+    aggregation_results: &mut AggregationResults,
+    path: &[String],
+    percentiles_estimation: &PercentilesEstimation,
+) {
+    let Some((agg_name, sub_path)) = path.split_first() else {
+        return;
+    };
+    let Some((_, aggregation_result)) = aggregation_results
+        .0
+        .iter_mut()
+        .find(|(name, _)| name == agg_name)
+    else {
+        return;
+    };
+    match aggregation_result {
+        AggregationResult::MetricResult(MetricResult::Percentiles(percentiles))
+            if sub_path.is_empty() =>
+        {
+            percentiles.values = percentiles_estimation.estimate(&percentiles.values);
+        }
+        AggregationResult::BucketResult(bucket_result) => {
+            for sub_aggregation in sub_aggregations_mut(bucket_result) {
+                apply_percentiles_estimation(sub_aggregation, sub_path, percentiles_estimation);
+            }
+        }
+        AggregationResult::MetricResult(_) => {}
+    }
+}
+
+fn sub_aggregations_mut(bucket_result: &mut BucketResult) -> Vec<&mut AggregationResults> {
+    match bucket_result {
+        BucketResult::Range {
+            buckets: BucketEntries::Vec(entries),
+        } => entries
+            .iter_mut()
+            .map(|entry| &mut entry.sub_aggregation)
+            .collect(),
+        BucketResult::Range {
+            buckets: BucketEntries::HashMap(entries),
+        } => entries
+            .values_mut()
+            .map(|entry| &mut entry.sub_aggregation)
+            .collect(),
+        BucketResult::Histogram {
+            buckets: BucketEntries::Vec(entries),
+        } => entries
+            .iter_mut()
+            .map(|entry| &mut entry.sub_aggregation)
+            .collect(),
+        BucketResult::Histogram {
+            buckets: BucketEntries::HashMap(entries),
+        } => entries
+            .values_mut()
+            .map(|entry| &mut entry.sub_aggregation)
+            .collect(),
+        BucketResult::Terms { buckets, .. } => buckets
+            .iter_mut()
+            .map(|entry| &mut entry.sub_aggregation)
+            .collect(),
+        BucketResult::Composite { buckets, .. } => buckets
+            .iter_mut()
+            .map(|entry| &mut entry.sub_aggregation)
+            .collect(),
+    }
+}
+
+impl PercentilesEstimation {
+    fn estimate(&self, grid_values: &PercentileValues) -> PercentileValues {
+        let grid: Vec<f64> = match grid_values {
+            PercentileValues::Vec(entries) => entries.iter().map(|entry| entry.value).collect(),
+            PercentileValues::HashMap(_) => Vec::new(),
+        };
+        let estimate_quantile: Box<dyn Fn(f64) -> f64> =
+            if grid.len() != NUM_GRID_QUANTILES || grid.iter().any(|value| !value.is_finite()) {
+                // The aggregation did not collect any value.
+                Box::new(|_quantile| f64::NAN)
+            } else {
+                match self.method {
+                    EstimationMethod::TDigest { compression } => {
+                        let centroids = build_tdigest_centroids(&grid, compression);
+                        let (min_value, max_value) = (grid[0], grid[grid.len() - 1]);
+                        Box::new(move |quantile| {
+                            tdigest_quantile(&centroids, min_value, max_value, quantile)
+                        })
+                    }
+                    EstimationMethod::Hdr {
+                        num_significant_digits,
+                    } => Box::new(move |quantile| {
+                        hdr_highest_equivalent_value(
+                            grid_quantile(&grid, quantile),
+                            num_significant_digits,
+                        )
+                    }),
+                }
+            };
+        if self.keyed {
+            let values = self
+                .percents
+                .iter()
+                .map(|percent| (format!("{percent:?}"), estimate_quantile(percent / 100.0)))
+                .collect();
+            PercentileValues::HashMap(values)
+        } else {
+            let entries: Vec<PercentileValuesVecEntry> = self
+                .percents
+                .iter()
+                .map(|percent| PercentileValuesVecEntry {
+                    key: *percent,
+                    value: estimate_quantile(percent / 100.0),
+                })
+                .collect();
+            PercentileValues::Vec(entries)
+        }
+    }
+}
+
+/// Interpolates linearly between the two grid quantiles surrounding the quantile.
+fn grid_quantile(grid: &[f64], quantile: f64) -> f64 {
+    let position = quantile.clamp(0.0, 1.0) * (grid.len() - 1) as f64;
+    let lower_ord = position.floor() as usize;
+    let upper_ord = position.ceil() as usize;
+    grid[lower_ord] + (grid[upper_ord] - grid[lower_ord]) * (position - lower_ord as f64)
+}
+
+#[derive(Debug)]
+struct Centroid {
+    mean: f64,
+    weight: f64,
+}
+
+/// Merges the grid quantiles, each holding the same share of the values, into t-digest
+/// centroids. The `k1` scale function keeps the centroids small at the tails and large around the
+/// median, and the compression bounds the number of centroids.
+fn build_tdigest_centroids(grid: &[f64], compression: f64) -> Vec<Centroid> {
+    let scale =
+        |quantile: f64| compression / (2.0 * std::f64::consts::PI) * (2.0 * quantile - 1.0).asin();
+    let total_weight = grid.len() as f64;
+    let mut centroids: Vec<Centroid> = Vec::new();
+    let mut centroid_sum = 0.0;
+    let mut centroid_weight = 0.0;
+    let mut centroid_start_weight = 0.0;
+
+    for (ord, value) in grid.iter().enumerate() {
+        let weight_so_far = ord as f64;
+        if centroid_weight > 0.0
+            && scale((weight_so_far + 1.0) / total_weight)
+                - scale(centroid_start_weight / total_weight)
+                > 1.0
+        {
+            centroids.push(Centroid {
+                mean: centroid_sum / centroid_weight,
+                weight: centroid_weight,
+            });
+            centroid_sum = 0.0;
+            centroid_weight = 0.0;
+            centroid_start_weight = weight_so_far;
+        }
+        centroid_sum += value;
+        centroid_weight += 1.0;
+    }
+    centroids.push(Centroid {
+        mean: centroid_sum / centroid_weight,
+        weight: centroid_weight,
+    });
+    centroids
+}
+
+/// Interpolates linearly between the means of the centroids surrounding the rank of the
+/// quantile, each centroid mean sitting at the middle of its weight.
+fn tdigest_quantile(centroids: &[Centroid], min_value: f64, max_value: f64, quantile: f64) -> f64 {
+    let total_weight: f64 = centroids.iter().map(|centroid| centroid.weight).sum();
+    let rank = quantile.clamp(0.0, 1.0) * total_weight;
+
+    let mut previous_center = 0.0;
+    let mut previous_mean = min_value;
+    let mut weight_so_far = 0.0;
+
+    for centroid in centroids {
+        let center = weight_so_far + centroid.weight / 2.0;
+        if rank <= center {
+            if center == previous_center {
+                return centroid.mean;
+            }
+            let ratio = (rank - previous_center) / (center - previous_center);
+            return previous_mean + (centroid.mean - previous_mean) * ratio;
+        }
+        previous_center = center;
+        previous_mean = centroid.mean;
+        weight_so_far += centroid.weight;
+    }
+    if total_weight == previous_center {
+        return max_value;
+    }
+    let ratio = (rank - previous_center) / (total_weight - previous_center);
+    previous_mean + (max_value - previous_mean) * ratio
+}
+
+/// Returns the upper bound of the HDR histogram bucket holding the value. Buckets are powers of
+/// two divided into sub-buckets of equal width, enough of them to tell apart values differing by
+/// one unit of their last significant digit. Negative values are bucketed by their magnitude.
+fn hdr_highest_equivalent_value(value: f64, num_significant_digits: u8) -> f64 {
+    if value == 0.0 || !value.is_finite() {
+        return value;
+    }
+    let sub_bucket_count_magnitude = (2.0 * 10f64.powi(num_significant_digits as i32))
+        .log2()
+        .ceil() as i32;
+    let magnitude = value.abs().log2().floor() as i32;
+    let sub_bucket_width = 2f64.powi(magnitude + 1 - sub_bucket_count_magnitude);
+    let lowest_equivalent_value = (value.abs() / sub_bucket_width).floor() * sub_bucket_width;
+    (lowest_equivalent_value + sub_bucket_width).copysign(value)
+}
@@ -0,0 +1,178 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+
+fn uniform_grid() -> Vec<f64> {
+    (0..NUM_GRID_QUANTILES)
+        .map(|quantile_ord| 1.0 + quantile_ord as f64 * 999.0 / (NUM_GRID_QUANTILES - 1) as f64)
+        .collect()
+}
+
+#[test]
+fn test_rewrite_percentiles_estimations() {
+    let aggregation_request = r#"{
+        "services": {
+            "terms": {"field": "service"},
+            "aggs": {
+                "latency": {
+                    "percentiles": {"field": "latency", "percents": [50, 99], "hdr": {}}
+                }
+            }
+        },
+        "latency": {"percentiles": {"field": "latency"}}
+    }"#;
+    let mut search_request = SearchRequest {
+        aggregation_request: Some(aggregation_request.to_string()),
+        ..Default::default()
+    };
+    let percentiles_estimations = rewrite_percentiles_estimations(&mut search_request).unwrap();
+    assert_eq!(
+        percentiles_estimations,
+        [PercentilesEstimation {
+            path: vec!["services".to_string(), "latency".to_string()],
+            method: EstimationMethod::Hdr {
+                num_significant_digits: 3
+            },
+            percents: vec![50.0, 99.0],
+            keyed: true,
+        }]
+    );
+    let aggs_json: JsonValue =
+        serde_json::from_str(search_request.aggregation_request.as_ref().unwrap()).unwrap();
+    let percentiles = &aggs_json["services"]["aggs"]["latency"]["percentiles"];
+    assert!(percentiles.get("hdr").is_none());
+    assert_eq!(percentiles["keyed"], false);
+    assert_eq!(
+        percentiles["percents"].as_array().unwrap().len(),
+        NUM_GRID_QUANTILES
+    );
+    // The percentiles aggregations without estimation options are left untouched.
+    assert!(
+        aggs_json["latency"]["percentiles"]
+            .get("percents")
+            .is_none()
+    );
+}
+
+#[test]
+fn test_rewrite_percentiles_estimations_invalid_options() {
+    for (percentiles_options, expected_error) in [
+        (r#""tdigest": {}, "hdr": {}"#, "cannot be set together"),
+        (r#""tdigest": {"compression": 0}"#, "must be positive"),
+        (
+            r#""hdr": {"number_of_significant_value_digits": 6}"#,
+            "must be between 0 and 5",
+        ),
+        (r#""hdr": {"digits": 3}"#, "invalid `hdr` options"),
+    ] {
+        let aggregation_request = format!(
+            r#"{{"latency": {{"percentiles": {{"field": "latency", {percentiles_options}}}}}}}"#
+        );
+        let mut search_request = SearchRequest {
+            aggregation_request: Some(aggregation_request),
+            ..Default::default()
+        };
+        let error = rewrite_percentiles_estimations(&mut search_request).unwrap_err();
+        let SearchError::InvalidAggregationRequest(error_message) = error else {
+            panic!("expected an invalid aggregation request error, got {error:?}");
+        };
+        assert!(error_message.contains(expected_error), "{error_message}");
+    }
+}
+
+#[test]
+fn test_tdigest_quantile() {
+    let grid = uniform_grid();
+
+    for compression in [10.0, 100.0, 1_000.0] {
+        let centroids = build_tdigest_centroids(&grid, compression);
+        assert!(centroids.len() as f64 <= compression);
+
+        for (quantile, exact_value) in [(0.0, 1.0), (0.5, 500.5), (0.99, 990.01), (1.0, 1000.0)] {
+            let estimated_value = tdigest_quantile(&centroids, 1.0, 1000.0, quantile);
+            assert!(
+                (estimated_value - exact_value).abs() <= 1.0,
+                "compression {compression}, quantile {quantile}: {estimated_value}"
+            );
+        }
+    }
+    // A lower compression merges the values into fewer centroids.
+    assert!(
+        build_tdigest_centroids(&grid, 10.0).len() < build_tdigest_centroids(&grid, 100.0).len()
+    );
+}
+
+#[test]
+fn test_hdr_highest_equivalent_value() {
+    assert_eq!(hdr_highest_equivalent_value(0.0, 3), 0.0);
+    // With no significant digit, the buckets are the powers of two.
+    assert_eq!(hdr_highest_equivalent_value(500.0, 0), 512.0);
+    assert_eq!(hdr_highest_equivalent_value(900.0, 0), 1024.0);
+    // With one significant digit, [256, 512) is divided into sub-buckets of width 16.
+    assert_eq!(hdr_highest_equivalent_value(500.0, 1), 512.0);
+    assert_eq!(hdr_highest_equivalent_value(300.0, 1), 304.0);
+    assert_eq!(hdr_highest_equivalent_value(-300.0, 1), -304.0);
+
+    for num_significant_digits in 0..=MAX_HDR_NUM_SIGNIFICANT_DIGITS {
+        for value in [0.5, 1.0, 3.0, 123.456, 1_000_000.0] {
+            let highest_equivalent_value =
+                hdr_highest_equivalent_value(value, num_significant_digits);
+            let relative_error = (highest_equivalent_value - value) / value;
+            assert!(relative_error > 0.0);
+            assert!(relative_error <= 10f64.powi(-(num_significant_digits as i32)));
+        }
+    }
+}
+
+#[test]
+fn test_apply_percentiles_estimations() {
+    let grid_entries: Vec<PercentileValuesVecEntry> = uniform_grid()
+        .into_iter()
+        .enumerate()
+        .map(|(quantile_ord, value)| PercentileValuesVecEntry {
+            key: quantile_ord as f64 / 10.0,
+            value,
+        })
+        .collect();
+    let mut aggregation_results = AggregationResults(vec![(
+        "latency".to_string(),
+        AggregationResult::MetricResult(MetricResult::Percentiles(
+            quickwit_query::aggregations::PercentilesMetricResult {
+                values: PercentileValues::Vec(grid_entries),
+            },
+        )),
+    )]);
+    let percentiles_estimations = [PercentilesEstimation {
+        path: vec!["latency".to_string()],
+        method: EstimationMethod::Hdr {
+            num_significant_digits: 0,
+        },
+        percents: vec![50.0, 99.0],
+        keyed: true,
+    }];
+    apply_percentiles_estimations(&mut aggregation_results, &percentiles_estimations);
+
+    let AggregationResult::MetricResult(MetricResult::Percentiles(percentiles)) =
+        &aggregation_results.0[0].1
+    else {
+        panic!("expected a percentiles result");
+    };
+    let PercentileValues::HashMap(values) = &percentiles.values else {
+        panic!("expected keyed percentiles");
+    };
+    assert_eq!(values.len(), 2);
+    assert_eq!(values["50.0"], 512.0);
+    assert_eq!(values["99.0"], 1024.0);
+}
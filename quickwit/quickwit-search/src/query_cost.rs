@@ -0,0 +1,183 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashSet;
+
+use quickwit_doc_mapper::WarmupInfo;
+use quickwit_metastore::SplitMetadata;
+use quickwit_proto::search::QueryCostEstimate;
+use tantivy::schema::Schema;
+
+use crate::SearchError;
+
+/// Adds the names of the fields read by a query or a collector, as reported by their warmup info,
+/// to `field_names`.
+pub(crate) fn extend_with_read_fields<'a>(
+    schema: &'a Schema,
+    warmup_info: &'a WarmupInfo,
+    field_names: &mut HashSet<&'a str>,
+) {
+    let fields = warmup_info
+        .term_dict_fields
+        .iter()
+        .chain(warmup_info.terms_grouped_by_field.keys())
+        .chain(warmup_info.term_ranges_grouped_by_field.keys())
+        .chain(warmup_info.automatons_grouped_by_field.keys());
+    for field in fields {
+        field_names.insert(schema.get_field_name(*field));
+    }
+    for fast_field in &warmup_info.fast_fields {
+        field_names.insert(&fast_field.name);
+    }
+}
+
+/// Estimates the cost of a query reading `num_fields` fields from the splits selected by the
+/// planning phase.
+///
+/// The cost is the number of splits to scan × the number of fields read × the estimated number of
+/// documents per split, i.e. the number of fields read × the number of documents of the splits.
+/// At least one field is accounted for, since even a query reading no field, such as a match-all
+/// query, scans the documents of the splits.
+pub(crate) fn estimate_query_cost(
+    num_fields: usize,
+    split_metadatas: &[SplitMetadata],
+) -> QueryCostEstimate {
+    let num_fields = num_fields.max(1) as u64;
+    let num_docs: u64 = split_metadatas
+        .iter()
+        .map(|split_metadata| split_metadata.num_docs as u64)
+        .sum();
+    QueryCostEstimate {
+        num_splits: split_metadatas.len() as u64,
+        num_fields,
+        num_docs,
+        cost: num_fields.saturating_mul(num_docs),
+    }
+}
+
+/// Rejects the query if its estimated cost exceeds the `searcher.max_query_cost` budget.
+pub(crate) fn check_query_cost(
+    cost_estimate: &QueryCostEstimate,
+    max_query_cost_opt: Option<u64>,
+) -> crate::Result<()> {
+    let Some(max_query_cost) = max_query_cost_opt else {
+        return Ok(());
+    };
+    if cost_estimate.cost <= max_query_cost {
+        return Ok(());
+    }
+    Err(SearchError::InvalidArgument(format!(
+        "estimated query cost {} ({} splits, {} fields, {} docs) exceeds the budget \
+         {max_query_cost} (`searcher.max_query_cost`)",
+        cost_estimate.cost,
+        cost_estimate.num_splits,
+        cost_estimate.num_fields,
+        cost_estimate.num_docs,
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_doc_mapper::FastFieldWarmupInfo;
+    use quickwit_proto::types::IndexUid;
+    use tantivy::schema::{FAST, STRING, TEXT};
+
+    use super::*;
+
+    fn split_metadata_for_test(split_id: &str, num_docs: usize) -> SplitMetadata {
+        SplitMetadata {
+            split_id: split_id.to_string(),
+            index_uid: IndexUid::for_test("test-index", 0),
+            num_docs,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_extend_with_read_fields() {
+        let mut schema_builder = Schema::builder();
+        let body_field = schema_builder.add_text_field("body", TEXT);
+        let service_field = schema_builder.add_text_field("service", STRING | FAST);
+        schema_builder.add_u64_field("latency", FAST);
+        let schema = schema_builder.build();
+
+        let query_warmup_info = WarmupInfo {
+            term_dict_fields: HashSet::from([body_field]),
+            terms_grouped_by_field: [(service_field, Default::default())].into_iter().collect(),
+            ..Default::default()
+        };
+        let collector_warmup_info = WarmupInfo {
+            fast_fields: HashSet::from([
+                FastFieldWarmupInfo {
+                    name: "latency".to_string(),
+                    with_subfields: false,
+                },
+                FastFieldWarmupInfo {
+                    name: "service".to_string(),
+                    with_subfields: false,
+                },
+            ]),
+            ..Default::default()
+        };
+        let mut field_names = HashSet::new();
+        extend_with_read_fields(&schema, &query_warmup_info, &mut field_names);
+        extend_with_read_fields(&schema, &collector_warmup_info, &mut field_names);
+        assert_eq!(field_names, HashSet::from(["body", "latency", "service"]));
+    }
+
+    #[test]
+    fn test_estimate_query_cost() {
+        let split_metadatas = vec![
+            split_metadata_for_test("split-1", 1_000),
+            split_metadata_for_test("split-2", 500),
+        ];
+        let cost_estimate = estimate_query_cost(3, &split_metadatas);
+        let expected_cost_estimate = QueryCostEstimate {
+            num_splits: 2,
+            num_fields: 3,
+            num_docs: 1_500,
+            cost: 4_500,
+        };
+        assert_eq!(cost_estimate, expected_cost_estimate);
+
+        let cost_estimate = estimate_query_cost(0, &split_metadatas);
+        assert_eq!(cost_estimate.num_fields, 1);
+        assert_eq!(cost_estimate.cost, 1_500);
+
+        let cost_estimate = estimate_query_cost(3, &[]);
+        assert_eq!(cost_estimate.cost, 0);
+    }
+
+    #[test]
+    fn test_check_query_cost() {
+        let cost_estimate = QueryCostEstimate {
+            num_splits: 2,
+            num_fields: 3,
+            num_docs: 1_500,
+            cost: 4_500,
+        };
+        check_query_cost(&cost_estimate, None).unwrap();
+        check_query_cost(&cost_estimate, Some(4_500)).unwrap();
+
+        let search_error = check_query_cost(&cost_estimate, Some(4_499)).unwrap_err();
+        let SearchError::InvalidArgument(error_message) = search_error else {
+            panic!("expected an invalid argument error, got `{search_error:?}`");
+        };
+        assert_eq!(
+            error_message,
+            "estimated query cost 4500 (2 splits, 3 fields, 1500 docs) exceeds the budget 4499 \
+             (`searcher.max_query_cost`)"
+        );
+    }
+}
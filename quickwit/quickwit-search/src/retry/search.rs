@@ -143,6 +143,7 @@ mod tests {
             error: "error".to_string(),
             split_id: "split_2".to_string(),
             retryable_error: true,
+            timed_out: false,
         };
         let response_res = Ok(LeafSearchResponse {
             num_hits: 0,
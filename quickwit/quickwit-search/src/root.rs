@@ -18,31 +18,35 @@ use std::sync::{Arc, LazyLock};
 use std::time::{Duration, Instant};
 
 use anyhow::Context;
+use bytesize::ByteSize;
 use futures::future::try_join_all;
 use itertools::Itertools;
 use quickwit_common::pretty::PrettySample;
 use quickwit_common::shared_consts;
 use quickwit_common::thread_pool::with_priority::Priority;
 use quickwit_common::uri::Uri;
-use quickwit_config::build_doc_mapper;
-use quickwit_doc_mapper::DYNAMIC_FIELD_NAME;
+use quickwit_config::{SearchSettings, build_doc_mapper, merge_response_headers};
 use quickwit_doc_mapper::tag_pruning::extract_tags_from_query;
-use quickwit_metastore::{IndexMetadata, ListIndexesMetadataResponseExt, SplitMetadata};
+use quickwit_doc_mapper::{DYNAMIC_FIELD_NAME, DocMapper, FieldMappingEntry};
+use quickwit_metastore::{
+    IndexIdMatcher, IndexMetadata, ListIndexesMetadataResponseExt, SplitMetadata,
+};
 use quickwit_proto::metastore::{
     ListIndexesMetadataRequest, MetastoreService, MetastoreServiceClient,
 };
 use quickwit_proto::search::{
     FetchDocsRequest, FetchDocsResponse, Hit, LeafHit, LeafRequestRef, LeafResourceStats,
-    LeafSearchRequest, LeafSearchResponse, PartialHit, RootResourceStats, SearchPlanResponse,
-    SearchRequest, SearchResponse, SnippetRequest, SortDatetimeFormat, SortField, SortValue,
-    SplitIdAndFooterOffsets,
+    LeafSearchRequest, LeafSearchResponse, PartialHit, QueryCostEstimate, RootResourceStats,
+    ScoreRequest, SearchPlanResponse, SearchRequest, SearchResolution, SearchResponse,
+    SnippetRequest, SortDatetimeFormat, SortField, SortValue, SplitIdAndFooterOffsets,
 };
-use quickwit_proto::types::{IndexUid, SplitId};
+use quickwit_proto::types::{IndexId, IndexUid, SplitId};
 use quickwit_query::query_ast::{
     BoolQuery, QueryAst, QueryAstVisitor, RangeQuery, TermQuery, TermSetQuery,
 };
+use quickwit_query::{BooleanOperand, Scoring};
 use serde::{Deserialize, Serialize};
-use tantivy::TantivyError;
+use tantivy::aggregation::AggregationLimitsGuard;
 use tantivy::aggregation::agg_result::AggregationResults;
 use tantivy::aggregation::intermediate_agg_result::IntermediateAggregationResults;
 use tantivy::collector::Collector;
@@ -52,12 +56,18 @@ use tracing::{debug, error, info, info_span, instrument};
 use crate::cluster_client::ClusterClient;
 use crate::collector::{QuickwitAggregations, make_merge_collector};
 use crate::metrics_trackers::{RootSearchMetricsFuture, RootSearchMetricsStep};
+use crate::missing_fields::{field_mappings_rendering_nulls, render_missing_fields_as_null};
+use crate::percentiles_estimation::{
+    PercentilesEstimation, apply_percentiles_estimations, rewrite_percentiles_estimations,
+};
+use crate::query_cost::{check_query_cost, estimate_query_cost, extend_with_read_fields};
 use crate::scroll_context::{ScrollContext, ScrollKeyAndStartOffset};
 use crate::search_job_placer::{Job, group_by, group_jobs_by_index_id};
 use crate::search_response_rest::StorageRequestCount;
 use crate::service::SearcherContext;
+use crate::sort_script::{SORT_SCRIPT_FIELD_NAME, SortScript};
 use crate::{
-    SearchError, SearchJobPlacer, SearchPlanResponseRest, SearchServiceClient,
+    SearchError, SearchJobPlacer, SearchPlanResponseRest, SearchPlanSplit, SearchServiceClient,
     extract_split_and_footer_offsets, list_relevant_splits,
 };
 
@@ -169,6 +179,66 @@ struct RequestMetadata {
     query_ast_resolved: QueryAst,
     indexes_meta_for_leaf_search: IndexesMetasForLeafSearch,
     sort_fields_is_datetime: HashMap<String, bool>,
+    num_read_fields: usize,
+}
+
+/// Parses the scoring of a search request, if any.
+pub(crate) fn parse_scoring(scoring_opt: Option<&str>) -> crate::Result<Option<Scoring>> {
+    scoring_opt
+        .map(str::parse::<Scoring>)
+        .transpose()
+        .map_err(|error| SearchError::InvalidArgument(error.to_string()))
+}
+
+/// Builds the doc mapper of each index targeted by a search.
+fn build_doc_mappers(indexes_metadata: &[IndexMetadata]) -> crate::Result<Vec<Arc<DocMapper>>> {
+    indexes_metadata
+        .iter()
+        .map(|index_metadata| {
+            build_doc_mapper(
+                &index_metadata.index_config.doc_mapping,
+                &index_metadata.index_config.search_settings,
+            )
+            .map_err(|err| {
+                SearchError::Internal(format!("failed to build doc mapper. cause: {err}"))
+            })
+        })
+        .collect()
+}
+
+/// Resolves the user input queries of a query AST against the default search fields of each
+/// targeted index, and ensures that the resolved query ASTs are the same across indexes.
+fn resolve_query_ast(
+    query_ast: &QueryAst,
+    doc_mappers: &[Arc<DocMapper>],
+) -> crate::Result<QueryAst> {
+    let mut query_ast_resolved_opt: Option<QueryAst> = None;
+
+    for doc_mapper in doc_mappers {
+        let query_ast_resolved_for_index = query_ast
+            .clone()
+            .parse_user_query(doc_mapper.default_search_fields())
+            // We convert the error to return a 400 to the user (and not a 500).
+            .map_err(|err| SearchError::InvalidQuery(err.to_string()))?;
+
+        // Validate uniqueness of resolved query AST.
+        if let Some(query_ast_resolved) = &query_ast_resolved_opt {
+            if query_ast_resolved != &query_ast_resolved_for_index {
+                return Err(SearchError::InvalidQuery(
+                    "resolved query ASTs must be the same across indexes. resolving queries with \
+                     different default fields are different between indexes is not supported"
+                        .to_string(),
+                ));
+            }
+        } else {
+            query_ast_resolved_opt = Some(query_ast_resolved_for_index);
+        }
+    }
+    query_ast_resolved_opt.ok_or_else(|| {
+        SearchError::Internal(
+            "resolved query AST must be present. this should never happen".to_string(),
+        )
+    })
 }
 
 /// Validates request against each index's doc mapper and ensures that:
@@ -177,13 +247,28 @@ struct RequestMetadata {
 /// - if a sort field is of type datetime, it must be a datetime field on all indexes. This
 ///   constraint come from the need to support datetime formatting on sort values.
 ///
-/// Returns the timestamp field, the resolved query AST and the indexes metadatas
-/// needed for leaf search requests.
+/// Returns the timestamp field, the resolved query AST, the indexes metadatas
+/// needed for leaf search requests, and the number of fields read by the request.
 /// Note: the requirements on timestamp fields and resolved query ASTs can be lifted
 /// but it adds complexity that does not seem needed right now.
 fn validate_request_and_build_metadata(
     indexes_metadata: &[IndexMetadata],
     search_request: &SearchRequest,
+) -> crate::Result<RequestMetadata> {
+    let doc_mappers = build_doc_mappers(indexes_metadata)?;
+    validate_request_and_build_metadata_with_doc_mappers(
+        indexes_metadata,
+        &doc_mappers,
+        search_request,
+    )
+}
+
+/// Same as [`validate_request_and_build_metadata`], with the doc mappers of the indexes already
+/// built.
+fn validate_request_and_build_metadata_with_doc_mappers(
+    indexes_metadata: &[IndexMetadata],
+    doc_mappers: &[Arc<DocMapper>],
+    search_request: &SearchRequest,
 ) -> crate::Result<RequestMetadata> {
     validate_sort_by_fields_and_search_after(
         &search_request.sort_fields,
@@ -191,39 +276,15 @@ fn validate_request_and_build_metadata(
     )?;
     let query_ast: QueryAst = serde_json::from_str(&search_request.query_ast)
         .map_err(|err| SearchError::InvalidQuery(err.to_string()))?;
+    let query_ast_resolved = resolve_query_ast(&query_ast, doc_mappers)?;
+    let scoring_opt = parse_scoring(search_request.scoring.as_deref())?;
     let mut indexes_meta_for_leaf_search: HashMap<IndexUid, IndexMetasForLeafSearch> =
         HashMap::new();
-    let mut query_ast_resolved_opt: Option<QueryAst> = None;
     let mut timestamp_field_opt: Option<String> = None;
     let mut sort_fields_is_datetime: HashMap<String, bool> = HashMap::new();
+    let mut num_read_fields: usize = 0;
 
-    for index_metadata in indexes_metadata {
-        let doc_mapper = build_doc_mapper(
-            &index_metadata.index_config.doc_mapping,
-            &index_metadata.index_config.search_settings,
-        )
-        .map_err(|err| {
-            SearchError::Internal(format!("failed to build doc mapper. cause: {err}"))
-        })?;
-        let query_ast_resolved_for_index = query_ast
-            .clone()
-            .parse_user_query(doc_mapper.default_search_fields())
-            // We convert the error to return a 400 to the user (and not a 500).
-            .map_err(|err| SearchError::InvalidQuery(err.to_string()))?;
-
-        // Validate uniqueness of resolved query AST.
-        if let Some(query_ast_resolved) = &query_ast_resolved_opt {
-            if query_ast_resolved != &query_ast_resolved_for_index {
-                return Err(SearchError::InvalidQuery(
-                    "resolved query ASTs must be the same across indexes. resolving queries with \
-                     different default fields are different between indexes is not supported"
-                        .to_string(),
-                ));
-            }
-        } else {
-            query_ast_resolved_opt = Some(query_ast_resolved_for_index.clone());
-        }
-
+    for (index_metadata, doc_mapper) in indexes_metadata.iter().zip(doc_mappers) {
         // Validate uniqueness of timestamp field if any.
         if let Some(timestamp_field_for_index) = doc_mapper.timestamp_field_name() {
             match timestamp_field_opt {
@@ -251,16 +312,23 @@ fn validate_request_and_build_metadata(
         )?;
 
         // Validates the query by effectively building it against the current schema.
-        doc_mapper.query(
+        let (_query, query_warmup_info) = doc_mapper.query(
             doc_mapper.schema(),
-            query_ast_resolved_for_index,
+            query_ast_resolved.clone(),
             true,
             None,
+            scoring_opt,
         )?;
+        let collector_warmup_info =
+            make_merge_collector(search_request, Default::default())?.warmup_info();
+        let mut read_field_names = HashSet::new();
+        extend_with_read_fields(&schema, &query_warmup_info, &mut read_field_names);
+        extend_with_read_fields(&schema, &collector_warmup_info, &mut read_field_names);
+        num_read_fields = num_read_fields.max(read_field_names.len());
 
         let index_metadata_for_leaf_search = IndexMetasForLeafSearch {
             index_uri: index_metadata.index_uri().clone(),
-            doc_mapper_str: serde_json::to_string(&doc_mapper).map_err(|err| {
+            doc_mapper_str: serde_json::to_string(doc_mapper).map_err(|err| {
                 SearchError::Internal(format!("failed to serialize doc mapper. cause: {err}"))
             })?,
         };
@@ -270,17 +338,12 @@ fn validate_request_and_build_metadata(
         );
     }
 
-    let query_ast_resolved = query_ast_resolved_opt.ok_or_else(|| {
-        SearchError::Internal(
-            "resolved query AST must be present. this should never happen".to_string(),
-        )
-    })?;
-
     Ok(RequestMetadata {
         timestamp_field_opt,
         query_ast_resolved,
         indexes_meta_for_leaf_search,
         sort_fields_is_datetime,
+        num_read_fields,
     })
 }
 
@@ -291,6 +354,11 @@ fn validate_sort_field_types(
     sort_field_is_datetime: &mut HashMap<String, bool>,
 ) -> crate::Result<()> {
     for sort_field in sort_fields.iter() {
+        if sort_field.field_name == SORT_SCRIPT_FIELD_NAME || sort_field.script.is_some() {
+            validate_sort_script(schema, sort_field)?;
+            sort_field_is_datetime.insert(sort_field.field_name.to_string(), false);
+            continue;
+        }
         if let Some(sort_field_entry) = get_sort_by_field_entry(&sort_field.field_name, schema)? {
             validate_sort_by_field_type(
                 sort_field_entry,
@@ -317,6 +385,52 @@ fn validate_sort_field_types(
     Ok(())
 }
 
+/// Validates a sort script: it must parse and only reference numeric fast fields.
+fn validate_sort_script(schema: &Schema, sort_field: &SortField) -> crate::Result<()> {
+    if sort_field.field_name != SORT_SCRIPT_FIELD_NAME {
+        return Err(SearchError::InvalidArgument(format!(
+            "a sort script can only be used with the `{SORT_SCRIPT_FIELD_NAME}` sort field, got \
+             `{}`",
+            sort_field.field_name
+        )));
+    }
+    let Some(script) = sort_field.script.as_deref() else {
+        return Err(SearchError::InvalidArgument(format!(
+            "sort by `{SORT_SCRIPT_FIELD_NAME}` requires a sort script"
+        )));
+    };
+    if sort_field.sort_datetime_format.is_some() {
+        return Err(SearchError::InvalidArgument(
+            "sort by script does not support a datetime format".to_string(),
+        ));
+    }
+    let sort_script = SortScript::parse(script)
+        .map_err(|error| SearchError::InvalidArgument(format!("invalid sort script: {error}")))?;
+
+    for field_name in sort_script.field_names() {
+        let Some(field_entry) = get_sort_by_field_entry(field_name, schema)? else {
+            return Err(SearchError::InvalidArgument(format!(
+                "field `{field_name}` cannot be used in a sort script"
+            )));
+        };
+        let is_numeric = matches!(
+            field_entry.field_type(),
+            FieldType::U64(_)
+                | FieldType::I64(_)
+                | FieldType::F64(_)
+                | FieldType::Bool(_)
+                | FieldType::Date(_)
+                | FieldType::JsonObject(_)
+        );
+        if !is_numeric || !field_entry.is_fast() {
+            return Err(SearchError::InvalidArgument(format!(
+                "sort script field `{field_name}` must be a numeric fast field"
+            )));
+        }
+    }
+    Ok(())
+}
+
 fn validate_requested_snippet_fields(
     schema: &Schema,
     snippet_fields: &[String],
@@ -375,6 +489,22 @@ fn simplify_search_request_for_scroll_api(req: &SearchRequest) -> crate::Result<
         count_hits: quickwit_proto::search::CountHits::Underestimate as i32,
         ignore_missing_indexes: req.ignore_missing_indexes,
         skip_aggregation_finalization: false,
+        skip_fetch_docs: req.skip_fetch_docs,
+        min_score: req.min_score,
+        explain: false,
+        read_preference: req.read_preference,
+        explain_scores: req.explain_scores,
+        per_split_timeout_millis: req.per_split_timeout_millis,
+        timeout_millis: req.timeout_millis,
+        max_aggregation_buckets: req.max_aggregation_buckets,
+        scoring: req.scoring.clone(),
+        missing_fields_as_null: req.missing_fields_as_null,
+        bypass_cache: req.bypass_cache,
+        track_scores: req.track_scores,
+        inherit_default_operator: req.inherit_default_operator,
+        inherit_default_max_hits: req.inherit_default_max_hits,
+        ignore_max_indexes_per_search: req.ignore_max_indexes_per_search,
+        explain_resolution: false,
     })
 }
 
@@ -564,16 +694,24 @@ fn get_scroll_ttl_duration(search_request: &SearchRequest) -> crate::Result<Opti
     Ok(Some(scroll_ttl))
 }
 
+/// A scroll context that is saved once the hits of the first page are fetched, so that the next
+/// page starts right after the last hit actually returned.
+struct PendingScroll {
+    scroll_ctx: ScrollContext,
+    scroll_ttl: Duration,
+}
+
 #[instrument(level = "debug", skip_all)]
 async fn search_partial_hits_phase_with_scroll(
     searcher_context: &SearcherContext,
     indexes_metas_for_leaf_search: &IndexesMetasForLeafSearch,
     mut search_request: SearchRequest,
     split_metadatas: &[SplitMetadata],
+    max_response_size_opt: Option<ByteSize>,
     cluster_client: &ClusterClient,
 ) -> crate::Result<(
     LeafSearchResponse,
-    Option<ScrollKeyAndStartOffset>,
+    Option<PendingScroll>,
     Option<RootResourceStats>,
 )> {
     let scroll_ttl_opt = get_scroll_ttl_duration(&search_request)?;
@@ -597,15 +735,10 @@ async fn search_partial_hits_phase_with_scroll(
         .await?;
         let cached_partial_hits = leaf_search_resp.partial_hits.clone();
         leaf_search_resp.partial_hits.truncate(max_hits as usize);
-        let last_hit = leaf_search_resp
-            .partial_hits
-            .last()
-            .cloned()
-            .unwrap_or_default();
 
         let scroll_context_search_request =
             simplify_search_request_for_scroll_api(&search_request)?;
-        let mut scroll_ctx = ScrollContext {
+        let scroll_ctx = ScrollContext {
             indexes_metas_for_leaf_search: indexes_metas_for_leaf_search.clone(),
             split_metadatas: split_metadatas.to_vec(),
             search_request: scroll_context_search_request,
@@ -615,26 +748,13 @@ async fn search_partial_hits_phase_with_scroll(
             cached_partial_hits,
             failed_splits: leaf_search_resp.failed_splits.clone(),
             num_successful_splits: leaf_search_resp.num_successful_splits,
+            max_response_size: max_response_size_opt,
         };
-        let scroll_key_and_start_offset: ScrollKeyAndStartOffset =
-            ScrollKeyAndStartOffset::new_with_start_offset(
-                scroll_ctx.search_request.start_offset,
-                max_hits as u32,
-                last_hit.clone(),
-            )
-            .next_page(leaf_search_resp.partial_hits.len() as u64, last_hit);
-
-        scroll_ctx.clear_cache_if_unneeded();
-        let payload: Vec<u8> = scroll_ctx.serialize();
-        let scroll_key = scroll_key_and_start_offset.scroll_key();
-        cluster_client
-            .put_kv(&scroll_key, &payload, scroll_ttl)
-            .await;
-        Ok((
-            leaf_search_resp,
-            Some(scroll_key_and_start_offset),
-            root_resource_stats,
-        ))
+        let pending_scroll = PendingScroll {
+            scroll_ctx,
+            scroll_ttl,
+        };
+        Ok((leaf_search_resp, Some(pending_scroll), root_resource_stats))
     } else {
         let (leaf_search_resp, root_resource_stats) = search_partial_hits_phase(
             searcher_context,
@@ -648,6 +768,44 @@ async fn search_partial_hits_phase_with_scroll(
     }
 }
 
+/// Saves the scroll context of a first page made of the first `num_returned_hits` partial hits,
+/// and returns the scroll key of the next page.
+async fn save_scroll_context(
+    pending_scroll: PendingScroll,
+    partial_hits: &[PartialHit],
+    num_returned_hits: usize,
+    cluster_client: &ClusterClient,
+) -> ScrollKeyAndStartOffset {
+    let PendingScroll {
+        mut scroll_ctx,
+        scroll_ttl,
+    } = pending_scroll;
+    let last_returned_hit_opt = num_returned_hits
+        .checked_sub(1)
+        .map(|last_hit_idx| partial_hits[last_hit_idx].clone());
+    let scroll_key_and_start_offset = ScrollKeyAndStartOffset::new_with_start_offset(
+        scroll_ctx.search_request.start_offset,
+        scroll_ctx.max_hits_per_page as u32,
+        last_returned_hit_opt.clone().unwrap_or_default(),
+    );
+    let scroll_key_and_start_offset = if num_returned_hits < partial_hits.len() {
+        scroll_key_and_start_offset
+            .next_truncated_page(num_returned_hits as u64, last_returned_hit_opt)
+    } else {
+        scroll_key_and_start_offset.next_page(
+            num_returned_hits as u64,
+            last_returned_hit_opt.unwrap_or_default(),
+        )
+    };
+    scroll_ctx.clear_cache_if_unneeded();
+    let payload: Vec<u8> = scroll_ctx.serialize();
+    let scroll_key = scroll_key_and_start_offset.scroll_key();
+    cluster_client
+        .put_kv(&scroll_key, &payload, scroll_ttl)
+        .await;
+    scroll_key_and_start_offset
+}
+
 /// Check if the request is a count request without any filters, so we can just return the split
 /// metadata count.
 ///
@@ -683,6 +841,9 @@ pub fn is_metadata_count_request_with_ast(query_ast: &QueryAst, request: &Search
     if request.aggregation_request.is_some() || !request.snippet_fields.is_empty() {
         return false;
     }
+    if request.min_score.is_some() {
+        return false;
+    }
     true
 }
 
@@ -855,7 +1016,7 @@ pub(crate) async fn search_partial_hits_phase(
         })
         .await
         .context("failed to merge leaf search responses")?
-        .map_err(|error: TantivyError| crate::SearchError::Internal(error.to_string()))?;
+        .map_err(crate::SearchError::from)?;
     debug!(
         num_hits = leaf_search_response.num_hits,
         num_failed_splits = leaf_search_response.failed_splits.len(),
@@ -898,6 +1059,16 @@ pub(crate) fn get_snippet_request(search_request: &SearchRequest) -> Option<Snip
     })
 }
 
+pub(crate) fn get_score_request(search_request: &SearchRequest) -> Option<ScoreRequest> {
+    if !search_request.explain_scores && !search_request.track_scores {
+        return None;
+    }
+    Some(ScoreRequest {
+        query_ast_resolved: search_request.query_ast.clone(),
+        scoring: search_request.scoring.clone(),
+    })
+}
+
 #[instrument(skip_all, fields(partial_hits_num=partial_hits.len()))]
 pub(crate) async fn fetch_docs_phase(
     indexes_metas_for_leaf_search: &IndexesMetasForLeafSearch,
@@ -907,6 +1078,7 @@ pub(crate) async fn fetch_docs_phase(
     cluster_client: &ClusterClient,
 ) -> crate::Result<Vec<Hit>> {
     let snippet_request: Option<SnippetRequest> = get_snippet_request(search_request);
+    let score_request: Option<ScoreRequest> = get_score_request(search_request);
     let hit_order: HashMap<(String, u32, u32), usize> = partial_hits
         .iter()
         .enumerate()
@@ -920,30 +1092,46 @@ pub(crate) async fn fetch_docs_phase(
         })
         .collect();
 
-    let assigned_fetch_docs_jobs = assign_client_fetch_docs_jobs(
-        partial_hits,
-        split_metadatas,
-        &cluster_client.search_job_placer,
-    )
-    .await?;
+    let leaf_hits: Vec<LeafHit> = if search_request.skip_fetch_docs {
+        // The hits are built from the partial hits alone, leaving their content empty.
+        partial_hits
+            .iter()
+            .map(|partial_hit| LeafHit {
+                leaf_json: String::new(),
+                partial_hit: Some(partial_hit.clone()),
+                leaf_snippet_json: None,
+                scores: None,
+            })
+            .collect()
+    } else {
+        let assigned_fetch_docs_jobs = assign_client_fetch_docs_jobs(
+            partial_hits,
+            split_metadatas,
+            &cluster_client.search_job_placer,
+        )
+        .await?;
 
-    let mut fetch_docs_tasks = Vec::new();
-    for (client, client_jobs) in assigned_fetch_docs_jobs {
-        let fetch_jobs_requests = jobs_to_fetch_docs_requests(
-            snippet_request.clone(),
-            indexes_metas_for_leaf_search,
-            client_jobs,
-        )?;
-        for fetch_docs_request in fetch_jobs_requests {
-            fetch_docs_tasks.push(cluster_client.fetch_docs(fetch_docs_request, client.clone()));
+        let mut fetch_docs_tasks = Vec::new();
+        for (client, client_jobs) in assigned_fetch_docs_jobs {
+            let fetch_jobs_requests = jobs_to_fetch_docs_requests(
+                snippet_request.clone(),
+                score_request.clone(),
+                indexes_metas_for_leaf_search,
+                client_jobs,
+            )?;
+            for fetch_docs_request in fetch_jobs_requests {
+                fetch_docs_tasks
+                    .push(cluster_client.fetch_docs(fetch_docs_request, client.clone()));
+            }
         }
-    }
-    let fetch_docs_responses: Vec<FetchDocsResponse> = try_join_all(fetch_docs_tasks).await?;
+        let fetch_docs_responses: Vec<FetchDocsResponse> = try_join_all(fetch_docs_tasks).await?;
 
-    // Merge the fetched docs.
-    let leaf_hits = fetch_docs_responses
-        .into_iter()
-        .flat_map(|response| response.hits.into_iter());
+        // Merge the fetched docs.
+        fetch_docs_responses
+            .into_iter()
+            .flat_map(|response| response.hits.into_iter())
+            .collect()
+    };
 
     // Build map of Split ID > index ID to add the index ID to the hits.
     // Used for ES compatibility.
@@ -962,6 +1150,7 @@ pub(crate) async fn fetch_docs_phase(
     let sort_field_2_datetime_format_opt: Option<SortDatetimeFormat> =
         get_sort_field_datetime_format(sort_field_iter.next())?;
     let mut hits_with_position: Vec<(usize, Hit)> = leaf_hits
+        .into_iter()
         .map(|leaf_hit| {
             build_hit_with_position(
                 leaf_hit,
@@ -982,6 +1171,78 @@ pub(crate) async fn fetch_docs_phase(
     Ok(hits)
 }
 
+/// Number of hits fetched by the first fetch docs round when the targeted indexes declare a
+/// maximum response size.
+const FIRST_FETCH_DOCS_BATCH_LEN: usize = 16;
+
+/// Fetches the documents of the partial hits in order, in rounds, and stops fetching once the
+/// total size of the fetched documents and snippets exceeds `max_response_size`. Returns the hits
+/// fitting within the limit, and the reason of the truncation if some hits were left out.
+pub(crate) async fn fetch_docs_phase_within_max_response_size(
+    indexes_metas_for_leaf_search: &IndexesMetasForLeafSearch,
+    partial_hits: &[PartialHit],
+    split_metadatas: &[SplitMetadata],
+    search_request: &SearchRequest,
+    max_response_size_opt: Option<ByteSize>,
+    cluster_client: &ClusterClient,
+) -> crate::Result<(Vec<Hit>, Option<String>)> {
+    let Some(max_response_size) = max_response_size_opt else {
+        let hits = fetch_docs_phase(
+            indexes_metas_for_leaf_search,
+            partial_hits,
+            split_metadatas,
+            search_request,
+            cluster_client,
+        )
+        .await?;
+        return Ok((hits, None));
+    };
+    let num_hits = partial_hits.len();
+    let mut hits = Vec::with_capacity(num_hits);
+    let mut response_size: u64 = 0;
+    let mut fetch_start = 0;
+    let mut num_hits_to_fetch = FIRST_FETCH_DOCS_BATCH_LEN;
+
+    while fetch_start < num_hits {
+        let fetch_end = (fetch_start + num_hits_to_fetch).min(num_hits);
+        let fetched_hits = fetch_docs_phase(
+            indexes_metas_for_leaf_search,
+            &partial_hits[fetch_start..fetch_end],
+            split_metadatas,
+            search_request,
+            cluster_client,
+        )
+        .await?;
+        fetch_start = fetch_end;
+
+        for hit in fetched_hits {
+            response_size += hit_size(&hit);
+
+            if response_size > max_response_size.as_u64() {
+                let truncation_reason = format!(
+                    "hits exceed the maximum response size of {max_response_size}: returned {} \
+                     out of {num_hits} hits",
+                    hits.len()
+                );
+                return Ok((hits, Some(truncation_reason)));
+            }
+            hits.push(hit);
+        }
+        // The next round fetches as many hits as are expected to fit in the remaining budget,
+        // based on the average size of the hits fetched so far, plus one to detect the truncation.
+        let average_hit_size = (response_size / hits.len().max(1) as u64).max(1);
+        let remaining_budget = max_response_size.as_u64() - response_size;
+        num_hits_to_fetch = (remaining_budget / average_hit_size) as usize + 1;
+    }
+    Ok((hits, None))
+}
+
+/// Size of the document and of the snippet of a hit.
+fn hit_size(hit: &Hit) -> u64 {
+    let snippet_size = hit.snippet.as_ref().map(String::len).unwrap_or_default();
+    (hit.json.len() + snippet_size) as u64
+}
+
 fn build_hit_with_position(
     mut leaf_hit: LeafHit,
     split_id_to_index_id_map: &HashMap<SplitId, &str>,
@@ -1029,6 +1290,7 @@ fn build_hit_with_position(
             partial_hit: leaf_hit.partial_hit,
             snippet: leaf_hit.leaf_snippet_json,
             index_id,
+            scores: leaf_hit.scores,
         },
     ))
 }
@@ -1056,20 +1318,23 @@ async fn root_search_aux(
     searcher_context: &SearcherContext,
     indexes_metas_for_leaf_search: &IndexesMetasForLeafSearch,
     search_request: SearchRequest,
+    percentiles_estimations: &[PercentilesEstimation],
     split_metadatas: Vec<SplitMetadata>,
+    max_response_size_opt: Option<ByteSize>,
     cluster_client: &ClusterClient,
 ) -> crate::Result<SearchResponse> {
     debug!(split_metadatas = ?PrettySample::new(&split_metadatas, 5));
     let start = Instant::now();
-    let (first_phase_result, scroll_key_and_start_offset_opt, mut root_resource_stats_opt): (
+    let (first_phase_result, pending_scroll_opt, mut root_resource_stats_opt): (
         LeafSearchResponse,
-        Option<ScrollKeyAndStartOffset>,
+        Option<PendingScroll>,
         Option<RootResourceStats>,
     ) = search_partial_hits_phase_with_scroll(
         searcher_context,
         indexes_metas_for_leaf_search,
         search_request.clone(),
         &split_metadatas[..],
+        max_response_size_opt,
         cluster_client,
     )
     .await?;
@@ -1079,17 +1344,34 @@ async fn root_search_aux(
             start.elapsed().as_micros() as u64;
     }
 
-    let hits = fetch_docs_phase(
+    let (hits, truncation_reason_opt) = fetch_docs_phase_within_max_response_size(
         indexes_metas_for_leaf_search,
         &first_phase_result.partial_hits,
         &split_metadatas[..],
         &search_request,
+        max_response_size_opt,
         cluster_client,
     )
     .await?;
 
+    // The scroll context is saved after the hits are truncated, so that the next page starts
+    // right after the last returned hit.
+    let scroll_key_and_start_offset_opt = if let Some(pending_scroll) = pending_scroll_opt {
+        let scroll_key_and_start_offset = save_scroll_context(
+            pending_scroll,
+            &first_phase_result.partial_hits,
+            hits.len(),
+            cluster_client,
+        )
+        .await;
+        Some(scroll_key_and_start_offset)
+    } else {
+        None
+    };
+
     let mut aggregation_result_postcard_opt = finalize_aggregation_if_any(
         &search_request,
+        percentiles_estimations,
         first_phase_result.intermediate_aggregation_result,
         searcher_context,
     )?;
@@ -1114,13 +1396,20 @@ async fn root_search_aux(
         failed_splits: first_phase_result.failed_splits,
         num_successful_splits: first_phase_result.num_successful_splits,
         resource_stats: root_resource_stats_opt,
+        response_headers: HashMap::new(),
+        cost_estimate: None,
+        truncation_reason: truncation_reason_opt,
+        resolution: None,
+        searched_index_ids: Vec::new(),
     })
 }
 
 fn finalize_aggregation(
     intermediate_aggregation_result_bytes_opt: Option<Vec<u8>>,
     aggregations: QuickwitAggregations,
-    searcher_context: &SearcherContext,
+    percentiles_estimations: &[PercentilesEstimation],
+    aggregation_limits: AggregationLimitsGuard,
+    max_aggregation_buckets_opt: Option<u32>,
 ) -> crate::Result<Option<Vec<u8>>> {
     let merge_aggregation_result = match aggregations {
         QuickwitAggregations::FindTraceIdsAggregation(_) => {
@@ -1140,17 +1429,78 @@ fn finalize_aggregation(
                     Default::default()
                 };
             let final_aggregation_results: AggregationResults = intermediate_aggregation_results
-                .into_final_result(aggregations, searcher_context.get_aggregation_limits())?;
-            let final_aggregation_proxy: quickwit_query::aggregations::AggregationResults =
+                .into_final_result(aggregations, aggregation_limits)?;
+            let mut final_aggregation_proxy: quickwit_query::aggregations::AggregationResults =
                 final_aggregation_results.into();
+            apply_percentiles_estimations(&mut final_aggregation_proxy, percentiles_estimations);
+            // The aggregation limits are shared by all the requests of the node, so a stricter
+            // bucket limit is checked on the final buckets.
+            if let Some(max_aggregation_buckets) = max_aggregation_buckets_opt {
+                let num_buckets = count_buckets(&final_aggregation_proxy);
+
+                if num_buckets > max_aggregation_buckets as usize {
+                    return Err(SearchError::InvalidAggregationRequest(format!(
+                        "aggregation created {num_buckets} buckets, exceeding the maximum of \
+                         {max_aggregation_buckets} buckets"
+                    )));
+                }
+            }
             postcard::to_stdvec(&final_aggregation_proxy)?
         }
     };
     Ok(Some(merge_aggregation_result))
 }
 
+/// Counts the buckets of aggregation results, including the buckets of their sub-aggregations.
+fn count_buckets(aggregation_results: &quickwit_query::aggregations::AggregationResults) -> usize {
+    use quickwit_query::aggregations::{AggregationResult, BucketEntries, BucketResult};
+
+    let count_entries = |bucket_entries: Vec<&quickwit_query::aggregations::AggregationResults>| {
+        bucket_entries
+            .into_iter()
+            .map(|sub_aggregation| 1 + count_buckets(sub_aggregation))
+            .sum::<usize>()
+    };
+    let mut num_buckets = 0;
+
+    for (_name, aggregation_result) in &aggregation_results.0 {
+        let AggregationResult::BucketResult(bucket_result) = aggregation_result else {
+            continue;
+        };
+        let sub_aggregations = match bucket_result {
+            BucketResult::Range {
+                buckets: BucketEntries::Vec(entries),
+            } => entries.iter().map(|entry| &entry.sub_aggregation).collect(),
+            BucketResult::Range {
+                buckets: BucketEntries::HashMap(entries),
+            } => entries
+                .iter()
+                .map(|(_key, entry)| &entry.sub_aggregation)
+                .collect(),
+            BucketResult::Histogram {
+                buckets: BucketEntries::Vec(entries),
+            } => entries.iter().map(|entry| &entry.sub_aggregation).collect(),
+            BucketResult::Histogram {
+                buckets: BucketEntries::HashMap(entries),
+            } => entries
+                .iter()
+                .map(|(_key, entry)| &entry.sub_aggregation)
+                .collect(),
+            BucketResult::Terms { buckets, .. } => {
+                buckets.iter().map(|entry| &entry.sub_aggregation).collect()
+            }
+            BucketResult::Composite { buckets, .. } => {
+                buckets.iter().map(|entry| &entry.sub_aggregation).collect()
+            }
+        };
+        num_buckets += count_entries(sub_aggregations);
+    }
+    num_buckets
+}
+
 fn finalize_aggregation_if_any(
     search_request: &SearchRequest,
+    percentiles_estimations: &[PercentilesEstimation],
     intermediate_aggregation_result_bytes_opt: Option<Vec<u8>>,
     searcher_context: &SearcherContext,
 ) -> crate::Result<Option<Vec<u8>>> {
@@ -1164,7 +1514,9 @@ fn finalize_aggregation_if_any(
     let aggregation_result_postcard = finalize_aggregation(
         intermediate_aggregation_result_bytes_opt,
         aggregations,
-        searcher_context,
+        percentiles_estimations,
+        searcher_context.get_aggregation_limits(),
+        searcher_context.get_max_aggregation_buckets_for_request(search_request),
     )?;
     Ok(aggregation_result_postcard)
 }
@@ -1172,7 +1524,8 @@ fn finalize_aggregation_if_any(
 /// Checks that all of the index researched as found.
 ///
 /// An index pattern (= containing a wildcard) not matching is not an error.
-/// A specific index id however must be found.
+/// A specific index id however must be found, unless a negative pattern (= starting with `-`)
+/// excludes it.
 ///
 /// We put this check here and not in the metastore to make sure the logic is independent
 /// of the metastore implementation, and some different use cases could require different
@@ -1191,6 +1544,13 @@ pub fn ensure_all_indexes_found(
         // All the patterns are wildcard or negative patterns.
         return Ok(());
     }
+    // A specific index ID that the patterns do not match is excluded by a negative pattern.
+    let index_id_matcher = IndexIdMatcher::try_from_index_id_patterns(index_id_patterns)?;
+    index_ids.retain(|index_id| index_id_matcher.is_match(index_id));
+
+    if index_ids.is_empty() {
+        return Ok(());
+    }
     for index_metadata in indexes_metadata {
         index_ids.remove(index_metadata.index_id());
     }
@@ -1207,70 +1567,331 @@ pub fn ensure_all_indexes_found(
     })
 }
 
-async fn refine_and_list_matches(
-    metastore: &MetastoreServiceClient,
-    search_request: &mut SearchRequest,
-    indexes_metadata: Vec<IndexMetadata>,
-    query_ast_resolved: QueryAst,
-    sort_fields_is_datetime: HashMap<String, bool>,
-    timestamp_field_opt: Option<String>,
-) -> crate::Result<Vec<SplitMetadata>> {
-    let index_uids = indexes_metadata
+/// Returns the value of a search setting shared by all the targeted indexes, if any.
+fn common_search_setting<T: PartialEq>(
+    indexes_metadata: &[IndexMetadata],
+    search_setting_fn: impl Fn(&SearchSettings) -> Option<T>,
+) -> Option<T> {
+    let mut search_settings = indexes_metadata
         .iter()
-        .map(|index_metadata| index_metadata.index_uid.clone())
-        .collect_vec();
-    search_request.query_ast = serde_json::to_string(&query_ast_resolved)?;
+        .map(|index_metadata| search_setting_fn(&index_metadata.index_config.search_settings));
+    let search_setting = search_settings.next()??;
 
-    // convert search_after datetime values from input datetime format to nanos.
-    convert_search_after_datetime_values(search_request, &sort_fields_is_datetime)?;
-
-    // update_search_after_datetime_in_nanos(&mut search_request)?;
-    if let Some(timestamp_field) = &timestamp_field_opt {
-        refine_start_end_timestamp_from_ast(
-            &query_ast_resolved,
-            timestamp_field,
-            &mut search_request.start_timestamp,
-            &mut search_request.end_timestamp,
-        );
+    if search_settings.any(|other_setting| other_setting.as_ref() != Some(&search_setting)) {
+        return None;
     }
-    let tag_filter_ast = extract_tags_from_query(query_ast_resolved);
-
-    // TODO if search after is set, we sort by timestamp and we don't want to count all results,
-    // we can refine more here. Same if we sort by _shard_doc
-    let split_metadatas: Vec<SplitMetadata> = list_relevant_splits(
-        index_uids,
-        search_request.start_timestamp,
-        search_request.end_timestamp,
-        tag_filter_ast,
-        metastore,
-    )
-    .await?;
-    Ok(split_metadatas)
+    Some(search_setting)
 }
 
-/// Fetches the list of splits and their metadata from the metastore
-async fn plan_splits_for_root_search(
+/// Applies the query defaults of the targeted indexes, i.e. the default operator of the user
+/// queries and the default number of hits, to a search request that does not set them. A default
+/// is only applied if all the targeted indexes declare the same one.
+fn apply_query_defaults(
+    indexes_metadata: &[IndexMetadata],
     search_request: &mut SearchRequest,
-    metastore: &MetastoreServiceClient,
-) -> crate::Result<(Vec<SplitMetadata>, IndexesMetasForLeafSearch)> {
-    let list_indexes_metadatas_request = ListIndexesMetadataRequest {
-        index_id_patterns: search_request.index_id_patterns.clone(),
-    };
-    let indexes_metadata: Vec<IndexMetadata> = metastore
-        .list_indexes_metadata(list_indexes_metadatas_request)
-        .await?
-        .deserialize_indexes_metadata()
-        .await?;
-
-    if !search_request.ignore_missing_indexes {
-        ensure_all_indexes_found(&indexes_metadata[..], &search_request.index_id_patterns[..])?;
-    }
-
-    if indexes_metadata.is_empty() {
-        return Ok((Vec::new(), HashMap::default()));
+) -> crate::Result<()> {
+    if search_request.inherit_default_max_hits
+        && let Some(default_max_hits) =
+            common_search_setting(indexes_metadata, |settings| settings.default_max_hits)
+    {
+        search_request.max_hits = default_max_hits;
     }
+    if search_request.inherit_default_operator
+        && let Some(default_operator) =
+            common_search_setting(indexes_metadata, |settings| settings.default_operator)
+    {
+        let query_ast: QueryAst = serde_json::from_str(&search_request.query_ast)
+            .map_err(|err| SearchError::InvalidQuery(err.to_string()))?;
+        search_request.query_ast =
+            serde_json::to_string(&query_ast.with_default_operator(default_operator))?;
+    }
+    Ok(())
+}
+
+/// Describes how a search request resolves against the targeted indexes: the indexes matched by
+/// its index ID patterns and the query defaults it inherits from them. Must be called before the
+/// query defaults are applied. The query is replaced by the final one once the request is planned.
+fn build_search_resolution(
+    indexes_metadata: &[IndexMetadata],
+    search_request: &SearchRequest,
+) -> SearchResolution {
+    let index_ids: Vec<String> = indexes_metadata
+        .iter()
+        .map(|index_metadata| index_metadata.index_id().to_string())
+        .sorted()
+        .collect();
+    let default_operator_opt = if search_request.inherit_default_operator {
+        common_search_setting(indexes_metadata, |settings| settings.default_operator)
+    } else {
+        None
+    };
+    let default_max_hits_opt = if search_request.inherit_default_max_hits {
+        common_search_setting(indexes_metadata, |settings| settings.default_max_hits)
+    } else {
+        None
+    };
+    SearchResolution {
+        index_ids,
+        default_operator: default_operator_opt.map(|default_operator| match default_operator {
+            BooleanOperand::And => "AND".to_string(),
+            BooleanOperand::Or => "OR".to_string(),
+        }),
+        default_max_hits: default_max_hits_opt,
+        query_ast: search_request.query_ast.clone(),
+    }
+}
+
+/// Applies the default sort of the targeted indexes to a search request without sort, so that its
+/// hits are returned in a deterministic order. The default sort is only applied if all the
+/// targeted indexes declare the same one. It is applied before the `search_after` values are
+/// validated, so that paginating with the sort values of the previous page keeps working.
+fn apply_default_sort(indexes_metadata: &[IndexMetadata], search_request: &mut SearchRequest) {
+    if !search_request.sort_fields.is_empty() || search_request.max_hits == 0 {
+        return;
+    }
+    let mut default_sorts = indexes_metadata
+        .iter()
+        .map(|index_metadata| &index_metadata.index_config.search_settings.default_sort);
+    let Some(default_sort) = default_sorts.next() else {
+        return;
+    };
+    if default_sort.is_empty()
+        || default_sorts.any(|other_default_sort| other_default_sort != default_sort)
+    {
+        return;
+    }
+    search_request.sort_fields = default_sort
+        .iter()
+        .map(|default_sort_field| SortField {
+            field_name: default_sort_field.field.clone(),
+            sort_order: default_sort_field.order as i32,
+            sort_datetime_format: None,
+            script: None,
+        })
+        .collect();
+}
+
+/// Checks that none of the indexes targeted by a search request is read-blocked.
+///
+/// Index patterns are resolved before the check, so a wildcard matching a blocked index is
+/// rejected as well.
+pub fn ensure_no_read_blocked_indexes(indexes_metadata: &[IndexMetadata]) -> crate::Result<()> {
+    let read_blocked_index_ids: Vec<&str> = indexes_metadata
+        .iter()
+        .filter(|index_metadata| index_metadata.index_config.search_settings.read_block)
+        .map(|index_metadata| index_metadata.index_id())
+        .collect();
+
+    if read_blocked_index_ids.is_empty() {
+        return Ok(());
+    }
+    Err(SearchError::Forbidden(format!(
+        "index(es) `{}` blocked for reads",
+        read_blocked_index_ids.join(", ")
+    )))
+}
+
+/// Rejects the searches whose index patterns resolve to more than `max_indexes_per_search`
+/// indexes, unless the request lifts the limit.
+fn check_max_indexes_per_search(
+    indexes_metadata: &[IndexMetadata],
+    search_request: &SearchRequest,
+    max_indexes_per_search_opt: Option<usize>,
+) -> crate::Result<()> {
+    let num_indexes = indexes_metadata.len();
+
+    if let Some(max_indexes_per_search) = max_indexes_per_search_opt
+        && max_indexes_per_search < num_indexes
+        && !search_request.ignore_max_indexes_per_search
+    {
+        return Err(SearchError::InvalidArgument(format!(
+            "index patterns `{}` match {num_indexes} indexes, which exceeds the limit of \
+             {max_indexes_per_search} indexes per search",
+            search_request.index_id_patterns.join(", ")
+        )));
+    }
+    Ok(())
+}
+
+/// Rejects the searches paginating with `start_offset` beyond `max_result_window` hits, which
+/// would collect and discard all the hits before `start_offset` on every split.
+fn check_result_window(
+    search_request: &SearchRequest,
+    max_result_window: u64,
+) -> crate::Result<()> {
+    let result_window = search_request
+        .start_offset
+        .saturating_add(search_request.max_hits);
+
+    if result_window > max_result_window {
+        return Err(SearchError::InvalidArgument(format!(
+            "result window is too large: `start_offset + max_hits` is {result_window}, which \
+             exceeds the limit of {max_result_window} (`searcher.max_result_window`). Use \
+             `search_after` or the scroll API to paginate through deeper hits"
+        )));
+    }
+    Ok(())
+}
+
+async fn refine_and_list_matches(
+    metastore: &MetastoreServiceClient,
+    search_request: &mut SearchRequest,
+    indexes_metadata: &[IndexMetadata],
+    query_ast_resolved: QueryAst,
+    sort_fields_is_datetime: HashMap<String, bool>,
+    timestamp_field_opt: Option<String>,
+) -> crate::Result<Vec<SplitMetadata>> {
+    let index_uids = indexes_metadata
+        .iter()
+        .map(|index_metadata| index_metadata.index_uid.clone())
+        .collect_vec();
+    search_request.query_ast = serde_json::to_string(&query_ast_resolved)?;
+
+    // convert search_after datetime values from input datetime format to nanos.
+    convert_search_after_datetime_values(search_request, &sort_fields_is_datetime)?;
+
+    // update_search_after_datetime_in_nanos(&mut search_request)?;
+    if let Some(timestamp_field) = &timestamp_field_opt {
+        refine_start_end_timestamp_from_ast(
+            &query_ast_resolved,
+            timestamp_field,
+            &mut search_request.start_timestamp,
+            &mut search_request.end_timestamp,
+        );
+    }
+    let tag_filter_ast = extract_tags_from_query(query_ast_resolved);
+
+    // TODO if search after is set, we sort by timestamp and we don't want to count all results,
+    // we can refine more here. Same if we sort by _shard_doc
+    let split_metadatas: Vec<SplitMetadata> = list_relevant_splits(
+        index_uids,
+        search_request.start_timestamp,
+        search_request.end_timestamp,
+        tag_filter_ast,
+        metastore,
+    )
+    .await?;
+    Ok(split_metadatas)
+}
+
+/// Returns the smallest maximum response size declared by the targeted indexes, if any.
+fn min_max_response_size(indexes_metadata: &[IndexMetadata]) -> Option<ByteSize> {
+    indexes_metadata
+        .iter()
+        .filter_map(|index_metadata| {
+            index_metadata
+                .index_config
+                .search_settings
+                .max_response_size
+        })
+        .min()
+}
+
+/// Sets the aggregation bucket limit of a search request from the `max_aggregation_buckets`
+/// search setting of the targeted indexes. When several indexes declare one, the smallest applies.
+fn apply_max_aggregation_buckets(
+    indexes_metadata: &[IndexMetadata],
+    search_request: &mut SearchRequest,
+) {
+    search_request.max_aggregation_buckets = indexes_metadata
+        .iter()
+        .filter_map(|index_metadata| {
+            index_metadata
+                .index_config
+                .search_settings
+                .max_aggregation_buckets
+        })
+        .min();
+}
+
+/// Lists the indexes targeted by a search request from the metastore, and checks that they can be
+/// searched.
+async fn list_indexes_for_root_search(
+    search_request: &SearchRequest,
+    metastore: &MetastoreServiceClient,
+    max_indexes_per_search_opt: Option<usize>,
+) -> crate::Result<Vec<IndexMetadata>> {
+    let list_indexes_metadatas_request = ListIndexesMetadataRequest {
+        index_id_patterns: search_request.index_id_patterns.clone(),
+        paused_only: false,
+    };
+    let indexes_metadata: Vec<IndexMetadata> = metastore
+        .list_indexes_metadata(list_indexes_metadatas_request)
+        .await?
+        .deserialize_indexes_metadata()
+        .await?;
+
+    if !search_request.ignore_missing_indexes {
+        ensure_all_indexes_found(&indexes_metadata[..], &search_request.index_id_patterns[..])?;
+    }
+    ensure_no_read_blocked_indexes(&indexes_metadata[..])?;
+    check_max_indexes_per_search(
+        &indexes_metadata,
+        search_request,
+        max_indexes_per_search_opt,
+    )?;
+    Ok(indexes_metadata)
+}
+
+/// Plan of a root search: the splits to search and their metadata, along with the response
+/// headers, the maximum response size, and the field mappings rendering missing fields as `null`
+/// declared by the targeted indexes, the estimated cost of the query, and the resolution of the
+/// request if `explain_resolution` is set.
+struct RootSearchPlan {
+    split_metadatas: Vec<SplitMetadata>,
+    indexes_meta_for_leaf_search: IndexesMetasForLeafSearch,
+    response_headers: HashMap<String, String>,
+    max_response_size_opt: Option<ByteSize>,
+    null_field_mappings_per_index: HashMap<IndexId, Vec<FieldMappingEntry>>,
+    cost_estimate: QueryCostEstimate,
+    resolution_opt: Option<SearchResolution>,
+}
 
-    let request_metadata = validate_request_and_build_metadata(&indexes_metadata, search_request)?;
+/// Fetches the list of splits and their metadata from the metastore for a search request
+/// targeting the given indexes, whose doc mappers are already built.
+async fn plan_splits_for_root_search(
+    search_request: &mut SearchRequest,
+    indexes_metadata: &[IndexMetadata],
+    doc_mappers: &[Arc<DocMapper>],
+    metastore: &MetastoreServiceClient,
+) -> crate::Result<RootSearchPlan> {
+    let mut resolution_opt = if search_request.explain_resolution {
+        Some(build_search_resolution(indexes_metadata, search_request))
+    } else {
+        None
+    };
+
+    if indexes_metadata.is_empty() {
+        return Ok(RootSearchPlan {
+            split_metadatas: Vec::new(),
+            indexes_meta_for_leaf_search: HashMap::default(),
+            response_headers: HashMap::default(),
+            max_response_size_opt: None,
+            null_field_mappings_per_index: HashMap::default(),
+            cost_estimate: QueryCostEstimate::default(),
+            resolution_opt,
+        });
+    }
+    apply_query_defaults(indexes_metadata, search_request)?;
+    apply_default_sort(indexes_metadata, search_request);
+    apply_max_aggregation_buckets(indexes_metadata, search_request);
+
+    let response_headers: HashMap<String, String> = merge_response_headers(
+        indexes_metadata
+            .iter()
+            .map(|index_metadata| &index_metadata.index_config),
+    )
+    .into_iter()
+    .collect();
+    let max_response_size_opt = min_max_response_size(indexes_metadata);
+    let null_field_mappings_per_index =
+        field_mappings_rendering_nulls(indexes_metadata, search_request.missing_fields_as_null);
+
+    let request_metadata = validate_request_and_build_metadata_with_doc_mappers(
+        indexes_metadata,
+        doc_mappers,
+        search_request,
+    )?;
     let split_metadatas = refine_and_list_matches(
         metastore,
         search_request,
@@ -1280,10 +1901,22 @@ async fn plan_splits_for_root_search(
         request_metadata.timestamp_field_opt,
     )
     .await?;
-    Ok((
+    let cost_estimate = estimate_query_cost(request_metadata.num_read_fields, &split_metadatas);
+
+    if let Some(resolution) = &mut resolution_opt {
+        // The query of the request has been replaced by the query resolved against the doc
+        // mappings of the indexes.
+        resolution.query_ast = search_request.query_ast.clone();
+    }
+    Ok(RootSearchPlan {
         split_metadatas,
-        request_metadata.indexes_meta_for_leaf_search,
-    ))
+        indexes_meta_for_leaf_search: request_metadata.indexes_meta_for_leaf_search,
+        response_headers,
+        max_response_size_opt,
+        null_field_mappings_per_index,
+        cost_estimate,
+        resolution_opt,
+    })
 }
 
 /// Performs a distributed search.
@@ -1299,15 +1932,62 @@ pub async fn root_search(
     cluster_client: &ClusterClient,
 ) -> crate::Result<SearchResponse> {
     let start_instant = Instant::now();
+    let percentiles_estimations = rewrite_percentiles_estimations(&mut search_request)?;
+    let max_indexes_per_search_opt = searcher_context.searcher_config.max_indexes_per_search;
 
-    let (split_metadatas, indexes_meta_for_leaf_search) = RootSearchMetricsFuture {
+    let root_search_plan = RootSearchMetricsFuture {
         start: start_instant,
-        tracked: plan_splits_for_root_search(&mut search_request, metastore),
+        tracked: async {
+            let indexes_metadata = list_indexes_for_root_search(
+                &search_request,
+                metastore,
+                max_indexes_per_search_opt,
+            )
+            .await?;
+            let doc_mappers = build_doc_mappers(&indexes_metadata)?;
+            plan_splits_for_root_search(
+                &mut search_request,
+                &indexes_metadata,
+                &doc_mappers,
+                metastore,
+            )
+            .await
+        },
         is_success: None,
         step: RootSearchMetricsStep::Plan,
     }
     .await?;
 
+    execute_root_search(
+        searcher_context,
+        search_request,
+        start_instant,
+        &percentiles_estimations,
+        root_search_plan,
+        cluster_client,
+    )
+    .await
+}
+
+/// Executes a planned root search.
+async fn execute_root_search(
+    searcher_context: &SearcherContext,
+    search_request: SearchRequest,
+    start_instant: Instant,
+    percentiles_estimations: &[PercentilesEstimation],
+    root_search_plan: RootSearchPlan,
+    cluster_client: &ClusterClient,
+) -> crate::Result<SearchResponse> {
+    let RootSearchPlan {
+        split_metadatas,
+        indexes_meta_for_leaf_search,
+        response_headers,
+        max_response_size_opt,
+        null_field_mappings_per_index,
+        cost_estimate,
+        resolution_opt,
+    } = root_search_plan;
+
     let num_docs: usize = split_metadatas.iter().map(|split| split.num_docs).sum();
     let num_splits = split_metadatas.len();
 
@@ -1337,6 +2017,20 @@ pub async fn root_search(
             "Number of targeted splits {num_splits} exceeds the limit {max_total_split_searches}"
         )));
     }
+    check_result_window(
+        &search_request,
+        searcher_context.searcher_config.max_result_window,
+    )?;
+    check_query_cost(
+        &cost_estimate,
+        searcher_context.searcher_config.max_query_cost,
+    )?;
+    let explain = search_request.explain;
+    let searched_index_ids: Vec<String> = indexes_meta_for_leaf_search
+        .keys()
+        .map(|index_uid| index_uid.index_id.clone())
+        .sorted()
+        .collect();
 
     let mut search_response_result = RootSearchMetricsFuture {
         start: start_instant,
@@ -1344,7 +2038,9 @@ pub async fn root_search(
             searcher_context,
             &indexes_meta_for_leaf_search,
             search_request,
+            percentiles_estimations,
             split_metadatas,
+            max_response_size_opt,
             cluster_client,
         ),
         is_success: None,
@@ -1356,18 +2052,115 @@ pub async fn root_search(
 
     if let Ok(search_response) = &mut search_response_result {
         search_response.elapsed_time_micros = start_instant.elapsed().as_micros() as u64;
+        search_response.response_headers = response_headers;
+        render_missing_fields_as_null(&mut search_response.hits, &null_field_mappings_per_index);
+        if explain {
+            search_response.cost_estimate = Some(cost_estimate);
+        }
+        search_response.resolution = resolution_opt;
+        search_response.searched_index_ids = searched_index_ids;
     }
 
     search_response_result
 }
 
+/// Part of a batch of searches shared by all its variants, which run the same search request with
+/// different filters: the targeted indexes, their doc mappers, and the query of the request
+/// resolved against them are listed, built, and resolved once for the whole batch.
+pub(crate) struct RootSearchBatch {
+    search_request: SearchRequest,
+    query_ast_resolved: QueryAst,
+    indexes_metadata: Vec<IndexMetadata>,
+    doc_mappers: Vec<Arc<DocMapper>>,
+}
+
+/// Lists the indexes targeted by the search request of a batch, builds their doc mappers, and
+/// resolves the query of the request against them.
+pub(crate) async fn prepare_root_search_batch(
+    searcher_context: &SearcherContext,
+    search_request: SearchRequest,
+    metastore: &MetastoreServiceClient,
+) -> crate::Result<RootSearchBatch> {
+    let indexes_metadata = list_indexes_for_root_search(
+        &search_request,
+        metastore,
+        searcher_context.searcher_config.max_indexes_per_search,
+    )
+    .await?;
+    let doc_mappers = build_doc_mappers(&indexes_metadata)?;
+
+    // The default operator of the indexes applies to the user input queries, so it is applied
+    // before they are resolved.
+    let mut search_request_with_defaults = search_request.clone();
+    apply_query_defaults(&indexes_metadata, &mut search_request_with_defaults)?;
+    let query_ast: QueryAst = serde_json::from_str(&search_request_with_defaults.query_ast)
+        .map_err(|err| SearchError::InvalidQuery(err.to_string()))?;
+    let query_ast_resolved = if doc_mappers.is_empty() {
+        query_ast
+    } else {
+        resolve_query_ast(&query_ast, &doc_mappers)?
+    };
+    Ok(RootSearchBatch {
+        search_request,
+        query_ast_resolved,
+        indexes_metadata,
+        doc_mappers,
+    })
+}
+
+/// Performs a variant of a batch of searches: the search request of the batch, with its hits
+/// restricted to the documents matching `filter_query_ast`.
+pub(crate) async fn root_search_batch_variant(
+    searcher_context: &SearcherContext,
+    root_search_batch: &RootSearchBatch,
+    filter_query_ast: QueryAst,
+    metastore: &MetastoreServiceClient,
+    cluster_client: &ClusterClient,
+) -> crate::Result<SearchResponse> {
+    let start_instant = Instant::now();
+    let variant_query_ast: QueryAst = BoolQuery {
+        must: vec![root_search_batch.query_ast_resolved.clone()],
+        filter: vec![filter_query_ast],
+        ..Default::default()
+    }
+    .into();
+    let mut search_request = root_search_batch.search_request.clone();
+    search_request.query_ast = serde_json::to_string(&variant_query_ast)?;
+    let percentiles_estimations = rewrite_percentiles_estimations(&mut search_request)?;
+
+    let root_search_plan = RootSearchMetricsFuture {
+        start: start_instant,
+        tracked: plan_splits_for_root_search(
+            &mut search_request,
+            &root_search_batch.indexes_metadata,
+            &root_search_batch.doc_mappers,
+            metastore,
+        ),
+        is_success: None,
+        step: RootSearchMetricsStep::Plan,
+    }
+    .await?;
+
+    execute_root_search(
+        searcher_context,
+        search_request,
+        start_instant,
+        &percentiles_estimations,
+        root_search_plan,
+        cluster_client,
+    )
+    .await
+}
+
 /// Returns details on how a query would be executed
 pub async fn search_plan(
     mut search_request: SearchRequest,
     metastore: &MetastoreServiceClient,
 ) -> crate::Result<SearchPlanResponse> {
+    rewrite_percentiles_estimations(&mut search_request)?;
     let list_indexes_metadatas_request = ListIndexesMetadataRequest {
         index_id_patterns: search_request.index_id_patterns.clone(),
+        paused_only: false,
     };
     let indexes_metadata: Vec<IndexMetadata> = metastore
         .list_indexes_metadata(list_indexes_metadatas_request)
@@ -1378,12 +2171,14 @@ pub async fn search_plan(
     if !search_request.ignore_missing_indexes {
         ensure_all_indexes_found(&indexes_metadata[..], &search_request.index_id_patterns[..])?;
     }
+    ensure_no_read_blocked_indexes(&indexes_metadata[..])?;
     if indexes_metadata.is_empty() {
         return Ok(SearchPlanResponse {
             result: serde_json::to_string(&SearchPlanResponseRest {
                 quickwit_ast: QueryAst::MatchAll,
                 tantivy_ast: String::new(),
                 searched_splits: Vec::new(),
+                searched_splits_details: Vec::new(),
                 storage_requests: StorageRequestCount::default(),
             })?,
         });
@@ -1394,11 +2189,13 @@ pub async fn search_plan(
     )
     .map_err(|err| SearchError::Internal(format!("failed to build doc mapper. cause: {err}")))?;
 
+    apply_query_defaults(&indexes_metadata, &mut search_request)?;
+    apply_default_sort(&indexes_metadata, &mut search_request);
     let request_metadata = validate_request_and_build_metadata(&indexes_metadata, &search_request)?;
     let split_metadatas = refine_and_list_matches(
         metastore,
         &mut search_request,
-        indexes_metadata,
+        &indexes_metadata,
         request_metadata.query_ast_resolved.clone(),
         request_metadata.sort_fields_is_datetime,
         request_metadata.timestamp_field_opt,
@@ -1410,14 +2207,31 @@ pub async fn search_plan(
         request_metadata.query_ast_resolved.clone(),
         true,
         None,
+        None,
     )?;
     let merge_collector = make_merge_collector(&search_request, Default::default())?;
     warmup_info.merge(merge_collector.warmup_info());
     warmup_info.simplify();
 
-    let split_ids = split_metadatas
+    let searched_splits_details: Vec<SearchPlanSplit> = split_metadatas
         .into_iter()
-        .map(|split| format!("{}/{}", split.index_uid.index_id, split.split_id))
+        .map(|split| SearchPlanSplit {
+            split_id: format!("{}/{}", split.index_uid.index_id, split.split_id),
+            num_docs: split.num_docs as u64,
+            size_in_bytes: split.footer_offsets.end,
+            time_range_start: split
+                .time_range
+                .as_ref()
+                .map(|time_range| *time_range.start()),
+            time_range_end: split
+                .time_range
+                .as_ref()
+                .map(|time_range| *time_range.end()),
+        })
+        .collect();
+    let split_ids = searched_splits_details
+        .iter()
+        .map(|split| split.split_id.clone())
         .collect();
     // this is an upper bound, we'd need access to a hotdir for more precise results
     let fieldnorm_query_count = if warmup_info.field_norms {
@@ -1465,6 +2279,7 @@ pub async fn search_plan(
             quickwit_ast: request_metadata.query_ast_resolved,
             tantivy_ast: format!("{query:#?}"),
             searched_splits: split_ids,
+            searched_splits_details,
             storage_requests: StorageRequestCount {
                 footer: 1,
                 fastfield: warmup_info.fast_fields.len(),
@@ -1847,6 +2662,7 @@ pub fn jobs_to_leaf_request(
 /// Builds a list of [`FetchDocsRequest`], one per index, from a list of [`FetchDocsJob`].
 pub fn jobs_to_fetch_docs_requests(
     snippet_request_opt: Option<SnippetRequest>,
+    score_request_opt: Option<ScoreRequest>,
     indexes_metas_for_leaf_search: &IndexesMetasForLeafSearch,
     jobs: Vec<FetchDocsJob>,
 ) -> crate::Result<Vec<FetchDocsRequest>> {
@@ -1878,6 +2694,7 @@ pub fn jobs_to_fetch_docs_requests(
                 split_offsets,
                 index_uri: index_meta.index_uri.to_string(),
                 snippet_request: snippet_request_opt.clone(),
+                score_request: score_request_opt.clone(),
                 doc_mapper: index_meta.doc_mapper_str.clone(),
             };
             fetch_docs_requests.push(fetch_docs_req);
@@ -1890,23 +2707,29 @@ pub fn jobs_to_fetch_docs_requests(
 
 #[cfg(test)]
 mod tests {
+    use std::collections::BTreeMap;
     use std::ops::Range;
     use std::str::FromStr;
+    use std::sync::atomic::AtomicUsize;
     use std::sync::{Arc, RwLock};
 
     use quickwit_common::ServiceStream;
     use quickwit_common::shared_consts::SCROLL_BATCH_LEN;
     use quickwit_config::{
-        DocMapping, IndexConfig, IndexingSettings, IngestSettings, SearchSettings,
+        DefaultSortField, DocMapping, IndexConfig, IndexingSettings, IngestSettings, MissingFields,
+        SearchSettings,
     };
     use quickwit_indexing::MockSplitBuilder;
-    use quickwit_metastore::{IndexMetadata, ListSplitsRequestExt, ListSplitsResponseExt};
+    use quickwit_metastore::{
+        CreateIndexRequestExt, IndexMetadata, ListSplitsRequestExt, ListSplitsResponseExt,
+        metastore_for_test,
+    };
     use quickwit_proto::metastore::{
-        ListIndexesMetadataResponse, ListSplitsResponse, MetastoreServiceClient,
-        MockMetastoreService,
+        CreateIndexRequest, ListIndexesMetadataResponse, ListSplitsResponse,
+        MetastoreServiceClient, MockMetastoreService,
     };
     use quickwit_proto::search::{
-        ScrollRequest, SortByValue, SortOrder, SortValue, SplitSearchError,
+        HitScores, ScrollRequest, SortByValue, SortOrder, SortValue, SplitSearchError,
     };
     use quickwit_query::query_ast::{qast_helper, qast_json_helper, query_ast_from_user_text};
     use tantivy::schema::{FAST, STORED, TEXT};
@@ -1995,6 +2818,13 @@ mod tests {
         let ingest_settings = IngestSettings::default();
         let search_settings = SearchSettings {
             default_search_fields: vec!["body".to_string()],
+            read_block: false,
+            default_sort: Vec::new(),
+            max_response_size: None,
+            max_aggregation_buckets: None,
+            missing_fields: MissingFields::Omit,
+            default_operator: None,
+            default_max_hits: None,
         };
         IndexMetadata::new(IndexConfig {
             index_id: index_id.to_string(),
@@ -2004,6 +2834,7 @@ mod tests {
             ingest_settings,
             search_settings,
             retention_policy_opt: None,
+            response_headers: BTreeMap::new(),
         })
     }
 
@@ -2020,11 +2851,13 @@ mod tests {
                     field_name: "timestamp".to_string(),
                     sort_order: SortOrder::Desc as i32,
                     sort_datetime_format: Some(SortDatetimeFormat::UnixTimestampMillis as i32),
+                    script: None,
                 },
                 SortField {
                     field_name: "_doc".to_string(),
                     sort_order: SortOrder::Asc as i32,
                     sort_datetime_format: None,
+                    script: None,
                 },
             ],
             ..Default::default()
@@ -2065,48 +2898,175 @@ mod tests {
     }
 
     #[test]
-    fn test_validate_request_and_build_metadatas_fail_with_different_timestamps() {
-        let search_request = quickwit_proto::search::SearchRequest {
+    fn test_apply_default_sort() {
+        let mut index_metadata = IndexMetadata::for_test("test-index", "ram:///test-index");
+        index_metadata.index_config.search_settings.default_sort = vec![DefaultSortField {
+            field: "timestamp".to_string(),
+            order: SortOrder::Desc,
+        }];
+        let indexes_metadata = [index_metadata];
+
+        // The default sort is applied to the requests without sort.
+        let mut search_request = quickwit_proto::search::SearchRequest {
             index_id_patterns: vec!["test-index".to_string()],
             query_ast: qast_json_helper("test", &["body"]),
             max_hits: 10,
-            start_offset: 10,
             ..Default::default()
         };
-        let index_metadata_1 = IndexMetadata::for_test("test-index-1", "ram:///test-index-1");
-        let mut index_metadata_2 = IndexMetadata::for_test("test-index-2", "ram:///test-index-2");
-        let doc_mapping_json_2 = r#"{
-            "mode": "lenient",
-            "field_mappings": [
-                {
-                    "name": "timestamp-2",
-                    "type": "datetime",
-                    "fast": true
-                },
-                {
-                    "name": "body",
-                    "type": "text"
-                }
-            ],
-            "timestamp_field": "timestamp-2",
-            "store_source": true
-        }"#;
-        let doc_mapping_2: DocMapping = serde_json::from_str(doc_mapping_json_2).unwrap();
-        index_metadata_2.index_config.doc_mapping = doc_mapping_2;
-        index_metadata_2
-            .index_config
-            .search_settings
-            .default_search_fields = Vec::new();
-        let timestamp_field_different = validate_request_and_build_metadata(
-            &[index_metadata_1, index_metadata_2],
-            &search_request,
-        )
-        .unwrap_err();
-        assert_eq!(
-            timestamp_field_different.to_string(),
-            "the timestamp field (if present) must be the same for all indexes"
-        );
-    }
+        apply_default_sort(&indexes_metadata, &mut search_request);
+        assert_eq!(search_request.sort_fields.len(), 1);
+        assert_eq!(search_request.sort_fields[0].field_name, "timestamp");
+        assert_eq!(search_request.sort_fields[0].sort_order(), SortOrder::Desc);
+
+        // The next pages are requested with the sort values of the last hit of the previous
+        // page, which are validated against the default sort.
+        search_request.search_after = Some(PartialHit {
+            sort_value: Some(SortByValue {
+                sort_value: Some(SortValue::I64(1_700_000_000_000_000_000)),
+            }),
+            ..Default::default()
+        });
+        let mut next_page_search_request = search_request.clone();
+        next_page_search_request.sort_fields.clear();
+        apply_default_sort(&indexes_metadata, &mut next_page_search_request);
+        assert_eq!(next_page_search_request, search_request);
+        validate_request_and_build_metadata(&indexes_metadata, &next_page_search_request).unwrap();
+
+        // The sort of the request overrides the default sort.
+        let mut search_request = quickwit_proto::search::SearchRequest {
+            index_id_patterns: vec!["test-index".to_string()],
+            query_ast: qast_json_helper("test", &["body"]),
+            max_hits: 10,
+            sort_fields: vec![SortField {
+                field_name: "_score".to_string(),
+                sort_order: SortOrder::Desc as i32,
+                sort_datetime_format: None,
+                script: None,
+            }],
+            ..Default::default()
+        };
+        apply_default_sort(&indexes_metadata, &mut search_request);
+        assert_eq!(search_request.sort_fields.len(), 1);
+        assert_eq!(search_request.sort_fields[0].field_name, "_score");
+
+        // The default sort is not applied if the targeted indexes disagree.
+        let other_index_metadata = IndexMetadata::for_test("other-index", "ram:///other-index");
+        let mut search_request = quickwit_proto::search::SearchRequest {
+            index_id_patterns: vec!["test-index".to_string(), "other-index".to_string()],
+            query_ast: qast_json_helper("test", &["body"]),
+            max_hits: 10,
+            ..Default::default()
+        };
+        apply_default_sort(
+            &[indexes_metadata[0].clone(), other_index_metadata],
+            &mut search_request,
+        );
+        assert!(search_request.sort_fields.is_empty());
+    }
+
+    #[test]
+    fn test_apply_query_defaults() {
+        let mut index_metadata = IndexMetadata::for_test("test-index", "ram:///test-index");
+        index_metadata.index_config.search_settings.default_operator = Some(BooleanOperand::Or);
+        index_metadata.index_config.search_settings.default_max_hits = Some(50);
+        let indexes_metadata = [index_metadata];
+
+        let user_query_operator = |search_request: &SearchRequest| {
+            let query_ast: QueryAst = serde_json::from_str(&search_request.query_ast).unwrap();
+            let QueryAst::UserInput(user_input_query) = query_ast else {
+                panic!("expected a user input query");
+            };
+            user_input_query.default_operator
+        };
+        let query_ast_json =
+            serde_json::to_string(&query_ast_from_user_text("hello world", None)).unwrap();
+
+        // The defaults of the index apply to the requests that do not set them.
+        let mut search_request = quickwit_proto::search::SearchRequest {
+            index_id_patterns: vec!["test-index".to_string()],
+            query_ast: query_ast_json.clone(),
+            max_hits: 20,
+            inherit_default_operator: true,
+            inherit_default_max_hits: true,
+            ..Default::default()
+        };
+        apply_query_defaults(&indexes_metadata, &mut search_request).unwrap();
+        assert_eq!(user_query_operator(&search_request), BooleanOperand::Or);
+        assert_eq!(search_request.max_hits, 50);
+
+        // The values of the request override the defaults of the index.
+        let mut search_request = quickwit_proto::search::SearchRequest {
+            index_id_patterns: vec!["test-index".to_string()],
+            query_ast: query_ast_json.clone(),
+            max_hits: 10,
+            ..Default::default()
+        };
+        apply_query_defaults(&indexes_metadata, &mut search_request).unwrap();
+        assert_eq!(user_query_operator(&search_request), BooleanOperand::And);
+        assert_eq!(search_request.max_hits, 10);
+
+        // The defaults are not applied if the targeted indexes disagree.
+        let other_index_metadata = IndexMetadata::for_test("other-index", "ram:///other-index");
+        let mut search_request = quickwit_proto::search::SearchRequest {
+            index_id_patterns: vec!["test-index".to_string(), "other-index".to_string()],
+            query_ast: query_ast_json,
+            max_hits: 20,
+            inherit_default_operator: true,
+            inherit_default_max_hits: true,
+            ..Default::default()
+        };
+        apply_query_defaults(
+            &[indexes_metadata[0].clone(), other_index_metadata],
+            &mut search_request,
+        )
+        .unwrap();
+        assert_eq!(user_query_operator(&search_request), BooleanOperand::And);
+        assert_eq!(search_request.max_hits, 20);
+    }
+
+    #[test]
+    fn test_validate_request_and_build_metadatas_fail_with_different_timestamps() {
+        let search_request = quickwit_proto::search::SearchRequest {
+            index_id_patterns: vec!["test-index".to_string()],
+            query_ast: qast_json_helper("test", &["body"]),
+            max_hits: 10,
+            start_offset: 10,
+            ..Default::default()
+        };
+        let index_metadata_1 = IndexMetadata::for_test("test-index-1", "ram:///test-index-1");
+        let mut index_metadata_2 = IndexMetadata::for_test("test-index-2", "ram:///test-index-2");
+        let doc_mapping_json_2 = r#"{
+            "mode": "lenient",
+            "field_mappings": [
+                {
+                    "name": "timestamp-2",
+                    "type": "datetime",
+                    "fast": true
+                },
+                {
+                    "name": "body",
+                    "type": "text"
+                }
+            ],
+            "timestamp_field": "timestamp-2",
+            "store_source": true
+        }"#;
+        let doc_mapping_2: DocMapping = serde_json::from_str(doc_mapping_json_2).unwrap();
+        index_metadata_2.index_config.doc_mapping = doc_mapping_2;
+        index_metadata_2
+            .index_config
+            .search_settings
+            .default_search_fields = Vec::new();
+        let timestamp_field_different = validate_request_and_build_metadata(
+            &[index_metadata_1, index_metadata_2],
+            &search_request,
+        )
+        .unwrap_err();
+        assert_eq!(
+            timestamp_field_different.to_string(),
+            "the timestamp field (if present) must be the same for all indexes"
+        );
+    }
 
     #[test]
     fn test_validate_request_and_build_metadatas_fail_with_different_resolved_qast() {
@@ -2169,6 +3129,13 @@ mod tests {
         let indexing_settings = IndexingSettings::default();
         let search_settings = SearchSettings {
             default_search_fields: vec!["body".to_string()],
+            read_block: false,
+            default_sort: Vec::new(),
+            max_response_size: None,
+            max_aggregation_buckets: None,
+            missing_fields: MissingFields::Omit,
+            default_operator: None,
+            default_max_hits: None,
         };
         IndexMetadata::new(IndexConfig {
             index_id: index_id.to_string(),
@@ -2178,6 +3145,7 @@ mod tests {
             indexing_settings,
             search_settings,
             retention_policy_opt: None,
+            response_headers: BTreeMap::new(),
         })
     }
 
@@ -2193,6 +3161,7 @@ mod tests {
                 field_name: "response_date".to_string(),
                 sort_order: SortOrder::Desc as i32,
                 sort_datetime_format: None,
+                script: None,
             }],
             ..Default::default()
         };
@@ -2286,11 +3255,13 @@ mod tests {
                 field_name: "_doc".to_string(),
                 sort_order: 0,
                 sort_datetime_format: None,
+                script: None,
             },
             SortField {
                 field_name: "_shard_doc".to_string(),
                 sort_order: 0,
                 sort_datetime_format: None,
+                script: None,
             },
         ];
         let mut schema_builder = Schema::builder();
@@ -2303,6 +3274,72 @@ mod tests {
         assert_eq!(sort_field_are_datetime.get("_shard_doc"), Some(&false));
     }
 
+    #[test]
+    fn test_validate_sort_field_types_with_script() {
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_f64_field("popularity", FAST);
+        schema_builder.add_i64_field("recency", FAST);
+        schema_builder.add_u64_field("views", STORED);
+        schema_builder.add_text_field("title", TEXT | FAST);
+        let schema = schema_builder.build();
+
+        let script_sort_field = |field_name: &str, script_opt: Option<&str>| SortField {
+            field_name: field_name.to_string(),
+            sort_order: 0,
+            sort_datetime_format: None,
+            script: script_opt.map(|script| script.to_string()),
+        };
+        let mut sort_field_are_datetime = HashMap::new();
+        validate_sort_field_types(
+            &schema,
+            &[script_sort_field(
+                "_script",
+                Some("popularity * 0.7 + recency * 0.3"),
+            )],
+            &mut sort_field_are_datetime,
+        )
+        .unwrap();
+        assert_eq!(sort_field_are_datetime.get("_script"), Some(&false));
+
+        for (sort_field, expected_error) in [
+            (
+                script_sort_field("_script", None),
+                "sort by `_script` requires a sort script",
+            ),
+            (
+                script_sort_field("popularity", Some("popularity")),
+                "a sort script can only be used with the `_script` sort field",
+            ),
+            (
+                script_sort_field("_script", Some("popularity *")),
+                "invalid sort script",
+            ),
+            (
+                script_sort_field("_script", Some("unknown * 2")),
+                "unknown field used in `sort by`: unknown",
+            ),
+            (
+                script_sort_field("_script", Some("views + 1")),
+                "sort script field `views` must be a numeric fast field",
+            ),
+            (
+                script_sort_field("_script", Some("title + 1")),
+                "sort script field `title` must be a numeric fast field",
+            ),
+            (
+                script_sort_field("_script", Some("_score * 2")),
+                "field `_score` cannot be used in a sort script",
+            ),
+        ] {
+            let error =
+                validate_sort_field_types(&schema, &[sort_field], &mut HashMap::new()).unwrap_err();
+            assert!(
+                error.to_string().contains(expected_error),
+                "expected `{expected_error}`, got `{error}`"
+            );
+        }
+    }
+
     #[test]
     fn test_validate_sort_field_types_valid() {
         let sort_fields = vec![
@@ -2310,11 +3347,13 @@ mod tests {
                 field_name: "timestamp".to_string(),
                 sort_order: 0,
                 sort_datetime_format: None,
+                script: None,
             },
             SortField {
                 field_name: "id".to_string(),
                 sort_order: 0,
                 sort_datetime_format: None,
+                script: None,
             },
         ];
         let mut schema_builder = Schema::builder();
@@ -2334,11 +3373,13 @@ mod tests {
                 field_name: "timestamp".to_string(),
                 sort_order: 0,
                 sort_datetime_format: None,
+                script: None,
             },
             SortField {
                 field_name: "id".to_string(),
                 sort_order: 0,
                 sort_datetime_format: None,
+                script: None,
             },
         ];
         let mut schema_builder = Schema::builder();
@@ -2377,11 +3418,13 @@ mod tests {
                 field_name: "timestamp".to_string(),
                 sort_order: 0,
                 sort_datetime_format: Some(SortDatetimeFormat::UnixTimestampMillis as i32),
+                script: None,
             },
             SortField {
                 field_name: "id".to_string(),
                 sort_order: 0,
                 sort_datetime_format: None,
+                script: None,
             },
         ];
         validate_sort_by_fields_and_search_after(&sort_fields, &None).unwrap();
@@ -2394,11 +3437,13 @@ mod tests {
                 field_name: "timestamp".to_string(),
                 sort_order: 0,
                 sort_datetime_format: Some(SortDatetimeFormat::UnixTimestampMillis as i32),
+                script: None,
             },
             SortField {
                 field_name: "id".to_string(),
                 sort_order: 0,
                 sort_datetime_format: None,
+                script: None,
             },
         ];
         let partial_hit = PartialHit {
@@ -2422,11 +3467,13 @@ mod tests {
                 field_name: "timestamp".to_string(),
                 sort_order: 0,
                 sort_datetime_format: Some(SortDatetimeFormat::UnixTimestampMillis as i32),
+                script: None,
             },
             SortField {
                 field_name: "_doc".to_string(),
                 sort_order: 0,
                 sort_datetime_format: None,
+                script: None,
             },
         ];
         let partial_hit = PartialHit {
@@ -2491,11 +3538,13 @@ mod tests {
                 field_name: "timestamp".to_string(),
                 sort_order: 0,
                 sort_datetime_format: Some(SortDatetimeFormat::UnixTimestampMillis as i32),
+                script: None,
             },
             SortField {
                 field_name: "id".to_string(),
                 sort_order: 0,
                 sort_datetime_format: None,
+                script: None,
             },
         ];
         let partial_hit = PartialHit {
@@ -2524,11 +3573,13 @@ mod tests {
                 field_name: "timestamp".to_string(),
                 sort_order: 0,
                 sort_datetime_format: Some(SortDatetimeFormat::UnixTimestampMillis as i32),
+                script: None,
             },
             SortField {
                 field_name: "_doc".to_string(),
                 sort_order: 0,
                 sort_datetime_format: None,
+                script: None,
             },
         ];
         let partial_hit = PartialHit {
@@ -2557,11 +3608,13 @@ mod tests {
                 field_name: "timestamp".to_string(),
                 sort_order: 0,
                 sort_datetime_format: Some(SortDatetimeFormat::UnixTimestampMillis as i32),
+                script: None,
             },
             SortField {
                 field_name: "id".to_string(),
                 sort_order: 0,
                 sort_datetime_format: None,
+                script: None,
             },
         ];
         let partial_hit = PartialHit {
@@ -2605,16 +3658,19 @@ mod tests {
                 field_name: "timestamp".to_string(),
                 sort_order: 0,
                 sort_datetime_format: Some(SortDatetimeFormat::UnixTimestampMillis as i32),
+                script: None,
             },
             SortField {
                 field_name: "timestamp".to_string(),
                 sort_order: 0,
                 sort_datetime_format: Some(SortDatetimeFormat::UnixTimestampMillis as i32),
+                script: None,
             },
             SortField {
                 field_name: "timestamp".to_string(),
                 sort_order: 0,
                 sort_datetime_format: Some(SortDatetimeFormat::UnixTimestampMillis as i32),
+                script: None,
             },
         ];
         let error = validate_sort_by_fields_and_search_after(&sort_fields, &None).unwrap_err();
@@ -2667,6 +3723,7 @@ mod tests {
                 .expect("Json serialization should not fail"),
                 partial_hit: Some(req),
                 leaf_snippet_json: None,
+                scores: None,
             })
             .collect()
     }
@@ -2839,6 +3896,166 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_root_search_skip_fetch_docs() {
+        let search_request = quickwit_proto::search::SearchRequest {
+            index_id_patterns: vec!["test-index".to_string()],
+            query_ast: qast_json_helper("test", &["body"]),
+            max_hits: 10,
+            skip_fetch_docs: true,
+            ..Default::default()
+        };
+        let mut mock_metastore = MockMetastoreService::new();
+        let index_metadata = IndexMetadata::for_test("test-index", "ram:///test-index");
+        let index_uid = index_metadata.index_uid.clone();
+        mock_metastore
+            .expect_list_indexes_metadata()
+            .returning(move |_index_ids_query| {
+                Ok(ListIndexesMetadataResponse::for_test(vec![
+                    index_metadata.clone(),
+                ]))
+            });
+        mock_metastore
+            .expect_list_splits()
+            .returning(move |_list_splits_request| {
+                let splits = vec![
+                    MockSplitBuilder::new("split1")
+                        .with_index_uid(&index_uid)
+                        .build(),
+                ];
+                let splits_response = ListSplitsResponse::try_from_splits(splits).unwrap();
+                Ok(ServiceStream::from(vec![Ok(splits_response)]))
+            });
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service.expect_leaf_search().returning(
+            |_leaf_search_req: quickwit_proto::search::LeafSearchRequest| {
+                Ok(quickwit_proto::search::LeafSearchResponse {
+                    num_hits: 3,
+                    partial_hits: vec![
+                        mock_partial_hit("split1", 3, 1),
+                        mock_partial_hit("split1", 2, 2),
+                        mock_partial_hit("split1", 1, 3),
+                    ],
+                    failed_splits: Vec::new(),
+                    num_attempted_splits: 1,
+                    ..Default::default()
+                })
+            },
+        );
+        mock_search_service.expect_fetch_docs().never();
+        let searcher_pool = searcher_pool_for_test([("127.0.0.1:1001", mock_search_service)]);
+        let search_job_placer = SearchJobPlacer::new(searcher_pool);
+        let cluster_client = ClusterClient::new(search_job_placer.clone());
+
+        let searcher_context = SearcherContext::for_test();
+        let search_response = root_search(
+            &searcher_context,
+            search_request,
+            &MetastoreServiceClient::from_mock(mock_metastore),
+            &cluster_client,
+        )
+        .await
+        .unwrap();
+        assert_eq!(search_response.num_hits, 3);
+        assert_eq!(search_response.hits.len(), 3);
+        for (hit, expected_doc_id) in search_response.hits.iter().zip([1, 2, 3]) {
+            assert!(hit.json.is_empty());
+            assert_eq!(hit.index_id, "test-index");
+            let partial_hit = hit.partial_hit.as_ref().unwrap();
+            assert_eq!(partial_hit.split_id, "split1");
+            assert_eq!(partial_hit.doc_id, expected_doc_id);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_root_search_track_scores() {
+        for track_scores in [false, true] {
+            let search_request = quickwit_proto::search::SearchRequest {
+                index_id_patterns: vec!["test-index".to_string()],
+                query_ast: qast_json_helper("test", &["body"]),
+                max_hits: 10,
+                sort_fields: vec![SortField {
+                    field_name: "timestamp".to_string(),
+                    sort_order: SortOrder::Desc as i32,
+                    sort_datetime_format: None,
+                    script: None,
+                }],
+                track_scores,
+                ..Default::default()
+            };
+            let mut mock_metastore = MockMetastoreService::new();
+            let index_metadata = IndexMetadata::for_test("test-index", "ram:///test-index");
+            let index_uid = index_metadata.index_uid.clone();
+            mock_metastore
+                .expect_list_indexes_metadata()
+                .returning(move |_index_ids_query| {
+                    Ok(ListIndexesMetadataResponse::for_test(vec![
+                        index_metadata.clone(),
+                    ]))
+                });
+            mock_metastore
+                .expect_list_splits()
+                .returning(move |_list_splits_request| {
+                    let splits = vec![
+                        MockSplitBuilder::new("split1")
+                            .with_index_uid(&index_uid)
+                            .build(),
+                    ];
+                    let splits_response = ListSplitsResponse::try_from_splits(splits).unwrap();
+                    Ok(ServiceStream::from(vec![Ok(splits_response)]))
+                });
+            let mut mock_search_service = MockSearchService::new();
+            mock_search_service.expect_leaf_search().returning(
+                |_leaf_search_req: quickwit_proto::search::LeafSearchRequest| {
+                    Ok(quickwit_proto::search::LeafSearchResponse {
+                        num_hits: 2,
+                        partial_hits: vec![
+                            mock_partial_hit("split1", 2, 1),
+                            mock_partial_hit("split1", 1, 2),
+                        ],
+                        failed_splits: Vec::new(),
+                        num_attempted_splits: 1,
+                        ..Default::default()
+                    })
+                },
+            );
+            // The hits are only scored when the scores are tracked.
+            mock_search_service
+                .expect_fetch_docs()
+                .withf(move |fetch_docs_req| fetch_docs_req.score_request.is_some() == track_scores)
+                .returning(|fetch_docs_req: quickwit_proto::search::FetchDocsRequest| {
+                    let scores_opt = fetch_docs_req.score_request.as_ref().map(|_| HitScores {
+                        score: 1.0,
+                        raw_score: 1.0,
+                    });
+                    let mut hits = get_doc_for_fetch_req(fetch_docs_req);
+
+                    for hit in &mut hits {
+                        hit.scores = scores_opt;
+                    }
+                    Ok(quickwit_proto::search::FetchDocsResponse { hits })
+                });
+            let searcher_pool = searcher_pool_for_test([("127.0.0.1:1001", mock_search_service)]);
+            let search_job_placer = SearchJobPlacer::new(searcher_pool);
+            let cluster_client = ClusterClient::new(search_job_placer.clone());
+
+            let searcher_context = SearcherContext::for_test();
+            let search_response = root_search(
+                &searcher_context,
+                search_request,
+                &MetastoreServiceClient::from_mock(mock_metastore),
+                &cluster_client,
+            )
+            .await
+            .unwrap();
+            assert_eq!(search_response.hits.len(), 2);
+
+            for hit in &search_response.hits {
+                assert_eq!(hit.scores.is_some(), track_scores);
+            }
+        }
+    }
+
     #[tokio::test]
     async fn test_root_search_multiple_splits() -> anyhow::Result<()> {
         let search_request = quickwit_proto::search::SearchRequest {
@@ -3306,6 +4523,7 @@ mod tests {
                             error: "some error".to_string(),
                             split_id: "split2".to_string(),
                             retryable_error: true,
+                            timed_out: false,
                         }],
                         num_attempted_splits: 2,
                         ..Default::default()
@@ -3355,6 +4573,7 @@ mod tests {
                 field_name: "response_date".to_string(),
                 sort_order: SortOrder::Asc.into(),
                 sort_datetime_format: Some(SortDatetimeFormat::UnixTimestampNanos as i32),
+                script: None,
             }],
             ..Default::default()
         };
@@ -3537,6 +4756,7 @@ mod tests {
                 field_name: "response_date".to_string(),
                 sort_order: SortOrder::Desc.into(),
                 sort_datetime_format: Some(SortDatetimeFormat::UnixTimestampNanos as i32),
+                script: None,
             }],
             ..Default::default()
         };
@@ -3894,6 +5114,7 @@ mod tests {
                             error: "mock_error".to_string(),
                             split_id: "split2".to_string(),
                             retryable_error: true,
+                            timed_out: false,
                         }],
                         num_attempted_splits: 1,
                         ..Default::default()
@@ -3974,6 +5195,7 @@ mod tests {
                         error: "mock_error".to_string(),
                         split_id: "split2".to_string(),
                         retryable_error: true,
+                        timed_out: false,
                     }],
                     num_attempted_splits: 1,
                     ..Default::default()
@@ -4035,6 +5257,7 @@ mod tests {
                         error: "mock_error".to_string(),
                         split_id: "split1".to_string(),
                         retryable_error: true,
+                        timed_out: false,
                     }],
                     num_attempted_splits: 1,
                     ..Default::default()
@@ -4109,6 +5332,7 @@ mod tests {
                             error: "mock_error".to_string(),
                             split_id: "split1".to_string(),
                             retryable_error: true,
+                            timed_out: false,
                         }],
                         num_attempted_splits: 1,
                         ..Default::default()
@@ -4187,6 +5411,7 @@ mod tests {
                         error: "mock_error".to_string(),
                         split_id: "split1".to_string(),
                         retryable_error: true,
+                        timed_out: false,
                     }],
                     num_attempted_splits: 1,
                     ..Default::default()
@@ -4274,6 +5499,7 @@ mod tests {
                         error: "mock_error".to_string(),
                         split_id: "split1".to_string(),
                         retryable_error: true,
+                        timed_out: false,
                     }],
                     num_attempted_splits: 1,
                     ..Default::default()
@@ -4663,6 +5889,22 @@ mod tests {
                     "test-index/split1".to_string(),
                     "test-index/split2".to_string()
                 ],
+                searched_splits_details: vec![
+                    SearchPlanSplit {
+                        split_id: "test-index/split1".to_string(),
+                        num_docs: 1_000_000,
+                        size_in_bytes: 800,
+                        time_range_start: Some(121000),
+                        time_range_end: Some(130198),
+                    },
+                    SearchPlanSplit {
+                        split_id: "test-index/split2".to_string(),
+                        num_docs: 10,
+                        size_in_bytes: 800,
+                        time_range_start: Some(121000),
+                        time_range_end: Some(130198),
+                    },
+                ],
                 storage_requests: StorageRequestCount {
                     footer: 1,
                     fastfield: 0,
@@ -5560,44 +6802,323 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_min_max_response_size() {
+        let mut index_metadata_1 =
+            index_metadata_for_multi_indexes_test("test-index-1", "ram:///test-index-1");
+        let mut index_metadata_2 =
+            index_metadata_for_multi_indexes_test("test-index-2", "ram:///test-index-2");
+        let index_metadata_3 =
+            index_metadata_for_multi_indexes_test("test-index-3", "ram:///test-index-3");
+        assert_eq!(min_max_response_size(&[index_metadata_3.clone()]), None);
+
+        index_metadata_1
+            .index_config
+            .search_settings
+            .max_response_size = Some(ByteSize::mb(2));
+        index_metadata_2
+            .index_config
+            .search_settings
+            .max_response_size = Some(ByteSize::mb(1));
+        let indexes_metadata = [index_metadata_1, index_metadata_2, index_metadata_3];
+        assert_eq!(
+            min_max_response_size(&indexes_metadata),
+            Some(ByteSize::mb(1))
+        );
+    }
+
     #[tokio::test]
-    async fn test_root_search_split_failures() -> anyhow::Result<()> {
-        let search_request = quickwit_proto::search::SearchRequest {
-            index_id_patterns: vec!["test-index-1".to_string()],
-            query_ast: qast_json_helper("test", &["body"]),
-            max_hits: 10,
-            ..Default::default()
-        };
+    async fn test_root_search_caps_fetch_to_max_response_size() {
         let mut mock_metastore = MockMetastoreService::new();
-        let index_metadata_1 = IndexMetadata::for_test("test-index-1", "ram:///test-index-1");
-        let index_uid_1 = index_metadata_1.index_uid.clone();
-        mock_metastore.expect_list_indexes_metadata().return_once(
-            move |_list_indexes_metadata_request: ListIndexesMetadataRequest| {
+        let mut index_metadata = IndexMetadata::for_test("test-index", "ram:///test-index");
+        index_metadata
+            .index_config
+            .search_settings
+            .max_response_size = Some(ByteSize::kb(1));
+        let index_uid = index_metadata.index_uid.clone();
+        mock_metastore
+            .expect_list_indexes_metadata()
+            .returning(move |_index_ids_query| {
                 Ok(ListIndexesMetadataResponse::for_test(vec![
-                    index_metadata_1,
+                    index_metadata.clone(),
                 ]))
-            },
-        );
+            });
         mock_metastore
             .expect_list_splits()
-            .return_once(move |list_splits_request| {
-                let list_splits_query =
-                    list_splits_request.deserialize_list_splits_query().unwrap();
-                assert!(list_splits_query.index_uids == Some(vec![index_uid_1.clone()]));
+            .returning(move |_list_splits_request| {
                 let splits = vec![
-                    MockSplitBuilder::new("index-1-split-1")
-                        .with_index_uid(&index_uid_1)
-                        .build(),
-                    MockSplitBuilder::new("index-1-split-2")
-                        .with_index_uid(&index_uid_1)
+                    MockSplitBuilder::new("split1")
+                        .with_index_uid(&index_uid)
                         .build(),
                 ];
                 let splits_response = ListSplitsResponse::try_from_splits(splits).unwrap();
                 Ok(ServiceStream::from(vec![Ok(splits_response)]))
             });
-        let mut mock_search_service_1 = MockSearchService::new();
-        mock_search_service_1
-            .expect_leaf_search()
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service.expect_leaf_search().returning(
+            |leaf_search_req: quickwit_proto::search::LeafSearchRequest| {
+                let search_request = leaf_search_req.search_request.unwrap();
+                // The second scroll page looks for the hits following the cached ones.
+                let partial_hits = if search_request.search_after.is_none() {
+                    (0..40)
+                        .map(|doc_id| mock_partial_hit("split1", 40 - doc_id as u64, doc_id))
+                        .collect()
+                } else {
+                    Vec::new()
+                };
+                Ok(quickwit_proto::search::LeafSearchResponse {
+                    num_hits: 40,
+                    partial_hits,
+                    num_attempted_splits: 1,
+                    num_successful_splits: 1,
+                    ..Default::default()
+                })
+            },
+        );
+        let num_fetched_docs = Arc::new(AtomicUsize::new(0));
+        let num_fetched_docs_clone = num_fetched_docs.clone();
+        mock_search_service.expect_fetch_docs().returning(
+            move |fetch_docs_req: quickwit_proto::search::FetchDocsRequest| {
+                num_fetched_docs_clone
+                    .fetch_add(fetch_docs_req.partial_hits.len(), Ordering::Relaxed);
+                // Each document is 100 bytes long, so 10 documents fit in 1KB.
+                let hits = fetch_docs_req
+                    .partial_hits
+                    .into_iter()
+                    .map(|partial_hit| quickwit_proto::search::LeafHit {
+                        leaf_json: format!(r#"{{"body": "{}"}}"#, "a".repeat(88)),
+                        partial_hit: Some(partial_hit),
+                        leaf_snippet_json: None,
+                        scores: None,
+                    })
+                    .collect();
+                Ok(quickwit_proto::search::FetchDocsResponse { hits })
+            },
+        );
+        let kv: Arc<RwLock<HashMap<Vec<u8>, Vec<u8>>>> = Default::default();
+        let kv_clone = kv.clone();
+        mock_search_service
+            .expect_put_kv()
+            .returning(move |put_kv_req| {
+                kv_clone
+                    .write()
+                    .unwrap()
+                    .insert(put_kv_req.key, put_kv_req.payload);
+            });
+        mock_search_service
+            .expect_get_kv()
+            .returning(move |get_kv_req| kv.read().unwrap().get(&get_kv_req.key).cloned());
+
+        let searcher_pool = searcher_pool_for_test([("127.0.0.1:1001", mock_search_service)]);
+        let search_job_placer = SearchJobPlacer::new(searcher_pool);
+        let cluster_client = ClusterClient::new(search_job_placer);
+        let searcher_context = SearcherContext::for_test();
+
+        let search_request = quickwit_proto::search::SearchRequest {
+            index_id_patterns: vec!["test-index".to_string()],
+            query_ast: qast_json_helper("test", &["body"]),
+            max_hits: 40,
+            scroll_ttl_secs: Some(60),
+            ..Default::default()
+        };
+        let search_response = root_search(
+            &searcher_context,
+            search_request,
+            &MetastoreServiceClient::from_mock(mock_metastore),
+            &cluster_client,
+        )
+        .await
+        .unwrap();
+        assert_eq!(search_response.num_hits, 40);
+        assert_eq!(search_response.hits.len(), 10);
+        let truncation_reason = search_response.truncation_reason.unwrap();
+        assert!(
+            truncation_reason.contains("returned 10 out of 40 hits"),
+            "{truncation_reason}"
+        );
+        // Only the first fetch round ran.
+        assert_eq!(
+            num_fetched_docs.load(Ordering::Relaxed),
+            FIRST_FETCH_DOCS_BATCH_LEN
+        );
+
+        // The next page starts right after the last returned hit.
+        let scroll_request = ScrollRequest {
+            scroll_id: search_response.scroll_id.unwrap(),
+            scroll_ttl_secs: Some(60),
+        };
+        let scroll_response =
+            crate::service::scroll(scroll_request, &cluster_client, &searcher_context)
+                .await
+                .unwrap();
+        let doc_ids: Vec<u32> = scroll_response
+            .hits
+            .iter()
+            .map(|hit| hit.partial_hit.as_ref().unwrap().doc_id)
+            .collect();
+        assert_eq!(doc_ids, (10..20).collect::<Vec<u32>>());
+        assert!(scroll_response.truncation_reason.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_root_search_merges_index_response_headers() {
+        let search_request = quickwit_proto::search::SearchRequest {
+            index_id_patterns: vec!["test-index-*".to_string()],
+            query_ast: qast_json_helper("test", &["body"]),
+            max_hits: 10,
+            ..Default::default()
+        };
+        let mut index_metadata_1 =
+            index_metadata_for_multi_indexes_test("test-index-1", "ram:///test-index-1");
+        index_metadata_1.index_config.response_headers =
+            BTreeMap::from([("X-Data-Classification".to_string(), "pii".to_string())]);
+        let mut index_metadata_2 =
+            index_metadata_for_multi_indexes_test("test-index-2", "ram:///test-index-2");
+        index_metadata_2.index_config.response_headers =
+            BTreeMap::from([("X-Data-Classification".to_string(), "internal".to_string())]);
+
+        let mut mock_metastore = MockMetastoreService::new();
+        mock_metastore.expect_list_indexes_metadata().return_once(
+            move |_list_indexes_metadata_request| {
+                Ok(ListIndexesMetadataResponse::for_test(vec![
+                    index_metadata_1,
+                    index_metadata_2,
+                ]))
+            },
+        );
+        mock_metastore
+            .expect_list_splits()
+            .return_once(|_list_splits_request| {
+                let splits_response = ListSplitsResponse::try_from_splits(Vec::new()).unwrap();
+                Ok(ServiceStream::from(vec![Ok(splits_response)]))
+            });
+        let searcher_pool = searcher_pool_for_test([("127.0.0.1:1001", MockSearchService::new())]);
+        let cluster_client = ClusterClient::new(SearchJobPlacer::new(searcher_pool));
+        let search_response = root_search(
+            &SearcherContext::for_test(),
+            search_request,
+            &MetastoreServiceClient::from_mock(mock_metastore),
+            &cluster_client,
+        )
+        .await
+        .unwrap();
+        assert_eq!(search_response.num_hits, 0);
+        assert_eq!(search_response.response_headers.len(), 1);
+        assert_eq!(
+            search_response.response_headers["x-data-classification"],
+            "pii"
+        );
+        assert_eq!(
+            search_response.searched_index_ids,
+            ["test-index-1", "test-index-2"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_root_search_batch_shares_indexes_and_query() {
+        let search_request = quickwit_proto::search::SearchRequest {
+            index_id_patterns: vec!["test-index".to_string()],
+            query_ast: serde_json::to_string(&query_ast_from_user_text("test", None)).unwrap(),
+            max_hits: 10,
+            explain_resolution: true,
+            ..Default::default()
+        };
+        let mut mock_metastore = MockMetastoreService::new();
+        // The indexes are listed once for all the variants of the batch.
+        mock_metastore
+            .expect_list_indexes_metadata()
+            .times(1)
+            .returning(|_list_indexes_metadata_request| {
+                Ok(ListIndexesMetadataResponse::for_test(vec![
+                    IndexMetadata::for_test("test-index", "ram:///test-index"),
+                ]))
+            });
+        mock_metastore
+            .expect_list_splits()
+            .times(2)
+            .returning(|_list_splits_request| {
+                let splits_response = ListSplitsResponse::try_from_splits(Vec::new()).unwrap();
+                Ok(ServiceStream::from(vec![Ok(splits_response)]))
+            });
+        let metastore = MetastoreServiceClient::from_mock(mock_metastore);
+        let searcher_pool = searcher_pool_for_test([("127.0.0.1:1001", MockSearchService::new())]);
+        let cluster_client = ClusterClient::new(SearchJobPlacer::new(searcher_pool));
+        let searcher_context = SearcherContext::for_test();
+
+        let root_search_batch =
+            prepare_root_search_batch(&searcher_context, search_request, &metastore)
+                .await
+                .unwrap();
+        assert_eq!(root_search_batch.doc_mappers.len(), 1);
+        // The query of the batch is resolved against the default search fields of the index.
+        assert!(!matches!(
+            root_search_batch.query_ast_resolved,
+            QueryAst::UserInput(_)
+        ));
+
+        for owner in ["acme", "globex"] {
+            let filter_query_ast = query_ast_from_user_text(&format!("owner:{owner}"), None);
+            let search_response = root_search_batch_variant(
+                &searcher_context,
+                &root_search_batch,
+                filter_query_ast,
+                &metastore,
+                &cluster_client,
+            )
+            .await
+            .unwrap();
+            let resolution = search_response.resolution.unwrap();
+            let query_ast: QueryAst = serde_json::from_str(&resolution.query_ast).unwrap();
+            let QueryAst::Bool(bool_query) = query_ast else {
+                panic!("expected a boolean query");
+            };
+            assert_eq!(
+                bool_query.must,
+                [root_search_batch.query_ast_resolved.clone()]
+            );
+            assert_eq!(bool_query.filter.len(), 1);
+            assert!(resolution.query_ast.contains(owner));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_root_search_split_failures() -> anyhow::Result<()> {
+        let search_request = quickwit_proto::search::SearchRequest {
+            index_id_patterns: vec!["test-index-1".to_string()],
+            query_ast: qast_json_helper("test", &["body"]),
+            max_hits: 10,
+            ..Default::default()
+        };
+        let mut mock_metastore = MockMetastoreService::new();
+        let index_metadata_1 = IndexMetadata::for_test("test-index-1", "ram:///test-index-1");
+        let index_uid_1 = index_metadata_1.index_uid.clone();
+        mock_metastore.expect_list_indexes_metadata().return_once(
+            move |_list_indexes_metadata_request: ListIndexesMetadataRequest| {
+                Ok(ListIndexesMetadataResponse::for_test(vec![
+                    index_metadata_1,
+                ]))
+            },
+        );
+        mock_metastore
+            .expect_list_splits()
+            .return_once(move |list_splits_request| {
+                let list_splits_query =
+                    list_splits_request.deserialize_list_splits_query().unwrap();
+                assert!(list_splits_query.index_uids == Some(vec![index_uid_1.clone()]));
+                let splits = vec![
+                    MockSplitBuilder::new("index-1-split-1")
+                        .with_index_uid(&index_uid_1)
+                        .build(),
+                    MockSplitBuilder::new("index-1-split-2")
+                        .with_index_uid(&index_uid_1)
+                        .build(),
+                ];
+                let splits_response = ListSplitsResponse::try_from_splits(splits).unwrap();
+                Ok(ServiceStream::from(vec![Ok(splits_response)]))
+            });
+        let mut mock_search_service_1 = MockSearchService::new();
+        mock_search_service_1
+            .expect_leaf_search()
             .withf(
                 |leaf_search_req: &quickwit_proto::search::LeafSearchRequest| {
                     leaf_search_req.leaf_requests.len() == 1
@@ -5616,6 +7137,7 @@ mod tests {
                                 error: "some error".to_string(),
                                 split_id: "index-1-split-1".to_string(),
                                 retryable_error: true,
+                                timed_out: false,
                             }
                         }],
                         num_attempted_splits: 3,
@@ -5642,6 +7164,7 @@ mod tests {
                                 error: "some error".to_string(),
                                 split_id: "index-1-split-1".to_string(),
                                 retryable_error: true,
+                                timed_out: false,
                             }
                         }],
                         num_attempted_splits: 1,
@@ -5725,6 +7248,455 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_root_search_max_result_window() {
+        let mut mock_metastore = MockMetastoreService::new();
+        mock_metastore
+            .expect_list_indexes_metadata()
+            .returning(|_list_indexes_metadata_request| {
+                let index_metadata = IndexMetadata::for_test("logs", "ram:///indexes/logs");
+                Ok(ListIndexesMetadataResponse::for_test(vec![index_metadata]))
+            });
+        mock_metastore.expect_list_splits().returning(|_filter| {
+            let splits_response = ListSplitsResponse::try_from_splits(Vec::new()).unwrap();
+            Ok(ServiceStream::from(vec![Ok(splits_response)]))
+        });
+        let metastore = MetastoreServiceClient::from_mock(mock_metastore);
+        let searcher_pool = searcher_pool_for_test([("127.0.0.1:1001", MockSearchService::new())]);
+        let cluster_client = ClusterClient::new(SearchJobPlacer::new(searcher_pool));
+        let searcher_context = SearcherContext::for_test();
+        assert_eq!(searcher_context.searcher_config.max_result_window, 10_000);
+
+        let search_request = quickwit_proto::search::SearchRequest {
+            index_id_patterns: vec!["logs".to_string()],
+            query_ast: qast_json_helper("test", &["body"]),
+            start_offset: 9_000,
+            max_hits: 1_000,
+            ..Default::default()
+        };
+        root_search(
+            &searcher_context,
+            search_request.clone(),
+            &metastore,
+            &cluster_client,
+        )
+        .await
+        .unwrap();
+
+        let deep_search_request = quickwit_proto::search::SearchRequest {
+            start_offset: 9_001,
+            ..search_request
+        };
+        let search_error = root_search(
+            &searcher_context,
+            deep_search_request,
+            &metastore,
+            &cluster_client,
+        )
+        .await
+        .unwrap_err();
+        let SearchError::InvalidArgument(error_message) = search_error else {
+            panic!("expected an invalid argument error, got {search_error:?}");
+        };
+        assert!(
+            error_message.contains("`start_offset + max_hits` is 10001"),
+            "{error_message}"
+        );
+        assert!(error_message.contains("`search_after`"), "{error_message}");
+
+        // The result window saturates instead of overflowing.
+        let overflowing_search_request = quickwit_proto::search::SearchRequest {
+            index_id_patterns: vec!["logs".to_string()],
+            query_ast: qast_json_helper("test", &["body"]),
+            start_offset: u64::MAX,
+            max_hits: 1_000,
+            ..Default::default()
+        };
+        let search_error = root_search(
+            &searcher_context,
+            overflowing_search_request,
+            &metastore,
+            &cluster_client,
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(search_error, SearchError::InvalidArgument(_)));
+    }
+
+    #[tokio::test]
+    async fn test_root_search_too_many_indexes() {
+        let mut mock_metastore = MockMetastoreService::new();
+        mock_metastore
+            .expect_list_indexes_metadata()
+            .returning(|list_indexes_metadata_request| {
+                let index_ids: &[&str] =
+                    if list_indexes_metadata_request.index_id_patterns == ["logs-*"] {
+                        &["logs-1", "logs-2", "logs-3"]
+                    } else {
+                        &["logs-1"]
+                    };
+                let indexes_metadata = index_ids
+                    .iter()
+                    .map(|index_id| {
+                        IndexMetadata::for_test(index_id, &format!("ram:///indexes/{index_id}"))
+                    })
+                    .collect();
+                Ok(ListIndexesMetadataResponse::for_test(indexes_metadata))
+            });
+        mock_metastore.expect_list_splits().returning(|_filter| {
+            let splits_response = ListSplitsResponse::try_from_splits(Vec::new()).unwrap();
+            Ok(ServiceStream::from(vec![Ok(splits_response)]))
+        });
+        let metastore = MetastoreServiceClient::from_mock(mock_metastore);
+        let searcher_pool = searcher_pool_for_test([("127.0.0.1:1001", MockSearchService::new())]);
+        let cluster_client = ClusterClient::new(SearchJobPlacer::new(searcher_pool));
+
+        let mut searcher_context = SearcherContext::for_test();
+        searcher_context.searcher_config.max_indexes_per_search = Some(2);
+
+        let search_request = quickwit_proto::search::SearchRequest {
+            index_id_patterns: vec!["logs-*".to_string()],
+            query_ast: qast_json_helper("test", &["body"]),
+            max_hits: 10,
+            ..Default::default()
+        };
+        let search_error = root_search(
+            &searcher_context,
+            search_request.clone(),
+            &metastore,
+            &cluster_client,
+        )
+        .await
+        .unwrap_err();
+        let SearchError::InvalidArgument(error_message) = search_error else {
+            panic!("expected an invalid argument error, got {search_error:?}");
+        };
+        assert!(error_message.contains("match 3 indexes"), "{error_message}");
+
+        // The limit is lifted for the requests ignoring it.
+        let unbounded_search_request = quickwit_proto::search::SearchRequest {
+            ignore_max_indexes_per_search: true,
+            ..search_request
+        };
+        root_search(
+            &searcher_context,
+            unbounded_search_request,
+            &metastore,
+            &cluster_client,
+        )
+        .await
+        .unwrap();
+
+        let narrower_search_request = quickwit_proto::search::SearchRequest {
+            index_id_patterns: vec!["logs-1*".to_string()],
+            query_ast: qast_json_helper("test", &["body"]),
+            max_hits: 10,
+            ..Default::default()
+        };
+        let search_response = root_search(
+            &searcher_context,
+            narrower_search_request,
+            &metastore,
+            &cluster_client,
+        )
+        .await
+        .unwrap();
+        assert_eq!(search_response.num_hits, 0);
+    }
+
+    async fn resolve_index_ids(
+        metastore: &MetastoreServiceClient,
+        patterns: &[&str],
+    ) -> crate::Result<Vec<String>> {
+        let index_id_patterns: Vec<String> =
+            patterns.iter().map(|pattern| pattern.to_string()).collect();
+        let mut index_ids: Vec<String> =
+            crate::resolve_index_patterns(&index_id_patterns, metastore)
+                .await?
+                .iter()
+                .map(|index_metadata| index_metadata.index_id().to_string())
+                .collect();
+        index_ids.sort();
+        Ok(index_ids)
+    }
+
+    #[tokio::test]
+    async fn test_root_search_index_id_exclusion_patterns() {
+        let metastore = metastore_for_test();
+        for index_id in [
+            "logs-app",
+            "logs-web",
+            "logs-debug-1",
+            "logs-debug-2",
+            "audit",
+        ] {
+            let index_uri = format!("ram:///indexes/{index_id}");
+            let index_config = IndexConfig::for_test(index_id, &index_uri);
+            let create_index_request =
+                CreateIndexRequest::try_from_index_config(&index_config).unwrap();
+            metastore.create_index(create_index_request).await.unwrap();
+        }
+        let index_ids = resolve_index_ids(&metastore, &["logs-*", "-logs-debug-*"])
+            .await
+            .unwrap();
+        assert_eq!(index_ids, ["logs-app", "logs-web"]);
+
+        let index_ids = resolve_index_ids(
+            &metastore,
+            &["logs-*", "audit", "-logs-debug-*", "-logs-web"],
+        )
+        .await
+        .unwrap();
+        assert_eq!(index_ids, ["audit", "logs-app"]);
+
+        // Excluding all the indexes is not an error, even when they are requested by ID.
+        let index_ids = resolve_index_ids(&metastore, &["logs-debug-*", "-logs-*"])
+            .await
+            .unwrap();
+        assert!(index_ids.is_empty());
+
+        let index_ids = resolve_index_ids(&metastore, &["logs-debug-1", "-logs-debug-*"])
+            .await
+            .unwrap();
+        assert!(index_ids.is_empty());
+
+        let search_error = resolve_index_ids(&metastore, &["logs-missing", "-logs-debug-*"])
+            .await
+            .unwrap_err();
+        let SearchError::IndexesNotFound {
+            index_ids: not_found_index_ids,
+        } = search_error
+        else {
+            panic!("expected an indexes not found error, got {search_error:?}");
+        };
+        assert_eq!(not_found_index_ids, ["logs-missing"]);
+
+        let searcher_pool = searcher_pool_for_test([("127.0.0.1:1001", MockSearchService::new())]);
+        let cluster_client = ClusterClient::new(SearchJobPlacer::new(searcher_pool));
+        let search_request = quickwit_proto::search::SearchRequest {
+            index_id_patterns: vec!["logs-*".to_string(), "-logs-*".to_string()],
+            query_ast: qast_json_helper("test", &["body"]),
+            max_hits: 10,
+            ..Default::default()
+        };
+        let search_response = root_search(
+            &SearcherContext::for_test(),
+            search_request,
+            &metastore,
+            &cluster_client,
+        )
+        .await
+        .unwrap();
+        assert_eq!(search_response.num_hits, 0);
+        assert!(search_response.hits.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_root_search_explain_resolution() {
+        let mut mock_metastore = MockMetastoreService::new();
+        mock_metastore
+            .expect_list_indexes_metadata()
+            .returning(|_list_indexes_metadata_request| {
+                let indexes_metadata = ["logs-2025", "logs-2024"]
+                    .iter()
+                    .map(|index_id| {
+                        let mut index_metadata = IndexMetadata::for_test(
+                            index_id,
+                            &format!("ram:///indexes/{index_id}"),
+                        );
+                        let search_settings = &mut index_metadata.index_config.search_settings;
+                        search_settings.default_operator = Some(BooleanOperand::Or);
+                        search_settings.default_max_hits = Some(5);
+                        index_metadata
+                    })
+                    .collect();
+                Ok(ListIndexesMetadataResponse::for_test(indexes_metadata))
+            });
+        mock_metastore.expect_list_splits().returning(|_filter| {
+            let splits_response = ListSplitsResponse::try_from_splits(Vec::new()).unwrap();
+            Ok(ServiceStream::from(vec![Ok(splits_response)]))
+        });
+        let metastore = MetastoreServiceClient::from_mock(mock_metastore);
+        let searcher_pool = searcher_pool_for_test([("127.0.0.1:1001", MockSearchService::new())]);
+        let cluster_client = ClusterClient::new(SearchJobPlacer::new(searcher_pool));
+        let searcher_context = SearcherContext::for_test();
+
+        let search_request = quickwit_proto::search::SearchRequest {
+            index_id_patterns: vec!["logs-*".to_string()],
+            query_ast: serde_json::to_string(&query_ast_from_user_text("hello world", None))
+                .unwrap(),
+            max_hits: 20,
+            inherit_default_operator: true,
+            inherit_default_max_hits: true,
+            explain_resolution: true,
+            ..Default::default()
+        };
+        let search_response = root_search(
+            &searcher_context,
+            search_request.clone(),
+            &metastore,
+            &cluster_client,
+        )
+        .await
+        .unwrap();
+        let resolution = search_response.resolution.unwrap();
+        assert_eq!(resolution.index_ids, ["logs-2024", "logs-2025"]);
+        assert_eq!(resolution.default_operator.as_deref(), Some("OR"));
+        assert_eq!(resolution.default_max_hits, Some(5));
+
+        // The user query is resolved against the default search fields of the indexes.
+        let query_ast: QueryAst = serde_json::from_str(&resolution.query_ast).unwrap();
+        assert!(!matches!(query_ast, QueryAst::UserInput(_)));
+        assert!(resolution.query_ast.contains("body"));
+
+        // The defaults set by the request are not inherited.
+        let search_request = quickwit_proto::search::SearchRequest {
+            inherit_default_operator: false,
+            inherit_default_max_hits: false,
+            ..search_request
+        };
+        let search_response = root_search(
+            &searcher_context,
+            search_request.clone(),
+            &metastore,
+            &cluster_client,
+        )
+        .await
+        .unwrap();
+        let resolution = search_response.resolution.unwrap();
+        assert!(resolution.default_operator.is_none());
+        assert!(resolution.default_max_hits.is_none());
+
+        let search_request = quickwit_proto::search::SearchRequest {
+            explain_resolution: false,
+            ..search_request
+        };
+        let search_response = root_search(
+            &searcher_context,
+            search_request,
+            &metastore,
+            &cluster_client,
+        )
+        .await
+        .unwrap();
+        assert!(search_response.resolution.is_none());
+    }
+
+    fn mock_metastore_with_two_splits() -> MockMetastoreService {
+        let mut mock_metastore = MockMetastoreService::new();
+        let index_metadata = IndexMetadata::for_test("test-index", "ram:///test-index");
+        let index_uid = index_metadata.index_uid.clone();
+        mock_metastore
+            .expect_list_indexes_metadata()
+            .returning(move |_index_ids_query| {
+                Ok(ListIndexesMetadataResponse::for_test(vec![
+                    index_metadata.clone(),
+                ]))
+            });
+        mock_metastore
+            .expect_list_splits()
+            .returning(move |_filter| {
+                // `split1` holds 1,000,000 documents, `split2` holds 10 documents.
+                let splits = vec![
+                    MockSplitBuilder::new("split1")
+                        .with_index_uid(&index_uid)
+                        .build(),
+                    MockSplitBuilder::new("split2")
+                        .with_index_uid(&index_uid)
+                        .build(),
+                ];
+                let splits_response = ListSplitsResponse::try_from_splits(splits).unwrap();
+                Ok(ServiceStream::from(vec![Ok(splits_response)]))
+            });
+        mock_metastore
+    }
+
+    #[tokio::test]
+    async fn test_root_search_query_cost_under_budget() {
+        let search_request = quickwit_proto::search::SearchRequest {
+            index_id_patterns: vec!["test-index".to_string()],
+            query_ast: qast_json_helper("test", &["body"]),
+            max_hits: 10,
+            explain: true,
+            ..Default::default()
+        };
+        let mock_metastore = mock_metastore_with_two_splits();
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service.expect_leaf_search().returning(
+            |_leaf_search_req: quickwit_proto::search::LeafSearchRequest| {
+                Ok(quickwit_proto::search::LeafSearchResponse {
+                    num_hits: 1,
+                    partial_hits: vec![mock_partial_hit("split1", 1, 1)],
+                    num_attempted_splits: 2,
+                    num_successful_splits: 2,
+                    ..Default::default()
+                })
+            },
+        );
+        mock_search_service.expect_fetch_docs().returning(
+            |fetch_docs_req: quickwit_proto::search::FetchDocsRequest| {
+                Ok(quickwit_proto::search::FetchDocsResponse {
+                    hits: get_doc_for_fetch_req(fetch_docs_req),
+                })
+            },
+        );
+        let searcher_pool = searcher_pool_for_test([("127.0.0.1:1001", mock_search_service)]);
+        let search_job_placer = SearchJobPlacer::new(searcher_pool);
+        let cluster_client = ClusterClient::new(search_job_placer.clone());
+
+        let mut searcher_context = SearcherContext::for_test();
+        searcher_context.searcher_config.max_query_cost = Some(1_000_010);
+        let search_response = root_search(
+            &searcher_context,
+            search_request,
+            &MetastoreServiceClient::from_mock(mock_metastore),
+            &cluster_client,
+        )
+        .await
+        .unwrap();
+        assert_eq!(search_response.num_hits, 1);
+
+        let expected_cost_estimate = QueryCostEstimate {
+            num_splits: 2,
+            num_fields: 1,
+            num_docs: 1_000_010,
+            cost: 1_000_010,
+        };
+        assert_eq!(search_response.cost_estimate, Some(expected_cost_estimate));
+    }
+
+    #[tokio::test]
+    async fn test_root_search_query_cost_over_budget() {
+        let search_request = quickwit_proto::search::SearchRequest {
+            index_id_patterns: vec!["test-index".to_string()],
+            query_ast: qast_json_helper("test", &["body"]),
+            max_hits: 10,
+            ..Default::default()
+        };
+        let mock_metastore = mock_metastore_with_two_splits();
+        // The search is rejected before reaching the leaves.
+        let mock_search_service = MockSearchService::new();
+        let searcher_pool = searcher_pool_for_test([("127.0.0.1:1001", mock_search_service)]);
+        let search_job_placer = SearchJobPlacer::new(searcher_pool);
+        let cluster_client = ClusterClient::new(search_job_placer.clone());
+
+        let mut searcher_context = SearcherContext::for_test();
+        searcher_context.searcher_config.max_query_cost = Some(1_000_000);
+        let search_error = root_search(
+            &searcher_context,
+            search_request,
+            &MetastoreServiceClient::from_mock(mock_metastore),
+            &cluster_client,
+        )
+        .await
+        .unwrap_err();
+        let SearchError::InvalidArgument(error_message) = search_error else {
+            panic!("expected an invalid argument error, got `{search_error:?}`");
+        };
+        let expected_estimate = "estimated query cost 1000010 (2 splits, 1 fields, 1000010 docs)";
+        assert!(error_message.contains(expected_estimate), "{error_message}");
+    }
+
     #[tokio::test]
     async fn test_finalize_aggregation_if_any_no_aggregation_request() {
         let search_request = SearchRequest {
@@ -5733,9 +7705,13 @@ mod tests {
             ..Default::default()
         };
         let searcher_context = SearcherContext::for_test();
-        let result =
-            finalize_aggregation_if_any(&search_request, Some(vec![1, 2, 3]), &searcher_context)
-                .unwrap();
+        let result = finalize_aggregation_if_any(
+            &search_request,
+            &[],
+            Some(vec![1, 2, 3]),
+            &searcher_context,
+        )
+        .unwrap();
         assert!(result.is_none());
     }
 
@@ -5751,6 +7727,7 @@ mod tests {
         let searcher_context = SearcherContext::for_test();
         let result = finalize_aggregation_if_any(
             &search_request,
+            &[],
             Some(intermediate_bytes.clone()),
             &searcher_context,
         )
@@ -5767,7 +7744,8 @@ mod tests {
             ..Default::default()
         };
         let searcher_context = SearcherContext::for_test();
-        let result = finalize_aggregation_if_any(&search_request, None, &searcher_context).unwrap();
+        let result =
+            finalize_aggregation_if_any(&search_request, &[], None, &searcher_context).unwrap();
         assert!(result.is_none());
     }
 
@@ -5784,6 +7762,7 @@ mod tests {
         let searcher_context = SearcherContext::for_test();
         let result = finalize_aggregation_if_any(
             &search_request,
+            &[],
             Some(intermediate_bytes.clone()),
             &searcher_context,
         )
@@ -5806,6 +7785,7 @@ mod tests {
         let searcher_context = SearcherContext::for_test();
         let result = finalize_aggregation_if_any(
             &search_request,
+            &[],
             Some(intermediate_bytes.clone()),
             &searcher_context,
         )
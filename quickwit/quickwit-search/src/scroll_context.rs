@@ -22,6 +22,7 @@ use std::time::Duration;
 use anyhow::Context;
 use base64::Engine;
 use base64::prelude::BASE64_STANDARD;
+use bytesize::ByteSize;
 use quickwit_common::shared_consts::SCROLL_BATCH_LEN;
 use quickwit_metastore::SplitMetadata;
 use quickwit_metrics::GaugeGuard;
@@ -58,6 +59,10 @@ pub(crate) struct ScrollContext {
     pub cached_partial_hits: Vec<PartialHit>,
     pub failed_splits: Vec<SplitSearchError>,
     pub num_successful_splits: u64,
+    /// Maximum total size of the documents and snippets of a page, from the `max_response_size`
+    /// search setting of the targeted indexes.
+    #[serde(default)]
+    pub max_response_size: Option<ByteSize>,
 }
 
 impl ScrollContext {
@@ -216,6 +221,21 @@ impl ScrollKeyAndStartOffset {
         self
     }
 
+    /// Moves to the page following a page truncated to `num_returned_hits` hits because it
+    /// exceeded the maximum response size. Unlike [`Self::next_page`], the scroll does not end
+    /// although the page is not full.
+    pub fn next_truncated_page(
+        mut self,
+        num_returned_hits: u64,
+        last_returned_hit_opt: Option<PartialHit>,
+    ) -> ScrollKeyAndStartOffset {
+        self.start_offset += num_returned_hits;
+        if let Some(last_returned_hit) = last_returned_hit_opt {
+            self.search_after = last_returned_hit;
+        }
+        self
+    }
+
     pub fn scroll_key(&self) -> [u8; 16] {
         u128::from(self.scroll_ulid).to_le_bytes()
     }
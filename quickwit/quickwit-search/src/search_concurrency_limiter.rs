@@ -0,0 +1,132 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::Duration;
+
+use quickwit_config::SearchConcurrencyConfig;
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+use crate::SearchError;
+
+/// Bounds the number of root searches running concurrently on a node.
+///
+/// Rather than being rejected right away, the searches exceeding the limit wait in a bounded
+/// queue for a slot to free up. A search is rejected with [`SearchError::TooManyRequests`] if the
+/// queue is full or if it does not get a slot within the maximum queue wait.
+pub struct SearchConcurrencyLimiter {
+    concurrency_semaphore: Semaphore,
+    queue_semaphore: Semaphore,
+    max_queue_wait: Duration,
+}
+
+impl SearchConcurrencyLimiter {
+    /// Creates a limiter running up to `max_concurrent_searches` searches at a time and queuing
+    /// up to `max_queued_searches` searches for at most `max_queue_wait`.
+    pub fn new(
+        max_concurrent_searches: usize,
+        max_queued_searches: usize,
+        max_queue_wait: Duration,
+    ) -> Self {
+        SearchConcurrencyLimiter {
+            concurrency_semaphore: Semaphore::new(max_concurrent_searches),
+            queue_semaphore: Semaphore::new(max_queued_searches),
+            max_queue_wait,
+        }
+    }
+
+    /// Creates a limiter from the `searcher.search_concurrency` config.
+    pub fn from_config(search_concurrency_config: &SearchConcurrencyConfig) -> Self {
+        Self::new(
+            search_concurrency_config.max_concurrent_searches.get(),
+            search_concurrency_config.max_queued_searches,
+            *search_concurrency_config.max_queue_wait,
+        )
+    }
+
+    /// Waits for a slot to run a search. The slot is released when the permit is dropped.
+    pub async fn acquire(&self) -> crate::Result<SemaphorePermit<'_>> {
+        if let Ok(concurrency_permit) = self.concurrency_semaphore.try_acquire() {
+            return Ok(concurrency_permit);
+        }
+        let Ok(_queue_permit) = self.queue_semaphore.try_acquire() else {
+            return Err(SearchError::TooManyRequests);
+        };
+        let acquire_fut = self.concurrency_semaphore.acquire();
+
+        match tokio::time::timeout(self.max_queue_wait, acquire_fut).await {
+            Ok(Ok(concurrency_permit)) => Ok(concurrency_permit),
+            Ok(Err(_closed)) => Err(SearchError::Internal(
+                "search concurrency semaphore should never be closed".to_string(),
+            )),
+            Err(_elapsed) => Err(SearchError::TooManyRequests),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_search_concurrency_limiter_admits_under_capacity() {
+        let limiter = SearchConcurrencyLimiter::new(2, 0, Duration::from_millis(10));
+        let _permit_1 = limiter.acquire().await.unwrap();
+        let _permit_2 = limiter.acquire().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_search_concurrency_limiter_queues_searches() {
+        let limiter = SearchConcurrencyLimiter::new(1, 1, Duration::from_secs(10));
+        let permit = limiter.acquire().await.unwrap();
+
+        let acquire_fut = limiter.acquire();
+        tokio::pin!(acquire_fut);
+        // The search is queued until a slot frees up.
+        tokio::time::timeout(Duration::from_millis(50), &mut acquire_fut)
+            .await
+            .unwrap_err();
+        drop(permit);
+        tokio::time::timeout(Duration::from_secs(1), acquire_fut)
+            .await
+            .unwrap()
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_search_concurrency_limiter_rejects_when_queue_is_full() {
+        let limiter = SearchConcurrencyLimiter::new(1, 1, Duration::from_secs(10));
+        let _permit = limiter.acquire().await.unwrap();
+
+        let queued_acquire_fut = limiter.acquire();
+        tokio::pin!(queued_acquire_fut);
+        tokio::time::timeout(Duration::from_millis(50), &mut queued_acquire_fut)
+            .await
+            .unwrap_err();
+
+        let error = limiter.acquire().await.unwrap_err();
+        assert!(matches!(error, SearchError::TooManyRequests));
+    }
+
+    #[tokio::test]
+    async fn test_search_concurrency_limiter_rejects_when_wait_is_exhausted() {
+        let limiter = SearchConcurrencyLimiter::new(1, 1, Duration::from_millis(50));
+        let _permit = limiter.acquire().await.unwrap();
+
+        let error = limiter.acquire().await.unwrap_err();
+        assert!(matches!(error, SearchError::TooManyRequests));
+
+        // The rejected search left the queue.
+        assert_eq!(limiter.queue_semaphore.available_permits(), 1);
+    }
+}
@@ -112,6 +112,11 @@ impl SearchJobPlacer {
     pub fn new(searcher_pool: SearcherPool) -> Self {
         Self { searcher_pool }
     }
+
+    /// Returns the clients of all the searchers of the cluster.
+    pub(crate) fn all_searchers(&self) -> Vec<SearchServiceClient> {
+        self.searcher_pool.values()
+    }
 }
 
 struct SocketAddrAndClient {
@@ -12,10 +12,11 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::HashMap;
 use std::convert::TryFrom;
 
-use quickwit_common::truncate_str;
-use quickwit_proto::search::SearchResponse;
+use quickwit_common::{is_false, truncate_str};
+use quickwit_proto::search::{HitScores, QueryCostEstimate, SearchResolution, SearchResponse};
 use quickwit_query::aggregations::AggregationResults as AggregationResultsProxy;
 use quickwit_query::query_ast::QueryAst;
 use serde::{Deserialize, Serialize};
@@ -33,7 +34,19 @@ impl AggregationResults {
     /// Parse an ES aggregation result ast from our non-ambiguous postcard format
     pub fn from_postcard(postcard_bytes: &[u8]) -> anyhow::Result<Self> {
         let aggregation_result: AggregationResultsProxy = postcard::from_bytes(postcard_bytes)?;
-        Ok(AggregationResults(aggregation_result.into()))
+        Ok(aggregation_result.into())
+    }
+
+    /// Returns the aggregation results as computed by tantivy, so that they can be serialized
+    /// piece by piece.
+    pub fn into_inner(self) -> tantivy::aggregation::agg_result::AggregationResults {
+        self.0
+    }
+}
+
+impl From<AggregationResultsProxy> for AggregationResults {
+    fn from(aggregation_result: AggregationResultsProxy) -> Self {
+        AggregationResults(aggregation_result.into())
     }
 }
 
@@ -58,22 +71,195 @@ pub struct SearchResponseRest {
     #[schema(value_type = Object)]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub aggregations: Option<AggregationResults>,
+    /// Estimated cost of the query, only returned if `explain` is set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cost_estimate: Option<QueryCostEstimate>,
+    /// Resolution of the request, only returned if `explain_resolution` is set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolution: Option<SearchResolutionRest>,
+    /// Summary of the searched splits, only returned if some splits failed.
+    #[serde(rename = "_splits")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub splits: Option<SplitsSummary>,
+    /// Whether the hits were truncated to fit in the maximum response size of the targeted
+    /// indexes. `num_hits` still counts all the matching documents.
+    #[serde(skip_serializing_if = "is_false")]
+    pub truncated: bool,
+    /// Reason of the truncation of the hits, only returned if `truncated` is set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub truncation_reason: Option<String>,
+    /// Breakdown of the time spent serving the search, only returned if `detailed_timings` is
+    /// set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timings: Option<SearchTimings>,
+    /// Hits laid out as columns, only returned if `columnar_hits` is set. `hits` is then empty.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub columnar_hits: Option<ColumnarHits>,
+    /// Size of each hit, in bytes, once serialized as compact JSON, only returned if `hit_bytes`
+    /// is set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hit_bytes: Option<Vec<u64>>,
+}
+
+/// How a search request was resolved before being executed: the indexes matched by its index ID
+/// patterns, the query defaults of the indexes it inherited, and the final query.
+#[derive(Serialize, Deserialize, PartialEq, Debug, utoipa::ToSchema)]
+pub struct SearchResolutionRest {
+    /// IDs of the indexes matched by the index ID patterns of the request.
+    pub index_ids: Vec<String>,
+    /// Default operator of the user queries inherited from the search settings of the indexes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_operator: Option<String>,
+    /// Default number of hits inherited from the search settings of the indexes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_max_hits: Option<u64>,
+    /// Final query AST, after the query defaults and the doc mappings of the indexes are applied.
+    #[schema(value_type = Object)]
+    pub query: JsonValue,
+}
+
+impl TryFrom<SearchResolution> for SearchResolutionRest {
+    type Error = SearchError;
+
+    fn try_from(resolution: SearchResolution) -> Result<Self, Self::Error> {
+        let query: JsonValue = serde_json::from_str(&resolution.query_ast)?;
+        Ok(SearchResolutionRest {
+            index_ids: resolution.index_ids,
+            default_operator: resolution.default_operator,
+            default_max_hits: resolution.default_max_hits,
+            query,
+        })
+    }
+}
+
+/// Hits laid out as columns rather than rows: the `i`-th array of `values` holds the values of
+/// the field named by the `i`-th entry of `columns`, one per hit, in the order of the hits.
+/// Field names are not repeated for each hit, which shrinks the responses listing many hits
+/// sharing the same fields.
+#[derive(Serialize, Deserialize, PartialEq, Debug, utoipa::ToSchema)]
+pub struct ColumnarHits {
+    /// Names of the top-level fields of the hits, in the order of their first appearance.
+    pub columns: Vec<String>,
+    /// Values of each column, holding `null` for the hits lacking the field. Hence, a field
+    /// explicitly set to `null` cannot be told apart from a missing one.
+    #[schema(value_type = Vec<Vec<Object>>)]
+    pub values: Vec<Vec<JsonValue>>,
+}
+
+impl ColumnarHits {
+    /// Pivots the hits, which must be JSON objects, into columns.
+    pub fn from_hits(hits: Vec<JsonValue>) -> Result<Self, SearchError> {
+        let num_hits = hits.len();
+        let mut column_ords: HashMap<String, usize> = HashMap::new();
+        let mut columns = Vec::new();
+        let mut values: Vec<Vec<JsonValue>> = Vec::new();
+
+        for (hit_ord, hit) in hits.into_iter().enumerate() {
+            let hit_map = match hit {
+                JsonValue::Object(hit_map) => hit_map,
+                other_hit => {
+                    return Err(SearchError::Internal(format!(
+                        "failed to lay out hits as columns: hit `{}` is not a JSON object",
+                        truncate_str(&other_hit.to_string(), 100)
+                    )));
+                }
+            };
+            for (field_name, field_value) in hit_map {
+                let column_ord = match column_ords.get(&field_name) {
+                    Some(column_ord) => *column_ord,
+                    None => {
+                        let column_ord = columns.len();
+                        column_ords.insert(field_name.clone(), column_ord);
+                        columns.push(field_name);
+                        values.push(vec![JsonValue::Null; num_hits]);
+                        column_ord
+                    }
+                };
+                values[column_ord][hit_ord] = field_value;
+            }
+        }
+        Ok(Self { columns, values })
+    }
+}
+
+/// Breakdown of the time spent by the node serving a search. The time spent transferring the
+/// response is not included: clients can derive it from the latency they measure.
+#[derive(Serialize, PartialEq, Debug, utoipa::ToSchema)]
+pub struct SearchTimings {
+    /// Time spent executing the search, from the planning of the search to the fetching of the
+    /// documents.
+    pub query_execution_micros: u64,
+    /// Time spent converting the search results into the response and serializing its body.
+    pub serialization_micros: u64,
+}
+
+/// Summary of the searched splits, in the manner of the `_shards` section of Elasticsearch
+/// responses. A response with failed splits is incomplete: it lacks the hits and aggregation
+/// results of these splits.
+#[derive(Serialize, PartialEq, Debug, utoipa::ToSchema)]
+pub struct SplitsSummary {
+    /// Number of searched splits.
+    pub total: u64,
+    /// Number of splits successfully searched.
+    pub successful: u64,
+    /// Number of failed splits, including the timed out ones.
+    pub failed: u64,
+    /// IDs of the splits whose search exceeded `per_split_timeout`.
+    pub timed_out_splits: Vec<String>,
+}
+
+impl SplitsSummary {
+    fn from_search_response(search_response: &SearchResponse) -> Option<Self> {
+        if search_response.failed_splits.is_empty() {
+            return None;
+        }
+        let num_failed_splits = search_response.failed_splits.len() as u64;
+        let timed_out_splits = search_response
+            .failed_splits
+            .iter()
+            .filter(|split_error| split_error.timed_out)
+            .map(|split_error| split_error.split_id.clone())
+            .collect();
+        Some(SplitsSummary {
+            total: search_response.num_successful_splits + num_failed_splits,
+            successful: search_response.num_successful_splits,
+            failed: num_failed_splits,
+            timed_out_splits,
+        })
+    }
+}
+
+/// Adds the scores of a hit to the hit itself, as `_score` and `_raw_score`, so that they cannot
+/// be told apart from the hit they belong to. They override the fields of the hit of the same
+/// name.
+fn attach_hit_scores(hit: &mut JsonValue, hit_scores: HitScores) {
+    if let JsonValue::Object(hit_map) = hit {
+        hit_map.insert("_score".to_string(), JsonValue::from(hit_scores.score));
+        hit_map.insert(
+            "_raw_score".to_string(),
+            JsonValue::from(hit_scores.raw_score),
+        );
+    }
 }
 
 impl TryFrom<SearchResponse> for SearchResponseRest {
     type Error = SearchError;
 
     fn try_from(search_response: SearchResponse) -> Result<Self, Self::Error> {
+        let splits_summary_opt = SplitsSummary::from_search_response(&search_response);
         let mut documents = Vec::with_capacity(search_response.hits.len());
         let mut snippets = Vec::new();
         for hit in search_response.hits {
-            let document: JsonValue = serde_json::from_str(&hit.json).map_err(|err| {
+            let mut document: JsonValue = serde_json::from_str(&hit.json).map_err(|err| {
                 SearchError::Internal(format!(
                     "failed to serialize document `{}` to JSON: `{}`",
                     truncate_str(&hit.json, 100),
                     err
                 ))
             })?;
+            if let Some(hit_scores) = hit.scores {
+                attach_hit_scores(&mut document, hit_scores);
+            }
             documents.push(document);
 
             if let Some(snippet_json) = hit.snippet {
@@ -109,6 +295,17 @@ impl TryFrom<SearchResponse> for SearchResponseRest {
             elapsed_time_micros: search_response.elapsed_time_micros,
             errors: search_response.errors,
             aggregations: aggregations_opt,
+            cost_estimate: search_response.cost_estimate,
+            resolution: search_response
+                .resolution
+                .map(SearchResolutionRest::try_from)
+                .transpose()?,
+            splits: splits_summary_opt,
+            truncated: search_response.truncation_reason.is_some(),
+            truncation_reason: search_response.truncation_reason,
+            timings: None,
+            columnar_hits: None,
+            hit_bytes: None,
         })
     }
 }
@@ -125,11 +322,34 @@ pub struct SearchPlanResponseRest {
     pub tantivy_ast: String,
     /// List of splits that would be searched by this query
     pub searched_splits: Vec<String>,
+    /// Number of documents, size, and time range of the splits that would be searched by this
+    /// query, in the same order as `searched_splits`.
+    #[serde(default)]
+    pub searched_splits_details: Vec<SearchPlanSplit>,
     /// Requests expected for each split
     #[schema(value_type = Object)]
     pub storage_requests: StorageRequestCount,
 }
 
+/// A split that would be searched by a query, after time and tag pruning.
+#[derive(Serialize, Deserialize, PartialEq, Debug, utoipa::ToSchema)]
+pub struct SearchPlanSplit {
+    /// ID of the split, prefixed with the ID of its index.
+    pub split_id: String,
+    /// Number of documents in the split.
+    pub num_docs: u64,
+    /// Size of the split file, in bytes.
+    pub size_in_bytes: u64,
+    /// Timestamp of the oldest document of the split, in seconds, if the index has a timestamp
+    /// field.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_range_start: Option<i64>,
+    /// Timestamp of the most recent document of the split, in seconds, if the index has a
+    /// timestamp field.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_range_end: Option<i64>,
+}
+
 /// Number of expected storage requests, per request kind.
 ///
 /// These figures do not take in account whether the data is already cached or not.
@@ -148,3 +368,102 @@ pub struct StorageRequestCount {
     /// Number of position list downloaded
     pub position: usize,
 }
+
+#[cfg(test)]
+mod tests {
+    use quickwit_proto::search::Hit;
+    use serde_json::json;
+
+    use super::*;
+
+    /// Pivots the columns back into hits, omitting the `null` values.
+    fn columnar_hits_to_rows(columnar_hits: &ColumnarHits) -> Vec<JsonValue> {
+        let num_hits = columnar_hits.values.first().map(Vec::len).unwrap_or(0);
+        let mut rows = vec![serde_json::Map::new(); num_hits];
+
+        for (column, column_values) in columnar_hits.columns.iter().zip(&columnar_hits.values) {
+            assert_eq!(column_values.len(), num_hits);
+
+            for (row, value) in rows.iter_mut().zip(column_values) {
+                if !value.is_null() {
+                    row.insert(column.clone(), value.clone());
+                }
+            }
+        }
+        rows.into_iter().map(JsonValue::Object).collect()
+    }
+
+    #[test]
+    fn test_columnar_hits_round_trip() {
+        let hits = vec![
+            json!({"service": "api", "latency": 12, "tags": ["a", "b"]}),
+            json!({"service": "db", "attributes": {"host": "db-1"}}),
+            json!({}),
+            json!({"latency": 3.5, "service": "web"}),
+        ];
+        let columnar_hits = ColumnarHits::from_hits(hits.clone()).unwrap();
+
+        let mut columns = columnar_hits.columns.clone();
+        columns.sort();
+        assert_eq!(columns, ["attributes", "latency", "service", "tags"]);
+
+        let service_ord = columnar_hits
+            .columns
+            .iter()
+            .position(|column| column == "service")
+            .unwrap();
+        assert_eq!(
+            columnar_hits.values[service_ord],
+            [json!("api"), json!("db"), JsonValue::Null, json!("web")]
+        );
+        assert_eq!(columnar_hits_to_rows(&columnar_hits), hits);
+    }
+
+    #[test]
+    fn test_columnar_hits_edge_cases() {
+        let columnar_hits = ColumnarHits::from_hits(Vec::new()).unwrap();
+        assert!(columnar_hits.columns.is_empty());
+        assert!(columnar_hits.values.is_empty());
+
+        // Explicit `null` values cannot be told apart from missing fields.
+        let hits = vec![json!({"service": null}), json!({"service": "api"})];
+        let columnar_hits = ColumnarHits::from_hits(hits).unwrap();
+        assert_eq!(
+            columnar_hits_to_rows(&columnar_hits),
+            [json!({}), json!({"service": "api"})]
+        );
+        let error = ColumnarHits::from_hits(vec![json!("not an object")]).unwrap_err();
+        assert!(matches!(error, SearchError::Internal(_)));
+    }
+
+    #[test]
+    fn test_search_response_rest_hit_scores() {
+        let hit_scores = HitScores {
+            score: 2.0,
+            raw_score: 0.5,
+        };
+        let search_response = SearchResponse {
+            num_hits: 2,
+            hits: vec![
+                Hit {
+                    json: r#"{"title": "unscored"}"#.to_string(),
+                    ..Default::default()
+                },
+                Hit {
+                    json: r#"{"title": "scored", "_score": "overridden"}"#.to_string(),
+                    scores: Some(hit_scores),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        let search_response_rest = SearchResponseRest::try_from(search_response).unwrap();
+        assert_eq!(
+            search_response_rest.hits,
+            [
+                json!({"title": "unscored"}),
+                json!({"title": "scored", "_score": 2.0, "_raw_score": 0.5}),
+            ]
+        );
+    }
+}
@@ -12,42 +12,60 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use async_trait::async_trait;
+use futures::StreamExt;
 use quickwit_common::uri::Uri;
 use quickwit_config::SearcherConfig;
 use quickwit_doc_mapper::DocMapper;
 use quickwit_proto::metastore::MetastoreServiceClient;
 use quickwit_proto::search::{
-    FetchDocsRequest, FetchDocsResponse, GetKvRequest, Hit, LeafListFieldsRequest,
-    LeafListTermsRequest, LeafListTermsResponse, LeafSearchRequest, LeafSearchResponse,
-    ListFieldsRequest, ListFieldsResponse, ListTermsRequest, ListTermsResponse, PutKvRequest,
+    ClearCacheRequest, ClearCacheResponse, FetchDocsRequest, FetchDocsResponse, GetKvRequest, Hit,
+    LeafClearCacheRequest, LeafClearCacheResponse, LeafListFieldsRequest, LeafListTermsRequest,
+    LeafListTermsResponse, LeafSearchRequest, LeafSearchResponse, ListFieldsRequest,
+    ListFieldsResponse, ListTermsRequest, ListTermsResponse, PutKvRequest, ReadPreference,
     ReportSplitsRequest, ReportSplitsResponse, RootResourceStats, ScrollRequest,
     SearchPlanResponse, SearchRequest, SearchResponse, SnippetRequest,
 };
+use quickwit_proto::types::SplitId;
+use quickwit_query::query_ast::QueryAst;
 use quickwit_storage::{
     MemorySizedCache, QuickwitCache, SearchSplitCache, StorageCache, StorageResolver,
 };
 use tantivy::aggregation::AggregationLimitsGuard;
+use tokio::sync::SemaphorePermit;
 
+use crate::clear_cache::root_clear_cache;
 use crate::invoker::LambdaLeafSearchInvoker;
 use crate::leaf::multi_index_leaf_search;
 use crate::leaf_cache::{LeafSearchCache, PredicateCacheImpl};
 use crate::list_fields::{ListFieldsCache, leaf_list_fields, root_list_fields};
 use crate::list_terms::{leaf_list_terms, root_list_terms};
 use crate::metrics_trackers::LeafSearchMetricsFuture;
-use crate::root::fetch_docs_phase;
+use crate::root::{
+    fetch_docs_phase_within_max_response_size, prepare_root_search_batch, root_search_batch_variant,
+};
 use crate::scroll_context::{MiniKV, ScrollContext, ScrollKeyAndStartOffset};
+use crate::search_concurrency_limiter::SearchConcurrencyLimiter;
 use crate::search_permit_provider::SearchPermitProvider;
-use crate::{ClusterClient, SearchError, fetch_docs, root_search, search_plan};
+use crate::{
+    ClusterClient, InFlightLeafSearches, SearchError, fetch_docs, root_search, search_plan,
+};
+
+/// Maximum number of variants of a batch of searches searched concurrently.
+const MAX_CONCURRENT_BATCH_VARIANT_SEARCHES: usize = 10;
 
 #[derive(Clone)]
 /// The search service implementation.
 pub struct SearchServiceImpl {
     metastore: MetastoreServiceClient,
+    /// Primary metastore, set when `metastore` is a read replica.
+    primary_metastore_opt: Option<MetastoreServiceClient>,
     storage_resolver: StorageResolver,
     cluster_client: ClusterClient,
     searcher_context: Arc<SearcherContext>,
@@ -70,6 +88,17 @@ pub trait SearchService: 'static + Send + Sync {
     /// It is also in charge of merging back the responses.
     async fn root_search(&self, request: SearchRequest) -> crate::Result<SearchResponse>;
 
+    /// Runs a search request once for each filter of a batch, and returns the result of each
+    /// variant in the order of the filters.
+    ///
+    /// The targeted indexes, their doc mappers, and the query of the request resolved against
+    /// them are listed, built, and resolved once for the whole batch.
+    async fn root_search_batch(
+        &self,
+        request: SearchRequest,
+        filter_query_asts: Vec<QueryAst>,
+    ) -> crate::Result<Vec<crate::Result<SearchResponse>>>;
+
     /// Performs a leaf search on a given set of splits.
     ///
     /// It is like a regular search except that:
@@ -136,6 +165,18 @@ pub trait SearchService: 'static + Send + Sync {
     /// Returns the current load of this searcher node, expressed as the sum of job costs
     /// across all queued and active tasks in the SearchPermitProvider.
     async fn get_load(&self) -> usize;
+
+    /// Evicts the splits of the given indexes from the caches of all the searcher nodes.
+    async fn root_clear_cache(
+        &self,
+        clear_cache_request: ClearCacheRequest,
+    ) -> crate::Result<ClearCacheResponse>;
+
+    /// Evicts the given splits from the caches of this searcher node.
+    async fn leaf_clear_cache(
+        &self,
+        leaf_clear_cache_request: LeafClearCacheRequest,
+    ) -> crate::Result<LeafClearCacheResponse>;
 }
 
 impl SearchServiceImpl {
@@ -148,12 +189,31 @@ impl SearchServiceImpl {
     ) -> Self {
         SearchServiceImpl {
             metastore,
+            primary_metastore_opt: None,
             storage_resolver,
             cluster_client,
             searcher_context,
             local_kv_store: MiniKV::default(),
         }
     }
+
+    /// Sets the primary metastore, when the metastore of the search service is a read replica.
+    /// The search requests preferring the freshest reads list their splits from it.
+    pub fn with_primary_metastore(mut self, primary_metastore: MetastoreServiceClient) -> Self {
+        self.primary_metastore_opt = Some(primary_metastore);
+        self
+    }
+
+    /// Returns the metastore the splits of a search request are listed from.
+    fn metastore_for(&self, search_request: &SearchRequest) -> &MetastoreServiceClient {
+        match (
+            search_request.read_preference(),
+            &self.primary_metastore_opt,
+        ) {
+            (ReadPreference::Freshest, Some(primary_metastore)) => primary_metastore,
+            _ => &self.metastore,
+        }
+    }
 }
 
 /// Deserializes a JSON-encoded doc mapper string into an `Arc<DocMapper>`.
@@ -166,17 +226,84 @@ pub(crate) fn deserialize_doc_mapper(doc_mapper_str: &str) -> crate::Result<Arc<
 
 #[async_trait]
 impl SearchService for SearchServiceImpl {
-    async fn root_search(&self, search_request: SearchRequest) -> crate::Result<SearchResponse> {
-        let search_result = root_search(
+    async fn root_search(
+        &self,
+        mut search_request: SearchRequest,
+    ) -> crate::Result<SearchResponse> {
+        let _search_concurrency_permit_opt = self
+            .searcher_context
+            .acquire_search_concurrency_permit()
+            .await?;
+        let max_request_timeout = self.searcher_context.searcher_config.max_request_timeout();
+        let clamped_timeout_opt = clamp_search_timeout(&mut search_request, max_request_timeout);
+        let timeout_opt = search_request.timeout_millis.map(Duration::from_millis);
+        let metastore = self.metastore_for(&search_request);
+        let search_fut = root_search(
             &self.searcher_context,
             search_request,
-            &self.metastore,
+            metastore,
             &self.cluster_client,
-        )
-        .await?;
+        );
+        let mut search_result = match timeout_opt {
+            Some(timeout) => tokio::time::timeout(timeout, search_fut).await??,
+            None => search_fut.await?,
+        };
+        if let Some(requested_timeout) = clamped_timeout_opt {
+            let warning = clamped_timeout_warning(requested_timeout, max_request_timeout);
+            search_result
+                .response_headers
+                .insert("warning".to_string(), warning);
+        }
         Ok(search_result)
     }
 
+    async fn root_search_batch(
+        &self,
+        mut search_request: SearchRequest,
+        filter_query_asts: Vec<QueryAst>,
+    ) -> crate::Result<Vec<crate::Result<SearchResponse>>> {
+        let max_request_timeout = self.searcher_context.searcher_config.max_request_timeout();
+        let clamped_timeout_opt = clamp_search_timeout(&mut search_request, max_request_timeout);
+        let timeout_opt = search_request.timeout_millis.map(Duration::from_millis);
+        let metastore = self.metastore_for(&search_request);
+        let root_search_batch =
+            prepare_root_search_batch(&self.searcher_context, search_request, metastore).await?;
+
+        let variant_searches = filter_query_asts.into_iter().map(|filter_query_ast| {
+            let root_search_batch = &root_search_batch;
+
+            async move {
+                let _search_concurrency_permit_opt = self
+                    .searcher_context
+                    .acquire_search_concurrency_permit()
+                    .await?;
+                let search_fut = root_search_batch_variant(
+                    &self.searcher_context,
+                    root_search_batch,
+                    filter_query_ast,
+                    metastore,
+                    &self.cluster_client,
+                );
+                let mut search_result = match timeout_opt {
+                    Some(timeout) => tokio::time::timeout(timeout, search_fut).await??,
+                    None => search_fut.await?,
+                };
+                if let Some(requested_timeout) = clamped_timeout_opt {
+                    let warning = clamped_timeout_warning(requested_timeout, max_request_timeout);
+                    search_result
+                        .response_headers
+                        .insert("warning".to_string(), warning);
+                }
+                Ok::<_, SearchError>(search_result)
+            }
+        });
+        let variant_results = futures::stream::iter(variant_searches)
+            .buffered(MAX_CONCURRENT_BATCH_VARIANT_SEARCHES)
+            .collect()
+            .await;
+        Ok(variant_results)
+    }
+
     async fn leaf_search(
         &self,
         leaf_search_request: LeafSearchRequest,
@@ -185,12 +312,23 @@ impl SearchService for SearchServiceImpl {
         if leaf_search_request.search_request.is_none() {
             return Err(SearchError::Internal("no search request".to_string()));
         }
+        // The timeout overridden by the search request, if any, replaces the leaf timeout.
+        let searcher_config = &self.searcher_context.searcher_config;
+        let timeout = leaf_search_request
+            .search_request
+            .as_ref()
+            .and_then(|search_request| search_request.timeout_millis)
+            .map(|timeout_millis| {
+                Duration::from_millis(timeout_millis).min(searcher_config.max_request_timeout())
+            })
+            .unwrap_or_else(|| searcher_config.leaf_request_timeout());
         let num_splits = leaf_search_request
             .leaf_requests
             .iter()
             .map(|req| req.split_offsets.len())
             .sum::<usize>();
 
+        let _in_flight_leaf_search_guard = self.searcher_context.in_flight_leaf_searches.track();
         let tracked_future = LeafSearchMetricsFuture {
             tracked: multi_index_leaf_search(
                 self.searcher_context.clone(),
@@ -201,7 +339,6 @@ impl SearchService for SearchServiceImpl {
             targeted_splits: num_splits,
             status: None,
         };
-        let timeout = self.searcher_context.searcher_config.leaf_request_timeout();
         tokio::time::timeout(timeout, tracked_future).await?
     }
 
@@ -221,6 +358,7 @@ impl SearchService for SearchServiceImpl {
             &fetch_docs_request.split_offsets,
             doc_mapper,
             snippet_request_opt,
+            fetch_docs_request.score_request.as_ref(),
         )
         .await?;
 
@@ -311,31 +449,94 @@ impl SearchService for SearchServiceImpl {
         &self,
         search_request: SearchRequest,
     ) -> crate::Result<SearchPlanResponse> {
-        let search_plan = search_plan(search_request, &self.metastore).await?;
+        let metastore = self.metastore_for(&search_request);
+        let search_plan = search_plan(search_request, metastore).await?;
         Ok(search_plan)
     }
 
     async fn get_load(&self) -> usize {
         self.searcher_context.search_permit_provider.get_load()
     }
+
+    async fn root_clear_cache(
+        &self,
+        clear_cache_request: ClearCacheRequest,
+    ) -> crate::Result<ClearCacheResponse> {
+        root_clear_cache(clear_cache_request, &self.metastore, &self.cluster_client).await
+    }
+
+    async fn leaf_clear_cache(
+        &self,
+        leaf_clear_cache_request: LeafClearCacheRequest,
+    ) -> crate::Result<LeafClearCacheResponse> {
+        let split_ids: HashSet<&str> = leaf_clear_cache_request
+            .split_ids
+            .iter()
+            .map(String::as_str)
+            .collect();
+        self.searcher_context
+            .split_footer_cache
+            .evict_if(|split_id| split_ids.contains(split_id.as_str()));
+        self.searcher_context
+            .leaf_search_cache
+            .evict_splits(&split_ids);
+        self.searcher_context
+            .predicate_cache
+            .evict_splits(&split_ids);
+        self.searcher_context
+            .list_fields_cache
+            .evict_splits(&split_ids);
+        // The fast fields of a split are cached under its ID.
+        let split_dir_paths: Vec<PathBuf> = split_ids.iter().map(PathBuf::from).collect();
+        self.searcher_context
+            .fast_fields_cache
+            .evict_dirs(&split_dir_paths)
+            .await;
+
+        let Some(split_cache) = self.searcher_context.split_cache_opt.as_ref() else {
+            return Ok(LeafClearCacheResponse::default());
+        };
+        let split_ids: Vec<SplitId> = split_ids.into_iter().map(SplitId::from).collect();
+        let num_evicted_splits = split_cache.evict_splits(&split_ids) as u64;
+        Ok(LeafClearCacheResponse { num_evicted_splits })
+    }
 }
 
-pub(crate) async fn scroll(
-    scroll_request: ScrollRequest,
+async fn load_scroll_context(
+    scroll_id: &str,
     cluster_client: &ClusterClient,
-    searcher_context: &SearcherContext,
-) -> crate::Result<SearchResponse> {
-    let start = Instant::now();
-    let current_scroll = ScrollKeyAndStartOffset::from_str(&scroll_request.scroll_id)
+) -> crate::Result<(ScrollKeyAndStartOffset, ScrollContext)> {
+    let current_scroll = ScrollKeyAndStartOffset::from_str(scroll_id)
         .map_err(|msg| SearchError::InvalidArgument(msg.to_string()))?;
-    let start_doc = current_scroll.start_offset;
     let scroll_key: [u8; 16] = current_scroll.scroll_key();
     let payload = cluster_client.get_kv(&scroll_key[..]).await;
     let payload =
         payload.ok_or_else(|| SearchError::Internal("scroll key not found".to_string()))?;
 
-    let mut scroll_context = ScrollContext::load(&payload)
+    let scroll_context = ScrollContext::load(&payload)
         .map_err(|_| SearchError::Internal("corrupted Scroll context".to_string()))?;
+    Ok((current_scroll, scroll_context))
+}
+
+/// Returns the search request a scroll replays, as it was when the scroll was opened.
+pub async fn scroll_search_request(
+    scroll_id: &str,
+    cluster_client: &ClusterClient,
+) -> crate::Result<SearchRequest> {
+    let (_, scroll_context) = load_scroll_context(scroll_id, cluster_client).await?;
+    Ok(scroll_context.search_request)
+}
+
+pub(crate) async fn scroll(
+    scroll_request: ScrollRequest,
+    cluster_client: &ClusterClient,
+    searcher_context: &SearcherContext,
+) -> crate::Result<SearchResponse> {
+    let start = Instant::now();
+    let (current_scroll, mut scroll_context) =
+        load_scroll_context(&scroll_request.scroll_id, cluster_client).await?;
+    let start_doc = current_scroll.start_offset;
+    let scroll_key: [u8; 16] = current_scroll.scroll_key();
 
     let end_doc: u64 = start_doc + scroll_context.max_hits_per_page;
 
@@ -359,19 +560,26 @@ pub(crate) async fn scroll(
     }
 
     // Fetch the actual documents.
-    let hits: Vec<Hit> = fetch_docs_phase(
-        &scroll_context.indexes_metas_for_leaf_search,
-        &partial_hits[..],
-        &scroll_context.split_metadatas[..],
-        &scroll_context.search_request,
-        cluster_client,
-    )
-    .await?;
+    let (hits, truncation_reason_opt): (Vec<Hit>, Option<String>) =
+        fetch_docs_phase_within_max_response_size(
+            &scroll_context.indexes_metas_for_leaf_search,
+            &partial_hits[..],
+            &scroll_context.split_metadatas[..],
+            &scroll_context.search_request,
+            scroll_context.max_response_size,
+            cluster_client,
+        )
+        .await?;
 
-    let next_scroll_id = current_scroll.next_page(
-        hits.len() as u64,
-        partial_hits.last().cloned().unwrap_or_default(),
-    );
+    let next_scroll_id = if truncation_reason_opt.is_some() {
+        let last_returned_hit_opt = hits.last().and_then(|hit| hit.partial_hit.clone());
+        current_scroll.next_truncated_page(hits.len() as u64, last_returned_hit_opt)
+    } else {
+        current_scroll.next_page(
+            hits.len() as u64,
+            partial_hits.last().cloned().unwrap_or_default(),
+        )
+    };
 
     if let Some(scroll_ttl_secs) = scroll_request.scroll_ttl_secs
         && scroll_context_modified
@@ -397,8 +605,39 @@ pub(crate) async fn scroll(
         // `search_partial_hits_phase`. Pure cache hits (served entirely from
         // the scroll context) carry `None` because no leaf search ran.
         resource_stats,
+        response_headers: HashMap::new(),
+        cost_estimate: None,
+        truncation_reason: truncation_reason_opt,
+        resolution: None,
+        searched_index_ids: Vec::new(),
     })
 }
+
+/// Bounds the timeout overridden by the search request, if any, by `max_request_timeout`.
+/// Returns the timeout requested by the client if it was clamped.
+fn clamp_search_timeout(
+    search_request: &mut SearchRequest,
+    max_request_timeout: Duration,
+) -> Option<Duration> {
+    let requested_timeout = Duration::from_millis(search_request.timeout_millis?);
+
+    if requested_timeout <= max_request_timeout {
+        return None;
+    }
+    search_request.timeout_millis = Some(max_request_timeout.as_millis() as u64);
+    Some(requested_timeout)
+}
+
+/// Returns the warning telling the client that the timeout of its search request was clamped.
+fn clamped_timeout_warning(requested_timeout: Duration, max_request_timeout: Duration) -> String {
+    format!(
+        "299 quickwit \"search timeout of {}ms exceeds the maximum of the searchers and was \
+         clamped to {}ms\"",
+        requested_timeout.as_millis(),
+        max_request_timeout.as_millis()
+    )
+}
+
 /// [`SearcherContext`] provides a common set of variables
 /// shared by a searcher instance (which instantiates a
 /// [`SearchServiceImpl`]).
@@ -424,6 +663,10 @@ pub struct SearcherContext {
     pub aggregation_limit: AggregationLimitsGuard,
     /// Optional Lambda invoker for offloading leaf search to serverless functions.
     pub lambda_invoker: Option<Arc<dyn LambdaLeafSearchInvoker>>,
+    /// Bounds the number of concurrent root searches. `None` if the concurrency is unbounded.
+    pub(crate) search_concurrency_limiter_opt: Option<SearchConcurrencyLimiter>,
+    /// Leaf searches running on the node.
+    pub in_flight_leaf_searches: InFlightLeafSearches,
 }
 
 impl std::fmt::Debug for SearcherContext {
@@ -480,6 +723,10 @@ impl SearcherContext {
 
         let lambda_invoker =
             lambda_invoker.map(|invoker| Arc::new(invoker) as Arc<dyn LambdaLeafSearchInvoker>);
+        let search_concurrency_limiter_opt = searcher_config
+            .search_concurrency
+            .as_ref()
+            .map(SearchConcurrencyLimiter::from_config);
 
         Self {
             searcher_config,
@@ -492,6 +739,8 @@ impl SearcherContext {
             split_cache_opt,
             aggregation_limit,
             lambda_invoker,
+            search_concurrency_limiter_opt,
+            in_flight_leaf_searches: InFlightLeafSearches::default(),
         }
     }
 
@@ -499,4 +748,239 @@ impl SearcherContext {
     pub fn get_aggregation_limits(&self) -> AggregationLimitsGuard {
         self.aggregation_limit.clone()
     }
+
+    /// Returns the maximum number of buckets of the aggregations of a search request, set from
+    /// the `max_aggregation_buckets` search setting of the targeted indexes. It can only lower the
+    /// bucket limit of the searcher config, and the aggregations remain subject to the shared
+    /// limits returned by [`Self::get_aggregation_limits`], memory included.
+    pub fn get_max_aggregation_buckets_for_request(
+        &self,
+        search_request: &SearchRequest,
+    ) -> Option<u32> {
+        search_request
+            .max_aggregation_buckets
+            .map(|max_aggregation_buckets| {
+                max_aggregation_buckets.min(self.searcher_config.aggregation_bucket_limit)
+            })
+    }
+
+    /// Waits for a slot to run a root search if the number of concurrent root searches is
+    /// bounded. The slot is released when the permit is dropped.
+    pub(crate) async fn acquire_search_concurrency_permit(
+        &self,
+    ) -> crate::Result<Option<SemaphorePermit<'_>>> {
+        let Some(search_concurrency_limiter) = &self.search_concurrency_limiter_opt else {
+            return Ok(None);
+        };
+        let search_concurrency_permit = search_concurrency_limiter.acquire().await?;
+        Ok(Some(search_concurrency_permit))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroU64;
+    use std::sync::Mutex;
+
+    use quickwit_common::ServiceStream;
+    use quickwit_indexing::MockSplitBuilder;
+    use quickwit_metastore::{IndexMetadata, ListSplitsResponseExt};
+    use quickwit_proto::metastore::{
+        ListIndexesMetadataResponse, ListSplitsResponse, MockMetastoreService,
+    };
+    use quickwit_query::query_ast::qast_json_helper;
+
+    use super::*;
+    use crate::{MockSearchService, SearchJobPlacer, searcher_pool_for_test};
+
+    fn mock_metastore_with_splits(split_ids: &'static [&'static str]) -> MetastoreServiceClient {
+        let mut mock_metastore = MockMetastoreService::new();
+        let index_metadata = IndexMetadata::for_test("test-index", "ram:///test-index");
+        let index_uid = index_metadata.index_uid.clone();
+        mock_metastore.expect_list_indexes_metadata().returning(
+            move |_list_indexes_metadata_request| {
+                Ok(ListIndexesMetadataResponse::for_test(vec![
+                    index_metadata.clone(),
+                ]))
+            },
+        );
+        mock_metastore
+            .expect_list_splits()
+            .returning(move |_list_splits_request| {
+                let splits = split_ids
+                    .iter()
+                    .map(|split_id| {
+                        MockSplitBuilder::new(split_id)
+                            .with_index_uid(&index_uid)
+                            .build()
+                    })
+                    .collect();
+                let splits_response = ListSplitsResponse::try_from_splits(splits).unwrap();
+                Ok(ServiceStream::from(vec![Ok(splits_response)]))
+            });
+        MetastoreServiceClient::from_mock(mock_metastore)
+    }
+
+    fn mock_leaf_search_service(searched_split_ids: Arc<Mutex<Vec<String>>>) -> MockSearchService {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service.expect_leaf_search().returning(
+            move |leaf_search_request: LeafSearchRequest| {
+                let mut searched_split_ids_guard = searched_split_ids.lock().unwrap();
+                let mut num_splits = 0;
+
+                for split_offsets in leaf_search_request
+                    .leaf_requests
+                    .iter()
+                    .flat_map(|leaf_request| &leaf_request.split_offsets)
+                {
+                    searched_split_ids_guard.push(split_offsets.split_id.clone());
+                    num_splits += 1;
+                }
+                Ok(LeafSearchResponse {
+                    num_hits: num_splits,
+                    num_attempted_splits: num_splits,
+                    num_successful_splits: num_splits,
+                    ..Default::default()
+                })
+            },
+        );
+        mock_search_service
+    }
+
+    #[tokio::test]
+    async fn test_root_search_read_preference() {
+        // The read replica lags behind the primary metastore, which has already published
+        // `split2`.
+        let replica_metastore = mock_metastore_with_splits(&["split1"]);
+        let primary_metastore = mock_metastore_with_splits(&["split1", "split2"]);
+
+        let searched_split_ids: Arc<Mutex<Vec<String>>> = Arc::default();
+        let searcher_pool = searcher_pool_for_test([
+            (
+                "127.0.0.1:1001",
+                mock_leaf_search_service(searched_split_ids.clone()),
+            ),
+            (
+                "127.0.0.1:1002",
+                mock_leaf_search_service(searched_split_ids.clone()),
+            ),
+        ]);
+        let cluster_client = ClusterClient::new(SearchJobPlacer::new(searcher_pool));
+        let search_service = SearchServiceImpl::new(
+            replica_metastore,
+            StorageResolver::for_test(),
+            cluster_client,
+            Arc::new(SearcherContext::for_test()),
+        )
+        .with_primary_metastore(primary_metastore);
+
+        for (read_preference, expected_split_ids) in [
+            (ReadPreference::Fastest, vec!["split1"]),
+            (ReadPreference::Freshest, vec!["split1", "split2"]),
+        ] {
+            let search_request = SearchRequest {
+                index_id_patterns: vec!["test-index".to_string()],
+                query_ast: qast_json_helper("test", &["body"]),
+                max_hits: 0,
+                read_preference: read_preference as i32,
+                ..Default::default()
+            };
+            let search_response = search_service.root_search(search_request).await.unwrap();
+            assert_eq!(search_response.num_hits, expected_split_ids.len() as u64);
+
+            let mut split_ids = std::mem::take(&mut *searched_split_ids.lock().unwrap());
+            split_ids.sort();
+            assert_eq!(split_ids, expected_split_ids);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_root_search_timeout_override() {
+        let leaf_timeouts_millis: Arc<Mutex<Vec<Option<u64>>>> = Arc::default();
+        let leaf_timeouts_millis_clone = leaf_timeouts_millis.clone();
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service.expect_leaf_search().returning(
+            move |leaf_search_request: LeafSearchRequest| {
+                let timeout_millis_opt = leaf_search_request
+                    .search_request
+                    .and_then(|search_request| search_request.timeout_millis);
+                leaf_timeouts_millis_clone
+                    .lock()
+                    .unwrap()
+                    .push(timeout_millis_opt);
+                Ok(LeafSearchResponse {
+                    num_attempted_splits: 1,
+                    num_successful_splits: 1,
+                    ..Default::default()
+                })
+            },
+        );
+        let searcher_pool = searcher_pool_for_test([("127.0.0.1:1001", mock_search_service)]);
+        let cluster_client = ClusterClient::new(SearchJobPlacer::new(searcher_pool));
+        let searcher_config = SearcherConfig {
+            max_request_timeout_secs: NonZeroU64::new(60),
+            ..Default::default()
+        };
+        let search_service = SearchServiceImpl::new(
+            mock_metastore_with_splits(&["split1"]),
+            StorageResolver::for_test(),
+            cluster_client,
+            Arc::new(SearcherContext::new_without_invoker(searcher_config, None)),
+        );
+        let search_request = SearchRequest {
+            index_id_patterns: vec!["test-index".to_string()],
+            query_ast: qast_json_helper("test", &["body"]),
+            max_hits: 0,
+            ..Default::default()
+        };
+
+        // An override within the maximum applies as is.
+        let search_response = search_service
+            .root_search(SearchRequest {
+                timeout_millis: Some(45_000),
+                ..search_request.clone()
+            })
+            .await
+            .unwrap();
+        assert!(!search_response.response_headers.contains_key("warning"));
+        assert_eq!(
+            std::mem::take(&mut *leaf_timeouts_millis.lock().unwrap()),
+            [Some(45_000)]
+        );
+
+        // An override beyond the maximum is clamped, with a warning.
+        let search_response = search_service
+            .root_search(SearchRequest {
+                timeout_millis: Some(120_000),
+                ..search_request
+            })
+            .await
+            .unwrap();
+        let warning = &search_response.response_headers["warning"];
+        assert!(warning.contains("clamped to 60000ms"));
+        assert_eq!(
+            std::mem::take(&mut *leaf_timeouts_millis.lock().unwrap()),
+            [Some(60_000)]
+        );
+    }
+
+    #[test]
+    fn test_clamp_search_timeout() {
+        let max_request_timeout = Duration::from_secs(60);
+
+        let mut search_request = SearchRequest::default();
+        assert!(clamp_search_timeout(&mut search_request, max_request_timeout).is_none());
+        assert!(search_request.timeout_millis.is_none());
+
+        search_request.timeout_millis = Some(60_000);
+        assert!(clamp_search_timeout(&mut search_request, max_request_timeout).is_none());
+        assert_eq!(search_request.timeout_millis, Some(60_000));
+
+        search_request.timeout_millis = Some(90_000);
+        assert_eq!(
+            clamp_search_timeout(&mut search_request, max_request_timeout),
+            Some(Duration::from_secs(90))
+        );
+        assert_eq!(search_request.timeout_millis, Some(60_000));
+    }
 }
@@ -0,0 +1,400 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+use anyhow::{Context, bail};
+use tantivy::columnar::{ColumnType, MonotonicallyMappableToU64};
+use tantivy::fastfield::Column;
+use tantivy::{DocId, SegmentReader};
+
+/// Name of the sort field sorting the hits by the value of the sort script.
+pub const SORT_SCRIPT_FIELD_NAME: &str = "_script";
+
+const MAX_SCRIPT_LEN: usize = 1_024;
+
+const MAX_SCRIPT_DEPTH: usize = 32;
+
+const NANOS_PER_SEC: f64 = 1_000_000_000.0;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+impl BinaryOp {
+    fn apply(self, left: f64, right: f64) -> f64 {
+        match self {
+            BinaryOp::Add => left + right,
+            BinaryOp::Sub => left - right,
+            BinaryOp::Mul => left * right,
+            BinaryOp::Div => left / right,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum ScriptExpr {
+    Literal(f64),
+    /// Index of the field in [`SortScript::field_names`].
+    Field(usize),
+    Neg(Box<ScriptExpr>),
+    Binary(BinaryOp, Box<ScriptExpr>, Box<ScriptExpr>),
+}
+
+/// An arithmetic expression over numeric fast fields, used to sort the hits by a computed value,
+/// for instance `popularity * 0.7 + recency * 0.3`.
+///
+/// The language is purposely small: it supports numeric literals, field names, the `+`, `-`, `*`
+/// and `/` operators, and parentheses. Datetime fields evaluate to seconds since the Unix epoch.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct SortScript {
+    expr: ScriptExpr,
+    field_names: Vec<String>,
+}
+
+impl SortScript {
+    /// Parses a sort script.
+    pub fn parse(script: &str) -> anyhow::Result<Self> {
+        if script.len() > MAX_SCRIPT_LEN {
+            bail!("sort script must be at most {MAX_SCRIPT_LEN} characters long");
+        }
+        let mut parser = ScriptParser {
+            script,
+            chars: script.char_indices().peekable(),
+            field_names: Vec::new(),
+            depth: 0,
+        };
+        let expr = parser.parse_expr()?;
+        parser.skip_whitespaces();
+
+        if let Some((position, character)) = parser.chars.next() {
+            bail!("unexpected character `{character}` at position {position} in sort script");
+        }
+        Ok(SortScript {
+            expr,
+            field_names: parser.field_names,
+        })
+    }
+
+    /// Returns the names of the fields referenced by the script.
+    pub fn field_names(&self) -> &[String] {
+        &self.field_names
+    }
+
+    /// Loads the fast field columns of the script for the given segment.
+    pub fn for_segment(self, segment_reader: &SegmentReader) -> tantivy::Result<SegmentSortScript> {
+        let mut columns = Vec::with_capacity(self.field_names.len());
+
+        for field_name in &self.field_names {
+            let column_opt: Option<(Column<u64>, ColumnType)> =
+                segment_reader.fast_fields().u64_lenient(field_name)?;
+            let column = column_opt.unwrap_or_else(|| {
+                (
+                    Column::build_empty_column(segment_reader.max_doc()),
+                    ColumnType::U64,
+                )
+            });
+            columns.push(column);
+        }
+        Ok(SegmentSortScript {
+            expr: self.expr,
+            columns,
+        })
+    }
+}
+
+/// A sort script bound to the fast field columns of a segment.
+pub(crate) struct SegmentSortScript {
+    expr: ScriptExpr,
+    columns: Vec<(Column<u64>, ColumnType)>,
+}
+
+impl SegmentSortScript {
+    /// Evaluates the script for the given document.
+    ///
+    /// Returns `None` if one of the fields has no value for the document or if the result is not
+    /// a finite number, for instance after a division by zero.
+    pub fn eval(&self, doc_id: DocId) -> Option<f64> {
+        self.eval_expr(&self.expr, doc_id)
+            .filter(|value| value.is_finite())
+    }
+
+    fn eval_expr(&self, expr: &ScriptExpr, doc_id: DocId) -> Option<f64> {
+        match expr {
+            ScriptExpr::Literal(value) => Some(*value),
+            ScriptExpr::Field(field_idx) => {
+                let (column, column_type) = &self.columns[*field_idx];
+                let value = column.first(doc_id)?;
+                column_value_to_f64(value, *column_type)
+            }
+            ScriptExpr::Neg(operand) => Some(-self.eval_expr(operand, doc_id)?),
+            ScriptExpr::Binary(op, left, right) => {
+                let left_value = self.eval_expr(left, doc_id)?;
+                let right_value = self.eval_expr(right, doc_id)?;
+                Some(op.apply(left_value, right_value))
+            }
+        }
+    }
+}
+
+fn column_value_to_f64(value: u64, column_type: ColumnType) -> Option<f64> {
+    match column_type {
+        ColumnType::U64 => Some(value as f64),
+        ColumnType::I64 => Some(i64::from_u64(value) as f64),
+        ColumnType::F64 => Some(f64::from_u64(value)),
+        ColumnType::Bool => Some(value as f64),
+        ColumnType::DateTime => Some(i64::from_u64(value) as f64 / NANOS_PER_SEC),
+        _ => None,
+    }
+}
+
+/// Recursive descent parser of the sort scripts.
+///
+/// ```text
+/// expr   := term (('+' | '-') term)*
+/// term   := factor (('*' | '/') factor)*
+/// factor := '-' factor | '(' expr ')' | number | field
+/// ```
+struct ScriptParser<'a> {
+    script: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+    field_names: Vec<String>,
+    depth: usize,
+}
+
+impl ScriptParser<'_> {
+    fn skip_whitespaces(&mut self) {
+        while self
+            .chars
+            .next_if(|(_, character)| character.is_whitespace())
+            .is_some()
+        {}
+    }
+
+    fn peek_char(&mut self) -> Option<char> {
+        self.skip_whitespaces();
+        self.chars.peek().map(|(_, character)| *character)
+    }
+
+    fn parse_expr(&mut self) -> anyhow::Result<ScriptExpr> {
+        self.depth += 1;
+
+        if self.depth > MAX_SCRIPT_DEPTH {
+            bail!("sort script must not be nested more than {MAX_SCRIPT_DEPTH} levels deep");
+        }
+        let mut expr = self.parse_term()?;
+
+        loop {
+            let op = match self.peek_char() {
+                Some('+') => BinaryOp::Add,
+                Some('-') => BinaryOp::Sub,
+                _ => break,
+            };
+            self.chars.next();
+            let right = self.parse_term()?;
+            expr = ScriptExpr::Binary(op, Box::new(expr), Box::new(right));
+        }
+        self.depth -= 1;
+        Ok(expr)
+    }
+
+    fn parse_term(&mut self) -> anyhow::Result<ScriptExpr> {
+        let mut expr = self.parse_factor()?;
+
+        loop {
+            let op = match self.peek_char() {
+                Some('*') => BinaryOp::Mul,
+                Some('/') => BinaryOp::Div,
+                _ => break,
+            };
+            self.chars.next();
+            let right = self.parse_factor()?;
+            expr = ScriptExpr::Binary(op, Box::new(expr), Box::new(right));
+        }
+        Ok(expr)
+    }
+
+    fn parse_factor(&mut self) -> anyhow::Result<ScriptExpr> {
+        let Some(character) = self.peek_char() else {
+            bail!("unexpected end of sort script");
+        };
+        match character {
+            '-' => {
+                self.chars.next();
+                self.depth += 1;
+
+                if self.depth > MAX_SCRIPT_DEPTH {
+                    bail!(
+                        "sort script must not be nested more than {MAX_SCRIPT_DEPTH} levels deep"
+                    );
+                }
+                let operand = self.parse_factor()?;
+                self.depth -= 1;
+                Ok(ScriptExpr::Neg(Box::new(operand)))
+            }
+            '(' => {
+                self.chars.next();
+                let expr = self.parse_expr()?;
+
+                if self.peek_char() != Some(')') {
+                    bail!("missing closing parenthesis in sort script");
+                }
+                self.chars.next();
+                Ok(expr)
+            }
+            '0'..='9' | '.' => self.parse_number(),
+            _ if is_field_name_start(character) => Ok(self.parse_field()),
+            _ => {
+                let position = self.position();
+                bail!("unexpected character `{character}` at position {position} in sort script")
+            }
+        }
+    }
+
+    fn parse_number(&mut self) -> anyhow::Result<ScriptExpr> {
+        let start = self.position();
+
+        while self
+            .chars
+            .next_if(|(_, character)| character.is_ascii_digit() || *character == '.')
+            .is_some()
+        {}
+        let number_str = &self.script[start..self.position()];
+        let number: f64 = number_str
+            .parse()
+            .with_context(|| format!("invalid number `{number_str}` in sort script"))?;
+        Ok(ScriptExpr::Literal(number))
+    }
+
+    fn parse_field(&mut self) -> ScriptExpr {
+        let start = self.position();
+
+        while self
+            .chars
+            .next_if(|(_, character)| is_field_name_char(*character))
+            .is_some()
+        {}
+        let field_name = &self.script[start..self.position()];
+        let field_idx = if let Some(field_idx) = self
+            .field_names
+            .iter()
+            .position(|other_field_name| other_field_name == field_name)
+        {
+            field_idx
+        } else {
+            self.field_names.push(field_name.to_string());
+            self.field_names.len() - 1
+        };
+        ScriptExpr::Field(field_idx)
+    }
+
+    fn position(&mut self) -> usize {
+        self.chars
+            .peek()
+            .map(|(position, _)| *position)
+            .unwrap_or(self.script.len())
+    }
+}
+
+fn is_field_name_start(character: char) -> bool {
+    character.is_ascii_alphabetic() || character == '_'
+}
+
+fn is_field_name_char(character: char) -> bool {
+    character.is_ascii_alphanumeric() || character == '_' || character == '.'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval_script(script: &str, field_values: &[f64]) -> f64 {
+        let sort_script = SortScript::parse(script).unwrap();
+        eval_script_expr(&sort_script.expr, field_values)
+    }
+
+    fn eval_script_expr(expr: &ScriptExpr, field_values: &[f64]) -> f64 {
+        match expr {
+            ScriptExpr::Literal(value) => *value,
+            ScriptExpr::Field(field_idx) => field_values[*field_idx],
+            ScriptExpr::Neg(operand) => -eval_script_expr(operand, field_values),
+            ScriptExpr::Binary(op, left, right) => op.apply(
+                eval_script_expr(left, field_values),
+                eval_script_expr(right, field_values),
+            ),
+        }
+    }
+
+    #[test]
+    fn test_sort_script_parse() {
+        let sort_script = SortScript::parse("popularity * 0.7 + recency * 0.3").unwrap();
+        assert_eq!(sort_script.field_names(), ["popularity", "recency"]);
+
+        let sort_script = SortScript::parse("attributes.score/(attributes.score+1)").unwrap();
+        assert_eq!(sort_script.field_names(), ["attributes.score"]);
+
+        assert_eq!(eval_script("1 + 2 * 3", &[]), 7.0);
+        assert_eq!(eval_script("(1 + 2) * 3", &[]), 9.0);
+        assert_eq!(eval_script("10 - 4 - 3", &[]), 3.0);
+        assert_eq!(eval_script("8 / 2 / 2", &[]), 2.0);
+        assert_eq!(eval_script("--2 * -a", &[3.0]), -6.0);
+        assert_eq!(eval_script("a * 0.5 + b", &[4.0, 1.0]), 3.0);
+    }
+
+    #[test]
+    fn test_sort_script_parse_errors() {
+        for script in [
+            "",
+            "a +",
+            "(a + b",
+            "a + b)",
+            "a b",
+            "1..2",
+            "a; b",
+            "a + 'b'",
+            "doc['a'].value",
+        ] {
+            SortScript::parse(script).unwrap_err();
+        }
+        let deeply_nested_script = format!("{}a{}", "(".repeat(100), ")".repeat(100));
+        SortScript::parse(&deeply_nested_script).unwrap_err();
+
+        let long_script = vec!["a"; 1_000].join(" + ");
+        SortScript::parse(&long_script).unwrap_err();
+    }
+
+    #[test]
+    fn test_column_value_to_f64() {
+        assert_eq!(column_value_to_f64(3, ColumnType::U64), Some(3.0));
+        assert_eq!(
+            column_value_to_f64((-3i64).to_u64(), ColumnType::I64),
+            Some(-3.0)
+        );
+        assert_eq!(
+            column_value_to_f64(1.5f64.to_u64(), ColumnType::F64),
+            Some(1.5)
+        );
+        assert_eq!(column_value_to_f64(1, ColumnType::Bool), Some(1.0));
+        assert_eq!(
+            column_value_to_f64(2_000_000_000i64.to_u64(), ColumnType::DateTime),
+            Some(2.0)
+        );
+        assert_eq!(column_value_to_f64(1, ColumnType::Str), None);
+    }
+}
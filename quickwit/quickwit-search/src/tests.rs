@@ -20,9 +20,11 @@ use quickwit_config::SearcherConfig;
 use quickwit_doc_mapper::DocMapper;
 use quickwit_doc_mapper::tag_pruning::extract_tags_from_query;
 use quickwit_indexing::TestSandbox;
+use quickwit_metastore::{IndexMetadataResponseExt, UpdateIndexRequestExt};
+use quickwit_proto::metastore::{IndexMetadataRequest, MetastoreService, UpdateIndexRequest};
 use quickwit_proto::search::{
-    LeafListTermsResponse, ListTermsRequest, SearchRequest, SortByValue, SortField, SortOrder,
-    SortValue, TraceId,
+    LeafListTermsResponse, ListTermsRequest, ScoreThreshold, SearchRequest, SortByValue, SortField,
+    SortOrder, SortValue, TraceId,
 };
 use quickwit_query::query_ast::{
     HitSet, PredicateCache, QueryAst, qast_helper, qast_json_helper, query_ast_from_user_text,
@@ -362,6 +364,7 @@ async fn test_single_node_filtering() -> anyhow::Result<()> {
             field_name: "ts".to_string(),
             sort_order: SortOrder::Desc as i32,
             sort_datetime_format: None,
+            script: None,
         }],
         ..Default::default()
     };
@@ -386,6 +389,7 @@ async fn test_single_node_filtering() -> anyhow::Result<()> {
             field_name: "ts".to_string(),
             sort_order: SortOrder::Desc as i32,
             sort_datetime_format: None,
+            script: None,
         }],
         ..Default::default()
     };
@@ -409,6 +413,7 @@ async fn test_single_node_filtering() -> anyhow::Result<()> {
             field_name: "ts".to_string(),
             sort_order: SortOrder::Desc as i32,
             sort_datetime_format: None,
+            script: None,
         }],
         ..Default::default()
     };
@@ -554,6 +559,7 @@ async fn single_node_search_sort_by_field(
             field_name: sort_by_field.to_string(),
             sort_order: SortOrder::Desc as i32,
             sort_datetime_format: None,
+            script: None,
         }],
         ..Default::default()
     };
@@ -642,6 +648,7 @@ async fn test_sort_bm25() {
                 field_name: "_score".to_string(),
                 sort_order: SortOrder::Desc as i32,
                 sort_datetime_format: None,
+                script: None,
             }],
             ..Default::default()
         };
@@ -690,6 +697,284 @@ async fn test_sort_bm25() {
     test_sandbox.assert_quit().await;
 }
 
+#[tokio::test]
+async fn test_search_min_score() {
+    let index_id = "search_min_score".to_string();
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: title
+                type: text
+                record: freq
+                fieldnorms: true
+            "#;
+    let test_sandbox = TestSandbox::create(&index_id, doc_mapping_yaml, "{}", &["title"])
+        .await
+        .unwrap();
+    let docs = vec![
+        json!({"title": "one pad"}), // 0: 0.12343242
+        json!({"title": "one"}),     // 1: 0.15965714
+        json!({"title": "one one"}), // 2: 0.1738279
+    ];
+    test_sandbox.add_documents(docs).await.unwrap();
+    let search_hits = |min_score: f32, start_offset: u64, max_hits: u64, sort_by_score: bool| {
+        let query_ast_json =
+            serde_json::to_string(&query_ast_from_user_text("title:one", None)).unwrap();
+        let sort_fields = if sort_by_score {
+            vec![SortField {
+                field_name: "_score".to_string(),
+                sort_order: SortOrder::Desc as i32,
+                sort_datetime_format: None,
+                script: None,
+            }]
+        } else {
+            Vec::new()
+        };
+        let search_request = SearchRequest {
+            index_id_patterns: vec![index_id.to_string()],
+            query_ast: query_ast_json,
+            start_offset,
+            max_hits,
+            sort_fields,
+            min_score: Some(ScoreThreshold { value: min_score }),
+            ..Default::default()
+        };
+        let metastore = test_sandbox.metastore();
+        let storage_resolver = test_sandbox.storage_resolver();
+        async move {
+            let search_resp = single_node_search(search_request, metastore, storage_resolver)
+                .await
+                .unwrap();
+            let doc_ids: Vec<u32> = search_resp
+                .hits
+                .into_iter()
+                .map(|hit| hit.partial_hit.unwrap().doc_id)
+                .collect();
+            (search_resp.num_hits, doc_ids)
+        }
+    };
+    // The hits below the threshold are neither returned nor counted.
+    assert_eq!(search_hits(0.15, 0, 10, true).await, (2, vec![2, 1]));
+    // The hits are filtered before the pagination is applied.
+    assert_eq!(search_hits(0.15, 0, 1, true).await, (2, vec![2]));
+    assert_eq!(search_hits(0.15, 1, 1, true).await, (2, vec![1]));
+    assert_eq!(search_hits(0.15, 2, 1, true).await, (2, Vec::new()));
+    // The hits are scored even if they are not sorted by score.
+    let (num_hits, mut doc_ids) = search_hits(0.15, 0, 10, false).await;
+    doc_ids.sort();
+    assert_eq!((num_hits, doc_ids), (2, vec![1, 2]));
+    // Only counting the hits still applies the threshold.
+    assert_eq!(search_hits(0.15, 0, 0, false).await, (2, Vec::new()));
+    assert_eq!(search_hits(1.0, 0, 10, true).await, (0, Vec::new()));
+    test_sandbox.assert_quit().await;
+}
+
+#[tokio::test]
+async fn test_search_scoring() {
+    let index_id = "search_scoring".to_string();
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: body
+                type: text
+                record: freq
+                fieldnorms: true
+            "#;
+    let test_sandbox = TestSandbox::create(&index_id, doc_mapping_yaml, "{}", &["body"])
+        .await
+        .unwrap();
+    let docs = vec![
+        json!({"body": "apple banana"}),
+        json!({"body": "apple apple apple apple cherry cherry cherry cherry cherry cherry"}),
+        json!({"body": "cherry"}),
+    ];
+    test_sandbox.add_documents(docs).await.unwrap();
+    let search_hits = |scoring_opt: Option<&str>| {
+        let query_ast_json =
+            serde_json::to_string(&query_ast_from_user_text("body:apple", None)).unwrap();
+        let search_request = SearchRequest {
+            index_id_patterns: vec![index_id.to_string()],
+            query_ast: query_ast_json,
+            max_hits: 10,
+            sort_fields: vec![SortField {
+                field_name: "_score".to_string(),
+                sort_order: SortOrder::Desc as i32,
+                sort_datetime_format: None,
+                script: None,
+            }],
+            scoring: scoring_opt.map(str::to_string),
+            ..Default::default()
+        };
+        let metastore = test_sandbox.metastore();
+        let storage_resolver = test_sandbox.storage_resolver();
+        async move {
+            let search_resp =
+                single_node_search(search_request, metastore, storage_resolver).await?;
+            let hits: Vec<(u32, f32)> = search_resp
+                .hits
+                .into_iter()
+                .map(|hit| {
+                    let partial_hit = hit.partial_hit.unwrap();
+                    let Some(SortByValue {
+                        sort_value: Some(SortValue::F64(score)),
+                    }) = partial_hit.sort_value
+                    else {
+                        panic!()
+                    };
+                    (partial_hit.doc_id, score as f32)
+                })
+                .collect();
+            crate::Result::Ok(hits)
+        }
+    };
+    let default_hits = search_hits(None).await.unwrap();
+    let doc_ids: Vec<u32> = default_hits.iter().map(|(doc_id, _)| *doc_id).collect();
+    assert_eq!(doc_ids, [1, 0]);
+    assert_eq!(search_hits(Some("bm25")).await.unwrap(), default_hits);
+
+    // TF-IDF penalizes long fields more than BM25 does, and favors the short document.
+    let tfidf_hits = search_hits(Some("tfidf")).await.unwrap();
+    let doc_ids: Vec<u32> = tfidf_hits.iter().map(|(doc_id, _)| *doc_id).collect();
+    assert_eq!(doc_ids, [0, 1]);
+
+    // Without term frequency saturation, the documents score identically.
+    let unsaturated_hits = search_hits(Some("bm25(k1=0)")).await.unwrap();
+    assert_eq!(unsaturated_hits.len(), 2);
+    assert_eq!(unsaturated_hits[0].1, unsaturated_hits[1].1);
+
+    let search_error = search_hits(Some("dfr")).await.unwrap_err();
+    assert!(matches!(search_error, SearchError::InvalidArgument(_)));
+    test_sandbox.assert_quit().await;
+}
+
+#[tokio::test]
+async fn test_search_explain_scores() {
+    let index_id = "search_explain_scores".to_string();
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: title
+                type: text
+                record: freq
+                fieldnorms: true
+            "#;
+    let test_sandbox = TestSandbox::create(&index_id, doc_mapping_yaml, "{}", &["title"])
+        .await
+        .unwrap();
+    let docs = vec![
+        json!({"title": "one pad"}),
+        json!({"title": "one"}),
+        json!({"title": "two"}),
+    ];
+    test_sandbox.add_documents(docs).await.unwrap();
+    let query_ast_json =
+        serde_json::to_string(&query_ast_from_user_text("title:one^2", None)).unwrap();
+    let search_request = SearchRequest {
+        index_id_patterns: vec![index_id.to_string()],
+        query_ast: query_ast_json.clone(),
+        max_hits: 10,
+        ..Default::default()
+    };
+    let search_response = single_node_search(
+        search_request.clone(),
+        test_sandbox.metastore(),
+        test_sandbox.storage_resolver(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(search_response.num_hits, 2);
+    assert!(search_response.hits.iter().all(|hit| hit.scores.is_none()));
+
+    let search_request = SearchRequest {
+        explain_scores: true,
+        ..search_request
+    };
+    let search_response = single_node_search(
+        search_request,
+        test_sandbox.metastore(),
+        test_sandbox.storage_resolver(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(search_response.num_hits, 2);
+
+    for hit in &search_response.hits {
+        let hit_scores = hit.scores.unwrap();
+        assert!(hit_scores.raw_score > 0.0);
+        assert_ne!(hit_scores.score, hit_scores.raw_score);
+        assert!((hit_scores.score - 2.0 * hit_scores.raw_score).abs() < 1e-5);
+    }
+    test_sandbox.assert_quit().await;
+}
+
+#[tokio::test]
+async fn test_search_track_scores() {
+    let index_id = "search_track_scores".to_string();
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: title
+                type: text
+                record: freq
+                fieldnorms: true
+              - name: rank
+                type: u64
+                fast: true
+            "#;
+    let test_sandbox = TestSandbox::create(&index_id, doc_mapping_yaml, "{}", &["title"])
+        .await
+        .unwrap();
+    let docs = vec![
+        json!({"title": "one pad", "rank": 1}),
+        json!({"title": "one", "rank": 2}),
+        json!({"title": "two", "rank": 3}),
+    ];
+    test_sandbox.add_documents(docs).await.unwrap();
+    let query_ast_json =
+        serde_json::to_string(&query_ast_from_user_text("title:one", None)).unwrap();
+    let search_request = SearchRequest {
+        index_id_patterns: vec![index_id.to_string()],
+        query_ast: query_ast_json,
+        max_hits: 10,
+        sort_fields: vec![SortField {
+            field_name: "rank".to_string(),
+            sort_order: SortOrder::Asc as i32,
+            sort_datetime_format: None,
+            script: None,
+        }],
+        ..Default::default()
+    };
+    let search_response = single_node_search(
+        search_request.clone(),
+        test_sandbox.metastore(),
+        test_sandbox.storage_resolver(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(search_response.num_hits, 2);
+    assert!(search_response.hits.iter().all(|hit| hit.scores.is_none()));
+
+    let search_request = SearchRequest {
+        track_scores: true,
+        ..search_request
+    };
+    let search_response = single_node_search(
+        search_request,
+        test_sandbox.metastore(),
+        test_sandbox.storage_resolver(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(search_response.num_hits, 2);
+
+    let scores: Vec<f32> = search_response
+        .hits
+        .iter()
+        .map(|hit| hit.scores.unwrap().score)
+        .collect();
+    // The hits are still sorted by rank: the shorter title scores higher but comes second.
+    assert_eq!(scores.len(), 2);
+    assert!(scores[0] > 0.0);
+    assert!(scores[1] > scores[0]);
+    test_sandbox.assert_quit().await;
+}
+
 #[tokio::test]
 async fn test_sort_by_static_and_dynamic_field() {
     let index_id = "sort_by_dynamic_field".to_string();
@@ -735,6 +1020,7 @@ async fn test_sort_by_static_and_dynamic_field() {
                 field_name: sort_field.to_string(),
                 sort_order: order as i32,
                 sort_datetime_format: None,
+                script: None,
             }],
             ..Default::default()
         };
@@ -834,11 +1120,13 @@ async fn test_sort_by_2_field() {
                         field_name: sort_field1.to_string(),
                         sort_order: order1 as i32,
                         sort_datetime_format: None,
+                        script: None,
                     },
                     SortField {
                         field_name: sort_field2.to_string(),
                         sort_order: order2 as i32,
                         sort_datetime_format: None,
+                        script: None,
                     },
                 ],
                 ..Default::default()
@@ -888,6 +1176,93 @@ async fn test_sort_by_2_field() {
     test_sandbox.assert_quit().await;
 }
 
+#[tokio::test]
+async fn test_sort_by_script() {
+    let index_id = "sort_by_script".to_string();
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: popularity
+                type: f64
+                fast: true
+              - name: recency
+                type: i64
+                fast: true
+            "#;
+    let test_sandbox = TestSandbox::create(&index_id, doc_mapping_yaml, "{}", &[])
+        .await
+        .unwrap();
+    let docs = vec![
+        // 0: 10 * 0.7 + 0 * 0.3 = 7.0
+        json!({"popularity": 10.0, "recency": 0}),
+        // 1: 2 * 0.7 + 20 * 0.3 = 7.4
+        json!({"popularity": 2.0, "recency": 20}),
+        // 2: 5 * 0.7 + 5 * 0.3 = 5.0
+        json!({"popularity": 5.0, "recency": 5}),
+        // 3: no recency, no script value
+        json!({"popularity": 1.0}),
+        // 4: 0 * 0.7 + 30 * 0.3 = 9.0
+        json!({"popularity": 0.0, "recency": 30}),
+    ];
+    test_sandbox.add_documents(docs).await.unwrap();
+    let search_hits = |script: &str, order: SortOrder| {
+        let query_ast_json = serde_json::to_string(&QueryAst::MatchAll).unwrap();
+        let search_request = SearchRequest {
+            index_id_patterns: vec![index_id.to_string()],
+            query_ast: query_ast_json,
+            max_hits: 1_000,
+            sort_fields: vec![SortField {
+                field_name: "_script".to_string(),
+                sort_order: order as i32,
+                sort_datetime_format: None,
+                script: Some(script.to_string()),
+            }],
+            ..Default::default()
+        };
+        let metastore = test_sandbox.metastore();
+        let storage_resolver = test_sandbox.storage_resolver();
+        async move {
+            let search_resp = single_node_search(search_request, metastore, storage_resolver)
+                .await
+                .unwrap();
+            assert_eq!(search_resp.num_hits, 5);
+            search_resp
+                .hits
+                .into_iter()
+                .map(|hit| {
+                    let partial_hit = hit.partial_hit.unwrap();
+                    let sort_value_opt = partial_hit
+                        .sort_value
+                        .and_then(|sort_by_value| sort_by_value.sort_value);
+                    (partial_hit.doc_id, sort_value_opt)
+                })
+                .collect::<Vec<(u32, Option<SortValue>)>>()
+        }
+    };
+    {
+        let ordered_docs = search_hits("popularity * 0.7 + recency * 0.3", SortOrder::Desc).await;
+        let ordered_doc_ids: Vec<u32> = ordered_docs.iter().map(|(doc_id, _)| *doc_id).collect();
+        assert_eq!(ordered_doc_ids, [4, 1, 0, 2, 3]);
+
+        let Some(SortValue::F64(sort_value)) = ordered_docs[0].1 else {
+            panic!("expected a f64 sort value");
+        };
+        assert!((sort_value - 9.0).abs() < 1e-9);
+        assert!(ordered_docs[4].1.is_none());
+    }
+    {
+        let ordered_docs = search_hits("popularity * 0.7 + recency * 0.3", SortOrder::Asc).await;
+        let ordered_doc_ids: Vec<u32> = ordered_docs.iter().map(|(doc_id, _)| *doc_id).collect();
+        assert_eq!(ordered_doc_ids, [2, 0, 1, 4, 3]);
+    }
+    {
+        // (10 - 0) / 2 = 5, (2 - 20) / 2 = -9, (5 - 5) / 2 = 0, (0 - 30) / 2 = -15
+        let ordered_docs = search_hits("(popularity - recency) / 2", SortOrder::Desc).await;
+        let ordered_doc_ids: Vec<u32> = ordered_docs.iter().map(|(doc_id, _)| *doc_id).collect();
+        assert_eq!(ordered_doc_ids, [0, 2, 1, 4, 3]);
+    }
+    test_sandbox.assert_quit().await;
+}
+
 #[tokio::test]
 async fn test_single_node_invalid_sorting_with_query() {
     let index_id = "single-node-invalid-sorting";
@@ -918,6 +1293,7 @@ async fn test_single_node_invalid_sorting_with_query() {
             field_name: "description".to_string(),
             sort_order: SortOrder::Desc as i32,
             sort_datetime_format: None,
+            script: None,
         }],
         ..Default::default()
     };
@@ -1448,6 +1824,431 @@ async fn test_single_node_aggregation_missing_fast_field() {
     test_sandbox.assert_quit().await;
 }
 
+#[tokio::test]
+async fn test_single_node_aggregation_percentiles() {
+    let index_id = "single-node-agg-percentiles";
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: service
+                type: text
+                tokenizer: raw
+                fast: true
+              - name: latency
+                type: f64
+                fast: true
+        "#;
+    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["service"])
+        .await
+        .unwrap();
+    let docs: Vec<JsonValue> = (1..=1_000)
+        .map(|latency| json!({"service": "api", "latency": latency as f64}))
+        .collect();
+    test_sandbox.add_documents(docs).await.unwrap();
+
+    let agg_req = r#"
+ {
+   "latency_percentiles": {
+     "percentiles": {
+       "field": "latency",
+       "percents": [50, 90, 99],
+       "keyed": true
+     }
+   }
+ }"#;
+    let search_request = SearchRequest {
+        index_id_patterns: vec![index_id.to_string()],
+        query_ast: qast_json_helper("*", &[]),
+        max_hits: 0,
+        aggregation_request: Some(agg_req.to_string()),
+        ..Default::default()
+    };
+    let single_node_result = single_node_search(
+        search_request,
+        test_sandbox.metastore(),
+        test_sandbox.storage_resolver(),
+    )
+    .await
+    .unwrap();
+    let agg_res_struct =
+        AggregationResults::from_postcard(&single_node_result.aggregation_postcard.unwrap())
+            .unwrap();
+    let agg_res_json = serde_json::to_value(&agg_res_struct).unwrap();
+    let percentile_values = &agg_res_json["latency_percentiles"]["values"];
+
+    // The values are uniformly distributed from 1 to 1,000, and the DDSketch estimates are within
+    // 1% of the exact percentiles.
+    for (percent, exact_value) in [("50.0", 500.0), ("90.0", 900.0), ("99.0", 990.0)] {
+        let estimated_value = percentile_values[percent].as_f64().unwrap();
+        let relative_error = (estimated_value - exact_value).abs() / exact_value;
+        assert!(relative_error <= 0.011, "{percent}: {estimated_value}");
+    }
+
+    // The t-digest estimates are interpolated between the centroids merged from the sketch,
+    // including in a sub-aggregation.
+    let agg_req = r#"
+ {
+   "services": {
+     "terms": {
+       "field": "service"
+     },
+     "aggs": {
+       "latency_percentiles": {
+         "percentiles": {
+           "field": "latency",
+           "percents": [50, 90, 99],
+           "tdigest": {
+             "compression": 200
+           }
+         }
+       }
+     }
+   }
+ }"#;
+    let search_request = SearchRequest {
+        index_id_patterns: vec![index_id.to_string()],
+        query_ast: qast_json_helper("*", &[]),
+        max_hits: 0,
+        aggregation_request: Some(agg_req.to_string()),
+        ..Default::default()
+    };
+    let single_node_result = single_node_search(
+        search_request,
+        test_sandbox.metastore(),
+        test_sandbox.storage_resolver(),
+    )
+    .await
+    .unwrap();
+    let agg_res_struct =
+        AggregationResults::from_postcard(&single_node_result.aggregation_postcard.unwrap())
+            .unwrap();
+    let agg_res_json = serde_json::to_value(&agg_res_struct).unwrap();
+    let percentile_values =
+        &agg_res_json["services"]["buckets"][0]["latency_percentiles"]["values"];
+
+    for (percent, exact_value) in [("50.0", 500.0), ("90.0", 900.0), ("99.0", 990.0)] {
+        let estimated_value = percentile_values[percent].as_f64().unwrap();
+        let relative_error = (estimated_value - exact_value).abs() / exact_value;
+        assert!(relative_error <= 0.015, "{percent}: {estimated_value}");
+    }
+
+    // The HDR histogram estimates are the upper bounds of the buckets holding the percentiles.
+    // With no significant digit, the buckets are the powers of two.
+    let agg_req = r#"
+ {
+   "latency_percentiles": {
+     "percentiles": {
+       "field": "latency",
+       "percents": [50, 90, 99],
+       "keyed": false,
+       "hdr": {
+         "number_of_significant_value_digits": 0
+       }
+     }
+   }
+ }"#;
+    let search_request = SearchRequest {
+        index_id_patterns: vec![index_id.to_string()],
+        query_ast: qast_json_helper("*", &[]),
+        max_hits: 0,
+        aggregation_request: Some(agg_req.to_string()),
+        ..Default::default()
+    };
+    let single_node_result = single_node_search(
+        search_request,
+        test_sandbox.metastore(),
+        test_sandbox.storage_resolver(),
+    )
+    .await
+    .unwrap();
+    let agg_res_struct =
+        AggregationResults::from_postcard(&single_node_result.aggregation_postcard.unwrap())
+            .unwrap();
+    let agg_res_json = serde_json::to_value(&agg_res_struct).unwrap();
+    assert_eq!(
+        agg_res_json["latency_percentiles"]["values"],
+        json!([
+            {"key": 50.0, "value": 512.0},
+            {"key": 90.0, "value": 1024.0},
+            {"key": 99.0, "value": 1024.0},
+        ])
+    );
+
+    // Invalid estimation options are rejected.
+    let agg_req = r#"
+ {
+   "latency_percentiles": {
+     "percentiles": {
+       "field": "latency",
+       "hdr": {
+         "number_of_significant_value_digits": 6
+       }
+     }
+   }
+ }"#;
+    let search_request = SearchRequest {
+        index_id_patterns: vec![index_id.to_string()],
+        query_ast: qast_json_helper("*", &[]),
+        max_hits: 0,
+        aggregation_request: Some(agg_req.to_string()),
+        ..Default::default()
+    };
+    let single_node_error = single_node_search(
+        search_request,
+        test_sandbox.metastore(),
+        test_sandbox.storage_resolver(),
+    )
+    .await
+    .unwrap_err();
+    let SearchError::InvalidAggregationRequest(error_msg) = single_node_error else {
+        panic!("expected an invalid aggregation request error, got {single_node_error:?}");
+    };
+    assert!(error_msg.contains("must be between 0 and 5"), "{error_msg}");
+    test_sandbox.assert_quit().await;
+}
+
+#[tokio::test]
+async fn test_single_node_aggregation_max_buckets() {
+    let index_id = "single-node-agg-max-buckets";
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: color
+                type: text
+                fast: true
+        "#;
+    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["color"])
+        .await
+        .unwrap();
+    let docs = vec![
+        json!({"color": "blue"}),
+        json!({"color": "green"}),
+        json!({"color": "white"}),
+    ];
+    test_sandbox.add_documents(docs).await.unwrap();
+
+    let update_max_aggregation_buckets = |max_aggregation_buckets: u32| {
+        let metastore = test_sandbox.metastore();
+        async move {
+            let index_metadata = metastore
+                .index_metadata(IndexMetadataRequest::for_index_id(index_id.to_string()))
+                .await
+                .unwrap()
+                .deserialize_index_metadata()
+                .unwrap();
+            let index_config = &index_metadata.index_config;
+            let mut search_settings = index_config.search_settings.clone();
+            search_settings.max_aggregation_buckets = Some(max_aggregation_buckets);
+
+            let update_index_request = UpdateIndexRequest::try_from_updates(
+                index_metadata.index_uid.clone(),
+                &index_config.doc_mapping,
+                &index_config.indexing_settings,
+                &index_config.ingest_settings,
+                &search_settings,
+                &index_config.retention_policy_opt,
+                &index_config.response_headers,
+            )
+            .unwrap();
+            metastore.update_index(update_index_request).await.unwrap();
+        }
+    };
+    let search_request = SearchRequest {
+        index_id_patterns: vec![index_id.to_string()],
+        query_ast: qast_json_helper("*", &[]),
+        max_hits: 0,
+        aggregation_request: Some(r#"{"colors": {"terms": {"field": "color"}}}"#.to_string()),
+        ..Default::default()
+    };
+    update_max_aggregation_buckets(10).await;
+
+    let single_node_response = single_node_search(
+        search_request.clone(),
+        test_sandbox.metastore(),
+        test_sandbox.storage_resolver(),
+    )
+    .await
+    .unwrap();
+    let aggregation_results =
+        AggregationResults::from_postcard(&single_node_response.aggregation_postcard.unwrap())
+            .unwrap();
+    let aggregation_results_json = serde_json::to_value(&aggregation_results).unwrap();
+    let buckets = aggregation_results_json["colors"]["buckets"]
+        .as_array()
+        .unwrap();
+    assert_eq!(buckets.len(), 3);
+
+    update_max_aggregation_buckets(2).await;
+
+    let single_node_error = single_node_search(
+        search_request,
+        test_sandbox.metastore(),
+        test_sandbox.storage_resolver(),
+    )
+    .await
+    .unwrap_err();
+    let SearchError::InvalidAggregationRequest(error_msg) = single_node_error else {
+        panic!();
+    };
+    assert!(
+        error_msg.contains("created 3 buckets, exceeding the maximum of 2 buckets"),
+        "{error_msg}"
+    );
+    test_sandbox.assert_quit().await;
+}
+
+#[tokio::test]
+async fn test_search_plan_prunes_splits_outside_time_range() {
+    let index_id = "search-plan-time-pruning";
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: body
+                type: text
+              - name: ts
+                type: datetime
+                input_formats:
+                    - "unix_timestamp"
+                fast: true
+            timestamp_field: ts
+        "#;
+    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["body"])
+        .await
+        .unwrap();
+    let start_timestamp = OffsetDateTime::now_utc().unix_timestamp();
+
+    let docs = vec![
+        json!({"body": "old", "ts": start_timestamp}),
+        json!({"body": "old", "ts": start_timestamp + 10}),
+    ];
+    test_sandbox.add_documents(docs).await.unwrap();
+    let docs = vec![json!({"body": "new", "ts": start_timestamp + 100})];
+    test_sandbox.add_documents(docs).await.unwrap();
+
+    let search_plan_for = |start_timestamp_opt: Option<i64>| {
+        let search_request = SearchRequest {
+            index_id_patterns: vec![index_id.to_string()],
+            query_ast: qast_json_helper("*", &[]),
+            start_timestamp: start_timestamp_opt,
+            max_hits: 10,
+            ..Default::default()
+        };
+        let metastore = test_sandbox.metastore();
+        async move {
+            let search_plan_response = search_plan(search_request, &metastore).await.unwrap();
+            serde_json::from_str::<SearchPlanResponseRest>(&search_plan_response.result).unwrap()
+        }
+    };
+    let search_plan_response = search_plan_for(None).await;
+    assert_eq!(search_plan_response.searched_splits_details.len(), 2);
+
+    let num_docs: u64 = search_plan_response
+        .searched_splits_details
+        .iter()
+        .map(|split| split.num_docs)
+        .sum();
+    assert_eq!(num_docs, 3);
+
+    let search_plan_response = search_plan_for(Some(start_timestamp + 50)).await;
+    assert_eq!(search_plan_response.searched_splits_details.len(), 1);
+
+    let split = &search_plan_response.searched_splits_details[0];
+    assert_eq!(
+        search_plan_response.searched_splits,
+        [split.split_id.clone()]
+    );
+    assert_eq!(split.num_docs, 1);
+    assert!(split.size_in_bytes > 0);
+    assert_eq!(split.time_range_start, Some(start_timestamp + 100));
+    assert_eq!(split.time_range_end, Some(start_timestamp + 100));
+    test_sandbox.assert_quit().await;
+}
+
+#[tokio::test]
+async fn test_single_node_composite_aggregation_paging() {
+    let index_id = "single-node-composite-agg-paging";
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: color
+                type: text
+                tokenizer: raw
+                fast: true
+        "#;
+    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["color"])
+        .await
+        .unwrap();
+    let colors: Vec<String> = (0..10).map(|i| format!("color-{i}")).collect();
+
+    // The colors are spread over two splits, and some of them appear in both.
+    let docs: Vec<JsonValue> = colors[..6]
+        .iter()
+        .map(|color| json!({ "color": color }))
+        .collect();
+    test_sandbox.add_documents(docs).await.unwrap();
+    let docs: Vec<JsonValue> = colors[4..]
+        .iter()
+        .map(|color| json!({ "color": color }))
+        .collect();
+    test_sandbox.add_documents(docs).await.unwrap();
+
+    let mut after_key_opt: Option<JsonValue> = None;
+    let mut bucket_keys: Vec<String> = Vec::new();
+    let mut num_docs = 0;
+
+    for page in 0.. {
+        assert!(page < 10, "the paging should end");
+
+        let mut composite_json = json!({
+            "size": 3,
+            "sources": [{"color": {"terms": {"field": "color"}}}],
+        });
+        if let Some(after_key) = after_key_opt.take() {
+            composite_json["after"] = after_key;
+        }
+        let aggregation_json = json!({"colors": {"composite": composite_json}});
+        let search_request = SearchRequest {
+            index_id_patterns: vec![index_id.to_string()],
+            query_ast: qast_json_helper("*", &[]),
+            max_hits: 0,
+            aggregation_request: Some(aggregation_json.to_string()),
+            ..Default::default()
+        };
+        let single_node_response = single_node_search(
+            search_request,
+            test_sandbox.metastore(),
+            test_sandbox.storage_resolver(),
+        )
+        .await
+        .unwrap();
+        let aggregation_results =
+            AggregationResults::from_postcard(&single_node_response.aggregation_postcard.unwrap())
+                .unwrap();
+        let aggregation_results_json = serde_json::to_value(&aggregation_results).unwrap();
+        let buckets = aggregation_results_json["colors"]["buckets"]
+            .as_array()
+            .unwrap();
+        if buckets.is_empty() {
+            break;
+        }
+        for bucket in buckets {
+            bucket_keys.push(bucket["key"]["color"].as_str().unwrap().to_string());
+            num_docs += bucket["doc_count"].as_u64().unwrap();
+        }
+        if page == 0 {
+            // Documents ingested while paging do not make the buckets already returned come
+            // back: the new bucket sorted before the cursor is skipped.
+            let docs = vec![json!({"color": "color-0"}), json!({"color": "a-new-color"})];
+            test_sandbox.add_documents(docs).await.unwrap();
+        }
+        let Some(after_key) = aggregation_results_json["colors"].get("after_key") else {
+            break;
+        };
+        after_key_opt = Some(after_key.clone());
+    }
+    let unique_bucket_keys: BTreeSet<&String> = bucket_keys.iter().collect();
+    assert_eq!(unique_bucket_keys.len(), bucket_keys.len());
+    assert_eq!(bucket_keys, colors);
+    assert_eq!(num_docs, 12);
+    test_sandbox.assert_quit().await;
+}
+
 #[tokio::test]
 async fn test_single_node_with_ip_field() -> anyhow::Result<()> {
     let index_id = "single-node-with-ip-field";
@@ -0,0 +1,300 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use quickwit_config::{AccessLogConfig, AccessLogVerbosity};
+use tracing::info;
+use warp::filters::log::{Info, Log};
+use warp::hyper::StatusCode;
+
+/// Target of the access log events, so they can be filtered or routed independently of the other
+/// logs.
+const ACCESS_LOG_TARGET: &str = "quickwit::access_log";
+
+/// Templates of the ingest routes, relative to `/api/v1`. A `*` segment matches any segment, such
+/// as an index ID, and a trailing `..` matches the remaining segments, if any.
+const INGEST_ROUTES: &[&str] = &[
+    "*/ingest",
+    "_elastic/_bulk",
+    "_elastic/*/_bulk",
+    "otlp/v1/logs",
+    "otlp/v1/traces",
+    "*/otlp/v1/logs",
+    "*/otlp/v1/traces",
+];
+
+/// Templates of the search routes, relative to `/api/v1`.
+const SEARCH_ROUTES: &[&str] = &[
+    "*/search",
+    "*/search-plan",
+    "_elastic/_search",
+    "_elastic/_search/scroll",
+    "_elastic/*/_search",
+    "_elastic/_msearch",
+    "_elastic/*/_count",
+    "_elastic/_field_caps",
+    "_elastic/*/_field_caps",
+    "*/jaeger/api/..",
+];
+
+fn matches_route(route: &str, segments: &[&str]) -> bool {
+    let mut route_segments = route.split('/');
+    let mut segments = segments.iter();
+
+    loop {
+        match (route_segments.next(), segments.next()) {
+            (Some(".."), _) | (None, None) => return true,
+            (Some("*"), Some(_)) => {}
+            (Some(route_segment), Some(segment)) if route_segment == *segment => {}
+            _ => return false,
+        }
+    }
+}
+
+/// Group of REST routes sharing the same access log verbosity and request duration histogram.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum RouteGroup {
+    Ingest,
+    Search,
+    Other,
+}
+
+impl RouteGroup {
+    /// Returns the group of the route matching the path. The path is matched against the route
+    /// templates segment by segment, so an index ID never passes for a route segment.
+    pub(crate) fn from_path(path: &str) -> RouteGroup {
+        let segments: Vec<&str> = path
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .collect();
+        let Some(api_v1_segments) = segments.strip_prefix(&["api", "v1"]) else {
+            return RouteGroup::Other;
+        };
+        if INGEST_ROUTES
+            .iter()
+            .any(|route| matches_route(route, api_v1_segments))
+        {
+            return RouteGroup::Ingest;
+        }
+        if SEARCH_ROUTES
+            .iter()
+            .any(|route| matches_route(route, api_v1_segments))
+        {
+            return RouteGroup::Search;
+        }
+        RouteGroup::Other
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            RouteGroup::Ingest => "ingest",
+            RouteGroup::Search => "search",
+            RouteGroup::Other => "other",
+        }
+    }
+
+    fn verbosity(&self, access_log_config: &AccessLogConfig) -> AccessLogVerbosity {
+        match self {
+            RouteGroup::Ingest => access_log_config.ingest,
+            RouteGroup::Search => access_log_config.search,
+            RouteGroup::Other => access_log_config.other,
+        }
+    }
+}
+
+fn should_log(verbosity: AccessLogVerbosity, status: StatusCode) -> bool {
+    match verbosity {
+        AccessLogVerbosity::None => false,
+        AccessLogVerbosity::Errors => status.is_client_error() || status.is_server_error(),
+        AccessLogVerbosity::All => true,
+    }
+}
+
+/// Emits one structured event per request, at the verbosity configured for the route group of the
+/// request.
+pub(crate) fn access_log(
+    access_log_config: AccessLogConfig,
+) -> Log<impl Fn(Info<'_>) + Clone + Send> {
+    warp::log::custom(move |info: Info| {
+        let route_group = RouteGroup::from_path(info.path());
+        let verbosity = route_group.verbosity(&access_log_config);
+
+        if !should_log(verbosity, info.status()) {
+            return;
+        }
+        info!(
+            target: ACCESS_LOG_TARGET,
+            route_group = route_group.as_str(),
+            method = %info.method(),
+            path = info.path(),
+            status = info.status().as_u16(),
+            elapsed_ms = info.elapsed().as_millis() as u64,
+            remote_addr = ?info.remote_addr(),
+            user_agent = info.user_agent().unwrap_or_default(),
+            "access-log"
+        );
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+
+    use quickwit_proto::search::SearchResponse;
+    use quickwit_search::{MockSearchService, SearchError};
+    use warp::Filter;
+
+    use super::*;
+    use crate::rest::recover_fn;
+    use crate::search_api::search_get_handler;
+
+    #[derive(Clone, Default)]
+    struct TestMakeWriter(Arc<Mutex<Vec<u8>>>);
+
+    impl TestMakeWriter {
+        fn num_access_logs(&self) -> usize {
+            let logs = String::from_utf8(self.0.lock().unwrap().clone()).unwrap();
+            logs.lines()
+                .filter(|line| line.contains("access-log"))
+                .count()
+        }
+    }
+
+    impl<'a> tracing_subscriber::fmt::MakeWriter<'a> for TestMakeWriter {
+        type Writer = TestWriter;
+
+        fn make_writer(&'a self) -> Self::Writer {
+            TestWriter(self.0.clone())
+        }
+    }
+
+    struct TestWriter(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for TestWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write_all(buf)?;
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_route_group_from_path() {
+        for ingest_path in [
+            "/api/v1/my-index/ingest",
+            "/api/v1/_elastic/_bulk",
+            "/api/v1/_elastic/my-index/_bulk",
+            "/api/v1/otlp/v1/logs",
+            "/api/v1/my-index/otlp/v1/traces",
+            "/api/v1/search/ingest",
+            "/api/v1/otlp/otlp/v1/logs",
+        ] {
+            assert_eq!(RouteGroup::from_path(ingest_path), RouteGroup::Ingest);
+        }
+        for search_path in [
+            "/api/v1/my-index/search",
+            "/api/v1/my-index/search-plan",
+            "/api/v1/_elastic/_search",
+            "/api/v1/_elastic/_search/scroll",
+            "/api/v1/_elastic/my-index/_search",
+            "/api/v1/_elastic/_msearch",
+            "/api/v1/_elastic/my-index/_count",
+            "/api/v1/_elastic/my-index/_field_caps",
+            "/api/v1/my-index/jaeger/api/services",
+            "/api/v1/otlp/search",
+            "/api/v1/jaeger/search",
+        ] {
+            assert_eq!(RouteGroup::from_path(search_path), RouteGroup::Search);
+        }
+        for other_path in [
+            "/api/v1/indexes",
+            "/api/v1/indexes/my-index/describe",
+            "/api/v1/indexes/search/describe",
+            "/api/v1/indexes/otlp",
+            "/api/v1/my-index/ingest/session",
+            "/my-index/search",
+            "/api/v1/cluster",
+            "/health/livez",
+            "/metrics",
+        ] {
+            assert_eq!(RouteGroup::from_path(other_path), RouteGroup::Other);
+        }
+    }
+
+    #[test]
+    fn test_should_log() {
+        assert!(!should_log(AccessLogVerbosity::None, StatusCode::OK));
+        assert!(!should_log(
+            AccessLogVerbosity::None,
+            StatusCode::INTERNAL_SERVER_ERROR
+        ));
+        assert!(!should_log(AccessLogVerbosity::Errors, StatusCode::OK));
+        assert!(should_log(
+            AccessLogVerbosity::Errors,
+            StatusCode::NOT_FOUND
+        ));
+        assert!(should_log(
+            AccessLogVerbosity::Errors,
+            StatusCode::INTERNAL_SERVER_ERROR
+        ));
+        assert!(should_log(AccessLogVerbosity::All, StatusCode::OK));
+    }
+
+    #[tokio::test]
+    async fn test_access_log_errors_only_skips_successful_searches() {
+        let writer = TestMakeWriter::default();
+        let _guard = tracing::subscriber::set_default(
+            tracing_subscriber::fmt()
+                .with_ansi(false)
+                .with_writer(writer.clone())
+                .finish(),
+        );
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .returning(|search_request| {
+                if search_request.max_hits == 0 {
+                    Err(SearchError::Internal("search failed".to_string()))
+                } else {
+                    Ok(SearchResponse::default())
+                }
+            });
+        let access_log_config = AccessLogConfig {
+            ingest: AccessLogVerbosity::All,
+            search: AccessLogVerbosity::Errors,
+            other: AccessLogVerbosity::None,
+        };
+        let search_handler = warp::path!("api" / "v1" / ..)
+            .and(search_get_handler(Arc::new(mock_search_service), false))
+            .recover(recover_fn)
+            .with(access_log(access_log_config));
+
+        let resp = warp::test::request()
+            .path("/api/v1/my-index/search?query=*&max_hits=10")
+            .reply(&search_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        assert_eq!(writer.num_access_logs(), 0);
+
+        let resp = warp::test::request()
+            .path("/api/v1/my-index/search?query=*&max_hits=0")
+            .reply(&search_handler)
+            .await;
+        assert_eq!(resp.status(), 500);
+        assert_eq!(writer.num_access_logs(), 1);
+    }
+}
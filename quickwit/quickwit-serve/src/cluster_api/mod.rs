@@ -12,6 +12,9 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod nodes_stats;
 mod rest_handler;
 
+pub(crate) use nodes_stats::{DeveloperClientFactory, developer_client, nodes_stats_handler};
 pub use rest_handler::{ClusterApi, cluster_handler};
+pub(crate) use rest_handler::{cluster_leave_handler, cluster_leave_status_handler};
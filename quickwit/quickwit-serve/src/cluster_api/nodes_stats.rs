@@ -0,0 +1,244 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use std::time::Duration;
+
+use futures::StreamExt;
+use futures::stream::FuturesUnordered;
+use quickwit_cluster::{Cluster, ClusterNode};
+use quickwit_proto::developer::{DeveloperService, DeveloperServiceClient, GetNodeStatsRequest};
+use quickwit_proto::tonic::codec::CompressionEncoding;
+use serde::Serialize;
+use tokio::time::timeout;
+use tracing::warn;
+use warp::{Filter, Rejection};
+
+use crate::developer_api::DeveloperApiServer;
+use crate::format::extract_format_from_qs;
+use crate::node_stats::NodeStats;
+use crate::rest::recover_fn;
+use crate::rest_api_response::into_rest_api_response;
+use crate::with_arg;
+
+const GET_NODE_STATS_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Builds the client used to query the developer service of a node.
+pub(crate) type DeveloperClientFactory = fn(&ClusterNode) -> DeveloperServiceClient;
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub(crate) struct NodesStatsResponse {
+    cluster_id: String,
+    num_nodes: usize,
+    num_unreachable_nodes: usize,
+    /// Stats of the live nodes of the cluster, keyed by node ID.
+    nodes: BTreeMap<String, NodeStatsEntry>,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub(crate) struct NodeStatsEntry {
+    enabled_services: Vec<String>,
+    is_ready: bool,
+    /// Whether the node answered the stats request. When it did not, `error` describes why.
+    is_reachable: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stats: Option<NodeStats>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Nodes stats handler.
+pub(crate) fn nodes_stats_handler(
+    cluster: Cluster,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("_nodes" / "stats")
+        .and(warp::get())
+        .and(with_arg(cluster))
+        .then(|cluster| get_nodes_stats(cluster, developer_client))
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+        .recover(recover_fn)
+        .boxed()
+}
+
+pub(crate) fn developer_client(node: &ClusterNode) -> DeveloperServiceClient {
+    DeveloperServiceClient::from_channel(
+        node.grpc_advertise_addr,
+        node.channel(),
+        DeveloperApiServer::MAX_GRPC_MESSAGE_SIZE,
+        Some(CompressionEncoding::Zstd),
+    )
+}
+
+#[utoipa::path(
+    get,
+    tag = "Cluster Info",
+    path = "/_nodes/stats",
+    responses(
+        (status = 200, description = "Successfully fetched the stats of the nodes.", body = NodesStatsResponse)
+    )
+)]
+
+/// Gets the resource usage of the live nodes of the cluster and the throughput of their
+/// services. Nodes that fail to answer in time are reported as unreachable.
+async fn get_nodes_stats(
+    cluster: Cluster,
+    developer_client_factory: DeveloperClientFactory,
+) -> Result<NodesStatsResponse, Infallible> {
+    let live_nodes = cluster.live_nodes().await;
+    let num_nodes = live_nodes.len();
+    let mut get_node_stats_futures = FuturesUnordered::new();
+
+    for live_node in live_nodes {
+        let client = developer_client_factory(&live_node);
+        let get_node_stats_future = async move {
+            let get_node_stats_res = timeout(
+                GET_NODE_STATS_TIMEOUT,
+                client.get_node_stats(GetNodeStatsRequest {}),
+            )
+            .await;
+            (live_node, get_node_stats_res)
+        };
+        get_node_stats_futures.push(get_node_stats_future);
+    }
+    let mut nodes = BTreeMap::new();
+    let mut num_unreachable_nodes = 0;
+
+    while let Some((node, get_node_stats_res)) = get_node_stats_futures.next().await {
+        let node_stats_res = match get_node_stats_res {
+            Ok(Ok(response)) => serde_json::from_slice::<NodeStats>(&response.node_stats_json)
+                .map_err(|error| format!("failed to parse node stats: {error}")),
+            Ok(Err(error)) => Err(error.to_string()),
+            Err(_elapsed) => Err("get node stats request timed out".to_string()),
+        };
+        let (stats, error) = match node_stats_res {
+            Ok(node_stats) => (Some(node_stats), None),
+            Err(error) => {
+                warn!(node_id=%node.node_id, %error, "failed to get node stats");
+                num_unreachable_nodes += 1;
+                (None, Some(error))
+            }
+        };
+        let mut enabled_services: Vec<String> = node
+            .enabled_services
+            .iter()
+            .map(|service| service.to_string())
+            .collect();
+        enabled_services.sort();
+
+        let node_stats_entry = NodeStatsEntry {
+            enabled_services,
+            is_ready: node.is_ready,
+            is_reachable: stats.is_some(),
+            stats,
+            error,
+        };
+        nodes.insert(node.node_id.to_string(), node_stats_entry);
+    }
+    let nodes_stats_response = NodesStatsResponse {
+        cluster_id: cluster.cluster_id().to_string(),
+        num_nodes,
+        num_unreachable_nodes,
+        nodes,
+    };
+    Ok(nodes_stats_response)
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use quickwit_cluster::{ChitchatTransport, create_cluster_for_test};
+    use quickwit_proto::developer::{DeveloperError, GetNodeStatsResponse, MockDeveloperService};
+
+    use super::*;
+
+    fn mock_developer_client(node: &ClusterNode) -> DeveloperServiceClient {
+        let node_stats = NodeStats::collect(&node.enabled_services);
+        let mut mock_developer_service = MockDeveloperService::new();
+        mock_developer_service
+            .expect_get_node_stats()
+            .returning(move |_| {
+                let node_stats_json = serde_json::to_vec(&node_stats).unwrap();
+                Ok(GetNodeStatsResponse {
+                    node_stats_json: Bytes::from(node_stats_json),
+                })
+            });
+        DeveloperServiceClient::from_mock(mock_developer_service)
+    }
+
+    fn unreachable_developer_client(_node: &ClusterNode) -> DeveloperServiceClient {
+        let mut mock_developer_service = MockDeveloperService::new();
+        mock_developer_service
+            .expect_get_node_stats()
+            .returning(|_| {
+                Err(DeveloperError::Unavailable(
+                    "connection refused".to_string(),
+                ))
+            });
+        DeveloperServiceClient::from_mock(mock_developer_service)
+    }
+
+    #[tokio::test]
+    async fn test_get_nodes_stats() {
+        let transport = ChitchatTransport::default();
+        let node_1 = create_cluster_for_test(Vec::new(), &["searcher"], &transport, true)
+            .await
+            .unwrap();
+        let peer_seeds = vec![node_1.gossip_listen_addr().to_string()];
+        let node_2 = create_cluster_for_test(peer_seeds, &["indexer"], &transport, true)
+            .await
+            .unwrap();
+        node_1
+            .wait_for_ready_members(|members| members.len() == 2, Duration::from_secs(30))
+            .await
+            .unwrap();
+
+        let nodes_stats_response = get_nodes_stats(node_1.clone(), mock_developer_client)
+            .await
+            .unwrap();
+        assert_eq!(nodes_stats_response.num_nodes, 2);
+        assert_eq!(nodes_stats_response.num_unreachable_nodes, 0);
+
+        let node_1_entry = &nodes_stats_response.nodes[node_1.self_node_id().as_str()];
+        assert!(node_1_entry.is_reachable);
+        assert_eq!(node_1_entry.enabled_services, ["searcher"]);
+        let node_1_stats = node_1_entry.stats.as_ref().unwrap();
+        assert!(node_1_stats.services.contains_key("searcher"));
+
+        let node_2_entry = &nodes_stats_response.nodes[node_2.self_node_id().as_str()];
+        assert!(node_2_entry.is_reachable);
+        assert_eq!(node_2_entry.enabled_services, ["indexer"]);
+        let node_2_stats = node_2_entry.stats.as_ref().unwrap();
+        assert!(node_2_stats.services["indexer"].contains_key("num_ingested_docs"));
+
+        let nodes_stats_response = get_nodes_stats(node_1.clone(), unreachable_developer_client)
+            .await
+            .unwrap();
+        assert_eq!(nodes_stats_response.num_nodes, 2);
+        assert_eq!(nodes_stats_response.num_unreachable_nodes, 2);
+
+        for node_stats_entry in nodes_stats_response.nodes.values() {
+            assert!(!node_stats_entry.is_reachable);
+            assert!(node_stats_entry.stats.is_none());
+            assert!(
+                node_stats_entry
+                    .error
+                    .as_ref()
+                    .unwrap()
+                    .contains("connection refused")
+            );
+        }
+    }
+}
@@ -13,21 +13,66 @@
 // limitations under the License.
 
 use std::convert::Infallible;
+use std::sync::Arc;
 
 use quickwit_cluster::{Cluster, ClusterSnapshot, NodeIdSchema};
-use warp::{Filter, Rejection};
+use quickwit_config::NodeConfig;
+use quickwit_proto::types::NodeId;
+use quickwit_proto::{ServiceError, ServiceErrorCode};
+use serde::Serialize;
+use warp::hyper::StatusCode;
+use warp::{Filter, Rejection, Reply};
 
-use crate::format::extract_format_from_qs;
+use super::nodes_stats::{__path_get_nodes_stats, NodeStatsEntry, NodesStatsResponse};
+use crate::format::{BodyFormat, extract_format_from_qs};
+use crate::node_decommission::{DecommissionStatus, NodeDecommission};
+use crate::node_stats::NodeStats;
+use crate::request_scope::{AdminRequired, ensure_admin};
 use crate::rest::recover_fn;
 use crate::rest_api_response::into_rest_api_response;
+use crate::with_arg;
 
 #[derive(utoipa::OpenApi)]
 #[openapi(
-    paths(get_cluster),
-    components(schemas(ClusterSnapshot, NodeIdSchema,))
+    paths(get_cluster, leave_cluster, get_leave_cluster_status, get_nodes_stats),
+    components(schemas(
+        ClusterSnapshot,
+        NodeIdSchema,
+        DecommissionStatus,
+        LeaveClusterResponse,
+        NodesStatsResponse,
+        NodeStatsEntry,
+        NodeStats,
+    ))
 )]
 pub struct ClusterApi;
 
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub(crate) struct LeaveClusterResponse {
+    #[schema(value_type = String)]
+    node_id: NodeId,
+    status: DecommissionStatus,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum LeaveClusterError {
+    #[error(transparent)]
+    AdminRequired(#[from] AdminRequired),
+    #[error(
+        "leaving the cluster requires `rest.jwt` or `rest.enable_cluster_leave` to be set in the \
+         node config"
+    )]
+    Disabled,
+}
+
+impl ServiceError for LeaveClusterError {
+    fn error_code(&self) -> ServiceErrorCode {
+        match self {
+            Self::AdminRequired(_) | Self::Disabled => ServiceErrorCode::Forbidden,
+        }
+    }
+}
+
 /// Cluster handler.
 pub fn cluster_handler(
     cluster: Cluster,
@@ -57,3 +102,102 @@ async fn get_cluster(cluster: Cluster) -> Result<ClusterSnapshot, Infallible> {
     let snapshot = cluster.snapshot().await;
     Ok(snapshot)
 }
+
+/// Cluster leave handler.
+pub(crate) fn cluster_leave_handler(
+    cluster: Cluster,
+    node_decommission: NodeDecommission,
+    node_config: Arc<NodeConfig>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("cluster" / "leave")
+        .and(warp::post())
+        .and(with_arg(cluster))
+        .and(with_arg(node_decommission))
+        .and(with_arg(node_config))
+        .then(leave_cluster)
+        .and(extract_format_from_qs())
+        .map(into_accepted_rest_api_response)
+        .recover(recover_fn)
+        .boxed()
+}
+
+/// Makes a `202 Accepted` response from the result of a leave request: the node drains its
+/// in-flight work in the background.
+fn into_accepted_rest_api_response(
+    leave_cluster_result: Result<LeaveClusterResponse, LeaveClusterError>,
+    body_format: BodyFormat,
+) -> warp::reply::Response {
+    let mut response = into_rest_api_response(leave_cluster_result, body_format).into_response();
+
+    if response.status() == StatusCode::OK {
+        *response.status_mut() = StatusCode::ACCEPTED;
+    }
+    response
+}
+
+#[utoipa::path(
+    post,
+    tag = "Cluster Info",
+    path = "/cluster/leave",
+    responses(
+        (status = 202, description = "Successfully marked the node as leaving the cluster. It drains its in-flight work in the background.", body = LeaveClusterResponse),
+        (status = 403, description = "The endpoint is disabled, or the token of the request does not grant admin access."),
+    )
+)]
+
+/// Marks the node as leaving the cluster, so that it stops accepting new ingest and search
+/// requests, and starts draining its in-flight work in the background. The progress of the drain
+/// is reported by `GET /cluster/leave`.
+async fn leave_cluster(
+    cluster: Cluster,
+    node_decommission: NodeDecommission,
+    node_config: Arc<NodeConfig>,
+) -> Result<LeaveClusterResponse, LeaveClusterError> {
+    // Without tokens, any client reaching the API could drain the node.
+    if node_config.rest_config.jwt.is_none() && !node_config.rest_config.enable_cluster_leave {
+        return Err(LeaveClusterError::Disabled);
+    }
+    ensure_admin()?;
+    let status = node_decommission.leave().await;
+
+    Ok(LeaveClusterResponse {
+        node_id: cluster.self_node_id(),
+        status,
+    })
+}
+
+/// Cluster leave status handler.
+pub(crate) fn cluster_leave_status_handler(
+    cluster: Cluster,
+    node_decommission: NodeDecommission,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("cluster" / "leave")
+        .and(warp::get())
+        .and(with_arg(cluster))
+        .and(with_arg(node_decommission))
+        .then(get_leave_cluster_status)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+        .recover(recover_fn)
+        .boxed()
+}
+
+#[utoipa::path(
+    get,
+    tag = "Cluster Info",
+    path = "/cluster/leave",
+    responses(
+        (status = 200, description = "Successfully fetched the progress of the decommission of the node.", body = LeaveClusterResponse)
+    )
+)]
+
+/// Returns the progress of the decommission of the node: `drained` once it can be shut down.
+async fn get_leave_cluster_status(
+    cluster: Cluster,
+    node_decommission: NodeDecommission,
+) -> Result<LeaveClusterResponse, Infallible> {
+    Ok(LeaveClusterResponse {
+        node_id: cluster.self_node_id(),
+        status: node_decommission.status(),
+    })
+}
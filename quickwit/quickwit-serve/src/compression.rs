@@ -0,0 +1,265 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use http_body::Body;
+use quickwit_config::RestCompressionConfig;
+use tower::layer::util::Stack;
+use tower_http::CompressionLevel;
+use tower_http::compression::CompressionLayer;
+use tower_http::compression::predicate::{And, NotForContentType, Predicate};
+use warp::hyper::http;
+
+/// Env variable key to define the minimum size above which a response should be compressed.
+/// If unset, no compression is applied. Overridden by `rest.compression.min_size`.
+const QW_MINIMUM_COMPRESSION_SIZE_KEY: &str = "QW_MINIMUM_COMPRESSION_SIZE";
+
+type SizedCompressionLayer = CompressionLayer<And<CompressionPredicate, NotForContentType>>;
+
+/// Compresses the responses within a range of sizes.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct CompressionPredicate {
+    /// Responses smaller than this size are not compressed.
+    min_size: u64,
+    /// Responses of at least this size are not compressed.
+    max_size_opt: Option<u64>,
+    /// Whether the responses of unknown size, such as streamed responses, are compressed.
+    compress_unknown_size: bool,
+}
+
+impl CompressionPredicate {
+    const NEVER: CompressionPredicate = CompressionPredicate {
+        min_size: u64::MAX,
+        max_size_opt: None,
+        compress_unknown_size: false,
+    };
+}
+
+impl Predicate for CompressionPredicate {
+    fn should_compress<B>(&self, response: &http::Response<B>) -> bool
+    where B: Body {
+        if !is_compressible(response) {
+            return false;
+        }
+        let Some(response_size) = response_size(response) else {
+            return self.compress_unknown_size;
+        };
+        if response_size < self.min_size {
+            return false;
+        }
+        if let Some(max_size) = self.max_size_opt {
+            return response_size < max_size;
+        }
+        true
+    }
+}
+
+/// Returns whether a response may be compressed regardless of its size. Partial responses are not,
+/// because their `Content-Range` refers to the uncompressed bytes, nor are binary files, such as
+/// split downloads, which are sent as is.
+fn is_compressible<B>(response: &http::Response<B>) -> bool {
+    if response.status() == http::StatusCode::PARTIAL_CONTENT {
+        return false;
+    }
+    let Some(content_type) = response.headers().get(http::header::CONTENT_TYPE) else {
+        return true;
+    };
+    !content_type
+        .as_bytes()
+        .starts_with(b"application/octet-stream")
+}
+
+fn response_size<B>(response: &http::Response<B>) -> Option<u64>
+where B: Body {
+    if let Some(exact_size) = response.body().size_hint().exact() {
+        return Some(exact_size);
+    }
+    response
+        .headers()
+        .get(http::header::CONTENT_LENGTH)?
+        .to_str()
+        .ok()?
+        .parse()
+        .ok()
+}
+
+/// Predicates of the two compression layers: one compressing small responses with the fastest
+/// quality, the other compressing large responses with the best quality.
+#[derive(Debug, Eq, PartialEq)]
+struct CompressionPredicates {
+    fastest: CompressionPredicate,
+    best: CompressionPredicate,
+}
+
+impl CompressionPredicates {
+    fn new(compression_config: &RestCompressionConfig, min_size_opt: Option<u64>) -> Self {
+        let Some(min_size) = compression_config
+            .min_size
+            .map(|min_size| min_size.as_u64())
+            .or(min_size_opt)
+        else {
+            return CompressionPredicates {
+                fastest: CompressionPredicate::NEVER,
+                best: CompressionPredicate::NEVER,
+            };
+        };
+        let best_quality_min_size = compression_config
+            .best_quality_min_size
+            .as_u64()
+            .max(min_size);
+        // Streamed responses, typically large, are compressed with the fastest quality so that
+        // they do not hold the CPU for too long.
+        let fastest = CompressionPredicate {
+            min_size,
+            max_size_opt: Some(best_quality_min_size),
+            compress_unknown_size: true,
+        };
+        let best = CompressionPredicate {
+            min_size: best_quality_min_size,
+            max_size_opt: None,
+            compress_unknown_size: false,
+        };
+        CompressionPredicates { fastest, best }
+    }
+}
+
+fn compression_layer(
+    quality: CompressionLevel,
+    compression_predicate: CompressionPredicate,
+) -> SizedCompressionLayer {
+    CompressionLayer::new()
+        .zstd(true)
+        .gzip(true)
+        .quality(quality)
+        .compress_when(compression_predicate.and(NotForContentType::IMAGES))
+}
+
+/// Builds the layer compressing the REST responses, with a quality depending on their size.
+///
+/// It stacks two compression layers: the inner one compresses the small responses with the
+/// fastest quality, and the outer one the large responses with the best quality. A response is
+/// never compressed twice because compression layers skip the responses that are already
+/// encoded.
+pub(crate) fn build_compression_layer(
+    compression_config: &RestCompressionConfig,
+) -> Stack<SizedCompressionLayer, SizedCompressionLayer> {
+    let min_size_opt =
+        quickwit_common::get_from_env_opt::<u64>(QW_MINIMUM_COMPRESSION_SIZE_KEY, false);
+    let compression_predicates = CompressionPredicates::new(compression_config, min_size_opt);
+    Stack::new(
+        compression_layer(CompressionLevel::Fastest, compression_predicates.fastest),
+        compression_layer(CompressionLevel::Best, compression_predicates.best),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use bytesize::ByteSize;
+    use tower::{ServiceBuilder, ServiceExt};
+
+    use super::*;
+
+    fn response_of_size(size: usize) -> http::Response<String> {
+        http::Response::new("a".repeat(size))
+    }
+
+    /// Compresses a response of the given size with gzip and returns the compressed body.
+    async fn gzip_response_of_size(size: usize) -> Vec<u8> {
+        let compression_config = RestCompressionConfig {
+            min_size: Some(ByteSize::b(10)),
+            best_quality_min_size: ByteSize::kb(1),
+        };
+        let service = ServiceBuilder::new()
+            .layer(build_compression_layer(&compression_config))
+            .service_fn(move |_request: http::Request<String>| async move {
+                Ok::<_, Infallible>(response_of_size(size))
+            });
+        let request = http::Request::builder()
+            .header(http::header::ACCEPT_ENCODING, "gzip")
+            .body(String::new())
+            .unwrap();
+        let response = service.oneshot(request).await.unwrap();
+        assert_eq!(response.headers()[http::header::CONTENT_ENCODING], "gzip");
+
+        let mut body = std::pin::pin!(response.into_body());
+        let mut compressed_body = Vec::new();
+        while let Some(frame) = std::future::poll_fn(|cx| body.as_mut().poll_frame(cx)).await {
+            if let Ok(data) = frame.unwrap().into_data() {
+                compressed_body.extend_from_slice(&data);
+            }
+        }
+        compressed_body
+    }
+
+    #[test]
+    fn test_compression_predicates() {
+        let compression_config = RestCompressionConfig::default();
+        let compression_predicates = CompressionPredicates::new(&compression_config, None);
+        assert_eq!(compression_predicates.fastest, CompressionPredicate::NEVER);
+        assert_eq!(compression_predicates.best, CompressionPredicate::NEVER);
+
+        let compression_predicates = CompressionPredicates::new(&compression_config, Some(100));
+        let CompressionPredicates { fastest, best } = compression_predicates;
+
+        assert!(!fastest.should_compress(&response_of_size(10)));
+        assert!(!best.should_compress(&response_of_size(10)));
+
+        assert!(fastest.should_compress(&response_of_size(1_000)));
+        assert!(!best.should_compress(&response_of_size(1_000)));
+
+        assert!(!fastest.should_compress(&response_of_size(2_000_000)));
+        assert!(best.should_compress(&response_of_size(2_000_000)));
+
+        let compression_config = RestCompressionConfig {
+            min_size: Some(ByteSize::kb(1)),
+            best_quality_min_size: ByteSize::b(10),
+        };
+        let compression_predicates = CompressionPredicates::new(&compression_config, Some(100));
+        let CompressionPredicates { fastest, best } = compression_predicates;
+
+        assert!(!fastest.should_compress(&response_of_size(100)));
+        assert!(!best.should_compress(&response_of_size(100)));
+        assert!(best.should_compress(&response_of_size(1_000)));
+    }
+
+    #[test]
+    fn test_compression_predicates_skip_partial_and_binary_responses() {
+        let compression_config = RestCompressionConfig::default();
+        let CompressionPredicates { fastest, .. } =
+            CompressionPredicates::new(&compression_config, Some(100));
+
+        let mut partial_response = response_of_size(1_000);
+        *partial_response.status_mut() = http::StatusCode::PARTIAL_CONTENT;
+        assert!(!fastest.should_compress(&partial_response));
+
+        let mut binary_response = response_of_size(1_000);
+        binary_response.headers_mut().insert(
+            http::header::CONTENT_TYPE,
+            http::HeaderValue::from_static("application/octet-stream"),
+        );
+        assert!(!fastest.should_compress(&binary_response));
+    }
+
+    #[tokio::test]
+    async fn test_compression_quality_depends_on_response_size() {
+        // The extra flags byte of the gzip header tells the compression quality: 4 for the
+        // fastest, 2 for the best.
+        let small_compressed_body = gzip_response_of_size(100).await;
+        assert_eq!(small_compressed_body[8], 4);
+
+        let large_compressed_body = gzip_response_of_size(10_000).await;
+        assert_eq!(large_compressed_body[8], 2);
+    }
+}
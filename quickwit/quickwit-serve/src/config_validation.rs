@@ -0,0 +1,325 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Validates a proposed node config before it is applied, with the checks of the REST and gRPC
+//! servers that do not depend on the environment of the node handling the request.
+
+use std::sync::Arc;
+
+use bytes::Bytes;
+use quickwit_common::redact_quoted_values;
+use quickwit_config::{
+    ConfigFormat, GrpcConfig, NodeConfig, RestConfig, TlsConfig, parse_rest_and_grpc_configs,
+};
+use quickwit_proto::{ServiceError, ServiceErrorCode};
+use quickwit_transport::validate_tls_server_config;
+use serde::Serialize;
+use warp::{Filter, Rejection};
+
+use crate::format::extract_format_from_qs;
+use crate::request_scope::{AdminRequired, ensure_admin};
+use crate::rest::{parse_cors_origin, recover_fn};
+use crate::rest_api_response::into_rest_api_response;
+use crate::with_arg;
+
+#[derive(utoipa::OpenApi)]
+#[openapi(
+    paths(validate_config),
+    components(schemas(ConfigProblem, ConfigValidationResponse))
+)]
+pub(crate) struct ConfigValidationApi;
+
+/// A problem found in a node config.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct ConfigProblem {
+    /// Path of the faulty field, e.g. `rest.cors_allow_origins[1]`. Empty if the config failed to
+    /// load.
+    pub field: String,
+    /// Description of the problem.
+    pub message: String,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct ConfigValidationResponse {
+    /// Whether no problem was found.
+    pub valid: bool,
+    /// The problems found in the config.
+    pub problems: Vec<ConfigProblem>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum ConfigValidationError {
+    #[error(transparent)]
+    AdminRequired(#[from] AdminRequired),
+    #[error("validating node configs requires `rest.enable_config_validation` to be set")]
+    Disabled,
+}
+
+impl ServiceError for ConfigValidationError {
+    fn error_code(&self) -> ServiceErrorCode {
+        match self {
+            Self::AdminRequired(_) | Self::Disabled => ServiceErrorCode::Forbidden,
+        }
+    }
+}
+
+pub(crate) fn config_validation_handler(
+    node_config: Arc<NodeConfig>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("_config" / "validate")
+        .and(warp::post())
+        .and(warp::body::content_length_limit(1024 * 1024))
+        .and(warp::body::bytes())
+        .and(with_arg(node_config))
+        .then(validate_config)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+        .recover(recover_fn)
+        .boxed()
+}
+
+#[utoipa::path(
+    post,
+    tag = "Node Info",
+    path = "/_config/validate",
+    request_body(content = String, description = "Proposed node config, in YAML or JSON.", content_type = "application/yaml"),
+    responses(
+        (status = 200, description = "Successfully validated the config.", body = ConfigValidationResponse),
+        (status = 403, description = "The endpoint is disabled, or the token of the request does not grant admin access."),
+    ),
+)]
+/// Parses the REST and gRPC sections of a proposed node config, then validates their CORS origins
+/// and TLS settings, reporting all their problems at once. The config is not applied: its
+/// environment variables are not substituted and its certificate and key files are not opened.
+///
+/// If the body is empty, the config of the node is validated instead, which checks that its
+/// certificates and keys are still loadable.
+async fn validate_config(
+    body: Bytes,
+    node_config: Arc<NodeConfig>,
+) -> Result<ConfigValidationResponse, ConfigValidationError> {
+    ensure_admin()?;
+
+    if !node_config.rest_config.enable_config_validation {
+        return Err(ConfigValidationError::Disabled);
+    }
+    let problems = if body.iter().all(u8::is_ascii_whitespace) {
+        let load_tls_files = true;
+        validate_rest_and_grpc_configs(
+            &node_config.rest_config,
+            &node_config.grpc_config,
+            load_tls_files,
+        )
+    } else {
+        match parse_rest_and_grpc_configs(ConfigFormat::Yaml, &body) {
+            Ok((rest_config, grpc_config)) => {
+                let load_tls_files = false;
+                validate_rest_and_grpc_configs(&rest_config, &grpc_config, load_tls_files)
+            }
+            Err(error) => vec![ConfigProblem {
+                field: String::new(),
+                message: redact_quoted_values(&format!("{error:#}")),
+            }],
+        }
+    };
+    Ok(ConfigValidationResponse {
+        valid: problems.is_empty(),
+        problems,
+    })
+}
+
+fn validate_rest_and_grpc_configs(
+    rest_config: &RestConfig,
+    grpc_config: &GrpcConfig,
+    load_tls_files: bool,
+) -> Vec<ConfigProblem> {
+    let mut problems = Vec::new();
+    validate_cors_allow_origins(&rest_config.cors_allow_origins, &mut problems);
+
+    if let Some(tls_config) = &rest_config.tls_config {
+        validate_tls_config("rest.tls", tls_config, load_tls_files, &mut problems);
+    }
+    if let Some(tls_config) = &grpc_config.tls_config {
+        validate_tls_config("grpc.tls", tls_config, load_tls_files, &mut problems);
+    }
+    problems
+}
+
+fn validate_cors_allow_origins(cors_allow_origins: &[String], problems: &mut Vec<ConfigProblem>) {
+    for (origin_idx, origin) in cors_allow_origins.iter().enumerate() {
+        if origin == "*" {
+            continue;
+        }
+        if let Err(error) = parse_cors_origin(origin) {
+            problems.push(ConfigProblem {
+                field: format!("rest.cors_allow_origins[{origin_idx}]"),
+                message: redact_quoted_values(&format!("{error:#}")),
+            });
+        }
+    }
+}
+
+/// Validates the TLS settings, and loads the certificates and keys they point to if
+/// `load_tls_files` is set. The files of a proposed config are not opened, so the endpoint cannot
+/// be used to probe the filesystem of the node.
+fn validate_tls_config(
+    field: &str,
+    tls_config: &TlsConfig,
+    load_tls_files: bool,
+    problems: &mut Vec<ConfigProblem>,
+) {
+    let validation_result = if load_tls_files {
+        validate_tls_server_config(tls_config)
+    } else {
+        tls_config.validate()
+    };
+    if let Err(error) = validation_result {
+        problems.push(ConfigProblem {
+            field: field.to_string(),
+            message: redact_quoted_values(&format!("{error:#}")),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{Value as JsonValue, json};
+
+    use super::*;
+
+    async fn validate_config_with(node_config: NodeConfig, body: &str) -> (u16, JsonValue) {
+        let handler = config_validation_handler(Arc::new(node_config));
+        let resp = warp::test::request()
+            .path("/_config/validate")
+            .method("POST")
+            .body(body.to_string())
+            .reply(&handler)
+            .await;
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        (resp.status().as_u16(), resp_json)
+    }
+
+    async fn validate_config(body: &str) -> (u16, JsonValue) {
+        let mut node_config = NodeConfig::for_test();
+        node_config.rest_config.enable_config_validation = true;
+        validate_config_with(node_config, body).await
+    }
+
+    #[tokio::test]
+    async fn test_validate_config_disabled() {
+        let (status, _) = validate_config_with(NodeConfig::for_test(), "").await;
+        assert_eq!(status, 403);
+    }
+
+    #[tokio::test]
+    async fn test_validate_config_valid() {
+        let node_config_yaml = r#"
+            version: 0.8
+            node_id: node-1
+            data_dir: /does/not/exist
+            rest:
+              cors_allow_origins: ["https://quickwit.io", "*"]
+              extra_headers:
+                x-custom-header: custom-value
+        "#;
+        let (status, resp_json) = validate_config(node_config_yaml).await;
+        assert_eq!(status, 200);
+        assert_eq!(resp_json, json!({"valid": true, "problems": []}));
+
+        // The config of the node is validated when the body is empty.
+        let (status, resp_json) = validate_config("").await;
+        assert_eq!(status, 200);
+        assert_eq!(resp_json, json!({"valid": true, "problems": []}));
+    }
+
+    #[tokio::test]
+    async fn test_validate_config_reports_all_problems() {
+        let node_config_json = json!({
+            "version": "0.8",
+            "node_id": "node-1",
+            "rest": {
+                "cors_allow_origins": ["https://quickwit.io", "invalid\norigin", "invalid\rorigin"],
+                "tls": {"cert_path": "/missing/cert.pem", "key_path": "/missing/key.pem"}
+            },
+            "grpc": {
+                "tls": {"cert_path": "/missing/cert.pem", "key_path": "/missing/key.pem"}
+            }
+        });
+        let (status, resp_json) = validate_config(&node_config_json.to_string()).await;
+        assert_eq!(status, 200);
+        assert_eq!(resp_json["valid"], false);
+
+        // The certificate and key files of a proposed config are not opened.
+        let fields: Vec<&str> = resp_json["problems"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|problem| problem["field"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            fields,
+            ["rest.cors_allow_origins[1]", "rest.cors_allow_origins[2]"]
+        );
+        assert_eq!(
+            resp_json["problems"][0]["message"],
+            "invalid CORS origin `<redacted>`: failed to parse header value"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_validate_config_does_not_render_templates() {
+        // SAFETY: this test may not be entirely sound if not run with nextest or --test-threads=1
+        // as this is only a test, and it would be extremely inconvenient to run it in a different
+        // way, we are keeping it that way
+        unsafe { std::env::set_var("QW_TEST_CONFIG_VALIDATION_SECRET", "secret-value") };
+
+        let node_config_yaml = r#"
+            version: 0.8
+            node_id: node-1
+            rest:
+              cors_allow_origins: ["${QW_TEST_CONFIG_VALIDATION_SECRET}\n"]
+        "#;
+        let (status, resp_json) = validate_config(node_config_yaml).await;
+        assert_eq!(status, 200);
+        assert_eq!(resp_json["valid"], false);
+        assert!(!resp_json.to_string().contains("secret-value"));
+
+        unsafe { std::env::remove_var("QW_TEST_CONFIG_VALIDATION_SECRET") };
+    }
+
+    #[tokio::test]
+    async fn test_validate_config_not_parsable() {
+        let (status, resp_json) = validate_config("rest: [").await;
+        assert_eq!(status, 200);
+        assert_eq!(resp_json["valid"], false);
+        assert_eq!(resp_json["problems"][0]["field"], "");
+
+        let node_config_json = json!({
+            "version": "0.8",
+            "node_id": "node-1",
+            "rest": {
+                "extra_headers": {"invalid header": "secret-value"}
+            }
+        });
+        let (status, resp_json) = validate_config(&node_config_json.to_string()).await;
+        assert_eq!(status, 200);
+        assert_eq!(resp_json["valid"], false);
+
+        let problems = resp_json["problems"].as_array().unwrap();
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0]["field"], "");
+        assert!(!problems[0]["message"].as_str().unwrap().is_empty());
+        assert!(!resp_json.to_string().contains("secret-value"));
+    }
+}
@@ -12,13 +12,19 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::fmt;
 use std::io::Read;
+use std::pin::pin;
 use std::sync::LazyLock;
+use std::time::Duration;
 
-use bytes::Bytes;
+use bytes::{Buf, BufMut, Bytes, BytesMut};
 use flate2::read::{MultiGzDecoder, ZlibDecoder};
+use futures::stream::BoxStream;
+use futures::{Stream, StreamExt};
 use quickwit_common::metrics::IN_FLIGHT_REST_SERVER;
 use quickwit_common::thread_pool::run_cpu_intensive;
+use quickwit_ingest::IngestServiceError;
 use quickwit_metrics::GaugeGuard;
 use thiserror::Error;
 use warp::Filter;
@@ -26,11 +32,101 @@ use warp::reject::Reject;
 
 use crate::load_shield::{LoadShield, LoadShieldPermit};
 
-fn get_ingest_load_shield() -> &'static LoadShield {
+pub(crate) fn get_ingest_load_shield() -> &'static LoadShield {
     static LOAD_SHIELD: LazyLock<LoadShield> = LazyLock::new(|| LoadShield::new("ingest"));
     &LOAD_SHIELD
 }
 
+const DEFAULT_BODY_READ_IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+const DEFAULT_MAX_DECOMPRESSION_RATIO: u64 = 100;
+
+/// Decompressed bodies up to this size are accepted whatever their compression ratio: the ratio
+/// of small bodies is not meaningful, and they cannot exhaust the memory of the node.
+const MIN_RATIO_CHECKED_SIZE: u64 = 1024 * 1024;
+
+/// Maximum time to wait for the next chunk of a request body before dropping the request. The
+/// timer is reset on every chunk, so large uploads are not penalized as long as they keep
+/// progressing.
+fn get_body_read_idle_timeout() -> Duration {
+    static BODY_READ_IDLE_TIMEOUT: LazyLock<Duration> = LazyLock::new(|| {
+        let body_read_idle_timeout_secs: u64 = quickwit_common::get_from_env(
+            "QW_BODY_READ_IDLE_TIMEOUT_SECS",
+            DEFAULT_BODY_READ_IDLE_TIMEOUT.as_secs(),
+            false,
+        );
+        Duration::from_secs(body_read_idle_timeout_secs.max(1))
+    });
+    *BODY_READ_IDLE_TIMEOUT
+}
+
+/// Maximum ratio between the size of a decompressed request body and its compressed size. Bodies
+/// expanding beyond it are rejected as likely decompression bombs. `0` disables the check.
+fn get_max_decompression_ratio() -> u64 {
+    static MAX_DECOMPRESSION_RATIO: LazyLock<u64> = LazyLock::new(|| {
+        quickwit_common::get_from_env(
+            "QW_MAX_DECOMPRESSION_RATIO",
+            DEFAULT_MAX_DECOMPRESSION_RATIO,
+            false,
+        )
+    });
+    *MAX_DECOMPRESSION_RATIO
+}
+
+/// Reads the whole output of a decoder, failing if it exceeds `max_ratio` times the size of the
+/// compressed body. The decoder is not read further than the limit, so a decompression bomb is
+/// never fully expanded in memory.
+fn read_decompressed(
+    decoder: impl Read,
+    compressed_len: usize,
+    max_ratio: u64,
+) -> Result<Bytes, warp::Rejection> {
+    let max_decompressed_len = if max_ratio == 0 {
+        u64::MAX
+    } else {
+        (compressed_len as u64)
+            .saturating_mul(max_ratio)
+            .max(MIN_RATIO_CHECKED_SIZE)
+    };
+    let mut decompressed = Vec::new();
+    decoder
+        .take(max_decompressed_len.saturating_add(1))
+        .read_to_end(&mut decompressed)
+        .map_err(|_| warp::reject::custom(CorruptedData))?;
+
+    if decompressed.len() as u64 > max_decompressed_len {
+        return Err(warp::reject::custom(CorruptedData));
+    }
+    Ok(Bytes::from(decompressed))
+}
+
+/// Reads the whole body, failing if no chunk is received for `idle_timeout`.
+///
+/// This protects the ingest endpoints against slow-loris clients that hold connections by
+/// trickling or stalling request bodies.
+async fn read_body_with_idle_timeout<B, E>(
+    body_stream: impl Stream<Item = Result<B, E>>,
+    idle_timeout: Duration,
+) -> Result<Bytes, warp::Rejection>
+where
+    B: Buf,
+    E: fmt::Display,
+{
+    let mut body_stream = pin!(body_stream);
+    let mut body = BytesMut::new();
+
+    loop {
+        match tokio::time::timeout(idle_timeout, body_stream.next()).await {
+            Ok(Some(Ok(chunk))) => body.put(chunk),
+            Ok(Some(Err(error))) => {
+                return Err(warp::reject::custom(BodyReadError(error.to_string())));
+            }
+            Ok(None) => return Ok(body.freeze()),
+            Err(_) => return Err(warp::reject::custom(BodyReadTimeout(idle_timeout))),
+        }
+    }
+}
+
 /// There are two ways to decompress the body:
 /// - Stream the body through an async decompressor
 /// - Fetch the body and then decompress the bytes
@@ -38,17 +134,17 @@ fn get_ingest_load_shield() -> &'static LoadShield {
 /// The first approach lowers the latency, while the second approach is more CPU efficient.
 /// Ingesting data is usually CPU bound and there is considerable latency until the data is
 /// searchable, so the second approach is more suitable for this use case.
-async fn decompress_body(encoding: Option<String>, body: Bytes) -> Result<Bytes, warp::Rejection> {
+async fn decompress_body(
+    encoding: Option<String>,
+    body: Bytes,
+    max_ratio: u64,
+) -> Result<Bytes, warp::Rejection> {
     match encoding.as_deref() {
         Some("identity") => Ok(body),
         Some("gzip" | "x-gzip") => {
             let decompressed = run_cpu_intensive(move || {
-                let mut decompressed = Vec::new();
-                let mut decoder = MultiGzDecoder::new(body.as_ref());
-                decoder
-                    .read_to_end(&mut decompressed)
-                    .map_err(|_| warp::reject::custom(CorruptedData))?;
-                Result::<_, warp::Rejection>::Ok(Bytes::from(decompressed))
+                let decoder = MultiGzDecoder::new(body.as_ref());
+                read_decompressed(decoder, body.len(), max_ratio)
             })
             .await
             .map_err(|_| warp::reject::custom(CorruptedData))??;
@@ -56,9 +152,9 @@ async fn decompress_body(encoding: Option<String>, body: Bytes) -> Result<Bytes,
         }
         Some("zstd") => {
             let decompressed = run_cpu_intensive(move || {
-                zstd::decode_all(body.as_ref())
-                    .map(Bytes::from)
-                    .map_err(|_| warp::reject::custom(CorruptedData))
+                let decoder = zstd::stream::read::Decoder::new(body.as_ref())
+                    .map_err(|_| warp::reject::custom(CorruptedData))?;
+                read_decompressed(decoder, body.len(), max_ratio)
             })
             .await
             .map_err(|_| warp::reject::custom(CorruptedData))??;
@@ -66,11 +162,8 @@ async fn decompress_body(encoding: Option<String>, body: Bytes) -> Result<Bytes,
         }
         Some("deflate" | "x-deflate") => {
             let decompressed = run_cpu_intensive(move || {
-                let mut decompressed = Vec::new();
-                ZlibDecoder::new(body.as_ref())
-                    .read_to_end(&mut decompressed)
-                    .map_err(|_| warp::reject::custom(CorruptedData))?;
-                Result::<_, warp::Rejection>::Ok(Bytes::from(decompressed))
+                let decoder = ZlibDecoder::new(body.as_ref());
+                read_decompressed(decoder, body.len(), max_ratio)
             })
             .await
             .map_err(|_| warp::reject::custom(CorruptedData))??;
@@ -95,18 +188,120 @@ pub(crate) struct UnsupportedEncoding(String);
 
 impl Reject for UnsupportedEncoding {}
 
+#[derive(Debug, Error)]
+#[error("request body read timed out: no data received for {}s", self.0.as_secs())]
+pub(crate) struct BodyReadTimeout(Duration);
+
+impl Reject for BodyReadTimeout {}
+
+#[derive(Debug, Error)]
+#[error("failed to read request body: {0}")]
+pub(crate) struct BodyReadError(String);
+
+impl Reject for BodyReadError {}
+
 /// Custom filter for optional decompression
 pub(crate) fn get_body_bytes() -> impl Filter<Extract = (Body,), Error = warp::Rejection> + Clone {
     warp::header::optional("content-encoding")
-        .and(warp::body::bytes())
-        .and_then(|encoding: Option<String>, body: Bytes| async move {
+        .and(warp::body::stream())
+        .and_then(|encoding: Option<String>, body_stream| async move {
+            let body =
+                read_body_with_idle_timeout(body_stream, get_body_read_idle_timeout()).await?;
             let permit = get_ingest_load_shield().acquire_permit().await?;
-            decompress_body(encoding, body)
+            decompress_body(encoding, body, get_max_decompression_ratio())
                 .await
                 .map(|content| Body::new(content, permit))
         })
 }
 
+/// Custom filter extracting the body as a stream of chunks. Only uncompressed bodies are
+/// accepted: the filter rejects compressed bodies with a "not found" rejection so that a fallback
+/// filter decompressing the whole body can take over.
+pub(crate) fn get_body_stream()
+-> impl Filter<Extract = (StreamingBody,), Error = warp::Rejection> + Clone {
+    warp::header::optional("content-encoding")
+        .and_then(|encoding: Option<String>| async move {
+            match encoding.as_deref() {
+                None | Some("identity") => Ok(()),
+                Some(_) => Err(warp::reject::not_found()),
+            }
+        })
+        .untuple_one()
+        .and(warp::body::stream())
+        .and_then(|body_stream| async move {
+            let permit = get_ingest_load_shield().acquire_permit().await?;
+            Ok::<_, warp::Rejection>(StreamingBody::new(body_stream, permit))
+        })
+}
+
+/// Request body read chunk by chunk as it arrives. The chunks are not read ahead: the client is
+/// held back by TCP flow control until the next chunk is requested.
+pub(crate) struct StreamingBody {
+    body_stream: BoxStream<'static, Result<Bytes, String>>,
+    idle_timeout: Duration,
+    load_shield_permit: LoadShieldPermit,
+}
+
+impl StreamingBody {
+    pub fn new<B, E>(
+        body_stream: impl Stream<Item = Result<B, E>> + Send + 'static,
+        load_shield_permit: LoadShieldPermit,
+    ) -> StreamingBody
+    where
+        B: Buf,
+        E: fmt::Display,
+    {
+        let body_stream = body_stream
+            .map(|chunk_res| {
+                chunk_res
+                    .map(|mut chunk| chunk.copy_to_bytes(chunk.remaining()))
+                    .map_err(|error| error.to_string())
+            })
+            .boxed();
+        StreamingBody {
+            body_stream,
+            idle_timeout: get_body_read_idle_timeout(),
+            load_shield_permit,
+        }
+    }
+
+    /// Returns the next chunk of the body, or `None` once the body has been read entirely. Fails
+    /// if no chunk is received for the body read idle timeout.
+    pub async fn next_chunk(&mut self) -> Result<Option<Bytes>, IngestServiceError> {
+        match tokio::time::timeout(self.idle_timeout, self.body_stream.next()).await {
+            Ok(Some(Ok(chunk))) => Ok(Some(chunk)),
+            Ok(Some(Err(error))) => Err(IngestServiceError::BadRequest(
+                BodyReadError(error).to_string(),
+            )),
+            Ok(None) => Ok(None),
+            Err(_) => Err(IngestServiceError::BadRequest(
+                BodyReadTimeout(self.idle_timeout).to_string(),
+            )),
+        }
+    }
+
+    /// Reads the rest of the body in memory, failing if it is larger than `max_num_bytes` or as
+    /// soon as `check_chunk` fails on one of its chunks.
+    pub async fn into_body(
+        mut self,
+        max_num_bytes: usize,
+        mut check_chunk: impl FnMut(&[u8]) -> Result<(), IngestServiceError>,
+    ) -> Result<Body, IngestServiceError> {
+        let mut content = BytesMut::new();
+
+        while let Some(chunk) = self.next_chunk().await? {
+            if content.len() + chunk.len() > max_num_bytes {
+                let message =
+                    format!("request body exceeds the maximum size of {max_num_bytes} bytes");
+                return Err(IngestServiceError::BadRequest(message));
+            }
+            check_chunk(&chunk)?;
+            content.put(chunk);
+        }
+        Ok(Body::new(content.freeze(), self.load_shield_permit))
+    }
+}
+
 pub(crate) struct Body {
     pub content: Bytes,
     _gauge_guard: GaugeGuard,
@@ -123,3 +318,107 @@ impl Body {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Write};
+
+    use flate2::Compression;
+    use flate2::write::GzEncoder;
+    use futures::stream;
+
+    use super::*;
+
+    fn gzip(content: &[u8]) -> Bytes {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(content).unwrap();
+        Bytes::from(encoder.finish().unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_decompress_body_accepts_normal_ratio() {
+        let ndjson: String = (0..50_000)
+            .map(|doc_idx| format!("{{\"id\": {doc_idx}, \"message\": \"event {doc_idx}\"}}\n"))
+            .collect();
+        assert!(ndjson.len() as u64 > MIN_RATIO_CHECKED_SIZE);
+
+        let decompressed = decompress_body(Some("gzip".to_string()), gzip(ndjson.as_bytes()), 100)
+            .await
+            .unwrap();
+        assert_eq!(decompressed, ndjson);
+
+        let compressed = Bytes::from(zstd::encode_all(ndjson.as_bytes(), 3).unwrap());
+        let decompressed = decompress_body(Some("zstd".to_string()), compressed, 100)
+            .await
+            .unwrap();
+        assert_eq!(decompressed, ndjson);
+
+        // Small bodies are accepted whatever their compression ratio.
+        let zeros = vec![0u8; 1_000];
+        let decompressed = decompress_body(Some("gzip".to_string()), gzip(&zeros), 100)
+            .await
+            .unwrap();
+        assert_eq!(decompressed, zeros);
+    }
+
+    #[tokio::test]
+    async fn test_decompress_body_rejects_extreme_ratio() {
+        let zeros = vec![0u8; 10 * 1024 * 1024];
+        let compressed = gzip(&zeros);
+        assert!(compressed.len() * 100 < zeros.len());
+
+        let rejection = decompress_body(Some("gzip".to_string()), compressed.clone(), 100)
+            .await
+            .unwrap_err();
+        assert!(rejection.find::<CorruptedData>().is_some());
+
+        let compressed_zstd = Bytes::from(zstd::encode_all(&zeros[..], 3).unwrap());
+        let rejection = decompress_body(Some("zstd".to_string()), compressed_zstd, 100)
+            .await
+            .unwrap_err();
+        assert!(rejection.find::<CorruptedData>().is_some());
+
+        // The check is disabled with a ratio of 0.
+        let decompressed = decompress_body(Some("gzip".to_string()), compressed, 0)
+            .await
+            .unwrap();
+        assert_eq!(decompressed.len(), zeros.len());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_read_body_with_idle_timeout_drops_stalled_body() {
+        let body_stream = stream::iter([Ok::<_, io::Error>(Bytes::from_static(b"{\"id\": 1}"))])
+            .chain(stream::pending());
+        let rejection = read_body_with_idle_timeout(body_stream, Duration::from_secs(5))
+            .await
+            .unwrap_err();
+        let body_read_timeout = rejection.find::<BodyReadTimeout>().unwrap();
+        assert_eq!(body_read_timeout.0, Duration::from_secs(5));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_read_body_with_idle_timeout_accepts_slow_steady_body() {
+        // The whole upload takes 20s, but no chunk is more than 4s apart.
+        let body_stream = stream::iter(0..5).then(|chunk_idx| async move {
+            tokio::time::sleep(Duration::from_secs(4)).await;
+            Ok::<_, io::Error>(Bytes::from(format!("chunk-{chunk_idx}\n")))
+        });
+        let body = read_body_with_idle_timeout(body_stream, Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert_eq!(body, "chunk-0\nchunk-1\nchunk-2\nchunk-3\nchunk-4\n");
+    }
+
+    #[tokio::test]
+    async fn test_read_body_with_idle_timeout_reports_stream_errors() {
+        let body_stream = stream::iter([
+            Ok(Bytes::from_static(b"chunk")),
+            Err(io::Error::other("connection reset")),
+        ]);
+        let rejection = read_body_with_idle_timeout(body_stream, Duration::from_secs(5))
+            .await
+            .unwrap_err();
+        let body_read_error = rejection.find::<BodyReadError>().unwrap();
+        assert!(body_read_error.to_string().contains("connection reset"));
+    }
+}
@@ -12,20 +12,25 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::sync::Arc;
+
 use quickwit_config::build_doc_mapper;
 use quickwit_janitor::error::JanitorError;
-use quickwit_metastore::IndexMetadataResponseExt;
+use quickwit_metastore::{IndexMetadata, IndexMetadataResponseExt};
 use quickwit_proto::metastore::{
-    DeleteQuery, DeleteTask, IndexMetadataRequest, ListDeleteTasksRequest, MetastoreResult,
-    MetastoreService, MetastoreServiceClient,
+    DeleteQuery, DeleteTask, IndexMetadataRequest, ListDeleteTasksRequest, MetastoreError,
+    MetastoreResult, MetastoreService, MetastoreServiceClient,
 };
 use quickwit_proto::search::SearchRequest;
 use quickwit_proto::types::{IndexId, IndexUid};
 use quickwit_query::query_ast::{QueryAst, query_ast_from_user_text};
-use serde::Deserialize;
+use quickwit_search::SearchService;
+use serde::{Deserialize, Serialize};
 use warp::{Filter, Rejection};
 
+use super::preview::{DeleteTaskPreview, preview_delete_query};
 use crate::format::extract_format_from_qs;
+use crate::request_scope::{current_request_scope, ensure_indexes_in_scope};
 use crate::rest::recover_fn;
 use crate::rest_api_response::into_rest_api_response;
 use crate::with_arg;
@@ -33,7 +38,13 @@ use crate::with_arg;
 #[derive(utoipa::OpenApi)]
 #[openapi(
     paths(get_delete_tasks, post_delete_request),
-    components(schemas(DeleteQueryRequest, DeleteTask, DeleteQuery))
+    components(schemas(
+        DeleteQueryRequest,
+        DeleteTask,
+        DeleteQuery,
+        DeleteTaskPreview,
+        CreateDeleteTaskResponse
+    ))
 )]
 pub struct DeleteTaskApi;
 
@@ -54,12 +65,29 @@ pub struct DeleteQueryRequest {
     pub end_timestamp: Option<i64>,
 }
 
+#[derive(Deserialize, Debug, Default, utoipa::IntoParams, utoipa::ToSchema)]
+#[into_params(parameter_in = Query)]
+pub struct DeleteTaskQueryParams {
+    /// If set, counts the documents and splits matching the delete query without creating the
+    /// delete task.
+    #[serde(default, alias = "count_only")]
+    pub dry_run: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, utoipa::ToSchema)]
+#[serde(untagged)]
+pub enum CreateDeleteTaskResponse {
+    Preview(DeleteTaskPreview),
+    Created(DeleteTask),
+}
+
 /// Delete query API handlers.
 pub fn delete_task_api_handlers(
     metastore: MetastoreServiceClient,
+    search_service: Arc<dyn SearchService>,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
     get_delete_tasks_handler(metastore.clone())
-        .or(post_delete_tasks_handler(metastore.clone()))
+        .or(post_delete_tasks_handler(metastore.clone(), search_service))
         .recover(recover_fn)
         .boxed()
 }
@@ -97,6 +125,7 @@ pub async fn get_delete_tasks(
     index_id: IndexId,
     metastore: MetastoreServiceClient,
 ) -> MetastoreResult<Vec<DeleteTask>> {
+    ensure_indexes_in_scope([index_id.as_str()])?;
     let index_metadata_request = IndexMetadataRequest::for_index_id(index_id.to_string());
     let index_uid: IndexUid = metastore
         .index_metadata(index_metadata_request)
@@ -113,11 +142,14 @@ pub async fn get_delete_tasks(
 
 pub fn post_delete_tasks_handler(
     metastore: MetastoreServiceClient,
+    search_service: Arc<dyn SearchService>,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
     warp::path!(String / "delete-tasks")
         .and(warp::body::json())
         .and(warp::post())
+        .and(warp::query())
         .and(with_arg(metastore))
+        .and(with_arg(search_service))
         .then(post_delete_request)
         .and(extract_format_from_qs())
         .map(into_rest_api_response)
@@ -129,9 +161,10 @@ pub fn post_delete_tasks_handler(
     path = "/{index_id}/delete-tasks",
     request_body = DeleteQueryRequest,
     responses(
-        (status = 200, description = "Successfully added a new delete task.", body = DeleteTask)
+        (status = 200, description = "Successfully added a new delete task, or computed its preview if `dry_run` is set.", body = CreateDeleteTaskResponse)
     ),
     params(
+        DeleteTaskQueryParams,
         ("index_id" = String, Path, description = "The index ID to add the delete task to."),
     )
 )]
@@ -139,30 +172,71 @@ pub fn post_delete_tasks_handler(
 ///
 /// This operation will not be immediately executed, instead it will be added to a queue
 /// and cleaned up in the near future.
+///
+/// With `dry_run=true`, no delete task is created: the response reports the number of documents
+/// and splits the delete query currently matches.
 pub async fn post_delete_request(
     index_id: IndexId,
     delete_request: DeleteQueryRequest,
+    delete_task_query_params: DeleteTaskQueryParams,
     metastore: MetastoreServiceClient,
-) -> Result<DeleteTask, JanitorError> {
+    search_service: Arc<dyn SearchService>,
+) -> Result<CreateDeleteTaskResponse, JanitorError> {
+    ensure_indexes_in_scope([index_id.as_str()]).map_err(MetastoreError::from)?;
     let index_metadata_request = IndexMetadataRequest::for_index_id(index_id.to_string());
-    let metadata = metastore
+    let index_metadata = metastore
         .index_metadata(index_metadata_request)
         .await?
         .deserialize_index_metadata()?;
-    let index_uid: IndexUid = metadata.index_uid.clone();
+    let delete_query = build_delete_query(&index_metadata, delete_request)?;
+
+    if delete_task_query_params.dry_run {
+        let delete_task_preview =
+            preview_delete_query(delete_query, &index_metadata, &metastore, &*search_service)
+                .await?;
+        return Ok(CreateDeleteTaskResponse::Preview(delete_task_preview));
+    }
+    let delete_query = restrict_delete_query_to_request_scope(delete_query)?;
+    let delete_task = metastore.create_delete_task(delete_query).await?;
+    Ok(CreateDeleteTaskResponse::Created(delete_task))
+}
+
+/// Restricts the delete query to the documents of the request scope: a token restricted to some
+/// documents can only delete, or preview the deletion of, these documents.
+pub(super) fn restrict_delete_query_to_request_scope(
+    mut delete_query: DeleteQuery,
+) -> Result<DeleteQuery, JanitorError> {
+    if let Some(request_scope) = current_request_scope() {
+        delete_query.query_ast = request_scope
+            .filter_query_ast(&delete_query.query_ast)
+            .map_err(|error| JanitorError::InvalidDeleteQuery(error.to_string()))?;
+    }
+    Ok(delete_query)
+}
+
+/// Parses the delete request and validates it against the current doc mapping configuration.
+fn build_delete_query(
+    index_metadata: &IndexMetadata,
+    delete_request: DeleteQueryRequest,
+) -> Result<DeleteQuery, JanitorError> {
     let query_ast = query_ast_from_user_text(&delete_request.query, delete_request.search_fields)
-        .parse_user_query(&metadata.index_config.search_settings.default_search_fields)
+        .parse_user_query(
+            &index_metadata
+                .index_config
+                .search_settings
+                .default_search_fields,
+        )
         .map_err(|err| JanitorError::InvalidDeleteQuery(err.to_string()))?;
     let query_ast_json = serde_json::to_string(&query_ast).map_err(|_err| {
         JanitorError::Internal("failed to serialized delete query ast".to_string())
     })?;
     let delete_query = DeleteQuery {
-        index_uid: Some(index_uid),
+        index_uid: Some(index_metadata.index_uid.clone()),
         start_timestamp: delete_request.start_timestamp,
         end_timestamp: delete_request.end_timestamp,
         query_ast: query_ast_json,
     };
-    let index_config = metadata.into_index_config();
+    let index_config = &index_metadata.index_config;
     // TODO should it be something else than a JanitorError?
     let doc_mapper = build_doc_mapper(&index_config.doc_mapping, &index_config.search_settings)
         .map_err(|error| JanitorError::Internal(error.to_string()))?;
@@ -175,14 +249,16 @@ pub async fn post_delete_request(
     doc_mapper
         .query(doc_mapper.schema(), query_ast, true, None)
         .map_err(|error| JanitorError::InvalidDeleteQuery(error.to_string()))?;
-    let delete_task = metastore.create_delete_task(delete_query).await?;
-    Ok(delete_task)
+    Ok(delete_query)
 }
 
 #[cfg(test)]
 mod tests {
+    use std::sync::Arc;
+
     use quickwit_indexing::TestSandbox;
     use quickwit_proto::metastore::DeleteTask;
+    use quickwit_search::MockSearchService;
     use warp::Filter;
 
     use crate::rest::recover_fn;
@@ -206,7 +282,8 @@ mod tests {
             .unwrap();
         let metastore = test_sandbox.metastore();
         let delete_query_api_handlers =
-            super::delete_task_api_handlers(metastore).recover(recover_fn);
+            super::delete_task_api_handlers(metastore, Arc::new(MockSearchService::new()))
+                .recover(recover_fn);
 
         // POST a delete query with explicit field name in query
         let resp = warp::test::request()
@@ -13,5 +13,6 @@
 // limitations under the License.
 
 mod handler;
+mod preview;
 
 pub use handler::{DeleteTaskApi, delete_task_api_handlers};
@@ -0,0 +1,281 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+
+use quickwit_config::build_doc_mapper;
+use quickwit_doc_mapper::tag_pruning::extract_tags_from_query;
+use quickwit_janitor::error::JanitorError;
+use quickwit_metastore::IndexMetadata;
+use quickwit_proto::metastore::{DeleteQuery, MetastoreError, MetastoreServiceClient};
+use quickwit_proto::search::SearchRequest;
+use quickwit_proto::types::IndexUid;
+use quickwit_query::query_ast::QueryAst;
+use quickwit_search::{
+    IndexMetasForLeafSearch, SearchJob, SearchService, jobs_to_leaf_request, list_relevant_splits,
+};
+use serde::{Deserialize, Serialize};
+
+use super::handler::restrict_delete_query_to_request_scope;
+use crate::request_scope::ensure_indexes_in_scope;
+
+/// Outcome of a dry-run delete task creation.
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, utoipa::ToSchema)]
+pub struct DeleteTaskPreview {
+    /// Number of published documents matching the delete query.
+    pub num_docs: u64,
+    /// Number of published splits containing at least one matching document. These are the
+    /// splits the delete task planner will rewrite.
+    pub num_splits: u64,
+    /// The delete query that a subsequent real delete creates.
+    pub delete_query: DeleteQuery,
+}
+
+/// Counts the documents and splits matching the delete query.
+///
+/// Splits are pruned on time range and tags, then searched one by one, exactly like the
+/// `DeleteTaskPlanner` does when it decides which splits need to be rewritten.
+pub(super) async fn preview_delete_query(
+    delete_query: DeleteQuery,
+    index_metadata: &IndexMetadata,
+    metastore: &MetastoreServiceClient,
+    search_service: &dyn SearchService,
+) -> Result<DeleteTaskPreview, JanitorError> {
+    // The leaf searches below bypass the root search, which enforces the request scope.
+    ensure_indexes_in_scope([index_metadata.index_id()]).map_err(MetastoreError::from)?;
+    let delete_query = restrict_delete_query_to_request_scope(delete_query)?;
+
+    let index_uid: IndexUid = index_metadata.index_uid.clone();
+    let index_config = &index_metadata.index_config;
+    let doc_mapper = build_doc_mapper(&index_config.doc_mapping, &index_config.search_settings)
+        .map_err(|error| JanitorError::Internal(error.to_string()))?;
+    let doc_mapper_str = serde_json::to_string(&doc_mapper)
+        .map_err(|error| JanitorError::Internal(error.to_string()))?;
+    let search_request = SearchRequest::try_from(delete_query.clone())
+        .map_err(|error| JanitorError::InvalidDeleteQuery(error.to_string()))?;
+    let query_ast: QueryAst = serde_json::from_str(&search_request.query_ast)
+        .map_err(|err| JanitorError::InvalidDeleteQuery(err.to_string()))?;
+    let tags_filter_opt = extract_tags_from_query(query_ast);
+
+    let splits_metadata = list_relevant_splits(
+        vec![index_uid.clone()],
+        delete_query.start_timestamp,
+        delete_query.end_timestamp,
+        tags_filter_opt,
+        metastore,
+    )
+    .await
+    .map_err(|error| JanitorError::Internal(error.to_string()))?;
+
+    let mut search_indexes_metas = HashMap::new();
+    search_indexes_metas.insert(
+        index_uid,
+        IndexMetasForLeafSearch {
+            doc_mapper_str,
+            index_uri: index_config.index_uri.clone(),
+        },
+    );
+    let mut num_docs = 0;
+    let mut num_splits = 0;
+
+    for split_metadata in &splits_metadata {
+        let search_job = SearchJob::from(split_metadata);
+        let leaf_search_request =
+            jobs_to_leaf_request(&search_request, &search_indexes_metas, vec![search_job])
+                .map_err(|error| JanitorError::Internal(error.to_string()))?;
+        let leaf_search_response = search_service
+            .leaf_search(leaf_search_request)
+            .await
+            .map_err(|error| JanitorError::Internal(error.to_string()))?;
+
+        if let Some(failed_split) = leaf_search_response.failed_splits.first() {
+            return Err(JanitorError::Internal(format!(
+                "failed to search split `{}`: {}",
+                failed_split.split_id, failed_split.error
+            )));
+        }
+        if leaf_search_response.num_hits > 0 {
+            num_docs += leaf_search_response.num_hits;
+            num_splits += 1;
+        }
+    }
+    let delete_task_preview = DeleteTaskPreview {
+        num_docs,
+        num_splits,
+        delete_query,
+    };
+    Ok(delete_task_preview)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use quickwit_config::SearcherConfig;
+    use quickwit_indexing::TestSandbox;
+    use quickwit_proto::metastore::DeleteTask;
+    use quickwit_proto::search::SearchRequest;
+    use quickwit_search::{
+        ClusterClient, SearchJobPlacer, SearchService, SearchServiceImpl, SearcherContext,
+        SearcherPool, single_node_search,
+    };
+    use serde_json::json;
+    use warp::Filter;
+
+    use super::DeleteTaskPreview;
+    use crate::delete_task_api::delete_task_api_handlers;
+    use crate::request_scope::with_index_scope_for_test;
+    use crate::rest::recover_fn;
+
+    fn local_search_service(test_sandbox: &TestSandbox) -> Arc<dyn SearchService> {
+        let search_job_placer = SearchJobPlacer::new(SearcherPool::default());
+        let searcher_context = Arc::new(SearcherContext::new_without_invoker(
+            SearcherConfig::default(),
+            None,
+        ));
+        Arc::new(SearchServiceImpl::new(
+            test_sandbox.metastore(),
+            test_sandbox.storage_resolver(),
+            ClusterClient::new(search_job_placer),
+            searcher_context,
+        ))
+    }
+
+    #[tokio::test]
+    async fn test_delete_task_api_dry_run() {
+        let index_id = "test-delete-task-rest-dry-run";
+        let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: title
+                type: text
+              - name: body
+                type: text
+              - name: ts
+                type: datetime
+                fast: true
+            timestamp_field: ts
+            mode: lenient
+        "#;
+        let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "", &["title"])
+            .await
+            .unwrap();
+        test_sandbox
+            .add_documents(vec![
+                json!({"title": "one", "body": "myterm", "ts": 1}),
+                json!({"title": "two", "body": "other", "ts": 2}),
+            ])
+            .await
+            .unwrap();
+        test_sandbox
+            .add_documents(vec![
+                json!({"title": "three", "body": "myterm", "ts": 3}),
+                json!({"title": "four", "body": "myterm", "ts": 4}),
+            ])
+            .await
+            .unwrap();
+        test_sandbox
+            .add_documents(vec![json!({"title": "five", "body": "other", "ts": 5})])
+            .await
+            .unwrap();
+        let metastore = test_sandbox.metastore();
+        let delete_query_api_handlers =
+            delete_task_api_handlers(metastore.clone(), local_search_service(&test_sandbox))
+                .recover(recover_fn);
+
+        let resp = warp::test::request()
+            .path("/test-delete-task-rest-dry-run/delete-tasks?dry_run=true")
+            .method("POST")
+            .body(r#"{"query": "body:myterm"}"#)
+            .reply(&delete_query_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let delete_task_preview: DeleteTaskPreview = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(delete_task_preview.num_docs, 3);
+        assert_eq!(delete_task_preview.num_splits, 2);
+
+        // `count_only` is an alias of `dry_run`.
+        let resp = warp::test::request()
+            .path("/test-delete-task-rest-dry-run/delete-tasks?count_only=true")
+            .method("POST")
+            .body(r#"{"query": "body:myterm", "end_timestamp": 4}"#)
+            .reply(&delete_query_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let time_bounded_preview: DeleteTaskPreview = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(time_bounded_preview.num_docs, 2);
+        assert_eq!(time_bounded_preview.num_splits, 2);
+
+        // A dry run does not schedule anything.
+        let resp = warp::test::request()
+            .path("/test-delete-task-rest-dry-run/delete-tasks")
+            .reply(&delete_query_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let delete_tasks: Vec<DeleteTask> = serde_json::from_slice(resp.body()).unwrap();
+        assert!(delete_tasks.is_empty());
+
+        // The real delete reuses the same query and matches the same documents.
+        let resp = warp::test::request()
+            .path("/test-delete-task-rest-dry-run/delete-tasks")
+            .method("POST")
+            .body(r#"{"query": "body:myterm"}"#)
+            .reply(&delete_query_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let created_delete_task: DeleteTask = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(created_delete_task.opstamp, 1);
+        let created_delete_query = created_delete_task.delete_query.unwrap();
+        assert_eq!(created_delete_query, delete_task_preview.delete_query);
+
+        let search_request = SearchRequest::try_from(created_delete_query).unwrap();
+        let search_response =
+            single_node_search(search_request, metastore, test_sandbox.storage_resolver())
+                .await
+                .unwrap();
+        assert_eq!(search_response.num_hits, delete_task_preview.num_docs);
+
+        test_sandbox.assert_quit().await;
+    }
+
+    #[tokio::test]
+    async fn test_delete_task_api_dry_run_out_of_scope_index() {
+        let index_id = "test-delete-task-rest-dry-run-scope";
+        let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: body
+                type: text
+        "#;
+        let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "", &["body"])
+            .await
+            .unwrap();
+        let delete_query_api_handlers = delete_task_api_handlers(
+            test_sandbox.metastore(),
+            local_search_service(&test_sandbox),
+        )
+        .recover(recover_fn);
+
+        let resp = with_index_scope_for_test(
+            &["other-*"],
+            warp::test::request()
+                .path("/test-delete-task-rest-dry-run-scope/delete-tasks?dry_run=true")
+                .method("POST")
+                .body(r#"{"query": "body:myterm"}"#)
+                .reply(&delete_query_api_handlers),
+        )
+        .await;
+        assert_eq!(resp.status(), 403);
+
+        test_sandbox.assert_quit().await;
+    }
+}
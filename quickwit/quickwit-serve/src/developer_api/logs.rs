@@ -0,0 +1,167 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::convert::Infallible;
+
+use bytes::Bytes;
+use futures::{Stream, StreamExt, stream};
+use quickwit_proto::metastore::MetastoreError;
+use quickwit_telemetry_exporters::log_tail;
+use serde::Deserialize;
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::wrappers::errors::BroadcastStreamRecvError;
+use warp::hyper::header::{CACHE_CONTROL, CONTENT_TYPE};
+use warp::hyper::http::HeaderValue;
+use warp::{Filter, Rejection, Reply};
+
+use crate::format::{BodyFormat, extract_format_from_qs};
+use crate::request_scope::ensure_admin;
+use crate::rest_api_response::into_rest_api_response;
+
+#[derive(Deserialize)]
+struct LogsQueryParams {
+    #[serde(default)]
+    follow: bool,
+}
+
+/// Streams the recent log lines of the node as server-sent events, one event per line, then the
+/// live ones if `follow` is set. The log lines honor the current log level, and the values of the
+/// sensitive fields are redacted.
+#[utoipa::path(get, tag = "Debug", path = "/logs")]
+pub fn logs_handler() -> impl warp::Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone
+{
+    warp::path("logs")
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(warp::query::<LogsQueryParams>())
+        .and(extract_format_from_qs())
+        .map(logs)
+}
+
+fn logs(query_params: LogsQueryParams, body_format: BodyFormat) -> warp::reply::Response {
+    if let Err(admin_required) = ensure_admin() {
+        let metastore_error = MetastoreError::from(admin_required);
+        return into_rest_api_response::<(), _>(Err(metastore_error), body_format).into_response();
+    }
+    let events = log_events(query_params.follow).map(Ok::<_, Infallible>);
+    let mut response = warp::reply::Response::new(warp::Body::wrap_stream(events));
+    let headers = response.headers_mut();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("text/event-stream"));
+    headers.insert(CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+    response
+}
+
+/// Returns the recent log lines as server-sent events, followed by the live ones if `follow` is
+/// set.
+fn log_events(follow: bool) -> impl Stream<Item = Bytes> + Send + 'static {
+    let (recent_lines, live_lines_rx) = log_tail().subscribe();
+    let recent_events = stream::iter(recent_lines).map(|line| log_event(&line));
+
+    if !follow {
+        return recent_events.left_stream();
+    }
+    let live_events = BroadcastStream::new(live_lines_rx).map(|line_res| match line_res {
+        Ok(line) => log_event(&line),
+        // Comments are ignored by the clients, but show in the raw stream.
+        Err(BroadcastStreamRecvError::Lagged(num_skipped_lines)) => {
+            Bytes::from(format!(": skipped {num_skipped_lines} log lines\n\n"))
+        }
+    });
+    recent_events.chain(live_events).right_stream()
+}
+
+/// Formats a log line as a server-sent event. The lines of a multiline log line, such as a
+/// backtrace, are sent as the data lines of a single event.
+fn log_event(line: &str) -> Bytes {
+    let mut event = String::with_capacity(line.len() + 8);
+
+    for data_line in line.lines() {
+        event.push_str("data: ");
+        event.push_str(data_line);
+        event.push('\n');
+    }
+    event.push('\n');
+    Bytes::from(event)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use quickwit_telemetry_exporters::log_tail_layer;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use super::*;
+
+    fn emit_log_line(message: &str) {
+        let subscriber = tracing_subscriber::registry().with(log_tail_layer());
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::warn!(password = "hunter2", "{message}");
+        });
+    }
+
+    #[test]
+    fn test_log_event() {
+        assert_eq!(log_event("first line"), "data: first line\n\n");
+        assert_eq!(
+            log_event("first line\nsecond line"),
+            "data: first line\ndata: second line\n\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_logs_handler() {
+        let handler = logs_handler();
+        emit_log_line("recent log line");
+
+        let resp = warp::test::request().path("/logs").reply(&handler).await;
+        assert_eq!(resp.status(), 200);
+        assert_eq!(
+            resp.headers().get(CONTENT_TYPE).unwrap(),
+            "text/event-stream"
+        );
+
+        let body = std::str::from_utf8(resp.body()).unwrap();
+        let event = body
+            .split("\n\n")
+            .find(|event| event.contains("recent log line"))
+            .unwrap();
+        assert!(event.starts_with("data: "));
+        assert!(event.contains("WARN"));
+        assert!(event.contains("password=[REDACTED]"));
+        assert!(!event.contains("hunter2"));
+    }
+
+    #[tokio::test]
+    async fn test_log_events_follow() {
+        let mut events = Box::pin(log_events(true));
+        emit_log_line("live log line");
+
+        let find_live_event = async {
+            while let Some(event) = events.next().await {
+                let event = String::from_utf8(event.to_vec()).unwrap();
+
+                if event.contains("live log line") {
+                    return event;
+                }
+            }
+            panic!("log events stream should not end");
+        };
+        let live_event = tokio::time::timeout(Duration::from_secs(5), find_live_event)
+            .await
+            .unwrap();
+        assert!(live_event.contains("password=[REDACTED]"));
+    }
+}
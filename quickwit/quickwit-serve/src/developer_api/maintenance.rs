@@ -0,0 +1,144 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use quickwit_proto::metastore::MetastoreError;
+use serde::{Deserialize, Serialize};
+use warp::{Filter, Rejection, Reply};
+
+use crate::format::{BodyFormat, extract_format_from_qs};
+use crate::maintenance_mode::MaintenanceMode;
+use crate::request_scope::ensure_admin;
+use crate::rest_api_response::into_rest_api_response;
+use crate::with_arg;
+
+#[derive(Deserialize)]
+struct MaintenanceModeQuery {
+    enabled: bool,
+}
+
+#[derive(Serialize)]
+struct MaintenanceModeResponse {
+    enabled: bool,
+}
+
+/// Gets or toggles the node's maintenance mode. Requires an admin token when the REST API
+/// authenticates the requests.
+#[utoipa::path(get, tag = "Debug", path = "/maintenance")]
+pub fn maintenance_handler(
+    maintenance_mode: MaintenanceMode,
+) -> impl warp::Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    let get_maintenance_mode_filter = warp::get()
+        .and(with_arg(maintenance_mode.clone()))
+        .and(extract_format_from_qs())
+        .map(get_maintenance_mode);
+    let set_maintenance_mode_filter = warp::put()
+        .or(warp::post())
+        .unify()
+        .and(with_arg(maintenance_mode))
+        .and(warp::query::<MaintenanceModeQuery>())
+        .and(extract_format_from_qs())
+        .map(set_maintenance_mode);
+    warp::path("maintenance").and(warp::path::end()).and(
+        get_maintenance_mode_filter
+            .or(set_maintenance_mode_filter)
+            .unify(),
+    )
+}
+
+fn get_maintenance_mode(
+    maintenance_mode: MaintenanceMode,
+    body_format: BodyFormat,
+) -> warp::reply::Response {
+    if let Err(admin_required) = ensure_admin() {
+        let metastore_error = MetastoreError::from(admin_required);
+        return into_rest_api_response::<(), _>(Err(metastore_error), body_format).into_response();
+    }
+    let maintenance_mode_response = MaintenanceModeResponse {
+        enabled: maintenance_mode.is_enabled(),
+    };
+    into_rest_api_response::<_, MetastoreError>(Ok(maintenance_mode_response), body_format)
+        .into_response()
+}
+
+/// Enables or disables the node's maintenance mode. `POST` is accepted as well.
+#[utoipa::path(
+    put,
+    tag = "Debug",
+    path = "/maintenance",
+    params(
+        ("enabled" = bool, Query, description = "Whether the maintenance mode is enabled."),
+    )
+)]
+fn set_maintenance_mode(
+    maintenance_mode: MaintenanceMode,
+    maintenance_mode_query: MaintenanceModeQuery,
+    body_format: BodyFormat,
+) -> warp::reply::Response {
+    if let Err(admin_required) = ensure_admin() {
+        let metastore_error = MetastoreError::from(admin_required);
+        return into_rest_api_response::<(), _>(Err(metastore_error), body_format).into_response();
+    }
+    maintenance_mode.set_enabled(maintenance_mode_query.enabled);
+    let maintenance_mode_response = MaintenanceModeResponse {
+        enabled: maintenance_mode_query.enabled,
+    };
+    into_rest_api_response::<_, MetastoreError>(Ok(maintenance_mode_response), body_format)
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{Value as JsonValue, json};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_maintenance_handler() {
+        let maintenance_mode = MaintenanceMode::default();
+        let handler = maintenance_handler(maintenance_mode.clone());
+
+        let resp = warp::test::request()
+            .method("POST")
+            .path("/maintenance?enabled=true")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(resp_json, json!({"enabled": true}));
+        assert!(maintenance_mode.is_enabled());
+
+        let resp = warp::test::request()
+            .path("/maintenance")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(resp_json, json!({"enabled": true}));
+
+        let resp = warp::test::request()
+            .method("PUT")
+            .path("/maintenance?enabled=false")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        assert!(!maintenance_mode.is_enabled());
+
+        let resp = warp::test::request()
+            .path("/maintenance?format=yaml")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        assert_eq!(resp.body(), "enabled: false\n");
+    }
+}
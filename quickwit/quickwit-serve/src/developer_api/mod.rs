@@ -17,33 +17,58 @@ mod debug;
 #[cfg_attr(not(feature = "jemalloc-profiled"), path = "heap_prof_disabled.rs")]
 mod heap_prof;
 mod log_level;
+mod logs;
+mod maintenance;
 #[cfg_attr(not(feature = "pprof"), path = "pprof_disabled.rs")]
 mod pprof;
+mod rest_config;
 mod server;
 
+use std::sync::Arc;
+
 use debug::debug_handler;
 use heap_prof::heap_prof_handlers;
 use log_level::log_level_handler;
+use logs::logs_handler;
+use maintenance::maintenance_handler;
 use pprof::pprof_handlers;
 use quickwit_cluster::Cluster;
+use quickwit_config::NodeConfig;
+use rest_config::rest_config_handler;
 pub(crate) use server::DeveloperApiServer;
 use warp::{Filter, Rejection};
 
 use crate::EnvFilterReloadFn;
+use crate::live_rest_config::LiveRestConfig;
+use crate::maintenance_mode::MaintenanceMode;
 use crate::rest::recover_fn;
 
 #[derive(utoipa::OpenApi)]
-#[openapi(paths(debug::debug_handler, log_level::log_level_handler))]
+#[openapi(paths(
+    debug::debug_handler,
+    log_level::log_level_handler,
+    logs::logs_handler,
+    maintenance::maintenance_handler,
+    maintenance::set_maintenance_mode,
+    rest_config::rest_config_handler,
+    rest_config::update_rest_config
+))]
 pub struct DeveloperApi;
 
 pub(crate) fn developer_api_routes(
     cluster: Cluster,
     env_filter_reload_fn: EnvFilterReloadFn,
+    maintenance_mode: MaintenanceMode,
+    live_rest_config: LiveRestConfig,
+    node_config: Arc<NodeConfig>,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
     warp::path!("api" / "developer" / ..)
         .and(
             debug_handler(cluster.clone())
                 .or(log_level_handler(env_filter_reload_fn.clone()).boxed())
+                .or(logs_handler().boxed())
+                .or(maintenance_handler(maintenance_mode).boxed())
+                .or(rest_config_handler(live_rest_config, node_config).boxed())
                 .or(pprof_handlers())
                 .or(heap_prof_handlers()),
         )
@@ -0,0 +1,281 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use quickwit_config::NodeConfig;
+use quickwit_proto::{ServiceError, ServiceErrorCode};
+use serde::{Deserialize, Serialize};
+use warp::hyper::http::HeaderMap;
+use warp::{Filter, Rejection};
+
+use crate::format::extract_format_from_qs;
+use crate::live_rest_config::{LiveRestConfig, parse_extra_header};
+use crate::request_scope::{AdminRequired, ensure_admin};
+use crate::rest_api_response::into_rest_api_response;
+use crate::with_arg;
+
+/// Update of the CORS origins and extra headers of the REST API. The omitted fields are left
+/// unchanged.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RestConfigUpdate {
+    cors_allow_origins: Option<Vec<String>>,
+    extra_headers: Option<BTreeMap<String, String>>,
+}
+
+#[derive(Serialize)]
+struct RestConfigResponse {
+    cors_allow_origins: Vec<String>,
+    extra_headers: BTreeMap<String, String>,
+}
+
+impl RestConfigResponse {
+    fn from_live_rest_config(live_rest_config: &LiveRestConfig) -> Self {
+        let rest_headers_config = live_rest_config.load();
+        let extra_headers = rest_headers_config
+            .extra_headers
+            .iter()
+            .map(|(header_name, header_value)| {
+                let header_value_str = String::from_utf8_lossy(header_value.as_bytes());
+                (header_name.to_string(), header_value_str.into_owned())
+            })
+            .collect();
+        RestConfigResponse {
+            cors_allow_origins: rest_headers_config.cors_allow_origins.clone(),
+            extra_headers,
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum RestConfigError {
+    #[error(transparent)]
+    AdminRequired(#[from] AdminRequired),
+    #[error(
+        "updating the REST API config requires `rest.jwt` or `rest.enable_rest_config_updates` to \
+         be set in the node config"
+    )]
+    Disabled,
+    #[error("failed to update REST API config: {0}")]
+    InvalidUpdate(String),
+}
+
+impl ServiceError for RestConfigError {
+    fn error_code(&self) -> ServiceErrorCode {
+        match self {
+            Self::AdminRequired(_) | Self::Disabled => ServiceErrorCode::Forbidden,
+            Self::InvalidUpdate(_) => ServiceErrorCode::BadRequest,
+        }
+    }
+}
+
+/// Gets or updates the CORS origins and extra headers of the REST API. The requests received
+/// after an update are served with the updated values. Requires an admin token when the REST API
+/// authenticates the requests.
+#[utoipa::path(get, tag = "Debug", path = "/rest-config")]
+pub fn rest_config_handler(
+    live_rest_config: LiveRestConfig,
+    node_config: Arc<NodeConfig>,
+) -> impl warp::Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    let get_rest_config_filter = warp::get()
+        .and(with_arg(live_rest_config.clone()))
+        .map(get_rest_config);
+    let update_rest_config_filter = warp::put()
+        .or(warp::post())
+        .unify()
+        .and(with_arg(live_rest_config))
+        .and(with_arg(node_config))
+        .and(warp::body::json())
+        .map(update_rest_config);
+    warp::path("rest-config")
+        .and(warp::path::end())
+        .and(get_rest_config_filter.or(update_rest_config_filter).unify())
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+}
+
+fn get_rest_config(
+    live_rest_config: LiveRestConfig,
+) -> Result<RestConfigResponse, RestConfigError> {
+    ensure_admin()?;
+    Ok(RestConfigResponse::from_live_rest_config(&live_rest_config))
+}
+
+/// Updates the CORS origins and extra headers of the REST API. `POST` is accepted as well.
+#[utoipa::path(
+    put,
+    tag = "Debug",
+    path = "/rest-config",
+    responses(
+        (status = 200, description = "Successfully updated the REST API config."),
+        (status = 400, description = "The CORS origins or the extra headers are invalid."),
+        (status = 403, description = "The updates are disabled, or the token of the request does not grant admin access."),
+    ),
+)]
+fn update_rest_config(
+    live_rest_config: LiveRestConfig,
+    node_config: Arc<NodeConfig>,
+    rest_config_update: RestConfigUpdate,
+) -> Result<RestConfigResponse, RestConfigError> {
+    // Without tokens, any client reaching the API could change the CORS origins.
+    if node_config.rest_config.jwt.is_none() && !node_config.rest_config.enable_rest_config_updates
+    {
+        return Err(RestConfigError::Disabled);
+    }
+    ensure_admin()?;
+    apply_rest_config_update(&live_rest_config, rest_config_update)
+        .map_err(|error| RestConfigError::InvalidUpdate(format!("{error:#}")))?;
+    Ok(RestConfigResponse::from_live_rest_config(&live_rest_config))
+}
+
+fn apply_rest_config_update(
+    live_rest_config: &LiveRestConfig,
+    rest_config_update: RestConfigUpdate,
+) -> anyhow::Result<()> {
+    let rest_headers_config = live_rest_config.load();
+    let cors_allow_origins = rest_config_update
+        .cors_allow_origins
+        .unwrap_or_else(|| rest_headers_config.cors_allow_origins.clone());
+    let extra_headers = match rest_config_update.extra_headers {
+        Some(extra_headers) => parse_extra_headers(extra_headers)?,
+        None => rest_headers_config.extra_headers.clone(),
+    };
+    live_rest_config.update(cors_allow_origins, extra_headers)
+}
+
+fn parse_extra_headers(extra_headers: BTreeMap<String, String>) -> anyhow::Result<HeaderMap> {
+    let mut header_map = HeaderMap::with_capacity(extra_headers.len());
+
+    for (header_name_str, header_value_str) in extra_headers {
+        let (header_name, header_value) = parse_extra_header(&header_name_str, &header_value_str)?;
+        header_map.insert(header_name, header_value);
+    }
+    Ok(header_map)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{Value as JsonValue, json};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_rest_config_handler() {
+        let live_rest_config = LiveRestConfig::new(
+            vec!["https://quickwit.io".to_string()],
+            &[],
+            HeaderMap::new(),
+        )
+        .unwrap();
+        let mut node_config = NodeConfig::for_test();
+        node_config.rest_config.enable_rest_config_updates = true;
+        let handler = rest_config_handler(live_rest_config.clone(), Arc::new(node_config));
+
+        let resp = warp::test::request()
+            .path("/rest-config")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        let expected_resp_json = json!({
+            "cors_allow_origins": ["https://quickwit.io"],
+            "extra_headers": {},
+        });
+        assert_eq!(resp_json, expected_resp_json);
+
+        let resp = warp::test::request()
+            .method("PUT")
+            .path("/rest-config")
+            .json(&json!({"extra_headers": {"x-custom-header": "custom-value"}}))
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        let expected_resp_json = json!({
+            "cors_allow_origins": ["https://quickwit.io"],
+            "extra_headers": {"x-custom-header": "custom-value"},
+        });
+        assert_eq!(resp_json, expected_resp_json);
+
+        let resp = warp::test::request()
+            .method("POST")
+            .path("/rest-config")
+            .json(&json!({"cors_allow_origins": ["invalid\norigin"]}))
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 400);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert!(
+            resp_json["message"]
+                .as_str()
+                .unwrap()
+                .starts_with("failed to update REST API config")
+        );
+        assert_eq!(
+            live_rest_config.load().cors_allow_origins,
+            ["https://quickwit.io"]
+        );
+
+        let resp = warp::test::request()
+            .method("POST")
+            .path("/rest-config")
+            .json(&json!({"cors_allow_origins": ["*"]}))
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        assert_eq!(live_rest_config.load().cors_allow_origins, ["*"]);
+        assert_eq!(
+            live_rest_config.load().extra_headers["x-custom-header"],
+            "custom-value"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rest_config_updates_disabled() {
+        let live_rest_config = LiveRestConfig::new(
+            vec!["https://quickwit.io".to_string()],
+            &[],
+            HeaderMap::new(),
+        )
+        .unwrap();
+        let handler =
+            rest_config_handler(live_rest_config.clone(), Arc::new(NodeConfig::for_test()));
+        let resp = warp::test::request()
+            .path("/rest-config")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+
+        let resp = warp::test::request()
+            .method("PUT")
+            .path("/rest-config")
+            .json(&json!({"cors_allow_origins": ["*"]}))
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 403);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert!(
+            resp_json["message"]
+                .as_str()
+                .unwrap()
+                .contains("rest.enable_rest_config_updates")
+        );
+        assert_eq!(
+            live_rest_config.load().cors_allow_origins,
+            ["https://quickwit.io"]
+        );
+    }
+}
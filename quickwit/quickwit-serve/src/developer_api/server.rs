@@ -24,12 +24,16 @@ use quickwit_cluster::Cluster;
 use quickwit_config::NodeConfig;
 use quickwit_config::service::QuickwitService;
 use quickwit_control_plane::control_plane::{ControlPlane, GetDebugInfo};
+use quickwit_indexing::actors::{ListMergeOperations, MergeSchedulerService};
 use quickwit_ingest::{IngestRouter, Ingester};
 use quickwit_proto::developer::{
     DeveloperError, DeveloperResult, DeveloperService, GetDebugInfoRequest, GetDebugInfoResponse,
+    GetNodeStatsRequest, GetNodeStatsResponse, ListMergeOperationsRequest,
+    ListMergeOperationsResponse,
 };
 use serde_json::json;
 
+use crate::node_stats::NodeStats;
 use crate::{BuildInfo, QuickwitServices, RuntimeInfo};
 
 #[derive(Clone)]
@@ -39,6 +43,7 @@ pub(crate) struct DeveloperApiServer {
     control_plane_mailbox_opt: Option<Mailbox<ControlPlane>>,
     ingest_router_opt: Option<IngestRouter>,
     ingester_opt: Option<Ingester>,
+    merge_scheduler_service_opt: Option<Mailbox<MergeSchedulerService>>,
 }
 
 impl fmt::Debug for DeveloperApiServer {
@@ -57,6 +62,7 @@ impl DeveloperApiServer {
             control_plane_mailbox_opt: services.control_plane_server_opt.clone(),
             ingest_router_opt: services.ingest_router_opt.clone(),
             ingester_opt: services.ingester_opt.clone(),
+            merge_scheduler_service_opt: services.merge_scheduler_service_opt.clone(),
         }
     }
 }
@@ -118,12 +124,68 @@ impl DeveloperService for DeveloperApiServer {
         };
         Ok(response)
     }
+
+    async fn get_node_stats(
+        &self,
+        _request: GetNodeStatsRequest,
+    ) -> DeveloperResult<GetNodeStatsResponse> {
+        let node_stats = NodeStats::collect(&self.node_config.enabled_services);
+
+        let node_stats_json = serde_json::to_vec(&node_stats).map_err(|error| {
+            let message = format!("failed to JSON serialize node stats: {error}");
+            DeveloperError::Internal(message)
+        })?;
+        let response = GetNodeStatsResponse {
+            node_stats_json: Bytes::from(node_stats_json),
+        };
+        Ok(response)
+    }
+
+    async fn list_merge_operations(
+        &self,
+        request: ListMergeOperationsRequest,
+    ) -> DeveloperResult<ListMergeOperationsResponse> {
+        // Nodes that do not run the indexer service have no merge scheduler, hence no merges.
+        let mut merge_operations =
+            if let Some(merge_scheduler_service) = &self.merge_scheduler_service_opt {
+                merge_scheduler_service
+                    .ask(ListMergeOperations)
+                    .await
+                    .map_err(|error| {
+                        let message = format!("failed to list merge operations: {error}");
+                        DeveloperError::Internal(message)
+                    })?
+            } else {
+                Vec::new()
+            };
+        if !request.index_uid.is_empty() {
+            merge_operations.retain(|merge_operation| {
+                merge_operation.index_uid.to_string() == request.index_uid
+            });
+        }
+        let merge_operations_json = serde_json::to_vec(&merge_operations).map_err(|error| {
+            let message = format!("failed to JSON serialize merge operations: {error}");
+            DeveloperError::Internal(message)
+        })?;
+        let response = ListMergeOperationsResponse {
+            merge_operations_json: Bytes::from(merge_operations_json),
+        };
+        Ok(response)
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use quickwit_actors::Universe;
     use quickwit_cluster::{ChitchatTransport, create_cluster_for_test};
+    use quickwit_indexing::actors::{
+        MergeOperationState, MergeOperationStatus, MergePhase, schedule_merge,
+    };
+    use quickwit_indexing::merge_policy::MergeOperation;
+    use quickwit_metastore::SplitMetadata;
+    use quickwit_proto::types::IndexUid;
     use serde_json::Value as JsonValue;
+    use tantivy::Inventory;
 
     use super::*;
 
@@ -152,6 +214,7 @@ mod tests {
             control_plane_mailbox_opt: None,
             ingest_router_opt: None,
             ingester_opt: None,
+            merge_scheduler_service_opt: None,
         };
         let request = GetDebugInfoRequest { roles: Vec::new() };
         let response = developer_api_server.get_debug_info(request).await.unwrap();
@@ -169,4 +232,74 @@ mod tests {
 
         // TODO: Test control plane and ingester debug info.
     }
+
+    #[tokio::test]
+    async fn test_developer_api_server_list_merge_operations() {
+        let universe = Universe::with_accelerated_time();
+        let transport = ChitchatTransport::default();
+        let cluster = create_cluster_for_test(Vec::new(), &["indexer"], &transport, true)
+            .await
+            .unwrap();
+        let (merge_scheduler_service, _) = universe
+            .spawn_builder()
+            .spawn(MergeSchedulerService::new(1));
+        // The merge tasks are held by the inbox, so that the first merge keeps running.
+        let (merge_split_downloader_mailbox, _merge_split_downloader_inbox) =
+            universe.create_test_mailbox();
+        let inventory = Inventory::new();
+
+        let index_uid = IndexUid::for_test("test-index", 0);
+        let other_index_uid = IndexUid::for_test("other-index", 0);
+
+        for (index_uid, split_id) in [(&index_uid, "split-1"), (&other_index_uid, "split-2")] {
+            let split_metadata = SplitMetadata {
+                split_id: split_id.to_string(),
+                index_uid: index_uid.clone(),
+                ..Default::default()
+            };
+            let merge_operation = MergeOperation::new_merge_operation(vec![split_metadata]);
+            schedule_merge(
+                &merge_scheduler_service,
+                inventory.track(merge_operation),
+                merge_split_downloader_mailbox.clone(),
+            )
+            .await
+            .unwrap();
+        }
+        let developer_api_server = DeveloperApiServer {
+            node_config: Arc::new(NodeConfig::for_test()),
+            cluster,
+            control_plane_mailbox_opt: None,
+            ingest_router_opt: None,
+            ingester_opt: None,
+            merge_scheduler_service_opt: Some(merge_scheduler_service),
+        };
+        let request = ListMergeOperationsRequest {
+            index_uid: String::new(),
+        };
+        let response = developer_api_server
+            .list_merge_operations(request)
+            .await
+            .unwrap();
+        let merge_operations: Vec<MergeOperationStatus> =
+            serde_json::from_slice(&response.merge_operations_json).unwrap();
+        assert_eq!(merge_operations.len(), 2);
+        assert_eq!(merge_operations[0].state, MergeOperationState::Running);
+        assert_eq!(merge_operations[0].phase, Some(MergePhase::Downloading));
+
+        let request = ListMergeOperationsRequest {
+            index_uid: other_index_uid.to_string(),
+        };
+        let response = developer_api_server
+            .list_merge_operations(request)
+            .await
+            .unwrap();
+        let merge_operations: Vec<MergeOperationStatus> =
+            serde_json::from_slice(&response.merge_operations_json).unwrap();
+        assert_eq!(merge_operations.len(), 1);
+        assert_eq!(merge_operations[0].index_uid, other_index_uid);
+        assert_eq!(merge_operations[0].split_ids, ["split-2"]);
+
+        universe.assert_quit().await;
+    }
 }
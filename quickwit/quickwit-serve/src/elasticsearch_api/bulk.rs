@@ -12,12 +12,15 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::num::NonZeroUsize;
 use std::time::Instant;
 
+use bytes::Bytes;
 use bytesize::ByteSize;
 use quickwit_ingest::{
     CommitType, DocBatchBuilder, IngestRequest, IngestService, IngestServiceClient,
+    IngestServiceError,
 };
 use quickwit_proto::ingest::router::IngestRouterServiceClient;
 use quickwit_proto::types::IndexId;
@@ -26,81 +29,119 @@ use warp::{Filter, Rejection};
 
 use super::bulk_v2::{ElasticBulkResponse, elastic_bulk_ingest_v2};
 use crate::elasticsearch_api::filter::{elastic_bulk_filter, elastic_index_bulk_filter};
-use crate::elasticsearch_api::make_elastic_api_response;
+use crate::elasticsearch_api::make_elastic_api_response_with_index_headers;
 use crate::elasticsearch_api::model::{BulkAction, ElasticBulkOptions, ElasticsearchError};
 use crate::format::extract_format_from_qs;
-use crate::ingest_api::lines;
+use crate::index_api::IndexMetadataCache;
+use crate::ingest_api::{IndexAllowList, IngestFilters, lines, too_many_docs_error};
+use crate::request_scope::ensure_indexes_in_scope;
 use crate::rest::recover_fn;
 use crate::{Body, with_arg};
 
 /// POST `_elastic/_bulk`
+#[allow(clippy::too_many_arguments)]
 pub fn es_compat_bulk_handler(
     ingest_service: IngestServiceClient,
     ingest_router: IngestRouterServiceClient,
+    index_metadata_cache: IndexMetadataCache,
     content_length_limit: ByteSize,
+    max_docs_per_request_opt: Option<NonZeroUsize>,
+    index_allow_list_opt: Option<IndexAllowList>,
     enable_ingest_v1: bool,
     enable_ingest_v2: bool,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
     elastic_bulk_filter(content_length_limit)
         .and(with_arg(ingest_service))
         .and(with_arg(ingest_router))
-        .then(move |body, bulk_options, ingest_service, ingest_router| {
-            elastic_ingest_bulk(
-                None,
-                body,
-                bulk_options,
-                ingest_service,
-                ingest_router,
-                enable_ingest_v1,
-                enable_ingest_v2,
-            )
-        })
+        .and(with_arg(index_metadata_cache))
+        .then(
+            move |body, bulk_options, ingest_service, ingest_router, index_metadata_cache| {
+                elastic_ingest_bulk(
+                    None,
+                    body,
+                    bulk_options,
+                    ingest_service,
+                    ingest_router,
+                    index_metadata_cache,
+                    max_docs_per_request_opt,
+                    index_allow_list_opt.clone(),
+                    enable_ingest_v1,
+                    enable_ingest_v2,
+                )
+            },
+        )
         .and(extract_format_from_qs())
-        .map(make_elastic_api_response)
+        .map(make_elastic_api_response_with_index_headers)
         .recover(recover_fn)
 }
 
 /// POST `_elastic/<index>/_bulk`
+#[allow(clippy::too_many_arguments)]
 pub fn es_compat_index_bulk_handler(
     ingest_service: IngestServiceClient,
     ingest_router: IngestRouterServiceClient,
+    index_metadata_cache: IndexMetadataCache,
     content_length_limit: ByteSize,
+    max_docs_per_request_opt: Option<NonZeroUsize>,
+    index_allow_list_opt: Option<IndexAllowList>,
     enable_ingest_v1: bool,
     enable_ingest_v2: bool,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
     elastic_index_bulk_filter(content_length_limit)
         .and(with_arg(ingest_service))
         .and(with_arg(ingest_router))
+        .and(with_arg(index_metadata_cache))
         .then(
-            move |index_id, body, bulk_options, ingest_service, ingest_router| {
+            move |index_id,
+                  body,
+                  bulk_options,
+                  ingest_service,
+                  ingest_router,
+                  index_metadata_cache| {
                 elastic_ingest_bulk(
                     Some(index_id),
                     body,
                     bulk_options,
                     ingest_service,
                     ingest_router,
+                    index_metadata_cache,
+                    max_docs_per_request_opt,
+                    index_allow_list_opt.clone(),
                     enable_ingest_v1,
                     enable_ingest_v2,
                 )
             },
         )
         .and(extract_format_from_qs())
-        .map(make_elastic_api_response)
+        .map(make_elastic_api_response_with_index_headers)
         .recover(recover_fn)
         .boxed()
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn elastic_ingest_bulk(
     default_index_id: Option<IndexId>,
     body: Body,
     bulk_options: ElasticBulkOptions,
     ingest_service: IngestServiceClient,
     ingest_router: IngestRouterServiceClient,
+    index_metadata_cache: IndexMetadataCache,
+    max_docs_per_request_opt: Option<NonZeroUsize>,
+    index_allow_list_opt: Option<IndexAllowList>,
     enable_ingest_v1: bool,
     enable_ingest_v2: bool,
-) -> Result<ElasticBulkResponse, ElasticsearchError> {
+) -> Result<(ElasticBulkResponse, BTreeMap<String, String>), ElasticsearchError> {
     if enable_ingest_v2 && !bulk_options.use_legacy_ingest {
-        return elastic_bulk_ingest_v2(default_index_id, body, bulk_options, ingest_router).await;
+        return elastic_bulk_ingest_v2(
+            default_index_id,
+            body,
+            bulk_options,
+            ingest_router,
+            index_metadata_cache,
+            max_docs_per_request_opt,
+            index_allow_list_opt,
+        )
+        .await;
     }
     if !enable_ingest_v1 {
         return Err(ElasticsearchError::new(
@@ -110,7 +151,7 @@ async fn elastic_ingest_bulk(
         ));
     }
     let now = Instant::now();
-    let mut doc_batch_builders = HashMap::new();
+    let mut docs: Vec<(IndexId, Bytes)> = Vec::new();
     let mut lines = lines(&body.content).enumerate();
 
     while let Some((line_number, line)) = lines.next() {
@@ -121,6 +162,13 @@ async fn elastic_ingest_bulk(
                 None,
             )
         })?;
+        // The documents are counted as their action lines are parsed, before any of them is
+        // ingested.
+        if let Some(max_docs_per_request) = max_docs_per_request_opt
+            && docs.len() >= max_docs_per_request.get()
+        {
+            return Err(too_many_docs_error(max_docs_per_request).into());
+        }
         let (_, source) = lines.next().ok_or_else(|| {
             ElasticsearchError::new(
                 StatusCode::BAD_REQUEST,
@@ -141,6 +189,24 @@ async fn elastic_ingest_bulk(
                     None,
                 )
             })?;
+        ensure_indexes_in_scope([index_id.as_str()]).map_err(IngestServiceError::from)?;
+
+        if let Some(index_allow_list) = &index_allow_list_opt {
+            index_allow_list.check(&index_id)?;
+        }
+
+        docs.push((index_id, body.content.slice_ref(source)));
+    }
+    let index_ids: HashSet<IndexId> = docs.iter().map(|(index_id, _)| index_id.clone()).collect();
+    let ingest_filters = IngestFilters::fetch(&index_metadata_cache, index_ids).await?;
+    let index_response_headers = ingest_filters.response_headers().clone();
+    let doc_matches = ingest_filters.matches_docs(docs.clone()).await?;
+    let mut doc_batch_builders = HashMap::new();
+
+    for ((index_id, source), is_match) in docs.into_iter().zip(doc_matches) {
+        if !is_match {
+            continue;
+        }
         let doc_batch_builder = doc_batch_builders
             .entry(index_id.clone())
             .or_insert(DocBatchBuilder::new(index_id));
@@ -165,11 +231,12 @@ async fn elastic_ingest_bulk(
         errors,
         actions: Vec::new(),
     };
-    Ok(bulk_response)
+    Ok((bulk_response, index_response_headers))
 }
 
 #[cfg(test)]
 mod tests {
+    use std::num::NonZeroUsize;
     use std::sync::Arc;
     use std::time::Duration;
 
@@ -178,7 +245,6 @@ mod tests {
     use quickwit_ingest::{FetchRequest, IngestServiceClient, SuggestTruncateRequest};
     use quickwit_metastore::metastore_for_test;
     use quickwit_proto::ingest::router::IngestRouterServiceClient;
-    use quickwit_proto::metastore::MetastoreServiceClient;
     use quickwit_search::MockSearchService;
     use quickwit_storage::StorageResolver;
     use warp::hyper::StatusCode;
@@ -187,7 +253,7 @@ mod tests {
     use crate::elasticsearch_api::elastic_api_handlers;
     use crate::elasticsearch_api::model::ElasticsearchError;
     use crate::elasticsearch_api::tests::mock_cluster;
-    use crate::ingest_api::setup_ingest_v1_service;
+    use crate::ingest_api::{IndexAllowList, setup_ingest_v1_service};
 
     #[tokio::test]
     async fn test_bulk_api_returns_404_if_index_id_does_not_exist() {
@@ -204,8 +270,9 @@ mod tests {
             search_service,
             ingest_service,
             ingest_router,
-            MetastoreServiceClient::mocked(),
+            metastore_for_test(),
             index_service,
+            None,
             true,
             false,
         );
@@ -240,8 +307,9 @@ mod tests {
             search_service,
             ingest_service,
             ingest_router,
-            MetastoreServiceClient::mocked(),
+            metastore_for_test(),
             index_service,
+            None,
             true,
             false,
         );
@@ -279,8 +347,9 @@ mod tests {
             search_service,
             ingest_service,
             ingest_router,
-            MetastoreServiceClient::mocked(),
+            metastore_for_test(),
             index_service,
+            None,
             true,
             false,
         );
@@ -316,8 +385,9 @@ mod tests {
             search_service,
             ingest_service,
             ingest_router,
-            MetastoreServiceClient::mocked(),
+            metastore_for_test(),
             index_service,
+            None,
             true,
             false,
         );
@@ -356,8 +426,9 @@ mod tests {
             search_service,
             ingest_service,
             ingest_router,
-            MetastoreServiceClient::mocked(),
+            metastore_for_test(),
             index_service,
+            None,
             true,
             false,
         );
@@ -447,8 +518,9 @@ mod tests {
             search_service,
             ingest_service,
             ingest_router,
-            MetastoreServiceClient::mocked(),
+            metastore_for_test(),
             index_service,
+            None,
             true,
             false,
         );
@@ -535,8 +607,9 @@ mod tests {
             search_service,
             ingest_service,
             ingest_router,
-            MetastoreServiceClient::mocked(),
+            metastore_for_test(),
             index_service,
+            None,
             true,
             false,
         );
@@ -557,4 +630,90 @@ mod tests {
             "Malformed action/metadata line [#0]. Details: `expected value at line 1 column 57`"
         );
     }
+    #[tokio::test]
+    async fn test_bulk_api_max_docs_per_request() {
+        let mut node_config = NodeConfig::for_test();
+        node_config.ingest_api_config.max_docs_per_request = NonZeroUsize::new(1);
+        let search_service = Arc::new(MockSearchService::new());
+        // The request is rejected before reaching the ingest service.
+        let ingest_service = IngestServiceClient::mocked();
+        let ingest_router = IngestRouterServiceClient::mocked();
+        let index_service =
+            IndexService::new(metastore_for_test(), StorageResolver::unconfigured());
+        let elastic_api_handlers = elastic_api_handlers(
+            mock_cluster().await,
+            Arc::new(node_config),
+            search_service,
+            ingest_service,
+            ingest_router,
+            metastore_for_test(),
+            index_service,
+            None,
+            true,
+            false,
+        );
+        let payload = r#"
+            { "create" : { "_index" : "my-index", "_id" : "1"} }
+            {"id": 1, "message": "push"}
+            { "create" : { "_index" : "my-index", "_id" : "2"} }
+            {"id": 2, "message": "push"}"#;
+        let resp = warp::test::request()
+            .path("/_elastic/_bulk")
+            .method("POST")
+            .body(payload)
+            .reply(&elastic_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 400);
+        let es_error: ElasticsearchError = serde_json::from_slice(resp.body()).unwrap();
+        assert!(
+            es_error
+                .error
+                .reason
+                .unwrap()
+                .contains("max_docs_per_request")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_bulk_api_index_allow_list() {
+        let index_allow_list = IndexAllowList::try_new(&["logs-*".to_string()]).unwrap();
+        let search_service = Arc::new(MockSearchService::new());
+        // The request is rejected before reaching the ingest service.
+        let ingest_service = IngestServiceClient::mocked();
+        let ingest_router = IngestRouterServiceClient::mocked();
+        let index_service =
+            IndexService::new(metastore_for_test(), StorageResolver::unconfigured());
+        let elastic_api_handlers = elastic_api_handlers(
+            mock_cluster().await,
+            Arc::new(NodeConfig::for_test()),
+            search_service,
+            ingest_service,
+            ingest_router,
+            metastore_for_test(),
+            index_service,
+            Some(index_allow_list),
+            true,
+            false,
+        );
+        let payload = r#"
+            { "create" : { "_index" : "logs-app", "_id" : "1"} }
+            {"id": 1, "message": "push"}
+            { "create" : { "_index" : "my-index", "_id" : "2"} }
+            {"id": 2, "message": "push"}"#;
+        let resp = warp::test::request()
+            .path("/_elastic/_bulk")
+            .method("POST")
+            .body(payload)
+            .reply(&elastic_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 403);
+        let es_error: ElasticsearchError = serde_json::from_slice(resp.body()).unwrap();
+        assert!(
+            es_error
+                .error
+                .reason
+                .unwrap()
+                .contains("`my-index` is not allowed")
+        );
+    }
 }
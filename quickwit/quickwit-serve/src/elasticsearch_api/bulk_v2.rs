@@ -12,12 +12,14 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::num::NonZeroUsize;
 use std::time::Instant;
 
+use bytes::Bytes;
 use quickwit_common::rate_limited_error;
 use quickwit_config::{INGEST_V2_SOURCE_ID, validate_identifier};
-use quickwit_ingest::IngestRequestV2Builder;
+use quickwit_ingest::{IngestRequestV2Builder, IngestServiceError};
 use quickwit_proto::ingest::CommitTypeV2;
 use quickwit_proto::ingest::router::{
     IngestFailureReason, IngestResponseV2, IngestRouterService, IngestRouterServiceClient,
@@ -29,7 +31,9 @@ use warp::hyper::StatusCode;
 use super::model::ElasticException;
 use crate::Body;
 use crate::elasticsearch_api::model::{BulkAction, ElasticBulkOptions, ElasticsearchError};
-use crate::ingest_api::lines;
+use crate::index_api::IndexMetadataCache;
+use crate::ingest_api::{IndexAllowList, IngestFilters, lines, too_many_docs_error};
+use crate::request_scope::ensure_indexes_in_scope;
 
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub(crate) struct ElasticBulkResponse {
@@ -80,18 +84,28 @@ struct DocHandle {
     is_parse_failure: bool,
 }
 
+/// A document of a bulk request, before it is checked against the ingest filter of its index.
+struct BulkDoc {
+    doc_position: usize,
+    index_id: IndexId,
+    es_doc_id: Option<ElasticDocId>,
+    doc: Bytes,
+}
+
 pub(crate) async fn elastic_bulk_ingest_v2(
     default_index_id: Option<IndexId>,
     body: Body,
     bulk_options: ElasticBulkOptions,
     ingest_router: IngestRouterServiceClient,
-) -> Result<ElasticBulkResponse, ElasticsearchError> {
+    index_metadata_cache: IndexMetadataCache,
+    max_docs_per_request_opt: Option<NonZeroUsize>,
+    index_allow_list_opt: Option<IndexAllowList>,
+) -> Result<(ElasticBulkResponse, BTreeMap<String, String>), ElasticsearchError> {
     let now = Instant::now();
-    let mut ingest_request_builder = IngestRequestV2Builder::default();
     let mut lines = lines(&body.content).enumerate();
-    let mut per_subrequest_doc_handles: HashMap<u32, Vec<DocHandle>> = HashMap::new();
+    let mut bulk_docs = Vec::new();
     let mut action_count = 0;
-    let mut invalid_index_id_items = Vec::new();
+    let mut non_ingested_items = Vec::new();
     while let Some((line_no, line)) = lines.next() {
         let action = serde_json::from_slice::<BulkAction>(line).map_err(|error| {
             ElasticsearchError::new(
@@ -100,6 +114,13 @@ pub(crate) async fn elastic_bulk_ingest_v2(
                 Some(ElasticException::IllegalArgument),
             )
         })?;
+        // The documents are counted as their action lines are parsed, before any of them is
+        // ingested.
+        if let Some(max_docs_per_request) = max_docs_per_request_opt
+            && action_count >= max_docs_per_request.get()
+        {
+            return Err(too_many_docs_error(max_docs_per_request).into());
+        }
         let (_, doc) = lines.next().ok_or_else(|| {
             ElasticsearchError::new(
                 StatusCode::BAD_REQUEST,
@@ -127,20 +148,54 @@ pub(crate) async fn elastic_bulk_ingest_v2(
         // from deeper ingest layers is harder
         if validate_identifier("", &index_id).is_err() {
             let invalid_item = make_invalid_index_id_item(index_id.clone(), meta.es_doc_id);
-            invalid_index_id_items.push((action_count, invalid_item));
+            non_ingested_items.push((action_count, invalid_item));
             action_count += 1;
             continue;
         }
+        ensure_indexes_in_scope([index_id.as_str()]).map_err(IngestServiceError::from)?;
+
+        if let Some(index_allow_list) = &index_allow_list_opt {
+            index_allow_list.check(&index_id)?;
+        }
+        let bulk_doc = BulkDoc {
+            doc_position: action_count,
+            index_id,
+            es_doc_id: meta.es_doc_id,
+            doc: body.content.slice_ref(doc),
+        };
+        bulk_docs.push(bulk_doc);
+        action_count += 1;
+    }
+    let index_ids: HashSet<IndexId> = bulk_docs
+        .iter()
+        .map(|bulk_doc| bulk_doc.index_id.clone())
+        .collect();
+    let ingest_filters = IngestFilters::fetch(&index_metadata_cache, index_ids).await?;
+    let index_response_headers = ingest_filters.response_headers().clone();
+    let docs: Vec<(IndexId, Bytes)> = bulk_docs
+        .iter()
+        .map(|bulk_doc| (bulk_doc.index_id.clone(), bulk_doc.doc.clone()))
+        .collect();
+    let doc_matches = ingest_filters.matches_docs(docs).await?;
+
+    let mut ingest_request_builder = IngestRequestV2Builder::default();
+    let mut per_subrequest_doc_handles: HashMap<u32, Vec<DocHandle>> = HashMap::new();
 
-        let (subrequest_id, doc_uid) = ingest_request_builder.add_doc(index_id, doc);
+    for (bulk_doc, is_match) in bulk_docs.into_iter().zip(doc_matches) {
+        if !is_match {
+            let filtered_item = make_filtered_item(bulk_doc.index_id, bulk_doc.es_doc_id);
+            non_ingested_items.push((bulk_doc.doc_position, filtered_item));
+            continue;
+        }
+        let (subrequest_id, doc_uid) =
+            ingest_request_builder.add_doc(bulk_doc.index_id, &bulk_doc.doc);
 
         let doc_handle = DocHandle {
-            doc_position: action_count,
+            doc_position: bulk_doc.doc_position,
             doc_uid,
-            es_doc_id: meta.es_doc_id,
+            es_doc_id: bulk_doc.es_doc_id,
             is_parse_failure: false,
         };
-        action_count += 1;
         per_subrequest_doc_handles
             .entry(subrequest_id)
             .or_default()
@@ -151,19 +206,27 @@ pub(crate) async fn elastic_bulk_ingest_v2(
     let ingest_request_opt = ingest_request_builder.build(INGEST_V2_SOURCE_ID, commit_type);
 
     let Some(ingest_request) = ingest_request_opt else {
-        return Ok(ElasticBulkResponse::default());
+        let bulk_response = make_elastic_bulk_response_v2(
+            IngestResponseV2::default(),
+            per_subrequest_doc_handles,
+            now,
+            action_count,
+            non_ingested_items,
+        )?;
+        return Ok((bulk_response, index_response_headers));
     };
     let ingest_response = ingest_router.ingest(ingest_request).await.map_err(|err| {
         rate_limited_error!(limit_per_min=6, err=?err, "router error");
         err
     })?;
-    make_elastic_bulk_response_v2(
+    let bulk_response = make_elastic_bulk_response_v2(
         ingest_response,
         per_subrequest_doc_handles,
         now,
         action_count,
-        invalid_index_id_items,
-    )
+        non_ingested_items,
+    )?;
+    Ok((bulk_response, index_response_headers))
 }
 
 #[allow(clippy::result_large_err)]
@@ -172,7 +235,7 @@ fn make_elastic_bulk_response_v2(
     mut per_subrequest_doc_handles: HashMap<u32, Vec<DocHandle>>,
     now: Instant,
     action_count: usize,
-    invalid_index_id_items: Vec<(usize, ElasticBulkItem)>,
+    non_ingested_items: Vec<(usize, ElasticBulkItem)>,
 ) -> Result<ElasticBulkResponse, ElasticsearchError> {
     let mut positioned_actions: Vec<(usize, ElasticBulkAction)> = Vec::with_capacity(action_count);
     let mut errors = false;
@@ -322,8 +385,8 @@ fn make_elastic_bulk_response_v2(
         "doc handles should be empty"
     );
 
-    for (position, item) in invalid_index_id_items {
-        errors = true;
+    for (position, item) in non_ingested_items {
+        errors |= item.error.is_some();
         let action = ElasticBulkAction::Index(item);
         positioned_actions.push((position, action));
     }
@@ -379,21 +442,35 @@ fn make_invalid_index_id_item(index_id: String, es_doc_id: Option<String>) -> El
     }
 }
 
+/// Item of a document dropped because it does not match the ingest filter of its index. It is
+/// reported as a successful no-op, like an Elasticsearch ingest pipeline dropping a document.
+fn make_filtered_item(index_id: String, es_doc_id: Option<String>) -> ElasticBulkItem {
+    ElasticBulkItem {
+        index_id,
+        es_doc_id,
+        status: StatusCode::OK,
+        error: None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use bytesize::ByteSize;
+    use quickwit_config::IndexConfig;
+    use quickwit_metastore::{CreateIndexRequestExt, metastore_for_test};
     use quickwit_proto::ingest::router::{
         IngestFailure, IngestFailureReason, IngestResponseV2, IngestSuccess,
         MockIngestRouterService,
     };
     use quickwit_proto::ingest::{ParseFailure, ParseFailureReason};
+    use quickwit_proto::metastore::{CreateIndexRequest, MetastoreService, MetastoreServiceClient};
     use quickwit_proto::types::{IndexUid, Position, ShardId};
     use warp::{Filter, Rejection, Reply};
 
     use super::*;
     use crate::elasticsearch_api::bulk_v2::ElasticBulkResponse;
     use crate::elasticsearch_api::filter::elastic_bulk_filter;
-    use crate::elasticsearch_api::make_elastic_api_response;
+    use crate::elasticsearch_api::make_elastic_api_response_with_index_headers;
     use crate::elasticsearch_api::model::ElasticsearchError;
     use crate::format::extract_format_from_qs;
     use crate::with_arg;
@@ -432,13 +509,35 @@ mod tests {
         ingest_router: IngestRouterServiceClient,
         content_length_limit: ByteSize,
     ) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+        es_compat_bulk_handler_v2_with_metastore(
+            ingest_router,
+            metastore_for_test(),
+            content_length_limit,
+        )
+    }
+
+    fn es_compat_bulk_handler_v2_with_metastore(
+        ingest_router: IngestRouterServiceClient,
+        metastore: MetastoreServiceClient,
+        content_length_limit: ByteSize,
+    ) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+        let index_metadata_cache = IndexMetadataCache::new(metastore);
         elastic_bulk_filter(content_length_limit)
             .and(with_arg(ingest_router))
-            .then(|body, bulk_options, ingest_router| {
-                elastic_bulk_ingest_v2(None, body, bulk_options, ingest_router)
+            .and(with_arg(index_metadata_cache))
+            .then(|body, bulk_options, ingest_router, index_metadata_cache| {
+                elastic_bulk_ingest_v2(
+                    None,
+                    body,
+                    bulk_options,
+                    ingest_router,
+                    index_metadata_cache,
+                    None,
+                    None,
+                )
             })
             .and(extract_format_from_qs())
-            .map(make_elastic_api_response)
+            .map(make_elastic_api_response_with_index_headers)
     }
 
     #[tokio::test]
@@ -947,4 +1046,61 @@ mod tests {
         assert_eq!(items[2].es_doc_id.as_ref().unwrap(), "1");
         assert_eq!(items[2].status, StatusCode::CREATED);
     }
+
+    #[tokio::test]
+    async fn test_bulk_api_ingest_filter() {
+        let metastore = metastore_for_test();
+        let mut index_config = IndexConfig::for_test("my-index", "ram:///indexes/my-index");
+        index_config.ingest_settings.ingest_filter = Some(r#"level != "debug""#.to_string());
+        let create_index_request =
+            CreateIndexRequest::try_from_index_config(&index_config).unwrap();
+        metastore.create_index(create_index_request).await.unwrap();
+
+        let mut mock_ingest_router = MockIngestRouterService::new();
+        mock_ingest_router
+            .expect_ingest()
+            .once()
+            .returning(|ingest_request| {
+                assert_eq!(ingest_request.subrequests.len(), 1);
+                let doc_batch = ingest_request.subrequests[0].doc_batch.as_ref().unwrap();
+                assert_eq!(doc_batch.num_docs(), 1);
+                Ok(IngestResponseV2 {
+                    successes: vec![IngestSuccess {
+                        subrequest_id: 0,
+                        index_uid: Some(IndexUid::for_test("my-index", 0)),
+                        source_id: INGEST_V2_SOURCE_ID.to_string(),
+                        shard_id: Some(ShardId::from(1)),
+                        replication_position_inclusive: Some(Position::offset(0u64)),
+                        num_ingested_docs: 1,
+                        parse_failures: Vec::new(),
+                    }],
+                    failures: Vec::new(),
+                })
+            });
+        let ingest_router = IngestRouterServiceClient::from_mock(mock_ingest_router);
+        let handler =
+            es_compat_bulk_handler_v2_with_metastore(ingest_router, metastore, ByteSize::mb(10));
+
+        let payload = r#"
+            {"create": {"_index": "my-index", "_id": "1"}}
+            {"level": "debug"}
+            {"create": {"_index": "my-index", "_id": "2"}}
+            {"level": "info"}
+        "#;
+        let response = warp::test::request()
+            .path("/_elastic/_bulk")
+            .method("POST")
+            .body(payload)
+            .reply(&handler)
+            .await;
+        assert_eq!(response.status(), 200);
+
+        let bulk_response: ElasticBulkResponse = serde_json::from_slice(response.body()).unwrap();
+        assert!(!bulk_response.errors);
+        assert_eq!(bulk_response.actions.len(), 2);
+        assert_eq!(bulk_response.actions[0].es_doc_id(), Some("1"));
+        assert_eq!(bulk_response.actions[0].status(), StatusCode::OK);
+        assert_eq!(bulk_response.actions[1].es_doc_id(), Some("2"));
+        assert_eq!(bulk_response.actions[1].status(), StatusCode::CREATED);
+    }
 }
@@ -20,14 +20,17 @@ use warp::{Filter, Rejection};
 
 use super::model::{
     CatIndexQueryParams, DeleteQueryParams, FieldCapabilityQueryParams, FieldCapabilityRequestBody,
-    IndexMappingQueryParams, MultiSearchQueryParams, SearchQueryParamsCount,
+    IndexMappingQueryParams, MultiSearchQueryParams, PutMappingRequestBody, ReindexQueryParams,
+    ReindexRequestBody, SearchQueryParamsCount,
 };
 use crate::Body;
 use crate::decompression::get_body_bytes;
 use crate::elasticsearch_api::model::{
     ElasticBulkOptions, ScrollQueryParams, SearchBody, SearchQueryParams,
 };
-use crate::search_api::{extract_index_id_patterns, extract_index_id_patterns_default};
+use crate::search_api::{
+    GetDocumentQueryParams, extract_index_id_patterns, extract_index_id_patterns_default,
+};
 
 const BODY_LENGTH_LIMIT: ByteSize = ByteSize::mib(1);
 
@@ -184,6 +187,14 @@ pub(crate) fn elastic_delete_index_filter()
         .and(warp::query())
 }
 
+#[utoipa::path(post, tag = "Indexes", path = "/{index}/_cache/clear")]
+pub(crate) fn elastic_index_clear_cache_filter()
+-> impl Filter<Extract = (Vec<String>,), Error = Rejection> + Clone {
+    warp::path!("_elastic" / String / "_cache" / "clear")
+        .and_then(extract_index_id_patterns)
+        .and(warp::post())
+}
+
 // No support for any query parameters for now.
 #[utoipa::path(get, tag = "Search", path = "/{index}/_stats")]
 pub(crate) fn elastic_index_stats_filter()
@@ -280,6 +291,14 @@ pub(crate) fn elastic_delete_scroll_filter() -> impl Filter<Extract = (), Error
     warp::path!("_elastic" / "_search" / "scroll").and(warp::delete())
 }
 
+#[utoipa::path(get, tag = "Search", path = "/{index}/_doc/{id}")]
+pub(crate) fn elastic_get_document_filter()
+-> impl Filter<Extract = (String, String, GetDocumentQueryParams), Error = Rejection> + Clone {
+    warp::path!("_elastic" / String / "_doc" / String)
+        .and(warp::get())
+        .and(warp::query())
+}
+
 pub(crate) fn elastic_aliases_filter() -> impl Filter<Extract = (), Error = Rejection> + Clone {
     warp::path!("_elastic" / "_aliases").and(warp::get())
 }
@@ -292,3 +311,28 @@ pub(crate) fn elastic_index_mapping_filter()
         .and(warp::get())
         .and(warp::query())
 }
+
+pub(crate) fn elastic_put_mapping_filter()
+-> impl Filter<Extract = (String, PutMappingRequestBody), Error = Rejection> + Clone {
+    warp::path!("_elastic" / String / "_mapping")
+        .or(warp::path!("_elastic" / String / "_mappings"))
+        .unify()
+        .and(warp::put().or(warp::post()).unify())
+        .and(warp::body::content_length_limit(BODY_LENGTH_LIMIT.as_u64()))
+        .and(warp::body::json())
+}
+
+#[utoipa::path(post, tag = "Indexes", path = "/_reindex")]
+pub(crate) fn elastic_reindex_filter()
+-> impl Filter<Extract = (ReindexQueryParams, ReindexRequestBody), Error = Rejection> + Clone {
+    warp::path!("_elastic" / "_reindex")
+        .and(warp::post())
+        .and(warp::query())
+        .and(warp::body::content_length_limit(BODY_LENGTH_LIMIT.as_u64()))
+        .and(warp::body::json())
+}
+
+pub(crate) fn elastic_get_task_filter()
+-> impl Filter<Extract = (String,), Error = Rejection> + Clone {
+    warp::path!("_elastic" / "_tasks" / String).and(warp::get())
+}
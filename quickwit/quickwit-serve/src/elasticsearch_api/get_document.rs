@@ -0,0 +1,172 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use quickwit_proto::ServiceError;
+use quickwit_proto::metastore::MetastoreServiceClient;
+use quickwit_search::SearchService;
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use warp::hyper::StatusCode;
+use warp::{Filter, Rejection};
+
+use super::filter::elastic_get_document_filter;
+use super::make_elastic_api_response;
+use super::model::ElasticsearchError;
+use crate::rest::recover_fn;
+use crate::rest_api_response::RestApiResponse;
+use crate::search_api::{GetDocumentError, GetDocumentQueryParams, get_document_by_id};
+use crate::{BodyFormat, with_arg};
+
+#[derive(Debug, Serialize)]
+struct ElasticGetDocumentResponse {
+    _index: String,
+    _id: String,
+    found: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    _source: Option<JsonValue>,
+}
+
+/// GET _elastic/{index}/_doc/{id}
+pub fn es_compat_get_document_handler(
+    search_service: Arc<dyn SearchService>,
+    metastore: MetastoreServiceClient,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    elastic_get_document_filter()
+        .and(with_arg(search_service))
+        .and(with_arg(metastore))
+        .then(es_compat_get_document)
+        .recover(recover_fn)
+}
+
+/// Returns the document whose value for the index's id field is `id`. Like Elasticsearch, a
+/// missing document yields a 404 response with `found` set to `false`.
+async fn es_compat_get_document(
+    index_id: String,
+    doc_id: String,
+    query_params: GetDocumentQueryParams,
+    search_service: Arc<dyn SearchService>,
+    metastore: MetastoreServiceClient,
+) -> RestApiResponse {
+    let document_opt =
+        match get_document_by_id(index_id.clone(), &doc_id, &*search_service, &metastore).await {
+            Ok(document_opt) => document_opt,
+            Err(GetDocumentError::Search(search_error)) => {
+                let elasticsearch_result: Result<(), _> =
+                    Err(ElasticsearchError::from(search_error));
+                return make_elastic_api_response(elasticsearch_result, BodyFormat::default());
+            }
+            Err(get_document_error) => {
+                let elasticsearch_error = ElasticsearchError::new(
+                    get_document_error.error_code().http_status_code(),
+                    get_document_error.to_string(),
+                    None,
+                );
+                let elasticsearch_result: Result<(), _> = Err(elasticsearch_error);
+                return make_elastic_api_response(elasticsearch_result, BodyFormat::default());
+            }
+        };
+    let (status_code, response) = match document_opt {
+        Some(document) => (
+            StatusCode::OK,
+            ElasticGetDocumentResponse {
+                _index: index_id,
+                _id: doc_id,
+                found: true,
+                _source: query_params.filter_document(document),
+            },
+        ),
+        None => (
+            StatusCode::NOT_FOUND,
+            ElasticGetDocumentResponse {
+                _index: index_id,
+                _id: doc_id,
+                found: false,
+                _source: None,
+            },
+        ),
+    };
+    RestApiResponse::new::<_, ()>(&Ok(response), status_code, BodyFormat::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::search_api::{mock_metastore_with_id_field, mock_search_service_with_doc};
+
+    #[tokio::test]
+    async fn test_es_compat_get_document() {
+        let handler = es_compat_get_document_handler(
+            Arc::new(mock_search_service_with_doc()),
+            mock_metastore_with_id_field(),
+        );
+        let resp = warp::test::request()
+            .path("/_elastic/test-index/_doc/doc-1?_source_includes=body")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        let expected_resp_json = json!({
+            "_index": "test-index",
+            "_id": "doc-1",
+            "found": true,
+            "_source": {"body": "hello"}
+        });
+        assert_eq!(resp_json, expected_resp_json);
+
+        let resp = warp::test::request()
+            .path("/_elastic/test-index/_doc/doc-1?_source=false")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert!(resp_json.get("_source").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_es_compat_get_document_duplicate_id() {
+        let handler = es_compat_get_document_handler(
+            Arc::new(mock_search_service_with_doc()),
+            mock_metastore_with_id_field(),
+        );
+        let resp = warp::test::request()
+            .path("/_elastic/test-index/_doc/doc-dup")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 409);
+    }
+
+    #[tokio::test]
+    async fn test_es_compat_get_document_not_found() {
+        let handler = es_compat_get_document_handler(
+            Arc::new(mock_search_service_with_doc()),
+            mock_metastore_with_id_field(),
+        );
+        let resp = warp::test::request()
+            .path("/_elastic/test-index/_doc/doc-2")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 404);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        let expected_resp_json = json!({
+            "_index": "test-index",
+            "_id": "doc-2",
+            "found": false
+        });
+        assert_eq!(resp_json, expected_resp_json);
+    }
+}
@@ -15,13 +15,18 @@
 mod bulk;
 mod bulk_v2;
 mod filter;
+mod get_document;
 pub(crate) mod model;
+mod put_mapping;
+mod reindex;
 pub(crate) mod rest_handler;
 
 use std::sync::Arc;
 
 use bulk::{es_compat_bulk_handler, es_compat_index_bulk_handler};
 pub use filter::ElasticCompatibleApi;
+use get_document::es_compat_get_document_handler;
+use put_mapping::es_compat_put_mapping_handler;
 use quickwit_cluster::Cluster;
 use quickwit_config::NodeConfig;
 use quickwit_index_management::IndexService;
@@ -29,27 +34,32 @@ use quickwit_ingest::IngestServiceClient;
 use quickwit_proto::ingest::router::IngestRouterServiceClient;
 use quickwit_proto::metastore::MetastoreServiceClient;
 use quickwit_search::SearchService;
+use reindex::{
+    ReindexContext, ReindexTasks, es_compat_get_task_handler, es_compat_reindex_handler,
+};
 pub use rest_handler::{
     es_compat_cat_indices_handler, es_compat_cluster_info_handler, es_compat_delete_index_handler,
     es_compat_delete_scroll_handler, es_compat_index_cat_indices_handler,
-    es_compat_index_count_handler, es_compat_index_field_capabilities_handler,
-    es_compat_index_multi_search_handler, es_compat_index_search_handler,
-    es_compat_index_stats_handler, es_compat_resolve_index_handler, es_compat_scroll_handler,
-    es_compat_search_handler, es_compat_stats_handler,
+    es_compat_index_clear_cache_handler, es_compat_index_count_handler,
+    es_compat_index_field_capabilities_handler, es_compat_index_multi_search_handler,
+    es_compat_index_search_handler, es_compat_index_stats_handler, es_compat_resolve_index_handler,
+    es_compat_scroll_handler, es_compat_search_handler, es_compat_stats_handler,
 };
 use rest_handler::{
     es_compat_cluster_health_handler, es_compat_nodes_handler, es_compat_search_shards_handler,
 };
 use serde::{Deserialize, Serialize};
 use warp::hyper::StatusCode;
-use warp::{Filter, Rejection};
+use warp::{Filter, Rejection, Reply};
 
 use crate::elasticsearch_api::model::ElasticsearchError;
 use crate::elasticsearch_api::rest_handler::{
     es_compat_aliases_handler, es_compat_index_mapping_handler,
 };
+use crate::index_api::IndexMetadataCache;
+use crate::ingest_api::IndexAllowList;
 use crate::rest::recover_fn;
-use crate::rest_api_response::RestApiResponse;
+use crate::rest_api_response::{RestApiResponse, insert_index_response_headers};
 use crate::{BodyFormat, BuildInfo};
 
 /// Setup Elasticsearch API handlers
@@ -65,17 +75,34 @@ pub fn elastic_api_handlers(
     ingest_router: IngestRouterServiceClient,
     metastore: MetastoreServiceClient,
     index_service: IndexService,
+    index_allow_list_opt: Option<IndexAllowList>,
     enable_ingest_v1: bool,
     enable_ingest_v2: bool,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
     let ingest_content_length_limit = node_config.ingest_api_config.content_length_limit;
+    let max_docs_per_request_opt = node_config.ingest_api_config.max_docs_per_request;
+    let index_metadata_cache = IndexMetadataCache::new(metastore.clone());
+    let reindex_tasks = ReindexTasks::new(node_config.node_id.clone());
+    let reindex_context = ReindexContext {
+        search_service: search_service.clone(),
+        ingest_service: ingest_service.clone(),
+        ingest_router: ingest_router.clone(),
+        index_metadata_cache: index_metadata_cache.clone(),
+        index_allow_list_opt: index_allow_list_opt.clone(),
+        enable_ingest_v1,
+        enable_ingest_v2,
+        tasks: reindex_tasks.clone(),
+    };
     es_compat_cluster_info_handler(node_config.clone(), BuildInfo::get())
         .or(es_compat_nodes_handler(node_config.clone()))
         .or(es_compat_search_handler(search_service.clone()))
         .or(es_compat_bulk_handler(
             ingest_service.clone(),
             ingest_router.clone(),
+            index_metadata_cache.clone(),
             ingest_content_length_limit,
+            max_docs_per_request_opt,
+            index_allow_list_opt.clone(),
             enable_ingest_v1,
             enable_ingest_v2,
         ))
@@ -83,7 +110,10 @@ pub fn elastic_api_handlers(
         .or(es_compat_index_bulk_handler(
             ingest_service,
             ingest_router,
+            index_metadata_cache,
             ingest_content_length_limit,
+            max_docs_per_request_opt,
+            index_allow_list_opt,
             enable_ingest_v1,
             enable_ingest_v2,
         ))
@@ -97,7 +127,7 @@ pub fn elastic_api_handlers(
         ))
         .boxed()
         .or(es_compat_index_stats_handler(metastore.clone()))
-        .or(es_compat_delete_index_handler(index_service))
+        .or(es_compat_delete_index_handler(index_service.clone()))
         .or(es_compat_stats_handler(metastore.clone()))
         .or(es_compat_cluster_health_handler(cluster))
         .or(es_compat_index_cat_indices_handler(metastore.clone()))
@@ -109,6 +139,15 @@ pub fn elastic_api_handlers(
             search_service.clone(),
         ))
         .or(es_compat_search_shards_handler(node_config))
+        .or(es_compat_index_clear_cache_handler(search_service.clone()))
+        .or(es_compat_get_document_handler(
+            search_service.clone(),
+            metastore.clone(),
+        ))
+        .boxed()
+        .or(es_compat_put_mapping_handler(index_service))
+        .or(es_compat_reindex_handler(reindex_context))
+        .or(es_compat_get_task_handler(reindex_tasks))
         .recover(recover_fn)
         .with(warp::reply::with::header(
             "X-Elastic-Product",
@@ -158,8 +197,31 @@ fn make_elastic_api_response<T: serde::Serialize>(
     RestApiResponse::new(&elasticsearch_result, status_code, body_format)
 }
 
+/// Makes an Elasticsearch API response from a result carrying the response headers declared in
+/// the configs of the indexes targeted by the request.
+fn make_elastic_api_response_with_index_headers<T, H>(
+    elasticsearch_result: Result<(T, H), ElasticsearchError>,
+    body_format: BodyFormat,
+) -> warp::reply::Response
+where
+    T: serde::Serialize,
+    H: IntoIterator<Item = (String, String)>,
+{
+    let (elasticsearch_result, index_response_headers_opt) = match elasticsearch_result {
+        Ok((value, index_response_headers)) => (Ok(value), Some(index_response_headers)),
+        Err(error) => (Err(error), None),
+    };
+    let mut response = make_elastic_api_response(elasticsearch_result, body_format).into_response();
+
+    if let Some(index_response_headers) = index_response_headers_opt {
+        insert_index_response_headers(&mut response, index_response_headers);
+    }
+    response
+}
+
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
     use std::sync::Arc;
 
     use assert_json_diff::assert_json_include;
@@ -170,7 +232,8 @@ mod tests {
     use quickwit_ingest::{IngestApiService, IngestServiceClient};
     use quickwit_metastore::metastore_for_test;
     use quickwit_proto::ingest::router::IngestRouterServiceClient;
-    use quickwit_proto::metastore::MetastoreServiceClient;
+    use quickwit_proto::metastore::{MetastoreServiceClient, MockMetastoreService};
+    use quickwit_proto::search::{ClearCacheResponse, ScoreThreshold};
     use quickwit_search::MockSearchService;
     use quickwit_storage::StorageResolver;
     use serde_json::Value as JsonValue;
@@ -179,7 +242,14 @@ mod tests {
     use super::elastic_api_handlers;
     use super::model::ElasticsearchError;
     use crate::BuildInfo;
-    use crate::elasticsearch_api::rest_handler::es_compat_cluster_info_handler;
+    use crate::elasticsearch_api::rest_handler::{
+        es_compat_cat_indices_handler, es_compat_cluster_info_handler,
+        es_compat_index_cat_indices_handler, es_compat_index_clear_cache_handler,
+        es_compat_index_mapping_handler, es_compat_index_stats_handler,
+        es_compat_resolve_index_handler, es_compat_stats_handler,
+    };
+    use crate::query_history::query_history;
+    use crate::request_scope::with_index_scope_for_test;
     use crate::rest::recover_fn;
 
     fn ingest_service_client() -> IngestServiceClient {
@@ -223,6 +293,7 @@ mod tests {
             ingest_router,
             MetastoreServiceClient::mocked(),
             index_service,
+            None,
             true,
             false,
         );
@@ -288,6 +359,7 @@ mod tests {
             ingest_router,
             MetastoreServiceClient::mocked(),
             index_service,
+            None,
             true,
             false,
         );
@@ -338,6 +410,7 @@ mod tests {
             ingest_router,
             MetastoreServiceClient::mocked(),
             index_service,
+            None,
             true,
             false,
         );
@@ -378,6 +451,7 @@ mod tests {
             ingest_router,
             MetastoreServiceClient::mocked(),
             index_service,
+            None,
             true,
             false,
         );
@@ -418,6 +492,7 @@ mod tests {
             ingest_router,
             MetastoreServiceClient::mocked(),
             index_service,
+            None,
             true,
             false,
         );
@@ -457,6 +532,7 @@ mod tests {
             ingest_router,
             MetastoreServiceClient::mocked(),
             index_service,
+            None,
             true,
             false,
         );
@@ -506,6 +582,7 @@ mod tests {
             ingest_router,
             MetastoreServiceClient::mocked(),
             index_service,
+            None,
             true,
             false,
         );
@@ -522,6 +599,181 @@ mod tests {
         assert_eq!(resp.status(), 200);
     }
 
+    #[tokio::test]
+    async fn test_search_api_with_source_false() {
+        let config = Arc::new(NodeConfig::for_test());
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .withf(|search_request| search_request.skip_fetch_docs)
+            .times(2)
+            .returning(|_| {
+                let hit = quickwit_proto::search::Hit {
+                    json: String::new(),
+                    partial_hit: Some(quickwit_proto::search::PartialHit {
+                        split_id: "split-1".to_string(),
+                        segment_ord: 0,
+                        doc_id: 3,
+                        ..Default::default()
+                    }),
+                    snippet: None,
+                    index_id: "index-1".to_string(),
+                    scores: None,
+                };
+                Ok(quickwit_proto::search::SearchResponse {
+                    num_hits: 1,
+                    hits: vec![hit],
+                    ..Default::default()
+                })
+            });
+        let ingest_router = IngestRouterServiceClient::mocked();
+        let index_service =
+            IndexService::new(metastore_for_test(), StorageResolver::unconfigured());
+        let es_search_api_handler = super::elastic_api_handlers(
+            mock_cluster().await,
+            config,
+            Arc::new(mock_search_service),
+            ingest_service_client(),
+            ingest_router,
+            MetastoreServiceClient::mocked(),
+            index_service,
+            None,
+            true,
+            false,
+        );
+        let resp = warp::test::request()
+            .path("/_elastic/index-1/_search")
+            .method("POST")
+            .body(r#"{"query": {"match_all": {}}, "_source": false}"#)
+            .reply(&es_search_api_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        let hit_json = &resp_json["hits"]["hits"][0];
+        assert!(hit_json["_source"].is_null());
+        assert_eq!(hit_json["_index"], "index-1");
+        assert!(!hit_json["_id"].as_str().unwrap().is_empty());
+
+        let resp = warp::test::request()
+            .path("/_elastic/index-1/_search?_source=false")
+            .reply(&es_search_api_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert!(resp_json["hits"]["hits"][0]["_source"].is_null());
+    }
+
+    #[tokio::test]
+    async fn test_search_api_with_min_score() {
+        let config = Arc::new(NodeConfig::for_test());
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .withf(|search_request| search_request.min_score == Some(ScoreThreshold { value: 0.5 }))
+            .return_once(|_| Ok(Default::default()));
+        let ingest_router = IngestRouterServiceClient::mocked();
+        let index_service =
+            IndexService::new(metastore_for_test(), StorageResolver::unconfigured());
+        let es_search_api_handler = super::elastic_api_handlers(
+            mock_cluster().await,
+            config,
+            Arc::new(mock_search_service),
+            ingest_service_client(),
+            ingest_router,
+            MetastoreServiceClient::mocked(),
+            index_service,
+            None,
+            true,
+            false,
+        );
+        let resp = warp::test::request()
+            .path("/_elastic/index-1/_search")
+            .method("POST")
+            .body(r#"{"query": {"match_all": {}}, "min_score": 0.5}"#)
+            .reply(&es_search_api_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+    }
+
+    #[tokio::test]
+    async fn test_search_api_index_response_headers() {
+        let config = Arc::new(NodeConfig::for_test());
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service.expect_root_search().returning(|_| {
+            Ok(quickwit_proto::search::SearchResponse {
+                response_headers: HashMap::from([(
+                    "x-data-classification".to_string(),
+                    "confidential".to_string(),
+                )]),
+                ..Default::default()
+            })
+        });
+        let ingest_router = IngestRouterServiceClient::mocked();
+        let index_service =
+            IndexService::new(metastore_for_test(), StorageResolver::unconfigured());
+        let es_search_api_handler = super::elastic_api_handlers(
+            mock_cluster().await,
+            config,
+            Arc::new(mock_search_service),
+            ingest_service_client(),
+            ingest_router,
+            MetastoreServiceClient::mocked(),
+            index_service,
+            None,
+            true,
+            false,
+        );
+        for path in ["/_elastic/index-1/_search", "/_elastic/index-1/_count"] {
+            let resp = warp::test::request()
+                .path(path)
+                .method("POST")
+                .body(r#"{"query": {"match_all": {}}}"#)
+                .reply(&es_search_api_handler)
+                .await;
+            assert_eq!(resp.status(), 200);
+            assert_eq!(resp.headers()["x-data-classification"], "confidential");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_search_api_records_query_history() {
+        let config = Arc::new(NodeConfig::for_test());
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service.expect_root_search().returning(|_| {
+            Ok(quickwit_proto::search::SearchResponse {
+                num_hits: 3,
+                searched_index_ids: vec!["es-query-history-1".to_string()],
+                ..Default::default()
+            })
+        });
+        let ingest_router = IngestRouterServiceClient::mocked();
+        let index_service =
+            IndexService::new(metastore_for_test(), StorageResolver::unconfigured());
+        let es_search_api_handler = super::elastic_api_handlers(
+            mock_cluster().await,
+            config,
+            Arc::new(mock_search_service),
+            ingest_service_client(),
+            ingest_router,
+            MetastoreServiceClient::mocked(),
+            index_service,
+            None,
+            true,
+            false,
+        );
+        let resp = warp::test::request()
+            .path("/_elastic/es-query-history-*/_search")
+            .method("POST")
+            .body(r#"{"query": {"match_all": {}}}"#)
+            .reply(&es_search_api_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+
+        let queries = query_history().get("es-query-history-1");
+        assert_eq!(queries.len(), 1);
+        assert_eq!(queries[0].num_hits, 3);
+    }
+
     #[tokio::test]
     async fn test_es_compat_cluster_info_handler() {
         let build_info = BuildInfo::get();
@@ -564,4 +816,91 @@ mod tests {
             .await;
         assert_eq!(resp.status(), 200);
     }
+
+    #[tokio::test]
+    async fn test_es_compat_index_clear_cache_handler() {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_clear_cache()
+            .withf(|clear_cache_request| clear_cache_request.index_id_patterns == ["index-1"])
+            .return_once(|_| {
+                Ok(ClearCacheResponse {
+                    num_searchers: 3,
+                    num_failed_searchers: 1,
+                })
+            });
+        let handler = es_compat_index_clear_cache_handler(Arc::new(mock_search_service));
+        let resp = warp::test::request()
+            .path("/_elastic/index-1/_cache/clear")
+            .method("POST")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        let expected_response_json = serde_json::json!({
+            "_shards": {
+                "total": 3,
+                "successful": 2,
+                "failed": 1,
+            }
+        });
+        assert_json_include!(actual: resp_json, expected: expected_response_json);
+    }
+
+    #[tokio::test]
+    async fn test_es_compat_index_clear_cache_handler_index_not_found() {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_clear_cache()
+            .return_once(|_| {
+                Err(quickwit_search::SearchError::IndexesNotFound {
+                    index_ids: vec!["index-1".to_string()],
+                })
+            });
+        let handler = es_compat_index_clear_cache_handler(Arc::new(mock_search_service));
+        let resp = warp::test::request()
+            .path("/_elastic/index-1/_cache/clear")
+            .method("POST")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 404);
+    }
+
+    #[tokio::test]
+    async fn test_es_compat_metadata_handlers_out_of_scope_indexes() {
+        // The mocks have no expectations: the out-of-scope indexes must be rejected before
+        // reading the metastore.
+        let metastore = MetastoreServiceClient::from_mock(MockMetastoreService::new());
+        let handler = es_compat_index_stats_handler(metastore.clone())
+            .or(es_compat_stats_handler(metastore.clone()))
+            .or(es_compat_index_cat_indices_handler(metastore.clone()))
+            .or(es_compat_cat_indices_handler(metastore.clone()))
+            .or(es_compat_resolve_index_handler(metastore.clone()))
+            .or(es_compat_index_mapping_handler(
+                metastore,
+                Arc::new(MockSearchService::new()),
+            ))
+            .recover(recover_fn);
+
+        for path in [
+            "/_elastic/other-logs/_stats",
+            "/_elastic/_stats",
+            "/_elastic/acme-logs,other-logs/_stats",
+            "/_elastic/_cat/indices/other-logs",
+            "/_elastic/_cat/indices",
+            "/_elastic/_resolve/index/other-*",
+            "/_elastic/other-logs/_mapping",
+            "/_elastic/acme-logs,other-logs/_mappings",
+        ] {
+            let resp = with_index_scope_for_test(
+                &["acme-*"],
+                warp::test::request()
+                    .path(path)
+                    .method("GET")
+                    .reply(&handler),
+            )
+            .await;
+            assert_eq!(resp.status(), 403, "{path}");
+        }
+    }
 }
@@ -0,0 +1,41 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use elasticsearch_dsl::ShardStatistics;
+use quickwit_proto::search::ClearCacheResponse;
+use serde::Serialize;
+
+/// Response of the clear cache API. Quickwit has no shards: each searcher whose cache was
+/// cleared counts as a shard.
+#[derive(Debug, Serialize)]
+pub struct ElasticsearchClearCacheResponse {
+    #[serde(rename = "_shards")]
+    pub shards: ShardStatistics,
+}
+
+impl From<ClearCacheResponse> for ElasticsearchClearCacheResponse {
+    fn from(clear_cache_response: ClearCacheResponse) -> Self {
+        let total = clear_cache_response.num_searchers as u32;
+        let failed = clear_cache_response.num_failed_searchers as u32;
+        ElasticsearchClearCacheResponse {
+            shards: ShardStatistics {
+                total,
+                successful: total.saturating_sub(failed),
+                skipped: 0,
+                failed,
+                failures: Vec::new(),
+            },
+        }
+    }
+}
@@ -100,28 +100,32 @@ fn build_properties(field_mappings: &[FieldMappingEntry]) -> HashMap<String, Fie
 }
 
 fn field_mapping_from_entry(entry: &FieldMappingEntry) -> Option<FieldMapping> {
-    match &entry.mapping_type {
+    let typ = es_type_from_field_mapping_type(&entry.mapping_type);
+
+    if let FieldMappingType::Object(options) = &entry.mapping_type {
+        let properties = build_properties(&options.field_mappings);
+        return Some(FieldMapping::Object { typ, properties });
+    }
+    Some(FieldMapping::Leaf { typ })
+}
+
+pub(super) fn es_type_from_field_mapping_type(mapping_type: &FieldMappingType) -> &'static str {
+    match mapping_type {
         // Quickwit text fields behave like ES keyword fields: they support exact
         // match, prefix, and regexp queries. Reporting them as "keyword" enables
         // downstream connectors (e.g. Trino ES connector) to push down filters and
         // LIKE predicates, which they only do for keyword-typed fields.
-        FieldMappingType::Text(..) => Some(FieldMapping::Leaf { typ: "keyword" }),
-        FieldMappingType::I64(..) => Some(FieldMapping::Leaf { typ: "long" }),
-        FieldMappingType::U64(..) => Some(FieldMapping::Leaf { typ: "long" }),
-        FieldMappingType::F64(..) => Some(FieldMapping::Leaf { typ: "double" }),
-        FieldMappingType::Bool(..) => Some(FieldMapping::Leaf { typ: "boolean" }),
-        FieldMappingType::DateTime(..) => Some(FieldMapping::Leaf { typ: "date" }),
-        FieldMappingType::IpAddr(..) => Some(FieldMapping::Leaf { typ: "ip" }),
-        FieldMappingType::Bytes(..) => Some(FieldMapping::Leaf { typ: "binary" }),
-        FieldMappingType::Json(..) => Some(FieldMapping::Leaf { typ: "object" }),
-        FieldMappingType::Object(options) => {
-            let properties = build_properties(&options.field_mappings);
-            Some(FieldMapping::Object {
-                typ: "object",
-                properties,
-            })
-        }
-        FieldMappingType::Concatenate(_) => Some(FieldMapping::Leaf { typ: "keyword" }),
+        FieldMappingType::Text(..) => "keyword",
+        FieldMappingType::I64(..) => "long",
+        FieldMappingType::U64(..) => "long",
+        FieldMappingType::F64(..) => "double",
+        FieldMappingType::Bool(..) => "boolean",
+        FieldMappingType::DateTime(..) => "date",
+        FieldMappingType::IpAddr(..) => "ip",
+        FieldMappingType::Bytes(..) => "binary",
+        FieldMappingType::Json(..) => "object",
+        FieldMappingType::Object(_) => "object",
+        FieldMappingType::Concatenate(_) => "keyword",
     }
 }
 
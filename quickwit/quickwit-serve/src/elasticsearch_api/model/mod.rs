@@ -15,11 +15,14 @@
 mod bulk_body;
 mod bulk_query_params;
 mod cat_indices;
+mod clear_cache;
 mod error;
 mod field_capability;
 mod index_mapping_query_params;
 mod mappings;
 mod multi_search;
+mod put_mapping;
+mod reindex;
 mod scroll;
 mod search_body;
 mod search_query_params;
@@ -32,6 +35,7 @@ pub use cat_indices::{
     CatIndexQueryParams, ElasticsearchCatIndexResponse, ElasticsearchResolveIndexEntryResponse,
     ElasticsearchResolveIndexResponse,
 };
+pub use clear_cache::ElasticsearchClearCacheResponse;
 pub use error::{ElasticException, ElasticsearchError};
 pub use field_capability::{
     FieldCapabilityQueryParams, FieldCapabilityRequestBody, FieldCapabilityResponse,
@@ -42,7 +46,12 @@ pub(crate) use mappings::ElasticsearchMappingsResponse;
 pub use multi_search::{
     MultiSearchHeader, MultiSearchQueryParams, MultiSearchResponse, MultiSearchSingleResponse,
 };
+pub(crate) use put_mapping::PutMappingRequestBody;
 use quickwit_proto::search::{SortDatetimeFormat, SortOrder};
+pub use reindex::{
+    ReindexFailure, ReindexFailureCause, ReindexQueryParams, ReindexRequestBody, ReindexResponse,
+    ReindexStatus, ReindexTaskCreatedResponse, TaskInfo, TaskResponse,
+};
 pub use scroll::ScrollQueryParams;
 pub use search_body::SearchBody;
 pub use search_query_params::{DeleteQueryParams, SearchQueryParams, SearchQueryParamsCount};
@@ -0,0 +1,228 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::BTreeMap;
+
+use quickwit_doc_mapper::{FieldMappingEntry, FieldMappingType};
+use serde::Deserialize;
+use serde_json::json;
+
+use super::mappings::es_type_from_field_mapping_type;
+
+/// Body of a `PUT /{index}/_mapping` request. Only the `properties` are taken into account.
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct PutMappingRequestBody {
+    #[serde(default)]
+    properties: BTreeMap<String, PutMappingProperty>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PutMappingProperty {
+    /// Defaults to `object`, like in Elasticsearch.
+    #[serde(rename = "type")]
+    typ: Option<String>,
+    #[serde(default)]
+    properties: BTreeMap<String, PutMappingProperty>,
+}
+
+impl PutMappingRequestBody {
+    /// Adds the fields of the request missing from `field_mappings`, and returns the number of
+    /// added fields. Fields already mapped with the same Elasticsearch type are left unchanged,
+    /// while changing the type of a field is rejected: the documents already indexed are never
+    /// reindexed.
+    pub fn add_new_fields(
+        &self,
+        field_mappings: &mut Vec<FieldMappingEntry>,
+    ) -> Result<usize, String> {
+        add_new_fields(field_mappings, &self.properties, "")
+    }
+}
+
+fn add_new_fields(
+    field_mappings: &mut Vec<FieldMappingEntry>,
+    properties: &BTreeMap<String, PutMappingProperty>,
+    path_prefix: &str,
+) -> Result<usize, String> {
+    let mut num_added_fields = 0;
+
+    for (field_name, property) in properties {
+        let field_path = format!("{path_prefix}{field_name}");
+        let new_field_mapping = field_mapping_from_property(field_name, property, &field_path)?;
+
+        let Some(field_mapping) = field_mappings
+            .iter_mut()
+            .find(|field_mapping| field_mapping.name == *field_name)
+        else {
+            field_mappings.push(new_field_mapping);
+            num_added_fields += 1;
+            continue;
+        };
+        if let (FieldMappingType::Object(options), FieldMappingType::Object(_)) = (
+            &mut field_mapping.mapping_type,
+            &new_field_mapping.mapping_type,
+        ) {
+            let object_path_prefix = format!("{field_path}.");
+            num_added_fields += add_new_fields(
+                &mut options.field_mappings,
+                &property.properties,
+                &object_path_prefix,
+            )?;
+            continue;
+        }
+        let current_es_type = es_type_from_field_mapping_type(&field_mapping.mapping_type);
+        let new_es_type = es_type_from_field_mapping_type(&new_field_mapping.mapping_type);
+
+        if current_es_type != new_es_type {
+            return Err(format!(
+                "mapper [{field_path}] cannot be changed from type [{current_es_type}] to \
+                 [{new_es_type}]"
+            ));
+        }
+    }
+    Ok(num_added_fields)
+}
+
+/// Converts an Elasticsearch field mapping into a Quickwit one. Aggregatable Elasticsearch types
+/// are mapped to fast fields.
+fn field_mapping_from_property(
+    field_name: &str,
+    property: &PutMappingProperty,
+    field_path: &str,
+) -> Result<FieldMappingEntry, String> {
+    let es_type = property.typ.as_deref().unwrap_or("object");
+    let mut field_mapping_json = match es_type {
+        "keyword" | "constant_keyword" | "wildcard" => {
+            json!({"type": "text", "tokenizer": "raw", "fast": true})
+        }
+        "text" | "match_only_text" => json!({"type": "text"}),
+        "long" | "integer" | "short" | "byte" => json!({"type": "i64", "fast": true}),
+        "unsigned_long" => json!({"type": "u64", "fast": true}),
+        "double" | "float" | "half_float" | "scaled_float" => json!({"type": "f64", "fast": true}),
+        "boolean" => json!({"type": "bool", "fast": true}),
+        "date" | "date_nanos" => json!({"type": "datetime", "fast": true}),
+        "ip" => json!({"type": "ip", "fast": true}),
+        "binary" => json!({"type": "bytes"}),
+        "flattened" => json!({"type": "json"}),
+        "object" if property.properties.is_empty() => json!({"type": "json"}),
+        "object" => {
+            let mut field_mappings = Vec::with_capacity(property.properties.len());
+
+            for (sub_field_name, sub_property) in &property.properties {
+                let sub_field_path = format!("{field_path}.{sub_field_name}");
+                let sub_field_mapping =
+                    field_mapping_from_property(sub_field_name, sub_property, &sub_field_path)?;
+                field_mappings.push(sub_field_mapping);
+            }
+            json!({"type": "object", "field_mappings": field_mappings})
+        }
+        _ => {
+            return Err(format!(
+                "type [{es_type}] of field [{field_path}] is not supported"
+            ));
+        }
+    };
+    field_mapping_json["name"] = json!(field_name);
+
+    serde_json::from_value(field_mapping_json)
+        .map_err(|error| format!("invalid mapping for field [{field_path}]: {error}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_put_mapping_add_new_fields() {
+        let mut field_mappings: Vec<FieldMappingEntry> = serde_json::from_value(json!([
+            { "name": "count", "type": "i64" },
+            {
+                "name": "metadata",
+                "type": "object",
+                "field_mappings": [
+                    { "name": "source", "type": "text" }
+                ]
+            }
+        ]))
+        .unwrap();
+        let put_mapping_request: PutMappingRequestBody = serde_json::from_value(json!({
+            "properties": {
+                "count": { "type": "integer" },
+                "host": { "type": "keyword", "ignore_above": 256 },
+                "metadata": {
+                    "properties": {
+                        "source": { "type": "keyword" },
+                        "region": { "type": "keyword" }
+                    }
+                }
+            }
+        }))
+        .unwrap();
+        let num_added_fields = put_mapping_request
+            .add_new_fields(&mut field_mappings)
+            .unwrap();
+        assert_eq!(num_added_fields, 2);
+
+        let field_mappings_json = serde_json::to_value(&field_mappings).unwrap();
+        assert_eq!(field_mappings_json[0]["type"], "i64");
+        assert_eq!(field_mappings_json[1]["name"], "metadata");
+        assert_eq!(
+            field_mappings_json[1]["field_mappings"][1]["name"],
+            "region"
+        );
+        assert_eq!(
+            field_mappings_json[1]["field_mappings"][1]["tokenizer"],
+            "raw"
+        );
+        assert_eq!(field_mappings_json[2]["name"], "host");
+        assert_eq!(field_mappings_json[2]["type"], "text");
+        assert_eq!(field_mappings_json[2]["fast"], true);
+        let num_added_fields = put_mapping_request
+            .add_new_fields(&mut field_mappings)
+            .unwrap();
+        assert_eq!(num_added_fields, 0);
+    }
+
+    #[test]
+    fn test_put_mapping_rejects_type_change() {
+        let mut field_mappings: Vec<FieldMappingEntry> =
+            serde_json::from_value(json!([{ "name": "count", "type": "i64" }])).unwrap();
+        let put_mapping_request: PutMappingRequestBody = serde_json::from_value(json!({
+            "properties": {
+                "count": { "type": "keyword" }
+            }
+        }))
+        .unwrap();
+        let error = put_mapping_request
+            .add_new_fields(&mut field_mappings)
+            .unwrap_err();
+        assert_eq!(
+            error,
+            "mapper [count] cannot be changed from type [long] to [keyword]"
+        );
+
+        let put_mapping_request: PutMappingRequestBody = serde_json::from_value(json!({
+            "properties": {
+                "location": { "type": "geo_point" }
+            }
+        }))
+        .unwrap();
+        let error = put_mapping_request
+            .add_new_fields(&mut field_mappings)
+            .unwrap_err();
+        assert_eq!(
+            error,
+            "type [geo_point] of field [location] is not supported"
+        );
+    }
+}
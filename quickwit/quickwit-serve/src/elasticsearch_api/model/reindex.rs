@@ -0,0 +1,126 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use elasticsearch_dsl::search::ErrorCause;
+use quickwit_query::ElasticQueryDsl;
+use serde::{Deserialize, Serialize};
+
+use super::ElasticException;
+
+#[derive(Debug, Deserialize)]
+pub struct ReindexQueryParams {
+    /// When `false`, the reindex runs in the background and the response holds the ID of a task
+    /// that can be polled with `GET _tasks/<task_id>`.
+    #[serde(default = "default_wait_for_completion")]
+    pub wait_for_completion: bool,
+}
+
+fn default_wait_for_completion() -> bool {
+    true
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ReindexRequestBody {
+    pub source: ReindexSource,
+    pub dest: ReindexDest,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ReindexSource {
+    pub index: String,
+    /// Only the documents matching this query are reindexed.
+    #[serde(default)]
+    pub query: Option<ElasticQueryDsl>,
+    /// Number of documents read from the source index per batch.
+    #[serde(default = "default_reindex_batch_size")]
+    pub size: u64,
+}
+
+fn default_reindex_batch_size() -> u64 {
+    1_000
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ReindexDest {
+    pub index: String,
+}
+
+/// Progress of a reindex.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ReindexStatus {
+    /// Number of source documents matching the query.
+    pub total: u64,
+    /// Number of documents written into the destination index.
+    pub created: u64,
+    /// Number of batches processed so far.
+    pub batches: u64,
+    /// Number of documents that could not be reindexed because they do not fit the mapping of
+    /// the destination index.
+    pub failed: u64,
+}
+
+/// A document that could not be reindexed.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReindexFailure {
+    pub index: String,
+    /// Address of the document in the source index.
+    pub id: String,
+    pub cause: ReindexFailureCause,
+    pub status: u16,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReindexFailureCause {
+    #[serde(rename = "type")]
+    pub exception: ElasticException,
+    pub reason: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReindexResponse {
+    pub took: u64,
+    pub timed_out: bool,
+    #[serde(flatten)]
+    pub status: ReindexStatus,
+    /// Documents that could not be reindexed. The list is truncated on large reindexes, while
+    /// `failed` counts them all.
+    pub failures: Vec<ReindexFailure>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReindexTaskCreatedResponse {
+    pub task: String,
+}
+
+/// Response of `GET _tasks/<task_id>`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TaskResponse {
+    pub completed: bool,
+    pub task: TaskInfo,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response: Option<ReindexResponse>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<ErrorCause>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TaskInfo {
+    pub node: String,
+    pub id: u64,
+    pub action: String,
+    pub status: ReindexStatus,
+}
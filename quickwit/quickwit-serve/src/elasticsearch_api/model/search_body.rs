@@ -81,11 +81,19 @@ pub struct SearchBody {
     pub stored_fields: Option<BTreeSet<String>>,
     #[serde(default)]
     pub search_after: Vec<serde_json::Value>,
+    /// Hits scoring below this value are excluded from the results.
+    #[serde(default)]
+    pub min_score: Option<f32>,
+    /// Computes the scores of the hits even when they are sorted by a field.
+    #[serde(default)]
+    pub track_scores: Option<bool>,
+    /// Only `false`, which omits the documents from the hits, is supported. Other values are
+    /// ignored, for compatibility with OpenSearch Dashboards.
+    #[serde(default)]
+    pub _source: Option<serde_json::Value>,
 
     // Ignored values, only here for compatibility with OpenSearch Dashboards.
     #[serde(default)]
-    pub _source: serde::de::IgnoredAny,
-    #[serde(default)]
     pub docvalue_fields: serde::de::IgnoredAny,
     #[serde(default)]
     pub script_fields: serde::de::IgnoredAny,
@@ -95,6 +103,13 @@ pub struct SearchBody {
     pub version: serde::de::IgnoredAny,
 }
 
+impl SearchBody {
+    /// Returns true if the documents should be omitted from the hits (`"_source": false`).
+    pub fn excludes_source(&self) -> bool {
+        matches!(self._source, Some(serde_json::Value::Bool(false)))
+    }
+}
+
 struct FieldSortVecVisitor;
 
 #[derive(Deserialize)]
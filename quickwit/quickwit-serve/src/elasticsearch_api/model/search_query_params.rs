@@ -293,6 +293,11 @@ impl SearchQueryParams {
         // By default, elastic search allows partial results.
         self.allow_partial_search_results.unwrap_or(true)
     }
+
+    /// Returns true if the documents should be omitted from the hits (`_source=false`).
+    pub fn excludes_source(&self) -> bool {
+        matches!(self._source.as_deref(), Some([source]) if source == "false")
+    }
 }
 
 #[doc = "Whether to expand wildcard expression to concrete indices that are open, closed or both."]
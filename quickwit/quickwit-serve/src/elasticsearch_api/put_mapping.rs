@@ -0,0 +1,253 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use quickwit_config::build_doc_mapper;
+use quickwit_index_management::{IndexService, IndexServiceError};
+use quickwit_proto::metastore::{IndexMetadataRequest, MetastoreError, MetastoreService};
+use quickwit_proto::search::SearchError;
+use quickwit_proto::types::DocMappingUid;
+use serde::Serialize;
+use tracing::info;
+use warp::hyper::StatusCode;
+use warp::{Filter, Rejection};
+
+use super::filter::elastic_put_mapping_filter;
+use super::make_elastic_api_response;
+use super::model::{ElasticException, ElasticsearchError, PutMappingRequestBody};
+use crate::format::extract_format_from_qs;
+use crate::request_scope::ensure_indexes_in_scope;
+use crate::rest::recover_fn;
+use crate::with_arg;
+
+#[derive(Debug, Serialize)]
+struct ElasticsearchPutMappingResponse {
+    acknowledged: bool,
+}
+
+/// PUT or POST _elastic/{index}/_mapping or _elastic/{index}/_mappings
+pub fn es_compat_put_mapping_handler(
+    index_service: IndexService,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    elastic_put_mapping_filter()
+        .and(with_arg(index_service))
+        .then(es_compat_put_mapping)
+        .and(extract_format_from_qs())
+        .map(make_elastic_api_response)
+        .recover(recover_fn)
+        .boxed()
+}
+
+/// Adds the new fields of the request to the doc mapping of the index. The new fields only apply
+/// to the documents ingested afterwards: the documents already indexed are not reindexed, which
+/// is why changing the type of an existing field is rejected.
+///
+/// The update only applies if the doc mapping was not updated concurrently since it was read,
+/// otherwise the request fails with a `409 Conflict` and can be retried.
+async fn es_compat_put_mapping(
+    index_id: String,
+    put_mapping_request: PutMappingRequestBody,
+    index_service: IndexService,
+) -> Result<ElasticsearchPutMappingResponse, ElasticsearchError> {
+    ensure_indexes_in_scope([index_id.as_str()]).map_err(SearchError::from)?;
+
+    let index_metadata_request = IndexMetadataRequest::for_index_id(index_id.clone());
+    let index_metadata = index_service
+        .metastore()
+        .index_metadata(index_metadata_request)
+        .await
+        .and_then(|response| response.deserialize_index_metadata())
+        .map_err(IndexServiceError::Metastore)?;
+    let index_uid = index_metadata.index_uid.clone();
+    let mut index_config = index_metadata.into_index_config();
+    let expected_doc_mapping_uid = index_config.doc_mapping.doc_mapping_uid;
+
+    let num_added_fields = put_mapping_request
+        .add_new_fields(&mut index_config.doc_mapping.field_mappings)
+        .map_err(illegal_argument_error)?;
+    if num_added_fields == 0 {
+        return Ok(ElasticsearchPutMappingResponse { acknowledged: true });
+    }
+    build_doc_mapper(&index_config.doc_mapping, &index_config.search_settings)
+        .map_err(|error| illegal_argument_error(format!("{error:#}")))?;
+
+    info!(index_id = %index_id, num_added_fields, "put-mapping");
+    index_config.doc_mapping.doc_mapping_uid = DocMappingUid::random();
+    index_service
+        .update_index_if_doc_mapping_unchanged(index_uid, index_config, expected_doc_mapping_uid)
+        .await
+        .map_err(update_error_to_elasticsearch_error)?;
+    Ok(ElasticsearchPutMappingResponse { acknowledged: true })
+}
+
+fn illegal_argument_error(error_message: String) -> ElasticsearchError {
+    ElasticsearchError::new(
+        StatusCode::BAD_REQUEST,
+        error_message,
+        Some(ElasticException::IllegalArgument),
+    )
+}
+
+fn update_error_to_elasticsearch_error(error: IndexServiceError) -> ElasticsearchError {
+    if let IndexServiceError::Metastore(MetastoreError::FailedPrecondition { .. }) = &error {
+        let reason = format!("the mapping was updated concurrently, retry the request: {error}");
+        return ElasticsearchError::new(StatusCode::CONFLICT, reason, None);
+    }
+    error.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_config::IndexConfig;
+    use quickwit_doc_mapper::FieldMappingType;
+    use quickwit_metastore::{CreateIndexRequestExt, metastore_for_test};
+    use quickwit_proto::metastore::{CreateIndexRequest, MetastoreServiceClient};
+    use quickwit_storage::StorageResolver;
+    use serde_json::{Value as JsonValue, json};
+
+    use super::*;
+    use crate::request_scope::with_index_scope_for_test;
+
+    async fn create_index(metastore: &MetastoreServiceClient, index_id: &str) {
+        let index_uri = format!("ram:///indexes/{index_id}");
+        let index_config = IndexConfig::for_test(index_id, &index_uri);
+        let create_index_request =
+            CreateIndexRequest::try_from_index_config(&index_config).unwrap();
+        metastore.create_index(create_index_request).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_put_mapping_adds_new_field() {
+        let metastore = metastore_for_test();
+        create_index(&metastore, "test-put-mapping").await;
+        let index_service = IndexService::new(metastore.clone(), StorageResolver::unconfigured());
+        let put_mapping_handler = es_compat_put_mapping_handler(index_service);
+
+        let put_mapping_body = json!({
+            "properties": {
+                "body": { "type": "keyword" },
+                "host": { "type": "keyword" }
+            }
+        });
+        let resp = warp::test::request()
+            .path("/_elastic/test-put-mapping/_mapping")
+            .method("PUT")
+            .json(&put_mapping_body)
+            .reply(&put_mapping_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(resp_json, json!({ "acknowledged": true }));
+
+        let index_metadata_request = IndexMetadataRequest::for_index_id("test-put-mapping".into());
+        let doc_mapping = metastore
+            .index_metadata(index_metadata_request)
+            .await
+            .unwrap()
+            .deserialize_index_metadata()
+            .unwrap()
+            .into_index_config()
+            .doc_mapping;
+        let host_field_mapping = doc_mapping
+            .field_mappings
+            .iter()
+            .find(|field_mapping| field_mapping.name == "host")
+            .unwrap();
+        assert!(matches!(
+            host_field_mapping.mapping_type,
+            FieldMappingType::Text(..)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_put_mapping_out_of_scope_index() {
+        let metastore = metastore_for_test();
+        create_index(&metastore, "test-put-mapping").await;
+        let index_service = IndexService::new(metastore, StorageResolver::unconfigured());
+        let put_mapping_handler = es_compat_put_mapping_handler(index_service);
+
+        let put_mapping_body = json!({
+            "properties": {
+                "host": { "type": "keyword" }
+            }
+        });
+        let resp = with_index_scope_for_test(
+            &["other-*"],
+            warp::test::request()
+                .path("/_elastic/test-put-mapping/_mapping")
+                .method("PUT")
+                .json(&put_mapping_body)
+                .reply(&put_mapping_handler),
+        )
+        .await;
+        assert_eq!(resp.status(), 403);
+    }
+
+    #[tokio::test]
+    async fn test_put_mapping_concurrent_update_conflicts() {
+        let metastore = metastore_for_test();
+        create_index(&metastore, "test-put-mapping").await;
+        let index_service = IndexService::new(metastore.clone(), StorageResolver::unconfigured());
+
+        let index_metadata_request = IndexMetadataRequest::for_index_id("test-put-mapping".into());
+        let index_metadata = metastore
+            .index_metadata(index_metadata_request)
+            .await
+            .unwrap()
+            .deserialize_index_metadata()
+            .unwrap();
+        let index_uid = index_metadata.index_uid.clone();
+        let stale_doc_mapping_uid = index_metadata.index_config.doc_mapping.doc_mapping_uid;
+
+        let mut index_config = index_metadata.into_index_config();
+        index_config.doc_mapping.doc_mapping_uid = DocMappingUid::random();
+        index_service
+            .update_index(index_uid.clone(), index_config.clone())
+            .await
+            .unwrap();
+
+        index_config.doc_mapping.doc_mapping_uid = DocMappingUid::random();
+        let error = index_service
+            .update_index_if_doc_mapping_unchanged(index_uid, index_config, stale_doc_mapping_uid)
+            .await
+            .unwrap_err();
+        let elasticsearch_error = update_error_to_elasticsearch_error(error);
+        assert_eq!(elasticsearch_error.status, StatusCode::CONFLICT);
+    }
+
+    #[tokio::test]
+    async fn test_put_mapping_rejects_type_change() {
+        let metastore = metastore_for_test();
+        create_index(&metastore, "test-put-mapping").await;
+        let index_service = IndexService::new(metastore, StorageResolver::unconfigured());
+        let put_mapping_handler = es_compat_put_mapping_handler(index_service);
+
+        let put_mapping_body = json!({
+            "properties": {
+                "response_time": { "type": "keyword" }
+            }
+        });
+        let resp = warp::test::request()
+            .path("/_elastic/test-put-mapping/_mapping")
+            .method("PUT")
+            .json(&put_mapping_body)
+            .reply(&put_mapping_handler)
+            .await;
+        assert_eq!(resp.status(), 400);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(
+            resp_json["error"]["reason"],
+            "mapper [response_time] cannot be changed from type [double] to [keyword]"
+        );
+    }
+}
@@ -0,0 +1,362 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+mod tasks;
+
+use std::sync::Arc;
+use std::time::Instant;
+
+use quickwit_common::spawn_named_task;
+use quickwit_config::{INGEST_V2_SOURCE_ID, build_doc_mapper};
+use quickwit_doc_mapper::DocMapper;
+use quickwit_ingest::{
+    CommitType, DocBatchBuilder, DocBatchV2Builder, IngestRequest, IngestService,
+    IngestServiceClient, IngestServiceError,
+};
+use quickwit_proto::ingest::CommitTypeV2;
+use quickwit_proto::ingest::router::{
+    IngestRequestV2, IngestRouterService, IngestRouterServiceClient, IngestSubrequest,
+};
+use quickwit_proto::search::{CountHits, ScrollRequest, SearchRequest, SearchResponse};
+use quickwit_proto::types::{DocUidGenerator, IndexId};
+use quickwit_query::query_ast::QueryAst;
+use quickwit_search::{GlobalDocAddress, SearchError, SearchService};
+use serde::Serialize;
+use tracing::info;
+use warp::hyper::StatusCode;
+use warp::{Filter, Rejection};
+
+use self::tasks::ReindexTaskHandle;
+pub(crate) use self::tasks::{ReindexTasks, es_compat_get_task_handler};
+use super::filter::elastic_reindex_filter;
+use super::make_elastic_api_response;
+use super::model::{
+    ElasticException, ElasticsearchError, ReindexFailure, ReindexFailureCause, ReindexQueryParams,
+    ReindexRequestBody, ReindexResponse, ReindexStatus, ReindexTaskCreatedResponse,
+};
+use crate::format::extract_format_from_qs;
+use crate::index_api::{IndexMetadataCache, ensure_indexes_writable};
+use crate::ingest_api::IndexAllowList;
+use crate::rest::recover_fn;
+use crate::with_arg;
+
+/// Scroll TTL of the source snapshot. It is renewed on every batch.
+const REINDEX_SCROLL_TTL_SECS: u32 = 5 * 60;
+
+/// Maximum number of failures listed in a reindex response.
+const MAX_REPORTED_FAILURES: usize = 1_000;
+
+/// Dependencies of a reindex.
+#[derive(Clone)]
+pub(crate) struct ReindexContext {
+    pub search_service: Arc<dyn SearchService>,
+    pub ingest_service: IngestServiceClient,
+    pub ingest_router: IngestRouterServiceClient,
+    pub index_metadata_cache: IndexMetadataCache,
+    pub index_allow_list_opt: Option<IndexAllowList>,
+    pub enable_ingest_v1: bool,
+    pub enable_ingest_v2: bool,
+    pub tasks: ReindexTasks,
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+enum ReindexReply {
+    Completed(ReindexResponse),
+    Started(ReindexTaskCreatedResponse),
+}
+
+/// POST `_elastic/_reindex`
+pub(crate) fn es_compat_reindex_handler(
+    reindex_context: ReindexContext,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    elastic_reindex_filter()
+        .and(with_arg(reindex_context))
+        .then(es_compat_reindex)
+        .and(extract_format_from_qs())
+        .map(make_elastic_api_response)
+        .recover(recover_fn)
+        .boxed()
+}
+
+async fn es_compat_reindex(
+    reindex_query_params: ReindexQueryParams,
+    reindex_request: ReindexRequestBody,
+    reindex_context: ReindexContext,
+) -> Result<ReindexReply, ElasticsearchError> {
+    let reindex_job = prepare_reindex(reindex_request, &reindex_context).await?;
+
+    if reindex_query_params.wait_for_completion {
+        let reindex_response = run_reindex(reindex_job, &reindex_context, None).await?;
+        return Ok(ReindexReply::Completed(reindex_response));
+    }
+    let task_handle = reindex_context.tasks.create_task();
+    let task_id = task_handle.task_id();
+    info!(task_id=%task_id, "reindex-started");
+
+    spawn_named_task(
+        async move {
+            let reindex_result =
+                run_reindex(reindex_job, &reindex_context, Some(&task_handle)).await;
+            task_handle.complete(reindex_result);
+        },
+        "reindex",
+    );
+    let task_created_response = ReindexTaskCreatedResponse { task: task_id };
+    Ok(ReindexReply::Started(task_created_response))
+}
+
+/// A validated reindex, whose source snapshot is opened.
+struct ReindexJob {
+    start: Instant,
+    dest_index_id: IndexId,
+    dest_doc_mapper: Arc<DocMapper>,
+    first_search_response: SearchResponse,
+}
+
+/// Validates the request and opens a scroll on the source index. The scroll pins the splits of
+/// the source index, so documents ingested during the reindex are not picked up.
+async fn prepare_reindex(
+    reindex_request: ReindexRequestBody,
+    reindex_context: &ReindexContext,
+) -> Result<ReindexJob, ElasticsearchError> {
+    let start = Instant::now();
+    let source_index_id = reindex_request.source.index;
+    let dest_index_id = reindex_request.dest.index;
+
+    if source_index_id == dest_index_id {
+        return Err(ElasticsearchError::new(
+            StatusCode::BAD_REQUEST,
+            "reindex cannot write into an index it's reading from".to_string(),
+            Some(ElasticException::ActionRequestValidation),
+        ));
+    }
+    if let Some(index_allow_list) = &reindex_context.index_allow_list_opt {
+        index_allow_list.check(&dest_index_id)?;
+    }
+    let dest_index_metadata = ensure_indexes_writable(
+        &reindex_context.index_metadata_cache,
+        [dest_index_id.clone()],
+    )
+    .await?
+    .into_iter()
+    .next()
+    .ok_or_else(|| {
+        ElasticsearchError::new(
+            StatusCode::NOT_FOUND,
+            format!("no such index [{dest_index_id}]"),
+            Some(ElasticException::IndexNotFound),
+        )
+    })?;
+    let dest_index_config = &dest_index_metadata.index_config;
+    let dest_doc_mapper = build_doc_mapper(
+        &dest_index_config.doc_mapping,
+        &dest_index_config.search_settings,
+    )
+    .map_err(|error| {
+        ElasticsearchError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("failed to build doc mapper of index `{dest_index_id}`: {error}"),
+            Some(ElasticException::Internal),
+        )
+    })?;
+    let query_ast: QueryAst = if let Some(query_dsl) = reindex_request.source.query {
+        query_dsl
+            .try_into()
+            .map_err(|error: anyhow::Error| SearchError::InvalidQuery(error.to_string()))?
+    } else {
+        QueryAst::MatchAll
+    };
+    let search_request = SearchRequest {
+        index_id_patterns: vec![source_index_id.clone()],
+        query_ast: serde_json::to_string(&query_ast).expect("Failed to serialize QueryAst"),
+        max_hits: reindex_request.source.size,
+        scroll_ttl_secs: Some(REINDEX_SCROLL_TTL_SECS),
+        count_hits: CountHits::CountAll.into(),
+        ..Default::default()
+    };
+    let first_search_response = reindex_context
+        .search_service
+        .root_search(search_request)
+        .await?;
+    ensure_no_failed_splits(&first_search_response)?;
+
+    info!(source_index_id=%source_index_id, dest_index_id=%dest_index_id, num_docs=first_search_response.num_hits, "reindex");
+    let reindex_job = ReindexJob {
+        start,
+        dest_index_id,
+        dest_doc_mapper,
+        first_search_response,
+    };
+    Ok(reindex_job)
+}
+
+/// Streams the source documents into the destination index, batch by batch. Documents that do
+/// not fit the mapping of the destination index are reported instead of aborting the reindex.
+async fn run_reindex(
+    reindex_job: ReindexJob,
+    reindex_context: &ReindexContext,
+    task_handle_opt: Option<&ReindexTaskHandle>,
+) -> Result<ReindexResponse, ElasticsearchError> {
+    let ReindexJob {
+        start,
+        dest_index_id,
+        dest_doc_mapper,
+        first_search_response,
+    } = reindex_job;
+
+    let mut status = ReindexStatus {
+        total: first_search_response.num_hits,
+        ..Default::default()
+    };
+    let mut failures = Vec::new();
+    let mut search_response = first_search_response;
+
+    while !search_response.hits.is_empty() {
+        let mut docs = Vec::with_capacity(search_response.hits.len());
+
+        for hit in search_response.hits {
+            let Err(parsing_error) = dest_doc_mapper.doc_from_json_str(&hit.json) else {
+                docs.push(hit.json);
+                continue;
+            };
+            status.failed += 1;
+
+            if failures.len() < MAX_REPORTED_FAILURES {
+                let doc_address = hit
+                    .partial_hit
+                    .as_ref()
+                    .map(|partial_hit| GlobalDocAddress::from_partial_hit(partial_hit).to_string())
+                    .unwrap_or_default();
+                let failure = ReindexFailure {
+                    index: dest_index_id.clone(),
+                    id: doc_address,
+                    cause: ReindexFailureCause {
+                        exception: ElasticException::DocumentParsing,
+                        reason: parsing_error.to_string(),
+                    },
+                    status: StatusCode::BAD_REQUEST.as_u16(),
+                };
+                failures.push(failure);
+            }
+        }
+        status.created += ingest_docs(reindex_context, &dest_index_id, docs).await?;
+        status.batches += 1;
+
+        if let Some(task_handle) = task_handle_opt {
+            task_handle.update_status(&status);
+        }
+        let Some(scroll_id) = search_response.scroll_id else {
+            break;
+        };
+        let scroll_request = ScrollRequest {
+            scroll_id,
+            scroll_ttl_secs: Some(REINDEX_SCROLL_TTL_SECS),
+        };
+        search_response = reindex_context
+            .search_service
+            .scroll(scroll_request)
+            .await?;
+        ensure_no_failed_splits(&search_response)?;
+    }
+    let reindex_response = ReindexResponse {
+        took: start.elapsed().as_millis() as u64,
+        timed_out: false,
+        status,
+        failures,
+    };
+    Ok(reindex_response)
+}
+
+/// A reindex must not silently skip documents, so partial search results are an error.
+fn ensure_no_failed_splits(search_response: &SearchResponse) -> Result<(), ElasticsearchError> {
+    if search_response.failed_splits.is_empty() {
+        return Ok(());
+    }
+    Err(ElasticsearchError::new(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        format!(
+            "failed to read {} split(s) of the source index",
+            search_response.failed_splits.len()
+        ),
+        Some(ElasticException::Internal),
+    ))
+}
+
+/// Ingests a batch of documents into the destination index and returns the number of documents
+/// accepted.
+async fn ingest_docs(
+    reindex_context: &ReindexContext,
+    dest_index_id: &IndexId,
+    docs: Vec<String>,
+) -> Result<u64, ElasticsearchError> {
+    if docs.is_empty() {
+        return Ok(0);
+    }
+    if reindex_context.enable_ingest_v2 {
+        let mut doc_batch_builder = DocBatchV2Builder::default();
+        let mut doc_uid_generator = DocUidGenerator::default();
+
+        for doc in &docs {
+            doc_batch_builder.add_doc(doc_uid_generator.next_doc_uid(), doc.as_bytes());
+        }
+        let subrequest = IngestSubrequest {
+            subrequest_id: 0,
+            index_id: dest_index_id.clone(),
+            source_id: INGEST_V2_SOURCE_ID.to_string(),
+            doc_batch: doc_batch_builder.build(),
+        };
+        let ingest_request = IngestRequestV2 {
+            commit_type: CommitTypeV2::Auto as i32,
+            subrequests: vec![subrequest],
+        };
+        let mut ingest_response = reindex_context.ingest_router.ingest(ingest_request).await?;
+
+        if let Some(ingest_failure) = ingest_response.failures.pop() {
+            let ingest_error = IngestServiceError::from(ingest_failure);
+            return Err(ingest_error.into());
+        }
+        let num_ingested_docs = ingest_response
+            .successes
+            .iter()
+            .map(|success| success.num_ingested_docs as u64)
+            .sum();
+        return Ok(num_ingested_docs);
+    }
+    if !reindex_context.enable_ingest_v1 {
+        return Err(ElasticsearchError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "ingest v1 is disabled: environment variable `QW_DISABLE_INGEST_V1` is set".to_string(),
+            None,
+        ));
+    }
+    let num_docs = docs.len() as u64;
+    let mut doc_batch_builder = DocBatchBuilder::new(dest_index_id.clone());
+
+    for doc in &docs {
+        doc_batch_builder.ingest_doc(doc.as_bytes());
+    }
+    let ingest_request = IngestRequest {
+        doc_batches: vec![doc_batch_builder.build()],
+        commit: CommitType::Auto.into(),
+    };
+    reindex_context
+        .ingest_service
+        .ingest(ingest_request)
+        .await?;
+    Ok(num_docs)
+}
+
+#[cfg(test)]
+mod tests;
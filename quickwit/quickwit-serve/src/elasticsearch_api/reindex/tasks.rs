@@ -0,0 +1,172 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use elasticsearch_dsl::search::ErrorCause;
+use quickwit_proto::types::NodeId;
+use warp::hyper::StatusCode;
+use warp::{Filter, Rejection};
+
+use crate::elasticsearch_api::filter::elastic_get_task_filter;
+use crate::elasticsearch_api::make_elastic_api_response;
+use crate::elasticsearch_api::model::{
+    ElasticsearchError, ReindexResponse, ReindexStatus, TaskInfo, TaskResponse,
+};
+use crate::format::extract_format_from_qs;
+use crate::rest::recover_fn;
+use crate::with_arg;
+
+/// Maximum number of completed reindex tasks kept around for polling.
+const MAX_COMPLETED_TASKS: usize = 100;
+
+const REINDEX_ACTION: &str = "indices:data/write/reindex";
+
+/// In-memory registry of the reindexes running in the background on this node.
+#[derive(Clone)]
+pub(crate) struct ReindexTasks {
+    node_id: NodeId,
+    inner: Arc<Mutex<ReindexTasksInner>>,
+}
+
+#[derive(Default)]
+struct ReindexTasksInner {
+    next_task_ord: u64,
+    tasks: BTreeMap<u64, ReindexTaskState>,
+}
+
+#[derive(Default)]
+struct ReindexTaskState {
+    status: ReindexStatus,
+    outcome_opt: Option<Result<ReindexResponse, ErrorCause>>,
+}
+
+impl ReindexTasks {
+    pub fn new(node_id: NodeId) -> Self {
+        Self {
+            node_id,
+            inner: Arc::default(),
+        }
+    }
+
+    pub(super) fn create_task(&self) -> ReindexTaskHandle {
+        let mut inner = self.lock_inner();
+        let task_ord = inner.next_task_ord;
+        inner.next_task_ord += 1;
+        inner.tasks.insert(task_ord, ReindexTaskState::default());
+
+        let num_completed_tasks = inner
+            .tasks
+            .values()
+            .filter(|task_state| task_state.outcome_opt.is_some())
+            .count();
+        if num_completed_tasks > MAX_COMPLETED_TASKS {
+            let oldest_completed_task_ord_opt = inner
+                .tasks
+                .iter()
+                .find(|(_, task_state)| task_state.outcome_opt.is_some())
+                .map(|(task_ord, _)| *task_ord);
+            if let Some(oldest_completed_task_ord) = oldest_completed_task_ord_opt {
+                inner.tasks.remove(&oldest_completed_task_ord);
+            }
+        }
+        ReindexTaskHandle {
+            tasks: self.clone(),
+            task_ord,
+        }
+    }
+
+    fn get_task(&self, task_id: &str) -> Option<TaskResponse> {
+        let (node_id, task_ord_str) = task_id.rsplit_once(':')?;
+
+        if node_id != self.node_id.as_str() {
+            return None;
+        }
+        let task_ord: u64 = task_ord_str.parse().ok()?;
+        let inner = self.lock_inner();
+        let task_state = inner.tasks.get(&task_ord)?;
+        let (response, error) = match &task_state.outcome_opt {
+            Some(Ok(reindex_response)) => (Some(reindex_response.clone()), None),
+            Some(Err(error_cause)) => (None, Some(error_cause.clone())),
+            None => (None, None),
+        };
+        let task_response = TaskResponse {
+            completed: task_state.outcome_opt.is_some(),
+            task: TaskInfo {
+                node: self.node_id.to_string(),
+                id: task_ord,
+                action: REINDEX_ACTION.to_string(),
+                status: task_state.status.clone(),
+            },
+            response,
+            error,
+        };
+        Some(task_response)
+    }
+
+    fn lock_inner(&self) -> MutexGuard<'_, ReindexTasksInner> {
+        self.inner
+            .lock()
+            .expect("the reindex tasks lock should not be poisoned")
+    }
+}
+
+/// Handle used by a background reindex to report its progress.
+pub(super) struct ReindexTaskHandle {
+    tasks: ReindexTasks,
+    task_ord: u64,
+}
+
+impl ReindexTaskHandle {
+    pub(super) fn task_id(&self) -> String {
+        format!("{}:{}", self.tasks.node_id, self.task_ord)
+    }
+
+    pub(super) fn update_status(&self, status: &ReindexStatus) {
+        if let Some(task_state) = self.tasks.lock_inner().tasks.get_mut(&self.task_ord) {
+            task_state.status = status.clone();
+        }
+    }
+
+    pub(super) fn complete(self, reindex_result: Result<ReindexResponse, ElasticsearchError>) {
+        if let Some(task_state) = self.tasks.lock_inner().tasks.get_mut(&self.task_ord) {
+            if let Ok(reindex_response) = &reindex_result {
+                task_state.status = reindex_response.status.clone();
+            }
+            task_state.outcome_opt = Some(reindex_result.map_err(|error| error.error));
+        }
+    }
+}
+
+/// GET `_elastic/_tasks/<task_id>`
+pub(crate) fn es_compat_get_task_handler(
+    reindex_tasks: ReindexTasks,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    elastic_get_task_filter()
+        .and(with_arg(reindex_tasks))
+        .then(|task_id: String, reindex_tasks: ReindexTasks| async move {
+            reindex_tasks.get_task(&task_id).ok_or_else(|| {
+                ElasticsearchError::new(
+                    StatusCode::NOT_FOUND,
+                    format!("task [{task_id}] isn't running and hasn't stored its results"),
+                    None,
+                )
+            })
+        })
+        .and(extract_format_from_qs())
+        .map(make_elastic_api_response)
+        .recover(recover_fn)
+        .boxed()
+}
@@ -0,0 +1,263 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use mockall::predicate;
+use quickwit_config::{DocMapping, IngestApiConfig, NodeConfig};
+use quickwit_index_management::IndexService;
+use quickwit_ingest::FetchRequest;
+use quickwit_metastore::{IndexMetadata, ListIndexesMetadataResponseExt};
+use quickwit_proto::ingest::router::IngestRouterServiceClient;
+use quickwit_proto::metastore::{
+    ListIndexesMetadataResponse, MetastoreServiceClient, MockMetastoreService,
+};
+use quickwit_proto::search::{Hit, PartialHit, ScrollRequest, SearchResponse};
+use quickwit_search::MockSearchService;
+use quickwit_storage::StorageResolver;
+use serde_json::{Value as JsonValue, json};
+
+use super::REINDEX_SCROLL_TTL_SECS;
+use crate::elasticsearch_api::elastic_api_handlers;
+use crate::elasticsearch_api::model::{ReindexResponse, ReindexTaskCreatedResponse};
+use crate::elasticsearch_api::tests::mock_cluster;
+use crate::ingest_api::setup_ingest_v1_service;
+
+fn hit(doc: JsonValue, doc_id: u32) -> Hit {
+    Hit {
+        json: doc.to_string(),
+        partial_hit: Some(PartialHit {
+            split_id: "split-1".to_string(),
+            doc_id,
+            ..Default::default()
+        }),
+        index_id: "source-index".to_string(),
+        ..Default::default()
+    }
+}
+
+fn mock_metastore() -> MetastoreServiceClient {
+    let mut dest_index_metadata =
+        IndexMetadata::for_test("dest-index", "ram:///indexes/dest-index");
+    dest_index_metadata.index_config.doc_mapping = serde_json::from_value::<DocMapping>(json!({
+        "mode": "strict",
+        "field_mappings": [
+            { "name": "body", "type": "text" },
+            { "name": "count", "type": "u64" }
+        ]
+    }))
+    .unwrap();
+    let mut mock_metastore = MockMetastoreService::new();
+    mock_metastore
+        .expect_list_indexes_metadata()
+        .returning(move |_| {
+            Ok(ListIndexesMetadataResponse::for_test(vec![
+                dest_index_metadata.clone(),
+            ]))
+        });
+    MetastoreServiceClient::from_mock(mock_metastore)
+}
+
+fn mock_search_service() -> MockSearchService {
+    let mut mock_search_service = MockSearchService::new();
+    mock_search_service
+        .expect_root_search()
+        .withf(|search_request| {
+            search_request.index_id_patterns == ["source-index"]
+                && search_request.max_hits == 2
+                && search_request.scroll_ttl_secs.is_some()
+        })
+        .returning(|_| {
+            Ok(SearchResponse {
+                num_hits: 3,
+                hits: vec![
+                    hit(json!({"body": "first", "count": 1}), 0),
+                    hit(json!({"body": "second", "count": 2}), 1),
+                ],
+                scroll_id: Some("scroll-1".to_string()),
+                ..Default::default()
+            })
+        });
+    mock_search_service
+        .expect_scroll()
+        .with(predicate::eq(ScrollRequest {
+            scroll_id: "scroll-1".to_string(),
+            scroll_ttl_secs: Some(REINDEX_SCROLL_TTL_SECS),
+        }))
+        .returning(|_| {
+            Ok(SearchResponse {
+                num_hits: 3,
+                hits: vec![hit(json!({"body": "third", "count": "three"}), 2)],
+                scroll_id: Some("scroll-2".to_string()),
+                ..Default::default()
+            })
+        });
+    mock_search_service
+        .expect_scroll()
+        .with(predicate::eq(ScrollRequest {
+            scroll_id: "scroll-2".to_string(),
+            scroll_ttl_secs: Some(REINDEX_SCROLL_TTL_SECS),
+        }))
+        .returning(|_| {
+            Ok(SearchResponse {
+                num_hits: 3,
+                scroll_id: Some("scroll-3".to_string()),
+                ..Default::default()
+            })
+        });
+    mock_search_service
+}
+
+const REINDEX_BODY: &str = r#"{
+    "source": { "index": "source-index", "size": 2 },
+    "dest": { "index": "dest-index" }
+}"#;
+
+#[tokio::test]
+async fn test_reindex_reports_incompatible_docs() {
+    let (universe, _temp_dir, ingest_service, ingest_service_mailbox) =
+        setup_ingest_v1_service(&["dest-index"], &IngestApiConfig::default()).await;
+    let index_service = IndexService::new(mock_metastore(), StorageResolver::unconfigured());
+    let elastic_api_handlers = elastic_api_handlers(
+        mock_cluster().await,
+        Arc::new(NodeConfig::for_test()),
+        Arc::new(mock_search_service()),
+        ingest_service,
+        IngestRouterServiceClient::mocked(),
+        mock_metastore(),
+        index_service,
+        None,
+        true,
+        false,
+    );
+    let resp = warp::test::request()
+        .path("/_elastic/_reindex")
+        .method("POST")
+        .body(REINDEX_BODY)
+        .reply(&elastic_api_handlers)
+        .await;
+    assert_eq!(resp.status(), 200);
+    let reindex_response: ReindexResponse = serde_json::from_slice(resp.body()).unwrap();
+    assert_eq!(reindex_response.status.total, 3);
+    assert_eq!(reindex_response.status.created, 2);
+    assert_eq!(reindex_response.status.batches, 2);
+    assert_eq!(reindex_response.status.failed, 1);
+    assert_eq!(reindex_response.failures.len(), 1);
+
+    let failure = &reindex_response.failures[0];
+    assert_eq!(failure.index, "dest-index");
+    assert_eq!(failure.status, 400);
+    assert!(failure.id.starts_with("split-1:"));
+    assert!(
+        failure.cause.reason.contains("count"),
+        "{}",
+        failure.cause.reason
+    );
+
+    let fetch_response = ingest_service_mailbox
+        .ask_for_res(FetchRequest {
+            index_id: "dest-index".to_string(),
+            start_after: None,
+            num_bytes_limit: None,
+        })
+        .await
+        .unwrap();
+    assert_eq!(fetch_response.doc_batch.unwrap().num_docs(), 2);
+    universe.assert_quit().await;
+}
+
+#[tokio::test]
+async fn test_reindex_in_background_exposes_task_progress() {
+    let (universe, _temp_dir, ingest_service, _) =
+        setup_ingest_v1_service(&["dest-index"], &IngestApiConfig::default()).await;
+    let index_service = IndexService::new(mock_metastore(), StorageResolver::unconfigured());
+    let node_config = NodeConfig::for_test();
+    let node_id = node_config.node_id.clone();
+    let elastic_api_handlers = elastic_api_handlers(
+        mock_cluster().await,
+        Arc::new(node_config),
+        Arc::new(mock_search_service()),
+        ingest_service,
+        IngestRouterServiceClient::mocked(),
+        mock_metastore(),
+        index_service,
+        None,
+        true,
+        false,
+    );
+    let resp = warp::test::request()
+        .path("/_elastic/_reindex?wait_for_completion=false")
+        .method("POST")
+        .body(REINDEX_BODY)
+        .reply(&elastic_api_handlers)
+        .await;
+    assert_eq!(resp.status(), 200);
+    let task_created_response: ReindexTaskCreatedResponse =
+        serde_json::from_slice(resp.body()).unwrap();
+    assert_eq!(task_created_response.task, format!("{node_id}:0"));
+
+    let task_response_json = loop {
+        let resp = warp::test::request()
+            .path(&format!("/_elastic/_tasks/{}", task_created_response.task))
+            .reply(&elastic_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let task_response_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+
+        if task_response_json["completed"] == json!(true) {
+            break task_response_json;
+        }
+        tokio::task::yield_now().await;
+    };
+    assert_eq!(
+        task_response_json["task"]["action"],
+        "indices:data/write/reindex"
+    );
+    assert_eq!(task_response_json["task"]["status"]["created"], 2);
+    assert_eq!(task_response_json["response"]["failed"], 1);
+
+    let resp = warp::test::request()
+        .path(&format!("/_elastic/_tasks/{node_id}:1"))
+        .reply(&elastic_api_handlers)
+        .await;
+    assert_eq!(resp.status(), 404);
+    universe.assert_quit().await;
+}
+
+#[tokio::test]
+async fn test_reindex_rejects_same_source_and_dest() {
+    let (universe, _temp_dir, ingest_service, _) =
+        setup_ingest_v1_service(&["dest-index"], &IngestApiConfig::default()).await;
+    let index_service = IndexService::new(mock_metastore(), StorageResolver::unconfigured());
+    let elastic_api_handlers = elastic_api_handlers(
+        mock_cluster().await,
+        Arc::new(NodeConfig::for_test()),
+        Arc::new(MockSearchService::new()),
+        ingest_service,
+        IngestRouterServiceClient::mocked(),
+        mock_metastore(),
+        index_service,
+        None,
+        true,
+        false,
+    );
+    let resp = warp::test::request()
+        .path("/_elastic/_reindex")
+        .method("POST")
+        .body(r#"{"source": {"index": "dest-index"}, "dest": {"index": "dest-index"}}"#)
+        .reply(&elastic_api_handlers)
+        .await;
+    assert_eq!(resp.status(), 400);
+    universe.assert_quit().await;
+}
@@ -29,8 +29,8 @@ use quickwit_index_management::IndexService;
 use quickwit_metastore::*;
 use quickwit_proto::metastore::{IndexMetadataRequest, MetastoreService, MetastoreServiceClient};
 use quickwit_proto::search::{
-    CountHits, ListFieldsResponse, PartialHit, ScrollRequest, SearchResponse, SortByValue,
-    SortDatetimeFormat,
+    ClearCacheRequest, CountHits, ListFieldsResponse, PartialHit, ScoreThreshold, ScrollRequest,
+    SearchResponse, SortByValue, SortDatetimeFormat,
 };
 use quickwit_proto::types::IndexUid;
 use quickwit_query::BooleanOperand;
@@ -48,24 +48,29 @@ use super::filter::{
     elastic_aliases_filter, elastic_cat_indices_filter, elastic_cluster_health_filter,
     elastic_cluster_info_filter, elastic_delete_index_filter, elastic_delete_scroll_filter,
     elastic_field_capabilities_filter, elastic_index_cat_indices_filter,
-    elastic_index_count_filter, elastic_index_field_capabilities_filter,
-    elastic_index_mapping_filter, elastic_index_search_filter, elastic_index_stats_filter,
-    elastic_multi_search_filter, elastic_nodes_filter, elastic_resolve_index_filter,
-    elastic_scroll_filter, elastic_search_shards_filter, elastic_stats_filter,
-    elasticsearch_filter,
+    elastic_index_clear_cache_filter, elastic_index_count_filter,
+    elastic_index_field_capabilities_filter, elastic_index_mapping_filter,
+    elastic_index_search_filter, elastic_index_stats_filter, elastic_multi_search_filter,
+    elastic_nodes_filter, elastic_resolve_index_filter, elastic_scroll_filter,
+    elastic_search_shards_filter, elastic_stats_filter, elasticsearch_filter,
 };
 use super::model::{
-    CatIndexQueryParams, DeleteQueryParams, ElasticsearchCatIndexResponse, ElasticsearchError,
-    ElasticsearchResolveIndexEntryResponse, ElasticsearchResolveIndexResponse,
-    ElasticsearchResponse, ElasticsearchStatsResponse, FieldCapabilityQueryParams,
-    FieldCapabilityRequestBody, FieldCapabilityResponse, IndexMappingQueryParams,
-    MultiSearchHeader, MultiSearchQueryParams, MultiSearchResponse, MultiSearchSingleResponse,
-    ScrollQueryParams, SearchBody, SearchQueryParams, SearchQueryParamsCount, StatsResponseEntry,
-    build_list_field_request_for_es_api, convert_to_es_field_capabilities_response,
+    CatIndexQueryParams, DeleteQueryParams, ElasticsearchCatIndexResponse,
+    ElasticsearchClearCacheResponse, ElasticsearchError, ElasticsearchResolveIndexEntryResponse,
+    ElasticsearchResolveIndexResponse, ElasticsearchResponse, ElasticsearchStatsResponse,
+    FieldCapabilityQueryParams, FieldCapabilityRequestBody, FieldCapabilityResponse,
+    IndexMappingQueryParams, MultiSearchHeader, MultiSearchQueryParams, MultiSearchResponse,
+    MultiSearchSingleResponse, ScrollQueryParams, SearchBody, SearchQueryParams,
+    SearchQueryParamsCount, StatsResponseEntry, build_list_field_request_for_es_api,
+    convert_to_es_field_capabilities_response,
+};
+use super::{
+    TrackTotalHits, make_elastic_api_response, make_elastic_api_response_with_index_headers,
 };
-use super::{TrackTotalHits, make_elastic_api_response};
 use crate::elasticsearch_api::model::ElasticsearchMappingsResponse;
 use crate::format::BodyFormat;
+use crate::query_history::query_history;
+use crate::request_scope::ensure_indexes_in_scope;
 use crate::rest::recover_fn;
 use crate::rest_api_response::{RestApiError, RestApiResponse};
 use crate::{BuildInfo, with_arg};
@@ -187,6 +192,17 @@ pub fn es_compat_index_mapping_handler(
         .recover(recover_fn)
 }
 
+/// Checks that the scope of the request allows the indexes the patterns resolved to, which the
+/// requested patterns alone cannot tell when the token excludes some indexes.
+fn ensure_resolved_indexes_in_scope(indexes_metadata: &[IndexMetadata]) -> Result<(), SearchError> {
+    ensure_indexes_in_scope(
+        indexes_metadata
+            .iter()
+            .map(|index_metadata| index_metadata.index_id()),
+    )?;
+    Ok(())
+}
+
 async fn get_index_metadata(
     index_id: String,
     metastore: MetastoreServiceClient,
@@ -207,12 +223,16 @@ pub(crate) async fn es_compat_index_mapping(
     metastore: MetastoreServiceClient,
     search_service: Arc<dyn SearchService>,
 ) -> Result<ElasticsearchMappingsResponse, ElasticsearchError> {
+    ensure_indexes_in_scope(index_id.split(',').map(str::trim)).map_err(SearchError::from)?;
+
     let indexes_metadata = if index_id.contains('*') || index_id.contains(',') {
         let patterns: Vec<String> = index_id.split(',').map(|s| s.trim().to_string()).collect();
         resolve_index_patterns(&patterns, &metastore).await?
     } else {
         vec![get_index_metadata(index_id.clone(), metastore).await?]
     };
+    ensure_resolved_indexes_in_scope(&indexes_metadata)?;
+
     let index_id_patterns: Vec<String> = indexes_metadata
         .iter()
         .map(|m| m.index_id().to_string())
@@ -287,6 +307,18 @@ pub fn es_compat_delete_index_handler(
         .boxed()
 }
 
+/// POST _elastic/{index}/_cache/clear
+pub fn es_compat_index_clear_cache_handler(
+    search_service: Arc<dyn SearchService>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    elastic_index_clear_cache_filter()
+        .and(with_arg(search_service))
+        .then(es_compat_index_clear_cache)
+        .map(|result| make_elastic_api_response(result, BodyFormat::default()))
+        .recover(recover_fn)
+        .boxed()
+}
+
 /// GET _elastic/_stats
 pub fn es_compat_stats_handler(
     metastore_service: MetastoreServiceClient,
@@ -403,7 +435,7 @@ pub fn es_compat_index_search_handler(
     elastic_index_search_filter()
         .and(with_arg(search_service))
         .then(es_compat_index_search)
-        .map(|result| make_elastic_api_response(result, BodyFormat::default()))
+        .map(|result| make_elastic_api_response_with_index_headers(result, BodyFormat::default()))
         .recover(recover_fn)
         .boxed()
 }
@@ -415,7 +447,7 @@ pub fn es_compat_index_count_handler(
     elastic_index_count_filter()
         .and(with_arg(search_service))
         .then(es_compat_index_count)
-        .map(|result| make_elastic_api_response(result, BodyFormat::default()))
+        .map(|result| make_elastic_api_response_with_index_headers(result, BodyFormat::default()))
         .recover(recover_fn)
         .boxed()
 }
@@ -550,6 +582,7 @@ fn build_request_for_es_api(
                 .date_format
                 .clone()
                 .map(|date_format| SortDatetimeFormat::from(date_format) as i32),
+            script: None,
         })
         .take_while_inclusive(|sort_field| !is_doc_field(sort_field))
         .collect();
@@ -563,7 +596,13 @@ fn build_request_for_es_api(
     let scroll_ttl_secs: Option<u32> = scroll_duration.map(|duration| duration.as_secs() as u32);
 
     let has_doc_id_field = sort_fields.iter().any(is_doc_field);
+    let skip_fetch_docs = search_params.excludes_source() || search_body.excludes_source();
     let search_after = partial_hit_from_search_after_param(search_body.search_after, &sort_fields)?;
+    let min_score = search_body.min_score.map(|value| ScoreThreshold { value });
+    let track_scores = search_params
+        .track_scores
+        .or(search_body.track_scores)
+        .unwrap_or(false);
 
     Ok((
         quickwit_proto::search::SearchRequest {
@@ -581,6 +620,23 @@ fn build_request_for_es_api(
             count_hits,
             ignore_missing_indexes,
             skip_aggregation_finalization: false,
+            skip_fetch_docs,
+            min_score,
+            explain: false,
+            read_preference: quickwit_proto::search::ReadPreference::Fastest as i32,
+            explain_scores: false,
+            per_split_timeout_millis: None,
+            timeout_millis: None,
+            // Set by the root from the search settings of the targeted indexes.
+            max_aggregation_buckets: None,
+            scoring: None,
+            missing_fields_as_null: None,
+            bypass_cache: false,
+            track_scores,
+            inherit_default_operator: false,
+            inherit_default_max_hits: false,
+            ignore_max_indexes_per_search: false,
+            explain_resolution: false,
         },
         has_doc_id_field,
     ))
@@ -659,7 +715,7 @@ pub(crate) async fn es_compat_index_count(
     search_params: SearchQueryParamsCount,
     search_body: SearchBody,
     search_service: Arc<dyn SearchService>,
-) -> Result<ElasticsearchCountResponse, ElasticsearchError> {
+) -> Result<(ElasticsearchCountResponse, HashMap<String, String>), ElasticsearchError> {
     let mut search_params: SearchQueryParams = search_params.into();
     search_params.track_total_hits = Some(TrackTotalHits::Track(true));
     let (search_request, _append_shard_doc) =
@@ -668,7 +724,7 @@ pub(crate) async fn es_compat_index_count(
     let search_response_rest: ElasticsearchCountResponse = ElasticsearchCountResponse {
         count: search_response.num_hits,
     };
-    Ok(search_response_rest)
+    Ok((search_response_rest, search_response.response_headers))
 }
 
 pub(crate) async fn es_compat_index_search(
@@ -676,7 +732,7 @@ pub(crate) async fn es_compat_index_search(
     search_params: SearchQueryParams,
     search_body: SearchBody,
     search_service: Arc<dyn SearchService>,
-) -> Result<ElasticsearchResponse, ElasticsearchError> {
+) -> Result<(ElasticsearchResponse, HashMap<String, String>), ElasticsearchError> {
     if search_params.scroll.is_some() && !search_params.allow_partial_search_results() {
         return Err(ElasticsearchError::from(SearchError::InvalidArgument(
             "Quickwit only supports scroll API with allow_partial_search_results set to true"
@@ -689,8 +745,16 @@ pub(crate) async fn es_compat_index_search(
     let allow_partial_search_results = search_params.allow_partial_search_results();
     let (search_request, append_shard_doc) =
         build_request_for_es_api(index_id_patterns, search_params, search_body)?;
-    let search_response: SearchResponse = search_service.root_search(search_request).await?;
+    let query_ast_json = search_request.query_ast.clone();
+    let mut search_response: SearchResponse = search_service.root_search(search_request).await?;
+    let index_response_headers = std::mem::take(&mut search_response.response_headers);
     let elapsed = start_instant.elapsed();
+    query_history().record(
+        &search_response.searched_index_ids,
+        &query_ast_json,
+        search_response.num_hits,
+        elapsed,
+    );
     let mut search_response_rest: ElasticsearchResponse = convert_to_es_search_response(
         search_response,
         append_shard_doc,
@@ -699,7 +763,7 @@ pub(crate) async fn es_compat_index_search(
         allow_partial_search_results,
     )?;
     search_response_rest.took = elapsed.as_millis() as u32;
-    Ok(search_response_rest)
+    Ok((search_response_rest, index_response_headers))
 }
 
 /// Returns JSON in the format:
@@ -717,6 +781,8 @@ async fn es_compat_delete_index(
     query_params: DeleteQueryParams,
     index_service: IndexService,
 ) -> Result<ElasticsearchDeleteResponse, ElasticsearchError> {
+    ensure_indexes_in_scope(index_id_patterns.iter().map(String::as_str))
+        .map_err(SearchError::from)?;
     index_service
         .delete_indexes(
             index_id_patterns,
@@ -733,11 +799,23 @@ async fn es_compat_stats(
     es_compat_index_stats(vec!["*".to_string()], metastore).await
 }
 
+async fn es_compat_index_clear_cache(
+    index_id_patterns: Vec<String>,
+    search_service: Arc<dyn SearchService>,
+) -> Result<ElasticsearchClearCacheResponse, ElasticsearchError> {
+    let clear_cache_request = ClearCacheRequest { index_id_patterns };
+    let clear_cache_response = search_service.root_clear_cache(clear_cache_request).await?;
+    Ok(clear_cache_response.into())
+}
+
 pub(crate) async fn es_compat_index_stats(
     index_id_patterns: Vec<String>,
     metastore: MetastoreServiceClient,
 ) -> Result<ElasticsearchStatsResponse, ElasticsearchError> {
+    ensure_indexes_in_scope(index_id_patterns.iter().map(String::as_str))
+        .map_err(SearchError::from)?;
     let indexes_metadata = resolve_index_patterns(&index_id_patterns, &metastore).await?;
+    ensure_resolved_indexes_in_scope(&indexes_metadata)?;
 
     // Index uid to index id mapping
     let index_uid_to_index_id: HashMap<IndexUid, String> = indexes_metadata
@@ -771,7 +849,10 @@ pub(crate) async fn es_compat_index_cat_indices(
     metastore: MetastoreServiceClient,
 ) -> Result<Vec<serde_json::Value>, ElasticsearchError> {
     query_params.validate()?;
+    ensure_indexes_in_scope(index_id_patterns.iter().map(String::as_str))
+        .map_err(SearchError::from)?;
     let indexes_metadata = resolve_index_patterns(&index_id_patterns, &metastore).await?;
+    ensure_resolved_indexes_in_scope(&indexes_metadata)?;
     let mut index_id_to_resp: HashMap<IndexUid, ElasticsearchCatIndexResponse> = indexes_metadata
         .iter()
         .map(|metadata| (metadata.index_uid.to_owned(), metadata.clone().into()))
@@ -816,7 +897,10 @@ pub(crate) async fn es_compat_resolve_index(
     index_id_patterns: Vec<String>,
     metastore: MetastoreServiceClient,
 ) -> Result<ElasticsearchResolveIndexResponse, ElasticsearchError> {
+    ensure_indexes_in_scope(index_id_patterns.iter().map(String::as_str))
+        .map_err(SearchError::from)?;
     let indexes_metadata = resolve_index_patterns(&index_id_patterns, &metastore).await?;
+    ensure_resolved_indexes_in_scope(&indexes_metadata)?;
     let mut indices: Vec<ElasticsearchResolveIndexEntryResponse> = indexes_metadata
         .into_iter()
         .map(|metadata| metadata.into())
@@ -845,7 +929,7 @@ pub(crate) async fn es_compat_index_field_capabilities(
     Ok(search_response_rest)
 }
 
-fn filter_source(
+pub(crate) fn filter_source(
     value: &mut serde_json::Value,
     _source_excludes: &Option<Vec<String>>,
     _source_includes: &Option<Vec<String>>,
@@ -939,11 +1023,26 @@ fn convert_hit(
     _source_excludes: &Option<Vec<String>>,
     _source_includes: &Option<Vec<String>>,
 ) -> ElasticHit {
-    let mut json: serde_json::Value = serde_json::from_str(&hit.json).unwrap_or(json!({}));
-    filter_source(&mut json, _source_excludes, _source_includes);
-    let source =
-        Source::from_string(serde_json::to_string(&json).unwrap_or_else(|_| "{}".to_string()))
-            .unwrap_or_else(|_| Source::from_string("{}".to_string()).unwrap());
+    // An empty JSON means the documents were not fetched (`_source: false`): the hit is then
+    // identified by its doc address.
+    let (source, id) = if hit.json.is_empty() {
+        let source = Source::from_string("null".to_string()).expect("`null` should be valid JSON");
+        let id = hit
+            .partial_hit
+            .as_ref()
+            .map(|partial_hit| {
+                quickwit_search::GlobalDocAddress::from_partial_hit(partial_hit).to_string()
+            })
+            .unwrap_or_default();
+        (source, id)
+    } else {
+        let mut json: serde_json::Value = serde_json::from_str(&hit.json).unwrap_or(json!({}));
+        filter_source(&mut json, _source_excludes, _source_includes);
+        let source =
+            Source::from_string(serde_json::to_string(&json).unwrap_or_else(|_| "{}".to_string()))
+                .unwrap_or_else(|_| Source::from_string("{}".to_string()).unwrap());
+        (source, String::new())
+    };
 
     let mut sort = Vec::new();
     if let Some(partial_hit) = hit.partial_hit {
@@ -964,8 +1063,8 @@ fn convert_hit(
         fields: Default::default(),
         explanation: None,
         index: hit.index_id,
-        id: "".to_string(),
-        score: None,
+        id,
+        score: hit.scores.map(|hit_scores| hit_scores.score),
         nested: None,
         source,
         highlight: Default::default(),
@@ -1257,6 +1356,7 @@ mod tests {
             field_name: "field1".to_string(),
             sort_order: 1,
             sort_datetime_format: None,
+            script: None,
         }];
         let error = partial_hit_from_search_after_param(search_after, sort_order).unwrap_err();
         assert_eq!(error.status, StatusCode::BAD_REQUEST);
@@ -1273,6 +1373,7 @@ mod tests {
             field_name: "_doc".to_string(),
             sort_order: 1,
             sort_datetime_format: None,
+            script: None,
         }];
         let error = partial_hit_from_search_after_param(search_after, sort_order).unwrap_err();
         assert_eq!(error.status, StatusCode::BAD_REQUEST);
@@ -1422,6 +1523,7 @@ mod tests {
             error: "some-error".to_string(),
             split_id: "some-split-id".to_string(),
             retryable_error: true,
+            timed_out: false,
         };
         {
             let search_response = SearchResponse {
@@ -13,13 +13,21 @@
 // limitations under the License.
 
 use std::fmt;
+use std::task::{Context, Poll};
 
-use quickwit_config::ConfigFormat;
+use quickwit_config::{ConfigFormat, RestResponseFormat};
 use serde::{self, Deserialize, Serialize, Serializer};
 use thiserror::Error;
-use warp::hyper::header::CONTENT_TYPE;
+use tokio::task::futures::TaskLocalFuture;
+use tower::{Layer, Service};
+use warp::hyper::header::{ACCEPT, CONTENT_TYPE};
+use warp::hyper::http;
 use warp::{Filter, Rejection};
 
+tokio::task_local! {
+    static NODE_DEFAULT_BODY_FORMAT: BodyFormat;
+}
+
 /// Body output format used for the REST API.
 #[derive(Deserialize, Clone, Debug, Eq, PartialEq, Copy, utoipa::ToSchema)]
 #[serde(rename_all = "snake_case")]
@@ -28,9 +36,39 @@ pub enum BodyFormat {
     Json,
     #[default]
     PrettyJson,
+    Yaml,
+    /// MessagePack, a compact binary encoding of the JSON data model.
+    #[serde(rename = "msgpack")]
+    MessagePack,
+}
+
+impl From<RestResponseFormat> for BodyFormat {
+    fn from(rest_response_format: RestResponseFormat) -> Self {
+        match rest_response_format {
+            RestResponseFormat::Json => Self::Json,
+            RestResponseFormat::PrettyJson => Self::PrettyJson,
+            RestResponseFormat::Yaml => Self::Yaml,
+        }
+    }
 }
 
 impl BodyFormat {
+    /// Returns the body format configured with `rest.default_response_format` for the request
+    /// being served, or the default body format outside of a request.
+    pub(crate) fn node_default() -> Self {
+        NODE_DEFAULT_BODY_FORMAT
+            .try_with(|body_format| *body_format)
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn content_type(&self) -> &'static str {
+        match &self {
+            Self::Json | Self::PrettyJson => "application/json",
+            Self::Yaml => "application/yaml",
+            Self::MessagePack => "application/msgpack",
+        }
+    }
+
     pub(crate) fn result_to_vec<T: serde::Serialize, E: serde::Serialize>(
         &self,
         result: &Result<T, E>,
@@ -43,8 +81,12 @@ impl BodyFormat {
 
     fn value_to_vec(&self, value: &impl serde::Serialize) -> Result<Vec<u8>, ()> {
         match &self {
-            Self::Json => serde_json::to_vec(value),
-            Self::PrettyJson => serde_json::to_vec_pretty(value),
+            Self::Json => serde_json::to_vec(value).map_err(anyhow::Error::from),
+            Self::PrettyJson => serde_json::to_vec_pretty(value).map_err(anyhow::Error::from),
+            Self::Yaml => serde_yaml::to_string(value)
+                .map(String::into_bytes)
+                .map_err(anyhow::Error::from),
+            Self::MessagePack => rmp_serde::to_vec_named(value).map_err(anyhow::Error::from),
         }
         .map_err(|_| {
             tracing::error!("response serialization failed");
@@ -57,6 +99,8 @@ impl fmt::Display for BodyFormat {
         match &self {
             Self::Json => write!(formatter, "json"),
             Self::PrettyJson => write!(formatter, "pretty_json"),
+            Self::Yaml => write!(formatter, "yaml"),
+            Self::MessagePack => write!(formatter, "msgpack"),
         }
     }
 }
@@ -74,7 +118,7 @@ impl Serialize for BodyFormat {
 #[into_params(parameter_in = Query)]
 struct FormatQueryString {
     /// The output format requested.
-    #[serde(default)]
+    #[serde(default = "BodyFormat::node_default")]
     pub format: BodyFormat,
 }
 
@@ -92,6 +136,83 @@ pub(crate) struct UnsupportedMediaType;
 
 impl warp::reject::Reject for UnsupportedMediaType {}
 
+/// Returns whether the `Accept` header of a request accepts MessagePack.
+fn accepts_msgpack(accept: &str) -> bool {
+    accept.split(',').any(|media_range| {
+        let mut media_range_parts = media_range.split(';');
+        let media_type = media_range_parts.next().unwrap_or_default().trim();
+
+        if !media_type.eq_ignore_ascii_case("application/msgpack")
+            && !media_type.eq_ignore_ascii_case("application/x-msgpack")
+        {
+            return false;
+        }
+        // A media range weighted with `q=0` is not acceptable.
+        !media_range_parts.any(|param| match param.trim().strip_prefix("q=") {
+            Some(weight) => weight.parse::<f32>().ok() == Some(0.0),
+            None => false,
+        })
+    })
+}
+
+/// Sets the body format of the responses to the requests not setting the `format` query
+/// parameter, for the REST routes relying on [`BodyFormat::node_default`]. Requests whose
+/// `Accept` header accepts MessagePack are responded to in MessagePack.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct DefaultBodyFormatLayer {
+    default_body_format: BodyFormat,
+}
+
+impl DefaultBodyFormatLayer {
+    pub fn new(default_body_format: BodyFormat) -> Self {
+        Self {
+            default_body_format,
+        }
+    }
+}
+
+impl<S> Layer<S> for DefaultBodyFormatLayer {
+    type Service = DefaultBodyFormatService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        DefaultBodyFormatService {
+            inner,
+            default_body_format: self.default_body_format,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct DefaultBodyFormatService<S> {
+    inner: S,
+    default_body_format: BodyFormat,
+}
+
+impl<S, B> Service<http::Request<B>> for DefaultBodyFormatService<S>
+where S: Service<http::Request<B>>
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = TaskLocalFuture<BodyFormat, S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        let default_body_format = match request.headers().get(ACCEPT) {
+            Some(accept) if accepts_msgpack(accept.to_str().unwrap_or_default()) => {
+                BodyFormat::MessagePack
+            }
+            _ => self.default_body_format,
+        };
+        // The inner layers may reply without polling the future, when rejecting a request.
+        let response_fut =
+            NODE_DEFAULT_BODY_FORMAT.sync_scope(default_body_format, || self.inner.call(request));
+        NODE_DEFAULT_BODY_FORMAT.scope(default_body_format, response_fut)
+    }
+}
+
 pub(crate) fn extract_config_format()
 -> impl Filter<Extract = (ConfigFormat,), Error = Rejection> + Copy {
     warp::filters::header::optional::<mime_guess::Mime>(CONTENT_TYPE.as_str()).and_then(
@@ -111,3 +232,135 @@ pub(crate) fn extract_config_format()
         },
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use http_body_util::BodyExt;
+    use serde_json::json;
+    use tower::ServiceExt;
+    use warp::hyper::StatusCode;
+
+    use super::*;
+    use crate::rest::recover_fn_final;
+    use crate::rest_api_response::RestApiResponse;
+
+    async fn get(
+        default_body_format_opt: Option<BodyFormat>,
+        path: &str,
+    ) -> (StatusCode, String, String) {
+        let routes = warp::path!("format")
+            .and(extract_format_from_qs())
+            .map(|body_format: BodyFormat| {
+                let result: Result<_, ()> = Ok(json!({"foo": "bar"}));
+                RestApiResponse::new(&result, StatusCode::OK, body_format)
+            })
+            .recover(recover_fn_final);
+        let request = http::Request::builder()
+            .uri(path)
+            .body(String::new())
+            .unwrap();
+        let response = if let Some(default_body_format) = default_body_format_opt {
+            DefaultBodyFormatLayer::new(default_body_format)
+                .layer(warp::service(routes))
+                .oneshot(request)
+                .await
+                .unwrap()
+        } else {
+            warp::service(routes).oneshot(request).await.unwrap()
+        };
+        let status_code = response.status();
+        let content_type = response.headers()[CONTENT_TYPE]
+            .to_str()
+            .unwrap()
+            .to_string();
+        let body_bytes = response.into_body().collect().await.unwrap().to_bytes();
+        let body = String::from_utf8(body_bytes.to_vec()).unwrap();
+        (status_code, content_type, body)
+    }
+
+    #[tokio::test]
+    async fn test_default_body_format() {
+        let (status_code, content_type, body) = get(Some(BodyFormat::Yaml), "/format").await;
+        assert_eq!(status_code, StatusCode::OK);
+        assert_eq!(content_type, "application/yaml");
+        assert_eq!(body, "foo: bar\n");
+
+        let (status_code, content_type, body) = get(Some(BodyFormat::Yaml), "/unknown").await;
+        assert_eq!(status_code, StatusCode::NOT_FOUND);
+        assert_eq!(content_type, "application/yaml");
+        assert_eq!(body, "message: Route not found\n");
+
+        // The `format` query parameter takes precedence over the default body format.
+        let (status_code, content_type, body) =
+            get(Some(BodyFormat::Yaml), "/format?format=json").await;
+        assert_eq!(status_code, StatusCode::OK);
+        assert_eq!(content_type, "application/json");
+        assert_eq!(body, r#"{"foo":"bar"}"#);
+
+        let (status_code, content_type, body) = get(None, "/format").await;
+        assert_eq!(status_code, StatusCode::OK);
+        assert_eq!(content_type, "application/json");
+        assert_eq!(body, "{\n  \"foo\": \"bar\"\n}");
+
+        let (status_code, content_type, body) = get(None, "/unknown").await;
+        assert_eq!(status_code, StatusCode::NOT_FOUND);
+        assert_eq!(content_type, "application/json");
+        assert_eq!(body, "{\n  \"message\": \"Route not found\"\n}");
+    }
+
+    #[test]
+    fn test_accepts_msgpack() {
+        assert!(accepts_msgpack("application/msgpack"));
+        assert!(accepts_msgpack(
+            "application/json;q=0.9, Application/X-MsgPack"
+        ));
+        assert!(!accepts_msgpack("application/msgpack;q=0"));
+        assert!(!accepts_msgpack("application/json, */*"));
+        assert!(!accepts_msgpack(""));
+    }
+
+    #[tokio::test]
+    async fn test_default_body_format_accept_msgpack() {
+        let routes = warp::path!("format")
+            .and(extract_format_from_qs())
+            .map(|body_format: BodyFormat| {
+                let result: Result<_, ()> = Ok(json!({"foo": "bar"}));
+                RestApiResponse::new(&result, StatusCode::OK, body_format)
+            })
+            .recover(recover_fn_final);
+
+        for (path, expected_content_type, expected_body) in [
+            ("/format", "application/msgpack", json!({"foo": "bar"})),
+            (
+                "/unknown",
+                "application/msgpack",
+                json!({"message": "Route not found"}),
+            ),
+            (
+                "/format?format=json",
+                "application/json",
+                json!({"foo": "bar"}),
+            ),
+        ] {
+            let request = http::Request::builder()
+                .uri(path)
+                .header(ACCEPT, "application/msgpack")
+                .body(String::new())
+                .unwrap();
+            let response = DefaultBodyFormatLayer::new(BodyFormat::Yaml)
+                .layer(warp::service(routes.clone()))
+                .oneshot(request)
+                .await
+                .unwrap();
+            assert_eq!(response.headers()[CONTENT_TYPE], expected_content_type);
+
+            let body_bytes = response.into_body().collect().await.unwrap().to_bytes();
+            let body: serde_json::Value = if expected_content_type == "application/msgpack" {
+                rmp_serde::from_slice(&body_bytes).unwrap()
+            } else {
+                serde_json::from_slice(&body_bytes).unwrap()
+            };
+            assert_eq!(body, expected_body);
+        }
+    }
+}
@@ -203,6 +203,8 @@ pub(crate) async fn start_grpc_server(
         let grpc_search_service = GrpcSearchAdapter::from(search_service);
         Some(
             SearchServiceServer::new(grpc_search_service)
+                .accept_compressed(CompressionEncoding::Gzip)
+                .accept_compressed(CompressionEncoding::Zstd)
                 .max_decoding_message_size(grpc_config.max_message_size.0 as usize)
                 .max_encoding_message_size(grpc_config.max_message_size.0 as usize),
         )
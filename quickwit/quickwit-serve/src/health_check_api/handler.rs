@@ -13,7 +13,6 @@
 // limitations under the License.
 
 use quickwit_actors::{Healthz, Mailbox};
-use quickwit_cluster::Cluster;
 use quickwit_indexing::IndexingService;
 use quickwit_janitor::JanitorService;
 use tracing::error;
@@ -21,6 +20,7 @@ use warp::hyper::StatusCode;
 use warp::reply::with_status;
 use warp::{Filter, Rejection};
 
+use crate::node_readiness::NodeReadiness;
 use crate::rest::recover_fn;
 use crate::with_arg;
 
@@ -30,11 +30,11 @@ pub struct HealthCheckApi;
 
 /// Health check handlers.
 pub(crate) fn health_check_handlers(
-    cluster: Cluster,
+    node_readiness: NodeReadiness,
     indexer_service_opt: Option<Mailbox<IndexingService>>,
     janitor_service_opt: Option<Mailbox<JanitorService>>,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
-    liveness_handler(indexer_service_opt, janitor_service_opt).or(readiness_handler(cluster))
+    liveness_handler(indexer_service_opt, janitor_service_opt).or(readiness_handler(node_readiness))
 }
 
 fn liveness_handler(
@@ -50,11 +50,11 @@ fn liveness_handler(
 }
 
 fn readiness_handler(
-    cluster: Cluster,
+    node_readiness: NodeReadiness,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
     warp::path!("health" / "readyz")
         .and(warp::get())
-        .and(with_arg(cluster))
+        .and(with_arg(node_readiness))
         .then(get_readiness)
         .recover(recover_fn)
 }
@@ -105,8 +105,10 @@ async fn get_liveness(
     ),
 )]
 /// Get Node Readiness
-async fn get_readiness(cluster: Cluster) -> impl warp::Reply {
-    let is_ready = cluster.is_self_node_ready().await;
+///
+/// The readiness is the one reported by the gRPC health service.
+async fn get_readiness(node_readiness: NodeReadiness) -> impl warp::Reply {
+    let is_ready = node_readiness.is_ready();
     let status_code = if is_ready {
         StatusCode::OK
     } else {
@@ -119,6 +121,9 @@ async fn get_readiness(cluster: Cluster) -> impl warp::Reply {
 mod tests {
 
     use quickwit_cluster::{ChitchatTransport, create_cluster_for_test};
+    use tonic_health::server::health_reporter;
+
+    use crate::node_readiness::NodeReadiness;
 
     #[tokio::test]
     async fn test_rest_search_api_health_checks() {
@@ -126,7 +131,9 @@ mod tests {
         let cluster = create_cluster_for_test(Vec::new(), &[], &transport, false)
             .await
             .unwrap();
-        let health_check_handler = super::health_check_handlers(cluster.clone(), None, None);
+        let (health_reporter, _health_service) = health_reporter();
+        let node_readiness = NodeReadiness::new(cluster, health_reporter).await;
+        let health_check_handler = super::health_check_handlers(node_readiness.clone(), None, None);
         let resp = warp::test::request()
             .path("/health/livez")
             .reply(&health_check_handler)
@@ -137,7 +144,7 @@ mod tests {
             .reply(&health_check_handler)
             .await;
         assert_eq!(resp.status(), 503);
-        cluster.set_self_node_readiness(true).await;
+        node_readiness.set_ready(true).await;
         let resp = warp::test::request()
             .path("/health/readyz")
             .reply(&health_check_handler)
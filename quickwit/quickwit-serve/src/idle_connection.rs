@@ -0,0 +1,169 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Detection of the idle REST connections, which have not read nor written anything for a while.
+
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::time::{Instant, sleep_until};
+
+/// Instant of the last read or write of a connection, shared between its I/O and the task
+/// serving it.
+#[derive(Clone)]
+pub(crate) struct ConnectionActivity {
+    accepted_at: Instant,
+    /// Milliseconds elapsed between `accepted_at` and the last activity.
+    last_activity_millis: Arc<AtomicU64>,
+}
+
+impl ConnectionActivity {
+    fn new() -> Self {
+        Self {
+            accepted_at: Instant::now(),
+            last_activity_millis: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    fn record(&self) {
+        let elapsed_millis = self.accepted_at.elapsed().as_millis() as u64;
+        self.last_activity_millis
+            .store(elapsed_millis, Ordering::Relaxed);
+    }
+
+    fn last_activity(&self) -> Instant {
+        let last_activity_millis = self.last_activity_millis.load(Ordering::Relaxed);
+        self.accepted_at + Duration::from_millis(last_activity_millis)
+    }
+
+    /// Resolves once the connection has not read nor written anything for `idle_timeout`.
+    pub(crate) async fn idle(&self, idle_timeout: Duration) {
+        loop {
+            let idle_deadline = self.last_activity() + idle_timeout;
+
+            if Instant::now() >= idle_deadline {
+                return;
+            }
+            sleep_until(idle_deadline).await;
+        }
+    }
+}
+
+/// Wraps the I/O of a connection to record its activity.
+pub(crate) struct ActivityTrackingIo<T> {
+    inner: T,
+    activity: ConnectionActivity,
+}
+
+impl<T> ActivityTrackingIo<T> {
+    pub(crate) fn new(inner: T) -> (Self, ConnectionActivity) {
+        let activity = ConnectionActivity::new();
+        let io = Self {
+            inner,
+            activity: activity.clone(),
+        };
+        (io, activity)
+    }
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for ActivityTrackingIo<T> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let num_filled_bytes_before = buf.filled().len();
+        let poll = Pin::new(&mut this.inner).poll_read(cx, buf);
+
+        if matches!(poll, Poll::Ready(Ok(()))) && buf.filled().len() > num_filled_bytes_before {
+            this.activity.record();
+        }
+        poll
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for ActivityTrackingIo<T> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let poll = Pin::new(&mut this.inner).poll_write(cx, buf);
+
+        if matches!(poll, Poll::Ready(Ok(num_bytes)) if num_bytes > 0) {
+            this.activity.record();
+        }
+        poll
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let poll = Pin::new(&mut this.inner).poll_write_vectored(cx, bufs);
+
+        if matches!(poll, Poll::Ready(Ok(num_bytes)) if num_bytes > 0) {
+            this.activity.record();
+        }
+        poll
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.inner.is_write_vectored()
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_connection_activity_idle() {
+        let (mut client, server) = tokio::io::duplex(64);
+        let (mut server_io, activity) = ActivityTrackingIo::new(server);
+        let idle_timeout = Duration::from_secs(10);
+
+        // Reads and writes postpone the idle deadline.
+        tokio::time::advance(Duration::from_secs(6)).await;
+        client.write_all(b"ping").await.unwrap();
+        let mut buf = [0u8; 4];
+        server_io.read_exact(&mut buf).await.unwrap();
+
+        tokio::time::advance(Duration::from_secs(6)).await;
+        server_io.write_all(b"pong").await.unwrap();
+
+        let start = Instant::now();
+        activity.idle(idle_timeout).await;
+        assert_eq!(start.elapsed(), idle_timeout);
+    }
+}
@@ -0,0 +1,408 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use quickwit_doc_mapper::{
+    DYNAMIC_FIELD_NAME, DocMapping, FieldMappingEntry, FieldMappingType, Mode, TokenizerEntry,
+};
+use quickwit_metastore::{IndexMetadataResponseExt, ListIndexesMetadataResponseExt};
+use quickwit_proto::metastore::{
+    IndexMetadataRequest, ListIndexesMetadataRequest, MetastoreResult, MetastoreService,
+    MetastoreServiceClient,
+};
+use quickwit_proto::types::IndexId;
+use quickwit_query::BUILTIN_TOKENIZERS;
+use serde::Serialize;
+use tracing::info;
+use warp::{Filter, Rejection};
+
+use crate::format::extract_format_from_qs;
+use crate::request_scope::ensure_indexes_in_scope;
+use crate::rest_api_response::into_rest_api_response;
+use crate::with_arg;
+
+/// An analyzer (tokenizer) that fields can be indexed with.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub(crate) enum Analyzer {
+    /// Analyzer available to all the indexes.
+    Builtin {
+        #[schema(value_type = String)]
+        name: &'static str,
+        #[schema(value_type = String)]
+        tokenizer: &'static str,
+        #[schema(value_type = Vec<String>)]
+        filters: &'static [&'static str],
+        #[schema(value_type = String)]
+        description: &'static str,
+    },
+    /// Analyzer defined in the doc mapping of an index.
+    Custom {
+        index_id: IndexId,
+        #[serde(flatten)]
+        tokenizer_entry: TokenizerEntry,
+    },
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub(crate) struct AnalyzersResponse {
+    analyzers: Vec<Analyzer>,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub(crate) struct FieldAnalyzer {
+    /// Path of the field, `_dynamic` standing for the fields captured by the dynamic mode.
+    field: String,
+    analyzer: String,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub(crate) struct IndexAnalyzersResponse {
+    /// Custom analyzers of the index and built-in analyzers used by its fields.
+    analyzers: Vec<Analyzer>,
+    /// Analyzers of the indexed text and JSON fields.
+    fields: Vec<FieldAnalyzer>,
+}
+
+fn builtin_analyzers() -> impl Iterator<Item = Analyzer> {
+    BUILTIN_TOKENIZERS
+        .iter()
+        .map(|builtin_tokenizer| Analyzer::Builtin {
+            name: builtin_tokenizer.name,
+            tokenizer: builtin_tokenizer.tokenizer,
+            filters: builtin_tokenizer.filters,
+            description: builtin_tokenizer.description,
+        })
+}
+
+fn custom_analyzers(index_id: &str, doc_mapping: &DocMapping) -> impl Iterator<Item = Analyzer> {
+    doc_mapping
+        .tokenizers
+        .iter()
+        .map(move |tokenizer_entry| Analyzer::Custom {
+            index_id: index_id.to_string(),
+            tokenizer_entry: tokenizer_entry.clone(),
+        })
+}
+
+fn collect_field_analyzers(
+    field_mappings: &[FieldMappingEntry],
+    path_prefix: &str,
+    field_analyzers: &mut Vec<FieldAnalyzer>,
+) {
+    for field_mapping in field_mappings {
+        let field_path = if path_prefix.is_empty() {
+            field_mapping.name.clone()
+        } else {
+            format!("{path_prefix}.{}", field_mapping.name)
+        };
+        let indexing_options_opt = match &field_mapping.mapping_type {
+            FieldMappingType::Text(text_options, _) => text_options.indexing_options.as_ref(),
+            FieldMappingType::Json(json_options, _) => json_options.indexing_options.as_ref(),
+            FieldMappingType::Concatenate(concatenate_options) => {
+                Some(&concatenate_options.indexing_options)
+            }
+            FieldMappingType::Object(object_options) => {
+                collect_field_analyzers(
+                    &object_options.field_mappings,
+                    &field_path,
+                    field_analyzers,
+                );
+                None
+            }
+            _ => None,
+        };
+        if let Some(indexing_options) = indexing_options_opt {
+            field_analyzers.push(FieldAnalyzer {
+                field: field_path,
+                analyzer: indexing_options.tokenizer.name().to_string(),
+            });
+        }
+    }
+}
+
+fn field_analyzers(doc_mapping: &DocMapping) -> Vec<FieldAnalyzer> {
+    let mut field_analyzers = Vec::new();
+    collect_field_analyzers(&doc_mapping.field_mappings, "", &mut field_analyzers);
+
+    if let Mode::Dynamic(json_options) = &doc_mapping.mode
+        && let Some(indexing_options) = &json_options.indexing_options
+    {
+        field_analyzers.push(FieldAnalyzer {
+            field: DYNAMIC_FIELD_NAME.to_string(),
+            analyzer: indexing_options.tokenizer.name().to_string(),
+        });
+    }
+    field_analyzers
+}
+
+pub fn list_analyzers_handler(
+    metastore: MetastoreServiceClient,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("_analyzers")
+        .and(warp::get())
+        .and(with_arg(metastore))
+        .then(list_analyzers)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+        .boxed()
+}
+
+#[utoipa::path(
+    get,
+    tag = "Indexes",
+    path = "/_analyzers",
+    responses(
+        (status = 200, description = "Successfully listed the analyzers.", body = AnalyzersResponse)
+    )
+)]
+/// Lists the built-in analyzers and the custom analyzers defined by the indexes. Only the custom
+/// analyzers of the indexes within the scope of the token of the request are listed.
+pub(crate) async fn list_analyzers(
+    metastore: MetastoreServiceClient,
+) -> MetastoreResult<AnalyzersResponse> {
+    info!("list-analyzers");
+    let indexes_metadata = metastore
+        .list_indexes_metadata(ListIndexesMetadataRequest::all())
+        .await?
+        .deserialize_indexes_metadata()
+        .await?;
+    let mut analyzers: Vec<Analyzer> = builtin_analyzers().collect();
+
+    for index_metadata in &indexes_metadata {
+        if ensure_indexes_in_scope([index_metadata.index_id()]).is_err() {
+            continue;
+        }
+        analyzers.extend(custom_analyzers(
+            index_metadata.index_id(),
+            &index_metadata.index_config.doc_mapping,
+        ));
+    }
+    Ok(AnalyzersResponse { analyzers })
+}
+
+pub fn get_index_analyzers_handler(
+    metastore: MetastoreServiceClient,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("indexes" / String / "_analyzers")
+        .and(warp::get())
+        .and(with_arg(metastore))
+        .then(get_index_analyzers)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+        .boxed()
+}
+
+#[utoipa::path(
+    get,
+    tag = "Indexes",
+    path = "/indexes/{index_id}/_analyzers",
+    responses(
+        (status = 200, description = "Successfully fetched the analyzers of the index.", body = IndexAnalyzersResponse)
+    ),
+    params(
+        ("index_id" = String, Path, description = "The index ID to fetch the analyzers of."),
+    )
+)]
+/// Returns the analyzers of the text and JSON fields of an index, along with the configuration
+/// of its custom analyzers and of the built-in analyzers it uses.
+pub(crate) async fn get_index_analyzers(
+    index_id: IndexId,
+    metastore: MetastoreServiceClient,
+) -> MetastoreResult<IndexAnalyzersResponse> {
+    info!(index_id = %index_id, "get-index-analyzers");
+    ensure_indexes_in_scope([index_id.as_str()])?;
+    let index_metadata_request = IndexMetadataRequest::for_index_id(index_id);
+    let index_metadata = metastore
+        .index_metadata(index_metadata_request)
+        .await?
+        .deserialize_index_metadata()?;
+    let doc_mapping = &index_metadata.index_config.doc_mapping;
+    let fields = field_analyzers(doc_mapping);
+
+    let mut analyzers: Vec<Analyzer> =
+        custom_analyzers(index_metadata.index_id(), doc_mapping).collect();
+    // Custom analyzers cannot be named after built-in ones.
+    analyzers.extend(builtin_analyzers().filter(|analyzer| {
+        let Analyzer::Builtin { name, .. } = analyzer else {
+            return false;
+        };
+        fields
+            .iter()
+            .any(|field_analyzer| field_analyzer.analyzer == *name)
+    }));
+    Ok(IndexAnalyzersResponse { analyzers, fields })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use quickwit_config::NodeConfig;
+    use quickwit_index_management::IndexService;
+    use quickwit_metastore::IndexMetadata;
+    use quickwit_proto::metastore::{
+        IndexMetadataResponse, ListIndexesMetadataResponse, MockMetastoreService,
+    };
+    use quickwit_storage::StorageResolver;
+    use serde_json::{Value as JsonValue, json};
+
+    use super::*;
+    use crate::index_api::index_management_handlers;
+    use crate::request_scope::with_index_scope_for_test;
+    use crate::rest::recover_fn;
+
+    fn index_metadata_with_custom_analyzer() -> IndexMetadata {
+        let mut index_metadata =
+            IndexMetadata::for_test("test-analyzers", "ram:///indexes/test-analyzers");
+        index_metadata.index_config.doc_mapping = serde_json::from_value::<DocMapping>(json!({
+            "mode": "strict",
+            "field_mappings": [
+                { "name": "title", "type": "text", "tokenizer": "my_ngram" },
+                { "name": "body", "type": "text" },
+                { "name": "severity", "type": "u64" },
+                {
+                    "name": "resource",
+                    "type": "object",
+                    "field_mappings": [
+                        { "name": "service", "type": "text", "tokenizer": "raw" }
+                    ]
+                }
+            ],
+            "tokenizers": [
+                {
+                    "name": "my_ngram",
+                    "type": "ngram",
+                    "min_gram": 2,
+                    "max_gram": 3,
+                    "filters": ["lower_caser"]
+                }
+            ]
+        }))
+        .unwrap();
+        index_metadata
+    }
+
+    fn mock_metastore() -> MetastoreServiceClient {
+        let mut mock_metastore = MockMetastoreService::new();
+        mock_metastore.expect_index_metadata().returning(|_| {
+            Ok(IndexMetadataResponse::try_from_index_metadata(
+                &index_metadata_with_custom_analyzer(),
+            )
+            .unwrap())
+        });
+        mock_metastore
+            .expect_list_indexes_metadata()
+            .returning(|_| {
+                Ok(ListIndexesMetadataResponse::for_test(vec![
+                    index_metadata_with_custom_analyzer(),
+                ]))
+            });
+        MetastoreServiceClient::from_mock(mock_metastore)
+    }
+
+    async fn get_json(path: &str) -> JsonValue {
+        let index_service = IndexService::new(mock_metastore(), StorageResolver::unconfigured());
+        let index_management_handler =
+            index_management_handlers(index_service, Arc::new(NodeConfig::for_test()))
+                .recover(recover_fn);
+        let resp = warp::test::request()
+            .path(path)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        serde_json::from_slice(resp.body()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_list_analyzers() {
+        let resp_json = get_json("/_analyzers").await;
+        let analyzers = resp_json["analyzers"].as_array().unwrap();
+
+        for builtin_name in ["raw", "default", "whitespace", "source_code_default"] {
+            let is_listed = analyzers
+                .iter()
+                .any(|analyzer| analyzer["kind"] == "builtin" && analyzer["name"] == builtin_name);
+            assert!(is_listed, "{builtin_name}");
+        }
+        let default_analyzer = analyzers
+            .iter()
+            .find(|analyzer| analyzer["name"] == "default")
+            .unwrap();
+        assert_eq!(default_analyzer["tokenizer"], "simple");
+        assert_eq!(
+            default_analyzer["filters"],
+            json!(["remove_long", "lower_caser"])
+        );
+        let custom_analyzer = analyzers
+            .iter()
+            .find(|analyzer| analyzer["kind"] == "custom")
+            .unwrap();
+        assert_eq!(
+            custom_analyzer,
+            &json!({
+                "kind": "custom",
+                "index_id": "test-analyzers",
+                "name": "my_ngram",
+                "type": "ngram",
+                "min_gram": 2,
+                "max_gram": 3,
+                "prefix_only": false,
+                "filters": ["lower_caser"]
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_index_analyzers() {
+        let resp_json = get_json("/indexes/test-analyzers/_analyzers").await;
+        assert_eq!(
+            resp_json["fields"],
+            json!([
+                { "field": "title", "analyzer": "my_ngram" },
+                { "field": "body", "analyzer": "default" },
+                { "field": "resource.service", "analyzer": "raw" }
+            ])
+        );
+        let analyzer_names: Vec<&str> = resp_json["analyzers"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|analyzer| analyzer["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(analyzer_names, ["my_ngram", "raw", "default"]);
+    }
+
+    #[tokio::test]
+    async fn test_list_analyzers_hides_indexes_out_of_scope() {
+        let analyzers_response =
+            with_index_scope_for_test(&["other-*"], list_analyzers(mock_metastore()))
+                .await
+                .unwrap();
+        let has_custom_analyzer = analyzers_response
+            .analyzers
+            .iter()
+            .any(|analyzer| matches!(analyzer, Analyzer::Custom { .. }));
+        assert!(!has_custom_analyzer);
+
+        let analyzers_response =
+            with_index_scope_for_test(&["test-*"], list_analyzers(mock_metastore()))
+                .await
+                .unwrap();
+        let has_custom_analyzer = analyzers_response
+            .analyzers
+            .iter()
+            .any(|analyzer| matches!(analyzer, Analyzer::Custom { .. }));
+        assert!(has_custom_analyzer);
+    }
+}
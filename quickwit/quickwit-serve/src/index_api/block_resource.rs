@@ -0,0 +1,220 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use quickwit_index_management::{IndexService, IndexServiceError};
+use quickwit_metastore::{IndexMetadata, IndexMetadataResponseExt};
+use quickwit_proto::metastore::{IndexMetadataRequest, MetastoreError, MetastoreService};
+use quickwit_proto::types::IndexId;
+use serde::Deserialize;
+use tracing::info;
+use warp::{Filter, Rejection};
+
+use super::rest_handler::{json_body, log_failure};
+use crate::format::extract_format_from_qs;
+use crate::request_scope::ensure_indexes_in_scope;
+use crate::rest_api_response::into_rest_api_response;
+use crate::with_arg;
+
+/// Blocks to set on an index. Omitted blocks are left unchanged.
+#[derive(Debug, Default, Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct IndexBlocks {
+    /// Rejects search requests targeting the index.
+    #[serde(default)]
+    pub read: Option<bool>,
+    /// Rejects ingest requests targeting the index. The index remains searchable.
+    #[serde(default)]
+    pub write: Option<bool>,
+}
+
+pub fn update_index_blocks_handler(
+    index_service: IndexService,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("indexes" / String / "_block")
+        .and(warp::put())
+        .and(json_body())
+        .and(with_arg(index_service))
+        .then(update_index_blocks)
+        .map(log_failure("failed to update index blocks"))
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+        .boxed()
+}
+
+#[utoipa::path(
+    put,
+    tag = "Indexes",
+    path = "/indexes/{index_id}/_block",
+    request_body = IndexBlocks,
+    responses(
+        // We return `VersionedIndexMetadata` as it's the serialized model view.
+        (status = 200, description = "Successfully updated the index blocks.", body = VersionedIndexMetadata)
+    ),
+    params(
+        ("index_id" = String, Path, description = "The index ID to block or unblock."),
+    )
+)]
+/// Blocks or unblocks reads and/or writes on an index.
+///
+/// The blocks are stored in the index configuration (`search_settings.read_block` and
+/// `ingest_settings.write_block`) so they persist in the metastore and survive restarts. Updating
+/// the index config leaves them unchanged.
+pub async fn update_index_blocks(
+    index_id: IndexId,
+    index_blocks: IndexBlocks,
+    index_service: IndexService,
+) -> Result<IndexMetadata, IndexServiceError> {
+    info!(index_id = %index_id, read = ?index_blocks.read, write = ?index_blocks.write, "update-index-blocks");
+    ensure_indexes_in_scope([index_id.as_str()]).map_err(MetastoreError::from)?;
+    let index_metadata_request = IndexMetadataRequest::for_index_id(index_id);
+    let index_metadata = index_service
+        .metastore()
+        .index_metadata(index_metadata_request)
+        .await?
+        .deserialize_index_metadata()?;
+    let index_uid = index_metadata.index_uid.clone();
+    let mut index_config = index_metadata.into_index_config();
+
+    if let Some(read_block) = index_blocks.read {
+        index_config.search_settings.read_block = read_block;
+    }
+    if let Some(write_block) = index_blocks.write {
+        index_config.ingest_settings.write_block = write_block;
+    }
+    index_service.update_index(index_uid, index_config).await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use quickwit_config::IngestApiConfig;
+    use quickwit_index_management::IndexService;
+    use quickwit_indexing::TestSandbox;
+    use quickwit_proto::ingest::router::IngestRouterServiceClient;
+    use quickwit_proto::search::SearchRequest;
+    use quickwit_query::query_ast::qast_json_helper;
+    use quickwit_search::{MockSearchService, SearchError, single_node_search};
+    use serde_json::{Value as JsonValue, json};
+    use warp::Filter;
+
+    use super::update_index_blocks_handler;
+    use crate::ingest_api::{ingest_api_handlers, setup_ingest_v1_service};
+    use crate::rest::recover_fn;
+
+    #[tokio::test]
+    async fn test_write_blocked_index_rejects_ingest_but_serves_searches() {
+        let index_id = "test-index-blocks";
+        let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: body
+                type: text
+        "#;
+        let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "", &["body"])
+            .await
+            .unwrap();
+        test_sandbox
+            .add_documents(vec![json!({"body": "hello"})])
+            .await
+            .unwrap();
+        let metastore = test_sandbox.metastore();
+        let index_service = IndexService::new(metastore.clone(), test_sandbox.storage_resolver());
+        let block_handler = update_index_blocks_handler(index_service).recover(recover_fn);
+
+        let (universe, _temp_dir, ingest_service, _) =
+            setup_ingest_v1_service(&[index_id], &IngestApiConfig::default()).await;
+        let ingest_handler = ingest_api_handlers(
+            IngestRouterServiceClient::mocked(),
+            ingest_service,
+            metastore.clone(),
+            Arc::new(MockSearchService::new()),
+            IngestApiConfig::default(),
+            None,
+            true,
+            false,
+        )
+        .recover(recover_fn);
+
+        let resp = warp::test::request()
+            .path("/indexes/test-index-blocks/_block")
+            .method("PUT")
+            .json(&true)
+            .body(r#"{"write": true}"#)
+            .reply(&block_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let index_metadata: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(
+            index_metadata["index_config"]["ingest_settings"]["write_block"],
+            json!(true)
+        );
+
+        let resp = warp::test::request()
+            .path("/test-index-blocks/ingest")
+            .method("POST")
+            .body(r#"{"body": "world"}"#)
+            .reply(&ingest_handler)
+            .await;
+        assert_eq!(resp.status(), 403);
+        let error_body: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert!(
+            error_body["message"]
+                .as_str()
+                .unwrap()
+                .contains("blocked for writes")
+        );
+
+        let search_request = SearchRequest {
+            index_id_patterns: vec![index_id.to_string()],
+            query_ast: qast_json_helper("hello", &["body"]),
+            max_hits: 10,
+            ..Default::default()
+        };
+        let search_response = single_node_search(
+            search_request.clone(),
+            metastore.clone(),
+            test_sandbox.storage_resolver(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(search_response.num_hits, 1);
+
+        // Lifting the write block and setting the read block flips the behavior.
+        let resp = warp::test::request()
+            .path("/indexes/test-index-blocks/_block")
+            .method("PUT")
+            .json(&true)
+            .body(r#"{"read": true, "write": false}"#)
+            .reply(&block_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+
+        let resp = warp::test::request()
+            .path("/test-index-blocks/ingest")
+            .method("POST")
+            .body(r#"{"body": "world"}"#)
+            .reply(&ingest_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+
+        let search_error =
+            single_node_search(search_request, metastore, test_sandbox.storage_resolver())
+                .await
+                .unwrap_err();
+        assert!(matches!(search_error, SearchError::Forbidden(_)));
+
+        universe.assert_quit().await;
+        test_sandbox.assert_quit().await;
+    }
+}
@@ -0,0 +1,354 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Translates a doc mapping into a JSON Schema or an Avro schema describing the documents
+//! returned by searches, flagging the features of the doc mapping the schema cannot express.
+
+use quickwit_doc_mapper::{
+    BinaryFormat, Cardinality, DocMapping, FieldMappingEntry, FieldMappingType, Mode,
+    NumericOutputFormat,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map as JsonMap, Value as JsonValue, json};
+
+/// Feature of the doc mapping a schema does not capture, or captures loosely.
+#[derive(Debug, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct UnsupportedFeature {
+    /// Path of the field, or `None` for features of the whole doc mapping.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    pub reason: String,
+}
+
+fn flag(unsupported_features: &mut Vec<UnsupportedFeature>, field: &str, reason: &str) {
+    unsupported_features.push(UnsupportedFeature {
+        field: Some(field.to_string()),
+        reason: reason.to_string(),
+    });
+}
+
+fn field_path(parent_path: &str, field_name: &str) -> String {
+    if parent_path.is_empty() {
+        field_name.to_string()
+    } else {
+        format!("{parent_path}.{field_name}")
+    }
+}
+
+pub(super) fn build_json_schema(
+    index_id: &str,
+    doc_mapping: &DocMapping,
+    unsupported_features: &mut Vec<UnsupportedFeature>,
+) -> JsonValue {
+    // Only the dynamic mode returns the unmapped fields.
+    let is_dynamic = matches!(doc_mapping.mode, Mode::Dynamic(_));
+    let mut schema = json_schema_object(
+        &doc_mapping.field_mappings,
+        "",
+        is_dynamic,
+        unsupported_features,
+    );
+    schema["$schema"] = json!("https://json-schema.org/draft/2020-12/schema");
+    schema["title"] = json!(index_id);
+    schema
+}
+
+fn json_schema_object(
+    field_mappings: &[FieldMappingEntry],
+    parent_path: &str,
+    is_dynamic: bool,
+    unsupported_features: &mut Vec<UnsupportedFeature>,
+) -> JsonValue {
+    let mut properties = JsonMap::new();
+
+    for field_mapping in field_mappings {
+        let path = field_path(parent_path, &field_mapping.name);
+
+        if let Some(field_schema) = json_schema_field(
+            &field_mapping.mapping_type,
+            &path,
+            is_dynamic,
+            unsupported_features,
+        ) {
+            properties.insert(field_mapping.name.clone(), field_schema);
+        }
+    }
+    json!({
+        "type": "object",
+        "properties": properties,
+        "additionalProperties": is_dynamic,
+    })
+}
+
+fn json_schema_field(
+    mapping_type: &FieldMappingType,
+    path: &str,
+    is_dynamic: bool,
+    unsupported_features: &mut Vec<UnsupportedFeature>,
+) -> Option<JsonValue> {
+    let (value_schema, cardinality) = match mapping_type {
+        FieldMappingType::Text(_, cardinality) => (json!({"type": "string"}), cardinality),
+        FieldMappingType::I64(options, cardinality) => (
+            json_schema_number("integer", options.output_format),
+            cardinality,
+        ),
+        FieldMappingType::U64(options, cardinality) => {
+            let mut value_schema = json_schema_number("integer", options.output_format);
+            if options.output_format == NumericOutputFormat::Number {
+                value_schema["minimum"] = json!(0);
+            }
+            (value_schema, cardinality)
+        }
+        FieldMappingType::F64(options, cardinality) => (
+            json_schema_number("number", options.output_format),
+            cardinality,
+        ),
+        FieldMappingType::Bool(_, cardinality) => (json!({"type": "boolean"}), cardinality),
+        FieldMappingType::DateTime(options, cardinality) => {
+            let value_schema = match options.output_format.as_str() {
+                "rfc3339" | "iso8601" => json!({"type": "string", "format": "date-time"}),
+                output_format if output_format.starts_with("unix_timestamp") => {
+                    json!({"type": "integer"})
+                }
+                _ => json!({"type": "string"}),
+            };
+            (value_schema, cardinality)
+        }
+        FieldMappingType::IpAddr(_, cardinality) => {
+            let value_schema = json!({
+                "type": "string",
+                "anyOf": [{"format": "ipv4"}, {"format": "ipv6"}],
+            });
+            (value_schema, cardinality)
+        }
+        FieldMappingType::Bytes(options, cardinality) => {
+            let content_encoding = match options.output_format {
+                BinaryFormat::Base64 => "base64",
+                BinaryFormat::Hex => "base16",
+            };
+            let value_schema = json!({"type": "string", "contentEncoding": content_encoding});
+            (value_schema, cardinality)
+        }
+        FieldMappingType::Json(_, cardinality) => (json!({"type": "object"}), cardinality),
+        FieldMappingType::Object(options) => {
+            let value_schema = json_schema_object(
+                &options.field_mappings,
+                path,
+                is_dynamic,
+                unsupported_features,
+            );
+            return Some(value_schema);
+        }
+        FieldMappingType::Concatenate(_) => {
+            flag(
+                unsupported_features,
+                path,
+                "concatenate fields are not part of the documents: omitted",
+            );
+            return None;
+        }
+    };
+    if *cardinality == Cardinality::MultiValued {
+        return Some(json!({"type": "array", "items": value_schema}));
+    }
+    Some(value_schema)
+}
+
+fn json_schema_number(number_type: &str, output_format: NumericOutputFormat) -> JsonValue {
+    match output_format {
+        NumericOutputFormat::Number => json!({"type": number_type}),
+        NumericOutputFormat::String => json!({"type": "string"}),
+    }
+}
+
+pub(super) fn build_avro_schema(
+    index_id: &str,
+    doc_mapping: &DocMapping,
+    unsupported_features: &mut Vec<UnsupportedFeature>,
+) -> JsonValue {
+    if matches!(doc_mapping.mode, Mode::Dynamic(_)) {
+        unsupported_features.push(UnsupportedFeature {
+            field: None,
+            reason: "the unmapped fields returned in dynamic mode cannot be described by an Avro \
+                     record: omitted"
+                .to_string(),
+        });
+    }
+    let mut schema = avro_record(
+        &to_avro_name(index_id),
+        &doc_mapping.field_mappings,
+        "",
+        unsupported_features,
+    );
+    schema["namespace"] = json!("quickwit");
+    schema
+}
+
+fn avro_record(
+    record_name: &str,
+    field_mappings: &[FieldMappingEntry],
+    parent_path: &str,
+    unsupported_features: &mut Vec<UnsupportedFeature>,
+) -> JsonValue {
+    let mut fields = Vec::with_capacity(field_mappings.len());
+
+    for field_mapping in field_mappings {
+        let path = field_path(parent_path, &field_mapping.name);
+        // Avro record names must be unique within a schema, so nested records are named after
+        // their path.
+        let nested_record_name = format!("{record_name}_{}", to_avro_name(&field_mapping.name));
+
+        let Some(field_type) = avro_field_type(
+            &field_mapping.mapping_type,
+            &path,
+            &nested_record_name,
+            unsupported_features,
+        ) else {
+            continue;
+        };
+        if to_avro_name(&field_mapping.name) != field_mapping.name {
+            flag(
+                unsupported_features,
+                &path,
+                "the field name is not a valid Avro name",
+            );
+        }
+        // Documents may lack any field.
+        fields.push(json!({
+            "name": field_mapping.name,
+            "type": ["null", field_type],
+            "default": null,
+        }));
+    }
+    json!({
+        "type": "record",
+        "name": record_name,
+        "fields": fields,
+    })
+}
+
+fn avro_field_type(
+    mapping_type: &FieldMappingType,
+    path: &str,
+    record_name: &str,
+    unsupported_features: &mut Vec<UnsupportedFeature>,
+) -> Option<JsonValue> {
+    let (value_type, cardinality) = match mapping_type {
+        FieldMappingType::Text(_, cardinality) => (json!("string"), cardinality),
+        FieldMappingType::I64(options, cardinality) => {
+            (avro_number("long", options.output_format), cardinality)
+        }
+        FieldMappingType::U64(options, cardinality) => {
+            if options.output_format == NumericOutputFormat::Number {
+                flag(
+                    unsupported_features,
+                    path,
+                    "Avro has no unsigned integer type: values above 2^63 - 1 overflow `long`",
+                );
+            }
+            (avro_number("long", options.output_format), cardinality)
+        }
+        FieldMappingType::F64(options, cardinality) => {
+            (avro_number("double", options.output_format), cardinality)
+        }
+        FieldMappingType::Bool(_, cardinality) => (json!("boolean"), cardinality),
+        FieldMappingType::DateTime(options, cardinality) => {
+            let logical_type_opt = match options.output_format.as_str() {
+                "unix_timestamp_millis" => Some("timestamp-millis"),
+                "unix_timestamp_micros" => Some("timestamp-micros"),
+                "unix_timestamp_nanos" => Some("timestamp-nanos"),
+                _ => None,
+            };
+            let value_type = match (logical_type_opt, options.output_format.as_str()) {
+                (Some(logical_type), _) => json!({"type": "long", "logicalType": logical_type}),
+                (None, "unix_timestamp_secs") => json!("long"),
+                (None, _) => json!("string"),
+            };
+            (value_type, cardinality)
+        }
+        FieldMappingType::IpAddr(_, cardinality) => (json!("string"), cardinality),
+        FieldMappingType::Bytes(_, cardinality) => (json!("bytes"), cardinality),
+        FieldMappingType::Json(_, cardinality) => {
+            flag(
+                unsupported_features,
+                path,
+                "JSON fields hold arbitrary objects, which Avro cannot describe: typed as \
+                 JSON-encoded strings",
+            );
+            (json!("string"), cardinality)
+        }
+        FieldMappingType::Object(options) => {
+            let record = avro_record(
+                record_name,
+                &options.field_mappings,
+                path,
+                unsupported_features,
+            );
+            return Some(record);
+        }
+        FieldMappingType::Concatenate(_) => {
+            flag(
+                unsupported_features,
+                path,
+                "concatenate fields are not part of the documents: omitted",
+            );
+            return None;
+        }
+    };
+    if *cardinality == Cardinality::MultiValued {
+        return Some(json!({"type": "array", "items": value_type}));
+    }
+    Some(value_type)
+}
+
+fn avro_number(number_type: &str, output_format: NumericOutputFormat) -> JsonValue {
+    match output_format {
+        NumericOutputFormat::Number => json!(number_type),
+        NumericOutputFormat::String => json!("string"),
+    }
+}
+
+/// Replaces the characters not allowed in Avro names with `_`.
+fn to_avro_name(name: &str) -> String {
+    let mut avro_name: String = name
+        .chars()
+        .map(|character| {
+            if character.is_ascii_alphanumeric() || character == '_' {
+                character
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let is_valid_first_character =
+        |character: char| character.is_ascii_alphabetic() || character == '_';
+
+    if !avro_name.starts_with(is_valid_first_character) {
+        avro_name.insert(0, '_');
+    }
+    avro_name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_avro_name() {
+        assert_eq!(to_avro_name("service_name"), "service_name");
+        assert_eq!(to_avro_name("service.name"), "service_name");
+        assert_eq!(to_avro_name("@timestamp"), "_timestamp");
+        assert_eq!(to_avro_name("1st-field"), "_1st_field");
+    }
+}
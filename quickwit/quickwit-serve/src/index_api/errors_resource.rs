@@ -0,0 +1,172 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use quickwit_metastore::IndexMetadataResponseExt;
+use quickwit_proto::metastore::{
+    IndexMetadataRequest, MetastoreResult, MetastoreService, MetastoreServiceClient,
+};
+use quickwit_proto::types::IndexId;
+use serde::{Deserialize, Serialize};
+use warp::{Filter, Rejection};
+
+use crate::format::extract_format_from_qs;
+use crate::recent_errors::{IndexErrorEvent, recent_errors};
+use crate::request_scope::ensure_indexes_in_scope;
+use crate::rest_api_response::into_rest_api_response;
+use crate::with_arg;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct IndexErrorsResponse {
+    pub index_id: IndexId,
+    /// Recent errors of the index, from the oldest to the newest.
+    pub errors: Vec<IndexErrorEvent>,
+}
+
+pub fn get_index_errors_handler(
+    metastore: MetastoreServiceClient,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("indexes" / String / "_errors")
+        .and(warp::get())
+        .and(with_arg(metastore))
+        .then(get_index_errors)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+        .boxed()
+}
+
+#[utoipa::path(
+    get,
+    tag = "Indexes",
+    path = "/indexes/{index_id}/_errors",
+    responses(
+        (status = 200, description = "Successfully fetched the recent errors of the index.", body = IndexErrorsResponse)
+    ),
+    params(
+        ("index_id" = String, Path, description = "The index ID to fetch the recent errors of."),
+    )
+)]
+/// Returns the recent ingest and search errors of an index.
+///
+/// The errors are kept in memory by each node, so only the errors of the requests served by the
+/// node receiving this request are returned.
+pub async fn get_index_errors(
+    index_id: IndexId,
+    metastore: MetastoreServiceClient,
+) -> MetastoreResult<IndexErrorsResponse> {
+    ensure_indexes_in_scope([index_id.as_str()])?;
+
+    let index_metadata_request = IndexMetadataRequest::for_index_id(index_id);
+    let index_metadata = metastore
+        .index_metadata(index_metadata_request)
+        .await?
+        .deserialize_index_metadata()?;
+    let errors = recent_errors().get(&index_metadata.index_config.index_id);
+
+    Ok(IndexErrorsResponse {
+        index_id: index_metadata.index_config.index_id,
+        errors,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use quickwit_config::{IndexConfig, IngestApiConfig, NodeConfig};
+    use quickwit_index_management::IndexService;
+    use quickwit_ingest::IngestServiceClient;
+    use quickwit_metastore::{CreateIndexRequestExt, metastore_for_test};
+    use quickwit_proto::ingest::router::{
+        IngestResponseV2, IngestRouterServiceClient, IngestSuccess, MockIngestRouterService,
+    };
+    use quickwit_proto::ingest::{ParseFailure, ParseFailureReason};
+    use quickwit_proto::metastore::CreateIndexRequest;
+    use quickwit_search::MockSearchService;
+    use quickwit_storage::StorageResolver;
+
+    use super::*;
+    use crate::index_api::index_management_handlers;
+    use crate::ingest_api::ingest_api_handlers;
+    use crate::recent_errors::{IndexErrorReason, IndexErrorType};
+
+    #[tokio::test]
+    async fn test_get_index_errors_returns_mapping_errors() {
+        let metastore = metastore_for_test();
+        let index_config =
+            IndexConfig::for_test("test-index-errors", "ram:///indexes/test-index-errors");
+        let create_index_request =
+            CreateIndexRequest::try_from_index_config(&index_config).unwrap();
+        metastore.create_index(create_index_request).await.unwrap();
+
+        let mut mock_ingest_router = MockIngestRouterService::new();
+        mock_ingest_router.expect_ingest().returning(|request| {
+            let doc_batch = request.subrequests[0].doc_batch.as_ref().unwrap();
+            let parse_failures = doc_batch
+                .docs()
+                .map(|(doc_uid, _)| ParseFailure {
+                    doc_uid: Some(doc_uid),
+                    reason: ParseFailureReason::InvalidSchema as i32,
+                    message: "the field `id` could not be parsed: got \"one\"".to_string(),
+                })
+                .collect();
+            Ok(IngestResponseV2 {
+                successes: vec![IngestSuccess {
+                    parse_failures,
+                    ..Default::default()
+                }],
+                failures: Vec::new(),
+            })
+        });
+        let ingest_api_handlers = ingest_api_handlers(
+            IngestRouterServiceClient::from_mock(mock_ingest_router),
+            IngestServiceClient::mocked(),
+            metastore.clone(),
+            Arc::new(MockSearchService::new()),
+            IngestApiConfig::default(),
+            None,
+            false,
+            true,
+        );
+        let resp = warp::test::request()
+            .path("/test-index-errors/ingest")
+            .method("POST")
+            .body(r#"{"id": "one"}"#)
+            .reply(&ingest_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 200);
+
+        let index_service = IndexService::new(metastore, StorageResolver::unconfigured());
+        let index_management_handler =
+            index_management_handlers(index_service, Arc::new(NodeConfig::for_test()));
+        let resp = warp::test::request()
+            .path("/indexes/test-index-errors/_errors")
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let index_errors_response: IndexErrorsResponse =
+            serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(index_errors_response.index_id, "test-index-errors");
+
+        let errors = &index_errors_response.errors;
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].error_type, IndexErrorType::Mapping);
+        assert_eq!(errors[0].reason, IndexErrorReason::InvalidSchema);
+
+        let resp = warp::test::request()
+            .path("/indexes/unknown-index/_errors")
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 404);
+    }
+}
@@ -0,0 +1,337 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use itertools::Itertools;
+use quickwit_config::{IndexingPauseMode, validate_identifier};
+use quickwit_ingest::IngestServiceError;
+use quickwit_metastore::{IndexMetadata, ListIndexesMetadataResponseExt};
+use quickwit_proto::metastore::{
+    ListIndexesMetadataRequest, MetastoreService, MetastoreServiceClient,
+};
+use quickwit_proto::types::IndexId;
+
+use crate::request_scope::ensure_indexes_in_scope;
+
+/// Duration for which the metadata of a writable index is reused by the ingest requests. A write
+/// block or an indexing pause set on another node takes effect on this node within this delay.
+const WRITABLE_INDEX_METADATA_TTL: Duration = Duration::from_secs(5);
+
+/// Caches the metadata of the indexes found writable by [`ensure_indexes_writable`], so that the
+/// ingest requests do not each fetch it from the metastore, along with the write aliases of the
+/// indexes.
+///
+/// The metadata of blocked or paused indexes is not cached, so lifting a block or a pause takes
+/// effect immediately.
+#[derive(Clone)]
+pub(crate) struct IndexMetadataCache {
+    metastore: MetastoreServiceClient,
+    writable_indexes_metadata: Arc<Mutex<HashMap<IndexId, (Instant, IndexMetadata)>>>,
+    /// IDs of the indexes holding the write aliases, keyed by alias.
+    write_aliases: Arc<Mutex<Option<(Instant, HashMap<String, IndexId>)>>>,
+}
+
+impl IndexMetadataCache {
+    pub fn new(metastore: MetastoreServiceClient) -> Self {
+        Self {
+            metastore,
+            writable_indexes_metadata: Arc::default(),
+            write_aliases: Arc::default(),
+        }
+    }
+
+    /// Returns the ID of the index holding the write alias `index_id`, or `index_id` itself if it
+    /// is not a write alias. An index ID takes precedence over a write alias with the same value.
+    ///
+    /// The write aliases are fetched only for the IDs missing from the cache of the writable
+    /// indexes, at most once per [`WRITABLE_INDEX_METADATA_TTL`], so a rollover takes effect on
+    /// this node within this delay.
+    pub async fn resolve_write_alias(
+        &self,
+        index_id: IndexId,
+    ) -> Result<IndexId, IngestServiceError> {
+        if self.get(std::slice::from_ref(&index_id)).is_some() {
+            return Ok(index_id);
+        }
+        if let Some((fetched_at, write_aliases)) = &*self
+            .write_aliases
+            .lock()
+            .expect("lock should not be poisoned")
+            && fetched_at.elapsed() < WRITABLE_INDEX_METADATA_TTL
+        {
+            return Ok(write_aliases.get(&index_id).cloned().unwrap_or(index_id));
+        }
+        let indexes_metadata = self
+            .metastore
+            .list_indexes_metadata(ListIndexesMetadataRequest::all())
+            .await
+            .map_err(|error| IngestServiceError::Unavailable(error.to_string()))?
+            .deserialize_indexes_metadata()
+            .await
+            .map_err(|error| IngestServiceError::Internal(error.to_string()))?;
+        let write_aliases = write_aliases(indexes_metadata);
+        let resolved_index_id = write_aliases.get(&index_id).cloned().unwrap_or(index_id);
+
+        *self
+            .write_aliases
+            .lock()
+            .expect("lock should not be poisoned") = Some((Instant::now(), write_aliases));
+        Ok(resolved_index_id)
+    }
+
+    pub fn metastore(&self) -> &MetastoreServiceClient {
+        &self.metastore
+    }
+
+    /// Returns the cached metadata of the indexes, unless one of them is missing or expired.
+    fn get(&self, index_ids: &[IndexId]) -> Option<Vec<IndexMetadata>> {
+        let writable_indexes_metadata = self
+            .writable_indexes_metadata
+            .lock()
+            .expect("lock should not be poisoned");
+        index_ids
+            .iter()
+            .map(|index_id| {
+                writable_indexes_metadata
+                    .get(index_id)
+                    .filter(|(cached_at, _)| cached_at.elapsed() < WRITABLE_INDEX_METADATA_TTL)
+                    .map(|(_, index_metadata)| index_metadata.clone())
+            })
+            .collect()
+    }
+
+    fn insert(&self, indexes_metadata: &[IndexMetadata]) {
+        let now = Instant::now();
+        let mut writable_indexes_metadata = self
+            .writable_indexes_metadata
+            .lock()
+            .expect("lock should not be poisoned");
+        // Evicts the expired entries, so that the deleted indexes are eventually forgotten.
+        writable_indexes_metadata.retain(|_, (cached_at, _)| {
+            now.duration_since(*cached_at) < WRITABLE_INDEX_METADATA_TTL
+        });
+
+        for index_metadata in indexes_metadata {
+            writable_indexes_metadata.insert(
+                index_metadata.index_id().to_string(),
+                (now, index_metadata.clone()),
+            );
+        }
+    }
+}
+
+/// Maps the write aliases to the IDs of the indexes holding them. An alias held by several indexes,
+/// for instance during a rollover, maps to the most recently created one.
+fn write_aliases(indexes_metadata: Vec<IndexMetadata>) -> HashMap<String, IndexId> {
+    let index_ids: HashSet<&str> = indexes_metadata
+        .iter()
+        .map(|index_metadata| index_metadata.index_id())
+        .collect();
+    let mut write_aliases: HashMap<String, (i64, IndexId)> = HashMap::new();
+
+    for index_metadata in &indexes_metadata {
+        let Some(write_alias) = &index_metadata.index_config.ingest_settings.write_alias else {
+            continue;
+        };
+        if index_ids.contains(write_alias.as_str()) {
+            continue;
+        }
+        let create_timestamp = index_metadata.create_timestamp;
+        let index_id = index_metadata.index_id().to_string();
+
+        match write_aliases.entry(write_alias.clone()) {
+            Entry::Occupied(mut entry) if entry.get().0 < create_timestamp => {
+                entry.insert((create_timestamp, index_id));
+            }
+            Entry::Occupied(_) => {}
+            Entry::Vacant(entry) => {
+                entry.insert((create_timestamp, index_id));
+            }
+        }
+    }
+    write_aliases
+        .into_iter()
+        .map(|(write_alias, (_, index_id))| (write_alias, index_id))
+        .collect()
+}
+
+/// Rejects the request if one of the target indexes is write-blocked, paused in `reject` mode, or
+/// outside of the scope of the token of the request. Otherwise, returns the metadata of the target
+/// indexes so callers can inspect their ingest settings.
+///
+/// The metadata of the writable indexes is read from the cache when it is fresh enough. Invalid or
+/// missing indexes are ignored: the ingest path reports them itself.
+pub(crate) async fn ensure_indexes_writable(
+    index_metadata_cache: &IndexMetadataCache,
+    index_ids: impl IntoIterator<Item = IndexId>,
+) -> Result<Vec<IndexMetadata>, IngestServiceError> {
+    let index_id_patterns: Vec<IndexId> = index_ids
+        .into_iter()
+        .filter(|index_id| validate_identifier("", index_id).is_ok())
+        .unique()
+        .collect();
+
+    if index_id_patterns.is_empty() {
+        return Ok(Vec::new());
+    }
+    ensure_indexes_in_scope(index_id_patterns.iter().map(|index_id| index_id.as_str()))?;
+
+    if let Some(indexes_metadata) = index_metadata_cache.get(&index_id_patterns) {
+        return Ok(indexes_metadata);
+    }
+    let list_indexes_metadata_request = ListIndexesMetadataRequest {
+        index_id_patterns,
+        paused_only: false,
+    };
+    let indexes_metadata = index_metadata_cache
+        .metastore
+        .list_indexes_metadata(list_indexes_metadata_request)
+        .await
+        .map_err(|error| IngestServiceError::Unavailable(error.to_string()))?
+        .deserialize_indexes_metadata()
+        .await
+        .map_err(|error| IngestServiceError::Internal(error.to_string()))?;
+
+    let write_blocked_index_ids = indexes_metadata
+        .iter()
+        .filter(|index_metadata| index_metadata.index_config.ingest_settings.write_block)
+        .map(|index_metadata| index_metadata.index_id())
+        .join(", ");
+
+    if !write_blocked_index_ids.is_empty() {
+        return Err(IngestServiceError::Forbidden(format!(
+            "index(es) `{write_blocked_index_ids}` blocked for writes"
+        )));
+    }
+    let paused_index_ids = indexes_metadata
+        .iter()
+        .filter(|index_metadata| {
+            index_metadata.index_config.ingest_settings.indexing_pause
+                == Some(IndexingPauseMode::Reject)
+        })
+        .map(|index_metadata| index_metadata.index_id())
+        .join(", ");
+
+    if !paused_index_ids.is_empty() {
+        return Err(IngestServiceError::Unavailable(format!(
+            "indexing of index(es) `{paused_index_ids}` is paused"
+        )));
+    }
+    index_metadata_cache.insert(&indexes_metadata);
+    Ok(indexes_metadata)
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_ingest::IngestServiceError;
+    use quickwit_metastore::{IndexMetadata, ListIndexesMetadataResponseExt};
+    use quickwit_proto::metastore::{
+        ListIndexesMetadataResponse, MetastoreServiceClient, MockMetastoreService,
+    };
+
+    use super::{IndexMetadataCache, ensure_indexes_writable};
+
+    #[tokio::test]
+    async fn test_ensure_indexes_writable_caches_writable_indexes_metadata() {
+        let mut mock_metastore = MockMetastoreService::new();
+        mock_metastore
+            .expect_list_indexes_metadata()
+            .times(1)
+            .returning(|_| {
+                let index_metadata =
+                    IndexMetadata::for_test("test-index", "ram:///indexes/test-index");
+                Ok(ListIndexesMetadataResponse::for_test(vec![index_metadata]))
+            });
+        let index_metadata_cache =
+            IndexMetadataCache::new(MetastoreServiceClient::from_mock(mock_metastore));
+
+        for _ in 0..2 {
+            let indexes_metadata =
+                ensure_indexes_writable(&index_metadata_cache, ["test-index".to_string()])
+                    .await
+                    .unwrap();
+            assert_eq!(indexes_metadata.len(), 1);
+            assert_eq!(indexes_metadata[0].index_id(), "test-index");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ensure_indexes_writable_does_not_cache_blocked_indexes_metadata() {
+        let mut mock_metastore = MockMetastoreService::new();
+        mock_metastore
+            .expect_list_indexes_metadata()
+            .times(2)
+            .returning(|_| {
+                let mut index_metadata =
+                    IndexMetadata::for_test("test-index", "ram:///indexes/test-index");
+                index_metadata.index_config.ingest_settings.write_block = true;
+                Ok(ListIndexesMetadataResponse::for_test(vec![index_metadata]))
+            });
+        let index_metadata_cache =
+            IndexMetadataCache::new(MetastoreServiceClient::from_mock(mock_metastore));
+
+        for _ in 0..2 {
+            let ingest_error =
+                ensure_indexes_writable(&index_metadata_cache, ["test-index".to_string()])
+                    .await
+                    .unwrap_err();
+            assert!(matches!(ingest_error, IngestServiceError::Forbidden(_)));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resolve_write_alias() {
+        let mut mock_metastore = MockMetastoreService::new();
+        mock_metastore
+            .expect_list_indexes_metadata()
+            .times(1)
+            .returning(|_| {
+                let mut indexes_metadata = Vec::new();
+
+                for (index_id, create_timestamp) in [("logs-000001", 1), ("logs-000002", 2)] {
+                    let mut index_metadata =
+                        IndexMetadata::for_test(index_id, &format!("ram:///indexes/{index_id}"));
+                    index_metadata.create_timestamp = create_timestamp;
+                    index_metadata.index_config.ingest_settings.write_alias =
+                        Some("logs".to_string());
+                    indexes_metadata.push(index_metadata);
+                }
+                // An index ID takes precedence over a write alias.
+                let mut index_metadata = IndexMetadata::for_test("traces", "ram:///indexes/traces");
+                index_metadata.index_config.ingest_settings.write_alias =
+                    Some("logs-000001".to_string());
+                indexes_metadata.push(index_metadata);
+
+                Ok(ListIndexesMetadataResponse::for_test(indexes_metadata))
+            });
+        let index_metadata_cache =
+            IndexMetadataCache::new(MetastoreServiceClient::from_mock(mock_metastore));
+
+        for (index_id, expected_index_id) in [
+            ("logs", "logs-000002"),
+            ("logs-000001", "logs-000001"),
+            ("unknown", "unknown"),
+        ] {
+            let resolved_index_id = index_metadata_cache
+                .resolve_write_alias(index_id.to_string())
+                .await
+                .unwrap();
+            assert_eq!(resolved_index_id, expected_index_id);
+        }
+    }
+}
@@ -35,6 +35,10 @@ use warp::{Filter, Rejection};
 
 use super::rest_handler::log_failure;
 use crate::format::{extract_config_format, extract_format_from_qs};
+use crate::json_projection::with_json_projection;
+use crate::query_params::query_params;
+use crate::recent_errors::recent_errors;
+use crate::request_scope::ensure_indexes_in_scope;
 use crate::rest_api_response::into_rest_api_response;
 use crate::simple_list::from_simple_list;
 use crate::with_arg;
@@ -42,13 +46,15 @@ use crate::with_arg;
 pub fn get_index_metadata_handler(
     metastore: MetastoreServiceClient,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
-    warp::path!("indexes" / String)
-        .and(warp::get())
-        .and(with_arg(metastore))
-        .then(get_index_metadata)
-        .and(extract_format_from_qs())
-        .map(into_rest_api_response)
-        .boxed()
+    with_json_projection(
+        warp::path!("indexes" / String)
+            .and(warp::get())
+            .and(with_arg(metastore))
+            .then(get_index_metadata)
+            .and(extract_format_from_qs())
+            .map(into_rest_api_response),
+    )
+    .boxed()
 }
 
 pub async fn get_index_metadata(
@@ -56,6 +62,7 @@ pub async fn get_index_metadata(
     metastore: MetastoreServiceClient,
 ) -> MetastoreResult<IndexMetadata> {
     info!(index_id = %index_id, "get-index-metadata");
+    ensure_indexes_in_scope([index_id.as_str()])?;
     let index_metadata_request = IndexMetadataRequest::for_index_id(index_id.to_string());
     let index_metadata = metastore
         .index_metadata(index_metadata_request)
@@ -80,7 +87,7 @@ pub fn list_indexes_metadata_handler(
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
     warp::path!("indexes")
         .and(warp::get())
-        .and(warp::query())
+        .and(query_params())
         .and(with_arg(metastore))
         .then(list_indexes_metadata)
         .and(extract_format_from_qs())
@@ -121,6 +128,7 @@ pub async fn describe_index(
     index_id: IndexId,
     metastore: MetastoreServiceClient,
 ) -> MetastoreResult<IndexStats> {
+    ensure_indexes_in_scope([index_id.as_str()])?;
     let index_metadata_request = IndexMetadataRequest::for_index_id(index_id.to_string());
     let index_metadata = metastore
         .index_metadata(index_metadata_request)
@@ -213,15 +221,22 @@ pub async fn list_indexes_metadata(
                     }
                 })?;
             }
-            ListIndexesMetadataRequest { index_id_patterns }
+            ListIndexesMetadataRequest {
+                index_id_patterns,
+                paused_only: false,
+            }
         } else {
             ListIndexesMetadataRequest::all()
         };
-    metastore
+    let mut indexes_metadata = metastore
         .list_indexes_metadata(list_indexes_metata_request)
         .await?
         .deserialize_indexes_metadata()
-        .await
+        .await?;
+    // The indexes outside of the scope of the token of the request are hidden.
+    indexes_metadata
+        .retain(|index_metadata| ensure_indexes_in_scope([index_metadata.index_id()]).is_ok());
+    Ok(indexes_metadata)
 }
 
 #[derive(Deserialize, utoipa::IntoParams, utoipa::ToSchema)]
@@ -237,7 +252,7 @@ pub fn create_index_handler(
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
     warp::path!("indexes")
         .and(warp::post())
-        .and(warp::query())
+        .and(query_params())
         .and(extract_config_format())
         .and(warp::body::content_length_limit(1024 * 1024))
         .and(warp::filters::body::bytes())
@@ -277,6 +292,7 @@ pub async fn create_index(
         &node_config.default_index_root_uri,
     )
     .map_err(IndexServiceError::InvalidConfig)?;
+    ensure_indexes_in_scope([index_config.index_id.as_str()]).map_err(MetastoreError::from)?;
     info!(index_id = %index_config.index_id, overwrite = create_index_query_params.overwrite, "create-index");
     index_service
         .create_index(index_config, create_index_query_params.overwrite)
@@ -293,7 +309,7 @@ pub struct UpdateQueryParams {
 }
 
 fn update_index_qp() -> impl Filter<Extract = (UpdateQueryParams,), Error = Rejection> + Clone {
-    warp::query::<UpdateQueryParams>()
+    query_params::<UpdateQueryParams>()
 }
 
 pub fn update_index_handler(
@@ -345,6 +361,7 @@ pub async fn update_index(
     node_config: Arc<NodeConfig>,
 ) -> Result<IndexMetadata, IndexServiceError> {
     info!(index_id = %target_index_id, "update-index");
+    ensure_indexes_in_scope([target_index_id.as_str()]).map_err(MetastoreError::from)?;
 
     let metastore = index_service.metastore();
     let index_metadata_request = IndexMetadataRequest::for_index_id(target_index_id.to_string());
@@ -427,6 +444,7 @@ pub async fn clear_index(
     mut index_service: IndexService,
 ) -> Result<(), IndexServiceError> {
     info!(index_id = %index_id, "clear-index");
+    ensure_indexes_in_scope([index_id.as_str()]).map_err(MetastoreError::from)?;
     index_service.clear_index(&index_id).await
 }
 
@@ -442,7 +460,7 @@ pub fn delete_index_handler(
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
     warp::path!("indexes" / String)
         .and(warp::delete())
-        .and(warp::query())
+        .and(query_params())
         .and(with_arg(index_service))
         .then(delete_index)
         .and(extract_format_from_qs())
@@ -470,7 +488,12 @@ pub async fn delete_index(
     mut index_service: IndexService,
 ) -> Result<Vec<SplitInfo>, IndexServiceError> {
     info!(index_id = %index_id, dry_run = delete_index_query_param.dry_run, "delete-index");
-    index_service
+    ensure_indexes_in_scope([index_id.as_str()]).map_err(MetastoreError::from)?;
+    let split_infos = index_service
         .delete_index(&index_id, delete_index_query_param.dry_run)
-        .await
+        .await?;
+    if !delete_index_query_param.dry_run {
+        recent_errors().evict(&index_id);
+    }
+    Ok(split_infos)
 }
@@ -0,0 +1,209 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use quickwit_metastore::IndexMetadataResponseExt;
+use quickwit_proto::metastore::{
+    IndexMetadataRequest, MetastoreResult, MetastoreService, MetastoreServiceClient,
+};
+use quickwit_proto::types::IndexId;
+use tracing::info;
+use warp::{Filter, Rejection};
+
+use crate::elasticsearch_api::model::ElasticsearchMappingsResponse;
+use crate::format::extract_format_from_qs;
+use crate::request_scope::ensure_indexes_in_scope;
+use crate::rest_api_response::into_rest_api_response;
+use crate::with_arg;
+
+pub fn get_index_mapping_handler(
+    metastore: MetastoreServiceClient,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("indexes" / String / "mapping")
+        .and(warp::get())
+        .and(with_arg(metastore))
+        .then(get_index_mapping)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+        .boxed()
+}
+
+#[utoipa::path(
+    get,
+    tag = "Indexes",
+    path = "/indexes/{index_id}/mapping",
+    responses(
+        (status = 200, description = "Successfully fetched the field mappings of the index, in the Elasticsearch `_mapping` shape.")
+    ),
+    params(
+        ("index_id" = String, Path, description = "The index ID to fetch the field mappings of."),
+    )
+)]
+/// Returns the field mappings of an index in the Elasticsearch `GET /{index}/_mapping` shape:
+/// `{"<index_id>": {"mappings": {"properties": {...}}}}`.
+///
+/// Unlike the `_elastic` endpoint, only the fields declared in the doc mapping are reported.
+async fn get_index_mapping(
+    index_id: IndexId,
+    metastore: MetastoreServiceClient,
+) -> MetastoreResult<ElasticsearchMappingsResponse> {
+    info!(index_id = %index_id, "get-index-mapping");
+    ensure_indexes_in_scope([index_id.as_str()])?;
+    let index_metadata_request = IndexMetadataRequest::for_index_id(index_id);
+    let index_metadata = metastore
+        .index_metadata(index_metadata_request)
+        .await?
+        .deserialize_index_metadata()?;
+    let mappings_response =
+        ElasticsearchMappingsResponse::from_doc_mapping(vec![index_metadata], None);
+    Ok(mappings_response)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use quickwit_config::{DocMapping, NodeConfig};
+    use quickwit_index_management::IndexService;
+    use quickwit_metastore::{IndexMetadata, IndexMetadataResponseExt};
+    use quickwit_proto::metastore::{
+        IndexMetadataResponse, MetastoreServiceClient, MockMetastoreService,
+    };
+    use quickwit_proto::search::{ListFieldsEntry, ListFieldsResponse, ListFieldsType};
+    use quickwit_search::MockSearchService;
+    use quickwit_storage::StorageResolver;
+    use serde_json::{Value as JsonValue, json};
+    use warp::Filter;
+
+    use crate::elasticsearch_api::rest_handler::es_compat_index_mapping_handler;
+    use crate::index_api::index_management_handlers;
+    use crate::rest::recover_fn;
+
+    fn nested_index_metadata() -> IndexMetadata {
+        let mut index_metadata =
+            IndexMetadata::for_test("test-mapping", "ram:///indexes/test-mapping");
+        index_metadata.index_config.doc_mapping = serde_json::from_value::<DocMapping>(json!({
+            "mode": "dynamic",
+            "field_mappings": [
+                { "name": "timestamp", "type": "datetime", "fast": true },
+                { "name": "tags", "type": "array<text>", "tokenizer": "raw" },
+                { "name": "latencies", "type": "array<f64>" },
+                { "name": "attributes", "type": "json" },
+                {
+                    "name": "resource",
+                    "type": "object",
+                    "field_mappings": [
+                        { "name": "service", "type": "text" },
+                        {
+                            "name": "host",
+                            "type": "object",
+                            "field_mappings": [
+                                { "name": "ip", "type": "ip" },
+                                { "name": "ports", "type": "array<u64>" }
+                            ]
+                        }
+                    ]
+                }
+            ],
+            "timestamp_field": "timestamp"
+        }))
+        .unwrap();
+        index_metadata
+    }
+
+    fn expected_properties() -> JsonValue {
+        json!({
+            "timestamp": { "type": "date" },
+            "tags": { "type": "keyword" },
+            "latencies": { "type": "double" },
+            "attributes": { "type": "object" },
+            "resource": {
+                "type": "object",
+                "properties": {
+                    "service": { "type": "keyword" },
+                    "host": {
+                        "type": "object",
+                        "properties": {
+                            "ip": { "type": "ip" },
+                            "ports": { "type": "long" }
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    fn mock_metastore() -> MetastoreServiceClient {
+        let mut mock_metastore = MockMetastoreService::new();
+        mock_metastore.expect_index_metadata().return_once(|_| {
+            Ok(IndexMetadataResponse::try_from_index_metadata(&nested_index_metadata()).unwrap())
+        });
+        MetastoreServiceClient::from_mock(mock_metastore)
+    }
+
+    #[tokio::test]
+    async fn test_get_index_mapping_has_es_shape() {
+        let index_service = IndexService::new(mock_metastore(), StorageResolver::unconfigured());
+        let index_management_handler =
+            index_management_handlers(index_service, Arc::new(NodeConfig::for_test()))
+                .recover(recover_fn);
+        let resp = warp::test::request()
+            .path("/indexes/test-mapping/mapping")
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        let expected_resp_json = json!({
+            "test-mapping": {
+                "mappings": {
+                    "properties": expected_properties()
+                }
+            }
+        });
+        assert_eq!(resp_json, expected_resp_json);
+    }
+
+    #[tokio::test]
+    async fn test_es_compat_index_mapping_has_es_shape() {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_list_fields()
+            .return_once(|_| {
+                Ok(ListFieldsResponse {
+                    entries: vec![ListFieldsEntry {
+                        field_name: "attributes.status".to_string(),
+                        field_type: ListFieldsType::U64 as i32,
+                        ..Default::default()
+                    }],
+                })
+            });
+        let es_mapping_handler =
+            es_compat_index_mapping_handler(mock_metastore(), Arc::new(mock_search_service));
+        let resp = warp::test::request()
+            .path("/_elastic/test-mapping/_mapping")
+            .reply(&es_mapping_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        let mut properties_with_dynamic_fields = expected_properties();
+        properties_with_dynamic_fields["attributes.status"] = json!({ "type": "long" });
+        let expected_resp_json = json!({
+            "test-mapping": {
+                "mappings": {
+                    "properties": properties_with_dynamic_fields
+                }
+            }
+        });
+        assert_eq!(resp_json, expected_resp_json);
+    }
+}
@@ -0,0 +1,351 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use futures::StreamExt;
+use futures::stream::FuturesUnordered;
+use quickwit_cluster::{Cluster, ClusterNode};
+use quickwit_config::merge_policy_config::MergePolicyConfig;
+use quickwit_config::service::QuickwitService;
+use quickwit_index_management::IndexServiceError;
+use quickwit_indexing::actors::{MergeOperationState, MergeOperationStatus};
+use quickwit_metastore::IndexMetadataResponseExt;
+use quickwit_proto::developer::{DeveloperService, ListMergeOperationsRequest};
+use quickwit_proto::metastore::{
+    IndexMetadataRequest, MetastoreError, MetastoreService, MetastoreServiceClient,
+};
+use quickwit_proto::types::IndexId;
+use serde::Serialize;
+use tokio::time::timeout;
+use tracing::{info, warn};
+use warp::{Filter, Rejection};
+
+use crate::cluster_api::{DeveloperClientFactory, developer_client};
+use crate::format::extract_format_from_qs;
+use crate::request_scope::ensure_indexes_in_scope;
+use crate::rest_api_response::into_rest_api_response;
+use crate::with_arg;
+
+const LIST_MERGE_OPERATIONS_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct IndexMergesResponse {
+    /// Number of documents above which a split is considered mature and no longer merged.
+    pub split_num_docs_target: usize,
+    /// The merge policy of the index, with its default parameters filled in.
+    pub merge_policy: MergePolicyConfig,
+    /// Number of live indexers asked for their merge operations.
+    pub num_indexers: usize,
+    /// Indexers that failed to list their merge operations in time, keyed by node ID, along with
+    /// the reason why.
+    pub unreachable_indexers: BTreeMap<String, String>,
+    /// The merge operations of the index pending or running on the indexers, running ones first.
+    pub merge_operations: Vec<IndexerMergeOperation>,
+}
+
+/// A merge operation, along with the indexer it is scheduled on.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct IndexerMergeOperation {
+    /// ID of the indexer the merge operation is scheduled on.
+    pub node_id: String,
+    #[serde(flatten)]
+    pub merge_operation: MergeOperationStatus,
+}
+
+pub fn get_index_merges_handler(
+    metastore: MetastoreServiceClient,
+    cluster: Cluster,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("indexes" / String / "merges")
+        .and(warp::get())
+        .and(with_arg(metastore))
+        .and(with_arg(cluster))
+        .then(|index_id, metastore, cluster| {
+            get_index_merges(index_id, metastore, cluster, developer_client)
+        })
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+        .boxed()
+}
+
+/// Returns the merge policy of an index and its merge operations pending or running on the
+/// indexers of the cluster.
+///
+/// Merges are planned and executed by the indexers, so the other nodes are not asked. Indexers that
+/// fail to answer in time are reported as unreachable.
+#[utoipa::path(
+    get,
+    tag = "Indexes",
+    path = "/indexes/{index_id}/merges",
+    responses(
+        (status = 200, description = "Successfully fetched the merges.", body = IndexMergesResponse)
+    ),
+    params(
+        ("index_id" = String, Path, description = "The index ID to get the merges of."),
+    )
+)]
+async fn get_index_merges(
+    index_id: IndexId,
+    metastore: MetastoreServiceClient,
+    cluster: Cluster,
+    developer_client_factory: DeveloperClientFactory,
+) -> Result<IndexMergesResponse, IndexServiceError> {
+    info!(index_id = %index_id, "get-index-merges");
+    ensure_indexes_in_scope([index_id.as_str()]).map_err(MetastoreError::from)?;
+    let index_metadata_request = IndexMetadataRequest::for_index_id(index_id);
+    let index_metadata = metastore
+        .index_metadata(index_metadata_request)
+        .await?
+        .deserialize_index_metadata()?;
+    let indexing_settings = &index_metadata.index_config.indexing_settings;
+
+    let indexers: Vec<ClusterNode> = cluster
+        .live_nodes()
+        .await
+        .into_iter()
+        .filter(|node| node.enabled_services.contains(&QuickwitService::Indexer))
+        .collect();
+    let num_indexers = indexers.len();
+    let mut list_merge_operations_futures = FuturesUnordered::new();
+
+    for indexer in indexers {
+        let client = developer_client_factory(&indexer);
+        let list_merge_operations_request = ListMergeOperationsRequest {
+            index_uid: index_metadata.index_uid.to_string(),
+        };
+        let list_merge_operations_future = async move {
+            let list_merge_operations_res = timeout(
+                LIST_MERGE_OPERATIONS_TIMEOUT,
+                client.list_merge_operations(list_merge_operations_request),
+            )
+            .await;
+            (indexer.node_id, list_merge_operations_res)
+        };
+        list_merge_operations_futures.push(list_merge_operations_future);
+    }
+    let mut merge_operations = Vec::new();
+    let mut unreachable_indexers = BTreeMap::new();
+
+    while let Some((node_id, list_merge_operations_res)) =
+        list_merge_operations_futures.next().await
+    {
+        let node_merge_operations_res = match list_merge_operations_res {
+            Ok(Ok(response)) => {
+                serde_json::from_slice::<Vec<MergeOperationStatus>>(&response.merge_operations_json)
+                    .map_err(|error| format!("failed to parse merge operations: {error}"))
+            }
+            Ok(Err(error)) => Err(error.to_string()),
+            Err(_elapsed) => Err("list merge operations request timed out".to_string()),
+        };
+        match node_merge_operations_res {
+            Ok(node_merge_operations) => {
+                merge_operations.extend(node_merge_operations.into_iter().map(|merge_operation| {
+                    IndexerMergeOperation {
+                        node_id: node_id.to_string(),
+                        merge_operation,
+                    }
+                }));
+            }
+            Err(error) => {
+                warn!(node_id=%node_id, %error, "failed to list merge operations");
+                unreachable_indexers.insert(node_id.to_string(), error);
+            }
+        }
+    }
+    // The sort is stable: the pending merge operations of an indexer stay in the order in which
+    // they will be scheduled.
+    merge_operations.sort_by(|left, right| {
+        let left_key = (
+            left.merge_operation.state != MergeOperationState::Running,
+            &left.node_id,
+        );
+        let right_key = (
+            right.merge_operation.state != MergeOperationState::Running,
+            &right.node_id,
+        );
+        left_key.cmp(&right_key)
+    });
+    let index_merges_response = IndexMergesResponse {
+        split_num_docs_target: indexing_settings.split_num_docs_target,
+        merge_policy: indexing_settings.merge_policy.clone(),
+        num_indexers,
+        unreachable_indexers,
+        merge_operations,
+    };
+    Ok(index_merges_response)
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use quickwit_cluster::{ChitchatTransport, create_cluster_for_test};
+    use quickwit_indexing::actors::MergePhase;
+    use quickwit_indexing::merge_policy::MergeOperationType;
+    use quickwit_metastore::IndexMetadata;
+    use quickwit_proto::developer::{
+        DeveloperError, DeveloperServiceClient, ListMergeOperationsResponse, MockDeveloperService,
+    };
+    use quickwit_proto::metastore::{IndexMetadataResponse, MockMetastoreService};
+    use quickwit_proto::types::IndexUid;
+    use serde_json::json;
+
+    use super::*;
+
+    fn merge_operation_for_test(
+        index_uid: IndexUid,
+        split_ids: [&str; 2],
+        phase: Option<MergePhase>,
+    ) -> MergeOperationStatus {
+        let state = if phase.is_some() {
+            MergeOperationState::Running
+        } else {
+            MergeOperationState::Pending
+        };
+        MergeOperationStatus {
+            index_uid,
+            source_id: "test-source".to_string(),
+            merge_split_id: format!("merge-{}", split_ids[0]),
+            operation_type: MergeOperationType::Merge,
+            state,
+            split_ids: split_ids.map(String::from).to_vec(),
+            num_docs: 20,
+            num_bytes: 4_000,
+            merge_level: 0,
+            running_secs: phase.map(|_| 12),
+            phase,
+        }
+    }
+
+    fn mock_developer_client(_node: &ClusterNode) -> DeveloperServiceClient {
+        let mut mock_developer_service = MockDeveloperService::new();
+        mock_developer_service
+            .expect_list_merge_operations()
+            .returning(|request| {
+                let index_uid: IndexUid = request.index_uid.parse().unwrap();
+                let merge_operations = vec![
+                    merge_operation_for_test(
+                        index_uid.clone(),
+                        ["split-1", "split-2"],
+                        Some(MergePhase::Uploading),
+                    ),
+                    merge_operation_for_test(index_uid, ["split-3", "split-4"], None),
+                ];
+                let merge_operations_json = serde_json::to_vec(&merge_operations).unwrap();
+                Ok(ListMergeOperationsResponse {
+                    merge_operations_json: Bytes::from(merge_operations_json),
+                })
+            });
+        DeveloperServiceClient::from_mock(mock_developer_service)
+    }
+
+    fn unreachable_developer_client(_node: &ClusterNode) -> DeveloperServiceClient {
+        let mut mock_developer_service = MockDeveloperService::new();
+        mock_developer_service
+            .expect_list_merge_operations()
+            .returning(|_| {
+                Err(DeveloperError::Unavailable(
+                    "connection refused".to_string(),
+                ))
+            });
+        DeveloperServiceClient::from_mock(mock_developer_service)
+    }
+
+    #[tokio::test]
+    async fn test_get_index_merges() {
+        let index_metadata = IndexMetadata::for_test("test-index", "ram:///indexes/test-index");
+        let index_uid = index_metadata.index_uid.clone();
+
+        let mut mock_metastore = MockMetastoreService::new();
+        mock_metastore
+            .expect_index_metadata()
+            .times(2)
+            .returning(move |_| {
+                Ok(IndexMetadataResponse::try_from_index_metadata(&index_metadata).unwrap())
+            });
+        let metastore = MetastoreServiceClient::from_mock(mock_metastore);
+
+        let transport = ChitchatTransport::default();
+        let searcher = create_cluster_for_test(Vec::new(), &["searcher"], &transport, true)
+            .await
+            .unwrap();
+        let peer_seeds = vec![searcher.gossip_listen_addr().to_string()];
+        let indexer = create_cluster_for_test(peer_seeds, &["indexer"], &transport, true)
+            .await
+            .unwrap();
+        searcher
+            .wait_for_ready_members(|members| members.len() == 2, Duration::from_secs(30))
+            .await
+            .unwrap();
+
+        let index_merges_response = get_index_merges(
+            "test-index".to_string(),
+            metastore.clone(),
+            searcher.clone(),
+            mock_developer_client,
+        )
+        .await
+        .unwrap();
+        let resp_json = serde_json::to_value(&index_merges_response).unwrap();
+
+        assert_eq!(resp_json["split_num_docs_target"], json!(10_000_000));
+        assert_eq!(resp_json["merge_policy"]["type"], json!("stable_log"));
+        assert_eq!(resp_json["merge_policy"]["merge_factor"], json!(10));
+        assert_eq!(
+            resp_json["merge_policy"]["maturation_period"],
+            json!("2days")
+        );
+        // Only the indexer is asked for its merge operations.
+        assert_eq!(resp_json["num_indexers"], json!(1));
+        assert_eq!(resp_json["unreachable_indexers"], json!({}));
+
+        let merge_operations = resp_json["merge_operations"].as_array().unwrap();
+        assert_eq!(merge_operations.len(), 2);
+
+        let indexer_node_id = indexer.self_node_id().to_string();
+        assert_eq!(merge_operations[0]["node_id"], json!(indexer_node_id));
+        assert_eq!(merge_operations[0]["state"], json!("running"));
+        assert_eq!(merge_operations[0]["phase"], json!("uploading"));
+        assert_eq!(
+            merge_operations[0]["split_ids"],
+            json!(["split-1", "split-2"])
+        );
+        assert_eq!(merge_operations[0]["running_secs"], json!(12));
+
+        assert_eq!(merge_operations[1]["node_id"], json!(indexer_node_id));
+        assert_eq!(merge_operations[1]["state"], json!("pending"));
+        assert_eq!(
+            merge_operations[1]["index_uid"],
+            json!(index_uid.to_string())
+        );
+        assert!(merge_operations[1].get("phase").is_none());
+        assert!(merge_operations[1].get("running_secs").is_none());
+
+        let index_merges_response = get_index_merges(
+            "test-index".to_string(),
+            metastore,
+            searcher,
+            unreachable_developer_client,
+        )
+        .await
+        .unwrap();
+        assert_eq!(index_merges_response.num_indexers, 1);
+        assert!(index_merges_response.merge_operations.is_empty());
+        assert!(
+            index_merges_response.unreachable_indexers[&indexer_node_id]
+                .contains("connection refused")
+        );
+    }
+}
@@ -12,11 +12,28 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod analyzers_resource;
+mod block_resource;
+mod doc_mapping_schema;
+mod errors_resource;
+mod index_metadata_cache;
 mod index_resource;
+mod mapping_resource;
+mod merge_resource;
+mod query_defaults_resource;
+mod query_history_resource;
 mod rest_handler;
+mod retention_preview_resource;
+mod rollover_resource;
+mod schema_resource;
 mod source_resource;
+mod split_download_resource;
+mod split_listing_etag;
 mod split_resource;
+mod validate_document_resource;
 
+pub(crate) use self::index_metadata_cache::{IndexMetadataCache, ensure_indexes_writable};
 pub use self::index_resource::get_index_metadata_handler;
+pub use self::merge_resource::get_index_merges_handler;
 pub use self::rest_handler::{IndexApi, index_management_handlers};
 pub use self::split_resource::{ListSplitsQueryParams, ListSplitsResponse};
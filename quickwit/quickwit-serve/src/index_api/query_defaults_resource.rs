@@ -0,0 +1,277 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use quickwit_config::{SearchSettings, build_doc_mapper};
+use quickwit_index_management::{IndexService, IndexServiceError};
+use quickwit_metastore::IndexMetadataResponseExt;
+use quickwit_proto::metastore::{
+    IndexMetadataRequest, MetastoreError, MetastoreResult, MetastoreService, MetastoreServiceClient,
+};
+use quickwit_proto::types::IndexId;
+use quickwit_query::BooleanOperand;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+use warp::{Filter, Rejection};
+
+use super::rest_handler::{json_body, log_failure};
+use crate::format::extract_format_from_qs;
+use crate::request_scope::ensure_indexes_in_scope;
+use crate::rest_api_response::into_rest_api_response;
+use crate::with_arg;
+
+/// Defaults applied to the search requests targeting an index that do not set the corresponding
+/// option.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct IndexQueryDefaults {
+    /// Fields searched by the clauses of the query that do not target a field.
+    #[serde(default)]
+    pub default_search_fields: Vec<String>,
+    /// Operator combining the clauses of the query that do not specify one, `AND` if unset.
+    #[schema(value_type = Option<String>)]
+    #[serde(default)]
+    pub default_operator: Option<BooleanOperand>,
+    /// Number of hits returned by the requests that do not set `max_hits`, 20 if unset.
+    #[serde(default)]
+    pub default_max_hits: Option<u64>,
+}
+
+impl From<SearchSettings> for IndexQueryDefaults {
+    fn from(search_settings: SearchSettings) -> Self {
+        Self {
+            default_search_fields: search_settings.default_search_fields,
+            default_operator: search_settings.default_operator,
+            default_max_hits: search_settings.default_max_hits,
+        }
+    }
+}
+
+pub fn get_index_query_defaults_handler(
+    metastore: MetastoreServiceClient,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("indexes" / String / "query-defaults")
+        .and(warp::get())
+        .and(with_arg(metastore))
+        .then(get_index_query_defaults)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+        .boxed()
+}
+
+#[utoipa::path(
+    get,
+    tag = "Indexes",
+    path = "/indexes/{index_id}/query-defaults",
+    responses(
+        (status = 200, description = "Successfully fetched the query defaults of the index.", body = IndexQueryDefaults)
+    ),
+    params(
+        ("index_id" = String, Path, description = "The index ID to get the query defaults of."),
+    )
+)]
+/// Returns the query defaults of an index.
+pub async fn get_index_query_defaults(
+    index_id: IndexId,
+    metastore: MetastoreServiceClient,
+) -> MetastoreResult<IndexQueryDefaults> {
+    info!(index_id = %index_id, "get-index-query-defaults");
+    ensure_indexes_in_scope([index_id.as_str()])?;
+    let index_metadata_request = IndexMetadataRequest::for_index_id(index_id);
+    let search_settings = metastore
+        .index_metadata(index_metadata_request)
+        .await?
+        .deserialize_index_metadata()?
+        .index_config
+        .search_settings;
+    Ok(search_settings.into())
+}
+
+pub fn update_index_query_defaults_handler(
+    index_service: IndexService,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("indexes" / String / "query-defaults")
+        .and(warp::put())
+        .and(json_body())
+        .and(with_arg(index_service))
+        .then(update_index_query_defaults)
+        .map(log_failure("failed to update index query defaults"))
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+        .boxed()
+}
+
+#[utoipa::path(
+    put,
+    tag = "Indexes",
+    path = "/indexes/{index_id}/query-defaults",
+    request_body = IndexQueryDefaults,
+    responses(
+        (status = 200, description = "Successfully updated the query defaults of the index.", body = IndexQueryDefaults)
+    ),
+    params(
+        ("index_id" = String, Path, description = "The index ID to update the query defaults of."),
+    )
+)]
+/// Replaces the query defaults of an index. Omitted defaults are unset.
+///
+/// The defaults are stored in the search settings of the index configuration, so they persist in
+/// the metastore.
+pub async fn update_index_query_defaults(
+    index_id: IndexId,
+    query_defaults: IndexQueryDefaults,
+    index_service: IndexService,
+) -> Result<IndexQueryDefaults, IndexServiceError> {
+    info!(index_id = %index_id, query_defaults = ?query_defaults, "update-index-query-defaults");
+    ensure_indexes_in_scope([index_id.as_str()]).map_err(MetastoreError::from)?;
+    let index_metadata_request = IndexMetadataRequest::for_index_id(index_id);
+    let index_metadata = index_service
+        .metastore()
+        .index_metadata(index_metadata_request)
+        .await?
+        .deserialize_index_metadata()?;
+    let index_uid = index_metadata.index_uid.clone();
+    let mut index_config = index_metadata.into_index_config();
+
+    index_config.search_settings.default_search_fields = query_defaults.default_search_fields;
+    index_config.search_settings.default_operator = query_defaults.default_operator;
+    index_config.search_settings.default_max_hits = query_defaults.default_max_hits;
+
+    build_doc_mapper(&index_config.doc_mapping, &index_config.search_settings)
+        .map_err(IndexServiceError::InvalidConfig)?;
+    index_config
+        .search_settings
+        .validate_query_defaults()
+        .map_err(IndexServiceError::InvalidConfig)?;
+
+    let search_settings = index_service
+        .update_index(index_uid, index_config)
+        .await?
+        .index_config
+        .search_settings;
+    Ok(search_settings.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_indexing::TestSandbox;
+    use quickwit_search::single_node_search;
+    use serde_json::json;
+    use warp::Filter;
+
+    use super::*;
+    use crate::rest::recover_fn;
+    use crate::{SearchRequestQueryString, search_request_from_api_request};
+
+    #[tokio::test]
+    async fn test_index_query_defaults() {
+        let index_id = "test-index-query-defaults";
+        let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: body
+                type: text
+        "#;
+        let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "", &["body"])
+            .await
+            .unwrap();
+        test_sandbox
+            .add_documents(vec![
+                json!({"body": "hello world"}),
+                json!({"body": "hello"}),
+            ])
+            .await
+            .unwrap();
+        let metastore = test_sandbox.metastore();
+        let index_service = IndexService::new(metastore.clone(), test_sandbox.storage_resolver());
+        let query_defaults_handler = get_index_query_defaults_handler(metastore.clone())
+            .or(update_index_query_defaults_handler(index_service))
+            .recover(recover_fn);
+
+        let resp = warp::test::request()
+            .path("/indexes/test-index-query-defaults/query-defaults")
+            .reply(&query_defaults_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let query_defaults: IndexQueryDefaults = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(
+            query_defaults,
+            IndexQueryDefaults {
+                default_search_fields: vec!["body".to_string()],
+                default_operator: None,
+                default_max_hits: None,
+            }
+        );
+        let resp = warp::test::request()
+            .path("/indexes/test-index-query-defaults/query-defaults")
+            .method("PUT")
+            .json(&true)
+            .body(r#"{"default_max_hits": 20000}"#)
+            .reply(&query_defaults_handler)
+            .await;
+        assert_eq!(resp.status(), 400);
+
+        let resp = warp::test::request()
+            .path("/indexes/test-index-query-defaults/query-defaults")
+            .method("PUT")
+            .json(&true)
+            .body(
+                r#"{"default_search_fields": ["body"], "default_operator": "OR",
+                "default_max_hits": 1}"#,
+            )
+            .reply(&query_defaults_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let query_defaults: IndexQueryDefaults = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(query_defaults.default_operator, Some(BooleanOperand::Or));
+        assert_eq!(query_defaults.default_max_hits, Some(1));
+
+        // The request without operator nor `max_hits` uses the defaults of the index.
+        let search_request = search_request_from_api_request(
+            vec![index_id.to_string()],
+            SearchRequestQueryString {
+                query: "hello world".to_string(),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let search_response = single_node_search(
+            search_request,
+            metastore.clone(),
+            test_sandbox.storage_resolver(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(search_response.num_hits, 2);
+        assert_eq!(search_response.hits.len(), 1);
+
+        // The values of the request override the defaults of the index.
+        let search_request = search_request_from_api_request(
+            vec![index_id.to_string()],
+            SearchRequestQueryString {
+                query: "hello world".to_string(),
+                default_operator: Some(BooleanOperand::And),
+                max_hits: Some(10),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let search_response =
+            single_node_search(search_request, metastore, test_sandbox.storage_resolver())
+                .await
+                .unwrap();
+        assert_eq!(search_response.num_hits, 1);
+        assert_eq!(search_response.hits.len(), 1);
+
+        test_sandbox.assert_quit().await;
+    }
+}
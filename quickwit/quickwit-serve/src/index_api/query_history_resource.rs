@@ -0,0 +1,151 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use quickwit_metastore::IndexMetadataResponseExt;
+use quickwit_proto::metastore::{
+    IndexMetadataRequest, MetastoreResult, MetastoreService, MetastoreServiceClient,
+};
+use quickwit_proto::types::IndexId;
+use serde::{Deserialize, Serialize};
+use warp::{Filter, Rejection};
+
+use crate::format::extract_format_from_qs;
+use crate::query_history::{QueryHistoryEntry, query_history};
+use crate::request_scope::{ensure_admin, ensure_indexes_in_scope};
+use crate::rest_api_response::into_rest_api_response;
+use crate::with_arg;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct IndexQueryHistoryResponse {
+    pub index_id: IndexId,
+    /// Recent searches of the index, from the oldest to the newest.
+    pub queries: Vec<QueryHistoryEntry>,
+}
+
+pub fn get_index_query_history_handler(
+    metastore: MetastoreServiceClient,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("indexes" / String / "_query_history")
+        .and(warp::get())
+        .and(with_arg(metastore))
+        .then(get_index_query_history)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+        .boxed()
+}
+
+#[utoipa::path(
+    get,
+    tag = "Indexes",
+    path = "/indexes/{index_id}/_query_history",
+    responses(
+        (status = 200, description = "Successfully fetched the recent searches of the index.", body = IndexQueryHistoryResponse),
+        (status = 403, description = "The token of the request does not grant admin access to the index."),
+    ),
+    params(
+        ("index_id" = String, Path, description = "The index ID to fetch the recent searches of."),
+    )
+)]
+/// Returns the recent searches of an index, with their latency and number of hits.
+///
+/// The values matched by the queries are redacted. The searches are kept in memory by each node,
+/// so only the searches served by the node receiving this request are returned.
+pub async fn get_index_query_history(
+    index_id: IndexId,
+    metastore: MetastoreServiceClient,
+) -> MetastoreResult<IndexQueryHistoryResponse> {
+    ensure_admin()?;
+    ensure_indexes_in_scope([index_id.as_str()])?;
+
+    let index_metadata_request = IndexMetadataRequest::for_index_id(index_id);
+    let index_metadata = metastore
+        .index_metadata(index_metadata_request)
+        .await?
+        .deserialize_index_metadata()?;
+    let queries = query_history().get(&index_metadata.index_config.index_id);
+
+    Ok(IndexQueryHistoryResponse {
+        index_id: index_metadata.index_config.index_id,
+        queries,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use quickwit_config::{IndexConfig, NodeConfig};
+    use quickwit_index_management::IndexService;
+    use quickwit_metastore::{CreateIndexRequestExt, metastore_for_test};
+    use quickwit_proto::metastore::CreateIndexRequest;
+    use quickwit_proto::search::SearchResponse;
+    use quickwit_search::MockSearchService;
+    use quickwit_storage::StorageResolver;
+
+    use super::*;
+    use crate::index_api::index_management_handlers;
+    use crate::search_api::search_get_handler;
+
+    #[tokio::test]
+    async fn test_get_index_query_history() {
+        let metastore = metastore_for_test();
+        let index_config = IndexConfig::for_test(
+            "test-index-query-history",
+            "ram:///indexes/test-index-query-history",
+        );
+        let create_index_request =
+            CreateIndexRequest::try_from_index_config(&index_config).unwrap();
+        metastore.create_index(create_index_request).await.unwrap();
+
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service.expect_root_search().returning(|_| {
+            Ok(SearchResponse {
+                num_hits: 7,
+                searched_index_ids: vec!["test-index-query-history".to_string()],
+                ..Default::default()
+            })
+        });
+        let search_handler = search_get_handler(Arc::new(mock_search_service), false);
+        let resp = warp::test::request()
+            .path("/test-index-query-*/search?query=user:alice")
+            .reply(&search_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+
+        let index_service = IndexService::new(metastore, StorageResolver::unconfigured());
+        let index_management_handler =
+            index_management_handlers(index_service, Arc::new(NodeConfig::for_test()));
+        let resp = warp::test::request()
+            .path("/indexes/test-index-query-history/_query_history")
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let query_history_response: IndexQueryHistoryResponse =
+            serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(query_history_response.index_id, "test-index-query-history");
+
+        let queries = &query_history_response.queries;
+        assert_eq!(queries.len(), 1);
+        assert_eq!(queries[0].num_hits, 7);
+        assert!(queries[0].timestamp > 0);
+        assert!(queries[0].query.contains("\"user\""));
+        assert!(!queries[0].query.contains("alice"));
+
+        let resp = warp::test::request()
+            .path("/indexes/unknown-index/_query_history")
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 404);
+    }
+}
@@ -17,12 +17,22 @@ use std::sync::Arc;
 use quickwit_config::NodeConfig;
 use quickwit_doc_mapper::{TokenizerConfig, analyze_text};
 use quickwit_index_management::{IndexService, IndexServiceError};
+use quickwit_indexing::actors::{MergeOperationState, MergeOperationStatus, MergePhase};
 use quickwit_query::query_ast::{QueryAst, query_ast_from_user_text};
 use serde::Deserialize;
 use serde::de::DeserializeOwned;
 use tracing::warn;
 use warp::{Filter, Rejection};
 
+use super::analyzers_resource::{
+    __path_get_index_analyzers, __path_list_analyzers, Analyzer, AnalyzersResponse, FieldAnalyzer,
+    IndexAnalyzersResponse, get_index_analyzers_handler, list_analyzers_handler,
+};
+use super::block_resource::{__path_update_index_blocks, IndexBlocks, update_index_blocks_handler};
+use super::doc_mapping_schema::UnsupportedFeature;
+use super::errors_resource::{
+    __path_get_index_errors, IndexErrorsResponse, get_index_errors_handler,
+};
 use super::get_index_metadata_handler;
 use super::index_resource::{
     __path_clear_index, __path_create_index, __path_delete_index, __path_describe_index,
@@ -30,17 +40,44 @@ use super::index_resource::{
     create_index_handler, delete_index_handler, describe_index_handler,
     list_indexes_metadata_handler, update_index_handler,
 };
+use super::mapping_resource::{__path_get_index_mapping, get_index_mapping_handler};
+use super::merge_resource::{__path_get_index_merges, IndexMergesResponse, IndexerMergeOperation};
+use super::query_defaults_resource::{
+    __path_get_index_query_defaults, __path_update_index_query_defaults, IndexQueryDefaults,
+    get_index_query_defaults_handler, update_index_query_defaults_handler,
+};
+use super::query_history_resource::{
+    __path_get_index_query_history, IndexQueryHistoryResponse, get_index_query_history_handler,
+};
+use super::retention_preview_resource::{
+    __path_preview_retention_policy, RetentionPreview, preview_retention_policy_handler,
+};
+use super::rollover_resource::{
+    __path_rollover_index, RolloverConditions, RolloverRequest, RolloverResponse,
+    rollover_index_handler,
+};
+use super::schema_resource::{
+    __path_get_index_schema, IndexSchemaResponse, SchemaFormat, get_index_schema_handler,
+};
 use super::source_resource::{
     __path_create_source, __path_delete_source, __path_reset_source_checkpoint,
     __path_toggle_source, __path_update_source, ToggleSource, create_source_handler,
     delete_source_handler, get_source_handler, get_source_shards_handler,
     reset_source_checkpoint_handler, toggle_source_handler, update_source_handler,
 };
+use super::split_download_resource::{__path_download_split, download_split_handler};
 use super::split_resource::{
-    __path_list_splits, __path_mark_splits_for_deletion, SplitsForDeletion, list_splits_handler,
-    mark_splits_for_deletion_handler,
+    __path_list_splits, __path_mark_splits_for_deletion, __path_update_splits_tags,
+    SplitsForDeletion, SplitsTagsUpdate, SplitsTagsUpdateResponse, list_splits_handler,
+    mark_splits_for_deletion_handler, update_splits_tags_handler,
+};
+use super::validate_document_resource::{
+    __path_validate_document, ValidateDocumentRequest, ValidateDocumentResponse,
+    validate_document_handler,
 };
 use crate::format::extract_format_from_qs;
+use crate::query_history::QueryHistoryEntry;
+use crate::recent_errors::{IndexErrorEvent, IndexErrorReason, IndexErrorType};
 use crate::rest::recover_fn;
 use crate::rest_api_response::into_rest_api_response;
 use crate::simple_list::from_simple_list;
@@ -50,19 +87,66 @@ use crate::simple_list::from_simple_list;
     paths(
         create_index,
         update_index,
+        update_index_blocks,
+        get_index_query_defaults,
+        update_index_query_defaults,
+        preview_retention_policy,
         clear_index,
         delete_index,
         list_indexes_metadata,
         list_splits,
         describe_index,
         mark_splits_for_deletion,
+        update_splits_tags,
+        download_split,
         create_source,
         update_source,
         reset_source_checkpoint,
         toggle_source,
         delete_source,
+        validate_document,
+        get_index_merges,
+        get_index_errors,
+        get_index_query_history,
+        rollover_index,
+        get_index_schema,
+        get_index_mapping,
+        list_analyzers,
+        get_index_analyzers,
     ),
-    components(schemas(ToggleSource, SplitsForDeletion, IndexStats))
+    components(schemas(
+        ToggleSource,
+        SplitsForDeletion,
+        SplitsTagsUpdate,
+        SplitsTagsUpdateResponse,
+        IndexStats,
+        IndexBlocks,
+        IndexQueryDefaults,
+        RetentionPreview,
+        ValidateDocumentRequest,
+        ValidateDocumentResponse,
+        IndexMergesResponse,
+        IndexerMergeOperation,
+        MergeOperationStatus,
+        MergeOperationState,
+        MergePhase,
+        IndexErrorsResponse,
+        IndexErrorEvent,
+        IndexErrorReason,
+        IndexErrorType,
+        IndexQueryHistoryResponse,
+        QueryHistoryEntry,
+        RolloverRequest,
+        RolloverConditions,
+        RolloverResponse,
+        IndexSchemaResponse,
+        SchemaFormat,
+        UnsupportedFeature,
+        Analyzer,
+        AnalyzersResponse,
+        FieldAnalyzer,
+        IndexAnalyzersResponse,
+    ))
 )]
 pub struct IndexApi;
 
@@ -93,14 +177,28 @@ pub fn index_management_handlers(
             index_service.clone(),
             node_config.clone(),
         ))
-        .or(update_index_handler(index_service.clone(), node_config))
+        .or(update_index_handler(
+            index_service.clone(),
+            node_config.clone(),
+        ))
+        .or(rollover_index_handler(index_service.clone(), node_config))
+        .or(update_index_blocks_handler(index_service.clone()))
+        .or(get_index_query_defaults_handler(index_service.metastore()))
+        .or(update_index_query_defaults_handler(index_service.clone()))
+        .or(preview_retention_policy_handler(index_service.metastore()))
+        .or(get_index_errors_handler(index_service.metastore()))
+        .or(get_index_query_history_handler(index_service.metastore()))
         .or(clear_index_handler(index_service.clone()))
         .or(delete_index_handler(index_service.clone()))
         .boxed()
         // Splits handlers
         .or(list_splits_handler(index_service.metastore()))
         .or(describe_index_handler(index_service.metastore()))
+        .or(get_index_mapping_handler(index_service.metastore()))
+        .or(get_index_schema_handler(index_service.metastore()))
         .or(mark_splits_for_deletion_handler(index_service.metastore()))
+        .or(update_splits_tags_handler(index_service.metastore()))
+        .or(download_split_handler(index_service.clone()))
         .boxed()
         // Sources handlers.
         .or(reset_source_checkpoint_handler(index_service.metastore()))
@@ -113,8 +211,12 @@ pub fn index_management_handlers(
         .boxed()
         // Tokenizer handlers.
         .or(analyze_request_handler())
+        .or(list_analyzers_handler(index_service.metastore()))
+        .or(get_index_analyzers_handler(index_service.metastore()))
         // Parse query into query AST handler.
         .or(parse_query_request_handler())
+        // Document validation handler.
+        .or(validate_document_handler())
         .recover(recover_fn)
         .boxed()
 }
@@ -209,6 +311,8 @@ async fn parse_query_request(request: ParseQueryRequest) -> Result<QueryAst, Ind
 #[cfg(test)]
 mod tests {
     use std::ops::{Bound, RangeInclusive};
+    use std::sync::Mutex;
+    use std::sync::atomic::{AtomicUsize, Ordering};
 
     use assert_json_diff::assert_json_include;
     use quickwit_common::ServiceStream;
@@ -219,14 +323,15 @@ mod tests {
     use quickwit_indexing::{MockSplitBuilder, mock_split};
     use quickwit_metastore::{
         IndexMetadata, IndexMetadataResponseExt, ListIndexesMetadataResponseExt,
-        ListSplitsRequestExt, ListSplitsResponseExt, SplitState, metastore_for_test,
+        ListSplitsRequestExt, ListSplitsResponseExt, Split, SplitState, metastore_for_test,
     };
     use quickwit_proto::metastore::{
         DeleteSourceRequest, EmptyResponse, EntityKind, IndexMetadataRequest,
-        IndexMetadataResponse, ListIndexesMetadataRequest, ListIndexesMetadataResponse,
-        ListSplitsRequest, ListSplitsResponse, MarkSplitsForDeletionRequest, MetastoreError,
-        MetastoreService, MetastoreServiceClient, MockMetastoreService,
-        ResetSourceCheckpointRequest, SourceType, ToggleSourceRequest,
+        IndexMetadataResponse, IndexStats, ListIndexStatsResponse, ListIndexesMetadataRequest,
+        ListIndexesMetadataResponse, ListSplitsRequest, ListSplitsResponse,
+        MarkSplitsForDeletionRequest, MetastoreError, MetastoreService, MetastoreServiceClient,
+        MockMetastoreService, ResetSourceCheckpointRequest, SourceType, SplitStats,
+        ToggleSourceRequest, UpdateSplitsTagsResponse,
     };
     use quickwit_proto::types::IndexUid;
     use quickwit_storage::StorageResolver;
@@ -285,17 +390,36 @@ mod tests {
         assert_eq!(resp.status(), 404);
     }
 
+    fn list_index_stats_response(index_uid: &IndexUid, splits: &[Split]) -> ListIndexStatsResponse {
+        let mut index_stats = IndexStats {
+            index_uid: Some(index_uid.clone()),
+            staged: Some(SplitStats::default()),
+            published: Some(SplitStats::default()),
+            marked_for_deletion: Some(SplitStats::default()),
+        };
+        for split in splits {
+            let split_stats_opt = match split.split_state {
+                SplitState::Staged => &mut index_stats.staged,
+                SplitState::Published => &mut index_stats.published,
+                SplitState::MarkedForDeletion => &mut index_stats.marked_for_deletion,
+            };
+            if let Some(split_stats) = split_stats_opt {
+                split_stats.num_splits += 1;
+            }
+        }
+        ListIndexStatsResponse {
+            index_stats: vec![index_stats],
+        }
+    }
+
     #[tokio::test]
     async fn test_get_splits() {
         let mut mock_metastore = MockMetastoreService::new();
-        let index_metadata =
-            IndexMetadata::for_test("quickwit-demo-index", "ram:///indexes/quickwit-demo-index");
-        let index_uid = index_metadata.index_uid.clone();
+        let index_uid = IndexUid::for_test("quickwit-demo-index", 0);
+        let index_uid_clone = index_uid.clone();
         mock_metastore
-            .expect_index_metadata()
-            .returning(move |_| {
-                Ok(IndexMetadataResponse::try_from_index_metadata(&index_metadata).unwrap())
-            })
+            .expect_list_index_stats()
+            .returning(move |_| Ok(list_index_stats_response(&index_uid_clone, &[])))
             .times(2);
         mock_metastore
             .expect_list_splits()
@@ -364,6 +488,157 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_get_splits_etag() {
+        let mut mock_metastore = MockMetastoreService::new();
+        let index_uid = IndexUid::for_test("quickwit-demo-index", 0);
+        let mut split_1 = MockSplitBuilder::new("split_1")
+            .with_index_uid(&index_uid)
+            .build();
+        split_1.update_timestamp = 10;
+        let mut split_2 = MockSplitBuilder::new("split_2")
+            .with_index_uid(&index_uid)
+            .build();
+        split_2.update_timestamp = 10;
+        let splits = Arc::new(Mutex::new(vec![split_1, split_2]));
+        let num_full_listings = Arc::new(AtomicUsize::new(0));
+
+        let index_uid_clone = index_uid.clone();
+        let splits_clone = splits.clone();
+        mock_metastore
+            .expect_list_index_stats()
+            .returning(move |_| {
+                let splits = splits_clone.lock().unwrap();
+                Ok(list_index_stats_response(&index_uid_clone, &splits))
+            });
+        let splits_clone = splits.clone();
+        let num_full_listings_clone = num_full_listings.clone();
+        mock_metastore.expect_list_splits().returning(
+            move |list_splits_request: ListSplitsRequest| {
+                let list_splits_query =
+                    list_splits_request.deserialize_list_splits_query().unwrap();
+                if list_splits_query.update_timestamp.is_unbounded() {
+                    num_full_listings_clone.fetch_add(1, Ordering::Relaxed);
+                }
+                let splits: Vec<Split> = splits_clone
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .filter(|split| {
+                        list_splits_query
+                            .update_timestamp
+                            .contains(&split.update_timestamp)
+                    })
+                    .take(list_splits_query.limit.unwrap_or(usize::MAX))
+                    .cloned()
+                    .collect();
+                let splits = ListSplitsResponse::try_from_splits(splits).unwrap();
+                Ok(ServiceStream::from(vec![Ok(splits)]))
+            },
+        );
+        let index_service = IndexService::new(
+            MetastoreServiceClient::from_mock(mock_metastore),
+            StorageResolver::unconfigured(),
+        );
+        let index_management_handler =
+            super::index_management_handlers(index_service, Arc::new(NodeConfig::for_test()))
+                .recover(recover_fn);
+
+        let resp = warp::test::request()
+            .path("/indexes/quickwit-demo-index/splits")
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let etag = resp.headers()["etag"].clone();
+
+        let list_splits_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(list_splits_json["size"], 2);
+        assert_eq!(num_full_listings.load(Ordering::Relaxed), 1);
+
+        // The splits are unchanged: they are not listed again.
+        let resp = warp::test::request()
+            .path("/indexes/quickwit-demo-index/splits")
+            .header("if-none-match", etag.clone())
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 304);
+        assert_eq!(resp.headers()["etag"], etag);
+        assert!(resp.body().is_empty());
+        assert_eq!(num_full_listings.load(Ordering::Relaxed), 1);
+
+        // The listing with other parameters has another ETag.
+        let resp = warp::test::request()
+            .path("/indexes/quickwit-demo-index/splits?limit=1")
+            .header("if-none-match", etag.clone())
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        assert_ne!(resp.headers()["etag"], etag);
+
+        // The tags of a split are updated.
+        {
+            let mut splits_guard = splits.lock().unwrap();
+            splits_guard[0].split_metadata.tags = ["tenant:acme".to_string()].into();
+            splits_guard[0].update_timestamp = 20;
+        }
+        let resp = warp::test::request()
+            .path("/indexes/quickwit-demo-index/splits")
+            .header("if-none-match", etag.clone())
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let tags_etag = resp.headers()["etag"].clone();
+        assert_ne!(tags_etag, etag);
+
+        let list_splits_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(list_splits_json["splits"][0]["tags"][0], "tenant:acme");
+
+        // The splits are merged.
+        {
+            let mut splits_guard = splits.lock().unwrap();
+            for split in splits_guard.iter_mut() {
+                split.split_state = SplitState::MarkedForDeletion;
+                split.update_timestamp = 30;
+            }
+            let mut split_3 = MockSplitBuilder::new("split_3")
+                .with_index_uid(&index_uid)
+                .build();
+            split_3.update_timestamp = 30;
+            splits_guard.push(split_3);
+        }
+        let resp = warp::test::request()
+            .path("/indexes/quickwit-demo-index/splits")
+            .header("if-none-match", tags_etag.clone())
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let merge_etag = resp.headers()["etag"].clone();
+        assert_ne!(merge_etag, tags_etag);
+
+        let list_splits_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(list_splits_json["size"], 3);
+
+        // The merged splits are deleted.
+        splits.lock().unwrap().truncate(1);
+
+        let resp = warp::test::request()
+            .path("/indexes/quickwit-demo-index/splits")
+            .header("if-none-match", merge_etag.clone())
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let delete_etag = resp.headers()["etag"].clone();
+        assert_ne!(delete_etag, merge_etag);
+
+        let if_none_match = format!("W/\"0000000000000000\", {}", delete_etag.to_str().unwrap());
+        let resp = warp::test::request()
+            .path("/indexes/quickwit-demo-index/splits")
+            .header("if-none-match", if_none_match)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 304);
+    }
+
     #[tokio::test]
     async fn test_describe_index() -> anyhow::Result<()> {
         let mut mock_metastore = MockMetastoreService::new();
@@ -431,14 +706,11 @@ mod tests {
     #[tokio::test]
     async fn test_get_all_splits() {
         let mut mock_metastore = MockMetastoreService::new();
-        let index_metadata =
-            IndexMetadata::for_test("quickwit-demo-index", "ram:///indexes/quickwit-demo-index");
-        let index_uid = index_metadata.index_uid.clone();
+        let index_uid = IndexUid::for_test("quickwit-demo-index", 0);
+        let index_uid_clone = index_uid.clone();
         mock_metastore
-            .expect_index_metadata()
-            .return_once(move |_| {
-                Ok(IndexMetadataResponse::try_from_index_metadata(&index_metadata).unwrap())
-            });
+            .expect_list_index_stats()
+            .return_once(move |_| Ok(list_index_stats_response(&index_uid_clone, &[])));
         mock_metastore.expect_list_splits().return_once(
             move |list_split_request: ListSplitsRequest| {
                 let list_split_query = list_split_request.deserialize_list_splits_query().unwrap();
@@ -528,6 +800,81 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_update_splits_tags() {
+        let mut mock_metastore = MockMetastoreService::new();
+        mock_metastore.expect_index_metadata().returning(|_| {
+            Ok(
+                IndexMetadataResponse::try_from_index_metadata(&IndexMetadata::for_test(
+                    "quickwit-demo-index",
+                    "ram:///indexes/quickwit-demo-index",
+                ))
+                .unwrap(),
+            )
+        });
+        mock_metastore
+            .expect_update_splits_tags()
+            .return_once(|update_splits_tags_request| {
+                assert_eq!(
+                    update_splits_tags_request.index_uid().index_id,
+                    "quickwit-demo-index"
+                );
+                assert_eq!(update_splits_tags_request.time_range_start, Some(0));
+                assert_eq!(update_splits_tags_request.time_range_end, None);
+                assert_eq!(update_splits_tags_request.tags, ["tier:hot"]);
+                assert_eq!(update_splits_tags_request.add_tags, ["tier:cold"]);
+                assert_eq!(update_splits_tags_request.remove_tags, ["tier:hot"]);
+                Ok(UpdateSplitsTagsResponse {
+                    num_updated_splits: 3,
+                })
+            });
+        let index_service = IndexService::new(
+            MetastoreServiceClient::from_mock(mock_metastore),
+            StorageResolver::unconfigured(),
+        );
+        let index_management_handler =
+            super::index_management_handlers(index_service, Arc::new(NodeConfig::for_test()))
+                .recover(recover_fn);
+        let resp = warp::test::request()
+            .path("/indexes/quickwit-demo-index/splits/tags")
+            .method("PUT")
+            .json(&serde_json::json!({
+                "start_timestamp": 0,
+                "tags": ["tier:hot"],
+                "add_tags": ["tier:cold"],
+                "remove_tags": ["tier:hot"],
+            }))
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(resp_json, serde_json::json!({"num_updated_splits": 3}));
+
+        let resp = warp::test::request()
+            .path("/indexes/quickwit-demo-index/splits/tags")
+            .method("PUT")
+            .json(&serde_json::json!({"tags": ["tier:hot"]}))
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 400);
+
+        // `owner` is a tag field of the index.
+        let resp = warp::test::request()
+            .path("/indexes/quickwit-demo-index/splits/tags")
+            .method("PUT")
+            .json(&serde_json::json!({"add_tags": ["owner:bob"]}))
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 400);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert!(
+            resp_json["message"]
+                .as_str()
+                .unwrap()
+                .contains("tag field `owner`")
+        );
+    }
+
     #[tokio::test]
     async fn test_get_list_indexes() -> anyhow::Result<()> {
         let mut mock_metastore = MockMetastoreService::new();
@@ -1064,6 +1411,51 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_update_index_preserves_blocks() {
+        let metastore = metastore_for_test();
+        let index_service = IndexService::new(metastore.clone(), StorageResolver::unconfigured());
+        let index_management_handler =
+            super::index_management_handlers(index_service, Arc::new(NodeConfig::for_test()));
+        let index_config_json = r#"{"version": "0.7", "index_id": "hdfs-logs", "index_uri": "ram:///indexes/hdfs-logs", "doc_mapping": {"field_mappings":[{"name": "timestamp", "type": "i64", "fast": true, "indexed": true}]}}"#;
+
+        let resp = warp::test::request()
+            .path("/indexes")
+            .method("POST")
+            .json(&true)
+            .body(index_config_json)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+
+        let resp = warp::test::request()
+            .path("/indexes/hdfs-logs/_block")
+            .method("PUT")
+            .json(&true)
+            .body(r#"{"read": true, "write": true}"#)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+
+        let resp = warp::test::request()
+            .path("/indexes/hdfs-logs")
+            .method("PUT")
+            .json(&true)
+            .body(index_config_json)
+            .reply(&index_management_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+
+        let index_metadata = metastore
+            .index_metadata(IndexMetadataRequest::for_index_id("hdfs-logs".to_string()))
+            .await
+            .unwrap()
+            .deserialize_index_metadata()
+            .unwrap();
+        assert!(index_metadata.index_config.search_settings.read_block);
+        assert!(index_metadata.index_config.ingest_settings.write_block);
+    }
+
     #[tokio::test]
     async fn test_create_source_with_bad_config() {
         let metastore = metastore_for_test();
@@ -0,0 +1,209 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use quickwit_common::is_parquet_pipeline_index;
+use quickwit_config::RetentionPolicy;
+use quickwit_index_management::IndexServiceError;
+use quickwit_janitor::list_expired_splits;
+use quickwit_metastore::{IndexMetadataResponseExt, SplitInfo};
+use quickwit_proto::metastore::{
+    IndexMetadataRequest, MetastoreError, MetastoreService, MetastoreServiceClient,
+};
+use quickwit_proto::types::{IndexId, SplitId};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+use warp::{Filter, Rejection};
+
+use super::rest_handler::json_body;
+use crate::format::extract_format_from_qs;
+use crate::request_scope::ensure_indexes_in_scope;
+use crate::rest_api_response::into_rest_api_response;
+use crate::with_arg;
+
+/// Splits a candidate retention policy would mark for deletion if it was applied now.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct RetentionPreview {
+    /// Splits that would be marked for deletion.
+    pub expired_splits: Vec<SplitInfo>,
+    /// Number of documents of the expired splits.
+    pub num_expired_docs: u64,
+    /// Size of the files of the expired splits, reclaimed once they are garbage collected.
+    pub reclaimed_num_bytes: u64,
+    /// Splits the retention policy cannot be applied to because they lack a time range.
+    #[schema(value_type = Vec<String>)]
+    pub ignored_split_ids: Vec<SplitId>,
+}
+
+pub fn preview_retention_policy_handler(
+    metastore: MetastoreServiceClient,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("indexes" / String / "retention" / "_preview")
+        .and(warp::post())
+        .and(json_body())
+        .and(with_arg(metastore))
+        .then(preview_retention_policy)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+        .boxed()
+}
+
+#[utoipa::path(
+    post,
+    tag = "Indexes",
+    path = "/indexes/{index_id}/retention/_preview",
+    request_body = RetentionPolicy,
+    responses(
+        (status = 200, description = "Successfully previewed the retention policy.", body = RetentionPreview)
+    ),
+    params(
+        ("index_id" = String, Path, description = "The index ID to preview the retention policy of."),
+    )
+)]
+/// Previews a retention policy.
+///
+/// Lists the splits the janitor would mark for deletion if the policy was the retention policy of
+/// the index, without applying it.
+pub async fn preview_retention_policy(
+    index_id: IndexId,
+    retention_policy: RetentionPolicy,
+    metastore: MetastoreServiceClient,
+) -> Result<RetentionPreview, IndexServiceError> {
+    info!(index_id = %index_id, retention_policy = ?retention_policy, "preview-retention-policy");
+    ensure_indexes_in_scope([index_id.as_str()]).map_err(MetastoreError::from)?;
+    let retention_period = retention_policy
+        .retention_period()
+        .map_err(IndexServiceError::InvalidConfig)?;
+
+    if is_parquet_pipeline_index(&index_id) {
+        let message = format!("retention policies cannot be previewed on index `{index_id}`");
+        return Err(IndexServiceError::OperationNotAllowed(message));
+    }
+    let index_metadata_request = IndexMetadataRequest::for_index_id(index_id);
+    let index_uid = metastore
+        .index_metadata(index_metadata_request)
+        .await?
+        .deserialize_index_metadata()?
+        .index_uid;
+    let (expired_splits, ignored_splits) =
+        list_expired_splits(index_uid, &metastore, retention_period).await?;
+
+    let mut retention_preview = RetentionPreview::default();
+
+    for split_metadata in expired_splits {
+        retention_preview.num_expired_docs += split_metadata.num_docs as u64;
+        retention_preview.reclaimed_num_bytes += split_metadata.footer_offsets.end;
+        retention_preview
+            .expired_splits
+            .push(split_metadata.as_split_info());
+    }
+    retention_preview.ignored_split_ids = ignored_splits
+        .into_iter()
+        .map(|split_metadata| split_metadata.split_id)
+        .collect();
+    Ok(retention_preview)
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_common::ServiceStream;
+    use quickwit_metastore::{
+        IndexMetadata, ListSplitsRequestExt, ListSplitsResponseExt, Split, SplitMetadata,
+        SplitState,
+    };
+    use quickwit_proto::metastore::{
+        IndexMetadataResponse, ListSplitsResponse, MockMetastoreService,
+    };
+    use time::OffsetDateTime;
+
+    use super::*;
+    use crate::rest::recover_fn;
+
+    fn split_for_test(split_id: &str, end_timestamp_opt: Option<i64>) -> Split {
+        Split {
+            split_metadata: SplitMetadata {
+                split_id: split_id.to_string(),
+                num_docs: 10,
+                footer_offsets: 1_000..2_000,
+                time_range: end_timestamp_opt
+                    .map(|end_timestamp| end_timestamp - 1_000..=end_timestamp),
+                ..Default::default()
+            },
+            split_state: SplitState::Published,
+            update_timestamp: 0,
+            publish_timestamp: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_preview_retention_policy() {
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+        let index_metadata = IndexMetadata::for_test("test-index", "ram:///indexes/test-index");
+
+        // The metastore is only read: the retention policy of the index is left as is.
+        let mut mock_metastore = MockMetastoreService::new();
+        mock_metastore.expect_index_metadata().returning(move |_| {
+            Ok(IndexMetadataResponse::try_from_index_metadata(&index_metadata).unwrap())
+        });
+        mock_metastore
+            .expect_list_splits()
+            .returning(move |list_splits_request| {
+                let query = list_splits_request.deserialize_list_splits_query().unwrap();
+                let max_time_range_end = query.max_time_range_end.unwrap();
+                let splits: Vec<Split> = [
+                    split_for_test("split-1", Some(now - 3 * 86_400)),
+                    split_for_test("split-2", Some(now - 2 * 86_400)),
+                    split_for_test("split-3", Some(now - 3_600)),
+                    split_for_test("split-4", None),
+                ]
+                .into_iter()
+                .filter(|split| match &split.split_metadata.time_range {
+                    Some(time_range) => *time_range.end() <= max_time_range_end,
+                    None => true,
+                })
+                .collect();
+                let splits_response = ListSplitsResponse::try_from_splits(splits).unwrap();
+                Ok(ServiceStream::from(vec![Ok(splits_response)]))
+            });
+        let metastore = MetastoreServiceClient::from_mock(mock_metastore);
+        let preview_handler = preview_retention_policy_handler(metastore).recover(recover_fn);
+
+        let resp = warp::test::request()
+            .path("/indexes/test-index/retention/_preview")
+            .method("POST")
+            .json(&true)
+            .body(r#"{"period": "1 day"}"#)
+            .reply(&preview_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let retention_preview: RetentionPreview = serde_json::from_slice(resp.body()).unwrap();
+        let expired_split_ids: Vec<&str> = retention_preview
+            .expired_splits
+            .iter()
+            .map(|split_info| split_info.split_id.as_str())
+            .collect();
+        assert_eq!(expired_split_ids, ["split-1", "split-2"]);
+        assert_eq!(retention_preview.num_expired_docs, 20);
+        assert_eq!(retention_preview.reclaimed_num_bytes, 4_000);
+        assert_eq!(retention_preview.ignored_split_ids, ["split-4"]);
+
+        let resp = warp::test::request()
+            .path("/indexes/test-index/retention/_preview")
+            .method("POST")
+            .json(&true)
+            .body(r#"{"period": "forever"}"#)
+            .reply(&preview_handler)
+            .await;
+        assert_eq!(resp.status(), 400);
+    }
+}
@@ -0,0 +1,339 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use bytesize::ByteSize;
+use futures::TryStreamExt;
+use quickwit_common::uri::Uri;
+use quickwit_config::{HumanDuration, IndexConfig, IndexTemplate, NodeConfig};
+use quickwit_index_management::{IndexService, IndexServiceError};
+use quickwit_metastore::{
+    IndexMetadata, ListIndexesMetadataResponseExt, ListSplitsQuery, ListSplitsRequestExt,
+    ListSplitsResponseExt, SplitState,
+};
+use quickwit_proto::metastore::{
+    EntityKind, FindIndexTemplateMatchesRequest, ListIndexStatsRequest, ListIndexesMetadataRequest,
+    ListSplitsRequest, MetastoreError, MetastoreResult, MetastoreService, MetastoreServiceClient,
+    serde_utils,
+};
+use quickwit_proto::types::IndexId;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+use warp::{Filter, Rejection};
+
+use super::rest_handler::{json_body, log_failure};
+use crate::format::extract_format_from_qs;
+use crate::request_scope::ensure_indexes_in_scope;
+use crate::rest_api_response::into_rest_api_response;
+use crate::with_arg;
+
+/// Conditions triggering the rollover of an index. The index is rolled over as soon as one of
+/// them is met.
+#[derive(Debug, Default, Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct RolloverConditions {
+    /// Minimum age of the index, since its creation, for instance `7d`.
+    #[schema(value_type = Option<String>)]
+    #[serde(default)]
+    pub max_age: Option<HumanDuration>,
+    /// Minimum number of published documents of the index.
+    #[serde(default)]
+    pub max_docs: Option<u64>,
+    /// Minimum size of the published splits of the index, for instance `50GB`.
+    #[schema(value_type = Option<String>)]
+    #[serde(default)]
+    pub max_size: Option<ByteSize>,
+}
+
+#[derive(Debug, Default, Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct RolloverRequest {
+    /// The index is rolled over unconditionally if no condition is set.
+    #[serde(default)]
+    pub conditions: RolloverConditions,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct RolloverResponse {
+    pub old_index: IndexId,
+    /// The index created by the rollover, or that would have been created if no condition is met.
+    pub new_index: IndexId,
+    pub rolled_over: bool,
+    /// Whether each condition of the request is met.
+    pub conditions: BTreeMap<String, bool>,
+}
+
+/// Statistics of an index the rollover conditions are evaluated against.
+struct RolloverStats {
+    age: Duration,
+    /// Number of published documents, counted up to the `max_docs` condition only.
+    num_docs: u64,
+    size: ByteSize,
+}
+
+pub fn rollover_index_handler(
+    index_service: IndexService,
+    node_config: Arc<NodeConfig>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("indexes" / String / "_rollover")
+        .and(warp::post())
+        .and(json_body())
+        .and(with_arg(index_service))
+        .and(with_arg(node_config))
+        .then(rollover_index)
+        .map(log_failure("failed to roll over index"))
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+        .boxed()
+}
+
+#[utoipa::path(
+    post,
+    tag = "Indexes",
+    path = "/indexes/{index_id}/_rollover",
+    request_body = RolloverRequest,
+    responses(
+        (status = 200, description = "Successfully evaluated the rollover conditions of the index.", body = RolloverResponse)
+    ),
+    params(
+        ("index_id" = String, Path, description = "The write alias or the index ID to roll over."),
+    )
+)]
+/// Rolls over an index.
+///
+/// `index_id` is either the write alias of the index to roll over or its ID. If one of the
+/// conditions is met, creates the index succeeding it, for instance `logs-000002` for
+/// `logs-000001`, from the index template matching its ID, and moves the write alias of the rolled
+/// over index to the new index. The rolled over indexes are searched together with an index ID
+/// pattern such as `logs-*`.
+pub async fn rollover_index(
+    rollover_target: String,
+    rollover_request: RolloverRequest,
+    mut index_service: IndexService,
+    node_config: Arc<NodeConfig>,
+) -> Result<RolloverResponse, IndexServiceError> {
+    let metastore = index_service.metastore();
+    let index_metadata = resolve_rollover_target(&metastore, &rollover_target).await?;
+    let index_id = index_metadata.index_id().to_string();
+
+    let Some(new_index_id) = next_index_id(&index_id) else {
+        return Err(IndexServiceError::InvalidIdentifier(format!(
+            "index ID `{index_id}` must end with a dash followed by a number to be rolled over, \
+             for instance `{index_id}-000001`"
+        )));
+    };
+    ensure_indexes_in_scope([new_index_id.as_str()]).map_err(MetastoreError::from)?;
+    let rollover_stats = fetch_rollover_stats(
+        &metastore,
+        &index_metadata,
+        rollover_request.conditions.max_docs,
+    )
+    .await?;
+    let conditions = evaluate_rollover_conditions(&rollover_request.conditions, &rollover_stats);
+    let rolled_over = conditions.is_empty() || conditions.values().any(|is_met| *is_met);
+
+    if rolled_over {
+        let write_alias_opt = index_metadata
+            .index_config
+            .ingest_settings
+            .write_alias
+            .clone();
+        let mut new_index_config = apply_index_template(
+            &metastore,
+            new_index_id.clone(),
+            &node_config.default_index_root_uri,
+        )
+        .await?;
+        new_index_config.ingest_settings.write_alias = write_alias_opt.clone();
+
+        info!(index_id=%index_id, new_index_id=%new_index_id, write_alias=?write_alias_opt, "rollover-index");
+        index_service.create_index(new_index_config, false).await?;
+
+        // Until the alias is removed from the rolled over index, it resolves to the most recently
+        // created index holding it, i.e. the new index.
+        if write_alias_opt.is_some() {
+            let index_uid = index_metadata.index_uid.clone();
+            let mut index_config = index_metadata.into_index_config();
+            index_config.ingest_settings.write_alias = None;
+            index_service.update_index(index_uid, index_config).await?;
+        }
+    }
+    Ok(RolloverResponse {
+        old_index: index_id,
+        new_index: new_index_id,
+        rolled_over,
+        conditions,
+    })
+}
+
+/// Returns the ID of the index succeeding `index_id` in a rollover, incrementing the number it
+/// ends with while preserving its zero padding.
+fn next_index_id(index_id: &str) -> Option<IndexId> {
+    let (prefix, counter_str) = index_id.rsplit_once('-')?;
+
+    if counter_str.is_empty() || !counter_str.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    let next_counter = counter_str.parse::<u64>().ok()?.checked_add(1)?;
+    let width = counter_str.len();
+    Some(format!("{prefix}-{next_counter:0width$}"))
+}
+
+/// Returns the metadata of the index targeted by a rollover: the most recently created index
+/// holding the write alias `rollover_target`, or else the index `rollover_target`. The indexes
+/// outside of the scope of the token of the request are ignored.
+async fn resolve_rollover_target(
+    metastore: &MetastoreServiceClient,
+    rollover_target: &str,
+) -> MetastoreResult<IndexMetadata> {
+    let indexes_metadata = metastore
+        .list_indexes_metadata(ListIndexesMetadataRequest::all())
+        .await?
+        .deserialize_indexes_metadata()
+        .await?;
+    let holds_write_alias = |index_metadata: &IndexMetadata| {
+        index_metadata
+            .index_config
+            .ingest_settings
+            .write_alias
+            .as_deref()
+            == Some(rollover_target)
+    };
+    indexes_metadata
+        .into_iter()
+        .filter(|index_metadata| {
+            (holds_write_alias(index_metadata) || index_metadata.index_id() == rollover_target)
+                && ensure_indexes_in_scope([index_metadata.index_id()]).is_ok()
+        })
+        .max_by_key(|index_metadata| {
+            (
+                holds_write_alias(index_metadata),
+                index_metadata.create_timestamp,
+            )
+        })
+        .ok_or_else(|| {
+            MetastoreError::NotFound(EntityKind::Index {
+                index_id: rollover_target.to_string(),
+            })
+        })
+}
+
+/// Computes the statistics of the index from the aggregated stats of its splits. The published
+/// documents are counted only if the `max_docs` condition is set, and up to its value.
+async fn fetch_rollover_stats(
+    metastore: &MetastoreServiceClient,
+    index_metadata: &IndexMetadata,
+    max_docs_opt: Option<u64>,
+) -> MetastoreResult<RolloverStats> {
+    let list_index_stats_request = ListIndexStatsRequest {
+        index_id_patterns: vec![index_metadata.index_id().to_string()],
+    };
+    let size_bytes = metastore
+        .list_index_stats(list_index_stats_request)
+        .await?
+        .index_stats
+        .into_iter()
+        .find(|index_stats| index_stats.index_uid.as_ref() == Some(&index_metadata.index_uid))
+        .and_then(|index_stats| index_stats.published)
+        .map(|published_split_stats| published_split_stats.total_size_bytes)
+        .unwrap_or_default();
+
+    let num_docs = match max_docs_opt {
+        Some(max_docs) => count_published_docs(metastore, index_metadata, max_docs).await?,
+        None => 0,
+    };
+    let now_secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs() as i64)
+        .unwrap_or_default();
+    let age_secs = (now_secs - index_metadata.create_timestamp).max(0) as u64;
+
+    Ok(RolloverStats {
+        age: Duration::from_secs(age_secs),
+        num_docs,
+        size: ByteSize(size_bytes),
+    })
+}
+
+/// Counts the published documents of the index, streaming its splits until `max_docs` documents
+/// are counted. The split stats of the metastore do not carry the number of documents.
+async fn count_published_docs(
+    metastore: &MetastoreServiceClient,
+    index_metadata: &IndexMetadata,
+    max_docs: u64,
+) -> MetastoreResult<u64> {
+    let query = ListSplitsQuery::for_index(index_metadata.index_uid.clone())
+        .with_split_state(SplitState::Published);
+    let list_splits_request = ListSplitsRequest::try_from_list_splits_query(&query)?;
+    let mut splits_stream = metastore.list_splits(list_splits_request).await?;
+    let mut num_docs = 0;
+
+    while num_docs < max_docs
+        && let Some(list_splits_response) = splits_stream.try_next().await?
+    {
+        for split_metadata in list_splits_response.deserialize_splits_metadata().await? {
+            num_docs += split_metadata.num_docs as u64;
+        }
+    }
+    Ok(num_docs)
+}
+
+fn evaluate_rollover_conditions(
+    rollover_conditions: &RolloverConditions,
+    rollover_stats: &RolloverStats,
+) -> BTreeMap<String, bool> {
+    let mut conditions = BTreeMap::new();
+
+    if let Some(max_age) = &rollover_conditions.max_age {
+        conditions.insert("max_age".to_string(), rollover_stats.age >= **max_age);
+    }
+    if let Some(max_docs) = rollover_conditions.max_docs {
+        conditions.insert("max_docs".to_string(), rollover_stats.num_docs >= max_docs);
+    }
+    if let Some(max_size) = rollover_conditions.max_size {
+        conditions.insert("max_size".to_string(), rollover_stats.size >= max_size);
+    }
+    conditions
+}
+
+/// Builds the config of the index `index_id` from the index template matching its ID with the
+/// highest priority.
+async fn apply_index_template(
+    metastore: &MetastoreServiceClient,
+    index_id: IndexId,
+    default_index_root_uri: &Uri,
+) -> Result<IndexConfig, IndexServiceError> {
+    let find_index_template_matches_request = FindIndexTemplateMatchesRequest {
+        index_ids: vec![index_id.clone()],
+    };
+    let find_index_template_matches_response = metastore
+        .find_index_template_matches(find_index_template_matches_request)
+        .await?;
+    let Some(index_template_match) = find_index_template_matches_response.matches.first() else {
+        return Err(IndexServiceError::InvalidConfig(anyhow::anyhow!(
+            "no index template matches the index ID `{index_id}`"
+        )));
+    };
+    let index_template: IndexTemplate =
+        serde_utils::from_json_str(&index_template_match.index_template_json)?;
+    index_template
+        .apply_template(index_id, default_index_root_uri)
+        .map_err(IndexServiceError::InvalidConfig)
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,203 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use quickwit_metastore::{
+    CreateIndexRequestExt, IndexMetadataResponseExt, SplitMetadata, StageSplitsRequestExt,
+    metastore_for_test,
+};
+use quickwit_proto::metastore::{
+    CreateIndexRequest, CreateIndexTemplateRequest, IndexMetadataRequest, PublishSplitsRequest,
+    StageSplitsRequest,
+};
+use quickwit_storage::StorageResolver;
+
+use super::*;
+use crate::index_api::index_management_handlers;
+
+#[test]
+fn test_next_index_id() {
+    assert_eq!(next_index_id("logs-000001").unwrap(), "logs-000002");
+    assert_eq!(next_index_id("logs-2024-09").unwrap(), "logs-2024-10");
+    assert_eq!(next_index_id("logs-999").unwrap(), "logs-1000");
+    assert!(next_index_id("logs").is_none());
+    assert!(next_index_id("logs-").is_none());
+    assert!(next_index_id("logs-v1").is_none());
+}
+
+#[tokio::test]
+async fn test_rollover_index() {
+    let metastore = metastore_for_test();
+
+    let index_template = IndexTemplate::for_test("test-template", &["logs-*"], 0);
+    let create_index_template_request = CreateIndexTemplateRequest {
+        index_template_json: serde_utils::to_json_str(&index_template).unwrap(),
+        overwrite: false,
+    };
+    metastore
+        .create_index_template(create_index_template_request)
+        .await
+        .unwrap();
+
+    let mut index_config = IndexConfig::for_test("logs-000001", "ram:///indexes/logs-000001");
+    index_config.ingest_settings.write_alias = Some("logs".to_string());
+    let create_index_request = CreateIndexRequest::try_from_index_config(&index_config).unwrap();
+    let index_uid = metastore
+        .create_index(create_index_request)
+        .await
+        .unwrap()
+        .index_uid()
+        .clone();
+    let split_metadata = SplitMetadata {
+        split_id: "split-1".to_string(),
+        index_uid: index_uid.clone(),
+        num_docs: 10,
+        ..Default::default()
+    };
+    let stage_splits_request =
+        StageSplitsRequest::try_from_split_metadata(index_uid.clone(), &split_metadata).unwrap();
+    metastore.stage_splits(stage_splits_request).await.unwrap();
+    let publish_splits_request = PublishSplitsRequest {
+        index_uid: Some(index_uid),
+        staged_split_ids: vec!["split-1".to_string()],
+        ..Default::default()
+    };
+    metastore
+        .publish_splits(publish_splits_request)
+        .await
+        .unwrap();
+
+    let index_service = IndexService::new(metastore.clone(), StorageResolver::unconfigured());
+    let index_management_handler =
+        index_management_handlers(index_service, Arc::new(NodeConfig::for_test()));
+
+    // No condition is met: the rollover is a no-op.
+    let resp = warp::test::request()
+        .path("/indexes/logs/_rollover")
+        .method("POST")
+        .json(&serde_json::json!({
+            "conditions": {"max_age": "1d", "max_docs": 100}
+        }))
+        .reply(&index_management_handler)
+        .await;
+    assert_eq!(resp.status(), 200);
+    let rollover_response: RolloverResponse = serde_json::from_slice(resp.body()).unwrap();
+    let expected_rollover_response = RolloverResponse {
+        old_index: "logs-000001".to_string(),
+        new_index: "logs-000002".to_string(),
+        rolled_over: false,
+        conditions: BTreeMap::from([
+            ("max_age".to_string(), false),
+            ("max_docs".to_string(), false),
+        ]),
+    };
+    assert_eq!(rollover_response, expected_rollover_response);
+
+    let index_metadata_request = IndexMetadataRequest::for_index_id("logs-000002".to_string());
+    let error = metastore
+        .index_metadata(index_metadata_request)
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        MetastoreError::NotFound(EntityKind::Index { .. })
+    ));
+
+    // The `max_docs` condition is met: the new index is created from the template, and the
+    // write alias moves to it.
+    let resp = warp::test::request()
+        .path("/indexes/logs/_rollover")
+        .method("POST")
+        .json(&serde_json::json!({
+            "conditions": {"max_age": "1d", "max_docs": 10}
+        }))
+        .reply(&index_management_handler)
+        .await;
+    assert_eq!(resp.status(), 200);
+    let rollover_response: RolloverResponse = serde_json::from_slice(resp.body()).unwrap();
+    assert!(rollover_response.rolled_over);
+    assert_eq!(rollover_response.new_index, "logs-000002");
+    assert!(rollover_response.conditions["max_docs"]);
+
+    let index_metadata_request = IndexMetadataRequest::for_index_id("logs-000002".to_string());
+    let index_metadata = metastore
+        .index_metadata(index_metadata_request)
+        .await
+        .unwrap()
+        .deserialize_index_metadata()
+        .unwrap();
+    assert_eq!(
+        index_metadata.index_config.index_uri,
+        "ram:///indexes/logs-000002"
+    );
+
+    let doc_mapping = &index_metadata.index_config.doc_mapping;
+    assert_eq!(doc_mapping.timestamp_field.as_deref(), Some("ts"));
+    assert_eq!(
+        index_metadata
+            .index_config
+            .ingest_settings
+            .write_alias
+            .as_deref(),
+        Some("logs")
+    );
+    let index_metadata_request = IndexMetadataRequest::for_index_id("logs-000001".to_string());
+    let old_index_metadata = metastore
+        .index_metadata(index_metadata_request)
+        .await
+        .unwrap()
+        .deserialize_index_metadata()
+        .unwrap();
+    assert!(
+        old_index_metadata
+            .index_config
+            .ingest_settings
+            .write_alias
+            .is_none()
+    );
+
+    // The alias now targets the new index.
+    let resp = warp::test::request()
+        .path("/indexes/logs/_rollover")
+        .method("POST")
+        .json(&serde_json::json!({"conditions": {"max_docs": 10}}))
+        .reply(&index_management_handler)
+        .await;
+    assert_eq!(resp.status(), 200);
+    let rollover_response: RolloverResponse = serde_json::from_slice(resp.body()).unwrap();
+    assert_eq!(rollover_response.old_index, "logs-000002");
+    assert_eq!(rollover_response.new_index, "logs-000003");
+    assert!(!rollover_response.rolled_over);
+
+    // Rolling over an unknown index or alias fails.
+    let resp = warp::test::request()
+        .path("/indexes/unknown/_rollover")
+        .method("POST")
+        .json(&serde_json::json!({}))
+        .reply(&index_management_handler)
+        .await;
+    assert_eq!(resp.status(), 404);
+
+    // Rolling over an index whose ID does not end with a number is rejected.
+    let index_config = IndexConfig::for_test("app-logs", "ram:///indexes/app-logs");
+    let create_index_request = CreateIndexRequest::try_from_index_config(&index_config).unwrap();
+    metastore.create_index(create_index_request).await.unwrap();
+
+    let resp = warp::test::request()
+        .path("/indexes/app-logs/_rollover")
+        .method("POST")
+        .json(&serde_json::json!({}))
+        .reply(&index_management_handler)
+        .await;
+    assert_eq!(resp.status(), 400);
+}
@@ -0,0 +1,297 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use quickwit_metastore::IndexMetadataResponseExt;
+use quickwit_proto::metastore::{
+    IndexMetadataRequest, MetastoreResult, MetastoreService, MetastoreServiceClient,
+};
+use quickwit_proto::types::IndexId;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use tracing::info;
+use warp::{Filter, Rejection};
+
+use super::doc_mapping_schema::{UnsupportedFeature, build_avro_schema, build_json_schema};
+use crate::format::BodyFormat;
+use crate::query_params::query_params;
+use crate::request_scope::ensure_indexes_in_scope;
+use crate::rest_api_response::into_rest_api_response;
+use crate::with_arg;
+
+/// Schema language the doc mapping is translated into.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SchemaFormat {
+    JsonSchema,
+    Avro,
+}
+
+#[derive(Debug, Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct IndexSchemaQueryParams {
+    /// Schema language: `jsonschema` or `avro`.
+    pub format: SchemaFormat,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct IndexSchemaResponse {
+    pub index_id: IndexId,
+    pub format: SchemaFormat,
+    #[schema(value_type = Object)]
+    pub schema: JsonValue,
+    /// Features of the doc mapping the schema does not capture, or captures loosely.
+    pub unsupported_features: Vec<UnsupportedFeature>,
+}
+
+pub fn get_index_schema_handler(
+    metastore: MetastoreServiceClient,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("indexes" / String / "schema")
+        .and(warp::get())
+        .and(query_params::<IndexSchemaQueryParams>())
+        .and(with_arg(metastore))
+        .then(get_index_schema)
+        // The `format` parameter names the schema language, so the schema is always JSON.
+        .map(|result| into_rest_api_response(result, BodyFormat::PrettyJson))
+        .boxed()
+}
+
+#[utoipa::path(
+    get,
+    tag = "Indexes",
+    path = "/indexes/{index_id}/schema",
+    responses(
+        (status = 200, description = "Successfully translated the doc mapping of the index.", body = IndexSchemaResponse)
+    ),
+    params(
+        ("index_id" = String, Path, description = "The index ID to translate the doc mapping of."),
+        IndexSchemaQueryParams,
+    )
+)]
+/// Translates the doc mapping of an index into a JSON Schema or an Avro schema.
+///
+/// The schema describes the documents returned by searches. The features of the doc mapping it
+/// cannot express are listed in `unsupported_features`.
+pub async fn get_index_schema(
+    index_id: IndexId,
+    query_params: IndexSchemaQueryParams,
+    metastore: MetastoreServiceClient,
+) -> MetastoreResult<IndexSchemaResponse> {
+    info!(index_id = %index_id, format = ?query_params.format, "get-index-schema");
+    ensure_indexes_in_scope([index_id.as_str()])?;
+    let index_metadata_request = IndexMetadataRequest::for_index_id(index_id);
+    let index_metadata = metastore
+        .index_metadata(index_metadata_request)
+        .await?
+        .deserialize_index_metadata()?;
+    let index_config = index_metadata.index_config;
+    let mut unsupported_features = Vec::new();
+    let schema = match query_params.format {
+        SchemaFormat::JsonSchema => build_json_schema(
+            &index_config.index_id,
+            &index_config.doc_mapping,
+            &mut unsupported_features,
+        ),
+        SchemaFormat::Avro => build_avro_schema(
+            &index_config.index_id,
+            &index_config.doc_mapping,
+            &mut unsupported_features,
+        ),
+    };
+    Ok(IndexSchemaResponse {
+        index_id: index_config.index_id,
+        format: query_params.format,
+        schema,
+        unsupported_features,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use quickwit_config::{DocMapping, NodeConfig};
+    use quickwit_index_management::IndexService;
+    use quickwit_metastore::IndexMetadata;
+    use quickwit_proto::metastore::{IndexMetadataResponse, MockMetastoreService};
+    use quickwit_storage::StorageResolver;
+    use serde_json::json;
+
+    use super::*;
+    use crate::index_api::index_management_handlers;
+
+    fn index_management_handler()
+    -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+        let mut index_metadata =
+            IndexMetadata::for_test("test-schema", "ram:///indexes/test-schema");
+        index_metadata.index_config.doc_mapping = serde_json::from_value::<DocMapping>(json!({
+            "mode": "dynamic",
+            "field_mappings": [
+                { "name": "timestamp", "type": "datetime", "fast": true },
+                { "name": "tags", "type": "array<text>", "tokenizer": "raw" },
+                { "name": "latencies", "type": "array<f64>" },
+                { "name": "attributes", "type": "json" },
+                {
+                    "name": "resource",
+                    "type": "object",
+                    "field_mappings": [
+                        { "name": "service", "type": "text" },
+                        {
+                            "name": "host",
+                            "type": "object",
+                            "field_mappings": [
+                                { "name": "ip", "type": "ip" },
+                                { "name": "ports", "type": "array<u64>" }
+                            ]
+                        }
+                    ]
+                },
+                {
+                    "name": "all",
+                    "type": "concatenate",
+                    "concatenate_fields": ["resource.service"]
+                }
+            ],
+            "timestamp_field": "timestamp"
+        }))
+        .unwrap();
+        let mut mock_metastore = MockMetastoreService::new();
+        mock_metastore.expect_index_metadata().returning(move |_| {
+            Ok(IndexMetadataResponse::try_from_index_metadata(&index_metadata).unwrap())
+        });
+        let metastore = MetastoreServiceClient::from_mock(mock_metastore);
+        let index_service = IndexService::new(metastore, StorageResolver::unconfigured());
+        index_management_handlers(index_service, Arc::new(NodeConfig::for_test()))
+    }
+
+    async fn get_index_schema_response(format: &str) -> IndexSchemaResponse {
+        let response = warp::test::request()
+            .path(&format!("/indexes/test-schema/schema?format={format}"))
+            .reply(&index_management_handler())
+            .await;
+        assert_eq!(response.status(), 200);
+        serde_json::from_slice(response.body()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_get_index_schema_json_schema() {
+        let schema_response = get_index_schema_response("jsonschema").await;
+        assert_eq!(schema_response.format, SchemaFormat::JsonSchema);
+
+        let expected_schema = json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "title": "test-schema",
+            "type": "object",
+            "additionalProperties": true,
+            "properties": {
+                "timestamp": { "type": "string", "format": "date-time" },
+                "tags": { "type": "array", "items": { "type": "string" } },
+                "latencies": { "type": "array", "items": { "type": "number" } },
+                "attributes": { "type": "object" },
+                "resource": {
+                    "type": "object",
+                    "additionalProperties": true,
+                    "properties": {
+                        "service": { "type": "string" },
+                        "host": {
+                            "type": "object",
+                            "additionalProperties": true,
+                            "properties": {
+                                "ip": {
+                                    "type": "string",
+                                    "anyOf": [{ "format": "ipv4" }, { "format": "ipv6" }]
+                                },
+                                "ports": {
+                                    "type": "array",
+                                    "items": { "type": "integer", "minimum": 0 }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        });
+        assert_eq!(schema_response.schema, expected_schema);
+
+        let expected_unsupported_features = vec![UnsupportedFeature {
+            field: Some("all".to_string()),
+            reason: "concatenate fields are not part of the documents: omitted".to_string(),
+        }];
+        assert_eq!(
+            schema_response.unsupported_features,
+            expected_unsupported_features
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_index_schema_avro() {
+        let schema_response = get_index_schema_response("avro").await;
+        assert_eq!(schema_response.format, SchemaFormat::Avro);
+
+        let optional_field = |name: &str, field_type: JsonValue| json!({ "name": name, "type": ["null", field_type], "default": null });
+        let host_record = json!({
+            "type": "record",
+            "name": "test_schema_resource_host",
+            "fields": [
+                optional_field("ip", json!("string")),
+                optional_field("ports", json!({ "type": "array", "items": "long" })),
+            ]
+        });
+        let resource_record = json!({
+            "type": "record",
+            "name": "test_schema_resource",
+            "fields": [
+                optional_field("service", json!("string")),
+                optional_field("host", host_record),
+            ]
+        });
+        let expected_schema = json!({
+            "type": "record",
+            "name": "test_schema",
+            "namespace": "quickwit",
+            "fields": [
+                optional_field("timestamp", json!("string")),
+                optional_field("tags", json!({ "type": "array", "items": "string" })),
+                optional_field("latencies", json!({ "type": "array", "items": "double" })),
+                optional_field("attributes", json!("string")),
+                optional_field("resource", resource_record),
+            ]
+        });
+        assert_eq!(schema_response.schema, expected_schema);
+
+        let flagged_fields: Vec<Option<&str>> = schema_response
+            .unsupported_features
+            .iter()
+            .map(|unsupported_feature| unsupported_feature.field.as_deref())
+            .collect();
+        assert_eq!(
+            flagged_fields,
+            [
+                None,
+                Some("attributes"),
+                Some("resource.host.ports"),
+                Some("all")
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_index_schema_unknown_format() {
+        let response = warp::test::request()
+            .path("/indexes/test-schema/schema?format=protobuf")
+            .reply(&index_management_handler())
+            .await;
+        assert_eq!(response.status(), 400);
+    }
+}
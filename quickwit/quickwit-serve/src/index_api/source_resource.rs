@@ -32,6 +32,8 @@ use warp::{Filter, Rejection};
 
 use super::rest_handler::{json_body, log_failure};
 use crate::format::{extract_config_format, extract_format_from_qs};
+use crate::query_params::query_params;
+use crate::request_scope::ensure_indexes_in_scope;
 use crate::rest_api_response::into_rest_api_response;
 use crate::with_arg;
 
@@ -90,6 +92,7 @@ pub async fn create_source(
         load_source_config_from_user_config(config_format, &source_config_bytes)
             .map_err(IndexServiceError::InvalidConfig)?;
     check_source_type(&source_config.source_params)?;
+    ensure_indexes_in_scope([index_id.as_str()]).map_err(MetastoreError::from)?;
     let index_metadata_request = IndexMetadataRequest::for_index_id(index_id.to_string());
     let index_uid: IndexUid = index_service
         .metastore()
@@ -111,7 +114,7 @@ pub struct UpdateQueryParams {
 }
 
 fn update_source_qp() -> impl Filter<Extract = (UpdateQueryParams,), Error = Rejection> + Clone {
-    warp::query::<UpdateQueryParams>()
+    query_params::<UpdateQueryParams>()
 }
 
 pub fn update_source_handler(
@@ -155,6 +158,7 @@ pub async fn update_source(
     source_config_bytes: Bytes,
     mut index_service: IndexService,
 ) -> Result<SourceConfig, IndexServiceError> {
+    ensure_indexes_in_scope([index_id.as_str()]).map_err(MetastoreError::from)?;
     let index_metadata_request = IndexMetadataRequest::for_index_id(index_id.to_string());
     let mut current_index_metadata = index_service
         .metastore()
@@ -216,6 +220,7 @@ pub async fn get_source(
     metastore: MetastoreServiceClient,
 ) -> MetastoreResult<SourceConfig> {
     info!(index_id = %index_id, source_id = %source_id, "get-source");
+    ensure_indexes_in_scope([index_id.as_str()])?;
     let index_metadata_request = IndexMetadataRequest::for_index_id(index_id.to_string());
     let source_config = metastore
         .index_metadata(index_metadata_request)
@@ -262,6 +267,7 @@ pub async fn reset_source_checkpoint(
     source_id: SourceId,
     metastore: MetastoreServiceClient,
 ) -> MetastoreResult<()> {
+    ensure_indexes_in_scope([index_id.as_str()])?;
     let index_metadata_request = IndexMetadataRequest::for_index_id(index_id.to_string());
     let index_uid: IndexUid = metastore
         .index_metadata(index_metadata_request)
@@ -319,6 +325,7 @@ pub async fn toggle_source(
     metastore: MetastoreServiceClient,
 ) -> Result<(), IndexServiceError> {
     info!(index_id = %index_id, source_id = %source_id, enable = toggle_source.enable, "toggle-source");
+    ensure_indexes_in_scope([index_id.as_str()]).map_err(MetastoreError::from)?;
     let index_metadata_request = IndexMetadataRequest::for_index_id(index_id.to_string());
     let index_uid: IndexUid = metastore
         .index_metadata(index_metadata_request)
@@ -371,6 +378,7 @@ pub async fn delete_source(
     metastore: MetastoreServiceClient,
 ) -> Result<(), IndexServiceError> {
     info!(index_id = %index_id, source_id = %source_id, "delete-source");
+    ensure_indexes_in_scope([index_id.as_str()]).map_err(MetastoreError::from)?;
     let index_metadata_request = IndexMetadataRequest::for_index_id(index_id.to_string());
     let index_uid: IndexUid = metastore
         .index_metadata(index_metadata_request)
@@ -409,6 +417,7 @@ pub async fn get_source_shards(
     metastore: MetastoreServiceClient,
 ) -> MetastoreResult<Vec<Shard>> {
     info!(index_id = %index_id, source_id = %source_id, "get-source-shards");
+    ensure_indexes_in_scope([index_id.as_str()])?;
     let index_metadata_request = IndexMetadataRequest::for_index_id(index_id.to_string());
     let index_uid: IndexUid = metastore
         .index_metadata(index_metadata_request)
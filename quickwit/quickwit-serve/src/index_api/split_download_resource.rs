@@ -0,0 +1,454 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashSet;
+use std::ops::Range;
+use std::path::Path;
+
+use futures::TryStreamExt;
+use quickwit_common::split_file;
+use quickwit_index_management::IndexService;
+use quickwit_metastore::{
+    IndexMetadataResponseExt, ListSplitsQuery, ListSplitsRequestExt, ListSplitsResponseExt,
+};
+use quickwit_proto::metastore::{
+    IndexMetadataRequest, ListSplitsRequest, MetastoreError, MetastoreService,
+};
+use quickwit_proto::types::{IndexId, SplitId};
+use quickwit_proto::{ServiceError, ServiceErrorCode};
+use quickwit_storage::{StorageError, StorageErrorKind, StorageResolverError};
+use tokio_util::io::ReaderStream;
+use tracing::info;
+use warp::hyper::StatusCode;
+use warp::hyper::header::{
+    ACCEPT_RANGES, CONTENT_DISPOSITION, CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE,
+};
+use warp::hyper::http::{HeaderName, HeaderValue};
+use warp::{Filter, Rejection, Reply};
+
+use crate::format::extract_format_from_qs;
+use crate::request_scope::{AdminRequired, ensure_admin};
+use crate::rest_api_response::into_rest_api_response;
+use crate::{BodyFormat, with_arg};
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum DownloadSplitError {
+    #[error(transparent)]
+    AdminRequired(#[from] AdminRequired),
+    #[error(transparent)]
+    Metastore(#[from] MetastoreError),
+    #[error("range not satisfiable: the split file holds {num_bytes} bytes")]
+    RangeNotSatisfiable { num_bytes: u64 },
+    #[error("split `{split_id}` not found in index `{index_id}`")]
+    SplitNotFound {
+        index_id: IndexId,
+        split_id: SplitId,
+    },
+    #[error("storage error: {0}")]
+    Storage(#[from] StorageError),
+    #[error("failed to resolve the storage: {0}")]
+    StorageResolver(#[from] StorageResolverError),
+}
+
+impl ServiceError for DownloadSplitError {
+    fn error_code(&self) -> ServiceErrorCode {
+        match self {
+            Self::AdminRequired(_) => ServiceErrorCode::Forbidden,
+            Self::Metastore(metastore_error) => metastore_error.error_code(),
+            Self::RangeNotSatisfiable { .. } => ServiceErrorCode::BadRequest,
+            Self::SplitNotFound { .. } => ServiceErrorCode::NotFound,
+            Self::Storage(storage_error) if storage_error.kind() == StorageErrorKind::NotFound => {
+                ServiceErrorCode::NotFound
+            }
+            Self::Storage(_) | Self::StorageResolver(_) => ServiceErrorCode::Internal,
+        }
+    }
+}
+
+/// A split file, or a range of it, ready to be streamed to the client.
+pub(crate) struct SplitFileDownload {
+    split_id: SplitId,
+    num_bytes: u64,
+    /// Range of the file to send, or `None` for the whole file.
+    range_opt: Option<Range<u64>>,
+    body: warp::Body,
+}
+
+pub fn download_split_handler(
+    index_service: IndexService,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("indexes" / String / "splits" / String / "download")
+        .and(warp::get())
+        .and(warp::header::optional::<String>("range"))
+        .and(with_arg(index_service))
+        .then(download_split)
+        .and(extract_format_from_qs())
+        .map(into_download_split_response)
+        .boxed()
+}
+
+#[utoipa::path(
+    get,
+    tag = "Splits",
+    path = "/indexes/{index_id}/splits/{split_id}/download",
+    responses(
+        (status = 200, description = "Successfully downloaded the split file."),
+        (status = 206, description = "Successfully downloaded the requested range of the split file."),
+        (status = 403, description = "The token of the request does not grant admin access."),
+        (status = 404, description = "The split does not exist in the index."),
+        (status = 416, description = "The requested range is not satisfiable."),
+    ),
+    params(
+        ("index_id" = String, Path, description = "The index ID of the split."),
+        ("split_id" = String, Path, description = "The ID of the split to download."),
+    )
+)]
+/// Downloads a split file.
+///
+/// Streams the split file from the storage of the index, for processing it with external tools.
+/// Supports single-range `Range` headers, for resuming interrupted downloads.
+pub(crate) async fn download_split(
+    index_id: IndexId,
+    split_id: SplitId,
+    range_header_opt: Option<String>,
+    index_service: IndexService,
+) -> Result<SplitFileDownload, DownloadSplitError> {
+    ensure_admin()?;
+    info!(index_id = %index_id, split_id = %split_id, range = ?range_header_opt, "download-split");
+
+    let metastore = index_service.metastore();
+    let index_metadata_request = IndexMetadataRequest::for_index_id(index_id.clone());
+    let index_metadata = metastore
+        .index_metadata(index_metadata_request)
+        .await?
+        .deserialize_index_metadata()?;
+
+    let list_splits_query = ListSplitsQuery::for_index(index_metadata.index_uid.clone())
+        .with_split_ids(HashSet::from([split_id.clone()]));
+    let list_splits_request = ListSplitsRequest::try_from_list_splits_query(&list_splits_query)?;
+    let mut list_splits_stream = metastore.list_splits(list_splits_request).await?;
+    let mut split_found = false;
+
+    // The split ID filter is ignored by older metastores, so the listed splits are checked too.
+    while let Some(list_splits_response) = list_splits_stream.try_next().await? {
+        let split_ids = list_splits_response.deserialize_split_ids().await?;
+
+        if split_ids.contains(&split_id) {
+            split_found = true;
+            break;
+        }
+    }
+    if !split_found {
+        return Err(DownloadSplitError::SplitNotFound { index_id, split_id });
+    }
+    let storage = index_service
+        .storage_resolver()
+        .resolve(index_metadata.index_uri())
+        .await?;
+    let split_file_name = split_file(&split_id);
+    let split_file_path = Path::new(&split_file_name);
+    let num_bytes = storage.file_num_bytes(split_file_path).await?;
+
+    let range_opt = match &range_header_opt {
+        Some(range_header) => parse_range_header(range_header, num_bytes)?,
+        None => None,
+    };
+    let Range { start, end } = range_opt.clone().unwrap_or(0..num_bytes);
+
+    let body = if start == end {
+        // The storages do not support streaming empty ranges.
+        Vec::<u8>::new().into()
+    } else {
+        let reader = storage
+            .get_slice_stream(split_file_path, start as usize..end as usize)
+            .await?;
+        warp::Body::wrap_stream(ReaderStream::new(reader))
+    };
+    Ok(SplitFileDownload {
+        split_id,
+        num_bytes,
+        range_opt,
+        body,
+    })
+}
+
+/// Parses the `Range` header of a request for a file of `num_bytes` bytes, and returns the range
+/// of bytes to send. Returns `None` if the whole file should be sent: the headers holding several
+/// ranges or that cannot be parsed are ignored.
+fn parse_range_header(
+    range_header: &str,
+    num_bytes: u64,
+) -> Result<Option<Range<u64>>, DownloadSplitError> {
+    let Some(byte_range_spec) = range_header.trim().strip_prefix("bytes=") else {
+        return Ok(None);
+    };
+    if byte_range_spec.contains(',') {
+        return Ok(None);
+    }
+    let Some((first_byte_str, last_byte_str)) = byte_range_spec.split_once('-') else {
+        return Ok(None);
+    };
+    let range = match (first_byte_str.trim(), last_byte_str.trim()) {
+        // Suffix range, e.g. `bytes=-500` for the last 500 bytes.
+        ("", suffix_len_str) => {
+            let Ok(suffix_len) = suffix_len_str.parse::<u64>() else {
+                return Ok(None);
+            };
+            if suffix_len == 0 {
+                return Err(DownloadSplitError::RangeNotSatisfiable { num_bytes });
+            }
+            num_bytes.saturating_sub(suffix_len)..num_bytes
+        }
+        (first_byte_str, "") => {
+            let Ok(first_byte) = first_byte_str.parse::<u64>() else {
+                return Ok(None);
+            };
+            first_byte..num_bytes
+        }
+        (first_byte_str, last_byte_str) => {
+            let (Ok(first_byte), Ok(last_byte)) =
+                (first_byte_str.parse::<u64>(), last_byte_str.parse::<u64>())
+            else {
+                return Ok(None);
+            };
+            if last_byte < first_byte {
+                return Ok(None);
+            }
+            first_byte..last_byte.saturating_add(1).min(num_bytes)
+        }
+    };
+    if range.start >= num_bytes {
+        return Err(DownloadSplitError::RangeNotSatisfiable { num_bytes });
+    }
+    Ok(Some(range))
+}
+
+fn into_download_split_response(
+    download_result: Result<SplitFileDownload, DownloadSplitError>,
+    body_format: BodyFormat,
+) -> warp::reply::Response {
+    let split_file_download = match download_result {
+        Ok(split_file_download) => split_file_download,
+        Err(DownloadSplitError::RangeNotSatisfiable { num_bytes }) => {
+            let mut response = warp::reply::Response::new(Vec::<u8>::new().into());
+            *response.status_mut() = StatusCode::RANGE_NOT_SATISFIABLE;
+            insert_header(&mut response, CONTENT_RANGE, format!("bytes */{num_bytes}"));
+            return response;
+        }
+        Err(download_error) => {
+            return into_rest_api_response::<(), _>(Err(download_error), body_format)
+                .into_response();
+        }
+    };
+    let SplitFileDownload {
+        split_id,
+        num_bytes,
+        range_opt,
+        body,
+    } = split_file_download;
+
+    let mut response = warp::reply::Response::new(body);
+    let headers = response.headers_mut();
+    headers.insert(
+        CONTENT_TYPE,
+        HeaderValue::from_static("application/octet-stream"),
+    );
+    headers.insert(ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+
+    let content_disposition = format!("attachment; filename=\"{}\"", split_file(&split_id));
+    insert_header(&mut response, CONTENT_DISPOSITION, content_disposition);
+
+    match range_opt {
+        Some(Range { start, end }) => {
+            *response.status_mut() = StatusCode::PARTIAL_CONTENT;
+            let content_range = format!("bytes {start}-{}/{num_bytes}", end - 1);
+            insert_header(&mut response, CONTENT_RANGE, content_range);
+            insert_header(&mut response, CONTENT_LENGTH, (end - start).to_string());
+        }
+        None => {
+            insert_header(&mut response, CONTENT_LENGTH, num_bytes.to_string());
+        }
+    }
+    response
+}
+
+fn insert_header(
+    response: &mut warp::reply::Response,
+    header_name: HeaderName,
+    header_value: String,
+) {
+    if let Ok(header_value) = HeaderValue::try_from(header_value) {
+        response.headers_mut().insert(header_name, header_value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_common::uri::Uri;
+    use quickwit_config::IndexConfig;
+    use quickwit_metastore::{
+        CreateIndexRequestExt, SplitMetadata, StageSplitsRequestExt, metastore_for_test,
+    };
+    use quickwit_proto::metastore::{
+        CreateIndexRequest, MetastoreServiceClient, StageSplitsRequest,
+    };
+    use quickwit_proto::types::IndexUid;
+    use quickwit_storage::{PutPayload, StorageResolver};
+
+    use super::*;
+    use crate::recover_fn;
+
+    const SPLIT_FILE_CONTENT: &[u8] = b"0123456789";
+
+    async fn create_index(metastore: &MetastoreServiceClient, index_id: &str) -> IndexUid {
+        let index_uri = format!("ram:///indexes/{index_id}");
+        let index_config = IndexConfig::for_test(index_id, &index_uri);
+        let create_index_request =
+            CreateIndexRequest::try_from_index_config(&index_config).unwrap();
+        metastore
+            .create_index(create_index_request)
+            .await
+            .unwrap()
+            .index_uid()
+            .clone()
+    }
+
+    async fn index_service_for_test() -> IndexService {
+        let metastore = metastore_for_test();
+        let storage_resolver = StorageResolver::for_test();
+
+        let index_uid = create_index(&metastore, "test-index").await;
+        create_index(&metastore, "other-index").await;
+
+        let split_metadata = SplitMetadata {
+            split_id: "test-split".to_string(),
+            index_uid: index_uid.clone(),
+            ..Default::default()
+        };
+        let stage_splits_request =
+            StageSplitsRequest::try_from_split_metadata(index_uid, &split_metadata).unwrap();
+        metastore.stage_splits(stage_splits_request).await.unwrap();
+
+        let storage = storage_resolver
+            .resolve(&Uri::for_test("ram:///indexes/test-index"))
+            .await
+            .unwrap();
+        let split_file_payload: Box<dyn PutPayload> = Box::new(SPLIT_FILE_CONTENT.to_vec());
+        storage
+            .put(Path::new("test-split.split"), split_file_payload)
+            .await
+            .unwrap();
+        IndexService::new(metastore, storage_resolver)
+    }
+
+    #[tokio::test]
+    async fn test_download_split() {
+        let index_service = index_service_for_test().await;
+        let handler = download_split_handler(index_service).recover(recover_fn);
+
+        let response = warp::test::request()
+            .path("/indexes/test-index/splits/test-split/download")
+            .reply(&handler)
+            .await;
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.headers()[CONTENT_TYPE], "application/octet-stream");
+        assert_eq!(response.headers()[CONTENT_LENGTH], "10");
+        assert_eq!(response.headers()[ACCEPT_RANGES], "bytes");
+        assert_eq!(
+            response.headers()[CONTENT_DISPOSITION],
+            "attachment; filename=\"test-split.split\""
+        );
+        assert_eq!(response.body(), SPLIT_FILE_CONTENT);
+
+        // The split does not belong to the index.
+        let response = warp::test::request()
+            .path("/indexes/other-index/splits/test-split/download")
+            .reply(&handler)
+            .await;
+        assert_eq!(response.status(), 404);
+
+        let response = warp::test::request()
+            .path("/indexes/test-index/splits/unknown-split/download")
+            .reply(&handler)
+            .await;
+        assert_eq!(response.status(), 404);
+    }
+
+    #[tokio::test]
+    async fn test_download_split_range() {
+        let index_service = index_service_for_test().await;
+        let handler = download_split_handler(index_service).recover(recover_fn);
+
+        let response = warp::test::request()
+            .path("/indexes/test-index/splits/test-split/download")
+            .header("Range", "bytes=4-")
+            .reply(&handler)
+            .await;
+        assert_eq!(response.status(), 206);
+        assert_eq!(response.headers()[CONTENT_RANGE], "bytes 4-9/10");
+        assert_eq!(response.headers()[CONTENT_LENGTH], "6");
+        assert_eq!(response.body(), &b"456789"[..]);
+
+        let response = warp::test::request()
+            .path("/indexes/test-index/splits/test-split/download")
+            .header("Range", "bytes=2-4")
+            .reply(&handler)
+            .await;
+        assert_eq!(response.status(), 206);
+        assert_eq!(response.headers()[CONTENT_RANGE], "bytes 2-4/10");
+        assert_eq!(response.body(), &b"234"[..]);
+
+        let response = warp::test::request()
+            .path("/indexes/test-index/splits/test-split/download")
+            .header("Range", "bytes=10-")
+            .reply(&handler)
+            .await;
+        assert_eq!(response.status(), 416);
+        assert_eq!(response.headers()[CONTENT_RANGE], "bytes */10");
+    }
+
+    #[test]
+    fn test_parse_range_header() {
+        assert_eq!(
+            parse_range_header("bytes=0-499", 1000).unwrap(),
+            Some(0..500)
+        );
+        assert_eq!(
+            parse_range_header("bytes=500-", 1000).unwrap(),
+            Some(500..1000)
+        );
+        assert_eq!(
+            parse_range_header("bytes=-100", 1000).unwrap(),
+            Some(900..1000)
+        );
+        assert_eq!(
+            parse_range_header("bytes=-2000", 1000).unwrap(),
+            Some(0..1000)
+        );
+        assert_eq!(
+            parse_range_header("bytes=900-2000", 1000).unwrap(),
+            Some(900..1000)
+        );
+
+        // Ignored headers.
+        assert_eq!(parse_range_header("bytes=0-1,5-6", 1000).unwrap(), None);
+        assert_eq!(parse_range_header("items=0-1", 1000).unwrap(), None);
+        assert_eq!(parse_range_header("bytes=5-1", 1000).unwrap(), None);
+        assert_eq!(parse_range_header("bytes=a-b", 1000).unwrap(), None);
+
+        // Unsatisfiable ranges.
+        parse_range_header("bytes=1000-", 1000).unwrap_err();
+        parse_range_header("bytes=-0", 1000).unwrap_err();
+    }
+}
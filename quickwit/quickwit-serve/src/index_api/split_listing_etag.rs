@@ -0,0 +1,166 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt;
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use quickwit_metastore::SplitState;
+use quickwit_proto::metastore::IndexStats;
+use quickwit_proto::types::IndexUid;
+
+use super::split_resource::ListSplitsQueryParams;
+use crate::format::BodyFormat;
+
+/// Returns a hash of the statistics of the splits of an index, along with the parameters of the
+/// listing. It changes whenever a split is staged, published, marked for deletion, or deleted.
+/// The other updates of the splits, such as the updates of their tags, are caught by comparing
+/// their update timestamps with the one of the ETag.
+pub(super) fn splits_version(
+    index_uid: &IndexUid,
+    index_stats: &IndexStats,
+    list_split_query: &ListSplitsQueryParams,
+    body_format: BodyFormat,
+) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    index_uid.to_string().hash(&mut hasher);
+
+    for split_stats_opt in [
+        &index_stats.staged,
+        &index_stats.published,
+        &index_stats.marked_for_deletion,
+    ] {
+        split_stats_opt
+            .as_ref()
+            .map(|split_stats| (split_stats.num_splits, split_stats.total_size_bytes))
+            .hash(&mut hasher);
+    }
+    list_split_query.offset.hash(&mut hasher);
+    list_split_query.limit.hash(&mut hasher);
+    list_split_query
+        .split_states
+        .as_ref()
+        .map(|split_states| {
+            split_states
+                .iter()
+                .map(SplitState::as_str)
+                .collect::<Vec<&str>>()
+        })
+        .hash(&mut hasher);
+    list_split_query.start_timestamp.hash(&mut hasher);
+    list_split_query.end_timestamp.hash(&mut hasher);
+    list_split_query.end_create_timestamp.hash(&mut hasher);
+    body_format.to_string().hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Weak ETag of a splits listing. It is made of:
+/// - a hash of the serialized listing, so that a listing whose content did not change is not sent
+///   again;
+/// - the version of the splits and the latest update timestamp of the listed splits, so that the
+///   listing is not fetched again from the metastore while the splits are unchanged.
+#[derive(Debug, PartialEq)]
+pub(super) struct SplitsListingETag {
+    pub content_hash: u64,
+    pub splits_version: u64,
+    pub max_update_timestamp: i64,
+}
+
+impl SplitsListingETag {
+    fn parse(etag: &str) -> Option<Self> {
+        let etag = etag.strip_prefix("W/").unwrap_or(etag);
+        let etag = etag.strip_prefix('"')?.strip_suffix('"')?;
+        let mut etag_parts = etag.split('-');
+        let content_hash = u64::from_str_radix(etag_parts.next()?, 16).ok()?;
+        let splits_version = u64::from_str_radix(etag_parts.next()?, 16).ok()?;
+        let max_update_timestamp = etag_parts.next()?.parse().ok()?;
+
+        if etag_parts.next().is_some() {
+            return None;
+        }
+        Some(Self {
+            content_hash,
+            splits_version,
+            max_update_timestamp,
+        })
+    }
+}
+
+impl fmt::Display for SplitsListingETag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "W/\"{:016x}-{:016x}-{}\"",
+            self.content_hash, self.splits_version, self.max_update_timestamp
+        )
+    }
+}
+
+/// Returns the first ETag listed in the `If-None-Match` header value identifying a listing of
+/// the given version of the splits.
+pub(super) fn find_etag_of_version(
+    if_none_match: &str,
+    splits_version: u64,
+) -> Option<SplitsListingETag> {
+    if_none_match
+        .split(',')
+        .filter_map(|listed_etag| SplitsListingETag::parse(listed_etag.trim()))
+        .find(|etag| etag.splits_version == splits_version)
+}
+
+/// Returns whether the `If-None-Match` header value lists an ETag of a listing with the same
+/// content, or is `*`.
+pub(super) fn if_none_match_contains_content(if_none_match: &str, content_hash: u64) -> bool {
+    if_none_match.split(',').map(str::trim).any(|listed_etag| {
+        listed_etag == "*"
+            || matches!(
+                SplitsListingETag::parse(listed_etag),
+                Some(etag) if etag.content_hash == content_hash
+            )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_splits_listing_etag_round_trip() {
+        let etag = SplitsListingETag {
+            content_hash: 0x1234,
+            splits_version: 0xabcd,
+            max_update_timestamp: 1_700_000_000,
+        };
+        let etag_str = etag.to_string();
+        assert_eq!(
+            etag_str,
+            "W/\"0000000000001234-000000000000abcd-1700000000\""
+        );
+        assert_eq!(SplitsListingETag::parse(&etag_str), Some(etag));
+        assert!(SplitsListingETag::parse("W/\"0000000000001234\"").is_none());
+        assert!(SplitsListingETag::parse("\"1234-abcd-1-2\"").is_none());
+    }
+
+    #[test]
+    fn test_if_none_match_etags() {
+        let if_none_match = "W/\"0000000000000001-0000000000000002-3\", \"legacy\"";
+        let etag = find_etag_of_version(if_none_match, 2).unwrap();
+        assert_eq!(etag.content_hash, 1);
+        assert_eq!(etag.max_update_timestamp, 3);
+        assert!(find_etag_of_version(if_none_match, 1).is_none());
+
+        assert!(if_none_match_contains_content(if_none_match, 1));
+        assert!(!if_none_match_contains_content(if_none_match, 2));
+        assert!(if_none_match_contains_content("*", 2));
+    }
+}
@@ -12,21 +12,33 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::hash::{DefaultHasher, Hash, Hasher};
+
 use quickwit_metastore::{
     IndexMetadataResponseExt, ListSplitsQuery, ListSplitsRequestExt,
-    MetastoreServiceStreamSplitsExt, Split, SplitState,
+    MetastoreServiceStreamSplitsExt, Split, SplitState, UpdateSplitsTagsRequestExt,
 };
 use quickwit_proto::metastore::{
-    IndexMetadataRequest, ListSplitsRequest, MarkSplitsForDeletionRequest, MetastoreResult,
-    MetastoreService, MetastoreServiceClient,
+    EntityKind, IndexMetadataRequest, ListIndexStatsRequest, ListSplitsRequest,
+    MarkSplitsForDeletionRequest, MetastoreError, MetastoreResult, MetastoreService,
+    MetastoreServiceClient, UpdateSplitsTagsRequest,
 };
 use quickwit_proto::types::{IndexId, IndexUid, SplitId};
 use serde::{Deserialize, Serialize};
 use tracing::info;
-use warp::{Filter, Rejection};
+use warp::hyper::StatusCode;
+use warp::hyper::header::{CONTENT_TYPE, ETAG, IF_NONE_MATCH};
+use warp::hyper::http::HeaderValue;
+use warp::reply::Response;
+use warp::{Filter, Rejection, Reply};
 
 use super::rest_handler::json_body;
-use crate::format::extract_format_from_qs;
+use super::split_listing_etag::{
+    SplitsListingETag, find_etag_of_version, if_none_match_contains_content, splits_version,
+};
+use crate::format::{BodyFormat, extract_format_from_qs};
+use crate::query_params::query_params;
+use crate::request_scope::{ensure_admin, ensure_indexes_in_scope};
 use crate::rest_api_response::into_rest_api_response;
 use crate::simple_list::{from_simple_list, to_simple_list};
 use crate::with_arg;
@@ -81,7 +93,8 @@ pub struct ListSplitsResponse {
     tag = "Indexes",
     path = "/indexes/{index_id}/splits",
     responses(
-        (status = 200, description = "Successfully fetched splits.", body = ListSplitsResponse)
+        (status = 200, description = "Successfully fetched splits.", body = ListSplitsResponse),
+        (status = 304, description = "The splits are unchanged since the listing identified by the `If-None-Match` ETag.")
     ),
     params(
         ListSplitsQueryParams,
@@ -90,17 +103,71 @@ pub struct ListSplitsResponse {
 )]
 
 /// Get splits.
+///
+/// Dashboards typically poll the splits listing. The response carries an ETag identifying the
+/// listed splits, and a request whose `If-None-Match` header matches it gets an empty `304 Not
+/// Modified` response.
 pub async fn list_splits(
     index_id: IndexId,
     list_split_query: ListSplitsQueryParams,
+    body_format: BodyFormat,
+    if_none_match_opt: Option<String>,
     metastore: MetastoreServiceClient,
-) -> MetastoreResult<ListSplitsResponse> {
-    let index_metadata_request = IndexMetadataRequest::for_index_id(index_id.to_string());
-    let index_uid: IndexUid = metastore
-        .index_metadata(index_metadata_request)
+) -> Response {
+    let splits_listing_result = fetch_splits_listing(
+        index_id,
+        list_split_query,
+        body_format,
+        if_none_match_opt,
+        metastore,
+    )
+    .await;
+    into_list_splits_response(splits_listing_result, body_format)
+}
+
+/// Splits listing, or its ETag if the splits are unchanged since the listing identified by the
+/// `If-None-Match` header of the request.
+enum SplitsListing {
+    NotModified { etag: String },
+    Modified { body: Vec<u8>, etag: String },
+}
+
+async fn fetch_splits_listing(
+    index_id: IndexId,
+    list_split_query: ListSplitsQueryParams,
+    body_format: BodyFormat,
+    if_none_match_opt: Option<String>,
+    metastore: MetastoreServiceClient,
+) -> MetastoreResult<SplitsListing> {
+    ensure_indexes_in_scope([index_id.as_str()])?;
+    // Fetching the split statistics of the index resolves its UID as well.
+    let list_index_stats_request = ListIndexStatsRequest {
+        index_id_patterns: vec![index_id.to_string()],
+    };
+    let Some((index_uid, index_stats)) = metastore
+        .list_index_stats(list_index_stats_request)
         .await?
-        .deserialize_index_metadata()?
-        .index_uid;
+        .index_stats
+        .into_iter()
+        .find_map(|index_stats| {
+            let index_uid = index_stats.index_uid.clone()?;
+            (index_uid.index_id == index_id).then_some((index_uid, index_stats))
+        })
+    else {
+        return Err(MetastoreError::NotFound(EntityKind::Index {
+            index_id: index_id.to_string(),
+        }));
+    };
+    let splits_version = splits_version(&index_uid, &index_stats, &list_split_query, body_format);
+
+    if let Some(if_none_match) = &if_none_match_opt
+        && let Some(etag) = find_etag_of_version(if_none_match, splits_version)
+        && !has_splits_updated_after(&metastore, &index_uid, etag.max_update_timestamp).await?
+    {
+        return Ok(SplitsListing::NotModified {
+            etag: etag.to_string(),
+        });
+    }
     info!(index_id = %index_id, list_split_query = ?list_split_query, "get-splits");
     let mut query = ListSplitsQuery::for_index(index_uid);
     let mut offset = 0;
@@ -129,26 +196,104 @@ pub async fn list_splits(
         .await?
         .collect_splits()
         .await?;
-    Ok(ListSplitsResponse {
+    let max_update_timestamp = splits
+        .iter()
+        .map(|split| split.update_timestamp)
+        .max()
+        .unwrap_or_default();
+    let list_splits_response = ListSplitsResponse {
         offset,
         size: splits.len(),
         splits,
+    };
+    let Ok(body) = body_format.result_to_vec::<_, ()>(&Ok(&list_splits_response)) else {
+        return Err(MetastoreError::Internal {
+            message: "failed to serialize the splits listing".to_string(),
+            cause: String::new(),
+        });
+    };
+    let mut hasher = DefaultHasher::new();
+    body_format.to_string().hash(&mut hasher);
+    body.hash(&mut hasher);
+
+    let etag = SplitsListingETag {
+        content_hash: hasher.finish(),
+        splits_version,
+        max_update_timestamp,
+    };
+    if let Some(if_none_match) = &if_none_match_opt
+        && if_none_match_contains_content(if_none_match, etag.content_hash)
+    {
+        return Ok(SplitsListing::NotModified {
+            etag: etag.to_string(),
+        });
+    }
+    Ok(SplitsListing::Modified {
+        body,
+        etag: etag.to_string(),
     })
 }
 
+/// Returns whether a split of the index was updated after the given timestamp, in seconds.
+async fn has_splits_updated_after(
+    metastore: &MetastoreServiceClient,
+    index_uid: &IndexUid,
+    timestamp: i64,
+) -> MetastoreResult<bool> {
+    let query = ListSplitsQuery::for_index(index_uid.clone())
+        .with_update_timestamp_gt(timestamp)
+        .with_limit(1);
+    let list_splits_request = ListSplitsRequest::try_from_list_splits_query(&query)?;
+    let splits = metastore
+        .list_splits(list_splits_request)
+        .await?
+        .collect_splits()
+        .await?;
+    Ok(!splits.is_empty())
+}
+
 pub fn list_splits_handler(
     metastore: MetastoreServiceClient,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
     warp::path!("indexes" / String / "splits")
         .and(warp::get())
-        .and(warp::query())
+        .and(query_params())
+        .and(extract_format_from_qs())
+        .and(warp::header::optional::<String>(IF_NONE_MATCH.as_str()))
         .and(with_arg(metastore))
         .then(list_splits)
-        .and(extract_format_from_qs())
-        .map(into_rest_api_response)
         .boxed()
 }
 
+fn into_list_splits_response(
+    splits_listing_result: MetastoreResult<SplitsListing>,
+    body_format: BodyFormat,
+) -> Response {
+    let (mut response, etag) = match splits_listing_result {
+        Ok(SplitsListing::NotModified { etag }) => {
+            let mut response = Response::default();
+            *response.status_mut() = StatusCode::NOT_MODIFIED;
+            (response, etag)
+        }
+        Ok(SplitsListing::Modified { body, etag }) => {
+            let mut response = Response::new(body.into());
+            response.headers_mut().insert(
+                CONTENT_TYPE,
+                HeaderValue::from_static(body_format.content_type()),
+            );
+            (response, etag)
+        }
+        Err(metastore_error) => {
+            return into_rest_api_response::<(), _>(Err(metastore_error), body_format)
+                .into_response();
+        }
+    };
+    if let Ok(etag_value) = HeaderValue::from_str(&etag) {
+        response.headers_mut().insert(ETAG, etag_value);
+    }
+    response
+}
+
 #[derive(Deserialize, utoipa::ToSchema)]
 #[serde(deny_unknown_fields)]
 pub struct SplitsForDeletion {
@@ -173,6 +318,7 @@ pub async fn mark_splits_for_deletion(
     splits_for_deletion: SplitsForDeletion,
     metastore: MetastoreServiceClient,
 ) -> MetastoreResult<()> {
+    ensure_indexes_in_scope([index_id.as_str()])?;
     let index_metadata_request = IndexMetadataRequest::for_index_id(index_id.to_string());
     let index_uid: IndexUid = metastore
         .index_metadata(index_metadata_request)
@@ -205,3 +351,95 @@ pub fn mark_splits_for_deletion_handler(
         .map(into_rest_api_response)
         .boxed()
 }
+
+/// Tags to add to and remove from the published splits matching a filter.
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct SplitsTagsUpdate {
+    /// If set, only the splits containing documents with a `timestamp >= start_timestamp` are
+    /// updated. This timestamp is in seconds.
+    #[serde(default)]
+    pub start_timestamp: Option<i64>,
+    /// If set, only the splits containing documents with a `timestamp < end_timestamp` are
+    /// updated. This timestamp is in seconds.
+    #[serde(default)]
+    pub end_timestamp: Option<i64>,
+    /// If set, only the splits carrying all these tags are updated.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Tags to add to the splits.
+    #[serde(default)]
+    pub add_tags: Vec<String>,
+    /// Tags to remove from the splits. They are removed before the tags of `add_tags` are added.
+    #[serde(default)]
+    pub remove_tags: Vec<String>,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct SplitsTagsUpdateResponse {
+    /// Number of splits whose tags changed.
+    pub num_updated_splits: u64,
+}
+
+#[utoipa::path(
+    put,
+    tag = "Splits",
+    path = "/indexes/{index_id}/splits/tags",
+    request_body = SplitsTagsUpdate,
+    responses(
+        (status = 200, description = "Successfully updated the tags of the splits.", body = SplitsTagsUpdateResponse),
+        (status = 403, description = "The token of the request does not grant admin access."),
+    ),
+    params(
+        ("index_id" = String, Path, description = "The index ID to update the splits tags for."),
+    )
+)]
+/// Adds and removes tags to/from the published splits matching a filter, in a single metastore
+/// transaction.
+pub async fn update_splits_tags(
+    index_id: IndexId,
+    splits_tags_update: SplitsTagsUpdate,
+    metastore: MetastoreServiceClient,
+) -> MetastoreResult<SplitsTagsUpdateResponse> {
+    ensure_admin()?;
+
+    if splits_tags_update.add_tags.is_empty() && splits_tags_update.remove_tags.is_empty() {
+        return Err(MetastoreError::InvalidArgument {
+            message: "at least one of `add_tags` and `remove_tags` must be set".to_string(),
+        });
+    }
+    let index_metadata_request = IndexMetadataRequest::for_index_id(index_id.to_string());
+    let index_metadata = metastore
+        .index_metadata(index_metadata_request)
+        .await?
+        .deserialize_index_metadata()?;
+    info!(index_id = %index_id, splits_tags_update = ?splits_tags_update, "update-splits-tags");
+    let update_splits_tags_request = UpdateSplitsTagsRequest {
+        index_uid: Some(index_metadata.index_uid),
+        time_range_start: splits_tags_update.start_timestamp,
+        time_range_end: splits_tags_update.end_timestamp,
+        tags: splits_tags_update.tags,
+        add_tags: splits_tags_update.add_tags,
+        remove_tags: splits_tags_update.remove_tags,
+    };
+    update_splits_tags_request
+        .validate_tags(&index_metadata.index_config.doc_mapping.tag_fields)?;
+    let num_updated_splits = metastore
+        .update_splits_tags(update_splits_tags_request)
+        .await?
+        .num_updated_splits;
+    Ok(SplitsTagsUpdateResponse { num_updated_splits })
+}
+
+pub fn update_splits_tags_handler(
+    metastore: MetastoreServiceClient,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("indexes" / String / "splits" / "tags")
+        .and(warp::put())
+        .and(json_body())
+        .and(with_arg(metastore))
+        .then(update_splits_tags)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+        .boxed()
+}
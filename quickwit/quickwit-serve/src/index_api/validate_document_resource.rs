@@ -0,0 +1,245 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use quickwit_config::{DocMapping, SearchSettings, build_doc_mapper};
+use quickwit_doc_mapper::JsonObject;
+use quickwit_index_management::IndexServiceError;
+use quickwit_proto::metastore::MetastoreError;
+use serde::{Deserialize, Serialize};
+use tantivy::schema::Document as _;
+use warp::{Filter, Rejection};
+
+use super::rest_handler::json_body;
+use crate::format::extract_format_from_qs;
+use crate::request_scope::ensure_indexes_in_scope;
+use crate::rest_api_response::into_rest_api_response;
+
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ValidateDocumentRequest {
+    /// The doc mapping to validate the document against.
+    pub doc_mapping: DocMapping,
+    /// The document to validate.
+    #[schema(value_type = Object)]
+    pub document: JsonObject,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct ValidateDocumentResponse {
+    /// Whether the document maps cleanly to the doc mapping.
+    pub valid: bool,
+    /// The reason why the document does not map to the doc mapping.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// The document fields, typed as they would be indexed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub fields: Option<JsonObject>,
+}
+
+pub fn validate_document_handler()
+-> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("_validate" / "document")
+        .and(warp::post())
+        .and(json_body())
+        .then(validate_document)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+        .boxed()
+}
+
+/// Validates a document against a doc mapping, without requiring an index.
+///
+/// The endpoint is not tied to an index, so it is only available to tokens allowed on all indexes.
+#[utoipa::path(
+    post,
+    tag = "Indexes",
+    path = "/_validate/document",
+    request_body = ValidateDocumentRequest,
+    responses(
+        (
+            status = 200,
+            description = "Successfully validated the document.",
+            body = ValidateDocumentResponse
+        ),
+        (status = 403, description = "The token of the request is restricted to some indexes."),
+    ),
+)]
+async fn validate_document(
+    validate_document_request: ValidateDocumentRequest,
+) -> Result<ValidateDocumentResponse, IndexServiceError> {
+    ensure_indexes_in_scope(["*"]).map_err(MetastoreError::from)?;
+    let ValidateDocumentRequest {
+        doc_mapping,
+        document,
+    } = validate_document_request;
+    let doc_mapper = build_doc_mapper(&doc_mapping, &SearchSettings::default())
+        .map_err(IndexServiceError::InvalidConfig)?;
+    let document_len = serde_json::to_vec(&document)
+        .map_err(|error| IndexServiceError::Internal(error.to_string()))?
+        .len() as u64;
+
+    let tantivy_doc = match doc_mapper.doc_from_json_obj(document, document_len) {
+        Ok((_partition, tantivy_doc)) => tantivy_doc,
+        Err(doc_parsing_error) => {
+            return Ok(ValidateDocumentResponse {
+                valid: false,
+                error: Some(doc_parsing_error.to_string()),
+                fields: None,
+            });
+        }
+    };
+    let named_doc = tantivy_doc.to_named_doc(&doc_mapper.schema());
+    let fields = doc_mapper.doc_to_json(named_doc.0).map_err(|error| {
+        IndexServiceError::Internal(format!("failed to convert document to JSON: {error}"))
+    })?;
+    Ok(ValidateDocumentResponse {
+        valid: true,
+        error: None,
+        fields: Some(fields),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use quickwit_config::NodeConfig;
+    use quickwit_index_management::IndexService;
+    use quickwit_proto::metastore::MetastoreServiceClient;
+    use quickwit_storage::StorageResolver;
+    use serde_json::{Value as JsonValue, json};
+    use warp::Filter;
+
+    use crate::index_api::index_management_handlers;
+    use crate::request_scope::with_index_scope_for_test;
+    use crate::rest::recover_fn;
+
+    async fn validate_document(request_json: JsonValue) -> (u16, JsonValue) {
+        let index_service = IndexService::new(
+            MetastoreServiceClient::mocked(),
+            StorageResolver::unconfigured(),
+        );
+        let index_management_handler =
+            index_management_handlers(index_service, Arc::new(NodeConfig::for_test()))
+                .recover(recover_fn);
+        let resp = warp::test::request()
+            .path("/_validate/document")
+            .method("POST")
+            .json(&request_json)
+            .reply(&index_management_handler)
+            .await;
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        (resp.status().as_u16(), resp_json)
+    }
+
+    fn doc_mapping_json() -> JsonValue {
+        json!({
+            "mode": "strict",
+            "field_mappings": [
+                {"name": "timestamp", "type": "datetime", "fast": true},
+                {"name": "severity", "type": "text", "tokenizer": "raw"},
+                {"name": "latency", "type": "f64"}
+            ],
+            "timestamp_field": "timestamp"
+        })
+    }
+
+    #[tokio::test]
+    async fn test_validate_document_clean() {
+        let request_json = json!({
+            "doc_mapping": doc_mapping_json(),
+            "document": {
+                "timestamp": "2024-01-01T00:00:00Z",
+                "severity": "INFO",
+                "latency": 12
+            }
+        });
+        let (status, resp_json) = validate_document(request_json).await;
+        assert_eq!(status, 200);
+        assert_eq!(resp_json["valid"], json!(true));
+        assert!(resp_json.get("error").is_none());
+        assert_eq!(resp_json["fields"]["severity"], json!("INFO"));
+        assert_eq!(resp_json["fields"]["latency"], json!(12.0));
+        assert_eq!(
+            resp_json["fields"]["timestamp"],
+            json!("2024-01-01T00:00:00Z")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_validate_document_violating_mapping() {
+        let request_json = json!({
+            "doc_mapping": doc_mapping_json(),
+            "document": {
+                "timestamp": "2024-01-01T00:00:00Z",
+                "severity": "INFO",
+                "latency": "fast"
+            }
+        });
+        let (status, resp_json) = validate_document(request_json).await;
+        assert_eq!(status, 200);
+        assert_eq!(resp_json["valid"], json!(false));
+        assert!(resp_json.get("fields").is_none());
+        let error = resp_json["error"].as_str().unwrap();
+        assert!(error.contains("latency"), "{error}");
+
+        let request_json = json!({
+            "doc_mapping": doc_mapping_json(),
+            "document": {
+                "timestamp": "2024-01-01T00:00:00Z",
+                "unknown_field": "foo"
+            }
+        });
+        let (status, resp_json) = validate_document(request_json).await;
+        assert_eq!(status, 200);
+        assert_eq!(resp_json["valid"], json!(false));
+    }
+
+    #[tokio::test]
+    async fn test_validate_document_invalid_doc_mapping() {
+        let request_json = json!({
+            "doc_mapping": {
+                "field_mappings": [{"name": "latency", "type": "f64"}],
+                "timestamp_field": "timestamp"
+            },
+            "document": {"latency": 12}
+        });
+        let (status, _resp_json) = validate_document(request_json).await;
+        assert_eq!(status, 400);
+    }
+
+    #[tokio::test]
+    async fn test_validate_document_out_of_scope() {
+        let request_json = json!({
+            "doc_mapping": doc_mapping_json(),
+            "document": {"timestamp": "2024-01-01T00:00:00Z"}
+        });
+        let (status, resp_json) =
+            with_index_scope_for_test(&["logs-*"], validate_document(request_json.clone())).await;
+        assert_eq!(status, 403);
+        assert!(
+            resp_json["message"]
+                .as_str()
+                .unwrap()
+                .contains("not allowed by the token"),
+            "{resp_json}"
+        );
+
+        let (status, resp_json) =
+            with_index_scope_for_test(&["*"], validate_document(request_json)).await;
+        assert_eq!(status, 200);
+        assert_eq!(resp_json["valid"], json!(true));
+    }
+}
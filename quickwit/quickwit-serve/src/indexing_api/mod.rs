@@ -12,6 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod pause_resource;
 mod rest_handler;
 
-pub use rest_handler::{IndexingApi, indexing_get_handler};
+pub use pause_resource::{pause_indexing_handler, resume_indexing_handler};
+pub use rest_handler::{IndexingApi, indexing_get_handler, indexing_rebalance_handler};
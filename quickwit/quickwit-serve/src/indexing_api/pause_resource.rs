@@ -0,0 +1,289 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use quickwit_config::IndexingPauseMode;
+use quickwit_index_management::{IndexService, IndexServiceError};
+use quickwit_metastore::{IndexMetadataResponseExt, ListIndexesMetadataResponseExt};
+use quickwit_proto::metastore::{
+    IndexMetadataRequest, ListIndexesMetadataRequest, MetastoreError, MetastoreResult,
+    MetastoreService, MetastoreServiceClient,
+};
+use quickwit_proto::types::IndexId;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+use warp::{Filter, Rejection};
+
+use crate::format::extract_format_from_qs;
+use crate::request_scope::ensure_admin;
+use crate::rest::recover_fn;
+use crate::rest_api_response::into_rest_api_response;
+use crate::with_arg;
+
+/// Indexing status of an index.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct IndexingPauseStatus {
+    pub index_id: IndexId,
+    /// Whether the indexing pipelines of the index are stopped.
+    pub paused: bool,
+    /// Fate of the documents ingested while indexing is paused, only returned if `paused` is
+    /// set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mode: Option<IndexingPauseMode>,
+}
+
+#[derive(Debug, Default, Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+struct PauseIndexingQueryParams {
+    /// Whether the documents ingested while indexing is paused are buffered until indexing
+    /// resumes (`buffer`) or rejected (`reject`). Defaults to `buffer`.
+    #[param(value_type = Option<IndexingPauseMode>)]
+    #[serde(default)]
+    mode: IndexingPauseMode,
+}
+
+pub fn pause_indexing_handler(
+    index_service: IndexService,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("indexing" / String / "pause")
+        .and(warp::post())
+        .and(warp::query::<PauseIndexingQueryParams>())
+        .and(with_arg(index_service))
+        .then(pause_indexing)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+        .recover(recover_fn)
+        .boxed()
+}
+
+#[utoipa::path(
+    post,
+    tag = "Indexing",
+    path = "/indexing/{index_id}/pause",
+    params(
+        ("index_id" = String, Path, description = "The index ID whose indexing to pause."),
+        PauseIndexingQueryParams,
+    ),
+    responses(
+        (status = 200, description = "Successfully paused the indexing of the index.", body = IndexingPauseStatus),
+        (status = 403, description = "The token of the request does not grant admin access."),
+    ),
+)]
+/// Pause Indexing
+///
+/// Stops the indexing pipelines of the index, without deleting its sources. The pause is stored
+/// in the index config (`ingest_settings.indexing_pause`), so it survives restarts, and updating
+/// the index config leaves it unchanged. The control
+/// plane removes the pipelines of the index from the indexing plan, and the documents ingested
+/// meanwhile are buffered or rejected, according to `mode`.
+async fn pause_indexing(
+    index_id: IndexId,
+    query_params: PauseIndexingQueryParams,
+    index_service: IndexService,
+) -> Result<IndexingPauseStatus, IndexServiceError> {
+    ensure_admin().map_err(MetastoreError::from)?;
+    info!(index_id = %index_id, mode = ?query_params.mode, "pause-indexing");
+    set_indexing_pause(index_id, Some(query_params.mode), index_service).await
+}
+
+pub fn resume_indexing_handler(
+    index_service: IndexService,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("indexing" / String / "resume")
+        .and(warp::post())
+        .and(with_arg(index_service))
+        .then(resume_indexing)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+        .recover(recover_fn)
+        .boxed()
+}
+
+#[utoipa::path(
+    post,
+    tag = "Indexing",
+    path = "/indexing/{index_id}/resume",
+    params(
+        ("index_id" = String, Path, description = "The index ID whose indexing to resume."),
+    ),
+    responses(
+        (status = 200, description = "Successfully resumed the indexing of the index.", body = IndexingPauseStatus),
+        (status = 403, description = "The token of the request does not grant admin access."),
+    ),
+)]
+/// Resume Indexing
+///
+/// Restarts the indexing pipelines of a paused index, which then index the documents buffered
+/// during the pause.
+async fn resume_indexing(
+    index_id: IndexId,
+    index_service: IndexService,
+) -> Result<IndexingPauseStatus, IndexServiceError> {
+    ensure_admin().map_err(MetastoreError::from)?;
+    info!(index_id = %index_id, "resume-indexing");
+    set_indexing_pause(index_id, None, index_service).await
+}
+
+/// Updates the index config through the control plane, which reschedules the indexing pipelines
+/// if the index is paused or resumed.
+async fn set_indexing_pause(
+    index_id: IndexId,
+    indexing_pause_opt: Option<IndexingPauseMode>,
+    index_service: IndexService,
+) -> Result<IndexingPauseStatus, IndexServiceError> {
+    let index_metadata_request = IndexMetadataRequest::for_index_id(index_id);
+    let index_metadata = index_service
+        .metastore()
+        .index_metadata(index_metadata_request)
+        .await?
+        .deserialize_index_metadata()?;
+    let index_uid = index_metadata.index_uid.clone();
+    let mut index_config = index_metadata.into_index_config();
+
+    if index_config.ingest_settings.indexing_pause != indexing_pause_opt {
+        index_config.ingest_settings.indexing_pause = indexing_pause_opt;
+        index_service
+            .update_index(index_uid.clone(), index_config)
+            .await?;
+    }
+    Ok(IndexingPauseStatus {
+        index_id: index_uid.index_id,
+        paused: indexing_pause_opt.is_some(),
+        mode: indexing_pause_opt,
+    })
+}
+
+/// Lists the indexes whose indexing is paused.
+pub(super) async fn list_paused_indexes(
+    metastore: &MetastoreServiceClient,
+) -> MetastoreResult<Vec<IndexingPauseStatus>> {
+    let list_indexes_metadata_request = ListIndexesMetadataRequest {
+        index_id_patterns: vec!["*".to_string()],
+        paused_only: true,
+    };
+    let indexes_metadata = metastore
+        .list_indexes_metadata(list_indexes_metadata_request)
+        .await?
+        .deserialize_indexes_metadata()
+        .await?;
+    let paused_indexes = indexes_metadata
+        .into_iter()
+        .filter_map(|index_metadata| {
+            let mode = index_metadata.index_config.ingest_settings.indexing_pause?;
+            Some(IndexingPauseStatus {
+                index_id: index_metadata.index_uid.index_id,
+                paused: true,
+                mode: Some(mode),
+            })
+        })
+        .collect();
+    Ok(paused_indexes)
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_indexing::TestSandbox;
+    use quickwit_ingest::IngestServiceError;
+    use serde_json::{Value as JsonValue, json};
+
+    use super::*;
+    use crate::index_api::{IndexMetadataCache, ensure_indexes_writable};
+
+    #[tokio::test]
+    async fn test_pause_and_resume_indexing() {
+        let index_id = "test-pause-indexing";
+        let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: body
+                type: text
+        "#;
+        let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "", &["body"])
+            .await
+            .unwrap();
+        let metastore = test_sandbox.metastore();
+        let index_service = IndexService::new(metastore.clone(), test_sandbox.storage_resolver());
+        let index_metadata_cache = IndexMetadataCache::new(metastore.clone());
+        let pause_handler = pause_indexing_handler(index_service.clone());
+        let resume_handler = resume_indexing_handler(index_service);
+
+        let resp = warp::test::request()
+            .path("/indexing/test-pause-indexing/pause?mode=reject")
+            .method("POST")
+            .reply(&pause_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let pause_status: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        let expected_pause_status = json!({
+            "index_id": "test-pause-indexing",
+            "paused": true,
+            "mode": "reject",
+        });
+        assert_eq!(pause_status, expected_pause_status);
+
+        let paused_indexes = list_paused_indexes(&metastore).await.unwrap();
+        assert_eq!(paused_indexes.len(), 1);
+        assert_eq!(paused_indexes[0].index_id, index_id);
+        assert_eq!(paused_indexes[0].mode, Some(IndexingPauseMode::Reject));
+
+        let ingest_error = ensure_indexes_writable(&index_metadata_cache, [index_id.to_string()])
+            .await
+            .unwrap_err();
+        assert!(matches!(ingest_error, IngestServiceError::Unavailable(_)));
+
+        // Documents ingested into an index paused in `buffer` mode are accepted.
+        let resp = warp::test::request()
+            .path("/indexing/test-pause-indexing/pause")
+            .method("POST")
+            .reply(&pause_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let pause_status: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(pause_status["mode"], "buffer");
+
+        ensure_indexes_writable(&index_metadata_cache, [index_id.to_string()])
+            .await
+            .unwrap();
+
+        let resp = warp::test::request()
+            .path("/indexing/test-pause-indexing/resume")
+            .method("POST")
+            .reply(&resume_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let pause_status: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        let expected_pause_status = json!({
+            "index_id": "test-pause-indexing",
+            "paused": false,
+        });
+        assert_eq!(pause_status, expected_pause_status);
+
+        let paused_indexes = list_paused_indexes(&metastore).await.unwrap();
+        assert!(paused_indexes.is_empty());
+
+        let resp = warp::test::request()
+            .path("/indexing/test-pause-indexing/pause?mode=drop")
+            .method("POST")
+            .reply(&pause_handler)
+            .await;
+        assert_eq!(resp.status(), 400);
+
+        let resp = warp::test::request()
+            .path("/indexing/unknown-index/pause")
+            .method("POST")
+            .reply(&pause_handler)
+            .await;
+        assert_eq!(resp.status(), 404);
+
+        test_sandbox.assert_quit().await;
+    }
+}
@@ -16,15 +16,38 @@ use std::convert::Infallible;
 
 use quickwit_actors::{AskError, Mailbox, Observe};
 use quickwit_indexing::actors::{IndexingService, IndexingServiceCounters};
+use quickwit_proto::control_plane::{
+    ControlPlaneResult, ControlPlaneService, ControlPlaneServiceClient, IndexingPipelineMove,
+    RebalanceIndexingPipelinesRequest, RebalanceIndexingPipelinesResponse,
+};
+use quickwit_proto::indexing::IndexingError;
+use quickwit_proto::metastore::{MetastoreError, MetastoreServiceClient};
+use serde::{Deserialize, Serialize};
 use warp::{Filter, Rejection};
 
+use super::pause_resource::{
+    __path_pause_indexing, __path_resume_indexing, IndexingPauseStatus, list_paused_indexes,
+};
 use crate::format::extract_format_from_qs;
-use crate::require;
+use crate::request_scope::ensure_admin;
 use crate::rest::recover_fn;
 use crate::rest_api_response::into_rest_api_response;
+use crate::{require, with_arg};
 
 #[derive(utoipa::OpenApi)]
-#[openapi(paths(indexing_endpoint))]
+#[openapi(
+    paths(
+        indexing_endpoint,
+        pause_indexing,
+        rebalance_indexing_pipelines,
+        resume_indexing,
+    ),
+    components(schemas(
+        IndexingPauseStatus,
+        IndexingPipelineMove,
+        RebalanceIndexingPipelinesResponse,
+    ))
+)]
 pub struct IndexingApi;
 
 #[utoipa::path(
@@ -32,16 +55,49 @@ pub struct IndexingApi;
     tag = "Indexing",
     path = "/indexing",
     responses(
-        (status = 200, description = "Successfully observed indexing pipelines.", body = IndexingStatistics)
+        (status = 200, description = "Successfully observed indexing pipelines.", body = IndexingStatus)
     ),
 )]
 /// Observe Indexing Pipeline
+///
+/// Returns the counters of the indexing pipelines of the node, along with the indexes of the
+/// cluster whose indexing is paused.
 async fn indexing_endpoint(
     indexing_service_mailbox: Mailbox<IndexingService>,
-) -> Result<IndexingServiceCounters, AskError<Infallible>> {
-    let counters = indexing_service_mailbox.ask(Observe).await?;
-    indexing_service_mailbox.ask(Observe).await?;
-    Ok(counters)
+    metastore: MetastoreServiceClient,
+) -> Result<IndexingStatus, IndexingError> {
+    let counters = indexing_service_mailbox
+        .ask(Observe)
+        .await
+        .map_err(observe_error)?;
+    indexing_service_mailbox
+        .ask(Observe)
+        .await
+        .map_err(observe_error)?;
+    let paused_indexes = list_paused_indexes(&metastore).await?;
+    Ok(IndexingStatus {
+        counters,
+        paused_indexes,
+    })
+}
+
+/// Counters of the indexing pipelines of the node and indexes whose indexing is paused.
+#[derive(Debug, Serialize)]
+struct IndexingStatus {
+    #[serde(flatten)]
+    counters: IndexingServiceCounters,
+    paused_indexes: Vec<IndexingPauseStatus>,
+}
+
+fn observe_error(ask_error: AskError<Infallible>) -> IndexingError {
+    match ask_error {
+        AskError::MessageNotDelivered => {
+            IndexingError::Unavailable("indexing service is not running".to_string())
+        }
+        AskError::ProcessMessageError | AskError::ErrorReply(_) => {
+            IndexingError::Internal("failed to observe indexing service".to_string())
+        }
+    }
 }
 
 fn indexing_get_filter() -> impl Filter<Extract = (), Error = Rejection> + Clone {
@@ -50,12 +106,123 @@ fn indexing_get_filter() -> impl Filter<Extract = (), Error = Rejection> + Clone
 
 pub fn indexing_get_handler(
     indexing_service_mailbox_opt: Option<Mailbox<IndexingService>>,
+    metastore: MetastoreServiceClient,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
     indexing_get_filter()
         .and(require(indexing_service_mailbox_opt))
+        .and(with_arg(metastore))
         .then(indexing_endpoint)
         .and(extract_format_from_qs())
         .map(into_rest_api_response)
         .recover(recover_fn)
         .boxed()
 }
+
+#[derive(Debug, Default, Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+struct RebalanceQueryParams {
+    /// Only returns the planned moves without applying them.
+    #[serde(default)]
+    dry_run: bool,
+}
+
+pub fn indexing_rebalance_handler(
+    control_plane_client: ControlPlaneServiceClient,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("indexing" / "rebalance")
+        .and(warp::post())
+        .and(warp::query::<RebalanceQueryParams>())
+        .and(with_arg(control_plane_client))
+        .then(rebalance_indexing_pipelines)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+        .recover(recover_fn)
+        .boxed()
+}
+
+#[utoipa::path(
+    post,
+    tag = "Indexing",
+    path = "/indexing/rebalance",
+    params(RebalanceQueryParams),
+    responses(
+        (status = 200, description = "Successfully planned the moves of the indexing pipelines.", body = RebalanceIndexingPipelinesResponse),
+        (status = 403, description = "The token of the request does not grant admin access."),
+    ),
+)]
+/// Rebalance Indexing Pipelines
+///
+/// Asks the control plane to schedule the indexing pipelines from scratch, as if none was
+/// running, and to move them accordingly. Returns the pipelines moving from one indexer to
+/// another. With `dry_run`, the moves are only planned.
+async fn rebalance_indexing_pipelines(
+    query_params: RebalanceQueryParams,
+    control_plane_client: ControlPlaneServiceClient,
+) -> ControlPlaneResult<RebalanceIndexingPipelinesResponse> {
+    ensure_admin().map_err(MetastoreError::from)?;
+    let rebalance_request = RebalanceIndexingPipelinesRequest {
+        dry_run: query_params.dry_run,
+    };
+    control_plane_client
+        .rebalance_indexing_pipelines(rebalance_request)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_proto::control_plane::MockControlPlaneService;
+    use quickwit_proto::types::IndexUid;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_rebalance_indexing_pipelines() {
+        let mut mock_control_plane = MockControlPlaneService::new();
+        mock_control_plane
+            .expect_rebalance_indexing_pipelines()
+            .times(2)
+            .returning(|request| {
+                let moves = vec![IndexingPipelineMove {
+                    index_uid: Some(IndexUid::for_test("test-index", 0)),
+                    source_id: "test-source".to_string(),
+                    from_node_id: "indexer-1".to_string(),
+                    to_node_id: if request.dry_run {
+                        "indexer-2".to_string()
+                    } else {
+                        "indexer-3".to_string()
+                    },
+                    num_pipelines: 2,
+                }];
+                Ok(RebalanceIndexingPipelinesResponse { moves })
+            });
+        let control_plane_client = ControlPlaneServiceClient::from_mock(mock_control_plane);
+        let handler = indexing_rebalance_handler(control_plane_client);
+
+        let response = warp::test::request()
+            .path("/indexing/rebalance?dry_run=true")
+            .method("POST")
+            .reply(&handler)
+            .await;
+        assert_eq!(response.status(), 200);
+        let response_json: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        let expected_response_json = serde_json::json!({
+            "moves": [{
+                "index_uid": "test-index:00000000000000000000000000",
+                "source_id": "test-source",
+                "from_node_id": "indexer-1",
+                "to_node_id": "indexer-2",
+                "num_pipelines": 2,
+            }]
+        });
+        assert_eq!(response_json, expected_response_json);
+
+        let response = warp::test::request()
+            .path("/indexing/rebalance")
+            .method("POST")
+            .reply(&handler)
+            .await;
+        assert_eq!(response.status(), 200);
+        let response_json: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(response_json["moves"][0]["to_node_id"], "indexer-3");
+    }
+}
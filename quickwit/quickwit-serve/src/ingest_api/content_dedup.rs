@@ -0,0 +1,469 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Drops the ingested documents whose content is identical to a document ingested within a
+//! sliding time window, for producers resending documents without any id.
+//!
+//! The hashes of the documents are remembered in a rolling Bloom filter: a queue of fixed-size
+//! Bloom filters, each one recording the documents of a fraction of the window, or fewer
+//! documents if they fill up before. Memory is bounded regardless of the ingest throughput, at
+//! the cost of false positives: a unique document may be mistaken for a duplicate and dropped.
+//! Under a throughput filling more than [`MAX_NUM_GENERATIONS`] generations per window, the
+//! oldest documents of the window are forgotten early.
+//!
+//! The documents are only recorded once ingested, so the documents of a failed request are not
+//! dropped when the producer retries. Identical documents ingested concurrently by two requests
+//! are not deduplicated.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::{DefaultHasher, Hasher};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use bytes::Bytes;
+use quickwit_common::thread_pool::run_cpu_intensive;
+use quickwit_proto::ingest::IngestServiceError;
+use quickwit_proto::types::IndexUid;
+
+use super::lines;
+use crate::metrics::DEDUPLICATED_DOCS_TOTAL;
+
+/// Number of bits of each generation of the rolling Bloom filter (1 MiB).
+const NUM_BITS_PER_GENERATION: usize = 1 << 23;
+
+/// Number of bits set for each document.
+const NUM_HASHES: usize = 4;
+
+/// Number of documents recorded by a generation before a new one is started, keeping the false
+/// positive rate of each generation under 1%: `n = -m / k * ln(1 - p^(1 / k))` with
+/// `m = NUM_BITS_PER_GENERATION`, `k = NUM_HASHES`, and `p = 0.01`.
+const MAX_DOCS_PER_GENERATION: usize = 797_000;
+
+/// Number of generations covering a window. The hashes are forgotten at most one generation
+/// span after the end of the window.
+const NUM_GENERATIONS_PER_WINDOW: u32 = 4;
+
+/// Maximum number of generations of a window (16 MiB), reached when the documents of a window
+/// fill more generations than `NUM_GENERATIONS_PER_WINDOW`.
+const MAX_NUM_GENERATIONS: usize = 16;
+
+/// Minimum interval between two sweeps of the windows of the indexes that stopped ingesting.
+const SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Bloom filter recording the hashes of the documents ingested since `started_at`.
+struct BloomGeneration {
+    started_at: Instant,
+    words: Vec<u64>,
+    num_docs: usize,
+}
+
+impl BloomGeneration {
+    fn new(started_at: Instant) -> Self {
+        Self {
+            started_at,
+            words: vec![0; NUM_BITS_PER_GENERATION / 64],
+            num_docs: 0,
+        }
+    }
+
+    fn contains(&self, bit_positions: &[usize; NUM_HASHES]) -> bool {
+        bit_positions
+            .iter()
+            .all(|bit_position| (self.words[bit_position / 64] & (1 << (bit_position % 64))) != 0)
+    }
+
+    fn insert(&mut self, bit_positions: &[usize; NUM_HASHES]) {
+        for bit_position in bit_positions {
+            self.words[bit_position / 64] |= 1 << (bit_position % 64);
+        }
+        self.num_docs += 1;
+    }
+}
+
+/// Positions of the bits of a document, derived from two hashes of its content with double
+/// hashing.
+fn bit_positions(doc: &[u8]) -> [usize; NUM_HASHES] {
+    let mut hasher = DefaultHasher::new();
+    hasher.write(doc);
+    let first_hash = hasher.finish();
+    hasher.write_u64(first_hash);
+    let second_hash = hasher.finish() | 1;
+
+    let mut bit_positions = [0; NUM_HASHES];
+
+    for (i, bit_position) in bit_positions.iter_mut().enumerate() {
+        let hash = first_hash.wrapping_add((i as u64).wrapping_mul(second_hash));
+        *bit_position = (hash % NUM_BITS_PER_GENERATION as u64) as usize;
+    }
+    bit_positions
+}
+
+/// Rolling Bloom filter remembering the documents ingested into an index over a sliding window.
+struct ContentHashWindow {
+    window: Duration,
+    generation_span: Duration,
+    /// Generations, from the oldest to the current one.
+    generations: VecDeque<BloomGeneration>,
+}
+
+impl ContentHashWindow {
+    fn new(window: Duration) -> Self {
+        Self {
+            window,
+            generation_span: window / NUM_GENERATIONS_PER_WINDOW,
+            generations: VecDeque::new(),
+        }
+    }
+
+    /// Drops the generations whose documents were all ingested before the window.
+    fn evict_expired_generations(&mut self, now: Instant) {
+        while let Some(oldest_generation) = self.generations.front() {
+            let age = now.saturating_duration_since(oldest_generation.started_at);
+
+            if age < self.window + self.generation_span {
+                break;
+            }
+            self.generations.pop_front();
+        }
+    }
+
+    /// Returns whether an identical document was ingested within the window.
+    fn contains(&self, bit_positions: &[usize; NUM_HASHES], now: Instant) -> bool {
+        self.generations.iter().any(|generation| {
+            now.saturating_duration_since(generation.started_at)
+                < self.window + self.generation_span
+                && generation.contains(bit_positions)
+        })
+    }
+
+    /// Records a document ingested at `now`.
+    fn insert(&mut self, bit_positions: &[usize; NUM_HASHES], now: Instant) {
+        self.evict_expired_generations(now);
+
+        let needs_new_generation = match self.generations.back() {
+            Some(current_generation) => {
+                now.saturating_duration_since(current_generation.started_at) >= self.generation_span
+                    || current_generation.num_docs >= MAX_DOCS_PER_GENERATION
+            }
+            None => true,
+        };
+        if needs_new_generation {
+            if self.generations.len() == MAX_NUM_GENERATIONS {
+                self.generations.pop_front();
+            }
+            self.generations.push_back(BloomGeneration::new(now));
+        }
+        if let Some(current_generation) = self.generations.back_mut() {
+            current_generation.insert(bit_positions);
+        }
+    }
+}
+
+/// Hashes of the documents kept by [`ContentDedup::dedup`], to record with
+/// [`ContentDedup::record`] once the documents are ingested.
+pub(crate) struct ContentHashes {
+    index_uid: IndexUid,
+    window: Duration,
+    bit_positions: Vec<[usize; NUM_HASHES]>,
+}
+
+#[derive(Default)]
+struct ContentDedupState {
+    windows: HashMap<IndexUid, ContentHashWindow>,
+    last_swept_at_opt: Option<Instant>,
+}
+
+/// Content-hash windows of the indexes deduplicating their ingested documents, shared by the
+/// ingest requests served by the node.
+#[derive(Clone, Default)]
+pub(crate) struct ContentDedup {
+    state: Arc<Mutex<ContentDedupState>>,
+}
+
+impl ContentDedup {
+    /// Drops the documents of `body` identical to a document ingested into the index within
+    /// `window`, including the ones earlier in `body`. Returns the remaining documents, as
+    /// NDJSON, the number of dropped documents, and the hashes of the remaining documents.
+    pub async fn dedup(
+        &self,
+        index_uid: &IndexUid,
+        window: Duration,
+        body: &Bytes,
+    ) -> Result<(Bytes, u64, ContentHashes), IngestServiceError> {
+        let content_dedup = self.clone();
+        let index_uid = index_uid.clone();
+        let body = body.clone();
+        let now = Instant::now();
+
+        let (deduped_body, num_deduped_docs, content_hashes) =
+            run_cpu_intensive(move || content_dedup.dedup_at(index_uid, window, &body, now))
+                .await
+                .map_err(|_| {
+                    IngestServiceError::Internal("failed to deduplicate documents".to_string())
+                })?;
+        DEDUPLICATED_DOCS_TOTAL.inc_by(num_deduped_docs);
+        Ok((deduped_body, num_deduped_docs, content_hashes))
+    }
+
+    /// Records the hashes of documents successfully ingested.
+    pub fn record(&self, content_hashes: ContentHashes) {
+        self.record_at(content_hashes, Instant::now());
+    }
+
+    fn dedup_at(
+        &self,
+        index_uid: IndexUid,
+        window: Duration,
+        body: &Bytes,
+        now: Instant,
+    ) -> (Bytes, u64, ContentHashes) {
+        // The documents are hashed before the lock is acquired.
+        let docs_bit_positions: Vec<(&[u8], [usize; NUM_HASHES])> =
+            lines(body).map(|doc| (doc, bit_positions(doc))).collect();
+
+        let mut kept_docs_bit_positions = HashSet::with_capacity(docs_bit_positions.len());
+        let mut deduped_body = Vec::with_capacity(body.len());
+        let mut num_deduped_docs = 0;
+        {
+            let mut state = self.state.lock().expect("lock should not be poisoned");
+            state.sweep(now);
+
+            // The window of the index may have been updated since its documents were recorded.
+            let content_hash_window_opt = state
+                .windows
+                .get(&index_uid)
+                .filter(|content_hash_window| content_hash_window.window == window);
+
+            for (doc, bit_positions) in docs_bit_positions {
+                let is_duplicate = content_hash_window_opt.is_some_and(|content_hash_window| {
+                    content_hash_window.contains(&bit_positions, now)
+                }) || !kept_docs_bit_positions.insert(bit_positions);
+
+                if is_duplicate {
+                    num_deduped_docs += 1;
+                } else {
+                    deduped_body.extend_from_slice(doc);
+                    deduped_body.push(b'\n');
+                }
+            }
+        }
+        let content_hashes = ContentHashes {
+            index_uid,
+            window,
+            bit_positions: kept_docs_bit_positions.into_iter().collect(),
+        };
+        if num_deduped_docs == 0 {
+            return (body.clone(), 0, content_hashes);
+        }
+        (Bytes::from(deduped_body), num_deduped_docs, content_hashes)
+    }
+
+    fn record_at(&self, content_hashes: ContentHashes, now: Instant) {
+        let mut state = self.state.lock().expect("lock should not be poisoned");
+
+        let content_hash_window = state
+            .windows
+            .entry(content_hashes.index_uid)
+            .or_insert_with(|| ContentHashWindow::new(content_hashes.window));
+
+        // The window of the index was updated.
+        if content_hash_window.window != content_hashes.window {
+            *content_hash_window = ContentHashWindow::new(content_hashes.window);
+        }
+        for bit_positions in &content_hashes.bit_positions {
+            content_hash_window.insert(bit_positions, now);
+        }
+    }
+}
+
+impl ContentDedupState {
+    /// Forgets the indexes that have not ingested any document within their window, which also
+    /// covers deleted indexes. The windows are swept at most once per [`SWEEP_INTERVAL`].
+    fn sweep(&mut self, now: Instant) {
+        if let Some(last_swept_at) = self.last_swept_at_opt
+            && now.saturating_duration_since(last_swept_at) < SWEEP_INTERVAL
+        {
+            return;
+        }
+        self.windows.retain(|_, content_hash_window| {
+            content_hash_window.evict_expired_generations(now);
+            !content_hash_window.generations.is_empty()
+        });
+        self.last_swept_at_opt = Some(now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check_and_insert(
+        content_hash_window: &mut ContentHashWindow,
+        doc: &[u8],
+        now: Instant,
+    ) -> bool {
+        let bit_positions = bit_positions(doc);
+
+        if content_hash_window.contains(&bit_positions, now) {
+            return true;
+        }
+        content_hash_window.insert(&bit_positions, now);
+        false
+    }
+
+    #[test]
+    fn test_content_hash_window_drops_duplicates_within_window() {
+        let window = Duration::from_secs(60);
+        let mut content_hash_window = ContentHashWindow::new(window);
+        let start = Instant::now();
+
+        assert!(!check_and_insert(&mut content_hash_window, b"doc-1", start));
+        assert!(!check_and_insert(&mut content_hash_window, b"doc-2", start));
+        assert!(check_and_insert(&mut content_hash_window, b"doc-1", start));
+
+        // The documents are remembered for the whole window, across generations.
+        for secs in [10, 20, 30, 45, 59] {
+            let now = start + Duration::from_secs(secs);
+            let new_doc = format!("doc-at-{secs}");
+            assert!(!check_and_insert(
+                &mut content_hash_window,
+                new_doc.as_bytes(),
+                now
+            ));
+            assert!(check_and_insert(&mut content_hash_window, b"doc-2", now));
+        }
+        assert_eq!(content_hash_window.generations.len(), 3);
+    }
+
+    #[test]
+    fn test_content_hash_window_keeps_identical_docs_outside_window() {
+        let window = Duration::from_secs(60);
+        let mut content_hash_window = ContentHashWindow::new(window);
+        let start = Instant::now();
+
+        assert!(!check_and_insert(&mut content_hash_window, b"doc-1", start));
+
+        let now = start + window + window / NUM_GENERATIONS_PER_WINDOW;
+        assert!(!check_and_insert(&mut content_hash_window, b"doc-1", now));
+        assert_eq!(content_hash_window.generations.len(), 1);
+        assert!(check_and_insert(&mut content_hash_window, b"doc-1", now));
+    }
+
+    #[test]
+    fn test_content_hash_window_rotates_full_generations() {
+        let window = Duration::from_secs(60);
+        let mut content_hash_window = ContentHashWindow::new(window);
+        let now = Instant::now();
+
+        assert!(!check_and_insert(&mut content_hash_window, b"doc-1", now));
+        content_hash_window.generations[0].num_docs = MAX_DOCS_PER_GENERATION;
+
+        // A full generation is rotated even though its span is not over.
+        assert!(!check_and_insert(&mut content_hash_window, b"doc-2", now));
+        assert_eq!(content_hash_window.generations.len(), 2);
+        assert_eq!(content_hash_window.generations[1].num_docs, 1);
+        assert!(check_and_insert(&mut content_hash_window, b"doc-1", now));
+
+        // The number of generations is capped: the oldest ones are forgotten early.
+        for i in 0..MAX_NUM_GENERATIONS {
+            if let Some(current_generation) = content_hash_window.generations.back_mut() {
+                current_generation.num_docs = MAX_DOCS_PER_GENERATION;
+            }
+            let new_doc = format!("doc-{i}");
+            assert!(!check_and_insert(
+                &mut content_hash_window,
+                new_doc.as_bytes(),
+                now
+            ));
+        }
+        assert_eq!(content_hash_window.generations.len(), MAX_NUM_GENERATIONS);
+        assert!(!check_and_insert(&mut content_hash_window, b"doc-1", now));
+    }
+
+    #[test]
+    fn test_content_dedup() {
+        let content_dedup = ContentDedup::default();
+        let index_uid = IndexUid::for_test("test-index", 0);
+        let window = Duration::from_secs(60);
+        let start = Instant::now();
+
+        let body = Bytes::from_static(b"{\"id\": 1}\n{\"id\": 2}\n{\"id\": 1}\n");
+        let (deduped_body, num_deduped_docs, content_hashes) =
+            content_dedup.dedup_at(index_uid.clone(), window, &body, start);
+        assert_eq!(deduped_body, "{\"id\": 1}\n{\"id\": 2}\n");
+        assert_eq!(num_deduped_docs, 1);
+        assert_eq!(content_hashes.bit_positions.len(), 2);
+        content_dedup.record_at(content_hashes, start);
+
+        let body = Bytes::from_static(b"{\"id\": 2}\n{\"id\": 3}");
+        let now = start + Duration::from_secs(30);
+        let (deduped_body, num_deduped_docs, _content_hashes) =
+            content_dedup.dedup_at(index_uid.clone(), window, &body, now);
+        assert_eq!(deduped_body, "{\"id\": 3}\n");
+        assert_eq!(num_deduped_docs, 1);
+
+        // The documents of a failed ingest are not recorded, so they are kept when retried.
+        let (deduped_body, num_deduped_docs, _content_hashes) =
+            content_dedup.dedup_at(index_uid.clone(), window, &body, now);
+        assert_eq!(deduped_body, "{\"id\": 3}\n");
+        assert_eq!(num_deduped_docs, 1);
+
+        // Other indexes have their own window.
+        let other_index_uid = IndexUid::for_test("other-index", 0);
+        let (deduped_body, num_deduped_docs, _content_hashes) =
+            content_dedup.dedup_at(other_index_uid, window, &body, now);
+        assert_eq!(deduped_body, body);
+        assert_eq!(num_deduped_docs, 0);
+
+        // The window of the index was updated.
+        let (deduped_body, num_deduped_docs, _content_hashes) =
+            content_dedup.dedup_at(index_uid.clone(), window * 2, &body, now);
+        assert_eq!(deduped_body, body);
+        assert_eq!(num_deduped_docs, 0);
+
+        let body = Bytes::from_static(b"{\"id\": 1}");
+        let now = start + Duration::from_secs(120);
+        let (deduped_body, num_deduped_docs, _content_hashes) =
+            content_dedup.dedup_at(index_uid, window, &body, now);
+        assert_eq!(deduped_body, body);
+        assert_eq!(num_deduped_docs, 0);
+    }
+
+    #[test]
+    fn test_content_dedup_sweeps_idle_indexes() {
+        let content_dedup = ContentDedup::default();
+        let index_uid = IndexUid::for_test("test-index", 0);
+        let window = Duration::from_secs(60);
+        let start = Instant::now();
+
+        let body = Bytes::from_static(b"{\"id\": 1}");
+        let (_, _, content_hashes) =
+            content_dedup.dedup_at(index_uid.clone(), window, &body, start);
+        content_dedup.record_at(content_hashes, start);
+
+        let other_index_uid = IndexUid::for_test("other-index", 0);
+        let now = start + Duration::from_secs(70);
+        content_dedup.dedup_at(other_index_uid.clone(), window, &body, now);
+        assert_eq!(content_dedup.state.lock().unwrap().windows.len(), 1);
+
+        // The windows are not swept more than once per interval.
+        let now = start + Duration::from_secs(100);
+        content_dedup.dedup_at(other_index_uid.clone(), window, &body, now);
+        assert_eq!(content_dedup.state.lock().unwrap().windows.len(), 1);
+
+        let now = start + Duration::from_secs(130);
+        content_dedup.dedup_at(other_index_uid, window, &body, now);
+        assert!(content_dedup.state.lock().unwrap().windows.is_empty());
+    }
+}
@@ -0,0 +1,235 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Captures the documents rejected by the REST ingest endpoint because they failed to parse, so
+//! they can be inspected and replayed once the doc mapping of their index is fixed.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use quickwit_config::{DeadLetterEviction, DeadLetterQueueConfig};
+use quickwit_proto::ingest::ParseFailureReason;
+use quickwit_proto::types::IndexUid;
+use serde::{Deserialize, Serialize};
+
+use super::RestParseFailure;
+
+/// A document rejected by the ingest API because it failed to parse.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct DeadLetter {
+    pub document: String,
+    pub reason: ParseFailureReason,
+    pub message: String,
+    /// Time of the last failed ingest attempt, as a Unix timestamp in seconds.
+    pub failed_at: u64,
+}
+
+#[derive(Default)]
+struct IndexDeadLetters {
+    dead_letters: VecDeque<DeadLetter>,
+    num_evicted_docs: u64,
+}
+
+/// Dead-letter queues of the indexes, shared by the ingest requests served by the node. Each queue
+/// is bounded by `max_docs_per_index`, the documents being evicted according to the eviction
+/// policy once it is full.
+///
+/// The queues are kept in memory and are not shared with the other nodes: they only hold the
+/// documents rejected by the ingest requests served by this node.
+#[derive(Clone)]
+pub(crate) struct DeadLetterQueue {
+    config: DeadLetterQueueConfig,
+    indexes: Arc<Mutex<HashMap<IndexUid, IndexDeadLetters>>>,
+}
+
+impl DeadLetterQueue {
+    pub fn new(config: DeadLetterQueueConfig) -> Self {
+        Self {
+            config,
+            indexes: Arc::default(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.is_enabled()
+    }
+
+    /// Appends the documents that failed to parse to the dead-letter queue of the index.
+    pub fn push(&self, index_uid: &IndexUid, parse_failures: Vec<RestParseFailure>) {
+        if !self.is_enabled() || parse_failures.is_empty() {
+            return;
+        }
+        let failed_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+        let mut indexes = self.lock_indexes();
+        let index_dead_letters = indexes.entry(index_uid.clone()).or_default();
+
+        for parse_failure in parse_failures {
+            let dead_letter = DeadLetter {
+                document: parse_failure.document,
+                reason: parse_failure.reason,
+                message: parse_failure.message,
+                failed_at,
+            };
+            index_dead_letters.dead_letters.push_back(dead_letter);
+        }
+        self.evict(index_dead_letters);
+    }
+
+    /// Returns the documents of the dead-letter queue of the index and the number of documents
+    /// evicted from it.
+    pub fn get(&self, index_uid: &IndexUid) -> (Vec<DeadLetter>, u64) {
+        let indexes = self.lock_indexes();
+
+        let Some(index_dead_letters) = indexes.get(index_uid) else {
+            return (Vec::new(), 0);
+        };
+        let dead_letters = index_dead_letters.dead_letters.iter().cloned().collect();
+        (dead_letters, index_dead_letters.num_evicted_docs)
+    }
+
+    /// Removes the documents of the dead-letter queue of the index and returns them.
+    pub fn take(&self, index_uid: &IndexUid) -> Vec<DeadLetter> {
+        let mut indexes = self.lock_indexes();
+
+        let Some(index_dead_letters) = indexes.get_mut(index_uid) else {
+            return Vec::new();
+        };
+        index_dead_letters.dead_letters.drain(..).collect()
+    }
+
+    /// Puts back documents previously taken from the dead-letter queue of the index, ahead of the
+    /// documents that failed to parse in the meantime.
+    pub fn restore(&self, index_uid: &IndexUid, dead_letters: Vec<DeadLetter>) {
+        if dead_letters.is_empty() {
+            return;
+        }
+        let mut indexes = self.lock_indexes();
+        let index_dead_letters = indexes.entry(index_uid.clone()).or_default();
+
+        for dead_letter in dead_letters.into_iter().rev() {
+            index_dead_letters.dead_letters.push_front(dead_letter);
+        }
+        self.evict(index_dead_letters);
+    }
+
+    /// The queues are left consistent by every operation, so they remain usable after a panic
+    /// while the lock was held.
+    fn lock_indexes(&self) -> MutexGuard<'_, HashMap<IndexUid, IndexDeadLetters>> {
+        self.indexes.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn evict(&self, index_dead_letters: &mut IndexDeadLetters) {
+        let max_docs = self.config.max_docs_per_index;
+        let num_docs = index_dead_letters.dead_letters.len();
+
+        if num_docs <= max_docs {
+            return;
+        }
+        match self.config.eviction {
+            DeadLetterEviction::DropOldest => {
+                index_dead_letters.dead_letters.drain(..num_docs - max_docs);
+            }
+            DeadLetterEviction::DropNewest => {
+                index_dead_letters.dead_letters.truncate(max_docs);
+            }
+        }
+        index_dead_letters.num_evicted_docs += (num_docs - max_docs) as u64;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_failure_for_test(document: &str) -> RestParseFailure {
+        RestParseFailure {
+            message: "failed to parse field `id`".to_string(),
+            document: document.to_string(),
+            reason: ParseFailureReason::InvalidSchema,
+        }
+    }
+
+    fn documents(dead_letters: &[DeadLetter]) -> Vec<&str> {
+        dead_letters
+            .iter()
+            .map(|dead_letter| dead_letter.document.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_dead_letter_queue_evicts_oldest_docs() {
+        let dead_letter_queue = DeadLetterQueue::new(DeadLetterQueueConfig {
+            max_docs_per_index: 2,
+            eviction: DeadLetterEviction::DropOldest,
+        });
+        let index_uid = IndexUid::for_test("test-index", 0);
+        let parse_failures = vec![
+            parse_failure_for_test("doc-1"),
+            parse_failure_for_test("doc-2"),
+            parse_failure_for_test("doc-3"),
+        ];
+        dead_letter_queue.push(&index_uid, parse_failures);
+
+        let (dead_letters, num_evicted_docs) = dead_letter_queue.get(&index_uid);
+        assert_eq!(documents(&dead_letters), ["doc-2", "doc-3"]);
+        assert_eq!(num_evicted_docs, 1);
+
+        // The documents restored after a failed replay come before the new ones.
+        let dead_letters = dead_letter_queue.take(&index_uid);
+        dead_letter_queue.push(&index_uid, vec![parse_failure_for_test("doc-4")]);
+        dead_letter_queue.restore(&index_uid, dead_letters);
+
+        let (dead_letters, num_evicted_docs) = dead_letter_queue.get(&index_uid);
+        assert_eq!(documents(&dead_letters), ["doc-3", "doc-4"]);
+        assert_eq!(num_evicted_docs, 2);
+
+        let other_index_uid = IndexUid::for_test("other-index", 0);
+        let (dead_letters, num_evicted_docs) = dead_letter_queue.get(&other_index_uid);
+        assert!(dead_letters.is_empty());
+        assert_eq!(num_evicted_docs, 0);
+    }
+
+    #[test]
+    fn test_dead_letter_queue_evicts_newest_docs() {
+        let dead_letter_queue = DeadLetterQueue::new(DeadLetterQueueConfig {
+            max_docs_per_index: 2,
+            eviction: DeadLetterEviction::DropNewest,
+        });
+        let index_uid = IndexUid::for_test("test-index", 0);
+        let parse_failures = vec![
+            parse_failure_for_test("doc-1"),
+            parse_failure_for_test("doc-2"),
+            parse_failure_for_test("doc-3"),
+        ];
+        dead_letter_queue.push(&index_uid, parse_failures);
+
+        let (dead_letters, num_evicted_docs) = dead_letter_queue.get(&index_uid);
+        assert_eq!(documents(&dead_letters), ["doc-1", "doc-2"]);
+        assert_eq!(num_evicted_docs, 1);
+    }
+
+    #[test]
+    fn test_dead_letter_queue_disabled() {
+        let dead_letter_queue = DeadLetterQueue::new(DeadLetterQueueConfig::default());
+        let index_uid = IndexUid::for_test("test-index", 0);
+        dead_letter_queue.push(&index_uid, vec![parse_failure_for_test("doc-1")]);
+
+        let (dead_letters, _) = dead_letter_queue.get(&index_uid);
+        assert!(dead_letters.is_empty());
+    }
+}
@@ -0,0 +1,481 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use quickwit_config::INGEST_V2_SOURCE_ID;
+use quickwit_ingest::{DocBatchV2Builder, IngestServiceError};
+use quickwit_metastore::IndexMetadataResponseExt;
+use quickwit_proto::ingest::CommitTypeV2;
+use quickwit_proto::ingest::router::{
+    IngestRequestV2, IngestRouterService, IngestRouterServiceClient, IngestSubrequest,
+};
+use quickwit_proto::metastore::{
+    IndexMetadataRequest, MetastoreError, MetastoreResult, MetastoreService, MetastoreServiceClient,
+};
+use quickwit_proto::types::{DocUidGenerator, IndexId};
+use serde::{Deserialize, Serialize};
+use warp::{Filter, Rejection};
+
+use super::RestIngestResponse;
+use super::dead_letter_queue::{DeadLetter, DeadLetterQueue};
+use super::index_allow_list::IndexAllowList;
+use crate::format::extract_format_from_qs;
+use crate::index_api::{IndexMetadataCache, ensure_indexes_writable};
+use crate::request_scope::ensure_indexes_in_scope;
+use crate::rest_api_response::into_rest_api_response;
+use crate::with_arg;
+
+const DEAD_LETTER_QUEUES_DISABLED_MESSAGE: &str = "dead-letter queues are disabled: set \
+                                                   `ingest_api.dead_letter_queue.\
+                                                   max_docs_per_index` to enable them";
+
+#[derive(utoipa::OpenApi)]
+#[openapi(
+    paths(get_dead_letter_queue, replay_dead_letter_queue),
+    components(schemas(DeadLetter, DeadLetterQueueResponse, ReplayDeadLetterQueueResponse))
+)]
+pub struct DeadLetterQueueApi;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct DeadLetterQueueResponse {
+    pub index_id: IndexId,
+    /// Number of documents dropped because the dead-letter queue was full.
+    pub num_evicted_docs: u64,
+    /// Documents of the dead-letter queue, from the oldest to the newest.
+    pub dead_letters: Vec<DeadLetter>,
+}
+
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct ReplayDeadLetterQueueResponse {
+    /// Number of documents taken from the dead-letter queue and ingested again.
+    pub num_replayed_docs: u64,
+    pub num_ingested_docs: u64,
+    /// Number of documents that failed to parse again, which are back in the dead-letter queue.
+    pub num_rejected_docs: u64,
+}
+
+pub(super) fn dead_letter_queue_handlers(
+    dead_letter_queue: DeadLetterQueue,
+    index_metadata_cache: IndexMetadataCache,
+    ingest_router: IngestRouterServiceClient,
+    index_allow_list_opt: Option<IndexAllowList>,
+    enable_ingest_v2: bool,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    get_dead_letter_queue_handler(
+        dead_letter_queue.clone(),
+        index_metadata_cache.metastore().clone(),
+    )
+    .or(replay_dead_letter_queue_handler(
+        dead_letter_queue,
+        index_metadata_cache,
+        ingest_router,
+        index_allow_list_opt,
+        enable_ingest_v2,
+    ))
+    .boxed()
+}
+
+fn get_dead_letter_queue_handler(
+    dead_letter_queue: DeadLetterQueue,
+    metastore: MetastoreServiceClient,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("indexes" / String / "_dlq")
+        .and(warp::get())
+        .and(with_arg(dead_letter_queue))
+        .and(with_arg(metastore))
+        .then(get_dead_letter_queue)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+        .boxed()
+}
+
+fn replay_dead_letter_queue_handler(
+    dead_letter_queue: DeadLetterQueue,
+    index_metadata_cache: IndexMetadataCache,
+    ingest_router: IngestRouterServiceClient,
+    index_allow_list_opt: Option<IndexAllowList>,
+    enable_ingest_v2: bool,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("indexes" / String / "_dlq" / "replay")
+        .and(warp::post())
+        .and(with_arg(dead_letter_queue))
+        .and(with_arg(index_metadata_cache))
+        .and(with_arg(ingest_router))
+        .and(with_arg(index_allow_list_opt))
+        .then(
+            move |index_id,
+                  dead_letter_queue,
+                  index_metadata_cache,
+                  ingest_router,
+                  index_allow_list_opt| {
+                replay_dead_letter_queue(
+                    index_id,
+                    dead_letter_queue,
+                    index_metadata_cache,
+                    ingest_router,
+                    index_allow_list_opt,
+                    enable_ingest_v2,
+                )
+            },
+        )
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+        .boxed()
+}
+
+#[utoipa::path(
+    get,
+    tag = "Ingest",
+    path = "/indexes/{index_id}/_dlq",
+    responses(
+        (status = 200, description = "Successfully fetched the dead-letter queue.", body = DeadLetterQueueResponse)
+    ),
+    params(
+        ("index_id" = String, Path, description = "The index ID of the dead-letter queue."),
+    )
+)]
+/// Returns the documents of an index that failed to parse.
+///
+/// The dead-letter queues are kept in memory by each node, so only the documents rejected by the
+/// ingest requests served by the node receiving this request are returned.
+async fn get_dead_letter_queue(
+    index_id: IndexId,
+    dead_letter_queue: DeadLetterQueue,
+    metastore: MetastoreServiceClient,
+) -> MetastoreResult<DeadLetterQueueResponse> {
+    if !dead_letter_queue.is_enabled() {
+        return Err(MetastoreError::InvalidArgument {
+            message: DEAD_LETTER_QUEUES_DISABLED_MESSAGE.to_string(),
+        });
+    }
+    ensure_indexes_in_scope([index_id.as_str()])?;
+
+    let index_metadata_request = IndexMetadataRequest::for_index_id(index_id);
+    let index_metadata = metastore
+        .index_metadata(index_metadata_request)
+        .await?
+        .deserialize_index_metadata()?;
+    let (dead_letters, num_evicted_docs) = dead_letter_queue.get(&index_metadata.index_uid);
+
+    Ok(DeadLetterQueueResponse {
+        index_id: index_metadata.index_config.index_id,
+        num_evicted_docs,
+        dead_letters,
+    })
+}
+
+#[utoipa::path(
+    post,
+    tag = "Ingest",
+    path = "/indexes/{index_id}/_dlq/replay",
+    responses(
+        (status = 200, description = "Successfully replayed the dead-letter queue.", body = ReplayDeadLetterQueueResponse)
+    ),
+    params(
+        ("index_id" = String, Path, description = "The index ID of the dead-letter queue."),
+    )
+)]
+/// Ingests again the documents of an index that failed to parse.
+///
+/// Only the dead-letter queue of the node receiving this request is replayed. The documents
+/// failing to parse again are put back in the dead-letter queue.
+async fn replay_dead_letter_queue(
+    index_id: IndexId,
+    dead_letter_queue: DeadLetterQueue,
+    index_metadata_cache: IndexMetadataCache,
+    ingest_router: IngestRouterServiceClient,
+    index_allow_list_opt: Option<IndexAllowList>,
+    enable_ingest_v2: bool,
+) -> Result<ReplayDeadLetterQueueResponse, IngestServiceError> {
+    if let Some(index_allow_list) = &index_allow_list_opt {
+        index_allow_list.check(&index_id)?;
+    }
+    if !enable_ingest_v2 {
+        let message = "dead-letter queues can only be replayed with ingest v2";
+        return Err(IngestServiceError::BadRequest(message.to_string()));
+    }
+    if !dead_letter_queue.is_enabled() {
+        let message = DEAD_LETTER_QUEUES_DISABLED_MESSAGE.to_string();
+        return Err(IngestServiceError::BadRequest(message));
+    }
+    let index_metadata = ensure_indexes_writable(&index_metadata_cache, [index_id.clone()])
+        .await?
+        .pop()
+        .ok_or(IngestServiceError::IndexNotFound { index_id })?;
+    let index_uid = index_metadata.index_uid;
+    let dead_letters = dead_letter_queue.take(&index_uid);
+
+    let mut doc_batch_builder = DocBatchV2Builder::default();
+    let mut doc_uid_generator = DocUidGenerator::default();
+
+    for dead_letter in &dead_letters {
+        doc_batch_builder.add_doc(
+            doc_uid_generator.next_doc_uid(),
+            dead_letter.document.as_bytes(),
+        );
+    }
+    let Some(doc_batch) = doc_batch_builder.build() else {
+        return Ok(ReplayDeadLetterQueueResponse::default());
+    };
+    let num_replayed_docs = doc_batch.num_docs() as u64;
+    let doc_batch_clone = doc_batch.clone();
+
+    let subrequest = IngestSubrequest {
+        subrequest_id: 0,
+        index_id: index_metadata.index_config.index_id,
+        source_id: INGEST_V2_SOURCE_ID.to_string(),
+        doc_batch: Some(doc_batch),
+    };
+    let ingest_request = IngestRequestV2 {
+        commit_type: CommitTypeV2::Auto as i32,
+        subrequests: vec![subrequest],
+    };
+    let ingest_result = match ingest_router.ingest(ingest_request).await {
+        Ok(ingest_response) => RestIngestResponse::from_ingest_v2(
+            ingest_response,
+            Some(&doc_batch_clone),
+            num_replayed_docs,
+        ),
+        Err(ingest_error) => Err(ingest_error.into()),
+    };
+    let ingest_response = match ingest_result {
+        Ok(ingest_response) => ingest_response,
+        Err(ingest_error) => {
+            // The documents may be replayed again once the error is resolved.
+            dead_letter_queue.restore(&index_uid, dead_letters);
+            return Err(ingest_error);
+        }
+    };
+    let parse_failures = ingest_response.parse_failures.unwrap_or_default();
+    let num_rejected_docs = parse_failures.len() as u64;
+    dead_letter_queue.push(&index_uid, parse_failures);
+
+    Ok(ReplayDeadLetterQueueResponse {
+        num_replayed_docs,
+        num_ingested_docs: ingest_response.num_ingested_docs.unwrap_or_default(),
+        num_rejected_docs,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use quickwit_config::{IndexConfig, IngestApiConfig};
+    use quickwit_ingest::IngestServiceClient;
+    use quickwit_metastore::{CreateIndexRequestExt, metastore_for_test};
+    use quickwit_proto::ingest::router::{
+        IngestResponseV2, IngestSuccess, MockIngestRouterService,
+    };
+    use quickwit_proto::ingest::{ParseFailure, ParseFailureReason};
+    use quickwit_proto::metastore::{CreateIndexRequest, MetastoreService};
+    use quickwit_search::MockSearchService;
+
+    use super::*;
+    use crate::ingest_api::ingest_api_handlers;
+    use crate::request_scope::with_index_scope_for_test;
+
+    const INVALID_DOC: &str = r#"{"id": "one"}"#;
+
+    #[tokio::test]
+    async fn test_dead_letter_queue_replay_after_mapping_fix() {
+        let metastore = metastore_for_test();
+        let index_config = IndexConfig::for_test("my-index", "ram:///indexes/my-index");
+        let create_index_request =
+            CreateIndexRequest::try_from_index_config(&index_config).unwrap();
+        metastore.create_index(create_index_request).await.unwrap();
+
+        // The router rejects the invalid document until its mapping is fixed.
+        let is_mapping_fixed = Arc::new(AtomicBool::new(false));
+        let is_mapping_fixed_clone = is_mapping_fixed.clone();
+        let mut mock_ingest_router = MockIngestRouterService::new();
+        mock_ingest_router
+            .expect_ingest()
+            .returning(move |request| {
+                let doc_batch = request.subrequests[0].doc_batch.as_ref().unwrap();
+                let parse_failures: Vec<ParseFailure> = doc_batch
+                    .docs()
+                    .filter(|(_, doc)| {
+                        !is_mapping_fixed_clone.load(Ordering::Relaxed) && doc == INVALID_DOC
+                    })
+                    .map(|(doc_uid, _)| ParseFailure {
+                        doc_uid: Some(doc_uid),
+                        reason: ParseFailureReason::InvalidSchema as i32,
+                        message: "failed to parse field `id`".to_string(),
+                    })
+                    .collect();
+                let num_ingested_docs = doc_batch.num_docs() - parse_failures.len();
+                Ok(IngestResponseV2 {
+                    successes: vec![IngestSuccess {
+                        num_ingested_docs: num_ingested_docs as u32,
+                        parse_failures,
+                        ..Default::default()
+                    }],
+                    failures: Vec::new(),
+                })
+            });
+        let mut config = IngestApiConfig::default();
+        config.dead_letter_queue.max_docs_per_index = 10;
+
+        let ingest_api_handlers = ingest_api_handlers(
+            IngestRouterServiceClient::from_mock(mock_ingest_router),
+            IngestServiceClient::mocked(),
+            metastore,
+            Arc::new(MockSearchService::new()),
+            config,
+            None,
+            false,
+            true,
+        );
+        let payload = format!("{{\"id\": 1}}\n{INVALID_DOC}");
+        let resp = warp::test::request()
+            .path("/my-index/ingest")
+            .method("POST")
+            .body(payload)
+            .reply(&ingest_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let ingest_response: RestIngestResponse = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(ingest_response.num_ingested_docs, Some(1));
+        assert_eq!(ingest_response.num_rejected_docs, Some(1));
+        assert!(ingest_response.parse_failures.is_none());
+
+        let resp = warp::test::request()
+            .path("/indexes/my-index/_dlq")
+            .reply(&ingest_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let dead_letter_queue_response: DeadLetterQueueResponse =
+            serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(dead_letter_queue_response.index_id, "my-index");
+        assert_eq!(dead_letter_queue_response.num_evicted_docs, 0);
+        let dead_letters = &dead_letter_queue_response.dead_letters;
+        assert_eq!(dead_letters.len(), 1);
+        assert_eq!(dead_letters[0].document, INVALID_DOC);
+        assert_eq!(dead_letters[0].reason, ParseFailureReason::InvalidSchema);
+
+        // Replaying before fixing the mapping puts the document back in the dead-letter queue.
+        let resp = warp::test::request()
+            .path("/indexes/my-index/_dlq/replay")
+            .method("POST")
+            .reply(&ingest_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let replay_response: ReplayDeadLetterQueueResponse =
+            serde_json::from_slice(resp.body()).unwrap();
+        let expected_replay_response = ReplayDeadLetterQueueResponse {
+            num_replayed_docs: 1,
+            num_ingested_docs: 0,
+            num_rejected_docs: 1,
+        };
+        assert_eq!(replay_response, expected_replay_response);
+
+        is_mapping_fixed.store(true, Ordering::Relaxed);
+
+        let resp = warp::test::request()
+            .path("/indexes/my-index/_dlq/replay")
+            .method("POST")
+            .reply(&ingest_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let replay_response: ReplayDeadLetterQueueResponse =
+            serde_json::from_slice(resp.body()).unwrap();
+        let expected_replay_response = ReplayDeadLetterQueueResponse {
+            num_replayed_docs: 1,
+            num_ingested_docs: 1,
+            num_rejected_docs: 0,
+        };
+        assert_eq!(replay_response, expected_replay_response);
+
+        let resp = warp::test::request()
+            .path("/indexes/my-index/_dlq")
+            .reply(&ingest_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let dead_letter_queue_response: DeadLetterQueueResponse =
+            serde_json::from_slice(resp.body()).unwrap();
+        assert!(dead_letter_queue_response.dead_letters.is_empty());
+
+        let resp = warp::test::request()
+            .path("/indexes/unknown-index/_dlq")
+            .reply(&ingest_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 404);
+    }
+
+    #[tokio::test]
+    async fn test_dead_letter_queue_of_write_blocked_index() {
+        let metastore = metastore_for_test();
+        let mut index_config = IndexConfig::for_test("my-index", "ram:///indexes/my-index");
+        index_config.ingest_settings.write_block = true;
+        let create_index_request =
+            CreateIndexRequest::try_from_index_config(&index_config).unwrap();
+        metastore.create_index(create_index_request).await.unwrap();
+
+        let mut config = IngestApiConfig::default();
+        config.dead_letter_queue.max_docs_per_index = 10;
+
+        let ingest_api_handlers = ingest_api_handlers(
+            IngestRouterServiceClient::mocked(),
+            IngestServiceClient::mocked(),
+            metastore,
+            Arc::new(MockSearchService::new()),
+            config,
+            None,
+            false,
+            true,
+        );
+        // The dead-letter queue of a write-blocked index can be inspected, but not replayed.
+        let resp = warp::test::request()
+            .path("/indexes/my-index/_dlq")
+            .reply(&ingest_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 200);
+
+        let resp = warp::test::request()
+            .path("/indexes/my-index/_dlq/replay")
+            .method("POST")
+            .reply(&ingest_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 403);
+
+        let resp = with_index_scope_for_test(
+            &["logs-*"],
+            warp::test::request()
+                .path("/indexes/my-index/_dlq")
+                .reply(&ingest_api_handlers),
+        )
+        .await;
+        assert_eq!(resp.status(), 403);
+    }
+
+    #[tokio::test]
+    async fn test_dead_letter_queue_endpoints_disabled() {
+        let ingest_api_handlers = ingest_api_handlers(
+            IngestRouterServiceClient::mocked(),
+            IngestServiceClient::mocked(),
+            metastore_for_test(),
+            Arc::new(MockSearchService::new()),
+            IngestApiConfig::default(),
+            None,
+            false,
+            true,
+        );
+        let resp = warp::test::request()
+            .path("/indexes/my-index/_dlq")
+            .reply(&ingest_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 400);
+    }
+}
@@ -0,0 +1,80 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Context;
+use quickwit_ingest::IngestServiceError;
+use quickwit_metastore::IndexIdMatcher;
+
+/// Glob patterns of the indexes the REST ingest endpoints may target, set with
+/// `ingest_api.index_allow_list`.
+#[derive(Clone, Debug)]
+pub(crate) struct IndexAllowList {
+    // `None` when the allow list is empty, in which case no index may be targeted.
+    index_id_matcher_opt: Option<IndexIdMatcher>,
+}
+
+impl IndexAllowList {
+    /// Builds the allow list from index ID patterns, in which `*` matches any sequence of
+    /// characters. Returns an error if a pattern is invalid.
+    pub fn try_new(index_id_patterns: &[String]) -> anyhow::Result<Self> {
+        if index_id_patterns.is_empty() {
+            return Ok(Self {
+                index_id_matcher_opt: None,
+            });
+        }
+        let index_id_matcher = IndexIdMatcher::try_from_index_id_patterns(index_id_patterns)
+            .context("invalid `ingest_api.index_allow_list`")?;
+        Ok(Self {
+            index_id_matcher_opt: Some(index_id_matcher),
+        })
+    }
+
+    /// Returns a `403 Forbidden` error if the index is not allowed. The error message does not
+    /// disclose the allowed indexes.
+    pub fn check(&self, index_id: &str) -> Result<(), IngestServiceError> {
+        if let Some(index_id_matcher) = &self.index_id_matcher_opt
+            && index_id_matcher.is_match(index_id)
+        {
+            return Ok(());
+        }
+        Err(IngestServiceError::Forbidden(format!(
+            "ingesting into index `{index_id}` is not allowed on this node"
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_index_allow_list() {
+        let index_allow_list =
+            IndexAllowList::try_new(&["logs-*".to_string(), "otel.traces".to_string()]).unwrap();
+        index_allow_list.check("logs-").unwrap();
+        index_allow_list.check("logs-app").unwrap();
+        index_allow_list.check("otel.traces").unwrap();
+        index_allow_list.check("otel-traces").unwrap_err();
+        index_allow_list.check("my-logs-app").unwrap_err();
+        index_allow_list.check("otel.traces-v2").unwrap_err();
+
+        let index_allow_list = IndexAllowList::try_new(&["*".to_string()]).unwrap();
+        index_allow_list.check("my-index").unwrap();
+
+        let index_allow_list = IndexAllowList::try_new(&[]).unwrap();
+        index_allow_list.check("my-index").unwrap_err();
+
+        IndexAllowList::try_new(&["logs/*".to_string()]).unwrap_err();
+    }
+}
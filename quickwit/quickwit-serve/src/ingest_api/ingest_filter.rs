@@ -0,0 +1,210 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{BTreeMap, HashMap};
+
+use bytes::Bytes;
+use quickwit_common::thread_pool::run_cpu_intensive;
+use quickwit_config::{IngestFilter, merge_response_headers};
+use quickwit_ingest::IngestServiceError;
+use quickwit_metastore::IndexMetadata;
+use quickwit_proto::types::IndexId;
+use serde_json::Value as JsonValue;
+
+use super::lines;
+use crate::index_api::{IndexMetadataCache, ensure_indexes_writable};
+
+/// Parses the ingest filter of the index, if any. The filter is validated when the index config
+/// is created or updated, so a filter that fails to parse is reported as a bad request.
+pub(crate) fn parse_ingest_filter(
+    index_metadata: &IndexMetadata,
+) -> Result<Option<IngestFilter>, IngestServiceError> {
+    let Some(ingest_filter_expr) = &index_metadata.index_config.ingest_settings.ingest_filter
+    else {
+        return Ok(None);
+    };
+    let ingest_filter = IngestFilter::parse(ingest_filter_expr).map_err(|error| {
+        IngestServiceError::BadRequest(format!(
+            "failed to parse the ingest filter of index `{}`: {error}",
+            index_metadata.index_id()
+        ))
+    })?;
+    Ok(Some(ingest_filter))
+}
+
+/// Drops the documents of `body` that do not match the ingest filter of the index. Returns the
+/// remaining documents, as NDJSON, and the number of dropped documents. Documents that are not
+/// valid JSON are kept, so that they are reported as parse failures.
+pub(crate) async fn filter_docs(
+    ingest_filter: IngestFilter,
+    body: Bytes,
+) -> Result<(Bytes, u64), IngestServiceError> {
+    run_cpu_intensive(move || filter_docs_blocking(&ingest_filter, &body))
+        .await
+        .map_err(|_| IngestServiceError::Internal("failed to filter documents".to_string()))
+}
+
+fn filter_docs_blocking(ingest_filter: &IngestFilter, body: &Bytes) -> (Bytes, u64) {
+    let mut filtered_body = Vec::with_capacity(body.len());
+    let mut num_filtered_docs = 0;
+
+    for doc in lines(body) {
+        if matches_doc(ingest_filter, doc) {
+            filtered_body.extend_from_slice(doc);
+            filtered_body.push(b'\n');
+        } else {
+            num_filtered_docs += 1;
+        }
+    }
+    if num_filtered_docs == 0 {
+        return (body.clone(), 0);
+    }
+    (Bytes::from(filtered_body), num_filtered_docs)
+}
+
+fn matches_doc(ingest_filter: &IngestFilter, doc: &[u8]) -> bool {
+    match serde_json::from_slice::<JsonValue>(doc) {
+        Ok(doc_json) => ingest_filter.matches(&doc_json),
+        Err(_) => true,
+    }
+}
+
+/// Ingest filters of the indexes targeted by a bulk request, keyed by index ID, along with the
+/// response headers declared in the configs of these indexes.
+#[derive(Default)]
+pub(crate) struct IngestFilters {
+    ingest_filters: HashMap<IndexId, IngestFilter>,
+    response_headers: BTreeMap<String, String>,
+}
+
+impl IngestFilters {
+    /// Fetches the ingest filters of the indexes. Rejects the request if one of the indexes is
+    /// not writable.
+    pub async fn fetch(
+        index_metadata_cache: &IndexMetadataCache,
+        index_ids: impl IntoIterator<Item = IndexId>,
+    ) -> Result<Self, IngestServiceError> {
+        let indexes_metadata = ensure_indexes_writable(index_metadata_cache, index_ids).await?;
+        let mut ingest_filters = HashMap::new();
+
+        for index_metadata in &indexes_metadata {
+            if let Some(ingest_filter) = parse_ingest_filter(index_metadata)? {
+                ingest_filters.insert(index_metadata.index_id().to_string(), ingest_filter);
+            }
+        }
+        let response_headers = merge_response_headers(
+            indexes_metadata
+                .iter()
+                .map(|index_metadata| &index_metadata.index_config),
+        );
+        Ok(Self {
+            ingest_filters,
+            response_headers,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ingest_filters.is_empty()
+    }
+
+    /// Returns the merged response headers declared in the configs of the indexes.
+    pub fn response_headers(&self) -> &BTreeMap<String, String> {
+        &self.response_headers
+    }
+
+    /// Returns, for each document, whether it matches the ingest filter of its index. Documents
+    /// of indexes without an ingest filter and documents that are not valid JSON always match.
+    pub async fn matches_docs(
+        self,
+        docs: Vec<(IndexId, Bytes)>,
+    ) -> Result<Vec<bool>, IngestServiceError> {
+        if self.is_empty() {
+            return Ok(vec![true; docs.len()]);
+        }
+        run_cpu_intensive(move || {
+            docs.iter()
+                .map(|(index_id, doc)| {
+                    self.ingest_filters
+                        .get(index_id)
+                        .is_none_or(|ingest_filter| matches_doc(ingest_filter, doc))
+                })
+                .collect()
+        })
+        .await
+        .map_err(|_| IngestServiceError::Internal("failed to filter documents".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_filter_docs() {
+        let ingest_filter = IngestFilter::parse(r#"level != "debug""#).unwrap();
+
+        let body = Bytes::from_static(b"{\"level\": \"info\"}\n{\"level\": \"error\"}");
+        let (filtered_body, num_filtered_docs) = filter_docs(ingest_filter.clone(), body.clone())
+            .await
+            .unwrap();
+        assert_eq!(filtered_body, body);
+        assert_eq!(num_filtered_docs, 0);
+
+        let body = Bytes::from_static(b"{\"level\": \"debug\"}\n{\"level\": \"info\"}\nnot-json\n");
+        let (filtered_body, num_filtered_docs) = filter_docs(ingest_filter, body).await.unwrap();
+        assert_eq!(filtered_body, "{\"level\": \"info\"}\nnot-json\n");
+        assert_eq!(num_filtered_docs, 1);
+    }
+
+    #[test]
+    fn test_parse_ingest_filter() {
+        let mut index_metadata = IndexMetadata::for_test("test-index", "ram:///test-index");
+        assert!(parse_ingest_filter(&index_metadata).unwrap().is_none());
+
+        index_metadata.index_config.ingest_settings.ingest_filter =
+            Some(r#"level != "debug""#.to_string());
+        assert!(parse_ingest_filter(&index_metadata).unwrap().is_some());
+
+        index_metadata.index_config.ingest_settings.ingest_filter = Some("level = ".to_string());
+        let error = parse_ingest_filter(&index_metadata).unwrap_err();
+        assert!(matches!(error, IngestServiceError::BadRequest(_)));
+    }
+
+    #[tokio::test]
+    async fn test_ingest_filters_matches_docs() {
+        let ingest_filters = IngestFilters {
+            ingest_filters: HashMap::from([(
+                "test-index".to_string(),
+                IngestFilter::parse(r#"level != "debug""#).unwrap(),
+            )]),
+            response_headers: BTreeMap::new(),
+        };
+        let docs = vec![
+            (
+                "test-index".to_string(),
+                Bytes::from_static(b"{\"level\": \"debug\"}"),
+            ),
+            (
+                "test-index".to_string(),
+                Bytes::from_static(b"{\"level\": \"info\"}"),
+            ),
+            (
+                "other-index".to_string(),
+                Bytes::from_static(b"{\"level\": \"debug\"}"),
+            ),
+        ];
+        let matches = ingest_filters.matches_docs(docs).await.unwrap();
+        assert_eq!(matches, [false, true, true]);
+    }
+}
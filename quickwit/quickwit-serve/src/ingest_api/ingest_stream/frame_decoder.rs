@@ -0,0 +1,100 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use bytes::{Buf, Bytes, BytesMut};
+use quickwit_ingest::IngestServiceError;
+
+/// Number of bytes of the big-endian length prefixing each batch of the stream.
+pub(super) const FRAME_HEADER_NUM_BYTES: usize = 4;
+
+/// Splits a streamed body into length-delimited frames, only holding on to the frame being
+/// received.
+pub(super) struct FrameDecoder {
+    buffer: BytesMut,
+    max_frame_num_bytes: usize,
+}
+
+impl FrameDecoder {
+    pub fn new(max_frame_num_bytes: usize) -> Self {
+        Self {
+            buffer: BytesMut::new(),
+            max_frame_num_bytes,
+        }
+    }
+
+    pub fn push_chunk(&mut self, chunk: Bytes) {
+        self.buffer.extend_from_slice(&chunk);
+    }
+
+    /// Returns the next complete frame, or `None` if more chunks are needed. Fails as soon as the
+    /// header of a frame exceeding the maximum size is received.
+    pub fn next_frame(&mut self) -> Result<Option<Bytes>, IngestServiceError> {
+        if self.buffer.len() < FRAME_HEADER_NUM_BYTES {
+            return Ok(None);
+        }
+        let frame_num_bytes = (&self.buffer[..FRAME_HEADER_NUM_BYTES]).get_u32() as usize;
+
+        if frame_num_bytes > self.max_frame_num_bytes {
+            let message = format!(
+                "batch exceeds the maximum size of {} bytes",
+                self.max_frame_num_bytes
+            );
+            return Err(IngestServiceError::BadRequest(message));
+        }
+        if self.buffer.len() < FRAME_HEADER_NUM_BYTES + frame_num_bytes {
+            return Ok(None);
+        }
+        self.buffer.advance(FRAME_HEADER_NUM_BYTES);
+        let frame = self.buffer.split_to(frame_num_bytes).freeze();
+        Ok(Some(frame))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ingest_api::ingest_stream::tests::frame_for_test;
+
+    #[test]
+    fn test_frame_decoder() {
+        let mut frame_decoder = FrameDecoder::new(16);
+        assert!(frame_decoder.next_frame().unwrap().is_none());
+
+        let mut body_bytes = frame_for_test(&[r#"{"id": 1}"#]);
+        body_bytes.extend(frame_for_test(&[]));
+        body_bytes.extend(frame_for_test(&[r#"{"id": 2}"#]));
+
+        frame_decoder.push_chunk(Bytes::copy_from_slice(&body_bytes[..15]));
+        let frame = frame_decoder.next_frame().unwrap().unwrap();
+        assert_eq!(frame, Bytes::from_static(br#"{"id": 1}"#));
+        let frame = frame_decoder.next_frame().unwrap().unwrap();
+        assert!(frame.is_empty());
+        assert!(frame_decoder.next_frame().unwrap().is_none());
+        assert!(!frame_decoder.is_empty());
+
+        frame_decoder.push_chunk(Bytes::copy_from_slice(&body_bytes[15..]));
+        let frame = frame_decoder.next_frame().unwrap().unwrap();
+        assert_eq!(frame, Bytes::from_static(br#"{"id": 2}"#));
+        assert!(frame_decoder.is_empty());
+
+        // A frame exceeding the maximum size is rejected before its end is received.
+        frame_decoder.push_chunk(Bytes::from_static(&[0, 0, 0, 17]));
+        let error = frame_decoder.next_frame().unwrap_err();
+        assert!(matches!(error, IngestServiceError::BadRequest(_)));
+    }
+}
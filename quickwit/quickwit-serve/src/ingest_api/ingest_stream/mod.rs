@@ -0,0 +1,483 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+mod frame_decoder;
+
+use std::convert::Infallible;
+use std::num::NonZeroUsize;
+
+use bytes::Bytes;
+use futures::StreamExt;
+use quickwit_config::IngestFilter;
+use quickwit_ingest::{DocBatchV2Builder, IngestServiceError};
+use quickwit_proto::ingest::CommitTypeV2;
+use quickwit_proto::ingest::router::IngestRouterServiceClient;
+use quickwit_proto::types::{DocUidGenerator, IndexId};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::SendError;
+use tokio_stream::wrappers::ReceiverStream;
+use warp::hyper::header::CONTENT_TYPE;
+use warp::hyper::http::HeaderValue;
+use warp::{Filter, Rejection, Reply};
+
+use self::frame_decoder::FrameDecoder;
+use super::index_allow_list::IndexAllowList;
+use super::ingest_filter::{filter_docs, parse_ingest_filter};
+use super::rest_handler::{lines, too_many_docs_error};
+use super::streaming::ingest_doc_batch;
+use crate::decompression::{StreamingBody, get_body_stream};
+use crate::format::extract_format_from_qs;
+use crate::index_api::{IndexMetadataCache, ensure_indexes_writable};
+use crate::query_params::query_params;
+use crate::rest_api_response::into_rest_api_response;
+use crate::{BodyFormat, with_arg};
+
+/// Number of acks buffered between the ingest task and the response body.
+const ACK_CHANNEL_CAPACITY: usize = 8;
+
+#[derive(utoipa::OpenApi)]
+#[openapi(paths(ingest_stream), components(schemas(IngestAck)))]
+pub struct IngestStreamApi;
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+struct IngestStreamOptions {
+    #[serde(alias = "commit", default = "IngestStreamOptions::default_commit_type")]
+    commit_type: CommitTypeV2,
+}
+
+impl IngestStreamOptions {
+    fn default_commit_type() -> CommitTypeV2 {
+        CommitTypeV2::Auto
+    }
+}
+
+/// Acknowledgement of a batch of a streaming ingest request.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct IngestAck {
+    /// Position of the batch in the stream, starting at 0.
+    pub batch_id: u64,
+    /// Number of documents of the batch successfully ingested.
+    pub num_accepted_docs: u64,
+    /// Number of documents of the batch dropped by the ingest filter of the index.
+    #[serde(default)]
+    pub num_filtered_docs: u64,
+    /// Parse failures of the rejected documents, or the reason the whole batch failed.
+    pub errors: Vec<String>,
+}
+
+impl IngestAck {
+    fn failure(batch_id: u64, error: IngestServiceError) -> Self {
+        Self {
+            batch_id,
+            num_accepted_docs: 0,
+            num_filtered_docs: 0,
+            errors: vec![error.to_string()],
+        }
+    }
+}
+
+pub(super) fn ingest_stream_handler(
+    ingest_router: IngestRouterServiceClient,
+    index_metadata_cache: IndexMetadataCache,
+    index_allow_list_opt: Option<IndexAllowList>,
+    max_frame_num_bytes: usize,
+    max_docs_per_request_opt: Option<NonZeroUsize>,
+    enable_ingest_v2: bool,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!(String / "ingest-stream")
+        .and(warp::post())
+        .and(query_params::<IngestStreamOptions>())
+        .and(get_body_stream())
+        .and(with_arg(ingest_router))
+        .and(with_arg(index_metadata_cache))
+        .then(
+            move |index_id, ingest_stream_options, body, ingest_router, index_metadata_cache| {
+                ingest_stream(
+                    index_id,
+                    ingest_stream_options,
+                    body,
+                    ingest_router,
+                    index_metadata_cache,
+                    index_allow_list_opt.clone(),
+                    max_frame_num_bytes,
+                    max_docs_per_request_opt,
+                    enable_ingest_v2,
+                )
+            },
+        )
+        .and(extract_format_from_qs())
+        .map(into_ack_stream_response)
+        .boxed()
+}
+
+#[utoipa::path(
+    post,
+    tag = "Ingest",
+    path = "/{index_id}/ingest-stream",
+    request_body(content = String, description = "Stream of batches of documents in NDJSON format, each prefixed by its length in bytes as a 4-byte big-endian integer. The body cannot be compressed.", content_type = "application/octet-stream"),
+    responses(
+        (status = 200, description = "Stream of newline-delimited JSON acks, one per batch, in the order of the batches.", body = IngestAck)
+    ),
+    params(
+        ("index_id" = String, Path, description = "The index ID to add docs to."),
+        ("commit" = Option<CommitType>, Query, description = "Force or wait for commit at the end of each batch."),
+    )
+)]
+/// Ingest a stream of batches of documents
+///
+/// The batches are ingested one at a time as they are received, and each batch is acknowledged
+/// with an NDJSON line once it is persisted, so a producer can keep a single connection open and
+/// correlate the acks with the batches it sent. A batch failing does not end the stream, but a
+/// malformed stream is acknowledged with a last ack holding the error.
+#[allow(clippy::too_many_arguments)]
+async fn ingest_stream(
+    index_id: IndexId,
+    ingest_stream_options: IngestStreamOptions,
+    body: StreamingBody,
+    ingest_router: IngestRouterServiceClient,
+    index_metadata_cache: IndexMetadataCache,
+    index_allow_list_opt: Option<IndexAllowList>,
+    max_frame_num_bytes: usize,
+    max_docs_per_request_opt: Option<NonZeroUsize>,
+    enable_ingest_v2: bool,
+) -> Result<ReceiverStream<Bytes>, IngestServiceError> {
+    if !enable_ingest_v2 {
+        let message = "streaming ingest requires ingest v2";
+        return Err(IngestServiceError::BadRequest(message.to_string()));
+    }
+    if let Some(index_allow_list) = &index_allow_list_opt {
+        index_allow_list.check(&index_id)?;
+    }
+    let index_metadata = ensure_indexes_writable(&index_metadata_cache, [index_id.clone()])
+        .await?
+        .pop()
+        .ok_or_else(|| IngestServiceError::IndexNotFound {
+            index_id: index_id.clone(),
+        })?;
+    let ingest_filter_opt = parse_ingest_filter(&index_metadata)?;
+    let (ack_tx, ack_rx) = mpsc::channel(ACK_CHANNEL_CAPACITY);
+    tokio::spawn(ingest_frames(
+        index_id,
+        body,
+        ingest_stream_options.commit_type,
+        ingest_router,
+        ingest_filter_opt,
+        max_frame_num_bytes,
+        max_docs_per_request_opt,
+        ack_tx,
+    ));
+    Ok(ReceiverStream::new(ack_rx))
+}
+
+/// Ingests the frames of the body one at a time and sends an ack for each of them. The next chunk
+/// of the body is not read until the current frame is acknowledged, so a slow ingest pipeline or
+/// a client not reading its acks slows down the producer.
+#[allow(clippy::too_many_arguments)]
+async fn ingest_frames(
+    index_id: IndexId,
+    mut body: StreamingBody,
+    commit_type: CommitTypeV2,
+    ingest_router: IngestRouterServiceClient,
+    ingest_filter_opt: Option<IngestFilter>,
+    max_frame_num_bytes: usize,
+    max_docs_per_request_opt: Option<NonZeroUsize>,
+    ack_tx: mpsc::Sender<Bytes>,
+) {
+    let mut frame_decoder = FrameDecoder::new(max_frame_num_bytes);
+    let mut batch_id = 0;
+
+    let error = loop {
+        match frame_decoder.next_frame() {
+            Ok(Some(frame)) => {
+                let ack = ingest_frame(
+                    &index_id,
+                    batch_id,
+                    frame,
+                    commit_type,
+                    &ingest_router,
+                    ingest_filter_opt.as_ref(),
+                    max_docs_per_request_opt,
+                )
+                .await;
+                if send_ack(&ack_tx, &ack).await.is_err() {
+                    // The client closed the connection.
+                    return;
+                }
+                batch_id += 1;
+                continue;
+            }
+            Ok(None) => {}
+            Err(error) => break error,
+        }
+        match body.next_chunk().await {
+            Ok(Some(chunk)) => frame_decoder.push_chunk(chunk),
+            Ok(None) if frame_decoder.is_empty() => return,
+            Ok(None) => {
+                let message = "stream ends with a truncated batch";
+                break IngestServiceError::BadRequest(message.to_string());
+            }
+            Err(error) => break error,
+        }
+    };
+    let _ = send_ack(&ack_tx, &IngestAck::failure(batch_id, error)).await;
+}
+
+async fn ingest_frame(
+    index_id: &IndexId,
+    batch_id: u64,
+    frame: Bytes,
+    commit_type: CommitTypeV2,
+    ingest_router: &IngestRouterServiceClient,
+    ingest_filter_opt: Option<&IngestFilter>,
+    max_docs_per_request_opt: Option<NonZeroUsize>,
+) -> IngestAck {
+    if let Some(max_docs_per_request) = max_docs_per_request_opt
+        && lines(&frame).nth(max_docs_per_request.get()).is_some()
+    {
+        return IngestAck::failure(batch_id, too_many_docs_error(max_docs_per_request));
+    }
+    let (frame, num_filtered_docs) = match ingest_filter_opt {
+        Some(ingest_filter) => match filter_docs(ingest_filter.clone(), frame).await {
+            Ok(filtered_frame) => filtered_frame,
+            Err(error) => return IngestAck::failure(batch_id, error),
+        },
+        None => (frame, 0),
+    };
+    let mut doc_batch_builder = DocBatchV2Builder::default();
+    let mut doc_uid_generator = DocUidGenerator::default();
+
+    for doc in lines(&frame) {
+        doc_batch_builder.add_doc(doc_uid_generator.next_doc_uid(), doc);
+    }
+    match ingest_doc_batch(
+        index_id,
+        doc_batch_builder,
+        commit_type,
+        true,
+        ingest_router,
+    )
+    .await
+    {
+        Ok(ingest_response) => IngestAck {
+            batch_id,
+            num_accepted_docs: ingest_response.num_ingested_docs.unwrap_or_default(),
+            num_filtered_docs,
+            errors: ingest_response
+                .parse_failures
+                .unwrap_or_default()
+                .into_iter()
+                .map(|parse_failure| parse_failure.message)
+                .collect(),
+        },
+        Err(error) => IngestAck::failure(batch_id, error),
+    }
+}
+
+async fn send_ack(ack_tx: &mpsc::Sender<Bytes>, ack: &IngestAck) -> Result<(), SendError<Bytes>> {
+    let mut ack_line = serde_json::to_vec(ack).expect("ack should be JSON serializable");
+    ack_line.push(b'\n');
+    ack_tx.send(Bytes::from(ack_line)).await
+}
+
+fn into_ack_stream_response(
+    ack_stream_result: Result<ReceiverStream<Bytes>, IngestServiceError>,
+    body_format: BodyFormat,
+) -> warp::reply::Response {
+    let ack_stream = match ack_stream_result {
+        Ok(ack_stream) => ack_stream,
+        Err(ingest_error) => {
+            return into_rest_api_response::<(), _>(Err(ingest_error), body_format).into_response();
+        }
+    };
+    let body = warp::Body::wrap_stream(ack_stream.map(Ok::<_, Infallible>));
+    let mut response = warp::reply::Response::new(body);
+    response.headers_mut().insert(
+        CONTENT_TYPE,
+        HeaderValue::from_static("application/x-ndjson"),
+    );
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use bytes::BufMut;
+    use futures::stream;
+    use quickwit_proto::ingest::ParseFailure;
+    use quickwit_proto::ingest::router::{
+        IngestFailure, IngestFailureReason, IngestResponseV2, IngestSuccess,
+        MockIngestRouterService,
+    };
+
+    use super::frame_decoder::FRAME_HEADER_NUM_BYTES;
+    use super::*;
+    use crate::decompression::get_ingest_load_shield;
+
+    pub(super) fn frame_for_test(docs: &[&str]) -> Vec<u8> {
+        let ndjson = docs.join("\n");
+        let mut frame = Vec::with_capacity(FRAME_HEADER_NUM_BYTES + ndjson.len());
+        frame.put_u32(ndjson.len() as u32);
+        frame.extend_from_slice(ndjson.as_bytes());
+        frame
+    }
+
+    async fn ingest_frames_for_test(
+        body_bytes: Vec<u8>,
+        chunk_num_bytes: usize,
+        ingest_router: IngestRouterServiceClient,
+        ingest_filter_opt: Option<IngestFilter>,
+    ) -> Vec<IngestAck> {
+        // The chunks cut the frames anywhere, including in their headers.
+        let chunks: Vec<Bytes> = body_bytes
+            .chunks(chunk_num_bytes)
+            .map(Bytes::copy_from_slice)
+            .collect();
+        let load_shield_permit = get_ingest_load_shield().acquire_permit().await.unwrap();
+        let body = StreamingBody::new(
+            stream::iter(chunks).map(Ok::<_, io::Error>),
+            load_shield_permit,
+        );
+        let (ack_tx, ack_rx) = mpsc::channel(ACK_CHANNEL_CAPACITY);
+        let ingest_handle = tokio::spawn(ingest_frames(
+            "test-index".to_string(),
+            body,
+            CommitTypeV2::Auto,
+            ingest_router,
+            ingest_filter_opt,
+            1024,
+            NonZeroUsize::new(3),
+            ack_tx,
+        ));
+        let ack_lines: Vec<Bytes> = ReceiverStream::new(ack_rx).collect().await;
+        ingest_handle.await.unwrap();
+
+        ack_lines
+            .iter()
+            .map(|ack_line| {
+                assert_eq!(ack_line.last(), Some(&b'\n'));
+                serde_json::from_slice(ack_line).unwrap()
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_ingest_frames_acks_batches_in_order() {
+        let mut mock_ingest_router = MockIngestRouterService::new();
+        mock_ingest_router.expect_ingest().returning(|request| {
+            let doc_batch = request.subrequests[0].doc_batch.as_ref().unwrap();
+
+            if doc_batch.doc_buffer.starts_with(br#"{"id": 3}"#) {
+                return Ok(IngestResponseV2 {
+                    successes: Vec::new(),
+                    failures: vec![IngestFailure {
+                        reason: IngestFailureReason::IndexNotFound as i32,
+                        ..Default::default()
+                    }],
+                });
+            }
+            // The documents that are not JSON objects fail to parse.
+            let parse_failures: Vec<ParseFailure> = doc_batch
+                .docs()
+                .filter(|(_, doc)| !doc.starts_with(b"{"))
+                .map(|(doc_uid, _)| ParseFailure {
+                    doc_uid: Some(doc_uid),
+                    message: "document is not an object".to_string(),
+                    ..Default::default()
+                })
+                .collect();
+            Ok(IngestResponseV2 {
+                successes: vec![IngestSuccess {
+                    num_ingested_docs: (doc_batch.num_docs() - parse_failures.len()) as u32,
+                    parse_failures,
+                    ..Default::default()
+                }],
+                failures: Vec::new(),
+            })
+        });
+        let ingest_router = IngestRouterServiceClient::from_mock(mock_ingest_router);
+
+        let mut body_bytes = frame_for_test(&[r#"{"id": 1}"#, r#"{"id": 2}"#]);
+        body_bytes.extend(frame_for_test(&[r#"{"id": 3}"#]));
+        body_bytes.extend(frame_for_test(&[r#"{"id": 4}"#, "5", r#"{"id": 6}"#]));
+        body_bytes.extend(frame_for_test(&["7", "8", "9", "10"]));
+        body_bytes.extend(frame_for_test(&[r#"{"id": 11}"#]));
+        let truncated_frame = frame_for_test(&[r#"{"id": 12}"#]);
+        body_bytes.extend_from_slice(&truncated_frame[..8]);
+
+        let acks = ingest_frames_for_test(body_bytes, 7, ingest_router, None).await;
+        assert_eq!(acks.len(), 6);
+
+        for (expected_batch_id, ack) in acks.iter().enumerate() {
+            assert_eq!(ack.batch_id, expected_batch_id as u64);
+        }
+        assert_eq!(acks[0].num_accepted_docs, 2);
+        assert!(acks[0].errors.is_empty());
+
+        assert_eq!(acks[1].num_accepted_docs, 0);
+        assert_eq!(acks[1].errors.len(), 1);
+
+        assert_eq!(acks[2].num_accepted_docs, 2);
+        assert_eq!(acks[2].errors, ["document is not an object"]);
+
+        // The batch holding too many documents is rejected without ending the stream.
+        assert_eq!(acks[3].num_accepted_docs, 0);
+        assert!(acks[3].errors[0].contains("max_docs_per_request"));
+
+        assert_eq!(acks[4].num_accepted_docs, 1);
+        assert!(acks[4].errors.is_empty());
+
+        // The truncated batch ends the stream.
+        assert_eq!(acks[5].num_accepted_docs, 0);
+        assert!(acks[5].errors[0].contains("truncated batch"));
+    }
+
+    #[tokio::test]
+    async fn test_ingest_frames_applies_ingest_filter() {
+        let mut mock_ingest_router = MockIngestRouterService::new();
+        mock_ingest_router.expect_ingest().returning(|request| {
+            let doc_batch = request.subrequests[0].doc_batch.as_ref().unwrap();
+            assert_eq!(
+                doc_batch.doc_buffer,
+                Bytes::from_static(br#"{"level": "info"}"#)
+            );
+
+            Ok(IngestResponseV2 {
+                successes: vec![IngestSuccess {
+                    num_ingested_docs: doc_batch.num_docs() as u32,
+                    ..Default::default()
+                }],
+                failures: Vec::new(),
+            })
+        });
+        let ingest_router = IngestRouterServiceClient::from_mock(mock_ingest_router);
+        let ingest_filter = IngestFilter::parse(r#"level != "debug""#).unwrap();
+
+        let mut body_bytes = frame_for_test(&[r#"{"level": "debug"}"#, r#"{"level": "info"}"#]);
+        body_bytes.extend(frame_for_test(&[r#"{"level": "debug"}"#]));
+
+        let acks = ingest_frames_for_test(body_bytes, 7, ingest_router, Some(ingest_filter)).await;
+        assert_eq!(acks.len(), 2);
+
+        assert_eq!(acks[0].num_accepted_docs, 1);
+        assert_eq!(acks[0].num_filtered_docs, 1);
+        assert!(acks[0].errors.is_empty());
+
+        // A batch whose documents are all filtered out is not sent to the router.
+        assert_eq!(acks[1].num_accepted_docs, 0);
+        assert_eq!(acks[1].num_filtered_docs, 1);
+        assert!(acks[1].errors.is_empty());
+    }
+}
@@ -12,11 +12,29 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod content_dedup;
+mod dead_letter_queue;
+mod dead_letter_queue_api;
+mod index_allow_list;
+mod ingest_filter;
+mod ingest_stream;
+mod pending_upserts;
 mod response;
 mod rest_handler;
+mod streaming;
+mod upload_session;
+mod upload_session_api;
+mod upsert;
 
+pub use dead_letter_queue_api::DeadLetterQueueApi;
+pub(crate) use index_allow_list::IndexAllowList;
+pub(crate) use ingest_filter::IngestFilters;
+pub use ingest_stream::IngestStreamApi;
 pub use response::{RestIngestResponse, RestParseFailure};
 #[cfg(test)]
 pub(crate) use rest_handler::tests::setup_ingest_v1_service;
 pub use rest_handler::{IngestApi, IngestApiSchemas};
-pub(crate) use rest_handler::{ingest_api_handlers, lines};
+pub(crate) use rest_handler::{
+    ensure_content_type_accepted, ingest_api_handlers, lines, too_many_docs_error,
+};
+pub use upload_session_api::UploadSessionApi;
@@ -0,0 +1,99 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Merged documents of the upserts replacing previous versions, kept on the node until the
+//! splits the delete tasks of the upserts apply to are closed.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use bytes::Bytes;
+use quickwit_doc_mapper::JsonObject;
+use quickwit_proto::types::IndexUid;
+
+/// Merged documents replacing indexed versions, per index and id, until they are ingested.
+#[derive(Clone, Default)]
+pub(crate) struct PendingUpserts {
+    pending_docs: Arc<Mutex<HashMap<IndexUid, HashMap<String, JsonObject>>>>,
+}
+
+impl PendingUpserts {
+    /// Merges the documents of an upsert request into the pending documents sharing their id, and
+    /// removes them from the request. Returns the number of merged documents.
+    pub fn merge_into_pending(
+        &self,
+        index_uid: &IndexUid,
+        ids: &mut Vec<String>,
+        docs: &mut HashMap<String, JsonObject>,
+    ) -> usize {
+        let mut pending_docs_guard = self
+            .pending_docs
+            .lock()
+            .expect("lock should not be poisoned");
+
+        let Some(pending_docs) = pending_docs_guard.get_mut(index_uid) else {
+            return 0;
+        };
+        let mut num_merged_docs = 0;
+
+        ids.retain(|id| {
+            let Some(pending_doc) = pending_docs.get_mut(id) else {
+                return true;
+            };
+            if let Some(doc) = docs.remove(id) {
+                pending_doc.extend(doc);
+            }
+            num_merged_docs += 1;
+            false
+        });
+        num_merged_docs
+    }
+
+    pub fn insert(&self, index_uid: &IndexUid, docs: Vec<(String, JsonObject)>) {
+        let mut pending_docs_guard = self
+            .pending_docs
+            .lock()
+            .expect("lock should not be poisoned");
+        let pending_docs = pending_docs_guard.entry(index_uid.clone()).or_default();
+
+        for (id, doc) in docs {
+            // An upsert of the same id may have completed its lookup concurrently.
+            pending_docs.entry(id).or_default().extend(doc);
+        }
+    }
+
+    /// Removes the pending documents of the index with the given ids and returns them, as NDJSON.
+    pub fn take(&self, index_uid: &IndexUid, ids: &[String]) -> Bytes {
+        let mut pending_docs_guard = self
+            .pending_docs
+            .lock()
+            .expect("lock should not be poisoned");
+
+        let Some(pending_docs) = pending_docs_guard.get_mut(index_uid) else {
+            return Bytes::new();
+        };
+        let mut ndjson = Vec::new();
+
+        for id in ids {
+            if let Some(doc) = pending_docs.remove(id) {
+                serde_json::to_writer(&mut ndjson, &doc).expect("JSON object should serialize");
+                ndjson.push(b'\n');
+            }
+        }
+        if pending_docs.is_empty() {
+            pending_docs_guard.remove(index_uid);
+        }
+        Bytes::from(ndjson)
+    }
+}
@@ -21,7 +21,7 @@ use quickwit_proto::ingest::{DocBatchV2, ParseFailureReason};
 use quickwit_proto::types::DocUid;
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, utoipa::ToSchema)]
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, utoipa::ToSchema)]
 pub struct RestParseFailure {
     pub message: String,
     pub document: String,
@@ -42,6 +42,14 @@ pub struct RestIngestResponse {
     /// `detailed_response` is set to `true`)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub parse_failures: Option<Vec<RestParseFailure>>,
+    /// Number of docs dropped because identical docs were ingested within the content
+    /// deduplication window of the index (available if the index enables content deduplication)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_deduplicated_docs: Option<u64>,
+    /// Number of docs dropped because they do not match the ingest filter of the index
+    /// (available if the index sets an ingest filter)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_filtered_docs: Option<u64>,
 }
 
 impl RestIngestResponse {
@@ -77,6 +85,8 @@ impl RestIngestResponse {
             num_ingested_docs: Some(success_resp.num_ingested_docs as u64),
             num_rejected_docs: Some(success_resp.parse_failures.len() as u64),
             parse_failures: None,
+            num_deduplicated_docs: None,
+            num_filtered_docs: None,
         };
         if let Some(doc_batch) = doc_batch_clone_opt {
             let docs: BTreeMap<DocUid, Bytes> = doc_batch.docs().collect();
@@ -112,6 +122,14 @@ impl RestIngestResponse {
             parse_failures: apply_op(self.parse_failures, other.parse_failures, |a, b| {
                 a.into_iter().chain(b).collect()
             }),
+            num_deduplicated_docs: apply_op(
+                self.num_deduplicated_docs,
+                other.num_deduplicated_docs,
+                |a, b| a + b,
+            ),
+            num_filtered_docs: apply_op(self.num_filtered_docs, other.num_filtered_docs, |a, b| {
+                a + b
+            }),
         }
     }
 }
@@ -216,6 +234,8 @@ mod tests {
                 document: "doc1".to_string(),
                 reason: ParseFailureReason::InvalidJson,
             }]),
+            num_deduplicated_docs: None,
+            num_filtered_docs: None,
         };
         let response2 = RestIngestResponse {
             num_docs_for_processing: 15,
@@ -226,6 +246,8 @@ mod tests {
                 document: "doc2".to_string(),
                 reason: ParseFailureReason::InvalidJson,
             }]),
+            num_deduplicated_docs: None,
+            num_filtered_docs: None,
         };
         let merged_response = response1.merge(response2);
         assert_eq!(merged_response.num_docs_for_processing, 25);
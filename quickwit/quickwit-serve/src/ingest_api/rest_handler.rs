@@ -12,24 +12,53 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::BTreeMap;
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+use std::time::Duration;
+
 use bytes::{Buf, Bytes};
-use quickwit_config::{INGEST_V2_SOURCE_ID, IngestApiConfig, validate_identifier};
+use quickwit_config::{
+    INGEST_V2_SOURCE_ID, IngestApiConfig, IngestBodyBuffering, merge_response_headers,
+    validate_identifier,
+};
 use quickwit_ingest::{
     CommitType, DocBatchBuilder, DocBatchV2Builder, FetchResponse, IngestRequest, IngestService,
     IngestServiceClient, IngestServiceError, TailRequest,
 };
+use quickwit_metastore::IndexMetadata;
 use quickwit_proto::ingest::CommitTypeV2;
 use quickwit_proto::ingest::router::{
     IngestRequestV2, IngestRouterService, IngestRouterServiceClient, IngestSubrequest,
 };
-use quickwit_proto::types::{DocUidGenerator, IndexId};
+use quickwit_proto::metastore::MetastoreServiceClient;
+use quickwit_proto::types::{DocUidGenerator, IndexId, IndexUid};
+use quickwit_search::SearchService;
 use serde::Deserialize;
+use tracing::error;
+use warp::hyper::header::CONTENT_TYPE;
 use warp::{Filter, Rejection};
 
 use super::RestIngestResponse;
-use crate::decompression::get_body_bytes;
+use super::content_dedup::ContentDedup;
+use super::dead_letter_queue::DeadLetterQueue;
+use super::dead_letter_queue_api::dead_letter_queue_handlers;
+use super::index_allow_list::IndexAllowList;
+use super::ingest_filter::{filter_docs, parse_ingest_filter};
+use super::ingest_stream::ingest_stream_handler;
+use super::pending_upserts::PendingUpserts;
+use super::streaming::{NdjsonDocCounter, ingest_v2_streaming};
+use super::upload_session::UploadSessions;
+use super::upload_session_api::upload_session_handlers;
+use super::upsert::prepare_upsert;
+use crate::decompression::{
+    StreamingBody, get_body_bytes, get_body_stream, get_ingest_load_shield,
+};
 use crate::format::extract_format_from_qs;
-use crate::rest_api_response::into_rest_api_response;
+use crate::index_api::{IndexMetadataCache, ensure_indexes_writable};
+use crate::query_params::query_params;
+use crate::recent_errors::{IndexErrorType, recent_errors};
+use crate::rest_api_response::{into_rest_api_response, into_rest_api_response_with_index_headers};
 use crate::{Body, BodyFormat, with_arg};
 
 #[derive(utoipa::OpenApi)]
@@ -71,60 +100,186 @@ impl IngestOptions {
     }
 }
 
+/// Body of an ingest request, read in full or streamed depending on the
+/// `ingest_api.body_buffering` setting.
+enum IngestBody {
+    Buffered(Body),
+    Streaming(StreamingBody),
+}
+
 pub(crate) fn ingest_api_handlers(
     ingest_router: IngestRouterServiceClient,
     ingest_service: IngestServiceClient,
+    metastore: MetastoreServiceClient,
+    search_service: Arc<dyn SearchService>,
     config: IngestApiConfig,
+    index_allow_list_opt: Option<IndexAllowList>,
     enable_ingest_v1: bool,
     enable_ingest_v2: bool,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    let index_metadata_cache = IndexMetadataCache::new(metastore);
+    let dead_letter_queue = DeadLetterQueue::new(config.dead_letter_queue);
+    let upload_sessions = UploadSessions::new(config.upload_session);
+    let content_length_limit = config.content_length_limit.as_u64();
+    let max_docs_per_request_opt = config.max_docs_per_request;
     ingest_handler(
-        ingest_router,
+        ingest_router.clone(),
         ingest_service.clone(),
+        index_metadata_cache.clone(),
+        search_service,
+        dead_letter_queue.clone(),
+        upload_sessions.clone(),
         config,
+        index_allow_list_opt.clone(),
         enable_ingest_v1,
         enable_ingest_v2,
     )
+    .or(ingest_stream_handler(
+        ingest_router.clone(),
+        index_metadata_cache.clone(),
+        index_allow_list_opt.clone(),
+        content_length_limit as usize,
+        max_docs_per_request_opt,
+        enable_ingest_v2,
+    ))
     .or(tail_handler(ingest_service))
+    .or(dead_letter_queue_handlers(
+        dead_letter_queue,
+        index_metadata_cache.clone(),
+        ingest_router,
+        index_allow_list_opt.clone(),
+        enable_ingest_v2,
+    ))
+    .or(upload_session_handlers(
+        upload_sessions,
+        index_metadata_cache,
+        index_allow_list_opt,
+        content_length_limit,
+    ))
     .boxed()
 }
 
 fn ingest_filter(
     config: IngestApiConfig,
-) -> impl Filter<Extract = (String, Body, IngestOptions), Error = Rejection> + Clone {
-    warp::path!(String / "ingest")
+    upload_sessions: UploadSessions,
+) -> impl Filter<Extract = (String, IngestBody, Option<String>, IngestOptions), Error = Rejection> + Clone
+{
+    let buffered_body_filter =
+        warp::body::content_length_limit(config.content_length_limit.as_u64())
+            .and(get_body_bytes())
+            .map(IngestBody::Buffered);
+    let body_filter = match config.body_buffering {
+        IngestBodyBuffering::Full => buffered_body_filter.boxed(),
+        // Compressed bodies are not streamed: they fall back to the buffered body filter.
+        IngestBodyBuffering::Streaming => get_body_stream()
+            .map(IngestBody::Streaming)
+            .or(buffered_body_filter)
+            .unify()
+            .boxed(),
+    };
+    let request_body_filter = warp::path!(String / "ingest")
+        .and(warp::post())
+        .and(body_filter)
+        .map(|index_id, body| (index_id, body, None::<String>))
+        .untuple_one();
+    // The body of an upload session is streamed to the ingest pipeline: the session is only
+    // committing once the body is read, after the index has been checked, and is removed once
+    // the body is ingested.
+    let upload_session_body_filter = warp::path!(String / "ingest" / "session" / String / "commit")
         .and(warp::post())
-        .and(warp::body::content_length_limit(
-            config.content_length_limit.as_u64(),
-        ))
-        .and(get_body_bytes())
-        .and(warp::query::<IngestOptions>())
+        .and(with_arg(upload_sessions))
+        .and_then(
+            |index_id: String, session_id: String, upload_sessions: UploadSessions| async move {
+                let permit = get_ingest_load_shield().acquire_permit().await?;
+                let body_stream =
+                    upload_sessions.commit_body_stream(index_id.clone(), session_id.clone());
+                let body = IngestBody::Streaming(StreamingBody::new(body_stream, permit));
+                Ok::<_, Rejection>((index_id, body, Some(session_id)))
+            },
+        )
+        .untuple_one();
+    request_body_filter
+        .or(upload_session_body_filter)
+        .unify()
+        .and(query_params::<IngestOptions>())
 }
 
+#[allow(clippy::too_many_arguments)]
 fn ingest_handler(
     ingest_router: IngestRouterServiceClient,
     ingest_service: IngestServiceClient,
+    index_metadata_cache: IndexMetadataCache,
+    search_service: Arc<dyn SearchService>,
+    dead_letter_queue: DeadLetterQueue,
+    upload_sessions: UploadSessions,
     config: IngestApiConfig,
+    index_allow_list_opt: Option<IndexAllowList>,
     enable_ingest_v1: bool,
     enable_ingest_v2: bool,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
-    ingest_filter(config)
+    let content_length_limit = config.content_length_limit.as_u64() as usize;
+    let max_docs_per_request_opt = config.max_docs_per_request;
+    let content_dedup = ContentDedup::default();
+    let pending_upserts = PendingUpserts::default();
+    ingest_filter(config, upload_sessions.clone())
+        .and(warp::header::optional::<String>(CONTENT_TYPE.as_str()))
         .and(with_arg(ingest_router))
         .and(with_arg(ingest_service))
+        .and(with_arg(index_metadata_cache))
+        .and(with_arg(search_service))
+        .and(with_arg(content_dedup))
+        .and(with_arg(pending_upserts))
+        .and(with_arg(dead_letter_queue))
         .then(
-            move |index_id, body, ingest_options, ingest_router, ingest_service| {
-                ingest(
-                    index_id,
+            move |index_id,
+                  body,
+                  upload_session_id_opt: Option<String>,
+                  ingest_options,
+                  content_type_opt,
+                  ingest_router,
+                  ingest_service,
+                  index_metadata_cache,
+                  search_service,
+                  content_dedup,
+                  pending_upserts,
+                  dead_letter_queue| {
+                let ingest_future = ingest(
+                    index_id.clone(),
                     body,
                     ingest_options,
+                    content_type_opt,
                     ingest_router,
                     ingest_service,
+                    index_metadata_cache,
+                    search_service,
+                    content_dedup,
+                    pending_upserts,
+                    dead_letter_queue,
+                    content_length_limit,
+                    max_docs_per_request_opt,
+                    index_allow_list_opt.clone(),
                     enable_ingest_v1,
                     enable_ingest_v2,
-                )
+                );
+                let upload_sessions = upload_sessions.clone();
+                async move {
+                    let ingest_result = ingest_future.await;
+
+                    if let Some(upload_session_id) = upload_session_id_opt {
+                        upload_sessions.end_commit(&upload_session_id, ingest_result.is_ok());
+                    }
+                    if let Err(ingest_error) = &ingest_result {
+                        recent_errors().record_error(
+                            [index_id.as_str()],
+                            IndexErrorType::Ingest,
+                            ingest_error,
+                        );
+                    }
+                    ingest_result
+                }
             },
         )
-        .map(|result| into_rest_api_response(result, BodyFormat::default()))
+        .map(|result| into_rest_api_response_with_index_headers(result, BodyFormat::node_default()))
         .boxed()
 }
 
@@ -132,39 +287,290 @@ fn ingest_handler(
     post,
     tag = "Ingest",
     path = "/{index_id}/ingest",
-    request_body(content = String, description = "Documents to ingest in NDJSON format and limited to 10MB", content_type = "application/json"),
+    request_body(content = String, description = "Documents to ingest in NDJSON format and limited to 10MB, unless the body is streamed", content_type = "application/json"),
     responses(
         (status = 200, description = "Successfully ingested documents.", body = RestIngestResponse)
     ),
     params(
-        ("index_id" = String, Path, description = "The index ID to add docs to."),
+        ("index_id" = String, Path, description = "The index ID or the write alias of the index to add docs to."),
         ("commit" = Option<CommitType>, Query, description = "Force or wait for commit at the end of the indexing operation."),
     )
 )]
 /// Ingest documents
+///
+/// The response carries the response headers declared in the config of the index.
+#[allow(clippy::too_many_arguments)]
 async fn ingest(
     index_id: IndexId,
-    body: Body,
-    ingest_options: IngestOptions,
+    ingest_body: IngestBody,
+    mut ingest_options: IngestOptions,
+    content_type_opt: Option<String>,
     ingest_router: IngestRouterServiceClient,
     ingest_service: IngestServiceClient,
+    index_metadata_cache: IndexMetadataCache,
+    search_service: Arc<dyn SearchService>,
+    content_dedup: ContentDedup,
+    pending_upserts: PendingUpserts,
+    dead_letter_queue: DeadLetterQueue,
+    content_length_limit: usize,
+    max_docs_per_request_opt: Option<NonZeroUsize>,
+    index_allow_list_opt: Option<IndexAllowList>,
     enable_ingest_v1: bool,
     enable_ingest_v2: bool,
-) -> Result<RestIngestResponse, IngestServiceError> {
-    if enable_ingest_v2 && !ingest_options.use_legacy_ingest {
-        return ingest_v2(index_id, body, ingest_options, ingest_router).await;
+) -> Result<(RestIngestResponse, BTreeMap<String, String>), IngestServiceError> {
+    // Checked before the index metadata is fetched, so as not to disclose whether the index exists.
+    if let Some(index_allow_list) = &index_allow_list_opt {
+        index_allow_list.check(&index_id)?;
+    }
+    // The index targeted through a write alias must be allowed as well.
+    let index_id = index_metadata_cache.resolve_write_alias(index_id).await?;
+
+    if let Some(index_allow_list) = &index_allow_list_opt {
+        index_allow_list.check(&index_id)?;
+    }
+    let indexes_metadata =
+        ensure_indexes_writable(&index_metadata_cache, [index_id.clone()]).await?;
+
+    if let Some(index_metadata) = indexes_metadata.first() {
+        ensure_content_type_accepted(index_metadata, content_type_opt.as_deref())?;
+    }
+    let index_response_headers = merge_response_headers(
+        indexes_metadata
+            .iter()
+            .map(|index_metadata| &index_metadata.index_config),
+    );
+    let use_ingest_v2 = enable_ingest_v2 && !ingest_options.use_legacy_ingest;
+    let is_upsert = indexes_metadata
+        .first()
+        .and_then(|index_metadata| {
+            index_metadata
+                .index_config
+                .ingest_settings
+                .upsert_id_field
+                .as_ref()
+        })
+        .is_some();
+    let content_dedup_window_secs_opt = indexes_metadata.first().and_then(|index_metadata| {
+        index_metadata
+            .index_config
+            .ingest_settings
+            .content_dedup_window_secs
+    });
+    let ingest_filter_opt = match indexes_metadata.first() {
+        Some(index_metadata) => parse_ingest_filter(index_metadata)?,
+        None => None,
+    };
+
+    let mut body = match ingest_body {
+        IngestBody::Buffered(buffered_body) => {
+            // Stops counting the documents as soon as the limit is exceeded.
+            if let Some(max_docs_per_request) = max_docs_per_request_opt
+                && lines(&buffered_body.content)
+                    .nth(max_docs_per_request.get())
+                    .is_some()
+            {
+                return Err(too_many_docs_error(max_docs_per_request));
+            }
+            buffered_body
+        }
+        IngestBody::Streaming(streaming_body)
+            if use_ingest_v2
+                && !is_upsert
+                && content_dedup_window_secs_opt.is_none()
+                && ingest_filter_opt.is_none()
+                && !dead_letter_queue.is_enabled() =>
+        {
+            let ingest_response = ingest_v2_streaming(
+                index_id,
+                streaming_body,
+                ingest_options.commit_type,
+                ingest_options.detailed_response,
+                ingest_router,
+                content_length_limit,
+                max_docs_per_request_opt,
+            )
+            .await?;
+            return Ok((ingest_response, index_response_headers));
+        }
+        // Upserts, content deduplication, ingest filters, dead-letter queues, and ingest v1 need
+        // the whole body. The documents are counted as the body is read.
+        IngestBody::Streaming(streaming_body) => {
+            let mut doc_counter_opt = max_docs_per_request_opt.map(NdjsonDocCounter::new);
+            streaming_body
+                .into_body(content_length_limit, |chunk| match &mut doc_counter_opt {
+                    Some(doc_counter) => doc_counter.count_chunk(chunk),
+                    None => Ok(()),
+                })
+                .await?
+        }
+    };
+
+    let mut num_filtered_docs_opt = None;
+
+    if let Some(ingest_filter) = ingest_filter_opt {
+        let (filtered_content, num_filtered_docs) =
+            filter_docs(ingest_filter, body.content).await?;
+        body.content = filtered_content;
+        num_filtered_docs_opt = Some(num_filtered_docs);
+    }
+    let mut num_deduplicated_docs_opt = None;
+    // Recorded once the documents are ingested, so the documents of a failed request are not
+    // dropped when retried.
+    let mut content_hashes_opt = None;
+
+    if let Some(index_metadata) = indexes_metadata.first()
+        && let Some(content_dedup_window_secs) = content_dedup_window_secs_opt
+    {
+        let content_dedup_window = Duration::from_secs(content_dedup_window_secs.get());
+        let (deduplicated_content, num_deduplicated_docs, content_hashes) = content_dedup
+            .dedup(
+                &index_metadata.index_uid,
+                content_dedup_window,
+                &body.content,
+            )
+            .await?;
+        body.content = deduplicated_content;
+        num_deduplicated_docs_opt = Some(num_deduplicated_docs);
+        content_hashes_opt = Some(content_hashes);
+    }
+
+    let mut num_pending_upserted_docs = 0;
+
+    if let Some(index_metadata) = indexes_metadata.first()
+        && let Some(upsert_id_field) = &index_metadata.index_config.ingest_settings.upsert_id_field
+    {
+        let prepared_upsert = prepare_upsert(
+            index_metadata,
+            upsert_id_field,
+            &body.content,
+            &*search_service,
+            index_metadata_cache.metastore(),
+            &pending_upserts,
+        )
+        .await?;
+        body.content = prepared_upsert.new_docs;
+        num_pending_upserted_docs =
+            prepared_upsert.replacing_ids.len() + prepared_upsert.num_merged_into_pending_docs;
+
+        if !prepared_upsert.replacing_ids.is_empty() {
+            let commit_timeout = index_metadata
+                .index_config
+                .indexing_settings
+                .commit_timeout();
+            let replacing_docs_ingest = ReplacingDocsIngest {
+                index_id: index_id.clone(),
+                index_uid: index_metadata.index_uid.clone(),
+                replacing_ids: prepared_upsert.replacing_ids,
+                ingest_options: ingest_options.clone(),
+                use_ingest_v2,
+                ingest_router: ingest_router.clone(),
+                ingest_service: ingest_service.clone(),
+                pending_upserts,
+            };
+            tokio::spawn(replacing_docs_ingest.ingest_after(commit_timeout));
+        }
+    }
+
+    if use_ingest_v2 {
+        let is_detailed_response = ingest_options.detailed_response;
+        // The documents that failed to parse are needed to fill the dead-letter queue.
+        ingest_options.detailed_response |= dead_letter_queue.is_enabled();
+
+        let mut ingest_response =
+            ingest_v2(index_id, body.content, ingest_options, ingest_router).await?;
+        ingest_response.num_docs_for_processing += num_pending_upserted_docs as u64;
+
+        if let Some(content_hashes) = content_hashes_opt {
+            content_dedup.record(content_hashes);
+        }
+        ingest_response.num_deduplicated_docs = num_deduplicated_docs_opt;
+        ingest_response.num_filtered_docs = num_filtered_docs_opt;
+
+        if let Some(index_metadata) = indexes_metadata.first()
+            && dead_letter_queue.is_enabled()
+        {
+            let parse_failures_opt = if is_detailed_response {
+                ingest_response.parse_failures.clone()
+            } else {
+                ingest_response.parse_failures.take()
+            };
+            dead_letter_queue.push(
+                &index_metadata.index_uid,
+                parse_failures_opt.unwrap_or_default(),
+            );
+        }
+        return Ok((ingest_response, index_response_headers));
     }
     if !enable_ingest_v1 {
         let message = "ingest v1 is disabled: environment variable `QW_DISABLE_INGEST_V1` is set";
         return Err(IngestServiceError::Internal(message.to_string()));
     }
-    ingest_v1(index_id, body, ingest_options, ingest_service).await
+    let mut ingest_response =
+        ingest_v1(index_id, body.content, ingest_options, ingest_service).await?;
+    ingest_response.num_docs_for_processing += num_pending_upserted_docs as u64;
+
+    if let Some(content_hashes) = content_hashes_opt {
+        content_dedup.record(content_hashes);
+    }
+    ingest_response.num_deduplicated_docs = num_deduplicated_docs_opt;
+    ingest_response.num_filtered_docs = num_filtered_docs_opt;
+    Ok((ingest_response, index_response_headers))
+}
+
+/// Ingests the merged documents of an upsert replacing previous versions, once the splits the
+/// delete task of the upsert applies to are closed.
+struct ReplacingDocsIngest {
+    index_id: IndexId,
+    index_uid: IndexUid,
+    replacing_ids: Vec<String>,
+    ingest_options: IngestOptions,
+    use_ingest_v2: bool,
+    ingest_router: IngestRouterServiceClient,
+    ingest_service: IngestServiceClient,
+    pending_upserts: PendingUpserts,
+}
+
+impl ReplacingDocsIngest {
+    async fn ingest_after(self, commit_timeout: Duration) {
+        // The indexer reads the last delete opstamp when it opens a split, and the delete task
+        // applies to the splits opened before it. These splits are closed at the latest once the
+        // commit timeout elapses.
+        tokio::time::sleep(commit_timeout).await;
+
+        let content = self
+            .pending_upserts
+            .take(&self.index_uid, &self.replacing_ids);
+        let ingest_result = if self.use_ingest_v2 {
+            ingest_v2(
+                self.index_id.clone(),
+                content,
+                self.ingest_options,
+                self.ingest_router,
+            )
+            .await
+        } else {
+            ingest_v1(
+                self.index_id.clone(),
+                content,
+                self.ingest_options,
+                self.ingest_service,
+            )
+            .await
+        };
+        if let Err(ingest_error) = ingest_result {
+            error!(
+                index_id = %self.index_id,
+                error = %ingest_error,
+                "failed to ingest upserted documents"
+            );
+        }
+    }
 }
 
 /// Ingest documents
 async fn ingest_v1(
     index_id: IndexId,
-    body: Body,
+    content: Bytes,
     ingest_options: IngestOptions,
     ingest_service: IngestServiceClient,
 ) -> Result<RestIngestResponse, IngestServiceError> {
@@ -175,8 +581,8 @@ async fn ingest_v1(
     }
     // The size of the body should be an upper bound of the size of the batch. The removal of the
     // end of line character for each doc compensates the addition of the `DocCommand` header.
-    let mut doc_batch_builder = DocBatchBuilder::with_capacity(index_id, body.content.remaining());
-    for line in lines(&body.content) {
+    let mut doc_batch_builder = DocBatchBuilder::with_capacity(index_id, content.remaining());
+    for line in lines(&content) {
         doc_batch_builder.ingest_doc(line);
     }
     let ingest_req = IngestRequest {
@@ -189,17 +595,17 @@ async fn ingest_v1(
 
 async fn ingest_v2(
     index_id: IndexId,
-    body: Body,
+    content: Bytes,
     ingest_options: IngestOptions,
     ingest_router: IngestRouterServiceClient,
 ) -> Result<RestIngestResponse, IngestServiceError> {
     let mut doc_batch_builder = DocBatchV2Builder::default();
     let mut doc_uid_generator = DocUidGenerator::default();
 
-    for doc in lines(&body.content) {
+    for doc in lines(&content) {
         doc_batch_builder.add_doc(doc_uid_generator.next_doc_uid(), doc);
     }
-    drop(body);
+    drop(content);
     let doc_batch_opt = doc_batch_builder.build();
 
     let Some(doc_batch) = doc_batch_opt else {
@@ -223,7 +629,7 @@ async fn ingest_v2(
 
     let subrequest = IngestSubrequest {
         subrequest_id: 0,
-        index_id,
+        index_id: index_id.clone(),
         source_id: INGEST_V2_SOURCE_ID.to_string(),
         doc_batch: Some(doc_batch),
     };
@@ -232,6 +638,7 @@ async fn ingest_v2(
         subrequests: vec![subrequest],
     };
     let response = ingest_router.ingest(request).await?;
+    recent_errors().record_parse_failures(&index_id, &response);
     RestIngestResponse::from_ingest_v2(
         response,
         doc_batch_clone_opt.as_ref(),
@@ -279,26 +686,60 @@ pub(crate) fn lines(body: &Bytes) -> impl Iterator<Item = &[u8]> {
         .filter(|line| !is_empty_or_blank_line(line))
 }
 
+pub(crate) fn too_many_docs_error(max_docs_per_request: NonZeroUsize) -> IngestServiceError {
+    let message = format!(
+        "request exceeds the limit of {max_docs_per_request} documents \
+         (`ingest_api.max_docs_per_request`)"
+    );
+    IngestServiceError::BadRequest(message)
+}
+
+/// Rejects the ingest requests whose content type is not accepted by the target index, as
+/// configured with `ingest_settings.accepted_content_types`.
+pub(crate) fn ensure_content_type_accepted(
+    index_metadata: &IndexMetadata,
+    content_type_opt: Option<&str>,
+) -> Result<(), IngestServiceError> {
+    let ingest_settings = &index_metadata.index_config.ingest_settings;
+
+    if ingest_settings.accepts_content_type(content_type_opt) {
+        return Ok(());
+    }
+    let message = format!(
+        "index `{}` does not accept content type `{}`, accepted content types are `{}`",
+        index_metadata.index_id(),
+        content_type_opt.unwrap_or("none"),
+        ingest_settings.accepted_content_types.join(", ")
+    );
+    Err(IngestServiceError::UnsupportedMediaType(message))
+}
+
 #[inline]
-fn is_empty_or_blank_line(line: &[u8]) -> bool {
+pub(super) fn is_empty_or_blank_line(line: &[u8]) -> bool {
     line.is_empty() || line.iter().all(|ch| ch.is_ascii_whitespace())
 }
 
 #[cfg(test)]
 pub(crate) mod tests {
+    use std::collections::BTreeMap;
+    use std::num::NonZeroU64;
     use std::str;
+    use std::sync::Arc;
     use std::time::Duration;
 
     use bytes::Bytes;
     use quickwit_actors::{Mailbox, Universe};
-    use quickwit_config::IngestApiConfig;
+    use quickwit_config::{IndexConfig, IngestApiConfig};
     use quickwit_ingest::{
         CreateQueueIfNotExistsRequest, FetchRequest, FetchResponse, IngestApiService,
         IngestServiceClient, QUEUES_DIR_NAME, SuggestTruncateRequest, init_ingest_api,
     };
+    use quickwit_metastore::{CreateIndexRequestExt, metastore_for_test};
     use quickwit_proto::ingest::router::IngestRouterServiceClient;
+    use quickwit_proto::metastore::{CreateIndexRequest, MetastoreService};
+    use quickwit_search::MockSearchService;
 
-    use super::{RestIngestResponse, ingest_api_handlers};
+    use super::{IndexAllowList, RestIngestResponse, ingest_api_handlers};
     use crate::ingest_api::lines;
 
     #[test]
@@ -355,7 +796,10 @@ pub(crate) mod tests {
         let ingest_api_handlers = ingest_api_handlers(
             ingest_router,
             ingest_service,
+            metastore_for_test(),
+            Arc::new(MockSearchService::new()),
             IngestApiConfig::default(),
+            None,
             true,
             false,
         );
@@ -388,6 +832,247 @@ pub(crate) mod tests {
         universe.assert_quit().await;
     }
 
+    #[tokio::test]
+    async fn test_ingest_api_index_response_headers() {
+        let (universe, _temp_dir, ingest_service, _) =
+            setup_ingest_v1_service(&["my-index"], &IngestApiConfig::default()).await;
+        let metastore = metastore_for_test();
+        let mut index_config = IndexConfig::for_test("my-index", "ram:///indexes/my-index");
+        index_config.response_headers =
+            BTreeMap::from([("X-Data-Classification".to_string(), "pii".to_string())]);
+        let create_index_request =
+            CreateIndexRequest::try_from_index_config(&index_config).unwrap();
+        metastore.create_index(create_index_request).await.unwrap();
+
+        let ingest_api_handlers = ingest_api_handlers(
+            IngestRouterServiceClient::mocked(),
+            ingest_service,
+            metastore,
+            Arc::new(MockSearchService::new()),
+            IngestApiConfig::default(),
+            None,
+            true,
+            false,
+        );
+        let resp = warp::test::request()
+            .path("/my-index/ingest")
+            .method("POST")
+            .json(&true)
+            .body(r#"{"id": 1, "message": "push"}"#)
+            .reply(&ingest_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 200);
+        assert_eq!(resp.headers()["x-data-classification"], "pii");
+
+        universe.assert_quit().await;
+    }
+
+    #[tokio::test]
+    async fn test_ingest_api_write_alias() {
+        let (universe, _temp_dir, ingest_service, _) =
+            setup_ingest_v1_service(&["my-index"], &IngestApiConfig::default()).await;
+        let metastore = metastore_for_test();
+        let mut index_config = IndexConfig::for_test("my-index", "ram:///indexes/my-index");
+        index_config.ingest_settings.write_alias = Some("my-alias".to_string());
+        let create_index_request =
+            CreateIndexRequest::try_from_index_config(&index_config).unwrap();
+        metastore.create_index(create_index_request).await.unwrap();
+
+        let ingest_api_handlers = ingest_api_handlers(
+            IngestRouterServiceClient::mocked(),
+            ingest_service,
+            metastore,
+            Arc::new(MockSearchService::new()),
+            IngestApiConfig::default(),
+            None,
+            true,
+            false,
+        );
+        let resp = warp::test::request()
+            .path("/my-alias/ingest")
+            .method("POST")
+            .body(r#"{"id": 1, "message": "push"}"#)
+            .reply(&ingest_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let ingest_response: RestIngestResponse = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(ingest_response.num_docs_for_processing, 1);
+
+        universe.assert_quit().await;
+    }
+
+    #[tokio::test]
+    async fn test_ingest_api_content_dedup() {
+        let (universe, _temp_dir, ingest_service, _) =
+            setup_ingest_v1_service(&["my-index"], &IngestApiConfig::default()).await;
+        let metastore = metastore_for_test();
+        let mut index_config = IndexConfig::for_test("my-index", "ram:///indexes/my-index");
+        index_config.ingest_settings.content_dedup_window_secs = NonZeroU64::new(3_600);
+        let create_index_request =
+            CreateIndexRequest::try_from_index_config(&index_config).unwrap();
+        metastore.create_index(create_index_request).await.unwrap();
+
+        let ingest_api_handlers = ingest_api_handlers(
+            IngestRouterServiceClient::mocked(),
+            ingest_service,
+            metastore,
+            Arc::new(MockSearchService::new()),
+            IngestApiConfig::default(),
+            None,
+            true,
+            false,
+        );
+        let payload = r#"
+            {"id": 1, "message": "push"}
+            {"id": 2, "message": "push"}
+            {"id": 1, "message": "push"}"#;
+        let resp = warp::test::request()
+            .path("/my-index/ingest")
+            .method("POST")
+            .body(payload)
+            .reply(&ingest_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let ingest_response: RestIngestResponse = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(ingest_response.num_docs_for_processing, 2);
+        assert_eq!(ingest_response.num_deduplicated_docs, Some(1));
+
+        // The producer resends a document along with a new one.
+        let payload = r#"
+            {"id": 2, "message": "push"}
+            {"id": 3, "message": "push"}"#;
+        let resp = warp::test::request()
+            .path("/my-index/ingest")
+            .method("POST")
+            .body(payload)
+            .reply(&ingest_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let ingest_response: RestIngestResponse = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(ingest_response.num_docs_for_processing, 1);
+        assert_eq!(ingest_response.num_deduplicated_docs, Some(1));
+
+        universe.assert_quit().await;
+    }
+
+    #[tokio::test]
+    async fn test_ingest_api_ingest_filter() {
+        let (universe, _temp_dir, ingest_service, _) =
+            setup_ingest_v1_service(&["my-index"], &IngestApiConfig::default()).await;
+        let metastore = metastore_for_test();
+        let mut index_config = IndexConfig::for_test("my-index", "ram:///indexes/my-index");
+        index_config.ingest_settings.ingest_filter =
+            Some(r#"level in ["warn", "error"] and not exists(debug)"#.to_string());
+        let create_index_request =
+            CreateIndexRequest::try_from_index_config(&index_config).unwrap();
+        metastore.create_index(create_index_request).await.unwrap();
+
+        let ingest_api_handlers = ingest_api_handlers(
+            IngestRouterServiceClient::mocked(),
+            ingest_service,
+            metastore,
+            Arc::new(MockSearchService::new()),
+            IngestApiConfig::default(),
+            None,
+            true,
+            false,
+        );
+        let payload = r#"
+            {"id": 1, "level": "info"}
+            {"id": 2, "level": "error"}
+            {"id": 3, "level": "warn", "debug": true}
+            {"id": 4, "level": "warn"}
+            {"id": 5}"#;
+        let resp = warp::test::request()
+            .path("/my-index/ingest")
+            .method("POST")
+            .body(payload)
+            .reply(&ingest_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let ingest_response: RestIngestResponse = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(ingest_response.num_docs_for_processing, 2);
+        assert_eq!(ingest_response.num_filtered_docs, Some(3));
+
+        let resp = warp::test::request()
+            .path("/my-index/tail")
+            .method("GET")
+            .reply(&ingest_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let fetch_response: FetchResponse = serde_json::from_slice(resp.body()).unwrap();
+        let doc_batch = fetch_response.doc_batch.unwrap();
+        assert_eq!(doc_batch.num_docs(), 2);
+
+        let doc_buffer = String::from_utf8_lossy(&doc_batch.doc_buffer);
+        assert!(doc_buffer.contains(r#""id": 2"#));
+        assert!(doc_buffer.contains(r#""id": 4"#));
+        assert!(!doc_buffer.contains(r#""id": 1"#));
+
+        universe.assert_quit().await;
+    }
+
+    #[tokio::test]
+    async fn test_ingest_api_accepted_content_types() {
+        let (universe, _temp_dir, ingest_service, _) =
+            setup_ingest_v1_service(&["my-index"], &IngestApiConfig::default()).await;
+        let metastore = metastore_for_test();
+        let mut index_config = IndexConfig::for_test("my-index", "ram:///indexes/my-index");
+        index_config.ingest_settings.accepted_content_types =
+            vec!["application/x-ndjson".to_string()];
+        let create_index_request =
+            CreateIndexRequest::try_from_index_config(&index_config).unwrap();
+        metastore.create_index(create_index_request).await.unwrap();
+
+        let ingest_api_handlers = ingest_api_handlers(
+            IngestRouterServiceClient::mocked(),
+            ingest_service,
+            metastore,
+            Arc::new(MockSearchService::new()),
+            IngestApiConfig::default(),
+            None,
+            true,
+            false,
+        );
+        let payload = r#"{"id": 1, "message": "push"}"#;
+        let resp = warp::test::request()
+            .path("/my-index/ingest")
+            .method("POST")
+            .header("content-type", "application/x-ndjson; charset=utf-8")
+            .body(payload)
+            .reply(&ingest_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let ingest_response: RestIngestResponse = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(ingest_response.num_docs_for_processing, 1);
+
+        let resp = warp::test::request()
+            .path("/my-index/ingest")
+            .method("POST")
+            .header("content-type", "application/x-protobuf")
+            .body(payload)
+            .reply(&ingest_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 415);
+        let resp_body = str::from_utf8(resp.body()).unwrap();
+        assert!(
+            resp_body
+                .contains("index `my-index` does not accept content type `application/x-protobuf`"),
+            "{resp_body}"
+        );
+
+        // Requests without content type are rejected as well.
+        let resp = warp::test::request()
+            .path("/my-index/ingest")
+            .method("POST")
+            .body(payload)
+            .reply(&ingest_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 415);
+
+        universe.assert_quit().await;
+    }
+
     #[tokio::test]
     async fn test_ingest_api_returns_200_when_ingest_ndjson_and_fetch() {
         let (universe, _temp_dir, ingest_service, _) =
@@ -396,7 +1081,10 @@ pub(crate) mod tests {
         let ingest_api_handlers = ingest_api_handlers(
             ingest_router,
             ingest_service,
+            metastore_for_test(),
+            Arc::new(MockSearchService::new()),
             IngestApiConfig::default(),
+            None,
             true,
             false,
         );
@@ -427,7 +1115,10 @@ pub(crate) mod tests {
         let ingest_api_handlers = ingest_api_handlers(
             ingest_router,
             ingest_service,
+            metastore_for_test(),
+            Arc::new(MockSearchService::new()),
             IngestApiConfig::default(),
+            None,
             true,
             false,
         );
@@ -449,8 +1140,16 @@ pub(crate) mod tests {
         let (universe, _temp_dir, ingest_service, _) =
             setup_ingest_v1_service(&["my-index"], &IngestApiConfig::default()).await;
         let ingest_router = IngestRouterServiceClient::mocked();
-        let ingest_api_handlers =
-            ingest_api_handlers(ingest_router, ingest_service, config.clone(), true, false);
+        let ingest_api_handlers = ingest_api_handlers(
+            ingest_router,
+            ingest_service,
+            metastore_for_test(),
+            Arc::new(MockSearchService::new()),
+            config.clone(),
+            None,
+            true,
+            false,
+        );
         let resp = warp::test::request()
             .path("/my-index/ingest")
             .method("POST")
@@ -462,6 +1161,156 @@ pub(crate) mod tests {
         universe.assert_quit().await;
     }
 
+    #[tokio::test]
+    async fn test_ingest_api_max_docs_per_request() {
+        let config: IngestApiConfig =
+            serde_json::from_str(r#"{ "max_docs_per_request": 2 }"#).unwrap();
+        let (universe, _temp_dir, ingest_service, _) =
+            setup_ingest_v1_service(&["my-index"], &IngestApiConfig::default()).await;
+        let ingest_router = IngestRouterServiceClient::mocked();
+        let ingest_api_handlers = ingest_api_handlers(
+            ingest_router,
+            ingest_service,
+            metastore_for_test(),
+            Arc::new(MockSearchService::new()),
+            config,
+            None,
+            true,
+            false,
+        );
+        let payload = r#"
+            {"id": 1, "message": "push"}
+
+            {"id": 2, "message": "push"}"#;
+        let resp = warp::test::request()
+            .path("/my-index/ingest")
+            .method("POST")
+            .body(payload)
+            .reply(&ingest_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let ingest_response: RestIngestResponse = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(ingest_response.num_docs_for_processing, 2);
+
+        let payload = r#"
+            {"id": 1, "message": "push"}
+            {"id": 2, "message": "push"}
+            {"id": 3, "message": "push"}"#;
+        let resp = warp::test::request()
+            .path("/my-index/ingest")
+            .method("POST")
+            .body(payload)
+            .reply(&ingest_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 400);
+        let resp_body = str::from_utf8(resp.body()).unwrap();
+        assert!(
+            resp_body.contains("exceeds the limit of 2 documents"),
+            "{resp_body}"
+        );
+        universe.assert_quit().await;
+    }
+
+    #[tokio::test]
+    async fn test_ingest_api_max_docs_per_request_streaming_body() {
+        let config: IngestApiConfig =
+            serde_json::from_str(r#"{ "max_docs_per_request": 2, "body_buffering": "streaming" }"#)
+                .unwrap();
+        let (universe, _temp_dir, ingest_service, _) =
+            setup_ingest_v1_service(&["my-index"], &IngestApiConfig::default()).await;
+        let ingest_router = IngestRouterServiceClient::mocked();
+        let ingest_api_handlers = ingest_api_handlers(
+            ingest_router,
+            ingest_service,
+            metastore_for_test(),
+            Arc::new(MockSearchService::new()),
+            config,
+            None,
+            true,
+            false,
+        );
+        // With ingest v1, the streamed body is read in memory and its documents are counted as
+        // it is read.
+        let payload = r#"
+            {"id": 1, "message": "push"}
+            {"id": 2, "message": "push"}"#;
+        let resp = warp::test::request()
+            .path("/my-index/ingest")
+            .method("POST")
+            .body(payload)
+            .reply(&ingest_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 200);
+
+        let payload = r#"
+            {"id": 1, "message": "push"}
+            {"id": 2, "message": "push"}
+            {"id": 3, "message": "push"}"#;
+        let resp = warp::test::request()
+            .path("/my-index/ingest")
+            .method("POST")
+            .body(payload)
+            .reply(&ingest_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 400);
+        let resp_body = str::from_utf8(resp.body()).unwrap();
+        assert!(
+            resp_body.contains("exceeds the limit of 2 documents"),
+            "{resp_body}"
+        );
+        universe.assert_quit().await;
+    }
+
+    #[tokio::test]
+    async fn test_ingest_api_index_allow_list() {
+        let index_allow_list = IndexAllowList::try_new(&["logs-*".to_string()]).unwrap();
+        let (universe, _temp_dir, ingest_service, _) =
+            setup_ingest_v1_service(&["logs-app", "my-index"], &IngestApiConfig::default()).await;
+        let ingest_router = IngestRouterServiceClient::mocked();
+        let ingest_api_handlers = ingest_api_handlers(
+            ingest_router,
+            ingest_service,
+            metastore_for_test(),
+            Arc::new(MockSearchService::new()),
+            IngestApiConfig::default(),
+            Some(index_allow_list),
+            true,
+            false,
+        );
+        let payload = r#"{"id": 1, "message": "push"}"#;
+        let resp = warp::test::request()
+            .path("/logs-app/ingest")
+            .method("POST")
+            .body(payload)
+            .reply(&ingest_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let ingest_response: RestIngestResponse = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(ingest_response.num_docs_for_processing, 1);
+
+        let resp = warp::test::request()
+            .path("/my-index/ingest")
+            .method("POST")
+            .body(payload)
+            .reply(&ingest_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 403);
+        let resp_body = str::from_utf8(resp.body()).unwrap();
+        assert!(
+            resp_body.contains("`my-index` is not allowed"),
+            "{resp_body}"
+        );
+        assert!(!resp_body.contains("logs-"), "{resp_body}");
+
+        let resp = warp::test::request()
+            .path("/indexes/my-index/_dlq/replay")
+            .method("POST")
+            .reply(&ingest_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 403);
+        universe.assert_quit().await;
+    }
+
     #[tokio::test]
     async fn test_ingest_api_blocks_when_wait_is_specified() {
         let (universe, _temp_dir, ingest_service_client, ingest_service_mailbox) =
@@ -470,7 +1319,10 @@ pub(crate) mod tests {
         let ingest_api_handlers = ingest_api_handlers(
             ingest_router,
             ingest_service_client,
+            metastore_for_test(),
+            Arc::new(MockSearchService::new()),
             IngestApiConfig::default(),
+            None,
             true,
             false,
         );
@@ -521,7 +1373,10 @@ pub(crate) mod tests {
         let ingest_api_handlers = ingest_api_handlers(
             ingest_router,
             ingest_service_client,
+            metastore_for_test(),
+            Arc::new(MockSearchService::new()),
             IngestApiConfig::default(),
+            None,
             true,
             false,
         );
@@ -572,7 +1427,10 @@ pub(crate) mod tests {
         let ingest_api_handlers = ingest_api_handlers(
             ingest_router,
             ingest_service,
+            metastore_for_test(),
+            Arc::new(MockSearchService::new()),
             IngestApiConfig::default(),
+            None,
             true,
             false,
         );
@@ -0,0 +1,269 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::mem;
+use std::num::NonZeroUsize;
+
+use bytes::{Bytes, BytesMut};
+use quickwit_common::retry::{Retry, RetryParams, retry};
+use quickwit_config::{INGEST_V2_SOURCE_ID, validate_identifier};
+use quickwit_ingest::{DocBatchV2Builder, IngestServiceError};
+use quickwit_proto::ingest::CommitTypeV2;
+use quickwit_proto::ingest::router::{
+    IngestRequestV2, IngestRouterService, IngestRouterServiceClient, IngestSubrequest,
+};
+use quickwit_proto::types::{DocUidGenerator, IndexId};
+
+use super::RestIngestResponse;
+use super::rest_handler::{is_empty_or_blank_line, too_many_docs_error};
+use crate::decompression::StreamingBody;
+use crate::recent_errors::recent_errors;
+
+/// Size above which the documents read from a streamed body are sent to the ingest router.
+const STREAMING_DOC_BATCH_NUM_BYTES: usize = 1024 * 1024;
+
+/// Splits the chunks of an NDJSON body into lines, only holding on to the trailing partial line of
+/// the last chunk.
+struct NdjsonLineSplitter {
+    partial_line: BytesMut,
+    max_line_num_bytes: usize,
+}
+
+impl NdjsonLineSplitter {
+    fn new(max_line_num_bytes: usize) -> Self {
+        Self {
+            partial_line: BytesMut::new(),
+            max_line_num_bytes,
+        }
+    }
+
+    /// Returns the lines completed by `chunk`, skipping the empty and blank lines.
+    fn push_chunk(&mut self, mut chunk: Bytes) -> Result<Vec<Bytes>, IngestServiceError> {
+        let mut lines = Vec::new();
+
+        while let Some(newline_pos) = chunk.iter().position(|byte| *byte == b'\n') {
+            let line_with_newline = chunk.split_to(newline_pos + 1);
+            let line = if self.partial_line.is_empty() {
+                line_with_newline.slice(..newline_pos)
+            } else {
+                self.partial_line
+                    .extend_from_slice(&line_with_newline[..newline_pos]);
+                self.partial_line.split().freeze()
+            };
+            self.check_line_num_bytes(line.len())?;
+
+            if !is_empty_or_blank_line(&line) {
+                lines.push(line);
+            }
+        }
+        self.partial_line.extend_from_slice(&chunk);
+        self.check_line_num_bytes(self.partial_line.len())?;
+        Ok(lines)
+    }
+
+    /// Returns the last line of the body if it is not terminated by a newline.
+    fn finish(&mut self) -> Option<Bytes> {
+        let line = self.partial_line.split().freeze();
+
+        if is_empty_or_blank_line(&line) {
+            return None;
+        }
+        Some(line)
+    }
+
+    fn check_line_num_bytes(&self, line_num_bytes: usize) -> Result<(), IngestServiceError> {
+        if line_num_bytes > self.max_line_num_bytes {
+            let message = format!(
+                "document exceeds the maximum size of {} bytes",
+                self.max_line_num_bytes
+            );
+            return Err(IngestServiceError::BadRequest(message));
+        }
+        Ok(())
+    }
+}
+
+/// Counts the documents of an NDJSON body as its chunks are read, so that a body holding too many
+/// documents is rejected as soon as the limit is exceeded rather than once it is read entirely.
+pub(super) struct NdjsonDocCounter {
+    max_docs_per_request: NonZeroUsize,
+    num_docs: usize,
+    is_in_doc: bool,
+}
+
+impl NdjsonDocCounter {
+    pub fn new(max_docs_per_request: NonZeroUsize) -> Self {
+        Self {
+            max_docs_per_request,
+            num_docs: 0,
+            is_in_doc: false,
+        }
+    }
+
+    /// Counts the documents starting in `chunk`, skipping the empty and blank lines.
+    pub fn count_chunk(&mut self, chunk: &[u8]) -> Result<(), IngestServiceError> {
+        for byte in chunk {
+            if *byte == b'\n' {
+                self.is_in_doc = false;
+            } else if !self.is_in_doc && !byte.is_ascii_whitespace() {
+                self.is_in_doc = true;
+                self.num_docs += 1;
+
+                if self.num_docs > self.max_docs_per_request.get() {
+                    return Err(too_many_docs_error(self.max_docs_per_request));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Ingests an NDJSON body as it is read, sending the documents to the ingest router in batches of
+/// up to [`STREAMING_DOC_BATCH_NUM_BYTES`]. The next chunk of the body is not read until the
+/// current batch is persisted, so a saturated ingest pipeline slows down the client instead of
+/// piling up the body in memory. Batches rejected because the WAL is full or the shards are rate
+/// limited are retried with backoff.
+///
+/// The batches are persisted independently: when a batch fails, the previous ones remain ingested.
+/// Likewise, when `max_docs_per_request_opt` is set, the documents are counted as they are read
+/// and the request fails as soon as the limit is exceeded, the batches sent until then remaining
+/// ingested.
+pub(super) async fn ingest_v2_streaming(
+    index_id: IndexId,
+    mut body: StreamingBody,
+    commit_type: CommitTypeV2,
+    detailed_response: bool,
+    ingest_router: IngestRouterServiceClient,
+    max_doc_num_bytes: usize,
+    max_docs_per_request_opt: Option<NonZeroUsize>,
+) -> Result<RestIngestResponse, IngestServiceError> {
+    // Validate index ID early because propagating back the right error (400)
+    // from deeper ingest layers is harder
+    if validate_identifier("", &index_id).is_err() {
+        return Err(IngestServiceError::BadRequest(
+            "invalid index ID".to_string(),
+        ));
+    }
+    let max_doc_batch_num_bytes = STREAMING_DOC_BATCH_NUM_BYTES.min(max_doc_num_bytes);
+    let mut line_splitter = NdjsonLineSplitter::new(max_doc_num_bytes);
+    let mut doc_batch_builder = DocBatchV2Builder::default();
+    let mut doc_batch_num_bytes = 0;
+    let mut doc_uid_generator = DocUidGenerator::default();
+    let mut num_docs = 0;
+    let mut ingest_response = RestIngestResponse::default();
+
+    loop {
+        let (docs, is_body_exhausted) = match body.next_chunk().await? {
+            Some(chunk) => (line_splitter.push_chunk(chunk)?, false),
+            None => (line_splitter.finish().into_iter().collect(), true),
+        };
+        num_docs += docs.len();
+
+        if let Some(max_docs_per_request) = max_docs_per_request_opt
+            && num_docs > max_docs_per_request.get()
+        {
+            return Err(too_many_docs_error(max_docs_per_request));
+        }
+        for doc in docs {
+            // The batch is sent before it exceeds the maximum size, so it only gets larger when it
+            // holds a single document.
+            if doc_batch_num_bytes > 0 && doc_batch_num_bytes + doc.len() > max_doc_batch_num_bytes
+            {
+                let batch_ingest_response = ingest_doc_batch(
+                    &index_id,
+                    mem::take(&mut doc_batch_builder),
+                    commit_type,
+                    detailed_response,
+                    &ingest_router,
+                )
+                .await?;
+                ingest_response = ingest_response.merge(batch_ingest_response);
+                doc_batch_num_bytes = 0;
+            }
+            doc_batch_builder.add_doc(doc_uid_generator.next_doc_uid(), &doc);
+            doc_batch_num_bytes += doc.len();
+        }
+        if is_body_exhausted {
+            break;
+        }
+    }
+    let batch_ingest_response = ingest_doc_batch(
+        &index_id,
+        doc_batch_builder,
+        commit_type,
+        detailed_response,
+        &ingest_router,
+    )
+    .await?;
+    Ok(ingest_response.merge(batch_ingest_response))
+}
+
+pub(super) async fn ingest_doc_batch(
+    index_id: &IndexId,
+    doc_batch_builder: DocBatchV2Builder,
+    commit_type: CommitTypeV2,
+    detailed_response: bool,
+    ingest_router: &IngestRouterServiceClient,
+) -> Result<RestIngestResponse, IngestServiceError> {
+    let Some(doc_batch) = doc_batch_builder.build() else {
+        return Ok(RestIngestResponse::default());
+    };
+    let num_docs_for_processing = doc_batch.num_docs() as u64;
+    let doc_batch_clone_opt = if detailed_response {
+        Some(doc_batch.clone())
+    } else {
+        None
+    };
+    let subrequest = IngestSubrequest {
+        subrequest_id: 0,
+        index_id: index_id.clone(),
+        source_id: INGEST_V2_SOURCE_ID.to_string(),
+        doc_batch: Some(doc_batch),
+    };
+    let request = IngestRequestV2 {
+        commit_type: commit_type as i32,
+        subrequests: vec![subrequest],
+    };
+    let doc_batch_clone_ref_opt = doc_batch_clone_opt.as_ref();
+
+    retry(&RetryParams::aggressive(), || {
+        let request_clone = request.clone();
+        async move {
+            let response = ingest_router
+                .ingest(request_clone)
+                .await
+                .map_err(|error| retry_if_rate_limited(error.into()))?;
+            recent_errors().record_parse_failures(index_id, &response);
+            RestIngestResponse::from_ingest_v2(
+                response,
+                doc_batch_clone_ref_opt,
+                num_docs_for_processing,
+            )
+            .map_err(retry_if_rate_limited)
+        }
+    })
+    .await
+    .map_err(Retry::into_inner)
+}
+
+fn retry_if_rate_limited(error: IngestServiceError) -> Retry<IngestServiceError> {
+    if matches!(error, IngestServiceError::RateLimited(_)) {
+        Retry::Transient(error)
+    } else {
+        Retry::Permanent(error)
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,328 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use async_trait::async_trait;
+use futures::{Stream, StreamExt, stream};
+use quickwit_proto::ingest::IngestV2Result;
+use quickwit_proto::ingest::router::{
+    IngestFailure, IngestFailureReason, IngestResponseV2, IngestSuccess, MockIngestRouterService,
+};
+use tokio::sync::{Semaphore, mpsc};
+
+use super::*;
+use crate::decompression::get_ingest_load_shield;
+
+/// A 127-byte document.
+fn doc_for_test() -> String {
+    let doc = format!(r#"{{"message": "{}"}}"#, "x".repeat(112));
+    assert_eq!(doc.len(), 127);
+    doc
+}
+
+async fn streaming_body_for_test(
+    chunks: impl Stream<Item = Bytes> + Send + 'static,
+) -> StreamingBody {
+    let load_shield_permit = get_ingest_load_shield().acquire_permit().await.unwrap();
+    StreamingBody::new(chunks.map(Ok::<_, io::Error>), load_shield_permit)
+}
+
+fn ingest_success(request: &IngestRequestV2) -> IngestResponseV2 {
+    let doc_batch = request.subrequests[0].doc_batch.as_ref().unwrap();
+    IngestResponseV2 {
+        successes: vec![IngestSuccess {
+            num_ingested_docs: doc_batch.num_docs() as u32,
+            ..Default::default()
+        }],
+        failures: Vec::new(),
+    }
+}
+
+#[test]
+fn test_ndjson_line_splitter() {
+    let mut line_splitter = NdjsonLineSplitter::new(16);
+
+    let lines = line_splitter
+        .push_chunk(Bytes::from_static(b"{\"id\": 1}\n{\"id"))
+        .unwrap();
+    assert_eq!(lines, [Bytes::from_static(b"{\"id\": 1}")]);
+
+    let lines = line_splitter
+        .push_chunk(Bytes::from_static(b"\": 2}\n  \n\n{\"id\": 3}"))
+        .unwrap();
+    assert_eq!(lines, [Bytes::from_static(b"{\"id\": 2}")]);
+
+    let line = line_splitter.finish().unwrap();
+    assert_eq!(line, Bytes::from_static(b"{\"id\": 3}"));
+    assert!(line_splitter.finish().is_none());
+
+    // A line exceeding the maximum size is rejected before its end is received.
+    let error = line_splitter
+        .push_chunk(Bytes::from_static(b"{\"message\": \"too long"))
+        .unwrap_err();
+    assert!(matches!(error, IngestServiceError::BadRequest(_)));
+}
+
+#[tokio::test]
+async fn test_ingest_v2_streaming_bounds_buffered_bytes() {
+    const CHUNK_NUM_BYTES: usize = 10_000;
+    const NUM_CHUNKS: usize = 3_200;
+
+    // The body holds 250,000 documents and the chunks cut the lines anywhere.
+    let line = format!("{}\n", doc_for_test()).into_bytes();
+    let num_bytes_read = Arc::new(AtomicUsize::new(0));
+    let num_bytes_read_clone = num_bytes_read.clone();
+    let body_stream = stream::iter(0..NUM_CHUNKS).map(move |chunk_idx| {
+        let chunk: Bytes = (chunk_idx * CHUNK_NUM_BYTES..(chunk_idx + 1) * CHUNK_NUM_BYTES)
+            .map(|pos| line[pos % line.len()])
+            .collect();
+        num_bytes_read_clone.fetch_add(chunk.len(), Ordering::Relaxed);
+        chunk
+    });
+    let body = streaming_body_for_test(body_stream).await;
+
+    let num_bytes_ingested = Arc::new(AtomicUsize::new(0));
+    let num_bytes_ingested_clone = num_bytes_ingested.clone();
+    let max_num_buffered_bytes = Arc::new(AtomicUsize::new(0));
+    let max_num_buffered_bytes_clone = max_num_buffered_bytes.clone();
+    let mut mock_ingest_router = MockIngestRouterService::new();
+    mock_ingest_router
+        .expect_ingest()
+        .returning(move |request| {
+            let doc_batch = request.subrequests[0].doc_batch.as_ref().unwrap();
+            // The bytes read but not ingested yet, including the current batch, are held in
+            // memory.
+            let num_buffered_bytes = num_bytes_read.load(Ordering::Relaxed)
+                - num_bytes_ingested_clone.load(Ordering::Relaxed);
+            max_num_buffered_bytes_clone.fetch_max(num_buffered_bytes, Ordering::Relaxed);
+            // Each document was followed by a newline.
+            let num_doc_bytes = doc_batch.doc_buffer.len() + doc_batch.num_docs();
+            num_bytes_ingested_clone.fetch_add(num_doc_bytes, Ordering::Relaxed);
+            Ok(ingest_success(&request))
+        });
+    let ingest_router = IngestRouterServiceClient::from_mock(mock_ingest_router);
+
+    let ingest_response = ingest_v2_streaming(
+        "test-index".to_string(),
+        body,
+        CommitTypeV2::Auto,
+        false,
+        ingest_router,
+        10 * 1024 * 1024,
+        None,
+    )
+    .await
+    .unwrap();
+    assert_eq!(ingest_response.num_docs_for_processing, 250_000);
+    assert_eq!(ingest_response.num_ingested_docs, Some(250_000));
+    assert_eq!(
+        num_bytes_ingested.load(Ordering::Relaxed),
+        CHUNK_NUM_BYTES * NUM_CHUNKS
+    );
+    let max_num_buffered_bytes = max_num_buffered_bytes.load(Ordering::Relaxed);
+    assert!(max_num_buffered_bytes > 0);
+    assert!(
+        max_num_buffered_bytes < 2 * STREAMING_DOC_BATCH_NUM_BYTES,
+        "{max_num_buffered_bytes}"
+    );
+}
+
+#[derive(Debug)]
+struct GatedIngestRouter {
+    gate: Arc<Semaphore>,
+    num_chunks_read: Arc<AtomicUsize>,
+    num_chunks_read_tx: mpsc::UnboundedSender<usize>,
+}
+
+#[async_trait]
+impl IngestRouterService for GatedIngestRouter {
+    async fn ingest(&self, request: IngestRequestV2) -> IngestV2Result<IngestResponseV2> {
+        let num_chunks_read = self.num_chunks_read.load(Ordering::Relaxed);
+        self.num_chunks_read_tx.send(num_chunks_read).unwrap();
+        // Each batch waits for a permit, as if the ingest queue was full.
+        self.gate.acquire().await.unwrap().forget();
+        Ok(ingest_success(&request))
+    }
+}
+
+#[tokio::test]
+async fn test_ingest_v2_streaming_applies_backpressure() {
+    const NUM_CHUNKS: usize = 8;
+    const NUM_DOCS_PER_CHUNK: usize = 4_096;
+
+    // Each chunk holds about half a batch of documents, so the first batch is sent while
+    // reading the third chunk.
+    let chunk = Bytes::from(format!("{}\n", doc_for_test()).repeat(NUM_DOCS_PER_CHUNK));
+    let num_chunks_read = Arc::new(AtomicUsize::new(0));
+    let num_chunks_read_clone = num_chunks_read.clone();
+    let body_stream = stream::iter(0..NUM_CHUNKS).map(move |_| {
+        num_chunks_read_clone.fetch_add(1, Ordering::Relaxed);
+        chunk.clone()
+    });
+    let body = streaming_body_for_test(body_stream).await;
+
+    let gate = Arc::new(Semaphore::new(0));
+    let (num_chunks_read_tx, mut num_chunks_read_rx) = mpsc::unbounded_channel();
+    let ingest_router = IngestRouterServiceClient::new(GatedIngestRouter {
+        gate: gate.clone(),
+        num_chunks_read: num_chunks_read.clone(),
+        num_chunks_read_tx,
+    });
+    let ingest_handle = tokio::spawn(ingest_v2_streaming(
+        "test-index".to_string(),
+        body,
+        CommitTypeV2::Auto,
+        false,
+        ingest_router,
+        10 * 1024 * 1024,
+        None,
+    ));
+    // The first batch is sent once the third chunk is read.
+    assert_eq!(num_chunks_read_rx.recv().await.unwrap(), 3);
+
+    // The first batch is pending, so the rest of the body is not read.
+    for _ in 0..100 {
+        tokio::task::yield_now().await;
+    }
+    assert_eq!(num_chunks_read.load(Ordering::Relaxed), 3);
+
+    // Each batch is sent only once the previous one is persisted.
+    gate.add_permits(1);
+    let num_chunks_read_at_second_batch = num_chunks_read_rx.recv().await.unwrap();
+    assert!(num_chunks_read_at_second_batch > 3);
+
+    gate.add_permits(NUM_CHUNKS);
+    let ingest_response = ingest_handle.await.unwrap().unwrap();
+    assert_eq!(
+        ingest_response.num_docs_for_processing,
+        (NUM_CHUNKS * NUM_DOCS_PER_CHUNK) as u64
+    );
+    assert_eq!(num_chunks_read.load(Ordering::Relaxed), NUM_CHUNKS);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_ingest_v2_streaming_retries_rate_limited_batches() {
+    let mut mock_ingest_router = MockIngestRouterService::new();
+    mock_ingest_router
+        .expect_ingest()
+        .times(1)
+        .returning(|_request| {
+            Ok(IngestResponseV2 {
+                successes: Vec::new(),
+                failures: vec![IngestFailure {
+                    reason: IngestFailureReason::WalFull as i32,
+                    ..Default::default()
+                }],
+            })
+        });
+    mock_ingest_router
+        .expect_ingest()
+        .times(1)
+        .returning(|request| Ok(ingest_success(&request)));
+    let ingest_router = IngestRouterServiceClient::from_mock(mock_ingest_router);
+    let body_stream = stream::iter([Bytes::from_static(b"{\"id\": 1}\n{\"id\": 2}")]);
+    let body = streaming_body_for_test(body_stream).await;
+
+    let ingest_response = ingest_v2_streaming(
+        "test-index".to_string(),
+        body,
+        CommitTypeV2::Auto,
+        false,
+        ingest_router,
+        10 * 1024 * 1024,
+        None,
+    )
+    .await
+    .unwrap();
+    assert_eq!(ingest_response.num_docs_for_processing, 2);
+    assert_eq!(ingest_response.num_ingested_docs, Some(2));
+}
+
+#[tokio::test]
+async fn test_ingest_v2_streaming_max_docs_per_request() {
+    let mut mock_ingest_router = MockIngestRouterService::new();
+    mock_ingest_router
+        .expect_ingest()
+        .times(1)
+        .returning(|request| Ok(ingest_success(&request)));
+    let ingest_router = IngestRouterServiceClient::from_mock(mock_ingest_router);
+    let body_stream = stream::iter([
+        Bytes::from_static(b"{\"id\": 1}\n{\"id\": 2}\n"),
+        Bytes::from_static(b"{\"id\": 3}"),
+    ]);
+    let body = streaming_body_for_test(body_stream.clone()).await;
+
+    let ingest_response = ingest_v2_streaming(
+        "test-index".to_string(),
+        body,
+        CommitTypeV2::Auto,
+        false,
+        ingest_router,
+        10 * 1024 * 1024,
+        NonZeroUsize::new(3),
+    )
+    .await
+    .unwrap();
+    assert_eq!(ingest_response.num_docs_for_processing, 3);
+
+    // Each document fills a batch of its own. The request fails as soon as the limit is
+    // exceeded, without reading the rest of the body, and the first batch remains persisted.
+    let mut mock_ingest_router = MockIngestRouterService::new();
+    mock_ingest_router
+        .expect_ingest()
+        .times(1)
+        .returning(|request| Ok(ingest_success(&request)));
+    let ingest_router = IngestRouterServiceClient::from_mock(mock_ingest_router);
+    let num_chunks_read = Arc::new(AtomicUsize::new(0));
+    let num_chunks_read_clone = num_chunks_read.clone();
+    let body_stream = stream::iter([
+        Bytes::from_static(b"{\"id\": 1}\n{\"id\": 2}\n"),
+        Bytes::from_static(b"{\"id\": 3}\n"),
+        Bytes::from_static(b"{\"id\": 4}\n"),
+    ])
+    .inspect(move |_| {
+        num_chunks_read_clone.fetch_add(1, Ordering::Relaxed);
+    });
+    let body = streaming_body_for_test(body_stream).await;
+    let error = ingest_v2_streaming(
+        "test-index".to_string(),
+        body,
+        CommitTypeV2::Auto,
+        false,
+        ingest_router,
+        10,
+        NonZeroUsize::new(2),
+    )
+    .await
+    .unwrap_err();
+    assert!(matches!(error, IngestServiceError::BadRequest(_)));
+    assert_eq!(num_chunks_read.load(Ordering::Relaxed), 2);
+}
+
+#[test]
+fn test_ndjson_doc_counter() {
+    let mut doc_counter = NdjsonDocCounter::new(NonZeroUsize::new(2).unwrap());
+    doc_counter
+        .count_chunk(b"{\"id\": 1}\n  \n{\"id\"")
+        .unwrap();
+    // The second document spans two chunks.
+    doc_counter.count_chunk(b": 2}\n\n").unwrap();
+    assert_eq!(doc_counter.num_docs, 2);
+
+    let error = doc_counter.count_chunk(b"\n{\"id\": 3}").unwrap_err();
+    assert!(matches!(error, IngestServiceError::BadRequest(_)));
+}
@@ -0,0 +1,438 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Resumable upload sessions of the REST ingest endpoint, assembling an NDJSON body sent in
+//! several chunks so that a large upload over a flaky link can resume after a drop instead of
+//! starting over.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use bytes::{Bytes, BytesMut};
+use futures::{Stream, StreamExt, stream};
+use quickwit_config::IngestUploadSessionConfig;
+use quickwit_ingest::IngestServiceError;
+use quickwit_proto::types::{IndexId, Ulid};
+use serde::{Deserialize, Serialize};
+
+/// Size of the chunks in which the body of a committed session is ingested.
+const COMMIT_CHUNK_NUM_BYTES: usize = 1024 * 1024;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct UploadSessionResponse {
+    pub session_id: String,
+    pub index_id: IndexId,
+    /// Number of bytes received so far, which is the offset of the next chunk to send.
+    pub num_received_bytes: u64,
+    /// Number of seconds left before the session is discarded if it is not committed.
+    pub expires_in_secs: u64,
+}
+
+struct UploadSession {
+    index_id: IndexId,
+    body: BytesMut,
+    /// Body being ingested by a commit of the session, which is only removed once the commit
+    /// succeeds.
+    committing_body_opt: Option<Bytes>,
+    expires_at: Instant,
+}
+
+impl UploadSession {
+    fn num_bytes(&self) -> usize {
+        match &self.committing_body_opt {
+            Some(committing_body) => committing_body.len(),
+            None => self.body.len(),
+        }
+    }
+
+    fn response(&self, session_id: &str, now: Instant) -> UploadSessionResponse {
+        UploadSessionResponse {
+            session_id: session_id.to_string(),
+            index_id: self.index_id.clone(),
+            num_received_bytes: self.num_bytes() as u64,
+            expires_in_secs: self.expires_at.saturating_duration_since(now).as_secs(),
+        }
+    }
+}
+
+/// Upload sessions in progress on the node, bounded in number, size, and lifetime by the
+/// `ingest_api.upload_session` settings. The sessions are kept in memory: a session can only be
+/// resumed and committed on the node that created it.
+#[derive(Clone)]
+pub(crate) struct UploadSessions {
+    config: IngestUploadSessionConfig,
+    sessions: Arc<Mutex<HashMap<String, UploadSession>>>,
+}
+
+impl UploadSessions {
+    pub fn new(config: IngestUploadSessionConfig) -> Self {
+        Self {
+            config,
+            sessions: Arc::default(),
+        }
+    }
+
+    /// Starts a new session assembling a body to ingest into the index.
+    pub fn create(&self, index_id: IndexId) -> Result<UploadSessionResponse, IngestServiceError> {
+        let now = Instant::now();
+        let mut sessions = self.lock_sessions(now);
+
+        if sessions.len() >= self.config.max_num_sessions.get() {
+            let message = "too many upload sessions in progress on the node \
+                           (`ingest_api.upload_session.max_num_sessions`)";
+            return Err(IngestServiceError::Unavailable(message.to_string()));
+        }
+        let session_id = Ulid::new().to_string();
+        let session = UploadSession {
+            index_id,
+            body: BytesMut::new(),
+            committing_body_opt: None,
+            expires_at: now + Duration::from_secs(self.config.max_lifetime_secs.get()),
+        };
+        let response = session.response(&session_id, now);
+        sessions.insert(session_id, session);
+        Ok(response)
+    }
+
+    /// Returns the state of a session, so that a client can find out where to resume its upload.
+    pub fn get(
+        &self,
+        index_id: &str,
+        session_id: &str,
+    ) -> Result<UploadSessionResponse, IngestServiceError> {
+        let now = Instant::now();
+        let mut sessions = self.lock_sessions(now);
+        let session = find_session(&mut sessions, index_id, session_id)?;
+        Ok(session.response(session_id, now))
+    }
+
+    /// Writes the chunk at `offset` in the body of the session. The chunk may overlap the bytes
+    /// already received, for instance when a client resends a chunk whose response was lost, but
+    /// it must not leave a gap after them.
+    pub fn append(
+        &self,
+        index_id: &str,
+        session_id: &str,
+        offset: u64,
+        chunk: &[u8],
+    ) -> Result<UploadSessionResponse, IngestServiceError> {
+        let now = Instant::now();
+        let max_body_num_bytes = self.config.max_body_size.as_u64();
+        let max_total_num_bytes = self.config.max_total_size.as_u64();
+        let mut sessions = self.lock_sessions(now);
+        let total_num_bytes: u64 = sessions
+            .values()
+            .map(|session| session.num_bytes() as u64)
+            .sum();
+        let session = find_session(&mut sessions, index_id, session_id)?;
+
+        if session.committing_body_opt.is_some() {
+            let message = format!("upload session `{session_id}` is being committed");
+            return Err(IngestServiceError::BadRequest(message));
+        }
+        let num_received_bytes = session.body.len() as u64;
+
+        if offset > num_received_bytes {
+            let message = format!(
+                "chunk starts at offset {offset}, but the session has only received \
+                 {num_received_bytes} bytes"
+            );
+            return Err(IngestServiceError::BadRequest(message));
+        }
+        let chunk_end = offset + chunk.len() as u64;
+
+        if chunk_end > max_body_num_bytes {
+            let message = format!(
+                "upload session body exceeds the maximum size of {max_body_num_bytes} bytes"
+            );
+            return Err(IngestServiceError::BadRequest(message));
+        }
+        let num_new_bytes = chunk_end.saturating_sub(num_received_bytes);
+
+        if total_num_bytes + num_new_bytes > max_total_num_bytes {
+            let message = format!(
+                "upload sessions in progress on the node exceed the maximum total size of \
+                 {max_total_num_bytes} bytes (`ingest_api.upload_session.max_total_size`)"
+            );
+            return Err(IngestServiceError::Unavailable(message));
+        }
+        let num_overlapping_bytes = (num_received_bytes.min(chunk_end) - offset) as usize;
+        let (overlapping_bytes, new_bytes) = chunk.split_at(num_overlapping_bytes);
+
+        if session.body[offset as usize..][..num_overlapping_bytes] != *overlapping_bytes {
+            let message = format!(
+                "chunk at offset {offset} differs from the bytes previously received at this \
+                 offset"
+            );
+            return Err(IngestServiceError::BadRequest(message));
+        }
+        session.body.extend_from_slice(new_bytes);
+        Ok(session.response(session_id, now))
+    }
+
+    /// Returns the body of a session as a stream of chunks, for the ingest endpoint to process it
+    /// as a streamed request body. The session can no longer be appended to or committed again
+    /// once the ingest endpoint starts reading its body, until [`Self::end_commit`] is called.
+    pub fn commit_body_stream(
+        &self,
+        index_id: IndexId,
+        session_id: String,
+    ) -> impl Stream<Item = Result<Bytes, String>> + Send + 'static {
+        let upload_sessions = self.clone();
+        let body_future = async move { upload_sessions.begin_commit(&index_id, &session_id) };
+
+        stream::once(body_future).flat_map(|body_result| {
+            let chunks: Vec<Result<Bytes, String>> = match body_result {
+                Ok(body) => split_into_chunks(body).map(Ok).collect(),
+                Err(error) => vec![Err(error.to_string())],
+            };
+            stream::iter(chunks)
+        })
+    }
+
+    fn begin_commit(&self, index_id: &str, session_id: &str) -> Result<Bytes, IngestServiceError> {
+        let mut sessions = self.lock_sessions(Instant::now());
+        let session = find_session(&mut sessions, index_id, session_id)?;
+
+        if session.committing_body_opt.is_some() {
+            let message = format!("upload session `{session_id}` is being committed");
+            return Err(IngestServiceError::BadRequest(message));
+        }
+        let body = std::mem::take(&mut session.body).freeze();
+        session.committing_body_opt = Some(body.clone());
+        Ok(body)
+    }
+
+    /// Removes the session once its body is ingested, or lets the client commit it again
+    /// otherwise. Does nothing if the commit did not start reading the body of the session.
+    pub fn end_commit(&self, session_id: &str, is_success: bool) {
+        let mut sessions = self.lock_sessions(Instant::now());
+
+        let Some(session) = sessions.get_mut(session_id) else {
+            return;
+        };
+        let Some(committing_body) = session.committing_body_opt.take() else {
+            return;
+        };
+        if is_success {
+            sessions.remove(session_id);
+        } else {
+            session.body = BytesMut::from(committing_body.as_ref());
+        }
+    }
+
+    /// Locks the sessions, discarding the expired ones.
+    fn lock_sessions(&self, now: Instant) -> MutexGuard<'_, HashMap<String, UploadSession>> {
+        let mut sessions = self.sessions.lock().expect("lock should not be poisoned");
+        sessions.retain(|_, session| session.expires_at > now);
+        sessions
+    }
+}
+
+fn find_session<'a>(
+    sessions: &'a mut HashMap<String, UploadSession>,
+    index_id: &str,
+    session_id: &str,
+) -> Result<&'a mut UploadSession, IngestServiceError> {
+    match sessions.get_mut(session_id) {
+        Some(session) if session.index_id == index_id => Ok(session),
+        _ => {
+            let message =
+                format!("upload session `{session_id}` of index `{index_id}` not found or expired");
+            Err(IngestServiceError::BadRequest(message))
+        }
+    }
+}
+
+fn split_into_chunks(body: Bytes) -> impl Iterator<Item = Bytes> {
+    (0..body.len())
+        .step_by(COMMIT_CHUNK_NUM_BYTES)
+        .map(move |start| body.slice(start..body.len().min(start + COMMIT_CHUNK_NUM_BYTES)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::{NonZeroU64, NonZeroUsize};
+
+    use bytesize::ByteSize;
+
+    use super::*;
+
+    fn upload_sessions_for_test(max_body_size: ByteSize) -> UploadSessions {
+        UploadSessions::new(IngestUploadSessionConfig {
+            max_body_size,
+            max_lifetime_secs: NonZeroU64::new(60).unwrap(),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_upload_sessions_append() {
+        let upload_sessions = upload_sessions_for_test(ByteSize::b(16));
+        let session_id = upload_sessions
+            .create("my-index".to_string())
+            .unwrap()
+            .session_id;
+
+        let session_response = upload_sessions
+            .append("my-index", &session_id, 0, b"abcdef")
+            .unwrap();
+        assert_eq!(session_response.num_received_bytes, 6);
+
+        // Resent bytes are ignored, only the new ones are appended.
+        let session_response = upload_sessions
+            .append("my-index", &session_id, 3, b"defgh")
+            .unwrap();
+        assert_eq!(session_response.num_received_bytes, 8);
+
+        let session_response = upload_sessions
+            .append("my-index", &session_id, 2, b"cd")
+            .unwrap();
+        assert_eq!(session_response.num_received_bytes, 8);
+
+        let error = upload_sessions
+            .append("my-index", &session_id, 9, b"j")
+            .unwrap_err();
+        assert!(matches!(error, IngestServiceError::BadRequest(_)));
+
+        let error = upload_sessions
+            .append("my-index", &session_id, 6, b"gX")
+            .unwrap_err();
+        assert!(matches!(error, IngestServiceError::BadRequest(_)));
+
+        let error = upload_sessions
+            .append("my-index", &session_id, 8, b"ijklmnopq")
+            .unwrap_err();
+        assert!(matches!(error, IngestServiceError::BadRequest(_)));
+
+        // Sessions are scoped to their index.
+        let error = upload_sessions
+            .append("other-index", &session_id, 8, b"i")
+            .unwrap_err();
+        assert!(matches!(error, IngestServiceError::BadRequest(_)));
+
+        let body = upload_sessions
+            .begin_commit("my-index", &session_id)
+            .unwrap();
+        assert_eq!(body, "abcdefgh");
+
+        // The session cannot be appended to nor committed while it is being committed.
+        let error = upload_sessions
+            .append("my-index", &session_id, 8, b"i")
+            .unwrap_err();
+        assert!(matches!(error, IngestServiceError::BadRequest(_)));
+        upload_sessions
+            .begin_commit("my-index", &session_id)
+            .unwrap_err();
+
+        // The session is kept if the commit fails.
+        upload_sessions.end_commit(&session_id, false);
+        let session_response = upload_sessions
+            .append("my-index", &session_id, 8, b"i")
+            .unwrap();
+        assert_eq!(session_response.num_received_bytes, 9);
+
+        upload_sessions
+            .begin_commit("my-index", &session_id)
+            .unwrap();
+        upload_sessions.end_commit(&session_id, true);
+        upload_sessions.get("my-index", &session_id).unwrap_err();
+    }
+
+    #[test]
+    fn test_upload_sessions_limits() {
+        let upload_sessions = UploadSessions::new(IngestUploadSessionConfig {
+            max_body_size: ByteSize::b(8),
+            max_lifetime_secs: NonZeroU64::new(60).unwrap(),
+            max_num_sessions: NonZeroUsize::new(2).unwrap(),
+            max_total_size: ByteSize::b(12),
+        });
+        let session_id = upload_sessions
+            .create("my-index".to_string())
+            .unwrap()
+            .session_id;
+        let other_session_id = upload_sessions
+            .create("my-index".to_string())
+            .unwrap()
+            .session_id;
+        let error = upload_sessions.create("my-index".to_string()).unwrap_err();
+        assert!(matches!(error, IngestServiceError::Unavailable(_)));
+
+        upload_sessions
+            .append("my-index", &session_id, 0, b"abcdefgh")
+            .unwrap();
+        upload_sessions
+            .append("my-index", &other_session_id, 0, b"abcd")
+            .unwrap();
+        let error = upload_sessions
+            .append("my-index", &other_session_id, 4, b"e")
+            .unwrap_err();
+        assert!(matches!(error, IngestServiceError::Unavailable(_)));
+
+        // Resent bytes do not count twice.
+        upload_sessions
+            .append("my-index", &other_session_id, 0, b"abcd")
+            .unwrap();
+    }
+
+    #[test]
+    fn test_upload_sessions_evict_expired_sessions() {
+        let upload_sessions = upload_sessions_for_test(ByteSize::mib(1));
+        let session_id = upload_sessions
+            .create("my-index".to_string())
+            .unwrap()
+            .session_id;
+
+        let now = Instant::now() + Duration::from_secs(61);
+        assert!(upload_sessions.lock_sessions(now).is_empty());
+        upload_sessions.get("my-index", &session_id).unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn test_upload_sessions_commit_body_stream() {
+        let upload_sessions = upload_sessions_for_test(ByteSize::mib(4));
+        let session_id = upload_sessions
+            .create("my-index".to_string())
+            .unwrap()
+            .session_id;
+        let body = vec![b'x'; COMMIT_CHUNK_NUM_BYTES * 2 + 1];
+        upload_sessions
+            .append("my-index", &session_id, 0, &body)
+            .unwrap();
+
+        let body_stream =
+            upload_sessions.commit_body_stream("my-index".to_string(), session_id.clone());
+        // The session is only committing once the body is read.
+        upload_sessions
+            .append("my-index", &session_id, 0, b"x")
+            .unwrap();
+
+        let chunk_lens: Vec<usize> = body_stream
+            .map(|chunk_res| chunk_res.unwrap().len())
+            .collect()
+            .await;
+        assert_eq!(
+            chunk_lens,
+            [COMMIT_CHUNK_NUM_BYTES, COMMIT_CHUNK_NUM_BYTES, 1]
+        );
+        upload_sessions.end_commit(&session_id, true);
+        upload_sessions.get("my-index", &session_id).unwrap_err();
+
+        let mut body_stream =
+            Box::pin(upload_sessions.commit_body_stream("my-index".to_string(), session_id));
+        body_stream.next().await.unwrap().unwrap_err();
+        assert!(body_stream.next().await.is_none());
+    }
+}
@@ -0,0 +1,375 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use bytes::Bytes;
+use quickwit_ingest::IngestServiceError;
+use quickwit_proto::types::IndexId;
+use serde::Deserialize;
+use warp::{Filter, Rejection};
+
+use super::index_allow_list::IndexAllowList;
+use super::upload_session::{UploadSessionResponse, UploadSessions};
+use crate::format::extract_format_from_qs;
+use crate::index_api::{IndexMetadataCache, ensure_indexes_writable};
+use crate::query_params::query_params;
+use crate::request_scope::ensure_indexes_in_scope;
+use crate::rest_api_response::into_rest_api_response;
+use crate::with_arg;
+
+#[derive(utoipa::OpenApi)]
+#[openapi(
+    paths(create_upload_session, append_upload_session_chunk, get_upload_session),
+    components(schemas(UploadSessionResponse))
+)]
+pub struct UploadSessionApi;
+
+#[derive(Debug, Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+struct AppendChunkQueryParams {
+    /// Position of the first byte of the chunk in the body of the session.
+    offset: u64,
+}
+
+pub(super) fn upload_session_handlers(
+    upload_sessions: UploadSessions,
+    index_metadata_cache: IndexMetadataCache,
+    index_allow_list_opt: Option<IndexAllowList>,
+    content_length_limit: u64,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    create_upload_session_handler(
+        upload_sessions.clone(),
+        index_metadata_cache,
+        index_allow_list_opt,
+    )
+    .or(append_upload_session_chunk_handler(
+        upload_sessions.clone(),
+        content_length_limit,
+    ))
+    .or(get_upload_session_handler(upload_sessions))
+    .boxed()
+}
+
+fn create_upload_session_handler(
+    upload_sessions: UploadSessions,
+    index_metadata_cache: IndexMetadataCache,
+    index_allow_list_opt: Option<IndexAllowList>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!(String / "ingest" / "session")
+        .and(warp::post())
+        .and(with_arg(upload_sessions))
+        .and(with_arg(index_metadata_cache))
+        .and(with_arg(index_allow_list_opt))
+        .then(create_upload_session)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+        .boxed()
+}
+
+fn append_upload_session_chunk_handler(
+    upload_sessions: UploadSessions,
+    content_length_limit: u64,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!(String / "ingest" / "session" / String)
+        .and(warp::patch())
+        .and(query_params::<AppendChunkQueryParams>())
+        .and(warp::body::content_length_limit(content_length_limit))
+        .and(warp::body::bytes())
+        .and(with_arg(upload_sessions))
+        .then(append_upload_session_chunk)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+        .boxed()
+}
+
+fn get_upload_session_handler(
+    upload_sessions: UploadSessions,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!(String / "ingest" / "session" / String)
+        .and(warp::get())
+        .and(with_arg(upload_sessions))
+        .then(get_upload_session)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+        .boxed()
+}
+
+#[utoipa::path(
+    post,
+    tag = "Ingest",
+    path = "/{index_id}/ingest/session",
+    responses(
+        (status = 200, description = "Successfully started the upload session.", body = UploadSessionResponse)
+    ),
+    params(
+        ("index_id" = String, Path, description = "The index ID to add docs to."),
+    )
+)]
+/// Starts an upload session
+///
+/// The body of the session is sent in chunks with `PATCH /{index_id}/ingest/session/{session_id}`
+/// and ingested with `POST /{index_id}/ingest/session/{session_id}/commit`, which accepts the
+/// query parameters of the ingest endpoint.
+async fn create_upload_session(
+    index_id: IndexId,
+    upload_sessions: UploadSessions,
+    index_metadata_cache: IndexMetadataCache,
+    index_allow_list_opt: Option<IndexAllowList>,
+) -> Result<UploadSessionResponse, IngestServiceError> {
+    if let Some(index_allow_list) = &index_allow_list_opt {
+        index_allow_list.check(&index_id)?;
+    }
+    ensure_indexes_writable(&index_metadata_cache, [index_id.clone()]).await?;
+    upload_sessions.create(index_id)
+}
+
+#[utoipa::path(
+    patch,
+    tag = "Ingest",
+    path = "/{index_id}/ingest/session/{session_id}",
+    request_body(content = String, description = "Chunk of the NDJSON body of the session", content_type = "application/octet-stream"),
+    responses(
+        (status = 200, description = "Successfully appended the chunk.", body = UploadSessionResponse)
+    ),
+    params(
+        ("index_id" = String, Path, description = "The index ID of the upload session."),
+        ("session_id" = String, Path, description = "The ID of the upload session."),
+        AppendChunkQueryParams,
+    )
+)]
+/// Appends a chunk to an upload session
+///
+/// A chunk may overlap the bytes already received, which must then be identical, but must not
+/// leave a gap after them.
+async fn append_upload_session_chunk(
+    index_id: IndexId,
+    session_id: String,
+    query_params: AppendChunkQueryParams,
+    chunk: Bytes,
+    upload_sessions: UploadSessions,
+) -> Result<UploadSessionResponse, IngestServiceError> {
+    ensure_indexes_in_scope([index_id.as_str()])?;
+    upload_sessions.append(&index_id, &session_id, query_params.offset, &chunk)
+}
+
+#[utoipa::path(
+    get,
+    tag = "Ingest",
+    path = "/{index_id}/ingest/session/{session_id}",
+    responses(
+        (status = 200, description = "Successfully fetched the upload session.", body = UploadSessionResponse)
+    ),
+    params(
+        ("index_id" = String, Path, description = "The index ID of the upload session."),
+        ("session_id" = String, Path, description = "The ID of the upload session."),
+    )
+)]
+/// Returns the number of bytes received by an upload session
+///
+/// A client resumes an interrupted upload from this offset.
+async fn get_upload_session(
+    index_id: IndexId,
+    session_id: String,
+    upload_sessions: UploadSessions,
+) -> Result<UploadSessionResponse, IngestServiceError> {
+    ensure_indexes_in_scope([index_id.as_str()])?;
+    upload_sessions.get(&index_id, &session_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use quickwit_config::{IndexConfig, IngestApiConfig};
+    use quickwit_ingest::IngestServiceClient;
+    use quickwit_metastore::{CreateIndexRequestExt, metastore_for_test};
+    use quickwit_proto::ingest::router::{
+        IngestResponseV2, IngestRouterServiceClient, IngestSuccess, MockIngestRouterService,
+    };
+    use quickwit_proto::metastore::{CreateIndexRequest, MetastoreService};
+    use quickwit_search::MockSearchService;
+
+    use super::*;
+    use crate::ingest_api::{RestIngestResponse, ingest_api_handlers};
+    use crate::request_scope::with_index_scope_for_test;
+
+    #[tokio::test]
+    async fn test_upload_session_assembles_chunks_and_commits() {
+        let metastore = metastore_for_test();
+        let index_config = IndexConfig::for_test("my-index", "ram:///indexes/my-index");
+        let create_index_request =
+            CreateIndexRequest::try_from_index_config(&index_config).unwrap();
+        metastore.create_index(create_index_request).await.unwrap();
+
+        let num_ingested_docs = Arc::new(AtomicUsize::new(0));
+        let num_ingested_docs_clone = num_ingested_docs.clone();
+        let mut mock_ingest_router = MockIngestRouterService::new();
+        mock_ingest_router
+            .expect_ingest()
+            .returning(move |request| {
+                let doc_batch = request.subrequests[0].doc_batch.as_ref().unwrap();
+                let docs: Vec<Bytes> = doc_batch.docs().map(|(_, doc)| doc).collect();
+                assert_eq!(docs, ["{\"id\": 1}", "{\"id\": 2}", "{\"id\": 3}"]);
+                num_ingested_docs_clone.fetch_add(doc_batch.num_docs(), Ordering::Relaxed);
+                Ok(IngestResponseV2 {
+                    successes: vec![IngestSuccess {
+                        num_ingested_docs: doc_batch.num_docs() as u32,
+                        ..Default::default()
+                    }],
+                    failures: Vec::new(),
+                })
+            });
+        let ingest_api_handlers = ingest_api_handlers(
+            IngestRouterServiceClient::from_mock(mock_ingest_router),
+            IngestServiceClient::mocked(),
+            metastore,
+            Arc::new(MockSearchService::new()),
+            IngestApiConfig::default(),
+            None,
+            false,
+            true,
+        );
+        let resp = warp::test::request()
+            .path("/my-index/ingest/session")
+            .method("POST")
+            .reply(&ingest_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let session_response: UploadSessionResponse = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(session_response.index_id, "my-index");
+        assert_eq!(session_response.num_received_bytes, 0);
+        assert_eq!(session_response.expires_in_secs, 3600);
+        let session_path = format!("/my-index/ingest/session/{}", session_response.session_id);
+
+        // The chunks cut the documents anywhere, and the second one is sent twice, as a client
+        // would after losing the response to a chunk.
+        for (offset, chunk, expected_num_received_bytes) in [
+            (0, "{\"id\": 1}\n{\"i", 13),
+            (13, "d\": 2}\n", 20),
+            (13, "d\": 2}\n", 20),
+            (17, "2}\n{\"id\": 3}", 30),
+        ] {
+            let resp = warp::test::request()
+                .path(&format!("{session_path}?offset={offset}"))
+                .method("PATCH")
+                .body(chunk)
+                .reply(&ingest_api_handlers)
+                .await;
+            assert_eq!(resp.status(), 200);
+            let session_response: UploadSessionResponse =
+                serde_json::from_slice(resp.body()).unwrap();
+            assert_eq!(
+                session_response.num_received_bytes,
+                expected_num_received_bytes
+            );
+        }
+        // A chunk leaving a gap after the received bytes is rejected.
+        let resp = warp::test::request()
+            .path(&format!("{session_path}?offset=31"))
+            .method("PATCH")
+            .body("\n")
+            .reply(&ingest_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 400);
+
+        let resp = warp::test::request()
+            .path(&session_path)
+            .reply(&ingest_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let session_response: UploadSessionResponse = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(session_response.num_received_bytes, 30);
+
+        let resp = warp::test::request()
+            .path(&format!("{session_path}/commit?commit=force"))
+            .method("POST")
+            .reply(&ingest_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let ingest_response: RestIngestResponse = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(ingest_response.num_ingested_docs, Some(3));
+        assert_eq!(num_ingested_docs.load(Ordering::Relaxed), 3);
+
+        // The session is consumed by its commit.
+        let resp = warp::test::request()
+            .path(&session_path)
+            .reply(&ingest_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 400);
+
+        let resp = warp::test::request()
+            .path(&format!("{session_path}/commit"))
+            .method("POST")
+            .reply(&ingest_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 400);
+    }
+
+    #[tokio::test]
+    async fn test_upload_session_out_of_scope_index() {
+        let upload_sessions = UploadSessions::new(Default::default());
+        let session_id = upload_sessions
+            .create("my-index".to_string())
+            .unwrap()
+            .session_id;
+        let handlers = append_upload_session_chunk_handler(upload_sessions.clone(), 1024)
+            .or(get_upload_session_handler(upload_sessions));
+        let session_path = format!("/my-index/ingest/session/{session_id}");
+
+        let resp = with_index_scope_for_test(
+            &["other-*"],
+            warp::test::request()
+                .path(&format!("{session_path}?offset=0"))
+                .method("PATCH")
+                .body("{}")
+                .reply(&handlers),
+        )
+        .await;
+        assert_eq!(resp.status(), 403);
+
+        let resp = with_index_scope_for_test(
+            &["other-*"],
+            warp::test::request().path(&session_path).reply(&handlers),
+        )
+        .await;
+        assert_eq!(resp.status(), 403);
+
+        let resp = with_index_scope_for_test(
+            &["my-*"],
+            warp::test::request().path(&session_path).reply(&handlers),
+        )
+        .await;
+        assert_eq!(resp.status(), 200);
+    }
+
+    #[tokio::test]
+    async fn test_upload_session_unknown_index() {
+        let ingest_api_handlers = ingest_api_handlers(
+            IngestRouterServiceClient::mocked(),
+            IngestServiceClient::mocked(),
+            metastore_for_test(),
+            Arc::new(MockSearchService::new()),
+            IngestApiConfig::default(),
+            None,
+            false,
+            true,
+        );
+        let resp = warp::test::request()
+            .path("/unknown-index/ingest/session")
+            .method("POST")
+            .reply(&ingest_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 404);
+    }
+}
@@ -0,0 +1,475 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Upserts are implemented as a delete-and-reindex on top of the append-only ingest path:
+//!
+//! 1. Documents of the request sharing the same id are merged together, in order.
+//! 2. Documents whose id has a merged document pending on this node are merged into it.
+//! 3. The currently searchable documents with the remaining ids are fetched and the new documents
+//!    are merged into them. The merge is shallow: top-level fields of the new document replace the
+//!    fields of the existing document.
+//! 4. A delete task removing the previous versions is created.
+//! 5. The documents without any previous version are ingested right away, with the commit type of
+//!    the request.
+//! 6. The merged documents replacing previous versions are kept pending on the node until the
+//!    commit timeout of the index elapses, so that every split opened before the delete task, to
+//!    which the delete task applies, is closed. They are then ingested in the background, with the
+//!    commit type of the request, and land in splits the delete task does not apply to.
+//!
+//! The upserts of a document are serialized on each node through the pending documents: an
+//! upsert of a document replacing a previous version merges into it until it is ingested.
+//!
+//! Consistency guarantees remain weak and should be understood before enabling upserts:
+//! - The previous version remains searchable until the janitor applies the delete task, so both
+//!   versions may be returned in the meantime.
+//! - The merged documents replacing previous versions become searchable at least one commit timeout
+//!   after the upsert request returns, even with `commit=wait_for` or `commit=force`.
+//! - Documents that are not searchable yet (i.e. still being indexed) are not seen by the lookup.
+//!   Upserting them results in a duplicate rather than a merge.
+//! - Upserts sent to different nodes are not serialized: concurrent upserts of the same id on two
+//!   nodes can lose an update or keep both versions. Send the upserts of an index to a single node.
+//! - The pending documents are lost if the node shuts down before ingesting them.
+
+use std::collections::{BTreeSet, HashMap};
+
+use bytes::Bytes;
+use quickwit_doc_mapper::JsonObject;
+use quickwit_ingest::IngestServiceError;
+use quickwit_metastore::IndexMetadata;
+use quickwit_proto::metastore::{DeleteQuery, MetastoreService, MetastoreServiceClient};
+use quickwit_proto::search::SearchRequest;
+use quickwit_query::query_ast::{QueryAst, TermSetQuery};
+use quickwit_search::{SearchError, SearchService};
+use serde_json::Value as JsonValue;
+use tracing::info;
+
+use super::lines;
+use super::pending_upserts::PendingUpserts;
+
+/// Maximum number of ids looked up by a single search request.
+const MAX_IDS_PER_LOOKUP: usize = 1_000;
+
+/// Outcome of [`prepare_upsert`].
+pub(crate) struct PreparedUpsert {
+    /// Documents without any previous version, as NDJSON, to ingest right away.
+    pub new_docs: Bytes,
+    /// Ids of the merged documents replacing previous versions, pending in [`PendingUpserts`]
+    /// until the splits the delete task applies to are closed.
+    pub replacing_ids: Vec<String>,
+    /// Number of documents merged into the pending documents of previous upserts.
+    pub num_merged_into_pending_docs: usize,
+}
+
+/// Documents of an upsert request, merged by id and kept in order of first appearance.
+#[derive(Debug, Default)]
+struct UpsertDocs {
+    ids: Vec<String>,
+    docs: HashMap<String, JsonObject>,
+}
+
+impl UpsertDocs {
+    fn parse(body: &Bytes, id_field: &str) -> Result<Self, IngestServiceError> {
+        let mut upsert_docs = UpsertDocs::default();
+
+        for (line_number, line) in lines(body).enumerate() {
+            let doc: JsonObject = serde_json::from_slice(line).map_err(|error| {
+                IngestServiceError::BadRequest(format!(
+                    "failed to parse document #{line_number} for upsert: {error}"
+                ))
+            })?;
+            let Some(id) = doc.get(id_field).and_then(id_from_json_value) else {
+                return Err(IngestServiceError::BadRequest(format!(
+                    "document #{line_number} is missing a string or numeric value for upsert id \
+                     field `{id_field}`"
+                )));
+            };
+            upsert_docs.upsert(id, doc);
+        }
+        Ok(upsert_docs)
+    }
+
+    fn upsert(&mut self, id: String, doc: JsonObject) {
+        if let Some(existing_doc) = self.docs.get_mut(&id) {
+            existing_doc.extend(doc);
+        } else {
+            self.ids.push(id.clone());
+            self.docs.insert(id, doc);
+        }
+    }
+
+    /// Merges the documents of the request into the currently indexed versions.
+    fn merge_into(&mut self, id: &str, mut indexed_doc: JsonObject) {
+        if let Some(doc) = self.docs.remove(id) {
+            indexed_doc.extend(doc);
+            self.docs.insert(id.to_string(), indexed_doc);
+        }
+    }
+
+    /// Removes the documents with the given ids from the request and returns them, in order.
+    fn split_off(&mut self, ids: &BTreeSet<String>) -> Vec<(String, JsonObject)> {
+        let mut split_docs = Vec::with_capacity(ids.len());
+
+        self.ids.retain(|id| {
+            if !ids.contains(id) {
+                return true;
+            }
+            if let Some(doc) = self.docs.remove(id) {
+                split_docs.push((id.clone(), doc));
+            }
+            false
+        });
+        split_docs
+    }
+
+    fn into_ndjson(mut self) -> Bytes {
+        let mut ndjson = Vec::new();
+
+        for id in &self.ids {
+            if let Some(doc) = self.docs.remove(id) {
+                // Serializing a JSON object cannot fail.
+                serde_json::to_writer(&mut ndjson, &doc).expect("JSON object should serialize");
+                ndjson.push(b'\n');
+            }
+        }
+        Bytes::from(ndjson)
+    }
+}
+
+fn id_from_json_value(json_value: &JsonValue) -> Option<String> {
+    match json_value {
+        JsonValue::String(id) => Some(id.clone()),
+        JsonValue::Number(id) => Some(id.to_string()),
+        _ => None,
+    }
+}
+
+fn ids_query_ast(id_field: &str, ids: impl IntoIterator<Item = String>) -> String {
+    let terms_per_field = HashMap::from([(id_field.to_string(), ids.into_iter().collect())]);
+    let query_ast: QueryAst = TermSetQuery { terms_per_field }.into();
+    serde_json::to_string(&query_ast).expect("query AST should be JSON serializable")
+}
+
+fn search_error_to_ingest_error(error: SearchError) -> IngestServiceError {
+    match error {
+        SearchError::InvalidArgument(message) | SearchError::InvalidQuery(message) => {
+            IngestServiceError::BadRequest(format!(
+                "failed to look up documents to upsert: {message}"
+            ))
+        }
+        SearchError::Forbidden(message) => IngestServiceError::Forbidden(message),
+        SearchError::Unavailable(message) | SearchError::Timeout(message) => {
+            IngestServiceError::Unavailable(message)
+        }
+        _ => {
+            IngestServiceError::Internal(format!("failed to look up documents to upsert: {error}"))
+        }
+    }
+}
+
+/// Merges the documents of an ingest request into the pending or indexed documents sharing the
+/// same value for `id_field`, and schedules the deletion of the previous versions.
+///
+/// The merged documents replacing previous versions are added to `pending_upserts`: callers must
+/// ingest them with [`PendingUpserts::take`] once the commit timeout of the index elapses, so that
+/// they are not indexed into a split the delete task applies to.
+pub(crate) async fn prepare_upsert(
+    index_metadata: &IndexMetadata,
+    id_field: &str,
+    body: &Bytes,
+    search_service: &dyn SearchService,
+    metastore: &MetastoreServiceClient,
+    pending_upserts: &PendingUpserts,
+) -> Result<PreparedUpsert, IngestServiceError> {
+    let mut upsert_docs = UpsertDocs::parse(body, id_field)?;
+    let num_merged_into_pending_docs = pending_upserts.merge_into_pending(
+        &index_metadata.index_uid,
+        &mut upsert_docs.ids,
+        &mut upsert_docs.docs,
+    );
+    let mut indexed_ids: BTreeSet<String> = BTreeSet::new();
+
+    let ids = upsert_docs.ids.clone();
+
+    for ids_chunk in ids.chunks(MAX_IDS_PER_LOOKUP) {
+        let search_request = SearchRequest {
+            index_id_patterns: vec![index_metadata.index_id().to_string()],
+            query_ast: ids_query_ast(id_field, ids_chunk.iter().cloned()),
+            max_hits: ids_chunk.len() as u64,
+            ..Default::default()
+        };
+        let search_response = search_service
+            .root_search(search_request)
+            .await
+            .map_err(search_error_to_ingest_error)?;
+
+        for hit in search_response.hits {
+            let indexed_doc: JsonObject = serde_json::from_str(&hit.json).map_err(|error| {
+                IngestServiceError::Internal(format!("failed to parse indexed document: {error}"))
+            })?;
+            let Some(id) = indexed_doc.get(id_field).and_then(id_from_json_value) else {
+                continue;
+            };
+            // If previous upserts left duplicates behind, the first one wins.
+            if indexed_ids.insert(id.clone()) {
+                upsert_docs.merge_into(&id, indexed_doc);
+            }
+        }
+    }
+    let replacing_docs = upsert_docs.split_off(&indexed_ids);
+    let replacing_ids: Vec<String> = replacing_docs.iter().map(|(id, _)| id.clone()).collect();
+
+    if !indexed_ids.is_empty() {
+        let num_indexed_ids = indexed_ids.len();
+        let delete_query = DeleteQuery {
+            index_uid: Some(index_metadata.index_uid.clone()),
+            start_timestamp: None,
+            end_timestamp: None,
+            query_ast: ids_query_ast(id_field, indexed_ids),
+        };
+        let delete_task = metastore
+            .create_delete_task(delete_query)
+            .await
+            .map_err(|error| {
+                IngestServiceError::Internal(format!(
+                    "failed to delete previous versions of upserted documents: {error}"
+                ))
+            })?;
+        info!(
+            index_id = index_metadata.index_id(),
+            opstamp = delete_task.opstamp,
+            num_docs = num_indexed_ids,
+            "upsert-replace-docs"
+        );
+        pending_upserts.insert(&index_metadata.index_uid, replacing_docs);
+    }
+    Ok(PreparedUpsert {
+        new_docs: upsert_docs.into_ndjson(),
+        replacing_ids,
+        num_merged_into_pending_docs,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, SocketAddr};
+    use std::sync::Arc;
+
+    use quickwit_config::SearcherConfig;
+    use quickwit_indexing::TestSandbox;
+    use quickwit_metastore::{
+        IndexMetadataResponseExt, ListSplitsRequestExt, MetastoreServiceStreamSplitsExt,
+    };
+    use quickwit_proto::metastore::{
+        IndexMetadataRequest, ListDeleteTasksRequest, ListSplitsRequest,
+    };
+    use quickwit_search::{
+        ClusterClient, SearchJobPlacer, SearchServiceClient, SearchServiceImpl, SearcherContext,
+        SearcherPool, single_node_search,
+    };
+    use serde_json::json;
+
+    use super::*;
+
+    fn local_search_service(test_sandbox: &TestSandbox) -> Arc<dyn SearchService> {
+        let socket_addr = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 7280);
+        let searcher_pool = SearcherPool::default();
+        let searcher_context = Arc::new(SearcherContext::new_without_invoker(
+            SearcherConfig::default(),
+            None,
+        ));
+        let search_service = Arc::new(SearchServiceImpl::new(
+            test_sandbox.metastore(),
+            test_sandbox.storage_resolver(),
+            ClusterClient::new(SearchJobPlacer::new(searcher_pool.clone())),
+            searcher_context,
+        ));
+        let search_service_client =
+            SearchServiceClient::from_service(search_service.clone(), socket_addr);
+        searcher_pool.insert(socket_addr, search_service_client);
+        search_service
+    }
+
+    fn parse_ndjson(ndjson: &Bytes) -> Vec<JsonValue> {
+        lines(ndjson)
+            .map(|line| serde_json::from_slice(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_upsert_docs_merges_docs_of_same_request() {
+        let body = Bytes::from_static(
+            br#"{"id": "doc-1", "status": "pending", "count": 1}
+                {"id": 2, "status": "pending"}
+                {"id": "doc-1", "status": "done"}"#,
+        );
+        let upsert_docs = UpsertDocs::parse(&body, "id").unwrap();
+        assert_eq!(upsert_docs.ids, ["doc-1", "2"]);
+
+        let docs = parse_ndjson(&upsert_docs.into_ndjson());
+        assert_eq!(
+            docs,
+            [
+                json!({"id": "doc-1", "status": "done", "count": 1}),
+                json!({"id": 2, "status": "pending"}),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_upsert_docs_rejects_docs_without_id() {
+        let body = Bytes::from_static(
+            br#"{"id": "doc-1"}
+                {"id": ["doc-2"]}"#,
+        );
+        let error = UpsertDocs::parse(&body, "id").unwrap_err();
+        assert!(matches!(error, IngestServiceError::BadRequest(_)));
+        assert!(error.to_string().contains("document #1"));
+
+        let body = Bytes::from_static(b"not-json");
+        let error = UpsertDocs::parse(&body, "id").unwrap_err();
+        assert!(matches!(error, IngestServiceError::BadRequest(_)));
+    }
+
+    #[tokio::test]
+    async fn test_upsert_after_insert_produces_single_merged_doc() {
+        let index_id = "test-upsert";
+        let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: id
+                type: text
+                tokenizer: raw
+                fast: true
+              - name: status
+                type: text
+                tokenizer: raw
+              - name: count
+                type: u64
+        "#;
+        let indexing_settings_yaml = "commit_timeout_secs: 1";
+        let test_sandbox = TestSandbox::create(
+            index_id,
+            doc_mapping_yaml,
+            indexing_settings_yaml,
+            &["status"],
+        )
+        .await
+        .unwrap();
+        test_sandbox
+            .add_documents(vec![
+                json!({"id": "doc-1", "status": "pending", "count": 1}),
+            ])
+            .await
+            .unwrap();
+        let metastore = test_sandbox.metastore();
+        let index_metadata = metastore
+            .index_metadata(IndexMetadataRequest::for_index_id(index_id.to_string()))
+            .await
+            .unwrap()
+            .deserialize_index_metadata()
+            .unwrap();
+        let search_service = local_search_service(&test_sandbox);
+
+        let body = Bytes::from_static(
+            br#"{"id": "doc-1", "status": "done"}
+                {"id": "doc-2", "status": "pending", "count": 1}"#,
+        );
+        let pending_upserts = PendingUpserts::default();
+        let prepared_upsert = prepare_upsert(
+            &index_metadata,
+            "id",
+            &body,
+            &*search_service,
+            &metastore,
+            &pending_upserts,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            parse_ndjson(&prepared_upsert.new_docs),
+            [json!({"id": "doc-2", "status": "pending", "count": 1})]
+        );
+        assert_eq!(prepared_upsert.replacing_ids, ["doc-1"]);
+        assert_eq!(prepared_upsert.num_merged_into_pending_docs, 0);
+
+        // An upsert of `doc-1` before the merged version is ingested merges into it.
+        let body = Bytes::from_static(br#"{"id": "doc-1", "count": 2}"#);
+        let next_prepared_upsert = prepare_upsert(
+            &index_metadata,
+            "id",
+            &body,
+            &*search_service,
+            &metastore,
+            &pending_upserts,
+        )
+        .await
+        .unwrap();
+        assert!(next_prepared_upsert.new_docs.is_empty());
+        assert!(next_prepared_upsert.replacing_ids.is_empty());
+        assert_eq!(next_prepared_upsert.num_merged_into_pending_docs, 1);
+
+        let upserted_docs = parse_ndjson(
+            &pending_upserts.take(&index_metadata.index_uid, &prepared_upsert.replacing_ids),
+        );
+        assert_eq!(
+            upserted_docs,
+            [json!({"id": "doc-1", "status": "done", "count": 2})]
+        );
+        assert!(
+            pending_upserts
+                .take(&index_metadata.index_uid, &prepared_upsert.replacing_ids)
+                .is_empty()
+        );
+
+        // The previous version of `doc-1`, and only it, is scheduled for deletion.
+        let delete_tasks = metastore
+            .list_delete_tasks(ListDeleteTasksRequest::new(test_sandbox.index_uid(), 0))
+            .await
+            .unwrap()
+            .delete_tasks;
+        assert_eq!(delete_tasks.len(), 1);
+        let delete_opstamp = delete_tasks[0].opstamp;
+        let delete_query = delete_tasks[0].delete_query.clone().unwrap();
+        let search_request = SearchRequest::try_from(delete_query).unwrap();
+        let search_response = single_node_search(
+            search_request,
+            metastore.clone(),
+            test_sandbox.storage_resolver(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(search_response.num_hits, 1);
+        let deleted_doc: JsonValue = serde_json::from_str(&search_response.hits[0].json).unwrap();
+        assert_eq!(deleted_doc["status"], "pending");
+
+        // The merged version lands in a new split that the delete task does not apply to, so
+        // once the janitor runs the task, `doc-1` only exists in its merged version.
+        test_sandbox.add_documents(upserted_docs).await.unwrap();
+
+        let list_splits_request =
+            ListSplitsRequest::try_from_index_uid(test_sandbox.index_uid()).unwrap();
+        let mut splits = metastore
+            .list_splits(list_splits_request)
+            .await
+            .unwrap()
+            .collect_splits()
+            .await
+            .unwrap();
+        assert_eq!(splits.len(), 2);
+        splits.sort_by_key(|split| split.split_metadata.delete_opstamp);
+        assert!(splits[0].split_metadata.delete_opstamp < delete_opstamp);
+        assert_eq!(splits[1].split_metadata.delete_opstamp, delete_opstamp);
+
+        test_sandbox.assert_quit().await;
+    }
+}
@@ -482,6 +482,11 @@ mod tests {
                     failed_splits: Vec::new(),
                     num_successful_splits: 1,
                     resource_stats: None,
+                    response_headers: HashMap::new(),
+                    cost_estimate: None,
+                    truncation_reason: None,
+                    resolution: None,
+                    searched_index_ids: Vec::new(),
                 })
             });
         let mock_search_service = Arc::new(mock_search_service);
@@ -516,6 +521,11 @@ mod tests {
                     failed_splits: Vec::new(),
                     num_successful_splits: 1,
                     resource_stats: None,
+                    response_headers: HashMap::new(),
+                    cost_estimate: None,
+                    truncation_reason: None,
+                    resolution: None,
+                    searched_index_ids: Vec::new(),
                 })
             });
         let mock_search_service = Arc::new(mock_search_service);
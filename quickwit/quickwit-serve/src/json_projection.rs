@@ -0,0 +1,315 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+
+use http_body_util::BodyExt;
+use serde::Deserialize;
+use serde_json::{Map as JsonMap, Value as JsonValue};
+use warp::hyper::{StatusCode, http};
+use warp::{Filter, Rejection, Reply};
+
+use crate::BodyFormat;
+use crate::format::extract_format_from_qs;
+use crate::rest_api_response::{RestApiError, RestApiResponse};
+
+/// Selects the subtrees of a JSON value designated by a set of paths.
+#[derive(Debug, Default, Eq, PartialEq)]
+pub(crate) struct FieldsProjection {
+    /// Whether the whole value is selected.
+    select_all: bool,
+    /// Projections of the children of the value, by key. The `*` key matches every key.
+    children: HashMap<String, FieldsProjection>,
+}
+
+impl FieldsProjection {
+    /// Parses a comma-separated list of JSONPath-style paths, such as `$.build.version`. Paths
+    /// are made of keys separated by dots and may start with `$.`. The `*` key matches any key,
+    /// and paths go through arrays, selecting the fields of each of their elements.
+    pub fn parse(fields: &str) -> Result<FieldsProjection, String> {
+        let mut projection = FieldsProjection::default();
+
+        for path in fields.split(',') {
+            let path = path.trim();
+            let relative_path = path.strip_prefix("$.").unwrap_or(path);
+
+            if relative_path.is_empty() {
+                return Err(format!("invalid `fields` path `{path}`: path is empty"));
+            }
+            let mut node = &mut projection;
+
+            for key in relative_path.split('.') {
+                if key.is_empty() {
+                    return Err(format!("invalid `fields` path `{path}`: key is empty"));
+                }
+                if key.contains(['[', ']', '$', '?', '@']) || key.contains(char::is_whitespace) {
+                    return Err(format!(
+                        "invalid `fields` path `{path}`: key `{key}` contains unsupported \
+                         characters, only dot-separated keys are supported"
+                    ));
+                }
+                node = node.children.entry(key.to_string()).or_default();
+            }
+            node.select_all = true;
+        }
+        Ok(projection)
+    }
+
+    /// Returns the selected subtrees of `value`, or `None` if no path matches.
+    fn project(&self, value: JsonValue) -> Option<JsonValue> {
+        if self.select_all {
+            return Some(value);
+        }
+        match value {
+            JsonValue::Object(object) => {
+                let mut projected_object = JsonMap::new();
+
+                for (key, child_value) in object {
+                    let Some(child_projection) =
+                        self.children.get(&key).or_else(|| self.children.get("*"))
+                    else {
+                        continue;
+                    };
+                    if let Some(projected_child) = child_projection.project(child_value) {
+                        projected_object.insert(key, projected_child);
+                    }
+                }
+                if projected_object.is_empty() {
+                    return None;
+                }
+                Some(JsonValue::Object(projected_object))
+            }
+            JsonValue::Array(values) => {
+                let projected_values: Vec<JsonValue> = values
+                    .into_iter()
+                    .filter_map(|value| self.project(value))
+                    .collect();
+                if projected_values.is_empty() {
+                    return None;
+                }
+                Some(JsonValue::Array(projected_values))
+            }
+            _ => None,
+        }
+    }
+}
+
+fn is_json_response(response: &warp::reply::Response) -> bool {
+    response
+        .headers()
+        .get(http::header::CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .map(|content_type| content_type.starts_with("application/json"))
+        .unwrap_or(false)
+}
+
+/// Applies the projection to the successful JSON responses. The other responses, errors
+/// included, are returned unchanged.
+pub(crate) async fn project_response(
+    response: warp::reply::Response,
+    projection: &FieldsProjection,
+    body_format: BodyFormat,
+) -> warp::reply::Response {
+    if !response.status().is_success() || !is_json_response(&response) {
+        return response;
+    }
+    let (mut parts, body) = response.into_parts();
+    let body_bytes = match body.collect().await {
+        Ok(collected_body) => collected_body.to_bytes(),
+        Err(error) => {
+            let error_message = format!("failed to read response body: {error}");
+            return error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                error_message,
+                body_format,
+            );
+        }
+    };
+    let Ok(body_json) = serde_json::from_slice::<JsonValue>(&body_bytes) else {
+        return warp::reply::Response::from_parts(parts, Vec::from(body_bytes).into());
+    };
+    let projected_json = projection
+        .project(body_json)
+        .unwrap_or_else(|| JsonValue::Object(JsonMap::new()));
+    // Compact JSON never contains line breaks, so this preserves the format of the response.
+    let is_pretty = body_bytes.contains(&b'\n');
+
+    let projected_body_res = if is_pretty {
+        serde_json::to_vec_pretty(&projected_json)
+    } else {
+        serde_json::to_vec(&projected_json)
+    };
+    let projected_body = match projected_body_res {
+        Ok(projected_body) => projected_body,
+        Err(error) => {
+            let error_message = format!("failed to serialize projected response: {error}");
+            return error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                error_message,
+                body_format,
+            );
+        }
+    };
+    parts.headers.remove(http::header::CONTENT_LENGTH);
+    warp::reply::Response::from_parts(parts, projected_body.into())
+}
+
+fn error_response(
+    status_code: StatusCode,
+    message: String,
+    body_format: BodyFormat,
+) -> warp::reply::Response {
+    let rest_api_error = RestApiError {
+        status_code,
+        message,
+    };
+    RestApiResponse::new::<(), _>(&Err(rest_api_error), status_code, body_format).into_response()
+}
+
+#[derive(Deserialize)]
+struct FieldsQueryString {
+    #[serde(default)]
+    fields: Option<String>,
+}
+
+/// Trims the successful JSON responses of the route down to the subtrees listed in the `fields`
+/// query parameter, so that clients can fetch only the part of a response they need. Routes opt
+/// in by being wrapped with this filter, so that the parameter is left to the routes defining a
+/// `fields` parameter of their own. Invalid paths are rejected with a 400 status code.
+pub(crate) fn with_json_projection<F, R>(
+    route: F,
+) -> impl Filter<Extract = (warp::reply::Response,), Error = Rejection> + Clone
+where
+    F: Filter<Extract = (R,), Error = Rejection> + Clone + Send + Sync + 'static,
+    R: Reply + 'static,
+{
+    route
+        .and(warp::query::<FieldsQueryString>())
+        .and(extract_format_from_qs())
+        .then(
+            |reply: R, fields_query_string: FieldsQueryString, body_format: BodyFormat| async move {
+                let response = reply.into_response();
+
+                let Some(fields) = fields_query_string.fields else {
+                    return response;
+                };
+                match FieldsProjection::parse(&fields) {
+                    Ok(projection) => project_response(response, &projection, body_format).await,
+                    Err(error_message) => {
+                        error_response(StatusCode::BAD_REQUEST, error_message, body_format)
+                    }
+                }
+            },
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use quickwit_config::NodeConfig;
+    use serde_json::json;
+
+    use super::*;
+    use crate::node_info_handler::node_info_handler;
+    use crate::{BuildInfo, RuntimeInfo};
+
+    async fn get_node_info(path: &str) -> (StatusCode, JsonValue) {
+        let routes = node_info_handler(
+            BuildInfo::get(),
+            RuntimeInfo::get(),
+            Arc::new(NodeConfig::for_test()),
+        );
+        let response = warp::test::request().path(path).reply(&routes).await;
+        let status_code = response.status();
+        let body_json: JsonValue = serde_json::from_slice(response.body()).unwrap();
+        (status_code, body_json)
+    }
+
+    #[tokio::test]
+    async fn test_json_projection_node_info() {
+        let (status_code, body_json) = get_node_info("/version?fields=$.build.version").await;
+        assert_eq!(status_code, StatusCode::OK);
+        let expected_body_json = json!({
+            "build": {
+                "version": BuildInfo::get().version,
+            }
+        });
+        assert_eq!(body_json, expected_body_json);
+
+        let (status_code, body_json) =
+            get_node_info("/version?fields=build.commit_hash,runtime.num_cpus").await;
+        assert_eq!(status_code, StatusCode::OK);
+        let expected_body_json = json!({
+            "build": {
+                "commit_hash": BuildInfo::get().commit_hash,
+            },
+            "runtime": {
+                "num_cpus": RuntimeInfo::get().num_cpus,
+            }
+        });
+        assert_eq!(body_json, expected_body_json);
+    }
+
+    #[tokio::test]
+    async fn test_json_projection_invalid_path() {
+        let (status_code, body_json) = get_node_info("/version?fields=build..version").await;
+        assert_eq!(status_code, StatusCode::BAD_REQUEST);
+        let error_message = body_json["message"].as_str().unwrap();
+        assert!(error_message.contains("build..version"), "{error_message}");
+
+        let (status_code, _body_json) = get_node_info("/version?fields=hits%5B0%5D").await;
+        assert_eq!(status_code, StatusCode::BAD_REQUEST);
+
+        let routes = node_info_handler(
+            BuildInfo::get(),
+            RuntimeInfo::get(),
+            Arc::new(NodeConfig::for_test()),
+        );
+        let response = warp::test::request()
+            .path("/version?fields=build..version&format=yaml")
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(response.headers()["content-type"], "application/yaml");
+    }
+
+    #[test]
+    fn test_fields_projection_project() {
+        let value = json!({
+            "num_hits": 2,
+            "hits": [
+                {"title": "foo", "body": "bar", "meta": {"lang": "en", "size": 3}},
+                {"title": "baz", "meta": {"lang": "fr", "size": 4}},
+            ],
+            "errors": [],
+        });
+        let projection = FieldsProjection::parse("num_hits, hits.title,hits.*.lang").unwrap();
+        let expected_value = json!({
+            "num_hits": 2,
+            "hits": [
+                {"title": "foo", "meta": {"lang": "en"}},
+                {"title": "baz", "meta": {"lang": "fr"}},
+            ],
+        });
+        assert_eq!(projection.project(value.clone()), Some(expected_value));
+
+        let projection = FieldsProjection::parse("missing.field").unwrap();
+        assert_eq!(projection.project(value), None);
+
+        FieldsProjection::parse("").unwrap_err();
+        FieldsProjection::parse("$.").unwrap_err();
+        FieldsProjection::parse("hits.").unwrap_err();
+    }
+}
@@ -14,8 +14,11 @@
 
 #![recursion_limit = "256"]
 
+mod access_log;
 mod build_info;
 mod cluster_api;
+mod compression;
+mod config_validation;
 #[cfg(feature = "datafusion")]
 mod datafusion_api;
 mod decompression;
@@ -25,21 +28,41 @@ mod elasticsearch_api;
 mod format;
 mod grpc;
 mod health_check_api;
+mod idle_connection;
 mod index_api;
 mod indexing_api;
 mod ingest_api;
 mod jaeger_api;
+mod json_projection;
+mod live_rest_config;
 mod load_shield;
+mod maintenance_mode;
 mod metastore;
+mod metastore_api;
 mod metrics;
 mod metrics_api;
+mod node_decommission;
 mod node_info_handler;
+mod node_readiness;
+mod node_stats;
 mod openapi;
+mod openapi_handler;
 mod otlp_api;
+mod pipelining;
+mod query_history;
+mod query_params;
 mod rate_modulator;
+mod recent_errors;
+mod request_scope;
 mod rest;
 mod rest_api_request_span;
 mod rest_api_response;
+mod rest_rate_limit;
+mod rest_request_filter;
+mod result_bytes;
+mod retry_hints;
+mod root_route;
+mod route_not_found;
 mod search_api;
 pub(crate) mod simple_list;
 pub mod tcp_listener;
@@ -75,10 +98,7 @@ use quickwit_common::tower::{
 use quickwit_common::uri::Uri;
 use quickwit_common::{get_bool_from_env, spawn_named_task};
 use quickwit_compaction::planner::CompactionPlanner;
-use quickwit_compaction::{
-    CompactorSupervisor, notify_compactor_decommission, start_compactor_service,
-    wait_for_compactor_decommission,
-};
+use quickwit_compaction::{CompactorSupervisor, start_compactor_service};
 use quickwit_config::service::QuickwitService;
 use quickwit_config::{ClusterConfig, IngestApiConfig, NodeConfig, disable_ingest_v1};
 use quickwit_control_plane::control_plane::{ControlPlane, ControlPlaneEventSubscriber};
@@ -89,10 +109,9 @@ use quickwit_indexing::models::ShardPositionsService;
 use quickwit_indexing::{IndexingSplitCache, start_indexing_service};
 use quickwit_ingest::{
     GetMemoryCapacity, IngestRequest, IngestRouter, IngestServiceClient, Ingester, IngesterPool,
-    IngesterPoolEntry, LocalShardsUpdate, get_idle_shard_timeout, notify_ingester_decommission,
+    IngesterPoolEntry, LocalShardsUpdate, get_idle_shard_timeout,
     setup_ingester_capacity_update_listener, setup_local_shards_update_listener,
-    start_ingest_api_service, try_get_ingester_status, wait_for_ingester_decommission,
-    wait_for_ingester_status,
+    start_ingest_api_service, try_get_ingester_status, wait_for_ingester_status,
 };
 use quickwit_jaeger::JaegerService;
 use quickwit_janitor::{JanitorService, start_janitor_service};
@@ -116,8 +135,8 @@ use quickwit_proto::metastore::{
 use quickwit_proto::search::ReportSplitsRequest;
 use quickwit_proto::types::NodeId;
 use quickwit_search::{
-    SearchJobPlacer, SearchService, SearchServiceClient, SearcherContext, SearcherPool,
-    create_search_client_from_channel, start_searcher_service,
+    ClusterClient, SearchJobPlacer, SearchService, SearchServiceClient, SearcherContext,
+    SearcherPool, create_search_client_from_channel, start_searcher_service,
 };
 use quickwit_storage::{SearchSplitCache, StorageResolver};
 pub use quickwit_telemetry_exporters::{EnvFilterReloadFn, do_nothing_env_filter_reload_fn};
@@ -125,18 +144,23 @@ pub use quickwit_transport::reload_tls_cert;
 use tcp_listener::TcpListenerResolver;
 use tokio::sync::oneshot;
 use tonic::codec::CompressionEncoding;
-use tonic_health::ServingStatus;
-use tonic_health::server::HealthReporter;
 use tower::ServiceBuilder;
 use tower::timeout::Timeout;
 use tracing::{debug, error, info, warn};
 use warp::{Filter, Rejection};
 
 pub use crate::build_info::{BuildInfo, RuntimeInfo};
+use crate::decompression::get_ingest_load_shield;
 pub use crate::index_api::{ListSplitsQueryParams, ListSplitsResponse};
+use crate::ingest_api::IndexAllowList;
 pub use crate::ingest_api::{RestIngestResponse, RestParseFailure};
+use crate::live_rest_config::LiveRestConfig;
+use crate::maintenance_mode::MaintenanceMode;
 use crate::metastore::start_metastore_service_if_needed;
 use crate::metrics::CIRCUIT_BREAK_TOTAL;
+pub use crate::metrics::set_request_duration_buckets;
+use crate::node_decommission::{NodeDecommission, drain_node};
+use crate::node_readiness::NodeReadiness;
 use crate::rate_modulator::RateModulator;
 #[cfg(test)]
 use crate::rest::recover_fn;
@@ -185,6 +209,7 @@ struct QuickwitServices {
     pub control_plane_client: ControlPlaneServiceClient,
     pub index_manager: IndexManager,
     pub indexing_service_opt: Option<Mailbox<IndexingService>>,
+    pub merge_scheduler_service_opt: Option<Mailbox<MergeSchedulerService>>,
     // Ingest v1
     pub ingest_service: IngestServiceClient,
     // Ingest v2
@@ -193,7 +218,7 @@ struct QuickwitServices {
     ingester_opt: Option<Ingester>,
 
     pub compaction_service_client_opt: Option<CompactionPlannerServiceClient>,
-    pub _compactor_supervisor_opt: Option<Mailbox<CompactorSupervisor>>,
+    pub compactor_supervisor_opt: Option<Mailbox<CompactorSupervisor>>,
     pub janitor_service_opt: Option<Mailbox<JanitorService>>,
     pub jaeger_service_opt: Option<JaegerService>,
     pub otlp_logs_service_opt: Option<OtlpGrpcLogsService>,
@@ -203,9 +228,24 @@ struct QuickwitServices {
     /// It is only used to serve the rest API calls and will only execute
     /// the root requests.
     pub search_service: Arc<dyn SearchService>,
+    /// Client of the searchers of the cluster, used to read the scroll contexts they store.
+    pub search_cluster_client: ClusterClient,
 
     pub env_filter_reload_fn: EnvFilterReloadFn,
 
+    /// Node-local maintenance mode, which blocks ingest and search on the REST API.
+    pub maintenance_mode: MaintenanceMode,
+    /// Readiness of the node, reported by both the REST and gRPC health checks.
+    pub node_readiness: NodeReadiness,
+    /// Graceful decommission of the node, requested through the REST API.
+    pub node_decommission: NodeDecommission,
+
+    /// CORS origins and extra headers of the REST API, updatable at runtime.
+    pub live_rest_config: LiveRestConfig,
+
+    /// Indexes the REST ingest endpoints may target, set with `ingest_api.index_allow_list`.
+    pub index_allow_list_opt: Option<IndexAllowList>,
+
     /// Generic DataFusion session builder (present if searcher role is active
     /// and the `datafusion` feature + `QW_ENABLE_DATAFUSION_ENDPOINT` env var
     /// are both enabled).
@@ -516,25 +556,8 @@ async fn shutdown_signal_handler(
     cluster: Cluster,
 ) -> HashMap<String, ActorExitStatus> {
     shutdown_signal.await;
-    if let Err(error) = notify_ingester_decommission(ingester_opt.as_ref()).await {
-        error!("failed to initiate ingester decommission: {:?}", error);
-    }
-    let compactor_status_rx_opt = notify_compactor_decommission(compactor_supervisor_opt.as_ref())
-        .await
-        .unwrap_or_else(|error| {
-            error!("failed to initiate compactor decommission: {:?}", error);
-            None
-        });
-    let (ingester_result, compactor_result) = tokio::join!(
-        wait_for_ingester_decommission(ingester_opt.as_ref(), Duration::from_secs(300)),
-        wait_for_compactor_decommission(compactor_status_rx_opt, Duration::from_secs(300)),
-    );
-    if let Err(error) = ingester_result {
-        error!("failed to decommission ingester gracefully: {:?}", error);
-    }
-    if let Err(error) = compactor_result {
-        error!("failed to decommission compactor gracefully: {:?}", error);
-    }
+    drain_node(ingester_opt.as_ref(), compactor_supervisor_opt.as_ref()).await;
+
     let actor_exit_statuses = universe.quit().await;
 
     if grpc_shutdown_trigger_tx.send(()).is_err() {
@@ -563,6 +586,14 @@ pub async fn serve_quickwit(
     shutdown_signal: BoxFutureInfaillible<()>,
     env_filter_reload_fn: EnvFilterReloadFn,
 ) -> anyhow::Result<HashMap<String, ActorExitStatus>> {
+    let index_allow_list_opt = node_config
+        .ingest_api_config
+        .index_allow_list
+        .as_deref()
+        .map(IndexAllowList::try_new)
+        .transpose()?;
+    get_ingest_load_shield().set_backpressure_config(node_config.ingest_api_config.backpressure);
+
     let cluster = start_cluster_service(&node_config)
         .await
         .context("failed to start cluster service")?;
@@ -623,15 +654,16 @@ pub async fn serve_quickwit(
 
     let indexing_split_cache = indexing_split_cache_for_config(&node_config).await?;
 
+    // if standalone compactors is enabled, indexing pipelines don't perform any merges.
+    // if standalone compactors is disabled, indexing pipelines perform all merges as before.
+    let merge_scheduler_mailbox_opt = if node_config.is_service_enabled(QuickwitService::Indexer)
+        && !node_config.enable_standalone_compactors
+    {
+        Some(spawn_merge_scheduler_service(&universe, &node_config))
+    } else {
+        None
+    };
     let indexing_service_opt = if node_config.is_service_enabled(QuickwitService::Indexer) {
-        // if standalone compactors is enabled, indexing pipelines don't perform any merges.
-        // if standalone compactors is disabled, indexing pipelines perform all merges as before.
-        let merge_scheduler_mailbox_opt = if !node_config.enable_standalone_compactors {
-            Some(spawn_merge_scheduler_service(&universe, &node_config))
-        } else {
-            None
-        };
-
         let split_cache = indexing_split_cache.clone();
         let indexing_service = start_indexing_service(
             &universe,
@@ -642,7 +674,7 @@ pub async fn serve_quickwit(
             ingester_pool.clone(),
             storage_resolver.clone(),
             event_broker.clone(),
-            merge_scheduler_mailbox_opt,
+            merge_scheduler_mailbox_opt.clone(),
             split_cache,
         )
         .await
@@ -778,12 +810,18 @@ pub async fn serve_quickwit(
         "configured search metastore client"
     );
 
+    let in_flight_leaf_searches = searcher_context.in_flight_leaf_searches.clone();
+
     let (search_job_placer, search_service, searcher_pool) = setup_searcher(
         &node_config,
         cluster.change_stream(),
         // search remains available without a control plane because not all
         // metastore RPCs are proxied
         search_metastore_client.clone(),
+        // the searches preferring the freshest reads bypass the read replica
+        read_replica_metastore_client_opt
+            .as_ref()
+            .map(|_| primary_metastore_through_control_plane.clone()),
         storage_resolver.clone(),
         searcher_context,
     )
@@ -826,6 +864,7 @@ pub async fn serve_quickwit(
         } else {
             None
         };
+    let search_cluster_client = ClusterClient::new(search_job_placer.clone());
 
     let janitor_service_opt = if node_config.is_service_enabled(QuickwitService::Janitor) {
         let janitor_service = start_janitor_service(
@@ -906,6 +945,19 @@ pub async fn serve_quickwit(
         None
     };
 
+    let live_rest_config = LiveRestConfig::new(
+        node_config.rest_config.cors_allow_origins.clone(),
+        &node_config.rest_config.cors_policies,
+        node_config.rest_config.extra_headers.clone(),
+    )?;
+    let (health_reporter, health_service) = tonic_health::server::health_reporter();
+    let node_readiness = NodeReadiness::new(cluster.clone(), health_reporter).await;
+    let node_decommission = NodeDecommission::new(
+        node_readiness.clone(),
+        ingester_opt.clone(),
+        compactor_supervisor_opt.clone(),
+        in_flight_leaf_searches,
+    );
     let grpc_listen_addr = node_config.grpc_listen_addr;
     let rest_listen_addr = node_config.rest_config.listen_addr;
     let quickwit_services: Arc<QuickwitServices> = Arc::new(QuickwitServices {
@@ -919,18 +971,25 @@ pub async fn serve_quickwit(
         _report_splits_subscription_handle_opt: report_splits_subscription_handle_opt,
         index_manager,
         indexing_service_opt,
+        merge_scheduler_service_opt: merge_scheduler_mailbox_opt,
         ingest_router_opt: Some(ingest_router),
         ingest_router_service,
         ingest_service,
         ingester_opt: ingester_opt.clone(),
         compaction_service_client_opt,
-        _compactor_supervisor_opt: compactor_supervisor_opt.clone(),
+        compactor_supervisor_opt: compactor_supervisor_opt.clone(),
         janitor_service_opt,
         jaeger_service_opt,
         otlp_logs_service_opt,
         otlp_traces_service_opt,
         search_service,
+        search_cluster_client,
         env_filter_reload_fn,
+        maintenance_mode: MaintenanceMode::default(),
+        node_readiness: node_readiness.clone(),
+        node_decommission,
+        live_rest_config,
+        index_allow_list_opt,
         #[cfg(feature = "datafusion")]
         datafusion_session_builder,
     });
@@ -947,7 +1006,6 @@ pub async fn serve_quickwit(
             debug!("gRPC server shutdown trigger sender was dropped");
         }
     });
-    let (health_reporter, health_service) = tonic_health::server::health_reporter();
     let grpc_server = grpc::start_grpc_server(
         tcp_listener_resolver.resolve(grpc_listen_addr).await?,
         grpc_config,
@@ -1015,13 +1073,12 @@ pub async fn serve_quickwit(
     // Thus readiness task is started once gRPC and REST servers are started.
     spawn_named_task(
         node_readiness_reporting_task(
-            cluster.clone(),
+            node_readiness,
             primary_metastore_through_control_plane,
             read_replica_metastore_client_opt,
             ingester_opt.clone(),
             grpc_readiness_signal_rx,
             rest_readiness_signal_rx,
-            health_reporter,
         ),
         "node_readiness_reporting",
     );
@@ -1323,6 +1380,7 @@ async fn setup_searcher(
     node_config: &NodeConfig,
     cluster_change_stream: ClusterChangeStream,
     metastore: MetastoreServiceClient,
+    primary_metastore_opt: Option<MetastoreServiceClient>,
     storage_resolver: StorageResolver,
     searcher_context: Arc<SearcherContext>,
 ) -> anyhow::Result<(SearchJobPlacer, Arc<dyn SearchService>, SearcherPool)> {
@@ -1331,6 +1389,7 @@ async fn setup_searcher(
 
     let search_service = start_searcher_service(
         metastore,
+        primary_metastore_opt,
         storage_resolver,
         search_job_placer.clone(),
         searcher_context,
@@ -1338,9 +1397,12 @@ async fn setup_searcher(
     .await?;
     let search_service_clone = search_service.clone();
     let max_message_size = node_config.grpc_config.max_message_size;
-    let request_timeout = node_config.searcher_config.request_timeout();
+    // Search requests may override their timeout up to the maximum request timeout.
+    let request_timeout = node_config.searcher_config.max_request_timeout();
+    let request_compression_opt = node_config.searcher_config.request_compression.clone();
     let searcher_change_stream = cluster_change_stream.filter_map(move |cluster_change| {
         let search_service_clone = search_service_clone.clone();
+        let request_compression_opt = request_compression_opt.clone();
         Box::pin(async move {
             match cluster_change {
                 ClusterChange::Add(node) if node.is_searcher() => {
@@ -1363,7 +1425,8 @@ async fn setup_searcher(
                             grpc_addr,
                             timeout_channel,
                             max_message_size,
-                        );
+                        )
+                        .with_request_compression(request_compression_opt);
                         Some(Change::Insert(grpc_addr, search_client))
                     }
                 }
@@ -1574,15 +1637,15 @@ fn with_arg<T: Clone + Send>(arg: T) -> impl Filter<Extract = (T,), Error = Infa
     warp::any().map(move || arg.clone())
 }
 
-/// Reports node readiness to chitchat cluster every 10 seconds (25 ms for tests).
+/// Reports node readiness to the chitchat cluster and the REST and gRPC health checks every 10
+/// seconds (25 ms for tests).
 async fn node_readiness_reporting_task(
-    cluster: Cluster,
+    node_readiness: NodeReadiness,
     primary_metastore: MetastoreServiceClient,
     read_replica_metastore_opt: Option<MetastoreServiceClient>,
     ingester_opt: Option<impl IngesterService>,
     grpc_readiness_signal_rx: oneshot::Receiver<()>,
     rest_readiness_signal_rx: oneshot::Receiver<()>,
-    health_reporter: HealthReporter,
 ) {
     // When a read replica metastore is configured, node readiness follows the replica only.
     // This keeps search/read traffic available if the primary metastore is down. Write-capable
@@ -1595,12 +1658,7 @@ async fn node_readiness_reporting_task(
     info!(metastore_kind, "configured metastore readiness dependency");
 
     let mut node_ready = false;
-    cluster.set_self_node_readiness(node_ready).await;
-    // Set the initial health status to `NotServing` with "" meaning all services, as per
-    // https://github.com/grpc/grpc/blob/master/doc/health-checking.md
-    health_reporter
-        .set_service_status("", ServingStatus::NotServing)
-        .await;
+    node_readiness.set_ready(node_ready).await;
 
     if grpc_readiness_signal_rx.await.is_err() {
         // the gRPC server failed.
@@ -1664,14 +1722,7 @@ async fn node_readiness_reporting_task(
                     "metastore unavailability caused node readiness to decrease"
                 );
             }
-            cluster.set_self_node_readiness(node_ready).await;
-
-            let serving_status = if node_ready {
-                ServingStatus::Serving
-            } else {
-                ServingStatus::NotServing
-            };
-            health_reporter.set_service_status("", serving_status).await;
+            node_readiness.set_ready(node_ready).await;
         }
     }
 }
@@ -1739,6 +1790,7 @@ mod tests {
     use quickwit_search::Job;
     use tokio::sync::watch;
     use tonic::transport::{Channel, Server};
+    use tonic_health::ServingStatus;
     use tonic_health::pb::HealthCheckRequest;
     use tonic_health::pb::health_client::HealthClient;
     use tonic_health::server::health_reporter;
@@ -1857,14 +1909,15 @@ mod tests {
 
         let mut health_client = HealthClient::new(channel);
 
+        let node_readiness = NodeReadiness::new(cluster.clone(), health_reporter).await;
+
         tokio::spawn(node_readiness_reporting_task(
-            cluster.clone(),
+            node_readiness,
             mock_metastore,
             None,
             Some(mock_ingester),
             grpc_readiness_signal_rx,
             rest_readiness_signal_rx,
-            health_reporter,
         ));
         assert!(!cluster.is_self_node_ready().await);
 
@@ -1913,15 +1966,15 @@ mod tests {
         let (grpc_readiness_trigger_tx, grpc_readiness_signal_rx) = oneshot::channel();
         let (rest_readiness_trigger_tx, rest_readiness_signal_rx) = oneshot::channel();
         let (health_reporter, _health_service) = health_reporter();
+        let node_readiness = NodeReadiness::new(cluster.clone(), health_reporter).await;
 
         tokio::spawn(node_readiness_reporting_task(
-            cluster.clone(),
+            node_readiness,
             primary_metastore,
             Some(replica_metastore),
             None::<MockIngesterService>,
             grpc_readiness_signal_rx,
             rest_readiness_signal_rx,
-            health_reporter,
         ));
         grpc_readiness_trigger_tx.send(()).unwrap();
         rest_readiness_trigger_tx.send(()).unwrap();
@@ -2087,6 +2140,7 @@ mod tests {
             &node_config,
             change_stream,
             metastore,
+            None,
             storage_resolver,
             searcher_context,
         )
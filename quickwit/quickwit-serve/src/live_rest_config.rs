@@ -0,0 +1,431 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use anyhow::Context as _;
+use arc_swap::ArcSwap;
+use quickwit_config::RestCorsPolicy;
+use regex::RegexSet;
+use tower::{Layer, Service};
+use tower_http::cors::{Cors, CorsLayer, ResponseFuture};
+use tracing::info;
+use warp::Reply;
+use warp::hyper::http::header::ORIGIN;
+use warp::hyper::http::{HeaderMap, HeaderName, HeaderValue, Request, Response};
+
+use crate::rest::{build_cors, build_cors_policy};
+
+/// CORS policy applied to the requests sent from a set of origins instead of the policy of
+/// `cors_allow_origins`.
+struct OriginCorsPolicy {
+    origin_matcher: RegexSet,
+    cors_layer: CorsLayer,
+}
+
+impl OriginCorsPolicy {
+    fn matches(&self, origin: &HeaderValue) -> bool {
+        let Ok(origin_str) = origin.to_str() else {
+            return false;
+        };
+        self.origin_matcher.is_match(origin_str)
+    }
+}
+
+/// Builds the matcher of the origins covered by the patterns, in which a `*` matches a single DNS
+/// label (or a port), so that `https://*.example.com` does not match
+/// `https://app.example.com.evil.io` nor `https://a.b.example.com`.
+fn build_origin_matcher(origin_patterns: &[String]) -> anyhow::Result<RegexSet> {
+    let regexes = origin_patterns.iter().map(|origin_pattern| {
+        let escaped_parts: Vec<String> = origin_pattern.split('*').map(regex::escape).collect();
+        format!("^{}$", escaped_parts.join("[A-Za-z0-9-]+"))
+    });
+    let origin_matcher = RegexSet::new(regexes).context("failed to build origin matcher")?;
+    Ok(origin_matcher)
+}
+
+fn build_origin_cors_policies(
+    cors_policies: &[RestCorsPolicy],
+) -> anyhow::Result<Vec<OriginCorsPolicy>> {
+    cors_policies
+        .iter()
+        .map(|cors_policy| {
+            let origin_matcher = build_origin_matcher(&cors_policy.origins)?;
+            let cors_layer = build_cors_policy(cors_policy)?;
+            Ok(OriginCorsPolicy {
+                origin_matcher,
+                cors_layer,
+            })
+        })
+        .collect()
+}
+
+/// The CORS origins and extra headers of the REST API at a point in time.
+pub(crate) struct RestHeadersConfig {
+    pub cors_allow_origins: Vec<String>,
+    pub extra_headers: HeaderMap,
+    cors_layer: CorsLayer,
+    // The per-origin policies are read from the node config and are not updatable at runtime.
+    origin_cors_policies: Arc<Vec<OriginCorsPolicy>>,
+}
+
+impl RestHeadersConfig {
+    fn new(
+        cors_allow_origins: Vec<String>,
+        origin_cors_policies: Arc<Vec<OriginCorsPolicy>>,
+        extra_headers: HeaderMap,
+    ) -> anyhow::Result<Self> {
+        let cors_layer = build_cors(&cors_allow_origins)?;
+        Ok(RestHeadersConfig {
+            cors_allow_origins,
+            extra_headers,
+            cors_layer,
+            origin_cors_policies,
+        })
+    }
+
+    /// Returns the CORS layer of the first policy matching the origin of a request, or the layer
+    /// of `cors_allow_origins` if none does.
+    fn cors_layer_for_origin(&self, origin_opt: Option<&HeaderValue>) -> &CorsLayer {
+        let Some(origin) = origin_opt else {
+            return &self.cors_layer;
+        };
+        self.origin_cors_policies
+            .iter()
+            .find(|origin_cors_policy| origin_cors_policy.matches(origin))
+            .map(|origin_cors_policy| &origin_cors_policy.cors_layer)
+            .unwrap_or(&self.cors_layer)
+    }
+}
+
+/// The CORS origins and extra headers of the REST API, read from the node config at startup and
+/// updatable at runtime through the developer API. The requests received after an update are
+/// served with the updated values.
+#[derive(Clone)]
+pub(crate) struct LiveRestConfig(Arc<ArcSwap<RestHeadersConfig>>);
+
+impl LiveRestConfig {
+    pub fn new(
+        cors_allow_origins: Vec<String>,
+        cors_policies: &[RestCorsPolicy],
+        extra_headers: HeaderMap,
+    ) -> anyhow::Result<Self> {
+        let origin_cors_policies = Arc::new(build_origin_cors_policies(cors_policies)?);
+        let rest_headers_config =
+            RestHeadersConfig::new(cors_allow_origins, origin_cors_policies, extra_headers)?;
+        Ok(LiveRestConfig(Arc::new(ArcSwap::from_pointee(
+            rest_headers_config,
+        ))))
+    }
+
+    pub fn load(&self) -> Arc<RestHeadersConfig> {
+        self.0.load_full()
+    }
+
+    /// Replaces the CORS origins and the extra headers. The config is left unchanged if one of the
+    /// origins is invalid. The per-origin CORS policies are kept.
+    pub fn update(
+        &self,
+        cors_allow_origins: Vec<String>,
+        extra_headers: HeaderMap,
+    ) -> anyhow::Result<()> {
+        let origin_cors_policies = self.load().origin_cors_policies.clone();
+        let rest_headers_config =
+            RestHeadersConfig::new(cors_allow_origins, origin_cors_policies, extra_headers)?;
+        info!(
+            cors_allow_origins=?rest_headers_config.cors_allow_origins,
+            extra_headers=?rest_headers_config.extra_headers,
+            "updating REST API CORS origins and extra headers"
+        );
+        self.0.store(Arc::new(rest_headers_config));
+        Ok(())
+    }
+
+    /// Returns a layer applying the CORS policy of the current config to each request.
+    pub fn cors_layer(&self) -> LiveCorsLayer {
+        LiveCorsLayer {
+            live_rest_config: self.clone(),
+        }
+    }
+}
+
+/// Parses an extra header of the REST API responses.
+pub(crate) fn parse_extra_header(
+    header_name_str: &str,
+    header_value_str: &str,
+) -> anyhow::Result<(HeaderName, HeaderValue)> {
+    let header_name = HeaderName::from_bytes(header_name_str.as_bytes())
+        .with_context(|| format!("invalid header name `{header_name_str}`"))?;
+    let header_value = HeaderValue::from_str(header_value_str)
+        .with_context(|| format!("invalid value for header `{header_name_str}`"))?;
+    Ok((header_name, header_value))
+}
+
+/// Adds the extra headers of the current config to a response.
+pub(crate) fn with_extra_headers(
+    reply: impl Reply,
+    live_rest_config: LiveRestConfig,
+) -> warp::reply::Response {
+    let mut response = reply.into_response();
+    let rest_headers_config = live_rest_config.load();
+
+    for (header_name, header_value) in &rest_headers_config.extra_headers {
+        response
+            .headers_mut()
+            .insert(header_name.clone(), header_value.clone());
+    }
+    response
+}
+
+#[derive(Clone)]
+pub(crate) struct LiveCorsLayer {
+    live_rest_config: LiveRestConfig,
+}
+
+impl<S> Layer<S> for LiveCorsLayer {
+    type Service = LiveCorsService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        LiveCorsService {
+            inner,
+            live_rest_config: self.live_rest_config.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct LiveCorsService<S> {
+    inner: S,
+    live_rest_config: LiveRestConfig,
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for LiveCorsService<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>> + Clone,
+    ResBody: Default,
+{
+    type Response = Response<ResBody>;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<ReqBody>) -> Self::Future {
+        // The inner service driven to readiness by `poll_ready` serves the request and is
+        // replaced with a clone.
+        let inner_clone = self.inner.clone();
+        let ready_inner = std::mem::replace(&mut self.inner, inner_clone);
+        let rest_headers_config = self.live_rest_config.load();
+        let cors_layer = rest_headers_config.cors_layer_for_origin(request.headers().get(ORIGIN));
+        let mut cors: Cors<S> = cors_layer.layer(ready_inner);
+        cors.call(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use tower::{ServiceBuilder, ServiceExt};
+    use warp::Filter;
+    use warp::hyper::Method;
+
+    use super::*;
+    use crate::with_arg;
+
+    async fn preflight_response(
+        live_rest_config: &LiveRestConfig,
+        origin: &'static str,
+    ) -> Response<String> {
+        let service = ServiceBuilder::new()
+            .layer(live_rest_config.cors_layer())
+            .service_fn(|_request: Request<()>| async {
+                Ok::<_, Infallible>(Response::new(String::new()))
+            });
+        let request = Request::builder()
+            .method(Method::OPTIONS)
+            .header("Origin", origin)
+            .body(())
+            .unwrap();
+        service.oneshot(request).await.unwrap()
+    }
+
+    async fn allowed_origin(
+        live_rest_config: &LiveRestConfig,
+        origin: &'static str,
+    ) -> Option<HeaderValue> {
+        preflight_response(live_rest_config, origin)
+            .await
+            .headers()
+            .get("access-control-allow-origin")
+            .cloned()
+    }
+
+    #[tokio::test]
+    async fn test_live_rest_config_updates_cors_origins() {
+        let live_rest_config = LiveRestConfig::new(
+            vec!["https://quickwit.io".to_string()],
+            &[],
+            HeaderMap::new(),
+        )
+        .unwrap();
+        assert_eq!(
+            allowed_origin(&live_rest_config, "https://quickwit.io").await,
+            Some(HeaderValue::from_static("https://quickwit.io"))
+        );
+        assert_eq!(
+            allowed_origin(&live_rest_config, "http://localhost:3000").await,
+            None
+        );
+
+        live_rest_config
+            .update(vec!["http://localhost:3000".to_string()], HeaderMap::new())
+            .unwrap();
+        assert_eq!(
+            allowed_origin(&live_rest_config, "http://localhost:3000").await,
+            Some(HeaderValue::from_static("http://localhost:3000"))
+        );
+        assert_eq!(
+            allowed_origin(&live_rest_config, "https://quickwit.io").await,
+            None
+        );
+
+        live_rest_config
+            .update(vec!["not a valid\norigin".to_string()], HeaderMap::new())
+            .unwrap_err();
+        assert_eq!(
+            live_rest_config.load().cors_allow_origins,
+            ["http://localhost:3000"]
+        );
+    }
+
+    #[test]
+    fn test_build_origin_matcher() {
+        let origin_matcher = build_origin_matcher(&["https://quickwit.io".to_string()]).unwrap();
+        assert!(origin_matcher.is_match("https://quickwit.io"));
+        assert!(!origin_matcher.is_match("https://quickwit.io.evil.com"));
+
+        let origin_matcher = build_origin_matcher(&["https://*.quickwit.io".to_string()]).unwrap();
+        assert!(origin_matcher.is_match("https://app.quickwit.io"));
+        assert!(!origin_matcher.is_match("https://a.b.quickwit.io"));
+        assert!(!origin_matcher.is_match("https://evil.io/.quickwit.io"));
+        assert!(!origin_matcher.is_match("https://quickwit.io"));
+        assert!(!origin_matcher.is_match("http://app.quickwit.io"));
+        assert!(!origin_matcher.is_match("https://app-quickwit.io"));
+
+        let origin_matcher = build_origin_matcher(&[
+            "http://localhost:*".to_string(),
+            "https://*.quickwit.*".to_string(),
+        ])
+        .unwrap();
+        assert!(origin_matcher.is_match("http://localhost:3000"));
+        assert!(origin_matcher.is_match("https://app.quickwit.dev"));
+        assert!(!origin_matcher.is_match("https://app.quickwit.dev.evil.io"));
+        assert!(!origin_matcher.is_match("https://app.other.dev"));
+    }
+
+    #[tokio::test]
+    async fn test_live_rest_config_per_origin_cors_policies() {
+        let cors_policies = [
+            RestCorsPolicy {
+                origins: vec!["https://admin.quickwit.io".to_string()],
+                allow_methods: vec!["get".to_string(), "delete".to_string()],
+                allow_headers: vec!["authorization".to_string()],
+                allow_credentials: true,
+            },
+            RestCorsPolicy {
+                origins: vec!["https://*.dashboards.io".to_string()],
+                allow_methods: vec!["post".to_string()],
+                allow_headers: vec!["content-type".to_string()],
+                allow_credentials: false,
+            },
+        ];
+        let live_rest_config = LiveRestConfig::new(
+            vec!["https://quickwit.io".to_string()],
+            &cors_policies,
+            HeaderMap::new(),
+        )
+        .unwrap();
+
+        let response = preflight_response(&live_rest_config, "https://admin.quickwit.io").await;
+        let headers = response.headers();
+        assert_eq!(
+            headers["access-control-allow-origin"],
+            "https://admin.quickwit.io"
+        );
+        assert_eq!(headers["access-control-allow-methods"], "GET,DELETE");
+        assert_eq!(headers["access-control-allow-headers"], "authorization");
+        assert_eq!(headers["access-control-allow-credentials"], "true");
+
+        let response = preflight_response(&live_rest_config, "https://app.dashboards.io").await;
+        let headers = response.headers();
+        assert_eq!(
+            headers["access-control-allow-origin"],
+            "https://app.dashboards.io"
+        );
+        assert_eq!(headers["access-control-allow-methods"], "POST");
+        assert_eq!(headers["access-control-allow-headers"], "content-type");
+        assert!(headers.get("access-control-allow-credentials").is_none());
+
+        // The other origins get the global policy.
+        let response = preflight_response(&live_rest_config, "https://quickwit.io").await;
+        let headers = response.headers();
+        assert_eq!(
+            headers["access-control-allow-origin"],
+            "https://quickwit.io"
+        );
+        assert_eq!(
+            headers["access-control-allow-methods"],
+            "GET,POST,PUT,DELETE,OPTIONS"
+        );
+        assert_eq!(
+            allowed_origin(&live_rest_config, "https://evil.com").await,
+            None
+        );
+
+        // The per-origin policies survive the updates of the global policy.
+        live_rest_config
+            .update(Vec::new(), HeaderMap::new())
+            .unwrap();
+        assert_eq!(
+            allowed_origin(&live_rest_config, "https://admin.quickwit.io").await,
+            Some(HeaderValue::from_static("https://admin.quickwit.io"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_live_rest_config_updates_extra_headers() {
+        let live_rest_config = LiveRestConfig::new(Vec::new(), &[], HeaderMap::new()).unwrap();
+        let handler = warp::any()
+            .map(warp::reply)
+            .and(with_arg(live_rest_config.clone()))
+            .map(with_extra_headers);
+
+        let response = warp::test::request().reply(&handler).await;
+        assert!(response.headers().get("x-custom-header").is_none());
+
+        let mut extra_headers = HeaderMap::new();
+        extra_headers.insert(
+            HeaderName::from_static("x-custom-header"),
+            HeaderValue::from_static("custom-value"),
+        );
+        live_rest_config.update(Vec::new(), extra_headers).unwrap();
+
+        let response = warp::test::request().reply(&handler).await;
+        assert_eq!(response.headers()["x-custom-header"], "custom-value");
+    }
+}
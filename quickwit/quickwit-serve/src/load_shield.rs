@@ -12,17 +12,22 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::sync::RwLock;
 use std::time::Duration;
 
+use quickwit_config::{IngestBackpressureConfig, IngestBackpressureMode};
 use quickwit_metrics::{Gauge, GaugeGuard, gauge, labels};
 use tokio::sync::{Semaphore, SemaphorePermit};
+use tracing::warn;
 
 use crate::metrics::{ONGOING_REQUESTS, PENDING_REQUESTS};
-use crate::rest::TooManyRequests;
+use crate::rest::{Backpressured, TooManyRequests};
 
 pub struct LoadShield {
+    endpoint_group: &'static str,
     in_flight_semaphore_opt: Option<Semaphore>, // This one is doing the load shedding.
     concurrency_semaphore_opt: Option<Semaphore>,
+    backpressure_config: RwLock<IngestBackpressureConfig>,
     ongoing_gauge: Gauge,
     pending_gauge: Gauge,
 }
@@ -42,19 +47,52 @@ impl LoadShield {
             quickwit_common::get_from_env_opt(&max_in_flight_env_key, false);
         let max_concurrency_opt: Option<usize> =
             quickwit_common::get_from_env_opt(&max_concurrency_env_key, false);
+        Self::with_limits(endpoint_group, max_in_flight_opt, max_concurrency_opt)
+    }
+
+    fn with_limits(
+        endpoint_group: &'static str,
+        max_in_flight_opt: Option<usize>,
+        max_concurrency_opt: Option<usize>,
+    ) -> LoadShield {
         let in_flight_semaphore_opt = max_in_flight_opt.map(Semaphore::new);
         let concurrency_semaphore_opt = max_concurrency_opt.map(Semaphore::new);
         let labels = labels!("endpoint_group" => endpoint_group);
         let pending_gauge = gauge!(parent: PENDING_REQUESTS, labels: [labels]);
         let ongoing_gauge = gauge!(parent: ONGOING_REQUESTS, labels: [labels]);
         LoadShield {
+            endpoint_group,
             in_flight_semaphore_opt,
             concurrency_semaphore_opt,
+            backpressure_config: RwLock::default(),
             ongoing_gauge,
             pending_gauge,
         }
     }
 
+    /// Sets how the requests exceeding the concurrency limit are handled, replacing the previous
+    /// config. Warns when the config differs from the default while no concurrency limit is set,
+    /// since it has no effect then.
+    pub fn set_backpressure_config(&self, backpressure_config: IngestBackpressureConfig) {
+        if self.concurrency_semaphore_opt.is_none()
+            && backpressure_config != IngestBackpressureConfig::default()
+        {
+            let max_concurrency_env_key = format!(
+                "QW_{}_MAX_CONCURRENCY",
+                self.endpoint_group.to_ascii_uppercase()
+            );
+            warn!(
+                "the backpressure config of the `{}` endpoints has no effect: set their \
+                 concurrency limit with `{max_concurrency_env_key}`",
+                self.endpoint_group
+            );
+        }
+        *self
+            .backpressure_config
+            .write()
+            .expect("lock should not be poisoned") = backpressure_config;
+    }
+
     async fn acquire_in_flight_permit(
         &'static self,
     ) -> Result<Option<SemaphorePermit<'static>>, warp::Rejection> {
@@ -70,15 +108,42 @@ impl LoadShield {
         Ok(Some(in_flight_permit))
     }
 
-    async fn acquire_concurrency_permit(&'static self) -> Option<SemaphorePermit<'static>> {
-        let concurrency_semaphore = self.concurrency_semaphore_opt.as_ref()?;
-        Some(concurrency_semaphore.acquire().await.unwrap())
+    async fn acquire_concurrency_permit(
+        &'static self,
+    ) -> Result<Option<SemaphorePermit<'static>>, warp::Rejection> {
+        let Some(concurrency_semaphore) = &self.concurrency_semaphore_opt else {
+            return Ok(None);
+        };
+        let backpressure_config = *self
+            .backpressure_config
+            .read()
+            .expect("lock should not be poisoned");
+        let concurrency_permit_opt = match backpressure_config.mode {
+            IngestBackpressureMode::FailFast => concurrency_semaphore.try_acquire().ok(),
+            IngestBackpressureMode::Block => {
+                if let Some(timeout_secs) = backpressure_config.timeout_secs {
+                    let timeout = Duration::from_secs(timeout_secs.get());
+                    tokio::time::timeout(timeout, concurrency_semaphore.acquire())
+                        .await
+                        .ok()
+                        .map(|permit_res| permit_res.expect("semaphore should not be closed"))
+                } else {
+                    let permit_res = concurrency_semaphore.acquire().await;
+                    Some(permit_res.expect("semaphore should not be closed"))
+                }
+            }
+        };
+        let Some(concurrency_permit) = concurrency_permit_opt else {
+            let retry_after = Duration::from_secs(backpressure_config.retry_after_secs.get());
+            return Err(warp::reject::custom(Backpressured { retry_after }));
+        };
+        Ok(Some(concurrency_permit))
     }
 
     pub async fn acquire_permit(&'static self) -> Result<LoadShieldPermit, warp::Rejection> {
         let pending_gauge_guard = GaugeGuard::new(&self.pending_gauge, 1.0);
         let in_flight_permit_opt = self.acquire_in_flight_permit().await?;
-        let concurrency_permit_opt = self.acquire_concurrency_permit().await;
+        let concurrency_permit_opt = self.acquire_concurrency_permit().await?;
         drop(pending_gauge_guard);
         let ongoing_gauge_guard = GaugeGuard::new(&self.ongoing_gauge, 1.0);
         Ok(LoadShieldPermit {
@@ -88,3 +153,92 @@ impl LoadShield {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroU64;
+
+    use warp::Reply;
+    use warp::hyper::StatusCode;
+    use warp::hyper::http::header::RETRY_AFTER;
+
+    use super::*;
+    use crate::rest::recover_fn;
+
+    /// Returns a load shield admitting a single request at a time, and the permit of the request
+    /// being served, so that the next requests exceed the concurrency limit.
+    async fn saturated_load_shield(
+        backpressure_config: IngestBackpressureConfig,
+    ) -> (&'static LoadShield, LoadShieldPermit) {
+        let load_shield: &'static LoadShield =
+            Box::leak(Box::new(LoadShield::with_limits("test", None, Some(1))));
+        load_shield.set_backpressure_config(backpressure_config);
+        let permit = load_shield.acquire_permit().await.unwrap();
+        (load_shield, permit)
+    }
+
+    async fn assert_backpressured(rejection: warp::Rejection, expected_retry_after: &str) {
+        let response = recover_fn(rejection).await.unwrap().into_response();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers()[RETRY_AFTER], expected_retry_after);
+    }
+
+    #[tokio::test]
+    async fn test_load_shield_backpressure_fail_fast() {
+        let backpressure_config = IngestBackpressureConfig {
+            mode: IngestBackpressureMode::FailFast,
+            timeout_secs: None,
+            retry_after_secs: NonZeroU64::new(3).unwrap(),
+        };
+        let (load_shield, permit) = saturated_load_shield(backpressure_config).await;
+
+        let rejection = load_shield.acquire_permit().await.err().unwrap();
+        assert_backpressured(rejection, "3").await;
+
+        drop(permit);
+        load_shield.acquire_permit().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_load_shield_backpressure_config_replaced() {
+        let backpressure_config = IngestBackpressureConfig {
+            mode: IngestBackpressureMode::FailFast,
+            timeout_secs: None,
+            retry_after_secs: NonZeroU64::new(3).unwrap(),
+        };
+        let (load_shield, _permit) = saturated_load_shield(backpressure_config).await;
+
+        let backpressure_config = IngestBackpressureConfig {
+            retry_after_secs: NonZeroU64::new(7).unwrap(),
+            ..backpressure_config
+        };
+        load_shield.set_backpressure_config(backpressure_config);
+
+        let rejection = load_shield.acquire_permit().await.err().unwrap();
+        assert_backpressured(rejection, "7").await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_load_shield_backpressure_block() {
+        let backpressure_config = IngestBackpressureConfig {
+            mode: IngestBackpressureMode::Block,
+            timeout_secs: Some(NonZeroU64::new(10).unwrap()),
+            retry_after_secs: NonZeroU64::new(1).unwrap(),
+        };
+        let (load_shield, permit) = saturated_load_shield(backpressure_config).await;
+
+        // The request waits for its turn up to the timeout.
+        let start = tokio::time::Instant::now();
+        let rejection = load_shield.acquire_permit().await.err().unwrap();
+        assert!(start.elapsed() >= Duration::from_secs(10));
+        assert_backpressured(rejection, "1").await;
+
+        // The request is served as soon as the request being served completes.
+        let acquire_permit_handle = tokio::spawn(load_shield.acquire_permit());
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        assert!(!acquire_permit_handle.is_finished());
+
+        drop(permit);
+        acquire_permit_handle.await.unwrap().unwrap();
+    }
+}
@@ -0,0 +1,107 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use tracing::{info, warn};
+use warp::{Filter, Rejection};
+
+use crate::with_arg;
+
+/// Node-local maintenance mode, toggled at runtime through the developer API.
+///
+/// While it is enabled, the REST API rejects ingest and search requests, as well as index and
+/// template updates, with a `503 Service Unavailable`. The cluster, node info, indexing, health,
+/// metrics, and developer routes, as well as index and template reads, remain available to
+/// operators.
+#[derive(Clone, Default)]
+pub(crate) struct MaintenanceMode(Arc<AtomicBool>);
+
+impl MaintenanceMode {
+    pub fn is_enabled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    pub fn set_enabled(&self, enabled: bool) {
+        let was_enabled = self.0.swap(enabled, Ordering::Relaxed);
+
+        if was_enabled == enabled {
+            return;
+        }
+        if enabled {
+            warn!("maintenance mode enabled: ingest and search requests are rejected");
+        } else {
+            info!("maintenance mode disabled");
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct UnderMaintenance;
+
+impl warp::reject::Reject for UnderMaintenance {}
+
+impl fmt::Display for UnderMaintenance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "node is in maintenance mode: only the cluster, node, indexing, health, metrics, and \
+             index read routes are available"
+        )
+    }
+}
+
+/// Rejects the requests with [`UnderMaintenance`] while maintenance mode is enabled. It guards the
+/// ingest and search routes, the routes available to operators being tried first.
+pub(crate) fn maintenance_mode_filter(
+    maintenance_mode: MaintenanceMode,
+) -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    with_arg(maintenance_mode)
+        .and_then(|maintenance_mode: MaintenanceMode| async move {
+            if maintenance_mode.is_enabled() {
+                return Err(warp::reject::custom(UnderMaintenance));
+            }
+            Ok(())
+        })
+        .untuple_one()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn is_rejected(maintenance_mode: &MaintenanceMode) -> bool {
+        let filter = maintenance_mode_filter(maintenance_mode.clone());
+        let rejection_opt = warp::test::request().filter(&filter).await.err();
+        match rejection_opt {
+            Some(rejection) => rejection.find::<UnderMaintenance>().is_some(),
+            None => false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_maintenance_mode_filter() {
+        let maintenance_mode = MaintenanceMode::default();
+        assert!(!is_rejected(&maintenance_mode).await);
+
+        maintenance_mode.set_enabled(true);
+        assert!(maintenance_mode.is_enabled());
+        assert!(is_rejected(&maintenance_mode).await);
+
+        maintenance_mode.set_enabled(false);
+        assert!(!is_rejected(&maintenance_mode).await);
+    }
+}
@@ -0,0 +1,235 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use bytes::Bytes;
+use futures::TryStreamExt;
+use quickwit_metastore::{
+    IndexMetadata, IndexMetadataResponseExt, ListSplitsQuery, ListSplitsRequestExt,
+    ListSplitsResponseExt, Split,
+};
+use quickwit_proto::metastore::{
+    EntityKind, IndexMetadataRequest, ListSplitsRequest, MetastoreError, MetastoreResult,
+    MetastoreService, MetastoreServiceClient,
+};
+use quickwit_proto::types::IndexUid;
+use serde::Serialize;
+use tokio::sync::mpsc;
+use tracing::warn;
+
+/// Number of times the metadata and the splits of an index are read before giving up on the
+/// index when its metadata keeps changing while its splits are read.
+pub(super) const MAX_EXPORT_INDEX_ATTEMPTS: usize = 5;
+
+/// A line of the newline-delimited JSON metastore export.
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ExportLine<'a> {
+    Index {
+        index_metadata: &'a IndexMetadata,
+    },
+    Split {
+        split: &'a Split,
+    },
+    /// Line replacing the lines of an index that could not be exported.
+    IndexError {
+        index_id: String,
+        message: String,
+    },
+    /// Last line of a complete export.
+    End {
+        num_indexes: usize,
+        num_splits: usize,
+        num_failed_indexes: usize,
+    },
+}
+
+/// Exports the indexes one at a time, replacing the lines of each index that fails to export with
+/// an `index_error` line, and terminates the export with an `end` line.
+pub(super) async fn export_indexes(
+    metastore: MetastoreServiceClient,
+    index_uids: Vec<IndexUid>,
+    ndjson_tx: mpsc::Sender<Bytes>,
+) {
+    let mut num_indexes = 0;
+    let mut num_splits = 0;
+    let mut num_failed_indexes = 0;
+
+    for index_uid in index_uids {
+        let index_id = index_uid.index_id.clone();
+
+        match export_index(&metastore, index_uid, &ndjson_tx).await {
+            Ok(Some(num_index_splits)) => {
+                num_indexes += 1;
+                num_splits += num_index_splits;
+            }
+            Ok(None) => {}
+            Err(error) => {
+                warn!(index_id=%index_id, %error, "failed to export index");
+                num_failed_indexes += 1;
+                let index_error_line = ExportLine::IndexError {
+                    index_id,
+                    message: error.to_string(),
+                };
+                // The client closed the connection.
+                if send_lines(&ndjson_tx, &[index_error_line]).await.is_err() {
+                    return;
+                }
+            }
+        }
+    }
+    let end_line = ExportLine::End {
+        num_indexes,
+        num_splits,
+        num_failed_indexes,
+    };
+    let _ = send_lines(&ndjson_tx, &[end_line]).await;
+}
+
+/// Exports the metadata and the splits of an index and returns the number of exported splits, or
+/// `None` if the index was deleted since the export started.
+///
+/// The lines of the index are only sent once the metadata read after the splits matches the
+/// metadata read before them, so that the exported checkpoint is consistent with the exported
+/// splits. The index is read again, up to `MAX_EXPORT_INDEX_ATTEMPTS` times, if its metadata
+/// changed in the meantime.
+async fn export_index(
+    metastore: &MetastoreServiceClient,
+    index_uid: IndexUid,
+    ndjson_tx: &mpsc::Sender<Bytes>,
+) -> MetastoreResult<Option<usize>> {
+    for _ in 0..MAX_EXPORT_INDEX_ATTEMPTS {
+        let Some(index_snapshot) = read_index_snapshot(metastore, &index_uid).await? else {
+            return Ok(None);
+        };
+        let IndexSnapshot::Consistent {
+            ndjson_chunks,
+            num_splits,
+        } = index_snapshot
+        else {
+            continue;
+        };
+        for ndjson_chunk in ndjson_chunks {
+            send_ndjson(ndjson_tx, ndjson_chunk).await?;
+        }
+        return Ok(Some(num_splits));
+    }
+    Err(MetastoreError::FailedPrecondition {
+        entity: EntityKind::Index {
+            index_id: index_uid.index_id.clone(),
+        },
+        message: format!(
+            "the index metadata was updated while its splits were read \
+             {MAX_EXPORT_INDEX_ATTEMPTS} times in a row, retry the export"
+        ),
+    })
+}
+
+enum IndexSnapshot {
+    /// The NDJSON chunks of the index line and of its split lines.
+    Consistent {
+        ndjson_chunks: Vec<Bytes>,
+        num_splits: usize,
+    },
+    /// The index metadata, for instance its checkpoint, changed while the splits were read.
+    Changed,
+}
+
+/// Reads the metadata and the splits of an index, or returns `None` if the index was deleted.
+async fn read_index_snapshot(
+    metastore: &MetastoreServiceClient,
+    index_uid: &IndexUid,
+) -> MetastoreResult<Option<IndexSnapshot>> {
+    let Some(index_metadata) = read_index_metadata(metastore, index_uid).await? else {
+        return Ok(None);
+    };
+    let index_line = ExportLine::Index {
+        index_metadata: &index_metadata,
+    };
+    let mut ndjson_chunks = vec![serialize_lines(&[index_line])?];
+
+    let list_splits_query = ListSplitsQuery::for_index(index_uid.clone());
+    let list_splits_request = ListSplitsRequest::try_from_list_splits_query(&list_splits_query)?;
+    let mut list_splits_stream = metastore.list_splits(list_splits_request).await?;
+    let mut num_splits = 0;
+
+    while let Some(list_splits_response) = list_splits_stream.try_next().await? {
+        let splits = list_splits_response.deserialize_splits().await?;
+        num_splits += splits.len();
+        let split_lines: Vec<ExportLine> = splits
+            .iter()
+            .map(|split| ExportLine::Split { split })
+            .collect();
+        ndjson_chunks.push(serialize_lines(&split_lines)?);
+    }
+    // Publishing newly indexed splits moves the checkpoint forward in the same transaction, so an
+    // unchanged checkpoint guarantees that no new documents were published while the splits were
+    // read.
+    let Some(index_metadata_after_splits) = read_index_metadata(metastore, index_uid).await? else {
+        return Ok(None);
+    };
+    if index_metadata_after_splits != index_metadata {
+        return Ok(Some(IndexSnapshot::Changed));
+    }
+    Ok(Some(IndexSnapshot::Consistent {
+        ndjson_chunks,
+        num_splits,
+    }))
+}
+
+async fn read_index_metadata(
+    metastore: &MetastoreServiceClient,
+    index_uid: &IndexUid,
+) -> MetastoreResult<Option<IndexMetadata>> {
+    let index_metadata_request = IndexMetadataRequest::for_index_uid(index_uid.clone());
+    match metastore.index_metadata(index_metadata_request).await {
+        Ok(index_metadata_response) => index_metadata_response
+            .deserialize_index_metadata()
+            .map(Some),
+        Err(MetastoreError::NotFound(_)) => Ok(None),
+        Err(error) => Err(error),
+    }
+}
+
+async fn send_lines(
+    ndjson_tx: &mpsc::Sender<Bytes>,
+    export_lines: &[ExportLine<'_>],
+) -> MetastoreResult<()> {
+    let ndjson = serialize_lines(export_lines)?;
+    send_ndjson(ndjson_tx, ndjson).await
+}
+
+fn serialize_lines(export_lines: &[ExportLine<'_>]) -> MetastoreResult<Bytes> {
+    let mut ndjson = Vec::new();
+
+    for export_line in export_lines {
+        serde_json::to_writer(&mut ndjson, export_line).map_err(|error| {
+            MetastoreError::JsonSerializeError {
+                struct_name: "ExportLine".to_string(),
+                message: error.to_string(),
+            }
+        })?;
+        ndjson.push(b'\n');
+    }
+    Ok(Bytes::from(ndjson))
+}
+
+async fn send_ndjson(ndjson_tx: &mpsc::Sender<Bytes>, ndjson: Bytes) -> MetastoreResult<()> {
+    ndjson_tx
+        .send(ndjson)
+        .await
+        .map_err(|_| MetastoreError::Internal {
+            message: "failed to send metastore export lines".to_string(),
+            cause: "the client closed the connection".to_string(),
+        })
+}
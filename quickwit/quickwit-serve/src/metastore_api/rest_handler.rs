@@ -0,0 +1,338 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::convert::Infallible;
+
+use bytes::Bytes;
+use futures::StreamExt;
+use quickwit_metastore::ListIndexesMetadataResponseExt;
+use quickwit_proto::metastore::{
+    ListIndexesMetadataRequest, MetastoreResult, MetastoreService, MetastoreServiceClient,
+};
+use quickwit_proto::types::IndexUid;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use warp::hyper::header::CONTENT_TYPE;
+use warp::hyper::http::HeaderValue;
+use warp::{Filter, Rejection, Reply};
+
+use super::export::export_indexes;
+use crate::format::extract_format_from_qs;
+use crate::request_scope::ensure_admin;
+use crate::rest::recover_fn;
+use crate::rest_api_response::into_rest_api_response;
+use crate::{BodyFormat, with_arg};
+
+/// Number of NDJSON chunks, each holding the lines of one batch of splits, buffered between the
+/// export task and the response body.
+const EXPORT_CHANNEL_CAPACITY: usize = 2;
+
+#[derive(utoipa::OpenApi)]
+#[openapi(paths(export_metastore))]
+pub(crate) struct MetastoreApi;
+
+pub(crate) fn metastore_api_handlers(
+    metastore: MetastoreServiceClient,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    warp::path!("_metastore" / "export")
+        .and(warp::get())
+        .and(with_arg(metastore))
+        .then(export_metastore)
+        .and(extract_format_from_qs())
+        .map(into_export_response)
+        .recover(recover_fn)
+        .boxed()
+}
+
+#[utoipa::path(
+    get,
+    tag = "Indexes",
+    path = "/_metastore/export",
+    responses(
+        (status = 200, description = "Successfully exported the metastore as newline-delimited JSON."),
+        (status = 403, description = "The token of the request does not grant admin access."),
+    ),
+)]
+/// Exports the metadata of all the indexes and their splits, for backups.
+///
+/// The response is a stream of newline-delimited JSON lines: for each index, an `index` line
+/// followed by one `split` line per split. The export is complete if and only if its last line is
+/// an `end` line. An index that cannot be exported, for instance because its metadata keeps
+/// changing while its splits are read, is replaced by an `index_error` line, and the export moves
+/// on to the next index.
+async fn export_metastore(
+    metastore: MetastoreServiceClient,
+) -> MetastoreResult<ReceiverStream<Bytes>> {
+    ensure_admin()?;
+    let index_uids: Vec<IndexUid> = metastore
+        .list_indexes_metadata(ListIndexesMetadataRequest::all())
+        .await?
+        .deserialize_indexes_metadata()
+        .await?
+        .into_iter()
+        .map(|index_metadata| index_metadata.index_uid)
+        .collect();
+    let (ndjson_tx, ndjson_rx) = mpsc::channel(EXPORT_CHANNEL_CAPACITY);
+    tokio::spawn(export_indexes(metastore, index_uids, ndjson_tx));
+    Ok(ReceiverStream::new(ndjson_rx))
+}
+
+fn into_export_response(
+    ndjson_stream_result: MetastoreResult<ReceiverStream<Bytes>>,
+    body_format: BodyFormat,
+) -> warp::reply::Response {
+    let ndjson_stream = match ndjson_stream_result {
+        Ok(ndjson_stream) => ndjson_stream,
+        Err(metastore_error) => {
+            return into_rest_api_response::<(), _>(Err(metastore_error), body_format)
+                .into_response();
+        }
+    };
+    let body = warp::Body::wrap_stream(ndjson_stream.map(Ok::<_, Infallible>));
+    let mut response = warp::reply::Response::new(body);
+    response.headers_mut().insert(
+        CONTENT_TYPE,
+        HeaderValue::from_static("application/x-ndjson"),
+    );
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use quickwit_common::ServiceStream;
+    use quickwit_config::IndexConfig;
+    use quickwit_metastore::checkpoint::IndexCheckpointDelta;
+    use quickwit_metastore::{
+        CreateIndexRequestExt, IndexMetadata, IndexMetadataResponseExt, Split, SplitMetadata,
+        StageSplitsRequestExt, metastore_for_test,
+    };
+    use quickwit_proto::metastore::{
+        CreateIndexRequest, IndexMetadataRequest, IndexMetadataResponse,
+        ListIndexesMetadataResponse, ListSplitsResponse, MockMetastoreService,
+        PublishSplitsRequest, StageSplitsRequest,
+    };
+    use serde_json::Value as JsonValue;
+
+    use super::super::export::MAX_EXPORT_INDEX_ATTEMPTS;
+    use super::*;
+
+    async fn export(metastore: MetastoreServiceClient) -> Vec<JsonValue> {
+        let handler = metastore_api_handlers(metastore);
+        let resp = warp::test::request()
+            .path("/_metastore/export")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        assert_eq!(resp.headers()[CONTENT_TYPE], "application/x-ndjson");
+        resp.body()
+            .split(|byte| *byte == b'\n')
+            .filter(|line| !line.is_empty())
+            .map(|line| serde_json::from_slice(line).unwrap())
+            .collect()
+    }
+
+    async fn create_index(metastore: &MetastoreServiceClient, index_id: &str) -> IndexUid {
+        let index_uri = format!("ram:///indexes/{index_id}");
+        let index_config = IndexConfig::for_test(index_id, &index_uri);
+        let create_index_request =
+            CreateIndexRequest::try_from_index_config(&index_config).unwrap();
+        metastore
+            .create_index(create_index_request)
+            .await
+            .unwrap()
+            .index_uid()
+            .clone()
+    }
+
+    async fn stage_split(metastore: &MetastoreServiceClient, index_uid: &IndexUid, split_id: &str) {
+        let split_metadata = SplitMetadata {
+            split_id: split_id.to_string(),
+            index_uid: index_uid.clone(),
+            ..Default::default()
+        };
+        let stage_splits_request =
+            StageSplitsRequest::try_from_split_metadata(index_uid.clone(), &split_metadata)
+                .unwrap();
+        metastore.stage_splits(stage_splits_request).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_export_metastore() {
+        let metastore = metastore_for_test();
+        let index_uid_1 = create_index(&metastore, "test-index-1").await;
+        let index_uid_2 = create_index(&metastore, "test-index-2").await;
+
+        stage_split(&metastore, &index_uid_1, "split-1").await;
+        stage_split(&metastore, &index_uid_1, "split-2").await;
+        let publish_splits_request = PublishSplitsRequest {
+            index_uid: Some(index_uid_1.clone()),
+            staged_split_ids: vec!["split-1".to_string()],
+            ..Default::default()
+        };
+        metastore
+            .publish_splits(publish_splits_request)
+            .await
+            .unwrap();
+
+        let export_lines = export(metastore.clone()).await;
+        assert_eq!(export_lines.len(), 5);
+
+        let mut exported_index_uids = Vec::new();
+        let mut exported_split_ids = Vec::new();
+
+        for export_line in &export_lines[..4] {
+            match export_line["type"].as_str().unwrap() {
+                "index" => {
+                    let index_metadata: IndexMetadata =
+                        serde_json::from_value(export_line["index_metadata"].clone()).unwrap();
+                    let index_metadata_request =
+                        IndexMetadataRequest::for_index_uid(index_metadata.index_uid.clone());
+                    let expected_index_metadata = metastore
+                        .index_metadata(index_metadata_request)
+                        .await
+                        .unwrap()
+                        .deserialize_index_metadata()
+                        .unwrap();
+                    assert_eq!(index_metadata, expected_index_metadata);
+                    exported_index_uids.push(index_metadata.index_uid);
+                }
+                "split" => {
+                    let split: Split =
+                        serde_json::from_value(export_line["split"].clone()).unwrap();
+                    // The splits of an index follow its metadata.
+                    assert_eq!(
+                        exported_index_uids.last(),
+                        Some(&split.split_metadata.index_uid)
+                    );
+                    exported_split_ids.push(split.split_metadata.split_id);
+                }
+                line_type => panic!("unexpected export line type `{line_type}`"),
+            }
+        }
+        exported_index_uids.sort();
+        let mut expected_index_uids = vec![index_uid_1, index_uid_2];
+        expected_index_uids.sort();
+        assert_eq!(exported_index_uids, expected_index_uids);
+
+        exported_split_ids.sort();
+        assert_eq!(exported_split_ids, ["split-1", "split-2"]);
+
+        assert_eq!(
+            export_lines[4],
+            serde_json::json!({
+                "type": "end",
+                "num_indexes": 2,
+                "num_splits": 2,
+                "num_failed_indexes": 0,
+            })
+        );
+    }
+
+    /// Returns a mock metastore serving `test-index` without splits, whose checkpoint moves forward
+    /// on each of the first `num_checkpoint_updates` index metadata requests.
+    fn mock_metastore_with_checkpoint_updates(
+        num_checkpoint_updates: usize,
+        num_list_splits_requests: usize,
+    ) -> MetastoreServiceClient {
+        let index_metadata = IndexMetadata::for_test("test-index", "ram:///indexes/test-index");
+        let mut mock_metastore = MockMetastoreService::new();
+        let index_metadata_clone = index_metadata.clone();
+        mock_metastore
+            .expect_list_indexes_metadata()
+            .return_once(move |_| {
+                Ok(ListIndexesMetadataResponse::for_test(vec![
+                    index_metadata_clone,
+                ]))
+            });
+        let num_index_metadata_requests = AtomicUsize::new(0);
+        mock_metastore
+            .expect_index_metadata()
+            .times(2 * num_list_splits_requests)
+            .returning(move |_| {
+                let num_updates = num_index_metadata_requests
+                    .fetch_add(1, Ordering::Relaxed)
+                    .min(num_checkpoint_updates) as u64;
+                let mut index_metadata = index_metadata.clone();
+                let checkpoint_delta =
+                    IndexCheckpointDelta::for_test("test-source", 0..num_updates + 1);
+                index_metadata
+                    .checkpoint
+                    .try_apply_delta(checkpoint_delta)
+                    .unwrap();
+                Ok(IndexMetadataResponse::try_from_index_metadata(&index_metadata).unwrap())
+            });
+        mock_metastore
+            .expect_list_splits()
+            .times(num_list_splits_requests)
+            .returning(|_| {
+                let list_splits_response = ListSplitsResponse::try_from_splits(Vec::new()).unwrap();
+                Ok(ServiceStream::from(vec![Ok(list_splits_response)]))
+            });
+        MetastoreServiceClient::from_mock(mock_metastore)
+    }
+
+    #[tokio::test]
+    async fn test_export_metastore_retries_index_updated_during_export() {
+        // The checkpoint moves forward while the splits are read the first time.
+        let metastore = mock_metastore_with_checkpoint_updates(1, 2);
+        let export_lines = export(metastore).await;
+        assert_eq!(export_lines.len(), 2);
+        assert_eq!(export_lines[0]["type"], "index");
+        let index_metadata: IndexMetadata =
+            serde_json::from_value(export_lines[0]["index_metadata"].clone()).unwrap();
+        // The exported checkpoint is the one read after the update.
+        let mut expected_index_metadata =
+            IndexMetadata::for_test("test-index", "ram:///indexes/test-index");
+        expected_index_metadata
+            .checkpoint
+            .try_apply_delta(IndexCheckpointDelta::for_test("test-source", 0..2))
+            .unwrap();
+        assert_eq!(index_metadata, expected_index_metadata);
+        assert_eq!(
+            export_lines[1],
+            serde_json::json!({
+                "type": "end",
+                "num_indexes": 1,
+                "num_splits": 0,
+                "num_failed_indexes": 0,
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_export_metastore_index_keeps_changing_during_export() {
+        let metastore =
+            mock_metastore_with_checkpoint_updates(usize::MAX, MAX_EXPORT_INDEX_ATTEMPTS);
+        let export_lines = export(metastore).await;
+        assert_eq!(export_lines.len(), 2);
+        assert_eq!(export_lines[0]["type"], "index_error");
+        assert_eq!(export_lines[0]["index_id"], "test-index");
+        let error_message = export_lines[0]["message"].as_str().unwrap();
+        assert!(
+            error_message.contains("retry the export"),
+            "{error_message}"
+        );
+        assert_eq!(
+            export_lines[1],
+            serde_json::json!({
+                "type": "end",
+                "num_indexes": 0,
+                "num_splits": 0,
+                "num_failed_indexes": 1,
+            })
+        );
+    }
+}
@@ -12,11 +12,58 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::sync::OnceLock;
+
 use quickwit_common::metrics::exponential_buckets;
+use quickwit_config::RequestDurationBucketsConfig;
 use quickwit_metrics::{
     LazyCounter, LazyGauge, LazyHistogram, lazy_counter, lazy_gauge, lazy_histogram,
 };
 
+static REQUEST_DURATION_BUCKETS_CONFIG: OnceLock<RequestDurationBucketsConfig> = OnceLock::new();
+
+/// Sets the bucket boundaries of the request duration histograms from the node config. The
+/// buckets are read when the metrics recorder is built, so this must be called before, and only
+/// the first call has an effect, the recorder being built once per process.
+pub fn set_request_duration_buckets(request_duration_buckets: RequestDurationBucketsConfig) {
+    let _ = REQUEST_DURATION_BUCKETS_CONFIG.set(request_duration_buckets);
+}
+
+/// Returns the bucket boundaries set in the node config, or the default ones.
+fn configured_buckets(
+    buckets_fn: impl FnOnce(&RequestDurationBucketsConfig) -> &Option<Vec<f64>>,
+    default_buckets: impl FnOnce() -> Vec<f64>,
+) -> Vec<f64> {
+    REQUEST_DURATION_BUCKETS_CONFIG
+        .get()
+        .and_then(|request_duration_buckets| buckets_fn(request_duration_buckets).clone())
+        .unwrap_or_else(default_buckets)
+}
+
+/// From 20ms to 163.84s by default.
+fn request_duration_buckets() -> Vec<f64> {
+    configured_buckets(
+        |request_duration_buckets| &request_duration_buckets.all,
+        || exponential_buckets(0.02, 2.0, 14).expect("buckets should be valid"),
+    )
+}
+
+/// From 5ms to 81.92s by default.
+fn search_request_duration_buckets() -> Vec<f64> {
+    configured_buckets(
+        |request_duration_buckets| &request_duration_buckets.search,
+        || exponential_buckets(0.005, 2.0, 15).expect("buckets should be valid"),
+    )
+}
+
+/// From 1ms to 16.384s by default.
+fn ingest_request_duration_buckets() -> Vec<f64> {
+    configured_buckets(
+        |request_duration_buckets| &request_duration_buckets.ingest,
+        || exponential_buckets(0.001, 2.0, 15).expect("buckets should be valid"),
+    )
+}
+
 pub(crate) static HTTP_REQUESTS_TOTAL: LazyCounter = lazy_counter!(
         name: "http_requests_total",
         description: "Total number of HTTP requests processed.",
@@ -27,8 +74,21 @@ pub(crate) static REQUEST_DURATION_SECS: LazyHistogram = lazy_histogram!(
         name: "request_duration_secs",
         description: "Response time in seconds",
         subsystem: "",
-        // last bucket is 163.84s
-        buckets: exponential_buckets(0.02, 2.0, 14).unwrap(),
+        buckets: request_duration_buckets(),
+);
+
+pub(crate) static SEARCH_REQUEST_DURATION_SECS: LazyHistogram = lazy_histogram!(
+        name: "search_request_duration_secs",
+        description: "Response time of the search routes in seconds",
+        subsystem: "",
+        buckets: search_request_duration_buckets(),
+);
+
+pub(crate) static INGEST_REQUEST_DURATION_SECS: LazyHistogram = lazy_histogram!(
+        name: "ingest_request_duration_secs",
+        description: "Response time of the ingest routes in seconds",
+        subsystem: "",
+        buckets: ingest_request_duration_buckets(),
 );
 
 pub(crate) static ONGOING_REQUESTS: LazyGauge = lazy_gauge!(
@@ -43,8 +103,38 @@ pub(crate) static PENDING_REQUESTS: LazyGauge = lazy_gauge!(
         subsystem: "",
 );
 
+pub(crate) static DEDUPLICATED_DOCS_TOTAL: LazyCounter = lazy_counter!(
+        name: "deduplicated_docs_total",
+        description: "Number of documents dropped because their content is identical to a document ingested within the content deduplication window of their index",
+        subsystem: "ingest",
+);
+
 pub(crate) static CIRCUIT_BREAK_TOTAL: LazyCounter = lazy_counter!(
         name: "circuit_break_total",
         description: "Circuit breaker counter",
         subsystem: "grpc",
 );
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_duration_buckets_from_node_config() {
+        set_request_duration_buckets(RequestDurationBucketsConfig {
+            search: Some(vec![0.001, 0.5, 30.0]),
+            ..Default::default()
+        });
+        let search_histogram_config = SEARCH_REQUEST_DURATION_SECS.__info();
+        assert_eq!(
+            search_histogram_config.info.key_name,
+            "quickwit_search_request_duration_secs"
+        );
+        assert_eq!((search_histogram_config.buckets_fn)(), [0.001, 0.5, 30.0]);
+
+        // The other histograms keep their default buckets.
+        let ingest_buckets = (INGEST_REQUEST_DURATION_SECS.__info().buckets_fn)();
+        assert_eq!(ingest_buckets.len(), 15);
+        assert_eq!(ingest_buckets[0], 0.001);
+    }
+}
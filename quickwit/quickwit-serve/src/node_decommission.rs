@@ -0,0 +1,190 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use quickwit_actors::Mailbox;
+use quickwit_compaction::{
+    CompactorSupervisor, notify_compactor_decommission, wait_for_compactor_decommission,
+};
+use quickwit_ingest::{Ingester, notify_ingester_decommission, wait_for_ingester_decommission};
+use quickwit_search::InFlightLeafSearches;
+use serde::Serialize;
+use tracing::{error, info};
+use warp::{Filter, Rejection};
+
+use crate::node_readiness::NodeReadiness;
+use crate::with_arg;
+
+const DECOMMISSION_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Progress of the decommission of the node.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum DecommissionStatus {
+    /// The node was not asked to leave the cluster.
+    #[default]
+    NotRequested,
+    /// The node is waiting for its in-flight work to finish.
+    Draining,
+    /// The node drained its in-flight work and can be shut down.
+    Drained,
+    /// The node failed to drain its in-flight work, see its logs for details.
+    Failed,
+}
+
+/// Graceful decommission of the node, requested through the `POST /api/v1/cluster/leave`
+/// endpoint ahead of a shutdown.
+#[derive(Clone)]
+pub(crate) struct NodeDecommission {
+    node_readiness: NodeReadiness,
+    ingester_opt: Option<Ingester>,
+    compactor_supervisor_opt: Option<Mailbox<CompactorSupervisor>>,
+    in_flight_leaf_searches: InFlightLeafSearches,
+    status: Arc<Mutex<DecommissionStatus>>,
+}
+
+impl NodeDecommission {
+    pub fn new(
+        node_readiness: NodeReadiness,
+        ingester_opt: Option<Ingester>,
+        compactor_supervisor_opt: Option<Mailbox<CompactorSupervisor>>,
+        in_flight_leaf_searches: InFlightLeafSearches,
+    ) -> Self {
+        Self {
+            node_readiness,
+            ingester_opt,
+            compactor_supervisor_opt,
+            in_flight_leaf_searches,
+            status: Arc::default(),
+        }
+    }
+
+    pub fn status(&self) -> DecommissionStatus {
+        *self.status.lock().expect("lock should not be poisoned")
+    }
+
+    /// Marks the node as leaving the cluster, so that it stops accepting new ingest and search
+    /// requests, then drains its in-flight work in the background: the leaf searches running on
+    /// the node, and the work of its ingester and compactor. Subsequent calls do not start another
+    /// drain and return the progress of the first one.
+    pub async fn leave(&self) -> DecommissionStatus {
+        {
+            let mut status = self.status.lock().expect("lock should not be poisoned");
+
+            if *status != DecommissionStatus::NotRequested {
+                return *status;
+            }
+            *status = DecommissionStatus::Draining;
+        }
+        info!("node is leaving the cluster");
+        self.node_readiness.set_leaving().await;
+
+        let node_decommission = self.clone();
+        tokio::spawn(async move {
+            let status = if node_decommission.drain().await {
+                info!("node drained its in-flight work and can be shut down");
+                DecommissionStatus::Drained
+            } else {
+                DecommissionStatus::Failed
+            };
+            *node_decommission
+                .status
+                .lock()
+                .expect("lock should not be poisoned") = status;
+        });
+        DecommissionStatus::Draining
+    }
+
+    async fn drain(&self) -> bool {
+        let (is_node_drained, are_leaf_searches_finished) = tokio::join!(
+            drain_node(
+                self.ingester_opt.as_ref(),
+                self.compactor_supervisor_opt.as_ref()
+            ),
+            self.in_flight_leaf_searches
+                .wait_for_completion(DECOMMISSION_TIMEOUT),
+        );
+        if !are_leaf_searches_finished {
+            error!(
+                num_in_flight_leaf_searches = self.in_flight_leaf_searches.num_in_flight(),
+                "leaf searches did not finish within the decommission timeout"
+            );
+        }
+        is_node_drained && are_leaf_searches_finished
+    }
+}
+
+/// Decommissions the ingester and the compactor of the node, if any, and waits for them to
+/// finish their in-flight work. Returns whether both were decommissioned successfully.
+pub(crate) async fn drain_node(
+    ingester_opt: Option<&Ingester>,
+    compactor_supervisor_opt: Option<&Mailbox<CompactorSupervisor>>,
+) -> bool {
+    if let Err(error) = notify_ingester_decommission(ingester_opt).await {
+        error!("failed to initiate ingester decommission: {:?}", error);
+    }
+    let compactor_status_rx_opt = notify_compactor_decommission(compactor_supervisor_opt)
+        .await
+        .unwrap_or_else(|error| {
+            error!("failed to initiate compactor decommission: {:?}", error);
+            None
+        });
+    let (ingester_result, compactor_result) = tokio::join!(
+        wait_for_ingester_decommission(ingester_opt, DECOMMISSION_TIMEOUT),
+        wait_for_compactor_decommission(compactor_status_rx_opt, DECOMMISSION_TIMEOUT),
+    );
+    let mut is_drained = true;
+
+    if let Err(error) = ingester_result {
+        error!("failed to decommission ingester gracefully: {:?}", error);
+        is_drained = false;
+    }
+    if let Err(error) = compactor_result {
+        error!("failed to decommission compactor gracefully: {:?}", error);
+        is_drained = false;
+    }
+    is_drained
+}
+
+#[derive(Debug)]
+pub(crate) struct NodeLeaving;
+
+impl warp::reject::Reject for NodeLeaving {}
+
+impl fmt::Display for NodeLeaving {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "node is leaving the cluster: it no longer accepts ingest and search requests"
+        )
+    }
+}
+
+/// Rejects the requests with [`NodeLeaving`] once the node is leaving the cluster. It guards the
+/// ingest and search routes, the routes available to operators being tried first.
+pub(crate) fn node_leaving_filter(
+    node_readiness: NodeReadiness,
+) -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    with_arg(node_readiness)
+        .and_then(|node_readiness: NodeReadiness| async move {
+            if node_readiness.is_leaving() {
+                return Err(warp::reject::custom(NodeLeaving));
+            }
+            Ok(())
+        })
+        .untuple_one()
+}
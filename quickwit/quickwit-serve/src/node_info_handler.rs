@@ -18,6 +18,7 @@ use quickwit_config::NodeConfig;
 use serde_json::json;
 use warp::{Filter, Rejection};
 
+use crate::json_projection::with_json_projection;
 use crate::rest::recover_fn;
 use crate::{BuildInfo, RuntimeInfo, with_arg};
 
@@ -30,10 +31,11 @@ pub fn node_info_handler(
     runtime_info: &'static RuntimeInfo,
     config: Arc<NodeConfig>,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
-    node_version_handler(build_info, runtime_info)
-        .or(node_config_handler(config))
-        .recover(recover_fn)
-        .boxed()
+    with_json_projection(
+        node_version_handler(build_info, runtime_info).or(node_config_handler(config)),
+    )
+    .recover(recover_fn)
+    .boxed()
 }
 
 #[utoipa::path(get, tag = "Node Info", path = "/version")]
@@ -0,0 +1,149 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use quickwit_cluster::Cluster;
+use tonic_health::ServingStatus;
+use tonic_health::server::HealthReporter;
+
+/// Readiness of the node, shared by the REST `/health/readyz` endpoint, the gRPC health service,
+/// and the readiness advertised to the cluster, so that they never disagree.
+///
+/// Once the node is leaving the cluster, it remains not ready for good.
+#[derive(Clone)]
+pub(crate) struct NodeReadiness {
+    is_ready: Arc<AtomicBool>,
+    is_leaving: Arc<AtomicBool>,
+    cluster: Cluster,
+    health_reporter: HealthReporter,
+}
+
+impl NodeReadiness {
+    /// Creates the readiness of a node that is not ready yet.
+    pub async fn new(cluster: Cluster, health_reporter: HealthReporter) -> Self {
+        let node_readiness = NodeReadiness {
+            is_ready: Arc::new(AtomicBool::new(false)),
+            is_leaving: Arc::new(AtomicBool::new(false)),
+            cluster,
+            health_reporter,
+        };
+        node_readiness.set_ready(false).await;
+        node_readiness
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.is_ready.load(Ordering::Relaxed)
+    }
+
+    pub fn is_leaving(&self) -> bool {
+        self.is_leaving.load(Ordering::Relaxed)
+    }
+
+    /// Marks the node as leaving the cluster and as not ready.
+    pub async fn set_leaving(&self) {
+        self.is_leaving.store(true, Ordering::Relaxed);
+        self.cluster.set_self_node_leaving().await;
+        self.set_ready(false).await;
+    }
+
+    pub async fn set_ready(&self, is_ready: bool) {
+        let is_ready_and_staying = is_ready && !self.is_leaving();
+        self.cluster
+            .set_self_node_readiness(is_ready_and_staying)
+            .await;
+
+        let serving_status = if is_ready_and_staying {
+            ServingStatus::Serving
+        } else {
+            ServingStatus::NotServing
+        };
+        // The empty service name stands for the whole server, as per
+        // https://github.com/grpc/grpc/blob/master/doc/health-checking.md
+        self.health_reporter
+            .set_service_status("", serving_status)
+            .await;
+        self.is_ready.store(is_ready_and_staying, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use quickwit_cluster::{ChitchatTransport, create_cluster_for_test};
+    use tonic::transport::{Channel, Server};
+    use tonic_health::pb::HealthCheckRequest;
+    use tonic_health::pb::health_client::HealthClient;
+    use tonic_health::server::health_reporter;
+    use warp::hyper::StatusCode;
+
+    use super::*;
+    use crate::health_check_api::health_check_handlers;
+
+    #[tokio::test]
+    async fn test_node_readiness_rest_and_grpc_parity() {
+        let transport = ChitchatTransport::default();
+        let cluster = create_cluster_for_test(Vec::new(), &[], &transport, false)
+            .await
+            .unwrap();
+        let (health_reporter, health_service) = health_reporter();
+        let (client, server) = tokio::io::duplex(1024);
+        tokio::spawn(async move {
+            Server::builder()
+                .add_service(health_service)
+                .serve_with_incoming(tokio_stream::once(Ok::<_, std::io::Error>(server)))
+                .await
+                .unwrap();
+        });
+        let mut client_opt = Some(client);
+        let connector = tower::service_fn(move |_: http::Uri| {
+            let client = client_opt.take().unwrap();
+            async move { Ok::<_, Infallible>(hyper_util::rt::TokioIo::new(client)) }
+        });
+        let channel = Channel::builder("http://[::]:50051".parse().unwrap())
+            .connect_with_connector(connector)
+            .await
+            .unwrap();
+        let mut health_client = HealthClient::new(channel);
+
+        let node_readiness = NodeReadiness::new(cluster.clone(), health_reporter).await;
+        let health_check_handler = health_check_handlers(node_readiness.clone(), None, None);
+
+        for transition_opt in [None, Some(true), Some(false), Some(true)] {
+            if let Some(is_ready) = transition_opt {
+                node_readiness.set_ready(is_ready).await;
+            }
+            let expected_is_ready = transition_opt.unwrap_or(false);
+
+            let rest_response = warp::test::request()
+                .path("/health/readyz")
+                .reply(&health_check_handler)
+                .await;
+            let rest_is_ready = rest_response.status() == StatusCode::OK;
+
+            let grpc_response = health_client
+                .check(HealthCheckRequest::default())
+                .await
+                .unwrap()
+                .into_inner();
+            let grpc_is_ready = grpc_response.status() == ServingStatus::Serving.into();
+
+            assert_eq!(rest_is_ready, grpc_is_ready);
+            assert_eq!(rest_is_ready, expected_is_ready);
+            assert_eq!(cluster.is_self_node_ready().await, expected_is_ready);
+        }
+    }
+}
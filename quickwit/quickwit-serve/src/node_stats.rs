@@ -0,0 +1,158 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{BTreeMap, HashSet};
+
+use quickwit_config::service::QuickwitService;
+use quickwit_metrics::counter_total;
+use serde::{Deserialize, Serialize};
+
+/// Counters reported for the services enabled on the node, as `(service, stat, metric)`.
+const SERVICE_COUNTERS: &[(QuickwitService, &str, &str)] = &[
+    (
+        QuickwitService::Indexer,
+        "num_ingested_docs",
+        "quickwit_ingest_docs_total",
+    ),
+    (
+        QuickwitService::Indexer,
+        "num_ingested_bytes",
+        "quickwit_ingest_docs_bytes_total",
+    ),
+    (
+        QuickwitService::Searcher,
+        "num_root_search_requests",
+        "quickwit_search_root_search_requests_total",
+    ),
+    (
+        QuickwitService::Searcher,
+        "num_leaf_search_requests",
+        "quickwit_search_leaf_search_requests_total",
+    ),
+];
+
+/// Resource usage of a node and throughput of its services. Throughputs are reported as
+/// cumulative counters, like their Prometheus counterparts: rates are derived by comparing two
+/// consecutive snapshots.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub(crate) struct NodeStats {
+    /// Number of logical CPUs available to the node.
+    pub num_cpus: usize,
+    /// CPU time consumed by the process since it started, in seconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_time_secs: Option<f64>,
+    /// Resident memory of the process, in bytes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resident_memory_bytes: Option<u64>,
+    /// Number of file descriptors opened by the process.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub num_open_fds: Option<u64>,
+    /// Number of REST requests served by the node.
+    pub num_http_requests: u64,
+    /// Counters of the services enabled on the node, keyed by service name.
+    pub services: BTreeMap<String, BTreeMap<String, u64>>,
+}
+
+impl NodeStats {
+    /// Collects the stats of the node. The process stats are only available on Linux.
+    pub fn collect(enabled_services: &HashSet<QuickwitService>) -> Self {
+        let mut services: BTreeMap<String, BTreeMap<String, u64>> = enabled_services
+            .iter()
+            .map(|service| (service.to_string(), BTreeMap::new()))
+            .collect();
+
+        for (service, stat_name, metric_name) in SERVICE_COUNTERS {
+            if let Some(service_stats) = services.get_mut(service.as_str()) {
+                service_stats.insert(stat_name.to_string(), counter_total(metric_name));
+            }
+        }
+        let process_stats = ProcessStats::collect();
+
+        Self {
+            num_cpus: quickwit_common::num_cpus(),
+            cpu_time_secs: process_stats.cpu_time_secs,
+            resident_memory_bytes: process_stats.resident_memory_bytes,
+            num_open_fds: process_stats.num_open_fds,
+            num_http_requests: counter_total("quickwit_http_requests_total"),
+            services,
+        }
+    }
+}
+
+#[derive(Default)]
+struct ProcessStats {
+    cpu_time_secs: Option<f64>,
+    resident_memory_bytes: Option<u64>,
+    num_open_fds: Option<u64>,
+}
+
+impl ProcessStats {
+    #[cfg(target_os = "linux")]
+    fn collect() -> Self {
+        use procfs::process::Process;
+
+        let Ok(process) = Process::myself() else {
+            return Self::default();
+        };
+        let cpu_time_secs = process
+            .stat()
+            .ok()
+            .map(|stat| (stat.utime + stat.stime) as f64 / procfs::ticks_per_second() as f64);
+        let resident_memory_bytes = process
+            .statm()
+            .ok()
+            .map(|statm| statm.resident * procfs::page_size());
+        let num_open_fds = process.fd_count().ok().map(|fd_count| fd_count as u64);
+
+        Self {
+            cpu_time_secs,
+            resident_memory_bytes,
+            num_open_fds,
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn collect() -> Self {
+        Self::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_node_stats_collect() {
+        let enabled_services = HashSet::from([QuickwitService::Searcher, QuickwitService::Janitor]);
+        let node_stats = NodeStats::collect(&enabled_services);
+
+        assert!(node_stats.num_cpus > 0);
+        assert_eq!(node_stats.services.len(), 2);
+        assert!(node_stats.services["janitor"].is_empty());
+
+        let searcher_stats = &node_stats.services["searcher"];
+        assert!(searcher_stats.contains_key("num_root_search_requests"));
+        assert!(searcher_stats.contains_key("num_leaf_search_requests"));
+
+        #[cfg(target_os = "linux")]
+        {
+            assert!(node_stats.cpu_time_secs.is_some());
+            assert!(node_stats.resident_memory_bytes.unwrap() > 0);
+            assert!(node_stats.num_open_fds.unwrap() > 0);
+        }
+        let node_stats_json = serde_json::to_string(&node_stats).unwrap();
+        let deserialized_node_stats: NodeStats = serde_json::from_str(&node_stats_json).unwrap();
+        assert_eq!(deserialized_node_stats, node_stats);
+    }
+}
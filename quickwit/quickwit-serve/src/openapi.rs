@@ -23,14 +23,18 @@ use utoipa::OpenApi;
 use utoipa::openapi::Tag;
 
 use crate::cluster_api::ClusterApi;
+use crate::config_validation::ConfigValidationApi;
 use crate::delete_task_api::DeleteTaskApi;
 use crate::developer_api::DeveloperApi;
 use crate::elasticsearch_api::ElasticCompatibleApi;
 use crate::health_check_api::HealthCheckApi;
 use crate::index_api::IndexApi;
 use crate::indexing_api::IndexingApi;
-use crate::ingest_api::{IngestApi, IngestApiSchemas};
+use crate::ingest_api::{
+    DeadLetterQueueApi, IngestApi, IngestApiSchemas, IngestStreamApi, UploadSessionApi,
+};
 use crate::jaeger_api::JaegerApi;
+use crate::metastore_api::MetastoreApi;
 use crate::metrics_api::MetricsApi;
 use crate::node_info_handler::NodeInfoApi;
 use crate::otlp_api::OtlpApi;
@@ -81,6 +85,8 @@ pub fn build_docs() -> utoipa::openapi::OpenApi {
 
     // Routing
     docs_base.merge_components_and_paths(ClusterApi::openapi().with_path_prefix("/api/v1"));
+    docs_base
+        .merge_components_and_paths(ConfigValidationApi::openapi().with_path_prefix("/api/v1"));
     docs_base.merge_components_and_paths(DeleteTaskApi::openapi().with_path_prefix("/api/v1"));
     docs_base
         .merge_components_and_paths(DeveloperApi::openapi().with_path_prefix("/api/developer"));
@@ -92,7 +98,11 @@ pub fn build_docs() -> utoipa::openapi::OpenApi {
     docs_base.merge_components_and_paths(IndexingApi::openapi().with_path_prefix("/api/v1"));
     docs_base.merge_components_and_paths(IndexTemplateApi::openapi().with_path_prefix("/api/v1"));
     docs_base.merge_components_and_paths(IngestApi::openapi().with_path_prefix("/api/v1"));
+    docs_base.merge_components_and_paths(DeadLetterQueueApi::openapi().with_path_prefix("/api/v1"));
+    docs_base.merge_components_and_paths(IngestStreamApi::openapi().with_path_prefix("/api/v1"));
+    docs_base.merge_components_and_paths(UploadSessionApi::openapi().with_path_prefix("/api/v1"));
     docs_base.merge_components_and_paths(JaegerApi::openapi().with_path_prefix("/api/v1"));
+    docs_base.merge_components_and_paths(MetastoreApi::openapi().with_path_prefix("/api/v1"));
     docs_base.merge_components_and_paths(MetricsApi::openapi().with_path_prefix("/metrics"));
     docs_base.merge_components_and_paths(NodeInfoApi::openapi().with_path_prefix("/api/v1"));
     docs_base.merge_components_and_paths(SearchApi::openapi().with_path_prefix("/api/v1"));
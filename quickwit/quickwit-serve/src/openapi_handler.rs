@@ -0,0 +1,190 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io::Write;
+use std::sync::{Arc, OnceLock};
+
+use bytes::Bytes;
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use utoipa::openapi::OpenApi;
+use warp::hyper::header::{CONTENT_ENCODING, CONTENT_TYPE, VARY};
+use warp::hyper::http::HeaderValue;
+use warp::reply::Response;
+use warp::{Filter, Rejection};
+
+use crate::openapi::build_docs;
+use crate::rest::recover_fn;
+
+/// OpenAPI document serialized as JSON, along with its gzip-compressed version.
+struct OpenApiDocBytes {
+    json: Bytes,
+    gzipped_json: Bytes,
+}
+
+/// Builds and serializes the OpenAPI document once, on the first request, and serves the cached
+/// bytes to all the requests.
+#[derive(Clone)]
+struct OpenApiDocCache {
+    build_docs_fn: fn() -> OpenApi,
+    doc_bytes: Arc<OnceLock<OpenApiDocBytes>>,
+}
+
+impl OpenApiDocCache {
+    fn new(build_docs_fn: fn() -> OpenApi) -> Self {
+        Self {
+            build_docs_fn,
+            doc_bytes: Arc::new(OnceLock::new()),
+        }
+    }
+
+    fn doc_bytes(&self) -> &OpenApiDocBytes {
+        self.doc_bytes.get_or_init(|| {
+            let docs = (self.build_docs_fn)();
+            let json = serde_json::to_vec(&docs).expect("OpenAPI docs should be JSON serializable");
+            let mut gz_encoder = GzEncoder::new(Vec::new(), Compression::best());
+            gz_encoder
+                .write_all(&json)
+                .expect("writing to a vector should not fail");
+            let gzipped_json = gz_encoder
+                .finish()
+                .expect("writing to a vector should not fail");
+            OpenApiDocBytes {
+                json: Bytes::from(json),
+                gzipped_json: Bytes::from(gzipped_json),
+            }
+        })
+    }
+
+    /// Returns the gzip-compressed document to clients accepting it, and the plain document
+    /// otherwise.
+    fn reply(&self, accept_encoding_opt: Option<String>) -> Response {
+        let doc_bytes = self.doc_bytes();
+
+        let mut response = if let Some(accept_encoding) = accept_encoding_opt
+            && accepts_gzip(&accept_encoding)
+        {
+            let mut response = Response::new(doc_bytes.gzipped_json.clone().into());
+            response
+                .headers_mut()
+                .insert(CONTENT_ENCODING, HeaderValue::from_static("gzip"));
+            response
+        } else {
+            Response::new(doc_bytes.json.clone().into())
+        };
+        let headers = response.headers_mut();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        headers.insert(VARY, HeaderValue::from_static("accept-encoding"));
+        response
+    }
+}
+
+/// Returns whether the `Accept-Encoding` header value lists `gzip` with a non-zero weight.
+fn accepts_gzip(accept_encoding: &str) -> bool {
+    accept_encoding.split(',').any(|coding| {
+        let mut coding_parts = coding.split(';').map(str::trim);
+        let Some(coding_name) = coding_parts.next() else {
+            return false;
+        };
+        if !coding_name.eq_ignore_ascii_case("gzip") {
+            return false;
+        }
+        coding_parts
+            .filter_map(|param| param.strip_prefix("q="))
+            .all(|weight| weight.parse::<f32>().unwrap_or(0.0) > 0.0)
+    })
+}
+
+/// Serves the OpenAPI document at `/openapi.json`. The document is built on the first request
+/// only.
+pub(crate) fn openapi_doc_handler()
+-> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    openapi_doc_handler_inner(OpenApiDocCache::new(build_docs))
+}
+
+fn openapi_doc_handler_inner(
+    openapi_doc_cache: OpenApiDocCache,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path("openapi.json")
+        .and(warp::get())
+        .and(warp::header::optional::<String>("accept-encoding"))
+        .map(move |accept_encoding_opt| openapi_doc_cache.reply(accept_encoding_opt))
+        .recover(recover_fn)
+        .boxed()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use flate2::read::GzDecoder;
+
+    use super::*;
+
+    #[test]
+    fn test_accepts_gzip() {
+        assert!(accepts_gzip("gzip"));
+        assert!(accepts_gzip("deflate, GZIP;q=0.5, br"));
+        assert!(!accepts_gzip("deflate, br"));
+        assert!(!accepts_gzip("gzip;q=0"));
+        assert!(!accepts_gzip("x-gzip"));
+    }
+
+    #[tokio::test]
+    async fn test_openapi_doc_is_built_once() {
+        static NUM_BUILDS: AtomicUsize = AtomicUsize::new(0);
+
+        fn counting_build_docs() -> OpenApi {
+            NUM_BUILDS.fetch_add(1, Ordering::Relaxed);
+            build_docs()
+        }
+        let handler = openapi_doc_handler_inner(OpenApiDocCache::new(counting_build_docs));
+        assert_eq!(NUM_BUILDS.load(Ordering::Relaxed), 0);
+
+        let first_resp = warp::test::request()
+            .path("/openapi.json")
+            .reply(&handler)
+            .await;
+        assert_eq!(first_resp.status(), 200);
+        assert_eq!(first_resp.headers()[CONTENT_TYPE], "application/json");
+        assert!(first_resp.headers().get(CONTENT_ENCODING).is_none());
+
+        let second_resp = warp::test::request()
+            .path("/openapi.json")
+            .reply(&handler)
+            .await;
+        assert_eq!(second_resp.status(), 200);
+        assert_eq!(second_resp.body(), first_resp.body());
+
+        let docs: OpenApi = serde_json::from_slice(first_resp.body()).unwrap();
+        assert_eq!(docs.info.title, "Quickwit");
+
+        let gzipped_resp = warp::test::request()
+            .path("/openapi.json")
+            .header("accept-encoding", "gzip, deflate")
+            .reply(&handler)
+            .await;
+        assert_eq!(gzipped_resp.status(), 200);
+        assert_eq!(gzipped_resp.headers()[CONTENT_ENCODING], "gzip");
+
+        let mut json = Vec::new();
+        GzDecoder::new(&gzipped_resp.body()[..])
+            .read_to_end(&mut json)
+            .unwrap();
+        assert_eq!(&json[..], &first_resp.body()[..]);
+
+        assert_eq!(NUM_BUILDS.load(Ordering::Relaxed), 1);
+    }
+}
@@ -12,8 +12,13 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::BTreeMap;
+
 use quickwit_common::rate_limited_error;
+use quickwit_config::merge_response_headers;
+use quickwit_ingest::IngestServiceError;
 use quickwit_opentelemetry::otlp::{OtelSignal, OtlpGrpcLogsService, OtlpGrpcTracesService};
+use quickwit_proto::metastore::MetastoreServiceClient;
 use quickwit_proto::opentelemetry::proto::collector::logs::v1::logs_service_server::LogsService;
 use quickwit_proto::opentelemetry::proto::collector::logs::v1::{
     ExportLogsServiceRequest, ExportLogsServiceResponse,
@@ -28,8 +33,10 @@ use serde::{self, Serialize};
 use warp::{Filter, Rejection};
 
 use crate::decompression::get_body_bytes;
+use crate::index_api::{IndexMetadataCache, ensure_indexes_writable};
+use crate::ingest_api::{IndexAllowList, ensure_content_type_accepted};
 use crate::rest::recover_fn;
-use crate::rest_api_response::into_rest_api_response;
+use crate::rest_api_response::into_rest_api_response_with_index_headers;
 use crate::{Body, BodyFormat, require, with_arg};
 
 #[derive(utoipa::OpenApi)]
@@ -41,16 +48,40 @@ use crate::{Body, BodyFormat, require, with_arg};
 ))]
 pub struct OtlpApi;
 
+const OTLP_PROTOBUF_CONTENT_TYPE: &str = "application/x-protobuf";
+
 /// Setup OpenTelemetry API handlers.
 pub(crate) fn otlp_ingest_api_handlers(
     otlp_logs_service: Option<OtlpGrpcLogsService>,
     otlp_traces_service: Option<OtlpGrpcTracesService>,
+    metastore: MetastoreServiceClient,
+    index_allow_list_opt: Option<IndexAllowList>,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
-    otlp_default_logs_handler(otlp_logs_service.clone())
-        .or(otlp_default_traces_handler(otlp_traces_service.clone()).recover(recover_fn))
-        .or(otlp_logs_handler(otlp_logs_service).recover(recover_fn))
-        .or(otlp_ingest_traces_handler(otlp_traces_service).recover(recover_fn))
-        .boxed()
+    let index_metadata_cache = IndexMetadataCache::new(metastore);
+    otlp_default_logs_handler(
+        otlp_logs_service.clone(),
+        index_metadata_cache.clone(),
+        index_allow_list_opt.clone(),
+    )
+    .or(otlp_default_traces_handler(
+        otlp_traces_service.clone(),
+        index_metadata_cache.clone(),
+        index_allow_list_opt.clone(),
+    )
+    .recover(recover_fn))
+    .or(otlp_logs_handler(
+        otlp_logs_service,
+        index_metadata_cache.clone(),
+        index_allow_list_opt.clone(),
+    )
+    .recover(recover_fn))
+    .or(otlp_ingest_traces_handler(
+        otlp_traces_service,
+        index_metadata_cache,
+        index_allow_list_opt,
+    )
+    .recover(recover_fn))
+    .boxed()
 }
 
 /// Open Telemetry REST/Protobuf logs ingest endpoint.
@@ -65,12 +96,16 @@ pub(crate) fn otlp_ingest_api_handlers(
 )]
 pub(crate) fn otlp_default_logs_handler(
     otlp_logs_service: Option<OtlpGrpcLogsService>,
+    index_metadata_cache: IndexMetadataCache,
+    index_allow_list_opt: Option<IndexAllowList>,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
     require(otlp_logs_service)
+        .and(with_arg(index_metadata_cache))
+        .and(with_arg(index_allow_list_opt))
         .and(warp::path!("otlp" / "v1" / "logs"))
         .and(warp::header::exact_ignore_case(
             "content-type",
-            "application/x-protobuf",
+            OTLP_PROTOBUF_CONTENT_TYPE,
         ))
         .and(warp::header::optional::<String>(
             OtelSignal::Logs.header_name(),
@@ -78,14 +113,25 @@ pub(crate) fn otlp_default_logs_handler(
         .and(warp::post())
         .and(get_body_bytes())
         .then(
-            |otlp_logs_service, index_id: Option<String>, body| async move {
+            |otlp_logs_service,
+             index_metadata_cache,
+             index_allow_list_opt,
+             index_id: Option<String>,
+             body| async move {
                 let index_id =
                     index_id.unwrap_or_else(|| OtelSignal::Logs.default_index_id().to_string());
-                otlp_ingest_logs(otlp_logs_service, index_id, body).await
+                otlp_ingest_logs(
+                    otlp_logs_service,
+                    index_metadata_cache,
+                    index_allow_list_opt,
+                    index_id,
+                    body,
+                )
+                .await
             },
         )
         .and(with_arg(BodyFormat::default()))
-        .map(into_rest_api_response)
+        .map(into_rest_api_response_with_index_headers)
         .boxed()
 }
 /// Open Telemetry REST/Protobuf logs ingest endpoint.
@@ -100,18 +146,22 @@ pub(crate) fn otlp_default_logs_handler(
 )]
 pub(crate) fn otlp_logs_handler(
     otlp_log_service: Option<OtlpGrpcLogsService>,
+    index_metadata_cache: IndexMetadataCache,
+    index_allow_list_opt: Option<IndexAllowList>,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
     require(otlp_log_service)
+        .and(with_arg(index_metadata_cache))
+        .and(with_arg(index_allow_list_opt))
         .and(warp::path!(String / "otlp" / "v1" / "logs"))
         .and(warp::header::exact_ignore_case(
             "content-type",
-            "application/x-protobuf",
+            OTLP_PROTOBUF_CONTENT_TYPE,
         ))
         .and(warp::post())
         .and(get_body_bytes())
         .then(otlp_ingest_logs)
         .and(with_arg(BodyFormat::default()))
-        .map(into_rest_api_response)
+        .map(into_rest_api_response_with_index_headers)
         .boxed()
 }
 
@@ -127,12 +177,16 @@ pub(crate) fn otlp_logs_handler(
 )]
 pub(crate) fn otlp_default_traces_handler(
     otlp_traces_service: Option<OtlpGrpcTracesService>,
+    index_metadata_cache: IndexMetadataCache,
+    index_allow_list_opt: Option<IndexAllowList>,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
     require(otlp_traces_service)
+        .and(with_arg(index_metadata_cache))
+        .and(with_arg(index_allow_list_opt))
         .and(warp::path!("otlp" / "v1" / "traces"))
         .and(warp::header::exact_ignore_case(
             "content-type",
-            "application/x-protobuf",
+            OTLP_PROTOBUF_CONTENT_TYPE,
         ))
         .and(warp::header::optional::<String>(
             OtelSignal::Traces.header_name(),
@@ -140,14 +194,25 @@ pub(crate) fn otlp_default_traces_handler(
         .and(warp::post())
         .and(get_body_bytes())
         .then(
-            |otlp_traces_service, index_id: Option<String>, body| async move {
+            |otlp_traces_service,
+             index_metadata_cache,
+             index_allow_list_opt,
+             index_id: Option<String>,
+             body| async move {
                 let index_id =
                     index_id.unwrap_or_else(|| OtelSignal::Traces.default_index_id().to_string());
-                otlp_ingest_traces(otlp_traces_service, index_id, body).await
+                otlp_ingest_traces(
+                    otlp_traces_service,
+                    index_metadata_cache,
+                    index_allow_list_opt,
+                    index_id,
+                    body,
+                )
+                .await
             },
         )
         .and(with_arg(BodyFormat::default()))
-        .map(into_rest_api_response)
+        .map(into_rest_api_response_with_index_headers)
         .boxed()
 }
 /// Open Telemetry REST/Protobuf traces ingest endpoint.
@@ -162,18 +227,22 @@ pub(crate) fn otlp_default_traces_handler(
 )]
 pub(crate) fn otlp_ingest_traces_handler(
     otlp_traces_service: Option<OtlpGrpcTracesService>,
+    index_metadata_cache: IndexMetadataCache,
+    index_allow_list_opt: Option<IndexAllowList>,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
     require(otlp_traces_service)
+        .and(with_arg(index_metadata_cache))
+        .and(with_arg(index_allow_list_opt))
         .and(warp::path!(String / "otlp" / "v1" / "traces"))
         .and(warp::header::exact_ignore_case(
             "content-type",
-            "application/x-protobuf",
+            OTLP_PROTOBUF_CONTENT_TYPE,
         ))
         .and(warp::post())
         .and(get_body_bytes())
         .then(otlp_ingest_traces)
         .and(with_arg(BodyFormat::default()))
-        .map(into_rest_api_response)
+        .map(into_rest_api_response_with_index_headers)
         .boxed()
 }
 
@@ -183,6 +252,25 @@ pub enum OtlpApiError {
     InvalidPayload(String),
     #[error("error when ingesting payload: {0}")]
     Ingest(String),
+    #[error("{0}")]
+    UnsupportedMediaType(String),
+    #[error("{0}")]
+    Forbidden(String),
+    #[error("{0}")]
+    Unavailable(String),
+}
+
+impl From<IngestServiceError> for OtlpApiError {
+    fn from(error: IngestServiceError) -> Self {
+        match error {
+            IngestServiceError::Forbidden(message) => OtlpApiError::Forbidden(message),
+            IngestServiceError::Unavailable(message) => OtlpApiError::Unavailable(message),
+            IngestServiceError::UnsupportedMediaType(message) => {
+                OtlpApiError::UnsupportedMediaType(message)
+            }
+            other_error => OtlpApiError::Ingest(other_error.to_string()),
+        }
+    }
 }
 
 impl ServiceError for OtlpApiError {
@@ -193,15 +281,53 @@ impl ServiceError for OtlpApiError {
                 rate_limited_error!(limit_per_min = 6, "otlp internal error: {err_msg}");
                 ServiceErrorCode::Internal
             }
+            OtlpApiError::UnsupportedMediaType(_) => ServiceErrorCode::UnsupportedMediaType,
+            OtlpApiError::Forbidden(_) => ServiceErrorCode::Forbidden,
+            OtlpApiError::Unavailable(_) => ServiceErrorCode::Unavailable,
         }
     }
 }
 
+/// Rejects the requests targeting an index outside of `ingest_api.index_allow_list`.
+fn ensure_index_allowed(
+    index_allow_list_opt: &Option<IndexAllowList>,
+    index_id: &IndexId,
+) -> Result<(), OtlpApiError> {
+    if let Some(index_allow_list) = index_allow_list_opt {
+        index_allow_list
+            .check(index_id)
+            .map_err(|error| OtlpApiError::Forbidden(error.to_string()))?;
+    }
+    Ok(())
+}
+
+/// Rejects the requests targeting an index that is not writable or does not accept OTLP protobuf
+/// payloads, and returns the response headers declared in the config of the index. The metadata of
+/// the index is read from the cache shared with the other ingest endpoints. Missing indexes are
+/// ignored: the ingest path reports them itself.
+async fn ensure_protobuf_accepted(
+    index_metadata_cache: &IndexMetadataCache,
+    index_id: &IndexId,
+) -> Result<BTreeMap<String, String>, OtlpApiError> {
+    let indexes_metadata =
+        ensure_indexes_writable(index_metadata_cache, [index_id.clone()]).await?;
+
+    let Some(index_metadata) = indexes_metadata.first() else {
+        return Ok(BTreeMap::new());
+    };
+    ensure_content_type_accepted(index_metadata, Some(OTLP_PROTOBUF_CONTENT_TYPE))?;
+    Ok(merge_response_headers([&index_metadata.index_config]))
+}
+
 async fn otlp_ingest_logs(
     otlp_logs_service: OtlpGrpcLogsService,
+    index_metadata_cache: IndexMetadataCache,
+    index_allow_list_opt: Option<IndexAllowList>,
     index_id: IndexId,
     body: Body,
-) -> Result<ExportLogsServiceResponse, OtlpApiError> {
+) -> Result<(ExportLogsServiceResponse, BTreeMap<String, String>), OtlpApiError> {
+    ensure_index_allowed(&index_allow_list_opt, &index_id)?;
+    let index_response_headers = ensure_protobuf_accepted(&index_metadata_cache, &index_id).await?;
     let export_logs_request: ExportLogsServiceRequest =
         prost::Message::decode(&body.content[..])
             .map_err(|err| OtlpApiError::InvalidPayload(err.to_string()))?;
@@ -216,14 +342,18 @@ async fn otlp_ingest_logs(
         .export(request)
         .await
         .map_err(|err| OtlpApiError::Ingest(err.to_string()))?;
-    Ok(result.into_inner())
+    Ok((result.into_inner(), index_response_headers))
 }
 
 async fn otlp_ingest_traces(
     otlp_traces_service: OtlpGrpcTracesService,
+    index_metadata_cache: IndexMetadataCache,
+    index_allow_list_opt: Option<IndexAllowList>,
     index_id: IndexId,
     body: Body,
-) -> Result<ExportTraceServiceResponse, OtlpApiError> {
+) -> Result<(ExportTraceServiceResponse, BTreeMap<String, String>), OtlpApiError> {
+    ensure_index_allowed(&index_allow_list_opt, &index_id)?;
+    let index_response_headers = ensure_protobuf_accepted(&index_metadata_cache, &index_id).await?;
     let export_traces_request: ExportTraceServiceRequest =
         prost::Message::decode(&body.content[..])
             .map_err(|err| OtlpApiError::InvalidPayload(err.to_string()))?;
@@ -238,23 +368,30 @@ async fn otlp_ingest_traces(
         .export(request)
         .await
         .map_err(|err| OtlpApiError::Ingest(err.to_string()))?;
-    Ok(response.into_inner())
+    Ok((response.into_inner(), index_response_headers))
 }
 
 #[cfg(test)]
 mod tests {
+    use std::collections::BTreeMap;
     use std::io::Write;
 
     use flate2::Compression;
     use flate2::write::GzEncoder;
     use prost::Message;
+    use quickwit_config::IndexConfig;
     use quickwit_ingest::CommitType;
+    use quickwit_metastore::{CreateIndexRequestExt, metastore_for_test};
     use quickwit_opentelemetry::otlp::{
         OtlpGrpcLogsService, OtlpGrpcTracesService, make_resource_spans_for_test,
     };
     use quickwit_proto::ingest::router::{
         IngestResponseV2, IngestRouterServiceClient, IngestSuccess, MockIngestRouterService,
     };
+    use quickwit_proto::metastore::{
+        CreateIndexRequest, MetastoreError, MetastoreService, MetastoreServiceClient,
+        MockMetastoreService,
+    };
     use quickwit_proto::opentelemetry::proto::collector::logs::v1::{
         ExportLogsServiceRequest, ExportLogsServiceResponse,
     };
@@ -265,7 +402,7 @@ mod tests {
     use quickwit_proto::opentelemetry::proto::resource::v1::Resource;
     use warp::Filter;
 
-    use super::otlp_ingest_api_handlers;
+    use super::{IndexAllowList, otlp_ingest_api_handlers};
     use crate::rest::recover_fn;
 
     fn compress(body: &[u8]) -> Vec<u8> {
@@ -352,8 +489,13 @@ mod tests {
             }],
         };
         let body = export_logs_request.encode_to_vec();
-        let otlp_traces_api_handler =
-            otlp_ingest_api_handlers(Some(logs_service), Some(traces_service)).recover(recover_fn);
+        let otlp_traces_api_handler = otlp_ingest_api_handlers(
+            Some(logs_service),
+            Some(traces_service),
+            metastore_for_test(),
+            None,
+        )
+        .recover(recover_fn);
         {
             // Test default otlp endpoint
             let resp = warp::test::request()
@@ -498,8 +640,13 @@ mod tests {
             resource_spans: make_resource_spans_for_test(),
         };
         let body = export_trace_request.encode_to_vec();
-        let otlp_traces_api_handler =
-            otlp_ingest_api_handlers(Some(logs_service), Some(traces_service)).recover(recover_fn);
+        let otlp_traces_api_handler = otlp_ingest_api_handlers(
+            Some(logs_service),
+            Some(traces_service),
+            metastore_for_test(),
+            None,
+        )
+        .recover(recover_fn);
         {
             // Test default otlp endpoint
             let resp = warp::test::request()
@@ -563,4 +710,166 @@ mod tests {
             assert_eq!(actual_response.partial_success.unwrap().rejected_spans, 0);
         }
     }
+
+    #[tokio::test]
+    async fn test_otlp_ingest_rejects_index_not_accepting_protobuf() {
+        let metastore = metastore_for_test();
+        let mut index_config = IndexConfig::for_test("ndjson-only", "ram:///indexes/ndjson-only");
+        index_config.ingest_settings.accepted_content_types =
+            vec!["application/x-ndjson".to_string()];
+        let create_index_request =
+            CreateIndexRequest::try_from_index_config(&index_config).unwrap();
+        metastore.create_index(create_index_request).await.unwrap();
+
+        let mut mock_ingest_router = MockIngestRouterService::new();
+        mock_ingest_router.expect_ingest().never();
+        let ingest_router = IngestRouterServiceClient::from_mock(mock_ingest_router);
+        let logs_service = OtlpGrpcLogsService::new(ingest_router.clone());
+        let traces_service = OtlpGrpcTracesService::new(ingest_router, Some(CommitType::Force));
+        let otlp_api_handler =
+            otlp_ingest_api_handlers(Some(logs_service), Some(traces_service), metastore, None)
+                .recover(recover_fn);
+        let export_trace_request = ExportTraceServiceRequest {
+            resource_spans: make_resource_spans_for_test(),
+        };
+        let resp = warp::test::request()
+            .path("/ndjson-only/otlp/v1/traces")
+            .method("POST")
+            .header("content-type", "application/x-protobuf")
+            .body(export_trace_request.encode_to_vec())
+            .reply(&otlp_api_handler)
+            .await;
+        assert_eq!(resp.status(), 415);
+        let response_body = std::str::from_utf8(resp.body()).unwrap();
+        assert!(response_body.contains("does not accept content type `application/x-protobuf`"));
+    }
+
+    #[tokio::test]
+    async fn test_otlp_ingest_index_response_headers() {
+        let metastore = metastore_for_test();
+        let mut index_config = IndexConfig::for_test("otel-logs", "ram:///indexes/otel-logs");
+        index_config.response_headers = BTreeMap::from([(
+            "X-Data-Classification".to_string(),
+            "confidential".to_string(),
+        )]);
+        let create_index_request =
+            CreateIndexRequest::try_from_index_config(&index_config).unwrap();
+        metastore.create_index(create_index_request).await.unwrap();
+
+        let mut mock_ingest_router = MockIngestRouterService::new();
+        mock_ingest_router.expect_ingest().returning(|_| {
+            Ok(IngestResponseV2 {
+                successes: vec![IngestSuccess {
+                    num_ingested_docs: 1,
+                    ..Default::default()
+                }],
+                failures: Vec::new(),
+            })
+        });
+        let ingest_router = IngestRouterServiceClient::from_mock(mock_ingest_router);
+        let logs_service = OtlpGrpcLogsService::new(ingest_router.clone());
+        let traces_service = OtlpGrpcTracesService::new(ingest_router, Some(CommitType::Force));
+        let otlp_api_handler =
+            otlp_ingest_api_handlers(Some(logs_service), Some(traces_service), metastore, None)
+                .recover(recover_fn);
+        let export_logs_request = ExportLogsServiceRequest {
+            resource_logs: vec![ResourceLogs {
+                resource: Some(Resource {
+                    attributes: Vec::new(),
+                    dropped_attributes_count: 0,
+                }),
+                scope_logs: vec![ScopeLogs {
+                    log_records: vec![LogRecord {
+                        body: None,
+                        attributes: Vec::new(),
+                        dropped_attributes_count: 0,
+                        time_unix_nano: 1704036033047000000,
+                        severity_number: 0,
+                        severity_text: "ERROR".to_string(),
+                        span_id: Vec::new(),
+                        trace_id: Vec::new(),
+                        flags: 0,
+                        observed_time_unix_nano: 0,
+                    }],
+                    scope: None,
+                    schema_url: "".to_string(),
+                }],
+                schema_url: "".to_string(),
+            }],
+        };
+        let resp = warp::test::request()
+            .path("/otel-logs/otlp/v1/logs")
+            .method("POST")
+            .header("content-type", "application/x-protobuf")
+            .body(export_logs_request.encode_to_vec())
+            .reply(&otlp_api_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        assert_eq!(resp.headers()["x-data-classification"], "confidential");
+    }
+
+    #[tokio::test]
+    async fn test_otlp_ingest_rejects_index_not_allowed() {
+        let mut mock_ingest_router = MockIngestRouterService::new();
+        mock_ingest_router.expect_ingest().never();
+        let ingest_router = IngestRouterServiceClient::from_mock(mock_ingest_router);
+        let logs_service = OtlpGrpcLogsService::new(ingest_router.clone());
+        let traces_service = OtlpGrpcTracesService::new(ingest_router, Some(CommitType::Force));
+        let index_allow_list = IndexAllowList::try_new(&["logs-*".to_string()]).unwrap();
+        let otlp_api_handler = otlp_ingest_api_handlers(
+            Some(logs_service),
+            Some(traces_service),
+            metastore_for_test(),
+            Some(index_allow_list),
+        )
+        .recover(recover_fn);
+        let export_trace_request = ExportTraceServiceRequest {
+            resource_spans: make_resource_spans_for_test(),
+        };
+        let resp = warp::test::request()
+            .path("/otlp/v1/traces")
+            .method("POST")
+            .header("content-type", "application/x-protobuf")
+            .body(export_trace_request.encode_to_vec())
+            .reply(&otlp_api_handler)
+            .await;
+        assert_eq!(resp.status(), 403);
+        let response_body = std::str::from_utf8(resp.body()).unwrap();
+        assert!(response_body.contains("is not allowed on this node"));
+    }
+
+    #[tokio::test]
+    async fn test_otlp_ingest_metastore_unavailable() {
+        let mut mock_metastore = MockMetastoreService::new();
+        mock_metastore
+            .expect_list_indexes_metadata()
+            .returning(|_| {
+                Err(MetastoreError::Unavailable(
+                    "connection refused".to_string(),
+                ))
+            });
+        let mut mock_ingest_router = MockIngestRouterService::new();
+        mock_ingest_router.expect_ingest().never();
+        let ingest_router = IngestRouterServiceClient::from_mock(mock_ingest_router);
+        let logs_service = OtlpGrpcLogsService::new(ingest_router.clone());
+        let traces_service = OtlpGrpcTracesService::new(ingest_router, Some(CommitType::Force));
+        let otlp_api_handler = otlp_ingest_api_handlers(
+            Some(logs_service),
+            Some(traces_service),
+            MetastoreServiceClient::from_mock(mock_metastore),
+            None,
+        )
+        .recover(recover_fn);
+        let export_trace_request = ExportTraceServiceRequest {
+            resource_spans: make_resource_spans_for_test(),
+        };
+        let resp = warp::test::request()
+            .path("/otlp/v1/traces")
+            .method("POST")
+            .header("content-type", "application/x-protobuf")
+            .body(export_trace_request.encode_to_vec())
+            .reply(&otlp_api_handler)
+            .await;
+        assert_eq!(resp.status(), 503);
+    }
 }
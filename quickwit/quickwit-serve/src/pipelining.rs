@@ -0,0 +1,173 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Limits the number of HTTP/1.1 requests a client pipelines on a connection, i.e. sends without
+//! waiting for the responses to the previous ones.
+//!
+//! hyper serves the pipelined requests of a connection one at a time and writes their responses in
+//! request order, buffering the requests waiting to be served. It only reads from the connection
+//! once these buffered requests are exhausted: the requests served since the last read finding
+//! nothing to read were all sent without waiting for a response.
+
+use std::io;
+use std::num::NonZeroUsize;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::task::{Context, Poll};
+
+use futures::future::{BoxFuture, FutureExt};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tower::{BoxError, Service};
+use tracing::warn;
+use warp::hyper::http::{self, Version};
+
+/// Number of requests served on a connection since the client last had nothing to send, shared
+/// between the I/O of the connection and the service serving its requests.
+#[derive(Clone, Default)]
+pub(crate) struct PipelinedRequests {
+    num_requests: Arc<AtomicUsize>,
+}
+
+/// Wraps the I/O of a connection to detect when the client has nothing left to send.
+pub(crate) struct PipelineTrackingIo<T> {
+    inner: T,
+    pipelined_requests: PipelinedRequests,
+}
+
+impl<T> PipelineTrackingIo<T> {
+    pub(crate) fn new(inner: T) -> (Self, PipelinedRequests) {
+        let pipelined_requests = PipelinedRequests::default();
+        let io = Self {
+            inner,
+            pipelined_requests: pipelined_requests.clone(),
+        };
+        (io, pipelined_requests)
+    }
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for PipelineTrackingIo<T> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let poll = Pin::new(&mut this.inner).poll_read(cx, buf);
+
+        if poll.is_pending() {
+            this.pipelined_requests
+                .num_requests
+                .store(0, Ordering::Relaxed);
+        }
+        poll
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for PipelineTrackingIo<T> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().inner).poll_write(cx, buf)
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().inner).poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.inner.is_write_vectored()
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_shutdown(cx)
+    }
+}
+
+/// Closes the connections pipelining more than `max_pipelined_requests` HTTP/1.1 requests. The
+/// request exceeding the limit fails, which makes hyper close the connection without serving the
+/// requests buffered after it. HTTP/2 requests are multiplexed rather than pipelined and are not
+/// limited.
+#[derive(Clone)]
+pub(crate) struct MaxPipelinedRequestsService<S> {
+    inner: S,
+    pipelined_requests: PipelinedRequests,
+    max_pipelined_requests_opt: Option<NonZeroUsize>,
+}
+
+impl<S> MaxPipelinedRequestsService<S> {
+    pub fn new(
+        inner: S,
+        pipelined_requests: PipelinedRequests,
+        max_pipelined_requests_opt: Option<NonZeroUsize>,
+    ) -> Self {
+        Self {
+            inner,
+            pipelined_requests,
+            max_pipelined_requests_opt,
+        }
+    }
+}
+
+impl<S, B> Service<http::Request<B>> for MaxPipelinedRequestsService<S>
+where
+    S: Service<http::Request<B>>,
+    S::Error: Into<BoxError>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = BoxError;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        if let Some(max_pipelined_requests) = self.max_pipelined_requests_opt
+            && request.version() < Version::HTTP_2
+        {
+            let num_requests = self
+                .pipelined_requests
+                .num_requests
+                .fetch_add(1, Ordering::Relaxed)
+                + 1;
+
+            if num_requests > max_pipelined_requests.get() {
+                warn!(
+                    max_pipelined_requests = max_pipelined_requests.get(),
+                    "closing connection pipelining too many requests"
+                );
+                let error = BoxError::from(format!(
+                    "connection pipelined more than {max_pipelined_requests} requests"
+                ));
+                return futures::future::ready(Err(error)).boxed();
+            }
+        }
+        self.inner
+            .call(request)
+            .map(|result| result.map_err(Into::into))
+            .boxed()
+    }
+}
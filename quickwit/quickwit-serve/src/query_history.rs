@@ -0,0 +1,275 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Keeps the recent searches of each index, so that operators can spot the expensive or frequent
+//! queries to optimize with `GET /api/v1/indexes/{index_id}/_query_history`.
+//!
+//! The searches are kept in memory in a bounded ring buffer per index, for a bounded number of
+//! indexes: each node only reports the searches it served. The values matched by the queries are
+//! redacted, only their structure and the fields they target are kept.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use quickwit_common::truncate_str;
+use quickwit_proto::types::IndexId;
+use quickwit_query::query_ast::QueryAst;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+use crate::request_scope::ensure_indexes_in_scope;
+
+/// Number of searches kept for each index.
+const MAX_QUERIES_PER_INDEX: usize = 100;
+
+/// Number of indexes whose searches are kept. Beyond that, the history of the index searched least
+/// recently is dropped, so that deleted indexes are eventually forgotten.
+const MAX_INDEXES: usize = 1_000;
+
+/// Maximum length of the redacted queries, in bytes.
+const MAX_QUERY_LEN: usize = 1_024;
+
+/// Placeholder replacing the values matched by the queries.
+const REDACTED_VALUE: &str = "<redacted>";
+
+/// Keys of the query AST holding the values matched by the queries.
+const VALUE_KEYS: [&str; 8] = [
+    "lower_bound",
+    "phrase",
+    "regex",
+    "terms_per_field",
+    "text",
+    "upper_bound",
+    "user_text",
+    "value",
+];
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct QueryHistoryEntry {
+    /// Time of the end of the search, as a Unix timestamp in seconds.
+    pub timestamp: u64,
+    /// Query AST of the search, as JSON, with the values it matches replaced by `<redacted>`.
+    pub query: String,
+    /// Number of documents matching the query.
+    pub num_hits: u64,
+    /// Time taken to run the search, in microseconds.
+    pub elapsed_time_micros: u64,
+}
+
+/// Recent searches of the indexes, shared by the requests served by the node.
+pub(crate) struct QueryHistory {
+    max_queries_per_index: usize,
+    max_indexes: usize,
+    indexes: Mutex<HashMap<IndexId, VecDeque<QueryHistoryEntry>>>,
+}
+
+/// Returns the recent searches of the indexes recorded by the node.
+pub(crate) fn query_history() -> &'static QueryHistory {
+    static QUERY_HISTORY: LazyLock<QueryHistory> =
+        LazyLock::new(|| QueryHistory::new(MAX_QUERIES_PER_INDEX, MAX_INDEXES));
+    &QUERY_HISTORY
+}
+
+impl QueryHistory {
+    fn new(max_queries_per_index: usize, max_indexes: usize) -> Self {
+        Self {
+            max_queries_per_index,
+            max_indexes,
+            indexes: Mutex::default(),
+        }
+    }
+
+    /// Records a successful search of the given indexes, i.e. the indexes matched by the index ID
+    /// patterns of the request.
+    ///
+    /// The indexes outside of the scope of the token of the request are skipped, so that a tenant
+    /// cannot fill the history of the indexes of another tenant.
+    pub fn record(
+        &self,
+        index_ids: &[IndexId],
+        query_ast_json: &str,
+        num_hits: u64,
+        elapsed: Duration,
+    ) {
+        let index_ids: Vec<&str> = index_ids
+            .iter()
+            .map(String::as_str)
+            .filter(|index_id| ensure_indexes_in_scope([*index_id]).is_ok())
+            .collect();
+
+        if index_ids.is_empty() {
+            return;
+        }
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed_since_epoch| elapsed_since_epoch.as_secs())
+            .unwrap_or_default();
+        let entry = QueryHistoryEntry {
+            timestamp,
+            query: redact_query(query_ast_json),
+            num_hits,
+            elapsed_time_micros: elapsed.as_micros() as u64,
+        };
+        let mut indexes = self.indexes.lock().expect("lock should not be poisoned");
+
+        for index_id in index_ids {
+            if !indexes.contains_key(index_id) && indexes.len() >= self.max_indexes {
+                evict_least_recently_searched_index(&mut indexes);
+            }
+            let index_queries = indexes.entry(index_id.to_string()).or_default();
+
+            if index_queries.len() == self.max_queries_per_index {
+                index_queries.pop_front();
+            }
+            index_queries.push_back(entry.clone());
+        }
+    }
+
+    /// Returns the recent searches of the index, from the oldest to the newest.
+    pub fn get(&self, index_id: &str) -> Vec<QueryHistoryEntry> {
+        let indexes = self.indexes.lock().expect("lock should not be poisoned");
+
+        let Some(index_queries) = indexes.get(index_id) else {
+            return Vec::new();
+        };
+        index_queries.iter().cloned().collect()
+    }
+}
+
+/// Drops the history of the index whose last recorded search is the oldest.
+fn evict_least_recently_searched_index(
+    indexes: &mut HashMap<IndexId, VecDeque<QueryHistoryEntry>>,
+) {
+    let least_recently_searched_index_id_opt = indexes
+        .iter()
+        .min_by_key(|(_, index_queries)| {
+            index_queries
+                .back()
+                .map(|entry| entry.timestamp)
+                .unwrap_or_default()
+        })
+        .map(|(index_id, _)| index_id.clone());
+
+    if let Some(index_id) = least_recently_searched_index_id_opt {
+        indexes.remove(&index_id);
+    }
+}
+
+/// Parses the user query of a query AST, when it does not depend on the default search fields of
+/// the index, so that the redacted query keeps its structure, and replaces the values it matches
+/// with a placeholder.
+fn redact_query(query_ast_json: &str) -> String {
+    let Ok(query_ast) = serde_json::from_str::<QueryAst>(query_ast_json) else {
+        return REDACTED_VALUE.to_string();
+    };
+    let query_ast = query_ast.clone().parse_user_query(&[]).unwrap_or(query_ast);
+
+    let Ok(mut query_ast_value) = serde_json::to_value(&query_ast) else {
+        return REDACTED_VALUE.to_string();
+    };
+    redact_values(&mut query_ast_value, false);
+    let redacted_query = query_ast_value.to_string();
+    truncate_str(&redacted_query, MAX_QUERY_LEN).to_string()
+}
+
+/// Replaces the strings and numbers nested under the keys holding values with a placeholder.
+fn redact_values(json_value: &mut JsonValue, is_value: bool) {
+    match json_value {
+        JsonValue::Object(json_object) => {
+            for (key, child_value) in json_object.iter_mut() {
+                redact_values(child_value, is_value || VALUE_KEYS.contains(&key.as_str()));
+            }
+        }
+        JsonValue::Array(json_values) => {
+            for child_value in json_values {
+                redact_values(child_value, is_value);
+            }
+        }
+        JsonValue::String(_) | JsonValue::Number(_) if is_value => {
+            *json_value = JsonValue::String(REDACTED_VALUE.to_string());
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_query::query_ast::query_ast_from_user_text;
+
+    use super::*;
+
+    fn query_ast_json(user_text: &str) -> String {
+        let query_ast = query_ast_from_user_text(user_text, None);
+        serde_json::to_string(&query_ast).unwrap()
+    }
+
+    #[test]
+    fn test_redact_query() {
+        let redacted_query = redact_query(&query_ast_json("user:alice AND age:[18 TO 30]"));
+        assert!(redacted_query.contains("\"user\""));
+        assert!(redacted_query.contains("\"age\""));
+        assert!(!redacted_query.contains("alice"));
+        assert!(!redacted_query.contains("18"));
+        assert!(!redacted_query.contains("30"));
+
+        // Queries depending on the default search fields of the index are redacted as a whole.
+        let redacted_query = redact_query(&query_ast_json("alice"));
+        assert!(redacted_query.contains("user_input"));
+        assert!(!redacted_query.contains("alice"));
+
+        assert_eq!(redact_query("not a query AST"), REDACTED_VALUE);
+    }
+
+    #[test]
+    fn test_query_history_evicts_oldest_queries() {
+        let query_history = QueryHistory::new(3, MAX_INDEXES);
+        let index_ids = ["test-index".to_string()];
+
+        for i in 0..5 {
+            let query_ast_json = query_ast_json(&format!("id:{i}"));
+            let elapsed = Duration::from_micros(i);
+            query_history.record(&index_ids, &query_ast_json, i, elapsed);
+        }
+        let queries = query_history.get("test-index");
+        let num_hits: Vec<u64> = queries.iter().map(|entry| entry.num_hits).collect();
+        assert_eq!(num_hits, [2, 3, 4]);
+
+        let elapsed_times: Vec<u64> = queries
+            .iter()
+            .map(|entry| entry.elapsed_time_micros)
+            .collect();
+        assert_eq!(elapsed_times, [2, 3, 4]);
+        assert!(queries[0].query.contains(REDACTED_VALUE));
+
+        assert!(query_history.get("other-index").is_empty());
+    }
+
+    #[test]
+    fn test_query_history_evicts_least_recently_searched_index() {
+        let query_history = QueryHistory::new(MAX_QUERIES_PER_INDEX, 2);
+        let query_ast_json = query_ast_json("id:1");
+
+        for index_id in ["index-1", "index-2", "index-3"] {
+            let index_ids = [index_id.to_string()];
+            query_history.record(&index_ids, &query_ast_json, 1, Duration::ZERO);
+        }
+        let num_indexes = ["index-1", "index-2", "index-3"]
+            .into_iter()
+            .filter(|index_id| !query_history.get(index_id).is_empty())
+            .count();
+        assert_eq!(num_indexes, 2);
+        assert_eq!(query_history.get("index-3").len(), 1);
+    }
+}
@@ -0,0 +1,339 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Parsing of the query strings of the search, ingest, and index API routes, whose unknown
+//! parameters are rejected when `rest.unknown_query_params` is set to `strict`.
+
+use std::collections::BTreeSet;
+use std::task::{Context, Poll};
+
+use percent_encoding::percent_decode_str;
+use quickwit_config::RestUnknownQueryParams;
+use serde::Deserializer;
+use serde::de::{self, DeserializeOwned, Visitor};
+use thiserror::Error;
+use tokio::task::futures::TaskLocalFuture;
+use tower::{Layer, Service};
+use warp::{Filter, Rejection};
+
+tokio::task_local! {
+    static NODE_UNKNOWN_QUERY_PARAMS: RestUnknownQueryParams;
+}
+
+/// Parameters accepted by all the routes, read by filters shared by the handlers.
+const COMMON_QUERY_PARAMS: [&str; 1] = ["format"];
+
+#[derive(Debug, Error)]
+#[error(
+    "unknown query parameter(s) {}: expected one of {}",
+    backquoted(.unknown_params),
+    backquoted(.expected_params)
+)]
+pub(crate) struct UnknownQueryParamsError {
+    unknown_params: Vec<String>,
+    expected_params: Vec<&'static str>,
+}
+
+impl warp::reject::Reject for UnknownQueryParamsError {}
+
+fn backquoted(names: &[impl AsRef<str>]) -> String {
+    names
+        .iter()
+        .map(|name| format!("`{}`", name.as_ref()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Deserializer capturing the names of the fields of a struct instead of deserializing it.
+struct FieldNamesDeserializer<'a> {
+    field_names_opt: &'a mut Option<&'static [&'static str]>,
+}
+
+impl<'de> Deserializer<'de> for FieldNamesDeserializer<'_> {
+    type Error = de::value::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
+        Err(de::Error::custom("expected a struct"))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        *self.field_names_opt = Some(fields);
+        Err(de::Error::custom("captured the field names"))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf option
+        unit unit_struct newtype_struct seq tuple tuple_struct map enum identifier ignored_any
+    }
+}
+
+/// Returns the names of the fields of a struct, including their aliases, or `None` if they cannot
+/// be listed, for instance because the struct has flattened fields.
+fn field_names<T: DeserializeOwned>() -> Option<&'static [&'static str]> {
+    let mut field_names_opt = None;
+    let _ = T::deserialize(FieldNamesDeserializer {
+        field_names_opt: &mut field_names_opt,
+    });
+    field_names_opt
+}
+
+/// Returns the names of the parameters of the query string that are neither fields of the struct
+/// nor common parameters, sorted and deduplicated.
+fn unknown_query_params(raw_query: &str, field_names: &[&str]) -> Vec<String> {
+    let mut unknown_params = BTreeSet::new();
+
+    for param in raw_query.split('&') {
+        let encoded_name = param.split_once('=').map(|(name, _)| name).unwrap_or(param);
+
+        if encoded_name.is_empty() {
+            continue;
+        }
+        let name = percent_decode_str(&encoded_name.replace('+', " "))
+            .decode_utf8_lossy()
+            .into_owned();
+
+        if !field_names.contains(&name.as_str()) && !COMMON_QUERY_PARAMS.contains(&name.as_str()) {
+            unknown_params.insert(name);
+        }
+    }
+    unknown_params.into_iter().collect()
+}
+
+fn node_unknown_query_params() -> RestUnknownQueryParams {
+    NODE_UNKNOWN_QUERY_PARAMS
+        .try_with(|unknown_query_params| *unknown_query_params)
+        .unwrap_or_default()
+}
+
+fn check_query_params<T: DeserializeOwned>(raw_query: &str) -> Result<(), Rejection> {
+    if node_unknown_query_params() == RestUnknownQueryParams::Lenient {
+        return Ok(());
+    }
+    let Some(field_names) = field_names::<T>() else {
+        return Ok(());
+    };
+    let unknown_params = unknown_query_params(raw_query, field_names);
+
+    if unknown_params.is_empty() {
+        return Ok(());
+    }
+    let expected_params = field_names
+        .iter()
+        .chain(&COMMON_QUERY_PARAMS)
+        .copied()
+        .collect();
+    let error = UnknownQueryParamsError {
+        unknown_params,
+        expected_params,
+    };
+    Err(warp::reject::custom(error))
+}
+
+/// Deserializes the query string into `T` like [`warp::query`]. When `rest.unknown_query_params`
+/// is `strict`, the requests with parameters that are not fields of `T` are rejected.
+pub(crate) fn query_params<T>() -> impl Filter<Extract = (T,), Error = Rejection> + Clone
+where T: DeserializeOwned + Send + 'static {
+    warp::query::raw()
+        .or(warp::any().map(String::new))
+        .unify()
+        .and_then(|raw_query: String| async move { check_query_params::<T>(&raw_query) })
+        .untuple_one()
+        .and(warp::query::<T>())
+}
+
+/// Sets the handling of the unknown query string parameters, for the REST routes parsing their
+/// query string with [`query_params`].
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct UnknownQueryParamsLayer {
+    unknown_query_params: RestUnknownQueryParams,
+}
+
+impl UnknownQueryParamsLayer {
+    pub fn new(unknown_query_params: RestUnknownQueryParams) -> Self {
+        Self {
+            unknown_query_params,
+        }
+    }
+}
+
+impl<S> Layer<S> for UnknownQueryParamsLayer {
+    type Service = UnknownQueryParamsService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        UnknownQueryParamsService {
+            inner,
+            unknown_query_params: self.unknown_query_params,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct UnknownQueryParamsService<S> {
+    inner: S,
+    unknown_query_params: RestUnknownQueryParams,
+}
+
+impl<S, R> Service<R> for UnknownQueryParamsService<S>
+where S: Service<R>
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = TaskLocalFuture<RestUnknownQueryParams, S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: R) -> Self::Future {
+        // The inner layers may reply without polling the future, when rejecting a request.
+        let response_fut = NODE_UNKNOWN_QUERY_PARAMS
+            .sync_scope(self.unknown_query_params, || self.inner.call(request));
+        NODE_UNKNOWN_QUERY_PARAMS.scope(self.unknown_query_params, response_fut)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use serde::Deserialize;
+    use tower::{ServiceBuilder, ServiceExt};
+
+    use super::*;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct LenientQueryParams {
+        #[serde(alias = "max_hits")]
+        limit: Option<usize>,
+        #[serde(rename = "search_field")]
+        search_fields: Option<String>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct StrictQueryParams {
+        #[allow(dead_code)]
+        limit: Option<usize>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct FlattenedQueryParams {
+        #[serde(flatten)]
+        #[allow(dead_code)]
+        inner: LenientQueryParams,
+    }
+
+    #[test]
+    fn test_field_names() {
+        let field_names = field_names::<LenientQueryParams>().unwrap();
+        assert!(field_names.contains(&"limit"));
+        assert!(field_names.contains(&"max_hits"));
+        assert!(field_names.contains(&"search_field"));
+        assert!(!field_names.contains(&"search_fields"));
+
+        assert!(field_names::<FlattenedQueryParams>().is_none());
+        assert!(field_names::<u64>().is_none());
+    }
+
+    #[test]
+    fn test_unknown_query_params() {
+        let field_names = ["limit", "search_field"];
+        assert!(unknown_query_params("", &field_names).is_empty());
+        let raw_query = "limit=10&format=json&search%5Ffield=a";
+        assert!(unknown_query_params(raw_query, &field_names).is_empty());
+        assert_eq!(
+            unknown_query_params("limit=10&offset=2&foo&offset=3&&max+hits=1", &field_names),
+            ["foo", "max hits", "offset"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_query_params_lenient() {
+        let filter = query_params::<LenientQueryParams>();
+        let query_params = warp::test::request()
+            .path("/?limit=10&unknown=1")
+            .filter(&filter)
+            .await
+            .unwrap();
+        let expected_query_params = LenientQueryParams {
+            limit: Some(10),
+            search_fields: None,
+        };
+        assert_eq!(query_params, expected_query_params);
+
+        // Structs denying unknown fields keep rejecting them.
+        let filter = query_params::<StrictQueryParams>();
+        let rejection = warp::test::request()
+            .path("/?limit=10&unknown=1")
+            .filter(&filter)
+            .await
+            .unwrap_err();
+        assert!(rejection.find::<UnknownQueryParamsError>().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_query_params_strict() {
+        let filter = query_params::<LenientQueryParams>();
+        let rejection = NODE_UNKNOWN_QUERY_PARAMS
+            .scope(
+                RestUnknownQueryParams::Strict,
+                warp::test::request()
+                    .path("/?limit=10&unknown=1&other=2")
+                    .filter(&filter),
+            )
+            .await
+            .unwrap_err();
+        let error = rejection.find::<UnknownQueryParamsError>().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "unknown query parameter(s) `other`, `unknown`: expected one of `limit`, `max_hits`, \
+             `search_field`, `format`"
+        );
+        let query_params = NODE_UNKNOWN_QUERY_PARAMS
+            .scope(
+                RestUnknownQueryParams::Strict,
+                warp::test::request()
+                    .path("/?max_hits=10&format=json")
+                    .filter(&filter),
+            )
+            .await
+            .unwrap();
+        assert_eq!(query_params.limit, Some(10));
+
+        let query_params = NODE_UNKNOWN_QUERY_PARAMS
+            .scope(
+                RestUnknownQueryParams::Strict,
+                warp::test::request().path("/").filter(&filter),
+            )
+            .await
+            .unwrap();
+        assert_eq!(query_params.limit, None);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_query_params_layer() {
+        let service = ServiceBuilder::new()
+            .layer(UnknownQueryParamsLayer::new(RestUnknownQueryParams::Strict))
+            .service_fn(|_request: ()| async { Ok::<_, Infallible>(node_unknown_query_params()) });
+        let unknown_query_params = service.oneshot(()).await.unwrap();
+        assert_eq!(unknown_query_params, RestUnknownQueryParams::Strict);
+
+        assert_eq!(node_unknown_query_params(), RestUnknownQueryParams::Lenient);
+    }
+}
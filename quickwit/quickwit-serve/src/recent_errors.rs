@@ -0,0 +1,316 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Keeps the recent ingest and search errors of each index, so that users can debug their
+//! requests with `GET /api/v1/indexes/{index_id}/_errors` without access to the logs of the nodes.
+//!
+//! The errors are kept in memory in a bounded ring buffer per index: each node only reports the
+//! errors of the requests it served. Only the kind of each error is kept: the messages of the
+//! errors may quote the content of documents or queries, in ways no pattern reliably redacts.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{LazyLock, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use quickwit_config::validate_identifier;
+use quickwit_proto::ingest::ParseFailureReason;
+use quickwit_proto::ingest::router::IngestResponseV2;
+use quickwit_proto::types::IndexId;
+use quickwit_proto::{ServiceError, ServiceErrorCode};
+use serde::{Deserialize, Serialize};
+
+use crate::request_scope::ensure_indexes_in_scope;
+
+/// Number of errors kept for each index.
+const MAX_ERRORS_PER_INDEX: usize = 100;
+
+/// Number of indexes whose errors are kept. Beyond, the index whose last error is the oldest is
+/// evicted.
+const MAX_INDEXES: usize = 1_000;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum IndexErrorType {
+    /// A document failed to parse against the doc mapping of the index.
+    Mapping,
+    /// An ingest request targeting the index failed or was rejected.
+    Ingest,
+    /// A search request targeting the index failed or was rejected.
+    Search,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum IndexErrorReason {
+    /// The document is not valid JSON.
+    InvalidJson,
+    /// The document does not match the doc mapping of the index.
+    InvalidSchema,
+    /// The request is invalid, for instance a query targeting an unknown field.
+    BadRequest,
+    /// The request targets a resource that already exists.
+    AlreadyExists,
+    /// The request conflicts with the state of the index, for instance several documents sharing
+    /// the requested id.
+    Conflict,
+    /// The content type of the request is not supported.
+    UnsupportedMediaType,
+    /// The request was rate limited or rejected because the node was overloaded.
+    TooManyRequests,
+    /// The request timed out.
+    Timeout,
+    /// A service required by the request was unavailable.
+    Unavailable,
+    /// The request failed because of an internal error.
+    Internal,
+    /// The reason of the error is unknown.
+    Unspecified,
+}
+
+impl IndexErrorReason {
+    /// Returns the reason of an error with the given code, or `None` for the errors disclosing
+    /// nothing about the index, such as a missing index or a forbidden request.
+    fn from_error_code(error_code: ServiceErrorCode) -> Option<Self> {
+        let reason = match error_code {
+            ServiceErrorCode::Forbidden
+            | ServiceErrorCode::NotFound
+            | ServiceErrorCode::Unauthenticated => return None,
+            ServiceErrorCode::AlreadyExists => Self::AlreadyExists,
+            ServiceErrorCode::BadRequest => Self::BadRequest,
+            ServiceErrorCode::Conflict => Self::Conflict,
+            ServiceErrorCode::Internal => Self::Internal,
+            ServiceErrorCode::Timeout => Self::Timeout,
+            ServiceErrorCode::TooManyRequests => Self::TooManyRequests,
+            ServiceErrorCode::Unavailable => Self::Unavailable,
+            ServiceErrorCode::UnsupportedMediaType => Self::UnsupportedMediaType,
+        };
+        Some(reason)
+    }
+
+    fn from_parse_failure_reason(parse_failure_reason: ParseFailureReason) -> Self {
+        match parse_failure_reason {
+            ParseFailureReason::InvalidJson => Self::InvalidJson,
+            ParseFailureReason::InvalidSchema => Self::InvalidSchema,
+            ParseFailureReason::Unspecified => Self::Unspecified,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct IndexErrorEvent {
+    /// Time of the error, as a Unix timestamp in seconds.
+    pub timestamp: u64,
+    pub error_type: IndexErrorType,
+    pub reason: IndexErrorReason,
+}
+
+/// Recent errors of the indexes, shared by the requests served by the node.
+pub(crate) struct RecentErrors {
+    max_indexes: usize,
+    max_errors_per_index: usize,
+    indexes: Mutex<HashMap<IndexId, VecDeque<IndexErrorEvent>>>,
+}
+
+/// Returns the recent errors of the indexes recorded by the node.
+pub(crate) fn recent_errors() -> &'static RecentErrors {
+    static RECENT_ERRORS: LazyLock<RecentErrors> =
+        LazyLock::new(|| RecentErrors::new(MAX_INDEXES, MAX_ERRORS_PER_INDEX));
+    &RECENT_ERRORS
+}
+
+impl RecentErrors {
+    fn new(max_indexes: usize, max_errors_per_index: usize) -> Self {
+        Self {
+            max_indexes,
+            max_errors_per_index,
+            indexes: Mutex::default(),
+        }
+    }
+
+    /// Records an error of a request targeting the given indexes.
+    ///
+    /// Index ID patterns, missing indexes, and the indexes outside of the scope of the token of
+    /// the request are skipped, so that a tenant cannot fill the errors of the indexes of another
+    /// tenant.
+    pub fn record_error<'a>(
+        &self,
+        index_id_patterns: impl IntoIterator<Item = &'a str>,
+        error_type: IndexErrorType,
+        error: &impl ServiceError,
+    ) {
+        let Some(reason) = IndexErrorReason::from_error_code(error.error_code()) else {
+            return;
+        };
+        for index_id in index_id_patterns {
+            if validate_identifier("", index_id).is_ok() {
+                self.record(index_id, error_type, reason);
+            }
+        }
+    }
+
+    /// Records the documents of an ingest response that failed to parse as mapping errors of the
+    /// index.
+    pub fn record_parse_failures(&self, index_id: &str, ingest_response: &IngestResponseV2) {
+        let reasons: Vec<IndexErrorReason> = ingest_response
+            .successes
+            .iter()
+            .flat_map(|ingest_success| &ingest_success.parse_failures)
+            .map(|parse_failure| {
+                IndexErrorReason::from_parse_failure_reason(parse_failure.reason())
+            })
+            .collect();
+        // Only the last failures would remain in the ring buffer anyway.
+        let num_skipped_reasons = reasons.len().saturating_sub(self.max_errors_per_index);
+
+        for reason in &reasons[num_skipped_reasons..] {
+            self.record(index_id, IndexErrorType::Mapping, *reason);
+        }
+    }
+
+    /// Records an error of the given index, unless the index is outside of the scope of the token
+    /// of the request.
+    pub fn record(&self, index_id: &str, error_type: IndexErrorType, reason: IndexErrorReason) {
+        if ensure_indexes_in_scope([index_id]).is_err() {
+            return;
+        }
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+        let error_event = IndexErrorEvent {
+            timestamp,
+            error_type,
+            reason,
+        };
+        let mut indexes = self.indexes.lock().expect("lock should not be poisoned");
+
+        if indexes.len() == self.max_indexes
+            && !indexes.contains_key(index_id)
+            && let Some(stalest_index_id) = indexes
+                .iter()
+                .min_by_key(|(_, index_errors)| {
+                    index_errors.back().map(|error_event| error_event.timestamp)
+                })
+                .map(|(index_id, _)| index_id.clone())
+        {
+            indexes.remove(&stalest_index_id);
+        }
+        let index_errors = indexes.entry(index_id.to_string()).or_default();
+
+        if index_errors.len() == self.max_errors_per_index {
+            index_errors.pop_front();
+        }
+        index_errors.push_back(error_event);
+    }
+
+    /// Returns the recent errors of the index, from the oldest to the newest.
+    pub fn get(&self, index_id: &str) -> Vec<IndexErrorEvent> {
+        let indexes = self.indexes.lock().expect("lock should not be poisoned");
+
+        let Some(index_errors) = indexes.get(index_id) else {
+            return Vec::new();
+        };
+        index_errors.iter().cloned().collect()
+    }
+
+    /// Forgets the errors of a deleted index.
+    pub fn evict(&self, index_id: &str) {
+        let mut indexes = self.indexes.lock().expect("lock should not be poisoned");
+        indexes.remove(index_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_search::SearchError;
+
+    use super::*;
+
+    #[test]
+    fn test_recent_errors_evicts_oldest_errors() {
+        let recent_errors = RecentErrors::new(2, 3);
+
+        for reason in [
+            IndexErrorReason::InvalidJson,
+            IndexErrorReason::InvalidJson,
+            IndexErrorReason::InvalidSchema,
+            IndexErrorReason::Timeout,
+            IndexErrorReason::Internal,
+        ] {
+            recent_errors.record("test-index", IndexErrorType::Mapping, reason);
+        }
+        let reasons: Vec<IndexErrorReason> = recent_errors
+            .get("test-index")
+            .into_iter()
+            .map(|error_event| error_event.reason)
+            .collect();
+        assert_eq!(
+            reasons,
+            [
+                IndexErrorReason::InvalidSchema,
+                IndexErrorReason::Timeout,
+                IndexErrorReason::Internal,
+            ]
+        );
+        assert!(recent_errors.get("other-index").is_empty());
+    }
+
+    #[test]
+    fn test_recent_errors_evicts_indexes() {
+        let recent_errors = RecentErrors::new(2, 3);
+
+        for index_id in ["test-index-1", "test-index-2"] {
+            recent_errors.record(index_id, IndexErrorType::Search, IndexErrorReason::Timeout);
+        }
+        recent_errors.evict("test-index-1");
+        assert!(recent_errors.get("test-index-1").is_empty());
+        assert_eq!(recent_errors.get("test-index-2").len(), 1);
+
+        for index_id in ["test-index-3", "test-index-4"] {
+            recent_errors.record(index_id, IndexErrorType::Search, IndexErrorReason::Timeout);
+        }
+        // The map holds at most 2 indexes, so recording the errors of `test-index-4` evicted one of
+        // the indexes recorded before.
+        let num_indexes = ["test-index-2", "test-index-3", "test-index-4"]
+            .into_iter()
+            .filter(|index_id| !recent_errors.get(index_id).is_empty())
+            .count();
+        assert_eq!(num_indexes, 2);
+        assert_eq!(recent_errors.get("test-index-4").len(), 1);
+    }
+
+    #[test]
+    fn test_recent_errors_record_error() {
+        let recent_errors = RecentErrors::new(2, 3);
+
+        let search_error = SearchError::InvalidQuery("unknown field `foo`".to_string());
+        recent_errors.record_error(
+            ["test-index", "test-*"],
+            IndexErrorType::Search,
+            &search_error,
+        );
+        let error_events = recent_errors.get("test-index");
+        assert_eq!(error_events.len(), 1);
+        assert_eq!(error_events[0].error_type, IndexErrorType::Search);
+        assert_eq!(error_events[0].reason, IndexErrorReason::BadRequest);
+        assert!(recent_errors.get("test-*").is_empty());
+
+        // Errors disclosing nothing about the index are not recorded.
+        let search_error = SearchError::IndexesNotFound {
+            index_ids: vec!["test-index".to_string()],
+        };
+        recent_errors.record_error(["test-index"], IndexErrorType::Search, &search_error);
+        assert_eq!(recent_errors.get("test-index").len(), 1);
+    }
+}
@@ -0,0 +1,405 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use anyhow::Context as _;
+use futures::future::{BoxFuture, FutureExt};
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use quickwit_config::JwtConfig;
+use tower::{Layer, Service};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use warp::Reply;
+use warp::hyper::StatusCode;
+use warp::hyper::http::{self, HeaderValue};
+
+use super::{REQUEST_SCOPE, RequestScope, ScopeClaims};
+use crate::BodyFormat;
+use crate::rest_api_response::{RestApiError, RestApiResponse};
+
+/// Keys verifying the signature of the tokens.
+enum DecodingKeys {
+    Static(DecodingKey),
+    /// Keys of a JSON Web Key Set, by key ID.
+    Jwks(HashMap<String, DecodingKey>),
+}
+
+/// Verifies the JWTs authenticating REST requests and extracts their scope.
+pub(crate) struct JwtVerifier {
+    decoding_keys: DecodingKeys,
+    validation: Validation,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("invalid token: {0}")]
+pub(crate) struct InvalidToken(String);
+
+impl From<jsonwebtoken::errors::Error> for InvalidToken {
+    fn from(error: jsonwebtoken::errors::Error) -> Self {
+        InvalidToken(error.to_string())
+    }
+}
+
+impl JwtVerifier {
+    pub(crate) fn from_config(jwt_config: &JwtConfig) -> anyhow::Result<Self> {
+        let algorithm = Algorithm::from_str(&jwt_config.algorithm)
+            .with_context(|| format!("unsupported JWT algorithm `{}`", jwt_config.algorithm))?;
+
+        let decoding_keys = if let Some(key_path) = &jwt_config.key_path {
+            let key_bytes = std::fs::read(key_path)
+                .with_context(|| format!("failed to read JWT key file `{key_path}`"))?;
+            let decoding_key = decoding_key_from_bytes(algorithm, &key_bytes)
+                .with_context(|| format!("failed to load JWT key file `{key_path}`"))?;
+            DecodingKeys::Static(decoding_key)
+        } else if let Some(jwks_path) = &jwt_config.jwks_path {
+            let jwks_bytes = std::fs::read(jwks_path)
+                .with_context(|| format!("failed to read JWKS file `{jwks_path}`"))?;
+            let jwk_set: JwkSet = serde_json::from_slice(&jwks_bytes)
+                .with_context(|| format!("failed to parse JWKS file `{jwks_path}`"))?;
+            let mut decoding_keys = HashMap::with_capacity(jwk_set.keys.len());
+
+            for jwk in &jwk_set.keys {
+                let Some(key_id) = &jwk.common.key_id else {
+                    anyhow::bail!("keys of JWKS file `{jwks_path}` must have a `kid`");
+                };
+                let decoding_key = DecodingKey::from_jwk(jwk)
+                    .with_context(|| format!("failed to load key `{key_id}` of `{jwks_path}`"))?;
+                decoding_keys.insert(key_id.clone(), decoding_key);
+            }
+            DecodingKeys::Jwks(decoding_keys)
+        } else {
+            anyhow::bail!("one of `rest.jwt.key_path` and `rest.jwt.jwks_path` must be set");
+        };
+        let mut validation = Validation::new(algorithm);
+
+        if let Some(issuer) = &jwt_config.issuer {
+            validation.set_issuer(&[issuer]);
+        }
+        if let Some(audience) = &jwt_config.audience {
+            validation.set_audience(&[audience]);
+        } else {
+            validation.validate_aud = false;
+        }
+        let jwt_verifier = JwtVerifier {
+            decoding_keys,
+            validation,
+        };
+        Ok(jwt_verifier)
+    }
+
+    /// Verifies the signature and the registered claims (expiration, issuer, audience) of the
+    /// token and returns its scope.
+    pub(crate) fn verify(&self, token: &str) -> Result<RequestScope, InvalidToken> {
+        let decoding_key = match &self.decoding_keys {
+            DecodingKeys::Static(decoding_key) => decoding_key,
+            DecodingKeys::Jwks(decoding_keys) => {
+                let header = jsonwebtoken::decode_header(token)?;
+                let key_id = header
+                    .kid
+                    .ok_or_else(|| InvalidToken("missing `kid` header".to_string()))?;
+                decoding_keys
+                    .get(&key_id)
+                    .ok_or_else(|| InvalidToken(format!("unknown key `{key_id}`")))?
+            }
+        };
+        let token_data =
+            jsonwebtoken::decode::<ScopeClaims>(token, decoding_key, &self.validation)?;
+        RequestScope::try_from_claims(token_data.claims)
+            .map_err(|error| InvalidToken(format!("invalid scope claims: {error}")))
+    }
+
+    fn verify_request<B>(&self, request: &http::Request<B>) -> Result<RequestScope, InvalidToken> {
+        let authorization = request
+            .headers()
+            .get(http::header::AUTHORIZATION)
+            .ok_or_else(|| InvalidToken("missing `Authorization` header".to_string()))?;
+        let token = authorization
+            .to_str()
+            .ok()
+            .and_then(|authorization| authorization.strip_prefix("Bearer "))
+            .ok_or_else(|| InvalidToken("expected a bearer token".to_string()))?;
+        self.verify(token.trim())
+    }
+}
+
+fn decoding_key_from_bytes(
+    algorithm: Algorithm,
+    key_bytes: &[u8],
+) -> jsonwebtoken::errors::Result<DecodingKey> {
+    match algorithm {
+        Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512 => {
+            // Secrets are commonly written to files with a trailing newline.
+            Ok(DecodingKey::from_secret(key_bytes.trim_ascii_end()))
+        }
+        Algorithm::ES256 | Algorithm::ES384 => DecodingKey::from_ec_pem(key_bytes),
+        Algorithm::EdDSA => DecodingKey::from_ed_pem(key_bytes),
+        Algorithm::RS256
+        | Algorithm::RS384
+        | Algorithm::RS512
+        | Algorithm::PS256
+        | Algorithm::PS384
+        | Algorithm::PS512 => DecodingKey::from_rsa_pem(key_bytes),
+    }
+}
+
+/// Only the API routes require a token: the UI assets, the health checks, and the metrics are
+/// fetched by clients that cannot provide one.
+fn requires_token(path: &str) -> bool {
+    path.starts_with("/api/")
+}
+
+/// Tower layer rejecting the API requests without a valid token with a 401, and running the
+/// other ones within the scope of their token.
+#[derive(Clone)]
+pub(crate) struct JwtAuthLayer {
+    jwt_verifier: Arc<JwtVerifier>,
+}
+
+impl JwtAuthLayer {
+    pub(crate) fn new(jwt_verifier: JwtVerifier) -> Self {
+        JwtAuthLayer {
+            jwt_verifier: Arc::new(jwt_verifier),
+        }
+    }
+}
+
+impl<S> Layer<S> for JwtAuthLayer {
+    type Service = JwtAuthService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        JwtAuthService {
+            inner,
+            jwt_verifier: self.jwt_verifier.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct JwtAuthService<S> {
+    inner: S,
+    jwt_verifier: Arc<JwtVerifier>,
+}
+
+impl<S, B> Service<http::Request<B>> for JwtAuthService<S>
+where
+    S: Service<http::Request<B>, Response = warp::reply::Response>,
+    S::Future: Send + 'static,
+{
+    type Response = warp::reply::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        if !requires_token(request.uri().path()) {
+            return self.inner.call(request).boxed();
+        }
+        match self.jwt_verifier.verify_request(&request) {
+            Ok(request_scope) => {
+                if let Some(tenant) = request_scope.tenant() {
+                    tracing::Span::current().set_attribute("tenant", tenant.to_string());
+                }
+                REQUEST_SCOPE
+                    .scope(Arc::new(request_scope), self.inner.call(request))
+                    .boxed()
+            }
+            Err(invalid_token) => {
+                let response = unauthorized_response(invalid_token);
+                futures::future::ready(Ok(response)).boxed()
+            }
+        }
+    }
+}
+
+fn unauthorized_response(invalid_token: InvalidToken) -> warp::reply::Response {
+    let rest_api_error = RestApiError {
+        status_code: StatusCode::UNAUTHORIZED,
+        message: invalid_token.to_string(),
+    };
+    let mut response = RestApiResponse::new::<(), _>(
+        &Err(rest_api_error),
+        StatusCode::UNAUTHORIZED,
+        BodyFormat::node_default(),
+    )
+    .into_response();
+    response.headers_mut().insert(
+        http::header::WWW_AUTHENTICATE,
+        HeaderValue::from_static("Bearer"),
+    );
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use jsonwebtoken::{EncodingKey, Header, get_current_timestamp};
+    use quickwit_proto::search::SearchResponse;
+    use quickwit_query::query_ast::{QueryAst, TermQuery};
+    use quickwit_search::MockSearchService;
+    use serde_json::json;
+    use tower::ServiceExt;
+    use warp::Filter;
+
+    use super::*;
+    use crate::request_scope::ScopedSearchService;
+    use crate::rest::recover_fn;
+    use crate::search_api::search_get_handler;
+
+    const SECRET: &[u8] = b"my-secret";
+
+    fn jwt_verifier_for_test() -> (tempfile::NamedTempFile, JwtVerifier) {
+        let mut key_file = tempfile::NamedTempFile::new().unwrap();
+        key_file.write_all(SECRET).unwrap();
+        key_file.write_all(b"\n").unwrap();
+
+        let jwt_config = JwtConfig {
+            algorithm: "HS256".to_string(),
+            key_path: Some(key_file.path().to_string_lossy().to_string()),
+            jwks_path: None,
+            issuer: Some("my-gateway".to_string()),
+            audience: None,
+        };
+        let jwt_verifier = JwtVerifier::from_config(&jwt_config).unwrap();
+        (key_file, jwt_verifier)
+    }
+
+    fn make_token(exp: u64) -> String {
+        let claims = json!({
+            "iss": "my-gateway",
+            "exp": exp,
+            "tenant": "acme",
+            "indexes": ["acme-*"],
+            "filters": {"tenant_id": "acme"},
+        });
+        jsonwebtoken::encode(
+            &Header::new(Algorithm::HS256),
+            &claims,
+            &EncodingKey::from_secret(SECRET),
+        )
+        .unwrap()
+    }
+
+    async fn search(
+        jwt_auth_layer: &JwtAuthLayer,
+        search_service: Arc<ScopedSearchService>,
+        path: &str,
+        token_opt: Option<&str>,
+    ) -> StatusCode {
+        let routes = warp::path!("api" / "v1" / ..)
+            .and(search_get_handler(search_service, false))
+            .recover(recover_fn);
+        let service = jwt_auth_layer.layer(warp::service(routes));
+        let mut request_builder = http::Request::builder().uri(path);
+
+        if let Some(token) = token_opt {
+            request_builder =
+                request_builder.header(http::header::AUTHORIZATION, format!("Bearer {token}"));
+        }
+        let request = request_builder.body(String::new()).unwrap();
+        let response = service.oneshot(request).await.unwrap();
+        response.status()
+    }
+
+    #[tokio::test]
+    async fn test_jwt_auth_valid_token_scopes_search() {
+        let (_key_file, jwt_verifier) = jwt_verifier_for_test();
+        let jwt_auth_layer = JwtAuthLayer::new(jwt_verifier);
+
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .withf(|search_request| {
+                let query_ast: QueryAst = serde_json::from_str(&search_request.query_ast).unwrap();
+                let QueryAst::Bool(bool_query) = query_ast else {
+                    return false;
+                };
+                let tenant_filter = QueryAst::Term(TermQuery {
+                    field: "tenant_id".to_string(),
+                    value: "acme".to_string(),
+                });
+                search_request.index_id_patterns == ["acme-logs"]
+                    && bool_query.filter == [tenant_filter]
+            })
+            .times(1)
+            .returning(|_| Ok(SearchResponse::default()));
+        let search_service = Arc::new(ScopedSearchService::for_test(Arc::new(mock_search_service)));
+        let token = make_token(get_current_timestamp() + 60);
+
+        let status = search(
+            &jwt_auth_layer,
+            search_service.clone(),
+            "/api/v1/acme-logs/search?query=*",
+            Some(&token),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+
+        let status = search(
+            &jwt_auth_layer,
+            search_service.clone(),
+            "/api/v1/other-logs/search?query=*",
+            Some(&token),
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        let status = search(
+            &jwt_auth_layer,
+            search_service,
+            "/api/v1/acme-logs/search?query=*",
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_jwt_auth_expired_token_gets_401() {
+        let (_key_file, jwt_verifier) = jwt_verifier_for_test();
+        let jwt_auth_layer = JwtAuthLayer::new(jwt_verifier);
+        let search_service = Arc::new(ScopedSearchService::for_test(Arc::new(
+            MockSearchService::new(),
+        )));
+        let expired_token = make_token(get_current_timestamp() - 3_600);
+
+        let status = search(
+            &jwt_auth_layer,
+            search_service.clone(),
+            "/api/v1/acme-logs/search?query=*",
+            Some(&expired_token),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let mut tampered_token = make_token(get_current_timestamp() + 60);
+        tampered_token.push('x');
+
+        let status = search(
+            &jwt_auth_layer,
+            search_service,
+            "/api/v1/acme-logs/search?query=*",
+            Some(&tampered_token),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+}
@@ -0,0 +1,454 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Restricts REST requests to the scope encoded in the JSON Web Token (JWT) they carry.
+//!
+//! The [`JwtAuthLayer`] verifies the token of every `/api/*` request and runs the request with
+//! its [`RequestScope`] set in a task-local. The [`ScopedSearchService`] and the ingest paths then
+//! read the scope to reject out-of-scope indexes and to filter the documents searches can read.
+
+mod jwt;
+mod search_service;
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use itertools::Itertools;
+use quickwit_ingest::IngestServiceError;
+use quickwit_metastore::IndexIdMatcher;
+use quickwit_proto::metastore::MetastoreError;
+use quickwit_query::query_ast::{BoolQuery, QueryAst, TermQuery};
+use quickwit_search::SearchError;
+use serde::Deserialize;
+
+pub(crate) use self::jwt::{JwtAuthLayer, JwtVerifier};
+pub(crate) use self::search_service::ScopedSearchService;
+
+tokio::task_local! {
+    static REQUEST_SCOPE: Arc<RequestScope>;
+}
+
+/// Scope claims of a token, next to the registered claims (`exp`, `iss`, `aud`, ...).
+#[derive(Debug, Default, Deserialize)]
+struct ScopeClaims {
+    /// Tenant the token was issued to. Attached to the logs of the request.
+    #[serde(default)]
+    tenant: Option<String>,
+    /// Index ID patterns the request may target. Any index when absent.
+    #[serde(default)]
+    indexes: Option<Vec<String>>,
+    /// Field values every document read by the request must have.
+    #[serde(default)]
+    filters: BTreeMap<String, String>,
+    /// Whether the token grants access to the admin routes, such as the metastore export.
+    #[serde(default)]
+    admin: bool,
+}
+
+/// Indexes a request may target, as granted by its token.
+#[derive(Debug)]
+enum IndexScope {
+    /// Any index, when the token does not list the indexes.
+    Any,
+    /// The indexes matching the index ID patterns of the token.
+    Matching(IndexIdMatcher),
+    /// No index, when the token lists no index ID pattern.
+    Empty,
+}
+
+impl IndexScope {
+    fn is_match(&self, index_id_pattern: &str) -> bool {
+        match self {
+            Self::Any => true,
+            Self::Matching(index_id_matcher) => index_id_matcher.is_match(index_id_pattern),
+            Self::Empty => false,
+        }
+    }
+}
+
+/// Restrictions applied to a request, as granted by its token.
+#[derive(Debug)]
+pub(crate) struct RequestScope {
+    tenant_opt: Option<String>,
+    index_scope: IndexScope,
+    filters: BTreeMap<String, String>,
+    is_admin: bool,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("index(es) `{0}` not allowed by the token of the request")]
+pub(crate) struct OutOfScopeIndexes(String);
+
+#[derive(Debug, thiserror::Error)]
+#[error("the token of the request does not grant admin access")]
+pub(crate) struct AdminRequired;
+
+impl From<OutOfScopeIndexes> for SearchError {
+    fn from(error: OutOfScopeIndexes) -> Self {
+        SearchError::Forbidden(error.to_string())
+    }
+}
+
+impl From<OutOfScopeIndexes> for IngestServiceError {
+    fn from(error: OutOfScopeIndexes) -> Self {
+        IngestServiceError::Forbidden(error.to_string())
+    }
+}
+
+impl From<OutOfScopeIndexes> for MetastoreError {
+    fn from(error: OutOfScopeIndexes) -> Self {
+        MetastoreError::Forbidden {
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<AdminRequired> for SearchError {
+    fn from(error: AdminRequired) -> Self {
+        SearchError::Forbidden(error.to_string())
+    }
+}
+
+impl From<AdminRequired> for MetastoreError {
+    fn from(error: AdminRequired) -> Self {
+        MetastoreError::Forbidden {
+            message: error.to_string(),
+        }
+    }
+}
+
+impl RequestScope {
+    fn try_from_claims(scope_claims: ScopeClaims) -> anyhow::Result<Self> {
+        let index_scope = match scope_claims.indexes {
+            None => IndexScope::Any,
+            Some(index_id_patterns) if index_id_patterns.is_empty() => IndexScope::Empty,
+            Some(index_id_patterns) => {
+                // The requested patterns are matched against the allowed ones, so an excluded
+                // index would still be reachable through a requested pattern covering it.
+                if let Some(negative_pattern) = index_id_patterns
+                    .iter()
+                    .find(|index_id_pattern| index_id_pattern.starts_with('-'))
+                {
+                    anyhow::bail!(
+                        "negative index ID pattern `{negative_pattern}` is not supported in token \
+                         claims"
+                    );
+                }
+                let index_id_matcher =
+                    IndexIdMatcher::try_from_index_id_patterns(&index_id_patterns)?;
+                IndexScope::Matching(index_id_matcher)
+            }
+        };
+        let request_scope = RequestScope {
+            tenant_opt: scope_claims.tenant,
+            index_scope,
+            filters: scope_claims.filters,
+            is_admin: scope_claims.admin,
+        };
+        Ok(request_scope)
+    }
+
+    pub(crate) fn tenant(&self) -> Option<&str> {
+        self.tenant_opt.as_deref()
+    }
+
+    pub(crate) fn has_filters(&self) -> bool {
+        !self.filters.is_empty()
+    }
+
+    /// Whether the scope neither restricts the indexes nor filters the documents.
+    pub(crate) fn is_unrestricted(&self) -> bool {
+        matches!(self.index_scope, IndexScope::Any) && self.filters.is_empty()
+    }
+
+    /// Checks that every index targeted by the patterns is allowed.
+    ///
+    /// A `*` in a requested pattern is matched as a literal char against the allowed patterns, so
+    /// `logs-*` is only allowed if an allowed pattern covers all of `logs-*` (e.g. `logs-*` or
+    /// `*`). Negative patterns only narrow down a request and are always allowed.
+    pub(crate) fn ensure_indexes_allowed<'a>(
+        &self,
+        index_id_patterns: impl IntoIterator<Item = &'a str>,
+    ) -> Result<(), OutOfScopeIndexes> {
+        if matches!(self.index_scope, IndexScope::Any) {
+            return Ok(());
+        }
+        let out_of_scope_patterns = index_id_patterns
+            .into_iter()
+            .filter(|index_id_pattern| {
+                !index_id_pattern.starts_with('-') && !self.index_scope.is_match(index_id_pattern)
+            })
+            .join(", ");
+
+        if out_of_scope_patterns.is_empty() {
+            return Ok(());
+        }
+        Err(OutOfScopeIndexes(out_of_scope_patterns))
+    }
+
+    /// Restricts the query to the documents matching the filters of the scope.
+    pub(crate) fn filter_query_ast(&self, query_ast_json: &str) -> Result<String, SearchError> {
+        if self.filters.is_empty() {
+            return Ok(query_ast_json.to_string());
+        }
+        let query_ast: QueryAst = serde_json::from_str(query_ast_json).map_err(|error| {
+            SearchError::InvalidQuery(format!("failed to deserialize query AST: {error}"))
+        })?;
+        let filter = self.filter_queries().collect();
+        let filtered_query_ast: QueryAst = BoolQuery {
+            must: vec![query_ast],
+            filter,
+            ..Default::default()
+        }
+        .into();
+        let filtered_query_ast_json = serde_json::to_string(&filtered_query_ast)
+            .expect("query AST should be JSON serializable");
+        Ok(filtered_query_ast_json)
+    }
+
+    /// Checks that the query was restricted to the documents matching the filters of the scope by
+    /// [`Self::filter_query_ast`].
+    pub(crate) fn ensure_query_ast_filtered(
+        &self,
+        query_ast_json: &str,
+    ) -> Result<(), SearchError> {
+        if self.filters.is_empty() {
+            return Ok(());
+        }
+        let query_ast: QueryAst = serde_json::from_str(query_ast_json).map_err(|error| {
+            SearchError::InvalidQuery(format!("failed to deserialize query AST: {error}"))
+        })?;
+        if let QueryAst::Bool(bool_query) = &query_ast
+            && self
+                .filter_queries()
+                .all(|filter_query| bool_query.filter.contains(&filter_query))
+        {
+            return Ok(());
+        }
+        Err(SearchError::Forbidden(
+            "query is not restricted to the filters of the token of the request".to_string(),
+        ))
+    }
+
+    fn filter_queries(&self) -> impl Iterator<Item = QueryAst> + '_ {
+        self.filters.iter().map(|(field, value)| {
+            TermQuery {
+                field: field.clone(),
+                value: value.clone(),
+            }
+            .into()
+        })
+    }
+}
+
+/// Returns the scope of the request being served, if the REST server verifies tokens.
+pub(crate) fn current_request_scope() -> Option<Arc<RequestScope>> {
+    REQUEST_SCOPE.try_with(Arc::clone).ok()
+}
+
+/// Checks that the scope of the request being served, if any, allows the given indexes.
+pub(crate) fn ensure_indexes_in_scope<'a>(
+    index_id_patterns: impl IntoIterator<Item = &'a str>,
+) -> Result<(), OutOfScopeIndexes> {
+    if let Some(request_scope) = current_request_scope() {
+        request_scope.ensure_indexes_allowed(index_id_patterns)?;
+    }
+    Ok(())
+}
+
+/// Checks that the scope of the request being served, if any, grants admin access.
+pub(crate) fn ensure_admin() -> Result<(), AdminRequired> {
+    if let Some(request_scope) = current_request_scope()
+        && !request_scope.is_admin
+    {
+        return Err(AdminRequired);
+    }
+    Ok(())
+}
+
+/// Runs the future within the scope of a token restricted to the given index ID patterns.
+#[cfg(test)]
+pub(crate) async fn with_index_scope_for_test<F: Future>(
+    index_id_patterns: &[&str],
+    future: F,
+) -> F::Output {
+    let scope_claims = ScopeClaims {
+        indexes: Some(
+            index_id_patterns
+                .iter()
+                .map(|index_id_pattern| index_id_pattern.to_string())
+                .collect(),
+        ),
+        ..Default::default()
+    };
+    let request_scope =
+        RequestScope::try_from_claims(scope_claims).expect("index ID patterns should be valid");
+    REQUEST_SCOPE.scope(Arc::new(request_scope), future).await
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_query::query_ast::qast_json_helper;
+
+    use super::*;
+
+    fn request_scope_for_test(
+        indexes_opt: Option<&[&str]>,
+        filters: &[(&str, &str)],
+    ) -> RequestScope {
+        let scope_claims = ScopeClaims {
+            tenant: Some("acme".to_string()),
+            indexes: indexes_opt
+                .map(|indexes| indexes.iter().map(|index| index.to_string()).collect()),
+            filters: filters
+                .iter()
+                .map(|(field, value)| (field.to_string(), value.to_string()))
+                .collect(),
+            admin: false,
+        };
+        RequestScope::try_from_claims(scope_claims).unwrap()
+    }
+
+    #[test]
+    fn test_request_scope_ensure_indexes_allowed() {
+        let request_scope = request_scope_for_test(None, &[]);
+        request_scope.ensure_indexes_allowed(["*"]).unwrap();
+
+        let request_scope = request_scope_for_test(Some(&["acme-*", "shared"]), &[]);
+        request_scope
+            .ensure_indexes_allowed(["acme-logs", "acme-*", "acme-logs-*", "shared"])
+            .unwrap();
+        request_scope
+            .ensure_indexes_allowed(["acme-*", "-acme-private"])
+            .unwrap();
+
+        let error = request_scope
+            .ensure_indexes_allowed(["acme-logs", "*", "a*", "shared-2", "other"])
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "index(es) `*, a*, shared-2, other` not allowed by the token of the request"
+        );
+
+        let request_scope = request_scope_for_test(Some(&[]), &[]);
+        request_scope
+            .ensure_indexes_allowed(["acme-logs"])
+            .unwrap_err();
+    }
+
+    #[test]
+    fn test_request_scope_rejects_negative_patterns() {
+        // Otherwise, `acme-*` would be allowed and resolve to the excluded `acme-private` index.
+        let scope_claims = ScopeClaims {
+            indexes: Some(vec!["acme-*".to_string(), "-acme-private".to_string()]),
+            ..Default::default()
+        };
+        let error = RequestScope::try_from_claims(scope_claims).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "negative index ID pattern `-acme-private` is not supported in token claims"
+        );
+    }
+
+    #[test]
+    fn test_request_scope_filter_query_ast() {
+        let query_ast_json = qast_json_helper("body:hello", &[]);
+
+        let request_scope = request_scope_for_test(None, &[]);
+        assert_eq!(
+            request_scope.filter_query_ast(&query_ast_json).unwrap(),
+            query_ast_json
+        );
+
+        let request_scope = request_scope_for_test(None, &[("tenant_id", "acme")]);
+        let filtered_query_ast: QueryAst =
+            serde_json::from_str(&request_scope.filter_query_ast(&query_ast_json).unwrap())
+                .unwrap();
+        let QueryAst::Bool(bool_query) = filtered_query_ast else {
+            panic!("expected a bool query");
+        };
+        assert_eq!(
+            bool_query.must,
+            [serde_json::from_str::<QueryAst>(&query_ast_json).unwrap()]
+        );
+        assert_eq!(
+            bool_query.filter,
+            [QueryAst::Term(TermQuery {
+                field: "tenant_id".to_string(),
+                value: "acme".to_string(),
+            })]
+        );
+    }
+
+    #[test]
+    fn test_request_scope_ensure_query_ast_filtered() {
+        let query_ast_json = qast_json_helper("body:hello", &[]);
+
+        let request_scope = request_scope_for_test(None, &[]);
+        request_scope
+            .ensure_query_ast_filtered(&query_ast_json)
+            .unwrap();
+
+        let request_scope = request_scope_for_test(None, &[("tenant_id", "acme")]);
+        request_scope
+            .ensure_query_ast_filtered(&query_ast_json)
+            .unwrap_err();
+
+        let filtered_query_ast_json = request_scope.filter_query_ast(&query_ast_json).unwrap();
+        request_scope
+            .ensure_query_ast_filtered(&filtered_query_ast_json)
+            .unwrap();
+
+        let other_request_scope = request_scope_for_test(None, &[("tenant_id", "other")]);
+        other_request_scope
+            .ensure_query_ast_filtered(&filtered_query_ast_json)
+            .unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn test_ensure_indexes_in_scope() {
+        ensure_indexes_in_scope(["acme-logs", "other"]).unwrap();
+
+        let request_scope = Arc::new(request_scope_for_test(Some(&["acme-*"]), &[]));
+        REQUEST_SCOPE
+            .scope(request_scope, async {
+                ensure_indexes_in_scope(["acme-logs"]).unwrap();
+                ensure_indexes_in_scope(["other"]).unwrap_err();
+            })
+            .await;
+    }
+
+    #[tokio::test]
+    async fn test_ensure_admin() {
+        ensure_admin().unwrap();
+
+        let request_scope = Arc::new(request_scope_for_test(None, &[]));
+        REQUEST_SCOPE
+            .scope(request_scope, async {
+                ensure_admin().unwrap_err();
+            })
+            .await;
+
+        let admin_scope_claims = ScopeClaims {
+            admin: true,
+            ..Default::default()
+        };
+        let request_scope = Arc::new(RequestScope::try_from_claims(admin_scope_claims).unwrap());
+        REQUEST_SCOPE
+            .scope(request_scope, async {
+                ensure_admin().unwrap();
+            })
+            .await;
+    }
+}
@@ -0,0 +1,382 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use quickwit_proto::search::{
+    ClearCacheRequest, ClearCacheResponse, FetchDocsRequest, FetchDocsResponse, GetKvRequest,
+    LeafClearCacheRequest, LeafClearCacheResponse, LeafListFieldsRequest, LeafListTermsRequest,
+    LeafListTermsResponse, LeafSearchRequest, LeafSearchResponse, ListFieldsRequest,
+    ListFieldsResponse, ListTermsRequest, ListTermsResponse, PutKvRequest, ReportSplitsRequest,
+    ReportSplitsResponse, ScrollRequest, SearchPlanResponse, SearchRequest, SearchResponse,
+};
+use quickwit_query::query_ast::QueryAst;
+use quickwit_search::{ClusterClient, SearchError, SearchService, scroll_search_request};
+
+use super::{AdminRequired, RequestScope, current_request_scope};
+
+/// Search service serving the REST API when tokens are verified. It rejects the requests
+/// targeting indexes outside of the scope of their token and restricts searches to the documents
+/// matching the filters of the scope.
+///
+/// The requests addressing splits or documents directly (fetch docs, clear cache, KV) carry no
+/// index ID nor query to check, so they are only served to unrestricted tokens. Scrolls are
+/// checked against the search request they replay.
+pub(crate) struct ScopedSearchService {
+    inner: Arc<dyn SearchService>,
+    cluster_client: ClusterClient,
+}
+
+impl ScopedSearchService {
+    pub(crate) fn new(inner: Arc<dyn SearchService>, cluster_client: ClusterClient) -> Self {
+        ScopedSearchService {
+            inner,
+            cluster_client,
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn for_test(inner: Arc<dyn SearchService>) -> Self {
+        let search_job_placer =
+            quickwit_search::SearchJobPlacer::new(quickwit_search::SearcherPool::default());
+        ScopedSearchService::new(inner, ClusterClient::new(search_job_placer))
+    }
+}
+
+/// The REST server runs every API request within the scope of its token, so a missing scope
+/// means the request escaped it (e.g. in a spawned task): fail closed.
+fn request_scope() -> quickwit_search::Result<Arc<RequestScope>> {
+    current_request_scope()
+        .ok_or_else(|| SearchError::Forbidden("request is not authenticated".to_string()))
+}
+
+/// Rejects the requests that cannot be restricted to the scope of a token, unless the token is
+/// unrestricted.
+fn ensure_unrestricted(request_name: &str) -> quickwit_search::Result<()> {
+    if !request_scope()?.is_unrestricted() {
+        return Err(SearchError::Forbidden(format!(
+            "{request_name} requests are not allowed with a scoped token"
+        )));
+    }
+    Ok(())
+}
+
+fn is_unrestricted() -> bool {
+    current_request_scope().is_some_and(|request_scope| request_scope.is_unrestricted())
+}
+
+fn scope_search_request(
+    mut search_request: SearchRequest,
+) -> quickwit_search::Result<SearchRequest> {
+    let request_scope = request_scope()?;
+    request_scope.ensure_indexes_allowed(
+        search_request
+            .index_id_patterns
+            .iter()
+            .map(|index_id_pattern| index_id_pattern.as_str()),
+    )?;
+    // Only admin tokens can lift the limit on the number of indexes targeted by a search.
+    if search_request.ignore_max_indexes_per_search && !request_scope.is_admin {
+        return Err(AdminRequired.into());
+    }
+    search_request.query_ast = request_scope.filter_query_ast(&search_request.query_ast)?;
+    Ok(search_request)
+}
+
+fn ensure_list_terms_allowed(list_terms_request: &ListTermsRequest) -> quickwit_search::Result<()> {
+    let request_scope = request_scope()?;
+    request_scope.ensure_indexes_allowed(
+        list_terms_request
+            .index_id_patterns
+            .iter()
+            .map(|index_id_pattern| index_id_pattern.as_str()),
+    )?;
+    // Terms are listed without a query, so they cannot be restricted to the documents matching
+    // the filters.
+    if request_scope.has_filters() {
+        return Err(SearchError::Forbidden(
+            "listing terms is not allowed with a filtered token".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+#[async_trait]
+impl SearchService for ScopedSearchService {
+    async fn root_search(
+        &self,
+        search_request: SearchRequest,
+    ) -> quickwit_search::Result<SearchResponse> {
+        let scoped_search_request = scope_search_request(search_request)?;
+        self.inner.root_search(scoped_search_request).await
+    }
+
+    async fn root_search_batch(
+        &self,
+        search_request: SearchRequest,
+        filter_query_asts: Vec<QueryAst>,
+    ) -> quickwit_search::Result<Vec<quickwit_search::Result<SearchResponse>>> {
+        // The filters of the variants only restrict the scoped query of the batch further.
+        let scoped_search_request = scope_search_request(search_request)?;
+        self.inner
+            .root_search_batch(scoped_search_request, filter_query_asts)
+            .await
+    }
+
+    async fn leaf_search(
+        &self,
+        mut leaf_search_request: LeafSearchRequest,
+    ) -> quickwit_search::Result<LeafSearchResponse> {
+        let search_request = leaf_search_request
+            .search_request
+            .take()
+            .ok_or_else(|| SearchError::Internal("no search request".to_string()))?;
+        leaf_search_request.search_request = Some(scope_search_request(search_request)?);
+        self.inner.leaf_search(leaf_search_request).await
+    }
+
+    async fn fetch_docs(
+        &self,
+        fetch_docs_request: FetchDocsRequest,
+    ) -> quickwit_search::Result<FetchDocsResponse> {
+        ensure_unrestricted("fetch docs")?;
+        self.inner.fetch_docs(fetch_docs_request).await
+    }
+
+    async fn root_list_terms(
+        &self,
+        list_terms_request: ListTermsRequest,
+    ) -> quickwit_search::Result<ListTermsResponse> {
+        ensure_list_terms_allowed(&list_terms_request)?;
+        self.inner.root_list_terms(list_terms_request).await
+    }
+
+    async fn leaf_list_terms(
+        &self,
+        leaf_list_terms_request: LeafListTermsRequest,
+    ) -> quickwit_search::Result<LeafListTermsResponse> {
+        let list_terms_request = leaf_list_terms_request
+            .list_terms_request
+            .as_ref()
+            .ok_or_else(|| SearchError::Internal("no list terms request".to_string()))?;
+        ensure_list_terms_allowed(list_terms_request)?;
+        self.inner.leaf_list_terms(leaf_list_terms_request).await
+    }
+
+    async fn scroll(
+        &self,
+        scroll_request: ScrollRequest,
+    ) -> quickwit_search::Result<SearchResponse> {
+        let request_scope = request_scope()?;
+
+        if !request_scope.is_unrestricted() {
+            // The scroll replays the search request it was opened with, possibly by another
+            // token, so that request must be within the scope of this one.
+            let search_request =
+                scroll_search_request(&scroll_request.scroll_id, &self.cluster_client).await?;
+            request_scope.ensure_indexes_allowed(
+                search_request
+                    .index_id_patterns
+                    .iter()
+                    .map(|index_id_pattern| index_id_pattern.as_str()),
+            )?;
+            request_scope.ensure_query_ast_filtered(&search_request.query_ast)?;
+        }
+        self.inner.scroll(scroll_request).await
+    }
+
+    async fn put_kv(&self, put_kv_request: PutKvRequest) {
+        if is_unrestricted() {
+            self.inner.put_kv(put_kv_request).await
+        }
+    }
+
+    async fn get_kv(&self, get_kv_request: GetKvRequest) -> Option<Vec<u8>> {
+        if !is_unrestricted() {
+            return None;
+        }
+        self.inner.get_kv(get_kv_request).await
+    }
+
+    async fn report_splits(
+        &self,
+        report_splits_request: ReportSplitsRequest,
+    ) -> ReportSplitsResponse {
+        if !is_unrestricted() {
+            return ReportSplitsResponse {};
+        }
+        self.inner.report_splits(report_splits_request).await
+    }
+
+    async fn root_list_fields(
+        &self,
+        list_fields_request: ListFieldsRequest,
+    ) -> quickwit_search::Result<ListFieldsResponse> {
+        request_scope()?.ensure_indexes_allowed(
+            list_fields_request
+                .index_id_patterns
+                .iter()
+                .map(|index_id_pattern| index_id_pattern.as_str()),
+        )?;
+        self.inner.root_list_fields(list_fields_request).await
+    }
+
+    async fn leaf_list_fields(
+        &self,
+        leaf_list_fields_request: LeafListFieldsRequest,
+    ) -> quickwit_search::Result<ListFieldsResponse> {
+        request_scope()?.ensure_indexes_allowed([leaf_list_fields_request.index_id.as_str()])?;
+        self.inner.leaf_list_fields(leaf_list_fields_request).await
+    }
+
+    async fn search_plan(
+        &self,
+        search_request: SearchRequest,
+    ) -> quickwit_search::Result<SearchPlanResponse> {
+        let scoped_search_request = scope_search_request(search_request)?;
+        self.inner.search_plan(scoped_search_request).await
+    }
+
+    async fn get_load(&self) -> usize {
+        self.inner.get_load().await
+    }
+
+    async fn root_clear_cache(
+        &self,
+        clear_cache_request: ClearCacheRequest,
+    ) -> quickwit_search::Result<ClearCacheResponse> {
+        request_scope()?.ensure_indexes_allowed(
+            clear_cache_request
+                .index_id_patterns
+                .iter()
+                .map(|index_id_pattern| index_id_pattern.as_str()),
+        )?;
+        self.inner.root_clear_cache(clear_cache_request).await
+    }
+
+    async fn leaf_clear_cache(
+        &self,
+        leaf_clear_cache_request: LeafClearCacheRequest,
+    ) -> quickwit_search::Result<LeafClearCacheResponse> {
+        ensure_unrestricted("leaf clear cache")?;
+        self.inner.leaf_clear_cache(leaf_clear_cache_request).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_proto::search::{LeafSearchResponse, SearchRequest};
+    use quickwit_query::query_ast::qast_json_helper;
+    use quickwit_search::MockSearchService;
+
+    use super::*;
+    use crate::request_scope::with_index_scope_for_test;
+
+    #[tokio::test]
+    async fn test_scoped_search_service_leaf_search() {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_leaf_search()
+            .withf(|leaf_search_request| {
+                leaf_search_request
+                    .search_request
+                    .as_ref()
+                    .unwrap()
+                    .index_id_patterns
+                    == ["acme-logs"]
+            })
+            .times(1)
+            .returning(|_| Ok(LeafSearchResponse::default()));
+        let search_service = ScopedSearchService::for_test(Arc::new(mock_search_service));
+
+        let leaf_search_request = |index_id: &str| LeafSearchRequest {
+            search_request: Some(SearchRequest {
+                index_id_patterns: vec![index_id.to_string()],
+                query_ast: qast_json_helper("body:hello", &[]),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        with_index_scope_for_test(&["acme-*"], async {
+            search_service
+                .leaf_search(leaf_search_request("acme-logs"))
+                .await
+                .unwrap();
+
+            let search_error = search_service
+                .leaf_search(leaf_search_request("other-logs"))
+                .await
+                .unwrap_err();
+            assert!(matches!(search_error, SearchError::Forbidden(_)));
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_scoped_search_service_rejects_unscopable_requests() {
+        // The mock has no expectations: any forwarded request panics.
+        let search_service = ScopedSearchService::for_test(Arc::new(MockSearchService::new()));
+
+        with_index_scope_for_test(&["acme-*"], async {
+            let search_error = search_service
+                .fetch_docs(FetchDocsRequest::default())
+                .await
+                .unwrap_err();
+            assert!(matches!(search_error, SearchError::Forbidden(_)));
+
+            let search_error = search_service
+                .leaf_clear_cache(LeafClearCacheRequest::default())
+                .await
+                .unwrap_err();
+            assert!(matches!(search_error, SearchError::Forbidden(_)));
+
+            let leaf_list_terms_request = LeafListTermsRequest {
+                list_terms_request: Some(ListTermsRequest {
+                    index_id_patterns: vec!["other-logs".to_string()],
+                    ..Default::default()
+                }),
+                ..Default::default()
+            };
+            let search_error = search_service
+                .leaf_list_terms(leaf_list_terms_request)
+                .await
+                .unwrap_err();
+            assert!(matches!(search_error, SearchError::Forbidden(_)));
+
+            let leaf_list_fields_request = LeafListFieldsRequest {
+                index_id: "other-logs".to_string(),
+                ..Default::default()
+            };
+            let search_error = search_service
+                .leaf_list_fields(leaf_list_fields_request)
+                .await
+                .unwrap_err();
+            assert!(matches!(search_error, SearchError::Forbidden(_)));
+
+            // The scroll context cannot be loaded, so the scroll is not forwarded either.
+            let scroll_request = ScrollRequest {
+                scroll_id: "unknown".to_string(),
+                scroll_ttl_secs: None,
+            };
+            search_service.scroll(scroll_request).await.unwrap_err();
+
+            let get_kv_request = GetKvRequest {
+                key: b"scroll-key".to_vec(),
+            };
+            assert!(search_service.get_kv(get_kv_request).await.is_none());
+        })
+        .await;
+    }
+}
@@ -14,17 +14,23 @@
 
 use std::fmt::Formatter;
 use std::io;
+use std::num::NonZeroUsize;
 use std::pin::Pin;
 use std::sync::Arc;
 use std::time::Duration;
 
+use anyhow::Context;
+use bytesize::ByteSize;
 use futures_util::{Stream, StreamExt};
 use hyper_util::rt::{TokioExecutor, TokioIo};
 use hyper_util::server::conn::auto::Builder;
 use hyper_util::server::graceful::GracefulConnection;
 use hyper_util::service::TowerToHyperService;
 use quickwit_common::tower::BoxFutureInfaillible;
-use quickwit_config::{disable_ingest_v1, enable_ingest_v2};
+use quickwit_config::{
+    RestCompressionConfig, RestConfig, RestCorsPolicy, RestUnknownQueryParams, disable_ingest_v1,
+    enable_ingest_v2,
+};
 use quickwit_metrics::{counter, histogram, labels};
 use quickwit_search::SearchService;
 use tokio::net::{TcpListener, TcpStream};
@@ -34,38 +40,65 @@ use tokio_rustls::server::TlsStream;
 use tokio_util::either::Either;
 use tokio_util::sync::CancellationToken;
 use tower::ServiceBuilder;
-use tower_http::compression::CompressionLayer;
-use tower_http::compression::predicate::{NotForContentType, Predicate, SizeAbove};
 use tower_http::cors::{AllowOrigin, CorsLayer};
 use tower_http::trace::TraceLayer;
 use tracing::{error, info, warn};
 use warp::filters::log::Info;
-use warp::hyper::http::HeaderValue;
+use warp::hyper::http::{HeaderMap, HeaderName, HeaderValue};
 use warp::hyper::{Method, StatusCode, http};
-use warp::{Filter, Rejection, Reply, redirect};
+use warp::{Filter, Rejection, Reply};
 
-use crate::cluster_api::cluster_handler;
-use crate::decompression::{CorruptedData, UnsupportedEncoding};
+use crate::access_log::{RouteGroup, access_log};
+use crate::cluster_api::{
+    cluster_handler, cluster_leave_handler, cluster_leave_status_handler, nodes_stats_handler,
+};
+use crate::compression::build_compression_layer;
+use crate::config_validation::config_validation_handler;
+use crate::decompression::{BodyReadError, BodyReadTimeout, CorruptedData, UnsupportedEncoding};
 use crate::delete_task_api::delete_task_api_handlers;
 use crate::developer_api::developer_api_routes;
 use crate::elasticsearch_api::elastic_api_handlers;
+use crate::format::DefaultBodyFormatLayer;
 use crate::health_check_api::health_check_handlers;
-use crate::index_api::index_management_handlers;
-use crate::indexing_api::indexing_get_handler;
+use crate::idle_connection::{ActivityTrackingIo, ConnectionActivity};
+use crate::index_api::{get_index_merges_handler, index_management_handlers};
+use crate::indexing_api::{
+    indexing_get_handler, indexing_rebalance_handler, pause_indexing_handler,
+    resume_indexing_handler,
+};
 use crate::ingest_api::ingest_api_handlers;
 use crate::jaeger_api::jaeger_api_handlers;
-use crate::metrics::{HTTP_REQUESTS_TOTAL, REQUEST_DURATION_SECS};
+use crate::live_rest_config::{LiveRestConfig, with_extra_headers};
+use crate::maintenance_mode::{UnderMaintenance, maintenance_mode_filter};
+use crate::metastore_api::metastore_api_handlers;
+use crate::metrics::{
+    HTTP_REQUESTS_TOTAL, INGEST_REQUEST_DURATION_SECS, REQUEST_DURATION_SECS,
+    SEARCH_REQUEST_DURATION_SECS,
+};
 use crate::metrics_api::metrics_handler;
+use crate::node_decommission::{NodeLeaving, node_leaving_filter};
 use crate::node_info_handler::node_info_handler;
+use crate::openapi_handler::openapi_doc_handler;
 use crate::otlp_api::otlp_ingest_api_handlers;
+use crate::pipelining::{MaxPipelinedRequestsService, PipelineTrackingIo};
+use crate::query_params::{UnknownQueryParamsError, UnknownQueryParamsLayer};
+use crate::request_scope::{JwtAuthLayer, JwtVerifier, ScopedSearchService};
 use crate::rest_api_request_span::{make_http_request_span, set_status_code_on_request_span};
 use crate::rest_api_response::{RestApiError, RestApiResponse};
+use crate::rest_rate_limit::RestRateLimitLayer;
+use crate::rest_request_filter::RestRequestFilterLayer;
+use crate::result_bytes::ResultBytesLayer;
+use crate::retry_hints::RetryHintsLayer;
+use crate::root_route::root_handler;
+use crate::route_not_found::{RouteNotFound, with_not_found_body};
 use crate::search_api::{
+    batch_search_handler, clear_cache_handler, count_handler, distinct_values_handler,
+    export_handler, get_document_handler, list_aggregations_handler, merge_stream_handler,
     search_get_handler, search_plan_get_handler, search_plan_post_handler, search_post_handler,
 };
 use crate::template_api::index_template_api_handlers;
 use crate::ui_handler::ui_handler;
-use crate::{BodyFormat, BuildInfo, QuickwitServices, RuntimeInfo};
+use crate::{BodyFormat, BuildInfo, QuickwitServices, RuntimeInfo, with_arg};
 
 #[derive(Debug)]
 pub(crate) struct InvalidJsonRequest(pub serde_json::Error);
@@ -88,36 +121,22 @@ impl std::fmt::Display for TooManyRequests {
     }
 }
 
-/// Env variable key to define the minimum size above which a response should be compressed.
-/// If unset, no compression is applied.
-const QW_MINIMUM_COMPRESSION_SIZE_KEY: &str = "QW_MINIMUM_COMPRESSION_SIZE";
-
-#[derive(Clone, Copy)]
-struct CompressionPredicate {
-    size_above_opt: Option<SizeAbove>,
+/// Rejection of an ingest request exceeding the ingest concurrency limit, as configured with
+/// `ingest_api.backpressure`. Answered with a `503 Service Unavailable` and a `Retry-After` header.
+#[derive(Debug)]
+pub struct Backpressured {
+    pub retry_after: Duration,
 }
 
-impl CompressionPredicate {
-    fn from_env() -> CompressionPredicate {
-        let minimum_compression_size_opt: Option<u16> =
-            quickwit_common::get_from_env_opt::<usize>(QW_MINIMUM_COMPRESSION_SIZE_KEY, false).map(
-                |minimum_compression_size: usize| {
-                    u16::try_from(minimum_compression_size).unwrap_or(u16::MAX)
-                },
-            );
-        let size_above_opt = minimum_compression_size_opt.map(SizeAbove::new);
-        CompressionPredicate { size_above_opt }
-    }
-}
+impl warp::reject::Reject for Backpressured {}
 
-impl Predicate for CompressionPredicate {
-    fn should_compress<B>(&self, response: &http::Response<B>) -> bool
-    where B: http_body::Body {
-        if let Some(size_above) = self.size_above_opt {
-            size_above.should_compress(response)
-        } else {
-            false
-        }
+impl std::fmt::Display for Backpressured {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "too many concurrent ingest requests, retry in {}s",
+            self.retry_after.as_secs()
+        )
     }
 }
 
@@ -152,7 +171,7 @@ pub(crate) async fn start_rest_server(
     shutdown_signal: BoxFutureInfaillible<()>,
 ) -> anyhow::Result<()> {
     let request_counter = warp::log::custom(|info: Info| {
-        let elapsed = info.elapsed();
+        let elapsed_secs = info.elapsed().as_secs_f64();
         let labels = labels!(
             "method" => info.method().as_str().to_string(),
             "status_code" => info.status().as_str().to_string()
@@ -161,7 +180,22 @@ pub(crate) async fn start_rest_server(
             parent: REQUEST_DURATION_SECS,
             labels: [labels],
         )
-        .observe(elapsed.as_secs_f64());
+        .observe(elapsed_secs);
+
+        // The search and ingest routes also have a histogram with buckets fitting their latencies.
+        match RouteGroup::from_path(info.path()) {
+            RouteGroup::Search => histogram!(
+                parent: SEARCH_REQUEST_DURATION_SECS,
+                labels: [labels],
+            )
+            .observe(elapsed_secs),
+            RouteGroup::Ingest => histogram!(
+                parent: INGEST_REQUEST_DURATION_SECS,
+                labels: [labels],
+            )
+            .observe(elapsed_secs),
+            RouteGroup::Other => {}
+        }
         counter!(
             parent: HTTP_REQUESTS_TOTAL,
             labels: [labels],
@@ -169,15 +203,11 @@ pub(crate) async fn start_rest_server(
         .inc();
     });
     // Docs routes
-    let api_doc = warp::path("openapi.json")
-        .and(warp::get())
-        .map(|| warp::reply::json(&crate::openapi::build_docs()))
-        .recover(recover_fn)
-        .boxed();
+    let api_doc = openapi_doc_handler();
 
     // `/health/*` routes.
     let health_check_routes = health_check_handlers(
-        quickwit_services.cluster.clone(),
+        quickwit_services.node_readiness.clone(),
         quickwit_services.indexing_service_opt.clone(),
         quickwit_services.janitor_service_opt.clone(),
     )
@@ -194,37 +224,36 @@ pub(crate) async fn start_rest_server(
     let developer_routes = developer_api_routes(
         quickwit_services.cluster.clone(),
         quickwit_services.env_filter_reload_fn.clone(),
+        quickwit_services.maintenance_mode.clone(),
+        quickwit_services.live_rest_config.clone(),
+        quickwit_services.node_config.clone(),
     )
     .boxed();
 
     // `/api/v1/*` routes.
     let api_v1_root_route = api_v1_routes(quickwit_services.clone());
 
-    let redirect_root_to_ui_route = warp::path::end()
-        .and(warp::get())
-        .map(|| redirect(http::Uri::from_static("/ui/search")))
-        .recover(recover_fn)
-        .boxed();
+    let root_route = root_handler(quickwit_services.node_config.rest_config.root_route);
 
-    let extra_headers = warp::reply::with::headers(
-        quickwit_services
-            .node_config
-            .rest_config
-            .extra_headers
-            .clone(),
-    );
+    let access_logger = access_log(quickwit_services.node_config.rest_config.access_log);
+    let not_found_config = Arc::new(quickwit_services.node_config.rest_config.not_found.clone());
 
     // Combine all the routes together.
     let rest_routes = api_v1_root_route
         .or(api_doc)
-        .or(redirect_root_to_ui_route)
+        .or(root_route)
         .or(ui_handler())
         .or(health_check_routes)
         .or(metrics_routes)
         .or(developer_routes)
         .with(request_counter)
+        .with(access_logger)
         .recover(recover_fn_final)
-        .with(extra_headers)
+        .and(warp::header::headers_cloned())
+        .and(with_arg(not_found_config))
+        .map(with_not_found_body)
+        .and(with_arg(quickwit_services.live_rest_config.clone()))
+        .map(with_extra_headers)
         .boxed();
 
     let tls_acceptor_opt: Option<TlsAcceptor> = if let Some(tls_config) =
@@ -237,6 +266,11 @@ pub(crate) async fn start_rest_server(
         None
     };
     let rest_config = &quickwit_services.node_config.rest_config;
+    let jwt_auth_layer_opt = rest_config
+        .jwt
+        .as_ref()
+        .map(|jwt_config| JwtVerifier::from_config(jwt_config).map(JwtAuthLayer::new))
+        .transpose()?;
     // `max_connection_age_grace` without `max_connection_age` is rejected at config validation, so
     // the grace is only carried when an age is present.
     let max_connection_age_opt =
@@ -250,13 +284,33 @@ pub(crate) async fn start_rest_server(
                     .as_ref()
                     .map(|max_connection_age_grace| **max_connection_age_grace),
             });
+    let idle_connection_timeout_opt = rest_config
+        .idle_connection_timeout
+        .as_ref()
+        .map(|idle_connection_timeout| **idle_connection_timeout);
+    let rate_limit_layer_opt = rest_config.rate_limit.as_ref().map(RestRateLimitLayer::new);
+    let request_filter_layer_opt = if rest_config.request_filters.is_empty() {
+        None
+    } else {
+        Some(RestRequestFilterLayer::new(&rest_config.request_filters)?)
+    };
     serve_warp_routes(
         "REST",
         tcp_listener,
         rest_routes,
-        rest_config.cors_allow_origins.clone(),
+        quickwit_services.live_rest_config.clone(),
         tls_acceptor_opt,
         max_connection_age_opt,
+        idle_connection_timeout_opt,
+        rest_config.max_pipelined_requests,
+        request_filter_layer_opt,
+        rate_limit_layer_opt,
+        jwt_auth_layer_opt,
+        HeaderLimits::new(rest_config),
+        &rest_config.compression,
+        rest_config.default_response_format.into(),
+        rest_config.unknown_query_params,
+        rest_config.retry_budget,
         readiness_trigger,
         shutdown_signal,
     )
@@ -275,21 +329,42 @@ pub(crate) async fn start_health_check_server(
     shutdown_signal: BoxFutureInfaillible<()>,
 ) -> anyhow::Result<()> {
     let health_check_routes = health_check_handlers(
-        quickwit_services.cluster.clone(),
+        quickwit_services.node_readiness.clone(),
         quickwit_services.indexing_service_opt.clone(),
         quickwit_services.janitor_service_opt.clone(),
     )
     .recover(recover_fn_final)
     .boxed();
+    let live_rest_config = LiveRestConfig::new(Vec::new(), &[], HeaderMap::new())?;
     // No TLS: the whole point of this server is to offer a plaintext probe surface that bypasses
     // the mTLS configured on the main REST server.
     serve_warp_routes(
         "health check",
         tcp_listener,
         health_check_routes,
-        Vec::new(),
+        live_rest_config,
+        None,
+        None,
+        None,
+        quickwit_services
+            .node_config
+            .rest_config
+            .max_pipelined_requests,
         None,
         None,
+        None,
+        HeaderLimits::new(&quickwit_services.node_config.rest_config),
+        &quickwit_services.node_config.rest_config.compression,
+        quickwit_services
+            .node_config
+            .rest_config
+            .default_response_format
+            .into(),
+        quickwit_services
+            .node_config
+            .rest_config
+            .unknown_query_params,
+        quickwit_services.node_config.rest_config.retry_budget,
         readiness_trigger,
         shutdown_signal,
     )
@@ -307,18 +382,64 @@ struct MaxConnectionAge {
     grace: Option<Duration>,
 }
 
+/// Limits on the headers of incoming requests, enforced by hyper before any handler runs. Requests
+/// exceeding them are answered with a `431 Request Header Fields Too Large`.
+#[derive(Clone, Copy, Default)]
+struct HeaderLimits {
+    max_header_list_size_opt: Option<ByteSize>,
+    max_header_count_opt: Option<NonZeroUsize>,
+}
+
+impl HeaderLimits {
+    fn new(rest_config: &RestConfig) -> Self {
+        HeaderLimits {
+            max_header_list_size_opt: rest_config.max_header_list_size,
+            max_header_count_opt: rest_config.max_header_count,
+        }
+    }
+
+    fn apply(&self, server: &mut Builder<TokioExecutor>) {
+        if let Some(max_header_list_size) = self.max_header_list_size_opt {
+            let max_header_list_num_bytes = max_header_list_size.as_u64();
+            // An HTTP/1 request head must fit in the read buffer, which is at least 8KiB as
+            // enforced by the config validation.
+            server
+                .http1()
+                .max_buf_size(max_header_list_num_bytes as usize);
+            server
+                .http2()
+                .max_header_list_size(u32::try_from(max_header_list_num_bytes).unwrap_or(u32::MAX));
+        }
+        if let Some(max_header_count) = self.max_header_count_opt {
+            server.http1().max_headers(max_header_count.get());
+        }
+    }
+}
+
 /// Serves a set of warp `routes` over `tcp_listener` until `shutdown_signal` resolves, optionally
-/// terminating TLS. Shared by the main REST server and the health-check server.
-// `serve_warp_routes` wires together several independent concerns (routing, CORS, TLS, connection
-// lifetime, readiness, shutdown); bundling them further would not aid readability.
+/// terminating TLS and verifying the tokens of API requests. Shared by the main REST server and
+/// the health-check server.
+// `serve_warp_routes` wires together several independent concerns (routing, CORS, TLS, auth,
+// compression, connection lifetime, readiness, shutdown); bundling them further would not aid
+// readability.
 #[allow(clippy::too_many_arguments)]
 async fn serve_warp_routes<F>(
     server_name: &str,
     tcp_listener: TcpListener,
     routes: F,
-    cors_allow_origins: Vec<String>,
+    live_rest_config: LiveRestConfig,
     tls_acceptor_opt: Option<TlsAcceptor>,
     max_connection_age_opt: Option<MaxConnectionAge>,
+    idle_connection_timeout_opt: Option<Duration>,
+    max_pipelined_requests_opt: Option<NonZeroUsize>,
+    request_filter_layer_opt: Option<RestRequestFilterLayer>,
+    rate_limit_layer_opt: Option<RestRateLimitLayer>,
+    jwt_auth_layer_opt: Option<JwtAuthLayer>,
+    header_limits: HeaderLimits,
+    compression_config: &RestCompressionConfig,
+    default_body_format: BodyFormat,
+    unknown_query_params: RestUnknownQueryParams,
+    retry_budget_opt: Option<u32>,
     readiness_trigger: BoxFutureInfaillible<()>,
     shutdown_signal: BoxFutureInfaillible<()>,
 ) -> anyhow::Result<()>
@@ -327,8 +448,6 @@ where
     F::Extract: Reply,
 {
     let warp_service = warp::service(routes);
-    let compression_predicate = CompressionPredicate::from_env().and(NotForContentType::IMAGES);
-    let cors = build_cors(&cors_allow_origins);
 
     let trace_layer = TraceLayer::new_for_http()
         .make_span_with(make_http_request_span as fn(&http::Request<_>) -> tracing::Span)
@@ -339,22 +458,22 @@ where
 
     let service = ServiceBuilder::new()
         .layer(trace_layer)
-        .layer(
-            CompressionLayer::new()
-                .zstd(true)
-                .gzip(true)
-                .quality(tower_http::CompressionLevel::Fastest)
-                .compress_when(compression_predicate),
-        )
-        .layer(cors)
+        .layer(DefaultBodyFormatLayer::new(default_body_format))
+        .layer(UnknownQueryParamsLayer::new(unknown_query_params))
+        .layer(RetryHintsLayer::new(retry_budget_opt))
+        .layer(ResultBytesLayer)
+        .layer(build_compression_layer(compression_config))
+        .layer(live_rest_config.cors_layer())
+        .option_layer(request_filter_layer_opt)
+        .option_layer(rate_limit_layer_opt)
+        .option_layer(jwt_auth_layer_opt)
         .service(warp_service);
 
     let listen_addr = tcp_listener.local_addr()?;
     info!(listen_addr=?listen_addr, "starting {server_name} server listening on {listen_addr}");
 
-    let service = TowerToHyperService::new(service);
-
-    let server = Builder::new(TokioExecutor::new());
+    let mut server = Builder::new(TokioExecutor::new());
+    header_limits.apply(&mut server);
     // Triggers a graceful shutdown (HTTP/2 GOAWAY) on every live connection. Fired once on server
     // shutdown; each connection also drains on its own when `max_connection_age` elapses.
     let cancellation_token = CancellationToken::new();
@@ -390,14 +509,26 @@ where
                         continue;
                     }
                 };
+                let (connection, connection_activity) = ActivityTrackingIo::new(connection);
+                let (connection, pipelined_requests) = PipelineTrackingIo::new(connection);
+                let connection_service = MaxPipelinedRequestsService::new(
+                    service.clone(),
+                    pipelined_requests,
+                    max_pipelined_requests_opt,
+                );
                 let serve_connection_fut = server
-                    .serve_connection_with_upgrades(TokioIo::new(connection), service.clone())
+                    .serve_connection_with_upgrades(
+                        TokioIo::new(connection),
+                        TowerToHyperService::new(connection_service),
+                    )
                     .into_owned();
                 let cancellation_token = cancellation_token.clone();
                 connection_tasks.spawn(serve_connection(
                     serve_connection_fut,
                     cancellation_token,
                     max_connection_age_opt,
+                    connection_activity,
+                    idle_connection_timeout_opt,
                 ));
             },
             // Reap finished connection tasks so the set does not grow without bound on a
@@ -423,6 +554,9 @@ where
 /// drain is requested via `cancellation_token`. When a grace period is configured, the connection
 /// is forcefully closed (dropped) if it has not finished draining within that period.
 ///
+/// The connection is also drained when it has not read nor written anything for
+/// `idle_connection_timeout_opt`, letting its in-flight requests complete.
+///
 /// Bounding the connection lifetime is what lets a hot-reloaded TLS certificate eventually reach
 /// long-lived clients: the new certificate is only presented on a fresh handshake, so the client
 /// must reconnect to pick it up.
@@ -430,6 +564,8 @@ async fn serve_connection<C>(
     connection: C,
     cancellation_token: CancellationToken,
     max_connection_age_opt: Option<MaxConnectionAge>,
+    connection_activity: ConnectionActivity,
+    idle_connection_timeout_opt: Option<Duration>,
 ) where
     C: GracefulConnection,
     C::Error: std::fmt::Display,
@@ -440,8 +576,14 @@ async fn serve_connection<C>(
         Some(max_connection_age) => Either::Left(tokio::time::sleep(max_connection_age.age)),
         None => Either::Right(std::future::pending::<()>()),
     };
-    // Phase 1: serve until the connection ends on its own, its max age elapses, or a global drain
-    // is requested.
+    let idle = match idle_connection_timeout_opt {
+        Some(idle_connection_timeout) => {
+            Either::Left(connection_activity.idle(idle_connection_timeout))
+        }
+        None => Either::Right(std::future::pending::<()>()),
+    };
+    // Phase 1: serve until the connection ends on its own, its max age elapses, it goes idle, or a
+    // global drain is requested.
     let max_age_exceeded = tokio::select! {
         connection_res = connection.as_mut() => {
             if let Err(serve_error) = connection_res {
@@ -450,6 +592,7 @@ async fn serve_connection<C>(
             return;
         }
         _ = max_age_sleep => true,
+        _ = idle => false,
         _ = cancellation_token.cancelled() => false,
     };
     // Phase 2: we asked the peer to reconnect; send GOAWAY and let in-flight requests drain.
@@ -479,11 +622,13 @@ async fn serve_connection<C>(
 
 fn search_routes(
     search_service: Arc<dyn SearchService>,
+    enable_jsonp: bool,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
-    search_get_handler(search_service.clone())
+    search_get_handler(search_service.clone(), enable_jsonp)
         .or(search_post_handler(search_service.clone()))
         .or(search_plan_get_handler(search_service.clone()))
         .or(search_plan_post_handler(search_service.clone()))
+        .or(batch_search_handler(search_service.clone()))
         .recover(recover_fn)
         .boxed()
 }
@@ -491,20 +636,31 @@ fn search_routes(
 fn api_v1_routes(
     quickwit_services: Arc<QuickwitServices>,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    // When tokens are verified, searches are restricted to the scope of the token of the request.
+    let search_service: Arc<dyn SearchService> =
+        if quickwit_services.node_config.rest_config.jwt.is_some() {
+            Arc::new(ScopedSearchService::new(
+                quickwit_services.search_service.clone(),
+                quickwit_services.search_cluster_client.clone(),
+            ))
+        } else {
+            quickwit_services.search_service.clone()
+        };
     let api_v1_root_url = warp::path!("api" / "v1" / ..);
-    api_v1_root_url.and(
-        elastic_api_handlers(
+    // The routes remaining available to operators while the node is in maintenance mode or leaving
+    // the cluster: the cluster, node, and indexing routes, as well as the reads of the indexes and
+    // templates.
+    let operator_routes = cluster_handler(quickwit_services.cluster.clone())
+        .or(cluster_leave_handler(
             quickwit_services.cluster.clone(),
+            quickwit_services.node_decommission.clone(),
             quickwit_services.node_config.clone(),
-            quickwit_services.search_service.clone(),
-            quickwit_services.ingest_service.clone(),
-            quickwit_services.ingest_router_service.clone(),
-            quickwit_services.metastore_client.clone(),
-            quickwit_services.index_manager.clone(),
-            !disable_ingest_v1(),
-            enable_ingest_v2(),
-        )
-        .or(cluster_handler(quickwit_services.cluster.clone()))
+        ))
+        .or(cluster_leave_status_handler(
+            quickwit_services.cluster.clone(),
+            quickwit_services.node_decommission.clone(),
+        ))
+        .or(nodes_stats_handler(quickwit_services.cluster.clone()))
         .boxed()
         .or(node_info_handler(
             BuildInfo::get(),
@@ -512,42 +668,123 @@ fn api_v1_routes(
             quickwit_services.node_config.clone(),
         ))
         .boxed()
-        .or(indexing_get_handler(
-            quickwit_services.indexing_service_opt.clone(),
+        .or(config_validation_handler(
+            quickwit_services.node_config.clone(),
         ))
         .boxed()
-        .or(search_routes(quickwit_services.search_service.clone()))
-        .boxed()
-        .or(ingest_api_handlers(
-            quickwit_services.ingest_router_service.clone(),
-            quickwit_services.ingest_service.clone(),
-            quickwit_services.node_config.ingest_api_config.clone(),
-            !disable_ingest_v1(),
-            enable_ingest_v2(),
+        .or(indexing_get_handler(
+            quickwit_services.indexing_service_opt.clone(),
+            quickwit_services.metastore_client.clone(),
         ))
         .boxed()
-        .or(otlp_ingest_api_handlers(
-            quickwit_services.otlp_logs_service_opt.clone(),
-            quickwit_services.otlp_traces_service_opt.clone(),
+        .or(indexing_rebalance_handler(
+            quickwit_services.control_plane_client.clone(),
         ))
         .boxed()
-        .or(index_management_handlers(
+        .or(pause_indexing_handler(
             quickwit_services.index_manager.clone(),
-            quickwit_services.node_config.clone(),
-        ))
-        .boxed()
-        .or(delete_task_api_handlers(
-            quickwit_services.metastore_client.clone(),
         ))
-        .boxed()
-        .or(jaeger_api_handlers(
-            quickwit_services.jaeger_service_opt.clone(),
+        .or(resume_indexing_handler(
+            quickwit_services.index_manager.clone(),
         ))
         .boxed()
-        .or(index_template_api_handlers(
-            quickwit_services.metastore_client.clone(),
+        .or(warp::get().and(
+            index_management_handlers(
+                quickwit_services.index_manager.clone(),
+                quickwit_services.node_config.clone(),
+            )
+            .or(get_index_merges_handler(
+                quickwit_services.metastore_client.clone(),
+                quickwit_services.cluster.clone(),
+            ))
+            .or(index_template_api_handlers(
+                quickwit_services.metastore_client.clone(),
+                quickwit_services.node_config.default_index_root_uri.clone(),
+            )),
         ))
-        .boxed(),
+        .boxed();
+    // The other routes, rejected while the node is in maintenance mode or leaving the cluster.
+    let workload_routes = elastic_api_handlers(
+        quickwit_services.cluster.clone(),
+        quickwit_services.node_config.clone(),
+        search_service.clone(),
+        quickwit_services.ingest_service.clone(),
+        quickwit_services.ingest_router_service.clone(),
+        quickwit_services.metastore_client.clone(),
+        quickwit_services.index_manager.clone(),
+        quickwit_services.index_allow_list_opt.clone(),
+        !disable_ingest_v1(),
+        enable_ingest_v2(),
+    )
+    .or(search_routes(
+        search_service.clone(),
+        quickwit_services.node_config.rest_config.enable_jsonp,
+    ))
+    .boxed()
+    .or(get_document_handler(
+        search_service.clone(),
+        quickwit_services.metastore_client.clone(),
+    ))
+    .boxed()
+    .or(count_handler(search_service.clone()))
+    .boxed()
+    .or(clear_cache_handler(search_service.clone()))
+    .boxed()
+    .or(distinct_values_handler(search_service.clone()))
+    .boxed()
+    .or(list_aggregations_handler())
+    .boxed()
+    .or(export_handler(
+        search_service.clone(),
+        quickwit_services.metastore_client.clone(),
+    ))
+    .boxed()
+    .or(merge_stream_handler(search_service.clone()))
+    .boxed()
+    .or(ingest_api_handlers(
+        quickwit_services.ingest_router_service.clone(),
+        quickwit_services.ingest_service.clone(),
+        quickwit_services.metastore_client.clone(),
+        search_service.clone(),
+        quickwit_services.node_config.ingest_api_config.clone(),
+        quickwit_services.index_allow_list_opt.clone(),
+        !disable_ingest_v1(),
+        enable_ingest_v2(),
+    ))
+    .boxed()
+    .or(otlp_ingest_api_handlers(
+        quickwit_services.otlp_logs_service_opt.clone(),
+        quickwit_services.otlp_traces_service_opt.clone(),
+        quickwit_services.metastore_client.clone(),
+        quickwit_services.index_allow_list_opt.clone(),
+    ))
+    .boxed()
+    .or(index_management_handlers(
+        quickwit_services.index_manager.clone(),
+        quickwit_services.node_config.clone(),
+    ))
+    .boxed()
+    .or(delete_task_api_handlers(
+        quickwit_services.metastore_client.clone(),
+        search_service.clone(),
+    ))
+    .boxed()
+    .or(jaeger_api_handlers(
+        quickwit_services.jaeger_service_opt.clone(),
+    ))
+    .boxed()
+    .or(index_template_api_handlers(
+        quickwit_services.metastore_client.clone(),
+        quickwit_services.node_config.default_index_root_uri.clone(),
+    ))
+    .boxed()
+    .or(metastore_api_handlers(
+        quickwit_services.metastore_client.clone(),
+    ))
+    .boxed();
+    api_v1_root_url.and(
+        operator_routes
+            .or(node_leaving_filter(quickwit_services.node_readiness.clone()).and(workload_routes)),
     )
 }
 
@@ -564,18 +801,21 @@ fn api_v1_routes(
 // More on this here: https://github.com/seanmonstar/warp/issues/388.
 // We may use this work on the PR is merged: https://github.com/seanmonstar/warp/pull/909.
 pub async fn recover_fn(rejection: Rejection) -> Result<impl Reply, Rejection> {
+    let retry_after_opt = backpressure_retry_after(&rejection);
     let error = get_status_with_error(rejection)?;
     let status_code = error.status_code;
-    Ok(RestApiResponse::new::<(), _>(
-        &Err(error),
-        status_code,
-        BodyFormat::default(),
-    ))
+    let response =
+        RestApiResponse::new::<(), _>(&Err(error), status_code, BodyFormat::node_default())
+            .into_response();
+    Ok(with_retry_after(response, retry_after_opt))
 }
 
 pub async fn recover_fn_final(rejection: Rejection) -> Result<impl Reply, Rejection> {
+    let retry_after_opt = backpressure_retry_after(&rejection);
+    let mut is_route_not_found = false;
     let error = get_status_with_error(rejection).unwrap_or_else(|rejection: Rejection| {
         if rejection.is_not_found() {
+            is_route_not_found = true;
             RestApiError {
                 status_code: StatusCode::NOT_FOUND,
                 message: "Route not found".to_string(),
@@ -589,11 +829,34 @@ pub async fn recover_fn_final(rejection: Rejection) -> Result<impl Reply, Reject
         }
     });
     let status_code = error.status_code;
-    Ok(RestApiResponse::new::<(), _>(
-        &Err(error),
-        status_code,
-        BodyFormat::default(),
-    ))
+    let mut response =
+        RestApiResponse::new::<(), _>(&Err(error), status_code, BodyFormat::node_default())
+            .into_response();
+    if is_route_not_found {
+        response.extensions_mut().insert(RouteNotFound);
+    }
+    Ok(with_retry_after(response, retry_after_opt))
+}
+
+/// Returns the delay after which the client of a request rejected because of backpressure should
+/// retry.
+fn backpressure_retry_after(rejection: &Rejection) -> Option<Duration> {
+    rejection
+        .find::<Backpressured>()
+        .map(|backpressured| backpressured.retry_after)
+}
+
+fn with_retry_after(
+    mut response: warp::reply::Response,
+    retry_after_opt: Option<Duration>,
+) -> warp::reply::Response {
+    if let Some(retry_after) = retry_after_opt {
+        response.headers_mut().insert(
+            http::header::RETRY_AFTER,
+            HeaderValue::from(retry_after.as_secs()),
+        );
+    }
+    response
 }
 
 fn get_status_with_error(rejection: Rejection) -> Result<RestApiError, Rejection> {
@@ -602,6 +865,11 @@ fn get_status_with_error(rejection: Rejection) -> Result<RestApiError, Rejection
             status_code: StatusCode::UNSUPPORTED_MEDIA_TYPE,
             message: error.to_string(),
         })
+    } else if let Some(error) = rejection.find::<UnknownQueryParamsError>() {
+        Ok(RestApiError {
+            status_code: StatusCode::BAD_REQUEST,
+            message: error.to_string(),
+        })
     } else if let Some(error) = rejection.find::<serde_qs::Error>() {
         Ok(RestApiError {
             status_code: StatusCode::BAD_REQUEST,
@@ -634,6 +902,16 @@ fn get_status_with_error(rejection: Rejection) -> Result<RestApiError, Rejection
             status_code: StatusCode::BAD_REQUEST,
             message: error.to_string(),
         })
+    } else if let Some(error) = rejection.find::<BodyReadTimeout>() {
+        Ok(RestApiError {
+            status_code: StatusCode::REQUEST_TIMEOUT,
+            message: error.to_string(),
+        })
+    } else if let Some(error) = rejection.find::<BodyReadError>() {
+        Ok(RestApiError {
+            status_code: StatusCode::BAD_REQUEST,
+            message: error.to_string(),
+        })
     } else if let Some(error) = rejection.find::<warp::reject::InvalidQuery>() {
         Ok(RestApiError {
             status_code: StatusCode::BAD_REQUEST,
@@ -664,6 +942,21 @@ fn get_status_with_error(rejection: Rejection) -> Result<RestApiError, Rejection
             status_code: StatusCode::TOO_MANY_REQUESTS,
             message: err.to_string(),
         })
+    } else if let Some(error) = rejection.find::<Backpressured>() {
+        Ok(RestApiError {
+            status_code: StatusCode::SERVICE_UNAVAILABLE,
+            message: error.to_string(),
+        })
+    } else if let Some(error) = rejection.find::<UnderMaintenance>() {
+        Ok(RestApiError {
+            status_code: StatusCode::SERVICE_UNAVAILABLE,
+            message: error.to_string(),
+        })
+    } else if let Some(error) = rejection.find::<NodeLeaving>() {
+        Ok(RestApiError {
+            status_code: StatusCode::SERVICE_UNAVAILABLE,
+            message: error.to_string(),
+        })
     } else if let Some(error) = rejection.find::<InvalidArgument>() {
         // Happens when the url path or request body contains invalid argument(s).
         Ok(RestApiError {
@@ -680,11 +973,11 @@ fn get_status_with_error(rejection: Rejection) -> Result<RestApiError, Rejection
     }
 }
 
-fn build_cors(cors_origins: &[String]) -> CorsLayer {
+pub(crate) fn build_cors(cors_origins: &[String]) -> anyhow::Result<CorsLayer> {
     let debug_mode = quickwit_common::get_bool_from_env_cached!("QW_ENABLE_CORS_DEBUG", false);
     if debug_mode {
         info!("CORS debug mode is enabled, localhost and 127.0.0.1 origins will be allowed");
-        return CorsLayer::new()
+        let cors = CorsLayer::new()
             .allow_methods([
                 Method::GET,
                 Method::POST,
@@ -698,6 +991,7 @@ fn build_cors(cors_origins: &[String]) -> CorsLayer {
                     .any(|prefix| origin.as_bytes().starts_with(*prefix))
             }))
             .allow_headers([http::header::CONTENT_TYPE]);
+        return Ok(cors);
     }
 
     let mut cors = CorsLayer::new().allow_methods([
@@ -717,17 +1011,52 @@ fn build_cors(cors_origins: &[String]) -> CorsLayer {
             info!(origins = ?cors_origins, "CORS is enabled, the following origins will be allowed");
             let origins = cors_origins
                 .iter()
-                .map(|origin| origin.parse::<HeaderValue>().unwrap())
-                .collect::<Vec<_>>();
+                .map(|origin| parse_cors_origin(origin))
+                .collect::<anyhow::Result<Vec<_>>>()?;
             cors = cors.allow_origin(origins);
         };
     }
-    cors
+    Ok(cors)
+}
+
+/// Parses an origin allowed by the CORS policy, other than `*`.
+pub(crate) fn parse_cors_origin(origin: &str) -> anyhow::Result<HeaderValue> {
+    origin
+        .parse::<HeaderValue>()
+        .with_context(|| format!("invalid CORS origin `{origin}`"))
+}
+
+/// Builds the CORS layer of a per-origin policy. The layer is only applied to the requests sent
+/// from one of the origins of the policy, so it allows the origin of any request.
+pub(crate) fn build_cors_policy(cors_policy: &RestCorsPolicy) -> anyhow::Result<CorsLayer> {
+    let methods = cors_policy
+        .allow_methods
+        .iter()
+        .map(|method| {
+            Method::from_bytes(method.to_ascii_uppercase().as_bytes())
+                .with_context(|| format!("invalid CORS method `{method}`"))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let headers = cors_policy
+        .allow_headers
+        .iter()
+        .map(|header_name| {
+            HeaderName::from_bytes(header_name.as_bytes())
+                .with_context(|| format!("invalid CORS header name `{header_name}`"))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let cors = CorsLayer::new()
+        .allow_origin(AllowOrigin::mirror_request())
+        .allow_methods(methods)
+        .allow_headers(headers)
+        .allow_credentials(cors_policy.allow_credentials);
+    Ok(cors)
 }
 
 #[cfg(test)]
 mod tests {
     use std::future::Future;
+    use std::net::SocketAddr;
     use std::pin::Pin;
     use std::task::{Context, Poll};
 
@@ -738,13 +1067,20 @@ mod tests {
     use quickwit_proto::control_plane::ControlPlaneServiceClient;
     use quickwit_proto::ingest::router::IngestRouterServiceClient;
     use quickwit_proto::metastore::MetastoreServiceClient;
-    use quickwit_search::MockSearchService;
+    use quickwit_search::{
+        ClusterClient, InFlightLeafSearches, MockSearchService, SearchJobPlacer, SearcherPool,
+    };
     use quickwit_storage::StorageResolver;
+    use serde_json::Value as JsonValue;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tonic_health::server::health_reporter;
     use tower::Service;
-    use warp::http::HeaderName;
     use warp::hyper::{Request, Response, StatusCode};
 
     use super::*;
+    use crate::maintenance_mode::MaintenanceMode;
+    use crate::node_decommission::NodeDecommission;
+    use crate::node_readiness::NodeReadiness;
     use crate::rest::recover_fn_final;
 
     pub(crate) fn ingest_service_client() -> IngestServiceClient {
@@ -757,7 +1093,7 @@ mod tests {
     async fn test_cors() {
         // No cors enabled
         {
-            let cors = build_cors(&[]);
+            let cors = build_cors(&[]).unwrap();
 
             let mut layer = ServiceBuilder::new().layer(cors).service(HelloWorld);
 
@@ -788,7 +1124,7 @@ mod tests {
 
         // Wildcard cors enabled
         {
-            let cors = build_cors(&["*".to_string()]);
+            let cors = build_cors(&["*".to_string()]).unwrap();
 
             let mut layer = ServiceBuilder::new().layer(cors).service(HelloWorld);
 
@@ -825,7 +1161,7 @@ mod tests {
 
         // Specific origin cors enabled
         {
-            let cors = build_cors(&["https://quickwit.io".to_string()]);
+            let cors = build_cors(&["https://quickwit.io".to_string()]).unwrap();
 
             let mut layer = ServiceBuilder::new().layer(cors).service(HelloWorld);
 
@@ -879,7 +1215,8 @@ mod tests {
             let cors = build_cors(&[
                 "https://quickwit.io".to_string(),
                 "http://localhost:3000".to_string(),
-            ]);
+            ])
+            .unwrap();
 
             let mut layer = ServiceBuilder::new().layer(cors).service(HelloWorld);
 
@@ -965,17 +1302,7 @@ mod tests {
         }
     }
 
-    #[tokio::test]
-    async fn test_extra_headers() {
-        let mut node_config = NodeConfig::for_test();
-        node_config.rest_config.extra_headers.insert(
-            HeaderName::from_static("x-custom-header"),
-            HeaderValue::from_static("custom-value"),
-        );
-        node_config.rest_config.extra_headers.insert(
-            HeaderName::from_static("x-custom-header-2"),
-            HeaderValue::from_static("custom-value-2"),
-        );
+    async fn quickwit_services_for_test(node_config: NodeConfig) -> QuickwitServices {
         let metastore_client = MetastoreServiceClient::mocked();
         let index_service =
             IndexService::new(metastore_client.clone(), StorageResolver::unconfigured());
@@ -984,7 +1311,21 @@ mod tests {
         let cluster = create_cluster_for_test(Vec::new(), &[], &transport, false)
             .await
             .unwrap();
-        let quickwit_services = QuickwitServices {
+        let live_rest_config = LiveRestConfig::new(
+            node_config.rest_config.cors_allow_origins.clone(),
+            &node_config.rest_config.cors_policies,
+            node_config.rest_config.extra_headers.clone(),
+        )
+        .unwrap();
+        let (health_reporter, _health_service) = health_reporter();
+        let node_readiness = NodeReadiness::new(cluster.clone(), health_reporter).await;
+        let node_decommission = NodeDecommission::new(
+            node_readiness.clone(),
+            None,
+            None,
+            InFlightLeafSearches::default(),
+        );
+        QuickwitServices {
             _report_splits_subscription_handle_opt: None,
             _local_shards_update_listener_handle_opt: None,
             cluster,
@@ -992,6 +1333,7 @@ mod tests {
             control_plane_server_opt: None,
             control_plane_client,
             indexing_service_opt: None,
+            merge_scheduler_service_opt: None,
             index_manager: index_service,
             ingest_service: ingest_service_client(),
             ingest_router_opt: None,
@@ -1002,20 +1344,42 @@ mod tests {
             otlp_traces_service_opt: None,
             metastore_client,
             metastore_server_opt: None,
-            node_config: Arc::new(node_config.clone()),
+            node_config: Arc::new(node_config),
             search_service: Arc::new(MockSearchService::new()),
+            search_cluster_client: ClusterClient::new(
+                SearchJobPlacer::new(SearcherPool::default()),
+            ),
             jaeger_service_opt: None,
-            _compactor_supervisor_opt: None,
+            compactor_supervisor_opt: None,
             env_filter_reload_fn: crate::do_nothing_env_filter_reload_fn(),
+            maintenance_mode: MaintenanceMode::default(),
+            node_readiness,
+            node_decommission,
+            live_rest_config,
+            index_allow_list_opt: None,
             #[cfg(feature = "datafusion")]
             datafusion_session_builder: None,
-        };
+        }
+    }
+
+    #[tokio::test]
+    async fn test_extra_headers() {
+        let mut node_config = NodeConfig::for_test();
+        node_config.rest_config.extra_headers.insert(
+            HeaderName::from_static("x-custom-header"),
+            HeaderValue::from_static("custom-value"),
+        );
+        node_config.rest_config.extra_headers.insert(
+            HeaderName::from_static("x-custom-header-2"),
+            HeaderValue::from_static("custom-value-2"),
+        );
+        let quickwit_services = quickwit_services_for_test(node_config).await;
+        let live_rest_config = quickwit_services.live_rest_config.clone();
 
         let handler = api_v1_routes(Arc::new(quickwit_services))
             .recover(recover_fn_final)
-            .with(warp::reply::with::headers(
-                node_config.rest_config.extra_headers.clone(),
-            ));
+            .and(with_arg(live_rest_config))
+            .map(with_extra_headers);
 
         let resp = warp::test::request()
             .path("/api/v1/version")
@@ -1047,4 +1411,407 @@ mod tests {
             "custom-value-2"
         );
     }
+
+    #[tokio::test]
+    async fn test_maintenance_mode() {
+        let quickwit_services = quickwit_services_for_test(NodeConfig::for_test()).await;
+        let maintenance_mode = quickwit_services.maintenance_mode.clone();
+        maintenance_mode.set_enabled(true);
+
+        let handler = api_v1_routes(Arc::new(quickwit_services))
+            .or(warp::path("metrics").and(warp::get()).map(metrics_handler))
+            .recover(recover_fn_final);
+
+        let resp = warp::test::request()
+            .path("/api/v1/quickwit-demo-index/search?query=*")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 503);
+        let resp_body = std::str::from_utf8(resp.body()).unwrap();
+        assert!(resp_body.contains("maintenance mode"), "{resp_body}");
+
+        let resp = warp::test::request()
+            .method("POST")
+            .path("/api/v1/quickwit-demo-index/ingest")
+            .body(r#"{"body": "foo"}"#)
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 503);
+
+        // An index named like an operator route is not let through.
+        let resp = warp::test::request()
+            .path("/api/v1/indexing/search?query=*")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 503);
+
+        let resp = warp::test::request()
+            .path("/api/v1/version")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+
+        let resp = warp::test::request().path("/metrics").reply(&handler).await;
+        assert_eq!(resp.status(), 200);
+
+        maintenance_mode.set_enabled(false);
+
+        let resp = warp::test::request()
+            .path("/api/v1/version")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+    }
+
+    #[tokio::test]
+    async fn test_cluster_leave() {
+        let mut node_config = NodeConfig::for_test();
+        node_config.rest_config.enable_cluster_leave = true;
+        let quickwit_services = quickwit_services_for_test(node_config).await;
+        let cluster = quickwit_services.cluster.clone();
+        let node_readiness = quickwit_services.node_readiness.clone();
+        node_readiness.set_ready(true).await;
+        assert!(cluster.is_self_node_ready().await);
+
+        let handler = api_v1_routes(Arc::new(quickwit_services)).recover(recover_fn_final);
+
+        let resp = warp::test::request()
+            .path("/api/v1/cluster/leave")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(resp_json["status"], "not_requested");
+
+        let resp = warp::test::request()
+            .method("POST")
+            .path("/api/v1/cluster/leave")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 202);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(resp_json["node_id"], cluster.self_node_id().as_str());
+
+        assert!(node_readiness.is_leaving());
+        assert!(cluster.is_self_node_leaving().await);
+        assert!(!cluster.is_self_node_ready().await);
+
+        // The readiness reporting task cannot bring a leaving node back.
+        node_readiness.set_ready(true).await;
+        assert!(!node_readiness.is_ready());
+        assert!(!cluster.is_self_node_ready().await);
+
+        // The node has no in-flight work to drain.
+        loop {
+            let resp = warp::test::request()
+                .path("/api/v1/cluster/leave")
+                .reply(&handler)
+                .await;
+            let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+
+            if resp_json["status"] == "drained" {
+                break;
+            }
+            assert_eq!(resp_json["status"], "draining");
+            tokio::task::yield_now().await;
+        }
+        // Leaving again does not start another drain.
+        let resp = warp::test::request()
+            .method("POST")
+            .path("/api/v1/cluster/leave")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 202);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(resp_json["status"], "drained");
+
+        let resp = warp::test::request()
+            .path("/api/v1/quickwit-demo-index/search?query=*")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 503);
+        let resp_body = std::str::from_utf8(resp.body()).unwrap();
+        assert!(resp_body.contains("leaving the cluster"), "{resp_body}");
+
+        // An index named like an operator route is not let through.
+        let resp = warp::test::request()
+            .path("/api/v1/cluster/search?query=*")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 503);
+
+        let resp = warp::test::request()
+            .path("/api/v1/cluster")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+    }
+
+    #[tokio::test]
+    async fn test_cluster_leave_requires_opt_in_without_tokens() {
+        let quickwit_services = quickwit_services_for_test(NodeConfig::for_test()).await;
+        let node_readiness = quickwit_services.node_readiness.clone();
+        let handler = api_v1_routes(Arc::new(quickwit_services)).recover(recover_fn_final);
+
+        let resp = warp::test::request()
+            .method("POST")
+            .path("/api/v1/cluster/leave")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 403);
+        let resp_body = std::str::from_utf8(resp.body()).unwrap();
+        assert!(
+            resp_body.contains("rest.enable_cluster_leave"),
+            "{resp_body}"
+        );
+        assert!(!node_readiness.is_leaving());
+    }
+
+    async fn ping(tcp_stream: &mut TcpStream) {
+        tcp_stream
+            .write_all(b"GET /ping HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = Vec::new();
+        let mut buffer = [0u8; 1024];
+
+        while !response.ends_with(b"pong") {
+            let num_bytes = tcp_stream.read(&mut buffer).await.unwrap();
+            assert!(num_bytes > 0, "connection should not be closed");
+            response.extend_from_slice(&buffer[..num_bytes]);
+        }
+        assert!(response.starts_with(b"HTTP/1.1 200 OK"));
+    }
+
+    #[tokio::test]
+    async fn test_idle_connection_timeout() {
+        let tcp_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let rest_listen_addr = tcp_listener.local_addr().unwrap();
+        let routes = warp::path("ping").map(|| "pong");
+        let live_rest_config = LiveRestConfig::new(Vec::new(), &[], HeaderMap::new()).unwrap();
+        let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel::<()>();
+
+        let server_handle = tokio::spawn(async move {
+            serve_warp_routes(
+                "REST",
+                tcp_listener,
+                routes,
+                live_rest_config,
+                None,
+                None,
+                Some(Duration::from_millis(300)),
+                None,
+                None,
+                None,
+                None,
+                HeaderLimits::default(),
+                &RestCompressionConfig::default(),
+                BodyFormat::default(),
+                RestUnknownQueryParams::default(),
+                None,
+                Box::pin(async {}),
+                Box::pin(async move {
+                    let _ = shutdown_rx.await;
+                }),
+            )
+            .await
+        });
+        let mut idle_connection = TcpStream::connect(rest_listen_addr).await.unwrap();
+        let mut active_connection = TcpStream::connect(rest_listen_addr).await.unwrap();
+        ping(&mut idle_connection).await;
+        ping(&mut active_connection).await;
+
+        for _ in 0..6 {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            ping(&mut active_connection).await;
+        }
+        let mut buffer = [0u8; 1024];
+        let num_bytes =
+            tokio::time::timeout(Duration::from_secs(1), idle_connection.read(&mut buffer))
+                .await
+                .expect("idle connection should be closed")
+                .unwrap_or(0);
+        assert_eq!(num_bytes, 0);
+
+        ping(&mut active_connection).await;
+
+        shutdown_tx.send(()).unwrap();
+        server_handle.await.unwrap().unwrap();
+    }
+
+    /// Sends `num_requests` pipelined requests, and returns what the server sent back until it
+    /// closed the connection.
+    async fn pipeline_echo_requests(rest_listen_addr: SocketAddr, num_requests: usize) -> String {
+        let mut tcp_stream = TcpStream::connect(rest_listen_addr).await.unwrap();
+        let mut pipelined_requests = String::new();
+
+        for request_id in 0..num_requests {
+            // The last request closes the connection once answered.
+            let connection = if request_id + 1 == num_requests {
+                "close"
+            } else {
+                "keep-alive"
+            };
+            pipelined_requests.push_str(&format!(
+                "GET /echo/{request_id} HTTP/1.1\r\nHost: localhost\r\nConnection: \
+                 {connection}\r\n\r\n"
+            ));
+        }
+        tcp_stream
+            .write_all(pipelined_requests.as_bytes())
+            .await
+            .unwrap();
+
+        let mut responses = Vec::new();
+        tokio::time::timeout(
+            Duration::from_secs(5),
+            tcp_stream.read_to_end(&mut responses),
+        )
+        .await
+        .expect("connection should be closed")
+        .unwrap();
+        String::from_utf8(responses).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_pipelined_requests() {
+        let tcp_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let rest_listen_addr = tcp_listener.local_addr().unwrap();
+        let routes = warp::path!("echo" / usize).then(|request_id: usize| async move {
+            // Answers the first requests last if they were processed concurrently.
+            let delay_millis = 50u64.saturating_sub(request_id as u64 * 10);
+            tokio::time::sleep(Duration::from_millis(delay_millis)).await;
+            format!("echo-{request_id};")
+        });
+        let live_rest_config = LiveRestConfig::new(Vec::new(), &[], HeaderMap::new()).unwrap();
+        let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel::<()>();
+
+        let server_handle = tokio::spawn(async move {
+            serve_warp_routes(
+                "REST",
+                tcp_listener,
+                routes,
+                live_rest_config,
+                None,
+                None,
+                None,
+                NonZeroUsize::new(5),
+                None,
+                None,
+                None,
+                HeaderLimits::default(),
+                &RestCompressionConfig::default(),
+                BodyFormat::default(),
+                RestUnknownQueryParams::default(),
+                None,
+                Box::pin(async {}),
+                Box::pin(async move {
+                    let _ = shutdown_rx.await;
+                }),
+            )
+            .await
+        });
+        // The responses are sent in the order of the requests.
+        let responses = pipeline_echo_requests(rest_listen_addr, 5).await;
+        let echoes: Vec<&str> = responses
+            .split("HTTP/1.1 200 OK")
+            .skip(1)
+            .map(|response| {
+                let body = &response[response.find("echo-").unwrap()..];
+                &body[..body.find(';').unwrap()]
+            })
+            .collect();
+        assert_eq!(echoes, ["echo-0", "echo-1", "echo-2", "echo-3", "echo-4"]);
+
+        // The connection is closed once it exceeds the limit, without answering the exceeding
+        // requests.
+        let responses = pipeline_echo_requests(rest_listen_addr, 8).await;
+        assert_eq!(responses.matches("HTTP/1.1 200 OK").count(), 5);
+        assert!(responses.contains("echo-4;"));
+        assert!(!responses.contains("echo-5;"));
+
+        shutdown_tx.send(()).unwrap();
+        server_handle.await.unwrap().unwrap();
+    }
+
+    /// Sends a `GET /ping` request with the given `extra_headers`, and returns the status line of
+    /// the response.
+    async fn ping_with_headers(rest_listen_addr: SocketAddr, extra_headers: &str) -> String {
+        let mut tcp_stream = TcpStream::connect(rest_listen_addr).await.unwrap();
+        let request = format!(
+            "GET /ping HTTP/1.1\r\nHost: localhost\r\n{extra_headers}Connection: close\r\n\r\n"
+        );
+        tcp_stream.write_all(request.as_bytes()).await.unwrap();
+
+        // The server closes the connection without reading the rest of a rejected request, so the
+        // connection may be reset once the response is received.
+        let mut response = Vec::new();
+        let mut buffer = [0u8; 1024];
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while let Ok(num_bytes) = tcp_stream.read(&mut buffer).await {
+                if num_bytes == 0 {
+                    break;
+                }
+                response.extend_from_slice(&buffer[..num_bytes]);
+            }
+        })
+        .await
+        .expect("connection should be closed");
+        let response = String::from_utf8(response).unwrap();
+        response.lines().next().unwrap_or_default().to_string()
+    }
+
+    #[tokio::test]
+    async fn test_header_limits() {
+        let tcp_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let rest_listen_addr = tcp_listener.local_addr().unwrap();
+        let routes = warp::path("ping").map(|| "pong");
+        let live_rest_config = LiveRestConfig::new(Vec::new(), &[], HeaderMap::new()).unwrap();
+        let header_limits = HeaderLimits {
+            max_header_list_size_opt: Some(ByteSize::kib(8)),
+            max_header_count_opt: NonZeroUsize::new(8),
+        };
+        let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel::<()>();
+
+        let server_handle = tokio::spawn(async move {
+            serve_warp_routes(
+                "REST",
+                tcp_listener,
+                routes,
+                live_rest_config,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                header_limits,
+                &RestCompressionConfig::default(),
+                BodyFormat::default(),
+                RestUnknownQueryParams::default(),
+                None,
+                Box::pin(async {}),
+                Box::pin(async move {
+                    let _ = shutdown_rx.await;
+                }),
+            )
+            .await
+        });
+        let status_line = ping_with_headers(rest_listen_addr, "Cookie: session=abc\r\n").await;
+        assert_eq!(status_line, "HTTP/1.1 200 OK");
+
+        let oversized_cookie = format!("Cookie: session={}\r\n", "a".repeat(12 * 1024));
+        let status_line = ping_with_headers(rest_listen_addr, &oversized_cookie).await;
+        assert_eq!(status_line, "HTTP/1.1 431 Request Header Fields Too Large");
+
+        let too_many_headers: String = (0..16)
+            .map(|header_idx| format!("X-Forwarded-Hop-{header_idx}: proxy\r\n"))
+            .collect();
+        let status_line = ping_with_headers(rest_listen_addr, &too_many_headers).await;
+        assert_eq!(status_line, "HTTP/1.1 431 Request Header Fields Too Large");
+
+        shutdown_tx.send(()).unwrap();
+        server_handle.await.unwrap().unwrap();
+    }
 }
@@ -14,10 +14,11 @@
 
 use quickwit_proto::ServiceError;
 use serde::{self, Serialize};
+use tracing::warn;
 use warp::Reply;
 use warp::hyper::StatusCode;
 use warp::hyper::header::CONTENT_TYPE;
-use warp::hyper::http::HeaderValue;
+use warp::hyper::http::{HeaderName, HeaderValue};
 
 use crate::format::BodyFormat;
 
@@ -50,9 +51,51 @@ pub(crate) fn into_rest_api_response<T: serde::Serialize, E: ServiceError>(
     RestApiResponse::new(&rest_api_result, status_code, body_format)
 }
 
+/// Makes a JSON API response from a result carrying the response headers declared in the configs
+/// of the indexes targeted by the request.
+pub(crate) fn into_rest_api_response_with_index_headers<T, E, H>(
+    result: Result<(T, H), E>,
+    body_format: BodyFormat,
+) -> warp::reply::Response
+where
+    T: serde::Serialize,
+    E: ServiceError,
+    H: IntoIterator<Item = (String, String)>,
+{
+    let (rest_api_result, index_response_headers_opt) = match result {
+        Ok((value, index_response_headers)) => (Ok(value), Some(index_response_headers)),
+        Err(error) => (Err(error), None),
+    };
+    let mut response = into_rest_api_response(rest_api_result, body_format).into_response();
+
+    if let Some(index_response_headers) = index_response_headers_opt {
+        insert_index_response_headers(&mut response, index_response_headers);
+    }
+    response
+}
+
+/// Inserts the response headers declared in the configs of the indexes targeted by a request. The
+/// headers are validated when the index configs are created, so invalid headers are skipped.
+pub(crate) fn insert_index_response_headers(
+    response: &mut warp::reply::Response,
+    index_response_headers: impl IntoIterator<Item = (String, String)>,
+) {
+    for (name, value) in index_response_headers {
+        let (Ok(header_name), Ok(header_value)) = (
+            HeaderName::from_bytes(name.as_bytes()),
+            HeaderValue::from_str(&value),
+        ) else {
+            warn!(header_name=%name, "skipping invalid index response header");
+            continue;
+        };
+        response.headers_mut().insert(header_name, header_value);
+    }
+}
+
 /// A JSON reply for the REST API.
 pub struct RestApiResponse {
     status_code: StatusCode,
+    content_type: &'static str,
     inner: Result<Vec<u8>, ()>,
 }
 
@@ -63,7 +106,11 @@ impl RestApiResponse {
         body_format: BodyFormat,
     ) -> Self {
         let inner = body_format.result_to_vec(result);
-        RestApiResponse { status_code, inner }
+        RestApiResponse {
+            status_code,
+            content_type: body_format.content_type(),
+            inner,
+        }
     }
 }
 
@@ -75,7 +122,7 @@ impl Reply for RestApiResponse {
                 let mut response = warp::reply::Response::new(body.into());
                 response
                     .headers_mut()
-                    .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+                    .insert(CONTENT_TYPE, HeaderValue::from_static(self.content_type));
                 *response.status_mut() = self.status_code;
                 response
             }
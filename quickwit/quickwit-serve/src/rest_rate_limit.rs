@@ -0,0 +1,250 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Limits the rate of the API requests served by the node with a token bucket, and exposes the
+//! state of the bucket in the `X-RateLimit-*` headers of the responses so that well-behaved
+//! clients can throttle themselves.
+
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures::future::{BoxFuture, FutureExt};
+use quickwit_common::rate_limiter::{RateLimiter, RateLimiterSettings};
+use quickwit_common::tower::ConstantRate;
+use quickwit_config::RestRateLimitConfig;
+use tower::{Layer, Service};
+use warp::Reply;
+use warp::hyper::StatusCode;
+use warp::hyper::http::{self, HeaderMap, HeaderValue};
+
+use crate::BodyFormat;
+use crate::rest_api_response::{RestApiError, RestApiResponse};
+
+/// Capacity of the token bucket.
+const RATE_LIMIT_LIMIT_HEADER: &str = "x-ratelimit-limit";
+
+/// Number of tokens left in the bucket once the request is served.
+const RATE_LIMIT_REMAINING_HEADER: &str = "x-ratelimit-remaining";
+
+/// Number of seconds until the bucket is full again.
+const RATE_LIMIT_RESET_HEADER: &str = "x-ratelimit-reset";
+
+/// The health checks, the metrics, and the UI assets are not rate limited.
+fn is_rate_limited(path: &str) -> bool {
+    path.starts_with("/api/")
+}
+
+/// State of the token bucket after a request took a token from it, or failed to.
+struct RateLimitState {
+    is_allowed: bool,
+    limit: u64,
+    remaining: u64,
+    reset: Duration,
+    /// Time until a token is available, set when the request is not allowed.
+    retry_after_opt: Option<Duration>,
+}
+
+impl RateLimitState {
+    fn insert_headers(&self, headers: &mut HeaderMap) {
+        headers.insert(RATE_LIMIT_LIMIT_HEADER, HeaderValue::from(self.limit));
+        headers.insert(
+            RATE_LIMIT_REMAINING_HEADER,
+            HeaderValue::from(self.remaining),
+        );
+        headers.insert(
+            RATE_LIMIT_RESET_HEADER,
+            HeaderValue::from(ceil_secs(self.reset)),
+        );
+
+        if let Some(retry_after) = self.retry_after_opt {
+            headers.insert(
+                http::header::RETRY_AFTER,
+                HeaderValue::from(ceil_secs(retry_after)),
+            );
+        }
+    }
+}
+
+fn ceil_secs(duration: Duration) -> u64 {
+    duration.as_secs_f64().ceil() as u64
+}
+
+/// Rejects the API requests exceeding the rate limit of the node, configured with
+/// `rest.rate_limit`, with a 429, and adds the `X-RateLimit-*` headers to the responses of all the
+/// API requests.
+#[derive(Clone)]
+pub(crate) struct RestRateLimitLayer {
+    rate_limiter: Arc<Mutex<RateLimiter>>,
+}
+
+impl RestRateLimitLayer {
+    pub fn new(rate_limit_config: &RestRateLimitConfig) -> Self {
+        let refill_period = Duration::from_secs(1);
+        let rate_limiter_settings = RateLimiterSettings {
+            burst_limit: rate_limit_config.burst.get(),
+            rate_limit: ConstantRate::new(rate_limit_config.requests_per_sec.get(), refill_period),
+            refill_period,
+        };
+        let rate_limiter = RateLimiter::from_settings(rate_limiter_settings);
+        Self {
+            rate_limiter: Arc::new(Mutex::new(rate_limiter)),
+        }
+    }
+}
+
+impl<S> Layer<S> for RestRateLimitLayer {
+    type Service = RestRateLimitService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RestRateLimitService {
+            inner,
+            rate_limiter: self.rate_limiter.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct RestRateLimitService<S> {
+    inner: S,
+    rate_limiter: Arc<Mutex<RateLimiter>>,
+}
+
+impl<S> RestRateLimitService<S> {
+    fn acquire(&self) -> RateLimitState {
+        let mut rate_limiter = self
+            .rate_limiter
+            .lock()
+            .expect("lock should not be poisoned");
+        let is_allowed = rate_limiter.acquire(1);
+        let retry_after_opt = if is_allowed {
+            None
+        } else {
+            Some(rate_limiter.time_until_available(1))
+        };
+        let limit = rate_limiter.max_capacity();
+
+        RateLimitState {
+            is_allowed,
+            limit,
+            remaining: rate_limiter.available_permits(),
+            reset: rate_limiter.time_until_available(limit),
+            retry_after_opt,
+        }
+    }
+}
+
+impl<S, B> Service<http::Request<B>> for RestRateLimitService<S>
+where
+    S: Service<http::Request<B>, Response = warp::reply::Response>,
+    S::Future: Send + 'static,
+{
+    type Response = warp::reply::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        if !is_rate_limited(request.uri().path()) {
+            return self.inner.call(request).boxed();
+        }
+        let rate_limit_state = self.acquire();
+
+        if !rate_limit_state.is_allowed {
+            let mut response = too_many_requests_response();
+            rate_limit_state.insert_headers(response.headers_mut());
+            return futures::future::ready(Ok(response)).boxed();
+        }
+        let response_fut = self.inner.call(request);
+
+        async move {
+            let mut response = response_fut.await?;
+            rate_limit_state.insert_headers(response.headers_mut());
+            Ok(response)
+        }
+        .boxed()
+    }
+}
+
+fn too_many_requests_response() -> warp::reply::Response {
+    let rest_api_error = RestApiError {
+        status_code: StatusCode::TOO_MANY_REQUESTS,
+        message: "too many requests: the rate limit of the node is exceeded".to_string(),
+    };
+    RestApiResponse::new::<(), _>(
+        &Err(rest_api_error),
+        StatusCode::TOO_MANY_REQUESTS,
+        BodyFormat::node_default(),
+    )
+    .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroU64;
+
+    use tower::ServiceExt;
+    use warp::Filter;
+
+    use super::*;
+
+    async fn call(rate_limit_layer: &RestRateLimitLayer, path: &str) -> (StatusCode, HeaderMap) {
+        let routes = warp::path!("api" / "v1" / "version")
+            .or(warp::path!("health" / "livez"))
+            .map(|_| warp::reply());
+        let request = http::Request::builder()
+            .uri(path)
+            .body(String::new())
+            .unwrap();
+        let response = rate_limit_layer
+            .layer(warp::service(routes))
+            .oneshot(request)
+            .await
+            .unwrap();
+        (response.status(), response.headers().clone())
+    }
+
+    #[tokio::test]
+    async fn test_rest_rate_limit_headers() {
+        let rate_limit_config = RestRateLimitConfig {
+            requests_per_sec: NonZeroU64::new(1).unwrap(),
+            burst: NonZeroU64::new(3).unwrap(),
+        };
+        let rate_limit_layer = RestRateLimitLayer::new(&rate_limit_config);
+
+        // Each request takes a token, so the bucket takes one more second to refill.
+        for (expected_remaining, expected_reset) in [("2", "1"), ("1", "2"), ("0", "3")] {
+            let (status_code, headers) = call(&rate_limit_layer, "/api/v1/version").await;
+            assert_eq!(status_code, StatusCode::OK);
+            assert_eq!(headers[RATE_LIMIT_LIMIT_HEADER], "3");
+            assert_eq!(headers[RATE_LIMIT_REMAINING_HEADER], expected_remaining);
+            assert_eq!(headers[RATE_LIMIT_RESET_HEADER], expected_reset);
+            assert!(headers.get(http::header::RETRY_AFTER).is_none());
+        }
+        let (status_code, headers) = call(&rate_limit_layer, "/api/v1/version").await;
+        assert_eq!(status_code, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(headers[RATE_LIMIT_LIMIT_HEADER], "3");
+        assert_eq!(headers[RATE_LIMIT_REMAINING_HEADER], "0");
+        assert_eq!(headers[RATE_LIMIT_RESET_HEADER], "3");
+        assert_eq!(headers[http::header::RETRY_AFTER], "1");
+
+        // The health checks are not rate limited.
+        let (status_code, headers) = call(&rate_limit_layer, "/health/livez").await;
+        assert_eq!(status_code, StatusCode::OK);
+        assert!(headers.get(RATE_LIMIT_REMAINING_HEADER).is_none());
+    }
+}
@@ -0,0 +1,353 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Rejects the REST requests matching one of the filtering rules of the node, configured with
+//! `rest.request_filters`, with a 403 before they reach the handlers, so that known-bad request
+//! patterns can be blocked without a separate proxy.
+
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use anyhow::Context as _;
+use futures::future::{BoxFuture, FutureExt};
+use percent_encoding::percent_decode_str;
+use quickwit_config::RestRequestFilterRule;
+use regex::Regex;
+use tower::{Layer, Service};
+use tracing::debug;
+use warp::Reply;
+use warp::hyper::StatusCode;
+use warp::hyper::http::{self, HeaderName, Method};
+
+use crate::BodyFormat;
+use crate::rest_api_response::{RestApiError, RestApiResponse};
+
+/// Request filtering rule with its regular expressions compiled.
+struct RequestFilter {
+    path_regex_opt: Option<Regex>,
+    methods: Vec<Method>,
+    header_name_opt: Option<HeaderName>,
+    header_value_regex_opt: Option<Regex>,
+}
+
+impl RequestFilter {
+    fn compile(request_filter_rule: &RestRequestFilterRule) -> anyhow::Result<Self> {
+        let path_regex_opt = request_filter_rule
+            .path
+            .as_deref()
+            .map(Regex::new)
+            .transpose()
+            .context("invalid `path` regular expression")?;
+        let methods = request_filter_rule
+            .methods
+            .iter()
+            .map(|method| Method::from_bytes(method.to_ascii_uppercase().as_bytes()))
+            .collect::<Result<Vec<Method>, _>>()
+            .context("invalid method")?;
+        let header_name_opt = request_filter_rule
+            .header
+            .as_ref()
+            .map(|header_name| HeaderName::from_bytes(header_name.as_bytes()))
+            .transpose()
+            .context("invalid header name")?;
+        let header_value_regex_opt = request_filter_rule
+            .header_value
+            .as_deref()
+            .map(Regex::new)
+            .transpose()
+            .context("invalid `header_value` regular expression")?;
+        Ok(Self {
+            path_regex_opt,
+            methods,
+            header_name_opt,
+            header_value_regex_opt,
+        })
+    }
+
+    fn matches<B>(&self, request: &http::Request<B>, normalized_path: &str) -> bool {
+        if let Some(path_regex) = &self.path_regex_opt
+            && !path_regex.is_match(normalized_path)
+        {
+            return false;
+        }
+        if !self.methods.is_empty() && !self.methods.contains(request.method()) {
+            return false;
+        }
+        let Some(header_name) = &self.header_name_opt else {
+            return true;
+        };
+        let mut header_values = request.headers().get_all(header_name).iter();
+
+        match &self.header_value_regex_opt {
+            Some(header_value_regex) => header_values.any(|header_value| {
+                matches!(
+                    header_value.to_str(),
+                    Ok(header_value_str) if header_value_regex.is_match(header_value_str)
+                )
+            }),
+            None => header_values.next().is_some(),
+        }
+    }
+}
+
+/// Rejects the requests matching one of the request filtering rules of the node with a 403. The
+/// rules are compiled once, when the layer is built.
+#[derive(Clone)]
+pub(crate) struct RestRequestFilterLayer {
+    request_filters: Arc<Vec<RequestFilter>>,
+}
+
+impl RestRequestFilterLayer {
+    pub fn new(request_filter_rules: &[RestRequestFilterRule]) -> anyhow::Result<Self> {
+        let request_filters = request_filter_rules
+            .iter()
+            .enumerate()
+            .map(|(rule_idx, request_filter_rule)| {
+                RequestFilter::compile(request_filter_rule)
+                    .with_context(|| format!("invalid rule `rest.request_filters[{rule_idx}]`"))
+            })
+            .collect::<anyhow::Result<Vec<RequestFilter>>>()?;
+        Ok(Self {
+            request_filters: Arc::new(request_filters),
+        })
+    }
+}
+
+impl<S> Layer<S> for RestRequestFilterLayer {
+    type Service = RestRequestFilterService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RestRequestFilterService {
+            inner,
+            request_filters: self.request_filters.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct RestRequestFilterService<S> {
+    inner: S,
+    request_filters: Arc<Vec<RequestFilter>>,
+}
+
+impl<S, B> Service<http::Request<B>> for RestRequestFilterService<S>
+where
+    S: Service<http::Request<B>, Response = warp::reply::Response>,
+    S::Future: Send + 'static,
+{
+    type Response = warp::reply::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        let normalized_path = normalize_path(request.uri().path());
+        let is_blocked = self
+            .request_filters
+            .iter()
+            .any(|request_filter| request_filter.matches(&request, &normalized_path));
+
+        if is_blocked {
+            debug!(method=%request.method(), path=%normalized_path, "request blocked");
+            return futures::future::ready(Ok(forbidden_response())).boxed();
+        }
+        self.inner.call(request).boxed()
+    }
+}
+
+/// Percent-decodes the path of a request and resolves its empty, `.`, and `..` segments, so that
+/// the rules cannot be bypassed by encoding the path differently from the routes matching it.
+fn normalize_path(path: &str) -> String {
+    let decoded_path = percent_decode_str(path).decode_utf8_lossy();
+    let mut segments: Vec<&str> = Vec::new();
+
+    for segment in decoded_path.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            _ => segments.push(segment),
+        }
+    }
+    let mut normalized_path = String::with_capacity(decoded_path.len());
+
+    for segment in &segments {
+        normalized_path.push('/');
+        normalized_path.push_str(segment);
+    }
+    if segments.is_empty() || decoded_path.ends_with('/') {
+        normalized_path.push('/');
+    }
+    normalized_path
+}
+
+fn forbidden_response() -> warp::reply::Response {
+    let rest_api_error = RestApiError {
+        status_code: StatusCode::FORBIDDEN,
+        message: "forbidden: the request is blocked by the request filtering rules of the node"
+            .to_string(),
+    };
+    RestApiResponse::new::<(), _>(
+        &Err(rest_api_error),
+        StatusCode::FORBIDDEN,
+        BodyFormat::node_default(),
+    )
+    .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use tower::ServiceExt;
+    use warp::Filter;
+
+    use super::*;
+
+    async fn call(
+        request_filter_layer: &RestRequestFilterLayer,
+        request: http::Request<String>,
+    ) -> StatusCode {
+        let routes = warp::path!("api" / "v1" / String / "search")
+            .or(warp::path!("api" / "v1" / "indexes" / String))
+            .map(|_| warp::reply());
+        let response = request_filter_layer
+            .layer(warp::service(routes))
+            .oneshot(request)
+            .await
+            .unwrap();
+        response.status()
+    }
+
+    fn request(method: Method, path: &str) -> http::Request<String> {
+        http::Request::builder()
+            .method(method)
+            .uri(path)
+            .body(String::new())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_rest_request_filter() {
+        let request_filter_rules = [
+            RestRequestFilterRule {
+                path: Some("^/api/v1/secret-[^/]*/".to_string()),
+                ..Default::default()
+            },
+            RestRequestFilterRule {
+                path: Some("^/api/v1/indexes/".to_string()),
+                methods: vec!["delete".to_string()],
+                ..Default::default()
+            },
+            RestRequestFilterRule {
+                header: Some("user-agent".to_string()),
+                header_value: Some("(?i)badbot".to_string()),
+                ..Default::default()
+            },
+        ];
+        let request_filter_layer = RestRequestFilterLayer::new(&request_filter_rules).unwrap();
+
+        let blocked_request = request(Method::GET, "/api/v1/secret-index/search");
+        assert_eq!(
+            call(&request_filter_layer, blocked_request).await,
+            StatusCode::FORBIDDEN
+        );
+        let allowed_request = request(Method::GET, "/api/v1/public-index/search");
+        assert_eq!(
+            call(&request_filter_layer, allowed_request).await,
+            StatusCode::OK
+        );
+        // The rules match on all their conditions.
+        let blocked_request = request(Method::DELETE, "/api/v1/indexes/my-index");
+        assert_eq!(
+            call(&request_filter_layer, blocked_request).await,
+            StatusCode::FORBIDDEN
+        );
+        let allowed_request = request(Method::GET, "/api/v1/indexes/my-index");
+        assert_eq!(
+            call(&request_filter_layer, allowed_request).await,
+            StatusCode::OK
+        );
+        let mut blocked_request = request(Method::GET, "/api/v1/public-index/search");
+        blocked_request
+            .headers_mut()
+            .insert(http::header::USER_AGENT, "BadBot/1.0".parse().unwrap());
+        assert_eq!(
+            call(&request_filter_layer, blocked_request).await,
+            StatusCode::FORBIDDEN
+        );
+        let mut allowed_request = request(Method::GET, "/api/v1/public-index/search");
+        allowed_request
+            .headers_mut()
+            .insert(http::header::USER_AGENT, "curl/8.0".parse().unwrap());
+        assert_eq!(
+            call(&request_filter_layer, allowed_request).await,
+            StatusCode::OK
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rest_request_filter_encoded_path() {
+        let request_filter_rules = [RestRequestFilterRule {
+            path: Some("^/api/v1/secret-[^/]*/".to_string()),
+            ..Default::default()
+        }];
+        let request_filter_layer = RestRequestFilterLayer::new(&request_filter_rules).unwrap();
+
+        for blocked_path in [
+            "/api/v1/%73ecret-index/search",
+            "/api%2Fv1/secret-index/search",
+            "//api/v1//secret-index/search",
+            "/api/./v1/public-index/../secret-index/search",
+        ] {
+            let blocked_request = request(Method::GET, blocked_path);
+            assert_eq!(
+                call(&request_filter_layer, blocked_request).await,
+                StatusCode::FORBIDDEN,
+                "`{blocked_path}` should be blocked"
+            );
+        }
+        let allowed_request = request(Method::GET, "/api/v1/public%2Dindex/search");
+        assert_eq!(
+            call(&request_filter_layer, allowed_request).await,
+            StatusCode::OK
+        );
+    }
+
+    #[test]
+    fn test_normalize_path() {
+        assert_eq!(normalize_path(""), "/");
+        assert_eq!(normalize_path("/"), "/");
+        assert_eq!(normalize_path("/api/v1/"), "/api/v1/");
+        assert_eq!(normalize_path("/api/v1/%69ndexes"), "/api/v1/indexes");
+        assert_eq!(normalize_path("/api//v1/./indexes"), "/api/v1/indexes");
+        assert_eq!(normalize_path("/../api/v1/x/../indexes"), "/api/v1/indexes");
+    }
+
+    #[test]
+    fn test_rest_request_filter_invalid_regex() {
+        let request_filter_rules = [RestRequestFilterRule {
+            path: Some("^/api/v1/(".to_string()),
+            ..Default::default()
+        }];
+        let error = RestRequestFilterLayer::new(&request_filter_rules)
+            .err()
+            .unwrap();
+        let error_message = format!("{error:#}");
+        assert!(error_message.contains("rest.request_filters[0]"));
+        assert!(error_message.contains("invalid `path` regular expression"));
+    }
+}
@@ -0,0 +1,155 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Reports the size of the body of the search responses, as sent to the client, for metering the
+//! egress of the searches.
+
+use std::task::{Context, Poll};
+
+use bytes::Bytes;
+use futures::future::{BoxFuture, FutureExt};
+use http_body::Body;
+use http_body_util::{BodyExt, Either, Full};
+use tower::{BoxError, Layer, Service};
+use tracing::error;
+use warp::hyper::StatusCode;
+use warp::hyper::http::{self, HeaderValue};
+
+const RESULT_BYTES_HEADER: &str = "x-quickwit-result-bytes";
+
+/// Marks the responses whose body size must be returned in the `X-Quickwit-Result-Bytes` header.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct MeasureResultBytes;
+
+/// Adds the `X-Quickwit-Result-Bytes` header, set to the size of the body, to the responses marked
+/// with [`MeasureResultBytes`]. The layer wraps the compression layer, so the size is the one of
+/// the body as written to the client. The marked bodies are buffered to be measured.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct ResultBytesLayer;
+
+impl<S> Layer<S> for ResultBytesLayer {
+    type Service = ResultBytesService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ResultBytesService { inner }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct ResultBytesService<S> {
+    inner: S,
+}
+
+impl<S, B, ResBody> Service<http::Request<B>> for ResultBytesService<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<ResBody>>,
+    S::Error: Send + 'static,
+    S::Future: Send + 'static,
+    ResBody: Body<Data = Bytes> + Send + 'static,
+    ResBody::Error: Into<BoxError>,
+{
+    type Response = http::Response<Either<ResBody, Full<Bytes>>>;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        let response_fut = self.inner.call(request);
+
+        async move {
+            let response = response_fut.await?;
+
+            if response.extensions().get::<MeasureResultBytes>().is_none() {
+                return Ok(response.map(Either::Left));
+            }
+            let (mut parts, body) = response.into_parts();
+            let body_bytes = match body.collect().await {
+                Ok(collected_body) => collected_body.to_bytes(),
+                Err(error) => {
+                    let error: BoxError = error.into();
+                    error!(%error, "failed to read response body");
+                    parts.status = StatusCode::INTERNAL_SERVER_ERROR;
+                    parts.headers.remove(http::header::CONTENT_ENCODING);
+                    parts.headers.remove(http::header::CONTENT_LENGTH);
+                    return Ok(http::Response::from_parts(
+                        parts,
+                        Either::Right(Full::default()),
+                    ));
+                }
+            };
+            parts
+                .headers
+                .insert(RESULT_BYTES_HEADER, HeaderValue::from(body_bytes.len()));
+            Ok(http::Response::from_parts(
+                parts,
+                Either::Right(Full::new(body_bytes)),
+            ))
+        }
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use bytesize::ByteSize;
+    use quickwit_config::RestCompressionConfig;
+    use tower::{ServiceBuilder, ServiceExt};
+
+    use super::*;
+    use crate::compression::build_compression_layer;
+
+    async fn call(measure_result_bytes: bool) -> (http::HeaderMap, Bytes) {
+        let compression_config = RestCompressionConfig {
+            min_size: Some(ByteSize::b(10)),
+            best_quality_min_size: ByteSize::kb(1),
+        };
+        let service = ServiceBuilder::new()
+            .layer(ResultBytesLayer)
+            .layer(build_compression_layer(&compression_config))
+            .service_fn(move |_request: http::Request<String>| async move {
+                let mut response = http::Response::new("a".repeat(100));
+                if measure_result_bytes {
+                    response.extensions_mut().insert(MeasureResultBytes);
+                }
+                Ok::<_, Infallible>(response)
+            });
+        let request = http::Request::builder()
+            .header(http::header::ACCEPT_ENCODING, "gzip")
+            .body(String::new())
+            .unwrap();
+        let response = service.oneshot(request).await.unwrap();
+        let headers = response.headers().clone();
+        let body_bytes = response.into_body().collect().await.unwrap().to_bytes();
+        (headers, body_bytes)
+    }
+
+    #[tokio::test]
+    async fn test_result_bytes() {
+        let (headers, _body_bytes) = call(false).await;
+        assert!(headers.get(RESULT_BYTES_HEADER).is_none());
+
+        let (headers, body_bytes) = call(true).await;
+        assert_eq!(headers[http::header::CONTENT_ENCODING], "gzip");
+        assert!(body_bytes.len() < 100);
+        assert_eq!(
+            headers[RESULT_BYTES_HEADER],
+            body_bytes.len().to_string().as_str()
+        );
+    }
+}
@@ -0,0 +1,214 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tells clients whether they can retry the requests that failed, so client libraries can decide
+//! automatically. A failed request is retryable when it was rejected before being processed, or
+//! when the error is transient and the operation idempotent.
+
+use std::task::{Context, Poll};
+
+use futures::future::{BoxFuture, FutureExt};
+use tower::{Layer, Service};
+use warp::hyper::StatusCode;
+use warp::hyper::http::{self, HeaderValue, Method};
+
+const RETRYABLE_HEADER: &str = "x-quickwit-retryable";
+
+const RETRY_BUDGET_HEADER: &str = "x-quickwit-retry-budget";
+
+/// Suffixes of the paths of the `POST` routes that do not modify anything, such as searches.
+const READ_ONLY_POST_PATH_SUFFIXES: &[&str] = &[
+    "/search",
+    "/search-plan",
+    "/count",
+    "/analyze",
+    "/parse-query",
+    "/_validate/document",
+    "/_search",
+    "/_search/scroll",
+    "/_msearch",
+    "/_count",
+    "/_field_caps",
+];
+
+/// Returns whether performing the request several times has the same effect as performing it
+/// once.
+fn is_idempotent(method: &Method, path: &str) -> bool {
+    match *method {
+        Method::GET | Method::HEAD | Method::OPTIONS | Method::PUT | Method::DELETE => true,
+        Method::POST => {
+            let path = path.trim_end_matches('/');
+            READ_ONLY_POST_PATH_SUFFIXES
+                .iter()
+                .any(|suffix| path.ends_with(suffix))
+        }
+        _ => false,
+    }
+}
+
+fn is_retryable(status_code: StatusCode, is_idempotent: bool) -> bool {
+    match status_code {
+        // The request was rejected before being processed.
+        StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE => true,
+        // The request may have been partially processed.
+        StatusCode::REQUEST_TIMEOUT => is_idempotent,
+        _ => status_code.is_server_error() && is_idempotent,
+    }
+}
+
+/// Adds the `X-Quickwit-Retryable` header to the error responses and, when a retry budget is
+/// configured with `rest.retry_budget`, the `X-Quickwit-Retry-Budget` header to the retryable
+/// ones.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct RetryHintsLayer {
+    retry_budget_opt: Option<u32>,
+}
+
+impl RetryHintsLayer {
+    pub fn new(retry_budget_opt: Option<u32>) -> Self {
+        Self { retry_budget_opt }
+    }
+}
+
+impl<S> Layer<S> for RetryHintsLayer {
+    type Service = RetryHintsService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RetryHintsService {
+            inner,
+            retry_budget_opt: self.retry_budget_opt,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct RetryHintsService<S> {
+    inner: S,
+    retry_budget_opt: Option<u32>,
+}
+
+impl<S, B, ResBody> Service<http::Request<B>> for RetryHintsService<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<ResBody>>,
+    S::Future: Send + 'static,
+{
+    type Response = http::Response<ResBody>;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        let is_idempotent = is_idempotent(request.method(), request.uri().path());
+        let retry_budget_opt = self.retry_budget_opt;
+        let response_fut = self.inner.call(request);
+
+        async move {
+            let mut response = response_fut.await?;
+            let status_code = response.status();
+
+            if !status_code.is_client_error() && !status_code.is_server_error() {
+                return Ok(response);
+            }
+            let is_retryable = is_retryable(status_code, is_idempotent);
+            let headers = response.headers_mut();
+            headers.insert(
+                RETRYABLE_HEADER,
+                HeaderValue::from_static(if is_retryable { "true" } else { "false" }),
+            );
+            if is_retryable && let Some(retry_budget) = retry_budget_opt {
+                headers.insert(RETRY_BUDGET_HEADER, HeaderValue::from(retry_budget));
+            }
+            Ok(response)
+        }
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+    use tower::ServiceExt;
+    use warp::Filter;
+
+    use super::*;
+    use crate::node_decommission::NodeLeaving;
+    use crate::rest::recover_fn_final;
+
+    #[derive(Deserialize)]
+    struct SearchParams {
+        #[serde(rename = "max_hits")]
+        _max_hits: u64,
+    }
+
+    async fn call(method: Method, path: &str) -> (StatusCode, http::HeaderMap) {
+        let search_route = warp::path!(String / "search")
+            .and(warp::query::<SearchParams>())
+            .map(|_index_id: String, _search_params: SearchParams| warp::reply());
+        // The node rejects the ingest requests while it is leaving the cluster.
+        let ingest_route = warp::path!(String / "ingest").and_then(|_index_id: String| async {
+            Err::<String, _>(warp::reject::custom(NodeLeaving))
+        });
+        let routes = search_route.or(ingest_route).recover(recover_fn_final);
+        let request = http::Request::builder()
+            .method(method)
+            .uri(path)
+            .body(String::new())
+            .unwrap();
+        let response = RetryHintsLayer::new(Some(3))
+            .layer(warp::service(routes))
+            .oneshot(request)
+            .await
+            .unwrap();
+        (response.status(), response.headers().clone())
+    }
+
+    #[tokio::test]
+    async fn test_retry_hints() {
+        let (status_code, headers) = call(Method::GET, "/my-index/search?max_hits=10").await;
+        assert_eq!(status_code, StatusCode::OK);
+        assert!(headers.get(RETRYABLE_HEADER).is_none());
+
+        let (status_code, headers) = call(Method::GET, "/my-index/search?max_hits=ten").await;
+        assert_eq!(status_code, StatusCode::BAD_REQUEST);
+        assert_eq!(headers[RETRYABLE_HEADER], "false");
+        assert!(headers.get(RETRY_BUDGET_HEADER).is_none());
+
+        let (status_code, headers) = call(Method::POST, "/my-index/ingest").await;
+        assert_eq!(status_code, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(headers[RETRYABLE_HEADER], "true");
+        assert_eq!(headers[RETRY_BUDGET_HEADER], "3");
+    }
+
+    #[test]
+    fn test_is_retryable() {
+        assert!(is_idempotent(&Method::GET, "/api/v1/indexes"));
+        assert!(is_idempotent(&Method::POST, "/api/v1/my-index/search"));
+        assert!(is_idempotent(
+            &Method::POST,
+            "/api/v1/_elastic/_search/scroll/"
+        ));
+        assert!(!is_idempotent(&Method::POST, "/api/v1/my-index/ingest"));
+        assert!(!is_idempotent(&Method::PATCH, "/api/v1/indexes/my-index"));
+
+        assert!(is_retryable(StatusCode::SERVICE_UNAVAILABLE, false));
+        assert!(is_retryable(StatusCode::TOO_MANY_REQUESTS, false));
+        assert!(is_retryable(StatusCode::INTERNAL_SERVER_ERROR, true));
+        assert!(!is_retryable(StatusCode::INTERNAL_SERVER_ERROR, false));
+        assert!(!is_retryable(StatusCode::BAD_REQUEST, true));
+        assert!(!is_retryable(StatusCode::NOT_FOUND, true));
+    }
+}
@@ -0,0 +1,97 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use quickwit_config::RestRootRoute;
+use serde_json::json;
+use warp::hyper::http::Uri;
+use warp::reply::Response;
+use warp::{Filter, Rejection, Reply, redirect};
+
+use crate::BuildInfo;
+use crate::rest::recover_fn;
+
+/// Handler of the `GET /` requests, answered according to the `rest.root_route` setting.
+pub(crate) fn root_handler(
+    root_route: RestRootRoute,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path::end()
+        .and(warp::get())
+        .and_then(move || async move { root_reply(root_route) })
+        .recover(recover_fn)
+        .boxed()
+}
+
+fn root_reply(root_route: RestRootRoute) -> Result<Response, Rejection> {
+    match root_route {
+        RestRootRoute::RedirectToUi => Ok(redirect(Uri::from_static("/ui/search")).into_response()),
+        RestRootRoute::ApiIndex => Ok(warp::reply::json(&api_index_document()).into_response()),
+        // The request then gets the configured response of the requests matching no route.
+        RestRootRoute::NotFound => Err(warp::reject::not_found()),
+    }
+}
+
+/// Document pointing API clients to the entry points of the API.
+fn api_index_document() -> serde_json::Value {
+    json!({
+        "name": "quickwit",
+        "version": BuildInfo::get().version,
+        "links": {
+            "api": "/api/v1",
+            "openapi": "/openapi.json",
+            "health": "/health/livez",
+            "metrics": "/metrics",
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value as JsonValue;
+    use warp::hyper::StatusCode;
+    use warp::hyper::header::LOCATION;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_root_route_redirect_to_ui() {
+        let handler = root_handler(RestRootRoute::RedirectToUi);
+        let resp = warp::test::request().path("/").reply(&handler).await;
+        assert_eq!(resp.status(), StatusCode::MOVED_PERMANENTLY);
+        assert_eq!(resp.headers()[LOCATION], "/ui/search");
+    }
+
+    #[tokio::test]
+    async fn test_root_route_api_index() {
+        let handler = root_handler(RestRootRoute::ApiIndex);
+        let resp = warp::test::request().path("/").reply(&handler).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(resp_json["name"], "quickwit");
+        assert_eq!(resp_json["version"], BuildInfo::get().version);
+        assert_eq!(resp_json["links"]["api"], "/api/v1");
+        assert_eq!(resp_json["links"]["openapi"], "/openapi.json");
+    }
+
+    #[tokio::test]
+    async fn test_root_route_not_found() {
+        let handler = root_handler(RestRootRoute::NotFound);
+        let rejection = warp::test::request()
+            .path("/")
+            .filter(&handler)
+            .await
+            .map(|_| ())
+            .unwrap_err();
+        assert!(rejection.is_not_found());
+    }
+}
@@ -0,0 +1,158 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use quickwit_config::RestNotFoundConfig;
+use warp::Reply;
+use warp::hyper::header::{ACCEPT, CONTENT_TYPE};
+use warp::hyper::http::{HeaderMap, HeaderValue};
+
+/// Response extension marking the responses to the requests matching no route.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct RouteNotFound;
+
+/// Replaces the body of the responses to the requests matching no route with the configured one,
+/// negotiated from the `Accept` header of the request: the HTML body is returned to the clients
+/// accepting `text/html`, typically browsers, and the JSON body to the other ones.
+pub(crate) fn with_not_found_body(
+    reply: impl Reply,
+    request_headers: HeaderMap,
+    not_found_config: Arc<RestNotFoundConfig>,
+) -> warp::reply::Response {
+    let mut response = reply.into_response();
+
+    if response.extensions().get::<RouteNotFound>().is_none() {
+        return response;
+    }
+    if let Some(html_body) = &not_found_config.html_body
+        && accepts_html(&request_headers)
+    {
+        *response.body_mut() = html_body.clone().into();
+        response.headers_mut().insert(
+            CONTENT_TYPE,
+            HeaderValue::from_static("text/html; charset=utf-8"),
+        );
+        return response;
+    }
+    if let Some(json_body) = &not_found_config.json_body
+        && let Ok(json_bytes) = serde_json::to_vec_pretty(json_body)
+    {
+        *response.body_mut() = json_bytes.into();
+        response
+            .headers_mut()
+            .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    }
+    response
+}
+
+/// Returns whether one of the media ranges of the `Accept` header is `text/html`.
+fn accepts_html(request_headers: &HeaderMap) -> bool {
+    request_headers
+        .get_all(ACCEPT)
+        .iter()
+        .filter_map(|header_value| header_value.to_str().ok())
+        .flat_map(|accept| accept.split(','))
+        .any(|media_range| {
+            let media_type = media_range.split(';').next().unwrap_or_default();
+            media_type.trim().eq_ignore_ascii_case("text/html")
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{Value as JsonValue, json};
+    use warp::Filter;
+
+    use super::*;
+    use crate::rest::recover_fn_final;
+    use crate::with_arg;
+
+    async fn get_not_found(
+        not_found_config: RestNotFoundConfig,
+        accept: &str,
+    ) -> warp::http::Response<warp::hyper::body::Bytes> {
+        let routes = warp::path("version")
+            .map(|| "0.8.0")
+            .recover(recover_fn_final)
+            .and(warp::header::headers_cloned())
+            .and(with_arg(Arc::new(not_found_config)))
+            .map(with_not_found_body);
+        warp::test::request()
+            .path("/unknown")
+            .header("accept", accept)
+            .reply(&routes)
+            .await
+    }
+
+    fn not_found_config() -> RestNotFoundConfig {
+        RestNotFoundConfig {
+            json_body: Some(json!({
+                "code": "route_not_found",
+                "docs": "https://quickwit.io/docs/reference/rest-api",
+            })),
+            html_body: Some("<h1>Page not found</h1>".to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_not_found_json_body_for_api_clients() {
+        for accept in ["application/json", "*/*"] {
+            let response = get_not_found(not_found_config(), accept).await;
+            assert_eq!(response.status(), 404);
+            assert_eq!(
+                response.headers().get(CONTENT_TYPE).unwrap(),
+                "application/json"
+            );
+            let response_json: JsonValue = serde_json::from_slice(response.body()).unwrap();
+            assert_eq!(response_json["code"], "route_not_found");
+            assert_eq!(
+                response_json["docs"],
+                "https://quickwit.io/docs/reference/rest-api"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_not_found_html_body_for_browsers() {
+        let accept = "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8";
+        let response = get_not_found(not_found_config(), accept).await;
+        assert_eq!(response.status(), 404);
+        assert_eq!(
+            response.headers().get(CONTENT_TYPE).unwrap(),
+            "text/html; charset=utf-8"
+        );
+        assert_eq!(response.body(), "<h1>Page not found</h1>");
+    }
+
+    #[tokio::test]
+    async fn test_not_found_default_body() {
+        let response = get_not_found(RestNotFoundConfig::default(), "text/html").await;
+        assert_eq!(response.status(), 404);
+        let response_json: JsonValue = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(response_json, json!({"message": "Route not found"}));
+    }
+
+    #[test]
+    fn test_accepts_html() {
+        let mut request_headers = HeaderMap::new();
+        assert!(!accepts_html(&request_headers));
+
+        request_headers.insert(ACCEPT, HeaderValue::from_static("application/json"));
+        assert!(!accepts_html(&request_headers));
+
+        request_headers.insert(ACCEPT, HeaderValue::from_static("Text/HTML; charset=utf-8"));
+        assert!(accepts_html(&request_headers));
+    }
+}
@@ -0,0 +1,290 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use quickwit_search::{AggregationResults, SearchError};
+use serde_json::Value as JsonValue;
+
+/// Name of the column holding the document count of the innermost buckets.
+const DOC_COUNT_COLUMN: &str = "doc_count";
+
+/// A row of the flattened aggregation results, as `(column, value)` pairs.
+type CsvRow = Vec<(String, JsonValue)>;
+
+/// Serializes the aggregation results as CSV, flattening the bucket paths into columns.
+///
+/// Each bucket aggregation contributes a column named after it, holding the key of the bucket
+/// (`key_as_string` when available). Nested bucket aggregations widen the rows, so that a `terms`
+/// aggregation named `service` nested in a `date_histogram` named `date` yields
+/// `date,service,doc_count` rows, where `doc_count` is the document count of the innermost bucket.
+/// Metric aggregations contribute a column named after them, or one column per value for
+/// multi-value metrics (e.g. `latency.min`, `latency.max`). Sibling bucket aggregations yield
+/// distinct rows, leaving each other's columns empty. A bucket whose bucket sub-aggregation is
+/// empty still yields a row, with an empty column for the sub-aggregation. The other fields of
+/// bucket aggregations, such as `sum_other_doc_count`, are not returned.
+pub(crate) fn aggregations_to_csv(
+    aggregations_opt: Option<&AggregationResults>,
+) -> Result<Vec<u8>, SearchError> {
+    let Some(aggregations) = aggregations_opt else {
+        return Ok(Vec::new());
+    };
+    let aggregations_json = serde_json::to_value(aggregations)?;
+    aggregations_json_to_csv(aggregations_json)
+}
+
+fn aggregations_json_to_csv(aggregations_json: JsonValue) -> Result<Vec<u8>, SearchError> {
+    let JsonValue::Object(aggregations_map) = aggregations_json else {
+        return Err(SearchError::Internal(
+            "aggregation results should serialize to a JSON object".to_string(),
+        ));
+    };
+    let aggregations: Vec<(&str, &JsonValue)> = aggregations_map
+        .iter()
+        .map(|(aggregation_name, aggregation_result)| {
+            (aggregation_name.as_str(), aggregation_result)
+        })
+        .collect();
+    let mut rows = Vec::new();
+    flatten_aggregations(aggregations, CsvRow::new(), None, &mut rows);
+
+    let mut columns: Vec<&str> = Vec::new();
+
+    for (column, _) in rows.iter().flatten() {
+        if !columns.contains(&column.as_str()) {
+            columns.push(column);
+        }
+    }
+    if columns.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut csv = String::new();
+    write_record(&mut csv, columns.iter().copied());
+
+    for row in &rows {
+        let values: Vec<String> = columns
+            .iter()
+            .map(|column| {
+                row.iter()
+                    .find(|(row_column, _)| row_column == column)
+                    .map(|(_, value)| format_value(value))
+                    .unwrap_or_default()
+            })
+            .collect();
+        write_record(&mut csv, values.iter().map(String::as_str));
+    }
+    Ok(csv.into_bytes())
+}
+
+/// Appends the rows obtained by flattening the aggregations of a bucket, or the top-level
+/// aggregations, to `rows`. `row` holds the columns of the enclosing buckets.
+fn flatten_aggregations(
+    aggregations: Vec<(&str, &JsonValue)>,
+    mut row: CsvRow,
+    doc_count_opt: Option<&JsonValue>,
+    rows: &mut Vec<CsvRow>,
+) {
+    let mut bucket_aggregations: Vec<(&str, Vec<&JsonValue>)> = Vec::new();
+
+    for (aggregation_name, aggregation_result) in aggregations {
+        match aggregation_result.get("buckets") {
+            Some(JsonValue::Array(buckets)) => {
+                bucket_aggregations.push((aggregation_name, buckets.iter().collect()));
+            }
+            Some(JsonValue::Object(keyed_buckets)) => {
+                bucket_aggregations.push((aggregation_name, keyed_buckets.values().collect()));
+            }
+            _ => flatten_metric(aggregation_name.to_string(), aggregation_result, &mut row),
+        }
+    }
+    if bucket_aggregations.is_empty() {
+        if let Some(doc_count) = doc_count_opt {
+            row.push((DOC_COUNT_COLUMN.to_string(), doc_count.clone()));
+        }
+        rows.push(row);
+        return;
+    }
+    for (aggregation_name, buckets) in bucket_aggregations {
+        if buckets.is_empty() {
+            // The enclosing bucket still gets a row, unless there is no enclosing bucket and no
+            // metric to report.
+            if doc_count_opt.is_none() && row.is_empty() {
+                continue;
+            }
+            let mut bucket_row = row.clone();
+            bucket_row.push((aggregation_name.to_string(), JsonValue::Null));
+
+            if let Some(doc_count) = doc_count_opt {
+                bucket_row.push((DOC_COUNT_COLUMN.to_string(), doc_count.clone()));
+            }
+            rows.push(bucket_row);
+            continue;
+        }
+        for bucket in buckets {
+            let JsonValue::Object(bucket_map) = bucket else {
+                continue;
+            };
+            let key = bucket_map
+                .get("key_as_string")
+                .or_else(|| bucket_map.get("key"))
+                .cloned()
+                .unwrap_or(JsonValue::Null);
+            let mut bucket_row = row.clone();
+            bucket_row.push((aggregation_name.to_string(), key));
+
+            // The sub-aggregations are the object fields of the bucket. Composite aggregations
+            // also have an object key, which is not a sub-aggregation.
+            let sub_aggregations: Vec<(&str, &JsonValue)> = bucket_map
+                .iter()
+                .filter(|(field, value)| *field != "key" && value.is_object())
+                .map(|(field, value)| (field.as_str(), value))
+                .collect();
+            flatten_aggregations(
+                sub_aggregations,
+                bucket_row,
+                bucket_map.get("doc_count"),
+                rows,
+            );
+        }
+    }
+}
+
+fn flatten_metric(column: String, metric_result: &JsonValue, row: &mut CsvRow) {
+    let JsonValue::Object(metric_map) = metric_result else {
+        row.push((column, metric_result.clone()));
+        return;
+    };
+    if let Some(value) = metric_map.get("value") {
+        row.push((column, value.clone()));
+        return;
+    }
+    for (field, value) in metric_map {
+        flatten_metric(format!("{column}.{field}"), value, row);
+    }
+}
+
+fn format_value(value: &JsonValue) -> String {
+    match value {
+        JsonValue::Null => String::new(),
+        JsonValue::String(value_str) => value_str.clone(),
+        _ => value.to_string(),
+    }
+}
+
+fn write_record<'a>(csv: &mut String, fields: impl Iterator<Item = &'a str>) {
+    for (field_idx, field) in fields.enumerate() {
+        if field_idx > 0 {
+            csv.push(',');
+        }
+        if field.contains([',', '"', '\n', '\r']) {
+            csv.push('"');
+            csv.push_str(&field.replace('"', "\"\""));
+            csv.push('"');
+        } else {
+            csv.push_str(field);
+        }
+    }
+    csv.push('\n');
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn to_csv_string(aggregations_json: JsonValue) -> String {
+        let csv = aggregations_json_to_csv(aggregations_json).unwrap();
+        String::from_utf8(csv).unwrap()
+    }
+
+    #[test]
+    fn test_aggregations_to_csv_flattens_two_level_aggregation() {
+        let aggregations_json = json!({
+            "date": {
+                "buckets": [
+                    {
+                        "key": 1704067200000.0,
+                        "key_as_string": "2024-01-01T00:00:00Z",
+                        "doc_count": 5,
+                        "service": {
+                            "buckets": [
+                                {"key": "api", "doc_count": 3},
+                                {"key": "db", "doc_count": 2},
+                            ],
+                            "sum_other_doc_count": 0,
+                        },
+                    },
+                    {
+                        "key": 1704153600000.0,
+                        "key_as_string": "2024-01-02T00:00:00Z",
+                        "doc_count": 1,
+                        "service": {
+                            "buckets": [{"key": "api", "doc_count": 1}],
+                            "sum_other_doc_count": 0,
+                        },
+                    },
+                ],
+            },
+        });
+        let expected_csv = "date,service,doc_count\n2024-01-01T00:00:00Z,api,3\n2024-01-01T00:00:\
+                            00Z,db,2\n2024-01-02T00:00:00Z,api,1\n";
+        assert_eq!(to_csv_string(aggregations_json), expected_csv);
+    }
+
+    #[test]
+    fn test_aggregations_to_csv_widens_columns() {
+        let aggregations_json = json!({
+            "service": {
+                "buckets": [
+                    {
+                        "key": "api, v2",
+                        "doc_count": 3,
+                        "latency": {"count": 3, "max": 12.0, "min": 1.0},
+                        "status": {
+                            "buckets": [{"key": 200, "doc_count": 3}],
+                        },
+                    },
+                    {
+                        "key": "db",
+                        "doc_count": 2,
+                        "latency": {"count": 2, "max": 7.0, "min": 4.0},
+                        "status": {"buckets": []},
+                    },
+                ],
+            },
+        });
+        let expected_csv =
+            "service,latency.count,latency.max,latency.min,status,doc_count\n\"api, \
+             v2\",3,12.0,1.0,200,3\ndb,2,7.0,4.0,,2\n";
+        assert_eq!(to_csv_string(aggregations_json), expected_csv);
+
+        // An empty top-level bucket aggregation yields no row.
+        let aggregations_json = json!({"service": {"buckets": []}});
+        assert_eq!(to_csv_string(aggregations_json), "");
+
+        let aggregations_json = json!({
+            "avg_latency": {"value": 6.5},
+            "max_latency": {"value": null},
+        });
+        assert_eq!(
+            to_csv_string(aggregations_json),
+            "avg_latency,max_latency\n6.5,\n"
+        );
+    }
+
+    #[test]
+    fn test_aggregations_to_csv_without_aggregations() {
+        let csv = aggregations_to_csv(None).unwrap();
+        assert!(csv.is_empty());
+    }
+}
@@ -0,0 +1,307 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::{iter, mem};
+
+use futures::stream;
+use quickwit_search::{AggregationResults, SearchError};
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use tantivy::aggregation::agg_result::{AggregationResult, BucketEntries, BucketResult};
+use warp::Reply;
+use warp::hyper::header::CONTENT_TYPE;
+use warp::hyper::http::HeaderValue;
+
+use crate::BodyFormat;
+use crate::rest_api_response::{insert_index_response_headers, into_rest_api_response};
+
+type NdjsonLines = Box<dyn Iterator<Item = serde_json::Result<Vec<u8>>> + Send>;
+
+/// A line of the newline-delimited JSON aggregation results.
+#[derive(Serialize)]
+struct AggregationLine<'a, B> {
+    aggregation: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bucket: Option<&'a B>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<&'a JsonValue>,
+}
+
+/// Serializes the aggregation results as newline-delimited JSON, so that clients can process
+/// the buckets of large aggregations one at a time instead of parsing a single JSON object. The
+/// lines are serialized one at a time as the body is streamed.
+///
+/// Each bucket of a top-level aggregation listing its buckets in an array (terms, histogram,
+/// composite, ...) is written as a `{"aggregation": <name>, "bucket": <bucket>}` line. The other
+/// fields of these aggregations, such as `sum_other_doc_count`, and the other aggregations are
+/// written as a `{"aggregation": <name>, "result": <result>}` line.
+pub(crate) fn aggregations_to_ndjson(aggregations_opt: Option<AggregationResults>) -> warp::Body {
+    let Some(aggregations) = aggregations_opt else {
+        return warp::Body::from(Vec::new());
+    };
+    warp::Body::wrap_stream(stream::iter(aggregations_ndjson_lines(aggregations)))
+}
+
+fn aggregations_ndjson_lines(
+    aggregations: AggregationResults,
+) -> impl Iterator<Item = serde_json::Result<Vec<u8>>> + Send + 'static {
+    let mut aggregations: Vec<(String, AggregationResult)> =
+        aggregations.into_inner().0.into_iter().collect();
+    aggregations.sort_unstable_by(|(left_name, _), (right_name, _)| left_name.cmp(right_name));
+    aggregations
+        .into_iter()
+        .flat_map(|(aggregation_name, aggregation_result)| {
+            aggregation_ndjson_lines(Arc::from(aggregation_name), aggregation_result)
+        })
+}
+
+fn aggregation_ndjson_lines(
+    aggregation_name: Arc<str>,
+    mut aggregation_result: AggregationResult,
+) -> impl Iterator<Item = serde_json::Result<Vec<u8>>> + Send + 'static {
+    let bucket_lines_opt = take_bucket_lines(&aggregation_name, &mut aggregation_result);
+    let has_taken_buckets = bucket_lines_opt.is_some();
+    let result_line_opt = iter::once_with(move || {
+        result_ndjson_line(&aggregation_name, &aggregation_result, has_taken_buckets)
+    })
+    .flatten();
+    bucket_lines_opt
+        .into_iter()
+        .flatten()
+        .chain(result_line_opt)
+}
+
+/// Takes the buckets out of the result of an aggregation listing them in an array. The returned
+/// lines serialize the buckets one at a time, as they are iterated over.
+fn take_bucket_lines(
+    aggregation_name: &Arc<str>,
+    aggregation_result: &mut AggregationResult,
+) -> Option<NdjsonLines> {
+    let AggregationResult::BucketResult(bucket_result) = aggregation_result else {
+        return None;
+    };
+    let aggregation_name = aggregation_name.clone();
+    let bucket_lines = match bucket_result {
+        BucketResult::Terms { buckets, .. }
+        | BucketResult::Histogram {
+            buckets: BucketEntries::Vec(buckets),
+        } => bucket_ndjson_lines(aggregation_name, mem::take(buckets)),
+        BucketResult::Range {
+            buckets: BucketEntries::Vec(buckets),
+        } => bucket_ndjson_lines(aggregation_name, mem::take(buckets)),
+        BucketResult::Composite { buckets, .. } => {
+            bucket_ndjson_lines(aggregation_name, mem::take(buckets))
+        }
+        // Keyed buckets are not streamed: they are written with the result.
+        _ => return None,
+    };
+    Some(bucket_lines)
+}
+
+fn bucket_ndjson_lines<B>(aggregation_name: Arc<str>, buckets: Vec<B>) -> NdjsonLines
+where B: Serialize + Send + 'static {
+    let bucket_lines = buckets
+        .into_iter()
+        .map(move |bucket| ndjson_line(&aggregation_name, Some(&bucket), None));
+    Box::new(bucket_lines)
+}
+
+fn result_ndjson_line(
+    aggregation_name: &str,
+    aggregation_result: &AggregationResult,
+    has_taken_buckets: bool,
+) -> Option<serde_json::Result<Vec<u8>>> {
+    let mut result = match serde_json::to_value(aggregation_result) {
+        Ok(result) => result,
+        Err(serde_error) => return Some(Err(serde_error)),
+    };
+    if has_taken_buckets && let JsonValue::Object(result_map) = &mut result {
+        // The buckets were taken out of the result and written on their own lines.
+        result_map.remove("buckets");
+
+        if result_map.is_empty() {
+            return None;
+        }
+    }
+    Some(ndjson_line::<JsonValue>(
+        aggregation_name,
+        None,
+        Some(&result),
+    ))
+}
+
+fn ndjson_line<B: Serialize>(
+    aggregation_name: &str,
+    bucket: Option<&B>,
+    result: Option<&JsonValue>,
+) -> serde_json::Result<Vec<u8>> {
+    let aggregation_line = AggregationLine {
+        aggregation: aggregation_name,
+        bucket,
+        result,
+    };
+    let mut line = serde_json::to_vec(&aggregation_line)?;
+    line.push(b'\n');
+    Ok(line)
+}
+
+/// Builds the response of a search returning only its aggregation results, serialized with the
+/// given content type, along with the response headers declared by the targeted indexes. Errors
+/// are returned as regular REST API errors.
+pub(crate) fn into_aggregations_response(
+    aggregations_result: Result<(warp::Body, HashMap<String, String>), SearchError>,
+    content_type: &'static str,
+    body_format: BodyFormat,
+) -> warp::reply::Response {
+    let (aggregations_body, index_response_headers) = match aggregations_result {
+        Ok(aggregations_and_headers) => aggregations_and_headers,
+        Err(search_error) => {
+            return into_rest_api_response::<(), _>(Err(search_error), body_format).into_response();
+        }
+    };
+    let mut response = warp::reply::Response::new(aggregations_body);
+    response
+        .headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+    insert_index_response_headers(&mut response, index_response_headers);
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use http_body_util::BodyExt;
+    use quickwit_query::aggregations::{
+        AggregationResult as AggregationResultProxy, AggregationResults as AggregationResultsProxy,
+        BucketEntries, BucketEntry, BucketResult, Key, MetricResult, RangeBucketEntry,
+    };
+    use serde_json::json;
+    use tantivy::aggregation::metric::SingleMetricResult;
+
+    use super::*;
+
+    fn service_bucket(service: &str, doc_count: u64, max_latency: f64) -> BucketEntry {
+        let max_latency_result =
+            AggregationResultProxy::MetricResult(MetricResult::Max(SingleMetricResult {
+                value: Some(max_latency),
+            }));
+        BucketEntry {
+            key_as_string: None,
+            key: Key::Str(service.to_string()),
+            doc_count,
+            sub_aggregation: AggregationResultsProxy(vec![(
+                "max_latency".to_string(),
+                max_latency_result,
+            )]),
+        }
+    }
+
+    #[test]
+    fn test_aggregations_to_ndjson_reassembles_into_buffered_response() {
+        let services_result = AggregationResultProxy::BucketResult(BucketResult::Terms {
+            buckets: vec![
+                service_bucket("api", 3, 12.0),
+                service_bucket("db", 2, 7.0),
+                service_bucket("web", 1, 3.0),
+            ],
+            sum_other_doc_count: 4,
+            doc_count_error_upper_bound: Some(0),
+        });
+        let latencies_result = AggregationResultProxy::BucketResult(BucketResult::Histogram {
+            buckets: BucketEntries::Vec(vec![
+                BucketEntry {
+                    key_as_string: None,
+                    key: Key::F64(0.0),
+                    doc_count: 4,
+                    sub_aggregation: AggregationResultsProxy(Vec::new()),
+                },
+                BucketEntry {
+                    key_as_string: None,
+                    key: Key::F64(10.0),
+                    doc_count: 2,
+                    sub_aggregation: AggregationResultsProxy(Vec::new()),
+                },
+            ]),
+        });
+        let ranges_result = AggregationResultProxy::BucketResult(BucketResult::Range {
+            buckets: BucketEntries::HashMap(vec![(
+                "*-10".to_string(),
+                RangeBucketEntry {
+                    key: Key::Str("*-10".to_string()),
+                    doc_count: 5,
+                    sub_aggregation: AggregationResultsProxy(Vec::new()),
+                    from: None,
+                    to: Some(10.0),
+                    from_as_string: None,
+                    to_as_string: None,
+                },
+            )]),
+        });
+        let avg_latency_result =
+            AggregationResultProxy::MetricResult(MetricResult::Average(SingleMetricResult {
+                value: Some(6.5),
+            }));
+        let aggregations_proxy = AggregationResultsProxy(vec![
+            ("services".to_string(), services_result),
+            ("latencies".to_string(), latencies_result),
+            ("ranges".to_string(), ranges_result),
+            ("avg_latency".to_string(), avg_latency_result),
+        ]);
+        let aggregations_json =
+            serde_json::to_value(AggregationResults::from(aggregations_proxy.clone())).unwrap();
+
+        let ndjson: Vec<u8> = aggregations_ndjson_lines(aggregations_proxy.into())
+            .flat_map(Result::unwrap)
+            .collect();
+
+        let mut reassembled_aggregations: BTreeMap<String, JsonValue> = BTreeMap::new();
+        for line in ndjson
+            .split(|byte| *byte == b'\n')
+            .filter(|line| !line.is_empty())
+        {
+            let line_json: JsonValue = serde_json::from_slice(line).unwrap();
+            let aggregation_name = line_json["aggregation"].as_str().unwrap().to_string();
+            let aggregation_json = reassembled_aggregations
+                .entry(aggregation_name)
+                .or_insert_with(|| json!({}));
+
+            if let Some(bucket) = line_json.get("bucket") {
+                let buckets = aggregation_json
+                    .as_object_mut()
+                    .unwrap()
+                    .entry("buckets")
+                    .or_insert_with(|| json!([]));
+                buckets.as_array_mut().unwrap().push(bucket.clone());
+            } else {
+                let result = line_json["result"].as_object().unwrap();
+                aggregation_json
+                    .as_object_mut()
+                    .unwrap()
+                    .extend(result.clone());
+            }
+        }
+        let reassembled_aggregations_json = serde_json::to_value(reassembled_aggregations).unwrap();
+        assert_eq!(reassembled_aggregations_json, aggregations_json);
+    }
+
+    #[tokio::test]
+    async fn test_aggregations_to_ndjson_without_aggregations() {
+        let ndjson_body = aggregations_to_ndjson(None);
+        let ndjson = ndjson_body.collect().await.unwrap().to_bytes();
+        assert!(ndjson.is_empty());
+    }
+}
@@ -0,0 +1,351 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use quickwit_query::query_ast::{QueryAst, query_ast_from_user_text};
+use quickwit_search::{SearchError, SearchResponseRest, SearchService};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+use warp::{Filter, Rejection};
+
+use super::{SearchRequestQueryString, extract_index_id_patterns, search_request_from_api_request};
+use crate::rest_api_response::into_rest_api_response;
+use crate::with_arg;
+
+/// Maximum number of filter variants of a batched search.
+const MAX_NUM_VARIANTS: usize = 100;
+
+/// Body of the batched search endpoint: a search request run once for each filter variant.
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct BatchSearchRequest {
+    /// Search request shared by all the variants, accepting the same parameters as the search
+    /// endpoint.
+    pub search: SearchRequestQueryString,
+    /// Filter of each variant, in the query language of `query`. The hits of a variant match both
+    /// the query of the search request and the filter.
+    pub filters: Vec<String>,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub(crate) struct BatchSearchResponse {
+    /// Results of the variants, in the order of the filters of the request.
+    pub responses: Vec<VariantSearchResponse>,
+}
+
+/// Result of a variant of a batched search: a search response, or the error of the variant.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub(crate) struct VariantSearchResponse {
+    /// Filter of the variant.
+    pub filter: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response: Option<SearchResponseRest>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+pub(crate) fn batch_search_handler(
+    search_service: Arc<dyn SearchService>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!(String / "search-batch")
+        .and_then(extract_index_id_patterns)
+        .and(warp::post())
+        .and(warp::body::content_length_limit(1024 * 1024))
+        .and(warp::body::json())
+        .and(with_arg(search_service))
+        .then(batch_search)
+}
+
+#[utoipa::path(
+    post,
+    tag = "Search",
+    path = "/{index_id}/search-batch",
+    request_body = BatchSearchRequest,
+    responses(
+        (status = 200, description = "Successfully executed the variants of the search.", body = BatchSearchResponse)
+    ),
+    params(
+        ("index_id" = String, Path, description = "The index ID to search."),
+    )
+)]
+/// Batched Search
+///
+/// Runs a search request once for each filter of a list, for instance to compute a leaderboard
+/// over tenants, and returns the results of the variants in the order of the filters. The targeted
+/// indexes are resolved, and the query of the search request parsed against their doc mappings,
+/// once for all the variants.
+async fn batch_search(
+    index_id_patterns: Vec<String>,
+    batch_search_request: BatchSearchRequest,
+    search_service: Arc<dyn SearchService>,
+) -> impl warp::Reply {
+    info!(request =? batch_search_request, "batch-search");
+    let body_format = batch_search_request.search.format;
+    let result =
+        batch_search_endpoint(index_id_patterns, batch_search_request, &*search_service).await;
+    into_rest_api_response(result, body_format)
+}
+
+async fn batch_search_endpoint(
+    index_id_patterns: Vec<String>,
+    batch_search_request: BatchSearchRequest,
+    search_service: &dyn SearchService,
+) -> Result<BatchSearchResponse, SearchError> {
+    let BatchSearchRequest {
+        search: search_request,
+        filters,
+    } = batch_search_request;
+
+    if filters.is_empty() {
+        return Err(SearchError::InvalidArgument(
+            "`filters` must not be empty".to_string(),
+        ));
+    }
+    if filters.len() > MAX_NUM_VARIANTS {
+        return Err(SearchError::InvalidArgument(format!(
+            "a batched search accepts at most {MAX_NUM_VARIANTS} filters, got {}",
+            filters.len()
+        )));
+    }
+    if search_request.stream_aggs
+        || search_request.csv_aggs
+        || search_request.columnar_hits
+        || search_request.detailed_timings
+        || search_request.hit_bytes
+        || search_request.result_bytes
+    {
+        return Err(SearchError::InvalidArgument(
+            "batched searches do not support `stream_aggs`, `csv_aggs`, `columnar_hits`, \
+             `detailed_timings`, `hit_bytes`, and `result_bytes`"
+                .to_string(),
+        ));
+    }
+    let allow_failed_splits = search_request.allow_failed_splits;
+    let filter_query_asts: Vec<QueryAst> = filters
+        .iter()
+        .map(|filter| query_ast_from_user_text(filter, search_request.search_fields.clone()))
+        .collect();
+    let search_request = search_request_from_api_request(index_id_patterns, search_request)?;
+    let variant_results = search_service
+        .root_search_batch(search_request, filter_query_asts)
+        .await?;
+
+    let responses = filters
+        .into_iter()
+        .zip(variant_results)
+        .map(|(filter, variant_result)| {
+            let variant_result = variant_result.and_then(|search_response| {
+                if (!allow_failed_splits || search_response.num_successful_splits == 0)
+                    && let Some(search_error) =
+                        SearchError::from_split_errors(&search_response.failed_splits[..])
+                {
+                    return Err(search_error);
+                }
+                SearchResponseRest::try_from(search_response)
+            });
+            match variant_result {
+                Ok(search_response) => VariantSearchResponse {
+                    filter,
+                    response: Some(search_response),
+                    error: None,
+                },
+                Err(search_error) => VariantSearchResponse {
+                    filter,
+                    response: None,
+                    error: Some(search_error.to_string()),
+                },
+            }
+        })
+        .collect();
+    Ok(BatchSearchResponse { responses })
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_proto::search::{Hit, SearchRequest, SearchResponse};
+    use quickwit_query::query_ast::{BoolQuery, UserInputQuery};
+    use quickwit_search::MockSearchService;
+    use serde_json::{Value as JsonValue, json};
+
+    use super::*;
+    use crate::recover_fn;
+    use crate::search_api::search_post_handler;
+
+    /// Returns, for each tenant, its number of documents and one of them. The user input queries
+    /// are parsed as the root searcher does.
+    fn root_search_by_tenant(search_request: SearchRequest) -> Result<SearchResponse, SearchError> {
+        let query_ast: QueryAst = serde_json::from_str(&search_request.query_ast).unwrap();
+        let parsed_query_ast = query_ast.parse_user_query(&[]).unwrap();
+        let query_ast_json = serde_json::to_string(&parsed_query_ast).unwrap();
+        assert!(query_ast_json.contains("ERROR"));
+
+        let (tenant, num_hits) = if query_ast_json.contains("acme") {
+            ("acme", 3)
+        } else if query_ast_json.contains("globex") {
+            ("globex", 5)
+        } else {
+            return Err(SearchError::InvalidQuery("unknown tenant".to_string()));
+        };
+        let hit = Hit {
+            json: json!({"tenant": tenant, "severity": "ERROR"}).to_string(),
+            ..Default::default()
+        };
+        Ok(SearchResponse {
+            num_hits,
+            hits: vec![hit],
+            ..Default::default()
+        })
+    }
+
+    #[tokio::test]
+    async fn test_batch_search_matches_separate_searches() {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search_batch()
+            .times(1)
+            .returning(|search_request, filter_query_asts| {
+                let query_ast: QueryAst = serde_json::from_str(&search_request.query_ast).unwrap();
+                let variant_results = filter_query_asts
+                    .into_iter()
+                    .map(|filter_query_ast| {
+                        let variant_query_ast: QueryAst = BoolQuery {
+                            must: vec![query_ast.clone()],
+                            filter: vec![filter_query_ast],
+                            ..Default::default()
+                        }
+                        .into();
+                        root_search_by_tenant(SearchRequest {
+                            query_ast: serde_json::to_string(&variant_query_ast).unwrap(),
+                            ..search_request.clone()
+                        })
+                    })
+                    .collect();
+                Ok(variant_results)
+            });
+        mock_search_service
+            .expect_root_search()
+            .times(2)
+            .returning(root_search_by_tenant);
+        let search_service: Arc<dyn SearchService> = Arc::new(mock_search_service);
+        let handler = batch_search_handler(search_service.clone())
+            .or(search_post_handler(search_service))
+            .recover(recover_fn);
+
+        let resp = warp::test::request()
+            .path("/test-index/search-batch")
+            .method("POST")
+            .json(&json!({
+                "search": {"query": "severity:ERROR", "max_hits": 10},
+                "filters": ["tenant:acme", "tenant:globex", "tenant:initech"],
+            }))
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let batch_resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        let variant_resps_json = batch_resp_json["responses"].as_array().unwrap();
+        assert_eq!(variant_resps_json.len(), 3);
+
+        for (variant_resp_json, filter) in variant_resps_json.iter().zip(["acme", "globex"]) {
+            assert_eq!(variant_resp_json["filter"], format!("tenant:{filter}"));
+
+            let resp = warp::test::request()
+                .path("/test-index/search")
+                .method("POST")
+                .json(&json!({
+                    "query": format!("severity:ERROR AND tenant:{filter}"),
+                    "max_hits": 10,
+                }))
+                .reply(&handler)
+                .await;
+            assert_eq!(resp.status(), 200);
+            let search_resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+            assert_eq!(variant_resp_json["response"], search_resp_json);
+        }
+        assert_eq!(variant_resps_json[2]["filter"], "tenant:initech");
+        assert!(variant_resps_json[2].get("response").is_none());
+        assert!(
+            variant_resps_json[2]["error"]
+                .as_str()
+                .unwrap()
+                .contains("unknown tenant")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_batch_search_shares_query_across_variants() {
+        let mut mock_search_service = MockSearchService::new();
+        // The variants are searched as one batch, so that the root searcher resolves the indexes
+        // and parses the query once for all of them.
+        mock_search_service
+            .expect_root_search_batch()
+            .times(1)
+            .returning(|search_request, filter_query_asts| {
+                let query_ast: QueryAst = serde_json::from_str(&search_request.query_ast).unwrap();
+                let QueryAst::UserInput(UserInputQuery { user_text, .. }) = query_ast else {
+                    panic!("expected a user input query");
+                };
+                assert_eq!(user_text, "severity:ERROR");
+
+                let filters: Vec<String> = filter_query_asts
+                    .into_iter()
+                    .map(|filter_query_ast| {
+                        let QueryAst::UserInput(UserInputQuery { user_text, .. }) =
+                            filter_query_ast
+                        else {
+                            panic!("expected a user input filter");
+                        };
+                        user_text
+                    })
+                    .collect();
+                assert_eq!(filters, ["tenant:acme", "tenant:globex"]);
+                Ok(vec![
+                    Ok(SearchResponse::default()),
+                    Ok(SearchResponse::default()),
+                ])
+            });
+        let handler = batch_search_handler(Arc::new(mock_search_service)).recover(recover_fn);
+
+        let resp = warp::test::request()
+            .path("/test-index/search-batch")
+            .method("POST")
+            .json(&json!({
+                "search": {"query": "severity:ERROR"},
+                "filters": ["tenant:acme", "tenant:globex"],
+            }))
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let batch_resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(batch_resp_json["responses"].as_array().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_batch_search_invalid_requests() {
+        let handler = batch_search_handler(Arc::new(MockSearchService::new())).recover(recover_fn);
+
+        for batch_search_request_json in [
+            json!({"search": {"query": "*"}, "filters": []}),
+            json!({"search": {"query": "*", "stream_aggs": true}, "filters": ["tenant:acme"]}),
+        ] {
+            let resp = warp::test::request()
+                .path("/test-index/search-batch")
+                .method("POST")
+                .json(&batch_search_request_json)
+                .reply(&handler)
+                .await;
+            assert_eq!(resp.status(), 400);
+        }
+    }
+}
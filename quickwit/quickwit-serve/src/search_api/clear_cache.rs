@@ -0,0 +1,119 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use quickwit_proto::search::ClearCacheRequest;
+use quickwit_search::{SearchError, SearchService};
+use warp::{Filter, Rejection};
+
+use super::extract_index_id_patterns;
+use crate::elasticsearch_api::model::ElasticsearchClearCacheResponse;
+use crate::format::extract_format_from_qs;
+use crate::rest_api_response::into_rest_api_response;
+use crate::with_arg;
+
+pub(crate) fn clear_cache_handler(
+    search_service: Arc<dyn SearchService>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("indexes" / String / "_clear_cache")
+        .and_then(extract_index_id_patterns)
+        .and(warp::post())
+        .and(with_arg(search_service))
+        .then(clear_cache)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+        .boxed()
+}
+
+#[utoipa::path(
+    post,
+    tag = "Search",
+    path = "/indexes/{index_id}/_clear_cache",
+    responses(
+        (status = 200, description = "Successfully evicted the splits of the indexes from the caches of the searchers.")
+    ),
+    params(
+        ("index_id" = String, Path, description = "The index ID, or a comma-separated list of index ID patterns, to evict from the caches."),
+    )
+)]
+/// Clear Index Caches
+///
+/// Evicts the splits of the indexes from the split cache and the in-memory caches of every
+/// searcher, leaving the caches of the other indexes untouched. This is the native counterpart of
+/// the Elasticsearch-compatible `_cache/clear` endpoint, and returns the same response.
+async fn clear_cache(
+    index_id_patterns: Vec<String>,
+    search_service: Arc<dyn SearchService>,
+) -> Result<ElasticsearchClearCacheResponse, SearchError> {
+    let clear_cache_request = ClearCacheRequest { index_id_patterns };
+    let clear_cache_response = search_service.root_clear_cache(clear_cache_request).await?;
+    Ok(clear_cache_response.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_proto::search::ClearCacheResponse;
+    use quickwit_search::MockSearchService;
+    use serde_json::{Value as JsonValue, json};
+
+    use super::*;
+    use crate::rest::recover_fn;
+
+    #[tokio::test]
+    async fn test_clear_cache_handler() {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_clear_cache()
+            .withf(|clear_cache_request| {
+                clear_cache_request.index_id_patterns == ["index-1", "index-2*"]
+            })
+            .return_once(|_| {
+                Ok(ClearCacheResponse {
+                    num_searchers: 3,
+                    num_failed_searchers: 1,
+                })
+            });
+        let handler = clear_cache_handler(Arc::new(mock_search_service)).recover(recover_fn);
+        let resp = warp::test::request()
+            .path("/indexes/index-1,index-2*/_clear_cache")
+            .method("POST")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(resp_json["_shards"]["total"], json!(3));
+        assert_eq!(resp_json["_shards"]["successful"], json!(2));
+        assert_eq!(resp_json["_shards"]["failed"], json!(1));
+    }
+
+    #[tokio::test]
+    async fn test_clear_cache_handler_index_not_found() {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_clear_cache()
+            .return_once(|_| {
+                Err(SearchError::IndexesNotFound {
+                    index_ids: vec!["index-1".to_string()],
+                })
+            });
+        let handler = clear_cache_handler(Arc::new(mock_search_service)).recover(recover_fn);
+        let resp = warp::test::request()
+            .path("/indexes/index-1/_clear_cache")
+            .method("POST")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 404);
+    }
+}
@@ -0,0 +1,236 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use quickwit_proto::search::{CountHits, SearchRequest};
+use quickwit_query::query_ast::query_ast_from_user_text;
+use quickwit_search::{SearchError, SearchService};
+use serde::{Deserialize, Serialize};
+use warp::{Filter, Rejection};
+
+use crate::query_params::query_params;
+use crate::rest_api_response::into_rest_api_response;
+use crate::simple_list::from_simple_list;
+use crate::{BodyFormat, with_arg};
+
+fn default_count_query() -> String {
+    "*".to_string()
+}
+
+/// Parameters of the count endpoint, passed in the query string (GET) or as a JSON body (POST).
+#[derive(Debug, Deserialize, utoipa::IntoParams, utoipa::ToSchema)]
+#[into_params(parameter_in = Query)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct CountRequestQueryString {
+    /// Query text. The query language is that of tantivy. Counts all the documents by default.
+    #[serde(default = "default_count_query")]
+    pub query: String,
+    /// Fields to search on if no field name is specified in the query.
+    #[param(rename = "search_field", value_type = Option<String>)]
+    #[schema(rename = "search_field", value_type = Option<String>)]
+    #[serde(default)]
+    #[serde(rename = "search_field")]
+    #[serde(deserialize_with = "from_simple_list")]
+    pub search_fields: Option<Vec<String>>,
+    /// If set, restrict the count to documents with a `timestamp >= start_timestamp`.
+    /// This timestamp is expressed in seconds.
+    pub start_timestamp: Option<i64>,
+    /// If set, restrict the count to documents with a `timestamp < end_timestamp`.
+    /// This timestamp is expressed in seconds.
+    pub end_timestamp: Option<i64>,
+    /// The output format.
+    #[serde(default = "BodyFormat::node_default")]
+    pub format: BodyFormat,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub(crate) struct CountResponse {
+    /// Number of documents matching the query.
+    pub count: u64,
+}
+
+fn count_get_filter()
+-> impl Filter<Extract = (String, CountRequestQueryString), Error = Rejection> + Clone {
+    warp::path!("indexes" / String / "count")
+        .and(warp::get())
+        .and(query_params())
+}
+
+fn count_post_filter()
+-> impl Filter<Extract = (String, CountRequestQueryString), Error = Rejection> + Clone {
+    warp::path!("indexes" / String / "count")
+        .and(warp::post())
+        .and(warp::body::content_length_limit(1024 * 1024))
+        .and(warp::body::json())
+}
+
+pub(crate) fn count_handler(
+    search_service: Arc<dyn SearchService>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    count_get_filter()
+        .or(count_post_filter())
+        .unify()
+        .and(with_arg(search_service))
+        .then(count)
+        .boxed()
+}
+
+#[utoipa::path(
+    get,
+    tag = "Search",
+    path = "/indexes/{index_id}/count",
+    responses(
+        (status = 200, description = "Successfully counted the documents.", body = CountResponse)
+    ),
+    params(
+        CountRequestQueryString,
+        ("index_id" = String, Path, description = "The index ID to count the documents of."),
+    )
+)]
+/// Count Documents
+///
+/// Returns the number of documents of the index matching the query, without fetching any hit.
+/// Also accepts POST requests with the parameters as a JSON body.
+async fn count(
+    index_id: String,
+    count_request: CountRequestQueryString,
+    search_service: Arc<dyn SearchService>,
+) -> impl warp::Reply {
+    let body_format = count_request.format;
+    let result = count_documents(index_id, count_request, &*search_service).await;
+    into_rest_api_response(result, body_format)
+}
+
+async fn count_documents(
+    index_id: String,
+    count_request: CountRequestQueryString,
+    search_service: &dyn SearchService,
+) -> Result<CountResponse, SearchError> {
+    let query_ast = query_ast_from_user_text(&count_request.query, count_request.search_fields);
+    let search_request = SearchRequest {
+        index_id_patterns: vec![index_id],
+        query_ast: serde_json::to_string(&query_ast)?,
+        start_timestamp: count_request.start_timestamp,
+        end_timestamp: count_request.end_timestamp,
+        max_hits: 0,
+        count_hits: CountHits::CountAll as i32,
+        ..Default::default()
+    };
+    let search_response = search_service.root_search(search_request).await?;
+
+    if let Some(search_error) = SearchError::from_split_errors(&search_response.failed_splits[..]) {
+        return Err(search_error);
+    }
+    Ok(CountResponse {
+        count: search_response.num_hits,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_proto::search::SearchResponse;
+    use quickwit_query::query_ast::QueryAst;
+    use quickwit_search::MockSearchService;
+    use serde_json::{Value as JsonValue, json};
+
+    use super::*;
+    use crate::recover_fn;
+
+    #[tokio::test]
+    async fn test_count_match_all() {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .returning(|search_request| {
+                assert_eq!(
+                    search_request.index_id_patterns,
+                    vec!["test-index".to_string()]
+                );
+                assert_eq!(search_request.max_hits, 0);
+                assert_eq!(search_request.count_hits(), CountHits::CountAll);
+
+                let query_ast: QueryAst = serde_json::from_str(&search_request.query_ast).unwrap();
+                let QueryAst::UserInput(user_input_query) = query_ast else {
+                    panic!("expected a user input query");
+                };
+                assert_eq!(user_input_query.user_text, "*");
+                Ok(SearchResponse {
+                    num_hits: 42,
+                    ..Default::default()
+                })
+            });
+        let handler = count_handler(Arc::new(mock_search_service)).recover(recover_fn);
+
+        let resp = warp::test::request()
+            .path("/indexes/test-index/count")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(resp_json, json!({"count": 42}));
+    }
+
+    #[tokio::test]
+    async fn test_count_filtered() {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .times(2)
+            .returning(|search_request| {
+                assert_eq!(search_request.max_hits, 0);
+                assert_eq!(search_request.start_timestamp, Some(1_700_000_000));
+
+                let query_ast: QueryAst = serde_json::from_str(&search_request.query_ast).unwrap();
+                let QueryAst::UserInput(user_input_query) = query_ast else {
+                    panic!("expected a user input query");
+                };
+                assert_eq!(user_input_query.user_text, "severity:ERROR");
+                assert_eq!(
+                    user_input_query.default_fields,
+                    Some(vec!["body".to_string()])
+                );
+                Ok(SearchResponse {
+                    num_hits: 3,
+                    ..Default::default()
+                })
+            });
+        let handler = count_handler(Arc::new(mock_search_service)).recover(recover_fn);
+
+        let resp = warp::test::request()
+            .path(
+                "/indexes/test-index/count?query=severity:ERROR&search_field=body&\
+                 start_timestamp=1700000000",
+            )
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(resp_json, json!({"count": 3}));
+
+        let resp = warp::test::request()
+            .method("POST")
+            .path("/indexes/test-index/count")
+            .json(&json!({
+                "query": "severity:ERROR",
+                "search_field": "body",
+                "start_timestamp": 1700000000,
+            }))
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(resp_json, json!({"count": 3}));
+    }
+}
@@ -0,0 +1,324 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use quickwit_proto::search::{CountHits, SearchRequest};
+use quickwit_query::query_ast::query_ast_from_user_text;
+use quickwit_search::{AggregationResults, SearchError, SearchService};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value as JsonValue, json};
+use warp::{Filter, Rejection};
+
+use crate::query_params::query_params;
+use crate::rest_api_response::into_rest_api_response;
+use crate::simple_list::from_simple_list;
+use crate::{BodyFormat, with_arg};
+
+const DISTINCT_VALUES_AGG_NAME: &str = "distinct_values";
+
+const DISTINCT_VALUES_SOURCE_NAME: &str = "value";
+
+const MAX_DISTINCT_VALUES_LIMIT: u32 = 10_000;
+
+fn default_distinct_values_query() -> String {
+    "*".to_string()
+}
+
+fn default_distinct_values_limit() -> u32 {
+    100
+}
+
+/// Parameters of the distinct values endpoint, passed in the query string (GET) or as a JSON body
+/// (POST).
+#[derive(Debug, Deserialize, utoipa::IntoParams, utoipa::ToSchema)]
+#[into_params(parameter_in = Query)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct DistinctValuesRequestQueryString {
+    /// Fast field to return the distinct values of.
+    pub field: String,
+    /// Query text. The query language is that of tantivy. Only the values of the documents
+    /// matching the query are returned. Matches all the documents by default.
+    #[serde(default = "default_distinct_values_query")]
+    pub query: String,
+    /// Fields to search on if no field name is specified in the query.
+    #[param(rename = "search_field", value_type = Option<String>)]
+    #[schema(rename = "search_field", value_type = Option<String>)]
+    #[serde(default)]
+    #[serde(rename = "search_field")]
+    #[serde(deserialize_with = "from_simple_list")]
+    pub search_fields: Option<Vec<String>>,
+    /// If set, restrict the values to documents with a `timestamp >= start_timestamp`.
+    /// This timestamp is expressed in seconds.
+    pub start_timestamp: Option<i64>,
+    /// If set, restrict the values to documents with a `timestamp < end_timestamp`.
+    /// This timestamp is expressed in seconds.
+    pub end_timestamp: Option<i64>,
+    /// Maximum number of values to return, up to 10,000.
+    #[serde(default = "default_distinct_values_limit")]
+    pub limit: u32,
+    /// Cursor returned by the previous page, to fetch the values that follow it.
+    pub after: Option<String>,
+    /// The output format.
+    #[serde(default = "BodyFormat::node_default")]
+    pub format: BodyFormat,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub(crate) struct DistinctValuesResponse {
+    /// Distinct values of the field, sorted.
+    #[schema(value_type = Vec<Object>)]
+    pub values: Vec<JsonValue>,
+    /// Cursor to pass as `after` to fetch the next page. Absent once all the values have been
+    /// returned.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after: Option<String>,
+}
+
+fn distinct_values_get_filter()
+-> impl Filter<Extract = (String, DistinctValuesRequestQueryString), Error = Rejection> + Clone {
+    warp::path!("indexes" / String / "distinct-values")
+        .and(warp::get())
+        .and(query_params())
+}
+
+fn distinct_values_post_filter()
+-> impl Filter<Extract = (String, DistinctValuesRequestQueryString), Error = Rejection> + Clone {
+    warp::path!("indexes" / String / "distinct-values")
+        .and(warp::post())
+        .and(warp::body::content_length_limit(1024 * 1024))
+        .and(warp::body::json())
+}
+
+pub(crate) fn distinct_values_handler(
+    search_service: Arc<dyn SearchService>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    distinct_values_get_filter()
+        .or(distinct_values_post_filter())
+        .unify()
+        .and(with_arg(search_service))
+        .then(distinct_values)
+        .boxed()
+}
+
+#[utoipa::path(
+    get,
+    tag = "Search",
+    path = "/indexes/{index_id}/distinct-values",
+    responses(
+        (status = 200, description = "Successfully fetched the distinct values.", body = DistinctValuesResponse)
+    ),
+    params(
+        DistinctValuesRequestQueryString,
+        ("index_id" = String, Path, description = "The index ID to fetch the distinct values of."),
+    )
+)]
+/// Distinct Values
+///
+/// Returns the distinct values of a fast field in the documents matching the query, sorted and
+/// paginated. Also accepts POST requests with the parameters as a JSON body.
+async fn distinct_values(
+    index_id: String,
+    distinct_values_request: DistinctValuesRequestQueryString,
+    search_service: Arc<dyn SearchService>,
+) -> impl warp::Reply {
+    let body_format = distinct_values_request.format;
+    let result = fetch_distinct_values(index_id, distinct_values_request, &*search_service).await;
+    into_rest_api_response(result, body_format)
+}
+
+/// Fetches the distinct values with a composite aggregation over the field, which enumerates the
+/// values in order without computing the buckets of the values beyond the page.
+async fn fetch_distinct_values(
+    index_id: String,
+    distinct_values_request: DistinctValuesRequestQueryString,
+    search_service: &dyn SearchService,
+) -> Result<DistinctValuesResponse, SearchError> {
+    let limit = distinct_values_request.limit;
+
+    if limit == 0 || limit > MAX_DISTINCT_VALUES_LIMIT {
+        return Err(SearchError::InvalidArgument(format!(
+            "`limit` must be between 1 and {MAX_DISTINCT_VALUES_LIMIT}"
+        )));
+    }
+    let mut composite_json = json!({
+        "size": limit,
+        "sources": [{
+            DISTINCT_VALUES_SOURCE_NAME: {"terms": {"field": distinct_values_request.field}}
+        }],
+    });
+    if let Some(after) = distinct_values_request.after {
+        composite_json["after"] = json!({ DISTINCT_VALUES_SOURCE_NAME: after });
+    }
+    let aggregation_json = json!({ DISTINCT_VALUES_AGG_NAME: {"composite": composite_json} });
+    let query_ast = query_ast_from_user_text(
+        &distinct_values_request.query,
+        distinct_values_request.search_fields,
+    );
+    let search_request = SearchRequest {
+        index_id_patterns: vec![index_id],
+        query_ast: serde_json::to_string(&query_ast)?,
+        start_timestamp: distinct_values_request.start_timestamp,
+        end_timestamp: distinct_values_request.end_timestamp,
+        max_hits: 0,
+        count_hits: CountHits::Underestimate as i32,
+        aggregation_request: Some(aggregation_json.to_string()),
+        ..Default::default()
+    };
+    let search_response = search_service.root_search(search_request).await?;
+
+    if let Some(search_error) = SearchError::from_split_errors(&search_response.failed_splits[..]) {
+        return Err(search_error);
+    }
+    let Some(aggregation_postcard) = search_response.aggregation_postcard else {
+        return Ok(DistinctValuesResponse {
+            values: Vec::new(),
+            after: None,
+        });
+    };
+    let aggregation_results = AggregationResults::from_postcard(&aggregation_postcard)
+        .map_err(|error| SearchError::Internal(error.to_string()))?;
+    let aggregation_results_json = serde_json::to_value(aggregation_results)?;
+    let composite_result_json = &aggregation_results_json[DISTINCT_VALUES_AGG_NAME];
+
+    let values: Vec<JsonValue> = composite_result_json["buckets"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|bucket| bucket["key"][DISTINCT_VALUES_SOURCE_NAME].clone())
+        .collect();
+    // A page holding fewer values than the limit is the last one.
+    let after = if values.len() < limit as usize {
+        None
+    } else {
+        composite_result_json["after_key"][DISTINCT_VALUES_SOURCE_NAME]
+            .as_str()
+            .map(ToString::to_string)
+    };
+    Ok(DistinctValuesResponse { values, after })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+    use std::net::{Ipv4Addr, SocketAddr};
+
+    use quickwit_config::SearcherConfig;
+    use quickwit_indexing::TestSandbox;
+    use quickwit_search::{
+        ClusterClient, MockSearchService, SearchJobPlacer, SearchServiceClient, SearchServiceImpl,
+        SearcherContext, SearcherPool,
+    };
+
+    use super::*;
+    use crate::recover_fn;
+
+    fn local_search_service(test_sandbox: &TestSandbox) -> Arc<dyn SearchService> {
+        let socket_addr = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 7280);
+        let searcher_pool = SearcherPool::default();
+        let searcher_context = Arc::new(SearcherContext::new_without_invoker(
+            SearcherConfig::default(),
+            None,
+        ));
+        let search_service = Arc::new(SearchServiceImpl::new(
+            test_sandbox.metastore(),
+            test_sandbox.storage_resolver(),
+            ClusterClient::new(SearchJobPlacer::new(searcher_pool.clone())),
+            searcher_context,
+        ));
+        let search_service_client =
+            SearchServiceClient::from_service(search_service.clone(), socket_addr);
+        searcher_pool.insert(socket_addr, search_service_client);
+        search_service
+    }
+
+    #[tokio::test]
+    async fn test_distinct_values_paginated_under_filter() {
+        let index_id = "test-distinct-values";
+        let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: service
+                type: text
+                tokenizer: raw
+                fast: true
+              - name: severity
+                type: text
+                tokenizer: raw
+        "#;
+        let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "", &["severity"])
+            .await
+            .unwrap();
+        let docs: Vec<JsonValue> = (0..40)
+            .map(|i| {
+                let severity = if i % 3 == 0 { "ERROR" } else { "INFO" };
+                json!({"service": format!("service-{}", i % 8), "severity": severity})
+            })
+            .collect();
+        let expected_value_set: BTreeSet<String> = docs
+            .iter()
+            .filter(|doc| doc["severity"] == "ERROR")
+            .map(|doc| doc["service"].as_str().unwrap().to_string())
+            .collect();
+        test_sandbox.add_documents(docs).await.unwrap();
+        let handler = distinct_values_handler(local_search_service(&test_sandbox));
+
+        let mut values: Vec<String> = Vec::new();
+        let mut after_opt: Option<String> = None;
+
+        for page in 0.. {
+            assert!(page < 10, "the pagination should end");
+
+            let mut path = format!(
+                "/indexes/{index_id}/distinct-values?field=service&query=severity:ERROR&limit=3"
+            );
+            if let Some(after) = after_opt.take() {
+                path.push_str(&format!("&after={after}"));
+            }
+            let resp = warp::test::request().path(&path).reply(&handler).await;
+            assert_eq!(resp.status(), 200);
+            let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+
+            for value in resp_json["values"].as_array().unwrap() {
+                values.push(value.as_str().unwrap().to_string());
+            }
+            let Some(after) = resp_json.get("after") else {
+                break;
+            };
+            after_opt = Some(after.as_str().unwrap().to_string());
+        }
+        let expected_values: Vec<String> = expected_value_set.into_iter().collect();
+        assert_eq!(values, expected_values);
+        test_sandbox.assert_quit().await;
+    }
+
+    #[tokio::test]
+    async fn test_distinct_values_rejects_invalid_limit() {
+        let handler =
+            distinct_values_handler(Arc::new(MockSearchService::new())).recover(recover_fn);
+
+        let resp = warp::test::request()
+            .path("/indexes/test-index/distinct-values?field=service&limit=0")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 400);
+
+        let resp = warp::test::request()
+            .path("/indexes/test-index/distinct-values")
+            .method("POST")
+            .json(&json!({"field": "service", "limit": 10_001}))
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 400);
+    }
+}
@@ -0,0 +1,344 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Full-index export: streams the documents of an index matching a query as NDJSON, one page of
+//! documents at a time.
+//!
+//! The documents are sorted by address, and each page is followed by a
+//! `{"_continuation": <token>}` line. An interrupted export is resumed by passing the token of
+//! the last continuation line received to a new export request, which streams the documents
+//! following that page, without gaps nor duplicates. An export fails instead if a split it has
+//! already read is merged while it runs or before it resumes, see [`export_continuation`].
+//!
+//! [`export_continuation`]: super::export_continuation
+
+use std::io::{self, Write};
+use std::sync::Arc;
+
+use bytes::Bytes;
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use futures::{StreamExt, stream};
+use quickwit_proto::metastore::MetastoreServiceClient;
+use quickwit_proto::search::{CountHits, PartialHit, SearchRequest, SortField, SortOrder};
+use quickwit_proto::types::IndexUid;
+use quickwit_query::query_ast::query_ast_from_user_text;
+use quickwit_search::{GlobalDocAddress, SearchError, SearchService};
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use warp::hyper::header::{ACCEPT_ENCODING, CONTENT_ENCODING, CONTENT_TYPE, VARY};
+use warp::hyper::http::HeaderValue;
+use warp::{Filter, Rejection, Reply};
+
+use super::export_continuation::{
+    ExportContinuation, ensure_read_splits_unchanged, resolve_index_uids,
+};
+use crate::query_params::query_params;
+use crate::rest_api_response::into_rest_api_response;
+use crate::{BodyFormat, with_arg};
+
+const DEFAULT_EXPORT_PAGE_SIZE: u64 = 1_000;
+
+const MAX_EXPORT_PAGE_SIZE: u64 = 10_000;
+
+fn default_export_query() -> String {
+    "*".to_string()
+}
+
+fn default_export_page_size() -> u64 {
+    DEFAULT_EXPORT_PAGE_SIZE
+}
+
+#[derive(Debug, Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ExportQueryParams {
+    /// Query text. The query language is that of tantivy. Exports all the documents by default.
+    #[serde(default = "default_export_query")]
+    pub query: String,
+    /// Number of documents fetched by each search of the export, up to 10,000.
+    #[serde(default = "default_export_page_size")]
+    pub page_size: u64,
+    /// Token of the last continuation line received from an interrupted export. The export
+    /// resumes with the documents following that line. Tokens expire after the split deletion
+    /// grace period.
+    pub continuation: Option<String>,
+}
+
+#[derive(Serialize)]
+struct ContinuationLine<'a> {
+    #[serde(rename = "_continuation")]
+    continuation: &'a str,
+}
+
+/// Returns whether the `Accept-Encoding` header of a request accepts gzip.
+fn accepts_gzip(accept_encoding: &str) -> bool {
+    accept_encoding.split(',').any(|coding| {
+        let mut coding_parts = coding.split(';');
+        let coding_name = coding_parts.next().unwrap_or_default().trim();
+
+        if !coding_name.eq_ignore_ascii_case("gzip") {
+            return false;
+        }
+        // A coding weighted with `q=0` is not acceptable.
+        !coding_parts.any(|param| match param.trim().strip_prefix("q=") {
+            Some(weight) => weight.parse::<f32>().ok() == Some(0.0),
+            None => false,
+        })
+    })
+}
+
+/// State of an export, fetching the documents one page at a time.
+struct Export {
+    search_service: Arc<dyn SearchService>,
+    metastore: MetastoreServiceClient,
+    search_request: SearchRequest,
+    /// Position of the last page exported.
+    continuation_opt: Option<ExportContinuation>,
+    index_uids_opt: Option<Vec<IndexUid>>,
+    encoder_opt: Option<GzEncoder<Vec<u8>>>,
+    is_done: bool,
+}
+
+impl Export {
+    fn new(
+        index_id: String,
+        export_params: ExportQueryParams,
+        use_gzip: bool,
+        search_service: Arc<dyn SearchService>,
+        metastore: MetastoreServiceClient,
+    ) -> Result<Self, SearchError> {
+        if export_params.page_size == 0 || export_params.page_size > MAX_EXPORT_PAGE_SIZE {
+            return Err(SearchError::InvalidArgument(format!(
+                "`page_size` must be between 1 and {MAX_EXPORT_PAGE_SIZE}, got {}",
+                export_params.page_size
+            )));
+        }
+        let continuation_opt = export_params
+            .continuation
+            .as_deref()
+            .map(str::parse::<ExportContinuation>)
+            .transpose()?;
+        if let Some(continuation) = &continuation_opt {
+            continuation.ensure_not_expired()?;
+        }
+        let search_after = continuation_opt.as_ref().map(|continuation| {
+            let doc_address = &continuation.doc_address;
+            PartialHit {
+                split_id: doc_address.split.clone(),
+                segment_ord: doc_address.doc_addr.segment_ord,
+                doc_id: doc_address.doc_addr.doc_id,
+                ..Default::default()
+            }
+        });
+        let query_ast = query_ast_from_user_text(&export_params.query, None);
+        let search_request = SearchRequest {
+            index_id_patterns: vec![index_id],
+            query_ast: serde_json::to_string(&query_ast)?,
+            max_hits: export_params.page_size,
+            sort_fields: vec![SortField {
+                field_name: "_shard_doc".to_string(),
+                sort_order: SortOrder::Asc as i32,
+                ..Default::default()
+            }],
+            search_after,
+            count_hits: CountHits::Underestimate as i32,
+            ..Default::default()
+        };
+        let encoder_opt = use_gzip.then(|| GzEncoder::new(Vec::new(), Compression::fast()));
+
+        Ok(Export {
+            search_service,
+            metastore,
+            search_request,
+            continuation_opt,
+            index_uids_opt: None,
+            encoder_opt,
+            is_done: false,
+        })
+    }
+
+    /// Fetches the next page of documents and returns them as NDJSON, followed by their
+    /// continuation line. Returns `None` once all the documents are exported.
+    async fn next_chunk(&mut self) -> Result<Option<Bytes>, SearchError> {
+        if self.is_done {
+            return Ok(None);
+        }
+        let search_timestamp = OffsetDateTime::now_utc().unix_timestamp();
+        let search_response = self
+            .search_service
+            .root_search(self.search_request.clone())
+            .await?;
+
+        if let Some(search_error) =
+            SearchError::from_split_errors(&search_response.failed_splits[..])
+        {
+            return Err(search_error);
+        }
+        // The page is consistent with the previous ones only if the splits read by the previous
+        // pages were still there when it was searched.
+        if let Some(continuation) = &self.continuation_opt {
+            let index_uids = self.index_uids().await?;
+            ensure_read_splits_unchanged(&self.metastore, index_uids, continuation).await?;
+        }
+        let mut ndjson = Vec::new();
+
+        for hit in &search_response.hits {
+            ndjson.extend_from_slice(hit.json.as_bytes());
+            ndjson.push(b'\n');
+        }
+        if let Some(last_partial_hit) = search_response
+            .hits
+            .last()
+            .and_then(|hit| hit.partial_hit.clone())
+        {
+            let continuation = ExportContinuation {
+                search_timestamp,
+                doc_address: GlobalDocAddress::from_partial_hit(&last_partial_hit),
+            };
+            let continuation_line = ContinuationLine {
+                continuation: &continuation.to_string(),
+            };
+            serde_json::to_writer(&mut ndjson, &continuation_line)?;
+            ndjson.push(b'\n');
+            self.search_request.search_after = Some(last_partial_hit);
+            self.continuation_opt = Some(continuation);
+        }
+        if (search_response.hits.len() as u64) < self.search_request.max_hits {
+            self.is_done = true;
+        }
+        let chunk = self.encode(ndjson).map_err(|io_error| {
+            SearchError::Internal(format!("failed to compress export: {io_error}"))
+        })?;
+        Ok(Some(chunk))
+    }
+
+    async fn index_uids(&mut self) -> Result<Vec<IndexUid>, SearchError> {
+        if let Some(index_uids) = &self.index_uids_opt {
+            return Ok(index_uids.clone());
+        }
+        let index_uids = resolve_index_uids(
+            &self.metastore,
+            self.search_request.index_id_patterns.clone(),
+        )
+        .await?;
+        self.index_uids_opt = Some(index_uids.clone());
+        Ok(index_uids)
+    }
+
+    fn encode(&mut self, ndjson: Vec<u8>) -> io::Result<Bytes> {
+        if self.is_done {
+            let Some(mut encoder) = self.encoder_opt.take() else {
+                return Ok(Bytes::from(ndjson));
+            };
+            encoder.write_all(&ndjson)?;
+            return encoder.finish().map(Bytes::from);
+        }
+        let Some(encoder) = &mut self.encoder_opt else {
+            return Ok(Bytes::from(ndjson));
+        };
+        encoder.write_all(&ndjson)?;
+        // The sync flush ends the chunk on a block boundary: a client can decompress every page
+        // received before a network drop, including its continuation line.
+        encoder.flush()?;
+        Ok(Bytes::from(std::mem::take(encoder.get_mut())))
+    }
+}
+
+fn export_filter()
+-> impl Filter<Extract = (String, ExportQueryParams, Option<String>), Error = Rejection> + Clone {
+    warp::path!(String / "_export")
+        .and(warp::get())
+        .and(query_params())
+        .and(warp::header::optional::<String>(ACCEPT_ENCODING.as_str()))
+}
+
+pub(crate) fn export_handler(
+    search_service: Arc<dyn SearchService>,
+    metastore: MetastoreServiceClient,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    export_filter()
+        .and(with_arg(search_service))
+        .and(with_arg(metastore))
+        .then(export)
+        .boxed()
+}
+
+#[utoipa::path(
+    get,
+    tag = "Search",
+    path = "/{index_id}/_export",
+    responses(
+        (status = 200, description = "Successfully exported the documents as NDJSON.")
+    ),
+    params(
+        ExportQueryParams,
+        ("index_id" = String, Path, description = "The index ID to export the documents of."),
+    )
+)]
+/// Export Documents
+///
+/// Streams the documents of the index matching the query as NDJSON, gzip-compressed if the
+/// `Accept-Encoding` header of the request accepts it. Each page of documents is followed by a
+/// continuation line, whose token resumes an interrupted export.
+async fn export(
+    index_id: String,
+    export_params: ExportQueryParams,
+    accept_encoding_opt: Option<String>,
+    search_service: Arc<dyn SearchService>,
+    metastore: MetastoreServiceClient,
+) -> warp::reply::Response {
+    let use_gzip = matches!(
+        accept_encoding_opt.as_deref(),
+        Some(accept_encoding) if accepts_gzip(accept_encoding)
+    );
+    let mut export = match Export::new(index_id, export_params, use_gzip, search_service, metastore)
+    {
+        Ok(export) => export,
+        Err(search_error) => {
+            return into_rest_api_response::<(), _>(Err(search_error), BodyFormat::node_default())
+                .into_response();
+        }
+    };
+    // The first page is fetched before responding, so that the errors of the export request, such
+    // as a missing index, are returned with an error status code.
+    let first_chunk = match export.next_chunk().await {
+        Ok(first_chunk_opt) => first_chunk_opt.unwrap_or_default(),
+        Err(search_error) => {
+            return into_rest_api_response::<(), _>(Err(search_error), BodyFormat::node_default())
+                .into_response();
+        }
+    };
+    let next_chunks = stream::try_unfold(export, |mut export| async move {
+        let chunk_opt = export.next_chunk().await?;
+        Ok::<_, SearchError>(chunk_opt.map(|chunk| (chunk, export)))
+    });
+    let chunks = stream::once(async { Ok(first_chunk) }).chain(next_chunks);
+
+    let mut response = warp::reply::Response::new(warp::Body::wrap_stream(chunks));
+    let headers = response.headers_mut();
+    headers.insert(
+        CONTENT_TYPE,
+        HeaderValue::from_static("application/x-ndjson"),
+    );
+    headers.insert(VARY, HeaderValue::from_static("accept-encoding"));
+
+    if use_gzip {
+        headers.insert(CONTENT_ENCODING, HeaderValue::from_static("gzip"));
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,285 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::ops::Bound;
+
+use flate2::write::GzDecoder;
+use quickwit_common::ServiceStream;
+use quickwit_indexing::MockSplitBuilder;
+use quickwit_metastore::{
+    IndexMetadata, ListIndexesMetadataResponseExt, ListSplitsRequestExt, ListSplitsResponseExt,
+    SplitState,
+};
+use quickwit_proto::metastore::{
+    ListIndexesMetadataResponse, ListSplitsResponse, MockMetastoreService,
+};
+use quickwit_proto::search::{Hit, SearchResponse};
+use quickwit_search::MockSearchService;
+use serde_json::Value as JsonValue;
+
+use super::*;
+
+const NUM_DOCS: u32 = 25;
+
+fn mock_search_service_with_docs() -> Arc<dyn SearchService> {
+    let mut mock_search_service = MockSearchService::new();
+    mock_search_service
+        .expect_root_search()
+        .returning(|search_request| {
+            assert_eq!(search_request.sort_fields[0].field_name, "_shard_doc");
+            let start_doc_id = match &search_request.search_after {
+                Some(partial_hit) => partial_hit.doc_id + 1,
+                None => 0,
+            };
+            let end_doc_id = (start_doc_id + search_request.max_hits as u32).min(NUM_DOCS);
+            let hits = (start_doc_id..end_doc_id)
+                .map(|doc_id| Hit {
+                    json: format!(r#"{{"id":{doc_id}}}"#),
+                    partial_hit: Some(PartialHit {
+                        split_id: "split-1".to_string(),
+                        segment_ord: 0,
+                        doc_id,
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                })
+                .collect();
+            Ok(SearchResponse {
+                hits,
+                ..Default::default()
+            })
+        });
+    Arc::new(mock_search_service)
+}
+
+/// Mocks a metastore where the given splits of the index were merged since `timestamp`.
+fn mock_metastore_with_replaced_splits(
+    replaced_split_ids: &'static [&'static str],
+    timestamp: i64,
+) -> MetastoreServiceClient {
+    let mut mock_metastore = MockMetastoreService::new();
+    mock_metastore
+        .expect_list_indexes_metadata()
+        .returning(|_| {
+            let index_metadata = IndexMetadata::for_test("my-index", "ram:///indexes/my-index");
+            Ok(ListIndexesMetadataResponse::for_test(vec![index_metadata]))
+        });
+    mock_metastore
+        .expect_list_splits()
+        .returning(move |list_splits_request| {
+            let query = list_splits_request.deserialize_list_splits_query().unwrap();
+            assert_eq!(query.split_states, vec![SplitState::MarkedForDeletion]);
+
+            let splits = match query.update_timestamp.start {
+                Bound::Included(update_timestamp) if update_timestamp <= timestamp => {
+                    replaced_split_ids
+                        .iter()
+                        .map(|split_id| MockSplitBuilder::new(split_id).build())
+                        .collect()
+                }
+                _ => Vec::new(),
+            };
+            let splits = ListSplitsResponse::try_from_splits(splits).unwrap();
+            Ok(ServiceStream::from(vec![Ok(splits)]))
+        });
+    MetastoreServiceClient::from_mock(mock_metastore)
+}
+
+fn mock_metastore() -> MetastoreServiceClient {
+    mock_metastore_with_replaced_splits(&[], i64::MIN)
+}
+
+fn now_timestamp() -> i64 {
+    OffsetDateTime::now_utc().unix_timestamp()
+}
+
+/// Decompresses a gzip stream, possibly truncated.
+fn gunzip(compressed: &[u8]) -> String {
+    let mut decoder = GzDecoder::new(Vec::new());
+    decoder.write_all(compressed).unwrap();
+    decoder.flush().unwrap();
+    String::from_utf8(decoder.get_ref().clone()).unwrap()
+}
+
+/// Splits an exported NDJSON stream, possibly truncated, into the document IDs of its
+/// complete pages and its last continuation token.
+fn parse_export(ndjson: &str) -> (Vec<u64>, Option<String>) {
+    let mut doc_ids = Vec::new();
+    let mut page_doc_ids = Vec::new();
+    let mut continuation_opt = None;
+
+    for line in ndjson.lines() {
+        // The last line of a truncated stream may be incomplete.
+        let Ok(line_json) = serde_json::from_str::<JsonValue>(line) else {
+            break;
+        };
+        if let Some(continuation) = line_json.get("_continuation") {
+            continuation_opt = Some(continuation.as_str().unwrap().to_string());
+            doc_ids.append(&mut page_doc_ids);
+        } else {
+            page_doc_ids.push(line_json["id"].as_u64().unwrap());
+        }
+    }
+    (doc_ids, continuation_opt)
+}
+
+async fn export_gzip(query_string: &str) -> Vec<u8> {
+    let handler = export_handler(mock_search_service_with_docs(), mock_metastore());
+    let response = warp::test::request()
+        .path(&format!("/my-index/_export?{query_string}"))
+        .header("accept-encoding", "br;q=0.5, gzip")
+        .reply(&handler)
+        .await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.headers()[CONTENT_ENCODING], "gzip");
+    assert_eq!(response.headers()[CONTENT_TYPE], "application/x-ndjson");
+    response.body().to_vec()
+}
+
+#[test]
+fn test_accepts_gzip() {
+    assert!(accepts_gzip("gzip"));
+    assert!(accepts_gzip("deflate, GZIP;q=0.8"));
+    assert!(!accepts_gzip("gzip;q=0"));
+    assert!(!accepts_gzip("zstd, br"));
+    assert!(!accepts_gzip(""));
+}
+
+#[tokio::test]
+async fn test_export_without_compression() {
+    let handler = export_handler(mock_search_service_with_docs(), mock_metastore());
+    let response = warp::test::request()
+        .path("/my-index/_export?page_size=10")
+        .reply(&handler)
+        .await;
+    assert_eq!(response.status(), 200);
+    assert!(response.headers().get(CONTENT_ENCODING).is_none());
+
+    let ndjson = std::str::from_utf8(response.body()).unwrap();
+    let (doc_ids, continuation_opt) = parse_export(ndjson);
+    assert_eq!(doc_ids, (0..NUM_DOCS as u64).collect::<Vec<u64>>());
+    let continuation: ExportContinuation = continuation_opt.unwrap().parse().unwrap();
+    assert_eq!(
+        continuation.doc_address.to_string(),
+        "split-1:00000000:00000018"
+    );
+    assert!(continuation.search_timestamp <= now_timestamp());
+}
+
+#[tokio::test]
+async fn test_export_resumes_compressed_export_from_continuation() {
+    let compressed_export = export_gzip("page_size=10").await;
+    let (full_doc_ids, _) = parse_export(&gunzip(&compressed_export));
+    assert_eq!(full_doc_ids.len(), NUM_DOCS as usize);
+
+    // Simulates a network drop in the middle of the second page.
+    let mut export = Export::new(
+        "my-index".to_string(),
+        serde_json::from_str(r#"{"page_size": 10}"#).unwrap(),
+        true,
+        mock_search_service_with_docs(),
+        mock_metastore(),
+    )
+    .unwrap();
+    let first_chunk = export.next_chunk().await.unwrap().unwrap();
+    let second_chunk = export.next_chunk().await.unwrap().unwrap();
+    let mut received = first_chunk.to_vec();
+    received.extend_from_slice(&second_chunk[..second_chunk.len() / 2]);
+
+    let (mut doc_ids, continuation_opt) = parse_export(&gunzip(&received));
+    let continuation = continuation_opt.unwrap();
+    assert_eq!(doc_ids.len(), 10);
+    assert!(continuation.ends_with(":split-1:00000000:00000009"));
+
+    let resumed_export = export_gzip(&format!("page_size=10&continuation={continuation}")).await;
+    let (resumed_doc_ids, _) = parse_export(&gunzip(&resumed_export));
+    doc_ids.extend(resumed_doc_ids);
+
+    assert_eq!(doc_ids.len(), NUM_DOCS as usize);
+    assert_eq!(doc_ids, full_doc_ids);
+}
+
+#[tokio::test]
+async fn test_export_fails_once_read_split_is_merged() {
+    let search_timestamp = now_timestamp();
+    let continuation = format!("{search_timestamp}:split-1:00000000:00000009");
+
+    // A split following the position of the export was merged: nothing was exported twice.
+    let handler = export_handler(
+        mock_search_service_with_docs(),
+        mock_metastore_with_replaced_splits(&["split-2"], search_timestamp),
+    );
+    let response = warp::test::request()
+        .path(&format!(
+            "/my-index/_export?page_size=10&continuation={continuation}"
+        ))
+        .reply(&handler)
+        .await;
+    assert_eq!(response.status(), 200);
+    let (doc_ids, _) = parse_export(std::str::from_utf8(response.body()).unwrap());
+    assert_eq!(doc_ids, (10..NUM_DOCS as u64).collect::<Vec<u64>>());
+
+    // The split of the position of the export was merged.
+    let handler = export_handler(
+        mock_search_service_with_docs(),
+        mock_metastore_with_replaced_splits(&["split-0", "split-1"], search_timestamp),
+    );
+    let response = warp::test::request()
+        .path(&format!(
+            "/my-index/_export?page_size=10&continuation={continuation}"
+        ))
+        .reply(&handler)
+        .await;
+    assert_eq!(response.status(), 400);
+    let error_json: JsonValue = serde_json::from_slice(response.body()).unwrap();
+    let error_message = error_json["message"].as_str().unwrap();
+    assert!(error_message.contains("`split-0`"), "{error_message}");
+
+    // The split was merged during a running export: the stream ends with an error after the
+    // first page.
+    let mut export = Export::new(
+        "my-index".to_string(),
+        serde_json::from_str(r#"{"page_size": 10}"#).unwrap(),
+        false,
+        mock_search_service_with_docs(),
+        mock_metastore_with_replaced_splits(&["split-1"], i64::MAX),
+    )
+    .unwrap();
+    export.next_chunk().await.unwrap().unwrap();
+    let error = export.next_chunk().await.unwrap_err();
+    assert!(matches!(error, SearchError::InvalidArgument(_)));
+}
+
+#[tokio::test]
+async fn test_export_invalid_params() {
+    let handler = export_handler(mock_search_service_with_docs(), mock_metastore());
+    let expired_continuation = format!(
+        "continuation={}:split-1:00000000:00000009",
+        now_timestamp() - 24 * 3600
+    );
+
+    for query_string in [
+        "page_size=0",
+        "page_size=10001",
+        "continuation=not-a-token",
+        "continuation=split-1:00000000:00000009",
+        expired_continuation.as_str(),
+    ] {
+        let response = warp::test::request()
+            .path(&format!("/my-index/_export?{query_string}"))
+            .reply(&handler)
+            .await;
+        assert_eq!(response.status(), 400, "{query_string}");
+    }
+}
@@ -0,0 +1,194 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Continuation tokens of the exports.
+//!
+//! The position of an export is the address of the last document exported, and the documents are
+//! sorted by split ID first. Merges replace splits with new ones, whose IDs sort after the ones of
+//! the splits they replace: once a split the export has already read is merged, the rest of the
+//! export would stream its documents a second time. Instead, the export fails explicitly.
+//!
+//! A merge marks the splits it replaces for deletion, so a token records when the search of its
+//! page started, and a split read by the export has been replaced since if it was marked for
+//! deletion after that. Splits marked for deletion are only deleted after a grace period, so a
+//! token expires after that period.
+
+use std::fmt;
+use std::str::FromStr;
+
+use quickwit_common::shared_consts::split_deletion_grace_period;
+use quickwit_metastore::{
+    ListIndexesMetadataResponseExt, ListSplitsQuery, ListSplitsRequestExt,
+    MetastoreServiceStreamSplitsExt, SplitState,
+};
+use quickwit_proto::metastore::{
+    ListIndexesMetadataRequest, ListSplitsRequest, MetastoreService, MetastoreServiceClient,
+};
+use quickwit_proto::types::IndexUid;
+use quickwit_search::{GlobalDocAddress, SearchError};
+use time::OffsetDateTime;
+
+/// Position of an export: the address of the last document exported, along with the time at
+/// which the search of its page started.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct ExportContinuation {
+    /// Unix timestamp, in seconds.
+    pub search_timestamp: i64,
+    pub doc_address: GlobalDocAddress,
+}
+
+impl fmt::Display for ExportContinuation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.search_timestamp, self.doc_address)
+    }
+}
+
+impl FromStr for ExportContinuation {
+    type Err = SearchError;
+
+    fn from_str(continuation: &str) -> Result<Self, Self::Err> {
+        let invalid_continuation_error =
+            || SearchError::InvalidArgument(format!("invalid continuation token `{continuation}`"));
+        let (search_timestamp_str, doc_address_str) = continuation
+            .split_once(':')
+            .ok_or_else(invalid_continuation_error)?;
+        let search_timestamp = search_timestamp_str
+            .parse()
+            .map_err(|_| invalid_continuation_error())?;
+        let doc_address = doc_address_str
+            .parse()
+            .map_err(|_| invalid_continuation_error())?;
+        Ok(ExportContinuation {
+            search_timestamp,
+            doc_address,
+        })
+    }
+}
+
+impl ExportContinuation {
+    /// Returns an error if the token is too old to tell whether the splits the export has read
+    /// are still there.
+    pub fn ensure_not_expired(&self) -> Result<(), SearchError> {
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+        let grace_period_secs = split_deletion_grace_period().as_secs() as i64;
+
+        if now - self.search_timestamp > grace_period_secs {
+            return Err(SearchError::InvalidArgument(format!(
+                "continuation token expired: tokens are valid for {grace_period_secs} seconds, \
+                 restart the export"
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Returns an error if one of the splits an export has read up to the continuation was merged or
+/// deleted since the search of the continuation page started.
+pub(super) async fn ensure_read_splits_unchanged(
+    metastore: &MetastoreServiceClient,
+    index_uids: Vec<IndexUid>,
+    continuation: &ExportContinuation,
+) -> Result<(), SearchError> {
+    let Some(query) = ListSplitsQuery::try_from_index_uids(index_uids) else {
+        return Ok(());
+    };
+    let query = query
+        .with_split_state(SplitState::MarkedForDeletion)
+        .with_update_timestamp_gte(continuation.search_timestamp);
+    let list_splits_request = ListSplitsRequest::try_from_list_splits_query(&query)?;
+    let replaced_split_ids = metastore
+        .list_splits(list_splits_request)
+        .await?
+        .collect_split_ids()
+        .await?;
+
+    if let Some(replaced_split_id) = replaced_split_ids
+        .iter()
+        .find(|split_id| **split_id <= continuation.doc_address.split)
+    {
+        return Err(SearchError::InvalidArgument(format!(
+            "split `{replaced_split_id}` was merged or deleted during the export, which would \
+             export some documents twice: restart the export"
+        )));
+    }
+    Ok(())
+}
+
+/// Resolves the UIDs of the indexes an export targets.
+pub(super) async fn resolve_index_uids(
+    metastore: &MetastoreServiceClient,
+    index_id_patterns: Vec<String>,
+) -> Result<Vec<IndexUid>, SearchError> {
+    let list_indexes_metadata_request = ListIndexesMetadataRequest {
+        index_id_patterns,
+        paused_only: false,
+    };
+    let index_uids = metastore
+        .list_indexes_metadata(list_indexes_metadata_request)
+        .await?
+        .deserialize_indexes_metadata()
+        .await?
+        .into_iter()
+        .map(|index_metadata| index_metadata.index_uid)
+        .collect();
+    Ok(index_uids)
+}
+
+#[cfg(test)]
+mod tests {
+    use tantivy::DocAddress;
+
+    use super::*;
+
+    #[test]
+    fn test_export_continuation_roundtrip() {
+        let continuation = ExportContinuation {
+            search_timestamp: 1_700_000_000,
+            doc_address: GlobalDocAddress {
+                split: "split-1".to_string(),
+                doc_addr: DocAddress {
+                    segment_ord: 1,
+                    doc_id: 24,
+                },
+            },
+        };
+        let continuation_str = continuation.to_string();
+        assert_eq!(continuation_str, "1700000000:split-1:00000001:00000018");
+        assert_eq!(
+            continuation_str.parse::<ExportContinuation>().unwrap(),
+            continuation
+        );
+
+        for invalid_continuation in ["split-1:00000001:00000018", "1700000000", "foo:bar"] {
+            invalid_continuation
+                .parse::<ExportContinuation>()
+                .unwrap_err();
+        }
+    }
+
+    #[test]
+    fn test_export_continuation_expiry() {
+        let mut continuation: ExportContinuation = format!(
+            "{}:split-1:00000000:00000000",
+            OffsetDateTime::now_utc().unix_timestamp()
+        )
+        .parse()
+        .unwrap();
+        continuation.ensure_not_expired().unwrap();
+
+        continuation.search_timestamp -= split_deletion_grace_period().as_secs() as i64 + 1;
+        let error = continuation.ensure_not_expired().unwrap_err();
+        assert!(matches!(error, SearchError::InvalidArgument(_)));
+    }
+}
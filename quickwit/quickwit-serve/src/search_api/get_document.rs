@@ -0,0 +1,404 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use quickwit_doc_mapper::JsonObject;
+use quickwit_metastore::IndexMetadataResponseExt;
+use quickwit_proto::metastore::{IndexMetadataRequest, MetastoreService, MetastoreServiceClient};
+use quickwit_proto::search::{SearchRequest, SearchResponse};
+use quickwit_proto::{ServiceError, ServiceErrorCode};
+use quickwit_query::query_ast::{QueryAst, TermQuery};
+use quickwit_search::{SearchError, SearchService};
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+use warp::{Filter, Rejection};
+
+use crate::elasticsearch_api::rest_handler::filter_source;
+use crate::format::extract_format_from_qs;
+use crate::query_params::query_params;
+use crate::request_scope::ensure_indexes_in_scope;
+use crate::rest_api_response::into_rest_api_response;
+use crate::simple_list::from_simple_list;
+use crate::with_arg;
+
+/// Query parameters of the get document endpoints, filtering the returned document.
+#[derive(Debug, Default, Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(crate) struct GetDocumentQueryParams {
+    /// `true` or `false` to return the document or not, or a comma-separated list of fields to
+    /// return.
+    #[serde(default)]
+    #[serde(deserialize_with = "from_simple_list")]
+    #[param(value_type = Option<String>)]
+    pub _source: Option<Vec<String>>,
+    /// Comma-separated list of fields to return.
+    #[serde(default)]
+    #[serde(deserialize_with = "from_simple_list")]
+    #[param(value_type = Option<String>)]
+    pub _source_includes: Option<Vec<String>>,
+    /// Comma-separated list of fields to omit.
+    #[serde(default)]
+    #[serde(deserialize_with = "from_simple_list")]
+    #[param(value_type = Option<String>)]
+    pub _source_excludes: Option<Vec<String>>,
+}
+
+impl GetDocumentQueryParams {
+    /// Applies the `_source` filters to the document. Returns `None` if the document should be
+    /// omitted altogether (`_source=false`).
+    pub(crate) fn filter_document(&self, document: JsonObject) -> Option<JsonValue> {
+        let mut source_includes = self._source_includes.clone();
+
+        match self._source.as_deref() {
+            Some([source]) if source == "false" => return None,
+            Some([source]) if source == "true" => {}
+            Some(source_fields) => source_includes
+                .get_or_insert_with(Vec::new)
+                .extend(source_fields.iter().cloned()),
+            None => {}
+        }
+        let mut document_json = JsonValue::Object(document);
+        filter_source(&mut document_json, &self._source_excludes, &source_includes);
+        Some(document_json)
+    }
+}
+
+/// Looks up the document of an index whose value for the index's id field
+/// (`ingest_settings.upsert_id_field`) is `doc_id`. Returns `None` if there is no such document,
+/// and an error if several documents share this id.
+pub(crate) async fn get_document_by_id(
+    index_id: String,
+    doc_id: &str,
+    search_service: &dyn SearchService,
+    metastore: &MetastoreServiceClient,
+) -> Result<Option<JsonObject>, GetDocumentError> {
+    let search_response =
+        search_documents_by_id(index_id.clone(), doc_id, search_service, metastore).await?;
+
+    if search_response.num_hits > 1 {
+        return Err(GetDocumentError::DuplicateId {
+            index_id,
+            doc_id: doc_id.to_string(),
+            num_docs: search_response.num_hits,
+        });
+    }
+    let Some(hit) = search_response.hits.into_iter().next() else {
+        return Ok(None);
+    };
+    let document: JsonObject = serde_json::from_str(&hit.json).map_err(SearchError::from)?;
+    Ok(Some(document))
+}
+
+/// Searches the documents of an index whose value for the index's id field is `doc_id`, returning
+/// up to two hits: enough to tell whether the id is unique.
+async fn search_documents_by_id(
+    index_id: String,
+    doc_id: &str,
+    search_service: &dyn SearchService,
+    metastore: &MetastoreServiceClient,
+) -> Result<SearchResponse, SearchError> {
+    ensure_indexes_in_scope([index_id.as_str()])?;
+
+    let index_metadata_request = IndexMetadataRequest::for_index_id(index_id.clone());
+    let index_metadata = metastore
+        .index_metadata(index_metadata_request)
+        .await?
+        .deserialize_index_metadata()?;
+    let Some(id_field) = &index_metadata.index_config.ingest_settings.upsert_id_field else {
+        return Err(SearchError::InvalidArgument(format!(
+            "index `{index_id}` does not define an id field: set \
+             `ingest_settings.upsert_id_field` in its config to get documents by id"
+        )));
+    };
+    let query_ast: QueryAst = TermQuery {
+        field: id_field.clone(),
+        value: doc_id.to_string(),
+    }
+    .into();
+    let search_request = SearchRequest {
+        index_id_patterns: vec![index_id],
+        query_ast: serde_json::to_string(&query_ast)?,
+        max_hits: 2,
+        ..Default::default()
+    };
+    search_service.root_search(search_request).await
+}
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum GetDocumentError {
+    #[error("document `{doc_id}` not found in index `{index_id}`")]
+    NotFound { index_id: String, doc_id: String },
+    #[error("{num_docs} documents share the id `{doc_id}` in index `{index_id}`")]
+    DuplicateId {
+        index_id: String,
+        doc_id: String,
+        num_docs: u64,
+    },
+    #[error(transparent)]
+    Search(#[from] SearchError),
+}
+
+impl ServiceError for GetDocumentError {
+    fn error_code(&self) -> ServiceErrorCode {
+        match self {
+            Self::NotFound { .. } => ServiceErrorCode::NotFound,
+            Self::DuplicateId { .. } => ServiceErrorCode::Conflict,
+            Self::Search(search_error) => search_error.error_code(),
+        }
+    }
+}
+
+fn get_document_filter()
+-> impl Filter<Extract = (String, String, GetDocumentQueryParams), Error = Rejection> + Clone {
+    warp::path!("indexes" / String / "_doc" / String)
+        .and(warp::get())
+        .and(query_params())
+}
+
+pub(crate) fn get_document_handler(
+    search_service: Arc<dyn SearchService>,
+    metastore: MetastoreServiceClient,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    get_document_filter()
+        .and(with_arg(search_service))
+        .and(with_arg(metastore))
+        .then(get_document)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+        .boxed()
+}
+
+#[utoipa::path(
+    get,
+    tag = "Search",
+    path = "/indexes/{index_id}/_doc/{doc_id}",
+    responses(
+        (status = 200, description = "Successfully fetched the document."),
+        (status = 404, description = "The document does not exist."),
+        (status = 409, description = "Several documents share the id."),
+    ),
+    params(
+        GetDocumentQueryParams,
+        ("index_id" = String, Path, description = "The index ID of the document."),
+        ("doc_id" = String, Path, description = "The value of the id field of the document."),
+    )
+)]
+/// Get Document
+///
+/// Returns the document whose value for the index's id field (`ingest_settings.upsert_id_field`)
+/// is `doc_id`, or a conflict error if several documents share this id.
+async fn get_document(
+    index_id: String,
+    doc_id: String,
+    query_params: GetDocumentQueryParams,
+    search_service: Arc<dyn SearchService>,
+    metastore: MetastoreServiceClient,
+) -> Result<JsonValue, GetDocumentError> {
+    let Some(document) =
+        get_document_by_id(index_id.clone(), &doc_id, &*search_service, &metastore).await?
+    else {
+        return Err(GetDocumentError::NotFound { index_id, doc_id });
+    };
+    let document_json = query_params
+        .filter_document(document)
+        .unwrap_or_else(|| JsonValue::Object(JsonObject::new()));
+    Ok(document_json)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use quickwit_config::IngestSettings;
+    use quickwit_metastore::IndexMetadata;
+    use quickwit_proto::metastore::{IndexMetadataResponse, MockMetastoreService};
+    use quickwit_proto::search::Hit;
+    use quickwit_search::MockSearchService;
+    use serde_json::json;
+
+    use super::*;
+    use crate::recover_fn;
+    use crate::request_scope::with_index_scope_for_test;
+
+    pub(crate) fn mock_metastore_with_id_field() -> MetastoreServiceClient {
+        let mut index_metadata = IndexMetadata::for_test("test-index", "ram:///indexes/test-index");
+        index_metadata.index_config.ingest_settings = IngestSettings {
+            upsert_id_field: Some("id".to_string()),
+            ..Default::default()
+        };
+        let mut mock_metastore = MockMetastoreService::new();
+        mock_metastore.expect_index_metadata().returning(move |_| {
+            Ok(IndexMetadataResponse::try_from_index_metadata(&index_metadata).unwrap())
+        });
+        MetastoreServiceClient::from_mock(mock_metastore)
+    }
+
+    pub(crate) fn mock_search_service_with_doc() -> MockSearchService {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .returning(|search_request| {
+                let query_ast: QueryAst = serde_json::from_str(&search_request.query_ast).unwrap();
+                let QueryAst::Term(term_query) = query_ast else {
+                    panic!("expected a term query");
+                };
+                assert_eq!(term_query.field, "id");
+                assert_eq!(search_request.max_hits, 2);
+
+                let hit = |json: &str| Hit {
+                    json: json.to_string(),
+                    index_id: "test-index".to_string(),
+                    ..Default::default()
+                };
+                let hits = match term_query.value.as_str() {
+                    "doc-1" => vec![hit(
+                        r#"{"id": "doc-1", "body": "hello", "severity": "INFO"}"#,
+                    )],
+                    "doc-dup" => vec![
+                        hit(r#"{"id": "doc-dup", "body": "hello"}"#),
+                        hit(r#"{"id": "doc-dup", "body": "world"}"#),
+                    ],
+                    _ => Vec::new(),
+                };
+                Ok(SearchResponse {
+                    num_hits: hits.len() as u64,
+                    hits,
+                    ..Default::default()
+                })
+            });
+        mock_search_service
+    }
+
+    #[tokio::test]
+    async fn test_get_document() {
+        let handler = get_document_handler(
+            Arc::new(mock_search_service_with_doc()),
+            mock_metastore_with_id_field(),
+        )
+        .recover(recover_fn);
+
+        let resp = warp::test::request()
+            .path("/indexes/test-index/_doc/doc-1")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(
+            resp_json,
+            json!({"id": "doc-1", "body": "hello", "severity": "INFO"})
+        );
+
+        let resp = warp::test::request()
+            .path("/indexes/test-index/_doc/doc-1?_source_excludes=body,severity")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(resp_json, json!({"id": "doc-1"}));
+
+        let resp = warp::test::request()
+            .path("/indexes/test-index/_doc/doc-1?_source=body")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(resp_json, json!({"body": "hello"}));
+
+        let resp = warp::test::request()
+            .path("/indexes/test-index/_doc/doc-1?_source=body&format=pretty_json")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        assert_eq!(resp.body(), "{\n  \"body\": \"hello\"\n}");
+    }
+
+    #[tokio::test]
+    async fn test_get_document_not_found() {
+        let handler = get_document_handler(
+            Arc::new(mock_search_service_with_doc()),
+            mock_metastore_with_id_field(),
+        )
+        .recover(recover_fn);
+
+        let resp = warp::test::request()
+            .path("/indexes/test-index/_doc/doc-2")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 404);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(
+            resp_json["message"],
+            "document `doc-2` not found in index `test-index`"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_document_duplicate_id() {
+        let handler = get_document_handler(
+            Arc::new(mock_search_service_with_doc()),
+            mock_metastore_with_id_field(),
+        )
+        .recover(recover_fn);
+
+        let resp = warp::test::request()
+            .path("/indexes/test-index/_doc/doc-dup")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 409);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(
+            resp_json["message"],
+            "2 documents share the id `doc-dup` in index `test-index`"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_document_out_of_scope() {
+        let mut mock_metastore = MockMetastoreService::new();
+        mock_metastore.expect_index_metadata().never();
+        let handler = get_document_handler(
+            Arc::new(MockSearchService::new()),
+            MetastoreServiceClient::from_mock(mock_metastore),
+        )
+        .recover(recover_fn);
+
+        let resp = with_index_scope_for_test(
+            &["logs-*"],
+            warp::test::request()
+                .path("/indexes/test-index/_doc/doc-1")
+                .reply(&handler),
+        )
+        .await;
+        assert_eq!(resp.status(), 403);
+    }
+
+    #[tokio::test]
+    async fn test_get_document_without_id_field() {
+        let mut mock_metastore = MockMetastoreService::new();
+        mock_metastore.expect_index_metadata().returning(|_| {
+            let index_metadata = IndexMetadata::for_test("test-index", "ram:///indexes/test-index");
+            Ok(IndexMetadataResponse::try_from_index_metadata(&index_metadata).unwrap())
+        });
+        let handler = get_document_handler(
+            Arc::new(MockSearchService::new()),
+            MetastoreServiceClient::from_mock(mock_metastore),
+        )
+        .recover(recover_fn);
+
+        let resp = warp::test::request()
+            .path("/indexes/test-index/_doc/doc-1")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 400);
+    }
+}
@@ -18,9 +18,9 @@ use async_trait::async_trait;
 use quickwit_common::tracing_utils::set_current_span_parent_from_metadata;
 use quickwit_proto::error::convert_to_grpc_result;
 use quickwit_proto::search::{
-    GetKvRequest, GetKvResponse, GetLoadRequest, GetLoadResponse, LeafListFieldsRequest,
-    ListFieldsRequest, ListFieldsResponse, ReportSplitsRequest, ReportSplitsResponse,
-    search_service_server as grpc,
+    GetKvRequest, GetKvResponse, GetLoadRequest, GetLoadResponse, LeafClearCacheRequest,
+    LeafClearCacheResponse, LeafListFieldsRequest, ListFieldsRequest, ListFieldsResponse,
+    ReportSplitsRequest, ReportSplitsResponse, search_service_server as grpc,
 };
 use quickwit_proto::tonic;
 use quickwit_search::SearchService;
@@ -174,4 +174,114 @@ impl grpc::SearchService for GrpcSearchAdapter {
         let load_job_cost = self.0.get_load().await as u64;
         Ok(tonic::Response::new(GetLoadResponse { load_job_cost }))
     }
+
+    #[instrument(skip(self, request))]
+    async fn leaf_clear_cache(
+        &self,
+        request: tonic::Request<LeafClearCacheRequest>,
+    ) -> Result<tonic::Response<LeafClearCacheResponse>, tonic::Status> {
+        set_current_span_parent_from_metadata(request.metadata());
+        let leaf_clear_cache_result = self.0.leaf_clear_cache(request.into_inner()).await;
+        convert_to_grpc_result(leaf_clear_cache_result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+    use std::net::SocketAddr;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    use bytesize::ByteSize;
+    use quickwit_config::{CompressionAlgorithm, SearchRequestCompressionConfig};
+    use quickwit_proto::search::search_service_server::SearchServiceServer;
+    use quickwit_proto::search::{LeafSearchRequest, LeafSearchResponse, SearchRequest};
+    use quickwit_proto::tonic::codec::CompressionEncoding;
+    use quickwit_proto::tonic::codegen::InterceptedService;
+    use quickwit_proto::tonic::transport::{Channel, Server};
+    use quickwit_search::{MockSearchService, create_search_client_from_channel};
+    use tower::timeout::Timeout;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_leaf_search_requests_above_threshold_are_compressed() {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_leaf_search()
+            .times(2)
+            .returning(|_| Ok(LeafSearchResponse::default()));
+        let grpc_search_adapter =
+            GrpcSearchAdapter::from(Arc::new(mock_search_service) as Arc<dyn SearchService>);
+        let search_service_server = SearchServiceServer::new(grpc_search_adapter)
+            .accept_compressed(CompressionEncoding::Gzip)
+            .accept_compressed(CompressionEncoding::Zstd);
+
+        let request_encodings: Arc<Mutex<Vec<Option<String>>>> = Arc::default();
+        let request_encodings_clone = request_encodings.clone();
+        let intercepted_service = InterceptedService::new(
+            search_service_server,
+            move |request: tonic::Request<()>| -> Result<tonic::Request<()>, tonic::Status> {
+                let request_encoding_opt = request
+                    .metadata()
+                    .get("grpc-encoding")
+                    .map(|encoding| encoding.to_str().unwrap().to_string());
+                request_encodings_clone
+                    .lock()
+                    .unwrap()
+                    .push(request_encoding_opt);
+                Ok(request)
+            },
+        );
+        let (client, server) = tokio::io::duplex(1024);
+        tokio::spawn(async move {
+            Server::builder()
+                .add_service(intercepted_service)
+                .serve_with_incoming(tokio_stream::once(Ok::<_, std::io::Error>(server)))
+                .await
+                .unwrap();
+        });
+        let mut client_opt = Some(client);
+        let connector = tower::service_fn(move |_: http::Uri| {
+            let client = client_opt.take().unwrap();
+            async move { Ok::<_, Infallible>(hyper_util::rt::TokioIo::new(client)) }
+        });
+        let channel = Channel::builder("http://[::]:50051".parse().unwrap())
+            .connect_with_connector(connector)
+            .await
+            .unwrap();
+        let grpc_addr: SocketAddr = "127.0.0.1:7281".parse().unwrap();
+        let request_compression = SearchRequestCompressionConfig {
+            algorithm: CompressionAlgorithm::Zstd,
+            min_request_size: ByteSize::kib(1),
+        };
+        let mut search_client = create_search_client_from_channel(
+            grpc_addr,
+            Timeout::new(channel, Duration::from_secs(5)),
+            ByteSize::mib(20),
+        )
+        .with_request_compression(Some(request_compression));
+
+        let large_leaf_search_request = LeafSearchRequest {
+            search_request: Some(SearchRequest {
+                query_ast: "a".repeat(10_000),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        search_client
+            .leaf_search(large_leaf_search_request)
+            .await
+            .unwrap();
+
+        let small_leaf_search_request = LeafSearchRequest::default();
+        search_client
+            .leaf_search(small_leaf_search_request)
+            .await
+            .unwrap();
+
+        let recorded_encodings = request_encodings.lock().unwrap().clone();
+        assert_eq!(recorded_encodings, [Some("zstd".to_string()), None]);
+    }
 }
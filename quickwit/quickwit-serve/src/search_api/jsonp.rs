@@ -0,0 +1,221 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! JSONP support of the GET search endpoint, for legacy clients unable to issue cross-origin
+//! requests: the JSON response is wrapped in a call to the function named by the `callback`
+//! parameter, and served as JavaScript.
+
+use http_body_util::BodyExt;
+use quickwit_search::SearchError;
+use warp::Reply;
+use warp::hyper::header::{CONTENT_LENGTH, CONTENT_TYPE, X_CONTENT_TYPE_OPTIONS};
+use warp::hyper::http::HeaderValue;
+
+use crate::BodyFormat;
+use crate::rest_api_response::into_rest_api_response;
+
+/// Maximum length of the name of a JSONP callback.
+const MAX_CALLBACK_LEN: usize = 128;
+
+const JAVASCRIPT_CONTENT_TYPE: &str = "application/javascript; charset=utf-8";
+
+/// Checks that the callback is a dot-separated path of JavaScript identifiers, such as
+/// `jQuery1234.handleResults`, so that it cannot inject code into the response.
+pub(super) fn validate_jsonp_callback(callback: &str) -> Result<(), SearchError> {
+    if callback.len() <= MAX_CALLBACK_LEN && callback.split('.').all(is_identifier) {
+        return Ok(());
+    }
+    Err(SearchError::InvalidArgument(format!(
+        "invalid `callback` `{}`: expected a JavaScript function name of at most \
+         {MAX_CALLBACK_LEN} characters, made of letters, digits, `_`, `$`, and `.`",
+        callback.escape_default()
+    )))
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+
+    let Some(first_char) = chars.next() else {
+        return false;
+    };
+    (first_char.is_ascii_alphabetic() || first_char == '_' || first_char == '$')
+        && chars.all(|char| char.is_ascii_alphanumeric() || char == '_' || char == '$')
+}
+
+pub(super) fn is_json_response(response: &warp::reply::Response) -> bool {
+    response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .map(|content_type| content_type.starts_with("application/json"))
+        .unwrap_or(false)
+}
+
+/// Wraps the JSON body of the response, error responses included, in a call to the callback,
+/// which must have been validated. The status code of the response is preserved, although
+/// JSONP clients cannot read it. The body starts with an empty comment so that it cannot be
+/// mistaken for another type of content by the clients sniffing it.
+pub(super) async fn into_jsonp_response(
+    response: warp::reply::Response,
+    callback: &str,
+) -> warp::reply::Response {
+    let (mut parts, body) = response.into_parts();
+
+    let body_bytes = match body.collect().await {
+        Ok(collected_body) => collected_body.to_bytes(),
+        Err(error) => {
+            let search_error =
+                SearchError::Internal(format!("failed to read response body: {error}"));
+            return into_rest_api_response::<(), _>(Err(search_error), BodyFormat::Json)
+                .into_response();
+        }
+    };
+    let mut jsonp_body = Vec::with_capacity(body_bytes.len() + callback.len() + 8);
+    jsonp_body.extend_from_slice(b"/**/");
+    jsonp_body.extend_from_slice(callback.as_bytes());
+    jsonp_body.push(b'(');
+    jsonp_body.extend_from_slice(&body_bytes);
+    jsonp_body.extend_from_slice(b");");
+
+    parts.headers.remove(CONTENT_LENGTH);
+    parts.headers.insert(
+        CONTENT_TYPE,
+        HeaderValue::from_static(JAVASCRIPT_CONTENT_TYPE),
+    );
+    parts
+        .headers
+        .insert(X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff"));
+    warp::reply::Response::from_parts(parts, jsonp_body.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use quickwit_proto::search::{Hit, SearchResponse};
+    use quickwit_search::MockSearchService;
+    use serde_json::Value as JsonValue;
+    use warp::Filter;
+
+    use super::*;
+    use crate::recover_fn;
+    use crate::search_api::search_get_handler;
+
+    #[test]
+    fn test_validate_jsonp_callback() {
+        for callback in ["handle", "_handle$1", "$", "jQuery1234.handle_results"] {
+            validate_jsonp_callback(callback).unwrap();
+        }
+        let too_long_callback = "a".repeat(MAX_CALLBACK_LEN + 1);
+
+        for callback in [
+            "",
+            "1handle",
+            "handle.",
+            "handle()",
+            "alert(1);handle",
+            "handle[0]",
+            "handle\n",
+            &too_long_callback,
+        ] {
+            validate_jsonp_callback(callback).unwrap_err();
+        }
+    }
+
+    fn mock_search_service() -> MockSearchService {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service.expect_root_search().returning(|_| {
+            Ok(SearchResponse {
+                num_hits: 1,
+                hits: vec![Hit {
+                    json: r#"{"body": "first"}"#.to_string(),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            })
+        });
+        mock_search_service
+    }
+
+    #[tokio::test]
+    async fn test_search_jsonp() {
+        let search_handler =
+            search_get_handler(Arc::new(mock_search_service()), true).recover(recover_fn);
+
+        let response = warp::test::request()
+            .path("/quickwit-demo-index/search?query=*&callback=jQuery1234.handle&format=json")
+            .reply(&search_handler)
+            .await;
+        assert_eq!(response.status(), 200);
+        assert_eq!(
+            response.headers().get(CONTENT_TYPE).unwrap(),
+            JAVASCRIPT_CONTENT_TYPE
+        );
+        assert_eq!(
+            response.headers().get(X_CONTENT_TYPE_OPTIONS).unwrap(),
+            "nosniff"
+        );
+
+        let body = std::str::from_utf8(response.body()).unwrap();
+        let wrapped_json = body
+            .strip_prefix("/**/jQuery1234.handle(")
+            .unwrap()
+            .strip_suffix(");")
+            .unwrap();
+        let response_json: JsonValue = serde_json::from_str(wrapped_json).unwrap();
+        assert_eq!(response_json["num_hits"], 1);
+        assert_eq!(response_json["hits"][0]["body"], "first");
+
+        // Non-JSON responses are not wrapped.
+        let response = warp::test::request()
+            .path("/quickwit-demo-index/search?query=*&callback=handle&format=yaml")
+            .reply(&search_handler)
+            .await;
+        assert_eq!(response.status(), 200);
+        assert!(!response.body().starts_with(b"/**/"));
+
+        let response = warp::test::request()
+            .path("/quickwit-demo-index/search?query=*&callback=alert(document.cookie)%3Bhandle")
+            .reply(&search_handler)
+            .await;
+        assert_eq!(response.status(), 400);
+        assert!(!response.body().starts_with(b"/**/"));
+        let error_json: JsonValue = serde_json::from_slice(response.body()).unwrap();
+        assert!(
+            error_json["message"]
+                .as_str()
+                .unwrap()
+                .contains("invalid `callback`")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_search_jsonp_disabled() {
+        let search_handler =
+            search_get_handler(Arc::new(MockSearchService::new()), false).recover(recover_fn);
+
+        let response = warp::test::request()
+            .path("/quickwit-demo-index/search?query=*&callback=handle")
+            .reply(&search_handler)
+            .await;
+        assert_eq!(response.status(), 400);
+        let error_json: JsonValue = serde_json::from_slice(response.body()).unwrap();
+        assert!(
+            error_json["message"]
+                .as_str()
+                .unwrap()
+                .contains("rest.enable_jsonp")
+        );
+    }
+}
@@ -0,0 +1,331 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::convert::Infallible;
+
+use serde::Serialize;
+use warp::{Filter, Rejection};
+
+use crate::format::extract_format_from_qs;
+use crate::rest_api_response::into_rest_api_response;
+
+/// Type of the value of an aggregation parameter, as named in JSON schemas.
+#[derive(Debug, Clone, Copy, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum AggregationParamType {
+    String,
+    Integer,
+    Number,
+    Boolean,
+    Array,
+    Object,
+    /// Either a string or a number, depending on the type of the field.
+    Any,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub(crate) struct AggregationParam {
+    name: &'static str,
+    #[serde(rename = "type")]
+    param_type: AggregationParamType,
+    required: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum AggregationKind {
+    /// Creates buckets of documents, and accepts sub-aggregations.
+    Bucket,
+    /// Computes metrics over the values of a field.
+    Metric,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub(crate) struct AggregationType {
+    /// Name of the aggregation in the `aggs` of a search request.
+    name: &'static str,
+    kind: AggregationKind,
+    #[schema(value_type = Vec<AggregationParam>)]
+    params: &'static [AggregationParam],
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub(crate) struct AggregationsResponse {
+    #[schema(value_type = Vec<AggregationType>)]
+    aggregations: &'static [AggregationType],
+}
+
+const fn param(
+    name: &'static str,
+    param_type: AggregationParamType,
+    required: bool,
+) -> AggregationParam {
+    AggregationParam {
+        name,
+        param_type,
+        required,
+    }
+}
+
+const FIELD: AggregationParam = param("field", AggregationParamType::String, true);
+
+const KEYED: AggregationParam = param("keyed", AggregationParamType::Boolean, false);
+
+const MIN_DOC_COUNT: AggregationParam =
+    param("min_doc_count", AggregationParamType::Integer, false);
+
+const HARD_BOUNDS: AggregationParam = param("hard_bounds", AggregationParamType::Object, false);
+
+const EXTENDED_BOUNDS: AggregationParam =
+    param("extended_bounds", AggregationParamType::Object, false);
+
+const METRIC_MISSING: AggregationParam = param("missing", AggregationParamType::Number, false);
+
+const METRIC_PARAMS: &[AggregationParam] = &[FIELD, METRIC_MISSING];
+
+const fn metric(name: &'static str) -> AggregationType {
+    AggregationType {
+        name,
+        kind: AggregationKind::Metric,
+        params: METRIC_PARAMS,
+    }
+}
+
+/// Aggregations supported by the search engine, and the parameters they accept.
+///
+/// The aggregations are executed by tantivy, and the tests check that each of them is accepted
+/// by its request parser, so this list must be updated along with the tantivy version.
+/// The options of the percentiles aggregation selecting another estimation method are left out
+/// because the root search rejects them.
+const AGGREGATIONS: &[AggregationType] = &[
+    AggregationType {
+        name: "histogram",
+        kind: AggregationKind::Bucket,
+        params: &[
+            FIELD,
+            param("interval", AggregationParamType::Number, true),
+            param("offset", AggregationParamType::Number, false),
+            MIN_DOC_COUNT,
+            HARD_BOUNDS,
+            EXTENDED_BOUNDS,
+            KEYED,
+        ],
+    },
+    AggregationType {
+        name: "date_histogram",
+        kind: AggregationKind::Bucket,
+        params: &[
+            FIELD,
+            param("fixed_interval", AggregationParamType::String, true),
+            param("offset", AggregationParamType::String, false),
+            MIN_DOC_COUNT,
+            HARD_BOUNDS,
+            EXTENDED_BOUNDS,
+            KEYED,
+        ],
+    },
+    AggregationType {
+        name: "range",
+        kind: AggregationKind::Bucket,
+        params: &[
+            FIELD,
+            param("ranges", AggregationParamType::Array, true),
+            KEYED,
+        ],
+    },
+    AggregationType {
+        name: "terms",
+        kind: AggregationKind::Bucket,
+        params: &[
+            FIELD,
+            param("size", AggregationParamType::Integer, false),
+            param("shard_size", AggregationParamType::Integer, false),
+            param("segment_size", AggregationParamType::Integer, false),
+            param(
+                "show_term_doc_count_error",
+                AggregationParamType::Boolean,
+                false,
+            ),
+            MIN_DOC_COUNT,
+            param("order", AggregationParamType::Object, false),
+            param("missing", AggregationParamType::Any, false),
+        ],
+    },
+    AggregationType {
+        name: "composite",
+        kind: AggregationKind::Bucket,
+        params: &[
+            param("sources", AggregationParamType::Array, true),
+            param("size", AggregationParamType::Integer, false),
+            param("after", AggregationParamType::Object, false),
+        ],
+    },
+    metric("avg"),
+    metric("value_count"),
+    metric("max"),
+    metric("min"),
+    metric("stats"),
+    AggregationType {
+        name: "extended_stats",
+        kind: AggregationKind::Metric,
+        params: &[
+            FIELD,
+            param("sigma", AggregationParamType::Number, false),
+            METRIC_MISSING,
+        ],
+    },
+    metric("sum"),
+    AggregationType {
+        name: "percentiles",
+        kind: AggregationKind::Metric,
+        params: &[
+            FIELD,
+            param("percents", AggregationParamType::Array, false),
+            KEYED,
+            METRIC_MISSING,
+        ],
+    },
+    AggregationType {
+        name: "cardinality",
+        kind: AggregationKind::Metric,
+        params: &[FIELD, param("missing", AggregationParamType::Any, false)],
+    },
+];
+
+pub(crate) fn list_aggregations_handler()
+-> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("_aggregations")
+        .and(warp::get())
+        .then(list_aggregations)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+        .boxed()
+}
+
+#[utoipa::path(
+    get,
+    tag = "Search",
+    path = "/_aggregations",
+    responses(
+        (status = 200, description = "Successfully listed the aggregations.", body = AggregationsResponse)
+    ),
+)]
+/// List Aggregations
+///
+/// Returns the aggregation types supported in the `aggs` of search requests, along with the
+/// name, type, and requiredness of their parameters.
+async fn list_aggregations() -> Result<AggregationsResponse, Infallible> {
+    Ok(AggregationsResponse {
+        aggregations: AGGREGATIONS,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{Value as JsonValue, json};
+    use tantivy::aggregation::agg_req::Aggregations;
+
+    use super::*;
+    use crate::recover_fn;
+
+    /// Returns a request of the aggregation, setting its required parameters.
+    fn sample_aggregation_request(aggregation_name: &str) -> JsonValue {
+        let params = match aggregation_name {
+            "histogram" => json!({ "field": "price", "interval": 10.0 }),
+            "date_histogram" => json!({ "field": "timestamp", "fixed_interval": "1h" }),
+            "range" => json!({ "field": "price", "ranges": [{ "to": 10.0 }, { "from": 10.0 }] }),
+            "composite" => json!({
+                "sources": [{ "genre": { "terms": { "field": "genre" } } }]
+            }),
+            _ => json!({ "field": "price" }),
+        };
+        json!({ "sample": { aggregation_name: params } })
+    }
+
+    #[tokio::test]
+    async fn test_list_aggregations() {
+        let handler = list_aggregations_handler().recover(recover_fn);
+        let resp = warp::test::request()
+            .path("/_aggregations")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        let aggregations = resp_json["aggregations"].as_array().unwrap();
+
+        let param_names = |aggregation_name: &str| -> Vec<String> {
+            let aggregation = aggregations
+                .iter()
+                .find(|aggregation| aggregation["name"] == aggregation_name)
+                .unwrap_or_else(|| panic!("`{aggregation_name}` should be listed"));
+            aggregation["params"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|param| param["name"].as_str().unwrap().to_string())
+                .collect()
+        };
+        assert_eq!(
+            param_names("terms"),
+            [
+                "field",
+                "size",
+                "shard_size",
+                "segment_size",
+                "show_term_doc_count_error",
+                "min_doc_count",
+                "order",
+                "missing"
+            ]
+        );
+        assert_eq!(
+            param_names("date_histogram"),
+            [
+                "field",
+                "fixed_interval",
+                "offset",
+                "min_doc_count",
+                "hard_bounds",
+                "extended_bounds",
+                "keyed"
+            ]
+        );
+        assert_eq!(param_names("stats"), ["field", "missing"]);
+        assert_eq!(
+            param_names("percentiles"),
+            ["field", "percents", "keyed", "missing"]
+        );
+        let date_histogram = aggregations
+            .iter()
+            .find(|aggregation| aggregation["name"] == "date_histogram")
+            .unwrap();
+        assert_eq!(date_histogram["kind"], "bucket");
+        assert_eq!(
+            date_histogram["params"][1],
+            json!({ "name": "fixed_interval", "type": "string", "required": true })
+        );
+    }
+
+    #[test]
+    fn test_listed_aggregations_are_supported() {
+        for aggregation in AGGREGATIONS {
+            let aggregation_request = sample_aggregation_request(aggregation.name);
+            serde_json::from_value::<Aggregations>(aggregation_request).unwrap_or_else(|error| {
+                panic!("`{}` should be supported: {error}", aggregation.name)
+            });
+        }
+        let unsupported_request = sample_aggregation_request("geohash_grid");
+        serde_json::from_value::<Aggregations>(unsupported_request).unwrap_err();
+    }
+}
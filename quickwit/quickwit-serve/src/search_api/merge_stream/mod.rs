@@ -0,0 +1,384 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Streaming merge of the hits of several indexes sorted by a fast field, such as their timestamp
+//! field, for global views of the recent events of many indexes.
+//!
+//! Each index is searched one page of hits at a time, and the pages are interleaved by sort value
+//! on the fly with a k-way merge, so the memory used by a merge is bounded by one page per index.
+//! An index failing or lagging behind is dropped from the merge with an `{"_error": ...}` line,
+//! and the hits of the other indexes keep streaming.
+
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+
+use bytes::Bytes;
+use futures::future::join_all;
+use futures::{StreamExt, stream};
+use quickwit_proto::search::{CountHits, Hit, PartialHit, SearchRequest, SortOrder, SortValue};
+use quickwit_query::query_ast::query_ast_from_user_text;
+use quickwit_search::{SearchError, SearchService};
+use serde::Deserialize;
+use serde_json::{Value as JsonValue, json};
+use warp::hyper::header::CONTENT_TYPE;
+use warp::hyper::http::HeaderValue;
+use warp::{Filter, Rejection, Reply};
+
+use super::{SortBy, extract_index_id_patterns};
+use crate::query_params::query_params;
+use crate::rest_api_response::into_rest_api_response;
+use crate::{BodyFormat, with_arg};
+
+const MAX_MERGE_PAGE_SIZE: u64 = 10_000;
+
+const DEFAULT_INDEX_TIMEOUT: Duration = Duration::from_secs(30);
+
+fn default_merge_query() -> String {
+    "*".to_string()
+}
+
+fn default_merge_page_size() -> u64 {
+    100
+}
+
+#[derive(Debug, Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct MergeStreamQueryParams {
+    /// Query text. The query language is that of tantivy. Matches all the documents by default.
+    #[serde(default = "default_merge_query")]
+    pub query: String,
+    /// Fast field the hits are sorted by, in descending order, or in ascending order if prefixed
+    /// with `-`.
+    pub sort_by: String,
+    /// Maximum number of hits streamed. Streams all the matching hits by default.
+    pub max_hits: Option<u64>,
+    /// Number of hits fetched by each search of an index, up to 10,000.
+    #[serde(default = "default_merge_page_size")]
+    pub page_size: u64,
+    /// Time after which an index not returning its next page of hits is dropped from the merge,
+    /// such as `10s`. Defaults to 30 seconds.
+    pub index_timeout: Option<String>,
+}
+
+/// Index, or index ID pattern, merged into the stream, with its page of hits not streamed yet.
+struct MergedIndex {
+    index_id_pattern: String,
+    search_request: SearchRequest,
+    hits: VecDeque<Hit>,
+    is_exhausted: bool,
+    is_failed: bool,
+}
+
+impl MergedIndex {
+    /// Whether the next page of hits must be fetched before merging the hits of the index.
+    fn needs_page(&self) -> bool {
+        self.hits.is_empty() && !self.is_exhausted
+    }
+}
+
+/// State of a merge, interleaving the pages of hits of the indexes.
+struct StreamMerge {
+    search_service: Arc<dyn SearchService>,
+    merged_indexes: Vec<MergedIndex>,
+    /// Indexes dropped from the merge and their error, not reported yet.
+    failures: Vec<(String, SearchError)>,
+    sort_order: SortOrder,
+    index_timeout: Duration,
+    num_hits_left: u64,
+    is_done: bool,
+}
+
+impl StreamMerge {
+    fn new(
+        index_id_patterns: Vec<String>,
+        merge_params: MergeStreamQueryParams,
+        search_service: Arc<dyn SearchService>,
+    ) -> Result<Self, SearchError> {
+        if merge_params.page_size == 0 || merge_params.page_size > MAX_MERGE_PAGE_SIZE {
+            return Err(SearchError::InvalidArgument(format!(
+                "`page_size` must be between 1 and {MAX_MERGE_PAGE_SIZE}, got {}",
+                merge_params.page_size
+            )));
+        }
+        let sort_by = SortBy::from(merge_params.sort_by);
+        let [sort_field] = &sort_by.sort_fields[..] else {
+            return Err(SearchError::InvalidArgument(
+                "`sort_by` must name exactly one field".to_string(),
+            ));
+        };
+        if sort_field.field_name.starts_with('_') {
+            return Err(SearchError::InvalidArgument(format!(
+                "cannot merge hits sorted by `{}`: expected a fast field",
+                sort_field.field_name
+            )));
+        }
+        let index_timeout = match &merge_params.index_timeout {
+            Some(index_timeout_str) => humantime::parse_duration(index_timeout_str)
+                .ok()
+                .filter(|index_timeout| !index_timeout.is_zero())
+                .ok_or_else(|| {
+                    SearchError::InvalidArgument(format!(
+                        "invalid `index_timeout` `{index_timeout_str}`: expected a positive \
+                         duration such as `10s`"
+                    ))
+                })?,
+            None => DEFAULT_INDEX_TIMEOUT,
+        };
+        let query_ast = query_ast_from_user_text(&merge_params.query, None);
+        let query_ast_json = serde_json::to_string(&query_ast)?;
+
+        // The negative patterns exclude indexes from each of the merged index ID patterns.
+        let (negative_patterns, positive_patterns): (Vec<String>, Vec<String>) = index_id_patterns
+            .into_iter()
+            .partition(|index_id_pattern| index_id_pattern.starts_with('-'));
+
+        let merged_indexes = positive_patterns
+            .into_iter()
+            .map(|index_id_pattern| MergedIndex {
+                search_request: SearchRequest {
+                    index_id_patterns: std::iter::once(index_id_pattern.clone())
+                        .chain(negative_patterns.iter().cloned())
+                        .collect(),
+                    query_ast: query_ast_json.clone(),
+                    max_hits: merge_params.page_size,
+                    sort_fields: vec![sort_field.clone()],
+                    count_hits: CountHits::Underestimate as i32,
+                    ..Default::default()
+                },
+                index_id_pattern,
+                hits: VecDeque::new(),
+                is_exhausted: false,
+                is_failed: false,
+            })
+            .collect();
+        let sort_order = SortOrder::try_from(sort_field.sort_order).unwrap_or(SortOrder::Desc);
+
+        Ok(StreamMerge {
+            search_service,
+            merged_indexes,
+            failures: Vec::new(),
+            sort_order,
+            index_timeout,
+            num_hits_left: merge_params.max_hits.unwrap_or(u64::MAX),
+            is_done: false,
+        })
+    }
+
+    /// Fetches the next page of hits of the indexes whose page is fully streamed, concurrently.
+    /// The indexes failing or timing out are dropped from the merge.
+    async fn fetch_pages(&mut self) {
+        let page_futures = self
+            .merged_indexes
+            .iter()
+            .enumerate()
+            .filter(|(_, merged_index)| merged_index.needs_page())
+            .map(|(index_ord, merged_index)| {
+                let search_service = self.search_service.clone();
+                let search_request = merged_index.search_request.clone();
+                let index_timeout = self.index_timeout;
+
+                async move {
+                    let page_result =
+                        fetch_page(&*search_service, search_request, index_timeout).await;
+                    (index_ord, page_result)
+                }
+            });
+        for (index_ord, page_result) in join_all(page_futures).await {
+            let merged_index = &mut self.merged_indexes[index_ord];
+
+            match page_result {
+                Ok(hits) => {
+                    merged_index.is_exhausted =
+                        (hits.len() as u64) < merged_index.search_request.max_hits;
+                    merged_index.search_request.search_after =
+                        hits.last().and_then(|hit| hit.partial_hit.clone());
+                    merged_index.hits = hits.into();
+                }
+                Err(search_error) => {
+                    merged_index.is_failed = true;
+                    let index_id_pattern = merged_index.index_id_pattern.clone();
+                    self.failures.push((index_id_pattern, search_error));
+                }
+            }
+        }
+        self.merged_indexes
+            .retain(|merged_index| !merged_index.is_failed);
+    }
+
+    /// Returns the index whose next hit comes first, once all the indexes have a page of hits
+    /// to merge. Hits missing the sort field come last.
+    fn next_index_ord(&self) -> Option<usize> {
+        if self.merged_indexes.iter().any(MergedIndex::needs_page) {
+            return None;
+        }
+        self.merged_indexes
+            .iter()
+            .enumerate()
+            .filter_map(|(index_ord, merged_index)| {
+                let hit = merged_index.hits.front()?;
+                let sort_value_opt = hit.partial_hit.as_ref().and_then(PartialHit::sort_value);
+                Some((index_ord, sort_value_opt))
+            })
+            .min_by(|(_, left_opt), (_, right_opt)| self.compare(*left_opt, *right_opt))
+            .map(|(index_ord, _)| index_ord)
+    }
+
+    fn compare(&self, left_opt: Option<SortValue>, right_opt: Option<SortValue>) -> Ordering {
+        match (left_opt, right_opt) {
+            (Some(left), Some(right)) if self.sort_order == SortOrder::Desc => right.cmp(&left),
+            (Some(left), Some(right)) => left.cmp(&right),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        }
+    }
+
+    /// Fetches the pages of hits needed to resume the merge, and merges hits until the page of
+    /// an index is fully streamed. Returns `None` once all the hits are streamed.
+    async fn next_chunk(&mut self) -> Result<Option<Bytes>, SearchError> {
+        if self.is_done {
+            return Ok(None);
+        }
+        self.fetch_pages().await;
+        let mut ndjson = Vec::new();
+
+        for (index_id_pattern, search_error) in self.failures.drain(..) {
+            let error_line = json!({
+                "_error": {"index_id": index_id_pattern, "message": search_error.to_string()},
+            });
+            serde_json::to_writer(&mut ndjson, &error_line)?;
+            ndjson.push(b'\n');
+        }
+        while self.num_hits_left > 0 {
+            let Some(index_ord) = self.next_index_ord() else {
+                break;
+            };
+            let hit = self.merged_indexes[index_ord]
+                .hits
+                .pop_front()
+                .expect("the next index should have a hit");
+            let sort_value = hit
+                .partial_hit
+                .as_ref()
+                .and_then(|partial_hit| partial_hit.sort_value)
+                .map(|sort_by_value| sort_by_value.into_json())
+                .unwrap_or(JsonValue::Null);
+            let merged_hit_line = json!({
+                "index_id": hit.index_id,
+                "sort_value": sort_value,
+                "document": serde_json::from_str::<JsonValue>(&hit.json)?,
+            });
+            serde_json::to_writer(&mut ndjson, &merged_hit_line)?;
+            ndjson.push(b'\n');
+            self.num_hits_left -= 1;
+        }
+        if self.num_hits_left == 0
+            || self
+                .merged_indexes
+                .iter()
+                .all(|merged_index| merged_index.hits.is_empty() && merged_index.is_exhausted)
+        {
+            self.is_done = true;
+        }
+        Ok(Some(Bytes::from(ndjson)))
+    }
+}
+
+async fn fetch_page(
+    search_service: &dyn SearchService,
+    search_request: SearchRequest,
+    index_timeout: Duration,
+) -> Result<Vec<Hit>, SearchError> {
+    let search_response =
+        tokio::time::timeout(index_timeout, search_service.root_search(search_request))
+            .await
+            .map_err(|_| {
+                SearchError::Timeout(format!(
+                    "no page of hits received within {}",
+                    humantime::format_duration(index_timeout)
+                ))
+            })??;
+    if let Some(search_error) = SearchError::from_split_errors(&search_response.failed_splits[..]) {
+        return Err(search_error);
+    }
+    Ok(search_response.hits)
+}
+
+pub(crate) fn merge_stream_handler(
+    search_service: Arc<dyn SearchService>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!(String / "_merge-stream")
+        .and_then(extract_index_id_patterns)
+        .and(warp::get())
+        .and(query_params())
+        .and(with_arg(search_service))
+        .then(merge_stream)
+        .boxed()
+}
+
+#[utoipa::path(
+    get,
+    tag = "Search",
+    path = "/{index_id}/_merge-stream",
+    responses(
+        (status = 200, description = "Successfully streamed the merged hits as NDJSON.")
+    ),
+    params(
+        MergeStreamQueryParams,
+        ("index_id" = String, Path, description = "Comma-separated IDs or ID patterns of the indexes to merge the hits of."),
+    )
+)]
+/// Stream Merged Hits
+///
+/// Streams the hits of several indexes matching the query as NDJSON, interleaved by the value of
+/// the sort field. Each line holds the ID of the index of the hit, its sort value, and its
+/// document. An index failing or timing out is dropped from the merge with an `_error` line.
+async fn merge_stream(
+    index_id_patterns: Vec<String>,
+    merge_params: MergeStreamQueryParams,
+    search_service: Arc<dyn SearchService>,
+) -> warp::reply::Response {
+    let mut stream_merge = match StreamMerge::new(index_id_patterns, merge_params, search_service) {
+        Ok(stream_merge) => stream_merge,
+        Err(search_error) => {
+            return into_rest_api_response::<(), _>(Err(search_error), BodyFormat::node_default())
+                .into_response();
+        }
+    };
+    // The first pages are fetched before responding, so that a merge failing for all its indexes,
+    // for instance because they are missing, is returned with an error status code.
+    stream_merge.fetch_pages().await;
+
+    if stream_merge.merged_indexes.is_empty() && !stream_merge.failures.is_empty() {
+        let (_, search_error) = stream_merge.failures.swap_remove(0);
+        return into_rest_api_response::<(), _>(Err(search_error), BodyFormat::node_default())
+            .into_response();
+    }
+    let chunks = stream::try_unfold(stream_merge, |mut stream_merge| async move {
+        let chunk_opt = stream_merge.next_chunk().await?;
+        Ok::<_, SearchError>(chunk_opt.map(|chunk| (chunk, stream_merge)))
+    });
+    let mut response = warp::reply::Response::new(warp::Body::wrap_stream(chunks));
+    response.headers_mut().insert(
+        CONTENT_TYPE,
+        HeaderValue::from_static("application/x-ndjson"),
+    );
+    response
+}
+
+#[cfg(test)]
+mod tests;
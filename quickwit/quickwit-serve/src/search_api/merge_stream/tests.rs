@@ -0,0 +1,153 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use quickwit_proto::search::{SearchResponse, SortByValue};
+use quickwit_search::MockSearchService;
+
+use super::*;
+
+/// Serves the even timestamps below 30 in `index-even`, and the odd ones in `index-odd`.
+fn mock_search_service_with_indexes() -> Arc<dyn SearchService> {
+    let mut mock_search_service = MockSearchService::new();
+    mock_search_service
+        .expect_root_search()
+        .returning(|search_request| {
+            assert_eq!(search_request.sort_fields[0].field_name, "timestamp");
+            let index_id = search_request.index_id_patterns[0].clone();
+            let excluded_index_id = format!("-{index_id}");
+
+            if search_request.index_id_patterns[1..].contains(&excluded_index_id) {
+                return Ok(SearchResponse::default());
+            }
+            let first_timestamp = match index_id.as_str() {
+                "index-even" => 0,
+                "index-odd" => 1,
+                _ => {
+                    return Err(SearchError::IndexesNotFound {
+                        index_ids: vec![index_id],
+                    });
+                }
+            };
+            let search_after_opt = search_request.search_after.as_ref();
+            let after_timestamp = match search_after_opt.and_then(PartialHit::sort_value) {
+                Some(SortValue::U64(timestamp)) => timestamp,
+                _ => u64::MAX,
+            };
+            let hits = (first_timestamp..30)
+                .step_by(2)
+                .rev()
+                .filter(|timestamp| *timestamp < after_timestamp)
+                .take(search_request.max_hits as usize)
+                .map(|timestamp| Hit {
+                    json: format!(r#"{{"timestamp":{timestamp}}}"#),
+                    partial_hit: Some(PartialHit {
+                        sort_value: Some(SortByValue {
+                            sort_value: Some(SortValue::U64(timestamp)),
+                        }),
+                        ..Default::default()
+                    }),
+                    index_id: index_id.clone(),
+                    ..Default::default()
+                })
+                .collect();
+            Ok(SearchResponse {
+                hits,
+                ..Default::default()
+            })
+        });
+    Arc::new(mock_search_service)
+}
+
+async fn merge_stream_lines(path: &str) -> Vec<JsonValue> {
+    let handler = merge_stream_handler(mock_search_service_with_indexes());
+    let response = warp::test::request().path(path).reply(&handler).await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.headers()[CONTENT_TYPE], "application/x-ndjson");
+    std::str::from_utf8(response.body())
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_merge_stream_interleaves_hits_by_sort_value() {
+    let lines =
+        merge_stream_lines("/index-even,index-odd/_merge-stream?sort_by=timestamp&page_size=4")
+            .await;
+    let timestamps: Vec<u64> = lines
+        .iter()
+        .map(|line| line["document"]["timestamp"].as_u64().unwrap())
+        .collect();
+    assert_eq!(timestamps, (0..30).rev().collect::<Vec<u64>>());
+    assert_eq!(lines[1]["index_id"], "index-even");
+
+    let lines =
+        merge_stream_lines("/index-even,index-odd/_merge-stream?sort_by=timestamp&max_hits=5")
+            .await;
+    assert_eq!(lines.len(), 5);
+    assert_eq!(lines[4]["sort_value"], 25);
+}
+
+#[tokio::test]
+async fn test_merge_stream_excludes_indexes() {
+    let lines = merge_stream_lines(
+        "/index-even,index-odd,-index-odd/_merge-stream?sort_by=timestamp&page_size=4",
+    )
+    .await;
+    let timestamps: Vec<u64> = lines
+        .iter()
+        .map(|line| line["sort_value"].as_u64().unwrap())
+        .collect();
+    assert_eq!(timestamps, (0..30).step_by(2).rev().collect::<Vec<u64>>());
+}
+
+#[tokio::test]
+async fn test_merge_stream_drops_failing_index() {
+    let lines =
+        merge_stream_lines("/index-odd,index-missing/_merge-stream?sort_by=timestamp").await;
+    assert_eq!(lines[0]["_error"]["index_id"], "index-missing");
+
+    let timestamps: Vec<u64> = lines[1..]
+        .iter()
+        .map(|line| line["sort_value"].as_u64().unwrap())
+        .collect();
+    assert_eq!(timestamps, (1..30).step_by(2).rev().collect::<Vec<u64>>());
+
+    // A merge failing for all its indexes responds with an error status code.
+    let handler = merge_stream_handler(mock_search_service_with_indexes());
+    let response = warp::test::request()
+        .path("/index-missing/_merge-stream?sort_by=timestamp")
+        .reply(&handler)
+        .await;
+    assert_eq!(response.status(), 404);
+}
+
+#[tokio::test]
+async fn test_merge_stream_invalid_params() {
+    let handler = merge_stream_handler(mock_search_service_with_indexes());
+
+    for query_string in [
+        "sort_by=timestamp,id",
+        "sort_by=_score",
+        "sort_by=timestamp&page_size=0",
+        "sort_by=timestamp&index_timeout=soon",
+    ] {
+        let response = warp::test::request()
+            .path(&format!("/index-even/_merge-stream?{query_string}"))
+            .reply(&handler)
+            .await;
+        assert_eq!(response.status(), 400, "{query_string}");
+    }
+}
@@ -12,10 +12,36 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod aggregations_csv;
+mod aggregations_ndjson;
+mod batch_search;
+mod clear_cache;
+mod count;
+mod distinct_values;
+mod export;
+mod export_continuation;
+mod get_document;
 mod grpc_adapter;
+mod jsonp;
+mod list_aggregations;
+mod merge_stream;
 mod rest_handler;
 
+pub(crate) use self::batch_search::batch_search_handler;
+pub(crate) use self::clear_cache::clear_cache_handler;
+pub(crate) use self::count::count_handler;
+pub(crate) use self::distinct_values::distinct_values_handler;
+pub(crate) use self::export::export_handler;
+#[cfg(test)]
+pub(crate) use self::get_document::tests::{
+    mock_metastore_with_id_field, mock_search_service_with_doc,
+};
+pub(crate) use self::get_document::{
+    GetDocumentError, GetDocumentQueryParams, get_document_by_id, get_document_handler,
+};
 pub use self::grpc_adapter::GrpcSearchAdapter;
+pub(crate) use self::list_aggregations::list_aggregations_handler;
+pub(crate) use self::merge_stream::merge_stream_handler;
 pub use self::rest_handler::{
     SearchApi, SearchRequestQueryString, SortBy, search_get_handler, search_plan_get_handler,
     search_plan_post_handler, search_post_handler, search_request_from_api_request,
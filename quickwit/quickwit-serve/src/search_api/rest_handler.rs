@@ -12,20 +12,35 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::sync::Arc;
+use std::time::Instant;
 
 use percent_encoding::percent_decode_str;
-use quickwit_config::validate_index_id_pattern;
-use quickwit_proto::search::{CountHits, SortField, SortOrder};
-use quickwit_query::query_ast::query_ast_from_user_text;
-use quickwit_search::{SearchError, SearchPlanResponseRest, SearchResponseRest, SearchService};
+use quickwit_config::{MissingFields, validate_index_id_pattern};
+use quickwit_proto::search::{CountHits, ReadPreference, ScoreThreshold, SortField, SortOrder};
+use quickwit_query::BooleanOperand;
+use quickwit_query::query_ast::{QueryAst, UserInputQuery};
+use quickwit_search::{
+    AggregationResults, ColumnarHits, SORT_SCRIPT_FIELD_NAME, SearchError, SearchPlanResponseRest,
+    SearchPlanSplit, SearchResolutionRest, SearchResponseRest, SearchService, SearchTimings,
+    SplitsSummary,
+};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::Value as JsonValue;
 use tracing::info;
-use warp::{Filter, Rejection};
+use warp::{Filter, Rejection, Reply};
 
-use crate::rest_api_response::into_rest_api_response;
+use super::aggregations_csv::aggregations_to_csv;
+use super::aggregations_ndjson::{aggregations_to_ndjson, into_aggregations_response};
+use super::jsonp::{into_jsonp_response, is_json_response, validate_jsonp_callback};
+use crate::json_projection::{FieldsProjection, project_response};
+use crate::query_history::query_history;
+use crate::query_params::query_params;
+use crate::recent_errors::{IndexErrorType, recent_errors};
+use crate::rest_api_response::{into_rest_api_response, into_rest_api_response_with_index_headers};
+use crate::result_bytes::MeasureResultBytes;
 use crate::simple_list::{from_simple_list, to_simple_list};
 use crate::{BodyFormat, with_arg};
 
@@ -36,15 +51,39 @@ use crate::{BodyFormat, with_arg};
         search_post_handler,
         search_plan_get_handler,
         search_plan_post_handler,
+        super::batch_search::batch_search,
+        super::clear_cache::clear_cache,
+        super::count::count,
+        super::distinct_values::distinct_values,
+        super::export::export,
+        super::get_document::get_document,
+        super::list_aggregations::list_aggregations,
+        super::merge_stream::merge_stream,
     ),
     components(schemas(
+        super::batch_search::BatchSearchRequest,
+        super::batch_search::BatchSearchResponse,
+        super::batch_search::VariantSearchResponse,
         BodyFormat,
+        ColumnarHits,
+        super::count::CountResponse,
+        super::distinct_values::DistinctValuesResponse,
+        super::list_aggregations::AggregationKind,
+        super::list_aggregations::AggregationParam,
+        super::list_aggregations::AggregationParamType,
+        super::list_aggregations::AggregationType,
+        super::list_aggregations::AggregationsResponse,
+        ReadPreference,
         SearchRequestQueryString,
+        SearchResolutionRest,
         SearchResponseRest,
         SearchPlanResponseRest,
+        SearchPlanSplit,
         SortBy,
         SortField,
         SortOrder,
+        SplitsSummary,
+        SearchTimings,
     ),)
 )]
 pub struct SearchApi;
@@ -75,6 +114,18 @@ pub(crate) async fn extract_index_id_patterns(
         index_id_patterns.push(index_id_pattern.to_string());
     }
     assert!(!index_id_patterns.is_empty());
+
+    // Negative patterns exclude indexes from the ones selected by the other patterns.
+    if index_id_patterns
+        .iter()
+        .all(|index_id_pattern| index_id_pattern.starts_with('-'))
+    {
+        let message = format!(
+            "index ID patterns `{percent_decoded_comma_separated_index_id_patterns}` only exclude \
+             indexes: at least one pattern must not start with `-`"
+        );
+        return Err(crate::rest::InvalidArgument(message).into());
+    }
     Ok(index_id_patterns)
 }
 
@@ -111,6 +162,7 @@ impl From<String> for SortBy {
                 field_name,
                 sort_order: sort_order as i32,
                 sort_datetime_format: None,
+                script: None,
             };
             sort_fields.push(sort_field);
         }
@@ -146,7 +198,7 @@ fn default_max_hits() -> u64 {
 /// This struct represents the QueryString passed to
 /// the rest API.
 #[derive(
-    Debug, Default, Eq, PartialEq, Serialize, Deserialize, utoipa::IntoParams, utoipa::ToSchema,
+    Debug, Default, PartialEq, Serialize, Deserialize, utoipa::IntoParams, utoipa::ToSchema,
 )]
 #[into_params(parameter_in = Query)]
 #[serde(deny_unknown_fields)]
@@ -181,9 +233,11 @@ pub struct SearchRequestQueryString {
     /// This timestamp is expressed in seconds.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub end_timestamp: Option<i64>,
-    /// Maximum number of hits to return (by default 20).
-    #[serde(default = "default_max_hits")]
-    pub max_hits: u64,
+    /// Maximum number of hits to return (by default 20, or the `default_max_hits` search setting
+    /// of the targeted indexes).
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_hits: Option<u64>,
     /// First hit to return. Together with num_hits, this parameter
     /// can be used for pagination.
     ///
@@ -192,7 +246,7 @@ pub struct SearchRequestQueryString {
     #[serde(default)] // Default to 0. (We are 0-indexed)
     pub start_offset: u64,
     /// The output format.
-    #[serde(default)]
+    #[serde(default = "BodyFormat::node_default")]
     pub format: BodyFormat,
     /// Specifies how documents are sorted.
     #[serde(alias = "sort_by_field")]
@@ -201,6 +255,27 @@ pub struct SearchRequestQueryString {
     #[serde(skip_serializing_if = "SortBy::is_empty")]
     #[param(value_type = String)]
     pub sort_by: SortBy,
+    /// Arithmetic expression over numeric fast fields, e.g. `popularity * 0.7 + recency * 0.3`,
+    /// used to sort the hits when `sort_by` contains `_script`.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort_by_script: Option<String>,
+    /// If set, the hits scoring below this threshold are discarded before being sorted and
+    /// paginated. They are not counted in `num_hits` either.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_score: Option<f32>,
+    /// Similarity function scoring the hits instead of the default BM25: `bm25` with optional
+    /// `k1` and `b` parameters, e.g. `bm25(k1=1.5,b=0.3)`, or `tfidf`.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scoring: Option<String>,
+    /// If set, overrides the `missing_fields` search setting of the targeted indexes: `omit` to
+    /// omit the fields of the doc mapping absent from the documents of the hits, or `as_null` to
+    /// render them as `null`.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub missing_fields: Option<MissingFields>,
     #[param(value_type = bool)]
     #[schema(value_type = bool)]
     #[serde(with = "count_hits_from_bool")]
@@ -210,6 +285,116 @@ pub struct SearchRequestQueryString {
     #[schema(value_type = bool)]
     #[serde(default)]
     pub allow_failed_splits: bool,
+    /// If set, the search of a split taking longer than this duration (e.g. `500ms`) is abandoned
+    /// and the split is listed in the `_splits` section of the response instead of failing the
+    /// whole search. Requires `allow_failed_splits`.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub per_split_timeout: Option<String>,
+    /// If set, overrides the timeout of the search (e.g. `2m`), up to the maximum request timeout
+    /// of the searchers. Overridden by the `X-Search-Timeout` header.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<String>,
+    /// If set, the JSON response is wrapped in a call to the JavaScript function with this name
+    /// (JSONP) and served as `application/javascript`. Only honored by the GET endpoint, and only
+    /// if `rest.enable_jsonp` is set in the node config.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub callback: Option<String>,
+    /// Comma-separated list of JSONPath-style paths, such as `num_hits,hits.title`. If set, the
+    /// JSON response is trimmed down to the subtrees designated by these paths.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fields: Option<String>,
+    /// If set, only the aggregation results are returned, as newline-delimited JSON with one
+    /// line per bucket.
+    #[param(value_type = bool)]
+    #[schema(value_type = bool)]
+    #[serde(default)]
+    pub stream_aggs: bool,
+    /// If set, only the aggregation results are returned, as CSV with one column per bucket
+    /// aggregation and metric.
+    #[param(value_type = bool)]
+    #[schema(value_type = bool)]
+    #[serde(default)]
+    pub csv_aggs: bool,
+    /// If set, the response includes the estimated cost of the query.
+    #[param(value_type = bool)]
+    #[schema(value_type = bool)]
+    #[serde(default)]
+    pub explain: bool,
+    /// If set, each hit is returned with its score and its raw score, computed without the
+    /// boosts of the query.
+    #[param(value_type = bool)]
+    #[schema(value_type = bool)]
+    #[serde(default)]
+    pub explain_scores: bool,
+    /// Operator combining the clauses of the query that do not specify one, `AND` or `OR` (by
+    /// default `AND`, or the `default_operator` search setting of the targeted indexes).
+    #[param(value_type = Option<String>)]
+    #[schema(value_type = Option<String>)]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_operator: Option<BooleanOperand>,
+    /// If set, each hit is returned with its score even when the hits are sorted by a field.
+    /// Otherwise, the scores are not computed, saving the cost of scoring the hits.
+    #[param(value_type = bool)]
+    #[schema(value_type = bool)]
+    #[serde(default)]
+    pub track_scores: bool,
+    /// If set, the search is not rejected when its index patterns match more indexes than the
+    /// `max_indexes_per_search` limit of the searchers. Requires an admin token.
+    #[param(value_type = bool)]
+    #[schema(value_type = bool)]
+    #[serde(default)]
+    pub ignore_max_indexes_per_search: bool,
+    /// If set, the response describes how the request was resolved: the indexes matched by the
+    /// index ID patterns, the query defaults of the indexes the request inherited, and the final
+    /// query.
+    #[param(value_type = bool)]
+    #[schema(value_type = bool)]
+    #[serde(default)]
+    pub explain_resolution: bool,
+    /// If set, the search is not served from the partial result and predicate caches of the
+    /// searchers, which read the splits instead.
+    #[param(value_type = bool)]
+    #[schema(value_type = bool)]
+    #[serde(default)]
+    pub fresh: bool,
+    /// If set, the response includes the time spent executing the search and serializing its
+    /// response, in addition to `elapsed_time_micros`.
+    #[param(value_type = bool)]
+    #[schema(value_type = bool)]
+    #[serde(default)]
+    pub detailed_timings: bool,
+    /// If set, the hits are returned as columns in `columnar_hits`, with the names of their
+    /// fields listed once, instead of as objects in `hits`.
+    #[param(value_type = bool)]
+    #[schema(value_type = bool)]
+    #[serde(default)]
+    pub columnar_hits: bool,
+    /// If set, the size of the response body, as sent to the client, is returned in the
+    /// `X-Quickwit-Result-Bytes` header, for metering the egress of the searches.
+    #[param(value_type = bool)]
+    #[schema(value_type = bool)]
+    #[serde(default)]
+    pub result_bytes: bool,
+    /// If set, the response includes the size of each hit, serialized as compact JSON, in
+    /// `hit_bytes`.
+    #[param(value_type = bool)]
+    #[schema(value_type = bool)]
+    #[serde(default)]
+    pub hit_bytes: bool,
+    /// Whether the splits are listed from the primary metastore (`freshest`) or from its read
+    /// replica, if any (`fastest`). Overridden by the `X-Read-Preference` header.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "is_fastest")]
+    pub read_preference: ReadPreference,
+}
+
+fn is_fastest(read_preference: &ReadPreference) -> bool {
+    *read_preference == ReadPreference::Fastest
 }
 
 mod count_hits_from_bool {
@@ -247,37 +432,147 @@ pub fn search_request_from_api_request(
     // The query ast below may still contain user input query. The actual
     // parsing of the user query will happen in the root service, and might require
     // the user of the docmapper default fields (which we do not have at this point).
-    let query_ast = query_ast_from_user_text(&search_request.query, search_request.search_fields);
+    let per_split_timeout_millis = parse_per_split_timeout(
+        search_request.per_split_timeout.as_deref(),
+        search_request.allow_failed_splits,
+    )?;
+    let timeout_millis = parse_search_timeout(search_request.timeout.as_deref())?;
+    let query_ast: QueryAst = UserInputQuery {
+        user_text: search_request.query,
+        default_fields: search_request.search_fields,
+        default_operator: search_request
+            .default_operator
+            .unwrap_or(BooleanOperand::And),
+        lenient: false,
+    }
+    .into();
     let query_ast_json = serde_json::to_string(&query_ast)?;
+    let sort_fields = attach_sort_script(
+        search_request.sort_by.sort_fields,
+        search_request.sort_by_script,
+    )?;
     let search_request = quickwit_proto::search::SearchRequest {
         index_id_patterns,
         query_ast: query_ast_json,
         snippet_fields: search_request.snippet_fields.unwrap_or_default(),
         start_timestamp: search_request.start_timestamp,
         end_timestamp: search_request.end_timestamp,
-        max_hits: search_request.max_hits,
+        max_hits: search_request.max_hits.unwrap_or_else(default_max_hits),
         start_offset: search_request.start_offset,
         aggregation_request: search_request
             .aggs
             .map(|agg| serde_json::to_string(&agg).expect("could not serialize JsonValue")),
-        sort_fields: search_request.sort_by.sort_fields,
+        sort_fields,
         scroll_ttl_secs: None,
         search_after: None,
         count_hits: search_request.count_all.into(),
         ignore_missing_indexes: false,
         skip_aggregation_finalization: false,
+        skip_fetch_docs: false,
+        min_score: search_request
+            .min_score
+            .map(|value| ScoreThreshold { value }),
+        explain: search_request.explain,
+        read_preference: search_request.read_preference as i32,
+        explain_scores: search_request.explain_scores,
+        per_split_timeout_millis,
+        timeout_millis,
+        // Set by the root from the search settings of the targeted indexes.
+        max_aggregation_buckets: None,
+        scoring: search_request.scoring,
+        missing_fields_as_null: search_request
+            .missing_fields
+            .map(|missing_fields| missing_fields == MissingFields::AsNull),
+        bypass_cache: search_request.fresh,
+        track_scores: search_request.track_scores,
+        inherit_default_operator: search_request.default_operator.is_none(),
+        inherit_default_max_hits: search_request.max_hits.is_none(),
+        ignore_max_indexes_per_search: search_request.ignore_max_indexes_per_search,
+        explain_resolution: search_request.explain_resolution,
     };
     Ok(search_request)
 }
 
+/// Parses the timeout of the search request into milliseconds. It is bounded by the searchers.
+fn parse_search_timeout(timeout_opt: Option<&str>) -> Result<Option<u64>, SearchError> {
+    let Some(timeout_str) = timeout_opt else {
+        return Ok(None);
+    };
+    let timeout = humantime::parse_duration(timeout_str)
+        .ok()
+        .filter(|timeout| !timeout.is_zero())
+        .ok_or_else(|| {
+            SearchError::InvalidArgument(format!(
+                "invalid search timeout `{timeout_str}`: expected a positive duration such as \
+                 `30s` or `2m`"
+            ))
+        })?;
+    Ok(Some(timeout.as_millis() as u64))
+}
+
+/// Parses the per-split timeout of the search request into milliseconds. Timed out splits are
+/// reported as failed splits, so the timeout is only accepted along with `allow_failed_splits`.
+fn parse_per_split_timeout(
+    per_split_timeout_opt: Option<&str>,
+    allow_failed_splits: bool,
+) -> Result<Option<u64>, SearchError> {
+    let Some(per_split_timeout_str) = per_split_timeout_opt else {
+        return Ok(None);
+    };
+    if !allow_failed_splits {
+        return Err(SearchError::InvalidArgument(
+            "`per_split_timeout` requires `allow_failed_splits` to be set".to_string(),
+        ));
+    }
+    let per_split_timeout = humantime::parse_duration(per_split_timeout_str)
+        .ok()
+        .filter(|per_split_timeout| !per_split_timeout.is_zero())
+        .ok_or_else(|| {
+            SearchError::InvalidArgument(format!(
+                "invalid `per_split_timeout` `{per_split_timeout_str}`: expected a positive \
+                 duration such as `500ms` or `2s`"
+            ))
+        })?;
+    Ok(Some(per_split_timeout.as_millis() as u64))
+}
+
+/// Attaches the sort script to the `_script` sort field.
+fn attach_sort_script(
+    mut sort_fields: Vec<SortField>,
+    sort_by_script_opt: Option<String>,
+) -> Result<Vec<SortField>, SearchError> {
+    let Some(sort_by_script) = sort_by_script_opt else {
+        return Ok(sort_fields);
+    };
+    let Some(script_sort_field) = sort_fields
+        .iter_mut()
+        .find(|sort_field| sort_field.field_name == SORT_SCRIPT_FIELD_NAME)
+    else {
+        return Err(SearchError::InvalidArgument(format!(
+            "`sort_by_script` requires `sort_by` to contain `{SORT_SCRIPT_FIELD_NAME}`"
+        )));
+    };
+    script_sort_field.script = Some(sort_by_script);
+    Ok(sort_fields)
+}
+
+/// Runs a search and returns its response along with the response headers declared by the targeted
+/// indexes.
 async fn search_endpoint(
     index_id_patterns: Vec<String>,
     search_request: SearchRequestQueryString,
     search_service: &dyn SearchService,
-) -> Result<SearchResponseRest, SearchError> {
+) -> Result<(SearchResponseRest, HashMap<String, String>), SearchError> {
     let allow_failed_splits = search_request.allow_failed_splits;
-    let search_request = search_request_from_api_request(index_id_patterns, search_request)?;
-    let search_response =
+    let detailed_timings = search_request.detailed_timings;
+    let columnar_hits = search_request.columnar_hits;
+    let hit_bytes = search_request.hit_bytes;
+    let body_format = search_request.format;
+    let search_request =
+        search_request_from_api_request(index_id_patterns.clone(), search_request)?;
+    let query_ast_json = search_request.query_ast.clone();
+    let query_execution_start = Instant::now();
+    let search_response_result =
         search_service
             .root_search(search_request)
             .await
@@ -289,9 +584,97 @@ async fn search_endpoint(
                     return Err(search_error);
                 }
                 Ok(search_response)
-            })?;
-    let search_response_rest = SearchResponseRest::try_from(search_response)?;
-    Ok(search_response_rest)
+            });
+    if let Err(search_error) = &search_response_result {
+        recent_errors().record_error(
+            index_id_patterns.iter().map(String::as_str),
+            IndexErrorType::Search,
+            search_error,
+        );
+    }
+    let mut search_response = search_response_result?;
+    let query_execution_elapsed = query_execution_start.elapsed();
+    query_history().record(
+        &search_response.searched_index_ids,
+        &query_ast_json,
+        search_response.num_hits,
+        query_execution_elapsed,
+    );
+    let serialization_start = Instant::now();
+    let index_response_headers = std::mem::take(&mut search_response.response_headers);
+    let mut search_response_rest = SearchResponseRest::try_from(search_response)?;
+
+    if hit_bytes {
+        let hit_sizes = search_response_rest
+            .hits
+            .iter()
+            .map(|hit| serde_json::to_vec(hit).map(|hit_json| hit_json.len() as u64))
+            .collect::<Result<Vec<u64>, _>>()
+            .map_err(|error| SearchError::Internal(format!("failed to serialize hit: {error}")))?;
+        search_response_rest.hit_bytes = Some(hit_sizes);
+    }
+    if columnar_hits {
+        let hits = std::mem::take(&mut search_response_rest.hits);
+        search_response_rest.columnar_hits = Some(ColumnarHits::from_hits(hits)?);
+    }
+    if detailed_timings {
+        // The body embeds its own serialization time, so the response is serialized once more,
+        // without the timings, to measure it.
+        let _ = body_format.result_to_vec::<_, ()>(&Ok(&search_response_rest));
+        search_response_rest.timings = Some(SearchTimings {
+            query_execution_micros: query_execution_elapsed.as_micros() as u64,
+            serialization_micros: serialization_start.elapsed().as_micros() as u64,
+        });
+    }
+    Ok((search_response_rest, index_response_headers))
+}
+
+/// Extracts the read preference from the `X-Read-Preference` header, if any.
+fn read_preference_header()
+-> impl Filter<Extract = (Option<ReadPreference>,), Error = Rejection> + Clone {
+    warp::header::optional::<String>("x-read-preference").and_then(
+        |header_value_opt: Option<String>| async move {
+            let Some(header_value) = header_value_opt else {
+                return Ok(None);
+            };
+            let read_preference =
+                ReadPreference::from_str_name(&header_value.trim().to_ascii_uppercase())
+                    .ok_or_else(|| {
+                        let message = format!(
+                            "invalid `X-Read-Preference` header `{header_value}`: expected \
+                             `freshest` or `fastest`"
+                        );
+                        crate::rest::InvalidArgument(message)
+                    })?;
+            Ok::<_, Rejection>(Some(read_preference))
+        },
+    )
+}
+
+/// Overrides the read preference of the search request with the one of the
+/// `X-Read-Preference` header, if any.
+fn with_read_preference_header(
+    index_id_patterns: Vec<String>,
+    mut search_request: SearchRequestQueryString,
+    read_preference_opt: Option<ReadPreference>,
+) -> (Vec<String>, SearchRequestQueryString) {
+    if let Some(read_preference) = read_preference_opt {
+        search_request.read_preference = read_preference;
+    }
+    (index_id_patterns, search_request)
+}
+
+/// Overrides the timeout of the search request with the one of the `X-Search-Timeout` header,
+/// if any.
+fn with_search_timeout_header(
+    index_id_patterns: Vec<String>,
+    mut search_request: SearchRequestQueryString,
+    search_timeout_opt: Option<String>,
+) -> (Vec<String>, SearchRequestQueryString) {
+    if let Some(search_timeout) = search_timeout_opt {
+        search_request.timeout = Some(search_timeout);
+    }
+    (index_id_patterns, search_request)
 }
 
 fn search_get_filter()
@@ -299,7 +682,13 @@ fn search_get_filter()
     warp::path!(String / "search")
         .and_then(extract_index_id_patterns)
         .and(warp::get())
-        .and(warp::query())
+        .and(query_params())
+        .and(read_preference_header())
+        .map(with_read_preference_header)
+        .untuple_one()
+        .and(warp::header::optional::<String>("x-search-timeout"))
+        .map(with_search_timeout_header)
+        .untuple_one()
 }
 
 fn search_post_filter()
@@ -309,6 +698,12 @@ fn search_post_filter()
         .and(warp::post())
         .and(warp::body::content_length_limit(1024 * 1024))
         .and(warp::body::json())
+        .and(read_preference_header())
+        .map(with_read_preference_header)
+        .untuple_one()
+        .and(warp::header::optional::<String>("x-search-timeout"))
+        .map(with_search_timeout_header)
+        .untuple_one()
 }
 
 fn search_plan_get_filter()
@@ -316,7 +711,10 @@ fn search_plan_get_filter()
     warp::path!(String / "search-plan")
         .and_then(extract_index_id_patterns)
         .and(warp::get())
-        .and(warp::query())
+        .and(query_params())
+        .and(read_preference_header())
+        .map(with_read_preference_header)
+        .untuple_one()
 }
 
 fn search_plan_post_filter()
@@ -326,17 +724,108 @@ fn search_plan_post_filter()
         .and(warp::post())
         .and(warp::body::content_length_limit(1024 * 1024))
         .and(warp::body::json())
+        .and(read_preference_header())
+        .map(with_read_preference_header)
+        .untuple_one()
 }
 
 async fn search(
     index_id_patterns: Vec<String>,
-    search_request: SearchRequestQueryString,
+    mut search_request: SearchRequestQueryString,
     search_service: Arc<dyn SearchService>,
-) -> impl warp::Reply {
+) -> warp::reply::Response {
     info!(request =? search_request, "search");
     let body_format = search_request.format;
+
+    if search_request.stream_aggs && search_request.csv_aggs {
+        let search_error = SearchError::InvalidArgument(
+            "`stream_aggs` and `csv_aggs` are mutually exclusive".to_string(),
+        );
+        return into_rest_api_response::<(), _>(Err(search_error), body_format).into_response();
+    }
+    let projection_opt = match search_request
+        .fields
+        .take()
+        .map(|fields| FieldsProjection::parse(&fields))
+        .transpose()
+    {
+        Ok(projection_opt) => projection_opt,
+        Err(error_message) => {
+            let search_error = SearchError::InvalidArgument(error_message);
+            return into_rest_api_response::<(), _>(Err(search_error), body_format).into_response();
+        }
+    };
+    if search_request.stream_aggs {
+        let ndjson_result =
+            stream_aggs_endpoint(index_id_patterns, search_request, &*search_service).await;
+        return into_aggregations_response(ndjson_result, "application/x-ndjson", body_format);
+    }
+    if search_request.csv_aggs {
+        let csv_result =
+            csv_aggs_endpoint(index_id_patterns, search_request, &*search_service).await;
+        return into_aggregations_response(csv_result, "text/csv", body_format);
+    }
+    let result_bytes = search_request.result_bytes;
     let result = search_endpoint(index_id_patterns, search_request, &*search_service).await;
-    into_rest_api_response(result, body_format)
+    let mut response = into_rest_api_response_with_index_headers(result, body_format);
+
+    if result_bytes {
+        response.extensions_mut().insert(MeasureResultBytes);
+    }
+    if let Some(projection) = projection_opt {
+        response = project_response(response, &projection, body_format).await;
+    }
+    response
+}
+
+async fn stream_aggs_endpoint(
+    index_id_patterns: Vec<String>,
+    search_request: SearchRequestQueryString,
+    search_service: &dyn SearchService,
+) -> Result<(warp::Body, HashMap<String, String>), SearchError> {
+    let (aggregations_opt, index_response_headers) = aggs_only_search(
+        index_id_patterns,
+        search_request,
+        search_service,
+        "stream_aggs",
+    )
+    .await?;
+    let ndjson = aggregations_to_ndjson(aggregations_opt);
+    Ok((ndjson, index_response_headers))
+}
+
+async fn csv_aggs_endpoint(
+    index_id_patterns: Vec<String>,
+    search_request: SearchRequestQueryString,
+    search_service: &dyn SearchService,
+) -> Result<(warp::Body, HashMap<String, String>), SearchError> {
+    let (aggregations_opt, index_response_headers) = aggs_only_search(
+        index_id_patterns,
+        search_request,
+        search_service,
+        "csv_aggs",
+    )
+    .await?;
+    let csv = aggregations_to_csv(aggregations_opt.as_ref())?;
+    Ok((csv.into(), index_response_headers))
+}
+
+/// Runs a search returning only its aggregation results, on behalf of the `option_name` option.
+async fn aggs_only_search(
+    index_id_patterns: Vec<String>,
+    mut search_request: SearchRequestQueryString,
+    search_service: &dyn SearchService,
+    option_name: &str,
+) -> Result<(Option<AggregationResults>, HashMap<String, String>), SearchError> {
+    if search_request.aggs.is_none() {
+        return Err(SearchError::InvalidArgument(format!(
+            "`{option_name}` requires an aggregation request"
+        )));
+    }
+    search_request.max_hits = Some(0);
+    let (search_response_rest, index_response_headers) =
+        search_endpoint(index_id_patterns, search_request, search_service).await?;
+    Ok((search_response_rest.aggregations, index_response_headers))
 }
 
 async fn search_plan(
@@ -372,10 +861,42 @@ async fn search_plan(
 /// Parses the search request from the request query string.
 pub fn search_get_handler(
     search_service: Arc<dyn SearchService>,
+    enable_jsonp: bool,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
     search_get_filter()
         .and(with_arg(search_service))
-        .then(search)
+        .and(with_arg(enable_jsonp))
+        .then(search_get)
+}
+
+/// Serves a GET search request, wrapping its JSON response in the JSONP callback, if any.
+async fn search_get(
+    index_id_patterns: Vec<String>,
+    mut search_request: SearchRequestQueryString,
+    search_service: Arc<dyn SearchService>,
+    enable_jsonp: bool,
+) -> warp::reply::Response {
+    let Some(callback) = search_request.callback.take() else {
+        return search(index_id_patterns, search_request, search_service).await;
+    };
+    let body_format = search_request.format;
+
+    if !enable_jsonp {
+        let search_error = SearchError::InvalidArgument(
+            "the `callback` parameter requires JSONP to be enabled with `rest.enable_jsonp`"
+                .to_string(),
+        );
+        return into_rest_api_response::<(), _>(Err(search_error), body_format).into_response();
+    }
+    if let Err(search_error) = validate_jsonp_callback(&callback) {
+        return into_rest_api_response::<(), _>(Err(search_error), body_format).into_response();
+    }
+    let response = search(index_id_patterns, search_request, search_service).await;
+
+    if !is_json_response(&response) {
+        return response;
+    }
+    into_jsonp_response(response, &callback).await
 }
 
 #[utoipa::path(
@@ -452,18 +973,23 @@ pub fn search_plan_post_handler(
 #[cfg(test)]
 mod tests {
     use assert_json_diff::{assert_json_eq, assert_json_include};
+    use http_body_util::BodyExt;
     use mockall::predicate;
+    use quickwit_proto::search::{Hit, QueryCostEstimate, SplitSearchError};
     use quickwit_search::{MockSearchService, SearchError};
     use serde_json::{Value as JsonValue, json};
+    use tower::{Layer, ServiceExt};
+    use warp::hyper::http;
 
     use super::*;
     use crate::recover_fn;
+    use crate::result_bytes::ResultBytesLayer;
 
     fn search_handler(
         mock_search_service: MockSearchService,
     ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
         let mock_search_service_in_arc = Arc::new(mock_search_service);
-        search_get_handler(mock_search_service_in_arc.clone())
+        search_get_handler(mock_search_service_in_arc.clone(), false)
             .or(search_post_handler(mock_search_service_in_arc.clone()))
             .or(search_plan_get_handler(mock_search_service_in_arc.clone()))
             .or(search_plan_post_handler(mock_search_service_in_arc.clone()))
@@ -487,6 +1013,15 @@ mod tests {
                 .unwrap(),
             vec!["my-index-1".to_string(), "my-index-*".to_string()]
         );
+        assert_eq!(
+            extract_index_id_patterns("logs-*,-logs-debug-*".to_string())
+                .await
+                .unwrap(),
+            vec!["logs-*".to_string(), "-logs-debug-*".to_string()]
+        );
+        extract_index_id_patterns("-logs-debug-*".to_string())
+            .await
+            .unwrap_err();
         extract_index_id_patterns("".to_string()).await.unwrap_err();
         extract_index_id_patterns(" ".to_string())
             .await
@@ -502,6 +1037,15 @@ mod tests {
             elapsed_time_micros: 0u64,
             errors: Vec::new(),
             aggregations: None,
+            cost_estimate: None,
+            resolution: None,
+            searched_index_ids: Vec::new(),
+            splits: None,
+            truncated: false,
+            truncation_reason: None,
+            timings: None,
+            columnar_hits: None,
+            hit_bytes: None,
         };
         let search_response_json: JsonValue = serde_json::to_value(search_response)?;
         let expected_search_response_json: JsonValue = json!({
@@ -534,7 +1078,7 @@ mod tests {
                 query: "*".to_string(),
                 search_fields: None,
                 start_timestamp: None,
-                max_hits: 10,
+                max_hits: Some(10),
                 format: BodyFormat::default(),
                 sort_by: SortBy::default(),
                 aggs: Some(json!({"range":[]})),
@@ -569,7 +1113,7 @@ mod tests {
                 query: "*".to_string(),
                 search_fields: None,
                 start_timestamp: None,
-                max_hits: 10,
+                max_hits: Some(10),
                 format: BodyFormat::default(),
                 sort_by: SortBy::default(),
                 aggs: Some(json!({"range":[]})),
@@ -618,7 +1162,7 @@ mod tests {
                 search_fields: None,
                 start_timestamp: None,
                 end_timestamp: Some(1450720000),
-                max_hits: 10,
+                max_hits: Some(10),
                 start_offset: 22,
                 format: BodyFormat::default(),
                 sort_by: SortBy::default(),
@@ -642,7 +1186,7 @@ mod tests {
                 query: "*".to_string(),
                 format: BodyFormat::default(),
                 sort_by: SortBy::default(),
-                max_hits: 20,
+                max_hits: None,
                 count_all: CountHits::CountAll,
                 ..Default::default()
             }
@@ -660,7 +1204,7 @@ mod tests {
                 query: "*".to_string(),
                 format: BodyFormat::default(),
                 sort_by: SortBy::default(),
-                max_hits: 20,
+                max_hits: None,
                 count_all: CountHits::Underestimate,
                 ..Default::default()
             }
@@ -686,7 +1230,7 @@ mod tests {
                 search_fields: Some(vec!["title".to_string(), "body".to_string()]),
                 start_timestamp: None,
                 end_timestamp: Some(1450720000),
-                max_hits: 20,
+                max_hits: None,
                 start_offset: 0,
                 format: BodyFormat::default(),
                 sort_by: SortBy::default(),
@@ -710,7 +1254,7 @@ mod tests {
                 query: "*".to_string(),
                 start_timestamp: None,
                 end_timestamp: None,
-                max_hits: 20,
+                max_hits: None,
                 start_offset: 0,
                 format: BodyFormat::Json,
                 search_fields: None,
@@ -731,6 +1275,7 @@ mod tests {
                     field_name: "field1".to_string(),
                     sort_order: SortOrder::Desc as i32,
                     sort_datetime_format: None,
+                    script: None,
                 }],
             ),
             (
@@ -739,6 +1284,7 @@ mod tests {
                     field_name: "field1".to_string(),
                     sort_order: SortOrder::Desc as i32,
                     sort_datetime_format: None,
+                    script: None,
                 }],
             ),
             (
@@ -747,6 +1293,7 @@ mod tests {
                     field_name: "field1".to_string(),
                     sort_order: SortOrder::Asc as i32,
                     sort_datetime_format: None,
+                    script: None,
                 }],
             ),
             (
@@ -755,6 +1302,7 @@ mod tests {
                     field_name: "_score".to_string(),
                     sort_order: SortOrder::Desc as i32,
                     sort_datetime_format: None,
+                    script: None,
                 }],
             ),
             (
@@ -763,6 +1311,7 @@ mod tests {
                     field_name: "_score".to_string(),
                     sort_order: SortOrder::Asc as i32,
                     sort_datetime_format: None,
+                    script: None,
                 }],
             ),
             (
@@ -771,6 +1320,7 @@ mod tests {
                     field_name: "_score".to_string(),
                     sort_order: SortOrder::Desc as i32,
                     sort_datetime_format: None,
+                    script: None,
                 }],
             ),
             (
@@ -780,11 +1330,13 @@ mod tests {
                         field_name: "field1".to_string(),
                         sort_order: SortOrder::Desc as i32,
                         sort_datetime_format: None,
+                        script: None,
                     },
                     SortField {
                         field_name: "field2".to_string(),
                         sort_order: SortOrder::Desc as i32,
                         sort_datetime_format: None,
+                        script: None,
                     },
                 ],
             ),
@@ -795,11 +1347,13 @@ mod tests {
                         field_name: "field1".to_string(),
                         sort_order: SortOrder::Desc as i32,
                         sort_datetime_format: None,
+                        script: None,
                     },
                     SortField {
                         field_name: "field2".to_string(),
                         sort_order: SortOrder::Asc as i32,
                         sort_datetime_format: None,
+                        script: None,
                     },
                 ],
             ),
@@ -810,11 +1364,13 @@ mod tests {
                         field_name: "field1".to_string(),
                         sort_order: SortOrder::Asc as i32,
                         sort_datetime_format: None,
+                        script: None,
                     },
                     SortField {
                         field_name: "field2".to_string(),
                         sort_order: SortOrder::Desc as i32,
                         sort_datetime_format: None,
+                        script: None,
                     },
                 ],
             ),
@@ -849,10 +1405,32 @@ mod tests {
                 field_name: "fiel1".to_string(),
                 sort_order: SortOrder::Desc as i32,
                 sort_datetime_format: None,
+                script: None,
             }],
         );
     }
 
+    #[test]
+    fn test_attach_sort_script() {
+        let sort_fields = SortBy::from("_script,timestamp".to_string()).sort_fields;
+        let sort_fields = attach_sort_script(
+            sort_fields,
+            Some("popularity * 0.7 + recency * 0.3".to_string()),
+        )
+        .unwrap();
+        assert_eq!(sort_fields[0].field_name, "_script");
+        assert_eq!(sort_fields[0].sort_order, SortOrder::Desc as i32);
+        assert_eq!(
+            sort_fields[0].script.as_deref(),
+            Some("popularity * 0.7 + recency * 0.3")
+        );
+        assert!(sort_fields[1].script.is_none());
+
+        let sort_fields = SortBy::from("timestamp".to_string()).sort_fields;
+        let error = attach_sort_script(sort_fields, Some("popularity".to_string())).unwrap_err();
+        assert!(matches!(error, SearchError::InvalidArgument(_)));
+    }
+
     #[tokio::test]
     async fn test_rest_search_api_route_invalid_key() {
         let resp = warp::test::request()
@@ -914,6 +1492,38 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_rest_search_api_msgpack_format() {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service.expect_root_search().returning(|_| {
+            Ok(quickwit_proto::search::SearchResponse {
+                hits: vec![quickwit_proto::search::Hit {
+                    json: r#"{"title": "foo", "count": 3, "tags": ["a", "b"]}"#.to_string(),
+                    ..Default::default()
+                }],
+                num_hits: 1,
+                elapsed_time_micros: 16,
+                ..Default::default()
+            })
+        });
+        let rest_search_api_handler = search_handler(mock_search_service);
+        let resp = warp::test::request()
+            .path("/quickwit-demo-index/search?query=*&format=msgpack")
+            .reply(&rest_search_api_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        assert_eq!(resp.headers()["content-type"], "application/msgpack");
+        let msgpack_resp: JsonValue = rmp_serde::from_slice(resp.body()).unwrap();
+
+        let resp = warp::test::request()
+            .path("/quickwit-demo-index/search?query=*&format=json")
+            .reply(&rest_search_api_handler)
+            .await;
+        let json_resp: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(msgpack_resp, json_resp);
+        assert_eq!(msgpack_resp["hits"][0]["tags"][1], "b");
+    }
+
     #[tokio::test]
     async fn test_rest_search_api_start_offset_and_num_hits_parameter() -> anyhow::Result<()> {
         let mut mock_search_service = MockSearchService::new();
@@ -938,83 +1548,592 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_rest_search_api_with_index_does_not_exist() -> anyhow::Result<()> {
+    async fn test_rest_search_api_min_score_parameter() {
         let mut mock_search_service = MockSearchService::new();
-        mock_search_service.expect_root_search().returning(|_| {
-            Err(SearchError::IndexesNotFound {
-                index_ids: vec!["not-found-index".to_string()],
-            })
-        });
+        mock_search_service
+            .expect_root_search()
+            .with(predicate::function(
+                |search_request: &quickwit_proto::search::SearchRequest| {
+                    search_request.min_score == Some(ScoreThreshold { value: 0.5 })
+                },
+            ))
+            .returning(|_| Ok(Default::default()));
         let rest_search_api_handler = search_handler(mock_search_service);
         assert_eq!(
             warp::test::request()
-                .path("/index-does-not-exist/search?query=myfield:test")
+                .path("/quickwit-demo-index/search?query=*&min_score=0.5")
                 .reply(&rest_search_api_handler)
                 .await
                 .status(),
-            404
+            200
         );
-        Ok(())
     }
 
     #[tokio::test]
-    async fn test_rest_search_api_with_wrong_fieldname() -> anyhow::Result<()> {
+    async fn test_rest_search_api_scoring_parameter() {
         let mut mock_search_service = MockSearchService::new();
         mock_search_service
             .expect_root_search()
-            .returning(|_| Err(SearchError::Internal("ty".to_string())));
+            .withf(|search_request| search_request.scoring.as_deref() == Some("bm25(k1=2,b=0.5)"))
+            .returning(|_| Ok(Default::default()));
         let rest_search_api_handler = search_handler(mock_search_service);
         assert_eq!(
             warp::test::request()
-                .path("/index-does-not-exist/search?query=myfield:test")
+                .path("/quickwit-demo-index/search?query=*&scoring=bm25(k1%3D2,b%3D0.5)")
                 .reply(&rest_search_api_handler)
                 .await
                 .status(),
-            500
+            200
         );
-        Ok(())
     }
 
     #[tokio::test]
-    async fn test_rest_search_api_with_invalid_query() -> anyhow::Result<()> {
+    async fn test_rest_search_api_fresh_parameter() {
         let mut mock_search_service = MockSearchService::new();
         mock_search_service
             .expect_root_search()
-            .returning(|_| Err(SearchError::InvalidQuery("invalid query".to_string())));
+            .withf(|search_request| search_request.bypass_cache)
+            .returning(|_| Ok(Default::default()));
+        let rest_search_api_handler = search_handler(mock_search_service);
+        assert_eq!(
+            warp::test::request()
+                .path("/quickwit-demo-index/search?query=*&fresh=true")
+                .reply(&rest_search_api_handler)
+                .await
+                .status(),
+            200
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rest_search_api_missing_fields_parameter() {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .withf(|search_request| search_request.missing_fields_as_null == Some(true))
+            .returning(|_| Ok(Default::default()));
         let rest_search_api_handler = search_handler(mock_search_service);
+        assert_eq!(
+            warp::test::request()
+                .path("/quickwit-demo-index/search?query=*&missing_fields=as_null")
+                .reply(&rest_search_api_handler)
+                .await
+                .status(),
+            200
+        );
         let response = warp::test::request()
-            .path("/my-index/search?query=myfield:test")
+            .path("/quickwit-demo-index/search?query=*&missing_fields=skip")
             .reply(&rest_search_api_handler)
             .await;
         assert_eq!(response.status(), 400);
-        let body = String::from_utf8_lossy(response.body());
-        assert!(body.contains("invalid query"));
-        Ok(())
     }
 
     #[tokio::test]
-    async fn test_rest_search_api_route_serialize_results_with_snippet() -> anyhow::Result<()> {
+    async fn test_rest_search_api_explain_returns_cost_estimate() {
         let mut mock_search_service = MockSearchService::new();
-        mock_search_service.expect_root_search().returning(|_| {
-            Ok(quickwit_proto::search::SearchResponse {
-                hits: vec![quickwit_proto::search::Hit {
-                    json: r#"{"title": "foo", "body": "foo bar baz"}"#.to_string(),
-                    partial_hit: None,
-                    snippet: Some(r#"{"title": [], "body": ["foo <em>bar</em> baz"]}"#.to_string()),
-                    index_id: "quickwit-demo-index".to_string(),
-                }],
-                num_hits: 1,
-                elapsed_time_micros: 16,
-                errors: Vec::new(),
-                ..Default::default()
-            })
-        });
+        mock_search_service
+            .expect_root_search()
+            .withf(|search_request| search_request.explain)
+            .returning(|_| {
+                Ok(quickwit_proto::search::SearchResponse {
+                    cost_estimate: Some(QueryCostEstimate {
+                        num_splits: 2,
+                        num_fields: 1,
+                        num_docs: 100,
+                        cost: 100,
+                    }),
+                    ..Default::default()
+                })
+            });
         let rest_search_api_handler = search_handler(mock_search_service);
-        let resp = warp::test::request()
-            .path(
-                "/quickwit-demo-index/search?query=bar&search_field=title,body&\
-                 snippet_fields=title,body",
-            )
+        let response = warp::test::request()
+            .path("/quickwit-demo-index/search?query=*&explain=true")
+            .reply(&rest_search_api_handler)
+            .await;
+        assert_eq!(response.status(), 200);
+        let response_json: JsonValue = serde_json::from_slice(response.body()).unwrap();
+        let expected_cost_estimate_json = json!({
+            "num_splits": 2,
+            "num_fields": 1,
+            "num_docs": 100,
+            "cost": 100,
+        });
+        assert_eq!(response_json["cost_estimate"], expected_cost_estimate_json);
+    }
+
+    #[tokio::test]
+    async fn test_rest_search_api_explain_resolution() {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .withf(|search_request| {
+                search_request.explain_resolution && search_request.index_id_patterns == ["logs-*"]
+            })
+            .returning(|_| {
+                Ok(quickwit_proto::search::SearchResponse {
+                    resolution: Some(quickwit_proto::search::SearchResolution {
+                        index_ids: vec!["logs-2024".to_string(), "logs-2025".to_string()],
+                        default_operator: Some("OR".to_string()),
+                        default_max_hits: None,
+                        query_ast: r#"{"type": "match_all"}"#.to_string(),
+                    }),
+                    ..Default::default()
+                })
+            });
+        let rest_search_api_handler = search_handler(mock_search_service);
+        let response = warp::test::request()
+            .path("/logs-*/search?query=*&explain_resolution=true")
+            .reply(&rest_search_api_handler)
+            .await;
+        assert_eq!(response.status(), 200);
+        let response_json: JsonValue = serde_json::from_slice(response.body()).unwrap();
+        let expected_resolution_json = json!({
+            "index_ids": ["logs-2024", "logs-2025"],
+            "default_operator": "OR",
+            "query": {"type": "match_all"},
+        });
+        assert_eq!(response_json["resolution"], expected_resolution_json);
+    }
+
+    #[tokio::test]
+    async fn test_rest_search_api_per_split_timeout() {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .withf(|search_request| search_request.per_split_timeout_millis == Some(500))
+            .returning(|_| {
+                Ok(quickwit_proto::search::SearchResponse {
+                    num_hits: 1,
+                    failed_splits: vec![SplitSearchError {
+                        error: "split search exceeded the per-split timeout of 500ms".to_string(),
+                        split_id: "slow-split".to_string(),
+                        retryable_error: false,
+                        timed_out: true,
+                    }],
+                    num_successful_splits: 2,
+                    ..Default::default()
+                })
+            });
+        let rest_search_api_handler = search_handler(mock_search_service);
+        let response = warp::test::request()
+            .path(
+                "/quickwit-demo-index/search?query=*&allow_failed_splits=true&\
+                 per_split_timeout=500ms",
+            )
+            .reply(&rest_search_api_handler)
+            .await;
+        assert_eq!(response.status(), 200);
+        let response_json: JsonValue = serde_json::from_slice(response.body()).unwrap();
+        let expected_splits_json = json!({
+            "total": 3,
+            "successful": 2,
+            "failed": 1,
+            "timed_out_splits": ["slow-split"],
+        });
+        assert_eq!(response_json["num_hits"], 1);
+        assert_eq!(response_json["_splits"], expected_splits_json);
+
+        let response = warp::test::request()
+            .path("/quickwit-demo-index/search?query=*&per_split_timeout=500ms")
+            .reply(&rest_search_api_handler)
+            .await;
+        assert_eq!(response.status(), 400);
+
+        let response = warp::test::request()
+            .path(
+                "/quickwit-demo-index/search?query=*&allow_failed_splits=true&per_split_timeout=0s",
+            )
+            .reply(&rest_search_api_handler)
+            .await;
+        assert_eq!(response.status(), 400);
+    }
+
+    #[tokio::test]
+    async fn test_rest_search_api_truncated_response() {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service.expect_root_search().returning(|_| {
+            Ok(quickwit_proto::search::SearchResponse {
+                num_hits: 3,
+                hits: vec![Hit {
+                    json: r#"{"body": "small"}"#.to_string(),
+                    ..Default::default()
+                }],
+                truncation_reason: Some(
+                    "hits exceed the maximum response size of 1.0 KiB: returned 1 out of 3 hits"
+                        .to_string(),
+                ),
+                ..Default::default()
+            })
+        });
+        let rest_search_api_handler = search_handler(mock_search_service);
+        let response = warp::test::request()
+            .path("/quickwit-demo-index/search?query=*")
+            .reply(&rest_search_api_handler)
+            .await;
+        assert_eq!(response.status(), 200);
+        let response_json: JsonValue = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(response_json["num_hits"], 3);
+        assert_eq!(response_json["hits"].as_array().unwrap().len(), 1);
+        assert_eq!(response_json["truncated"], true);
+        assert!(
+            response_json["truncation_reason"]
+                .as_str()
+                .unwrap()
+                .contains("returned 1 out of 3 hits")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rest_search_api_detailed_timings() {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .times(2)
+            .returning(|_| {
+                Ok(quickwit_proto::search::SearchResponse {
+                    num_hits: 1,
+                    hits: vec![Hit {
+                        json: r#"{"body": "first"}"#.to_string(),
+                        ..Default::default()
+                    }],
+                    elapsed_time_micros: 16,
+                    ..Default::default()
+                })
+            });
+        let rest_search_api_handler = search_handler(mock_search_service);
+
+        let response = warp::test::request()
+            .path("/quickwit-demo-index/search?query=*")
+            .reply(&rest_search_api_handler)
+            .await;
+        assert_eq!(response.status(), 200);
+        let response_json: JsonValue = serde_json::from_slice(response.body()).unwrap();
+        assert!(response_json.get("timings").is_none());
+
+        let response = warp::test::request()
+            .path("/quickwit-demo-index/search?query=*&detailed_timings=true")
+            .reply(&rest_search_api_handler)
+            .await;
+        assert_eq!(response.status(), 200);
+        let response_json: JsonValue = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(response_json["elapsed_time_micros"], 16);
+
+        let timings = &response_json["timings"];
+        assert!(timings["query_execution_micros"].as_u64().is_some());
+        assert!(timings["serialization_micros"].as_u64().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_rest_search_api_columnar_hits() {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service.expect_root_search().returning(|_| {
+            Ok(quickwit_proto::search::SearchResponse {
+                num_hits: 2,
+                hits: vec![
+                    Hit {
+                        json: r#"{"body": "first", "severity": 1}"#.to_string(),
+                        ..Default::default()
+                    },
+                    Hit {
+                        json: r#"{"body": "second"}"#.to_string(),
+                        ..Default::default()
+                    },
+                ],
+                ..Default::default()
+            })
+        });
+        let rest_search_api_handler = search_handler(mock_search_service);
+
+        let response = warp::test::request()
+            .path("/quickwit-demo-index/search?query=*&columnar_hits=true")
+            .reply(&rest_search_api_handler)
+            .await;
+        assert_eq!(response.status(), 200);
+        let response_json: JsonValue = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(response_json["num_hits"], 2);
+        assert_eq!(response_json["hits"], json!([]));
+
+        let columnar_hits: ColumnarHits =
+            serde_json::from_value(response_json["columnar_hits"].clone()).unwrap();
+        let body_ord = columnar_hits
+            .columns
+            .iter()
+            .position(|column| column == "body")
+            .unwrap();
+        let severity_ord = columnar_hits
+            .columns
+            .iter()
+            .position(|column| column == "severity")
+            .unwrap();
+        assert_eq!(
+            columnar_hits.values[body_ord],
+            [json!("first"), json!("second")]
+        );
+        assert_eq!(
+            columnar_hits.values[severity_ord],
+            [json!(1), JsonValue::Null]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rest_search_api_result_bytes() {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service.expect_root_search().returning(|_| {
+            Ok(quickwit_proto::search::SearchResponse {
+                num_hits: 2,
+                hits: vec![
+                    Hit {
+                        json: r#"{"body": "first", "severity": 1}"#.to_string(),
+                        ..Default::default()
+                    },
+                    Hit {
+                        json: r#"{"body": "second"}"#.to_string(),
+                        ..Default::default()
+                    },
+                ],
+                ..Default::default()
+            })
+        });
+        let rest_search_api_handler = search_handler(mock_search_service);
+
+        let call = |path: &'static str| {
+            let request = http::Request::builder()
+                .uri(path)
+                .body(String::new())
+                .unwrap();
+            ResultBytesLayer
+                .layer(warp::service(rest_search_api_handler.clone()))
+                .oneshot(request)
+        };
+        let response = call("/quickwit-demo-index/search?query=*").await.unwrap();
+        assert_eq!(response.status(), 200);
+        assert!(response.headers().get("x-quickwit-result-bytes").is_none());
+        let body_bytes = response.into_body().collect().await.unwrap().to_bytes();
+        let response_json: JsonValue = serde_json::from_slice(&body_bytes).unwrap();
+        assert!(response_json.get("hit_bytes").is_none());
+
+        let response = call("/quickwit-demo-index/search?query=*&result_bytes=true&hit_bytes=true")
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+        let result_bytes_header = response.headers()["x-quickwit-result-bytes"].clone();
+        let body_bytes = response.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(result_bytes_header, body_bytes.len().to_string().as_str());
+
+        for format in ["json", "pretty_json"] {
+            let response = warp::test::request()
+                .path(&format!(
+                    "/quickwit-demo-index/search?query=*&hit_bytes=true&format={format}"
+                ))
+                .reply(&rest_search_api_handler)
+                .await;
+            assert_eq!(response.status(), 200);
+            let response_json: JsonValue = serde_json::from_slice(response.body()).unwrap();
+
+            let hit_sizes: Vec<u64> = response_json["hits"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|hit| serde_json::to_vec(hit).unwrap().len() as u64)
+                .collect();
+            assert_eq!(response_json["hit_bytes"], json!(hit_sizes));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rest_search_api_read_preference() {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .withf(|search_request| search_request.read_preference() == ReadPreference::Freshest)
+            .times(2)
+            .returning(|_| Ok(Default::default()));
+        mock_search_service
+            .expect_root_search()
+            .withf(|search_request| search_request.read_preference() == ReadPreference::Fastest)
+            .times(2)
+            .returning(|_| Ok(Default::default()));
+        let rest_search_api_handler = search_handler(mock_search_service);
+
+        let response = warp::test::request()
+            .path("/quickwit-demo-index/search?query=*")
+            .header("X-Read-Preference", "Freshest")
+            .reply(&rest_search_api_handler)
+            .await;
+        assert_eq!(response.status(), 200);
+
+        let response = warp::test::request()
+            .path("/quickwit-demo-index/search?query=*&read_preference=freshest")
+            .reply(&rest_search_api_handler)
+            .await;
+        assert_eq!(response.status(), 200);
+
+        let response = warp::test::request()
+            .path("/quickwit-demo-index/search?query=*")
+            .reply(&rest_search_api_handler)
+            .await;
+        assert_eq!(response.status(), 200);
+
+        // The header overrides the parameter.
+        let response = warp::test::request()
+            .path("/quickwit-demo-index/search?query=*&read_preference=freshest")
+            .header("X-Read-Preference", "fastest")
+            .reply(&rest_search_api_handler)
+            .await;
+        assert_eq!(response.status(), 200);
+
+        let response = warp::test::request()
+            .path("/quickwit-demo-index/search?query=*")
+            .header("X-Read-Preference", "stalest")
+            .reply(&rest_search_api_handler)
+            .await;
+        assert_eq!(response.status(), 400);
+        let response_json: JsonValue = serde_json::from_slice(response.body()).unwrap();
+        assert!(
+            response_json["message"]
+                .as_str()
+                .unwrap()
+                .contains("invalid `X-Read-Preference` header `stalest`")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rest_search_api_timeout() {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .withf(|search_request| search_request.timeout_millis == Some(120_000))
+            .times(2)
+            .returning(|_| {
+                let response_headers = HashMap::from([(
+                    "warning".to_string(),
+                    "299 quickwit \"search timeout clamped\"".to_string(),
+                )]);
+                Ok(quickwit_proto::search::SearchResponse {
+                    response_headers,
+                    ..Default::default()
+                })
+            });
+        mock_search_service
+            .expect_root_search()
+            .withf(|search_request| search_request.timeout_millis == Some(500))
+            .times(1)
+            .returning(|_| Ok(Default::default()));
+        let rest_search_api_handler = search_handler(mock_search_service);
+
+        let response = warp::test::request()
+            .path("/quickwit-demo-index/search?query=*")
+            .header("X-Search-Timeout", "2m")
+            .reply(&rest_search_api_handler)
+            .await;
+        assert_eq!(response.status(), 200);
+        assert_eq!(
+            response.headers()["warning"],
+            "299 quickwit \"search timeout clamped\""
+        );
+
+        // The header overrides the parameter.
+        let response = warp::test::request()
+            .path("/quickwit-demo-index/search?query=*&timeout=500ms")
+            .header("X-Search-Timeout", "120s")
+            .reply(&rest_search_api_handler)
+            .await;
+        assert_eq!(response.status(), 200);
+
+        let response = warp::test::request()
+            .path("/quickwit-demo-index/search?query=*&timeout=500ms")
+            .reply(&rest_search_api_handler)
+            .await;
+        assert_eq!(response.status(), 200);
+        assert!(response.headers().get("warning").is_none());
+
+        let response = warp::test::request()
+            .path("/quickwit-demo-index/search?query=*")
+            .header("X-Search-Timeout", "0s")
+            .reply(&rest_search_api_handler)
+            .await;
+        assert_eq!(response.status(), 400);
+    }
+
+    #[tokio::test]
+    async fn test_rest_search_api_with_index_does_not_exist() -> anyhow::Result<()> {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service.expect_root_search().returning(|_| {
+            Err(SearchError::IndexesNotFound {
+                index_ids: vec!["not-found-index".to_string()],
+            })
+        });
+        let rest_search_api_handler = search_handler(mock_search_service);
+        assert_eq!(
+            warp::test::request()
+                .path("/index-does-not-exist/search?query=myfield:test")
+                .reply(&rest_search_api_handler)
+                .await
+                .status(),
+            404
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rest_search_api_with_wrong_fieldname() -> anyhow::Result<()> {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .returning(|_| Err(SearchError::Internal("ty".to_string())));
+        let rest_search_api_handler = search_handler(mock_search_service);
+        assert_eq!(
+            warp::test::request()
+                .path("/index-does-not-exist/search?query=myfield:test")
+                .reply(&rest_search_api_handler)
+                .await
+                .status(),
+            500
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rest_search_api_with_invalid_query() -> anyhow::Result<()> {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .returning(|_| Err(SearchError::InvalidQuery("invalid query".to_string())));
+        let rest_search_api_handler = search_handler(mock_search_service);
+        let response = warp::test::request()
+            .path("/my-index/search?query=myfield:test")
+            .reply(&rest_search_api_handler)
+            .await;
+        assert_eq!(response.status(), 400);
+        let body = String::from_utf8_lossy(response.body());
+        assert!(body.contains("invalid query"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rest_search_api_route_serialize_results_with_snippet() -> anyhow::Result<()> {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service.expect_root_search().returning(|_| {
+            Ok(quickwit_proto::search::SearchResponse {
+                hits: vec![quickwit_proto::search::Hit {
+                    json: r#"{"title": "foo", "body": "foo bar baz"}"#.to_string(),
+                    partial_hit: None,
+                    snippet: Some(r#"{"title": [], "body": ["foo <em>bar</em> baz"]}"#.to_string()),
+                    index_id: "quickwit-demo-index".to_string(),
+                    scores: None,
+                }],
+                num_hits: 1,
+                elapsed_time_micros: 16,
+                errors: Vec::new(),
+                ..Default::default()
+            })
+        });
+        let rest_search_api_handler = search_handler(mock_search_service);
+        let resp = warp::test::request()
+            .path(
+                "/quickwit-demo-index/search?query=bar&search_field=title,body&\
+                 snippet_fields=title,body",
+            )
             .reply(&rest_search_api_handler)
             .await;
 
@@ -1083,4 +2202,125 @@ mod tests {
             assert_eq!(response.status(), 400);
         }
     }
+
+    #[tokio::test]
+    async fn test_rest_search_api_stream_aggs() {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .withf(|search_request| {
+                search_request.max_hits == 0 && search_request.aggregation_request.is_some()
+            })
+            .return_once(|_| Ok(Default::default()));
+        let rest_search_api_handler = search_handler(mock_search_service);
+        let response = warp::test::request()
+            .method("POST")
+            .path("/quickwit-demo-index/search")
+            .json(&true)
+            .body(r#"{"query": "*", "aggs": {"range": []}, "stream_aggs": true}"#)
+            .reply(&rest_search_api_handler)
+            .await;
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.headers()["content-type"], "application/x-ndjson");
+        assert!(response.body().is_empty());
+
+        let rest_search_api_handler = search_handler(MockSearchService::new());
+        let response = warp::test::request()
+            .path("/quickwit-demo-index/search?query=*&stream_aggs=true")
+            .reply(&rest_search_api_handler)
+            .await;
+        assert_eq!(response.status(), 400);
+    }
+
+    #[tokio::test]
+    async fn test_rest_search_api_csv_aggs() {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .withf(|search_request| {
+                search_request.max_hits == 0 && search_request.aggregation_request.is_some()
+            })
+            .return_once(|_| Ok(Default::default()));
+        let rest_search_api_handler = search_handler(mock_search_service);
+        let response = warp::test::request()
+            .method("POST")
+            .path("/quickwit-demo-index/search")
+            .json(&true)
+            .body(r#"{"query": "*", "aggs": {"range": []}, "csv_aggs": true}"#)
+            .reply(&rest_search_api_handler)
+            .await;
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.headers()["content-type"], "text/csv");
+        assert!(response.body().is_empty());
+
+        let rest_search_api_handler = search_handler(MockSearchService::new());
+        let response = warp::test::request()
+            .path("/quickwit-demo-index/search?query=*&csv_aggs=true")
+            .reply(&rest_search_api_handler)
+            .await;
+        assert_eq!(response.status(), 400);
+
+        let rest_search_api_handler = search_handler(MockSearchService::new());
+        let response = warp::test::request()
+            .method("POST")
+            .path("/quickwit-demo-index/search")
+            .json(&true)
+            .body(r#"{"query": "*", "aggs": {}, "csv_aggs": true, "stream_aggs": true}"#)
+            .reply(&rest_search_api_handler)
+            .await;
+        assert_eq!(response.status(), 400);
+    }
+
+    #[tokio::test]
+    async fn test_rest_search_api_fields_projection() {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service.expect_root_search().returning(|_| {
+            Ok(quickwit_proto::search::SearchResponse {
+                num_hits: 1,
+                hits: vec![Hit {
+                    json: r#"{"title": "foo", "body": "bar"}"#.to_string(),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            })
+        });
+        let rest_search_api_handler = search_handler(mock_search_service);
+        let response = warp::test::request()
+            .path("/quickwit-demo-index/search?query=*&fields=num_hits,hits.title")
+            .reply(&rest_search_api_handler)
+            .await;
+        assert_eq!(response.status(), 200);
+        let response_json: JsonValue = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(
+            response_json,
+            json!({"num_hits": 1, "hits": [{"title": "foo"}]})
+        );
+
+        let response = warp::test::request()
+            .path("/quickwit-demo-index/search?query=*&fields=hits..title")
+            .reply(&rest_search_api_handler)
+            .await;
+        assert_eq!(response.status(), 400);
+    }
+
+    #[tokio::test]
+    async fn test_rest_search_api_index_response_headers() {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service.expect_root_search().returning(|_| {
+            Ok(quickwit_proto::search::SearchResponse {
+                response_headers: HashMap::from([(
+                    "x-data-classification".to_string(),
+                    "confidential".to_string(),
+                )]),
+                ..Default::default()
+            })
+        });
+        let rest_search_api_handler = search_handler(mock_search_service);
+        let response = warp::test::request()
+            .path("/quickwit-demo-index-*/search?query=*")
+            .reply(&rest_search_api_handler)
+            .await;
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.headers()["x-data-classification"], "confidential");
+    }
 }
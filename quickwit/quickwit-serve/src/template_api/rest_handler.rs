@@ -15,12 +15,18 @@
 use std::any::type_name;
 
 use bytes::Bytes;
-use quickwit_config::{ConfigFormat, IndexTemplate, IndexTemplateId, VersionedIndexTemplate};
+use quickwit_common::uri::Uri;
+use quickwit_config::{
+    ConfigFormat, IndexConfig, IndexTemplate, IndexTemplateId, VersionedIndexTemplate,
+    validate_identifier,
+};
 use quickwit_proto::metastore::{
     CreateIndexTemplateRequest, DeleteIndexTemplatesRequest, GetIndexTemplateRequest,
     ListIndexTemplatesRequest, MetastoreError, MetastoreResult, MetastoreService,
     MetastoreServiceClient, serde_utils,
 };
+use quickwit_proto::types::{DocMappingUid, IndexId};
+use serde::Deserialize;
 use serde_json::Value as JsonValue;
 use warp::reject::Rejection;
 use warp::{Filter, Reply};
@@ -38,6 +44,7 @@ use crate::with_arg;
         update_index_template,
         delete_index_template,
         list_index_templates,
+        simulate_index_template,
     ),
     components(schemas(VersionedIndexTemplate))
 )]
@@ -45,8 +52,10 @@ pub(crate) struct IndexTemplateApi;
 
 pub(crate) fn index_template_api_handlers(
     metastore: MetastoreServiceClient,
+    default_index_root_uri: Uri,
 ) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
-    create_index_template_handler(metastore.clone())
+    simulate_index_template_handler(default_index_root_uri)
+        .or(create_index_template_handler(metastore.clone()))
         .or(get_index_template_handler(metastore.clone()))
         .or(update_index_template_handler(metastore.clone()))
         .or(delete_index_template_handler(metastore.clone()))
@@ -267,6 +276,75 @@ async fn list_index_templates(
     Ok(index_templates)
 }
 
+#[derive(Deserialize)]
+struct SimulateIndexTemplateQueryParams {
+    index_id: IndexId,
+}
+
+fn simulate_index_template_handler(
+    default_index_root_uri: Uri,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("templates" / "_simulate")
+        .and(warp::post())
+        .and(warp::query::<SimulateIndexTemplateQueryParams>())
+        .and(warp::filters::body::bytes())
+        .and(extract_config_format())
+        .and(with_arg(default_index_root_uri))
+        .then(simulate_index_template)
+        .and(extract_format_from_qs())
+        .map(into_rest_api_response)
+}
+
+#[utoipa::path(
+    post,
+    tag = "Templates",
+    path = "/templates/_simulate",
+    request_body = VersionedIndexTemplate,
+    params(
+        ("index_id" = String, Query, description = "The ID of the hypothetical index the template is applied to."),
+    ),
+    responses(
+        (status = 200, description = "The index config resulting from applying the template.", body = VersionedIndexConfig),
+        (status = 400, description = "The index template or the index ID is invalid.")
+    ),
+)]
+/// Simulates the creation of an index from an index template.
+///
+/// Applies the index template of the request body, which does not need to be stored in the
+/// metastore, to the index `index_id`, and returns the resulting index config without creating
+/// the index. The index ID does not need to match the index ID patterns of the template. The doc
+/// mapping UID, random for each created index, is zeroed so that the simulations can be compared.
+async fn simulate_index_template(
+    query_params: SimulateIndexTemplateQueryParams,
+    body: Bytes,
+    config_format: ConfigFormat,
+    default_index_root_uri: Uri,
+) -> MetastoreResult<IndexConfig> {
+    let index_template: IndexTemplate =
+        config_format
+            .parse(&body)
+            .map_err(|error| MetastoreError::JsonDeserializeError {
+                struct_name: type_name::<IndexTemplate>().to_string(),
+                message: error.to_string(),
+            })?;
+    index_template.validate().map_err(|error| {
+        let message = format!("invalid index template: {error}");
+        MetastoreError::InvalidArgument { message }
+    })?;
+    validate_identifier("index", &query_params.index_id).map_err(|error| {
+        let message = error.to_string();
+        MetastoreError::InvalidArgument { message }
+    })?;
+    let mut index_config = index_template
+        .apply_template(query_params.index_id, &default_index_root_uri)
+        .map_err(|error| {
+            let message = format!("failed to apply index template: {error}");
+            MetastoreError::InvalidArgument { message }
+        })?;
+    index_config.doc_mapping.doc_mapping_uid = DocMappingUid::default();
+    Ok(index_config)
+}
+
 #[cfg(test)]
 mod tests {
     use quickwit_proto::metastore::{
@@ -445,4 +523,85 @@ mod tests {
         assert_eq!(index_templates[0].template_id, "test-template-bar");
         assert_eq!(index_templates[1].template_id, "test-template-foo");
     }
+
+    #[tokio::test]
+    async fn test_simulate_index_template() {
+        let default_index_root_uri = Uri::for_test("s3://quickwit-indexes");
+        let simulate_index_template_handler =
+            simulate_index_template_handler(default_index_root_uri);
+        let index_template_json = json!({
+            "version": "0.8",
+            "template_id": "test-template-foo",
+            "index_id_patterns": ["test-index-foo*"],
+            "doc_mapping": {
+                "field_mappings": [{"name": "message", "type": "text"}],
+            },
+            "indexing_settings": {
+                "commit_timeout_secs": 42,
+            },
+            "retention": {
+                "period": "42 days",
+            },
+        });
+        let response = warp::test::request()
+            .path("/templates/_simulate?index_id=test-index-foo-1")
+            .method("POST")
+            .json(&index_template_json)
+            .reply(&simulate_index_template_handler)
+            .await;
+        assert_eq!(response.status(), 200);
+
+        let index_config: IndexConfig = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(index_config.index_id, "test-index-foo-1");
+        assert_eq!(
+            index_config.index_uri,
+            "s3://quickwit-indexes/test-index-foo-1"
+        );
+        assert_eq!(
+            index_config.doc_mapping.doc_mapping_uid,
+            DocMappingUid::default()
+        );
+        assert_eq!(index_config.doc_mapping.field_mappings.len(), 1);
+        assert_eq!(index_config.indexing_settings.commit_timeout_secs, 42);
+
+        let retention_policy = index_config.retention_policy_opt.unwrap();
+        assert_eq!(retention_policy.retention_period, "42 days");
+        assert_eq!(retention_policy.evaluation_schedule, "hourly");
+
+        // The simulation is deterministic.
+        let response = warp::test::request()
+            .path("/templates/_simulate?index_id=test-index-foo-1")
+            .method("POST")
+            .json(&index_template_json)
+            .reply(&simulate_index_template_handler)
+            .await;
+        assert_eq!(response.status(), 200);
+
+        let other_index_config: IndexConfig = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(
+            serde_json::to_value(&other_index_config).unwrap(),
+            serde_json::to_value(&index_config).unwrap()
+        );
+
+        let response = warp::test::request()
+            .path("/templates/_simulate?index_id=-test-index-foo-1")
+            .method("POST")
+            .json(&index_template_json)
+            .reply(&simulate_index_template_handler)
+            .await;
+        assert_eq!(response.status(), 400);
+
+        let response = warp::test::request()
+            .path("/templates/_simulate?index_id=test-index-foo-1")
+            .method("POST")
+            .json(&json!({
+                "version": "0.8",
+                "template_id": "test-template-foo",
+                "index_id_patterns": [],
+                "doc_mapping": {},
+            }))
+            .reply(&simulate_index_template_handler)
+            .await;
+        assert_eq!(response.status(), 400);
+    }
 }
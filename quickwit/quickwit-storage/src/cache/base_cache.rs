@@ -13,6 +13,7 @@
 // limitations under the License.
 
 use std::borrow::Borrow;
+use std::cell::Cell;
 use std::hash::Hash;
 use std::sync::{Arc, Weak};
 use std::time::Duration;
@@ -120,6 +121,16 @@ impl<K: Hash + Eq + Send + Sync + 'static, V: ValueLen + Clone + Send + Sync + '
             AnyCache::TinyLfu(tiny_lfu) => tiny_lfu.put(key, value),
         }
     }
+
+    /// Evicts the entries whose key matches the predicate and returns their number.
+    pub fn evict_if(&mut self, predicate: impl Fn(&K) -> bool) -> usize
+    where K: Clone {
+        match self {
+            AnyCache::Lru(lru) => lru.evict_if(predicate),
+            AnyCache::S3Fifo(s3fifo) => s3fifo.evict_if(predicate),
+            AnyCache::TinyLfu(tiny_lfu) => tiny_lfu.evict_if(predicate),
+        }
+    }
 }
 
 pub struct Lru<K: Hash + Eq, V> {
@@ -241,6 +252,24 @@ impl<K: Hash + Eq, V: ValueLen + Clone> Lru<K, V> {
         self.record_item(bytes.len() as u64);
         self.lru_cache.put(key, StoredItem::new(bytes, now));
     }
+
+    fn evict_if(&mut self, predicate: impl Fn(&K) -> bool) -> usize
+    where K: Clone {
+        let keys_to_evict: Vec<K> = self
+            .lru_cache
+            .iter()
+            .map(|(key, _)| key)
+            .filter(|key| predicate(key))
+            .cloned()
+            .collect();
+
+        for key in &keys_to_evict {
+            if let Some(item) = self.lru_cache.pop(key) {
+                self.drop_item(item.len() as u64);
+            }
+        }
+        keys_to_evict.len()
+    }
 }
 
 // actually, quick_cache is a Clock-PRO, not a S3-fifo contrary to what quick-cache and Moka's
@@ -350,6 +379,32 @@ impl<K: Hash + Eq, V: ValueLen + Clone> S3Fifo<K, V> {
         self.cache_metrics.evict_num_items.inc_by(evicted.count);
         self.cache_metrics.evict_num_bytes.inc_by(evicted.bytes);
     }
+
+    fn evict_if(&mut self, predicate: impl Fn(&K) -> bool) -> usize {
+        let num_evicted_items = Cell::new(0u64);
+        let num_evicted_bytes = Cell::new(0u64);
+
+        self.cache.retain(|key, value| {
+            if !predicate(key) {
+                return true;
+            }
+            num_evicted_items.set(num_evicted_items.get() + 1);
+            num_evicted_bytes.set(num_evicted_bytes.get() + value.len() as u64);
+            false
+        });
+        let num_evicted_items = num_evicted_items.get();
+        let num_evicted_bytes = num_evicted_bytes.get();
+
+        self.cache_metrics
+            .in_cache_count
+            .dec_by(num_evicted_items as f64);
+        self.cache_metrics
+            .in_cache_num_bytes
+            .dec_by(num_evicted_bytes as f64);
+        self.cache_metrics.evict_num_items.inc_by(num_evicted_items);
+        self.cache_metrics.evict_num_bytes.inc_by(num_evicted_bytes);
+        num_evicted_items as usize
+    }
 }
 
 // We don't make this value Clone to ensure each item is dropped only once
@@ -459,4 +514,20 @@ impl<K: Hash + Eq + Send + Sync + 'static, V: ValueLen + Clone + Send + Sync + '
             .into(),
         );
     }
+
+    fn evict_if(&mut self, predicate: impl Fn(&K) -> bool) -> usize
+    where K: Clone {
+        let keys_to_evict: Vec<K> = self
+            .cache
+            .iter()
+            .filter(|entry| predicate(entry.key()))
+            .map(|entry| entry.key().clone())
+            .collect();
+
+        // The eviction metrics are updated when the `CapacityTracker` of the entries are dropped.
+        for key in &keys_to_evict {
+            self.cache.invalidate(key);
+        }
+        keys_to_evict.len()
+    }
 }
@@ -0,0 +1,98 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::OwnedBytes;
+use crate::cache::StorageCache;
+
+/// Stores the entries of a shared cache under a directory, for instance the ID of the split the
+/// files belong to, so that they can be evicted together with [`StorageCache::evict_dirs`].
+pub struct DirCache {
+    dir_path: PathBuf,
+    cache: Arc<dyn StorageCache>,
+}
+
+impl DirCache {
+    pub fn new(dir_path: impl Into<PathBuf>, cache: Arc<dyn StorageCache>) -> Self {
+        Self {
+            dir_path: dir_path.into(),
+            cache,
+        }
+    }
+}
+
+#[async_trait]
+impl StorageCache for DirCache {
+    async fn get(&self, path: &Path, byte_range: Range<usize>) -> Option<OwnedBytes> {
+        self.cache.get(&self.dir_path.join(path), byte_range).await
+    }
+
+    async fn get_all(&self, path: &Path) -> Option<OwnedBytes> {
+        self.cache.get_all(&self.dir_path.join(path)).await
+    }
+
+    async fn put(&self, path: PathBuf, byte_range: Range<usize>, bytes: OwnedBytes) {
+        self.cache
+            .put(self.dir_path.join(path), byte_range, bytes)
+            .await;
+    }
+
+    async fn put_all(&self, path: PathBuf, bytes: OwnedBytes) {
+        self.cache.put_all(self.dir_path.join(path), bytes).await;
+    }
+
+    async fn evict_dirs(&self, dir_paths: &[PathBuf]) {
+        let dir_paths: Vec<PathBuf> = dir_paths
+            .iter()
+            .map(|dir_path| self.dir_path.join(dir_path))
+            .collect();
+        self.cache.evict_dirs(&dir_paths).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytesize::ByteSize;
+    use quickwit_config::CacheConfig;
+
+    use super::*;
+    use crate::QuickwitCache;
+
+    #[tokio::test]
+    async fn test_dir_cache_evict_dirs() {
+        let cache_config = CacheConfig::default_with_capacity(ByteSize::mb(1));
+        let cache: Arc<dyn StorageCache> = Arc::new(QuickwitCache::new(&cache_config));
+        let split_1_cache = DirCache::new("split-1", cache.clone());
+        let split_2_cache = DirCache::new("split-2", cache.clone());
+
+        let path = Path::new("segment.fast");
+        split_1_cache
+            .put(path.to_path_buf(), 0..4, OwnedBytes::new(&b"abcd"[..]))
+            .await;
+        split_2_cache
+            .put_all(path.to_path_buf(), OwnedBytes::new(&b"efgh"[..]))
+            .await;
+        assert_eq!(split_1_cache.get(path, 0..4).await.unwrap(), &b"abcd"[..]);
+        assert_eq!(split_2_cache.get_all(path).await.unwrap(), &b"efgh"[..]);
+
+        cache.evict_dirs(&[PathBuf::from("split-1")]).await;
+        assert!(split_1_cache.get(path, 0..4).await.is_none());
+        assert_eq!(split_2_cache.get_all(path).await.unwrap(), &b"efgh"[..]);
+    }
+}
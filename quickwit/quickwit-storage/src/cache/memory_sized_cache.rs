@@ -84,6 +84,13 @@ impl<K: Hash + Eq + Clone + Send + Sync + 'static> CacheState<K> {
 
         self.cache.put(key, bytes)
     }
+
+    fn evict_if(&mut self, predicate: impl Fn(&K) -> bool) -> usize {
+        for virtual_cache in &mut self.virtual_caches {
+            virtual_cache.evict_if(&predicate);
+        }
+        self.cache.evict_if(predicate)
+    }
 }
 
 /// A simple in-resident memory slice cache.
@@ -122,6 +129,11 @@ impl<K: Hash + Eq + Clone + Send + Sync + 'static> MemorySizedCache<K> {
     pub fn put(&self, val: K, bytes: OwnedBytes) {
         self.inner.lock().unwrap().put(val, bytes);
     }
+
+    /// Evicts the entries whose key matches the predicate and returns their number.
+    pub fn evict_if(&self, predicate: impl Fn(&K) -> bool) -> usize {
+        self.inner.lock().unwrap().evict_if(predicate)
+    }
 }
 
 impl MemorySizedCache<SliceAddress> {
@@ -228,4 +240,24 @@ mod tests {
         cache.put("hello.seg", data);
         assert!(cache.get(&"hello.seg").is_none());
     }
+    #[test]
+    fn test_cache_evict_if() {
+        for policy in ["lru", "s3-fifo", "tiny-lfu"] {
+            let cache_config: CacheConfig = serde_json::from_value(serde_json::json!({
+                "capacity": "10KB",
+                "policy": policy,
+            }))
+            .unwrap();
+            let cache = MemorySizedCache::from_config(&cache_config, &CACHE_METRICS_FOR_TESTS);
+            cache.put("split-1/a".to_string(), OwnedBytes::new(&b"abc"[..]));
+            cache.put("split-1/b".to_string(), OwnedBytes::new(&b"def"[..]));
+            cache.put("split-2/a".to_string(), OwnedBytes::new(&b"ghi"[..]));
+
+            let num_evicted_items = cache.evict_if(|key| key.starts_with("split-1/"));
+            assert_eq!(num_evicted_items, 2, "policy: {policy}");
+            assert!(cache.get(&"split-1/a".to_string()).is_none());
+            assert!(cache.get(&"split-1/b".to_string()).is_none());
+            assert_eq!(cache.get(&"split-2/a".to_string()).unwrap(), &b"ghi"[..]);
+        }
+    }
 }
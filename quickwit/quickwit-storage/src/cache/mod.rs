@@ -14,6 +14,7 @@
 
 mod base_cache;
 mod byte_range_cache;
+mod dir_cache;
 mod memory_sized_cache;
 mod quickwit_cache;
 mod slice_address;
@@ -29,6 +30,7 @@ pub use quickwit_cache::QuickwitCache;
 pub use storage_with_cache::StorageWithCache;
 
 pub use self::byte_range_cache::ByteRangeCache;
+pub use self::dir_cache::DirCache;
 pub use self::memory_sized_cache::MemorySizedCache;
 use crate::{OwnedBytes, Storage};
 
@@ -62,4 +64,6 @@ pub trait StorageCache: Send + Sync + 'static {
     async fn put(&self, path: PathBuf, byte_range: Range<usize>, bytes: OwnedBytes);
     /// Put an entire file into the cache.
     async fn put_all(&self, path: PathBuf, bytes: OwnedBytes);
+    /// Evicts the entries of the files located within one of the directories.
+    async fn evict_dirs(&self, dir_paths: &[PathBuf]);
 }
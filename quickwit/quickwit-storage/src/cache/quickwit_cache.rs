@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::HashSet;
 use std::ops::Range;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
@@ -103,6 +104,12 @@ impl StorageCache for QuickwitCache {
             cache.put(path, FULL_SLICE, bytes).await;
         }
     }
+
+    async fn evict_dirs(&self, dir_paths: &[PathBuf]) {
+        for (_, cache) in &self.router {
+            cache.evict_dirs(dir_paths).await;
+        }
+    }
 }
 
 /// The Quickwit cache logic is very simple for the moment.
@@ -143,6 +150,17 @@ impl StorageCache for SimpleCache {
     async fn put_all(&self, path: PathBuf, bytes: OwnedBytes) {
         self.slice_cache.put_slice(path, FULL_SLICE.clone(), bytes);
     }
+
+    async fn evict_dirs(&self, dir_paths: &[PathBuf]) {
+        let dir_paths: HashSet<&Path> = dir_paths.iter().map(PathBuf::as_path).collect();
+        self.slice_cache.evict_if(|slice_address| {
+            slice_address
+                .path
+                .ancestors()
+                .skip(1)
+                .any(|dir_path| dir_paths.contains(dir_path))
+        });
+    }
 }
 
 #[cfg(test)]
@@ -125,6 +125,18 @@ impl SearchSplitCache {
         delete_evicted_splits(&self.root_path, splits_to_evict);
     }
 
+    /// Evicts the given splits from the cache, deleting their files. The splits that are not on
+    /// disk are ignored. Returns the number of evicted splits.
+    pub fn evict_splits(&self, split_ids: &[SplitId]) -> usize {
+        let evicted_split_ids = self
+            .split_table
+            .lock()
+            .unwrap()
+            .remove_on_disk_splits(split_ids);
+        self.evict(&evicted_split_ids);
+        evicted_split_ids.len()
+    }
+
     /// Wraps a storage with our split cache.
     pub fn wrap_storage(self_arc: Arc<Self>, storage: Arc<dyn Storage>) -> Arc<dyn Storage> {
         let cache = Arc::new(SplitCacheBackingStorage {
@@ -243,4 +255,7 @@ impl StorageCache for SplitCacheBackingStorage {
 
     async fn put(&self, _path: PathBuf, _byte_range: Range<usize>, _bytes: OwnedBytes) {}
     async fn put_all(&self, _path: PathBuf, _bytes: OwnedBytes) {}
+
+    // Splits are evicted from the split cache with `SplitCache::evict_splits`.
+    async fn evict_dirs(&self, _dir_paths: &[PathBuf]) {}
 }
@@ -415,6 +415,26 @@ impl SplitTable {
         }
     }
 
+    /// Removes the given splits from the table if they are on disk, and returns their IDs. The
+    /// removed splits are forgotten: they become candidates again when they are next touched or
+    /// reported.
+    pub(crate) fn remove_on_disk_splits(&mut self, split_ids: &[SplitId]) -> Vec<SplitId> {
+        let mut removed_split_ids = Vec::new();
+        for split_id in split_ids {
+            let is_on_disk = matches!(
+                self.split_to_status.get(split_id),
+                Some(SplitInfo {
+                    status: Status::OnDisk { .. },
+                    ..
+                })
+            );
+            if is_on_disk && self.remove(split_id).is_some() {
+                removed_split_ids.push(split_id.clone());
+            }
+        }
+        removed_split_ids
+    }
+
     pub(crate) fn find_download_opportunity(&mut self) -> Option<DownloadOpportunity> {
         let best_candidate_split_key = self.best_candidate()?;
         let splits_to_delete: Vec<SplitId> = self
@@ -599,6 +619,40 @@ mod tests {
         assert_eq!(split_to_download.split_id, new_split_id);
     }
 
+    #[test]
+    fn test_split_table_remove_on_disk_splits() {
+        let mut split_table = SplitTable::with_limits_and_existing_splits(
+            SplitCacheLimits {
+                max_num_bytes: ByteSize::mb(10),
+                max_num_splits: NonZeroU32::new(30).unwrap(),
+                num_concurrent_downloads: NonZeroU32::new(1).unwrap(),
+                max_file_descriptors: NonZeroU32::new(100).unwrap(),
+            },
+            Default::default(),
+        );
+        let split_ids = sorted_split_ids(3);
+        for split_id in &split_ids[..2] {
+            split_table.report(split_id.clone(), Uri::for_test(TEST_STORAGE_URI));
+            split_table.register_as_downloaded(split_id.clone(), 1_000);
+        }
+        split_table.report(split_ids[2].clone(), Uri::for_test(TEST_STORAGE_URI));
+        assert_eq!(split_table.num_bytes(), 2_000);
+
+        let removed_split_ids =
+            split_table.remove_on_disk_splits(&[split_ids[0].clone(), split_ids[2].clone()]);
+        assert_eq!(removed_split_ids, [split_ids[0].clone()]);
+        assert_eq!(split_table.num_bytes(), 1_000);
+        assert!(
+            split_table
+                .touch(split_ids[0].clone(), &Uri::for_test(TEST_STORAGE_URI))
+                .is_none()
+        );
+        assert_eq!(
+            split_table.touch(split_ids[1].clone(), &Uri::for_test(TEST_STORAGE_URI)),
+            Some(1_000)
+        );
+    }
+
     #[test]
     fn test_eviction_due_to_num_splits() {
         let mut split_table = SplitTable::with_limits_and_existing_splits(
@@ -30,11 +30,14 @@ use tracing_subscriber::prelude::*;
 use tracing_subscriber::registry::LookupSpan;
 use tracing_subscriber::{EnvFilter, Layer};
 
+mod log_tail;
 mod logs;
 mod metrics;
 mod otlp;
 pub mod prometheus;
 
+pub use log_tail::{LogTail, log_tail, log_tail_layer};
+
 pub type EnvFilterReloadFn = Arc<dyn Fn(&str) -> anyhow::Result<()> + Send + Sync>;
 
 pub fn do_nothing_env_filter_reload_fn() -> EnvFilterReloadFn {
@@ -106,7 +109,8 @@ where S: Subscriber + for<'span> LookupSpan<'span> {
         .with_ansi(ansi_colors)
 }
 
-/// Builds the default tracing registry and its reload callback.
+/// Builds the default tracing registry and its reload callback. The registry also feeds the
+/// [`log_tail`].
 pub fn default_tracing_registry(
     level: Level,
     ansi_colors: bool,
@@ -117,7 +121,8 @@ pub fn default_tracing_registry(
     let (reloadable_env_filter, reload_handle) = ReloadLayer::new(startup_env_filter(level)?);
     let registry = tracing_subscriber::registry()
         .with(reloadable_env_filter)
-        .with(logging_layer(ansi_colors));
+        .with(logging_layer(ansi_colors))
+        .with(log_tail_layer());
     let env_filter_reload_fn: EnvFilterReloadFn = Arc::new(move |env_filter_def: &str| {
         let new_env_filter = EnvFilter::try_new(env_filter_def)?;
         reload_handle.reload(new_env_filter)?;
@@ -0,0 +1,187 @@
+// Copyright 2021-Present Datadog, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! In-memory tail of the logs of the process, so that operators can follow the logs of a node
+//! through the developer API, without shell access to the node.
+
+use std::collections::VecDeque;
+use std::sync::{LazyLock, Mutex, MutexGuard};
+use std::{fmt, io};
+
+use tokio::sync::broadcast;
+use tracing::Subscriber;
+use tracing::field::Field;
+use tracing_subscriber::Layer;
+use tracing_subscriber::field::MakeExt;
+use tracing_subscriber::fmt::format::{Writer, debug_fn};
+use tracing_subscriber::registry::LookupSpan;
+
+use crate::logs::time_formatter;
+
+/// Number of recent log lines kept in memory.
+const NUM_RECENT_LINES: usize = 1_000;
+
+/// Number of live log lines buffered for each follower. The followers lagging further behind
+/// skip the oldest lines.
+const LIVE_LINES_CAPACITY: usize = 1_024;
+
+/// Values of the fields whose name contains one of these substrings, such as `auth_token` or
+/// `db_password`, are redacted from the tail.
+const SENSITIVE_FIELD_NAME_PATTERNS: &[&str] = &[
+    "authorization",
+    "cookie",
+    "credential",
+    "password",
+    "secret",
+    "token",
+];
+
+const REDACTED_VALUE: &str = "[REDACTED]";
+
+static LOG_TAIL: LazyLock<LogTail> = LazyLock::new(LogTail::new);
+
+/// Returns the tail of the logs of the process, fed by the [`log_tail_layer`].
+pub fn log_tail() -> &'static LogTail {
+    &LOG_TAIL
+}
+
+/// Recent log lines of the process, along with a channel broadcasting the new ones.
+pub struct LogTail {
+    recent_lines: Mutex<VecDeque<String>>,
+    live_lines_tx: broadcast::Sender<String>,
+}
+
+impl LogTail {
+    fn new() -> Self {
+        let (live_lines_tx, _) = broadcast::channel(LIVE_LINES_CAPACITY);
+        Self {
+            recent_lines: Mutex::new(VecDeque::with_capacity(NUM_RECENT_LINES)),
+            live_lines_tx,
+        }
+    }
+
+    /// Returns the recent log lines, from the oldest to the newest, and a receiver of the lines
+    /// logged afterwards.
+    pub fn subscribe(&self) -> (Vec<String>, broadcast::Receiver<String>) {
+        let recent_lines = self.lock_recent_lines();
+        // Subscribing while holding the lock ensures that no line is either missed or received
+        // twice.
+        let live_lines_rx = self.live_lines_tx.subscribe();
+        (recent_lines.iter().cloned().collect(), live_lines_rx)
+    }
+
+    fn push(&self, line: String) {
+        let mut recent_lines = self.lock_recent_lines();
+
+        if recent_lines.len() == NUM_RECENT_LINES {
+            recent_lines.pop_front();
+        }
+        recent_lines.push_back(line.clone());
+        // Sending only fails when nobody follows the logs.
+        let _ = self.live_lines_tx.send(line);
+    }
+
+    fn lock_recent_lines(&self) -> MutexGuard<'_, VecDeque<String>> {
+        self.recent_lines
+            .lock()
+            .expect("lock should not be poisoned")
+    }
+}
+
+/// Writer pushing the formatted events to the log tail.
+struct LogTailWriter;
+
+impl io::Write for LogTailWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // The fmt layer writes each event at once, once formatted.
+        let line = String::from_utf8_lossy(buf).trim_end().to_string();
+        LOG_TAIL.push(line);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn is_sensitive_field(field_name: &str) -> bool {
+    let field_name = field_name.to_ascii_lowercase();
+
+    SENSITIVE_FIELD_NAME_PATTERNS
+        .iter()
+        .any(|pattern| field_name.contains(pattern))
+}
+
+fn format_field(writer: &mut Writer<'_>, field: &Field, value: &dyn fmt::Debug) -> fmt::Result {
+    if field.name() == "message" {
+        write!(writer, "{value:?}")
+    } else if is_sensitive_field(field.name()) {
+        write!(writer, "{field}={REDACTED_VALUE}")
+    } else {
+        write!(writer, "{field}={value:?}")
+    }
+}
+
+/// Returns the layer feeding the log tail. The events are formatted like the regular logs,
+/// without colors, and the values of the sensitive fields are redacted. Added to a registry
+/// behind the reloadable env filter, the layer honors the current log level.
+pub fn log_tail_layer<S>() -> impl Layer<S> + Send + Sync + 'static
+where S: Subscriber + for<'span> LookupSpan<'span> {
+    let event_format = tracing_subscriber::fmt::format()
+        .with_target(true)
+        .with_timer(time_formatter());
+    tracing_subscriber::fmt::layer()
+        .event_format(event_format)
+        .fmt_fields(debug_fn(format_field).delimited(" "))
+        .with_ansi(false)
+        .with_writer(|| LogTailWriter)
+}
+
+#[cfg(test)]
+mod tests {
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use super::*;
+
+    #[test]
+    fn test_log_tail() {
+        let subscriber = tracing_subscriber::registry().with(log_tail_layer());
+        let (_, mut live_lines_rx) = log_tail().subscribe();
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!(
+                index_id = "test-index",
+                auth_token = "my-token",
+                "test log tail line"
+            );
+        });
+        let line = live_lines_rx.try_recv().unwrap();
+        assert!(line.contains("INFO"));
+        assert!(line.contains("test log tail line"));
+        assert!(line.contains("index_id=\"test-index\""));
+        assert!(line.contains("auth_token=[REDACTED]"));
+        assert!(!line.contains("my-token"));
+
+        let (recent_lines, _) = log_tail().subscribe();
+        assert!(recent_lines.contains(&line));
+    }
+
+    #[test]
+    fn test_is_sensitive_field() {
+        assert!(is_sensitive_field("password"));
+        assert!(is_sensitive_field("aws_secret_access_key"));
+        assert!(is_sensitive_field("Authorization"));
+        assert!(!is_sensitive_field("index_id"));
+    }
+}
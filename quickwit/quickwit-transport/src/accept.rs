@@ -18,11 +18,20 @@ use std::io;
 use std::time::Duration;
 
 use futures_util::{Stream, StreamExt};
+use quickwit_metrics::{LazyCounter, counter, lazy_counter};
+use rustls::{AlertDescription, PeerIncompatible};
 use tokio::net::TcpStream;
 use tokio_rustls::TlsAcceptor;
 use tokio_rustls::server::TlsStream;
 use tracing::{error, warn};
 
+static TLS_HANDSHAKE_FAILURES_TOTAL: LazyCounter = lazy_counter!(
+        name: "handshake_failures_total",
+        description: "Total number of failed server-side TLS handshakes, labeled by `reason` \
+                      (`bad_cert`, `protocol_version`, `alert`, `timeout`, or `other`).",
+        subsystem: "tls",
+);
+
 /// Maximum number of TLS handshakes performed concurrently. A handshake holds a slot only until it
 /// completes or times out, so this bounds the work a burst of new connections can create while
 /// still letting independent handshakes make progress.
@@ -65,10 +74,13 @@ where
                 {
                     Ok(Ok(tls_stream)) => Some(Ok::<_, io::Error>(tls_stream)),
                     Ok(Err(error)) => {
-                        error!("failed to perform TLS handshake: {error:#}");
+                        let reason = tls_handshake_failure_reason(&error);
+                        counter!(parent: TLS_HANDSHAKE_FAILURES_TOTAL, "reason" => reason).inc();
+                        error!(reason, "failed to perform TLS handshake: {error:#}");
                         None
                     }
                     Err(_elapsed) => {
+                        counter!(parent: TLS_HANDSHAKE_FAILURES_TOTAL, "reason" => "timeout").inc();
                         warn!("timed out while performing TLS handshake");
                         None
                     }
@@ -78,3 +90,122 @@ where
         .buffer_unordered(MAX_CONCURRENT_TLS_HANDSHAKES)
         .filter_map(|item| async move { item })
 }
+
+/// Classifies a TLS handshake error for the `reason` label of the handshake failure counter, so
+/// that misconfigured clients (untrusted or missing certificates, unsupported TLS versions) can be
+/// told apart from connections that simply went away.
+fn tls_handshake_failure_reason(error: &io::Error) -> &'static str {
+    let Some(tls_error) = error
+        .get_ref()
+        .and_then(|inner_error| inner_error.downcast_ref::<rustls::Error>())
+    else {
+        return "other";
+    };
+    match tls_error {
+        rustls::Error::InvalidCertificate(_) | rustls::Error::NoCertificatesPresented => "bad_cert",
+        rustls::Error::PeerIncompatible(
+            PeerIncompatible::ServerDoesNotSupportTls12Or13
+            | PeerIncompatible::SupportedVersionsExtensionRequired
+            | PeerIncompatible::Tls12NotOffered
+            | PeerIncompatible::Tls12NotOfferedOrEnabled,
+        )
+        | rustls::Error::AlertReceived(AlertDescription::ProtocolVersion) => "protocol_version",
+        rustls::Error::AlertReceived(_) => "alert",
+        _ => "other",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::stream;
+    use quickwit_config::{HumanDuration, TlsConfig};
+    use rustls::pki_types::ServerName;
+    use tokio::net::TcpListener;
+    use tokio_rustls::TlsConnector;
+
+    use super::*;
+    use crate::{make_tls_client_config, make_tls_server_config};
+
+    const SERVER_CERT_PATH: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../resources/tests/tls/server.crt"
+    );
+    const SERVER_KEY_PATH: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../resources/tests/tls/server.key"
+    );
+    const CA_CERT_PATH: &str =
+        concat!(env!("CARGO_MANIFEST_DIR"), "/../resources/tests/tls/ca.crt");
+
+    #[test]
+    fn test_tls_handshake_failure_reason() {
+        let bad_cert_error = io::Error::new(
+            io::ErrorKind::InvalidData,
+            rustls::Error::NoCertificatesPresented,
+        );
+        assert_eq!(tls_handshake_failure_reason(&bad_cert_error), "bad_cert");
+
+        let protocol_version_error = io::Error::new(
+            io::ErrorKind::InvalidData,
+            rustls::Error::PeerIncompatible(PeerIncompatible::Tls12NotOfferedOrEnabled),
+        );
+        assert_eq!(
+            tls_handshake_failure_reason(&protocol_version_error),
+            "protocol_version"
+        );
+
+        let alert_error = io::Error::new(
+            io::ErrorKind::InvalidData,
+            rustls::Error::AlertReceived(AlertDescription::UnknownCA),
+        );
+        assert_eq!(tls_handshake_failure_reason(&alert_error), "alert");
+
+        let other_error = io::Error::from(io::ErrorKind::UnexpectedEof);
+        assert_eq!(tls_handshake_failure_reason(&other_error), "other");
+    }
+
+    #[tokio::test]
+    async fn test_accept_tls_incoming_counts_handshake_failures() {
+        let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
+        let server_tls_config = TlsConfig {
+            cert_path: SERVER_CERT_PATH.to_string(),
+            key_path: SERVER_KEY_PATH.to_string(),
+            ca_path: CA_CERT_PATH.to_string(),
+            expected_name: None,
+            verify_client_cert: true,
+            cert_poll_interval: HumanDuration::try_from("5m".to_string()).unwrap(),
+        };
+        let server_config = make_tls_server_config(&server_tls_config, &[b"h2"]).unwrap();
+        // The server requires a client certificate, which this client does not present.
+        let client_tls_config = TlsConfig {
+            verify_client_cert: false,
+            ..server_tls_config
+        };
+        let client_config = make_tls_client_config(&client_tls_config).unwrap();
+
+        let tcp_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let listen_addr = tcp_listener.local_addr().unwrap();
+        let tcp_incoming = stream::once(async move {
+            let (tcp_stream, _peer_addr) = tcp_listener.accept().await?;
+            Ok::<_, io::Error>(tcp_stream)
+        });
+        let tls_incoming = accept_tls_incoming(tcp_incoming, TlsAcceptor::from(server_config));
+
+        let bad_cert_failures =
+            counter!(parent: TLS_HANDSHAKE_FAILURES_TOTAL, "reason" => "bad_cert");
+        let num_bad_cert_failures_before = bad_cert_failures.get();
+
+        let connect_fut = async move {
+            let tcp_stream = TcpStream::connect(listen_addr).await.unwrap();
+            let server_name = ServerName::IpAddress(listen_addr.ip().into());
+            TlsConnector::from(client_config)
+                .connect(server_name, tcp_stream)
+                .await
+        };
+        let (tls_streams, _connect_result) =
+            tokio::join!(tls_incoming.collect::<Vec<_>>(), connect_fut);
+        // The connection is dropped rather than yielded.
+        assert!(tls_streams.is_empty());
+        assert_eq!(bad_cert_failures.get(), num_bad_cert_failures_before + 1);
+    }
+}
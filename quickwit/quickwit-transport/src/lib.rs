@@ -25,4 +25,6 @@ mod tls;
 
 pub use accept::accept_tls_incoming;
 pub use channel::ChannelFactory;
-pub use tls::{make_tls_client_config, make_tls_server_config, reload_tls_cert};
+pub use tls::{
+    make_tls_client_config, make_tls_server_config, reload_tls_cert, validate_tls_server_config,
+};
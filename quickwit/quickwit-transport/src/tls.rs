@@ -196,6 +196,22 @@ pub fn make_tls_server_config(
     tls_config: &TlsConfig,
     alpn_protocols: &[&[u8]],
 ) -> anyhow::Result<Arc<ServerConfig>> {
+    let (server_config, resolver) = build_tls_server_config(tls_config, alpn_protocols)?;
+    spawn_cert_reload_task(resolver, *tls_config.cert_poll_interval);
+    Ok(Arc::new(server_config))
+}
+
+/// Checks that a [`ServerConfig`] can be built from `tls_config`, i.e. that the certificate, the
+/// private key, and the CA certificates are loadable, without spawning the reload task.
+pub fn validate_tls_server_config(tls_config: &TlsConfig) -> anyhow::Result<()> {
+    build_tls_server_config(tls_config, &[])?;
+    Ok(())
+}
+
+fn build_tls_server_config(
+    tls_config: &TlsConfig,
+    alpn_protocols: &[&[u8]],
+) -> anyhow::Result<(ServerConfig, Arc<ReloadableCertResolver>)> {
     let resolver = ReloadableCertResolver::load(&tls_config.cert_path, &tls_config.key_path)?;
 
     let builder = ServerConfig::builder();
@@ -211,9 +227,7 @@ pub fn make_tls_server_config(
         .iter()
         .map(|protocol| protocol.to_vec())
         .collect();
-
-    spawn_cert_reload_task(resolver, *tls_config.cert_poll_interval);
-    Ok(Arc::new(server_config))
+    Ok((server_config, resolver))
 }
 
 /// Builds a [`ClientConfig`] used by the gRPC client to connect to peers. Server certificates are
@@ -462,6 +476,37 @@ mod tests {
         assert_eq!(client_config.alpn_protocols, vec![b"h2".to_vec()]);
     }
 
+    #[test]
+    fn test_validate_tls_server_config() {
+        install_crypto_provider();
+        // Does not spawn the reload task, hence no Tokio runtime.
+        let tls_config = test_tls_config(SERVER_CERT_PATH, SERVER_KEY_PATH, CA_CERT_PATH);
+        validate_tls_server_config(&tls_config).unwrap();
+
+        let tls_config = test_tls_config("/missing/server.crt", SERVER_KEY_PATH, CA_CERT_PATH);
+        let error = validate_tls_server_config(&tls_config).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("failed to open /missing/server.crt"),
+            "unexpected error: {error:#}"
+        );
+
+        // Client certificates cannot be verified without CA certificates to validate them against.
+        let tls_config = test_tls_config(SERVER_CERT_PATH, SERVER_KEY_PATH, "");
+        let error = validate_tls_server_config(&tls_config).unwrap_err();
+        assert!(
+            error.to_string().contains("`tls.ca_path`) is not set"),
+            "unexpected error: {error:#}"
+        );
+
+        let tls_config = TlsConfig {
+            verify_client_cert: false,
+            ..test_tls_config(SERVER_CERT_PATH, SERVER_KEY_PATH, "")
+        };
+        validate_tls_server_config(&tls_config).unwrap();
+    }
+
     #[test]
     fn test_reload_tls_cert_notifies_subscribers() {
         let mut reload_request_rx = CERT_RELOAD_TX.subscribe();